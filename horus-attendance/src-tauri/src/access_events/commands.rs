@@ -0,0 +1,125 @@
+//! Tauri command handlers for access events.
+
+use super::types::*;
+use super::{import, reconcile, store};
+use crate::analytics::types::DateRange;
+use crate::db;
+use crate::summary::commands::validate_date;
+
+/// Pairing window when none is given
+const DEFAULT_WINDOW_MINUTES: u32 = 15;
+
+fn validate_source(source: &str) -> Result<String, String> {
+    let source = source.trim();
+    if source.is_empty() {
+        return Err("Source name is required".to_string());
+    }
+    Ok(source.to_string())
+}
+
+fn validate_period(period: &DateRange) -> Result<(), String> {
+    validate_date(&period.start_date)?;
+    validate_date(&period.end_date)?;
+    if period.start_date > period.end_date {
+        return Err("Start date must not be after end date".to_string());
+    }
+    Ok(())
+}
+
+/// Import events from the panel software's CSV export under a source name.
+/// Slashed dates are read day first unless `monthFirst` is set. Events
+/// already imported are counted as duplicates.
+#[tauri::command]
+pub async fn import_access_events_csv(
+    app: tauri::AppHandle,
+    source: String,
+    content: String,
+    month_first: Option<bool>,
+) -> Result<AccessImportResult, String> {
+    let source = validate_source(&source)?;
+    let (events, errors) = import::parse(&content, month_first.unwrap_or(false))?;
+    let (imported, duplicates) = store::insert(&mut *db::open(&app)?, &source, &events)?;
+    log::info!(
+        "[access_events::cmd] Imported {} events from {} ({} duplicates, {} lines rejected)",
+        imported,
+        source,
+        duplicates,
+        errors.len()
+    );
+    Ok(AccessImportResult { imported, duplicates, errors })
+}
+
+/// Store events pushed by a connector reading the panel
+#[tauri::command]
+pub async fn ingest_access_events(
+    app: tauri::AppHandle,
+    source: String,
+    events: Vec<NewAccessEvent>,
+) -> Result<AccessImportResult, String> {
+    let source = validate_source(&source)?;
+    let mut valid = Vec::with_capacity(events.len());
+    let mut errors = Vec::new();
+    for (i, mut event) in events.into_iter().enumerate() {
+        match import::normalize_timestamp(&event.timestamp, false) {
+            Some(timestamp) => {
+                event.timestamp = timestamp;
+                valid.push(event);
+            }
+            None => errors.push(AccessImportError {
+                line: i + 1,
+                message: format!("Invalid time: {}", event.timestamp),
+            }),
+        }
+    }
+    let (imported, duplicates) = store::insert(&mut *db::open(&app)?, &source, &valid)?;
+    log::info!(
+        "[access_events::cmd] Ingested {} events from {} ({} duplicates, {} rejected)",
+        imported,
+        source,
+        duplicates,
+        errors.len()
+    );
+    Ok(AccessImportResult { imported, duplicates, errors })
+}
+
+/// Access events in a period, optionally for one PIN
+#[tauri::command]
+pub async fn list_access_events(
+    app: tauri::AppHandle,
+    period: DateRange,
+    device_user_id: Option<String>,
+    limit: Option<u32>,
+) -> Result<Vec<AccessEvent>, String> {
+    validate_period(&period)?;
+    store::list(
+        &*db::open(&app)?,
+        &period.start_date,
+        &period.end_date,
+        device_user_id.as_deref(),
+        limit.unwrap_or(store::MAX_LIMIT),
+    )
+}
+
+/// Access events in a period paired with punches, optionally for one user.
+/// `windowMinutes` (default 15) is how far apart an event and a punch may be.
+#[tauri::command]
+pub async fn get_access_reconciliation(
+    app: tauri::AppHandle,
+    period: DateRange,
+    user_id: Option<String>,
+    window_minutes: Option<u32>,
+) -> Result<AccessReconciliation, String> {
+    validate_period(&period)?;
+    let conn = db::open(&app)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        reconcile::reconcile(
+            &conn,
+            &period.start_date,
+            &period.end_date,
+            user_id.as_deref(),
+            window_minutes.unwrap_or(DEFAULT_WINDOW_MINUTES),
+        )
+    })
+    .await
+    .map_err(|e| format!("Access reconciliation task failed: {}", e))?
+}
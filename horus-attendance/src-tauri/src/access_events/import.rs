@@ -0,0 +1,155 @@
+//! Reading the panel software's event export
+//!
+//! Exports differ between ZKAccess, ZKBioAccess and BioTime in column order
+//! and naming, so columns are found by header rather than position.
+
+use chrono::{DateTime, Local, NaiveDateTime};
+
+use super::types::{AccessImportError, NewAccessEvent};
+use crate::quarantine::validate::TIMESTAMP_FORMAT;
+use crate::roster::csv::split_line;
+
+/// Header names (lowercased, letters and digits only) for each field
+const TIME_HEADERS: &[&str] = &["time", "datetime", "eventtime", "timestamp", "recordtime"];
+const DATE_HEADERS: &[&str] = &["date", "eventdate"];
+const PIN_HEADERS: &[&str] = &["pin", "userid", "personnelid", "personid", "employeeid", "empcode"];
+const CARD_HEADERS: &[&str] = &["cardno", "cardnumber", "card"];
+const DOOR_HEADERS: &[&str] = &["door", "doorname", "eventpoint", "reader", "readername", "device"];
+const EVENT_HEADERS: &[&str] = &["event", "eventdescription", "eventname", "description"];
+const DIRECTION_HEADERS: &[&str] = &["inoutstatus", "inout", "direction"];
+
+/// Date-time layouts seen in exports, tried in order. Day/month order of
+/// slashed dates is settled by `month_first`.
+const ISO_FORMATS: &[&str] = &[
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M",
+    "%Y/%m/%d %H:%M:%S",
+    "%Y/%m/%d %H:%M",
+];
+const DAY_FIRST_FORMATS: &[&str] = &["%d/%m/%Y %H:%M:%S", "%d/%m/%Y %H:%M", "%d-%m-%Y %H:%M:%S", "%d-%m-%Y %H:%M"];
+const MONTH_FIRST_FORMATS: &[&str] = &["%m/%d/%Y %H:%M:%S", "%m/%d/%Y %H:%M", "%m-%d-%Y %H:%M:%S", "%m-%d-%Y %H:%M"];
+
+/// Column positions found in the header line
+struct Columns {
+    time: Option<usize>,
+    date: Option<usize>,
+    pin: Option<usize>,
+    card: Option<usize>,
+    door: Option<usize>,
+    event: Option<usize>,
+    direction: Option<usize>,
+}
+
+impl Columns {
+    fn find(headers: &[String]) -> Result<Self, String> {
+        let keys: Vec<String> = headers
+            .iter()
+            .map(|h| h.chars().filter(|c| c.is_ascii_alphanumeric()).collect::<String>().to_lowercase())
+            .collect();
+        let find = |names: &[&str]| keys.iter().position(|k| names.contains(&k.as_str()));
+        let (time, date) = match (find(TIME_HEADERS), find(DATE_HEADERS)) {
+            // A lone date column holds the full date and time
+            (None, date) => (date, None),
+            found => found,
+        };
+        let columns = Self {
+            time,
+            date,
+            pin: find(PIN_HEADERS),
+            card: find(CARD_HEADERS),
+            door: find(DOOR_HEADERS),
+            event: find(EVENT_HEADERS),
+            direction: find(DIRECTION_HEADERS),
+        };
+        if columns.time.is_none() {
+            return Err("The header line has no time column".to_string());
+        }
+        if columns.pin.is_none() && columns.card.is_none() {
+            return Err("The header line has no PIN or card number column".to_string());
+        }
+        Ok(columns)
+    }
+}
+
+/// A panel timestamp in the format of attendance logs. Offsets are
+/// converted to this PC's local time; timestamps without one are taken as
+/// local already.
+pub fn normalize_timestamp(value: &str, month_first: bool) -> Option<String> {
+    let value = value.trim();
+    if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+        return Some(dt.with_timezone(&Local).naive_local().format(TIMESTAMP_FORMAT).to_string());
+    }
+    let value = value.trim_end_matches('Z');
+    let slashed = if month_first { MONTH_FIRST_FORMATS } else { DAY_FIRST_FORMATS };
+    ISO_FORMATS
+        .iter()
+        .chain(slashed)
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .map(|time| time.format(TIMESTAMP_FORMAT).to_string())
+}
+
+/// A trimmed, non-empty field
+fn field(fields: &[String], column: Option<usize>) -> Option<String> {
+    column
+        .and_then(|i| fields.get(i))
+        .map(|f| f.trim().to_string())
+        .filter(|f| !f.is_empty())
+}
+
+/// Panels write the direction as "In"/"Out", "Entry"/"Exit" or 0/1
+fn direction(value: Option<String>) -> Option<String> {
+    match value?.to_lowercase().as_str() {
+        "in" | "entry" | "enter" | "0" => Some("in".to_string()),
+        "out" | "exit" | "1" => Some("out".to_string()),
+        _ => None,
+    }
+}
+
+/// Events in CSV text, with the lines that couldn't be read. The first
+/// non-empty line must be the header.
+pub fn parse(content: &str, month_first: bool) -> Result<(Vec<NewAccessEvent>, Vec<AccessImportError>), String> {
+    let mut lines = content
+        .trim_start_matches('\u{FEFF}')
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty());
+    let Some((_, header)) = lines.next() else {
+        return Err("The file is empty".to_string());
+    };
+    let columns = Columns::find(&split_line(header))?;
+
+    let mut events = Vec::new();
+    let mut errors = Vec::new();
+    for (i, line) in lines {
+        let fields = split_line(line);
+        let time = match (field(&fields, columns.date), field(&fields, columns.time)) {
+            // Some exports split the date and the time of day into two columns
+            (Some(date), Some(time)) if !time.contains(['-', '/']) => format!("{} {}", date, time),
+            (_, Some(time)) => time,
+            _ => {
+                errors.push(AccessImportError { line: i + 1, message: "Missing time".to_string() });
+                continue;
+            }
+        };
+        let Some(timestamp) = normalize_timestamp(&time, month_first) else {
+            errors.push(AccessImportError { line: i + 1, message: format!("Invalid time: {}", time) });
+            continue;
+        };
+        let event = NewAccessEvent {
+            timestamp,
+            device_user_id: field(&fields, columns.pin),
+            card_number: field(&fields, columns.card),
+            door: field(&fields, columns.door),
+            event: field(&fields, columns.event),
+            direction: direction(field(&fields, columns.direction)),
+        };
+        // Door-level events (exit button, alarms) have no PIN or card but are kept
+        if event.device_user_id.is_none() && event.card_number.is_none() && event.event.is_none() {
+            errors.push(AccessImportError { line: i + 1, message: "No PIN, card or event".to_string() });
+            continue;
+        }
+        events.push(event);
+    }
+    Ok((events, errors))
+}
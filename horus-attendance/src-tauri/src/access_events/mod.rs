@@ -0,0 +1,17 @@
+//! Door controller events
+//!
+//! Sites with a ZKTeco access-control panel next to the attendance
+//! terminals can bring its event log in for investigations: was the person
+//! who punched in actually let through the door, and did anyone badge in
+//! without punching? Panels speak the PullSDK protocol rather than the
+//! terminal protocol in `zkteco`, so events arrive either as the CSV the
+//! panel software exports or as JSON pushed by a connector. They are kept in
+//! `access_events`, apart from attendance logs, and never change summaries.
+//! The reconciliation pairs each event with the nearest punch by the same
+//! person within a time window.
+
+pub mod commands;
+pub mod import;
+pub mod reconcile;
+pub mod store;
+pub mod types;
@@ -0,0 +1,182 @@
+//! Pairing access events with attendance punches
+//!
+//! Events are attributed by PIN, which panels share with the terminals'
+//! device user IDs; events with only a card number can't be attributed.
+//! Each event is paired with the same person's nearest punch within the
+//! window, and a punch is paired with at most one event.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use rusqlite::{params, Connection};
+
+use super::store;
+use super::types::{AccessReconciliation, ReconciledEvent, UnmatchedPunch};
+use crate::db::logs::{day_bounds, not_voided_sql};
+use crate::quarantine::validate::parse_timestamp;
+
+pub const STATUS_MATCHED: &str = "matched";
+pub const STATUS_NO_PUNCH: &str = "no_punch";
+pub const STATUS_UNKNOWN_PERSON: &str = "unknown_person";
+
+/// Widest window accepted, so punches a day either side cover it
+pub const MAX_WINDOW_MINUTES: u32 = 240;
+
+struct Punch {
+    log_id: String,
+    device_user_id: String,
+    timestamp: String,
+    time: NaiveDateTime,
+    device_name: Option<String>,
+}
+
+/// User ID and display name by device user ID, own and linked
+fn people(conn: &Connection) -> Result<HashMap<String, (String, String)>, String> {
+    let mut stmt = conn
+        .prepare_cached(
+            "SELECT device_user_id, id, display_name FROM users WHERE COALESCE(device_user_id, '') != ''
+             UNION ALL
+             SELECT k.device_user_id, u.id, u.display_name
+             FROM user_device_links k JOIN users u ON u.id = k.user_id",
+        )
+        .map_err(|e| format!("Failed to query users: {}", e))?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, (row.get(1)?, row.get(2)?))))
+        .map_err(|e| format!("Failed to query users: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read users: {}", e))
+}
+
+/// Punches not voided by a correction, from the day before `start_date`
+/// to the day after `end_date` so events near the edges can be paired
+fn punches(conn: &Connection, start_date: &str, end_date: &str) -> Result<Vec<Punch>, String> {
+    let shift = |date: &str, days: i64| {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map(|d| (d + Duration::days(days)).format("%Y-%m-%d").to_string())
+            .unwrap_or_else(|_| date.to_string())
+    };
+    let (start, end) = day_bounds(&shift(start_date, -1), &shift(end_date, 1));
+    let mut stmt = conn
+        .prepare_cached(&format!(
+            "SELECT l.id, l.device_user_id, l.timestamp, dv.name
+             FROM attendance_logs_raw l
+             LEFT JOIN devices dv ON dv.id = l.device_id
+             WHERE l.timestamp >= ?1 AND l.timestamp < ?2 AND {}
+             ORDER BY l.timestamp",
+            not_voided_sql("l.id")
+        ))
+        .map_err(|e| format!("Failed to query punches: {}", e))?;
+    let rows = stmt
+        .query_map(params![start, end], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?, row.get(3)?))
+        })
+        .map_err(|e| format!("Failed to query punches: {}", e))?;
+    let mut punches = Vec::new();
+    for row in rows {
+        let (log_id, device_user_id, timestamp, device_name) =
+            row.map_err(|e| format!("Failed to read punches: {}", e))?;
+        if let Some(time) = parse_timestamp(&timestamp) {
+            punches.push(Punch { log_id, device_user_id, timestamp, time, device_name });
+        }
+    }
+    Ok(punches)
+}
+
+/// Pair events between two dates (inclusive) with punches no more than
+/// `window_minutes` apart, optionally for one user
+pub fn reconcile(
+    conn: &Connection,
+    start_date: &str,
+    end_date: &str,
+    user_id: Option<&str>,
+    window_minutes: u32,
+) -> Result<AccessReconciliation, String> {
+    let window_minutes = window_minutes.clamp(1, MAX_WINDOW_MINUTES);
+    let window = Duration::minutes(window_minutes as i64);
+    let people = people(conn)?;
+    // Punches are keyed by user, or by device user ID when unlinked
+    let key = |device_user_id: &str| {
+        people.get(device_user_id).map_or_else(|| device_user_id.to_string(), |(id, _)| id.clone())
+    };
+    let mut by_person: HashMap<String, Vec<Punch>> = HashMap::new();
+    for punch in punches(conn, start_date, end_date)? {
+        by_person.entry(key(&punch.device_user_id)).or_default().push(punch);
+    }
+
+    let events = store::between(conn, start_date, end_date)?;
+
+    let mut used: HashSet<String> = HashSet::new();
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut report = AccessReconciliation {
+        start_date: start_date.to_string(),
+        end_date: end_date.to_string(),
+        window_minutes,
+        matched: 0,
+        no_punch: 0,
+        unknown_person: 0,
+        events: Vec::new(),
+        unmatched_punches: Vec::new(),
+    };
+    for event in events {
+        let person = event.device_user_id.as_deref().and_then(|pin| people.get(pin));
+        if user_id.is_some() && person.map(|(id, _)| id.as_str()) != user_id {
+            continue;
+        }
+        let person_key = event.device_user_id.as_deref().map(&key);
+        let nearest = match (&person_key, parse_timestamp(&event.timestamp)) {
+            (Some(k), Some(time)) => by_person.get(k).and_then(|list| {
+                list.iter()
+                    .filter(|p| !used.contains(&p.log_id) && (p.time - time).abs() <= window)
+                    .min_by_key(|p| (p.time - time).abs())
+                    .map(|p| (p, (p.time - time).num_seconds()))
+            }),
+            _ => None,
+        };
+        let status = match (&nearest, person) {
+            (Some(_), _) => STATUS_MATCHED,
+            (None, Some(_)) => STATUS_NO_PUNCH,
+            (None, None) => STATUS_UNKNOWN_PERSON,
+        };
+        match status {
+            STATUS_MATCHED => report.matched += 1,
+            STATUS_NO_PUNCH => report.no_punch += 1,
+            _ => report.unknown_person += 1,
+        }
+        if let Some((id, _)) = person {
+            seen.insert(id.clone());
+        }
+        if let Some((punch, _)) = &nearest {
+            used.insert(punch.log_id.clone());
+        }
+        report.events.push(ReconciledEvent {
+            user_id: person.map(|(id, _)| id.clone()),
+            display_name: person.map(|(_, name)| name.clone()),
+            punch_log_id: nearest.as_ref().map(|(p, _)| p.log_id.clone()),
+            punch_timestamp: nearest.as_ref().map(|(p, _)| p.timestamp.clone()),
+            punch_device_name: nearest.as_ref().and_then(|(p, _)| p.device_name.clone()),
+            offset_seconds: nearest.as_ref().map(|(_, offset)| *offset),
+            status: status.to_string(),
+            event,
+        });
+    }
+
+    let (start, end) = day_bounds(start_date, end_date);
+    for user in &seen {
+        let Some(list) = by_person.get(user) else { continue };
+        for punch in list {
+            if used.contains(&punch.log_id) || punch.timestamp < start || punch.timestamp >= end {
+                continue;
+            }
+            report.unmatched_punches.push(UnmatchedPunch {
+                log_id: punch.log_id.clone(),
+                user_id: user.clone(),
+                display_name: people.get(&punch.device_user_id).map_or_else(String::new, |(_, name)| name.clone()),
+                device_user_id: punch.device_user_id.clone(),
+                device_name: punch.device_name.clone(),
+                timestamp: punch.timestamp.clone(),
+            });
+        }
+    }
+    report.unmatched_punches.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+    Ok(report)
+}
@@ -0,0 +1,100 @@
+//! Storing and listing access events
+
+use rusqlite::{params, Connection, Row};
+
+use super::types::{AccessEvent, NewAccessEvent};
+use crate::db::logs::day_bounds;
+
+/// Most events listed at once
+pub const MAX_LIMIT: u32 = 5000;
+
+const COLUMNS: &str = "id, source, timestamp, device_user_id, card_number, door, event, direction";
+
+fn row_to_event(row: &Row) -> rusqlite::Result<AccessEvent> {
+    Ok(AccessEvent {
+        id: row.get(0)?,
+        source: row.get(1)?,
+        timestamp: row.get(2)?,
+        device_user_id: row.get(3)?,
+        card_number: row.get(4)?,
+        door: row.get(5)?,
+        event: row.get(6)?,
+        direction: row.get(7)?,
+    })
+}
+
+/// Store events from one source, skipping ones already stored. Returns how
+/// many were new and how many were duplicates.
+pub fn insert(conn: &mut Connection, source: &str, events: &[NewAccessEvent]) -> Result<(u32, u32), String> {
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    let mut imported = 0;
+    {
+        let mut stmt = tx
+            .prepare_cached(
+                "INSERT OR IGNORE INTO access_events
+                     (id, source, timestamp, device_user_id, card_number, door, event, direction)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            )
+            .map_err(|e| format!("Failed to prepare access event insert: {}", e))?;
+        for event in events {
+            imported += stmt
+                .execute(params![
+                    uuid::Uuid::new_v4().to_string(),
+                    source,
+                    event.timestamp,
+                    event.device_user_id,
+                    event.card_number,
+                    event.door,
+                    event.event,
+                    event.direction,
+                ])
+                .map_err(|e| format!("Failed to store access event: {}", e))? as u32;
+        }
+    }
+    tx.commit()
+        .map_err(|e| format!("Failed to commit access events: {}", e))?;
+    Ok((imported, events.len() as u32 - imported))
+}
+
+/// Events between two dates (inclusive), oldest first, optionally for one PIN
+pub fn list(
+    conn: &Connection,
+    start_date: &str,
+    end_date: &str,
+    device_user_id: Option<&str>,
+    limit: u32,
+) -> Result<Vec<AccessEvent>, String> {
+    let (start, end) = day_bounds(start_date, end_date);
+    let mut stmt = conn
+        .prepare_cached(&format!(
+            "SELECT {} FROM access_events
+             WHERE timestamp >= ?1 AND timestamp < ?2 AND (?3 IS NULL OR device_user_id = ?3)
+             ORDER BY timestamp, id
+             LIMIT ?4",
+            COLUMNS
+        ))
+        .map_err(|e| format!("Failed to query access events: {}", e))?;
+    let rows = stmt
+        .query_map(params![start, end, device_user_id, limit.clamp(1, MAX_LIMIT)], row_to_event)
+        .map_err(|e| format!("Failed to query access events: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read access events: {}", e))
+}
+
+/// Every event between two dates (inclusive), oldest first
+pub fn between(conn: &Connection, start_date: &str, end_date: &str) -> Result<Vec<AccessEvent>, String> {
+    let (start, end) = day_bounds(start_date, end_date);
+    let mut stmt = conn
+        .prepare_cached(&format!(
+            "SELECT {} FROM access_events WHERE timestamp >= ?1 AND timestamp < ?2 ORDER BY timestamp, id",
+            COLUMNS
+        ))
+        .map_err(|e| format!("Failed to query access events: {}", e))?;
+    let rows = stmt
+        .query_map(params![start, end], row_to_event)
+        .map_err(|e| format!("Failed to query access events: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read access events: {}", e))
+}
@@ -0,0 +1,96 @@
+//! Access event data types for Tauri command serialization
+
+use serde::{Deserialize, Serialize};
+
+/// An event as ingested from a connector. The timestamp is panel-local,
+/// ISO 8601 or `YYYY-MM-DD HH:MM:SS`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewAccessEvent {
+    pub timestamp: String,
+    /// The panel's PIN for the person, matching their device user ID
+    pub device_user_id: Option<String>,
+    pub card_number: Option<String>,
+    pub door: Option<String>,
+    /// The panel's description, e.g. "Normal Verify Open"
+    pub event: Option<String>,
+    /// "in" or "out" when the reader reports it
+    pub direction: Option<String>,
+}
+
+/// A stored access event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessEvent {
+    pub id: String,
+    /// Name of the panel or export the event came from
+    pub source: String,
+    pub timestamp: String,
+    pub device_user_id: Option<String>,
+    pub card_number: Option<String>,
+    pub door: Option<String>,
+    pub event: Option<String>,
+    pub direction: Option<String>,
+}
+
+/// A CSV line or ingested event that could not be stored
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessImportError {
+    /// 1-based line number, or position in the ingested list
+    pub line: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessImportResult {
+    pub imported: u32,
+    /// Events already stored from an earlier import
+    pub duplicates: u32,
+    pub errors: Vec<AccessImportError>,
+}
+
+/// An access event with the punch it was paired with
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReconciledEvent {
+    pub event: AccessEvent,
+    /// None when the PIN or card matches nobody
+    pub user_id: Option<String>,
+    pub display_name: Option<String>,
+    pub punch_log_id: Option<String>,
+    pub punch_timestamp: Option<String>,
+    pub punch_device_name: Option<String>,
+    /// Punch time minus event time
+    pub offset_seconds: Option<i64>,
+    /// "matched", "no_punch" or "unknown_person"
+    pub status: String,
+}
+
+/// A punch no access event was paired with
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnmatchedPunch {
+    pub log_id: String,
+    pub user_id: String,
+    pub display_name: String,
+    pub device_user_id: String,
+    pub device_name: Option<String>,
+    pub timestamp: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessReconciliation {
+    pub start_date: String,
+    pub end_date: String,
+    pub window_minutes: u32,
+    pub matched: u32,
+    pub no_punch: u32,
+    pub unknown_person: u32,
+    pub events: Vec<ReconciledEvent>,
+    /// Punches by people with access events in the period that no event
+    /// was paired with
+    pub unmatched_punches: Vec<UnmatchedPunch>,
+}
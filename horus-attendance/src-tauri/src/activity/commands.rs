@@ -0,0 +1,25 @@
+//! Tauri command handlers for the activity registry.
+
+use tauri::State;
+
+use super::registry::ActivityRegistry;
+use super::types::ActivityStatus;
+
+/// Register work the frontend is about to run (e.g. "Device sync"). Fails
+/// while a backup or restore is in progress. Pass the ID to `end_activity`.
+#[tauri::command]
+pub fn begin_activity(registry: State<'_, ActivityRegistry>, label: String) -> Result<u64, String> {
+    registry.begin_external(&label)
+}
+
+/// Mark registered work as finished
+#[tauri::command]
+pub fn end_activity(registry: State<'_, ActivityRegistry>, activity_id: u64) {
+    registry.end(activity_id);
+}
+
+/// Running work, and the backup or restore holding new work off, if any
+#[tauri::command]
+pub fn get_activity_status(registry: State<'_, ActivityRegistry>) -> Result<ActivityStatus, String> {
+    registry.status()
+}
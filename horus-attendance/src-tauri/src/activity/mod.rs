@@ -0,0 +1,15 @@
+//! Registry of running write activity
+//!
+//! Anything that writes to the database for a while (a device sync, summary
+//! recomputation, anomaly checks, API punches) registers here for as long as
+//! it runs. Backup and restore go through [`registry::ActivityRegistry::pause`]
+//! first: new activity is turned away, running activity gets a bounded time to
+//! finish, and the WAL is checkpointed, so the copy or file swap doesn't race a
+//! writer and fail with "database is locked" halfway through.
+//!
+//! Background jobs skip their turn while paused; user-started work gets an
+//! error saying what is in progress.
+
+pub mod commands;
+pub mod registry;
+pub mod types;
@@ -0,0 +1,205 @@
+//! The registry itself, held as Tauri managed state
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tauri::Manager;
+
+use super::types::{Activity, ActivityStatus};
+use crate::db;
+
+/// How long backup/restore waits for running work to finish
+pub const DRAIN_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Work registered by the frontend is dropped after this long without being
+/// ended, so a reloaded window can't block backups forever
+const EXTERNAL_STALE_AFTER: Duration = Duration::from_secs(2 * 60 * 60);
+
+struct Entry {
+    activity: Activity,
+    started: Instant,
+    external: bool,
+}
+
+#[derive(Default)]
+struct State {
+    active: BTreeMap<u64, Entry>,
+    next_id: u64,
+    paused_for: Option<String>,
+}
+
+#[derive(Default)]
+struct Shared {
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> Result<MutexGuard<'_, State>, String> {
+        self.state.lock().map_err(|_| "Activity registry is poisoned".to_string())
+    }
+
+    fn end(&self, id: u64) {
+        if let Ok(mut state) = self.state.lock() {
+            state.active.remove(&id);
+        }
+        self.changed.notify_all();
+    }
+}
+
+/// Running write activity and the maintenance pause
+#[derive(Default, Clone)]
+pub struct ActivityRegistry(Arc<Shared>);
+
+impl ActivityRegistry {
+    /// Register work that lasts as long as the returned guard. Fails while
+    /// backup or restore has paused new work.
+    pub fn begin(&self, label: &str) -> Result<ActivityGuard, String> {
+        let id = self.register(label, false)?;
+        Ok(ActivityGuard {
+            id,
+            shared: Arc::clone(&self.0),
+        })
+    }
+
+    /// Register work the frontend runs; it must call [`Self::end`] with the ID
+    pub fn begin_external(&self, label: &str) -> Result<u64, String> {
+        self.register(label, true)
+    }
+
+    pub fn end(&self, id: u64) {
+        self.0.end(id);
+    }
+
+    fn register(&self, label: &str, external: bool) -> Result<u64, String> {
+        let mut state = self.0.lock()?;
+        if let Some(operation) = &state.paused_for {
+            return Err(format!("{} is in progress; try again when it finishes", operation));
+        }
+        state.next_id += 1;
+        let id = state.next_id;
+        state.active.insert(
+            id,
+            Entry {
+                activity: Activity {
+                    id,
+                    label: label.to_string(),
+                    started_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+                },
+                started: Instant::now(),
+                external,
+            },
+        );
+        Ok(id)
+    }
+
+    pub fn status(&self) -> Result<ActivityStatus, String> {
+        let state = self.0.lock()?;
+        Ok(ActivityStatus {
+            active: state.active.values().map(|e| e.activity.clone()).collect(),
+            paused_for: state.paused_for.clone(),
+        })
+    }
+
+    /// Hold off new work and wait up to `timeout` for running work to end.
+    /// New work stays held off until the returned guard is dropped.
+    pub fn pause(&self, operation: &str, timeout: Duration) -> Result<PauseGuard, String> {
+        let mut state = self.0.lock()?;
+        if let Some(other) = &state.paused_for {
+            return Err(format!("{} could not start: {} is already in progress", operation, other));
+        }
+        state.paused_for = Some(operation.to_string());
+        state.active.retain(|_, e| {
+            let stale = e.external && e.started.elapsed() > EXTERNAL_STALE_AFTER;
+            if stale {
+                log::warn!("[activity] Dropping stale '{}' started at {}", e.activity.label, e.activity.started_at);
+            }
+            !stale
+        });
+        if !state.active.is_empty() {
+            log::info!("[activity] {} waiting for {} running task(s)", operation, state.active.len());
+        }
+
+        let (mut state, _) = self
+            .0
+            .changed
+            .wait_timeout_while(state, timeout, |s| !s.active.is_empty())
+            .map_err(|_| "Activity registry is poisoned".to_string())?;
+        if !state.active.is_empty() {
+            let running: Vec<&str> = state.active.values().map(|e| e.activity.label.as_str()).collect();
+            let message = format!(
+                "{} could not start: {} did not finish within {} seconds. Try again once it completes.",
+                operation,
+                running.join(", "),
+                timeout.as_secs()
+            );
+            state.paused_for = None;
+            drop(state);
+            self.0.changed.notify_all();
+            return Err(message);
+        }
+        Ok(PauseGuard {
+            shared: Arc::clone(&self.0),
+        })
+    }
+}
+
+/// Ends its activity when dropped
+pub struct ActivityGuard {
+    id: u64,
+    shared: Arc<Shared>,
+}
+
+impl Drop for ActivityGuard {
+    fn drop(&mut self) {
+        self.shared.end(self.id);
+    }
+}
+
+/// Lets new work start again when dropped
+pub struct PauseGuard {
+    shared: Arc<Shared>,
+}
+
+impl Drop for PauseGuard {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.shared.state.lock() {
+            state.paused_for = None;
+        }
+        self.shared.changed.notify_all();
+    }
+}
+
+/// Register write work for as long as the returned guard lives
+pub fn begin(app: &tauri::AppHandle, label: &str) -> Result<ActivityGuard, String> {
+    app.state::<ActivityRegistry>().begin(label)
+}
+
+/// Get the database ready for `operation` (a backup, restore, ...): pause
+/// new work, wait for running work to finish, then checkpoint the WAL so the
+/// main file is complete. Hold the guard until the operation is done.
+pub async fn quiesce(app: &tauri::AppHandle, operation: &str) -> Result<PauseGuard, String> {
+    quiesce_within(app, operation, DRAIN_TIMEOUT).await
+}
+
+/// [`quiesce`], waiting at most `timeout` for running work
+pub async fn quiesce_within(app: &tauri::AppHandle, operation: &str, timeout: Duration) -> Result<PauseGuard, String> {
+    let registry = app.state::<ActivityRegistry>().inner().clone();
+    let label = operation.to_string();
+    let guard = tauri::async_runtime::spawn_blocking(move || registry.pause(&label, timeout))
+        .await
+        .map_err(|e| format!("{} could not start: {}", operation, e))??;
+
+    if crate::get_db_path(app)?.exists() {
+        let conn = db::open(app)?;
+        let (busy, pages) = conn
+            .query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)))
+            .map_err(|e| format!("{} could not checkpoint the database: {}", operation, e))?;
+        if busy != 0 {
+            // The frontend's connection still has a read open; the copy is
+            // still consistent, the WAL just isn't emptied
+            log::warn!("[activity] Checkpoint before {} left {} pages in the WAL", operation, pages);
+        }
+    }
+    Ok(guard)
+}
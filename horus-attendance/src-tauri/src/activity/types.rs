@@ -0,0 +1,22 @@
+//! Types for the activity registry
+
+use serde::{Deserialize, Serialize};
+
+/// A registered piece of running work
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Activity {
+    pub id: u64,
+    /// What is running, shown in errors (e.g. "Device sync")
+    pub label: String,
+    pub started_at: String,
+}
+
+/// What is running and whether new work is being held off
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivityStatus {
+    pub active: Vec<Activity>,
+    /// The maintenance operation (backup, restore) holding new work off
+    pub paused_for: Option<String>,
+}
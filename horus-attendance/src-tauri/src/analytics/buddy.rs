@@ -0,0 +1,179 @@
+//! Buddy-punching heuristics
+//!
+//! Flags patterns that suggest one employee punching for another: pairs who
+//! keep punching on the same device within seconds of each other, and
+//! fingerprint-enrolled users punching with a card only. The result is a
+//! report for review; nothing is changed or flagged automatically.
+
+use rusqlite::types::Value;
+use rusqlite::{params_from_iter, Connection};
+
+use super::scope;
+use super::types::{BuddyPunchOptions, BuddyPunchReport, CardOnlyUser, DateRange, Scope, SuspiciousPair};
+use crate::db;
+use crate::summary::{engine, rules};
+
+/// ZKTeco verify modes stored in attendance_logs_raw.verify_type
+const VERIFY_FINGERPRINT: i64 = 1;
+const VERIFY_CARD: i64 = 2;
+
+/// Build the report for punches between two dates (inclusive)
+pub fn report(
+    conn: &Connection,
+    period: &DateRange,
+    scope: &Scope,
+    options: &BuddyPunchOptions,
+) -> Result<BuddyPunchReport, String> {
+    // Same bounds as the summary engine so the end date is fully included
+    let cutoff_hour = engine::day_cutoff_hour(conn)?;
+    let (start, end) = db::logs::working_day_bounds(&period.start_date, &period.end_date, cutoff_hour);
+
+    Ok(BuddyPunchReport {
+        period: period.clone(),
+        options: options.clone(),
+        pairs: pairs(conn, &start, &end, cutoff_hour, scope, options)?,
+        card_only: card_only(conn, &start, &end, scope, options)?,
+    })
+}
+
+fn pairs(
+    conn: &Connection,
+    start: &str,
+    end: &str,
+    cutoff_hour: u32,
+    scope: &Scope,
+    options: &BuddyPunchOptions,
+) -> Result<Vec<SuspiciousPair>, String> {
+    let (scope_sql, scope_params) = scope::condition(scope, "a.device_user_id", "device_user_id", 5);
+    // b is the next punch on the same device within the window; the upper
+    // bound on the text timestamp lets the (device_id, timestamp) index do
+    // the work instead of comparing every punch of the day
+    let sql = format!(
+        "WITH logs AS (
+             SELECT id, device_id, device_user_id, timestamp FROM attendance_logs_raw
+             WHERE timestamp >= ?1 AND timestamp < ?2
+         ),
+         together AS (
+             SELECT MIN(a.device_user_id, b.device_user_id) AS u1,
+                    MAX(a.device_user_id, b.device_user_id) AS u2,
+                    {a_day} AS day,
+                    (julianday(b.timestamp) - julianday(a.timestamp)) * 86400 AS gap
+             FROM logs a
+             JOIN logs b
+               ON b.device_id = a.device_id
+              AND b.device_user_id != a.device_user_id
+              AND b.timestamp >= a.timestamp
+              AND b.timestamp <= strftime('%Y-%m-%dT%H:%M:%SZ', a.timestamp, '+' || ?3 || ' seconds')
+             WHERE {scope_sql}
+         ),
+         days AS (
+             SELECT DISTINCT device_user_id, {day} AS day FROM logs
+         )
+         SELECT t.u1, t.u2, COUNT(DISTINCT t.day), COUNT(*), AVG(t.gap),
+                (SELECT COUNT(*) FROM days x JOIN days y ON y.day = x.day
+                 WHERE x.device_user_id = t.u1 AND y.device_user_id = t.u2),
+                ua.id, ua.display_name, ub.id, ub.display_name
+         FROM together t
+         LEFT JOIN users ua ON ua.device_user_id = t.u1
+         LEFT JOIN users ub ON ub.device_user_id = t.u2
+         WHERE t.gap <= ?3
+         GROUP BY t.u1, t.u2
+         HAVING COUNT(DISTINCT t.day) >= ?4",
+        scope_sql = scope_sql,
+        a_day = rules::working_date_sql("a.timestamp", cutoff_hour),
+        day = rules::working_date_sql("timestamp", cutoff_hour),
+    );
+    let mut params = vec![
+        Value::Text(start.to_string()),
+        Value::Text(end.to_string()),
+        Value::Integer(options.window_seconds),
+        Value::Integer(options.min_shared_days),
+    ];
+    params.extend(scope_params);
+
+    let mut stmt = conn
+        .prepare(&sql)
+        .map_err(|e| format!("Failed to query punch pairs: {}", e))?;
+    let rows = stmt
+        .query_map(params_from_iter(params), |row| {
+            let shared_days: i64 = row.get(2)?;
+            let days_both_present: i64 = row.get(5)?;
+            Ok(SuspiciousPair {
+                device_user_ids: [row.get(0)?, row.get(1)?],
+                user_ids: [row.get(6)?, row.get(8)?],
+                names: [row.get(7)?, row.get(9)?],
+                shared_days,
+                days_both_present,
+                ratio: if days_both_present > 0 {
+                    (shared_days as f64 / days_both_present as f64 * 100.0).round() / 100.0
+                } else {
+                    0.0
+                },
+                punches_together: row.get(3)?,
+                average_gap_seconds: (row.get::<_, f64>(4)? * 10.0).round() / 10.0,
+            })
+        })
+        .map_err(|e| format!("Failed to query punch pairs: {}", e))?;
+
+    let mut pairs: Vec<SuspiciousPair> = rows
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read punch pairs: {}", e))?
+        .into_iter()
+        .filter(|p| p.ratio >= options.min_ratio)
+        .collect();
+    pairs.sort_by(|a, b| b.shared_days.cmp(&a.shared_days).then(b.ratio.total_cmp(&a.ratio)));
+    Ok(pairs)
+}
+
+fn card_only(
+    conn: &Connection,
+    start: &str,
+    end: &str,
+    scope: &Scope,
+    options: &BuddyPunchOptions,
+) -> Result<Vec<CardOnlyUser>, String> {
+    let (scope_sql, scope_params) = scope::condition(scope, "l.device_user_id", "device_user_id", 6);
+    // "Fingerprint-enrolled" is inferred from having verified by fingerprint
+    // at least once, since enrollment data isn't synced from the devices
+    let sql = format!(
+        "SELECT l.device_user_id,
+                SUM(l.verify_type = ?3), SUM(l.verify_type = ?4),
+                MAX(CASE WHEN l.verify_type = ?3 THEN l.timestamp END),
+                u.id, u.display_name
+         FROM attendance_logs_raw l
+         LEFT JOIN users u ON u.device_user_id = l.device_user_id
+         WHERE l.timestamp >= ?1 AND l.timestamp < ?2 AND {scope_sql}
+           AND EXISTS (SELECT 1 FROM attendance_logs_raw f
+                       WHERE f.device_user_id = l.device_user_id AND f.verify_type = ?4)
+         GROUP BY l.device_user_id
+         HAVING SUM(l.verify_type = ?3) >= ?5
+         ORDER BY SUM(l.verify_type = ?3) DESC",
+        scope_sql = scope_sql
+    );
+    let mut params = vec![
+        Value::Text(start.to_string()),
+        Value::Text(end.to_string()),
+        Value::Integer(VERIFY_CARD),
+        Value::Integer(VERIFY_FINGERPRINT),
+        Value::Integer(options.min_card_punches),
+    ];
+    params.extend(scope_params);
+
+    let mut stmt = conn
+        .prepare(&sql)
+        .map_err(|e| format!("Failed to query card punches: {}", e))?;
+    let rows = stmt
+        .query_map(params_from_iter(params), |row| {
+            Ok(CardOnlyUser {
+                device_user_id: row.get(0)?,
+                card_punches: row.get(1)?,
+                fingerprint_punches: row.get(2)?,
+                last_card_punch: row.get(3)?,
+                user_id: row.get(4)?,
+                name: row.get(5)?,
+            })
+        })
+        .map_err(|e| format!("Failed to query card punches: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read card punches: {}", e))
+}
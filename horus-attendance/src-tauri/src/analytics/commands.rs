@@ -0,0 +1,139 @@
+//! Tauri command handlers for dashboard analytics.
+
+use chrono::{Duration, Local, NaiveDate};
+
+use super::buddy;
+use super::compare;
+use super::cost_centers;
+use super::heatmap;
+use super::kpis;
+use super::types::*;
+use crate::db;
+use crate::report_cache::store::cached;
+use crate::summary::commands::validate_date;
+
+/// Validate an inclusive date range
+fn validate_range(range: &DateRange) -> Result<(), String> {
+    validate_date(&range.start_date)?;
+    validate_date(&range.end_date)?;
+    if range.start_date > range.end_date {
+        return Err("Start date must not be after end date".to_string());
+    }
+    Ok(())
+}
+
+/// Punch counts by weekday × hour for arrival-time heatmaps
+#[tauri::command]
+pub async fn get_punch_heatmap(
+    app: tauri::AppHandle,
+    date_range: DateRange,
+    scope: Option<Scope>,
+    arrivals_only: Option<bool>,
+) -> Result<PunchHeatmap, String> {
+    validate_range(&date_range)?;
+    let scope = scope.unwrap_or_default();
+    let conn = db::open(&app)?;
+    let arrivals_only = arrivals_only.unwrap_or(false);
+    tauri::async_runtime::spawn_blocking(move || {
+        let params = serde_json::json!({ "scope": scope, "arrivalsOnly": arrivals_only });
+        cached(&conn, "punch_heatmap", &params, &date_range.start_date, &date_range.end_date, || {
+            heatmap::punch_heatmap(&conn, &date_range.start_date, &date_range.end_date, &scope, arrivals_only)
+        })
+    })
+    .await
+    .map_err(|e| format!("Heatmap task failed: {}", e))?
+}
+
+/// Attendance rate, average arrival, average worked hours and late percentage
+/// for a period, with the change from the previous period of the same length
+#[tauri::command]
+pub async fn get_kpis(app: tauri::AppHandle, period: DateRange, scope: Option<Scope>) -> Result<Kpis, String> {
+    validate_range(&period)?;
+    let scope = scope.unwrap_or_default();
+    let conn = db::open(&app)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        // The previous period is compared too, and days after today don't count yet
+        let start = NaiveDate::parse_from_str(&period.start_date, "%Y-%m-%d").map_err(|e| e.to_string())?;
+        let end = NaiveDate::parse_from_str(&period.end_date, "%Y-%m-%d").map_err(|e| e.to_string())?;
+        let previous_start = (start - (end - start) - Duration::days(1)).format("%Y-%m-%d").to_string();
+        let today = Local::now().format("%Y-%m-%d").to_string();
+        let params = serde_json::json!({ "period": period, "scope": scope, "today": today });
+        cached(&conn, "kpis", &params, &previous_start, &period.end_date, || {
+            kpis::kpis(&conn, &period, &scope)
+        })
+    })
+    .await
+        .map_err(|e| format!("KPI task failed: {}", e))?
+}
+
+/// Worked hours, late arrivals and absences of two periods side by side, per
+/// user and per department, with the change from A to B
+#[tauri::command]
+pub async fn compare_periods(
+    app: tauri::AppHandle,
+    period_a: DateRange,
+    period_b: DateRange,
+    scope: Option<Scope>,
+) -> Result<PeriodComparison, String> {
+    validate_range(&period_a)?;
+    validate_range(&period_b)?;
+    let scope = scope.unwrap_or_default();
+    let conn = db::open(&app)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        // Keyed on the span covering both periods; days after today don't count yet
+        let start = period_a.start_date.as_str().min(&period_b.start_date).to_string();
+        let end = period_a.end_date.as_str().max(&period_b.end_date).to_string();
+        let today = Local::now().format("%Y-%m-%d").to_string();
+        let params = serde_json::json!({ "periodA": period_a, "periodB": period_b, "scope": scope, "today": today });
+        cached(&conn, "compare_periods", &params, &start, &end, || {
+            compare::compare_periods(&conn, &period_a, &period_b, &scope)
+        })
+    })
+    .await
+    .map_err(|e| format!("Period comparison task failed: {}", e))?
+}
+
+/// Worked hours, late arrivals and absences per cost center, each day booked
+/// to the cost center in effect for the user that day
+#[tauri::command]
+pub async fn get_cost_center_totals(
+    app: tauri::AppHandle,
+    period: DateRange,
+    scope: Option<Scope>,
+) -> Result<CostCenterReport, String> {
+    validate_range(&period)?;
+    let scope = scope.unwrap_or_default();
+    let conn = db::open(&app)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let today = Local::now().format("%Y-%m-%d").to_string();
+        let params = serde_json::json!({ "period": period, "scope": scope, "today": today });
+        cached(&conn, "cost_center_totals", &params, &period.start_date, &period.end_date, || {
+            cost_centers::totals(&conn, &period, &scope)
+        })
+    })
+    .await
+    .map_err(|e| format!("Cost center totals task failed: {}", e))?
+}
+
+/// Report pairs who habitually punch together and fingerprint users punching
+/// by card, for review. Nothing is flagged or changed automatically.
+#[tauri::command]
+pub async fn get_buddy_punching_report(
+    app: tauri::AppHandle,
+    date_range: DateRange,
+    scope: Option<Scope>,
+    options: Option<BuddyPunchOptions>,
+) -> Result<BuddyPunchReport, String> {
+    validate_range(&date_range)?;
+    let scope = scope.unwrap_or_default();
+    let options = options.unwrap_or_default();
+    let conn = db::open(&app)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let params = serde_json::json!({ "scope": scope, "options": options });
+        cached(&conn, "buddy_punching", &params, &date_range.start_date, &date_range.end_date, || {
+            buddy::report(&conn, &date_range, &scope, &options)
+        })
+    })
+    .await
+        .map_err(|e| format!("Buddy-punching report task failed: {}", e))?
+}
@@ -0,0 +1,160 @@
+//! Attendance of two periods side by side, per user and per department
+
+use rusqlite::types::Value;
+use rusqlite::{params_from_iter, Connection, Row};
+
+use super::kpis::{self, ARRIVAL_MINUTES, DEPARTURE_MINUTES};
+use super::scope;
+use super::types::*;
+use crate::db;
+use crate::summary::types::AttendanceRules;
+
+/// Active users in scope with their metrics per period ('a' or 'b'). A user
+/// has no `totals` row for a period without working days.
+/// Parameters: ?1/?2 working dates of A/B as JSON, scope from ?3.
+fn totals_sql(scope_sql: &str) -> String {
+    format!(
+        "WITH scoped AS (
+             SELECT id, display_name, department_id, {counts_absences} AS counts_absences
+             FROM users u WHERE status = 'active' AND {scope_sql}
+         ),
+         days AS (
+             SELECT 'a' AS period, value AS date FROM json_each(?1)
+             UNION ALL
+             SELECT 'b', value FROM json_each(?2)
+         ),
+         totals AS (
+             SELECT days.period, u.id AS user_id,
+                    COALESCE(SUM(CASE WHEN s.check_in_time IS NOT NULL AND s.check_out_time IS NOT NULL
+                                       AND {departure} > {arrival} THEN {departure} - {arrival} END), 0) AS worked,
+                    COALESCE(SUM(s.check_in_time IS NOT NULL AND (s.late_minutes > 0 OR s.status = 'late')), 0) AS late,
+                    COALESCE(SUM((s.user_id IS NULL AND u.counts_absences) OR s.status = 'absent'), 0) AS absent
+             FROM scoped u
+             CROSS JOIN days
+             LEFT JOIN attendance_day_summary s ON s.user_id = u.id AND s.date = days.date
+             GROUP BY days.period, u.id
+         )",
+        scope_sql = scope_sql,
+        counts_absences = db::users::counts_absences_sql("u"),
+        arrival = ARRIVAL_MINUTES,
+        departure = DEPARTURE_MINUTES,
+    )
+}
+
+/// Joins `totals` of both periods onto `scoped u`
+const PERIOD_JOINS: &str = "LEFT JOIN departments d ON d.id = u.department_id
+     LEFT JOIN totals a ON a.user_id = u.id AND a.period = 'a'
+     LEFT JOIN totals b ON b.user_id = u.id AND b.period = 'b'";
+
+/// Read worked minutes, late count and absences of A then B from `first`
+fn read_periods(row: &Row, first: usize) -> rusqlite::Result<(PeriodMetrics, PeriodMetrics)> {
+    let metrics = |offset: usize| -> rusqlite::Result<PeriodMetrics> {
+        let worked: f64 = row.get(first + offset)?;
+        Ok(PeriodMetrics {
+            worked_hours: kpis::round1(worked / 60.0),
+            late_count: row.get(first + offset + 1)?,
+            absence_days: row.get(first + offset + 2)?,
+        })
+    };
+    Ok((metrics(0)?, metrics(3)?))
+}
+
+fn delta(a: &PeriodMetrics, b: &PeriodMetrics) -> PeriodMetrics {
+    PeriodMetrics {
+        worked_hours: kpis::round1(b.worked_hours - a.worked_hours),
+        late_count: b.late_count - a.late_count,
+        absence_days: b.absence_days - a.absence_days,
+    }
+}
+
+/// Compare worked hours, late arrivals and absences of two periods for the
+/// users in scope. Only working days up to today count, as for the KPIs.
+pub fn compare_periods(
+    conn: &Connection,
+    period_a: &DateRange,
+    period_b: &DateRange,
+    scope: &Scope,
+) -> Result<PeriodComparison, String> {
+    let rules: AttendanceRules = db::get_json_setting(conn, "attendance")?.unwrap_or_default();
+    let dates_a = kpis::working_dates(conn, kpis::parse(&period_a.start_date)?, kpis::parse(&period_a.end_date)?, &rules)?;
+    let dates_b = kpis::working_dates(conn, kpis::parse(&period_b.start_date)?, kpis::parse(&period_b.end_date)?, &rules)?;
+
+    let (scope_sql, scope_params) = scope::condition(scope, "id", "id", 3);
+    let mut params = vec![
+        Value::Text(serde_json::to_string(&dates_a).map_err(|e| format!("Failed to encode dates: {}", e))?),
+        Value::Text(serde_json::to_string(&dates_b).map_err(|e| format!("Failed to encode dates: {}", e))?),
+    ];
+    params.extend(scope_params);
+    let totals = totals_sql(&scope_sql);
+
+    let user_sql = format!(
+        "{totals}
+         SELECT u.id, u.display_name, u.department_id, d.name,
+                COALESCE(a.worked, 0), COALESCE(a.late, 0), COALESCE(a.absent, 0),
+                COALESCE(b.worked, 0), COALESCE(b.late, 0), COALESCE(b.absent, 0)
+         FROM scoped u
+         {joins}
+         ORDER BY u.display_name COLLATE NOCASE, u.id",
+        totals = totals,
+        joins = PERIOD_JOINS,
+    );
+    let mut stmt = conn
+        .prepare(&user_sql)
+        .map_err(|e| format!("Failed to compare periods: {}", e))?;
+    let users = stmt
+        .query_map(params_from_iter(params.iter()), |row| {
+            let (period_a, period_b) = read_periods(row, 4)?;
+            Ok(UserPeriodComparison {
+                user_id: row.get(0)?,
+                name: row.get(1)?,
+                department_id: row.get(2)?,
+                department_name: row.get(3)?,
+                delta: delta(&period_a, &period_b),
+                period_a,
+                period_b,
+            })
+        })
+        .map_err(|e| format!("Failed to compare periods: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read period comparison: {}", e))?;
+
+    let department_sql = format!(
+        "{totals}
+         SELECT u.department_id, d.name, COUNT(*),
+                COALESCE(SUM(a.worked), 0), COALESCE(SUM(a.late), 0), COALESCE(SUM(a.absent), 0),
+                COALESCE(SUM(b.worked), 0), COALESCE(SUM(b.late), 0), COALESCE(SUM(b.absent), 0)
+         FROM scoped u
+         {joins}
+         GROUP BY u.department_id
+         ORDER BY d.name IS NULL, d.name COLLATE NOCASE",
+        totals = totals,
+        joins = PERIOD_JOINS,
+    );
+    let mut stmt = conn
+        .prepare(&department_sql)
+        .map_err(|e| format!("Failed to compare periods: {}", e))?;
+    let departments = stmt
+        .query_map(params_from_iter(params.iter()), |row| {
+            let (period_a, period_b) = read_periods(row, 3)?;
+            Ok(DepartmentPeriodComparison {
+                department_id: row.get(0)?,
+                department_name: row.get(1)?,
+                user_count: row.get(2)?,
+                delta: delta(&period_a, &period_b),
+                period_a,
+                period_b,
+            })
+        })
+        .map_err(|e| format!("Failed to compare periods: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read period comparison: {}", e))?;
+
+    Ok(PeriodComparison {
+        period_a: period_a.clone(),
+        period_b: period_b.clone(),
+        working_days_a: dates_a.len() as i64,
+        working_days_b: dates_b.len() as i64,
+        users,
+        departments,
+    })
+}
@@ -0,0 +1,82 @@
+//! Attendance grouped by cost center
+
+use rusqlite::types::Value;
+use rusqlite::{params_from_iter, Connection};
+
+use super::kpis::{self, ARRIVAL_MINUTES, DEPARTURE_MINUTES};
+use super::scope;
+use super::types::*;
+use crate::cost_centers::store::effective_sql;
+use crate::db;
+use crate::summary::types::AttendanceRules;
+
+/// Worked hours, late arrivals and absences of the users in scope, each
+/// working day booked to the cost center in effect for the user that day.
+/// Days without a cost center are grouped under `cost_center_id: None`.
+pub fn totals(conn: &Connection, period: &DateRange, scope: &Scope) -> Result<CostCenterReport, String> {
+    let rules: AttendanceRules = db::get_json_setting(conn, "attendance")?.unwrap_or_default();
+    let dates = kpis::working_dates(conn, kpis::parse(&period.start_date)?, kpis::parse(&period.end_date)?, &rules)?;
+
+    let (scope_sql, scope_params) = scope::condition(scope, "id", "id", 2);
+    let sql = format!(
+        "WITH scoped AS (
+             SELECT id, department_id, {counts_absences} AS counts_absences
+             FROM users u WHERE status = 'active' AND {scope_sql}
+         ),
+         booked AS (
+             SELECT u.id AS user_id, {cost_center} AS cost_center_id, s.user_id AS summary_user_id, u.counts_absences,
+                    s.check_in_time, s.check_out_time, s.late_minutes, s.status
+             FROM scoped u
+             CROSS JOIN json_each(?1) days
+             LEFT JOIN attendance_day_summary s ON s.user_id = u.id AND s.date = days.value
+         )
+         SELECT b.cost_center_id, cc.code, cc.name, COUNT(DISTINCT b.user_id),
+                COALESCE(SUM(b.status IN ('present', 'late', 'early_leave')), 0),
+                COALESCE(SUM(CASE WHEN check_in_time IS NOT NULL AND check_out_time IS NOT NULL
+                                   AND {departure} > {arrival} THEN {departure} - {arrival} END), 0),
+                COALESCE(SUM(check_in_time IS NOT NULL AND (b.late_minutes > 0 OR b.status = 'late')), 0),
+                COALESCE(SUM((b.summary_user_id IS NULL AND b.counts_absences) OR b.status = 'absent'), 0)
+         FROM booked b
+         LEFT JOIN cost_centers cc ON cc.id = b.cost_center_id
+         GROUP BY b.cost_center_id
+         ORDER BY cc.code IS NULL, cc.code",
+        scope_sql = scope_sql,
+        counts_absences = db::users::counts_absences_sql("u"),
+        cost_center = effective_sql("u.id", "u.department_id", "days.value"),
+        arrival = ARRIVAL_MINUTES,
+        departure = DEPARTURE_MINUTES,
+    );
+    let mut params = vec![Value::Text(
+        serde_json::to_string(&dates).map_err(|e| format!("Failed to encode dates: {}", e))?,
+    )];
+    params.extend(scope_params);
+
+    let mut stmt = conn
+        .prepare(&sql)
+        .map_err(|e| format!("Failed to group by cost center: {}", e))?;
+    let cost_centers = stmt
+        .query_map(params_from_iter(params), |row| {
+            let worked: f64 = row.get(5)?;
+            Ok(CostCenterTotals {
+                cost_center_id: row.get(0)?,
+                code: row.get(1)?,
+                name: row.get(2)?,
+                user_count: row.get(3)?,
+                days_present: row.get(4)?,
+                metrics: PeriodMetrics {
+                    worked_hours: kpis::round1(worked / 60.0),
+                    late_count: row.get(6)?,
+                    absence_days: row.get(7)?,
+                },
+            })
+        })
+        .map_err(|e| format!("Failed to group by cost center: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read cost center totals: {}", e))?;
+
+    Ok(CostCenterReport {
+        period: period.clone(),
+        working_days: dates.len() as i64,
+        cost_centers,
+    })
+}
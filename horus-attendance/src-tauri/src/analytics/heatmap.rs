@@ -0,0 +1,80 @@
+//! Weekday × hour punch heatmap
+
+use rusqlite::types::Value;
+use rusqlite::{params_from_iter, Connection};
+
+use super::scope;
+use super::types::{PunchHeatmap, Scope};
+use crate::db;
+use crate::summary::{engine, rules};
+
+/// Count punches between two dates (inclusive) per weekday and hour.
+/// With `arrivals_only`, only each user's first punch of the day counts.
+pub fn punch_heatmap(
+    conn: &Connection,
+    start_date: &str,
+    end_date: &str,
+    scope: &Scope,
+    arrivals_only: bool,
+) -> Result<PunchHeatmap, String> {
+    let (scope_sql, scope_params) = scope::condition(scope, "device_user_id", "device_user_id", 3);
+    let cutoff_hour = engine::day_cutoff_hour(conn)?;
+    let day = rules::working_date_sql("timestamp", cutoff_hour);
+    let source = if arrivals_only {
+        format!(
+            "SELECT MIN(timestamp) AS timestamp FROM attendance_logs_raw
+             WHERE timestamp >= ?1 AND timestamp < ?2 AND {}
+             GROUP BY device_user_id, {}",
+            scope_sql, day
+        )
+    } else {
+        format!(
+            "SELECT timestamp FROM attendance_logs_raw
+             WHERE timestamp >= ?1 AND timestamp < ?2 AND {}",
+            scope_sql
+        )
+    };
+    // Same bounds as the summary engine so the end date is fully included
+    let (start, end) = db::logs::working_day_bounds(start_date, end_date, cutoff_hour);
+    let mut params = vec![Value::Text(start), Value::Text(end)];
+    params.extend(scope_params);
+
+    // Weekday and hour come from the literal timestamp text: stored times are
+    // device wall-clock time and must not be shifted. The weekday is that of
+    // the working day, so a night shift's small hours stay on its start day.
+    let sql = format!(
+        "SELECT CAST(strftime('%w', {}) AS INTEGER) AS weekday,
+                CAST(substr(timestamp, 12, 2) AS INTEGER) AS hour,
+                COUNT(*)
+         FROM ({})
+         GROUP BY weekday, hour",
+        day, source
+    );
+    let mut stmt = conn
+        .prepare(&sql)
+        .map_err(|e| format!("Failed to query punch heatmap: {}", e))?;
+    let rows = stmt
+        .query_map(params_from_iter(params), |row| {
+            Ok((row.get::<_, Option<i64>>(0)?, row.get::<_, Option<i64>>(1)?, row.get::<_, i64>(2)?))
+        })
+        .map_err(|e| format!("Failed to query punch heatmap: {}", e))?;
+
+    let mut matrix = vec![vec![0i64; 24]; 7];
+    let mut total = 0;
+    for row in rows {
+        let (weekday, hour, count) = row.map_err(|e| format!("Failed to read punch heatmap: {}", e))?;
+        // Unparseable timestamps are skipped rather than failing the dashboard
+        if let (Some(w @ 0..=6), Some(h @ 0..=23)) = (weekday, hour) {
+            matrix[w as usize][h as usize] += count;
+            total += count;
+        }
+    }
+    let max = matrix.iter().flatten().copied().max().unwrap_or(0);
+
+    Ok(PunchHeatmap {
+        matrix,
+        max,
+        total,
+        arrivals_only,
+    })
+}
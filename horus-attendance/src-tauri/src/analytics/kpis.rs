@@ -0,0 +1,144 @@
+//! Attendance KPIs with comparison against the previous period
+
+use chrono::{Duration, Local, NaiveDate};
+use rusqlite::types::Value;
+use rusqlite::{params_from_iter, Connection};
+
+use super::scope;
+use super::types::{DateRange, KpiTrend, KpiValues, Kpis, Scope};
+use crate::db;
+use crate::summary::types::AttendanceRules;
+
+/// Minutes since midnight of a stored HH:MM time, in SQL
+pub(super) const ARRIVAL_MINUTES: &str =
+    "(CAST(substr(check_in_time, 1, 2) AS INTEGER) * 60 + CAST(substr(check_in_time, 4, 2) AS INTEGER))";
+pub(super) const DEPARTURE_MINUTES: &str =
+    "(CAST(substr(check_out_time, 1, 2) AS INTEGER) * 60 + CAST(substr(check_out_time, 4, 2) AS INTEGER))";
+
+/// Compute KPIs for a period and the equally long period just before it
+pub fn kpis(conn: &Connection, period: &DateRange, scope: &Scope) -> Result<Kpis, String> {
+    let start = parse(&period.start_date)?;
+    let end = parse(&period.end_date)?;
+    let length = (end - start).num_days() + 1;
+    let previous_end = start - Duration::days(1);
+    let previous_start = previous_end - Duration::days(length - 1);
+    let previous_period = DateRange {
+        start_date: previous_start.format("%Y-%m-%d").to_string(),
+        end_date: previous_end.format("%Y-%m-%d").to_string(),
+    };
+
+    let rules: AttendanceRules = db::get_json_setting(conn, "attendance")?.unwrap_or_default();
+    let current = period_values(conn, start, end, scope, &rules)?;
+    let previous = period_values(conn, previous_start, previous_end, scope, &rules)?;
+
+    let trend = KpiTrend {
+        attendance_rate: round1(current.attendance_rate - previous.attendance_rate),
+        average_arrival_minutes: difference(current.average_arrival_minutes, previous.average_arrival_minutes),
+        average_worked_hours: difference(current.average_worked_hours, previous.average_worked_hours),
+        late_percentage: round1(current.late_percentage - previous.late_percentage),
+    };
+    Ok(Kpis {
+        period: period.clone(),
+        previous_period,
+        current,
+        previous,
+        trend,
+    })
+}
+
+fn period_values(
+    conn: &Connection,
+    start: NaiveDate,
+    end: NaiveDate,
+    scope: &Scope,
+    rules: &AttendanceRules,
+) -> Result<KpiValues, String> {
+    let working_dates = working_dates(conn, start, end, rules)?;
+    let working_json = serde_json::to_string(&working_dates).map_err(|e| format!("Failed to encode dates: {}", e))?;
+
+    let (scope_sql, scope_params) = scope::condition(scope, "id", "id", 1);
+    let active_users: i64 = conn
+        .query_row(
+            &format!("SELECT COUNT(*) FROM users WHERE status = 'active' AND {}", scope_sql),
+            params_from_iter(scope_params),
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to count users: {}", e))?;
+
+    let (user_sql, user_params) = scope::condition(scope, "user_id", "id", 2);
+    let sql = format!(
+        "SELECT
+            COALESCE(SUM(status IN ('present', 'late', 'early_leave')), 0),
+            COALESCE(SUM(check_in_time IS NOT NULL), 0),
+            COALESCE(SUM(check_in_time IS NOT NULL AND (late_minutes > 0 OR status = 'late')), 0),
+            AVG(CASE WHEN check_in_time IS NOT NULL THEN {arrival} END),
+            AVG(CASE WHEN check_in_time IS NOT NULL AND check_out_time IS NOT NULL
+                      AND {departure} > {arrival} THEN {departure} - {arrival} END)
+         FROM attendance_day_summary
+         WHERE date IN (SELECT value FROM json_each(?1))
+           AND user_id IN (SELECT id FROM users WHERE status = 'active')
+           AND {user_sql}",
+        arrival = ARRIVAL_MINUTES,
+        departure = DEPARTURE_MINUTES,
+        user_sql = user_sql,
+    );
+    let mut params = vec![Value::Text(working_json)];
+    params.extend(user_params);
+    let (days_present, days_checked_in, days_late, arrival, worked): (i64, i64, i64, Option<f64>, Option<f64>) = conn
+        .query_row(&sql, params_from_iter(params), |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?))
+        })
+        .map_err(|e| format!("Failed to compute KPIs: {}", e))?;
+
+    let working_days = working_dates.len() as i64;
+    let expected_days = working_days * active_users;
+    Ok(KpiValues {
+        working_days,
+        expected_days,
+        days_present,
+        attendance_rate: percent(days_present, expected_days),
+        average_arrival: arrival.map(|m| {
+            let m = m.round() as i64;
+            format!("{:02}:{:02}", m / 60, m % 60)
+        }),
+        average_arrival_minutes: arrival.map(round1),
+        average_worked_hours: worked.map(|m| round1(m / 60.0)),
+        late_percentage: percent(days_late, days_checked_in),
+    })
+}
+
+/// Workdays between two dates that are not holidays for everyone, up to today
+pub(super) fn working_dates(conn: &Connection, start: NaiveDate, end: NaiveDate, rules: &AttendanceRules) -> Result<Vec<String>, String> {
+    let holidays = crate::holidays::store::global_dates(
+        conn,
+        &start.format("%Y-%m-%d").to_string(),
+        &end.format("%Y-%m-%d").to_string(),
+    )?;
+    let last = end.min(Local::now().date_naive());
+    Ok(start
+        .iter_days()
+        .take_while(|d| *d <= last)
+        .map(|d| d.format("%Y-%m-%d").to_string())
+        .filter(|d| crate::summary::rules::is_workday(d, rules) && !holidays.contains(d))
+        .collect())
+}
+
+pub(super) fn parse(date: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| format!("Invalid date (expected YYYY-MM-DD): {}", date))
+}
+
+fn percent(part: i64, whole: i64) -> f64 {
+    if whole > 0 {
+        round1(part as f64 * 100.0 / whole as f64)
+    } else {
+        0.0
+    }
+}
+
+fn difference(current: Option<f64>, previous: Option<f64>) -> Option<f64> {
+    Some(round1(current? - previous?))
+}
+
+pub(super) fn round1(value: f64) -> f64 {
+    (value * 10.0).round() / 10.0
+}
@@ -0,0 +1,13 @@
+//! Dashboard analytics
+//!
+//! Aggregates computed in SQL so the dashboard receives compact results
+//! instead of raw logs or a full period of summaries.
+
+pub mod buddy;
+pub mod commands;
+pub mod compare;
+pub mod cost_centers;
+pub mod heatmap;
+pub mod kpis;
+pub mod scope;
+pub mod types;
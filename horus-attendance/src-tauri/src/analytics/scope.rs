@@ -0,0 +1,36 @@
+//! SQL filters for an analytics [`Scope`]
+
+use rusqlite::types::Value;
+
+use super::types::Scope;
+
+/// Build a condition restricting `column` to users in the scope.
+/// `user_column` is the users column it refers to ("id" for summaries,
+/// "device_user_id" for raw logs). Placeholders are numbered from
+/// `first_param`, so the caller's own ?N parameters can come first.
+/// Returns ("1", []) for an empty scope.
+pub fn condition(scope: &Scope, column: &str, user_column: &str, first_param: usize) -> (String, Vec<Value>) {
+    let mut filters = Vec::new();
+    let mut params = Vec::new();
+    if let Some(department_id) = &scope.department_id {
+        filters.push(format!("department_id = ?{}", first_param));
+        params.push(Value::Text(department_id.clone()));
+    }
+    if !scope.user_ids.is_empty() {
+        let placeholders: Vec<String> = (0..scope.user_ids.len())
+            .map(|i| format!("?{}", first_param + params.len() + i))
+            .collect();
+        filters.push(format!("id IN ({})", placeholders.join(", ")));
+        params.extend(scope.user_ids.iter().cloned().map(Value::Text));
+    }
+    if filters.is_empty() {
+        return ("1".to_string(), params);
+    }
+    let sql = format!(
+        "{} IN (SELECT {} FROM users WHERE {})",
+        column,
+        user_column,
+        filters.join(" AND ")
+    );
+    (sql, params)
+}
@@ -0,0 +1,212 @@
+//! Analytics data types for Tauri command serialization
+
+use serde::{Deserialize, Serialize};
+
+pub use crate::export::types::DateRange;
+
+/// Which users an aggregate covers. Empty means everyone.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Scope {
+    #[serde(default)]
+    pub department_id: Option<String>,
+    #[serde(default)]
+    pub user_ids: Vec<String>,
+}
+
+/// Punch counts by weekday × hour
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PunchHeatmap {
+    /// 7 rows (0 = Sunday) of 24 hourly buckets, device wall-clock time
+    pub matrix: Vec<Vec<i64>>,
+    /// Largest bucket, for colour scaling
+    pub max: i64,
+    pub total: i64,
+    /// Only the first punch of each user and day was counted
+    pub arrivals_only: bool,
+}
+
+/// Attendance KPIs for one period
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KpiValues {
+    /// Workdays in the period that are not holidays and not in the future
+    pub working_days: i64,
+    /// working_days × active users in scope
+    pub expected_days: i64,
+    /// Working days with status present, late or early_leave
+    pub days_present: i64,
+    /// days_present / expected_days, percent
+    pub attendance_rate: f64,
+    /// HH:MM
+    pub average_arrival: Option<String>,
+    pub average_arrival_minutes: Option<f64>,
+    /// Over days with both check-in and check-out
+    pub average_worked_hours: Option<f64>,
+    /// Late arrivals / days with a check-in, percent
+    pub late_percentage: f64,
+}
+
+/// Change from the previous period (current - previous)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KpiTrend {
+    pub attendance_rate: f64,
+    pub average_arrival_minutes: Option<f64>,
+    pub average_worked_hours: Option<f64>,
+    pub late_percentage: f64,
+}
+
+/// KPIs for a period compared with the equally long period before it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Kpis {
+    pub period: DateRange,
+    pub previous_period: DateRange,
+    pub current: KpiValues,
+    pub previous: KpiValues,
+    pub trend: KpiTrend,
+}
+
+/// What [`PeriodComparison`] measures for a user or department in a period
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeriodMetrics {
+    /// Check-in to check-out on working days
+    pub worked_hours: f64,
+    /// Working days arrived late
+    pub late_count: i64,
+    /// Working days absent or with no summary
+    pub absence_days: i64,
+}
+
+/// One user's metrics in both periods; `delta` is B - A
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserPeriodComparison {
+    pub user_id: String,
+    pub name: String,
+    pub department_id: Option<String>,
+    pub department_name: Option<String>,
+    pub period_a: PeriodMetrics,
+    pub period_b: PeriodMetrics,
+    pub delta: PeriodMetrics,
+}
+
+/// A department's summed metrics in both periods; `delta` is B - A.
+/// Users without a department are grouped under `department_id: None`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DepartmentPeriodComparison {
+    pub department_id: Option<String>,
+    pub department_name: Option<String>,
+    /// Active users in scope
+    pub user_count: i64,
+    pub period_a: PeriodMetrics,
+    pub period_b: PeriodMetrics,
+    pub delta: PeriodMetrics,
+}
+
+/// Two periods compared per user and per department
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeriodComparison {
+    pub period_a: DateRange,
+    pub period_b: DateRange,
+    /// Workdays in each period that are not holidays and not in the future
+    pub working_days_a: i64,
+    pub working_days_b: i64,
+    pub users: Vec<UserPeriodComparison>,
+    pub departments: Vec<DepartmentPeriodComparison>,
+}
+
+/// One cost center's share of a period. `user_count` counts users who
+/// booked at least one working day to it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CostCenterTotals {
+    /// None for days without a cost center
+    pub cost_center_id: Option<String>,
+    pub code: Option<String>,
+    pub name: Option<String>,
+    pub user_count: i64,
+    /// Working days with status present, late or early_leave
+    pub days_present: i64,
+    pub metrics: PeriodMetrics,
+}
+
+/// A period's attendance grouped by cost center
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CostCenterReport {
+    pub period: DateRange,
+    pub working_days: i64,
+    pub cost_centers: Vec<CostCenterTotals>,
+}
+
+/// Thresholds for the buddy-punching report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BuddyPunchOptions {
+    /// Punches on the same device this close together count as "together"
+    pub window_seconds: i64,
+    /// Minimum days a pair punched together to be reported
+    pub min_shared_days: i64,
+    /// Minimum share of days both were in on which they punched together
+    pub min_ratio: f64,
+    /// Minimum card-only punches to report a fingerprint-enrolled user
+    pub min_card_punches: i64,
+}
+
+impl Default for BuddyPunchOptions {
+    fn default() -> Self {
+        Self {
+            window_seconds: 30,
+            min_shared_days: 5,
+            min_ratio: 0.6,
+            min_card_punches: 3,
+        }
+    }
+}
+
+/// Two employees who repeatedly punch within seconds of each other
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SuspiciousPair {
+    pub device_user_ids: [String; 2],
+    pub user_ids: [Option<String>; 2],
+    pub names: [Option<String>; 2],
+    /// Days with at least one punch together
+    pub shared_days: i64,
+    /// Days both punched at all
+    pub days_both_present: i64,
+    /// shared_days / days_both_present
+    pub ratio: f64,
+    pub punches_together: i64,
+    pub average_gap_seconds: f64,
+}
+
+/// A user who verifies by fingerprint but also punched with a card only
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CardOnlyUser {
+    pub device_user_id: String,
+    pub user_id: Option<String>,
+    pub name: Option<String>,
+    /// Card-only punches in the period
+    pub card_punches: i64,
+    /// Fingerprint punches in the period
+    pub fingerprint_punches: i64,
+    pub last_card_punch: String,
+}
+
+/// Patterns suggestive of buddy punching, for manual review
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuddyPunchReport {
+    pub period: DateRange,
+    pub options: BuddyPunchOptions,
+    pub pairs: Vec<SuspiciousPair>,
+    pub card_only: Vec<CardOnlyUser>,
+}
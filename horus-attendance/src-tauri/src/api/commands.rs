@@ -0,0 +1,303 @@
+//! Tauri command handlers for the embedded API.
+
+use tauri::{Manager, State};
+
+use super::{pairing, ports, self_service};
+use super::server::{ApiServer, API_SERVER_KEY};
+use super::{keys, tokens};
+use super::types::*;
+use crate::db;
+use crate::tls::{self, types::TlsMaterial};
+
+fn load_settings(app: &tauri::AppHandle) -> Result<ApiServerSettings, String> {
+    let conn = db::open(app)?;
+    Ok(db::get_json_setting(&conn, API_SERVER_KEY)?.unwrap_or_default())
+}
+
+/// The certificate to serve, per the `tls` setting. Blocks on the keychain.
+fn load_tls(app: &tauri::AppHandle) -> Result<Option<TlsMaterial>, String> {
+    let settings = tls::store::load_settings(&*db::open(app)?)?;
+    tls::store::server_material(app, &settings)
+}
+
+/// Keep the port the server fell back to in the settings, or clear it once
+/// the configured port was free again
+fn remember_port(app: &tauri::AppHandle, settings: &ApiServerSettings, status: &ApiServerStatus) -> Result<(), String> {
+    let Some(port) = status.last_start.as_ref().and_then(|s| s.port) else {
+        return Ok(());
+    };
+    let fallback = (port != settings.port).then_some(port);
+    if fallback == settings.fallback_port {
+        return Ok(());
+    }
+    let updated = ApiServerSettings {
+        fallback_port: fallback,
+        ..settings.clone()
+    };
+    let json = serde_json::to_string(&updated).map_err(|e| format!("Failed to serialize API server settings: {}", e))?;
+    db::set_setting(&*db::open(app)?, API_SERVER_KEY, &json)
+}
+
+/// Check the firewall for the port the server started on, in the background
+fn check_firewall(app: &tauri::AppHandle, settings: &ApiServerSettings, status: &ApiServerStatus) {
+    let Some(port) = status.last_start.as_ref().and_then(|s| s.port) else {
+        return;
+    };
+    let app = app.clone();
+    let bind_address = settings.bind_address.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let firewall = ports::firewall(&bind_address, port);
+        if firewall.status == ports::FIREWALL_BLOCKED {
+            log::warn!(
+                "[api] LAN clients may not reach port {}: {}",
+                port,
+                firewall.detail.as_deref().unwrap_or("blocked by the firewall")
+            );
+        }
+        if let Err(e) = app.state::<ApiServer>().set_firewall(firewall) {
+            log::warn!("[api] {}", e);
+        }
+    });
+}
+
+/// Start the server and carry out what follows a start: remembering a
+/// fallback port and checking the firewall
+fn start_with(
+    app: &tauri::AppHandle,
+    server: &ApiServer,
+    settings: &ApiServerSettings,
+    tls: Option<TlsMaterial>,
+) -> Result<ApiServerStatus, String> {
+    let status = server.start(app.clone(), settings, tls)?;
+    if let Err(e) = remember_port(app, settings, &status) {
+        log::warn!("[api] Failed to save the fallback port: {}", e);
+    }
+    check_firewall(app, settings, &status);
+    Ok(status)
+}
+
+/// Start (or restart) the API server with the saved settings. When the
+/// port is taken the server falls back to a free one above it; the status
+/// says which ports were tried.
+#[tauri::command]
+pub async fn start_api_server(app: tauri::AppHandle, server: State<'_, ApiServer>) -> Result<ApiServerStatus, String> {
+    let settings = load_settings(&app)?;
+    let handle = app.clone();
+    let tls = tauri::async_runtime::spawn_blocking(move || load_tls(&handle))
+        .await
+        .map_err(|e| format!("Certificate task failed: {}", e))??;
+    start_with(&app, &server, &settings, tls)
+}
+
+/// Check the configured port and the firewall without starting the server.
+/// `port` checks another port instead. The running server's own port shows
+/// as in use.
+#[tauri::command]
+pub async fn check_api_server_port(app: tauri::AppHandle, port: Option<u16>) -> Result<ApiPortReport, String> {
+    let settings = load_settings(&app)?;
+    let port = port.unwrap_or(settings.port);
+    tauri::async_runtime::spawn_blocking(move || ApiPortReport {
+        port: ports::check(&settings.bind_address, port),
+        firewall: ports::firewall(&settings.bind_address, port),
+    })
+    .await
+    .map_err(|e| format!("Port check task failed: {}", e))
+}
+
+/// Stop the API server
+#[tauri::command]
+pub async fn stop_api_server(server: State<'_, ApiServer>) -> Result<(), String> {
+    log::info!("[api::cmd] stop_api_server");
+    server.stop()
+}
+
+/// Whether the API server is running, and where
+#[tauri::command]
+pub async fn get_api_server_status(server: State<'_, ApiServer>) -> Result<ApiServerStatus, String> {
+    server.status()
+}
+
+/// Request, rate limit and lockout counters since the server started;
+/// none when it isn't running
+#[tauri::command]
+pub async fn get_api_metrics(server: State<'_, ApiServer>) -> Result<Option<ApiMetrics>, String> {
+    server.metrics()
+}
+
+/// Issue an API token for a user. The token is only returned this once.
+#[tauri::command]
+pub async fn create_user_token(app: tauri::AppHandle, user_id: String, label: Option<String>) -> Result<IssuedToken, String> {
+    log::info!("[api::cmd] create_user_token for {}", user_id);
+    let conn = db::open(&app)?;
+    tokens::issue(&conn, &user_id, label.as_deref().unwrap_or(""))
+}
+
+/// Issue a manager token, which can also read the users, summaries and
+/// registers of the given departments. Returned only this once.
+#[tauri::command]
+pub async fn create_manager_token(
+    app: tauri::AppHandle,
+    user_id: String,
+    department_ids: Vec<String>,
+    label: Option<String>,
+) -> Result<IssuedToken, String> {
+    log::info!("[api::cmd] create_manager_token for {} ({} departments)", user_id, department_ids.len());
+    let conn = db::open(&app)?;
+    tokens::issue_manager(&conn, &user_id, label.as_deref().unwrap_or(""), &department_ids)
+}
+
+/// Change the departments a manager token can see
+#[tauri::command]
+pub async fn set_token_departments(
+    app: tauri::AppHandle,
+    token_id: String,
+    department_ids: Vec<String>,
+) -> Result<UserToken, String> {
+    log::info!("[api::cmd] set_token_departments {} ({} departments)", token_id, department_ids.len());
+    let conn = db::open(&app)?;
+    tokens::set_departments(&conn, &token_id, &department_ids)
+}
+
+/// List API tokens, optionally for one user
+#[tauri::command]
+pub async fn list_user_tokens(app: tauri::AppHandle, user_id: Option<String>) -> Result<Vec<UserToken>, String> {
+    let conn = db::open(&app)?;
+    tokens::list(&conn, user_id.as_deref())
+}
+
+/// Revoke an API token
+#[tauri::command]
+pub async fn revoke_user_token(app: tauri::AppHandle, token_id: String) -> Result<(), String> {
+    log::info!("[api::cmd] revoke_user_token {}", token_id);
+    let conn = db::open(&app)?;
+    tokens::revoke(&conn, &token_id)
+}
+
+/// Issue an integration API key. The key is only returned this once.
+#[tauri::command]
+pub async fn create_api_key(app: tauri::AppHandle, name: String, scopes: Vec<String>) -> Result<IssuedApiKey, String> {
+    log::info!("[api::cmd] create_api_key {:?} ({})", name, scopes.join(", "));
+    keys::issue(&*db::open(&app)?, &name, &scopes)
+}
+
+/// List integration API keys
+#[tauri::command]
+pub async fn list_api_keys(app: tauri::AppHandle) -> Result<Vec<ApiKey>, String> {
+    keys::list(&*db::open(&app)?)
+}
+
+/// Revoke an integration API key at once
+#[tauri::command]
+pub async fn revoke_api_key(app: tauri::AppHandle, key_id: String) -> Result<(), String> {
+    log::info!("[api::cmd] revoke_api_key {}", key_id);
+    keys::revoke(&*db::open(&app)?, &key_id)
+}
+
+/// Replace an API key, keeping the old one valid for a grace period
+/// (default 24 hours) while the integration switches over
+#[tauri::command]
+pub async fn rotate_api_key(app: tauri::AppHandle, key_id: String, grace_hours: Option<i64>) -> Result<IssuedApiKey, String> {
+    log::info!("[api::cmd] rotate_api_key {}", key_id);
+    let mut conn = db::open(&app)?;
+    keys::rotate(&mut conn, &key_id, grace_hours.unwrap_or(keys::DEFAULT_GRACE_HOURS))
+}
+
+/// Create a one-time QR invite for pairing an employee's companion app.
+/// The server must be running, since the invite carries its address.
+#[tauri::command]
+pub async fn create_pairing_invite(
+    app: tauri::AppHandle,
+    server: State<'_, ApiServer>,
+    user_id: String,
+    ttl_minutes: Option<i64>,
+) -> Result<PairingInvite, String> {
+    if !server.status()?.running {
+        return Err("Start the API server before pairing a device".to_string());
+    }
+    log::info!("[api::cmd] create_pairing_invite for {}", user_id);
+    let settings = load_settings(&app)?;
+    let conn = db::open(&app)?;
+    let tls_settings = tls::store::load_settings(&conn)?;
+    let https = server.status()?.tls;
+    let fingerprint = if https {
+        tls::store::status(&app, &tls_settings)?.fingerprint_sha256
+    } else {
+        None
+    };
+    pairing::purge_expired(&conn)?;
+    pairing::create_invite(
+        &conn,
+        &user_id,
+        &pairing::endpoint(&settings.bind_address, settings.listening_port(), https),
+        fingerprint.as_deref(),
+        ttl_minutes.unwrap_or(pairing::DEFAULT_TTL_MINUTES),
+    )
+}
+
+/// Create signed links to employees' own monthly reports, for HR to email
+/// out. One user, one department, or every active user.
+#[tauri::command]
+pub async fn create_self_service_links(
+    app: tauri::AppHandle,
+    year: i32,
+    month: u32,
+    department_id: Option<String>,
+    user_id: Option<String>,
+    ttl_days: Option<i64>,
+) -> Result<Vec<SelfServiceLink>, String> {
+    log::info!("[api::cmd] create_self_service_links {}-{:02}", year, month);
+    let settings = load_settings(&app)?;
+    let conn = db::open(&app)?;
+    let endpoint = pairing::endpoint(&settings.bind_address, settings.listening_port(), tls::store::enabled(&conn)?);
+    tauri::async_runtime::spawn_blocking(move || {
+        self_service::create_links(
+            &app,
+            &conn,
+            &endpoint,
+            year,
+            month,
+            department_id.as_deref(),
+            user_id.as_deref(),
+            ttl_days.unwrap_or(self_service::DEFAULT_TTL_DAYS),
+        )
+    })
+    .await
+    .map_err(|e| format!("Link task failed: {}", e))?
+}
+
+/// Replace the report signing key, invalidating every self-service link issued so far
+#[tauri::command]
+pub async fn rotate_report_signing_key(app: tauri::AppHandle) -> Result<(), String> {
+    log::info!("[api::cmd] rotate_report_signing_key");
+    tauri::async_runtime::spawn_blocking(move || self_service::rotate_key(&app))
+        .await
+        .map_err(|e| format!("Key rotation task failed: {}", e))?
+}
+
+/// Start the server at launch when enabled in settings
+pub async fn start_on_launch(app: tauri::AppHandle) {
+    // No database yet on first launch
+    let Ok(settings) = load_settings(&app) else {
+        return;
+    };
+    if !settings.enabled {
+        return;
+    }
+    let handle = app.clone();
+    let tls = match tauri::async_runtime::spawn_blocking(move || load_tls(&handle)).await {
+        Ok(Ok(tls)) => tls,
+        // Never fall back to cleartext when TLS is configured
+        Ok(Err(e)) => {
+            log::error!("[api] Not starting without its certificate: {}", e);
+            return;
+        }
+        Err(e) => {
+            log::error!("[api] Certificate task failed: {}", e);
+            return;
+        }
+    };
+    // The failure is kept in the server status for the settings screen
+    if let Err(e) = start_with(&app, &app.state::<ApiServer>(), &settings, tls) {
+        log::error!("[api] {}", e);
+    }
+}
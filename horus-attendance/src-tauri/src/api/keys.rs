@@ -0,0 +1,178 @@
+//! Integration API keys
+//!
+//! Keys belong to an integration rather than a user, carry a set of scopes,
+//! and read the `/team` routes across every department. Like user tokens
+//! they are random, prefixed (`hak_`), and stored only as SHA-256 hashes.
+//!
+//! Rotating a key issues a replacement with the same name and scopes and
+//! lets the old key keep working for a grace period, so the integration can
+//! be switched over without downtime.
+
+use chrono::{Duration, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+
+use super::tokens::{self, hash};
+use super::types::{ApiKey, IssuedApiKey, Principal};
+
+pub const KEY_PREFIX: &str = "hak_";
+
+pub const ROLE_INTEGRATION: &str = "integration";
+
+pub const SCOPE_USERS_READ: &str = "users:read";
+pub const SCOPE_ATTENDANCE_READ: &str = "attendance:read";
+pub const SCOPE_REPORTS_READ: &str = "reports:read";
+pub const SCOPE_METRICS_READ: &str = "metrics:read";
+
+/// Every scope a key can be granted
+pub const SCOPES: &[&str] = &[SCOPE_USERS_READ, SCOPE_ATTENDANCE_READ, SCOPE_REPORTS_READ, SCOPE_METRICS_READ];
+
+/// Grace period for the old key when rotating, unless told otherwise
+pub const DEFAULT_GRACE_HOURS: i64 = 24;
+const MAX_GRACE_HOURS: i64 = 24 * 30;
+
+/// Characters of the key kept in the clear for display
+const SHOWN_PREFIX_LEN: usize = 12;
+
+fn validate(name: &str, scopes: &[String]) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("Give the key a name, e.g. the integration it is for".to_string());
+    }
+    if scopes.is_empty() {
+        return Err("Grant the key at least one scope".to_string());
+    }
+    if let Some(unknown) = scopes.iter().find(|s| !SCOPES.contains(&s.as_str())) {
+        return Err(format!("Unknown scope: {} (expected one of {})", unknown, SCOPES.join(", ")));
+    }
+    Ok(())
+}
+
+/// Issue a key. The key itself is only returned here.
+pub fn issue(conn: &Connection, name: &str, scopes: &[String]) -> Result<IssuedApiKey, String> {
+    validate(name, scopes)?;
+    let mut scopes = scopes.to_vec();
+    scopes.sort();
+    scopes.dedup();
+    let scopes_json = serde_json::to_string(&scopes).map_err(|e| format!("Failed to serialize scopes: {}", e))?;
+
+    let key = tokens::generate(KEY_PREFIX);
+    let id = uuid::Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO api_keys (id, name, key_prefix, key_hash, scopes) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![id, name.trim(), &key[..SHOWN_PREFIX_LEN], hash(&key), scopes_json],
+    )
+    .map_err(|e| format!("Failed to save API key: {}", e))?;
+    Ok(IssuedApiKey {
+        key,
+        info: get(conn, &id)?,
+    })
+}
+
+const SELECT: &str = "SELECT id, name, key_prefix, scopes, created_at, last_used_at, expires_at, revoked_at, replaced_by
+                      FROM api_keys";
+
+fn map_row(row: &rusqlite::Row) -> rusqlite::Result<ApiKey> {
+    let scopes: String = row.get(3)?;
+    Ok(ApiKey {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        key_prefix: row.get(2)?,
+        scopes: serde_json::from_str(&scopes).unwrap_or_default(),
+        created_at: row.get(4)?,
+        last_used_at: row.get(5)?,
+        expires_at: row.get(6)?,
+        revoked_at: row.get(7)?,
+        replaced_by: row.get(8)?,
+    })
+}
+
+fn get(conn: &Connection, id: &str) -> Result<ApiKey, String> {
+    conn.query_row(&format!("{} WHERE id = ?1", SELECT), [id], map_row)
+        .optional()
+        .map_err(|e| format!("Failed to read API key: {}", e))?
+        .ok_or_else(|| format!("API key not found: {}", id))
+}
+
+/// Keys, newest first, including revoked ones
+pub fn list(conn: &Connection) -> Result<Vec<ApiKey>, String> {
+    let mut stmt = conn
+        .prepare(&format!("{} ORDER BY created_at DESC", SELECT))
+        .map_err(|e| format!("Failed to query API keys: {}", e))?;
+    let rows = stmt
+        .query_map([], map_row)
+        .map_err(|e| format!("Failed to query API keys: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read API keys: {}", e))
+}
+
+/// Revoke a key now. It stays listed so its last use remains visible.
+pub fn revoke(conn: &Connection, id: &str) -> Result<(), String> {
+    let updated = conn
+        .execute(
+            "UPDATE api_keys SET revoked_at = datetime('now') WHERE id = ?1 AND revoked_at IS NULL",
+            [id],
+        )
+        .map_err(|e| format!("Failed to revoke API key: {}", e))?;
+    if updated == 0 {
+        return Err(format!("API key not found or already revoked: {}", id));
+    }
+    Ok(())
+}
+
+/// Replace a key with a new one of the same name and scopes. The old key
+/// keeps working for `grace_hours` (0 revokes it at once).
+pub fn rotate(conn: &mut Connection, id: &str, grace_hours: i64) -> Result<IssuedApiKey, String> {
+    if !(0..=MAX_GRACE_HOURS).contains(&grace_hours) {
+        return Err(format!("Grace period must be between 0 and {} hours", MAX_GRACE_HOURS));
+    }
+    let old = get(conn, id)?;
+    if old.revoked_at.is_some() || old.replaced_by.is_some() {
+        return Err("Only an active key can be rotated".to_string());
+    }
+
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    let issued = issue(&tx, &old.name, &old.scopes)?;
+    if grace_hours == 0 {
+        tx.execute(
+            "UPDATE api_keys SET revoked_at = datetime('now'), replaced_by = ?2 WHERE id = ?1",
+            params![id, issued.info.id],
+        )
+    } else {
+        let expires_at = (Utc::now() + Duration::hours(grace_hours)).format("%Y-%m-%d %H:%M:%S").to_string();
+        tx.execute(
+            "UPDATE api_keys SET expires_at = ?3, replaced_by = ?2 WHERE id = ?1",
+            params![id, issued.info.id, expires_at],
+        )
+    }
+    .map_err(|e| format!("Failed to retire old API key: {}", e))?;
+    tx.commit().map_err(|e| format!("Failed to commit rotation: {}", e))?;
+    Ok(issued)
+}
+
+/// The integration a presented key belongs to. Records the use. None for
+/// unknown, revoked and expired keys.
+pub fn authenticate(conn: &Connection, key: &str) -> Result<Option<Principal>, String> {
+    let found: Option<(String, String)> = conn
+        .query_row(
+            "SELECT id, scopes FROM api_keys
+             WHERE key_hash = ?1 AND revoked_at IS NULL
+               AND (expires_at IS NULL OR expires_at > datetime('now'))",
+            [hash(key)],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to check API key: {}", e))?;
+    let Some((id, scopes)) = found else {
+        return Ok(None);
+    };
+    conn.execute("UPDATE api_keys SET last_used_at = datetime('now') WHERE id = ?1", [&id])
+        .map_err(|e| format!("Failed to record API key use: {}", e))?;
+    Ok(Some(Principal {
+        user_id: None,
+        device_user_id: None,
+        role: ROLE_INTEGRATION.to_string(),
+        department_ids: None,
+        scopes: serde_json::from_str(&scopes).unwrap_or_default(),
+    }))
+}
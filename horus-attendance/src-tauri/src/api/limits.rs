@@ -0,0 +1,343 @@
+//! Rate limiting and failed sign-in lockout
+//!
+//! The server runs on a PC open to the office LAN, so clients are
+//! throttled: each IP address and each credential gets a number of requests
+//! per one-minute window, and an IP address or a credential that fails to
+//! authenticate too many times in a row (bad tokens, API keys, pairing codes
+//! or report links) is locked out for a while. Failures are cleared only by
+//! a request whose credential was verified, never by an open route such as
+//! `/health`. State is kept in memory and starts afresh with the server. The
+//! counters are served at `GET /metrics`.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::types::{ApiMetrics, ApiServerSettings};
+
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Smallest body size cap honoured
+const MIN_BODY_BYTES: u64 = 1024;
+
+/// Clients tracked before expired entries are dropped
+const PRUNE_AT: usize = 10_000;
+
+/// Why a request was refused, and when to try again
+pub struct Rejection {
+    pub retry_after_secs: u64,
+    pub message: String,
+}
+
+struct Window {
+    started: Instant,
+    requests: u32,
+}
+
+struct Failures {
+    count: u32,
+    last: Instant,
+    locked_until: Option<Instant>,
+}
+
+#[derive(Default)]
+struct Counters {
+    requests: AtomicU64,
+    rate_limited: AtomicU64,
+    body_too_large: AtomicU64,
+    auth_failures: AtomicU64,
+    lockouts: AtomicU64,
+    locked_out_requests: AtomicU64,
+}
+
+pub struct Limits {
+    rate_limit: u32,
+    max_body_bytes: u64,
+    lockout_after: u32,
+    lockout_for: Duration,
+    started: Instant,
+    windows: Mutex<HashMap<String, Window>>,
+    failures: Mutex<HashMap<String, Failures>>,
+    counters: Counters,
+}
+
+impl Limits {
+    pub fn new(settings: &ApiServerSettings) -> Self {
+        Self {
+            rate_limit: settings.rate_limit_per_minute,
+            // Even pairing requests need a body
+            max_body_bytes: settings.max_body_bytes.max(MIN_BODY_BYTES),
+            lockout_after: settings.lockout_after_failures,
+            lockout_for: Duration::from_secs(u64::from(settings.lockout_minutes) * 60),
+            started: Instant::now(),
+            windows: Mutex::new(HashMap::new()),
+            failures: Mutex::new(HashMap::new()),
+            counters: Counters::default(),
+        }
+    }
+
+    pub fn max_body_bytes(&self) -> u64 {
+        self.max_body_bytes
+    }
+
+    /// Count a request from an IP address, with the hash of its credential
+    /// if it presented one, and refuse it if either is over its limit or
+    /// the address is locked out
+    pub fn admit(&self, client: &str, credential: Option<&str>) -> Result<(), Rejection> {
+        self.admit_at(client, credential, Instant::now())
+    }
+
+    fn admit_at(&self, client: &str, credential: Option<&str>, now: Instant) -> Result<(), Rejection> {
+        self.counters.requests.fetch_add(1, Ordering::Relaxed);
+
+        {
+            let failures = self.failures.lock().unwrap_or_else(|e| e.into_inner());
+            for key in keys(client, credential) {
+                let locked_until = failures
+                    .get(&key)
+                    .and_then(|f| f.locked_until)
+                    .filter(|until| *until > now);
+                if let Some(until) = locked_until {
+                    self.counters.locked_out_requests.fetch_add(1, Ordering::Relaxed);
+                    let from = if key.starts_with("ip:") { "from this address" } else { "with this credential" };
+                    return Err(Rejection {
+                        retry_after_secs: seconds_until(now, until),
+                        message: format!("Too many failed sign-ins {}; try again later", from),
+                    });
+                }
+            }
+        }
+
+        if self.rate_limit == 0 {
+            return Ok(());
+        }
+        let mut windows = self.windows.lock().unwrap_or_else(|e| e.into_inner());
+        if windows.len() >= PRUNE_AT {
+            windows.retain(|_, w| now.duration_since(w.started) < WINDOW);
+        }
+        for key in keys(client, credential) {
+            let window = windows.entry(key).or_insert(Window {
+                started: now,
+                requests: 0,
+            });
+            if now.duration_since(window.started) >= WINDOW {
+                window.started = now;
+                window.requests = 0;
+            }
+            window.requests += 1;
+            if window.requests > self.rate_limit {
+                self.counters.rate_limited.fetch_add(1, Ordering::Relaxed);
+                return Err(Rejection {
+                    retry_after_secs: seconds_until(now, window.started + WINDOW),
+                    message: format!("Rate limit of {} requests a minute exceeded", self.rate_limit),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Count a failed sign-in from an IP address, with the hash of the
+    /// credential it tried if any, locking either out once it has failed too
+    /// many times in a row
+    pub fn record_failure(&self, client: &str, credential: Option<&str>) {
+        self.record_failure_at(client, credential, Instant::now());
+    }
+
+    fn record_failure_at(&self, client: &str, credential: Option<&str>, now: Instant) {
+        self.counters.auth_failures.fetch_add(1, Ordering::Relaxed);
+        if self.lockout_after == 0 {
+            return;
+        }
+        let mut failures = self.failures.lock().unwrap_or_else(|e| e.into_inner());
+        if failures.len() >= PRUNE_AT {
+            let lockout_for = self.lockout_for;
+            failures.retain(|_, f| {
+                f.locked_until.is_some_and(|until| until > now) || now.duration_since(f.last) < lockout_for
+            });
+        }
+        for key in keys(client, credential) {
+            // Credential hashes stay out of the log
+            let who = if key.starts_with("ip:") { client } else { "a credential" };
+            let entry = failures.entry(key).or_insert(Failures {
+                count: 0,
+                last: now,
+                locked_until: None,
+            });
+            // Old failures and served lockouts are forgotten
+            if now.duration_since(entry.last) >= self.lockout_for || entry.locked_until.is_some_and(|until| until <= now) {
+                entry.count = 0;
+                entry.locked_until = None;
+            }
+            entry.count += 1;
+            entry.last = now;
+            if entry.count >= self.lockout_after {
+                entry.count = 0;
+                entry.locked_until = Some(now + self.lockout_for);
+                self.counters.lockouts.fetch_add(1, Ordering::Relaxed);
+                log::warn!(
+                    "[api] Locked out {} for {} min after repeated failed sign-ins",
+                    who,
+                    self.lockout_for.as_secs() / 60
+                );
+            }
+        }
+    }
+
+    /// A verified credential clears its own failures and those of the
+    /// address it came from. Call only once the credential was checked:
+    /// requests to open routes prove nothing.
+    pub fn record_success(&self, client: &str, credential: Option<&str>) {
+        let mut failures = self.failures.lock().unwrap_or_else(|e| e.into_inner());
+        for key in keys(client, credential) {
+            if failures.get(&key).is_some_and(|f| f.locked_until.is_none()) {
+                failures.remove(&key);
+            }
+        }
+    }
+
+    pub fn record_too_large(&self) {
+        self.counters.body_too_large.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn metrics(&self) -> ApiMetrics {
+        let now = Instant::now();
+        let active_lockouts = self
+            .failures
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .filter(|f| f.locked_until.is_some_and(|until| until > now))
+            .count() as u64;
+        let counters = &self.counters;
+        ApiMetrics {
+            uptime_seconds: now.duration_since(self.started).as_secs(),
+            requests: counters.requests.load(Ordering::Relaxed),
+            rate_limited: counters.rate_limited.load(Ordering::Relaxed),
+            body_too_large: counters.body_too_large.load(Ordering::Relaxed),
+            auth_failures: counters.auth_failures.load(Ordering::Relaxed),
+            lockouts: counters.lockouts.load(Ordering::Relaxed),
+            locked_out_requests: counters.locked_out_requests.load(Ordering::Relaxed),
+            active_lockouts,
+        }
+    }
+}
+
+/// The IP address's key, then the credential's
+fn keys(client: &str, credential: Option<&str>) -> impl Iterator<Item = String> {
+    std::iter::once(format!("ip:{}", client)).chain(credential.map(|c| format!("key:{}", c)))
+}
+
+/// Whole seconds from now until a moment, at least one
+fn seconds_until(now: Instant, until: Instant) -> u64 {
+    until.saturating_duration_since(now).as_secs().max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    fn limits(rate_limit: u32, lockout_after: u32) -> Limits {
+        Limits::new(&ApiServerSettings {
+            rate_limit_per_minute: rate_limit,
+            lockout_after_failures: lockout_after,
+            lockout_minutes: 15,
+            ..ApiServerSettings::default()
+        })
+    }
+
+    #[test]
+    fn window_rolls_over_after_a_minute() {
+        let limits = limits(2, 0);
+        let t0 = Instant::now();
+        assert!(limits.admit_at("10.0.0.1", None, t0).is_ok());
+        assert!(limits.admit_at("10.0.0.1", None, t0 + Duration::from_secs(10)).is_ok());
+        let rejected = limits.admit_at("10.0.0.1", None, t0 + Duration::from_secs(20)).unwrap_err();
+        assert_eq!(rejected.retry_after_secs, 40);
+        // Other addresses have their own window
+        assert!(limits.admit_at("10.0.0.2", None, t0 + Duration::from_secs(20)).is_ok());
+        assert!(limits.admit_at("10.0.0.1", None, t0 + MINUTE).is_ok());
+    }
+
+    #[test]
+    fn credential_is_limited_across_addresses() {
+        let limits = limits(2, 0);
+        let t0 = Instant::now();
+        assert!(limits.admit_at("10.0.0.1", Some("k"), t0).is_ok());
+        assert!(limits.admit_at("10.0.0.2", Some("k"), t0).is_ok());
+        assert!(limits.admit_at("10.0.0.3", Some("k"), t0).is_err());
+        assert!(limits.admit_at("10.0.0.3", None, t0).is_ok());
+    }
+
+    #[test]
+    fn lockout_expires() {
+        let limits = limits(0, 3);
+        let t0 = Instant::now();
+        for _ in 0..3 {
+            limits.record_failure_at("10.0.0.1", None, t0);
+        }
+        let rejected = limits.admit_at("10.0.0.1", None, t0 + MINUTE).unwrap_err();
+        assert!(rejected.message.contains("this address"));
+        assert_eq!(rejected.retry_after_secs, 14 * 60);
+        assert_eq!(limits.metrics().lockouts, 1);
+
+        let served = t0 + 15 * MINUTE;
+        assert!(limits.admit_at("10.0.0.1", None, served).is_ok());
+        // The count starts over once the lockout is served
+        limits.record_failure_at("10.0.0.1", None, served);
+        limits.record_failure_at("10.0.0.1", None, served);
+        assert!(limits.admit_at("10.0.0.1", None, served).is_ok());
+    }
+
+    #[test]
+    fn old_failures_are_forgotten() {
+        let limits = limits(0, 3);
+        let t0 = Instant::now();
+        limits.record_failure_at("10.0.0.1", None, t0);
+        limits.record_failure_at("10.0.0.1", None, t0);
+        limits.record_failure_at("10.0.0.1", None, t0 + 15 * MINUTE);
+        assert!(limits.admit_at("10.0.0.1", None, t0 + 15 * MINUTE).is_ok());
+    }
+
+    #[test]
+    fn credential_is_locked_out_across_addresses() {
+        let limits = limits(0, 3);
+        let t0 = Instant::now();
+        for ip in ["10.0.0.1", "10.0.0.2", "10.0.0.3"] {
+            limits.record_failure_at(ip, Some("k"), t0);
+        }
+        let rejected = limits.admit_at("10.0.0.4", Some("k"), t0).unwrap_err();
+        assert!(rejected.message.contains("this credential"));
+        assert!(limits.admit_at("10.0.0.4", None, t0).is_ok());
+        assert!(limits.admit_at("10.0.0.1", Some("other"), t0).is_ok());
+    }
+
+    #[test]
+    fn success_resets_failures_but_not_lockouts() {
+        let limits = limits(0, 3);
+        let t0 = Instant::now();
+        limits.record_failure_at("10.0.0.1", Some("a"), t0);
+        limits.record_failure_at("10.0.0.1", Some("b"), t0);
+        limits.record_success("10.0.0.1", Some("c"));
+        limits.record_failure_at("10.0.0.1", Some("d"), t0);
+        limits.record_failure_at("10.0.0.1", Some("e"), t0);
+        assert!(limits.admit_at("10.0.0.1", None, t0).is_ok());
+
+        limits.record_failure_at("10.0.0.1", Some("f"), t0);
+        assert!(limits.admit_at("10.0.0.1", None, t0).is_err());
+        limits.record_success("10.0.0.1", Some("c"));
+        assert!(limits.admit_at("10.0.0.1", None, t0).is_err());
+    }
+
+    #[test]
+    fn success_leaves_other_credentials_failures() {
+        let limits = limits(0, 2);
+        let t0 = Instant::now();
+        limits.record_failure_at("10.0.0.1", Some("k"), t0);
+        limits.record_success("10.0.0.2", Some("other"));
+        limits.record_failure_at("10.0.0.3", Some("k"), t0);
+        assert!(limits.admit_at("10.0.0.4", Some("k"), t0).is_err());
+    }
+}
@@ -0,0 +1,55 @@
+//! The token holder's own data (`/me` routes)
+
+use chrono::{Datelike, Local, NaiveDate};
+use rusqlite::Connection;
+
+use super::types::{MeDay, MeProfile};
+use crate::db::summaries;
+use crate::summary::commands::validate_date;
+
+/// Profile of the user a token belongs to
+pub fn profile(conn: &Connection, user_id: &str) -> Result<MeProfile, String> {
+    conn.query_row(
+        "SELECT u.id, u.display_name, u.employee_code, d.name
+         FROM users u LEFT JOIN departments d ON d.id = u.department_id
+         WHERE u.id = ?1",
+        [user_id],
+        |row| {
+            Ok(MeProfile {
+                user_id: row.get(0)?,
+                display_name: row.get(1)?,
+                employee_code: row.get(2)?,
+                department: row.get(3)?,
+            })
+        },
+    )
+    .map_err(|e| format!("Failed to read user: {}", e))
+}
+
+/// The dates asked for, defaulting to the current month so far
+pub(super) fn period(from: Option<&str>, to: Option<&str>) -> Result<(String, String), String> {
+    let today = Local::now().date_naive();
+    let first_of_month = NaiveDate::from_ymd_opt(today.year(), today.month(), 1).unwrap_or(today);
+    let from = from.map(str::to_string).unwrap_or_else(|| first_of_month.format("%Y-%m-%d").to_string());
+    let to = to.map(str::to_string).unwrap_or_else(|| today.format("%Y-%m-%d").to_string());
+    validate_date(&from)?;
+    validate_date(&to)?;
+    Ok((from, to))
+}
+
+/// Daily summaries between two dates (inclusive); defaults to the current
+/// month so far
+pub fn attendance(conn: &Connection, user_id: &str, from: Option<&str>, to: Option<&str>) -> Result<Vec<MeDay>, String> {
+    let (from, to) = period(from, to)?;
+    Ok(summaries::between(conn, &from, &to, Some(user_id))?
+        .into_iter()
+        .map(|day| MeDay {
+            date: day.date,
+            check_in_time: day.check_in_time,
+            check_out_time: day.check_out_time,
+            status: day.status,
+            late_minutes: day.late_minutes,
+            early_minutes: day.early_minutes,
+        })
+        .collect())
+}
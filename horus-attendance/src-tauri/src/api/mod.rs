@@ -0,0 +1,45 @@
+//! Embedded REST API
+//!
+//! An opt-in HTTP server on the office LAN for clients without terminal
+//! access, e.g. field staff punching from a phone. It is configured under the
+//! `apiServer` setting and runs on its own threads next to the app. It
+//! serves HTTPS when TLS is turned on (see [`crate::tls`]). A port taken by
+//! another program falls back to a free one, and the firewall is checked
+//! after starting; both show in the server status (see [`ports`]).
+//!
+//! Requests authenticate with a per-user bearer token. Only a SHA-256 hash of
+//! each token is stored; the token itself is shown once when issued. Manager
+//! tokens can also read the `/team` routes, limited to the departments
+//! assigned to the token. Integrations use scoped API keys instead, which
+//! read the `/team` routes across the organization (see [`keys`]).
+//! Requests are rate limited per IP address and credential, and addresses
+//! that keep failing to sign in are locked out (see [`limits`]).
+//!
+//! Routes:
+//!
+//! ```text
+//! GET  /health          liveness probe, no auth
+//! GET  /metrics         request, rate limit and lockout counters; metrics:read
+//! POST /pair            exchange a QR pairing code for a token, no auth
+//! POST /punches         record a punch for the token's user
+//! GET  /me              the token's user
+//! GET  /me/attendance   their daily summaries (?from=&to=, default this month)
+//! GET  /reports/monthly one employee's month via a signed link (?token=[&format=pdf])
+//! POST /reports/disputes dispute that month before its closure deadline (?token=)
+//! GET  /team/users      users of the manager's departments (?department=); users:read
+//! GET  /team/attendance their daily summaries (?from=&to=&department=); attendance:read
+//! GET  /team/reports/monthly a department's register PDF (?year=&month=[&department=]); reports:read
+//! ```
+
+pub mod commands;
+pub mod keys;
+pub mod limits;
+pub mod me;
+pub mod pairing;
+pub mod ports;
+pub mod punches;
+pub mod scope;
+pub mod self_service;
+pub mod server;
+pub mod tokens;
+pub mod types;
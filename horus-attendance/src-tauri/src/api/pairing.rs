@@ -0,0 +1,132 @@
+//! Pairing companion apps by QR code
+//!
+//! HR creates a pairing invite for an employee; the app shows it as a QR
+//! code holding the server endpoint and a one-time code:
+//!
+//! ```json
+//! {"v":1,"endpoint":"https://192.168.1.20:8787","code":"...","fingerprint":"AB:CD:..."}
+//! ```
+//!
+//! The fingerprint of the server's certificate is included when it serves
+//! HTTPS, so the app can pin a self-signed certificate.
+//!
+//! The companion app (or PWA) scans it and calls `POST /pair` with the code
+//! and a device name, receiving a regular per-user API token labelled with
+//! that name. Codes expire after a few minutes and work once; only their
+//! hashes are stored.
+
+use chrono::{Duration, Utc};
+use qrcode::render::svg;
+use qrcode::QrCode;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::json;
+use std::net::{IpAddr, UdpSocket};
+
+use super::tokens;
+use super::types::{IssuedToken, PairingInvite};
+use crate::db::users;
+
+/// Version of the QR payload format
+const PAYLOAD_VERSION: u32 = 1;
+
+/// Minutes an invite stays valid by default
+pub const DEFAULT_TTL_MINUTES: i64 = 10;
+
+/// Create a one-time invite for `user_id`, reachable at `endpoint`
+pub fn create_invite(
+    conn: &Connection,
+    user_id: &str,
+    endpoint: &str,
+    fingerprint: Option<&str>,
+    ttl_minutes: i64,
+) -> Result<PairingInvite, String> {
+    let user_name = users::get_active(conn, user_id)?.display_name;
+
+    let code = uuid::Uuid::new_v4().simple().to_string();
+    let id = uuid::Uuid::new_v4().to_string();
+    let expires_at = (Utc::now() + Duration::minutes(ttl_minutes.clamp(1, 24 * 60)))
+        .to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+    conn.execute(
+        "INSERT INTO pairing_codes (id, user_id, code_hash, expires_at) VALUES (?1, ?2, ?3, ?4)",
+        params![id, user_id, tokens::hash(&code), expires_at],
+    )
+    .map_err(|e| format!("Failed to save pairing code: {}", e))?;
+
+    let mut payload = json!({ "v": PAYLOAD_VERSION, "endpoint": endpoint, "code": code });
+    if let Some(fingerprint) = fingerprint {
+        payload["fingerprint"] = json!(fingerprint);
+    }
+    let payload = payload.to_string();
+    let qr_svg = QrCode::new(payload.as_bytes())
+        .map_err(|e| format!("Failed to build QR code: {}", e))?
+        .render::<svg::Color>()
+        .min_dimensions(256, 256)
+        .build();
+
+    Ok(PairingInvite {
+        id,
+        user_id: user_id.to_string(),
+        user_name,
+        endpoint: endpoint.to_string(),
+        payload,
+        qr_svg,
+        expires_at,
+    })
+}
+
+/// Exchange a pairing code for an API token. Fails for unknown, used or
+/// expired codes.
+pub fn redeem(conn: &mut Connection, code: &str, device_name: &str) -> Result<IssuedToken, String> {
+    let device_name = device_name.trim();
+    if device_name.is_empty() {
+        return Err("Device name is required".to_string());
+    }
+    let now = Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+
+    let tx = conn.transaction().map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let found: Option<(String, String)> = tx
+        .query_row(
+            "SELECT id, user_id FROM pairing_codes
+             WHERE code_hash = ?1 AND used_at IS NULL AND expires_at > ?2",
+            params![tokens::hash(code), now],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to check pairing code: {}", e))?;
+    let (id, user_id) = found.ok_or_else(|| "Invalid or expired pairing code".to_string())?;
+
+    let issued = tokens::issue(&tx, &user_id, device_name)?;
+    tx.execute(
+        "UPDATE pairing_codes SET used_at = ?1, token_id = ?2 WHERE id = ?3",
+        params![now, issued.info.id, id],
+    )
+    .map_err(|e| format!("Failed to mark pairing code used: {}", e))?;
+    tx.commit().map_err(|e| format!("Failed to commit pairing: {}", e))?;
+
+    log::info!("[api::pairing] Paired '{}' for user {}", device_name, user_id);
+    Ok(issued)
+}
+
+/// Drop invites that were never used and have expired
+pub fn purge_expired(conn: &Connection) -> Result<usize, String> {
+    let now = Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+    conn.execute("DELETE FROM pairing_codes WHERE used_at IS NULL AND expires_at <= ?1", [now])
+        .map_err(|e| format!("Failed to purge pairing codes: {}", e))
+}
+
+/// Base URL phones on the LAN can reach the server at. When listening on all
+/// interfaces, uses the address of the interface that routes outwards.
+pub fn endpoint(bind_address: &str, port: u16, https: bool) -> String {
+    let host = match bind_address.parse::<IpAddr>() {
+        Ok(ip) if !ip.is_unspecified() => ip.to_string(),
+        _ => lan_address().unwrap_or_else(|| "127.0.0.1".to_string()),
+    };
+    format!("{}://{}:{}", if https { "https" } else { "http" }, host, port)
+}
+
+/// Local address of the default route. Connecting a UDP socket sends nothing.
+fn lan_address() -> Option<String> {
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("192.0.2.1:80").ok()?;
+    Some(socket.local_addr().ok()?.ip().to_string())
+}
@@ -0,0 +1,188 @@
+//! Port conflicts and firewall checks for the listener
+//!
+//! Another program on the configured port used to leave the server quietly
+//! stopped. Ports are now checked before binding: a taken port falls back
+//! to the next free one above it (per `fallbackPortCount`), and the port
+//! fallen back to is kept in the settings so links and paired phones stay
+//! valid. The firewall is read through the OS tools — `netsh` on Windows,
+//! `socketfilterfw` on macOS — and reported as unknown elsewhere or when
+//! their output can't be read (e.g. a localized Windows).
+
+use std::io::ErrorKind;
+use std::net::{IpAddr, TcpListener};
+
+use super::types::{ApiServerSettings, FirewallCheck, PortCheck};
+use crate::zkteco::connectivity::run_tool;
+
+pub const PORT_FREE: &str = "free";
+pub const PORT_IN_USE: &str = "in_use";
+pub const PORT_PERMISSION_DENIED: &str = "permission_denied";
+pub const PORT_ADDRESS_UNAVAILABLE: &str = "address_unavailable";
+pub const PORT_ERROR: &str = "error";
+
+pub const FIREWALL_ALLOWED: &str = "allowed";
+pub const FIREWALL_BLOCKED: &str = "blocked";
+pub const FIREWALL_OFF: &str = "off";
+pub const FIREWALL_NOT_APPLICABLE: &str = "not_applicable";
+pub const FIREWALL_UNKNOWN: &str = "unknown";
+
+/// Whether `bind_address:port` can be listened on, by binding it briefly
+pub fn check(bind_address: &str, port: u16) -> PortCheck {
+    let (status, detail) = match TcpListener::bind((bind_address, port)) {
+        Ok(listener) => {
+            drop(listener);
+            (PORT_FREE, None)
+        }
+        Err(e) => {
+            let status = match e.kind() {
+                ErrorKind::AddrInUse => PORT_IN_USE,
+                ErrorKind::PermissionDenied => PORT_PERMISSION_DENIED,
+                ErrorKind::AddrNotAvailable => PORT_ADDRESS_UNAVAILABLE,
+                _ => PORT_ERROR,
+            };
+            (status, Some(e.to_string()))
+        }
+    };
+    PortCheck {
+        bind_address: bind_address.to_string(),
+        port,
+        status: status.to_string(),
+        detail,
+    }
+}
+
+/// Ports to try in order: the configured one, the last fallback, then the
+/// ones above the configured port
+pub fn candidates(settings: &ApiServerSettings) -> Vec<u16> {
+    let mut ports = vec![settings.port];
+    ports.extend(settings.fallback_port.filter(|p| *p != settings.port));
+    if settings.fallback_port_count > 0 {
+        let above = (1..=settings.fallback_port_count).filter_map(|i| settings.port.checked_add(i));
+        ports.extend(above.filter(|p| Some(*p) != settings.fallback_port));
+    }
+    ports
+}
+
+/// What a port check means for someone setting up the server
+pub fn explain(check: &PortCheck) -> String {
+    match check.status.as_str() {
+        PORT_IN_USE => format!("Port {} is already used by another program", check.port),
+        PORT_PERMISSION_DENIED => format!("This app isn't allowed to listen on port {}", check.port),
+        PORT_ADDRESS_UNAVAILABLE => format!("{} isn't an address of this computer", check.bind_address),
+        PORT_FREE => format!("Port {} is free", check.port),
+        _ => format!(
+            "Can't listen on {}:{}: {}",
+            check.bind_address,
+            check.port,
+            check.detail.as_deref().unwrap_or("unknown error")
+        ),
+    }
+}
+
+fn firewall_result(status: &str, detail: impl Into<String>) -> FirewallCheck {
+    FirewallCheck {
+        status: status.to_string(),
+        detail: Some(detail.into()),
+    }
+}
+
+/// Whether the firewall lets LAN clients reach this app on `port`. Blocks
+/// on the OS tools.
+pub fn firewall(bind_address: &str, port: u16) -> FirewallCheck {
+    if bind_address.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback()) {
+        return firewall_result(FIREWALL_NOT_APPLICABLE, "Listening on this computer only");
+    }
+    let exe = std::env::current_exe().ok();
+    let exe = exe.as_deref().map(|p| p.to_string_lossy().to_string()).unwrap_or_default();
+    if cfg!(windows) {
+        windows_firewall(&exe, port)
+    } else if cfg!(target_os = "macos") {
+        macos_firewall(&exe)
+    } else {
+        firewall_result(FIREWALL_UNKNOWN, "Firewall state isn't checked on this system")
+    }
+}
+
+/// Windows Firewall blocks inbound connections for programs without an
+/// allow rule, so no rule counts as blocked
+fn windows_firewall(exe: &str, port: u16) -> FirewallCheck {
+    let args = |list: &[&str]| list.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+    let Some(state) = run_tool("netsh", &args(&["advfirewall", "show", "currentprofile", "state"])) else {
+        return firewall_result(FIREWALL_UNKNOWN, "netsh isn't available");
+    };
+    let state = String::from_utf8_lossy(&state.stdout).to_lowercase();
+    let Some(on) = state
+        .lines()
+        .find(|l| l.starts_with("state"))
+        .map(|l| l.split_whitespace().nth(1) == Some("on"))
+    else {
+        return firewall_result(FIREWALL_UNKNOWN, "Couldn't read the firewall state");
+    };
+    if !on {
+        return firewall_result(FIREWALL_OFF, "Windows Firewall is off for the current network");
+    }
+    let Some(rules) = run_tool("netsh", &args(&["advfirewall", "firewall", "show", "rule", "name=all", "dir=in", "verbose"]))
+    else {
+        return firewall_result(FIREWALL_UNKNOWN, "Couldn't list the firewall rules");
+    };
+    let rules = String::from_utf8_lossy(&rules.stdout).to_lowercase();
+    let exe = exe.to_lowercase();
+    let port = port.to_string();
+    let mut allowed = false;
+    // Rules are blocks of "Field: value" lines separated by blank lines
+    for rule in rules.split("\r\n\r\n").flat_map(|r| r.split("\n\n")) {
+        let field = |name: &str| {
+            rule.lines()
+                .find_map(|l| l.split_once(':').filter(|(k, _)| k.trim() == name).map(|(_, v)| v.trim().to_string()))
+        };
+        if field("enabled").as_deref() != Some("yes") {
+            continue;
+        }
+        let for_app = !exe.is_empty() && field("program").is_some_and(|p| p == exe);
+        let for_port = field("localport").is_some_and(|p| p.split(',').any(|p| p.trim() == port));
+        if !(for_app || for_port) {
+            continue;
+        }
+        match field("action").as_deref() {
+            Some("block") => return firewall_result(FIREWALL_BLOCKED, "An inbound firewall rule blocks this app"),
+            Some("allow") => allowed = true,
+            _ => {}
+        }
+    }
+    if allowed {
+        firewall_result(FIREWALL_ALLOWED, "An inbound firewall rule allows this app")
+    } else {
+        firewall_result(
+            FIREWALL_BLOCKED,
+            format!("No inbound firewall rule allows this app or port {}; add one in Windows Defender Firewall", port),
+        )
+    }
+}
+
+/// The macOS application firewall asks on first listen and remembers the
+/// answer per app
+fn macos_firewall(exe: &str) -> FirewallCheck {
+    const TOOL: &str = "/usr/libexec/ApplicationFirewall/socketfilterfw";
+    let Some(state) = run_tool(TOOL, &["--getglobalstate".to_string()]) else {
+        return firewall_result(FIREWALL_UNKNOWN, "The firewall tool isn't available");
+    };
+    if String::from_utf8_lossy(&state.stdout).to_lowercase().contains("disabled") {
+        return firewall_result(FIREWALL_OFF, "The macOS firewall is off");
+    }
+    if let Some(block_all) = run_tool(TOOL, &["--getblockall".to_string()]) {
+        if String::from_utf8_lossy(&block_all.stdout).to_lowercase().contains("enabled") {
+            return firewall_result(FIREWALL_BLOCKED, "The macOS firewall blocks all incoming connections");
+        }
+    }
+    let Some(app) = run_tool(TOOL, &["--getappblocked".to_string(), exe.to_string()]) else {
+        return firewall_result(FIREWALL_UNKNOWN, "Couldn't read the firewall rule for this app");
+    };
+    let answer = String::from_utf8_lossy(&app.stdout).to_lowercase();
+    if answer.contains("blocked") {
+        firewall_result(FIREWALL_BLOCKED, "The macOS firewall blocks incoming connections to this app")
+    } else if answer.contains("permitted") {
+        firewall_result(FIREWALL_ALLOWED, "The macOS firewall allows incoming connections to this app")
+    } else {
+        firewall_result(FIREWALL_UNKNOWN, "macOS will ask whether to allow incoming connections")
+    }
+}
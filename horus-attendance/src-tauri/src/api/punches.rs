@@ -0,0 +1,113 @@
+//! Punches submitted over the API
+//!
+//! API punches are stored in attendance_logs_raw like device punches, under
+//! the pseudo-device [`API_DEVICE_ID`] and the user's device user ID, with
+//! `source = 'api'`, the phone's position and the employee's note. The
+//! day's summaries are recomputed right away so the punch shows up in
+//! reports without waiting for the next sync.
+
+use chrono::{DateTime, Duration, Local, NaiveDateTime};
+use rusqlite::Connection;
+use serde_json::json;
+
+use super::types::{PunchAccepted, PunchRequest};
+use crate::db::logs::{self, NewPunch};
+use crate::summary::types::ChangeSource;
+use crate::summary::{engine, rules};
+
+/// devices.id that API punches are recorded under (created by migration 5)
+pub const API_DEVICE_ID: &str = "api";
+
+/// attendance_logs_raw.source for punches from the API
+pub const SOURCE_API: &str = "api";
+
+/// How far ahead of this PC's clock a punch may be, for phones with drifting clocks
+const MAX_CLOCK_SKEW_MINUTES: i64 = 5;
+
+/// Longest note a punch may carry
+const MAX_NOTE_CHARS: usize = 500;
+
+/// Device punches are local wall-clock time in this format
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.3fZ";
+
+/// Validate and store a punch for `device_user_id`, then recompute its day
+pub fn record(conn: &mut Connection, device_user_id: &str, request: &PunchRequest) -> Result<PunchAccepted, String> {
+    let local = match request.timestamp.as_deref() {
+        Some(value) => parse_timestamp(value)?,
+        None => Local::now().naive_local(),
+    };
+    if local > Local::now().naive_local() + Duration::minutes(MAX_CLOCK_SKEW_MINUTES) {
+        return Err("Timestamp is in the future".to_string());
+    }
+    match (request.latitude, request.longitude) {
+        (Some(lat), Some(lon)) => {
+            if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
+                return Err("Latitude or longitude out of range".to_string());
+            }
+        }
+        (None, None) => {}
+        _ => return Err("Latitude and longitude must be sent together".to_string()),
+    }
+    let note = request.note.as_deref().map(str::trim).filter(|n| !n.is_empty());
+    if note.is_some_and(|n| n.chars().count() > MAX_NOTE_CHARS) {
+        return Err(format!("Note is longer than {} characters", MAX_NOTE_CHARS));
+    }
+
+    let timestamp = local.format(TIMESTAMP_FORMAT).to_string();
+    let payload = json!({
+        "timestamp": request.timestamp,
+        "punchType": request.punch_type,
+        "latitude": request.latitude,
+        "longitude": request.longitude,
+        "accuracy": request.accuracy,
+        "note": note,
+    });
+
+    let (id, inserted) = logs::insert(
+        conn,
+        &NewPunch {
+            device_id: API_DEVICE_ID,
+            device_user_id,
+            timestamp: &timestamp,
+            punch_type: request.punch_type,
+            raw_payload: Some(payload.to_string()),
+            source: SOURCE_API,
+            latitude: request.latitude,
+            longitude: request.longitude,
+            note,
+        },
+    )?;
+    if !inserted {
+        return Ok(PunchAccepted {
+            id,
+            timestamp,
+            duplicate: true,
+        });
+    }
+
+    let date = rules::working_date(&timestamp, engine::day_cutoff_hour(conn)?);
+    let actor = format!("api:{}", device_user_id);
+    let source = ChangeSource {
+        reason: "api_punch",
+        actor: Some(&actor),
+    };
+    engine::recompute(conn, &date, &date, &source)?;
+    log::info!("[api] Recorded punch for {} at {}", device_user_id, timestamp);
+
+    Ok(PunchAccepted {
+        id,
+        timestamp,
+        duplicate: false,
+    })
+}
+
+/// RFC 3339 timestamps are converted to this PC's local time; ones without
+/// an offset are taken as local already
+fn parse_timestamp(value: &str) -> Result<NaiveDateTime, String> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+        return Ok(dt.with_timezone(&Local).naive_local());
+    }
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f")
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S"))
+        .map_err(|_| format!("Invalid timestamp: {}", value))
+}
@@ -0,0 +1,91 @@
+//! Department-scoped reads for manager tokens and API keys (`/team` routes)
+//!
+//! Every query here takes the caller's departments and filters on them in
+//! SQL, so a manager can't reach another department's users by guessing IDs.
+//! API keys see every department, including users without one.
+
+use rusqlite::{params, Connection};
+
+use super::me;
+use super::types::{TeamDay, TeamMember};
+
+/// The departments as a JSON array, for `IN (SELECT value FROM json_each(?))`;
+/// NULL when unrestricted
+fn department_list(department_ids: Option<&[String]>) -> Option<String> {
+    department_ids.map(|ids| serde_json::to_string(ids).unwrap_or_else(|_| "[]".to_string()))
+}
+
+/// Whether `department_id` is visible to the caller
+pub fn allows(department_ids: Option<&[String]>, department_id: &str) -> bool {
+    department_ids.map_or(true, |ids| ids.iter().any(|id| id == department_id))
+}
+
+/// Users of the token's departments, optionally only one of them, by
+/// department then name
+pub fn users(
+    conn: &Connection,
+    department_ids: Option<&[String]>,
+    department_id: Option<&str>,
+) -> Result<Vec<TeamMember>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT u.id, u.display_name, u.employee_code, d.id, d.name, u.status
+             FROM users u LEFT JOIN departments d ON d.id = u.department_id
+             WHERE (?1 IS NULL OR u.department_id IN (SELECT value FROM json_each(?1)))
+               AND (?2 IS NULL OR u.department_id = ?2)
+             ORDER BY d.name, u.display_name",
+        )
+        .map_err(|e| format!("Failed to query users: {}", e))?;
+    let rows = stmt
+        .query_map(params![department_list(department_ids), department_id], |row| {
+            Ok(TeamMember {
+                user_id: row.get(0)?,
+                display_name: row.get(1)?,
+                employee_code: row.get(2)?,
+                department_id: row.get(3)?,
+                department: row.get(4)?,
+                status: row.get(5)?,
+            })
+        })
+        .map_err(|e| format!("Failed to query users: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read users: {}", e))
+}
+
+/// Daily summaries of the token's departments between two dates
+/// (inclusive, default the current month so far), optionally only one
+/// department, by user then date
+pub fn attendance(
+    conn: &Connection,
+    department_ids: Option<&[String]>,
+    department_id: Option<&str>,
+    from: Option<&str>,
+    to: Option<&str>,
+) -> Result<Vec<TeamDay>, String> {
+    let (from, to) = me::period(from, to)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT s.user_id, s.date, s.check_in_time, s.check_out_time, s.status, s.late_minutes, s.early_minutes
+             FROM attendance_day_summary s JOIN users u ON u.id = s.user_id
+             WHERE (?1 IS NULL OR u.department_id IN (SELECT value FROM json_each(?1)))
+               AND (?2 IS NULL OR u.department_id = ?2)
+               AND s.date >= ?3 AND s.date <= ?4
+             ORDER BY s.user_id, s.date",
+        )
+        .map_err(|e| format!("Failed to query summaries: {}", e))?;
+    let rows = stmt
+        .query_map(params![department_list(department_ids), department_id, from, to], |row| {
+            Ok(TeamDay {
+                user_id: row.get(0)?,
+                date: row.get(1)?,
+                check_in_time: row.get(2)?,
+                check_out_time: row.get(3)?,
+                status: row.get(4)?,
+                late_minutes: row.get(5)?,
+                early_minutes: row.get(6)?,
+            })
+        })
+        .map_err(|e| format!("Failed to query summaries: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read summaries: {}", e))
+}
@@ -0,0 +1,277 @@
+//! Signed self-service report links
+//!
+//! HR generates a link per employee for a month; opening it on the LAN
+//! returns that employee's monthly attendance (JSON, or the register PDF with
+//! `&format=pdf`) and nothing else. Links carry no server-side state:
+//!
+//! ```text
+//! token = base64url("v1|<user id>|<YYYY-MM>|<expires unix>") "." base64url(HMAC-SHA256)
+//! ```
+//!
+//! The HMAC key is kept in the OS keychain, falling back to a file in the
+//! app config directory where no keychain is available. Rotating the key
+//! invalidates every link issued so far.
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{Duration, NaiveDate, Utc};
+use hmac::{Hmac, Mac};
+use rusqlite::{params, Connection};
+use sha2::Sha256;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use tauri::Manager;
+
+use super::me;
+use super::types::{SelfServiceLink, SelfServiceReport};
+use crate::export::{pdf, sheet};
+use crate::secrets::keychain;
+
+type HmacSha256 = Hmac<Sha256>;
+
+const TOKEN_VERSION: &str = "v1";
+
+/// Keychain credential name of the signing key
+const SIGNING_KEY_NAME: &str = "report-signing-key";
+
+/// Fallback key file in the app config directory
+const SIGNING_KEY_FILE: &str = "report_signing.key";
+
+/// Days a link stays valid by default
+pub const DEFAULT_TTL_DAYS: i64 = 30;
+
+/// A verified link: whose report, for which month
+pub struct Grant {
+    pub user_id: String,
+    pub year: i32,
+    pub month: u32,
+}
+
+fn key_file(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app
+        .path()
+        .app_config_dir()
+        .map_err(|e| format!("Cannot resolve app config dir: {}", e))?
+        .join(SIGNING_KEY_FILE))
+}
+
+fn new_key() -> String {
+    format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple())
+}
+
+/// The signing key, created on first use. Blocks on the keychain.
+fn signing_key(app: &tauri::AppHandle) -> Result<Vec<u8>, String> {
+    let account = keychain::credential_account(SIGNING_KEY_NAME);
+    match keychain::get(&account) {
+        Ok(Some(key)) => return Ok(key.into_bytes()),
+        Ok(None) => {
+            let key = new_key();
+            if keychain::set(&account, &key).is_ok() {
+                return Ok(key.into_bytes());
+            }
+        }
+        Err(e) => log::warn!("[api::self_service] Using key file: {}", e),
+    }
+
+    let path = key_file(app)?;
+    if let Ok(key) = fs::read_to_string(&path) {
+        if !key.trim().is_empty() {
+            return Ok(key.trim().as_bytes().to_vec());
+        }
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create config directory: {}", e))?;
+    }
+    let key = new_key();
+    fs::write(&path, &key).map_err(|e| format!("Failed to save signing key: {}", e))?;
+    Ok(key.into_bytes())
+}
+
+/// Replace the signing key, invalidating all issued links. Blocks on the keychain.
+pub fn rotate_key(app: &tauri::AppHandle) -> Result<(), String> {
+    keychain::delete(&keychain::credential_account(SIGNING_KEY_NAME)).ok();
+    let path = key_file(app)?;
+    if path.exists() {
+        fs::remove_file(&path).map_err(|e| format!("Failed to remove old signing key: {}", e))?;
+    }
+    signing_key(app).map(|_| ())
+}
+
+fn mac(key: &[u8], payload: &[u8]) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(payload);
+    mac
+}
+
+fn sign(key: &[u8], user_id: &str, year: i32, month: u32, expires: i64) -> String {
+    let payload = format!("{}|{}|{:04}-{:02}|{}", TOKEN_VERSION, user_id, year, month, expires);
+    let signature = mac(key, payload.as_bytes()).finalize().into_bytes();
+    format!("{}.{}", URL_SAFE_NO_PAD.encode(&payload), URL_SAFE_NO_PAD.encode(signature))
+}
+
+/// Check a link token's signature and expiry
+pub fn verify(app: &tauri::AppHandle, token: &str) -> Result<Grant, String> {
+    verify_token(&signing_key(app)?, token, Utc::now().timestamp())
+}
+
+/// Check a token against `key` as of the unix time `now`
+fn verify_token(key: &[u8], token: &str, now: i64) -> Result<Grant, String> {
+    let invalid = || "Invalid report link".to_string();
+    let (payload, signature) = token.split_once('.').ok_or_else(invalid)?;
+    let payload = URL_SAFE_NO_PAD.decode(payload).map_err(|_| invalid())?;
+    let signature = URL_SAFE_NO_PAD.decode(signature).map_err(|_| invalid())?;
+    mac(key, &payload).verify_slice(&signature).map_err(|_| invalid())?;
+
+    let payload = String::from_utf8(payload).map_err(|_| invalid())?;
+    let parts: Vec<&str> = payload.split('|').collect();
+    let [TOKEN_VERSION, user_id, month, expires] = parts[..] else {
+        return Err(invalid());
+    };
+    let expires: i64 = expires.parse().map_err(|_| invalid())?;
+    if expires <= now {
+        return Err("This report link has expired; ask HR for a new one".to_string());
+    }
+    let first = NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d").map_err(|_| invalid())?;
+    Ok(Grant {
+        user_id: user_id.to_string(),
+        year: chrono::Datelike::year(&first),
+        month: chrono::Datelike::month(&first),
+    })
+}
+
+/// Links for active users (optionally one department or one user), with the
+/// email address to send each to. Blocks on the keychain.
+#[allow(clippy::too_many_arguments)]
+pub fn create_links(
+    app: &tauri::AppHandle,
+    conn: &Connection,
+    endpoint: &str,
+    year: i32,
+    month: u32,
+    department_id: Option<&str>,
+    user_id: Option<&str>,
+    ttl_days: i64,
+) -> Result<Vec<SelfServiceLink>, String> {
+    NaiveDate::from_ymd_opt(year, month, 1).ok_or_else(|| format!("Invalid month: {}-{}", year, month))?;
+    let key = signing_key(app)?;
+    let expires_at = Utc::now() + Duration::days(ttl_days.clamp(1, 366));
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, display_name, email FROM users
+             WHERE status = 'active' AND (?1 IS NULL OR department_id = ?1) AND (?2 IS NULL OR id = ?2)
+             ORDER BY display_name",
+        )
+        .map_err(|e| format!("Failed to query users: {}", e))?;
+    let rows = stmt
+        .query_map(params![department_id, user_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, Option<String>>(2)?))
+        })
+        .map_err(|e| format!("Failed to query users: {}", e))?;
+
+    let mut links = Vec::new();
+    for row in rows {
+        let (user_id, user_name, email) = row.map_err(|e| format!("Failed to read users: {}", e))?;
+        let token = sign(&key, &user_id, year, month, expires_at.timestamp());
+        links.push(SelfServiceLink {
+            url: format!("{}/reports/monthly?token={}", endpoint, token),
+            user_id,
+            user_name,
+            email: email.filter(|e| !e.trim().is_empty()),
+            expires_at: expires_at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        });
+    }
+    Ok(links)
+}
+
+/// The granted month for the granted user
+pub fn report(conn: &Connection, grant: &Grant) -> Result<SelfServiceReport, String> {
+    let register = sheet::load_monthly(conn, grant.year, grant.month, None, Some(&grant.user_id))?;
+    let row = register.rows.first().ok_or("This employee is no longer active")?;
+    let totals: BTreeMap<String, i64> = sheet::TOTAL_HEADINGS
+        .iter()
+        .map(|heading| heading.to_string())
+        .zip(row.totals())
+        .collect();
+
+    let from = register.dates.first().map(|d| d.format("%Y-%m-%d").to_string());
+    let to = register.dates.last().map(|d| d.format("%Y-%m-%d").to_string());
+    Ok(SelfServiceReport {
+        profile: me::profile(conn, &grant.user_id)?,
+        period: register.period,
+        days: me::attendance(conn, &grant.user_id, from.as_deref(), to.as_deref())?,
+        totals,
+    })
+}
+
+/// The granted user's register page as a PDF
+pub fn report_pdf(conn: &Connection, grant: &Grant) -> Result<Vec<u8>, String> {
+    let register = sheet::load_monthly(conn, grant.year, grant.month, None, Some(&grant.user_id))?;
+    if register.rows.is_empty() {
+        return Err("This employee is no longer active".to_string());
+    }
+    pdf::monthly_register_bytes(&register)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &[u8] = b"test-signing-key";
+    const NOW: i64 = 1_717_200_000;
+
+    fn payload_of(token: &str) -> String {
+        let (payload, _) = token.split_once('.').unwrap();
+        String::from_utf8(URL_SAFE_NO_PAD.decode(payload).unwrap()).unwrap()
+    }
+
+    /// The token with its payload replaced and its signature kept
+    fn with_payload(token: &str, payload: &str) -> String {
+        let (_, signature) = token.split_once('.').unwrap();
+        format!("{}.{}", URL_SAFE_NO_PAD.encode(payload), signature)
+    }
+
+    #[test]
+    fn valid_link_grants_its_user_and_month() {
+        let token = sign(KEY, "u1", 2024, 5, NOW + 60);
+        let grant = verify_token(KEY, &token, NOW).unwrap();
+        assert_eq!((grant.user_id.as_str(), grant.year, grant.month), ("u1", 2024, 5));
+    }
+
+    #[test]
+    fn tampered_signature_is_refused() {
+        let token = sign(KEY, "u1", 2024, 5, NOW + 60);
+        let (payload, signature) = token.split_once('.').unwrap();
+        let mut bytes = URL_SAFE_NO_PAD.decode(signature).unwrap();
+        bytes[0] ^= 1;
+        let tampered = format!("{}.{}", payload, URL_SAFE_NO_PAD.encode(bytes));
+        assert_eq!(verify_token(KEY, &tampered, NOW).err().as_deref(), Some("Invalid report link"));
+
+        // Signed with another key, as after a rotation
+        let other = sign(b"rotated-key", "u1", 2024, 5, NOW + 60);
+        assert!(verify_token(KEY, &other, NOW).is_err());
+        // A later expiry needs a new signature
+        let later = payload_of(&token).replace(&(NOW + 60).to_string(), &(NOW + 86_400).to_string());
+        let extended = with_payload(&token, &later);
+        assert!(verify_token(KEY, &extended, NOW).is_err());
+    }
+
+    #[test]
+    fn expired_link_is_refused() {
+        let token = sign(KEY, "u1", 2024, 5, NOW);
+        let err = verify_token(KEY, &token, NOW).err().unwrap();
+        assert!(err.contains("expired"), "{}", err);
+        assert!(verify_token(KEY, &token, NOW - 1).is_ok());
+    }
+
+    #[test]
+    fn link_cannot_be_moved_to_another_employee() {
+        let token = sign(KEY, "u1", 2024, 5, NOW + 60);
+        let forged = with_payload(&token, &payload_of(&token).replace("|u1|", "|u2|"));
+        assert_eq!(verify_token(KEY, &forged, NOW).err().as_deref(), Some("Invalid report link"));
+
+        let other_month = with_payload(&token, &payload_of(&token).replace("2024-05", "2024-06"));
+        assert!(verify_token(KEY, &other_month, NOW).is_err());
+    }
+}
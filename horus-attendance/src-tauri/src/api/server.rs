@@ -0,0 +1,520 @@
+//! HTTP listener and routing
+//!
+//! tiny_http accepts connections on a dedicated thread; each request is then
+//! handled on its own short-lived thread with a pooled database connection,
+//! so a slow client can't hold up the others. Stopping the server unblocks
+//! the accept loop and lets in-flight requests finish. Every request passes
+//! the rate limits and lockouts in [`limits`](super::limits) first.
+
+use std::io::Read;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use serde::Serialize;
+use serde_json::json;
+use tiny_http::{Header, Method, Request, Response, Server, SslConfig};
+
+use super::limits::Limits;
+use super::types::{
+    ApiMetrics, ApiServerSettings, ApiServerStart, ApiServerStatus, FirewallCheck, PairRequest, Principal, PunchRequest,
+};
+use super::{keys, me, pairing, ports, punches, scope, self_service, tokens};
+use crate::activity::registry;
+use crate::closure::disputes;
+use crate::closure::types::DisputeRequest;
+use crate::db::{self, pool::PooledConnection};
+use crate::export::{pdf, sheet};
+use crate::tls::types::TlsMaterial;
+
+/// Settings key for the server configuration
+pub const API_SERVER_KEY: &str = "apiServer";
+
+/// Routes whose 403 means a bad pairing code or report link, counted
+/// towards lockouts like a bad token
+const SIGN_IN_ROUTES: &[&str] = &["/pair", "/reports/monthly", "/reports/disputes"];
+
+/// Routes that check no credential, so serving them clears no failures
+const OPEN_ROUTES: &[&str] = &["/health"];
+
+struct Running {
+    server: Arc<Server>,
+    limits: Arc<Limits>,
+    address: String,
+    tls: bool,
+    started_at: String,
+}
+
+/// The running server, if any, and how the last start went, held as Tauri
+/// managed state
+#[derive(Default)]
+pub struct ApiServer {
+    running: Mutex<Option<Running>>,
+    last_start: Mutex<Option<ApiServerStart>>,
+}
+
+impl ApiServer {
+    /// Start listening, over HTTPS when given a certificate. Restarts the
+    /// server if it is already running. A taken port falls back to a free
+    /// one per the settings; the attempt is kept for [`status`](Self::status)
+    /// either way.
+    pub fn start(
+        &self,
+        app: tauri::AppHandle,
+        settings: &ApiServerSettings,
+        tls: Option<TlsMaterial>,
+    ) -> Result<ApiServerStatus, String> {
+        self.stop()?;
+
+        let mut attempt = ApiServerStart {
+            attempted_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            requested_port: settings.port,
+            port: None,
+            fallback_used: false,
+            checks: Vec::new(),
+            firewall: None,
+            error: None,
+        };
+        let outcome = self.listen(app, settings, tls, &mut attempt);
+        if let Err(e) = &outcome {
+            attempt.error = Some(e.clone());
+        }
+        *self.last_start.lock().map_err(|_| "API server state is poisoned".to_string())? = Some(attempt);
+        outcome?;
+        self.status()
+    }
+
+    /// Bind the first free candidate port and start the accept loop
+    fn listen(
+        &self,
+        app: tauri::AppHandle,
+        settings: &ApiServerSettings,
+        tls: Option<TlsMaterial>,
+        attempt: &mut ApiServerStart,
+    ) -> Result<(), String> {
+        for port in ports::candidates(settings) {
+            let check = ports::check(&settings.bind_address, port);
+            let status = check.status.clone();
+            attempt.checks.push(check);
+            if status == ports::PORT_FREE {
+                attempt.port = Some(port);
+                break;
+            }
+            // Only a taken port is worth falling back from; a bad address fails on every port
+            if status != ports::PORT_IN_USE {
+                break;
+            }
+        }
+        let Some(port) = attempt.port else {
+            let last = attempt.checks.last().expect("the configured port is always checked");
+            return Err(if attempt.checks.len() > 1 && last.status == ports::PORT_IN_USE {
+                format!(
+                    "Failed to start API server: port {} and the {} ports tried after it are in use",
+                    settings.port,
+                    attempt.checks.len() - 1
+                )
+            } else {
+                format!("Failed to start API server: {}", ports::explain(last))
+            });
+        };
+        attempt.fallback_used = port != settings.port;
+        if attempt.fallback_used {
+            log::warn!("[api] Port {} is in use; falling back to {}", settings.port, port);
+        }
+
+        let address = format!("{}:{}", settings.bind_address, port);
+        let https = tls.is_some();
+        let server = match tls {
+            Some(tls) => Server::https(
+                &address,
+                SslConfig {
+                    certificate: tls.certificate,
+                    private_key: tls.private_key,
+                },
+            ),
+            None => Server::http(&address),
+        }
+        .map_err(|e| format!("Failed to start API server on {}: {}", address, e))?;
+        let server = Arc::new(server);
+        let listener = Arc::clone(&server);
+        let limits = Arc::new(Limits::new(settings));
+        let request_limits = Arc::clone(&limits);
+        thread::Builder::new()
+            .name("api-server".to_string())
+            .spawn(move || {
+                for request in listener.incoming_requests() {
+                    let app = app.clone();
+                    let limits = Arc::clone(&request_limits);
+                    let spawned = thread::Builder::new()
+                        .name("api-request".to_string())
+                        .spawn(move || handle(&app, &limits, request));
+                    if let Err(e) = spawned {
+                        log::error!("[api] Failed to spawn request handler: {}", e);
+                    }
+                }
+                log::info!("[api] Server stopped");
+            })
+            .map_err(|e| format!("Failed to start API server thread: {}", e))?;
+
+        log::info!("[api] Listening on {} ({})", address, if https { "HTTPS" } else { "HTTP" });
+        *self.lock()? = Some(Running {
+            server,
+            limits,
+            address,
+            tls: https,
+            started_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        });
+        Ok(())
+    }
+
+    /// Attach the firewall check, which runs after starting, to the last start
+    pub fn set_firewall(&self, firewall: FirewallCheck) -> Result<(), String> {
+        if let Some(attempt) = self
+            .last_start
+            .lock()
+            .map_err(|_| "API server state is poisoned".to_string())?
+            .as_mut()
+        {
+            attempt.firewall = Some(firewall);
+        }
+        Ok(())
+    }
+
+    /// Stop listening. A no-op when not running.
+    pub fn stop(&self) -> Result<(), String> {
+        if let Some(running) = self.lock()?.take() {
+            running.server.unblock();
+        }
+        Ok(())
+    }
+
+    pub fn status(&self) -> Result<ApiServerStatus, String> {
+        let last_start = self
+            .last_start
+            .lock()
+            .map_err(|_| "API server state is poisoned".to_string())?
+            .clone();
+        Ok(status_of(self.lock()?.as_ref(), last_start))
+    }
+
+    /// Request counters since the server started; none when not running
+    pub fn metrics(&self) -> Result<Option<ApiMetrics>, String> {
+        Ok(self.lock()?.as_ref().map(|running| running.limits.metrics()))
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, Option<Running>>, String> {
+        self.running.lock().map_err(|_| "API server state is poisoned".to_string())
+    }
+}
+
+fn status_of(running: Option<&Running>, last_start: Option<ApiServerStart>) -> ApiServerStatus {
+    ApiServerStatus {
+        running: running.is_some(),
+        address: running.map(|r| r.address.clone()),
+        tls: running.is_some_and(|r| r.tls),
+        started_at: running.map(|r| r.started_at.clone()),
+        last_start,
+    }
+}
+
+/// An error response: status code and message
+type Failure = (u16, String);
+
+/// A successful response
+struct Reply {
+    status: u16,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Reply {
+    fn json<T: Serialize>(status: u16, value: &T) -> Result<Self, Failure> {
+        let body = serde_json::to_vec(value).map_err(|e| (500, format!("Failed to serialize response: {}", e)))?;
+        Ok(Self {
+            status,
+            content_type: "application/json",
+            body,
+        })
+    }
+}
+
+/// Paths with a handler, for telling 405 from 404
+const ROUTES: &[&str] = &[
+    "/health",
+    "/metrics",
+    "/pair",
+    "/punches",
+    "/me",
+    "/me/attendance",
+    "/reports/monthly",
+    "/reports/disputes",
+    "/team/users",
+    "/team/attendance",
+    "/team/reports/monthly",
+];
+
+fn handle(app: &tauri::AppHandle, limits: &Limits, mut request: Request) {
+    let url = request.url().to_string();
+    let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+    let client = request.remote_addr().map(|addr| addr.ip().to_string()).unwrap_or_default();
+    // Report links carry theirs in the query
+    let credential = bearer_token(&request)
+        .or_else(|| query_param(query, "token").map(str::to_string))
+        .map(|token| tokens::hash(&token));
+    if let Err(rejection) = limits.admit(&client, credential.as_deref()) {
+        let retry_after = Header::from_bytes(&b"Retry-After"[..], rejection.retry_after_secs.to_string().as_bytes())
+            .expect("retry-after header is valid");
+        let reply = Reply {
+            status: 429,
+            content_type: "application/json",
+            body: json!({ "error": rejection.message }).to_string().into_bytes(),
+        };
+        respond(request, reply, Some(retry_after));
+        return;
+    }
+
+    let max_body = limits.max_body_bytes();
+    let result = match (request.method(), path) {
+        (Method::Get, "/health") => Reply::json(200, &json!({ "status": "ok" })),
+        (Method::Get, "/metrics") => get_metrics(app, limits, &request),
+        (Method::Post, "/pair") => post_pair(app, &mut request, max_body),
+        (Method::Post, "/punches") => post_punch(app, &mut request, max_body),
+        (Method::Get, "/me") => get_me(app, &request),
+        (Method::Get, "/me/attendance") => get_my_attendance(app, &request, query),
+        (Method::Get, "/reports/monthly") => get_monthly_report(app, query),
+        (Method::Post, "/reports/disputes") => post_dispute(app, &mut request, query, max_body),
+        (Method::Get, "/team/users") => get_team_users(app, &request, query),
+        (Method::Get, "/team/attendance") => get_team_attendance(app, &request, query),
+        (Method::Get, "/team/reports/monthly") => get_team_register(app, &request, query),
+        _ if ROUTES.contains(&path) => Err((405, "Method not allowed".to_string())),
+        _ => Err((404, "Not found".to_string())),
+    };
+
+    match &result {
+        Ok(_) if !OPEN_ROUTES.contains(&path) => limits.record_success(&client, credential.as_deref()),
+        Err((401, _)) => limits.record_failure(&client, credential.as_deref()),
+        Err((403, _)) if SIGN_IN_ROUTES.contains(&path) => limits.record_failure(&client, credential.as_deref()),
+        Err((413, _)) => limits.record_too_large(),
+        _ => {}
+    }
+
+    let reply = result.unwrap_or_else(|(status, message)| {
+        if status >= 500 {
+            log::error!("[api] {} {}: {}", request.method(), path, message);
+        }
+        Reply {
+            status,
+            content_type: "application/json",
+            body: json!({ "error": message }).to_string().into_bytes(),
+        }
+    });
+    respond(request, reply, None);
+}
+
+fn respond(request: Request, reply: Reply, extra_header: Option<Header>) {
+    let content_type =
+        Header::from_bytes(&b"Content-Type"[..], reply.content_type.as_bytes()).expect("content type header is valid");
+    let mut response = Response::from_data(reply.body)
+        .with_status_code(reply.status)
+        .with_header(content_type);
+    if let Some(header) = extra_header {
+        response = response.with_header(header);
+    }
+    if let Err(e) = request.respond(response) {
+        log::warn!("[api] Failed to send response: {}", e);
+    }
+}
+
+/// The token or API key holder, with a connection to serve them from
+fn authenticate(app: &tauri::AppHandle, request: &Request) -> Result<(PooledConnection, Principal), Failure> {
+    let token = bearer_token(request).ok_or((401, "Missing bearer token".to_string()))?;
+    let conn = db::open(app).map_err(|e| (503, e))?;
+    let principal = if token.starts_with(keys::KEY_PREFIX) {
+        keys::authenticate(&conn, &token)
+    } else {
+        tokens::authenticate(&conn, &token)
+    }
+    .map_err(|e| (500, e))?
+    .ok_or((401, "Invalid or revoked token".to_string()))?;
+    Ok((conn, principal))
+}
+
+/// The user a token acts for; API keys have none
+fn require_user(principal: &Principal) -> Result<&str, Failure> {
+    principal
+        .user_id
+        .as_deref()
+        .ok_or((403, "API keys can't use the /me routes".to_string()))
+}
+
+/// Check the caller may read team data under `scope_name`, and return the
+/// `department` query parameter when it is within their departments.
+/// Managers hold every read scope for their departments.
+fn authorize_team<'a>(principal: &Principal, query: &'a str, scope_name: &str) -> Result<Option<&'a str>, Failure> {
+    let allowed = match principal.role.as_str() {
+        tokens::ROLE_MANAGER => true,
+        keys::ROLE_INTEGRATION => principal.scopes.iter().any(|s| s == scope_name),
+        _ => false,
+    };
+    if !allowed {
+        return Err((403, format!("This credential lacks the {} scope", scope_name)));
+    }
+    let department = query_param(query, "department");
+    if department.is_some_and(|id| !scope::allows(principal.department_ids.as_deref(), id)) {
+        return Err((403, "Department is outside this token's scope".to_string()));
+    }
+    Ok(department)
+}
+
+/// Request counters; needs an API key with the metrics:read scope
+fn get_metrics(app: &tauri::AppHandle, limits: &Limits, request: &Request) -> Result<Reply, Failure> {
+    let (_, principal) = authenticate(app, request)?;
+    let allowed = principal.role == keys::ROLE_INTEGRATION
+        && principal.scopes.iter().any(|s| s == keys::SCOPE_METRICS_READ);
+    if !allowed {
+        return Err((403, format!("This credential lacks the {} scope", keys::SCOPE_METRICS_READ)));
+    }
+    Reply::json(200, &limits.metrics())
+}
+
+fn post_pair(app: &tauri::AppHandle, request: &mut Request, max_body: u64) -> Result<Reply, Failure> {
+    let body: PairRequest = read_json(request, max_body)?;
+    let _activity = registry::begin(app, "API pairing").map_err(|e| (503, e))?;
+    let mut conn = db::open(app).map_err(|e| (503, e))?;
+    let issued = pairing::redeem(&mut conn, &body.code, &body.device_name).map_err(|e| (403, e))?;
+    Reply::json(201, &issued)
+}
+
+fn get_me(app: &tauri::AppHandle, request: &Request) -> Result<Reply, Failure> {
+    let (conn, principal) = authenticate(app, request)?;
+    let profile = me::profile(&conn, require_user(&principal)?).map_err(|e| (500, e))?;
+    Reply::json(200, &profile)
+}
+
+fn get_my_attendance(app: &tauri::AppHandle, request: &Request, query: &str) -> Result<Reply, Failure> {
+    let (conn, principal) = authenticate(app, request)?;
+    let days = me::attendance(&conn, require_user(&principal)?, query_param(query, "from"), query_param(query, "to"))
+        .map_err(|e| (400, e))?;
+    Reply::json(200, &days)
+}
+
+fn get_monthly_report(app: &tauri::AppHandle, query: &str) -> Result<Reply, Failure> {
+    let token = query_param(query, "token").ok_or((401, "Missing report token".to_string()))?;
+    let grant = self_service::verify(app, token).map_err(|e| (403, e))?;
+    let conn = db::open(app).map_err(|e| (503, e))?;
+    if query_param(query, "format") == Some("pdf") {
+        let body = self_service::report_pdf(&conn, &grant).map_err(|e| (404, e))?;
+        return Ok(Reply {
+            status: 200,
+            content_type: "application/pdf",
+            body,
+        });
+    }
+    let report = self_service::report(&conn, &grant).map_err(|e| (404, e))?;
+    Reply::json(200, &report)
+}
+
+fn post_dispute(app: &tauri::AppHandle, request: &mut Request, query: &str, max_body: u64) -> Result<Reply, Failure> {
+    let token = query_param(query, "token").ok_or((401, "Missing report token".to_string()))?;
+    let grant = self_service::verify(app, token).map_err(|e| (403, e))?;
+    let body: DisputeRequest = read_json(request, max_body)?;
+    let _activity = registry::begin(app, "API dispute").map_err(|e| (503, e))?;
+    let conn = db::open(app).map_err(|e| (503, e))?;
+    let receipt = disputes::submit(&conn, &grant, &body).map_err(|e| (422, e))?;
+    Reply::json(201, &receipt)
+}
+
+fn get_team_users(app: &tauri::AppHandle, request: &Request, query: &str) -> Result<Reply, Failure> {
+    let (conn, principal) = authenticate(app, request)?;
+    let department = authorize_team(&principal, query, keys::SCOPE_USERS_READ)?;
+    let users = scope::users(&conn, principal.department_ids.as_deref(), department).map_err(|e| (500, e))?;
+    Reply::json(200, &users)
+}
+
+fn get_team_attendance(app: &tauri::AppHandle, request: &Request, query: &str) -> Result<Reply, Failure> {
+    let (conn, principal) = authenticate(app, request)?;
+    let department = authorize_team(&principal, query, keys::SCOPE_ATTENDANCE_READ)?;
+    let days = scope::attendance(
+        &conn,
+        principal.department_ids.as_deref(),
+        department,
+        query_param(query, "from"),
+        query_param(query, "to"),
+    )
+    .map_err(|e| (400, e))?;
+    Reply::json(200, &days)
+}
+
+fn get_team_register(app: &tauri::AppHandle, request: &Request, query: &str) -> Result<Reply, Failure> {
+    let (conn, principal) = authenticate(app, request)?;
+    // A manager of several departments picks one; API keys may take the whole organization
+    let department = match (
+        authorize_team(&principal, query, keys::SCOPE_REPORTS_READ)?,
+        principal.department_ids.as_deref(),
+    ) {
+        (Some(id), _) => Some(id.to_string()),
+        (None, None) => None,
+        (None, Some([only])) => Some(only.clone()),
+        (None, Some(_)) => return Err((400, "Pick a department with ?department=".to_string())),
+    };
+    let year = query_param(query, "year").and_then(|v| v.parse().ok());
+    let month = query_param(query, "month").and_then(|v| v.parse().ok());
+    let (Some(year), Some(month)) = (year, month) else {
+        return Err((400, "Give the month as ?year=&month=".to_string()));
+    };
+    let sheet = sheet::load_monthly(&conn, year, month, department.as_deref(), None).map_err(|e| (400, e))?;
+    let body = pdf::monthly_register_bytes(&sheet).map_err(|e| (500, e))?;
+    Ok(Reply {
+        status: 200,
+        content_type: "application/pdf",
+        body,
+    })
+}
+
+fn post_punch(app: &tauri::AppHandle, request: &mut Request, max_body: u64) -> Result<Reply, Failure> {
+    let (mut conn, principal) = authenticate(app, request)?;
+    let device_user_id = principal
+        .device_user_id
+        .ok_or((403, "This token's user has no device user ID to punch under".to_string()))?;
+    let body: PunchRequest = read_json(request, max_body)?;
+    let _activity = registry::begin(app, "API punch").map_err(|e| (503, e))?;
+
+    let accepted = punches::record(&mut conn, &device_user_id, &body).map_err(|e| (422, e))?;
+    let status = if accepted.duplicate { 200 } else { 201 };
+    Reply::json(status, &accepted)
+}
+
+fn bearer_token(request: &Request) -> Option<String> {
+    request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv("Authorization"))
+        .and_then(|h| h.value.as_str().strip_prefix("Bearer "))
+        .map(|token| token.trim().to_string())
+}
+
+/// Value of a query string parameter. Only used for plain values like
+/// dates, so no percent-decoding.
+fn query_param<'a>(query: &'a str, name: &str) -> Option<&'a str> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
+        .filter(|value| !value.is_empty())
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(request: &mut Request, max_body: u64) -> Result<T, Failure> {
+    if request.body_length().is_some_and(|len| len as u64 > max_body) {
+        return Err((413, "Request body too large".to_string()));
+    }
+    let mut body = Vec::new();
+    request
+        .as_reader()
+        .take(max_body + 1)
+        .read_to_end(&mut body)
+        .map_err(|e| (400, format!("Failed to read request body: {}", e)))?;
+    if body.len() as u64 > max_body {
+        return Err((413, "Request body too large".to_string()));
+    }
+    serde_json::from_slice(&body).map_err(|e| (400, format!("Invalid JSON: {}", e)))
+}
@@ -0,0 +1,213 @@
+//! Per-user API tokens
+//!
+//! Tokens are random, prefixed with `hat_` so they are recognisable in logs
+//! and config files, and stored only as SHA-256 hashes.
+//!
+//! An employee token acts for its user only. A manager token additionally
+//! reads the users, summaries and registers of the departments assigned to
+//! it (see [`super::scope`]).
+
+use rusqlite::{params, Connection, OptionalExtension};
+use sha2::{Digest, Sha256};
+
+use super::types::{IssuedToken, Principal, UserToken};
+use crate::db::users;
+
+const TOKEN_PREFIX: &str = "hat_";
+
+pub const ROLE_EMPLOYEE: &str = "employee";
+pub const ROLE_MANAGER: &str = "manager";
+
+/// Hex SHA-256 of a token, as stored in user_api_tokens.token_hash
+pub fn hash(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// 244 random bits from two v4 UUIDs, after `prefix`
+pub(super) fn generate(prefix: &str) -> String {
+    format!(
+        "{}{}{}",
+        prefix,
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    )
+}
+
+/// Issue a token for a user. The user needs a device user ID, which is what
+/// their API punches are recorded under.
+pub fn issue(conn: &Connection, user_id: &str, label: &str) -> Result<IssuedToken, String> {
+    let user = users::get(conn, user_id)?.ok_or_else(|| format!("User not found: {}", user_id))?;
+    if user.linked_device_user_id().is_none() {
+        return Err("User has no device user ID; link them to a device user first".to_string());
+    }
+    insert(conn, user_id, label, ROLE_EMPLOYEE)
+}
+
+/// Issue a manager token for a user, scoped to the given departments
+pub fn issue_manager(conn: &Connection, user_id: &str, label: &str, department_ids: &[String]) -> Result<IssuedToken, String> {
+    users::get(conn, user_id)?.ok_or_else(|| format!("User not found: {}", user_id))?;
+    check_departments(conn, department_ids)?;
+    let issued = insert(conn, user_id, label, ROLE_MANAGER)?;
+    write_departments(conn, &issued.info.id, department_ids)?;
+    let info = get(conn, &issued.info.id)?;
+    Ok(IssuedToken { info, ..issued })
+}
+
+fn insert(conn: &Connection, user_id: &str, label: &str, role: &str) -> Result<IssuedToken, String> {
+    let token = generate(TOKEN_PREFIX);
+    let id = uuid::Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO user_api_tokens (id, user_id, label, token_hash, role) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![id, user_id, label.trim(), hash(&token), role],
+    )
+    .map_err(|e| format!("Failed to save token: {}", e))?;
+
+    let info = get(conn, &id)?;
+    Ok(IssuedToken { token, info })
+}
+
+/// A manager needs at least one department, and each must exist
+fn check_departments(conn: &Connection, department_ids: &[String]) -> Result<(), String> {
+    if department_ids.is_empty() {
+        return Err("Assign at least one department to a manager token".to_string());
+    }
+    for id in department_ids {
+        let exists: bool = conn
+            .query_row("SELECT EXISTS(SELECT 1 FROM departments WHERE id = ?1)", [id], |row| row.get(0))
+            .map_err(|e| format!("Failed to look up department: {}", e))?;
+        if !exists {
+            return Err(format!("Department not found: {}", id));
+        }
+    }
+    Ok(())
+}
+
+fn write_departments(conn: &Connection, token_id: &str, department_ids: &[String]) -> Result<(), String> {
+    conn.execute("DELETE FROM user_api_token_departments WHERE token_id = ?1", [token_id])
+        .map_err(|e| format!("Failed to clear token departments: {}", e))?;
+    for id in department_ids {
+        conn.execute(
+            "INSERT OR IGNORE INTO user_api_token_departments (token_id, department_id) VALUES (?1, ?2)",
+            params![token_id, id],
+        )
+        .map_err(|e| format!("Failed to save token department: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Replace the departments a manager token can see. Takes effect on its
+/// next request.
+pub fn set_departments(conn: &Connection, token_id: &str, department_ids: &[String]) -> Result<UserToken, String> {
+    let token = get(conn, token_id)?;
+    if token.role != ROLE_MANAGER {
+        return Err("Only manager tokens are scoped to departments".to_string());
+    }
+    check_departments(conn, department_ids)?;
+    write_departments(conn, token_id, department_ids)?;
+    get(conn, token_id)
+}
+
+/// Departments assigned to a token
+fn departments(conn: &Connection, token_id: &str) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = conn.prepare(
+        "SELECT department_id FROM user_api_token_departments WHERE token_id = ?1 ORDER BY department_id",
+    )?;
+    let rows = stmt.query_map([token_id], |row| row.get(0))?;
+    rows.collect()
+}
+
+const SELECT: &str = "SELECT t.id, t.user_id, u.display_name, t.label, t.role,
+                             (SELECT json_group_array(department_id) FROM user_api_token_departments
+                              WHERE token_id = t.id),
+                             t.created_at, t.last_used_at, t.revoked_at
+                      FROM user_api_tokens t JOIN users u ON u.id = t.user_id";
+
+fn map_row(row: &rusqlite::Row) -> rusqlite::Result<UserToken> {
+    let department_ids: String = row.get(5)?;
+    Ok(UserToken {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        user_name: row.get(2)?,
+        label: row.get(3)?,
+        role: row.get(4)?,
+        department_ids: serde_json::from_str(&department_ids).unwrap_or_default(),
+        created_at: row.get(6)?,
+        last_used_at: row.get(7)?,
+        revoked_at: row.get(8)?,
+    })
+}
+
+fn get(conn: &Connection, id: &str) -> Result<UserToken, String> {
+    conn.query_row(&format!("{} WHERE t.id = ?1", SELECT), [id], map_row)
+        .map_err(|e| format!("Failed to read token: {}", e))
+}
+
+/// Tokens, newest first, optionally for one user
+pub fn list(conn: &Connection, user_id: Option<&str>) -> Result<Vec<UserToken>, String> {
+    let mut stmt = conn
+        .prepare(&format!("{} WHERE ?1 IS NULL OR t.user_id = ?1 ORDER BY t.created_at DESC", SELECT))
+        .map_err(|e| format!("Failed to query tokens: {}", e))?;
+    let rows = stmt
+        .query_map([user_id], map_row)
+        .map_err(|e| format!("Failed to query tokens: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read tokens: {}", e))
+}
+
+/// Revoke a token. It stays listed so its last use remains visible.
+pub fn revoke(conn: &Connection, id: &str) -> Result<(), String> {
+    let updated = conn
+        .execute(
+            "UPDATE user_api_tokens SET revoked_at = datetime('now') WHERE id = ?1 AND revoked_at IS NULL",
+            [id],
+        )
+        .map_err(|e| format!("Failed to revoke token: {}", e))?;
+    if updated == 0 {
+        return Err(format!("Token not found or already revoked: {}", id));
+    }
+    Ok(())
+}
+
+/// Who a presented token belongs to, and for a manager token which
+/// departments it sees. Records the use. None for unknown or revoked
+/// tokens, for users who have since been deactivated, and for employee
+/// tokens whose user lost their device user ID.
+pub fn authenticate(conn: &Connection, token: &str) -> Result<Option<Principal>, String> {
+    if !token.starts_with(TOKEN_PREFIX) {
+        return Ok(None);
+    }
+    let found: Option<(String, String, Option<String>, String)> = conn
+        .query_row(
+            "SELECT t.id, u.id, u.device_user_id, t.role
+             FROM user_api_tokens t JOIN users u ON u.id = t.user_id
+             WHERE t.token_hash = ?1 AND t.revoked_at IS NULL AND u.status = 'active'
+               AND (t.role = 'manager' OR u.device_user_id IS NOT NULL)",
+            [hash(token)],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to check token: {}", e))?;
+    let Some((token_id, user_id, device_user_id, role)) = found else {
+        return Ok(None);
+    };
+    let department_ids = if role == ROLE_MANAGER {
+        departments(conn, &token_id).map_err(|e| format!("Failed to read token departments: {}", e))?
+    } else {
+        Vec::new()
+    };
+    conn.execute(
+        "UPDATE user_api_tokens SET last_used_at = datetime('now') WHERE id = ?1",
+        [&token_id],
+    )
+    .map_err(|e| format!("Failed to record token use: {}", e))?;
+    Ok(Some(Principal {
+        user_id: Some(user_id),
+        device_user_id,
+        role,
+        department_ids: Some(department_ids),
+        scopes: Vec::new(),
+    }))
+}
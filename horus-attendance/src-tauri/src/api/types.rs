@@ -0,0 +1,321 @@
+//! Embedded API data types for Tauri command and HTTP serialization
+
+use serde::{Deserialize, Serialize};
+
+/// Server configuration, stored under the `apiServer` setting
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ApiServerSettings {
+    /// Start with the app
+    pub enabled: bool,
+    /// Interface to listen on; 0.0.0.0 for the whole LAN
+    pub bind_address: String,
+    pub port: u16,
+    /// Requests a minute allowed per IP address and per credential; 0 for no limit
+    pub rate_limit_per_minute: u32,
+    /// Largest request body accepted
+    pub max_body_bytes: u64,
+    /// Failed sign-ins in a row from one IP address, or with one credential,
+    /// before it is locked out; 0 to never lock out
+    pub lockout_after_failures: u32,
+    pub lockout_minutes: u32,
+    /// Ports above `port` tried in turn when it is taken; 0 to never fall back
+    pub fallback_port_count: u16,
+    /// The port the server fell back to last time, tried first while `port`
+    /// stays taken so paired phones keep reaching it. Cleared once `port`
+    /// is free again.
+    pub fallback_port: Option<u16>,
+}
+
+impl ApiServerSettings {
+    /// The port the server listens on when started: the fallback while one
+    /// is in use
+    pub fn listening_port(&self) -> u16 {
+        self.fallback_port.unwrap_or(self.port)
+    }
+}
+
+impl Default for ApiServerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind_address: "0.0.0.0".to_string(),
+            port: 8787,
+            rate_limit_per_minute: 120,
+            max_body_bytes: 64 * 1024,
+            lockout_after_failures: 5,
+            lockout_minutes: 15,
+            fallback_port_count: 10,
+            fallback_port: None,
+        }
+    }
+}
+
+/// Whether the server is listening, and where
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiServerStatus {
+    pub running: bool,
+    /// host:port it is bound to
+    pub address: Option<String>,
+    /// Serving HTTPS
+    pub tls: bool,
+    pub started_at: Option<String>,
+    /// How the last start went, including failed ones
+    pub last_start: Option<ApiServerStart>,
+}
+
+/// Whether a port can be listened on
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortCheck {
+    pub bind_address: String,
+    pub port: u16,
+    /// "free", "in_use", "permission_denied", "address_unavailable" or "error"
+    pub status: String,
+    pub detail: Option<String>,
+}
+
+/// Whether the OS firewall lets LAN clients reach the server
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FirewallCheck {
+    /// "allowed", "blocked", "off", "not_applicable" (listening on loopback
+    /// only) or "unknown"
+    pub status: String,
+    pub detail: Option<String>,
+}
+
+/// A start attempt: the ports tried and where it ended up
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiServerStart {
+    pub attempted_at: String,
+    /// The configured port
+    pub requested_port: u16,
+    /// The port listened on; None when the start failed
+    pub port: Option<u16>,
+    pub fallback_used: bool,
+    /// Each port checked, in order
+    pub checks: Vec<PortCheck>,
+    /// Checked after starting; None when the check hasn't finished
+    pub firewall: Option<FirewallCheck>,
+    pub error: Option<String>,
+}
+
+/// The configured port and firewall, checked without starting
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiPortReport {
+    pub port: PortCheck,
+    pub firewall: FirewallCheck,
+}
+
+/// Request counters since the server started
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiMetrics {
+    pub uptime_seconds: u64,
+    pub requests: u64,
+    /// Refused for going over the rate limit
+    pub rate_limited: u64,
+    /// Refused for a body over the size cap
+    pub body_too_large: u64,
+    /// Bad tokens, API keys, pairing codes and report links
+    pub auth_failures: u64,
+    /// IP addresses locked out for failing too often
+    pub lockouts: u64,
+    /// Requests refused while their IP address was locked out
+    pub locked_out_requests: u64,
+    /// IP addresses locked out right now
+    pub active_lockouts: u64,
+}
+
+/// A per-user API token (without the secret)
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserToken {
+    pub id: String,
+    pub user_id: String,
+    pub user_name: String,
+    pub label: String,
+    /// employee or manager
+    pub role: String,
+    /// Departments a manager token can see; empty for employee tokens
+    pub department_ids: Vec<String>,
+    pub created_at: String,
+    pub last_used_at: Option<String>,
+    pub revoked_at: Option<String>,
+}
+
+/// Who a presented token or API key belongs to and what it may see
+#[derive(Debug, Clone)]
+pub struct Principal {
+    /// None for integration API keys
+    pub user_id: Option<String>,
+    /// Always set for employee tokens; punches are recorded under it
+    pub device_user_id: Option<String>,
+    /// employee, manager or integration
+    pub role: String,
+    /// Departments visible through the /team routes; None for all of them
+    pub department_ids: Option<Vec<String>>,
+    /// What an API key was granted; user tokens have none
+    pub scopes: Vec<String>,
+}
+
+/// A newly issued token. `token` is only ever returned here.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IssuedToken {
+    pub token: String,
+    pub info: UserToken,
+}
+
+/// An integration API key (without the secret)
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKey {
+    pub id: String,
+    pub name: String,
+    /// First characters of the key, to tell keys apart
+    pub key_prefix: String,
+    pub scopes: Vec<String>,
+    pub created_at: String,
+    pub last_used_at: Option<String>,
+    /// Set when the key was rotated with a grace period
+    pub expires_at: Option<String>,
+    pub revoked_at: Option<String>,
+    /// The key that replaced this one on rotation
+    pub replaced_by: Option<String>,
+}
+
+/// A newly issued API key. `key` is only ever returned here.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IssuedApiKey {
+    pub key: String,
+    pub info: ApiKey,
+}
+
+/// Body of POST /punches
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PunchRequest {
+    /// RFC 3339 or local YYYY-MM-DDTHH:MM:SS; defaults to now
+    pub timestamp: Option<String>,
+    /// Device punch state: 0 check-in, 1 check-out, ...
+    pub punch_type: Option<i64>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// GPS accuracy in meters, kept with the raw payload
+    pub accuracy: Option<f64>,
+    /// Why or where, e.g. "Client site visit"; up to 500 characters
+    pub note: Option<String>,
+}
+
+/// Response of POST /punches
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PunchAccepted {
+    pub id: String,
+    /// Stored timestamp, in the same local format as device punches
+    pub timestamp: String,
+    /// An identical punch was already recorded
+    pub duplicate: bool,
+}
+
+/// A one-time invite for pairing a companion app
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PairingInvite {
+    pub id: String,
+    pub user_id: String,
+    pub user_name: String,
+    /// Base URL the companion app will call
+    pub endpoint: String,
+    /// JSON encoded in the QR code
+    pub payload: String,
+    /// The QR code as an SVG document
+    pub qr_svg: String,
+    pub expires_at: String,
+}
+
+/// Body of POST /pair
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PairRequest {
+    pub code: String,
+    /// Shown in token management, e.g. "Ahmed's iPhone"
+    pub device_name: String,
+}
+
+/// Response of GET /me
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MeProfile {
+    pub user_id: String,
+    pub display_name: String,
+    pub employee_code: Option<String>,
+    pub department: Option<String>,
+}
+
+/// One day of GET /me/attendance
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MeDay {
+    pub date: String,
+    pub check_in_time: Option<String>,
+    pub check_out_time: Option<String>,
+    pub status: String,
+    pub late_minutes: i64,
+    pub early_minutes: i64,
+}
+
+/// One user of GET /team/users
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TeamMember {
+    pub user_id: String,
+    pub display_name: String,
+    pub employee_code: Option<String>,
+    pub department_id: Option<String>,
+    pub department: Option<String>,
+    pub status: String,
+}
+
+/// One user-day of GET /team/attendance
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TeamDay {
+    pub user_id: String,
+    pub date: String,
+    pub check_in_time: Option<String>,
+    pub check_out_time: Option<String>,
+    pub status: String,
+    pub late_minutes: i64,
+    pub early_minutes: i64,
+}
+
+/// A signed link to one employee's monthly report
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SelfServiceLink {
+    pub user_id: String,
+    pub user_name: String,
+    /// Where to send the link, when the user has an email address
+    pub email: Option<String>,
+    pub url: String,
+    pub expires_at: String,
+}
+
+/// Response of GET /reports/monthly
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SelfServiceReport {
+    pub profile: MeProfile,
+    /// e.g. "March 2024"
+    pub period: String,
+    pub days: Vec<MeDay>,
+    /// Register totals by heading (Present, Absent, Late days, ...)
+    pub totals: std::collections::BTreeMap<String, i64>,
+}
@@ -0,0 +1,125 @@
+//! Backup browser
+//!
+//! Describes each backup in the backup directory by opening it read-only:
+//! record counts, schema version, the app version that made it and a quick
+//! integrity check. Nothing is restored or modified.
+
+use rusqlite::OptionalExtension;
+use std::fs;
+use std::path::Path;
+
+use super::compat::{open_read_only, BACKUP_METADATA_KEY};
+use super::types::BackupInfo;
+use crate::BackupMetadata;
+
+/// Classify a backup by its file name prefix
+fn backup_kind(file_name: &str) -> &'static str {
+    ["pre_restore", "pre_reset", "pre_op"]
+        .into_iter()
+        .find(|prefix| file_name.starts_with(prefix))
+        .unwrap_or("backup")
+}
+
+/// Describe one backup file
+pub fn describe(path: &Path) -> BackupInfo {
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let file_metadata = fs::metadata(path).ok();
+    let modified = file_metadata
+        .as_ref()
+        .and_then(|m| m.modified().ok())
+        .map(|t| chrono::DateTime::<chrono::Utc>::from(t).to_rfc3339_opts(chrono::SecondsFormat::Millis, true));
+
+    let mut info = BackupInfo {
+        kind: backup_kind(&file_name).to_string(),
+        file_name,
+        path: path.to_string_lossy().to_string(),
+        file_size: file_metadata.map(|m| m.len()).unwrap_or(0),
+        created_at: modified,
+        user_count: None,
+        log_count: None,
+        summary_count: None,
+        device_count: None,
+        schema_version: None,
+        app_version: None,
+        integrity_ok: false,
+        error: None,
+    };
+    if let Err(e) = read_contents(path, &mut info) {
+        info.error = Some(e);
+    }
+    info
+}
+
+fn read_contents(path: &Path, info: &mut BackupInfo) -> Result<(), String> {
+    let conn = open_read_only(path)?;
+
+    let integrity: String = conn
+        .query_row("PRAGMA quick_check", [], |row| row.get(0))
+        .map_err(|e| format!("Not a readable database: {}", e))?;
+    info.integrity_ok = integrity == "ok";
+
+    let tables: Vec<String> = {
+        let mut stmt = conn
+            .prepare("SELECT name FROM sqlite_master WHERE type = 'table'")
+            .map_err(|e| format!("Failed to read schema: {}", e))?;
+        let rows = stmt
+            .query_map([], |row| row.get(0))
+            .map_err(|e| format!("Failed to read schema: {}", e))?;
+        rows.collect::<Result<_, _>>()
+            .map_err(|e| format!("Failed to read schema: {}", e))?
+    };
+    let has = |name: &str| tables.iter().any(|t| t == name);
+    let count = |table: &str| -> Result<Option<i64>, String> {
+        if !has(table) {
+            return Ok(None);
+        }
+        conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get(0))
+            .map(Some)
+            .map_err(|e| format!("Failed to count {}: {}", table, e))
+    };
+
+    info.user_count = count("users")?;
+    info.log_count = count("attendance_logs_raw")?;
+    info.summary_count = count("attendance_day_summary")?;
+    info.device_count = count("devices")?;
+
+    if has("_sqlx_migrations") {
+        info.schema_version = conn
+            .query_row("SELECT MAX(version) FROM _sqlx_migrations WHERE success = 1", [], |row| row.get(0))
+            .map_err(|e| format!("Failed to read migrations: {}", e))?;
+    }
+
+    if has("settings") {
+        let metadata = conn
+            .query_row("SELECT value FROM settings WHERE key = ?1", [BACKUP_METADATA_KEY], |row| {
+                row.get::<_, String>(0)
+            })
+            .optional()
+            .map_err(|e| format!("Failed to read backup metadata: {}", e))?
+            .and_then(|raw| serde_json::from_str::<BackupMetadata>(&raw).ok());
+        if let Some(metadata) = metadata {
+            info.app_version = Some(metadata.app_version);
+            info.created_at = Some(metadata.created_at);
+        }
+    }
+    Ok(())
+}
+
+/// Describe every .db backup in a directory, newest first
+pub fn list(backup_dir: &Path) -> Vec<BackupInfo> {
+    let mut backups: Vec<BackupInfo> = fs::read_dir(backup_dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == "db"))
+                .map(|path| describe(&path))
+                .collect()
+        })
+        .unwrap_or_default();
+    backups.sort_by(|a, b| b.created_at.cmp(&a.created_at).then_with(|| b.file_name.cmp(&a.file_name)));
+    backups
+}
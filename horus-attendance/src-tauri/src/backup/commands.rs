@@ -0,0 +1,522 @@
+//! Tauri command handlers for the backup subsystem.
+
+use std::fs;
+use std::path::PathBuf;
+
+use super::catalog;
+use super::compat;
+use super::diff;
+use super::dump;
+use super::selective;
+use super::targets;
+use super::transfer;
+use super::undo;
+use super::types::*;
+use crate::{activity, db, BackupResult, RestoreResult};
+
+/// Export the database as a plain-text SQL dump (schema + data).
+/// `destination` is a full file path; defaults to the backup directory.
+#[tauri::command]
+pub async fn export_sql_dump(
+    app: tauri::AppHandle,
+    destination: Option<String>,
+    options: Option<SqlDumpOptions>,
+) -> Result<BackupResult, String> {
+    let opened = match activity::registry::quiesce(&app, "SQL dump export").await {
+        Ok(paused) => db::open(&app).map(|conn| (paused, conn)),
+        Err(e) => Err(e),
+    };
+    let (_paused, mut conn) = match opened {
+        Ok(opened) => opened,
+        Err(e) => {
+            return Ok(BackupResult {
+                success: false,
+                file_path: String::new(),
+                file_size: 0,
+                error: Some(e),
+            })
+        }
+    };
+
+    let dump_path = match destination {
+        Some(dest) => crate::path_policy::resolve_write_target(&app, &dest)?,
+        None => {
+            let backup_dir = crate::get_backup_dir(&app)?;
+            let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
+            backup_dir.join(format!("horus_dump_{}.sql", timestamp))
+        }
+    };
+    if let Some(parent) = dump_path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create backup directory: {}", e))?;
+    }
+
+    let options = options.unwrap_or_default();
+    let path = dump_path.clone();
+    let written = tauri::async_runtime::spawn_blocking(move || dump::write_dump(&mut conn, &path, &options))
+        .await
+        .map_err(|e| format!("Dump task failed: {}", e))?;
+
+    Ok(match written {
+        Ok(_) => BackupResult {
+            success: true,
+            file_path: dump_path.to_string_lossy().to_string(),
+            file_size: fs::metadata(&dump_path).map(|m| m.len()).unwrap_or(0),
+            error: None,
+        },
+        Err(e) => BackupResult {
+            success: false,
+            file_path: dump_path.to_string_lossy().to_string(),
+            file_size: 0,
+            error: Some(e),
+        },
+    })
+}
+
+/// Check whether a backup file can be restored by this app version
+#[tauri::command]
+pub async fn check_backup_compatibility(backup_path: String) -> Result<BackupCompatibility, String> {
+    let path = PathBuf::from(&backup_path);
+    if !path.exists() {
+        return Err("Backup file not found".to_string());
+    }
+    Ok(compat::check(&path, crate::current_schema_version()))
+}
+
+/// Preview what restoring a backup would change: row counts per table and
+/// the raw logs each side holds, overall and per device
+#[tauri::command]
+pub async fn diff_backup(app: tauri::AppHandle, path: String) -> Result<BackupDiff, String> {
+    let backup_path = PathBuf::from(&path);
+    if !backup_path.exists() {
+        return Err("Backup file not found".to_string());
+    }
+    let compatibility = compat::check(&backup_path, crate::current_schema_version());
+    if let Some(error) = compatibility.error.filter(|_| compatibility.schema_version.is_none()) {
+        return Err(error);
+    }
+    let mut conn = db::open(&app)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        diff::diff(
+            &mut conn,
+            &backup_path,
+            compatibility.schema_version,
+            compatibility.current_schema_version,
+        )
+    })
+    .await
+    .map_err(|e| format!("Backup diff failed: {}", e))?
+}
+
+/// List backups with size, creation time, record counts, schema version and
+/// integrity, newest first
+#[tauri::command]
+pub async fn list_backups_detailed(app: tauri::AppHandle) -> Result<Vec<BackupInfo>, String> {
+    let backup_dir = crate::get_backup_dir(&app)?;
+    tauri::async_runtime::spawn_blocking(move || catalog::list(&backup_dir))
+        .await
+        .map_err(|e| format!("Backup listing failed: {}", e))
+}
+
+/// Restore the database from a full SQL dump.
+/// The dump is loaded into a scratch database first and only swapped in if it
+/// passes an integrity check.
+/// NOTE: After import the app must be restarted for the SQL plugin to pick up the new file.
+#[tauri::command]
+pub async fn import_sql_dump(app: tauri::AppHandle, dump_path: String) -> Result<RestoreResult, String> {
+    let source = PathBuf::from(&dump_path);
+    if !source.exists() {
+        return Ok(RestoreResult {
+            success: false,
+            error: Some("Dump file not found".to_string()),
+        });
+    }
+
+    let _paused = match activity::registry::quiesce(&app, "SQL dump import").await {
+        Ok(guard) => guard,
+        Err(e) => {
+            return Ok(RestoreResult {
+                success: false,
+                error: Some(e),
+            })
+        }
+    };
+
+    let backup_dir = crate::get_backup_dir(&app)?;
+    let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
+    let scratch = backup_dir.join(format!("import_{}.db", timestamp));
+
+    let target = scratch.clone();
+    let loaded = tauri::async_runtime::spawn_blocking(move || dump::load_dump(&source, &target))
+        .await
+        .map_err(|e| format!("Import task failed: {}", e))?;
+
+    let result = match loaded {
+        Ok(()) => {
+            let compatibility = compat::check(&scratch, crate::current_schema_version());
+            if compatibility.compatible {
+                let label = format!("Import SQL dump {}", crate::file_label(&PathBuf::from(&dump_path)));
+                crate::replace_database(&app, &scratch, &label)
+            } else {
+                Ok(RestoreResult {
+                    success: false,
+                    error: compatibility.error,
+                })
+            }
+        }
+        Err(e) => Ok(RestoreResult {
+            success: false,
+            error: Some(e),
+        }),
+    };
+    let _ = fs::remove_file(&scratch);
+    result
+}
+
+/// Snapshot the tables an operation is about to change so it can be undone.
+/// Call before mass deletes, merges, device removal and similar.
+#[tauri::command]
+pub async fn create_operation_snapshot(
+    app: tauri::AppHandle,
+    label: String,
+    tables: Vec<String>,
+) -> Result<OperationSnapshot, String> {
+    if tables.is_empty() {
+        return Err("At least one table is required".to_string());
+    }
+    let conn = db::open(&app)?;
+    for table in &tables {
+        let exists: bool = conn
+            .query_row(
+                "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = ?1",
+                [table],
+                |row| row.get(0),
+            )
+            .map_err(|e| format!("Failed to read schema: {}", e))?;
+        if !exists {
+            return Err(format!("Unknown table: {}", table));
+        }
+    }
+    drop(conn);
+    log::info!("[backup::cmd] create_operation_snapshot '{}' ({})", label, tables.join(", "));
+
+    let db_path = crate::get_db_path(&app)?;
+    let backup_dir = crate::get_backup_dir(&app)?;
+    tauri::async_runtime::spawn_blocking(move || undo::create(&db_path, &backup_dir, None, &label, Some(tables)))
+        .await
+        .map_err(|e| format!("Snapshot task failed: {}", e))?
+}
+
+/// List pre-operation snapshots, newest first
+#[tauri::command]
+pub async fn list_operation_snapshots(app: tauri::AppHandle) -> Result<Vec<OperationSnapshot>, String> {
+    let backup_dir = crate::get_backup_dir(&app)?;
+    let mut snapshots = undo::list(&backup_dir)?;
+    snapshots.reverse();
+    Ok(snapshots)
+}
+
+/// Roll back the most recent snapshotted operation. The current state is
+/// snapshotted first, so an undo can itself be undone.
+#[tauri::command]
+pub async fn undo_last_operation(app: tauri::AppHandle) -> Result<UndoResult, String> {
+    let backup_dir = crate::get_backup_dir(&app)?;
+    let Some(snapshot) = undo::last_pending(&backup_dir)? else {
+        return Ok(UndoResult {
+            success: false,
+            label: None,
+            restart_required: false,
+            error: Some("Nothing to undo".to_string()),
+        });
+    };
+    log::info!("[backup::cmd] undo_last_operation '{}'", snapshot.label);
+    let _paused = match activity::registry::quiesce(&app, "Undo").await {
+        Ok(guard) => guard,
+        Err(e) => {
+            return Ok(UndoResult {
+                success: false,
+                label: Some(snapshot.label),
+                restart_required: false,
+                error: Some(e),
+            })
+        }
+    };
+
+    let snapshot_path = backup_dir.join(&snapshot.file_name);
+    let label = format!("Undo {}", snapshot.label);
+    let (restored, restart_required) = match snapshot.tables.clone() {
+        None => {
+            let result = crate::replace_database(&app, &snapshot_path, &label)?;
+            (result.error.map_or(Ok(()), Err), true)
+        }
+        Some(tables) => {
+            let db_path = crate::get_db_path(&app)?;
+            let mut conn = db::open(&app)?;
+            let dir = backup_dir.clone();
+            let restored = tauri::async_runtime::spawn_blocking(move || {
+                undo::create(&db_path, &dir, None, &label, Some(tables.clone()))?;
+                selective::replace_tables(&mut conn, &snapshot_path, &tables)
+            })
+            .await
+            .map_err(|e| format!("Undo task failed: {}", e))?;
+            (restored.map(|_| ()), false)
+        }
+    };
+
+    Ok(match restored {
+        Ok(()) => {
+            undo::mark_undone(&backup_dir, &snapshot.id)?;
+            UndoResult {
+                success: true,
+                label: Some(snapshot.label),
+                restart_required,
+                error: None,
+            }
+        }
+        Err(e) => UndoResult {
+            success: false,
+            label: Some(snapshot.label),
+            restart_required: false,
+            error: Some(e),
+        },
+    })
+}
+
+/// List registered backup targets
+#[tauri::command]
+pub async fn list_backup_targets(app: tauri::AppHandle) -> Result<Vec<BackupTarget>, String> {
+    let conn = db::open(&app)?;
+    targets::list(&conn)
+}
+
+/// Register or update a named backup target
+#[tauri::command]
+pub async fn save_backup_target(app: tauri::AppHandle, target: BackupTarget) -> Result<BackupTarget, String> {
+    log::info!("[backup::cmd] save_backup_target '{}' -> {}", target.name, target.path);
+    let conn = db::open(&app)?;
+    targets::save(&conn, target)
+}
+
+/// Remove a backup target
+#[tauri::command]
+pub async fn delete_backup_target(app: tauri::AppHandle, target_id: String) -> Result<(), String> {
+    let conn = db::open(&app)?;
+    targets::delete(&conn, &target_id)
+}
+
+/// Size of the live database file, the space a backup needs
+fn database_size(app: &tauri::AppHandle) -> Result<u64, String> {
+    let db_path = crate::get_db_path(app)?;
+    Ok(fs::metadata(db_path).map(|m| m.len()).unwrap_or(0))
+}
+
+/// Check a target is connected, writable and has room for a backup
+#[tauri::command]
+pub async fn test_backup_target(app: tauri::AppHandle, target_id: String) -> Result<BackupTargetStatus, String> {
+    let target = targets::get(&*db::open(&app)?, &target_id)?;
+    let required = database_size(&app)?;
+    tauri::async_runtime::spawn_blocking(move || targets::test(&target, required))
+        .await
+        .map_err(|e| format!("Target test failed: {}", e))
+}
+
+/// Back up to a registered target after checking it is usable
+#[tauri::command]
+pub async fn backup_to_target(app: tauri::AppHandle, target_id: String) -> Result<BackupResult, String> {
+    let target = targets::get(&*db::open(&app)?, &target_id)?;
+    let required = database_size(&app)?;
+    let probe = target.clone();
+    let status = tauri::async_runtime::spawn_blocking(move || targets::test(&probe, required))
+        .await
+        .map_err(|e| format!("Target test failed: {}", e))?;
+    if let Some(error) = status.error {
+        return Ok(BackupResult {
+            success: false,
+            file_path: String::new(),
+            file_size: 0,
+            error: Some(error),
+        });
+    }
+
+    let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
+    let destination = PathBuf::from(&target.path).join(format!("horus_backup_{}.db", timestamp));
+    log::info!("[backup::cmd] backup_to_target '{}' -> {}", target.name, destination.display());
+    crate::export_backup(app, Some(destination.to_string_lossy().to_string())).await
+}
+
+/// Test every scheduled target at startup and emit `backup-target-unavailable`
+/// for each one that can't take a backup right now
+pub async fn check_scheduled_targets(app: tauri::AppHandle) {
+    use tauri::Emitter;
+
+    // No database yet on first launch
+    let Ok(conn) = db::open(&app) else {
+        return;
+    };
+    let scheduled: Vec<BackupTarget> = match targets::list(&conn) {
+        Ok(all) => all.into_iter().filter(|t| t.scheduled).collect(),
+        Err(e) => {
+            log::warn!("[backup] Failed to load backup targets: {}", e);
+            return;
+        }
+    };
+    drop(conn);
+    if scheduled.is_empty() {
+        return;
+    }
+
+    let required = database_size(&app).unwrap_or(0);
+    let statuses = tauri::async_runtime::spawn_blocking(move || {
+        scheduled.iter().map(|t| targets::test(t, required)).collect::<Vec<_>>()
+    })
+    .await
+    .unwrap_or_default();
+
+    for status in statuses.into_iter().filter(|s| s.error.is_some()) {
+        log::warn!(
+            "[backup] Scheduled backup target '{}' unavailable: {}",
+            status.name,
+            status.error.as_deref().unwrap_or_default()
+        );
+        if let Err(e) = app.emit("backup-target-unavailable", &status) {
+            log::warn!("[backup] Failed to emit target warning: {}", e);
+        }
+    }
+}
+
+/// Directories used when packing or unpacking a transfer bundle
+fn transfer_paths(app: &tauri::AppHandle) -> Result<transfer::TransferPaths, String> {
+    use tauri::Manager;
+
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    let backup_dir = crate::get_backup_dir(app)?;
+    let documents_dir = backup_dir
+        .parent()
+        .map(PathBuf::from)
+        .ok_or("Failed to resolve documents directory")?;
+    Ok(transfer::TransferPaths {
+        db_path: crate::get_db_path(app)?,
+        app_data_dir,
+        documents_dir,
+        backup_dir,
+    })
+}
+
+/// Package the database (with settings and devices), backups and photos into
+/// one archive for moving to another computer.
+/// `destination` is a full file path; defaults to Documents/HorusAttendance.
+#[tauri::command]
+pub async fn export_full_transfer_bundle(
+    app: tauri::AppHandle,
+    destination: Option<String>,
+) -> Result<TransferExportResult, String> {
+    let paths = transfer_paths(&app)?;
+    let bundle_path = match destination {
+        Some(dest) => crate::path_policy::resolve_write_target(&app, &dest)?,
+        None => {
+            let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
+            paths.documents_dir.join(format!("horus_transfer_{}.zip", timestamp))
+        }
+    };
+    if let Some(parent) = bundle_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {}", e))?;
+    }
+    log::info!("[backup::cmd] export_full_transfer_bundle -> {}", bundle_path.display());
+    let _paused = match activity::registry::quiesce(&app, "Transfer bundle export").await {
+        Ok(guard) => guard,
+        Err(e) => {
+            return Ok(TransferExportResult {
+                success: false,
+                file_path: bundle_path.to_string_lossy().to_string(),
+                file_size: 0,
+                files: 0,
+                error: Some(e),
+            })
+        }
+    };
+
+    let path = bundle_path.clone();
+    let packed = tauri::async_runtime::spawn_blocking(move || {
+        transfer::export_bundle(&paths, crate::current_schema_version(), &path)
+    })
+    .await
+    .map_err(|e| format!("Transfer task failed: {}", e))?;
+
+    Ok(match packed {
+        Ok(files) => TransferExportResult {
+            success: true,
+            file_path: bundle_path.to_string_lossy().to_string(),
+            file_size: fs::metadata(&bundle_path).map(|m| m.len()).unwrap_or(0),
+            files,
+            error: None,
+        },
+        Err(e) => TransferExportResult {
+            success: false,
+            file_path: bundle_path.to_string_lossy().to_string(),
+            file_size: 0,
+            files: 0,
+            error: Some(e),
+        },
+    })
+}
+
+/// Restore a transfer bundle on this computer: backups and photos are
+/// unpacked, stored paths are rewritten for this machine, and the database
+/// is replaced. Migrations run when the app restarts.
+#[tauri::command]
+pub async fn import_full_transfer_bundle(
+    app: tauri::AppHandle,
+    bundle_path: String,
+) -> Result<TransferImportResult, String> {
+    let source = PathBuf::from(&bundle_path);
+    let failed = |error: String| TransferImportResult {
+        success: false,
+        restart_required: false,
+        files_restored: 0,
+        paths_updated: 0,
+        warnings: Vec::new(),
+        error: Some(error),
+    };
+    if !source.exists() {
+        return Ok(failed("Bundle file not found".to_string()));
+    }
+    log::info!("[backup::cmd] import_full_transfer_bundle {}", bundle_path);
+    let _paused = match activity::registry::quiesce(&app, "Transfer bundle import").await {
+        Ok(guard) => guard,
+        Err(e) => return Ok(failed(e)),
+    };
+
+    let paths = transfer_paths(&app)?;
+    fs::create_dir_all(&paths.app_data_dir).map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    let unpacked = tauri::async_runtime::spawn_blocking(move || {
+        transfer::unpack_bundle(&source, &paths, crate::current_schema_version())
+    })
+    .await
+    .map_err(|e| format!("Transfer task failed: {}", e))?;
+    let bundle = match unpacked {
+        Ok(bundle) => bundle,
+        Err(e) => return Ok(failed(e)),
+    };
+
+    let label = format!(
+        "Import transfer bundle from {} (v{})",
+        crate::file_label(&PathBuf::from(&bundle_path)),
+        bundle.manifest.app_version
+    );
+    let replaced = crate::replace_database(&app, &bundle.database, &label);
+    let _ = fs::remove_file(&bundle.database);
+
+    let mut result = bundle.result;
+    match replaced {
+        Ok(RestoreResult { success: true, .. }) => {
+            result.success = true;
+            result.restart_required = true;
+        }
+        Ok(RestoreResult { error, .. }) => result.error = error,
+        Err(e) => result.error = Some(e),
+    }
+    Ok(result)
+}
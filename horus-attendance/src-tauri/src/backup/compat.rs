@@ -0,0 +1,172 @@
+//! Backup metadata and compatibility checks
+//!
+//! A database from a newer app version may carry migrations this build
+//! doesn't know, which the SQL plugin rejects on startup (or worse, half
+//! understands). Before restoring, the backup's migration history and the
+//! metadata stamped into it at export time are compared with this build.
+
+use rusqlite::{Connection, OpenFlags, OptionalExtension};
+use std::path::Path;
+
+use super::types::BackupCompatibility;
+use crate::BackupMetadata;
+
+/// Settings key holding the metadata stamped into exported backups
+pub const BACKUP_METADATA_KEY: &str = "backupMetadata";
+
+/// Version of the BackupMetadata format
+const METADATA_VERSION: &str = "1";
+
+/// Record who made a backup and what it contains in the backup file itself
+pub fn stamp_metadata(backup_path: &Path) -> Result<BackupMetadata, String> {
+    let conn = crate::db::open_path(backup_path)?;
+    let count = |table: &str| -> Result<u32, String> {
+        conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get(0))
+            .map_err(|e| format!("Failed to count {}: {}", table, e))
+    };
+
+    let metadata = BackupMetadata {
+        version: METADATA_VERSION.to_string(),
+        created_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        user_count: count("users")?,
+        log_count: count("attendance_logs_raw")?,
+    };
+    let json = serde_json::to_string(&metadata)
+        .map_err(|e| format!("Failed to serialize backup metadata: {}", e))?;
+    crate::db::set_setting(&conn, BACKUP_METADATA_KEY, &json)?;
+    Ok(metadata)
+}
+
+/// Open a backup file for inspection. `immutable` tells SQLite the file
+/// won't change, so backups copied from a WAL-mode database open without
+/// needing (or creating) -wal/-shm sidecars.
+pub fn open_read_only(path: &Path) -> Result<Connection, String> {
+    let mut uri_path = path.to_string_lossy().replace('\\', "/");
+    if !uri_path.starts_with('/') {
+        // Windows drive paths: file:/C:/...
+        uri_path.insert(0, '/');
+    }
+    let uri = format!(
+        "file:{}?immutable=1",
+        uri_path.replace('%', "%25").replace('?', "%3f").replace('#', "%23")
+    );
+    Connection::open_with_flags(
+        uri,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_URI | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .map_err(|e| format!("Failed to open backup: {}", e))
+}
+
+/// Parse "major.minor.patch" into a comparable tuple
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let mut parts = version.trim().trim_start_matches('v').split('.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next().unwrap_or("0").parse().ok()?;
+    let patch = parts
+        .next()
+        .unwrap_or("0")
+        .split(|c: char| !c.is_ascii_digit())
+        .next()
+        .unwrap_or("0")
+        .parse()
+        .unwrap_or(0);
+    Some((major, minor, patch))
+}
+
+/// Inspect a backup file without modifying it
+pub fn check(backup_path: &Path, current_schema_version: i64) -> BackupCompatibility {
+    let mut result = BackupCompatibility {
+        compatible: false,
+        schema_version: None,
+        current_schema_version,
+        app_version: None,
+        current_app_version: env!("CARGO_PKG_VERSION").to_string(),
+        warnings: Vec::new(),
+        error: None,
+    };
+    match inspect(backup_path, &mut result) {
+        Ok(()) => result.compatible = result.error.is_none(),
+        Err(e) => result.error = Some(e),
+    }
+    result
+}
+
+fn inspect(backup_path: &Path, result: &mut BackupCompatibility) -> Result<(), String> {
+    let conn = open_read_only(backup_path)?;
+
+    let integrity: String = conn
+        .query_row("PRAGMA quick_check", [], |row| row.get(0))
+        .map_err(|e| format!("Backup is not a readable database: {}", e))?;
+    if integrity != "ok" {
+        return Err(format!("Backup failed integrity check: {}", integrity));
+    }
+
+    let has_table = |name: &str| -> Result<bool, String> {
+        conn.query_row(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = ?1",
+            [name],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to read backup schema: {}", e))
+    };
+    if !has_table("attendance_logs_raw")? {
+        return Err("Backup does not contain a Horus Attendance database".to_string());
+    }
+
+    if has_table("_sqlx_migrations")? {
+        result.schema_version = conn
+            .query_row("SELECT MAX(version) FROM _sqlx_migrations WHERE success = 1", [], |row| row.get(0))
+            .map_err(|e| format!("Failed to read backup migrations: {}", e))?;
+    } else {
+        result
+            .warnings
+            .push("Backup has no migration history; it will be migrated when the app restarts".to_string());
+    }
+
+    if has_table("settings")? {
+        let raw: Option<String> = conn
+            .query_row(
+                "SELECT value FROM settings WHERE key = ?1",
+                [BACKUP_METADATA_KEY],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| format!("Failed to read backup metadata: {}", e))?;
+        if let Some(metadata) = raw.and_then(|r| serde_json::from_str::<BackupMetadata>(&r).ok()) {
+            result.app_version = Some(metadata.app_version);
+        }
+    }
+
+    if let Some(version) = result.schema_version {
+        if version > result.current_schema_version {
+            result.error = Some(format!(
+                "Backup uses database schema v{} but this app only supports up to v{}. Update the app before restoring.",
+                version, result.current_schema_version
+            ));
+        } else if version < result.current_schema_version {
+            result.warnings.push(format!(
+                "Backup uses an older database schema (v{}); it will be upgraded to v{} when the app restarts",
+                version, result.current_schema_version
+            ));
+        }
+    }
+
+    match &result.app_version {
+        Some(version) => {
+            if let (Some(backup), Some(current)) = (parse_version(version), parse_version(&result.current_app_version)) {
+                if backup > current {
+                    result.warnings.push(format!(
+                        "Backup was created by a newer app version ({}); settings it introduced will be ignored",
+                        version
+                    ));
+                }
+            }
+        }
+        None => result
+            .warnings
+            .push("Backup does not record which app version created it".to_string()),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,184 @@
+//! Restore preview
+//!
+//! Compares a backup with the live database before it is restored: row
+//! counts per table, and which raw logs each side holds (overall and per
+//! device) with their date ranges, so the right backup can be picked without
+//! restoring it. The backup is attached only to be read; nothing is written
+//! to either database.
+
+use rusqlite::Connection;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use super::dump::quote_ident;
+use super::selective::{with_attached, BACKUP_SCHEMA};
+use super::types::{BackupDiff, DeviceLogDiff, LogRange, TableDiff};
+
+const LOGS: &str = "attendance_logs_raw";
+
+/// User tables in `schema`
+fn tables(conn: &Connection, schema: &str) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT name FROM {}.sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%'",
+            schema
+        ))
+        .map_err(|e| format!("Failed to read schema: {}", e))?;
+    let rows = stmt
+        .query_map([], |row| row.get(0))
+        .map_err(|e| format!("Failed to read schema: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read schema: {}", e))
+}
+
+fn count(conn: &Connection, schema: &str, table: &str) -> Result<i64, String> {
+    conn.query_row(
+        &format!("SELECT COUNT(*) FROM {}.{}", schema, quote_ident(table)),
+        [],
+        |row| row.get(0),
+    )
+    .map_err(|e| format!("Failed to count {}: {}", table, e))
+}
+
+/// Log ranges in `schema`, overall and per device
+fn log_ranges(conn: &Connection, schema: &str) -> Result<(LogRange, BTreeMap<String, LogRange>), String> {
+    let range = |row: &rusqlite::Row| -> rusqlite::Result<LogRange> {
+        Ok(LogRange {
+            count: row.get(0)?,
+            first: row.get(1)?,
+            last: row.get(2)?,
+            days: row.get(3)?,
+        })
+    };
+    let columns = "COUNT(*), MIN(timestamp), MAX(timestamp), COUNT(DISTINCT substr(timestamp, 1, 10))";
+    let overall = conn
+        .query_row(&format!("SELECT {} FROM {}.{}", columns, schema, LOGS), [], range)
+        .map_err(|e| format!("Failed to read log range: {}", e))?;
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT device_id, {} FROM {}.{} GROUP BY device_id",
+            columns, schema, LOGS
+        ))
+        .map_err(|e| format!("Failed to read device log ranges: {}", e))?;
+    let per_device = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                LogRange {
+                    count: row.get(1)?,
+                    first: row.get(2)?,
+                    last: row.get(3)?,
+                    days: row.get(4)?,
+                },
+            ))
+        })
+        .map_err(|e| format!("Failed to read device log ranges: {}", e))?
+        .collect::<Result<BTreeMap<_, _>, _>>()
+        .map_err(|e| format!("Failed to read device log ranges: {}", e))?;
+    Ok((overall, per_device))
+}
+
+/// Device names from either side, live names first
+fn device_names(conn: &Connection, schemas: &[&str]) -> Result<BTreeMap<String, String>, String> {
+    let mut names = BTreeMap::new();
+    for schema in schemas {
+        let mut stmt = conn
+            .prepare(&format!("SELECT id, name FROM {}.devices", schema))
+            .map_err(|e| format!("Failed to read devices: {}", e))?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+            .map_err(|e| format!("Failed to read devices: {}", e))?;
+        for row in rows {
+            let (id, name) = row.map_err(|e| format!("Failed to read devices: {}", e))?;
+            names.entry(id).or_insert(name);
+        }
+    }
+    Ok(names)
+}
+
+/// Compare the backup at `backup_path` with the live database
+pub fn diff(
+    conn: &mut Connection,
+    backup_path: &Path,
+    schema_version: Option<i64>,
+    current_schema_version: i64,
+) -> Result<BackupDiff, String> {
+    with_attached(conn, backup_path, |conn| {
+        let live_tables = tables(conn, "main")?;
+        let backup_tables = tables(conn, BACKUP_SCHEMA)?;
+        if !backup_tables.iter().any(|t| t == LOGS) {
+            return Err("Not a Horus Attendance backup: it has no attendance logs".to_string());
+        }
+
+        let mut names: Vec<&String> = live_tables.iter().chain(backup_tables.iter()).collect();
+        names.sort();
+        names.dedup();
+        let mut table_diffs = Vec::with_capacity(names.len());
+        for table in names {
+            let live_rows = if live_tables.contains(table) {
+                Some(count(conn, "main", table)?)
+            } else {
+                None
+            };
+            let backup_rows = if backup_tables.contains(table) {
+                Some(count(conn, BACKUP_SCHEMA, table)?)
+            } else {
+                None
+            };
+            table_diffs.push(TableDiff {
+                table: table.clone(),
+                live_rows,
+                backup_rows,
+                delta: backup_rows.unwrap_or(0) - live_rows.unwrap_or(0),
+            });
+        }
+
+        let (live_logs, mut live_devices) = log_ranges(conn, "main")?;
+        let (backup_logs, mut backup_devices) = log_ranges(conn, BACKUP_SCHEMA)?;
+        let only_in = |from: &str, other: &str| -> Result<i64, String> {
+            conn.query_row(
+                &format!(
+                    "SELECT COUNT(*) FROM {}.{} l
+                     WHERE NOT EXISTS (SELECT 1 FROM {}.{} o WHERE o.id = l.id)",
+                    from, LOGS, other, LOGS
+                ),
+                [],
+                |row| row.get(0),
+            )
+            .map_err(|e| format!("Failed to compare logs: {}", e))
+        };
+        let logs_only_in_live = only_in("main", BACKUP_SCHEMA)?;
+        let logs_only_in_backup = only_in(BACKUP_SCHEMA, "main")?;
+
+        let mut schemas = vec!["main"];
+        if backup_tables.iter().any(|t| t == "devices") {
+            schemas.push(BACKUP_SCHEMA);
+        }
+        let device_names = device_names(conn, &schemas)?;
+        let mut device_ids: Vec<String> = live_devices.keys().chain(backup_devices.keys()).cloned().collect();
+        device_ids.sort();
+        device_ids.dedup();
+        let devices = device_ids
+            .into_iter()
+            .map(|device_id| DeviceLogDiff {
+                device_name: device_names.get(&device_id).cloned(),
+                live: live_devices.remove(&device_id).unwrap_or_default(),
+                backup: backup_devices.remove(&device_id).unwrap_or_default(),
+                device_id,
+            })
+            .collect();
+
+        Ok(BackupDiff {
+            backup_path: backup_path.to_string_lossy().to_string(),
+            schema_version,
+            current_schema_version,
+            tables: table_diffs,
+            live_logs,
+            backup_logs,
+            logs_only_in_live,
+            logs_only_in_backup,
+            devices,
+        })
+    })
+}
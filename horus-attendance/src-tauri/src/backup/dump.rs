@@ -0,0 +1,450 @@
+//! Plain-text SQL dumps
+//!
+//! Produces output in the style of the sqlite3 `.dump` command so backups can
+//! be inspected, diffed, and loaded with any SQLite tooling. CREATE statements
+//! use IF NOT EXISTS so a dump can also be replayed onto an existing schema.
+//!
+//! Only a full dump can replace the database: a per-table or data-only dump
+//! is marked as partial in its header, and loading checks that every table of
+//! the dump's schema version is present before the file is swapped in.
+//!
+//! Views and triggers are written after the data, so the punch lock triggers
+//! don't fire on the load itself. Loading then checks they match the app's
+//! schema at the dump's version, so a dump can't swap in a database whose
+//! locked periods are no longer guarded.
+//!
+//! A dump to be loaded is untrusted SQL. It runs with attaching disabled and
+//! an authorizer that refuses ATTACH, pragmas other than `foreign_keys`, and
+//! `load_extension`, so it can only build tables in the scratch file.
+
+use rusqlite::hooks::{AuthAction, AuthContext, Authorization};
+use rusqlite::limits::Limit;
+use rusqlite::types::ValueRef;
+use rusqlite::Connection;
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use super::types::SqlDumpOptions;
+use crate::secrets::redact;
+
+/// A table, index, view or trigger definition from sqlite_master
+struct SchemaObject {
+    kind: String,
+    name: String,
+    table: String,
+    sql: String,
+}
+
+/// Header line recording what a dump holds
+const SCOPE_PREFIX: &str = "-- Scope: ";
+
+/// Scope header value of a dump that can replace the database
+const FULL_SCOPE: &str = "full";
+
+/// Quote an identifier for SQL
+pub fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Render a column value as a SQL literal
+fn sql_literal(value: ValueRef) -> String {
+    match value {
+        ValueRef::Null => "NULL".to_string(),
+        ValueRef::Integer(i) => i.to_string(),
+        ValueRef::Real(f) => format!("{:?}", f),
+        ValueRef::Text(t) => format!("'{}'", String::from_utf8_lossy(t).replace('\'', "''")),
+        ValueRef::Blob(b) => {
+            let mut hex = String::with_capacity(b.len() * 2 + 3);
+            hex.push_str("X'");
+            for byte in b {
+                let _ = write!(hex, "{:02X}", byte);
+            }
+            hex.push('\'');
+            hex
+        }
+    }
+}
+
+/// Make CREATE statements idempotent
+fn if_not_exists(sql: &str) -> String {
+    for prefix in [
+        "CREATE TABLE ",
+        "CREATE INDEX ",
+        "CREATE UNIQUE INDEX ",
+        "CREATE VIEW ",
+        "CREATE TRIGGER ",
+    ] {
+        if let Some(rest) = sql.strip_prefix(prefix) {
+            if rest.trim_start().to_uppercase().starts_with("IF NOT EXISTS") {
+                return sql.to_string();
+            }
+            return format!("{}IF NOT EXISTS {}", prefix, rest);
+        }
+    }
+    sql.to_string()
+}
+
+/// Schema objects in the order a dump creates them: tables, then indexes,
+/// views and triggers. Views and triggers keep their creation order, since
+/// later ones can depend on earlier ones.
+fn schema_objects(conn: &Connection) -> Result<Vec<SchemaObject>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT type, name, tbl_name, sql FROM sqlite_master
+             WHERE type IN ('table', 'index', 'view', 'trigger') AND sql IS NOT NULL
+               AND name NOT LIKE 'sqlite_%'
+             ORDER BY CASE type WHEN 'table' THEN 0 WHEN 'index' THEN 1 WHEN 'view' THEN 2 ELSE 3 END,
+                      CASE WHEN type IN ('table', 'index') THEN name END, rowid",
+        )
+        .map_err(|e| format!("Failed to read schema: {}", e))?;
+    let objects = stmt
+        .query_map([], |row| {
+            Ok(SchemaObject {
+                kind: row.get(0)?,
+                name: row.get(1)?,
+                table: row.get(2)?,
+                sql: row.get(3)?,
+            })
+        })
+        .map_err(|e| format!("Failed to read schema: {}", e))?
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read schema: {}", e));
+    objects
+}
+
+/// Write a dump of the database to `path`. Returns the number of rows written.
+pub fn write_dump(conn: &mut Connection, path: &Path, options: &SqlDumpOptions) -> Result<u64, String> {
+    // A read transaction gives a consistent snapshot while the app keeps writing
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to begin read transaction: {}", e))?;
+
+    let objects = schema_objects(&tx)?;
+
+    let selected = |table: &str| match &options.tables {
+        Some(tables) => tables.iter().any(|t| t == table),
+        None => true,
+    };
+    if let Some(tables) = &options.tables {
+        for table in tables {
+            if !objects.iter().any(|o| o.kind == "table" && &o.name == table) {
+                return Err(format!("Unknown table: {}", table));
+            }
+        }
+    }
+
+    let file = File::create(path).map_err(|e| format!("Failed to create dump file: {}", e))?;
+    let mut out = BufWriter::new(file);
+    let io_err = |e: std::io::Error| format!("Failed to write dump: {}", e);
+
+    writeln!(out, "-- Horus Attendance SQL dump").map_err(io_err)?;
+    writeln!(out, "-- Created: {}", chrono::Local::now().to_rfc3339()).map_err(io_err)?;
+    writeln!(out, "-- App version: {}", env!("CARGO_PKG_VERSION")).map_err(io_err)?;
+    let scope = match (&options.tables, options.data_only) {
+        (None, false) => FULL_SCOPE.to_string(),
+        (Some(tables), false) => format!("tables {}", tables.join(", ")),
+        (None, true) => "data only".to_string(),
+        (Some(tables), true) => format!("data only, tables {}", tables.join(", ")),
+    };
+    writeln!(out, "{}{}", SCOPE_PREFIX, scope).map_err(io_err)?;
+    writeln!(out, "PRAGMA foreign_keys=OFF;").map_err(io_err)?;
+    writeln!(out, "BEGIN TRANSACTION;").map_err(io_err)?;
+
+    let mut total_rows = 0u64;
+    for object in objects.iter().filter(|o| o.kind == "table" && selected(&o.name)) {
+        if !options.data_only {
+            writeln!(out, "{};", if_not_exists(&object.sql)).map_err(io_err)?;
+        }
+
+        let table = quote_ident(&object.name);
+        let mut stmt = tx
+            .prepare(&format!("SELECT * FROM {}", table))
+            .map_err(|e| format!("Failed to read {}: {}", object.name, e))?;
+        let column_count = stmt.column_count();
+        // Plaintext secrets (comm keys not yet in the keychain) never leave the database
+        let secret_columns: Vec<bool> = stmt
+            .column_names()
+            .iter()
+            .map(|column| redact::is_secret(&object.name, column))
+            .collect();
+        let mut rows = stmt
+            .query([])
+            .map_err(|e| format!("Failed to read {}: {}", object.name, e))?;
+
+        while let Some(row) = rows.next().map_err(|e| format!("Failed to read {}: {}", object.name, e))? {
+            let mut values = Vec::with_capacity(column_count);
+            for (idx, secret) in secret_columns.iter().enumerate() {
+                let value = row
+                    .get_ref(idx)
+                    .map_err(|e| format!("Failed to read {}: {}", object.name, e))?;
+                let value = match value {
+                    ValueRef::Text(t) if *secret && redact::needs_redaction(&String::from_utf8_lossy(t)) => ValueRef::Text(b""),
+                    other => other,
+                };
+                values.push(sql_literal(value));
+            }
+            writeln!(out, "INSERT INTO {} VALUES({});", table, values.join(",")).map_err(io_err)?;
+            total_rows += 1;
+        }
+    }
+
+    if !options.data_only {
+        // Views read any table, so only a dump of all of them carries them
+        let included = |o: &&SchemaObject| match o.kind.as_str() {
+            "table" => false,
+            "view" => options.tables.is_none(),
+            _ => selected(&o.table),
+        };
+        for object in objects.iter().filter(included) {
+            writeln!(out, "{};", if_not_exists(&object.sql)).map_err(io_err)?;
+        }
+    }
+
+    writeln!(out, "COMMIT;").map_err(io_err)?;
+    out.flush().map_err(io_err)?;
+
+    log::info!("[backup::dump] Wrote {} rows to {}", total_rows, path.display());
+    Ok(total_rows)
+}
+
+/// Authorizer for loading a dump: deny anything that reaches past the
+/// scratch database
+fn guard_dump(ctx: AuthContext<'_>) -> Authorization {
+    match ctx.action {
+        AuthAction::Attach { .. } | AuthAction::Detach { .. } => Authorization::Deny,
+        // Dumps switch foreign keys off for the load; nothing else is needed
+        AuthAction::Pragma { pragma_name, .. } if !pragma_name.eq_ignore_ascii_case("foreign_keys") => Authorization::Deny,
+        AuthAction::Function { function_name } if function_name.eq_ignore_ascii_case("load_extension") => {
+            Authorization::Deny
+        }
+        _ => Authorization::Allow,
+    }
+}
+
+/// The app's schema at migration `version`
+fn app_schema(version: i64) -> Result<Vec<SchemaObject>, String> {
+    let conn = Connection::open_in_memory().map_err(|e| format!("Failed to open schema database: {}", e))?;
+    for migration in crate::get_migrations().into_iter().filter(|m| m.version <= version) {
+        conn.execute_batch(migration.sql)
+            .map_err(|e| format!("Failed to build schema v{}: {}", migration.version, e))?;
+    }
+    schema_objects(&conn)
+}
+
+/// Refuse dumps that don't hold the whole database, which would otherwise
+/// replace every table they leave out with nothing, and dumps whose views
+/// and triggers differ from the app's, which could leave locked periods
+/// unguarded
+fn check_complete(conn: &Connection) -> Result<(), String> {
+    let objects = schema_objects(conn)?;
+    let has_table = |name: &str| objects.iter().any(|o| o.kind == "table" && o.name == name);
+    if !has_table("attendance_logs_raw") {
+        return Err("Dump does not contain a Horus Attendance database".to_string());
+    }
+    if !has_table("_sqlx_migrations") {
+        return Err("Dump has no migration history; only a full dump can replace the database".to_string());
+    }
+
+    let version: Option<i64> = conn
+        .query_row("SELECT MAX(version) FROM _sqlx_migrations WHERE success = 1", [], |row| row.get(0))
+        .map_err(|e| format!("Failed to read dump migrations: {}", e))?;
+    let expected = app_schema(version.unwrap_or(0))?;
+    let missing: Vec<&str> = expected
+        .iter()
+        .filter(|e| e.kind == "table" && !has_table(&e.name))
+        .map(|e| e.name.as_str())
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!(
+            "Dump is missing tables {}; only a full dump can replace the database",
+            missing.join(", ")
+        ));
+    }
+
+    let guarded = |o: &&SchemaObject| o.kind == "view" || o.kind == "trigger";
+    let same = |a: &SchemaObject, b: &SchemaObject| a.kind == b.kind && a.name == b.name && a.sql == b.sql;
+    let mut mismatched: Vec<&str> = expected
+        .iter()
+        .filter(guarded)
+        .filter(|e| !objects.iter().any(|o| same(o, e)))
+        .chain(objects.iter().filter(guarded).filter(|o| !expected.iter().any(|e| same(o, e))))
+        .map(|o| o.name.as_str())
+        .collect();
+    mismatched.sort_unstable();
+    mismatched.dedup();
+    if !mismatched.is_empty() {
+        return Err(format!(
+            "Dump's views and triggers {} don't match the app's schema",
+            mismatched.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+/// Build a new database file at `target` from a full dump, verifying its
+/// integrity and that no table is missing
+pub fn load_dump(dump_path: &Path, target: &Path) -> Result<(), String> {
+    let sql = std::fs::read_to_string(dump_path)
+        .map_err(|e| format!("Failed to read dump file: {}", e))?;
+
+    let scope = sql
+        .lines()
+        .take_while(|line| line.starts_with("--"))
+        .find_map(|line| line.strip_prefix(SCOPE_PREFIX));
+    if let Some(scope) = scope.filter(|s| s.trim() != FULL_SCOPE) {
+        return Err(format!(
+            "Dump holds only part of the database ({}); only a full dump can replace the database",
+            scope.trim()
+        ));
+    }
+
+    if target.exists() {
+        std::fs::remove_file(target).map_err(|e| format!("Failed to clear import file: {}", e))?;
+    }
+    let conn = Connection::open(target).map_err(|e| format!("Failed to create import database: {}", e))?;
+    conn.set_limit(Limit::SQLITE_LIMIT_ATTACHED, 0);
+    conn.authorizer(Some(guard_dump));
+    conn.execute_batch(&sql)
+        .map_err(|e| format!("Failed to load dump: {}", e))?;
+    conn.authorizer(None::<fn(AuthContext<'_>) -> Authorization>);
+
+    let integrity: String = conn
+        .query_row("PRAGMA integrity_check", [], |row| row.get(0))
+        .map_err(|e| format!("Failed to check imported database: {}", e))?;
+    if integrity != "ok" {
+        return Err(format!("Imported database failed integrity check: {}", integrity));
+    }
+
+    check_complete(&conn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn scratch(ext: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("horus_dump_test_{}.{}", uuid::Uuid::new_v4(), ext))
+    }
+
+    fn dump_and_load(options: &SqlDumpOptions, edit: impl FnOnce(String) -> String) -> Result<(), String> {
+        let mut conn = crate::db::migrated();
+        let dump = scratch("sql");
+        let target = scratch("db");
+        write_dump(&mut conn, &dump, options).expect("write dump");
+        let sql = edit(std::fs::read_to_string(&dump).expect("read dump"));
+        std::fs::write(&dump, sql).expect("rewrite dump");
+        let result = load_dump(&dump, &target);
+        let _ = std::fs::remove_file(&dump);
+        let _ = std::fs::remove_file(&target);
+        result
+    }
+
+    #[test]
+    fn full_dump_loads() {
+        dump_and_load(&SqlDumpOptions::default(), |sql| sql).expect("full dump loads");
+    }
+
+    #[test]
+    fn loaded_dumps_keep_locked_punches_locked() {
+        let mut conn = crate::db::migrated();
+        conn.execute_batch(
+            "INSERT INTO devices (id, name, ip, port) VALUES ('d1', 'Front door', '10.0.0.2', 4370);
+             INSERT INTO attendance_logs_raw (id, device_id, device_user_id, timestamp)
+                 VALUES ('l1', 'd1', '7', '2024-01-08T08:00:00.000Z');
+             INSERT INTO locked_periods (start_date, end_date) VALUES ('2024-01-01', '2024-01-31');",
+        )
+        .unwrap();
+        let dump = scratch("sql");
+        let target = scratch("db");
+        write_dump(&mut conn, &dump, &SqlDumpOptions::default()).expect("write dump");
+        let result = load_dump(&dump, &target);
+        let loaded = Connection::open(&target).expect("open loaded dump");
+        let deleted = loaded.execute("DELETE FROM attendance_logs_raw WHERE id = 'l1'", []);
+        drop(loaded);
+        let _ = std::fs::remove_file(&dump);
+        let _ = std::fs::remove_file(&target);
+        result.expect("dump loads");
+        let err = deleted.unwrap_err().to_string();
+        assert!(err.contains("Attendance period is locked"), "{}", err);
+    }
+
+    #[test]
+    fn dumps_with_altered_triggers_are_refused() {
+        let err = dump_and_load(&SqlDumpOptions::default(), |sql| {
+            sql.replacen("RAISE(ABORT, 'Attendance period is locked')", "NULL", 1)
+        })
+        .unwrap_err();
+        assert!(err.contains("don't match the app's schema"), "{}", err);
+
+        let err = dump_and_load(&SqlDumpOptions::default(), |sql| {
+            sql.replace("COMMIT;", "DROP TRIGGER trg_logs_locked_delete;\nCOMMIT;")
+        })
+        .unwrap_err();
+        assert!(err.contains("trg_logs_locked_delete"), "{}", err);
+    }
+
+    #[test]
+    fn partial_dumps_are_refused() {
+        let tables = SqlDumpOptions {
+            tables: Some(vec!["attendance_logs_raw".to_string(), "_sqlx_migrations".to_string()]),
+            data_only: false,
+        };
+        let err = dump_and_load(&tables, |sql| sql).unwrap_err();
+        assert!(err.contains("only part of the database"), "{}", err);
+
+        let data_only = SqlDumpOptions {
+            tables: None,
+            data_only: true,
+        };
+        let err = dump_and_load(&data_only, |sql| sql).unwrap_err();
+        assert!(err.contains("only part of the database"), "{}", err);
+    }
+
+    #[test]
+    fn dumps_cannot_reach_outside_the_scratch_file() {
+        let outside = scratch("db");
+        let outside_sql = outside.to_string_lossy().replace('\'', "''");
+        for statement in [
+            format!("ATTACH DATABASE '{}' AS evil;", outside_sql),
+            format!("VACUUM INTO '{}';", outside_sql),
+            "PRAGMA writable_schema=ON;".to_string(),
+            "SELECT load_extension('evil');".to_string(),
+        ] {
+            let err = dump_and_load(&SqlDumpOptions::default(), |sql| {
+                sql.replacen("BEGIN TRANSACTION;", &format!("{}\nBEGIN TRANSACTION;", statement), 1)
+            })
+            .unwrap_err();
+            assert!(err.starts_with("Failed to load dump"), "{}: {}", statement, err);
+            assert!(!outside.exists(), "{} wrote outside the scratch file", statement);
+        }
+    }
+
+    #[test]
+    fn dumps_missing_tables_are_refused() {
+        // A per-table dump without the scope header, as older builds wrote them
+        let tables = SqlDumpOptions {
+            tables: Some(vec!["attendance_logs_raw".to_string(), "_sqlx_migrations".to_string()]),
+            data_only: false,
+        };
+        let err = dump_and_load(&tables, |sql| {
+            sql.lines()
+                .filter(|line| !line.starts_with(SCOPE_PREFIX))
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .unwrap_err();
+        assert!(err.contains("missing tables") && err.contains("users"), "{}", err);
+
+        let err = dump_and_load(&SqlDumpOptions::default(), |sql| {
+            sql.lines()
+                .filter(|line| !line.contains("_sqlx_migrations"))
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .unwrap_err();
+        assert!(err.contains("no migration history"), "{}", err);
+    }
+}
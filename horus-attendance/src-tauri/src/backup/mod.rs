@@ -0,0 +1,16 @@
+//! Backup subsystem
+//!
+//! Backup formats and restore paths beyond the plain database file copy
+//! handled by export_backup/restore_backup.
+
+pub mod catalog;
+pub mod commands;
+pub mod compat;
+pub mod diff;
+pub mod dump;
+pub mod selective;
+pub mod snapshot;
+pub mod targets;
+pub mod transfer;
+pub mod types;
+pub mod undo;
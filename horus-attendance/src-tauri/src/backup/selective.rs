@@ -0,0 +1,374 @@
+//! Selective restore
+//!
+//! Instead of swapping the whole database file, the backup is attached as a
+//! secondary database and only the chosen tables are copied into the live
+//! one. Rows are merged by primary key: backup rows overwrite live rows with
+//! the same ID, and live rows missing from the backup are kept. Because the
+//! live file is updated in place, no restart is needed afterwards.
+//!
+//! Only columns present in both databases are copied, so a backup from an
+//! older schema restores cleanly into a newer one.
+
+use chrono::{Duration, NaiveDate};
+use rusqlite::Connection;
+use std::path::Path;
+
+use super::dump::quote_ident;
+use super::types::{RestoreMode, RestoreOptions};
+use crate::summary::engine;
+use crate::{db, ledger};
+
+/// Alias the backup is attached under
+pub(super) const BACKUP_SCHEMA: &str = "restore_src";
+
+/// How rows that already exist in the live database are handled
+#[derive(Clone, Copy)]
+enum Conflict {
+    /// Overwrite the live row
+    Update,
+    /// Keep the live row
+    Ignore,
+}
+
+/// Tables a restore mode writes to; None for a full restore
+pub fn affected_tables(mode: RestoreMode) -> Option<Vec<String>> {
+    let tables: &[&str] = match mode {
+        RestoreMode::Full => return None,
+        RestoreMode::UsersDepartments => &["departments", "users"],
+        RestoreMode::SettingsDevices => &["settings", "devices"],
+        RestoreMode::Logs => &["devices", "attendance_logs_raw"],
+    };
+    Some(tables.iter().map(|t| t.to_string()).collect())
+}
+
+/// Run `f` with the backup attached, detaching it afterwards
+pub(super) fn with_attached<T>(
+    conn: &mut Connection,
+    backup_path: &Path,
+    f: impl FnOnce(&mut Connection) -> Result<T, String>,
+) -> Result<T, String> {
+    conn.execute(
+        &format!("ATTACH DATABASE ?1 AS {}", BACKUP_SCHEMA),
+        [backup_path.to_string_lossy()],
+    )
+    .map_err(|e| format!("Failed to open backup file: {}", e))?;
+
+    let result = f(conn);
+
+    if let Err(e) = conn.execute(&format!("DETACH DATABASE {}", BACKUP_SCHEMA), []) {
+        log::warn!("[backup::selective] Failed to detach backup: {}", e);
+    }
+    result
+}
+
+/// Restore part of a backup into the live database. Returns the number of rows copied.
+pub fn restore(conn: &mut Connection, backup_path: &Path, options: &RestoreOptions) -> Result<u64, String> {
+    with_attached(conn, backup_path, |conn| copy_selection(conn, options))
+}
+
+/// IDs of punches only one of the live database and the attached backup has
+fn changed_log_ids(conn: &Connection) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT id FROM (SELECT id FROM main.attendance_logs_raw EXCEPT SELECT id FROM {0}.attendance_logs_raw)
+             UNION SELECT id FROM (SELECT id FROM {0}.attendance_logs_raw EXCEPT SELECT id FROM main.attendance_logs_raw)",
+            BACKUP_SCHEMA
+        ))
+        .map_err(|e| format!("Failed to compare punches: {}", e))?;
+    let ids = stmt
+        .query_map([], |row| row.get(0))
+        .map_err(|e| format!("Failed to compare punches: {}", e))?
+        .collect::<Result<Vec<String>, _>>()
+        .map_err(|e| format!("Failed to compare punches: {}", e));
+    ids
+}
+
+/// Replace the full contents of `tables` with their contents in the backup.
+/// Unlike the merge modes, rows added since the backup are removed, locked
+/// periods included, and sealed ledger batches are amended to match.
+/// Returns the number of rows copied.
+pub fn replace_tables(conn: &mut Connection, backup_path: &Path, tables: &[String]) -> Result<u64, String> {
+    // Deleting parent rows would otherwise cascade into tables we aren't
+    // restoring; consistency is checked explicitly before committing instead
+    conn.pragma_update(None, "foreign_keys", "OFF")
+        .map_err(|e| format!("Failed to disable foreign keys: {}", e))?;
+
+    let result = with_attached(conn, backup_path, |conn| {
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("Failed to begin restore transaction: {}", e))?;
+        let copied = db::maintenance::bypass(&tx, "Restore tables", |conn| {
+            let changed_logs = if tables.iter().any(|t| t == "attendance_logs_raw") {
+                changed_log_ids(conn)?
+            } else {
+                Vec::new()
+            };
+            let mut copied = 0;
+            for table in tables {
+                conn.execute(&format!("DELETE FROM main.{}", quote_ident(table)), [])
+                    .map_err(|e| format!("Failed to clear {}: {}", table, e))?;
+                copied += copy_table(conn, table, None, &[], Conflict::Ignore)?;
+            }
+            ledger::store::amend(conn, &changed_logs, "Restore")?;
+            Ok(copied)
+        })?;
+
+        let violations = {
+            let mut stmt = tx
+                .prepare("PRAGMA main.foreign_key_check")
+                .map_err(|e| format!("Failed to check foreign keys: {}", e))?;
+            let mut rows = stmt
+                .query([])
+                .map_err(|e| format!("Failed to check foreign keys: {}", e))?;
+            let mut count = 0;
+            while rows.next().map_err(|e| format!("Failed to check foreign keys: {}", e))?.is_some() {
+                count += 1;
+            }
+            count
+        };
+        if violations > 0 {
+            return Err(format!(
+                "Restoring {} would leave {} rows referencing missing records",
+                tables.join(", "),
+                violations
+            ));
+        }
+
+        tx.commit()
+            .map_err(|e| format!("Failed to commit restore: {}", e))?;
+        Ok(copied)
+    });
+
+    if let Err(e) = conn.pragma_update(None, "foreign_keys", "ON") {
+        log::warn!("[backup::selective] Failed to re-enable foreign keys: {}", e);
+    }
+    result
+}
+
+fn copy_selection(conn: &mut Connection, options: &RestoreOptions) -> Result<u64, String> {
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to begin restore transaction: {}", e))?;
+
+    let copied = match options.mode {
+        RestoreMode::Full => return Err("Full restores replace the database file".to_string()),
+        RestoreMode::UsersDepartments => {
+            // Departments first so users' department_id references resolve
+            copy_table(&tx, "departments", None, &[], Conflict::Update)?
+                + copy_table(&tx, "users", None, &[], Conflict::Update)?
+        }
+        RestoreMode::SettingsDevices => {
+            copy_table(&tx, "settings", None, &[], Conflict::Update)?
+                + copy_table(&tx, "devices", None, &[], Conflict::Update)?
+        }
+        RestoreMode::Logs => {
+            let (start_date, end_date) = match (&options.start_date, &options.end_date) {
+                (Some(start), Some(end)) => (start, end),
+                _ => return Err("A start and end date are required to restore logs".to_string()),
+            };
+            let (start, end) = log_bounds(&tx, start_date, end_date)?;
+
+            // Logs reference their device; bring over any device the live DB no longer has
+            let devices = copy_table(
+                &tx,
+                "devices",
+                Some(&format!(
+                    "id IN (SELECT device_id FROM {}.attendance_logs_raw WHERE timestamp >= ?1 AND timestamp < ?2)",
+                    BACKUP_SCHEMA
+                )),
+                &[&start, &end],
+                Conflict::Ignore,
+            )?;
+            // Punches are immutable, so an existing row is already the same punch
+            devices
+                + copy_table(
+                    &tx,
+                    "attendance_logs_raw",
+                    Some("timestamp >= ?1 AND timestamp < ?2"),
+                    &[&start, &end],
+                    Conflict::Ignore,
+                )?
+        }
+    };
+
+    tx.commit()
+        .map_err(|e| format!("Failed to commit restore: {}", e))?;
+    Ok(copied)
+}
+
+/// Half-open timestamp bounds of the working days `start_date` to
+/// `end_date`, built like the locked_punch_bounds view: from the day cutoff
+/// hour on the first day to the same hour on the day after the last
+fn log_bounds(conn: &Connection, start_date: &str, end_date: &str) -> Result<(String, String), String> {
+    let parse = |date: &str| {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| format!("Invalid date (expected YYYY-MM-DD): {}", date))
+    };
+    let (start, end) = (parse(start_date)?, parse(end_date)?);
+    let cutoff_hour = engine::day_cutoff_hour(conn)?;
+    Ok((
+        format!("{}T{:02}:00:00", start, cutoff_hour),
+        format!("{}T{:02}:00:00", end + Duration::days(1), cutoff_hour),
+    ))
+}
+
+/// Column names of a table, empty if the table doesn't exist
+fn table_columns(tx: &Connection, schema: &str, table: &str) -> Result<Vec<(String, bool)>, String> {
+    let mut stmt = tx
+        .prepare(&format!("PRAGMA {}.table_info({})", schema, quote_ident(table)))
+        .map_err(|e| format!("Failed to read columns of {}: {}", table, e))?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(1)?, row.get::<_, i64>(5)? > 0)))
+        .map_err(|e| format!("Failed to read columns of {}: {}", table, e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read columns of {}: {}", table, e))
+}
+
+/// Copy rows of one table from the attached backup into the live database
+fn copy_table(
+    tx: &Connection,
+    table: &str,
+    filter: Option<&str>,
+    filter_params: &[&dyn rusqlite::ToSql],
+    conflict: Conflict,
+) -> Result<u64, String> {
+    let live = table_columns(tx, "main", table)?;
+    let backup = table_columns(tx, BACKUP_SCHEMA, table)?;
+    if backup.is_empty() {
+        return Err(format!("Backup does not contain table {}", table));
+    }
+
+    let shared: Vec<&(String, bool)> = live
+        .iter()
+        .filter(|(name, _)| backup.iter().any(|(b, _)| b == name))
+        .collect();
+    let primary_key: Vec<String> = shared.iter().filter(|(_, pk)| *pk).map(|(n, _)| quote_ident(n)).collect();
+    if primary_key.is_empty() {
+        return Err(format!("Backup table {} has no matching primary key", table));
+    }
+    let columns: Vec<String> = shared.iter().map(|(n, _)| quote_ident(n)).collect();
+    let column_list = columns.join(", ");
+
+    let mut sql = format!(
+        "INSERT INTO main.{table} ({cols}) SELECT {cols} FROM {schema}.{table} WHERE {filter}",
+        table = quote_ident(table),
+        cols = column_list,
+        schema = BACKUP_SCHEMA,
+        filter = filter.unwrap_or("1"),
+    );
+    match conflict {
+        Conflict::Update => {
+            let updates: Vec<String> = shared
+                .iter()
+                .filter(|(_, pk)| !*pk)
+                .map(|(n, _)| format!("{0} = excluded.{0}", quote_ident(n)))
+                .collect();
+            if updates.is_empty() {
+                sql.push_str(&format!(" ON CONFLICT({}) DO NOTHING", primary_key.join(", ")));
+            } else {
+                sql.push_str(&format!(" ON CONFLICT({}) DO UPDATE SET {}", primary_key.join(", "), updates.join(", ")));
+            }
+        }
+        Conflict::Ignore => sql = sql.replacen("INSERT INTO", "INSERT OR IGNORE INTO", 1),
+    }
+
+    let copied = tx
+        .execute(&sql, filter_params)
+        .map_err(|e| format!("Failed to restore {}: {}", table, e))?;
+    log::info!("[backup::selective] Restored {} rows into {}", copied, table);
+    Ok(copied as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUNCHES: &str = "INSERT INTO devices (id, name, ip, port) VALUES ('d1', 'Front door', '10.0.0.2', 4370);
+         INSERT INTO attendance_logs_raw (id, device_id, device_user_id, timestamp)
+             VALUES ('l1', 'd1', '7', '2024-01-08T08:00:00.000Z');";
+
+    /// Undoing a sync whose punches were since locked and sealed
+    #[test]
+    fn replace_tables_replaces_locked_and_sealed_punches() {
+        let backup = std::env::temp_dir().join(format!("horus_selective_test_{}.db", uuid::Uuid::new_v4()));
+        let snapshot = db::migrated();
+        snapshot.execute_batch(PUNCHES).unwrap();
+        snapshot
+            .execute("VACUUM INTO ?1", [backup.to_string_lossy()])
+            .unwrap();
+
+        let mut conn = db::migrated();
+        conn.execute_batch(PUNCHES).unwrap();
+        conn.execute_batch(
+            "INSERT INTO attendance_logs_raw (id, device_id, device_user_id, timestamp)
+                 VALUES ('l2', 'd1', '7', '2024-01-08T17:00:00.000Z');
+             INSERT INTO locked_periods (start_date, end_date) VALUES ('2024-01-01', '2024-01-31');",
+        )
+        .unwrap();
+        ledger::store::seal(&mut conn).unwrap();
+
+        let result = replace_tables(&mut conn, &backup, &["attendance_logs_raw".to_string()]);
+        let _ = std::fs::remove_file(&backup);
+        assert_eq!(result, Ok(1));
+
+        let ids: Vec<String> = conn
+            .prepare("SELECT id FROM attendance_logs_raw")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(ids, ["l1"]);
+        assert!(conn.execute("DELETE FROM attendance_logs_raw", []).is_err());
+
+        let verification = ledger::store::verify(&conn).unwrap();
+        assert!(!verification.intact);
+        assert!(verification.broken.is_empty(), "{:?}", verification.broken);
+        assert_eq!(verification.amended.len(), 1);
+        assert_eq!(verification.amended[0].reason, "Restore");
+    }
+
+    /// With the working day starting at 06:00, 05:00 belongs to the day before
+    #[test]
+    fn logs_mode_follows_the_day_cutoff() {
+        let backup = std::env::temp_dir().join(format!("horus_selective_test_{}.db", uuid::Uuid::new_v4()));
+        let snapshot = db::migrated();
+        snapshot
+            .execute_batch(
+                "INSERT INTO devices (id, name, ip, port) VALUES ('d1', 'Front door', '10.0.0.2', 4370);
+                 INSERT INTO attendance_logs_raw (id, device_id, device_user_id, timestamp) VALUES
+                     ('l1', 'd1', '7', '2024-01-08T05:00:00.000Z'),
+                     ('l2', 'd1', '7', '2024-01-08T06:00:00.000Z'),
+                     ('l3', 'd1', '7', '2024-01-09T05:59:59.000Z'),
+                     ('l4', 'd1', '7', '2024-01-09T06:00:00.000Z');",
+            )
+            .unwrap();
+        snapshot
+            .execute("VACUUM INTO ?1", [backup.to_string_lossy()])
+            .unwrap();
+
+        let mut conn = db::migrated();
+        let rules = crate::summary::types::AttendanceRules {
+            day_cutoff_hour: 6,
+            ..Default::default()
+        };
+        db::set_setting(&conn, "attendance", &serde_json::to_string(&rules).unwrap()).unwrap();
+        let options = RestoreOptions {
+            mode: RestoreMode::Logs,
+            start_date: Some("2024-01-08".to_string()),
+            end_date: Some("2024-01-08".to_string()),
+        };
+        let result = restore(&mut conn, &backup, &options);
+        let _ = std::fs::remove_file(&backup);
+        // The device and the two punches of the 8th
+        assert_eq!(result, Ok(3));
+
+        let ids: Vec<String> = conn
+            .prepare("SELECT id FROM attendance_logs_raw ORDER BY id")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(ids, ["l2", "l3"]);
+    }
+}
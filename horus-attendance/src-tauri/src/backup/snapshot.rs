@@ -0,0 +1,120 @@
+//! Consistent database file copies
+//!
+//! The database runs in WAL mode, so recent writes (often the last few hours
+//! of punches) live in the -wal sidecar until a checkpoint folds them into
+//! the main file. Copying only the .db file would silently drop them. Copies
+//! are therefore taken through SQLite itself: the WAL is checkpointed, then
+//! the online backup API copies every page within one read transaction, and
+//! the row counts of the copy are checked against that same snapshot.
+
+use rusqlite::backup::{Backup, StepResult};
+use rusqlite::Connection;
+use std::path::Path;
+
+use super::dump::quote_ident;
+
+/// Tables whose row counts must match between the live database and the copy
+const VERIFIED_TABLES: &[&str] = &[
+    "devices",
+    "departments",
+    "users",
+    "attendance_logs_raw",
+    "attendance_day_summary",
+    "settings",
+    "holidays",
+];
+
+/// Fold the WAL into the main database file and truncate it.
+/// Returns false if other connections kept the checkpoint from completing.
+pub fn checkpoint(conn: &Connection) -> Result<bool, String> {
+    let (busy, log_frames, checkpointed): (i64, i64, i64) = conn
+        .query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })
+        .map_err(|e| format!("Failed to checkpoint database: {}", e))?;
+    if busy != 0 {
+        log::warn!(
+            "[backup::snapshot] WAL checkpoint incomplete ({} of {} frames), copying through SQLite",
+            checkpointed,
+            log_frames
+        );
+    }
+    Ok(busy == 0)
+}
+
+/// Row counts for the verified tables present in the database
+fn row_counts(conn: &Connection) -> Result<Vec<(String, i64)>, String> {
+    let mut counts = Vec::new();
+    for table in VERIFIED_TABLES {
+        let exists: bool = conn
+            .query_row(
+                "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = ?1",
+                [table],
+                |row| row.get(0),
+            )
+            .map_err(|e| format!("Failed to read schema: {}", e))?;
+        if exists {
+            let count: i64 = conn
+                .query_row(&format!("SELECT COUNT(*) FROM {}", quote_ident(table)), [], |row| row.get(0))
+                .map_err(|e| format!("Failed to count {}: {}", table, e))?;
+            counts.push((table.to_string(), count));
+        }
+    }
+    Ok(counts)
+}
+
+/// Copy the database at `source` to `destination`, including any data still
+/// in the WAL, and verify the copy. A copy that fails verification is removed.
+pub fn copy_database(source: &Path, destination: &Path) -> Result<(), String> {
+    let conn = crate::db::open_path(source)?;
+    checkpoint(&conn)?;
+
+    if destination.exists() {
+        std::fs::remove_file(destination)
+            .map_err(|e| format!("Failed to replace {}: {}", destination.display(), e))?;
+    }
+
+    let result = copy_verified(&conn, destination);
+    if result.is_err() {
+        let _ = std::fs::remove_file(destination);
+    }
+    result
+}
+
+fn copy_verified(conn: &Connection, destination: &Path) -> Result<(), String> {
+    // Counting inside the read transaction pins the snapshot the backup copies
+    conn.execute_batch("BEGIN")
+        .map_err(|e| format!("Failed to begin read transaction: {}", e))?;
+    let copied = row_counts(conn).and_then(|counts| {
+        let mut dest = Connection::open(destination)
+            .map_err(|e| format!("Failed to create {}: {}", destination.display(), e))?;
+        {
+            let backup = Backup::new(conn, &mut dest).map_err(|e| format!("Failed to start copy: {}", e))?;
+            match backup.step(-1).map_err(|e| format!("Failed to copy database: {}", e))? {
+                StepResult::Done => {}
+                other => return Err(format!("Database copy did not complete: {:?}", other)),
+            }
+        }
+        // A single-file copy is easier to move around than one needing a -wal sidecar
+        dest.pragma_update(None, "journal_mode", "DELETE")
+            .map_err(|e| format!("Failed to finalize copy: {}", e))?;
+        Ok((counts, dest))
+    });
+    let _ = conn.execute_batch("COMMIT");
+    let (expected, dest) = copied?;
+
+    let actual = row_counts(&dest)?;
+    for (table, count) in &expected {
+        let copied = actual.iter().find(|(t, _)| t == table).map(|(_, c)| *c);
+        if copied != Some(*count) {
+            return Err(format!(
+                "Backup verification failed for {}: expected {} rows, found {}",
+                table,
+                count,
+                copied.map_or_else(|| "none".to_string(), |c| c.to_string())
+            ));
+        }
+    }
+    log::info!("[backup::snapshot] Copied and verified {} tables to {}", expected.len(), destination.display());
+    Ok(())
+}
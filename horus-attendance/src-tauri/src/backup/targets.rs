@@ -0,0 +1,182 @@
+//! Backup target management
+//!
+//! Users point backups at USB sticks and NAS shares that come and go. Targets
+//! are registered by name under the `backupTargets` setting and tested before
+//! use: the folder must exist (the drive is plugged in / the share mounted),
+//! accept a probe file, and have room for a copy of the database.
+
+use rusqlite::Connection;
+use std::fs;
+use std::path::Path;
+
+use super::types::{BackupTarget, BackupTargetStatus};
+use crate::db;
+
+/// Settings key holding the registered targets
+pub const BACKUP_TARGETS_KEY: &str = "backupTargets";
+
+/// Warn when free space is below this multiple of the database size
+const FREE_SPACE_HEADROOM: u64 = 2;
+
+/// Registered targets
+pub fn list(conn: &Connection) -> Result<Vec<BackupTarget>, String> {
+    Ok(db::get_json_setting(conn, BACKUP_TARGETS_KEY)?.unwrap_or_default())
+}
+
+fn save_all(conn: &Connection, targets: &[BackupTarget]) -> Result<(), String> {
+    let json = serde_json::to_string(targets).map_err(|e| format!("Failed to serialize backup targets: {}", e))?;
+    db::set_setting(conn, BACKUP_TARGETS_KEY, &json)
+}
+
+/// Add or update a target (matched by ID)
+pub fn save(conn: &Connection, mut target: BackupTarget) -> Result<BackupTarget, String> {
+    target.name = target.name.trim().to_string();
+    target.path = target.path.trim().to_string();
+    if target.name.is_empty() {
+        return Err("Target name is required".to_string());
+    }
+    if target.path.is_empty() {
+        return Err("Target path is required".to_string());
+    }
+    if !matches!(target.kind.as_str(), "local" | "removable" | "network") {
+        return Err(format!("Unknown target kind: {}", target.kind));
+    }
+
+    let mut targets = list(conn)?;
+    if targets.iter().any(|t| t.id != target.id && t.name.eq_ignore_ascii_case(&target.name)) {
+        return Err(format!("A backup target named '{}' already exists", target.name));
+    }
+    match targets.iter_mut().find(|t| !target.id.is_empty() && t.id == target.id) {
+        Some(existing) => {
+            target.created_at = existing.created_at.clone();
+            *existing = target.clone();
+        }
+        None => {
+            target.id = uuid::Uuid::new_v4().to_string();
+            target.created_at = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+            targets.push(target.clone());
+        }
+    }
+    save_all(conn, &targets)?;
+    Ok(target)
+}
+
+/// Remove a target. Backups already written there are left alone.
+pub fn delete(conn: &Connection, id: &str) -> Result<(), String> {
+    let mut targets = list(conn)?;
+    let before = targets.len();
+    targets.retain(|t| t.id != id);
+    if targets.len() == before {
+        return Err("Backup target not found".to_string());
+    }
+    save_all(conn, &targets)
+}
+
+/// Look up a target by ID
+pub fn get(conn: &Connection, id: &str) -> Result<BackupTarget, String> {
+    list(conn)?
+        .into_iter()
+        .find(|t| t.id == id)
+        .ok_or_else(|| "Backup target not found".to_string())
+}
+
+/// Check a target is present, writable and has room for a backup of `required_bytes`
+pub fn test(target: &BackupTarget, required_bytes: u64) -> BackupTargetStatus {
+    let mut status = BackupTargetStatus {
+        target_id: target.id.clone(),
+        name: target.name.clone(),
+        path: target.path.clone(),
+        available: false,
+        writable: false,
+        free_bytes: None,
+        required_bytes,
+        warnings: Vec::new(),
+        error: None,
+    };
+
+    let path = Path::new(&target.path);
+    if !path.is_dir() {
+        status.error = Some(match target.kind.as_str() {
+            "removable" => format!("{} is not connected ({} not found)", target.name, target.path),
+            "network" => format!("{} is not reachable ({} not found)", target.name, target.path),
+            _ => format!("Folder not found: {}", target.path),
+        });
+        return status;
+    }
+    status.available = true;
+
+    let probe = path.join(format!(".horus_write_test_{}", uuid::Uuid::new_v4()));
+    match fs::write(&probe, b"ok") {
+        Ok(()) => {
+            status.writable = true;
+            if let Err(e) = fs::remove_file(&probe) {
+                status.warnings.push(format!("Could not remove write test file: {}", e));
+            }
+        }
+        Err(e) => {
+            status.error = Some(format!("{} is not writable: {}", target.path, e));
+            return status;
+        }
+    }
+
+    status.free_bytes = free_space(path);
+    match status.free_bytes {
+        Some(free) if free < required_bytes => {
+            status.error = Some(format!(
+                "Not enough space on {}: {} free, {} needed",
+                target.name,
+                format_bytes(free),
+                format_bytes(required_bytes)
+            ));
+        }
+        Some(free) if free < required_bytes.saturating_mul(FREE_SPACE_HEADROOM) => {
+            status.warnings.push(format!("{} is nearly full ({} free)", target.name, format_bytes(free)));
+        }
+        Some(_) => {}
+        None => status.warnings.push("Free space could not be determined".to_string()),
+    }
+    status
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const MB: f64 = 1024.0 * 1024.0;
+    if bytes as f64 >= 1024.0 * MB {
+        format!("{:.1} GB", bytes as f64 / (1024.0 * MB))
+    } else {
+        format!("{:.1} MB", bytes as f64 / MB)
+    }
+}
+
+/// Bytes available to this user on the filesystem holding `path`
+#[cfg(unix)]
+fn free_space(path: &Path) -> Option<u64> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: c_path is a valid NUL-terminated string and stat is a valid out pointer
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    #[allow(clippy::unnecessary_cast)]
+    Some(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+/// Bytes available to this user on the volume holding `path`
+#[cfg(windows)]
+fn free_space(path: &Path) -> Option<u64> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(std::iter::once(0)).collect();
+    let mut available = 0u64;
+    // SAFETY: wide is NUL-terminated; null pointers are allowed for the unused outputs
+    let ok = unsafe { GetDiskFreeSpaceExW(wide.as_ptr(), &mut available, std::ptr::null_mut(), std::ptr::null_mut()) };
+    (ok != 0).then_some(available)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn free_space(_path: &Path) -> Option<u64> {
+    None
+}
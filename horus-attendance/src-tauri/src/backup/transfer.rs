@@ -0,0 +1,331 @@
+//! Full transfer bundles for moving to a new computer
+//!
+//! A bundle is a zip archive holding everything a fresh install needs:
+//!
+//! ```text
+//! manifest.json                 TransferManifest
+//! database/horus_attendance.db  consistent copy (settings, devices, users, logs...)
+//! backups/...                   the backup directory, snapshots included
+//! photos/...                    app data photos, when present
+//! ```
+//!
+//! On import the database is unpacked to a scratch file, checked for schema
+//! compatibility, and settings holding paths from the old machine (backup
+//! folder, backup targets) are rewritten to this machine's directories
+//! before it replaces the live database.
+
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use zip::write::SimpleFileOptions;
+use zip::{AesMode, CompressionMethod, ZipArchive, ZipWriter};
+
+use super::types::{TransferImportResult, TransferManifest};
+use super::{compat, snapshot};
+
+/// Bumped when the bundle layout changes incompatibly
+pub const FORMAT_VERSION: u32 = 1;
+
+const MANIFEST_ENTRY: &str = "manifest.json";
+const DATABASE_ENTRY: &str = "database/horus_attendance.db";
+const BACKUPS_PREFIX: &str = "backups/";
+const PHOTOS_PREFIX: &str = "photos/";
+
+/// Directories a bundle is packed from / unpacked into
+pub struct TransferPaths {
+    pub db_path: PathBuf,
+    pub app_data_dir: PathBuf,
+    /// Documents/HorusAttendance
+    pub documents_dir: PathBuf,
+    pub backup_dir: PathBuf,
+}
+
+impl TransferPaths {
+    fn photos_dir(&self) -> PathBuf {
+        self.app_data_dir.join("photos")
+    }
+}
+
+fn zip_err(e: zip::result::ZipError) -> String {
+    format!("Archive error: {}", e)
+}
+
+/// Add a file to the archive, streaming its contents
+pub(crate) fn add_file<W: Write + io::Seek>(zip: &mut ZipWriter<W>, name: &str, path: &Path) -> Result<(), String> {
+    add_file_protected(zip, name, path, None)
+}
+
+/// Add a file to the archive, encrypted with AES-256 when `password` is set
+pub(crate) fn add_file_protected<W: Write + io::Seek>(
+    zip: &mut ZipWriter<W>,
+    name: &str,
+    path: &Path,
+    password: Option<&str>,
+) -> Result<(), String> {
+    let size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .large_file(size >= u32::MAX as u64);
+    let options = match password {
+        Some(password) => options.with_aes_encryption(AesMode::Aes256, password),
+        None => options,
+    };
+    zip.start_file(name, options).map_err(zip_err)?;
+    let mut file = File::open(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    io::copy(&mut file, zip).map_err(|e| format!("Failed to pack {}: {}", path.display(), e))?;
+    Ok(())
+}
+
+/// Add every regular file under `dir` with the given entry prefix
+fn add_dir<W: Write + io::Seek>(
+    zip: &mut ZipWriter<W>,
+    prefix: &str,
+    dir: &Path,
+    skip: &Path,
+) -> Result<u32, String> {
+    let mut added = 0;
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Ok(0);
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        if path == skip {
+            continue;
+        }
+        if path.is_dir() {
+            added += add_dir(zip, &format!("{}{}/", prefix, name), &path, skip)?;
+        } else if path.is_file() {
+            add_file(zip, &format!("{}{}", prefix, name), &path)?;
+            added += 1;
+        }
+    }
+    Ok(added)
+}
+
+/// Package the database, backups and photos into `destination`. Returns the file count.
+pub fn export_bundle(paths: &TransferPaths, schema_version: i64, destination: &Path) -> Result<u32, String> {
+    if !paths.db_path.exists() {
+        return Err("Database file not found".to_string());
+    }
+
+    let scratch = std::env::temp_dir().join(format!("horus_transfer_{}.db", uuid::Uuid::new_v4()));
+    let result = (|| {
+        snapshot::copy_database(&paths.db_path, &scratch)?;
+        compat::stamp_metadata(&scratch)?;
+        // Keychain entries don't travel with the bundle and plaintext keys
+        // must not either; comm keys are re-entered on the new machine
+        let redacted = crate::secrets::redact::redact_database(&crate::db::open_path(&scratch)?)?;
+        if redacted > 0 {
+            log::info!("[backup::transfer] Redacted {} plaintext secrets from the bundle", redacted);
+        }
+
+        let file = File::create(destination).map_err(|e| format!("Failed to create bundle: {}", e))?;
+        let mut zip = ZipWriter::new(io::BufWriter::new(file));
+
+        add_file(&mut zip, DATABASE_ENTRY, &scratch)?;
+        let mut files = 1;
+        files += add_dir(&mut zip, BACKUPS_PREFIX, &paths.backup_dir, destination)?;
+        files += add_dir(&mut zip, PHOTOS_PREFIX, &paths.photos_dir(), destination)?;
+
+        let manifest = TransferManifest {
+            format_version: FORMAT_VERSION,
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            created_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            schema_version: Some(schema_version),
+            app_data_dir: paths.app_data_dir.to_string_lossy().to_string(),
+            documents_dir: paths.documents_dir.to_string_lossy().to_string(),
+            files,
+        };
+        let json = serde_json::to_vec_pretty(&manifest).map_err(|e| format!("Failed to write manifest: {}", e))?;
+        zip.start_file(MANIFEST_ENTRY, SimpleFileOptions::default())
+            .map_err(zip_err)?;
+        zip.write_all(&json).map_err(|e| format!("Failed to write manifest: {}", e))?;
+
+        zip.finish()
+            .map_err(zip_err)?
+            .flush()
+            .map_err(|e| format!("Failed to finish bundle: {}", e))?;
+        Ok(files)
+    })();
+
+    let _ = fs::remove_file(&scratch);
+    if result.is_err() {
+        let _ = fs::remove_file(destination);
+    }
+    result
+}
+
+/// A bundle unpacked into scratch space, ready to be swapped in
+pub struct UnpackedBundle {
+    pub manifest: TransferManifest,
+    /// Scratch copy of the bundled database; the caller removes it
+    pub database: PathBuf,
+    pub result: TransferImportResult,
+}
+
+/// Read a bundle: extract backups and photos in place, the database to a
+/// scratch file with paths fixed up for this machine
+pub fn unpack_bundle(bundle: &Path, paths: &TransferPaths, schema_version: i64) -> Result<UnpackedBundle, String> {
+    let file = File::open(bundle).map_err(|e| format!("Failed to open bundle: {}", e))?;
+    let mut archive = ZipArchive::new(io::BufReader::new(file)).map_err(zip_err)?;
+
+    let manifest: TransferManifest = {
+        let mut entry = archive
+            .by_name(MANIFEST_ENTRY)
+            .map_err(|_| "Not a Horus Attendance transfer bundle (manifest missing)".to_string())?;
+        let mut raw = String::new();
+        entry
+            .read_to_string(&mut raw)
+            .map_err(|e| format!("Failed to read manifest: {}", e))?;
+        serde_json::from_str(&raw).map_err(|e| format!("Invalid bundle manifest: {}", e))?
+    };
+    if manifest.format_version > FORMAT_VERSION {
+        return Err(format!(
+            "Bundle format v{} was made by a newer app ({}); update before importing",
+            manifest.format_version, manifest.app_version
+        ));
+    }
+
+    let mut result = TransferImportResult {
+        success: false,
+        restart_required: false,
+        files_restored: 0,
+        paths_updated: 0,
+        warnings: Vec::new(),
+        error: None,
+    };
+
+    let database = std::env::temp_dir().join(format!("horus_transfer_{}.db", uuid::Uuid::new_v4()));
+    {
+        let mut entry = archive
+            .by_name(DATABASE_ENTRY)
+            .map_err(|_| "Bundle does not contain a database".to_string())?;
+        let mut out = File::create(&database).map_err(|e| format!("Failed to unpack database: {}", e))?;
+        io::copy(&mut entry, &mut out).map_err(|e| format!("Failed to unpack database: {}", e))?;
+    }
+
+    let unpacked = (|| {
+        let compatibility = compat::check(&database, schema_version);
+        if let Some(error) = compatibility.error {
+            return Err(error);
+        }
+        result.warnings.extend(compatibility.warnings);
+
+        result.paths_updated = fix_up_paths(&database, &manifest, paths)?;
+
+        for index in 0..archive.len() {
+            let mut entry = archive.by_index(index).map_err(zip_err)?;
+            // enclosed_name rejects absolute paths and `..` traversal
+            let Some(name) = entry.enclosed_name() else {
+                result.warnings.push(format!("Skipped unsafe entry {}", entry.name()));
+                continue;
+            };
+            let name = name.to_string_lossy().replace('\\', "/");
+            let target = if let Some(rest) = name.strip_prefix(BACKUPS_PREFIX) {
+                paths.backup_dir.join(rest)
+            } else if let Some(rest) = name.strip_prefix(PHOTOS_PREFIX) {
+                paths.photos_dir().join(rest)
+            } else {
+                continue;
+            };
+            if entry.is_dir() {
+                continue;
+            }
+            if target.exists() {
+                // Never overwrite local backups with same-named ones from the old machine
+                result.warnings.push(format!("Kept existing {}", target.display()));
+                continue;
+            }
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+            }
+            let mut out = File::create(&target).map_err(|e| format!("Failed to write {}: {}", target.display(), e))?;
+            io::copy(&mut entry, &mut out).map_err(|e| format!("Failed to write {}: {}", target.display(), e))?;
+            result.files_restored += 1;
+        }
+        Ok(())
+    })();
+
+    match unpacked {
+        Ok(()) => Ok(UnpackedBundle {
+            manifest,
+            database,
+            result,
+        }),
+        Err(e) => {
+            let _ = fs::remove_file(&database);
+            Err(e)
+        }
+    }
+}
+
+/// Replace `old` prefixes with their new equivalents in every string of a JSON value
+fn rewrite_paths(value: &mut serde_json::Value, mappings: &[(String, String)]) -> bool {
+    match value {
+        serde_json::Value::String(s) => {
+            for (old, new) in mappings {
+                if let Some(rest) = s.strip_prefix(old.as_str()) {
+                    *s = format!("{}{}", new, rest);
+                    return true;
+                }
+            }
+            false
+        }
+        // Every element must be visited, so no short-circuiting `any`
+        serde_json::Value::Array(items) => {
+            let mut changed = false;
+            for item in items.iter_mut() {
+                changed |= rewrite_paths(item, mappings);
+            }
+            changed
+        }
+        serde_json::Value::Object(map) => {
+            let mut changed = false;
+            for item in map.values_mut() {
+                changed |= rewrite_paths(item, mappings);
+            }
+            changed
+        }
+        _ => false,
+    }
+}
+
+/// Point settings that reference the old machine's folders at this machine's.
+/// Returns the number of settings changed.
+fn fix_up_paths(database: &Path, manifest: &TransferManifest, paths: &TransferPaths) -> Result<u32, String> {
+    let mut mappings = vec![
+        (manifest.documents_dir.clone(), paths.documents_dir.to_string_lossy().to_string()),
+        (manifest.app_data_dir.clone(), paths.app_data_dir.to_string_lossy().to_string()),
+    ];
+    mappings.retain(|(old, new)| !old.is_empty() && old != new);
+    if mappings.is_empty() {
+        return Ok(0);
+    }
+
+    let conn = crate::db::open_path(database)?;
+    let settings: Vec<(String, String)> = {
+        let mut stmt = conn
+            .prepare("SELECT key, value FROM settings")
+            .map_err(|e| format!("Failed to read settings: {}", e))?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| format!("Failed to read settings: {}", e))?;
+        rows.collect::<Result<_, _>>()
+            .map_err(|e| format!("Failed to read settings: {}", e))?
+    };
+
+    let mut updated = 0;
+    for (key, raw) in settings {
+        // Non-JSON values aren't paths we manage
+        let Ok(mut value) = serde_json::from_str::<serde_json::Value>(&raw) else {
+            continue;
+        };
+        if rewrite_paths(&mut value, &mappings) {
+            crate::db::set_setting(&conn, &key, &value.to_string())?;
+            updated += 1;
+        }
+    }
+    Ok(updated)
+}
@@ -0,0 +1,248 @@
+//! Backup data types for Tauri command serialization
+
+use serde::{Deserialize, Serialize};
+
+/// Options for a plain-text SQL dump
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SqlDumpOptions {
+    /// Only dump these tables (all tables when omitted)
+    #[serde(default)]
+    pub tables: Option<Vec<String>>,
+    /// Emit data only, without CREATE statements
+    #[serde(default)]
+    pub data_only: bool,
+}
+
+/// What part of a backup to restore
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RestoreMode {
+    /// Replace the whole database file
+    #[default]
+    Full,
+    /// Merge users and departments
+    UsersDepartments,
+    /// Merge settings and devices
+    SettingsDevices,
+    /// Merge raw logs within a date range
+    Logs,
+}
+
+/// Options for restore_backup
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreOptions {
+    #[serde(default)]
+    pub mode: RestoreMode,
+    /// First day of logs to restore (YYYY-MM-DD), required for `logs` mode
+    #[serde(default)]
+    pub start_date: Option<String>,
+    /// Last day of logs to restore (YYYY-MM-DD), required for `logs` mode
+    #[serde(default)]
+    pub end_date: Option<String>,
+}
+
+/// Result of checking whether a backup can be restored by this app version
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupCompatibility {
+    /// False when restoring would be refused
+    pub compatible: bool,
+    /// Highest migration applied in the backup (None if it has no migration history)
+    pub schema_version: Option<i64>,
+    /// Highest migration this app knows about
+    pub current_schema_version: i64,
+    /// App version that created the backup, when recorded
+    pub app_version: Option<String>,
+    pub current_app_version: String,
+    /// Non-blocking issues the user should know about
+    pub warnings: Vec<String>,
+    /// Why the backup is incompatible
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A snapshot taken before a destructive operation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OperationSnapshot {
+    pub id: String,
+    /// What was about to happen, e.g. "Delete device Front Door"
+    pub label: String,
+    /// Snapshot file name within the backup directory
+    pub file_name: String,
+    /// Tables the operation touches; None means the whole database
+    pub tables: Option<Vec<String>>,
+    pub created_at: String,
+    /// Set once the snapshot has been restored by undo
+    #[serde(default)]
+    pub undone: bool,
+}
+
+/// Result of undo_last_operation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UndoResult {
+    pub success: bool,
+    /// Label of the operation that was undone
+    pub label: Option<String>,
+    /// Whole-database undos replace the file and need an app restart
+    pub restart_required: bool,
+    pub error: Option<String>,
+}
+
+/// Details of a backup file, read without restoring it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupInfo {
+    pub file_name: String,
+    pub path: String,
+    pub file_size: u64,
+    /// When the backup was made (stamped metadata, else file modification time)
+    pub created_at: Option<String>,
+    /// "backup", "pre_restore", "pre_reset" or "pre_op"
+    pub kind: String,
+    pub user_count: Option<i64>,
+    pub log_count: Option<i64>,
+    pub summary_count: Option<i64>,
+    pub device_count: Option<i64>,
+    pub schema_version: Option<i64>,
+    pub app_version: Option<String>,
+    /// Result of PRAGMA quick_check
+    pub integrity_ok: bool,
+    /// Why the file couldn't be read, if it couldn't
+    pub error: Option<String>,
+}
+
+/// Row counts of one table in the live database and a backup
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableDiff {
+    pub table: String,
+    /// None when the table doesn't exist on that side
+    pub live_rows: Option<i64>,
+    pub backup_rows: Option<i64>,
+    /// Backup rows minus live rows: what a full restore would change
+    pub delta: i64,
+}
+
+/// Raw logs held on one side
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogRange {
+    pub count: i64,
+    /// Earliest and latest punch timestamps
+    pub first: Option<String>,
+    pub last: Option<String>,
+    /// Distinct days with punches
+    pub days: i64,
+}
+
+/// Raw logs of one device in the live database and a backup
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceLogDiff {
+    pub device_id: String,
+    pub device_name: Option<String>,
+    pub live: LogRange,
+    pub backup: LogRange,
+}
+
+/// What restoring a backup would change, table by table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupDiff {
+    pub backup_path: String,
+    pub schema_version: Option<i64>,
+    pub current_schema_version: i64,
+    pub tables: Vec<TableDiff>,
+    pub live_logs: LogRange,
+    pub backup_logs: LogRange,
+    /// Live logs the backup doesn't have, lost by a full restore
+    pub logs_only_in_live: i64,
+    /// Backup logs the live database doesn't have
+    pub logs_only_in_backup: i64,
+    pub devices: Vec<DeviceLogDiff>,
+}
+
+/// A named place backups can be written to (local folder, USB drive, NAS share)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupTarget {
+    /// Generated when empty on save
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    pub path: String,
+    /// "local", "removable" or "network"; informational, used in warnings
+    #[serde(default = "default_target_kind")]
+    pub kind: String,
+    /// Used by scheduled backups; checked at startup
+    #[serde(default)]
+    pub scheduled: bool,
+    #[serde(default)]
+    pub created_at: String,
+}
+
+fn default_target_kind() -> String {
+    "local".to_string()
+}
+
+/// Result of testing a backup target
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupTargetStatus {
+    pub target_id: String,
+    pub name: String,
+    pub path: String,
+    /// The folder exists (drive plugged in / share mounted)
+    pub available: bool,
+    /// A probe file could be created and removed
+    pub writable: bool,
+    pub free_bytes: Option<u64>,
+    /// Size of the current database, for comparison with free space
+    pub required_bytes: u64,
+    pub warnings: Vec<String>,
+    pub error: Option<String>,
+}
+
+/// Describes a transfer bundle; stored as manifest.json inside the archive
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferManifest {
+    pub format_version: u32,
+    pub app_version: String,
+    pub created_at: String,
+    pub schema_version: Option<i64>,
+    /// Directories on the source machine, used to fix up stored paths
+    pub app_data_dir: String,
+    pub documents_dir: String,
+    pub files: u32,
+}
+
+/// Result of export_full_transfer_bundle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferExportResult {
+    pub success: bool,
+    pub file_path: String,
+    pub file_size: u64,
+    /// Files packaged, including the database
+    pub files: u32,
+    pub error: Option<String>,
+}
+
+/// Result of import_full_transfer_bundle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferImportResult {
+    pub success: bool,
+    /// The database was replaced; migrations run when the app restarts
+    pub restart_required: bool,
+    pub files_restored: u32,
+    /// Settings paths rewritten for this machine
+    pub paths_updated: u32,
+    pub warnings: Vec<String>,
+    pub error: Option<String>,
+}
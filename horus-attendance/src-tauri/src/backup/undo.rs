@@ -0,0 +1,125 @@
+//! Pre-operation snapshots and undo
+//!
+//! Before a destructive operation (restore, reset, deleting a device, mass
+//! deletes, merges) a copy of the database is taken and recorded in an index
+//! alongside the backups, labeled with what was about to happen and which
+//! tables it touches. `undo_last_operation` restores the most recent one:
+//! only the listed tables are rolled back, in place, so unrelated work done
+//! since (e.g. a sync) survives. Snapshots without a table list roll back
+//! the whole database file.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::snapshot;
+use super::types::OperationSnapshot;
+
+/// Index of snapshots, kept in the backup directory
+const INDEX_FILE: &str = "operation_snapshots.json";
+
+/// File prefix for table-level operation snapshots
+const OPERATION_PREFIX: &str = "pre_op";
+
+/// Table-level snapshots kept before the oldest are pruned. Whole-database
+/// snapshots (pre_restore, pre_reset) are regular backups and never pruned.
+const MAX_OPERATION_SNAPSHOTS: usize = 10;
+
+fn index_path(backup_dir: &Path) -> PathBuf {
+    backup_dir.join(INDEX_FILE)
+}
+
+/// Read the snapshot index, oldest first
+pub fn list(backup_dir: &Path) -> Result<Vec<OperationSnapshot>, String> {
+    let path = index_path(backup_dir);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let raw = fs::read_to_string(&path).map_err(|e| format!("Failed to read snapshot index: {}", e))?;
+    match serde_json::from_str(&raw) {
+        Ok(snapshots) => Ok(snapshots),
+        Err(e) => {
+            log::warn!("[backup::undo] Ignoring malformed snapshot index: {}", e);
+            Ok(Vec::new())
+        }
+    }
+}
+
+fn save_index(backup_dir: &Path, snapshots: &[OperationSnapshot]) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(snapshots)
+        .map_err(|e| format!("Failed to serialize snapshot index: {}", e))?;
+    fs::write(index_path(backup_dir), json).map_err(|e| format!("Failed to write snapshot index: {}", e))
+}
+
+/// Snapshot the database before an operation.
+/// `prefix` names the file (e.g. "pre_restore"); table-level snapshots use "pre_op".
+pub fn create(
+    db_path: &Path,
+    backup_dir: &Path,
+    prefix: Option<&str>,
+    label: &str,
+    tables: Option<Vec<String>>,
+) -> Result<OperationSnapshot, String> {
+    let prefix = prefix.unwrap_or(OPERATION_PREFIX);
+    let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
+    let mut file_name = format!("{}_{}.db", prefix, timestamp);
+    // Several operations can run within the same second
+    let mut attempt = 1;
+    while backup_dir.join(&file_name).exists() {
+        attempt += 1;
+        file_name = format!("{}_{}_{}.db", prefix, timestamp, attempt);
+    }
+
+    snapshot::copy_database(db_path, &backup_dir.join(&file_name))?;
+
+    let entry = OperationSnapshot {
+        id: uuid::Uuid::new_v4().to_string(),
+        label: label.to_string(),
+        file_name,
+        tables,
+        created_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        undone: false,
+    };
+
+    let mut snapshots = list(backup_dir)?;
+    snapshots.push(entry.clone());
+    prune(backup_dir, &mut snapshots);
+    save_index(backup_dir, &snapshots)?;
+
+    log::info!("[backup::undo] Snapshot '{}' saved as {}", entry.label, entry.file_name);
+    Ok(entry)
+}
+
+/// Drop the oldest table-level snapshots beyond the retention limit
+fn prune(backup_dir: &Path, snapshots: &mut Vec<OperationSnapshot>) {
+    let table_level = snapshots.iter().filter(|s| s.tables.is_some()).count();
+    let mut excess = table_level.saturating_sub(MAX_OPERATION_SNAPSHOTS);
+    snapshots.retain(|s| {
+        if excess > 0 && s.tables.is_some() {
+            excess -= 1;
+            if let Err(e) = fs::remove_file(backup_dir.join(&s.file_name)) {
+                log::warn!("[backup::undo] Failed to remove old snapshot {}: {}", s.file_name, e);
+            }
+            return false;
+        }
+        true
+    });
+    // Forget snapshots whose files were deleted by hand
+    snapshots.retain(|s| backup_dir.join(&s.file_name).exists());
+}
+
+/// The most recent snapshot that hasn't been undone yet
+pub fn last_pending(backup_dir: &Path) -> Result<Option<OperationSnapshot>, String> {
+    Ok(list(backup_dir)?
+        .into_iter()
+        .rev()
+        .find(|s| !s.undone && backup_dir.join(&s.file_name).exists()))
+}
+
+/// Record that a snapshot has been restored
+pub fn mark_undone(backup_dir: &Path, id: &str) -> Result<(), String> {
+    let mut snapshots = list(backup_dir)?;
+    if let Some(entry) = snapshots.iter_mut().find(|s| s.id == id) {
+        entry.undone = true;
+    }
+    save_index(backup_dir, &snapshots)
+}
@@ -0,0 +1,69 @@
+//! Tauri command handlers for the central bell schedule.
+
+use crate::db;
+use crate::zkteco::bells;
+use crate::zkteco::capabilities::{self, FEATURE_BELLS};
+use crate::zkteco::client::ZKClient;
+use crate::zkteco::commands::{resolve_comm_key, validate_config};
+use crate::zkteco::types::{Bell, DeviceConfig, DeviceWriteResult};
+
+const BELL_SCHEDULE_KEY: &str = "bell_schedule";
+
+/// The saved schedule
+#[tauri::command]
+pub async fn get_bell_schedule(app: tauri::AppHandle) -> Result<Vec<Bell>, String> {
+    let conn = db::open(&app)?;
+    Ok(db::get_json_setting(&conn, BELL_SCHEDULE_KEY)?.unwrap_or_default())
+}
+
+/// Save the schedule. Devices keep theirs until it is pushed.
+#[tauri::command]
+pub async fn save_bell_schedule(app: tauri::AppHandle, bells: Vec<Bell>) -> Result<(), String> {
+    bells::validate(&bells)?;
+    let json = serde_json::to_string(&bells).map_err(|e| format!("Failed to serialize bell schedule: {}", e))?;
+    let conn = db::open(&app)?;
+    db::set_setting(&conn, BELL_SCHEDULE_KEY, &json)
+}
+
+/// Write the saved schedule to each device
+#[tauri::command]
+pub async fn push_bell_schedule(app: tauri::AppHandle, devices: Vec<DeviceConfig>) -> Result<Vec<DeviceWriteResult>, String> {
+    let schedule: Vec<Bell> = {
+        let conn = db::open(&app)?;
+        db::get_json_setting(&conn, BELL_SCHEDULE_KEY)?.unwrap_or_default()
+    };
+
+    let mut results = Vec::with_capacity(devices.len());
+    for config in devices {
+        let supported = capabilities::require(&*db::open(&app)?, config.device_id.as_deref(), FEATURE_BELLS);
+        let outcome = match supported {
+            Ok(()) => push(config.clone(), &schedule).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = &outcome {
+            log::warn!("[bells] {}: {}", config.ip, e);
+        }
+        results.push(DeviceWriteResult {
+            device_id: config.device_id,
+            ip: config.ip,
+            success: outcome.is_ok(),
+            error: outcome.err(),
+        });
+    }
+    log::info!(
+        "[bells] Pushed {} bells to {} of {} devices",
+        schedule.len(),
+        results.iter().filter(|r| r.success).count(),
+        results.len()
+    );
+    Ok(results)
+}
+
+async fn push(config: DeviceConfig, schedule: &[Bell]) -> Result<(), String> {
+    validate_config(&config)?;
+    let config = resolve_comm_key(config).await?;
+    let mut client = ZKClient::connect(&config).await?;
+    let result = bells::write(&mut client, schedule).await;
+    let _ = client.disconnect().await;
+    result
+}
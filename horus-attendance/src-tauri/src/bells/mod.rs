@@ -0,0 +1,7 @@
+//! Central bell schedule
+//!
+//! Shift-start bells are configured once in the app and pushed to every
+//! terminal, rather than set up in each terminal's menu. The schedule is kept
+//! in the settings table.
+
+pub mod commands;
@@ -0,0 +1,149 @@
+//! Evaluation of break rules over the day's punches
+
+use std::collections::BTreeMap;
+
+use chrono::NaiveDateTime;
+use rusqlite::{params, Connection};
+use serde_json::json;
+
+use super::types::{BreakCheckResult, BreakRules};
+use crate::db;
+use crate::exceptions::store as exceptions;
+use crate::exceptions::types::NewException;
+use crate::quarantine::validate::parse_timestamp;
+use crate::summary::engine;
+use crate::summary::rules::working_date_sql;
+
+pub const KIND_BREAK_VIOLATION: &str = "break_violation";
+
+pub const SETTINGS_KEY: &str = "breakRules";
+
+/// Punches this close to the previous one are double taps, not a new session
+const DOUBLE_TAP_SECONDS: i64 = 60;
+
+pub fn load_rules(conn: &Connection) -> Result<BreakRules, String> {
+    Ok(db::get_json_setting(conn, SETTINGS_KEY)?.unwrap_or_default())
+}
+
+pub fn validate(rules: &BreakRules) -> Result<(), String> {
+    for rule in &rules.rules {
+        if !(rule.after_hours > 0.0 && rule.after_hours <= 24.0) {
+            return Err("Break rule shift length must be between 0 and 24 hours".to_string());
+        }
+        if !(1..=240).contains(&rule.min_break_minutes) {
+            return Err("Required break must be between 1 and 240 minutes".to_string());
+        }
+    }
+    if !(0..=120).contains(&rules.min_counted_break_minutes) {
+        return Err("Shortest counted break must be between 0 and 120 minutes".to_string());
+    }
+    Ok(())
+}
+
+/// Check every user day between two dates (inclusive) and queue violations
+pub fn check(conn: &Connection, rules: &BreakRules, start_date: &str, end_date: &str) -> Result<BreakCheckResult, String> {
+    let mut result = BreakCheckResult::default();
+    let mut findings = Vec::new();
+    for ((device_user_id, date), punches) in punches_by_day(conn, start_date, end_date)? {
+        let punches = without_double_taps(punches);
+        if punches.len() < 2 || punches.len() % 2 != 0 {
+            continue;
+        }
+        result.days_checked += 1;
+        if let Some(finding) = evaluate(rules, &device_user_id, &date, &punches) {
+            findings.push(finding);
+        }
+    }
+    result.violations = findings.len() as i64;
+    result.new_exceptions = exceptions::insert(conn, &findings)?;
+    Ok(result)
+}
+
+/// The violation for one paired day, if any
+fn evaluate(rules: &BreakRules, device_user_id: &str, date: &str, punches: &[NaiveDateTime]) -> Option<NewException> {
+    let shift_minutes = (punches[punches.len() - 1] - punches[0]).num_minutes();
+    let shift_hours = shift_minutes as f64 / 60.0;
+    let required = rules
+        .rules
+        .iter()
+        .filter(|rule| shift_hours > rule.after_hours)
+        .max_by_key(|rule| rule.min_break_minutes)?;
+
+    // Sessions are punches[0..2], punches[2..4], ...; breaks are the gaps between them
+    let breaks: Vec<i64> = punches[1..punches.len() - 1]
+        .chunks(2)
+        .map(|gap| (gap[1] - gap[0]).num_minutes())
+        .filter(|minutes| *minutes >= rules.min_counted_break_minutes)
+        .collect();
+    let taken: i64 = breaks.iter().sum();
+    if taken >= required.min_break_minutes {
+        return None;
+    }
+
+    let sessions: Vec<[String; 2]> = punches
+        .chunks(2)
+        .map(|session| [session[0].format("%H:%M").to_string(), session[1].format("%H:%M").to_string()])
+        .collect();
+    Some(NewException {
+        kind: KIND_BREAK_VIOLATION,
+        severity: "warning",
+        device_user_id: Some(device_user_id.to_string()),
+        date: Some(date.to_string()),
+        message: format!(
+            "{} min of breaks in a {:.1} h shift ({} min required after {} h)",
+            taken, shift_hours, required.min_break_minutes, required.after_hours
+        ),
+        details: json!({
+            "shiftMinutes": shift_minutes,
+            "breakMinutes": taken,
+            "breaks": breaks,
+            "requiredBreakMinutes": required.min_break_minutes,
+            "afterHours": required.after_hours,
+            "sessions": sessions,
+        }),
+        dedupe_key: format!("{}:{}:{}", KIND_BREAK_VIOLATION, device_user_id, date),
+    })
+}
+
+fn without_double_taps(punches: Vec<NaiveDateTime>) -> Vec<NaiveDateTime> {
+    let mut kept: Vec<NaiveDateTime> = Vec::with_capacity(punches.len());
+    for punch in punches {
+        if kept.last().map_or(true, |last| (punch - *last).num_seconds() >= DOUBLE_TAP_SECONDS) {
+            kept.push(punch);
+        }
+    }
+    kept
+}
+
+/// Punch times by (device user, working date), oldest first
+fn punches_by_day(
+    conn: &Connection,
+    start_date: &str,
+    end_date: &str,
+) -> Result<BTreeMap<(String, String), Vec<NaiveDateTime>>, String> {
+    let cutoff_hour = engine::day_cutoff_hour(conn)?;
+    let (start, end) = db::logs::working_day_bounds(start_date, end_date, cutoff_hour);
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT device_user_id, {} AS day, timestamp
+             FROM attendance_logs_raw
+             WHERE timestamp >= ?1 AND timestamp < ?2
+             ORDER BY device_user_id, timestamp",
+            working_date_sql("timestamp", cutoff_hour)
+        ))
+        .map_err(|e| format!("Failed to query logs: {}", e))?;
+    let rows = stmt
+        .query_map(params![start, end], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?, row.get::<_, String>(2)?))
+        })
+        .map_err(|e| format!("Failed to query logs: {}", e))?;
+    let mut by_day: BTreeMap<(String, String), Vec<NaiveDateTime>> = BTreeMap::new();
+    for row in rows {
+        let (device_user_id, day, timestamp) = row.map_err(|e| format!("Failed to read logs: {}", e))?;
+        // Unreadable timestamps are the anomaly check's business
+        if let (Some(day), Some(time)) = (day, parse_timestamp(&timestamp)) {
+            by_day.entry((device_user_id, day)).or_default().push(time);
+        }
+    }
+    Ok(by_day)
+}
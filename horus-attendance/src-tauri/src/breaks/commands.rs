@@ -0,0 +1,102 @@
+//! Tauri command handlers for break compliance.
+
+use std::time::Duration;
+
+use chrono::Local;
+
+use super::check::{self, SETTINGS_KEY};
+use super::types::*;
+use crate::activity::registry;
+use crate::db;
+use crate::jobs;
+use crate::summary::commands::validate_date;
+
+/// How often the scheduled check runs
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Days back the scheduled check covers, ending yesterday
+const CHECK_DAYS: i64 = 7;
+
+/// ID of the scheduled check in the jobs registry
+pub const JOB_ID: &str = "break_checks";
+
+#[tauri::command]
+pub async fn get_break_rules(app: tauri::AppHandle) -> Result<BreakRules, String> {
+    check::load_rules(&*db::open(&app)?)
+}
+
+#[tauri::command]
+pub async fn save_break_rules(app: tauri::AppHandle, rules: BreakRules) -> Result<(), String> {
+    check::validate(&rules)?;
+    let json = serde_json::to_string(&rules).map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    log::info!(
+        "[breaks::cmd] Break checks {}, {} rules",
+        if rules.enabled { "enabled" } else { "disabled" },
+        rules.rules.len()
+    );
+    db::set_setting(&*db::open(&app)?, SETTINGS_KEY, &json)
+}
+
+/// Check breaks between two dates now and queue violations
+#[tauri::command]
+pub async fn run_break_check(app: tauri::AppHandle, start_date: String, end_date: String) -> Result<BreakCheckResult, String> {
+    validate_date(&start_date)?;
+    validate_date(&end_date)?;
+    if start_date > end_date {
+        return Err("Start date must not be after end date".to_string());
+    }
+    log::info!("[breaks::cmd] run_break_check {} to {}", start_date, end_date);
+
+    let activity = registry::begin(&app, "Break check")?;
+    let conn = db::open(&app)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let _activity = activity;
+        let rules = check::load_rules(&conn)?;
+        check::check(&conn, &rules, &start_date, &end_date)
+    })
+    .await
+    .map_err(|e| format!("Break check task failed: {}", e))?
+}
+
+/// Check the last week's breaks when enabled and emit `exceptions-updated`
+/// when anything new is queued. Runs for the lifetime of the app.
+pub async fn run_scheduled(app: tauri::AppHandle) {
+    use tauri::Emitter;
+
+    let job = jobs::registry::register(
+        &app,
+        JOB_ID,
+        "Break checks",
+        "Flags shifts without the required breaks in the last week",
+        CHECK_INTERVAL,
+    );
+    let handle = app.clone();
+    jobs::registry::drive_blocking(
+        job,
+        move |_| {
+            let _activity = registry::begin(&handle, "Break check")?;
+            let conn = db::open(&handle)?;
+            let rules = check::load_rules(&conn)?;
+            if !rules.enabled {
+                return Ok(BreakCheckResult::default());
+            }
+            let yesterday = Local::now().date_naive() - chrono::Duration::days(1);
+            let start = yesterday - chrono::Duration::days(CHECK_DAYS - 1);
+            check::check(
+                &conn,
+                &rules,
+                &start.format("%Y-%m-%d").to_string(),
+                &yesterday.format("%Y-%m-%d").to_string(),
+            )
+        },
+        |result| {
+            if result.new_exceptions > 0 {
+                log::info!("[breaks] Break check queued {} new exceptions", result.new_exceptions);
+                if let Err(e) = app.emit("exceptions-updated", &result) {
+                    log::warn!("[breaks] Failed to emit exceptions update: {}", e);
+                }
+            }
+        },
+    )
+    .await;
+}
@@ -0,0 +1,16 @@
+//! Break compliance
+//!
+//! Labour inspections ask for evidence that breaks were taken. Break rules
+//! (`breakRules` setting) require a minimum total break once a shift runs
+//! longer than some number of hours, e.g. 30 minutes past 6 hours. A day's
+//! punches are paired into sessions in order (in, out, in, out, ...);
+//! the shift runs from the first punch to the last and the breaks are the
+//! gaps between sessions. Days with an odd number of punches can't be
+//! paired and are left to the summary's incomplete status. Violations go
+//! to the exception queue, keyed by user and day so a re-run never
+//! re-opens a reviewed entry. The scheduled check covers the last week up
+//! to yesterday, since today's shifts may not have had their break yet.
+
+pub mod check;
+pub mod commands;
+pub mod types;
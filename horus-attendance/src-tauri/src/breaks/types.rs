@@ -0,0 +1,50 @@
+//! Break rule data types for Tauri command serialization
+
+use serde::{Deserialize, Serialize};
+
+/// A minimum break once a shift runs longer than some hours
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BreakRule {
+    /// Applies to shifts longer than this
+    pub after_hours: f64,
+    /// Total break required
+    pub min_break_minutes: i64,
+}
+
+/// Break compliance settings (`breakRules` setting)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BreakRules {
+    /// Run the scheduled check
+    pub enabled: bool,
+    /// The strictest rule a shift falls under applies
+    pub rules: Vec<BreakRule>,
+    /// Gaps shorter than this don't count as a break
+    pub min_counted_break_minutes: i64,
+}
+
+impl Default for BreakRules {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            rules: vec![BreakRule {
+                after_hours: 6.0,
+                min_break_minutes: 30,
+            }],
+            min_counted_break_minutes: 10,
+        }
+    }
+}
+
+/// Result of a break check run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BreakCheckResult {
+    /// User days with paired sessions
+    pub days_checked: i64,
+    /// Violations found, including ones already in the queue
+    pub violations: i64,
+    /// Entries newly added to the queue
+    pub new_exceptions: i64,
+}
@@ -0,0 +1,78 @@
+//! Tauri command handlers for monthly attendance closure.
+
+use super::job;
+use super::mail::Mailer;
+use super::store::{self, CLOSURE_KEY};
+use super::types::*;
+use crate::activity::registry;
+use crate::db;
+
+/// The closure settings
+#[tauri::command]
+pub async fn get_closure_settings(app: tauri::AppHandle) -> Result<ClosureSettings, String> {
+    store::load_settings(&*db::open(&app)?)
+}
+
+/// Save the closure settings. The SMTP password is stored separately with
+/// `set_credential` as `smtp-password`.
+#[tauri::command]
+pub async fn save_closure_settings(app: tauri::AppHandle, settings: ClosureSettings) -> Result<(), String> {
+    if !(1..=28).contains(&settings.day_of_month) {
+        return Err("Closure day must be between 1 and 28".to_string());
+    }
+    if settings.dispute_days == 0 {
+        return Err("Allow at least one day for disputes".to_string());
+    }
+    if settings.enabled && (settings.smtp.host.trim().is_empty() || settings.smtp.from_address.trim().is_empty()) {
+        return Err("Set the mail server and sender address before enabling closure emails".to_string());
+    }
+    let json = serde_json::to_string(&settings).map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    log::info!(
+        "[closure::cmd] Closure emails {} (day {}, {} days to dispute)",
+        if settings.enabled { "on" } else { "off" },
+        settings.day_of_month,
+        settings.dispute_days
+    );
+    db::set_setting(&*db::open(&app)?, CLOSURE_KEY, &json)
+}
+
+/// Send a test message with the saved mail settings
+#[tauri::command]
+pub async fn send_test_email(app: tauri::AppHandle, to: String) -> Result<(), String> {
+    let settings = store::load_settings(&*db::open(&app)?)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        Mailer::new(&settings.smtp)?.send(
+            to.trim(),
+            "Horus Attendance test message",
+            "Mail settings work; attendance closure emails can be sent.",
+            None,
+        )
+    })
+    .await
+    .map_err(|e| format!("Test email task failed: {}", e))?
+}
+
+/// Email employees their attendance for a month now. Employees already
+/// sent it are skipped unless `resend`.
+#[tauri::command]
+pub async fn close_attendance_month(
+    app: tauri::AppHandle,
+    year: i32,
+    month: u32,
+    resend: Option<bool>,
+) -> Result<ClosureRun, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let _activity = registry::begin(&app, "Attendance closure")?;
+        let conn = db::open(&app)?;
+        let settings = store::load_settings(&conn)?;
+        job::close_month(&app, &conn, &settings, year, month, resend.unwrap_or(false))
+    })
+    .await
+    .map_err(|e| format!("Closure task failed: {}", e))?
+}
+
+/// Who was sent a month's closure and what happened
+#[tauri::command]
+pub async fn list_closures(app: tauri::AppHandle, year: i32, month: u32) -> Result<Vec<AttendanceClosure>, String> {
+    store::list(&*db::open(&app)?, year, month)
+}
@@ -0,0 +1,93 @@
+//! Disputes posted by employees through their self-service link
+
+use chrono::{Local, NaiveDate};
+use rusqlite::{params, Connection};
+
+use super::store;
+use super::types::*;
+use crate::api::self_service::Grant;
+use crate::exceptions::types::NewException;
+use crate::periods;
+
+/// Longest dispute message accepted
+const MAX_MESSAGE_CHARS: usize = 2000;
+
+/// Disputes one employee may raise about one month, so a leaked link can't
+/// flood the queue
+const MAX_DISPUTES_PER_MONTH: i64 = 20;
+
+/// Queue a dispute about the month a link grants access to. Only months that
+/// were closed for the employee can be disputed, and only until the deadline.
+pub fn submit(conn: &Connection, grant: &Grant, request: &DisputeRequest) -> Result<DisputeReceipt, String> {
+    let closure = store::get(conn, grant.year, grant.month, &grant.user_id)?
+        .ok_or("This month hasn't been closed for you; there is nothing to dispute yet")?;
+    let today = Local::now().date_naive().format("%Y-%m-%d").to_string();
+    if today > closure.dispute_deadline {
+        return Err(format!("The dispute window closed on {}", closure.dispute_deadline));
+    }
+
+    let message = request.message.trim();
+    if message.is_empty() {
+        return Err("Describe what is wrong with the record".to_string());
+    }
+    if message.chars().count() > MAX_MESSAGE_CHARS {
+        return Err(format!("Keep the message under {} characters", MAX_MESSAGE_CHARS));
+    }
+    if let Some(date) = &request.date {
+        let day = NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| format!("Invalid date: {}", date))?;
+        let (first, last) = periods::calc::month_range(&periods::calc::load_settings(conn)?, grant.year, grant.month)?;
+        if day < first || day > last {
+            return Err(format!("{} is not in {}-{:02}", date, grant.year, grant.month));
+        }
+    }
+
+    let prefix = format!("attendance_dispute:{}:{}-{:02}:", grant.user_id, grant.year, grant.month);
+    let raised: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM attendance_exceptions WHERE dedupe_key LIKE ?1 || '%'",
+            [&prefix],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to count disputes: {}", e))?;
+    if raised >= MAX_DISPUTES_PER_MONTH {
+        return Err("Too many disputes for this month; contact HR directly".to_string());
+    }
+
+    let (device_user_id, name): (Option<String>, String) = conn
+        .query_row(
+            "SELECT device_user_id, display_name FROM users WHERE id = ?1",
+            [&grant.user_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| format!("Failed to look up employee: {}", e))?;
+    let dedupe_key = format!("{}{}", prefix, uuid::Uuid::new_v4());
+    crate::exceptions::store::insert(
+        conn,
+        &[NewException {
+            kind: "attendance_dispute",
+            severity: "warning",
+            device_user_id,
+            date: request.date.clone(),
+            message: format!("{} disputes their {}-{:02} attendance: {}", name, grant.year, grant.month, message),
+            details: serde_json::json!({
+                "userId": grant.user_id,
+                "year": grant.year,
+                "month": grant.month,
+                "text": message,
+            }),
+            dedupe_key: dedupe_key.clone(),
+        }],
+    )?;
+    let exception_id: String = conn
+        .query_row(
+            "SELECT id FROM attendance_exceptions WHERE dedupe_key = ?1",
+            params![dedupe_key],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to read dispute: {}", e))?;
+    log::info!("[closure] Dispute from {} for {}-{:02}", name, grant.year, grant.month);
+    Ok(DisputeReceipt {
+        exception_id,
+        dispute_deadline: closure.dispute_deadline,
+    })
+}
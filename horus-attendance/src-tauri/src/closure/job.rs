@@ -0,0 +1,284 @@
+//! Closing a month: one email per employee with their register page
+
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, Duration, Local, NaiveDate};
+use rusqlite::Connection;
+
+use super::mail::Mailer;
+use super::store::{self, Outcome};
+use super::types::*;
+use crate::api::pairing;
+use crate::api::self_service::{self, Grant};
+use crate::api::server::API_SERVER_KEY;
+use crate::api::types::{ApiServerSettings, SelfServiceLink};
+use crate::db;
+use crate::deliveries;
+use crate::deliveries::types::NewDelivery;
+use crate::jobs;
+use crate::periods;
+use crate::templates::render::render;
+use crate::templates::store as templates;
+use crate::templates::types::{OrganizationSettings, TemplateContext};
+use crate::tls;
+
+/// Names of the templates the email is rendered from
+const SUBJECT_TEMPLATE: &str = "Closure email subject";
+const BODY_TEMPLATE: &str = "Closure email body";
+
+/// Payload kind of closure emails in the delivery log
+pub const DELIVERY_KIND: &str = "attendance_closure";
+
+/// What emailing a month's closure needs, loaded once per run
+struct Closing {
+    mailer: Mailer,
+    organization: OrganizationSettings,
+    subject_template: String,
+    body_template: String,
+    endpoint: String,
+    link_ttl_days: i64,
+    year: i32,
+    month: u32,
+    first: NaiveDate,
+    last: NaiveDate,
+    deadline: String,
+}
+
+impl Closing {
+    fn prepare(conn: &Connection, settings: &ClosureSettings, year: i32, month: u32) -> Result<Self, String> {
+        let (first, last) = periods::calc::month_range(&periods::calc::load_settings(conn)?, year, month)?;
+        let today = Local::now().date_naive();
+        if last >= today {
+            return Err(format!("{}-{:02} can't be closed before it has ended", year, month));
+        }
+        let api: ApiServerSettings = db::get_json_setting(conn, API_SERVER_KEY)?.unwrap_or_default();
+        if !api.enabled {
+            return Err("Enable the API server first; employees open their report and dispute it through it".to_string());
+        }
+        Ok(Self {
+            mailer: Mailer::new(&settings.smtp)?,
+            organization: templates::organization(conn)?,
+            subject_template: templates::get_by_name(conn, SUBJECT_TEMPLATE)?.body,
+            body_template: templates::get_by_name(conn, BODY_TEMPLATE)?.body,
+            endpoint: pairing::endpoint(&api.bind_address, api.listening_port(), tls::store::enabled(conn)?),
+            link_ttl_days: self_service::DEFAULT_TTL_DAYS.max(i64::from(settings.dispute_days) + 1),
+            year,
+            month,
+            first,
+            last,
+            deadline: (today + Duration::days(i64::from(settings.dispute_days))).format("%Y-%m-%d").to_string(),
+        })
+    }
+
+    /// Self-service links for every active employee, or one
+    fn links(&self, app: &tauri::AppHandle, conn: &Connection, user_id: Option<&str>) -> Result<Vec<SelfServiceLink>, String> {
+        self_service::create_links(
+            app,
+            conn,
+            &self.endpoint,
+            self.year,
+            self.month,
+            None,
+            user_id,
+            self.link_ttl_days,
+        )
+    }
+
+    /// Email one employee, logging the delivery. Returns whether it was sent.
+    fn send(&self, conn: &Connection, link: &SelfServiceLink, email: &str) -> Result<bool, String> {
+        let delivery = deliveries::store::begin(
+            conn,
+            &NewDelivery {
+                channel: deliveries::store::CHANNEL_EMAIL,
+                target: email,
+                summary: format!("Attendance closure {}-{:02} for {}", self.year, self.month, link.user_name),
+                payload: serde_json::json!({
+                    "kind": DELIVERY_KIND,
+                    "year": self.year,
+                    "month": self.month,
+                    "userId": link.user_id,
+                }),
+            },
+        )?;
+        let sent = self.compose_and_send(conn, link, email);
+        deliveries::store::finish(conn, delivery, &sent)?;
+        self.record(conn, link, email, &sent)?;
+        Ok(sent.is_ok())
+    }
+
+    fn compose_and_send(&self, conn: &Connection, link: &SelfServiceLink, email: &str) -> Result<(), String> {
+        let grant = Grant {
+            user_id: link.user_id.clone(),
+            year: self.year,
+            month: self.month,
+        };
+        let report = self_service::report(conn, &grant)?;
+        let ctx = TemplateContext {
+            period_start: Some(self.first.format("%Y-%m-%d").to_string()),
+            period_end: Some(self.last.format("%Y-%m-%d").to_string()),
+            totals: report.totals.iter().map(|(k, v)| (k.clone(), (*v).into())).collect(),
+            extra: BTreeMap::from([
+                ("employee".to_string(), link.user_name.clone().into()),
+                ("month".to_string(), report.period.clone().into()),
+                ("link".to_string(), link.url.clone().into()),
+                ("dispute_deadline".to_string(), self.deadline.clone().into()),
+            ]),
+        };
+        let subject = render(&self.subject_template, &self.organization, &ctx)?;
+        let body = render(&self.body_template, &self.organization, &ctx)?;
+        let pdf = self_service::report_pdf(conn, &grant)?;
+        let filename = format!("attendance_{}-{:02}.pdf", self.year, self.month);
+        self.mailer.send(email, subject.trim(), &body, Some((&filename, pdf)))
+    }
+
+    fn record(&self, conn: &Connection, link: &SelfServiceLink, email: &str, sent: &Result<(), String>) -> Result<(), String> {
+        let error = sent.as_ref().err();
+        if let Some(e) = error {
+            log::warn!("[closure] Failed to email {}: {}", link.user_name, e);
+        }
+        store::record(
+            conn,
+            self.year,
+            self.month,
+            &Outcome {
+                user_id: &link.user_id,
+                email: Some(email),
+                status: if error.is_some() { "failed" } else { "sent" },
+                error: error.map(String::as_str),
+                dispute_deadline: &self.deadline,
+            },
+        )
+    }
+}
+
+/// Email every active employee their attendance for a month, skipping those
+/// already sent it unless `resend`. Blocks on the keychain and the SMTP
+/// server.
+pub fn close_month(
+    app: &tauri::AppHandle,
+    conn: &Connection,
+    settings: &ClosureSettings,
+    year: i32,
+    month: u32,
+    resend: bool,
+) -> Result<ClosureRun, String> {
+    let closing = Closing::prepare(conn, settings, year, month)?;
+    let mut run = ClosureRun {
+        year,
+        month,
+        dispute_deadline: closing.deadline.clone(),
+        ..Default::default()
+    };
+    for link in &closing.links(app, conn, None)? {
+        let Some(email) = link.email.as_deref() else {
+            run.no_email += 1;
+            store::record(
+                conn,
+                year,
+                month,
+                &Outcome {
+                    user_id: &link.user_id,
+                    email: None,
+                    status: "no_email",
+                    error: None,
+                    dispute_deadline: &closing.deadline,
+                },
+            )?;
+            continue;
+        };
+        if !resend && store::get(conn, year, month, &link.user_id)?.is_some_and(|c| c.status == "sent") {
+            run.already_sent += 1;
+            continue;
+        }
+        if closing.send(conn, link, email)? {
+            run.sent += 1;
+        } else {
+            run.failed += 1;
+        }
+    }
+    log::info!(
+        "[closure] Closed {}-{:02}: {} sent, {} failed, {} without email, {} already sent",
+        year,
+        month,
+        run.sent,
+        run.failed,
+        run.no_email,
+        run.already_sent
+    );
+    Ok(run)
+}
+
+/// Send one employee's closure email again for a delivery being retried,
+/// with a fresh link and dispute deadline. The attempt is not logged as a
+/// new delivery.
+pub fn redeliver(app: &tauri::AppHandle, conn: &Connection, payload: &serde_json::Value) -> Result<(), String> {
+    let year = payload["year"].as_i64().ok_or("Delivery has no year")? as i32;
+    let month = payload["month"].as_u64().ok_or("Delivery has no month")? as u32;
+    let user_id = payload["userId"].as_str().ok_or("Delivery has no user")?;
+    let closing = Closing::prepare(conn, &store::load_settings(conn)?, year, month)?;
+    let link = closing
+        .links(app, conn, Some(user_id))?
+        .pop()
+        .ok_or("This employee is no longer active")?;
+    let email = link.email.clone().ok_or("This employee no longer has an email address")?;
+    let sent = closing.compose_and_send(conn, &link, &email);
+    closing.record(conn, &link, &email, &sent)?;
+    sent
+}
+
+/// How often the scheduled job checks whether a month is due
+const CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(3600);
+
+/// ID of the scheduled closure in the jobs registry
+pub const JOB_ID: &str = "attendance_closure";
+
+/// Close the previous month once the configured day has come, and emit
+/// `attendance-closed`. A month that couldn't be closed (mail server down,
+/// API server off) is tried again on the next check; employees whose email
+/// failed among others sent are left for a manual run. Runs for the lifetime
+/// of the app.
+pub async fn run_scheduled(app: tauri::AppHandle) {
+    use tauri::Emitter;
+
+    let job = jobs::registry::register(
+        &app,
+        JOB_ID,
+        "Attendance closure",
+        "Emails last month's attendance to employees on the closure day",
+        CHECK_INTERVAL,
+    );
+    let handle = app.clone();
+    jobs::registry::drive_blocking(
+        job,
+        move |_| {
+            let _activity = crate::activity::registry::begin(&handle, "Attendance closure")?;
+            let conn = db::open(&handle)?;
+            let settings = store::load_settings(&conn)?;
+            let today = Local::now().date_naive();
+            if !settings.enabled || today.day() < settings.day_of_month {
+                return Ok(None);
+            }
+            let (year, month) = periods::calc::last_ended_month(&periods::calc::load_settings(&conn)?, today);
+            let due = format!("{}-{:02}", year, month);
+            let last: Option<String> = db::get_json_setting(&conn, store::LAST_CLOSED_KEY)?;
+            if last.as_deref() >= Some(due.as_str()) {
+                return Ok(None);
+            }
+            let run = close_month(&handle, &conn, &settings, year, month, false)?;
+            if run.sent == 0 && run.failed > 0 {
+                return Err(format!("Every email for {} failed", due));
+            }
+            let json = serde_json::to_string(&due).map_err(|e| format!("Failed to serialize month: {}", e))?;
+            db::set_setting(&conn, store::LAST_CLOSED_KEY, &json)?;
+            Ok(Some(run))
+        },
+        |run| {
+            if let Some(run) = run {
+                if let Err(e) = app.emit("attendance-closed", &run) {
+                    log::warn!("[closure] Failed to emit attendance-closed: {}", e);
+                }
+            }
+        },
+    )
+    .await;
+}
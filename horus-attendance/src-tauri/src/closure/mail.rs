@@ -0,0 +1,79 @@
+//! Sending mail over SMTP
+
+use std::time::Duration;
+
+use lettre::message::header::ContentType;
+use lettre::message::{Attachment, Mailbox, MultiPart, SinglePart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+
+use super::types::{SmtpSecurity, SmtpSettings};
+use crate::secrets::keychain;
+
+/// Keychain credential name of the SMTP password
+pub const SMTP_PASSWORD_NAME: &str = "smtp-password";
+
+const SMTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A connection setup for one SMTP server and sender
+pub struct Mailer {
+    transport: SmtpTransport,
+    from: Mailbox,
+}
+
+impl Mailer {
+    /// Set up sending with `settings`, reading the password from the
+    /// keychain when there is a username. Blocks on the keychain.
+    pub fn new(settings: &SmtpSettings) -> Result<Self, String> {
+        if settings.host.trim().is_empty() {
+            return Err("No SMTP server is configured".to_string());
+        }
+        let from: Mailbox = settings
+            .from_address
+            .parse()
+            .map_err(|e| format!("Invalid sender address '{}': {}", settings.from_address, e))?;
+        let host = settings.host.trim();
+        let builder = match settings.security {
+            SmtpSecurity::Starttls => SmtpTransport::starttls_relay(host),
+            SmtpSecurity::Tls => SmtpTransport::relay(host),
+            SmtpSecurity::None => Ok(SmtpTransport::builder_dangerous(host)),
+        }
+        .map_err(|e| format!("Invalid SMTP server '{}': {}", host, e))?
+        .port(settings.port)
+        .timeout(Some(SMTP_TIMEOUT));
+
+        let builder = if settings.username.trim().is_empty() {
+            builder
+        } else {
+            let password = keychain::get(&keychain::credential_account(SMTP_PASSWORD_NAME))?
+                .ok_or("No SMTP password is stored; set it before sending")?;
+            builder.credentials(Credentials::new(settings.username.trim().to_string(), password))
+        };
+        Ok(Self {
+            transport: builder.build(),
+            from,
+        })
+    }
+
+    /// Send a plain-text message, optionally with a PDF attached
+    pub fn send(&self, to: &str, subject: &str, body: &str, pdf: Option<(&str, Vec<u8>)>) -> Result<(), String> {
+        let to: Mailbox = to.parse().map_err(|e| format!("Invalid address '{}': {}", to, e))?;
+        let builder = Message::builder().from(self.from.clone()).to(to).subject(subject);
+        let message = match pdf {
+            Some((filename, bytes)) => builder.multipart(
+                MultiPart::mixed()
+                    .singlepart(SinglePart::plain(body.to_string()))
+                    .singlepart(
+                        Attachment::new(filename.to_string())
+                            .body(bytes, ContentType::parse("application/pdf").expect("PDF content type is valid")),
+                    ),
+            ),
+            None => builder.body(body.to_string()),
+        }
+        .map_err(|e| format!("Failed to build email: {}", e))?;
+        self.transport
+            .send(&message)
+            .map(|_| ())
+            .map_err(|e| format!("Failed to send email: {}", e))
+    }
+}
@@ -0,0 +1,20 @@
+//! Monthly attendance closure
+//!
+//! Once a month each employee with an email address is sent their own
+//! register page as a PDF, with a self-service link and a deadline for
+//! disputing it. Disputes posted through the link before the deadline land
+//! in the exception queue, so they are dealt with before payroll runs.
+//!
+//! The job runs on the configured day for the last month whose reporting
+//! period has ended (see `periods`) when enabled under the `closureEmails`
+//! setting, and can be run by hand for any month.
+//! Mail goes out over SMTP; the password is kept in the keychain as the
+//! `smtp-password` credential. Links point at the embedded API server, which
+//! has to be enabled for employees to open them.
+
+pub mod commands;
+pub mod disputes;
+pub mod job;
+pub mod mail;
+pub mod store;
+pub mod types;
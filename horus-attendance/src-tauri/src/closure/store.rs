@@ -0,0 +1,95 @@
+//! Storage for closure settings and per-employee closure records
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use super::types::*;
+use crate::db;
+
+/// Settings key for closure emails
+pub const CLOSURE_KEY: &str = "closureEmails";
+
+/// Settings key holding the last month closed automatically (YYYY-MM)
+pub const LAST_CLOSED_KEY: &str = "closureLastMonth";
+
+/// Load the settings, falling back to defaults (off)
+pub fn load_settings(conn: &Connection) -> Result<ClosureSettings, String> {
+    Ok(db::get_json_setting(conn, CLOSURE_KEY)?.unwrap_or_default())
+}
+
+const SELECT: &str = "SELECT c.id, c.year, c.month, c.user_id, u.display_name, c.email, c.status, c.error,
+                             c.dispute_deadline, c.sent_at
+                      FROM attendance_closures c LEFT JOIN users u ON u.id = c.user_id";
+
+fn map_row(row: &rusqlite::Row) -> rusqlite::Result<AttendanceClosure> {
+    Ok(AttendanceClosure {
+        id: row.get(0)?,
+        year: row.get(1)?,
+        month: row.get(2)?,
+        user_id: row.get(3)?,
+        user_name: row.get(4)?,
+        email: row.get(5)?,
+        status: row.get(6)?,
+        error: row.get(7)?,
+        dispute_deadline: row.get(8)?,
+        sent_at: row.get(9)?,
+    })
+}
+
+/// Closure records of a month, by employee name
+pub fn list(conn: &Connection, year: i32, month: u32) -> Result<Vec<AttendanceClosure>, String> {
+    let mut stmt = conn
+        .prepare(&format!("{} WHERE c.year = ?1 AND c.month = ?2 ORDER BY u.display_name", SELECT))
+        .map_err(|e| format!("Failed to query closures: {}", e))?;
+    let rows = stmt
+        .query_map(params![year, month], map_row)
+        .map_err(|e| format!("Failed to query closures: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read closures: {}", e))
+}
+
+/// One employee's closure record for a month
+pub fn get(conn: &Connection, year: i32, month: u32, user_id: &str) -> Result<Option<AttendanceClosure>, String> {
+    conn.query_row(
+        &format!("{} WHERE c.year = ?1 AND c.month = ?2 AND c.user_id = ?3", SELECT),
+        params![year, month, user_id],
+        map_row,
+    )
+    .optional()
+    .map_err(|e| format!("Failed to read closure: {}", e))
+}
+
+/// What happened when closing a month for one employee
+pub struct Outcome<'a> {
+    pub user_id: &'a str,
+    pub email: Option<&'a str>,
+    /// sent, failed or no_email
+    pub status: &'a str,
+    pub error: Option<&'a str>,
+    pub dispute_deadline: &'a str,
+}
+
+/// Record an outcome, replacing the employee's earlier one for the month
+pub fn record(conn: &Connection, year: i32, month: u32, outcome: &Outcome) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO attendance_closures (id, year, month, user_id, email, status, error, dispute_deadline)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+         ON CONFLICT(year, month, user_id) DO UPDATE SET
+             email = excluded.email,
+             status = excluded.status,
+             error = excluded.error,
+             dispute_deadline = excluded.dispute_deadline,
+             sent_at = datetime('now')",
+        params![
+            uuid::Uuid::new_v4().to_string(),
+            year,
+            month,
+            outcome.user_id,
+            outcome.email,
+            outcome.status,
+            outcome.error,
+            outcome.dispute_deadline,
+        ],
+    )
+    .map_err(|e| format!("Failed to record closure: {}", e))?;
+    Ok(())
+}
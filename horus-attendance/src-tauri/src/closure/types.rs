@@ -0,0 +1,114 @@
+//! Types for monthly closure emails and disputes
+
+use serde::{Deserialize, Serialize};
+
+/// How to reach the SMTP server
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    /// Plain connection upgraded with STARTTLS (usually port 587)
+    #[default]
+    Starttls,
+    /// TLS from the start (usually port 465)
+    Tls,
+    /// Unencrypted, for a relay on the local network only
+    None,
+}
+
+/// Outgoing mail server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SmtpSettings {
+    pub host: String,
+    pub port: u16,
+    pub security: SmtpSecurity,
+    /// Left empty for servers that don't need a login
+    pub username: String,
+    pub from_address: String,
+}
+
+impl Default for SmtpSettings {
+    fn default() -> Self {
+        Self {
+            host: String::new(),
+            port: 587,
+            security: SmtpSecurity::Starttls,
+            username: String::new(),
+            from_address: String::new(),
+        }
+    }
+}
+
+/// Closure email settings (`closureEmails` setting)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ClosureSettings {
+    /// Send the previous month's emails automatically
+    pub enabled: bool,
+    /// Day of the month the previous month is closed on (1-28)
+    pub day_of_month: u32,
+    /// Days after sending that employees may dispute
+    pub dispute_days: u32,
+    pub smtp: SmtpSettings,
+}
+
+impl Default for ClosureSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            day_of_month: 3,
+            dispute_days: 5,
+            smtp: SmtpSettings::default(),
+        }
+    }
+}
+
+/// One employee's closure email for a month
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttendanceClosure {
+    pub id: String,
+    pub year: i32,
+    pub month: u32,
+    pub user_id: String,
+    pub user_name: Option<String>,
+    pub email: Option<String>,
+    /// sent, failed or no_email
+    pub status: String,
+    pub error: Option<String>,
+    /// Last day a dispute is accepted (YYYY-MM-DD)
+    pub dispute_deadline: String,
+    pub sent_at: String,
+}
+
+/// Outcome of closing a month
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClosureRun {
+    pub year: i32,
+    pub month: u32,
+    pub sent: u32,
+    pub failed: u32,
+    /// Active employees without an email address
+    pub no_email: u32,
+    /// Already sent in an earlier run
+    pub already_sent: u32,
+    pub dispute_deadline: String,
+}
+
+/// Body of POST /reports/disputes
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DisputeRequest {
+    /// The day in question (YYYY-MM-DD), if it is about one day
+    pub date: Option<String>,
+    pub message: String,
+}
+
+/// Response of POST /reports/disputes
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DisputeReceipt {
+    pub exception_id: String,
+    pub dispute_deadline: String,
+}
@@ -0,0 +1,87 @@
+//! Tauri command handlers for punch corrections and locked periods.
+
+use super::locks;
+use super::store;
+use super::types::*;
+use crate::db;
+use crate::periods;
+use crate::summary::commands::validate_date;
+
+/// Payroll periods that can no longer change
+#[tauri::command]
+pub async fn list_locked_periods(app: tauri::AppHandle) -> Result<Vec<LockedPeriod>, String> {
+    locks::list(&*db::open(&app)?)
+}
+
+/// Lock the working days from `start_date` to `end_date` (inclusive): their
+/// punches can't be corrected or deleted and their summaries stay as they are
+#[tauri::command]
+pub async fn lock_period(
+    app: tauri::AppHandle,
+    start_date: String,
+    end_date: String,
+    label: Option<String>,
+) -> Result<LockedPeriod, String> {
+    validate_date(&start_date)?;
+    validate_date(&end_date)?;
+    if start_date > end_date {
+        return Err("Start date must not be after end date".to_string());
+    }
+    log::info!("[corrections::cmd] lock_period {} to {}", start_date, end_date);
+    locks::lock(&*db::open(&app)?, &start_date, &end_date, label.as_deref().unwrap_or("").trim())
+}
+
+/// Lock the reporting period (see [`crate::periods`]) that `date` falls in,
+/// labelled with the period's name unless `label` is given
+#[tauri::command]
+pub async fn lock_pay_period(
+    app: tauri::AppHandle,
+    date: String,
+    label: Option<String>,
+) -> Result<LockedPeriod, String> {
+    let conn = db::open(&app)?;
+    let period = periods::calc::containing(&periods::calc::load_settings(&conn)?, periods::calc::parse_date(&date)?)?;
+    let label = label
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+        .unwrap_or(period.label);
+    log::info!("[corrections::cmd] lock_pay_period {} to {}", period.start_date, period.end_date);
+    locks::lock(&conn, &period.start_date, &period.end_date, &label)
+}
+
+#[tauri::command]
+pub async fn unlock_period(app: tauri::AppHandle, id: i64) -> Result<bool, String> {
+    log::info!("[corrections::cmd] unlock_period {}", id);
+    locks::unlock(&*db::open(&app)?, id)
+}
+
+/// Add, move or void a punch. Device punches stay as read; the change is
+/// recorded as a correction and the affected days are recomputed.
+#[tauri::command]
+pub async fn correct_punch(app: tauri::AppHandle, correction: PunchCorrectionInput) -> Result<PunchCorrection, String> {
+    let mut conn = db::open(&app)?;
+    tauri::async_runtime::spawn_blocking(move || store::apply(&mut conn, &correction))
+        .await
+        .map_err(|e| format!("Correction task failed: {}", e))?
+}
+
+/// Corrections touching punches between two dates (inclusive)
+#[tauri::command]
+pub async fn list_punch_corrections(
+    app: tauri::AppHandle,
+    start_date: String,
+    end_date: String,
+) -> Result<Vec<PunchCorrection>, String> {
+    validate_date(&start_date)?;
+    validate_date(&end_date)?;
+    store::list(&*db::open(&app)?, &start_date, &end_date)
+}
+
+/// Undo a correction and recompute the days it touched
+#[tauri::command]
+pub async fn revert_punch_correction(app: tauri::AppHandle, id: String) -> Result<bool, String> {
+    let mut conn = db::open(&app)?;
+    tauri::async_runtime::spawn_blocking(move || store::revert(&mut conn, &id))
+        .await
+        .map_err(|e| format!("Revert task failed: {}", e))?
+}
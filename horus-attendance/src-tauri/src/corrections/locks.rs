@@ -0,0 +1,75 @@
+//! Locked payroll periods
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use super::types::LockedPeriod;
+
+fn map_row(row: &rusqlite::Row) -> rusqlite::Result<LockedPeriod> {
+    Ok(LockedPeriod {
+        id: row.get(0)?,
+        start_date: row.get(1)?,
+        end_date: row.get(2)?,
+        label: row.get(3)?,
+        locked_at: row.get(4)?,
+    })
+}
+
+/// Every locked period, latest first
+pub fn list(conn: &Connection) -> Result<Vec<LockedPeriod>, String> {
+    let mut stmt = conn
+        .prepare("SELECT id, start_date, end_date, label, locked_at FROM locked_periods ORDER BY start_date DESC")
+        .map_err(|e| format!("Failed to query locked periods: {}", e))?;
+    let rows = stmt
+        .query_map([], map_row)
+        .map_err(|e| format!("Failed to query locked periods: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read locked periods: {}", e))
+}
+
+pub fn lock(conn: &Connection, start_date: &str, end_date: &str, label: &str) -> Result<LockedPeriod, String> {
+    conn.execute(
+        "INSERT INTO locked_periods (start_date, end_date, label) VALUES (?1, ?2, ?3)",
+        params![start_date, end_date, label],
+    )
+    .map_err(|e| format!("Failed to lock period: {}", e))?;
+    conn.query_row(
+        "SELECT id, start_date, end_date, label, locked_at FROM locked_periods WHERE id = ?1",
+        [conn.last_insert_rowid()],
+        map_row,
+    )
+    .map_err(|e| format!("Failed to read locked period: {}", e))
+}
+
+pub fn unlock(conn: &Connection, id: i64) -> Result<bool, String> {
+    conn.execute("DELETE FROM locked_periods WHERE id = ?1", [id])
+        .map(|n| n > 0)
+        .map_err(|e| format!("Failed to unlock period: {}", e))
+}
+
+/// The locked period whose working days include `timestamp`, if any
+pub fn covering(conn: &Connection, timestamp: &str) -> Result<Option<LockedPeriod>, String> {
+    conn.query_row(
+        "SELECT p.id, p.start_date, p.end_date, p.label, p.locked_at
+         FROM locked_punch_bounds b JOIN locked_periods p ON p.id = b.id
+         WHERE ?1 >= b.start_ts AND ?1 < b.end_ts
+         LIMIT 1",
+        [timestamp],
+        map_row,
+    )
+    .optional()
+    .map_err(|e| format!("Failed to check locked periods: {}", e))
+}
+
+/// An error naming the locked period that includes `timestamp`, if any
+pub fn ensure_unlocked(conn: &Connection, timestamp: &str) -> Result<(), String> {
+    match covering(conn, timestamp)? {
+        Some(period) => Err(format!(
+            "{} falls in the locked period {} to {}{}",
+            &timestamp[..16.min(timestamp.len())],
+            period.start_date,
+            period.end_date,
+            if period.label.is_empty() { String::new() } else { format!(" ({})", period.label) }
+        )),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,21 @@
+//! Punch corrections and locked payroll periods
+//!
+//! The frontend writes through the SQL plugin, so the rules are enforced by
+//! triggers (migration 25) rather than by whoever calls:
+//!
+//! - Punches read from a device are never edited. Voiding or moving one
+//!   records a correction pointing at it, and the moved or added punch is
+//!   stored under the `correction` pseudo-device; summaries leave voided
+//!   punches out.
+//! - Nothing may change the punches of a locked period, or delete its
+//!   summaries. Syncs and recomputes leave a locked period's summaries as
+//!   they are; device punches synced late are still stored.
+//!
+//! The commands here check the same rules first so callers get a clear error.
+//! Data migrations and restores get past the triggers with
+//! [`crate::db::maintenance::bypass`], which drops them within its transaction.
+
+pub mod commands;
+pub mod locks;
+pub mod store;
+pub mod types;
@@ -0,0 +1,269 @@
+//! Recording and reverting punch corrections
+
+use chrono::{Duration, Local};
+use rusqlite::{params, Connection, OptionalExtension};
+
+use super::locks::ensure_unlocked;
+use super::types::*;
+use crate::db::logs::{self, NewPunch};
+use crate::db::users;
+use crate::quarantine::validate::{parse_timestamp, TIMESTAMP_FORMAT};
+use crate::summary::engine::{self, UserMatcher};
+use crate::summary::rules;
+use crate::summary::types::ChangeSource;
+
+/// devices.id that added and moved punches are recorded under (created by migration 25)
+pub const CORRECTION_DEVICE_ID: &str = "correction";
+
+/// attendance_logs_raw.source for added and moved punches
+pub const SOURCE_CORRECTION: &str = "correction";
+
+pub const ACTION_ADD: &str = "add";
+pub const ACTION_MOVE: &str = "move";
+pub const ACTION_VOID: &str = "void";
+
+/// How far ahead of this PC's clock a corrected punch may be
+const MAX_FUTURE_MINUTES: i64 = 5;
+
+const SELECT: &str = "SELECT id, action, user_id, device_user_id, original_log_id, original_timestamp,
+                             corrected_log_id, corrected_timestamp, reason, created_at
+                      FROM punch_corrections";
+
+fn map_row(row: &rusqlite::Row) -> rusqlite::Result<PunchCorrection> {
+    Ok(PunchCorrection {
+        id: row.get(0)?,
+        action: row.get(1)?,
+        user_id: row.get(2)?,
+        device_user_id: row.get(3)?,
+        original_log_id: row.get(4)?,
+        original_timestamp: row.get(5)?,
+        corrected_log_id: row.get(6)?,
+        corrected_timestamp: row.get(7)?,
+        reason: row.get(8)?,
+        created_at: row.get(9)?,
+    })
+}
+
+pub fn get(conn: &Connection, id: &str) -> Result<Option<PunchCorrection>, String> {
+    conn.query_row(&format!("{} WHERE id = ?1", SELECT), [id], map_row)
+        .optional()
+        .map_err(|e| format!("Failed to read correction: {}", e))
+}
+
+/// Corrections touching punches on the days from `start_date` to `end_date`
+/// (inclusive), newest first
+pub fn list(conn: &Connection, start_date: &str, end_date: &str) -> Result<Vec<PunchCorrection>, String> {
+    let (start, end) = logs::day_bounds(start_date, end_date);
+    let mut stmt = conn
+        .prepare(&format!(
+            "{} WHERE (original_timestamp >= ?1 AND original_timestamp < ?2)
+                OR (corrected_timestamp >= ?1 AND corrected_timestamp < ?2)
+             ORDER BY created_at DESC",
+            SELECT
+        ))
+        .map_err(|e| format!("Failed to query corrections: {}", e))?;
+    let rows = stmt
+        .query_map(params![start, end], map_row)
+        .map_err(|e| format!("Failed to query corrections: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read corrections: {}", e))
+}
+
+/// A stored punch being voided or moved
+struct Original {
+    device_user_id: String,
+    timestamp: String,
+    punch_type: Option<i64>,
+    source: Option<String>,
+}
+
+fn load_original(conn: &Connection, log_id: &str) -> Result<Original, String> {
+    conn.query_row(
+        "SELECT device_user_id, timestamp, punch_type, source FROM attendance_logs_raw WHERE id = ?1",
+        [log_id],
+        |row| {
+            Ok(Original {
+                device_user_id: row.get(0)?,
+                timestamp: row.get(1)?,
+                punch_type: row.get(2)?,
+                source: row.get(3)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(|e| format!("Failed to read punch: {}", e))?
+    .ok_or_else(|| format!("Punch not found: {}", log_id))
+}
+
+/// The corrected time in the stored format, refusing future times
+fn corrected_timestamp(value: Option<&str>) -> Result<String, String> {
+    let value = value.ok_or_else(|| "A time is needed to add or move a punch".to_string())?;
+    let time = parse_timestamp(value)
+        .or_else(|| parse_timestamp(&format!("{}:00", value)))
+        .ok_or_else(|| format!("Invalid time (expected YYYY-MM-DDTHH:MM): {}", value))?;
+    if time > Local::now().naive_local() + Duration::minutes(MAX_FUTURE_MINUTES) {
+        return Err("A punch can't be in the future".to_string());
+    }
+    Ok(time.format(TIMESTAMP_FORMAT).to_string())
+}
+
+/// Recompute the working days of `timestamps`
+fn recompute(conn: &mut Connection, timestamps: &[&str]) -> Result<(), String> {
+    let cutoff_hour = engine::day_cutoff_hour(conn)?;
+    let dates: Vec<String> = timestamps.iter().map(|t| rules::working_date(t, cutoff_hour)).collect();
+    let (Some(start), Some(end)) = (dates.iter().min(), dates.iter().max()) else {
+        return Ok(());
+    };
+    let source = ChangeSource {
+        reason: "punch_correction",
+        actor: None,
+    };
+    engine::recompute(conn, start, end, &source).map(|_| ())
+}
+
+/// Record `input`, then recompute the days it touches
+pub fn apply(conn: &mut Connection, input: &PunchCorrectionInput) -> Result<PunchCorrection, String> {
+    if input.reason.trim().is_empty() {
+        return Err("A reason is needed for every correction".to_string());
+    }
+    let action = input.action.as_str();
+    let original = match action {
+        ACTION_MOVE | ACTION_VOID => {
+            let log_id = input
+                .log_id
+                .as_deref()
+                .ok_or_else(|| format!("A punch is needed to {}", action))?;
+            let original = load_original(conn, log_id)?;
+            if original.source.as_deref() == Some(SOURCE_CORRECTION) {
+                return Err("This punch was added by a correction; revert that correction instead".to_string());
+            }
+            let corrected: bool = conn
+                .query_row(
+                    "SELECT EXISTS(SELECT 1 FROM punch_corrections WHERE original_log_id = ?1)",
+                    [log_id],
+                    |row| row.get(0),
+                )
+                .map_err(|e| format!("Failed to check corrections: {}", e))?;
+            if corrected {
+                return Err("This punch was already corrected".to_string());
+            }
+            ensure_unlocked(conn, &original.timestamp)?;
+            Some(original)
+        }
+        ACTION_ADD => None,
+        other => return Err(format!("Unknown correction: {}", other)),
+    };
+    let (user_id, device_user_id) = match &original {
+        Some(original) => (
+            UserMatcher::load(conn)?.resolve(&original.device_user_id).map(str::to_string),
+            original.device_user_id.clone(),
+        ),
+        None => {
+            let user_id = input.user_id.as_deref().ok_or_else(|| "A user is needed to add a punch".to_string())?;
+            let user = users::get(conn, user_id)?.ok_or_else(|| format!("User not found: {}", user_id))?;
+            let device_user_id = user
+                .linked_device_user_id()
+                .ok_or_else(|| format!("{} has no device user ID to record punches under", user.display_name))?
+                .to_string();
+            (Some(user.id), device_user_id)
+        }
+    };
+    let corrected = match action {
+        ACTION_VOID => None,
+        _ => {
+            let timestamp = corrected_timestamp(input.timestamp.as_deref())?;
+            ensure_unlocked(conn, &timestamp)?;
+            Some(timestamp)
+        }
+    };
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let corrected_log_id = match &corrected {
+        Some(timestamp) => {
+            let (log_id, inserted) = logs::insert(
+                &tx,
+                &NewPunch {
+                    device_id: CORRECTION_DEVICE_ID,
+                    device_user_id: &device_user_id,
+                    timestamp,
+                    punch_type: original.as_ref().and_then(|o| o.punch_type),
+                    raw_payload: None,
+                    source: SOURCE_CORRECTION,
+                    latitude: None,
+                    longitude: None,
+                    note: Some(input.reason.trim()),
+                },
+            )?;
+            if !inserted {
+                return Err("A corrected punch already exists at that time".to_string());
+            }
+            Some(log_id)
+        }
+        None => None,
+    };
+    tx.execute(
+        "INSERT INTO punch_corrections
+             (id, action, user_id, device_user_id, original_log_id, original_timestamp,
+              corrected_log_id, corrected_timestamp, reason)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            id,
+            action,
+            user_id,
+            device_user_id,
+            input.log_id.as_deref().filter(|_| original.is_some()),
+            original.as_ref().map(|o| o.timestamp.as_str()),
+            corrected_log_id,
+            corrected,
+            input.reason.trim(),
+        ],
+    )
+    .map_err(|e| format!("Failed to record correction: {}", e))?;
+    tx.commit().map_err(|e| format!("Failed to commit correction: {}", e))?;
+
+    let touched: Vec<&str> = original
+        .as_ref()
+        .map(|o| o.timestamp.as_str())
+        .into_iter()
+        .chain(corrected.as_deref())
+        .collect();
+    recompute(conn, &touched)?;
+    log::info!("[corrections] {} punch of {} ({})", action, device_user_id, input.reason.trim());
+    get(conn, &id)?.ok_or_else(|| "Correction disappeared".to_string())
+}
+
+/// Undo a correction: the original punch counts again and the added one is
+/// removed. Returns false if there was no such correction.
+pub fn revert(conn: &mut Connection, id: &str) -> Result<bool, String> {
+    let Some(correction) = get(conn, id)? else {
+        return Ok(false);
+    };
+    let touched: Vec<&str> = correction
+        .original_timestamp
+        .as_deref()
+        .into_iter()
+        .chain(correction.corrected_timestamp.as_deref())
+        .collect();
+    for timestamp in &touched {
+        ensure_unlocked(conn, timestamp)?;
+    }
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    tx.execute("DELETE FROM punch_corrections WHERE id = ?1", [id])
+        .map_err(|e| format!("Failed to revert correction: {}", e))?;
+    if let Some(log_id) = &correction.corrected_log_id {
+        tx.execute(
+            "DELETE FROM attendance_logs_raw WHERE id = ?1 AND source = ?2",
+            params![log_id, SOURCE_CORRECTION],
+        )
+        .map_err(|e| format!("Failed to remove corrected punch: {}", e))?;
+    }
+    tx.commit().map_err(|e| format!("Failed to commit: {}", e))?;
+    recompute(conn, &touched)?;
+    log::info!("[corrections] Reverted {} of {}", correction.action, correction.device_user_id);
+    Ok(true)
+}
@@ -0,0 +1,50 @@
+//! Correction and lock data types for Tauri command serialization
+
+use serde::{Deserialize, Serialize};
+
+/// A locked payroll period (one locked_periods row)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LockedPeriod {
+    pub id: i64,
+    pub start_date: String,
+    pub end_date: String,
+    pub label: String,
+    pub locked_at: String,
+}
+
+/// A change to make to the punches
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PunchCorrectionInput {
+    /// "add", "move" or "void"
+    pub action: String,
+    /// The punch to move or void
+    #[serde(default)]
+    pub log_id: Option<String>,
+    /// Whose punch to add
+    #[serde(default)]
+    pub user_id: Option<String>,
+    /// Local time of the added or moved punch (YYYY-MM-DDTHH:MM[:SS])
+    #[serde(default)]
+    pub timestamp: Option<String>,
+    pub reason: String,
+}
+
+/// A recorded correction (one punch_corrections row)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PunchCorrection {
+    pub id: String,
+    pub action: String,
+    pub user_id: Option<String>,
+    pub device_user_id: String,
+    /// The punch voided or moved
+    pub original_log_id: Option<String>,
+    pub original_timestamp: Option<String>,
+    /// The punch added, or where the original was moved to
+    pub corrected_log_id: Option<String>,
+    pub corrected_timestamp: Option<String>,
+    pub reason: String,
+    pub created_at: String,
+}
@@ -0,0 +1,78 @@
+//! Tauri command handlers for cost centers.
+
+use super::store;
+use super::types::*;
+use crate::db;
+use crate::summary::commands::validate_date;
+
+/// Every cost center
+#[tauri::command]
+pub async fn list_cost_centers(app: tauri::AppHandle) -> Result<Vec<CostCenter>, String> {
+    store::list(&*db::open(&app)?)
+}
+
+/// Create (empty ID) or update a cost center
+#[tauri::command]
+pub async fn save_cost_center(app: tauri::AppHandle, cost_center: CostCenter) -> Result<CostCenter, String> {
+    let saved = store::save(&*db::open(&app)?, cost_center)?;
+    log::info!("[cost_centers::cmd] Saved cost center {}", saved.code);
+    Ok(saved)
+}
+
+/// Delete a cost center and its assignments. Deactivate it instead to keep
+/// past days grouped under it.
+#[tauri::command]
+pub async fn delete_cost_center(app: tauri::AppHandle, id: String) -> Result<bool, String> {
+    store::delete(&*db::open(&app)?, &id)
+}
+
+/// Assignments, optionally only of one cost center, user or department
+#[tauri::command]
+pub async fn list_cost_center_assignments(
+    app: tauri::AppHandle,
+    cost_center_id: Option<String>,
+    user_id: Option<String>,
+    department_id: Option<String>,
+) -> Result<Vec<CostCenterAssignment>, String> {
+    store::list_assignments(
+        &*db::open(&app)?,
+        cost_center_id.as_deref(),
+        user_id.as_deref(),
+        department_id.as_deref(),
+    )
+}
+
+/// Assign a cost center to a user or department from an effective date.
+/// An open-ended earlier assignment of the same user or department ends the
+/// day before.
+#[tauri::command]
+pub async fn assign_cost_center(
+    app: tauri::AppHandle,
+    assignment: CostCenterAssignment,
+) -> Result<CostCenterAssignment, String> {
+    let mut conn = db::open(&app)?;
+    let saved = store::assign(&mut conn, assignment)?;
+    log::info!(
+        "[cost_centers::cmd] Assigned cost center {} to {} from {}",
+        saved.cost_center_id,
+        saved.user_id.as_deref().or(saved.department_id.as_deref()).unwrap_or_default(),
+        saved.effective_from
+    );
+    Ok(saved)
+}
+
+#[tauri::command]
+pub async fn delete_cost_center_assignment(app: tauri::AppHandle, id: String) -> Result<bool, String> {
+    store::delete_assignment(&*db::open(&app)?, &id)
+}
+
+/// The cost center a user books to on a date
+#[tauri::command]
+pub async fn get_effective_cost_center(
+    app: tauri::AppHandle,
+    user_id: String,
+    date: String,
+) -> Result<EffectiveCostCenter, String> {
+    validate_date(&date)?;
+    store::effective(&*db::open(&app)?, &user_id, &date)
+}
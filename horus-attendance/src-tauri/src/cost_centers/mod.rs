@@ -0,0 +1,12 @@
+//! Cost centers
+//!
+//! Finance bills attendance against cost centers rather than departments.
+//! A cost center is assigned to a whole department or to one user from an
+//! effective date, optionally up to an end date; a user's own assignment
+//! wins over their department's. Which cost center a summary day belongs to
+//! is resolved in SQL ([`store::effective_sql`]) so exports and analytics
+//! can group by the cost center in effect on each day.
+
+pub mod commands;
+pub mod store;
+pub mod types;
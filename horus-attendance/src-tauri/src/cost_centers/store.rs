@@ -0,0 +1,275 @@
+//! Storage and resolution of cost centers
+
+use chrono::{Duration, NaiveDate};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+use super::types::*;
+
+/// SQL expression for the ID of the cost center in effect for a user on a
+/// date: the user's own assignment, else their department's. The arguments
+/// are SQL expressions for the user ID, the user's department ID and the
+/// YYYY-MM-DD date.
+pub fn effective_sql(user_id: &str, department_id: &str, date: &str) -> String {
+    format!(
+        "COALESCE(
+            (SELECT cca.cost_center_id FROM cost_center_assignments cca
+             WHERE cca.user_id = {user_id} AND cca.effective_from <= {date}
+               AND (cca.effective_to IS NULL OR cca.effective_to >= {date})
+             ORDER BY cca.effective_from DESC LIMIT 1),
+            (SELECT cca.cost_center_id FROM cost_center_assignments cca
+             WHERE cca.department_id = {department_id} AND cca.effective_from <= {date}
+               AND (cca.effective_to IS NULL OR cca.effective_to >= {date})
+             ORDER BY cca.effective_from DESC LIMIT 1))",
+        user_id = user_id,
+        department_id = department_id,
+        date = date,
+    )
+}
+
+fn map_cost_center(row: &Row) -> rusqlite::Result<CostCenter> {
+    Ok(CostCenter {
+        id: row.get(0)?,
+        code: row.get(1)?,
+        name: row.get(2)?,
+        active: row.get(3)?,
+        created_at: row.get(4)?,
+        updated_at: row.get(5)?,
+    })
+}
+
+const SELECT_COST_CENTER: &str = "SELECT id, code, name, active, created_at, updated_at FROM cost_centers";
+
+/// Every cost center by code
+pub fn list(conn: &Connection) -> Result<Vec<CostCenter>, String> {
+    let mut stmt = conn
+        .prepare(&format!("{} ORDER BY code", SELECT_COST_CENTER))
+        .map_err(|e| format!("Failed to query cost centers: {}", e))?;
+    let rows = stmt
+        .query_map([], map_cost_center)
+        .map_err(|e| format!("Failed to query cost centers: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read cost centers: {}", e))
+}
+
+pub fn get(conn: &Connection, id: &str) -> Result<Option<CostCenter>, String> {
+    conn.query_row(&format!("{} WHERE id = ?1", SELECT_COST_CENTER), [id], map_cost_center)
+        .optional()
+        .map_err(|e| format!("Failed to read cost center: {}", e))
+}
+
+/// Create a cost center (empty ID) or update one
+pub fn save(conn: &Connection, mut cost_center: CostCenter) -> Result<CostCenter, String> {
+    cost_center.code = cost_center.code.trim().to_string();
+    cost_center.name = cost_center.name.trim().to_string();
+    if cost_center.code.is_empty() {
+        return Err("Cost center code is required".to_string());
+    }
+    if cost_center.name.is_empty() {
+        return Err("Cost center name is required".to_string());
+    }
+    if cost_center.id.is_empty() {
+        cost_center.id = uuid::Uuid::new_v4().to_string();
+    }
+    conn.execute(
+        "INSERT INTO cost_centers (id, code, name, active) VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(id) DO UPDATE SET
+           code = excluded.code,
+           name = excluded.name,
+           active = excluded.active,
+           updated_at = datetime('now')",
+        params![cost_center.id, cost_center.code, cost_center.name, cost_center.active],
+    )
+    .map_err(|e| match e {
+        rusqlite::Error::SqliteFailure(err, _) if err.code == rusqlite::ErrorCode::ConstraintViolation => {
+            format!("A cost center with code '{}' already exists", cost_center.code)
+        }
+        e => format!("Failed to save cost center: {}", e),
+    })?;
+    get(conn, &cost_center.id)?.ok_or_else(|| "Cost center disappeared while saving".to_string())
+}
+
+/// Delete a cost center with its assignments
+pub fn delete(conn: &Connection, id: &str) -> Result<bool, String> {
+    conn.execute("DELETE FROM cost_center_assignments WHERE cost_center_id = ?1", [id])
+        .map_err(|e| format!("Failed to delete cost center assignments: {}", e))?;
+    conn.execute("DELETE FROM cost_centers WHERE id = ?1", [id])
+        .map(|n| n > 0)
+        .map_err(|e| format!("Failed to delete cost center: {}", e))
+}
+
+fn map_assignment(row: &Row) -> rusqlite::Result<CostCenterAssignment> {
+    Ok(CostCenterAssignment {
+        id: row.get(0)?,
+        cost_center_id: row.get(1)?,
+        user_id: row.get(2)?,
+        department_id: row.get(3)?,
+        effective_from: row.get(4)?,
+        effective_to: row.get(5)?,
+        created_at: row.get(6)?,
+    })
+}
+
+const SELECT_ASSIGNMENT: &str = "SELECT id, cost_center_id, user_id, department_id, effective_from, effective_to, created_at
+                                 FROM cost_center_assignments";
+
+/// Assignments, optionally only of one cost center, user or department,
+/// latest first
+pub fn list_assignments(
+    conn: &Connection,
+    cost_center_id: Option<&str>,
+    user_id: Option<&str>,
+    department_id: Option<&str>,
+) -> Result<Vec<CostCenterAssignment>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "{} WHERE (?1 IS NULL OR cost_center_id = ?1)
+                 AND (?2 IS NULL OR user_id = ?2)
+                 AND (?3 IS NULL OR department_id = ?3)
+               ORDER BY effective_from DESC, created_at DESC",
+            SELECT_ASSIGNMENT
+        ))
+        .map_err(|e| format!("Failed to query assignments: {}", e))?;
+    let rows = stmt
+        .query_map(params![cost_center_id, user_id, department_id], map_assignment)
+        .map_err(|e| format!("Failed to query assignments: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read assignments: {}", e))
+}
+
+fn parse_date(date: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| format!("Invalid date (expected YYYY-MM-DD): {}", date))
+}
+
+/// Assign a cost center from its effective date. An open-ended assignment
+/// of the same user or department that started earlier is ended the day
+/// before; any other overlap is refused.
+pub fn assign(conn: &mut Connection, mut assignment: CostCenterAssignment) -> Result<CostCenterAssignment, String> {
+    let from = parse_date(&assignment.effective_from)?;
+    if let Some(to) = &assignment.effective_to {
+        if parse_date(to)? < from {
+            return Err("The end date must not be before the effective date".to_string());
+        }
+    }
+    let (column, target) = match (&assignment.user_id, &assignment.department_id) {
+        (Some(user_id), None) => ("user_id", user_id.clone()),
+        (None, Some(department_id)) => ("department_id", department_id.clone()),
+        _ => return Err("Assign a cost center to either a user or a department".to_string()),
+    };
+    match get(conn, &assignment.cost_center_id)? {
+        Some(cost_center) if !cost_center.active => {
+            return Err(format!("Cost center '{}' is inactive", cost_center.code))
+        }
+        Some(_) => {}
+        None => return Err(format!("Cost center not found: {}", assignment.cost_center_id)),
+    }
+    let table = if column == "user_id" { "users" } else { "departments" };
+    let exists: bool = conn
+        .query_row(&format!("SELECT EXISTS(SELECT 1 FROM {} WHERE id = ?1)", table), [&target], |row| row.get(0))
+        .map_err(|e| format!("Failed to look up {}: {}", table, e))?;
+    if !exists {
+        return Err(format!("Not found: {}", target));
+    }
+
+    if assignment.id.is_empty() {
+        assignment.id = uuid::Uuid::new_v4().to_string();
+    }
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let day_before = (from - Duration::days(1)).format("%Y-%m-%d").to_string();
+    tx.execute(
+        &format!(
+            "UPDATE cost_center_assignments SET effective_to = ?2
+             WHERE {} = ?1 AND id <> ?4 AND effective_to IS NULL AND effective_from < ?3",
+            column
+        ),
+        params![target, day_before, assignment.effective_from, assignment.id],
+    )
+    .map_err(|e| format!("Failed to end previous assignment: {}", e))?;
+    let overlap: Option<String> = tx
+        .query_row(
+            &format!(
+                "SELECT effective_from FROM cost_center_assignments
+                 WHERE {} = ?1 AND id <> ?2
+                   AND effective_from <= COALESCE(?4, '9999-12-31')
+                   AND COALESCE(effective_to, '9999-12-31') >= ?3
+                 LIMIT 1",
+                column
+            ),
+            params![target, assignment.id, assignment.effective_from, assignment.effective_to],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to check assignments: {}", e))?;
+    if let Some(start) = overlap {
+        return Err(format!("Overlaps the cost center assignment effective from {}", start));
+    }
+    tx.execute(
+        "INSERT INTO cost_center_assignments (id, cost_center_id, user_id, department_id, effective_from, effective_to)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT(id) DO UPDATE SET
+           cost_center_id = excluded.cost_center_id,
+           user_id = excluded.user_id,
+           department_id = excluded.department_id,
+           effective_from = excluded.effective_from,
+           effective_to = excluded.effective_to",
+        params![
+            assignment.id,
+            assignment.cost_center_id,
+            assignment.user_id,
+            assignment.department_id,
+            assignment.effective_from,
+            assignment.effective_to
+        ],
+    )
+    .map_err(|e| format!("Failed to save assignment: {}", e))?;
+    let saved = tx
+        .query_row(&format!("{} WHERE id = ?1", SELECT_ASSIGNMENT), [&assignment.id], map_assignment)
+        .map_err(|e| format!("Failed to read assignment: {}", e))?;
+    tx.commit().map_err(|e| format!("Failed to commit assignment: {}", e))?;
+    Ok(saved)
+}
+
+pub fn delete_assignment(conn: &Connection, id: &str) -> Result<bool, String> {
+    conn.execute("DELETE FROM cost_center_assignments WHERE id = ?1", [id])
+        .map(|n| n > 0)
+        .map_err(|e| format!("Failed to delete assignment: {}", e))
+}
+
+/// The cost center `user_id` books to on `date`
+pub fn effective(conn: &Connection, user_id: &str, date: &str) -> Result<EffectiveCostCenter, String> {
+    parse_date(date)?;
+    let department_id: Option<String> = conn
+        .query_row("SELECT department_id FROM users WHERE id = ?1", [user_id], |row| row.get(0))
+        .optional()
+        .map_err(|e| format!("Failed to look up user: {}", e))?
+        .ok_or_else(|| format!("User not found: {}", user_id))?;
+    let own: Option<String> = conn
+        .query_row(&format!("SELECT {}", effective_sql("?1", "NULL", "?2")), params![user_id, date], |row| {
+            row.get(0)
+        })
+        .map_err(|e| format!("Failed to resolve cost center: {}", e))?;
+    let (cost_center_id, source) = match own {
+        Some(id) => (Some(id), Some("user")),
+        None => {
+            let inherited: Option<String> = conn
+                .query_row(
+                    &format!("SELECT {}", effective_sql("NULL", "?1", "?2")),
+                    params![department_id, date],
+                    |row| row.get(0),
+                )
+                .map_err(|e| format!("Failed to resolve cost center: {}", e))?;
+            let source = inherited.as_ref().map(|_| "department");
+            (inherited, source)
+        }
+    };
+    Ok(EffectiveCostCenter {
+        user_id: user_id.to_string(),
+        date: date.to_string(),
+        cost_center: match cost_center_id {
+            Some(id) => get(conn, &id)?,
+            None => None,
+        },
+        source: source.map(str::to_string),
+    })
+}
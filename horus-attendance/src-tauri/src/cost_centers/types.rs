@@ -0,0 +1,58 @@
+//! Cost center data types for Tauri command serialization
+
+use serde::{Deserialize, Serialize};
+
+/// A cost center (one cost_centers row)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CostCenter {
+    /// Generated when saving a new cost center
+    #[serde(default)]
+    pub id: String,
+    /// Code as used by finance, unique
+    pub code: String,
+    pub name: String,
+    /// Inactive cost centers can't be assigned but keep their history
+    #[serde(default = "default_active")]
+    pub active: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+}
+
+fn default_active() -> bool {
+    true
+}
+
+/// A cost center assigned to a user or a department
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CostCenterAssignment {
+    #[serde(default)]
+    pub id: String,
+    pub cost_center_id: String,
+    /// Exactly one of user_id and department_id is set
+    #[serde(default)]
+    pub user_id: Option<String>,
+    #[serde(default)]
+    pub department_id: Option<String>,
+    /// YYYY-MM-DD
+    pub effective_from: String,
+    /// YYYY-MM-DD, inclusive; None while open-ended
+    #[serde(default)]
+    pub effective_to: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+}
+
+/// The cost center a user books to on a date, and why
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EffectiveCostCenter {
+    pub user_id: String,
+    pub date: String,
+    pub cost_center: Option<CostCenter>,
+    /// "user", "department", or None when nothing is assigned
+    pub source: Option<String>,
+}
@@ -0,0 +1,22 @@
+//! Tauri command handlers for data migrations.
+
+use super::runner;
+use super::types::*;
+use crate::db;
+
+/// Run pending data migrations. The frontend calls this after loading the
+/// database, once the SQL migrations have been applied.
+#[tauri::command]
+pub async fn run_data_migrations(app: tauri::AppHandle) -> Result<DataMigrationReport, String> {
+    let mut conn = db::open(&app)?;
+    tauri::async_runtime::spawn_blocking(move || runner::run_pending(&mut conn))
+        .await
+        .map_err(|e| format!("Data migration task failed: {}", e))?
+}
+
+/// Status of every data migration
+#[tauri::command]
+pub async fn list_data_migrations(app: tauri::AppHandle) -> Result<Vec<DataMigrationStatus>, String> {
+    let conn = db::open(&app)?;
+    runner::list(&conn)
+}
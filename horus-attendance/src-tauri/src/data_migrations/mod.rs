@@ -0,0 +1,18 @@
+//! Data migrations written in Rust
+//!
+//! Schema changes stay as SQL migrations in `get_migrations()`, applied by
+//! tauri-plugin-sql when the frontend loads the database. Upgrades that have
+//! to rewrite existing rows (parsing, deduplicating, backfilling) are Rust
+//! functions in [`steps`] instead, run in order once the frontend reports the
+//! schema is current.
+//!
+//! Each step records its progress in the `data_migrations` table. Long steps
+//! work in batches and save a checkpoint in the same transaction as each
+//! batch, so a step that fails or is interrupted resumes where it stopped on
+//! the next launch rather than starting over. A failed step blocks the ones
+//! after it.
+
+pub mod commands;
+pub mod runner;
+pub mod steps;
+pub mod types;
@@ -0,0 +1,182 @@
+//! Runs data migration steps and tracks their progress
+
+use rusqlite::{params, Connection, OptionalExtension};
+use std::sync::Mutex;
+
+use super::steps::{self, Step};
+use super::types::{DataMigrationReport, DataMigrationStatus};
+
+/// The frontend may ask twice during start-up (e.g. a remounted provider);
+/// only one run goes at a time
+static RUN_LOCK: Mutex<()> = Mutex::new(());
+
+/// Saved progress of a running step
+pub struct Checkpoint {
+    name: &'static str,
+    value: Option<String>,
+}
+
+impl Checkpoint {
+    /// Where the step got to last time, if it was interrupted
+    pub fn get(&self) -> Option<&str> {
+        self.value.as_deref()
+    }
+
+    /// Record progress. Pass the transaction the batch ran in, so the batch
+    /// and the checkpoint commit together.
+    pub fn save(&mut self, conn: &Connection, value: &str) -> Result<(), String> {
+        conn.execute(
+            "UPDATE data_migrations SET checkpoint = ?2 WHERE name = ?1",
+            params![self.name, value],
+        )
+        .map_err(|e| format!("Failed to save checkpoint for {}: {}", self.name, e))?;
+        self.value = Some(value.to_string());
+        Ok(())
+    }
+}
+
+/// Highest SQL migration applied, or 0 before the plugin has run
+pub fn schema_version(conn: &Connection) -> Result<i64, String> {
+    let has_table: bool = conn
+        .query_row(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = '_sqlx_migrations'",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to read schema version: {}", e))?;
+    if !has_table {
+        return Ok(0);
+    }
+    conn.query_row("SELECT COALESCE(MAX(version), 0) FROM _sqlx_migrations WHERE success = 1", [], |row| {
+        row.get(0)
+    })
+    .map_err(|e| format!("Failed to read schema version: {}", e))
+}
+
+/// The data_migrations table arrives with SQL migration 7
+const TRACKING_SCHEMA_VERSION: i64 = 7;
+
+/// Status of every known step
+pub fn list(conn: &Connection) -> Result<Vec<DataMigrationStatus>, String> {
+    let tracked = schema_version(conn)? >= TRACKING_SCHEMA_VERSION;
+    steps::all()
+        .iter()
+        .map(|step| {
+            let recorded = if tracked { load(conn, step)? } else { None };
+            Ok(recorded.unwrap_or_else(|| pending(step)))
+        })
+        .collect()
+}
+
+/// Run every step that hasn't completed, in order, stopping at the first
+/// failure or at a step whose schema isn't there yet
+pub fn run_pending(conn: &mut Connection) -> Result<DataMigrationReport, String> {
+    let _guard = RUN_LOCK.lock().map_err(|_| "Data migration lock is poisoned".to_string())?;
+
+    let schema_version = schema_version(conn)?;
+    let mut report = DataMigrationReport {
+        schema_version,
+        applied: Vec::new(),
+        pending: Vec::new(),
+        failed: None,
+    };
+
+    for step in steps::all() {
+        let blocked = report.failed.is_some()
+            || !report.pending.is_empty()
+            || schema_version < TRACKING_SCHEMA_VERSION.max(step.after_schema);
+        if blocked {
+            report.pending.push(step.name.to_string());
+            continue;
+        }
+
+        let recorded = load(conn, step)?;
+        if recorded.as_ref().is_some_and(|r| r.status == "completed") {
+            continue;
+        }
+
+        let mut checkpoint = begin(conn, step)?;
+        match (step.run)(conn, &mut checkpoint) {
+            Ok(()) => {
+                finish(conn, step)?;
+                log::info!("[data_migrations] Completed {}", step.name);
+                report.applied.push(step.name.to_string());
+            }
+            Err(error) => {
+                log::error!("[data_migrations] {} failed: {}", step.name, error);
+                fail(conn, step, &error)?;
+                report.failed = load(conn, step)?;
+            }
+        }
+    }
+    Ok(report)
+}
+
+fn pending(step: &Step) -> DataMigrationStatus {
+    DataMigrationStatus {
+        name: step.name.to_string(),
+        description: step.description.to_string(),
+        status: "pending".to_string(),
+        attempts: 0,
+        last_error: None,
+        completed_at: None,
+    }
+}
+
+fn load(conn: &Connection, step: &Step) -> Result<Option<DataMigrationStatus>, String> {
+    conn.query_row(
+        "SELECT status, attempts, last_error, completed_at FROM data_migrations WHERE name = ?1",
+        [step.name],
+        |row| {
+            Ok(DataMigrationStatus {
+                name: step.name.to_string(),
+                description: step.description.to_string(),
+                status: row.get(0)?,
+                attempts: row.get(1)?,
+                last_error: row.get(2)?,
+                completed_at: row.get(3)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(|e| format!("Failed to read data migration {}: {}", step.name, e))
+}
+
+/// Mark a step running and load its checkpoint. A step found "running" was
+/// interrupted and picks up from its checkpoint.
+fn begin(conn: &Connection, step: &Step) -> Result<Checkpoint, String> {
+    conn.execute(
+        "INSERT INTO data_migrations (name, status, attempts, started_at)
+         VALUES (?1, 'running', 1, datetime('now'))
+         ON CONFLICT(name) DO UPDATE SET
+           status = 'running',
+           attempts = attempts + 1,
+           last_error = NULL,
+           started_at = excluded.started_at",
+        [step.name],
+    )
+    .map_err(|e| format!("Failed to start data migration {}: {}", step.name, e))?;
+    let value = conn
+        .query_row("SELECT checkpoint FROM data_migrations WHERE name = ?1", [step.name], |row| row.get(0))
+        .map_err(|e| format!("Failed to read checkpoint for {}: {}", step.name, e))?;
+    Ok(Checkpoint { name: step.name, value })
+}
+
+fn finish(conn: &Connection, step: &Step) -> Result<(), String> {
+    conn.execute(
+        "UPDATE data_migrations SET status = 'completed', checkpoint = NULL, completed_at = datetime('now')
+         WHERE name = ?1",
+        [step.name],
+    )
+    .map_err(|e| format!("Failed to complete data migration {}: {}", step.name, e))?;
+    Ok(())
+}
+
+fn fail(conn: &Connection, step: &Step, error: &str) -> Result<(), String> {
+    conn.execute(
+        "UPDATE data_migrations SET status = 'failed', last_error = ?2 WHERE name = ?1",
+        params![step.name, error],
+    )
+    .map_err(|e| format!("Failed to record data migration failure for {}: {}", step.name, e))?;
+    Ok(())
+}
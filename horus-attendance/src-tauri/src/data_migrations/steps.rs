@@ -0,0 +1,499 @@
+//! The data migration steps, in the order they run
+//!
+//! Steps are never renamed or reordered once released: the name is the key
+//! in `data_migrations`. A step must tolerate being re-run from any saved
+//! checkpoint, including from the start.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use rusqlite::{params, Connection};
+
+use super::runner::Checkpoint;
+use crate::db;
+use crate::ledger;
+use crate::payloads;
+use crate::summary::{engine, types::ChangeSource};
+use crate::zkteco::protocol::decode_record_data_40;
+
+/// A data migration step
+pub struct Step {
+    /// Stable key in the data_migrations table
+    pub name: &'static str,
+    pub description: &'static str,
+    /// SQL migration version the step needs
+    pub after_schema: i64,
+    pub run: fn(&mut Connection, &mut Checkpoint) -> Result<(), String>,
+}
+
+/// Every step, oldest first
+pub fn all() -> &'static [Step] {
+    &[
+        Step {
+            name: NORMALIZE_LOG_TIMESTAMPS,
+            description: "Rewrite punch timestamps stored by older versions into the standard format",
+            // Records ledger amendments (migration 49)
+            after_schema: 49,
+            run: normalize_log_timestamps,
+        },
+        Step {
+            name: RESTORE_DEVICE_USER_IDS,
+            description: "Restore device user IDs that older versions cut short or stripped of leading zeros",
+            // Records ledger amendments (migration 49)
+            after_schema: 49,
+            run: restore_device_user_ids,
+        },
+    ]
+}
+
+/// Name of [`normalize_log_timestamps`], also its maintenance and ledger amendment reason
+const NORMALIZE_LOG_TIMESTAMPS: &str = "normalize_log_timestamps";
+
+/// Rows handled per transaction
+const BATCH_SIZE: i64 = 500;
+
+/// Punch timestamps are device-local time as `YYYY-MM-DDTHH:MM:SS.sssZ`
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.3fZ";
+
+/// Matches timestamps already in [`TIMESTAMP_FORMAT`]
+const CANONICAL_GLOB: &str = "[0-9][0-9][0-9][0-9]-[0-9][0-9]-[0-9][0-9]T[0-9][0-9]:[0-9][0-9]:[0-9][0-9].[0-9][0-9][0-9]Z";
+
+/// Versions that went through the old sidecar stored some punches as
+/// `YYYY-MM-DD HH:MM:SS` or without milliseconds. Date ranges compare
+/// timestamps as strings, so those rows fell outside reports at day edges.
+/// Rows whose standard form already exists for the same device and user are
+/// duplicates and are removed. Values with an explicit UTC offset other than
+/// `Z` are left alone: there's no telling what local time they meant.
+/// Device punches can't be edited otherwise, so each batch runs under the
+/// maintenance bypass and sealed punches it touches are recorded as ledger
+/// amendments.
+fn normalize_log_timestamps(conn: &mut Connection, checkpoint: &mut Checkpoint) -> Result<(), String> {
+    let mut last_rowid: i64 = checkpoint.get().and_then(|v| v.parse().ok()).unwrap_or(0);
+    let (mut rewritten, mut removed, mut skipped) = (0u64, 0u64, 0u64);
+
+    loop {
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+        let batch: Vec<(i64, String, String)> = {
+            let mut stmt = tx
+                .prepare(
+                    "SELECT rowid, id, timestamp FROM attendance_logs_raw
+                     WHERE rowid > ?1 AND timestamp NOT GLOB ?2
+                     ORDER BY rowid LIMIT ?3",
+                )
+                .map_err(|e| format!("Failed to query logs: {}", e))?;
+            let rows = stmt
+                .query_map(params![last_rowid, CANONICAL_GLOB, BATCH_SIZE], |row| {
+                    Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+                })
+                .map_err(|e| format!("Failed to query logs: {}", e))?;
+            rows.collect::<Result<_, _>>()
+                .map_err(|e| format!("Failed to read logs: {}", e))?
+        };
+        let Some(&(batch_end, _, _)) = batch.last() else {
+            break;
+        };
+
+        db::maintenance::bypass(&tx, NORMALIZE_LOG_TIMESTAMPS, |tx| {
+            let mut log_ids = Vec::new();
+            for (rowid, id, timestamp) in &batch {
+                let Some(normalized) = normalize(timestamp) else {
+                    skipped += 1;
+                    continue;
+                };
+                let updated = tx
+                    .execute(
+                        "UPDATE OR IGNORE attendance_logs_raw SET timestamp = ?2 WHERE rowid = ?1",
+                        params![rowid, normalized],
+                    )
+                    .map_err(|e| format!("Failed to update log: {}", e))?;
+                if updated > 0 {
+                    rewritten += 1;
+                } else {
+                    tx.execute("DELETE FROM attendance_logs_raw WHERE rowid = ?1", [rowid])
+                        .map_err(|e| format!("Failed to remove duplicate log: {}", e))?;
+                    removed += 1;
+                }
+                log_ids.push(id.clone());
+            }
+            ledger::store::amend(tx, &log_ids, NORMALIZE_LOG_TIMESTAMPS)
+        })?;
+
+        last_rowid = batch_end;
+        checkpoint.save(&tx, &last_rowid.to_string())?;
+        tx.commit()
+            .map_err(|e| format!("Failed to commit batch: {}", e))?;
+    }
+
+    log::info!(
+        "[data_migrations] Normalized {} log timestamps ({} duplicates removed, {} left as is)",
+        rewritten,
+        removed,
+        skipped
+    );
+    Ok(())
+}
+
+fn normalize(timestamp: &str) -> Option<String> {
+    let value = timestamp.trim().trim_end_matches('Z');
+    ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .map(|dt| dt.format(TIMESTAMP_FORMAT).to_string())
+}
+
+/// Earliest and latest punch timestamps touched by a step
+type Span = Option<(String, String)>;
+
+fn widen(span: &mut Span, first: &str, last: &str) {
+    match span {
+        Some((start, end)) => {
+            if first < start.as_str() {
+                *start = first.to_string();
+            }
+            if last > end.as_str() {
+                *end = last.to_string();
+            }
+        }
+        None => *span = Some((first.to_string(), last.to_string())),
+    }
+}
+
+/// Device user IDs are text and must be kept as the device sent them.
+/// Older versions read only the first 9 characters of the user ID in TCP
+/// attendance records, cutting extended ("SSR") IDs short, and some paths
+/// went through a number, turning "00123" into "123". Either way the punches
+/// matched no profile. Punches whose record was retained get their full ID
+/// back from it; a number without leading zeros that no profile or link has
+/// is rewritten to the one zero-padded ID that a profile or link has for the
+/// same number, and a profile whose number matches no punches gets the one
+/// padded ID the punches use. Summaries of the days touched are recomputed.
+/// Every change removes its own trigger, so a re-run picks up where a
+/// failed one stopped. Device punches can't be edited otherwise, so the
+/// rewrites run under the maintenance bypass and sealed punches they touch
+/// are recorded as ledger amendments.
+fn restore_device_user_ids(conn: &mut Connection, _checkpoint: &mut Checkpoint) -> Result<(), String> {
+    let mut span: Span = None;
+    let truncated = restore_truncated_ids(conn, &mut span)?;
+    let (logs, profiles) = restore_leading_zeros(conn, &mut span)?;
+
+    if let Some((first, last)) = span {
+        // A punch after midnight can belong to the previous working day
+        let start = NaiveDate::parse_from_str(first.get(..10).unwrap_or_default(), "%Y-%m-%d")
+            .map(|d| (d - Duration::days(1)).format("%Y-%m-%d").to_string())
+            .unwrap_or_else(|_| first.chars().take(10).collect());
+        let end: String = last.chars().take(10).collect();
+        let source = ChangeSource {
+            reason: "data_migration",
+            actor: None,
+        };
+        engine::recompute(conn, &start, &end, &source)?;
+    }
+    log::info!(
+        "[data_migrations] Restored {} cut-short punch user IDs, re-padded {} punch IDs and {} profile IDs",
+        truncated,
+        logs,
+        profiles
+    );
+    Ok(())
+}
+
+/// Name of [`restore_device_user_ids`], also its maintenance and ledger amendment reason
+const RESTORE_DEVICE_USER_IDS: &str = "restore_device_user_ids";
+
+/// IDs of the punches matching `condition`
+fn log_ids_where(conn: &Connection, condition: &str, params: impl rusqlite::Params) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare(&format!("SELECT id FROM attendance_logs_raw WHERE {}", condition))
+        .map_err(|e| format!("Failed to query logs: {}", e))?;
+    let rows = stmt
+        .query_map(params, |row| row.get(0))
+        .map_err(|e| format!("Failed to query logs: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read logs: {}", e))
+}
+
+/// Full user IDs of punches stored with 9 characters, from their retained
+/// 40-byte records. Returns how many punches were fixed.
+fn restore_truncated_ids(conn: &mut Connection, span: &mut Span) -> Result<u64, String> {
+    let candidates: Vec<(String, String, String, String, Vec<u8>, i64)> = {
+        let mut stmt = conn
+            .prepare(
+                "SELECT device_id, device_user_id, timestamp, encoding, payload, original_size FROM raw_payloads
+                 WHERE length(device_user_id) = 9 AND original_size = 40",
+            )
+            .map_err(|e| format!("Failed to query raw payloads: {}", e))?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?))
+            })
+            .map_err(|e| format!("Failed to query raw payloads: {}", e))?;
+        rows.collect::<Result<_, _>>()
+            .map_err(|e| format!("Failed to read raw payloads: {}", e))?
+    };
+
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    let fixed = db::maintenance::bypass(&tx, RESTORE_DEVICE_USER_IDS, |tx| {
+        let mut fixed = 0;
+        let mut log_ids = Vec::new();
+        for (device_id, stored, timestamp, encoding, payload, original_size) in candidates {
+            let bytes = match payloads::store::decode_stored(&encoding, &payload, original_size) {
+                Ok(bytes) if bytes.len() == 40 => bytes,
+                _ => continue,
+            };
+            let (full, _, _, _) = decode_record_data_40(&bytes);
+            if full.len() <= stored.len() || !full.starts_with(&stored) {
+                continue;
+            }
+            log_ids.extend(log_ids_where(
+                tx,
+                "device_id = ?1 AND device_user_id = ?2 AND timestamp = ?3",
+                params![device_id, stored, timestamp],
+            )?);
+            let updated = tx
+                .execute(
+                    "UPDATE OR IGNORE attendance_logs_raw SET device_user_id = ?4
+                     WHERE device_id = ?1 AND device_user_id = ?2 AND timestamp = ?3",
+                    params![device_id, stored, timestamp, full],
+                )
+                .map_err(|e| format!("Failed to update log: {}", e))?;
+            if updated == 0 {
+                // Already stored under the full ID too
+                tx.execute(
+                    "DELETE FROM attendance_logs_raw WHERE device_id = ?1 AND device_user_id = ?2 AND timestamp = ?3",
+                    params![device_id, stored, timestamp],
+                )
+                .map_err(|e| format!("Failed to remove duplicate log: {}", e))?;
+            }
+            tx.execute(
+                "UPDATE OR IGNORE raw_payloads SET device_user_id = ?4
+                 WHERE device_id = ?1 AND device_user_id = ?2 AND timestamp = ?3",
+                params![device_id, stored, timestamp, full],
+            )
+            .map_err(|e| format!("Failed to update raw payload: {}", e))?;
+            widen(span, &timestamp, &timestamp);
+            fixed += 1;
+        }
+        ledger::store::amend(tx, &log_ids, RESTORE_DEVICE_USER_IDS)?;
+        Ok(fixed)
+    })?;
+    tx.commit()
+        .map_err(|e| format!("Failed to commit restored IDs: {}", e))?;
+    Ok(fixed)
+}
+
+fn is_number(id: &str) -> bool {
+    !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Zero-padded numeric IDs by the number they spell, e.g. "123" for "00123"
+fn padded_by_number<'a>(ids: impl Iterator<Item = &'a String>) -> HashMap<&'a str, Vec<&'a String>> {
+    let mut padded: HashMap<&str, Vec<&String>> = HashMap::new();
+    for id in ids.filter(|id| is_number(id) && id.starts_with('0')) {
+        let number = id.trim_start_matches('0');
+        if !number.is_empty() {
+            padded.entry(number).or_default().push(id);
+        }
+    }
+    padded
+}
+
+fn query_ids(conn: &Connection, sql: &str) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare(sql)
+        .map_err(|e| format!("Failed to query device user IDs: {}", e))?;
+    let rows = stmt
+        .query_map([], |row| row.get(0))
+        .map_err(|e| format!("Failed to query device user IDs: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read device user IDs: {}", e))
+}
+
+/// Put leading zeros back where exactly one padded ID fits. Returns how
+/// many punch IDs and profile IDs were rewritten.
+fn restore_leading_zeros(conn: &mut Connection, span: &mut Span) -> Result<(u64, u64), String> {
+    let known = query_ids(
+        conn,
+        "SELECT device_user_id FROM users WHERE COALESCE(device_user_id, '') != ''
+         UNION SELECT device_user_id FROM user_device_links",
+    )?;
+    let punched = query_ids(conn, "SELECT DISTINCT device_user_id FROM attendance_logs_raw")?;
+    let known_set: HashSet<&str> = known.iter().map(String::as_str).collect();
+    let punched_set: HashSet<&str> = punched.iter().map(String::as_str).collect();
+    let known_padded = padded_by_number(known.iter());
+    let punched_padded = padded_by_number(punched.iter());
+
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    let logs = db::maintenance::bypass(&tx, RESTORE_DEVICE_USER_IDS, |tx| {
+        let mut logs = 0;
+        let mut log_ids = Vec::new();
+        for id in punched.iter().filter(|id| is_number(id) && !id.starts_with('0')) {
+            let Some([padded]) = known_padded.get(id.as_str()).map(Vec::as_slice) else {
+                continue;
+            };
+            if known_set.contains(id.as_str()) {
+                continue;
+            }
+            let (first, last): (Option<String>, Option<String>) = tx
+                .query_row(
+                    "SELECT MIN(timestamp), MAX(timestamp) FROM attendance_logs_raw WHERE device_user_id = ?1",
+                    [id],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .map_err(|e| format!("Failed to query logs: {}", e))?;
+            if let (Some(first), Some(last)) = (first, last) {
+                widen(span, &first, &last);
+            }
+            log_ids.extend(log_ids_where(tx, "device_user_id = ?1", params![id])?);
+            tx.execute(
+                "UPDATE OR IGNORE attendance_logs_raw SET device_user_id = ?2 WHERE device_user_id = ?1",
+                params![id, padded],
+            )
+            .map_err(|e| format!("Failed to update logs: {}", e))?;
+            // What is left was stored under the padded ID as well
+            tx.execute("DELETE FROM attendance_logs_raw WHERE device_user_id = ?1", [id])
+                .map_err(|e| format!("Failed to remove duplicate logs: {}", e))?;
+            tx.execute(
+                "UPDATE OR IGNORE raw_payloads SET device_user_id = ?2 WHERE device_user_id = ?1",
+                params![id, padded],
+            )
+            .map_err(|e| format!("Failed to update raw payloads: {}", e))?;
+            logs += 1;
+        }
+        ledger::store::amend(tx, &log_ids, RESTORE_DEVICE_USER_IDS)?;
+        Ok(logs)
+    })?;
+    let mut profiles = 0;
+    let profile_ids = query_ids(&tx, "SELECT device_user_id FROM users WHERE COALESCE(device_user_id, '') != ''")?;
+    for id in profile_ids.iter().filter(|id| is_number(id) && !id.starts_with('0')) {
+        let Some([padded]) = punched_padded.get(id.as_str()).map(Vec::as_slice) else {
+            continue;
+        };
+        if punched_set.contains(id.as_str()) || known_set.contains(padded.as_str()) {
+            continue;
+        }
+        let (first, last): (Option<String>, Option<String>) = tx
+            .query_row(
+                "SELECT MIN(timestamp), MAX(timestamp) FROM attendance_logs_raw WHERE device_user_id = ?1",
+                [padded],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .map_err(|e| format!("Failed to query logs: {}", e))?;
+        if let (Some(first), Some(last)) = (first, last) {
+            widen(span, &first, &last);
+        }
+        profiles += tx
+            .execute(
+                "UPDATE OR IGNORE users SET device_user_id = ?2, updated_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+                 WHERE device_user_id = ?1",
+                params![id, padded],
+            )
+            .map_err(|e| format!("Failed to update user: {}", e))? as u64;
+    }
+    tx.commit()
+        .map_err(|e| format!("Failed to commit restored IDs: {}", e))?;
+    Ok((logs, profiles))
+}
+
+#[cfg(test)]
+mod tests {
+    use rusqlite::Connection;
+
+    use super::super::runner;
+    use crate::{db, ledger};
+
+    /// How many of the lock and immutability triggers are in place
+    fn triggers(conn: &Connection) -> i64 {
+        conn.query_row(
+            "SELECT COUNT(*) FROM sqlite_master
+             WHERE type = 'trigger' AND (name LIKE '%locked%' OR name = 'trg_logs_device_immutable')",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap()
+    }
+
+    fn timestamp(conn: &Connection, id: &str) -> Option<String> {
+        conn.query_row("SELECT timestamp FROM attendance_logs_raw WHERE id = ?1", [id], |row| row.get(0))
+            .ok()
+    }
+
+    /// An upgrade from before the punch triggers: old timestamps and an
+    /// unpadded ID, in a locked period, already sealed into the ledger
+    #[test]
+    fn steps_rewrite_locked_and_sealed_punches() {
+        let mut conn = db::migrated();
+        conn.execute_batch(
+            "INSERT INTO devices (id, name, ip, port) VALUES ('d1', 'Front door', '10.0.0.2', 4370);
+             INSERT INTO users (id, device_user_id, display_name) VALUES ('u1', '00123', 'Ana');
+             INSERT INTO attendance_logs_raw (id, device_id, device_user_id, timestamp) VALUES
+                 ('l1', 'd1', '00123', '2024-01-08 08:00:00'),
+                 ('l2', 'd1', '123', '2024-01-08T17:00:00.000Z'),
+                 ('l3', 'd1', '00123', '2024-01-09T08:00:00.000Z'),
+                 ('l4', 'd1', '00123', '2024-01-09 08:00:00');
+             INSERT INTO locked_periods (start_date, end_date) VALUES ('2024-01-01', '2024-01-31');",
+        )
+        .unwrap();
+        ledger::store::seal(&mut conn).unwrap();
+        let guarded = "UPDATE attendance_logs_raw SET timestamp = '2024-01-08T08:00:00.000Z' WHERE id = 'l1'";
+        assert!(conn.execute(guarded, []).is_err());
+
+        let report = runner::run_pending(&mut conn).unwrap();
+        assert!(report.failed.is_none(), "{:?}", report.failed);
+        assert_eq!(report.applied, ["normalize_log_timestamps", "restore_device_user_ids"]);
+
+        assert_eq!(timestamp(&conn, "l1").as_deref(), Some("2024-01-08T08:00:00.000Z"));
+        // The same punch as l3 once normalized
+        assert_eq!(timestamp(&conn, "l4"), None);
+        let l2_user: String = conn
+            .query_row("SELECT device_user_id FROM attendance_logs_raw WHERE id = 'l2'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(l2_user, "00123");
+
+        assert_eq!(triggers(&conn), 9);
+        assert!(conn.execute(guarded, []).is_err());
+
+        // The sealed batch is accounted for by the last step's amendment, but no longer intact
+        let verification = ledger::store::verify(&conn).unwrap();
+        assert!(!verification.intact);
+        assert!(verification.broken.is_empty(), "{:?}", verification.broken);
+        let reasons: Vec<&str> = verification.amended.iter().map(|a| a.reason.as_str()).collect();
+        assert_eq!(reasons, ["restore_device_user_ids"]);
+        let amendments: Vec<String> = ledger::store::amendments(&conn).unwrap().into_iter().map(|a| a.reason).collect();
+        assert_eq!(amendments, ["normalize_log_timestamps", "restore_device_user_ids"]);
+    }
+
+    /// A step that fails rolls back, triggers and all
+    #[test]
+    fn bypass_ends_with_its_transaction() {
+        let mut conn = db::migrated();
+        conn.execute_batch(
+            "INSERT INTO devices (id, name, ip, port) VALUES ('d1', 'Front door', '10.0.0.2', 4370);
+             INSERT INTO attendance_logs_raw (id, device_id, device_user_id, timestamp)
+                 VALUES ('l1', 'd1', '7', '2024-01-08T08:00:00.000Z');",
+        )
+        .unwrap();
+        assert!(db::maintenance::bypass(&conn, "test", |_| Ok(())).is_err());
+
+        let tx = conn.transaction().unwrap();
+        let failed: Result<(), String> = db::maintenance::bypass(&tx, "test", |tx| {
+            tx.execute("UPDATE attendance_logs_raw SET device_user_id = '07'", [])
+                .map_err(|e| e.to_string())?;
+            Err("stopped".to_string())
+        });
+        assert!(failed.is_err());
+        drop(tx);
+
+        let user: String = conn
+            .query_row("SELECT device_user_id FROM attendance_logs_raw", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(user, "7");
+        assert_eq!(triggers(&conn), 9);
+        assert!(conn.execute("UPDATE attendance_logs_raw SET device_user_id = '07'", []).is_err());
+    }
+}
@@ -0,0 +1,30 @@
+//! Types for data migrations
+
+use serde::{Deserialize, Serialize};
+
+/// State of one data migration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataMigrationStatus {
+    pub name: String,
+    pub description: String,
+    /// "pending", "running" (interrupted), "completed" or "failed"
+    pub status: String,
+    pub attempts: i64,
+    pub last_error: Option<String>,
+    pub completed_at: Option<String>,
+}
+
+/// Outcome of running pending data migrations
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataMigrationReport {
+    /// Highest SQL migration applied to the database
+    pub schema_version: i64,
+    /// Steps completed by this run
+    pub applied: Vec<String>,
+    /// Steps still waiting for a newer schema or behind a failed step
+    pub pending: Vec<String>,
+    /// The step that failed, if any; it is retried on the next run
+    pub failed: Option<DataMigrationStatus>,
+}
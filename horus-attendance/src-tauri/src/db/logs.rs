@@ -0,0 +1,192 @@
+//! Raw punch logs (attendance_logs_raw)
+//!
+//! Timestamps are the device's local wall-clock time, stored as
+//! `YYYY-MM-DDTHH:MM:SS.sssZ` despite the `Z`, so date ranges are plain
+//! string comparisons against [`day_bounds`].
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+/// Half-open timestamp bounds covering whole days from `start_date` to
+/// `end_date` (inclusive), for `timestamp >= start AND timestamp < end`
+pub fn day_bounds(start_date: &str, end_date: &str) -> (String, String) {
+    working_day_bounds(start_date, end_date, 0)
+}
+
+/// Half-open timestamp bounds covering working days from `start_date` to
+/// `end_date` (inclusive) that start at `cutoff_hour`: from the cutoff on the
+/// first day to the cutoff on the day after the last, in the same format as
+/// the locked_punch_bounds view
+pub fn working_day_bounds(start_date: &str, end_date: &str, cutoff_hour: u32) -> (String, String) {
+    let next_day = chrono::NaiveDate::parse_from_str(end_date, "%Y-%m-%d")
+        .map(|d| (d + chrono::Duration::days(1)).format("%Y-%m-%d").to_string())
+        .unwrap_or_else(|_| end_date.to_string());
+    (
+        format!("{}T{:02}:00:00", start_date, cutoff_hour),
+        format!("{}T{:02}:00:00", next_day, cutoff_hour),
+    )
+}
+
+/// Condition leaving out punches voided or moved by a correction, for the
+/// attendance_logs_raw ID column `id_column`
+pub fn not_voided_sql(id_column: &str) -> String {
+    format!(
+        "{} NOT IN (SELECT original_log_id FROM punch_corrections WHERE original_log_id IS NOT NULL)",
+        id_column
+    )
+}
+
+/// A punch as stored, for matching and summary processing
+#[derive(Debug, Clone)]
+pub struct Punch {
+    pub device_user_id: String,
+    pub timestamp: String,
+}
+
+/// Punches from every device on the working days between two dates
+/// (inclusive), oldest first, without those a correction voided
+pub fn between(conn: &Connection, start_date: &str, end_date: &str, cutoff_hour: u32) -> Result<Vec<Punch>, String> {
+    let (start, end) = working_day_bounds(start_date, end_date, cutoff_hour);
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT device_user_id, timestamp FROM attendance_logs_raw
+             WHERE timestamp >= ?1 AND timestamp < ?2 AND {}
+             ORDER BY timestamp ASC",
+            not_voided_sql("id")
+        ))
+        .map_err(|e| format!("Failed to query logs: {}", e))?;
+    let rows = stmt
+        .query_map(params![start, end], |row| {
+            Ok(Punch {
+                device_user_id: row.get(0)?,
+                timestamp: row.get(1)?,
+            })
+        })
+        .map_err(|e| format!("Failed to query logs: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read logs: {}", e))
+}
+
+/// A punch to store
+pub struct NewPunch<'a> {
+    pub device_id: &'a str,
+    pub device_user_id: &'a str,
+    pub timestamp: &'a str,
+    pub punch_type: Option<i64>,
+    pub raw_payload: Option<String>,
+    pub source: &'a str,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub note: Option<&'a str>,
+}
+
+/// Store a punch unless the device already has one for that user and
+/// timestamp. Returns the ID of the new row, or of the existing one with
+/// `false`.
+pub fn insert(conn: &Connection, punch: &NewPunch) -> Result<(String, bool), String> {
+    let id = uuid::Uuid::new_v4().to_string();
+    let inserted = conn
+        .execute(
+            "INSERT OR IGNORE INTO attendance_logs_raw
+                 (id, device_id, device_user_id, timestamp, punch_type, raw_payload, source, latitude, longitude, note)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                id,
+                punch.device_id,
+                punch.device_user_id,
+                punch.timestamp,
+                punch.punch_type,
+                punch.raw_payload,
+                punch.source,
+                punch.latitude,
+                punch.longitude,
+                punch.note,
+            ],
+        )
+        .map_err(|e| format!("Failed to save punch: {}", e))?;
+    if inserted > 0 {
+        return Ok((id, true));
+    }
+
+    let existing = find_id(conn, punch.device_id, punch.device_user_id, punch.timestamp)?
+        .ok_or_else(|| "Punch was neither saved nor found".to_string())?;
+    Ok((existing, false))
+}
+
+/// ID of the punch a device recorded for a user at a timestamp
+pub fn find_id(conn: &Connection, device_id: &str, device_user_id: &str, timestamp: &str) -> Result<Option<String>, String> {
+    conn.query_row(
+        "SELECT id FROM attendance_logs_raw WHERE device_id = ?1 AND device_user_id = ?2 AND timestamp = ?3",
+        params![device_id, device_user_id, timestamp],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(|e| format!("Failed to read punch: {}", e))
+}
+
+/// A punch read from a device during sync
+pub struct DevicePunch<'a> {
+    pub device_user_id: &'a str,
+    pub timestamp: &'a str,
+    pub verify_type: u8,
+    pub punch_type: u8,
+}
+
+/// Store punches read from `device_id` in one transaction, skipping those
+/// already stored. Returns how many were new.
+pub fn insert_from_device(conn: &mut Connection, device_id: &str, punches: &[DevicePunch]) -> Result<usize, String> {
+    let punches: Vec<(&str, &DevicePunch)> = punches.iter().map(|punch| (device_id, punch)).collect();
+    insert_from_devices(conn, &punches)
+}
+
+/// Store punches from any number of devices in one transaction, skipping
+/// those already stored. Returns how many were new.
+pub fn insert_from_devices(conn: &mut Connection, punches: &[(&str, &DevicePunch)]) -> Result<usize, String> {
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    let mut inserted = 0;
+    {
+        let mut stmt = tx
+            .prepare(
+                "INSERT OR IGNORE INTO attendance_logs_raw
+                     (id, device_id, device_user_id, timestamp, verify_type, punch_type)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            )
+            .map_err(|e| format!("Failed to prepare punch insert: {}", e))?;
+        for (device_id, punch) in punches {
+            inserted += stmt
+                .execute(params![
+                    uuid::Uuid::new_v4().to_string(),
+                    device_id,
+                    punch.device_user_id,
+                    punch.timestamp,
+                    punch.verify_type,
+                    punch.punch_type,
+                ])
+                .map_err(|e| format!("Failed to save punch: {}", e))?;
+        }
+    }
+    tx.commit().map_err(|e| format!("Failed to commit punches: {}", e))?;
+    Ok(inserted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn working_day_bounds_are_half_open_at_the_cutoff() {
+        let (start, end) = working_day_bounds("2024-03-01", "2024-03-01", 5);
+        assert_eq!((start.as_str(), end.as_str()), ("2024-03-01T05:00:00", "2024-03-02T05:00:00"));
+        let inside = |timestamp: &str| timestamp >= start.as_str() && timestamp < end.as_str();
+        assert!(inside("2024-03-01T05:00:00.000Z"));
+        assert!(inside("2024-03-02T04:59:59.999Z"));
+        assert!(!inside("2024-03-02T05:00:00.000Z"));
+        assert!(!inside("2024-03-01T04:59:59.000Z"));
+
+        assert_eq!(
+            day_bounds("2024-03-01", "2024-03-31"),
+            ("2024-03-01T00:00:00".to_string(), "2024-04-01T00:00:00".to_string())
+        );
+    }
+}
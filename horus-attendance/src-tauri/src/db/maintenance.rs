@@ -0,0 +1,62 @@
+//! Bypass for the punch lock and immutability triggers
+//!
+//! Device punches can't be edited and locked periods can't change (SQL
+//! migration 25). Data migrations and restores still have to rewrite or
+//! replace such rows, so they drop those triggers for the length of their
+//! work and create them again before committing. All of it happens inside
+//! the caller's transaction: other connections, the frontend's included,
+//! keep seeing the triggers, and nothing they can write turns them off.
+
+use rusqlite::{Connection, OptionalExtension};
+
+/// The triggers guarding device punches, corrections and the summaries of
+/// locked periods
+const GUARD_TRIGGERS: [&str; 9] = [
+    "trg_logs_device_immutable",
+    "trg_logs_locked_update",
+    "trg_logs_locked_delete",
+    "trg_logs_locked_correction",
+    "trg_corrections_locked_insert",
+    "trg_corrections_locked_delete",
+    "trg_summary_locked_insert",
+    "trg_summary_locked_update",
+    "trg_summary_locked_delete",
+];
+
+/// Run `f` with the triggers off. `conn` must be inside a transaction, which
+/// `f` runs in as well.
+pub fn bypass<T>(
+    conn: &Connection,
+    reason: &str,
+    f: impl FnOnce(&Connection) -> Result<T, String>,
+) -> Result<T, String> {
+    if conn.is_autocommit() {
+        return Err("Maintenance bypass needs a transaction".to_string());
+    }
+    let mut dropped = Vec::new();
+    for name in GUARD_TRIGGERS {
+        let sql: Option<String> = conn
+            .query_row(
+                "SELECT sql FROM sqlite_master WHERE type = 'trigger' AND name = ?1",
+                [name],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| format!("Failed to read trigger {}: {}", name, e))?;
+        if let Some(sql) = sql {
+            conn.execute_batch(&format!("DROP TRIGGER {}", name))
+                .map_err(|e| format!("Failed to start maintenance: {}", e))?;
+            dropped.push(sql);
+        }
+    }
+    log::info!("[db] Lock triggers off for {}", reason);
+    let result = f(conn);
+    // On failure the caller rolls back, which restores them as well
+    let restored = dropped.iter().try_for_each(|sql| {
+        conn.execute_batch(sql)
+            .map_err(|e| format!("Failed to end maintenance: {}", e))
+    });
+    let value = result?;
+    restored?;
+    Ok(value)
+}
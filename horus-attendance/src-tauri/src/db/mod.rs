@@ -0,0 +1,118 @@
+//! Direct SQLite access for backend features
+//!
+//! The frontend still runs its own queries through tauri-plugin-sql, and the
+//! schema migrations stay there. Work that runs entirely in Rust (summary
+//! recomputation, analytics, the API server, maintenance) borrows a
+//! connection from the [`pool::Pool`] in managed state and goes through the
+//! repository modules below for the core tables, so new features don't each
+//! grow their own copies of the same queries.
+
+pub mod logs;
+pub mod maintenance;
+pub mod pool;
+pub mod summaries;
+pub mod users;
+
+use rusqlite::{Connection, OpenFlags, OptionalExtension};
+use serde::de::DeserializeOwned;
+use std::path::Path;
+use std::time::Duration;
+use tauri::Manager;
+
+use pool::{Pool, PooledConnection};
+
+/// Borrow a connection to the application database from the pool
+pub fn open(app: &tauri::AppHandle) -> Result<PooledConnection, String> {
+    let db_path = crate::get_db_path(app)?;
+    app.state::<Pool>().get(&db_path)
+}
+
+/// Drop pooled connections after the database file was replaced or removed
+pub fn release(app: &tauri::AppHandle) {
+    app.state::<Pool>().clear();
+}
+
+/// Open a connection to a database file without creating it
+pub fn open_path(path: &Path) -> Result<Connection, String> {
+    if !path.exists() {
+        return Err("Database file not found".to_string());
+    }
+
+    let conn = Connection::open_with_flags(
+        path,
+        OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_URI | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .map_err(|e| format!("Failed to open database: {}", e))?;
+
+    // Same settings the frontend applies: wait on locks instead of failing
+    // immediately, and enforce foreign keys
+    conn.busy_timeout(Duration::from_millis(30000))
+        .map_err(|e| format!("Failed to set busy timeout: {}", e))?;
+    conn.pragma_update(None, "foreign_keys", "ON")
+        .map_err(|e| format!("Failed to enable foreign keys: {}", e))?;
+
+    Ok(conn)
+}
+
+/// Read a raw setting value by key
+pub fn get_setting(conn: &Connection, key: &str) -> Result<Option<String>, String> {
+    conn.query_row("SELECT value FROM settings WHERE key = ?1", [key], |row| row.get(0))
+        .optional()
+        .map_err(|e| format!("Failed to read setting '{}': {}", key, e))
+}
+
+/// Read a JSON-encoded setting, returning None when missing or malformed
+/// (mirrors getTypedSetting on the frontend, which falls back to defaults)
+pub fn get_json_setting<T: DeserializeOwned>(conn: &Connection, key: &str) -> Result<Option<T>, String> {
+    let Some(raw) = get_setting(conn, key)? else {
+        return Ok(None);
+    };
+    match serde_json::from_str(&raw) {
+        Ok(value) => Ok(Some(value)),
+        Err(e) => {
+            log::warn!("[db] Ignoring malformed setting '{}': {}", key, e);
+            Ok(None)
+        }
+    }
+}
+
+/// Insert or update a raw setting value
+pub fn set_setting(conn: &Connection, key: &str, value: &str) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO settings (key, value, updated_at)
+         VALUES (?1, ?2, datetime('now'))
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at",
+        [key, value],
+    )
+    .map_err(|e| format!("Failed to write setting '{}': {}", key, e))?;
+    Ok(())
+}
+
+/// Delete a setting
+pub fn delete_setting(conn: &Connection, key: &str) -> Result<(), String> {
+    conn.execute("DELETE FROM settings WHERE key = ?1", [key])
+        .map_err(|e| format!("Failed to delete setting '{}': {}", key, e))?;
+    Ok(())
+}
+
+/// An in-memory database with every SQL migration applied and recorded the
+/// way tauri-plugin-sql records them
+#[cfg(test)]
+pub fn migrated() -> Connection {
+    let conn = Connection::open_in_memory().expect("in-memory database");
+    conn.execute_batch(
+        "CREATE TABLE _sqlx_migrations (version INTEGER PRIMARY KEY, description TEXT NOT NULL, success INTEGER NOT NULL)",
+    )
+    .expect("migrations table");
+    for migration in crate::get_migrations() {
+        conn.execute_batch(migration.sql)
+            .unwrap_or_else(|e| panic!("migration {} failed: {}", migration.version, e));
+        conn.execute(
+            "INSERT INTO _sqlx_migrations (version, description, success) VALUES (?1, ?2, 1)",
+            rusqlite::params![migration.version, migration.description],
+        )
+        .expect("record migration");
+    }
+    conn.pragma_update(None, "foreign_keys", "ON").expect("foreign keys");
+    conn
+}
@@ -0,0 +1,92 @@
+//! Connection pool held as Tauri managed state
+//!
+//! Opening a connection means a file open plus the busy-timeout and
+//! foreign-key pragmas, which adds up for API requests and background checks
+//! that each want one. Connections are handed out from a small idle list and
+//! go back on drop. Swapping or deleting the database file (restore, reset)
+//! must [`Pool::clear`] it so no connection keeps pointing at the old file.
+
+use rusqlite::Connection;
+use std::ops::{Deref, DerefMut};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Idle connections kept around; extra ones are closed when returned
+const MAX_IDLE: usize = 4;
+
+#[derive(Default)]
+struct Shared {
+    idle: Mutex<Vec<Connection>>,
+    /// Bumped by `clear`; connections from an older generation are closed
+    /// instead of being returned
+    generation: AtomicU64,
+}
+
+/// Pool of connections to the application database
+#[derive(Default)]
+pub struct Pool(Arc<Shared>);
+
+impl Pool {
+    /// Take an idle connection, or open a new one to `path`
+    pub fn get(&self, path: &Path) -> Result<PooledConnection, String> {
+        let generation = self.0.generation.load(Ordering::Acquire);
+        let idle = self.0.idle.lock().ok().and_then(|mut idle| idle.pop());
+        let conn = match idle {
+            Some(conn) => conn,
+            None => super::open_path(path)?,
+        };
+        Ok(PooledConnection {
+            conn: Some(conn),
+            generation,
+            pool: Arc::clone(&self.0),
+        })
+    }
+
+    /// Close idle connections and keep checked-out ones from coming back.
+    /// Call after the database file is replaced or removed.
+    pub fn clear(&self) {
+        self.0.generation.fetch_add(1, Ordering::AcqRel);
+        if let Ok(mut idle) = self.0.idle.lock() {
+            idle.clear();
+        }
+    }
+}
+
+/// A connection borrowed from the [`Pool`]; derefs to [`Connection`]
+pub struct PooledConnection {
+    conn: Option<Connection>,
+    generation: u64,
+    pool: Arc<Shared>,
+}
+
+impl Deref for PooledConnection {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn.as_ref().expect("connection is present until drop")
+    }
+}
+
+impl DerefMut for PooledConnection {
+    fn deref_mut(&mut self) -> &mut Connection {
+        self.conn.as_mut().expect("connection is present until drop")
+    }
+}
+
+impl Drop for PooledConnection {
+    fn drop(&mut self) {
+        let Some(conn) = self.conn.take() else {
+            return;
+        };
+        // A connection left mid-transaction (or from before a clear) is closed, not reused
+        if !conn.is_autocommit() || self.generation != self.pool.generation.load(Ordering::Acquire) {
+            return;
+        }
+        if let Ok(mut idle) = self.pool.idle.lock() {
+            if idle.len() < MAX_IDLE {
+                idle.push(conn);
+            }
+        }
+    }
+}
@@ -0,0 +1,92 @@
+//! Computed daily summaries (attendance_day_summary)
+
+use rusqlite::{params, Connection};
+
+use crate::summary::types::{ChangeSource, DaySummary};
+
+/// Summaries between two dates (inclusive), for one user or everyone,
+/// ordered by user then date
+pub fn between(conn: &Connection, start_date: &str, end_date: &str, user_id: Option<&str>) -> Result<Vec<DaySummary>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT user_id, date, check_in_time, check_out_time, is_incomplete,
+                    late_minutes, early_minutes, status, flags
+             FROM attendance_day_summary
+             WHERE date >= ?1 AND date <= ?2 AND (?3 IS NULL OR user_id = ?3)
+             ORDER BY user_id, date",
+        )
+        .map_err(|e| format!("Failed to query summaries: {}", e))?;
+    let rows = stmt
+        .query_map(params![start_date, end_date, user_id], |row| {
+            let flags: Option<String> = row.get(8)?;
+            Ok(DaySummary {
+                user_id: row.get(0)?,
+                date: row.get(1)?,
+                check_in_time: row.get(2)?,
+                check_out_time: row.get(3)?,
+                is_incomplete: row.get::<_, i64>(4)? != 0,
+                late_minutes: row.get(5)?,
+                early_minutes: row.get(6)?,
+                status: row.get(7)?,
+                flags: flags
+                    .and_then(|f| serde_json::from_str(&f).ok())
+                    .unwrap_or_default(),
+            })
+        })
+        .map_err(|e| format!("Failed to query summaries: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read summaries: {}", e))
+}
+
+/// Upsert summaries in a single transaction (a savepoint, so it can run
+/// inside a caller's). Replaced values go to summary_history (by trigger)
+/// tagged with `source`.
+pub fn upsert(conn: &mut Connection, summaries: &[DaySummary], source: &ChangeSource) -> Result<(), String> {
+    let now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+    let tx = conn
+        .savepoint()
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    {
+        let mut stmt = tx
+            .prepare(
+                "INSERT INTO attendance_day_summary
+                 (id, user_id, date, check_in_time, check_out_time, is_incomplete,
+                  late_minutes, early_minutes, status, flags, created_at, updated_at,
+                  change_reason, changed_by)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?11, ?12, ?13)
+                 ON CONFLICT(user_id, date) DO UPDATE SET
+                   check_in_time = excluded.check_in_time,
+                   check_out_time = excluded.check_out_time,
+                   is_incomplete = excluded.is_incomplete,
+                   late_minutes = excluded.late_minutes,
+                   early_minutes = excluded.early_minutes,
+                   status = excluded.status,
+                   flags = excluded.flags,
+                   updated_at = excluded.updated_at,
+                   change_reason = excluded.change_reason,
+                   changed_by = excluded.changed_by",
+            )
+            .map_err(|e| format!("Failed to prepare summary insert: {}", e))?;
+        for s in summaries {
+            let flags = serde_json::to_string(&s.flags).unwrap_or_else(|_| "[]".to_string());
+            stmt.execute(params![
+                uuid::Uuid::new_v4().to_string(),
+                s.user_id,
+                s.date,
+                s.check_in_time,
+                s.check_out_time,
+                s.is_incomplete as i64,
+                s.late_minutes,
+                s.early_minutes,
+                s.status,
+                flags,
+                now,
+                source.reason,
+                source.actor,
+            ])
+            .map_err(|e| format!("Failed to save summary for {} on {}: {}", s.user_id, s.date, e))?;
+        }
+    }
+    tx.commit()
+        .map_err(|e| format!("Failed to commit summaries: {}", e))
+}
@@ -0,0 +1,110 @@
+//! Users table
+
+use rusqlite::{Connection, OptionalExtension};
+
+/// Identity and status columns of a user
+#[derive(Debug, Clone)]
+pub struct UserRecord {
+    pub id: String,
+    pub device_user_id: Option<String>,
+    pub device_name: Option<String>,
+    pub display_name: String,
+    pub status: String,
+}
+
+impl UserRecord {
+    pub fn is_active(&self) -> bool {
+        self.status == "active"
+    }
+
+    /// The device user ID, unless unset or blank
+    pub fn linked_device_user_id(&self) -> Option<&str> {
+        self.device_user_id.as_deref().filter(|id| !id.is_empty())
+    }
+}
+
+const SELECT: &str = "SELECT id, device_user_id, device_name, display_name, status FROM users";
+
+fn map_row(row: &rusqlite::Row) -> rusqlite::Result<UserRecord> {
+    Ok(UserRecord {
+        id: row.get(0)?,
+        device_user_id: row.get(1)?,
+        device_name: row.get(2)?,
+        display_name: row.get(3)?,
+        status: row.get(4)?,
+    })
+}
+
+/// A user by ID
+pub fn get(conn: &Connection, id: &str) -> Result<Option<UserRecord>, String> {
+    conn.query_row(&format!("{} WHERE id = ?1", SELECT), [id], map_row)
+        .optional()
+        .map_err(|e| format!("Failed to read user: {}", e))
+}
+
+/// An active user by ID, or an error naming the ID
+pub fn get_active(conn: &Connection, id: &str) -> Result<UserRecord, String> {
+    get(conn, id)?
+        .filter(UserRecord::is_active)
+        .ok_or_else(|| format!("Active user not found: {}", id))
+}
+
+/// Every user, active or not
+pub fn list(conn: &Connection) -> Result<Vec<UserRecord>, String> {
+    let mut stmt = conn
+        .prepare(&format!("{} ORDER BY display_name", SELECT))
+        .map_err(|e| format!("Failed to query users: {}", e))?;
+    let rows = stmt
+        .query_map([], map_row)
+        .map_err(|e| format!("Failed to query users: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read users: {}", e))
+}
+
+/// A device user ID linked to a profile besides the profile's own
+#[derive(Debug, Clone)]
+pub struct DeviceLink {
+    pub device_user_id: String,
+    pub user_id: String,
+}
+
+/// Every row of user_device_links
+pub fn device_links(conn: &Connection) -> Result<Vec<DeviceLink>, String> {
+    let mut stmt = conn
+        .prepare("SELECT device_user_id, user_id FROM user_device_links")
+        .map_err(|e| format!("Failed to query device links: {}", e))?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(DeviceLink {
+                device_user_id: row.get(0)?,
+                user_id: row.get(1)?,
+            })
+        })
+        .map_err(|e| format!("Failed to query device links: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read device links: {}", e))
+}
+
+/// SQL condition that the user row `alias` counts days without punches as
+/// absences (employees; contractors and visitors don't)
+pub fn counts_absences_sql(alias: &str) -> String {
+    format!("{}.classification = 'employee'", alias)
+}
+
+/// SQL condition that the user ID expression `user_id` is tracked for
+/// hours only (contractors), without lateness or early leave
+pub fn hours_only_sql(user_id: &str) -> String {
+    format!("{} IN (SELECT id FROM users WHERE classification = 'contractor')", user_id)
+}
+
+/// IDs of users tracked for hours only
+pub fn hours_only_ids(conn: &Connection) -> Result<std::collections::HashSet<String>, String> {
+    let mut stmt = conn
+        .prepare("SELECT id FROM users WHERE classification = 'contractor'")
+        .map_err(|e| format!("Failed to query users: {}", e))?;
+    let rows = stmt
+        .query_map([], |row| row.get(0))
+        .map_err(|e| format!("Failed to query users: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read users: {}", e))
+}
@@ -0,0 +1,99 @@
+//! Tauri command handlers for the outbound delivery log.
+
+use std::time::Duration;
+
+use super::types::*;
+use super::{retry, store};
+use crate::activity::registry;
+use crate::db;
+use crate::jobs;
+
+const DEFAULT_LIMIT: u32 = 200;
+const MAX_LIMIT: u32 = 5000;
+
+/// How often due retries are sent
+const RETRY_INTERVAL: Duration = Duration::from_secs(300);
+
+/// ID of the retry job in the jobs registry
+pub const JOB_ID: &str = "delivery_retries";
+
+/// Logged deliveries, newest first, optionally by status and channel
+#[tauri::command]
+pub async fn list_deliveries(
+    app: tauri::AppHandle,
+    status: Option<String>,
+    channel: Option<String>,
+    limit: Option<u32>,
+) -> Result<Vec<Delivery>, String> {
+    if let Some(status) = status.as_deref() {
+        if ![store::STATUS_PENDING, store::STATUS_DELIVERED, store::STATUS_FAILED].contains(&status) {
+            return Err(format!("Unknown delivery status: {}", status));
+        }
+    }
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    store::list(&*db::open(&app)?, status.as_deref(), channel.as_deref(), limit)
+}
+
+/// Delivery counts by status
+#[tauri::command]
+pub async fn get_delivery_stats(app: tauri::AppHandle) -> Result<DeliveryStats, String> {
+    store::stats(&*db::open(&app)?)
+}
+
+/// Send failed deliveries again now, whether or not their automatic retry
+/// is due or their attempts have run out
+#[tauri::command]
+pub async fn retry_deliveries(app: tauri::AppHandle, ids: Vec<i64>) -> Result<Vec<RetryOutcome>, String> {
+    log::info!("[deliveries::cmd] retry_deliveries {:?}", ids);
+    tauri::async_runtime::spawn_blocking(move || {
+        let _activity = registry::begin(&app, "Delivery retry")?;
+        let conn = db::open(&app)?;
+        let mut outcomes = Vec::with_capacity(ids.len());
+        for id in ids {
+            let delivery = store::get(&conn, id)?.ok_or_else(|| format!("Delivery not found: {}", id))?;
+            if delivery.status != store::STATUS_FAILED {
+                return Err(format!("Delivery {} is {}, not failed", id, delivery.status));
+            }
+            let result = retry::attempt(&app, &conn, &delivery)?;
+            outcomes.push(RetryOutcome {
+                id,
+                delivered: result.is_ok(),
+                error: result.err(),
+            });
+        }
+        Ok(outcomes)
+    })
+    .await
+    .map_err(|e| format!("Retry task failed: {}", e))?
+}
+
+/// Send due retries every few minutes and emit `deliveries-updated` when
+/// any went through. Runs for the lifetime of the app.
+pub async fn run_background_retries(app: tauri::AppHandle) {
+    use tauri::Emitter;
+
+    let job = jobs::registry::register(
+        &app,
+        JOB_ID,
+        "Delivery retries",
+        "Resends failed webhook calls and emails as their retries come due",
+        RETRY_INTERVAL,
+    );
+    let handle = app.clone();
+    jobs::registry::drive_blocking(
+        job,
+        move |_| {
+            let _activity = registry::begin(&handle, "Delivery retry")?;
+            retry::retry_due(&handle, &*db::open(&handle)?)
+        },
+        |delivered| {
+            if delivered > 0 {
+                log::info!("[deliveries] Retried {} deliveries successfully", delivered);
+                if let Err(e) = app.emit("deliveries-updated", delivered) {
+                    log::warn!("[deliveries] Failed to emit deliveries update: {}", e);
+                }
+            }
+        },
+    )
+    .await;
+}
@@ -0,0 +1,15 @@
+//! Outbound delivery log
+//!
+//! Every message the app sends to another system (emails, webhook calls and
+//! changes queued for offline terminals; MQTT publishes will share the same
+//! table) is logged with a short summary, the payload needed to send it
+//! again, and each attempt's result. Failed deliveries are retried in the
+//! background with exponential backoff until they run out of attempts, and
+//! can be retried by hand from the log, so a message that never arrived can
+//! be traced and replayed.
+
+pub mod commands;
+pub mod retry;
+pub mod store;
+pub mod types;
+pub mod webhook;
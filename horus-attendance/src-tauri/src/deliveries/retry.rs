@@ -0,0 +1,54 @@
+//! Sending logged deliveries again
+
+use rusqlite::Connection;
+
+use super::store;
+use super::types::Delivery;
+use crate::closure;
+use crate::device_names;
+use crate::points;
+use crate::staffing;
+use crate::watchdog;
+use crate::working_time;
+
+/// Send a delivery again through whatever produced it, and record the
+/// attempt. Blocks on the network.
+pub fn attempt(app: &tauri::AppHandle, conn: &Connection, delivery: &Delivery) -> Result<Result<(), String>, String> {
+    let result = match (delivery.channel.as_str(), delivery.payload["kind"].as_str()) {
+        (store::CHANNEL_EMAIL, Some(closure::job::DELIVERY_KIND)) => {
+            closure::job::redeliver(app, conn, &delivery.payload)
+        }
+        (store::CHANNEL_DEVICE, Some(device_names::push::DELIVERY_KIND)) => {
+            device_names::push::redeliver(conn, &delivery.target, &delivery.payload)
+        }
+        (channel, Some(watchdog::notify::DELIVERY_KIND)) => {
+            watchdog::notify::redeliver(conn, channel, &delivery.target, &delivery.payload)
+        }
+        (channel, Some(points::check::DELIVERY_KIND)) => {
+            points::check::redeliver(channel, &delivery.target, &delivery.payload)
+        }
+        (channel, Some(staffing::check::DELIVERY_KIND)) => {
+            staffing::check::redeliver(channel, &delivery.target, &delivery.payload)
+        }
+        (channel, Some(working_time::check::DELIVERY_KIND)) => {
+            working_time::check::redeliver(channel, &delivery.target, &delivery.payload)
+        }
+        (channel, kind) => Err(format!("Don't know how to resend {} deliveries of kind {:?}", channel, kind)),
+    };
+    store::finish(conn, delivery.id, &result)?;
+    if let Err(e) = &result {
+        log::warn!("[deliveries] Retry of delivery {} failed: {}", delivery.id, e);
+    }
+    Ok(result)
+}
+
+/// Retry every failed delivery that is due. Returns how many went through.
+pub fn retry_due(app: &tauri::AppHandle, conn: &Connection) -> Result<u32, String> {
+    let mut delivered = 0;
+    for delivery in store::due(conn)? {
+        if attempt(app, conn, &delivery)?.is_ok() {
+            delivered += 1;
+        }
+    }
+    Ok(delivered)
+}
@@ -0,0 +1,150 @@
+//! Storage for the outbound delivery log
+
+use chrono::{Duration, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+
+use super::types::*;
+
+pub const CHANNEL_EMAIL: &str = "email";
+pub const CHANNEL_WEBHOOK: &str = "webhook";
+/// Changes for a terminal that couldn't be reached; the target is the device ID
+pub const CHANNEL_DEVICE: &str = "device";
+
+pub const STATUS_PENDING: &str = "pending";
+pub const STATUS_DELIVERED: &str = "delivered";
+pub const STATUS_FAILED: &str = "failed";
+
+/// Attempts before a delivery is left for a manual retry
+pub const MAX_ATTEMPTS: u32 = 5;
+
+/// Wait before the first automatic retry; doubles with each attempt
+const FIRST_RETRY_MINUTES: i64 = 5;
+
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Log a delivery before attempting it. Returns its ID.
+pub fn begin(conn: &Connection, delivery: &NewDelivery) -> Result<i64, String> {
+    conn.execute(
+        "INSERT INTO outbound_deliveries (channel, target, summary, payload, status) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            delivery.channel,
+            delivery.target,
+            delivery.summary,
+            delivery.payload.to_string(),
+            STATUS_PENDING
+        ],
+    )
+    .map_err(|e| format!("Failed to log delivery: {}", e))?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Record the result of an attempt, scheduling the next automatic retry
+/// after a failure while attempts remain
+pub fn finish(conn: &Connection, id: i64, result: &Result<(), String>) -> Result<(), String> {
+    let attempts: u32 = conn
+        .query_row("SELECT attempts FROM outbound_deliveries WHERE id = ?1", [id], |row| row.get(0))
+        .map_err(|e| format!("Failed to read delivery {}: {}", id, e))?;
+    let attempts = attempts + 1;
+    let updated = match result {
+        Ok(()) => conn.execute(
+            "UPDATE outbound_deliveries SET status = ?2, attempts = ?3, last_error = NULL, next_retry_at = NULL,
+                 updated_at = datetime('now'), delivered_at = datetime('now')
+             WHERE id = ?1",
+            params![id, STATUS_DELIVERED, attempts],
+        ),
+        Err(error) => {
+            let next_retry_at = (attempts < MAX_ATTEMPTS).then(|| {
+                let wait = Duration::minutes(FIRST_RETRY_MINUTES << (attempts - 1).min(10));
+                (Utc::now() + wait).format(TIMESTAMP_FORMAT).to_string()
+            });
+            conn.execute(
+                "UPDATE outbound_deliveries SET status = ?2, attempts = ?3, last_error = ?4, next_retry_at = ?5,
+                     updated_at = datetime('now')
+                 WHERE id = ?1",
+                params![id, STATUS_FAILED, attempts, error, next_retry_at],
+            )
+        }
+    };
+    updated.map_err(|e| format!("Failed to record delivery attempt: {}", e))?;
+    Ok(())
+}
+
+const SELECT: &str = "SELECT id, channel, target, summary, payload, status, attempts, last_error, next_retry_at,
+                             created_at, updated_at, delivered_at
+                      FROM outbound_deliveries";
+
+fn map_row(row: &rusqlite::Row) -> rusqlite::Result<Delivery> {
+    let payload: String = row.get(4)?;
+    Ok(Delivery {
+        id: row.get(0)?,
+        channel: row.get(1)?,
+        target: row.get(2)?,
+        summary: row.get(3)?,
+        payload: serde_json::from_str(&payload).unwrap_or(serde_json::Value::Null),
+        status: row.get(5)?,
+        attempts: row.get(6)?,
+        last_error: row.get(7)?,
+        next_retry_at: row.get(8)?,
+        created_at: row.get(9)?,
+        updated_at: row.get(10)?,
+        delivered_at: row.get(11)?,
+    })
+}
+
+/// Deliveries, newest first, optionally by status and channel
+pub fn list(conn: &Connection, status: Option<&str>, channel: Option<&str>, limit: u32) -> Result<Vec<Delivery>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "{} WHERE (?1 IS NULL OR status = ?1) AND (?2 IS NULL OR channel = ?2) ORDER BY id DESC LIMIT ?3",
+            SELECT
+        ))
+        .map_err(|e| format!("Failed to query deliveries: {}", e))?;
+    let rows = stmt
+        .query_map(params![status, channel, limit], map_row)
+        .map_err(|e| format!("Failed to query deliveries: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read deliveries: {}", e))
+}
+
+/// A delivery by ID
+pub fn get(conn: &Connection, id: i64) -> Result<Option<Delivery>, String> {
+    conn.query_row(&format!("{} WHERE id = ?1", SELECT), [id], map_row)
+        .optional()
+        .map_err(|e| format!("Failed to read delivery: {}", e))
+}
+
+/// Failed deliveries whose automatic retry is due, oldest first
+pub fn due(conn: &Connection) -> Result<Vec<Delivery>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "{} WHERE status = ?1 AND next_retry_at IS NOT NULL AND next_retry_at <= ?2 ORDER BY next_retry_at",
+            SELECT
+        ))
+        .map_err(|e| format!("Failed to query deliveries: {}", e))?;
+    let now = Utc::now().format(TIMESTAMP_FORMAT).to_string();
+    let rows = stmt
+        .query_map(params![STATUS_FAILED, now], map_row)
+        .map_err(|e| format!("Failed to query deliveries: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read deliveries: {}", e))
+}
+
+/// Counts by status
+pub fn stats(conn: &Connection) -> Result<DeliveryStats, String> {
+    conn.query_row(
+        "SELECT COALESCE(SUM(status = 'pending'), 0), COALESCE(SUM(status = 'delivered'), 0),
+                COALESCE(SUM(status = 'failed'), 0),
+                COALESCE(SUM(status = 'failed' AND next_retry_at IS NOT NULL), 0)
+         FROM outbound_deliveries",
+        [],
+        |row| {
+            Ok(DeliveryStats {
+                pending: row.get(0)?,
+                delivered: row.get(1)?,
+                failed: row.get(2)?,
+                retrying: row.get(3)?,
+            })
+        },
+    )
+    .map_err(|e| format!("Failed to count deliveries: {}", e))
+}
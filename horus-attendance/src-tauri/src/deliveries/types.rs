@@ -0,0 +1,54 @@
+//! Types for the outbound delivery log
+
+use serde::Serialize;
+
+/// A logged outbound message
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Delivery {
+    pub id: i64,
+    /// email, webhook, device or mqtt
+    pub channel: String,
+    /// Address, URL, device ID or topic it went to
+    pub target: String,
+    pub summary: String,
+    /// What the sender needs to send it again
+    pub payload: serde_json::Value,
+    /// pending, delivered or failed
+    pub status: String,
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    /// When it is retried automatically; None once delivered or out of attempts
+    pub next_retry_at: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+    pub delivered_at: Option<String>,
+}
+
+/// A delivery about to be attempted
+pub struct NewDelivery<'a> {
+    pub channel: &'static str,
+    pub target: &'a str,
+    pub summary: String,
+    pub payload: serde_json::Value,
+}
+
+/// Counts for the delivery dashboard
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeliveryStats {
+    pub pending: u32,
+    pub delivered: u32,
+    pub failed: u32,
+    /// Failed deliveries that will be retried automatically
+    pub retrying: u32,
+}
+
+/// Result of retrying one delivery by hand
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetryOutcome {
+    pub id: i64,
+    pub delivered: bool,
+    pub error: Option<String>,
+}
@@ -0,0 +1,31 @@
+//! Posting JSON to webhooks
+
+use std::time::Duration;
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Check a webhook URL before saving it
+pub fn validate_url(url: &str) -> Result<(), String> {
+    let url = url.trim();
+    if !(url.starts_with("https://") || url.starts_with("http://")) || url.len() <= "https://".len() {
+        return Err(format!("Webhook URL must start with http:// or https://: {}", url));
+    }
+    Ok(())
+}
+
+/// POST `body` to `url`; any 2xx response counts as delivered. Blocks on the
+/// network.
+pub fn post(url: &str, body: &serde_json::Value) -> Result<(), String> {
+    validate_url(url)?;
+    let agent = ureq::AgentBuilder::new().timeout(WEBHOOK_TIMEOUT).build();
+    match agent
+        .post(url.trim())
+        .set("Content-Type", "application/json")
+        .set("User-Agent", concat!("horus-attendance/", env!("CARGO_PKG_VERSION")))
+        .send_string(&body.to_string())
+    {
+        Ok(_) => Ok(()),
+        Err(ureq::Error::Status(code, _)) => Err(format!("Webhook answered HTTP {}", code)),
+        Err(e) => Err(format!("Failed to reach webhook: {}", e)),
+    }
+}
@@ -0,0 +1,68 @@
+//! Tauri command handlers for demo devices.
+
+use super::store;
+use super::types::*;
+use crate::db;
+
+/// Demo devices and their profiles
+#[tauri::command]
+pub async fn list_demo_devices(app: tauri::AppHandle) -> Result<Vec<DemoDevice>, String> {
+    store::list(&*db::open(&app)?)
+}
+
+/// Add a demo device. It syncs like any other device; the first sync
+/// brings in its users and `historyDays` of punches.
+#[tauri::command]
+pub async fn create_demo_device(
+    app: tauri::AppHandle,
+    name: String,
+    timezone: Option<String>,
+    profile: Option<DemoProfile>,
+) -> Result<DemoDevice, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Device name is required".to_string());
+    }
+    let timezone = timezone.unwrap_or_else(|| "UTC".to_string());
+    let device = store::create(&mut *db::open(&app)?, name, &timezone, profile.unwrap_or_default())?;
+    log::info!(
+        "[demo::cmd] Created demo device {} at {} with {} users",
+        device.device_id,
+        device.ip,
+        device.profile.user_count
+    );
+    Ok(device)
+}
+
+/// Change what a demo device generates
+#[tauri::command]
+pub async fn update_demo_device(
+    app: tauri::AppHandle,
+    device_id: String,
+    profile: DemoProfile,
+) -> Result<DemoDevice, String> {
+    let device = store::update_profile(&*db::open(&app)?, &device_id, profile)?;
+    log::info!("[demo::cmd] Updated demo device {}", device_id);
+    Ok(device)
+}
+
+/// Remove a demo device and everything synced from it
+#[tauri::command]
+pub async fn delete_demo_device(app: tauri::AppHandle, device_id: String) -> Result<(), String> {
+    store::delete(&*db::open(&app)?, &device_id)?;
+    log::info!("[demo::cmd] Deleted demo device {}", device_id);
+    Ok(())
+}
+
+/// Make saved demo devices answer connections
+pub fn install_on_launch(app: &tauri::AppHandle) {
+    // No database yet on first launch
+    let Ok(conn) = db::open(app) else {
+        return;
+    };
+    match store::install(&conn) {
+        Ok(0) => {}
+        Ok(count) => log::info!("[demo] {} demo devices available", count),
+        Err(e) => log::warn!("[demo] Failed to load demo devices: {}", e),
+    }
+}
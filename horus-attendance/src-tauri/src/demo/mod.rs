@@ -0,0 +1,17 @@
+//! Demo devices
+//!
+//! A demo device is a normal devices row on a TEST-NET-1 address
+//! (192.0.2.0/24, reserved for documentation, so never a real terminal)
+//! with a generation profile in demo_devices. The device client answers
+//! connections to those addresses itself (see `zkteco::demo`), inventing
+//! users and punches from the profile, so sync, initial import, audits and
+//! reports run exactly as they would against hardware. Sales demos and
+//! new-admin training then need neither a terminal nor production data.
+//!
+//! Generation is seeded: the same profile always yields the same people and
+//! punches, and new punches appear as the clock moves on, like a device in
+//! use.
+
+pub mod commands;
+pub mod store;
+pub mod types;
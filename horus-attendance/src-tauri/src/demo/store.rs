@@ -0,0 +1,169 @@
+//! Demo device storage
+
+use chrono::{Duration, Local, NaiveTime};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashSet;
+
+use super::types::{DemoDevice, DemoProfile};
+use crate::zkteco::demo;
+
+/// Demo devices get 192.0.2.1 onwards
+const ADDRESS_PREFIX: &str = "192.0.2.";
+
+const MAX_USERS: u32 = 500;
+const MAX_HISTORY_DAYS: u32 = 366;
+
+fn parse_time(value: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(value, "%H:%M").map_err(|_| format!("Invalid time (expected HH:MM): {}", value))
+}
+
+fn validate(profile: &DemoProfile) -> Result<(), String> {
+    if !(1..=MAX_USERS).contains(&profile.user_count) {
+        return Err(format!("User count must be between 1 and {}", MAX_USERS));
+    }
+    if profile.history_days > MAX_HISTORY_DAYS {
+        return Err(format!("History can be at most {} days", MAX_HISTORY_DAYS));
+    }
+    if parse_time(&profile.work_start)? >= parse_time(&profile.work_end)? {
+        return Err("Work must end after it starts".to_string());
+    }
+    if profile.work_days.is_empty() || profile.work_days.iter().any(|d| !(1..=7).contains(d)) {
+        return Err("Work days must be weekdays 1 (Monday) to 7 (Sunday)".to_string());
+    }
+    let rates = [
+        ("Late", profile.late_rate),
+        ("Absence", profile.absence_rate),
+        ("Missed punch", profile.missed_punch_rate),
+        ("Overtime", profile.overtime_rate),
+    ];
+    for (name, rate) in rates {
+        if !(0.0..=1.0).contains(&rate) {
+            return Err(format!("{} rate must be between 0 and 1", name));
+        }
+    }
+    Ok(())
+}
+
+fn from_row(row: &rusqlite::Row) -> rusqlite::Result<(DemoDevice, String)> {
+    let profile: String = row.get(3)?;
+    Ok((
+        DemoDevice {
+            device_id: row.get(0)?,
+            name: row.get(1)?,
+            ip: row.get(2)?,
+            profile: DemoProfile::default(),
+            created_at: row.get(4)?,
+        },
+        profile,
+    ))
+}
+
+fn parse_profile((mut device, profile): (DemoDevice, String)) -> Result<DemoDevice, String> {
+    device.profile = serde_json::from_str(&profile)
+        .map_err(|e| format!("Invalid profile for demo device {}: {}", device.device_id, e))?;
+    Ok(device)
+}
+
+const SELECT: &str = "SELECT d.id, d.name, d.ip, dd.profile, dd.created_at
+                      FROM demo_devices dd JOIN devices d ON d.id = dd.device_id";
+
+pub fn list(conn: &Connection) -> Result<Vec<DemoDevice>, String> {
+    let mut stmt = conn
+        .prepare(&format!("{} ORDER BY d.name", SELECT))
+        .map_err(|e| format!("Failed to prepare query: {}", e))?;
+    let rows = stmt
+        .query_map([], from_row)
+        .map_err(|e| format!("Failed to query demo devices: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read demo device: {}", e))?;
+    rows.into_iter().map(parse_profile).collect()
+}
+
+pub fn get(conn: &Connection, device_id: &str) -> Result<Option<DemoDevice>, String> {
+    conn.query_row(&format!("{} WHERE d.id = ?1", SELECT), [device_id], from_row)
+        .optional()
+        .map_err(|e| format!("Failed to read demo device: {}", e))?
+        .map(parse_profile)
+        .transpose()
+}
+
+/// Hand every demo device's profile to the device client
+pub fn install(conn: &Connection) -> Result<usize, String> {
+    let devices = list(conn)?;
+    let count = devices.len();
+    demo::install(devices.into_iter().map(|d| (d.ip, d.profile)).collect());
+    Ok(count)
+}
+
+/// First TEST-NET-1 address no device uses
+fn free_address(conn: &Connection) -> Result<String, String> {
+    let mut stmt = conn
+        .prepare("SELECT ip FROM devices WHERE ip LIKE '192.0.2.%'")
+        .map_err(|e| format!("Failed to prepare query: {}", e))?;
+    let used = stmt
+        .query_map([], |row| row.get(0))
+        .map_err(|e| format!("Failed to query devices: {}", e))?
+        .collect::<Result<HashSet<String>, _>>()
+        .map_err(|e| format!("Failed to read device: {}", e))?;
+    (1..=254)
+        .map(|host| format!("{}{}", ADDRESS_PREFIX, host))
+        .find(|ip| !used.contains(ip))
+        .ok_or_else(|| "No addresses left for demo devices".to_string())
+}
+
+/// Add a device row and its profile. The history is anchored here, so the
+/// device keeps the punches it started with as new ones accumulate.
+pub fn create(conn: &mut Connection, name: &str, timezone: &str, mut profile: DemoProfile) -> Result<DemoDevice, String> {
+    validate(&profile)?;
+    let start = Local::now().date_naive() - Duration::days(profile.history_days as i64);
+    profile.start_date = Some(start.format("%Y-%m-%d").to_string());
+    let json = serde_json::to_string(&profile).map_err(|e| format!("Failed to serialize profile: {}", e))?;
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    let ip = free_address(&tx)?;
+    tx.execute(
+        "INSERT INTO devices (id, name, ip, port, comm_key, timezone, sync_mode) VALUES (?1, ?2, ?3, 4370, '', ?4, 'manual')",
+        params![id, name, ip, timezone],
+    )
+    .map_err(|e| format!("Failed to save device: {}", e))?;
+    tx.execute(
+        "INSERT INTO demo_devices (device_id, profile) VALUES (?1, ?2)",
+        params![id, json],
+    )
+    .map_err(|e| format!("Failed to save demo device: {}", e))?;
+    tx.commit().map_err(|e| format!("Failed to commit demo device: {}", e))?;
+
+    install(conn)?;
+    get(conn, &id)?.ok_or_else(|| "Demo device disappeared".to_string())
+}
+
+/// Change what a demo device generates. Its start date stays, so punches
+/// already synced keep matching unless the seed or patterns change.
+pub fn update_profile(conn: &Connection, device_id: &str, mut profile: DemoProfile) -> Result<DemoDevice, String> {
+    validate(&profile)?;
+    let existing = get(conn, device_id)?.ok_or_else(|| format!("Demo device not found: {}", device_id))?;
+    profile.start_date = existing.profile.start_date;
+    let json = serde_json::to_string(&profile).map_err(|e| format!("Failed to serialize profile: {}", e))?;
+    conn.execute(
+        "UPDATE demo_devices SET profile = ?1 WHERE device_id = ?2",
+        params![json, device_id],
+    )
+    .map_err(|e| format!("Failed to save demo device: {}", e))?;
+
+    install(conn)?;
+    get(conn, device_id)?.ok_or_else(|| "Demo device disappeared".to_string())
+}
+
+/// Remove a demo device along with everything synced from it
+pub fn delete(conn: &Connection, device_id: &str) -> Result<(), String> {
+    if get(conn, device_id)?.is_none() {
+        return Err(format!("Demo device not found: {}", device_id));
+    }
+    conn.execute("DELETE FROM devices WHERE id = ?1", [device_id])
+        .map_err(|e| format!("Failed to delete device: {}", e))?;
+    install(conn)?;
+    Ok(())
+}
@@ -0,0 +1,60 @@
+//! Demo device data types for Tauri command serialization
+
+use serde::{Deserialize, Serialize};
+
+/// What a demo device generates
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DemoProfile {
+    /// Enrolled users, 1-500
+    pub user_count: u32,
+    /// Days of punches already on the device when it is created, up to 366
+    pub history_days: u32,
+    /// Scheduled start and end, HH:MM
+    pub work_start: String,
+    pub work_end: String,
+    /// ISO weekdays people work (1 = Monday)
+    pub work_days: Vec<u32>,
+    /// Share of working days someone arrives late
+    pub late_rate: f64,
+    /// Share of working days someone doesn't come in
+    pub absence_rate: f64,
+    /// Share of working days someone forgets a punch
+    pub missed_punch_rate: f64,
+    /// Share of working days someone stays late
+    pub overtime_rate: f64,
+    /// Same seed, same people and punches
+    pub seed: u32,
+    /// First day with punches, YYYY-MM-DD. Set when the device is created.
+    pub start_date: Option<String>,
+}
+
+impl Default for DemoProfile {
+    fn default() -> Self {
+        Self {
+            user_count: 25,
+            history_days: 30,
+            work_start: "08:00".to_string(),
+            work_end: "17:00".to_string(),
+            work_days: vec![1, 2, 3, 4, 5],
+            late_rate: 0.1,
+            absence_rate: 0.04,
+            missed_punch_rate: 0.02,
+            overtime_rate: 0.1,
+            seed: 1,
+            start_date: None,
+        }
+    }
+}
+
+/// A demo device and its profile
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DemoDevice {
+    pub device_id: String,
+    pub name: String,
+    /// Address the client recognises it by
+    pub ip: String,
+    pub profile: DemoProfile,
+    pub created_at: String,
+}
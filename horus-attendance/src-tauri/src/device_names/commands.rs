@@ -0,0 +1,119 @@
+//! Tauri command handlers for pushing names to devices.
+
+use super::push::{self, DELIVERY_KIND, STATUS_FAILED, STATUS_UPDATED};
+use super::store;
+use super::types::*;
+use crate::activity::registry;
+use crate::db;
+use crate::deliveries::{self, types::NewDelivery};
+use crate::zkteco::commands::{resolve_comm_key, saved_config, validate_config};
+
+/// Push every name to one saved device
+async fn push_to(app: &tauri::AppHandle, device_id: String, device_name: String, names: &[DeviceName]) -> DeviceNamesResult {
+    let outcome = async {
+        let (config, utf8) = {
+            let conn = db::open(app)?;
+            (saved_config(&conn, &device_id)?, push::utf8_names(&conn, &device_id))
+        };
+        validate_config(&config)?;
+        push::push(&resolve_comm_key(config).await?, names, utf8).await
+    }
+    .await;
+
+    let (users, error, retry) = match outcome {
+        Ok(users) => {
+            let failed: Vec<DeviceName> = users
+                .iter()
+                .filter(|u| u.status == STATUS_FAILED)
+                .map(|u| DeviceName {
+                    device_user_id: u.device_user_id.clone(),
+                    name: u.name.clone(),
+                })
+                .collect();
+            let error = users
+                .iter()
+                .find_map(|u| u.error.as_ref().filter(|_| u.status == STATUS_FAILED))
+                .map(|e| format!("Some names could not be written: {}", e));
+            (users, error, failed)
+        }
+        Err(e) => (Vec::new(), Some(e), names.to_vec()),
+    };
+    let retry_delivery_id = match (&error, retry.is_empty()) {
+        (Some(error), false) => match queue_retry(app, &device_id, &device_name, &retry, error) {
+            Ok(id) => Some(id),
+            Err(e) => {
+                log::warn!("[device_names] Could not queue a retry for {}: {}", device_name, e);
+                None
+            }
+        },
+        _ => None,
+    };
+    if let Some(e) = &error {
+        log::warn!("[device_names] {}: {}", device_name, e);
+    }
+    DeviceNamesResult {
+        device_id,
+        device_name,
+        success: error.is_none(),
+        error,
+        users,
+        retry_delivery_id,
+    }
+}
+
+/// Log the names that didn't reach a device as a failed delivery, so the
+/// delivery retries send them again
+fn queue_retry(
+    app: &tauri::AppHandle,
+    device_id: &str,
+    device_name: &str,
+    names: &[DeviceName],
+    error: &str,
+) -> Result<i64, String> {
+    let conn = db::open(app)?;
+    let id = deliveries::store::begin(
+        &conn,
+        &NewDelivery {
+            channel: deliveries::store::CHANNEL_DEVICE,
+            target: device_id,
+            summary: format!("Names of {} user(s) for {}", names.len(), device_name),
+            payload: serde_json::json!({ "kind": DELIVERY_KIND, "names": names }),
+        },
+    )?;
+    deliveries::store::finish(&conn, id, &Err(error.to_string()))?;
+    Ok(id)
+}
+
+/// Write each active user's display name to every saved device, under their
+/// own and linked device user IDs. What fails is retried from the delivery
+/// log.
+#[tauri::command]
+pub async fn push_names_to_all_devices(app: tauri::AppHandle) -> Result<NamePushReport, String> {
+    let _activity = registry::begin(&app, "Name push to devices")?;
+    let (names, devices) = {
+        let conn = db::open(&app)?;
+        (store::names(&conn)?, store::devices(&conn)?)
+    };
+    if devices.is_empty() {
+        return Err("No devices are set up".to_string());
+    }
+    log::info!("[device_names] Pushing {} name(s) to {} device(s)", names.len(), devices.len());
+
+    let mut report = NamePushReport {
+        names: names.len() as u32,
+        updated: 0,
+        failed: 0,
+        devices: Vec::with_capacity(devices.len()),
+    };
+    for (device_id, device_name) in devices {
+        let result = push_to(&app, device_id, device_name, &names).await;
+        report.updated += result.users.iter().filter(|u| u.status == STATUS_UPDATED).count() as u32;
+        report.failed += if result.users.is_empty() && result.error.is_some() {
+            names.len() as u32
+        } else {
+            result.users.iter().filter(|u| u.status == STATUS_FAILED).count() as u32
+        };
+        report.devices.push(result);
+    }
+    Ok(report)
+}
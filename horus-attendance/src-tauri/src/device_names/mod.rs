@@ -0,0 +1,17 @@
+//! Pushing user names to the terminals
+//!
+//! Terminals show whatever name a user was enrolled with, often a
+//! placeholder. After proper names are imported (CSV, LDAP) one push writes
+//! each active user's display name to every saved terminal under their own
+//! and linked device user IDs. Users are rewritten from the record the
+//! device returns, so privilege, password, card and group stay as they
+//! were; names a device already has are left alone. Non-Latin names are
+//! only written to devices with UTF-8 names.
+//!
+//! Devices that can't be reached, and users whose write failed, go to the
+//! outbound delivery log on the `device` channel and are retried with it.
+
+pub mod commands;
+pub mod push;
+pub mod store;
+pub mod types;
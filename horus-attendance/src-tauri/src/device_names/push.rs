@@ -0,0 +1,106 @@
+//! Writing names to a device
+
+use rusqlite::Connection;
+
+use super::types::{DeviceName, UserNameResult};
+use crate::zkteco::capabilities::{self, FEATURE_UTF8_NAMES};
+use crate::zkteco::client::ZKClient;
+use crate::zkteco::commands::{resolve_comm_key, saved_config, validate_config};
+use crate::zkteco::protocol::{decode_user_record, rename_user_record};
+use crate::zkteco::types::DeviceConfig;
+
+pub const STATUS_UPDATED: &str = "updated";
+pub const STATUS_UNCHANGED: &str = "unchanged";
+pub const STATUS_NOT_ON_DEVICE: &str = "not_on_device";
+pub const STATUS_SKIPPED: &str = "skipped";
+pub const STATUS_FAILED: &str = "failed";
+
+/// `kind` of name retries in the delivery log
+pub const DELIVERY_KIND: &str = "device_user_names";
+
+fn result(name: &DeviceName, status: &str, error: Option<String>) -> UserNameResult {
+    UserNameResult {
+        device_user_id: name.device_user_id.clone(),
+        name: name.name.clone(),
+        status: status.to_string(),
+        error,
+    }
+}
+
+/// Whether names with non-ASCII characters can go to a saved device.
+/// Devices that weren't probed are given the benefit of the doubt.
+pub fn utf8_names(conn: &Connection, device_id: &str) -> bool {
+    capabilities::require(conn, Some(device_id), FEATURE_UTF8_NAMES).is_ok()
+}
+
+/// Write `names` to the device, one result per name. Fails only when the
+/// device can't be reached or its users can't be read.
+pub async fn push(config: &DeviceConfig, names: &[DeviceName], utf8: bool) -> Result<Vec<UserNameResult>, String> {
+    let mut client = ZKClient::connect(config).await?;
+    let outcome = async {
+        let records = client.get_user_records().await?;
+        let mut results = Vec::with_capacity(names.len());
+        for name in names {
+            let Some(record) = records.get(&name.device_user_id) else {
+                results.push(result(name, STATUS_NOT_ON_DEVICE, None));
+                continue;
+            };
+            if decode_user_record(record).2 == name.name {
+                results.push(result(name, STATUS_UNCHANGED, None));
+                continue;
+            }
+            if !utf8 && !name.name.is_ascii() {
+                let error = "This device's firmware doesn't support non-Latin user names".to_string();
+                results.push(result(name, STATUS_SKIPPED, Some(error)));
+                continue;
+            }
+            let renamed = match rename_user_record(record, &name.name) {
+                Ok(renamed) => renamed,
+                Err(e) => {
+                    results.push(result(name, STATUS_SKIPPED, Some(e)));
+                    continue;
+                }
+            };
+            results.push(match client.write_user_record(&renamed).await {
+                Ok(()) => result(name, STATUS_UPDATED, None),
+                Err(e) => result(name, STATUS_FAILED, Some(e)),
+            });
+        }
+        if results.iter().any(|r| r.status == STATUS_UPDATED) {
+            // Until the refresh the device keeps showing the old names
+            if let Err(e) = client.refresh_data().await {
+                log::warn!("[device_names] {}: refresh after writing names failed: {}", config.ip, e);
+            }
+        }
+        Ok(results)
+    }
+    .await;
+    let _ = client.disconnect().await;
+    outcome
+}
+
+/// Names from a delivery log payload
+fn payload_names(payload: &serde_json::Value) -> Result<Vec<DeviceName>, String> {
+    serde_json::from_value(payload["names"].clone()).map_err(|e| format!("Invalid name retry: {}", e))
+}
+
+/// Push the names in a logged retry to the saved device `device_id` again.
+/// Blocks on the network; fails if any write still fails.
+pub fn redeliver(conn: &Connection, device_id: &str, payload: &serde_json::Value) -> Result<(), String> {
+    let names = payload_names(payload)?;
+    let config = saved_config(conn, device_id)?;
+    validate_config(&config)?;
+    let utf8 = utf8_names(conn, device_id);
+    let results = tauri::async_runtime::block_on(async { push(&resolve_comm_key(config).await?, &names, utf8).await })?;
+    let failed: Vec<&UserNameResult> = results.iter().filter(|r| r.status == STATUS_FAILED).collect();
+    match failed.first() {
+        None => Ok(()),
+        Some(first) => Err(format!(
+            "{} of {} names failed, e.g. {}: {}",
+            failed.len(),
+            names.len(),
+            first.device_user_id,
+            first.error.as_deref().unwrap_or("unknown error")
+        )),
+    }
+}
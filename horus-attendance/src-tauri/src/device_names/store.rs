@@ -0,0 +1,46 @@
+//! Names and devices for a name push
+
+use rusqlite::Connection;
+
+use super::types::DeviceName;
+
+/// Each active user's display name under their own device user ID and every
+/// linked one
+pub fn names(conn: &Connection) -> Result<Vec<DeviceName>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT device_user_id, display_name FROM users
+             WHERE status = 'active' AND COALESCE(device_user_id, '') != ''
+             UNION
+             SELECT k.device_user_id, u.display_name FROM user_device_links k
+             JOIN users u ON u.id = k.user_id
+             WHERE u.status = 'active'
+             ORDER BY 1",
+        )
+        .map_err(|e| format!("Failed to query user names: {}", e))?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(DeviceName {
+                device_user_id: row.get(0)?,
+                name: row.get::<_, String>(1)?.trim().to_string(),
+            })
+        })
+        .map_err(|e| format!("Failed to query user names: {}", e))?;
+    let names: Vec<DeviceName> = rows
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read user names: {}", e))?;
+    Ok(names.into_iter().filter(|n| !n.name.is_empty()).collect())
+}
+
+/// Saved terminals (id, name). Pseudo-devices (API punches, corrections)
+/// have no address and are left out.
+pub fn devices(conn: &Connection) -> Result<Vec<(String, String)>, String> {
+    let mut stmt = conn
+        .prepare("SELECT id, name FROM devices WHERE ip != '' ORDER BY name")
+        .map_err(|e| format!("Failed to query devices: {}", e))?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| format!("Failed to query devices: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read devices: {}", e))
+}
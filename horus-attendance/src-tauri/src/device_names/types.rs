@@ -0,0 +1,48 @@
+//! Name push data types for Tauri command serialization
+
+use serde::{Deserialize, Serialize};
+
+/// A name to write under a device user ID
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceName {
+    pub device_user_id: String,
+    pub name: String,
+}
+
+/// What happened to one name on one device
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserNameResult {
+    pub device_user_id: String,
+    pub name: String,
+    /// updated, unchanged, not_on_device, skipped (the device can't store
+    /// the name) or failed
+    pub status: String,
+    pub error: Option<String>,
+}
+
+/// Outcome on one device
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceNamesResult {
+    pub device_id: String,
+    pub device_name: String,
+    /// False when the device couldn't be reached or any write failed
+    pub success: bool,
+    pub error: Option<String>,
+    pub users: Vec<UserNameResult>,
+    /// Delivery log entry retrying what didn't go through
+    pub retry_delivery_id: Option<i64>,
+}
+
+/// Result of a push to every device
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NamePushReport {
+    /// Names pushed to each device
+    pub names: u32,
+    pub updated: u32,
+    pub failed: u32,
+    pub devices: Vec<DeviceNamesResult>,
+}
@@ -0,0 +1,28 @@
+//! Tauri command handlers for device diagnostics.
+
+use super::store;
+use super::types::*;
+use crate::db;
+
+/// Days of attempts summed up unless asked otherwise
+const DEFAULT_DAYS: u32 = 30;
+
+const MAX_DAYS: u32 = 365;
+
+/// A device's last error, recent failures and latency history over the
+/// last `days` days
+#[tauri::command]
+pub async fn get_device_diagnostics(
+    app: tauri::AppHandle,
+    device_id: String,
+    days: Option<u32>,
+) -> Result<DeviceDiagnostics, String> {
+    let days = days.unwrap_or(DEFAULT_DAYS);
+    if days == 0 || days > MAX_DAYS {
+        return Err(format!("Days must be between 1 and {}", MAX_DAYS));
+    }
+    let since = (chrono::Utc::now() - chrono::Duration::days(i64::from(days)))
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string();
+    store::diagnostics(&*db::open(&app)?, &device_id, &since)?.ok_or_else(|| format!("Device not found: {}", device_id))
+}
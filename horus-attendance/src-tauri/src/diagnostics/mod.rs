@@ -0,0 +1,12 @@
+//! Per-device connection diagnostics
+//!
+//! Every connection test, info read and sync against a saved device is
+//! recorded in `device_attempts` with how long it took and, when it failed,
+//! why. A failure also lands in the device's `last_error`/`last_error_at`
+//! so the device list can show it. The diagnostics command sums up recent
+//! failures and the latency history, so troubleshooting a terminal doesn't
+//! mean digging through the log files.
+
+pub mod commands;
+pub mod store;
+pub mod types;
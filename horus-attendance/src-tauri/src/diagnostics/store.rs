@@ -0,0 +1,172 @@
+//! Device attempts and the diagnostics summed up from them
+
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+use super::types::*;
+
+pub const OP_CONNECTION_TEST: &str = "connection_test";
+pub const OP_DEVICE_INFO: &str = "device_info";
+pub const OP_SYNC: &str = "sync";
+
+/// Attempts kept per device; older ones are pruned as new ones come in
+const KEEP_PER_DEVICE: u32 = 500;
+
+/// Failures listed in the diagnostics
+const RECENT_FAILURES: u32 = 20;
+
+/// Record an attempt against a saved device; a failure also becomes the
+/// device's last error. Returns false for a device that isn't saved.
+pub fn record(
+    conn: &Connection,
+    device_id: &str,
+    operation: &str,
+    latency_ms: u64,
+    error: Option<&str>,
+) -> Result<bool, String> {
+    let inserted = conn
+        .execute(
+            "INSERT INTO device_attempts (device_id, operation, success, latency_ms, error)
+             SELECT ?1, ?2, ?3, ?4, ?5 WHERE EXISTS (SELECT 1 FROM devices WHERE id = ?1)",
+            params![device_id, operation, error.is_none(), latency_ms as i64, error],
+        )
+        .map_err(|e| format!("Failed to record device attempt: {}", e))?;
+    if inserted == 0 {
+        return Ok(false);
+    }
+    if let Some(error) = error {
+        conn.execute(
+            "UPDATE devices SET last_error = ?2, last_error_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now') WHERE id = ?1",
+            params![device_id, error],
+        )
+        .map_err(|e| format!("Failed to save device error: {}", e))?;
+    }
+    conn.execute(
+        "DELETE FROM device_attempts
+         WHERE device_id = ?1 AND id <= (SELECT id FROM device_attempts WHERE device_id = ?1
+                                         ORDER BY id DESC LIMIT 1 OFFSET ?2)",
+        params![device_id, KEEP_PER_DEVICE],
+    )
+    .map_err(|e| format!("Failed to prune device attempts: {}", e))?;
+    Ok(true)
+}
+
+/// Record an attempt against a saved device, logging rather than failing:
+/// the attempt's own outcome is what the caller reports
+pub fn remember(
+    app: &tauri::AppHandle,
+    device_id: Option<&str>,
+    operation: &str,
+    latency_ms: u64,
+    error: Option<&str>,
+) {
+    let Some(device_id) = device_id.filter(|id| !id.is_empty()) else {
+        return;
+    };
+    let recorded = crate::db::open(app).and_then(|conn| record(&conn, device_id, operation, latency_ms, error));
+    if let Err(e) = recorded {
+        log::warn!("[diagnostics] Could not record {} of {}: {}", operation, device_id, e);
+    }
+}
+
+fn map_attempt(row: &Row) -> rusqlite::Result<DeviceAttempt> {
+    Ok(DeviceAttempt {
+        id: row.get(0)?,
+        operation: row.get(1)?,
+        success: row.get(2)?,
+        latency_ms: row.get::<_, i64>(3)?.max(0) as u64,
+        error: row.get(4)?,
+        attempted_at: row.get(5)?,
+    })
+}
+
+/// Attempts of a device since `since` matching `condition`, in `order`
+fn attempts(
+    conn: &Connection,
+    device_id: &str,
+    since: &str,
+    condition: &str,
+    order: &str,
+    limit: u32,
+) -> Result<Vec<DeviceAttempt>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT id, operation, success, latency_ms, error, attempted_at FROM device_attempts
+             WHERE device_id = ?1 AND attempted_at >= ?2 AND {}
+             ORDER BY id {} LIMIT ?3",
+            condition, order
+        ))
+        .map_err(|e| format!("Failed to query device attempts: {}", e))?;
+    let rows = stmt
+        .query_map(params![device_id, since, limit], map_attempt)
+        .map_err(|e| format!("Failed to query device attempts: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read device attempts: {}", e))
+}
+
+/// A device's latest `limit` attempts, newest first
+pub fn latest(conn: &Connection, device_id: &str, limit: u32) -> Result<Vec<DeviceAttempt>, String> {
+    attempts(conn, device_id, "", "1", "DESC", limit)
+}
+
+/// A device's diagnostics over the attempts since `since` (a SQLite
+/// datetime), or None for an unknown device
+pub fn diagnostics(conn: &Connection, device_id: &str, since: &str) -> Result<Option<DeviceDiagnostics>, String> {
+    let device = conn
+        .query_row(
+            "SELECT name, ip, last_error, last_error_at, last_sync_at,
+                    (SELECT MAX(attempted_at) FROM device_attempts WHERE device_id = d.id AND success)
+             FROM devices d WHERE id = ?1",
+            [device_id],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                    row.get::<_, Option<String>>(5)?,
+                ))
+            },
+        )
+        .optional()
+        .map_err(|e| format!("Failed to load device: {}", e))?;
+    let Some((device_name, ip, last_error, last_error_at, last_sync_at, last_success_at)) = device else {
+        return Ok(None);
+    };
+
+    let (attempts_count, failures, average, max): (u32, u32, Option<f64>, Option<i64>) = conn
+        .query_row(
+            "SELECT COUNT(*), COALESCE(SUM(NOT success), 0),
+                    AVG(CASE WHEN success THEN latency_ms END), MAX(CASE WHEN success THEN latency_ms END)
+             FROM device_attempts WHERE device_id = ?1 AND attempted_at >= ?2",
+            params![device_id, since],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .map_err(|e| format!("Failed to sum up device attempts: {}", e))?;
+    let consecutive_failures: u32 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM device_attempts
+             WHERE device_id = ?1 AND NOT success
+               AND id > COALESCE((SELECT MAX(id) FROM device_attempts WHERE device_id = ?1 AND success), 0)",
+            [device_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to count device failures: {}", e))?;
+
+    Ok(Some(DeviceDiagnostics {
+        device_id: device_id.to_string(),
+        device_name,
+        ip,
+        last_error,
+        last_error_at,
+        last_success_at,
+        last_sync_at,
+        attempts: attempts_count,
+        failures,
+        consecutive_failures,
+        average_latency_ms: average.map(|a| a.round() as u64),
+        max_latency_ms: max.map(|m| m.max(0) as u64),
+        recent_failures: attempts(conn, device_id, since, "NOT success", "DESC", RECENT_FAILURES)?,
+        latency_history: attempts(conn, device_id, since, "success", "ASC", KEEP_PER_DEVICE)?,
+    }))
+}
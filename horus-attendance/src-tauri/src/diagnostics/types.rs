@@ -0,0 +1,41 @@
+//! Diagnostics data types for Tauri command serialization
+
+use serde::{Deserialize, Serialize};
+
+/// One connection, info read or sync against a device
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceAttempt {
+    pub id: i64,
+    /// "connection_test", "device_info" or "sync"
+    pub operation: String,
+    pub success: bool,
+    pub latency_ms: u64,
+    pub error: Option<String>,
+    pub attempted_at: String,
+}
+
+/// A device's recent connection history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceDiagnostics {
+    pub device_id: String,
+    pub device_name: String,
+    pub ip: String,
+    pub last_error: Option<String>,
+    pub last_error_at: Option<String>,
+    pub last_success_at: Option<String>,
+    pub last_sync_at: Option<String>,
+    /// Attempts within the window
+    pub attempts: u32,
+    pub failures: u32,
+    /// Failed attempts in a row, most recent first
+    pub consecutive_failures: u32,
+    /// Of successful attempts; None without any
+    pub average_latency_ms: Option<u64>,
+    pub max_latency_ms: Option<u64>,
+    /// Newest first
+    pub recent_failures: Vec<DeviceAttempt>,
+    /// Successful attempts, oldest first
+    pub latency_history: Vec<DeviceAttempt>,
+}
@@ -0,0 +1,133 @@
+//! Tauri command handlers for temporary enrollments.
+
+use chrono::{Local, Utc};
+
+use super::push;
+use super::store::{self, STATUS_FAILED, STATUS_NOT_ENROLLED, STATUS_REMOVED};
+use super::types::*;
+use crate::db;
+use crate::jobs;
+use crate::zkteco::commands::{resolve_comm_key, saved_config, validate_config};
+
+/// How often the scheduled job looks for expired enrollments
+const CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(6 * 3600);
+
+/// ID of the expiry job in the jobs registry
+pub const JOB_ID: &str = "enrollment_expiry";
+
+/// Users with an enrollment expiry date. With `withinDays`, only those
+/// expiring within that many days or expired and not yet removed.
+#[tauri::command]
+pub async fn list_enrollment_expiries(
+    app: tauri::AppHandle,
+    within_days: Option<u32>,
+) -> Result<Vec<EnrollmentExpiry>, String> {
+    let today = Local::now().format("%Y-%m-%d").to_string();
+    store::list(&*db::open(&app)?, &today, within_days)
+}
+
+/// Deactivate users whose enrollment expired and remove them from the
+/// devices now, as the scheduled job would
+#[tauri::command]
+pub async fn run_enrollment_expiry(app: tauri::AppHandle) -> Result<ExpiryRun, String> {
+    expire(&app).await
+}
+
+/// Remove one user's enrollment from one device
+async fn remove_from(
+    app: &tauri::AppHandle,
+    user: &store::ExpiredUser,
+    device_id: String,
+    device_name: String,
+) -> EnrollmentRemoval {
+    let outcome = async {
+        let config = saved_config(&*db::open(app)?, &device_id)?;
+        validate_config(&config)?;
+        push::remove(&resolve_comm_key(config).await?, &user.device_user_ids).await
+    }
+    .await;
+    if let Err(e) = &outcome {
+        log::warn!("[enrollments] Removing {} from {} failed: {}", user.user_id, device_name, e);
+    }
+    EnrollmentRemoval {
+        user_id: user.user_id.clone(),
+        device_id,
+        device_name,
+        status: match &outcome {
+            Ok(true) => STATUS_REMOVED,
+            Ok(false) => STATUS_NOT_ENROLLED,
+            Err(_) => STATUS_FAILED,
+        }
+        .to_string(),
+        error: outcome.err(),
+    }
+}
+
+/// Deactivate every user past their expiry date and remove their device
+/// user IDs from each device not done yet
+async fn expire(app: &tauri::AppHandle) -> Result<ExpiryRun, String> {
+    let today = Local::now().format("%Y-%m-%d").to_string();
+    let expired = store::expired(&*db::open(app)?, &today)?;
+    let mut run = ExpiryRun {
+        checked_at: Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        expired: expired.len() as u32,
+        deactivated: Vec::new(),
+        removals: Vec::new(),
+        completed: Vec::new(),
+    };
+    for user in &expired {
+        if user.active {
+            store::deactivate(&*db::open(app)?, &user.user_id)?;
+            log::info!("[enrollments] Deactivated {}: enrollment expired", user.display_name);
+            run.deactivated.push(user.user_id.clone());
+        }
+        let devices = if user.device_user_ids.is_empty() {
+            Vec::new()
+        } else {
+            store::pending_devices(&*db::open(app)?, &user.user_id)?
+        };
+        let mut failed = false;
+        for (device_id, device_name) in devices {
+            let removal = remove_from(app, user, device_id, device_name).await;
+            failed |= removal.status == STATUS_FAILED;
+            store::record_removal(&*db::open(app)?, &removal)?;
+            run.removals.push(removal);
+        }
+        if !failed {
+            store::complete(&*db::open(app)?, &user.user_id)?;
+            run.completed.push(user.user_id.clone());
+        }
+    }
+    Ok(run)
+}
+
+/// Carry out expired enrollments a few times a day. Runs for the lifetime
+/// of the app.
+pub async fn run_scheduled(app: tauri::AppHandle) {
+    let job = jobs::registry::register(
+        &app,
+        JOB_ID,
+        "Enrollment expiry",
+        "Deactivates temporary users after their last day and removes them from the devices",
+        CHECK_INTERVAL,
+    );
+    let handle = app.clone();
+    jobs::registry::drive(
+        job,
+        move |_| {
+            let app = handle.clone();
+            async move { expire(&app).await }
+        },
+        |run| {
+            if run.expired > 0 {
+                log::info!(
+                    "[enrollments] {} expired enrollments, {} users deactivated, {} removed everywhere",
+                    run.expired,
+                    run.deactivated.len(),
+                    run.completed.len()
+                );
+            }
+        },
+    )
+    .await;
+}
@@ -0,0 +1,14 @@
+//! Temporary device enrollments
+//!
+//! Contractors and visitors are often enrolled on the terminals for a fixed
+//! stretch. A user with `enrollment_expires_at` set is deactivated the day
+//! after that date, and their device user IDs (their own and any linked
+//! ones) are removed from every terminal so they can no longer punch.
+//! A scheduled job does this; the outcome per device is kept in
+//! `enrollment_removals`, and devices that couldn't be reached are tried
+//! again on later runs until the enrollment is gone everywhere.
+
+pub mod commands;
+pub mod push;
+pub mod store;
+pub mod types;
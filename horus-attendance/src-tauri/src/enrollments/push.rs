@@ -0,0 +1,24 @@
+//! Removing expired enrollments from devices
+
+use crate::zkteco::client::ZKClient;
+use crate::zkteco::types::DeviceConfig;
+
+/// Delete each of `device_user_ids` enrolled on the device. Returns whether
+/// any of them was.
+pub async fn remove(config: &DeviceConfig, device_user_ids: &[String]) -> Result<bool, String> {
+    let mut client = ZKClient::connect(config).await?;
+    let outcome = async {
+        let uids = client.get_user_uids().await?;
+        let mut removed = false;
+        for id in device_user_ids {
+            if let Some(uid) = uids.get(id) {
+                client.delete_user(*uid).await?;
+                removed = true;
+            }
+        }
+        Ok(removed)
+    }
+    .await;
+    let _ = client.disconnect().await;
+    outcome
+}
@@ -0,0 +1,137 @@
+//! Expired enrollments and their removal from devices
+
+use rusqlite::{params, Connection};
+
+use super::types::*;
+use crate::users::types::UserClassification;
+
+pub const STATUS_REMOVED: &str = "removed";
+pub const STATUS_NOT_ENROLLED: &str = "not_enrolled";
+pub const STATUS_FAILED: &str = "failed";
+
+/// A user whose enrollment expired before `today` and isn't yet gone from
+/// every device
+pub struct ExpiredUser {
+    pub user_id: String,
+    pub display_name: String,
+    pub active: bool,
+    /// Their own device user ID and linked ones
+    pub device_user_ids: Vec<String>,
+}
+
+/// Users with an expiry date, soonest first. With `within_days`, only
+/// those expiring by then or already expired and not yet removed.
+pub fn list(conn: &Connection, today: &str, within_days: Option<u32>) -> Result<Vec<EnrollmentExpiry>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT u.id, u.display_name, u.classification, u.status, u.enrollment_expires_at, u.enrollment_removed_at,
+                    (SELECT COUNT(*) FROM enrollment_removals r WHERE r.user_id = u.id AND r.status = 'failed')
+             FROM users u
+             WHERE u.enrollment_expires_at IS NOT NULL
+               AND (?2 IS NULL OR (u.enrollment_removed_at IS NULL
+                                   AND u.enrollment_expires_at <= date(?1, '+' || ?2 || ' days')))
+             ORDER BY u.enrollment_expires_at, u.display_name",
+        )
+        .map_err(|e| format!("Failed to query enrollments: {}", e))?;
+    let rows = stmt
+        .query_map(params![today, within_days], |row| {
+            let classification: String = row.get(2)?;
+            Ok(EnrollmentExpiry {
+                user_id: row.get(0)?,
+                display_name: row.get(1)?,
+                classification: UserClassification::parse(&classification).unwrap_or_default(),
+                status: row.get(3)?,
+                expires_at: row.get(4)?,
+                removed_at: row.get(5)?,
+                failed_devices: row.get(6)?,
+            })
+        })
+        .map_err(|e| format!("Failed to query enrollments: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read enrollments: {}", e))
+}
+
+/// Users whose last valid day was before `today` (YYYY-MM-DD) and whose
+/// enrollment isn't gone from every device yet
+pub fn expired(conn: &Connection, today: &str) -> Result<Vec<ExpiredUser>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT u.id, u.display_name, u.status = 'active',
+                    (SELECT json_group_array(device_user_id) FROM (
+                         SELECT u.device_user_id AS device_user_id WHERE COALESCE(u.device_user_id, '') != ''
+                         UNION
+                         SELECT l.device_user_id FROM user_device_links l WHERE l.user_id = u.id
+                     ))
+             FROM users u
+             WHERE u.enrollment_expires_at < ?1 AND u.enrollment_removed_at IS NULL
+             ORDER BY u.enrollment_expires_at, u.display_name",
+        )
+        .map_err(|e| format!("Failed to query expired enrollments: {}", e))?;
+    let rows = stmt
+        .query_map([today], |row| {
+            let ids: String = row.get(3)?;
+            Ok(ExpiredUser {
+                user_id: row.get(0)?,
+                display_name: row.get(1)?,
+                active: row.get(2)?,
+                device_user_ids: serde_json::from_str(&ids).unwrap_or_default(),
+            })
+        })
+        .map_err(|e| format!("Failed to query expired enrollments: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read expired enrollments: {}", e))
+}
+
+/// Terminals (id, name) the enrollment hasn't been removed from yet.
+/// Pseudo-devices (API punches, corrections) have no address and are left
+/// out.
+pub fn pending_devices(conn: &Connection, user_id: &str) -> Result<Vec<(String, String)>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT d.id, d.name FROM devices d
+             WHERE d.ip != ''
+               AND NOT EXISTS (SELECT 1 FROM enrollment_removals r
+                               WHERE r.user_id = ?1 AND r.device_id = d.id AND r.status != 'failed')
+             ORDER BY d.name",
+        )
+        .map_err(|e| format!("Failed to query devices: {}", e))?;
+    let rows = stmt
+        .query_map([user_id], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| format!("Failed to query devices: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read devices: {}", e))
+}
+
+/// Set an expired user inactive
+pub fn deactivate(conn: &Connection, user_id: &str) -> Result<(), String> {
+    conn.execute(
+        "UPDATE users SET status = 'inactive', updated_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+         WHERE id = ?1 AND status = 'active'",
+        [user_id],
+    )
+    .map_err(|e| format!("Failed to deactivate user: {}", e))?;
+    Ok(())
+}
+
+pub fn record_removal(conn: &Connection, removal: &EnrollmentRemoval) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO enrollment_removals (user_id, device_id, status, error) VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(user_id, device_id) DO UPDATE SET
+             status = excluded.status,
+             error = excluded.error,
+             attempted_at = datetime('now')",
+        params![removal.user_id, removal.device_id, removal.status, removal.error],
+    )
+    .map_err(|e| format!("Failed to record enrollment removal: {}", e))?;
+    Ok(())
+}
+
+/// Mark the enrollment gone from every device
+pub fn complete(conn: &Connection, user_id: &str) -> Result<(), String> {
+    conn.execute(
+        "UPDATE users SET enrollment_removed_at = datetime('now') WHERE id = ?1",
+        [user_id],
+    )
+    .map_err(|e| format!("Failed to complete enrollment removal: {}", e))?;
+    Ok(())
+}
@@ -0,0 +1,47 @@
+//! Enrollment expiry data types for Tauri command serialization
+
+use serde::{Deserialize, Serialize};
+
+use crate::users::types::UserClassification;
+
+/// A user with an enrollment expiry date
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnrollmentExpiry {
+    pub user_id: String,
+    pub display_name: String,
+    pub classification: UserClassification,
+    pub status: String,
+    /// Last valid day (YYYY-MM-DD)
+    pub expires_at: String,
+    /// When the enrollment was removed from every device
+    pub removed_at: Option<String>,
+    /// Devices the enrollment couldn't be removed from yet
+    pub failed_devices: u32,
+}
+
+/// What became of an expired enrollment on one device
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnrollmentRemoval {
+    pub user_id: String,
+    pub device_id: String,
+    pub device_name: String,
+    /// "removed", "not_enrolled" or "failed"
+    pub status: String,
+    pub error: Option<String>,
+}
+
+/// Result of one expiry run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpiryRun {
+    pub checked_at: String,
+    /// Users past their expiry date whose enrollment wasn't gone everywhere
+    pub expired: u32,
+    /// Users this run set inactive
+    pub deactivated: Vec<String>,
+    pub removals: Vec<EnrollmentRemoval>,
+    /// Users whose enrollment is now gone from every device
+    pub completed: Vec<String>,
+}
@@ -0,0 +1,280 @@
+//! Detection of physically impossible punch sequences
+//!
+//! Three checks, all in SQL over attendance_logs_raw:
+//! - impossible travel: consecutive punches by the same user on two different
+//!   devices closer together than `travelMinutes` (each device is treated as
+//!   its own site, except terminals paired as covering the same door)
+//! - excessive punches: more than `maxPunchesPerDay` punches by one user in a day
+//! - invalid timestamps: years before 2000 or after the current year, or
+//!   unparseable values, typically from corrupted device records
+
+use chrono::{Datelike, Local};
+use rusqlite::{params, Connection};
+use serde_json::json;
+
+use super::store;
+use super::types::{AnomalyCheckResult, AnomalyRules, NewException};
+use crate::db;
+use crate::summary::engine;
+use crate::summary::rules::working_date_sql;
+
+pub const KIND_IMPOSSIBLE_TRAVEL: &str = "impossible_travel";
+pub const KIND_EXCESSIVE_PUNCHES: &str = "excessive_punches";
+pub const KIND_INVALID_TIMESTAMP: &str = "invalid_timestamp";
+
+/// Settings key for the thresholds
+pub const ANOMALY_RULES_KEY: &str = "anomalyRules";
+
+/// Settings key holding the created_at of the newest log already checked
+const WATERMARK_KEY: &str = "anomalyCheckWatermark";
+
+/// Earliest plausible punch year
+const MIN_YEAR: i32 = 2000;
+
+/// Load the thresholds, falling back to defaults
+pub fn load_rules(conn: &Connection) -> Result<AnomalyRules, String> {
+    Ok(db::get_json_setting(conn, ANOMALY_RULES_KEY)?.unwrap_or_default())
+}
+
+/// Check logs synced since the last run. Cheap when nothing is new, so it
+/// can run on a timer.
+pub fn check_new(conn: &Connection, rules: &AnomalyRules) -> Result<AnomalyCheckResult, String> {
+    let watermark = db::get_setting(conn, WATERMARK_KEY)?.unwrap_or_default();
+    let newest: Option<String> = conn
+        .query_row("SELECT MAX(created_at) FROM attendance_logs_raw", [], |row| row.get(0))
+        .map_err(|e| format!("Failed to query logs: {}", e))?;
+    let Some(newest) = newest else {
+        return Ok(AnomalyCheckResult::default());
+    };
+    if newest == watermark {
+        return Ok(AnomalyCheckResult::default());
+    }
+
+    // Re-check whole days touched by the new logs, so sequences spanning old
+    // and new punches are seen. created_at has one-second resolution, hence
+    // >= rather than >; already-queued findings are deduplicated anyway.
+    let day = working_date_sql("timestamp", engine::day_cutoff_hour(conn)?);
+    let (first, last): (Option<String>, Option<String>) = conn
+        .query_row(
+            &format!(
+                "SELECT MIN({day}), MAX({day})
+                 FROM attendance_logs_raw
+                 WHERE created_at >= ?1 AND timestamp >= ?2 AND timestamp < ?3",
+                day = day
+            ),
+            params![watermark, MIN_YEAR.to_string(), next_year()],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| format!("Failed to query new logs: {}", e))?;
+
+    let range = first.zip(last);
+    let result = check(conn, rules, &watermark, range.as_ref().map(|(a, b)| (a.as_str(), b.as_str())))?;
+    db::set_setting(conn, WATERMARK_KEY, &newest)?;
+    Ok(result)
+}
+
+/// Check every log, or only punches between two dates (inclusive).
+/// Invalid timestamps are always looked for across all logs, since they fall
+/// outside any sensible date range.
+pub fn check_all(
+    conn: &Connection,
+    rules: &AnomalyRules,
+    start_date: Option<&str>,
+    end_date: Option<&str>,
+) -> Result<AnomalyCheckResult, String> {
+    let range = match (start_date, end_date) {
+        (Some(start), Some(end)) => Some((start.to_string(), end.to_string())),
+        _ => {
+            let day = working_date_sql("timestamp", engine::day_cutoff_hour(conn)?);
+            let (first, last): (Option<String>, Option<String>) = conn
+                .query_row(
+                    &format!(
+                        "SELECT MIN({day}), MAX({day})
+                         FROM attendance_logs_raw
+                         WHERE timestamp >= ?1 AND timestamp < ?2",
+                        day = day
+                    ),
+                    params![MIN_YEAR.to_string(), next_year()],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .map_err(|e| format!("Failed to query logs: {}", e))?;
+            first.zip(last)
+        }
+    };
+    check(conn, rules, "", range.as_ref().map(|(a, b)| (a.as_str(), b.as_str())))
+}
+
+/// Run the checks: sequences within `range`, timestamps of logs created at
+/// or after `created_since`
+fn check(
+    conn: &Connection,
+    rules: &AnomalyRules,
+    created_since: &str,
+    range: Option<(&str, &str)>,
+) -> Result<AnomalyCheckResult, String> {
+    let mut result = AnomalyCheckResult::default();
+    let mut findings = invalid_timestamps(conn, created_since)?;
+    result.findings.insert(KIND_INVALID_TIMESTAMP.to_string(), findings.len() as i64);
+
+    if let Some((start_date, end_date)) = range {
+        let cutoff_hour = engine::day_cutoff_hour(conn)?;
+        let (start, end) = db::logs::working_day_bounds(start_date, end_date, cutoff_hour);
+        result.logs_checked = conn
+            .query_row(
+                "SELECT COUNT(*) FROM attendance_logs_raw WHERE timestamp >= ?1 AND timestamp < ?2",
+                [&start, &end],
+                |row| row.get(0),
+            )
+            .map_err(|e| format!("Failed to count logs: {}", e))?;
+
+        let travel = impossible_travel(conn, &start, &end, rules.travel_minutes)?;
+        result.findings.insert(KIND_IMPOSSIBLE_TRAVEL.to_string(), travel.len() as i64);
+        findings.extend(travel);
+
+        let excessive = excessive_punches(conn, &start, &end, rules.max_punches_per_day, cutoff_hour)?;
+        result.findings.insert(KIND_EXCESSIVE_PUNCHES.to_string(), excessive.len() as i64);
+        findings.extend(excessive);
+    }
+
+    result.new_exceptions = store::insert(conn, &findings)?;
+    Ok(result)
+}
+
+fn impossible_travel(conn: &Connection, start: &str, end: &str, minutes: i64) -> Result<Vec<NewException>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT l.id, l.device_user_id, l.device_id, l.timestamp,
+                    l.prev_id, l.prev_device_id, l.prev_timestamp, l.minutes,
+                    COALESCE(d.name, l.device_id), COALESCE(pd.name, l.prev_device_id)
+             FROM (
+                 SELECT *, (julianday(timestamp) - julianday(prev_timestamp)) * 1440 AS minutes
+                 FROM (
+                     SELECT id, device_user_id, device_id, timestamp,
+                            LAG(id) OVER w AS prev_id,
+                            LAG(device_id) OVER w AS prev_device_id,
+                            LAG(timestamp) OVER w AS prev_timestamp
+                     FROM attendance_logs_raw
+                     WHERE timestamp >= ?1 AND timestamp < ?2
+                     WINDOW w AS (PARTITION BY device_user_id ORDER BY timestamp)
+                 )
+                 WHERE prev_device_id IS NOT NULL AND prev_device_id != device_id
+             ) l
+             LEFT JOIN devices d ON d.id = l.device_id
+             LEFT JOIN devices pd ON pd.id = l.prev_device_id
+             WHERE l.minutes < ?3
+               AND NOT EXISTS (
+                   SELECT 1 FROM device_pairs p
+                   WHERE (p.primary_device_id = l.device_id AND p.secondary_device_id = l.prev_device_id)
+                      OR (p.primary_device_id = l.prev_device_id AND p.secondary_device_id = l.device_id)
+               )",
+        )
+        .map_err(|e| format!("Failed to query punch sequences: {}", e))?;
+    let rows = stmt
+        .query_map(params![start, end, minutes], |row| {
+            let id: String = row.get(0)?;
+            let device_user_id: String = row.get(1)?;
+            let timestamp: String = row.get(3)?;
+            let prev_id: String = row.get(4)?;
+            let prev_timestamp: String = row.get(6)?;
+            let minutes_apart: f64 = row.get::<_, Option<f64>>(7)?.unwrap_or(0.0);
+            let device_name: String = row.get(8)?;
+            let prev_device_name: String = row.get(9)?;
+            Ok(NewException {
+                kind: KIND_IMPOSSIBLE_TRAVEL,
+                severity: "warning",
+                device_user_id: Some(device_user_id),
+                date: timestamp.get(0..10).map(str::to_string),
+                message: format!(
+                    "Punched on {} {:.0} min after punching on {}",
+                    device_name, minutes_apart, prev_device_name
+                ),
+                details: json!({
+                    "logId": id,
+                    "deviceId": row.get::<_, String>(2)?,
+                    "timestamp": timestamp,
+                    "previousLogId": prev_id,
+                    "previousDeviceId": row.get::<_, String>(5)?,
+                    "previousTimestamp": prev_timestamp,
+                    "minutesApart": (minutes_apart * 10.0).round() / 10.0,
+                }),
+                dedupe_key: format!("{}:{}:{}", KIND_IMPOSSIBLE_TRAVEL, prev_id, id),
+            })
+        })
+        .map_err(|e| format!("Failed to query punch sequences: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read punch sequences: {}", e))
+}
+
+fn excessive_punches(
+    conn: &Connection,
+    start: &str,
+    end: &str,
+    max: i64,
+    cutoff_hour: u32,
+) -> Result<Vec<NewException>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT device_user_id, {} AS day, COUNT(*)
+             FROM attendance_logs_raw
+             WHERE timestamp >= ?1 AND timestamp < ?2
+             GROUP BY device_user_id, day
+             HAVING COUNT(*) > ?3",
+            working_date_sql("timestamp", cutoff_hour)
+        ))
+        .map_err(|e| format!("Failed to query punch counts: {}", e))?;
+    let rows = stmt
+        .query_map(params![start, end, max], |row| {
+            let device_user_id: String = row.get(0)?;
+            let date: String = row.get(1)?;
+            let count: i64 = row.get(2)?;
+            Ok(NewException {
+                kind: KIND_EXCESSIVE_PUNCHES,
+                severity: "warning",
+                dedupe_key: format!("{}:{}:{}", KIND_EXCESSIVE_PUNCHES, device_user_id, date),
+                message: format!("{} punches in one day (limit {})", count, max),
+                details: json!({ "count": count, "limit": max }),
+                device_user_id: Some(device_user_id),
+                date: Some(date),
+            })
+        })
+        .map_err(|e| format!("Failed to query punch counts: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read punch counts: {}", e))
+}
+
+fn invalid_timestamps(conn: &Connection, created_since: &str) -> Result<Vec<NewException>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, device_user_id, device_id, timestamp
+             FROM attendance_logs_raw
+             WHERE created_at >= ?1
+               AND (timestamp < ?2 OR timestamp >= ?3 OR julianday(timestamp) IS NULL)",
+        )
+        .map_err(|e| format!("Failed to query log timestamps: {}", e))?;
+    let rows = stmt
+        .query_map(params![created_since, MIN_YEAR.to_string(), next_year()], |row| {
+            let id: String = row.get(0)?;
+            let timestamp: String = row.get(3)?;
+            Ok(NewException {
+                kind: KIND_INVALID_TIMESTAMP,
+                severity: "critical",
+                device_user_id: Some(row.get(1)?),
+                date: None,
+                message: format!("Punch has an impossible timestamp '{}' (likely a corrupted record)", timestamp),
+                details: json!({
+                    "logId": id,
+                    "deviceId": row.get::<_, String>(2)?,
+                    "timestamp": timestamp,
+                }),
+                dedupe_key: format!("{}:{}", KIND_INVALID_TIMESTAMP, id),
+            })
+        })
+        .map_err(|e| format!("Failed to query log timestamps: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read log timestamps: {}", e))
+}
+
+/// First timestamp prefix past the current year, e.g. "2027"
+fn next_year() -> String {
+    (Local::now().year() + 1).to_string()
+}
@@ -0,0 +1,104 @@
+//! Tauri command handlers for the exception queue.
+
+use std::time::Duration;
+
+use super::anomalies;
+use super::store;
+use super::types::*;
+use crate::activity::registry;
+use crate::db;
+use crate::jobs;
+use crate::summary::commands::validate_date;
+
+/// How often the background check looks for newly synced logs
+const CHECK_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// ID of the background check in the jobs registry
+pub const JOB_ID: &str = "anomaly_checks";
+
+/// List exception queue entries, newest first
+#[tauri::command]
+pub async fn list_exceptions(
+    app: tauri::AppHandle,
+    status: Option<String>,
+    kind: Option<String>,
+    limit: Option<u32>,
+) -> Result<Vec<AttendanceException>, String> {
+    let conn = db::open(&app)?;
+    store::list(&conn, status.as_deref(), kind.as_deref(), limit.unwrap_or(500))
+}
+
+/// Mark an entry resolved or dismissed (or re-open it), with an optional note
+#[tauri::command]
+pub async fn resolve_exception(
+    app: tauri::AppHandle,
+    exception_id: String,
+    status: String,
+    note: Option<String>,
+) -> Result<(), String> {
+    let conn = db::open(&app)?;
+    store::set_status(&conn, &exception_id, &status, note.as_deref())
+}
+
+/// Run the anomaly checks now over all logs, or punches between two dates
+#[tauri::command]
+pub async fn run_anomaly_check(
+    app: tauri::AppHandle,
+    start_date: Option<String>,
+    end_date: Option<String>,
+) -> Result<AnomalyCheckResult, String> {
+    if let Some(date) = &start_date {
+        validate_date(date)?;
+    }
+    if let Some(date) = &end_date {
+        validate_date(date)?;
+    }
+    log::info!("[exceptions::cmd] run_anomaly_check {:?} to {:?}", start_date, end_date);
+
+    let activity = registry::begin(&app, "Anomaly check")?;
+    let conn = db::open(&app)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let _activity = activity;
+        let rules = anomalies::load_rules(&conn)?;
+        anomalies::check_all(&conn, &rules, start_date.as_deref(), end_date.as_deref())
+    })
+    .await
+    .map_err(|e| format!("Anomaly check task failed: {}", e))?
+}
+
+/// Periodically check newly synced logs and emit `exceptions-updated` when
+/// anything new is queued. Runs for the lifetime of the app.
+pub async fn run_background_checks(app: tauri::AppHandle) {
+    use tauri::Emitter;
+
+    let job = jobs::registry::register(
+        &app,
+        JOB_ID,
+        "Anomaly checks",
+        "Flags impossible punch sequences in newly synced logs",
+        CHECK_INTERVAL,
+    );
+    let handle = app.clone();
+    jobs::registry::drive_blocking(
+        job,
+        move |_| {
+            // Skipped while a backup or restore runs; no database yet on first launch
+            let _activity = registry::begin(&handle, "Anomaly check")?;
+            let conn = db::open(&handle)?;
+            let rules = anomalies::load_rules(&conn)?;
+            if !rules.enabled {
+                return Ok(AnomalyCheckResult::default());
+            }
+            anomalies::check_new(&conn, &rules)
+        },
+        |result| {
+            if result.new_exceptions > 0 {
+                log::info!("[exceptions] Anomaly check queued {} new exceptions", result.new_exceptions);
+                if let Err(e) = app.emit("exceptions-updated", &result) {
+                    log::warn!("[exceptions] Failed to emit exceptions update: {}", e);
+                }
+            }
+        },
+    )
+    .await;
+}
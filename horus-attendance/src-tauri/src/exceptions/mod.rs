@@ -0,0 +1,11 @@
+//! Exception queue
+//!
+//! Suspicious or corrupted attendance data is recorded in
+//! `attendance_exceptions` for a person to review instead of being silently
+//! used or dropped. Each entry has a dedupe key, so re-running a check never
+//! re-opens something that was already resolved or dismissed.
+
+pub mod anomalies;
+pub mod commands;
+pub mod store;
+pub mod types;
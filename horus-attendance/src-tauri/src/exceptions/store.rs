@@ -0,0 +1,119 @@
+//! Storage for the exception queue
+
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection};
+
+use super::types::{AttendanceException, NewException, EXCEPTION_STATUSES};
+
+/// Add findings to the queue, skipping any whose dedupe key is already
+/// present (whatever its status). Returns the number of new entries.
+pub fn insert(conn: &Connection, findings: &[NewException]) -> Result<i64, String> {
+    Ok(insert_new(conn, findings)?.len() as i64)
+}
+
+/// [`insert`], returning the indices of the findings that were new
+pub fn insert_new(conn: &Connection, findings: &[NewException]) -> Result<Vec<usize>, String> {
+    let mut stmt = conn
+        .prepare(
+            "INSERT OR IGNORE INTO attendance_exceptions
+                (id, kind, severity, device_user_id, user_id, date, message, details, dedupe_key)
+             VALUES (?1, ?2, ?3, ?4, (SELECT id FROM users WHERE device_user_id = ?4), ?5, ?6, ?7, ?8)",
+        )
+        .map_err(|e| format!("Failed to prepare exception insert: {}", e))?;
+    let mut inserted = Vec::new();
+    for (index, finding) in findings.iter().enumerate() {
+        let added = stmt
+            .execute(params![
+                uuid::Uuid::new_v4().to_string(),
+                finding.kind,
+                finding.severity,
+                finding.device_user_id,
+                finding.date,
+                finding.message,
+                finding.details.to_string(),
+                finding.dedupe_key,
+            ])
+            .map_err(|e| format!("Failed to record exception: {}", e))?;
+        if added > 0 {
+            inserted.push(index);
+        }
+    }
+    Ok(inserted)
+}
+
+/// List queue entries, newest first
+pub fn list(
+    conn: &Connection,
+    status: Option<&str>,
+    kind: Option<&str>,
+    limit: u32,
+) -> Result<Vec<AttendanceException>, String> {
+    let mut filters = vec!["1".to_string()];
+    let mut values = Vec::new();
+    if let Some(status) = status {
+        filters.push("e.status = ?".to_string());
+        values.push(Value::Text(status.to_string()));
+    }
+    if let Some(kind) = kind {
+        filters.push("e.kind = ?".to_string());
+        values.push(Value::Text(kind.to_string()));
+    }
+    values.push(Value::Integer(limit as i64));
+
+    let sql = format!(
+        "SELECT e.id, e.kind, e.severity, e.device_user_id, e.user_id, u.display_name, e.date,
+                e.message, e.details, e.status, e.resolution_note, e.created_at, e.resolved_at
+         FROM attendance_exceptions e
+         LEFT JOIN users u ON u.id = e.user_id
+         WHERE {}
+         ORDER BY e.created_at DESC, e.date DESC
+         LIMIT ?",
+        filters.join(" AND ")
+    );
+    let mut stmt = conn
+        .prepare(&sql)
+        .map_err(|e| format!("Failed to query exceptions: {}", e))?;
+    let rows = stmt
+        .query_map(params_from_iter(values), |row| {
+            let details: Option<String> = row.get(8)?;
+            Ok(AttendanceException {
+                id: row.get(0)?,
+                kind: row.get(1)?,
+                severity: row.get(2)?,
+                device_user_id: row.get(3)?,
+                user_id: row.get(4)?,
+                user_name: row.get(5)?,
+                date: row.get(6)?,
+                message: row.get(7)?,
+                details: details.and_then(|d| serde_json::from_str(&d).ok()),
+                status: row.get(9)?,
+                resolution_note: row.get(10)?,
+                created_at: row.get(11)?,
+                resolved_at: row.get(12)?,
+            })
+        })
+        .map_err(|e| format!("Failed to query exceptions: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read exceptions: {}", e))
+}
+
+/// Resolve, dismiss or re-open an entry
+pub fn set_status(conn: &Connection, id: &str, status: &str, note: Option<&str>) -> Result<(), String> {
+    if !EXCEPTION_STATUSES.contains(&status) {
+        return Err(format!("Unknown exception status: {}", status));
+    }
+    let changed = conn
+        .execute(
+            "UPDATE attendance_exceptions
+             SET status = ?2,
+                 resolution_note = ?3,
+                 resolved_at = CASE WHEN ?2 = 'open' THEN NULL ELSE datetime('now') END
+             WHERE id = ?1",
+            params![id, status, note],
+        )
+        .map_err(|e| format!("Failed to update exception: {}", e))?;
+    if changed == 0 {
+        return Err(format!("Exception not found: {}", id));
+    }
+    Ok(())
+}
@@ -0,0 +1,73 @@
+//! Exception queue data types for Tauri command serialization
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Exception statuses, matching the CHECK constraint on attendance_exceptions
+pub const EXCEPTION_STATUSES: &[&str] = &["open", "resolved", "dismissed"];
+
+/// An entry in the exception queue
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttendanceException {
+    pub id: String,
+    pub kind: String,
+    /// info, warning or critical
+    pub severity: String,
+    pub device_user_id: Option<String>,
+    pub user_id: Option<String>,
+    pub user_name: Option<String>,
+    pub date: Option<String>,
+    pub message: String,
+    /// Check-specific data (log IDs, timestamps, counts)
+    pub details: Option<serde_json::Value>,
+    pub status: String,
+    pub resolution_note: Option<String>,
+    pub created_at: String,
+    pub resolved_at: Option<String>,
+}
+
+/// A finding to add to the queue
+#[derive(Debug, Clone)]
+pub struct NewException {
+    pub kind: &'static str,
+    pub severity: &'static str,
+    pub device_user_id: Option<String>,
+    pub date: Option<String>,
+    pub message: String,
+    pub details: serde_json::Value,
+    /// Identifies the finding across runs
+    pub dedupe_key: String,
+}
+
+/// Thresholds for the anomaly check (`anomalyRules` setting)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AnomalyRules {
+    pub enabled: bool,
+    /// Punches on two different devices closer together than this are flagged
+    pub travel_minutes: i64,
+    /// More punches than this for one user on one day are flagged
+    pub max_punches_per_day: i64,
+}
+
+impl Default for AnomalyRules {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            travel_minutes: 10,
+            max_punches_per_day: 20,
+        }
+    }
+}
+
+/// Result of an anomaly check run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnomalyCheckResult {
+    pub logs_checked: i64,
+    /// Findings per kind, including ones already in the queue
+    pub findings: BTreeMap<String, i64>,
+    /// Entries newly added to the queue
+    pub new_exceptions: i64,
+}
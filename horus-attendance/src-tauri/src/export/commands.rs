@@ -0,0 +1,384 @@
+//! Tauri command handlers for backend exports.
+
+use std::fs;
+use std::path::PathBuf;
+use tauri::Manager;
+
+use super::hijri::{self, CalendarSettings};
+use super::sheet::{self, SHEET_TEMPLATES};
+use super::{device_users, fixed_width, parquet, pdf, protect, xlsx};
+use super::types::*;
+use crate::db;
+use crate::periods;
+use crate::report_cache::store::cached;
+use crate::signing;
+use crate::summary::commands::validate_date;
+use crate::summary::history;
+
+/// Get the default export directory
+pub(crate) fn get_export_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let document_dir = app.path().document_dir()
+        .map_err(|e| format!("Failed to get document directory: {}", e))?;
+    let export_dir = document_dir.join("HorusAttendance").join("exports");
+    fs::create_dir_all(&export_dir)
+        .map_err(|e| format!("Failed to create export directory: {}", e))?;
+    Ok(export_dir)
+}
+
+/// Export raw logs and daily summaries for a period as Parquet files, or as
+/// one encrypted zip holding both when `password` is set.
+/// `destination` is a directory; defaults to Documents/HorusAttendance/exports.
+#[tauri::command]
+pub async fn export_parquet(
+    app: tauri::AppHandle,
+    start_date: String,
+    end_date: String,
+    destination: Option<String>,
+    password: Option<String>,
+) -> Result<ExportResult, String> {
+    validate_date(&start_date)?;
+    validate_date(&end_date)?;
+    if let Some(password) = &password {
+        protect::validate_password(password)?;
+    }
+    log::info!("[export::cmd] export_parquet {} to {}", start_date, end_date);
+
+    let dir = match destination {
+        Some(dest) => PathBuf::from(dest),
+        None => get_export_dir(&app)?,
+    };
+    let suffix = format!("{}_{}", start_date.replace('-', ""), end_date.replace('-', ""));
+    let logs_path = crate::path_policy::resolve_write_target(
+        &app,
+        &dir.join(format!("attendance_logs_{}.parquet", suffix)).to_string_lossy(),
+    )?;
+    let summaries_path = crate::path_policy::resolve_write_target(
+        &app,
+        &dir.join(format!("attendance_summaries_{}.parquet", suffix)).to_string_lossy(),
+    )?;
+    let archive_path = crate::path_policy::resolve_write_target(
+        &app,
+        &dir.join(format!("attendance_export_{}.zip", suffix)).to_string_lossy(),
+    )?;
+
+    let conn = db::open(&app)?;
+    let calendar = CalendarSettings::load(&conn)?;
+    hijri::register_sql_functions(&conn, calendar.hijri_adjustment)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let logs = parquet::export_logs(&conn, &start_date, &end_date, &logs_path)?;
+        let summaries =
+            parquet::export_summaries(&conn, &start_date, &end_date, &summaries_path, calendar.hijri_enabled)?;
+        let files = match password {
+            Some(password) => vec![protect::encrypt(&[logs, summaries], &archive_path, &password)?],
+            None => vec![logs, summaries],
+        };
+        signing::store::sign_exported(&conn, &files, "attendance_parquet")?;
+        Ok(ExportResult { files })
+    })
+    .await
+    .map_err(|e| format!("Export task failed: {}", e))?
+}
+
+/// Export the change history of summaries between two dates as CSV,
+/// optionally for one user, encrypted into a zip when `password` is set.
+/// `destination` is a directory; defaults to Documents/HorusAttendance/exports.
+#[tauri::command]
+pub async fn export_summary_history(
+    app: tauri::AppHandle,
+    user_id: Option<String>,
+    start_date: String,
+    end_date: String,
+    destination: Option<String>,
+    password: Option<String>,
+) -> Result<ExportedFile, String> {
+    validate_date(&start_date)?;
+    validate_date(&end_date)?;
+    if let Some(password) = &password {
+        protect::validate_password(password)?;
+    }
+    log::info!("[export::cmd] export_summary_history {} to {}", start_date, end_date);
+
+    let dir = match destination {
+        Some(dest) => PathBuf::from(dest),
+        None => get_export_dir(&app)?,
+    };
+    let suffix = format!("{}_{}", start_date.replace('-', ""), end_date.replace('-', ""));
+    let path = crate::path_policy::resolve_write_target(
+        &app,
+        &dir.join(format!("summary_history_{}.csv", suffix)).to_string_lossy(),
+    )?;
+
+    let conn = db::open(&app)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let changes = history::list(&conn, user_id.as_deref(), &start_date, &end_date)?;
+        history::write_csv(&changes, &path)?;
+        let file = ExportedFile {
+            path: path.to_string_lossy().to_string(),
+            rows: changes.len() as u64,
+            file_size: fs::metadata(&path).map(|m| m.len()).unwrap_or(0),
+        };
+        let file = protect::finish(file, password.as_deref())?;
+        signing::store::sign_exported(&conn, std::slice::from_ref(&file), "summary_history")?;
+        Ok(file)
+    })
+    .await
+    .map_err(|e| format!("Export task failed: {}", e))?
+}
+
+/// Export a fixed-layout monthly register (see sheet::SHEET_TEMPLATES) as
+/// "xlsx" or "pdf", optionally for one department. With a `password` the
+/// file is encrypted into a zip (and an XLSX sheet locked for editing).
+/// `destination` is a directory; defaults to Documents/HorusAttendance/exports.
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+pub async fn export_attendance_sheet(
+    app: tauri::AppHandle,
+    template: String,
+    year: i32,
+    month: u32,
+    format: String,
+    department_id: Option<String>,
+    destination: Option<String>,
+    password: Option<String>,
+) -> Result<ExportedFile, String> {
+    if let Some(password) = &password {
+        protect::validate_password(password)?;
+    }
+    if !SHEET_TEMPLATES.contains(&template.as_str()) {
+        return Err(format!(
+            "Unknown sheet template '{}' (available: {})",
+            template,
+            SHEET_TEMPLATES.join(", ")
+        ));
+    }
+    if format != "xlsx" && format != "pdf" {
+        return Err(format!("Unsupported sheet format: {}", format));
+    }
+    log::info!("[export::cmd] export_attendance_sheet {} {}-{:02} as {}", template, year, month, format);
+
+    let dir = match destination {
+        Some(dest) => PathBuf::from(dest),
+        None => get_export_dir(&app)?,
+    };
+    let path = crate::path_policy::resolve_write_target(
+        &app,
+        &dir.join(format!("{}_{}{:02}.{}", template, year, month, format)).to_string_lossy(),
+    )?;
+
+    let conn = db::open(&app)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let register = sheet::load_monthly(&conn, year, month, department_id.as_deref(), None)?;
+        let file = match format.as_str() {
+            "pdf" => pdf::write_monthly_register(&register, &path)?,
+            _ => xlsx::write_monthly_register(&register, &path, password.as_deref())?,
+        };
+        let file = protect::finish(file, password.as_deref())?;
+        signing::store::sign_exported(&conn, std::slice::from_ref(&file), "attendance_sheet")?;
+        Ok(file)
+    })
+    .await
+    .map_err(|e| format!("Export task failed: {}", e))?
+}
+
+/// Export every user on the saved devices (or those in `device_ids`) with
+/// their fingerprint, face and card enrollment, as "csv" or "xlsx". Devices
+/// that can't be read are listed in the result. With a `password` the file
+/// is encrypted into a zip.
+/// `destination` is a directory; defaults to Documents/HorusAttendance/exports.
+#[tauri::command]
+pub async fn export_device_users(
+    app: tauri::AppHandle,
+    format: String,
+    device_ids: Option<Vec<String>>,
+    destination: Option<String>,
+    password: Option<String>,
+) -> Result<DeviceUserExport, String> {
+    if let Some(password) = &password {
+        protect::validate_password(password)?;
+    }
+    if format != "csv" && format != "xlsx" {
+        return Err(format!("Unsupported device user export format: {}", format));
+    }
+    let devices = device_users::devices(&*db::open(&app)?, device_ids.as_deref())?;
+    if devices.is_empty() {
+        return Err("No devices to export".to_string());
+    }
+    log::info!("[export::cmd] export_device_users from {} device(s) as {}", devices.len(), format);
+
+    let dir = match destination {
+        Some(dest) => PathBuf::from(dest),
+        None => get_export_dir(&app)?,
+    };
+    let path = crate::path_policy::resolve_write_target(
+        &app,
+        &dir.join(format!("device_users_{}.{}", chrono::Local::now().format("%Y%m%d"), format))
+            .to_string_lossy(),
+    )?;
+
+    let (rows, reads) = device_users::load(&app, devices).await?;
+    let conn = db::open(&app)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let file = match format.as_str() {
+            "xlsx" => device_users::write_xlsx(&rows, &path, password.as_deref())?,
+            _ => device_users::write_csv(&rows, &path)?,
+        };
+        let file = protect::finish(file, password.as_deref())?;
+        signing::store::sign_exported(&conn, std::slice::from_ref(&file), "device_users")?;
+        Ok(DeviceUserExport { file, devices: reads })
+    })
+    .await
+    .map_err(|e| format!("Export task failed: {}", e))?
+}
+
+/// Convert Gregorian dates (YYYY-MM-DD) to Hijri, applying the configured adjustment
+#[tauri::command]
+pub async fn convert_dates_to_hijri(app: tauri::AppHandle, dates: Vec<String>) -> Result<Vec<HijriDate>, String> {
+    let calendar = CalendarSettings::load(&*db::open(&app)?)?;
+    dates
+        .iter()
+        .map(|date| {
+            let parsed = chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .map_err(|_| format!("Invalid date (expected YYYY-MM-DD): {}", date))?;
+            Ok(hijri::describe(parsed, calendar.hijri_adjustment))
+        })
+        .collect()
+}
+
+/// Gregorian start and end dates of a Hijri month, for period pickers
+#[tauri::command]
+pub async fn get_hijri_month_range(app: tauri::AppHandle, year: i64, month: u32) -> Result<DateRange, String> {
+    let calendar = CalendarSettings::load(&*db::open(&app)?)?;
+    let (start, end) = hijri::month_range(year, month, calendar.hijri_adjustment)
+        .ok_or_else(|| format!("Invalid Hijri month: {}-{}", year, month))?;
+    Ok(DateRange {
+        start_date: start.format("%Y-%m-%d").to_string(),
+        end_date: end.format("%Y-%m-%d").to_string(),
+    })
+}
+
+/// Summary status counts per month. `calendar` is "hijri", "gregorian" or
+/// "period" (the configured reporting periods); defaults to Hijri months
+/// with the groupByHijriMonth setting, else to the reporting periods.
+#[tauri::command]
+pub async fn get_summary_month_totals(
+    app: tauri::AppHandle,
+    start_date: String,
+    end_date: String,
+    calendar: Option<String>,
+) -> Result<Vec<MonthTotals>, String> {
+    validate_date(&start_date)?;
+    validate_date(&end_date)?;
+    let conn = db::open(&app)?;
+    let settings = CalendarSettings::load(&conn)?;
+    let period_settings = periods::calc::load_settings(&conn)?;
+    let calendar = match calendar.as_deref() {
+        Some(c @ ("hijri" | "gregorian" | "period")) => c.to_string(),
+        Some(other) => return Err(format!("Unknown calendar: {}", other)),
+        None if settings.group_by_hijri_month => "hijri".to_string(),
+        None => "period".to_string(),
+    };
+    hijri::register_sql_functions(&conn, settings.hijri_adjustment)?;
+    periods::calc::register_sql_function(&conn, &period_settings)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let params = serde_json::json!({
+            "calendar": calendar,
+            "hijriAdjustment": settings.hijri_adjustment,
+            "periods": period_settings,
+        });
+        cached(&conn, "summary_month_totals", &params, &start_date, &end_date, || {
+            hijri::month_totals(&conn, &start_date, &end_date, &calendar, &period_settings)
+        })
+    })
+    .await
+        .map_err(|e| format!("Monthly totals task failed: {}", e))?
+}
+
+/// Saved fixed-width export layouts
+#[tauri::command]
+pub async fn list_export_layouts(app: tauri::AppHandle) -> Result<Vec<FixedWidthLayout>, String> {
+    fixed_width::list_layouts(&*db::open(&app)?)
+}
+
+/// Create or update a fixed-width layout
+#[tauri::command]
+pub async fn save_export_layout(app: tauri::AppHandle, layout: FixedWidthLayout) -> Result<FixedWidthLayout, String> {
+    log::info!("[export::cmd] save_export_layout '{}'", layout.name);
+    fixed_width::save_layout(&*db::open(&app)?, layout)
+}
+
+#[tauri::command]
+pub async fn delete_export_layout(app: tauri::AppHandle, id: String) -> Result<bool, String> {
+    log::info!("[export::cmd] delete_export_layout {}", id);
+    fixed_width::delete_layout(&*db::open(&app)?, &id)
+}
+
+/// The first records a layout produces for a period, saved or not, to check
+/// it against the payroll spec
+#[tauri::command]
+pub async fn preview_fixed_width(
+    app: tauri::AppHandle,
+    layout: FixedWidthLayout,
+    start_date: String,
+    end_date: String,
+    department_id: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<String>, String> {
+    validate_date(&start_date)?;
+    validate_date(&end_date)?;
+    let conn = db::open(&app)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        fixed_width::preview(
+            &conn,
+            &layout,
+            &start_date,
+            &end_date,
+            department_id.as_deref(),
+            limit.unwrap_or(20).min(500),
+        )
+    })
+    .await
+    .map_err(|e| format!("Preview task failed: {}", e))?
+}
+
+/// Export summaries between two dates as a fixed-width text file laid out by
+/// a saved layout, optionally for one department, encrypted into a zip when
+/// `password` is set.
+/// `destination` is a directory; defaults to Documents/HorusAttendance/exports.
+#[tauri::command]
+pub async fn export_fixed_width(
+    app: tauri::AppHandle,
+    layout_id: String,
+    start_date: String,
+    end_date: String,
+    department_id: Option<String>,
+    destination: Option<String>,
+    password: Option<String>,
+) -> Result<ExportedFile, String> {
+    validate_date(&start_date)?;
+    validate_date(&end_date)?;
+    if let Some(password) = &password {
+        protect::validate_password(password)?;
+    }
+    let conn = db::open(&app)?;
+    let layout = fixed_width::get_layout(&conn, &layout_id)?;
+    log::info!("[export::cmd] export_fixed_width '{}' {} to {}", layout.name, start_date, end_date);
+
+    let dir = match destination {
+        Some(dest) => PathBuf::from(dest),
+        None => get_export_dir(&app)?,
+    };
+    let suffix = format!("{}_{}", start_date.replace('-', ""), end_date.replace('-', ""));
+    let path = crate::path_policy::resolve_write_target(
+        &app,
+        &dir.join(format!("payroll_{}_{}.txt", layout.scope, suffix)).to_string_lossy(),
+    )?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let file = fixed_width::write(&conn, &layout, &start_date, &end_date, department_id.as_deref(), &path)?;
+        let file = protect::finish(file, password.as_deref())?;
+        signing::store::sign_exported(&conn, std::slice::from_ref(&file), "fixed_width_payroll")?;
+        Ok(file)
+    })
+    .await
+    .map_err(|e| format!("Export task failed: {}", e))?
+}
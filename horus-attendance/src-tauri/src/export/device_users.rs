@@ -0,0 +1,227 @@
+//! Device user list with enrollment status
+//!
+//! One row per user per terminal, with their fingerprint and face template
+//! counts and whether a card is set, and the profile the device user ID
+//! belongs to. Used to audit who still has to enroll on a terminal, a new
+//! face device for one.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+
+use rusqlite::Connection;
+use rust_xlsxwriter::{Format, Workbook, Worksheet, XlsxError};
+
+use super::types::{DeviceUserRead, ExportedFile};
+use crate::summary::history::csv_field;
+use crate::zkteco::client::ZKClient;
+use crate::zkteco::commands::{resolve_comm_key, saved_config, validate_config};
+use crate::zkteco::templates::{self, UserEnrollment};
+
+const HEADINGS: [&str; 11] = [
+    "Device",
+    "Device user ID",
+    "Name on device",
+    "Employee",
+    "Employee code",
+    "Department",
+    "Fingerprints",
+    "Faces",
+    "Card",
+    "Enrolled",
+    "Profile",
+];
+
+/// The profile a device user ID belongs to
+#[derive(Debug, Clone, Default)]
+pub struct Profile {
+    pub display_name: String,
+    pub employee_code: Option<String>,
+    pub department: Option<String>,
+}
+
+/// A row of the export
+#[derive(Debug, Clone)]
+pub struct DeviceUserRow {
+    pub device_name: String,
+    pub user: UserEnrollment,
+    pub profile: Option<Profile>,
+}
+
+impl DeviceUserRow {
+    fn enrolled(&self) -> bool {
+        self.user.fingerprints > 0 || self.user.faces > 0 || self.user.has_card
+    }
+}
+
+/// Profiles by device user ID, their own and linked ones
+pub fn profiles(conn: &Connection) -> Result<HashMap<String, Profile>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT u.device_user_id, u.display_name, u.employee_code, d.name FROM users u
+             LEFT JOIN departments d ON d.id = u.department_id
+             WHERE COALESCE(u.device_user_id, '') != ''
+             UNION ALL
+             SELECT k.device_user_id, u.display_name, u.employee_code, d.name FROM user_device_links k
+             JOIN users u ON u.id = k.user_id
+             LEFT JOIN departments d ON d.id = u.department_id",
+        )
+        .map_err(|e| format!("Failed to query users: {}", e))?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                Profile {
+                    display_name: row.get(1)?,
+                    employee_code: row.get(2)?,
+                    department: row.get(3)?,
+                },
+            ))
+        })
+        .map_err(|e| format!("Failed to query users: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read users: {}", e))
+}
+
+/// Saved terminals (id, name), all or those in `device_ids`. Pseudo-devices
+/// (API punches, corrections) have no address and are left out.
+pub fn devices(conn: &Connection, device_ids: Option<&[String]>) -> Result<Vec<(String, String)>, String> {
+    let mut stmt = conn
+        .prepare("SELECT id, name FROM devices WHERE ip != '' ORDER BY name")
+        .map_err(|e| format!("Failed to query devices: {}", e))?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|e| format!("Failed to query devices: {}", e))?;
+    let devices: Vec<(String, String)> = rows
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read devices: {}", e))?;
+    Ok(match device_ids {
+        Some(ids) => devices.into_iter().filter(|(id, _)| ids.contains(id)).collect(),
+        None => devices,
+    })
+}
+
+/// Read the users and templates of each saved device in `devices`
+/// (id, name). A device that can't be read is reported and left out.
+pub async fn load(
+    app: &tauri::AppHandle,
+    devices: Vec<(String, String)>,
+) -> Result<(Vec<DeviceUserRow>, Vec<DeviceUserRead>), String> {
+    let profiles = profiles(&*crate::db::open(app)?)?;
+    let mut rows = Vec::new();
+    let mut reads = Vec::with_capacity(devices.len());
+    for (device_id, device_name) in devices {
+        let users = async {
+            let config = saved_config(&*crate::db::open(app)?, &device_id)?;
+            validate_config(&config)?;
+            let mut client = ZKClient::connect(&resolve_comm_key(config).await?).await?;
+            let users = templates::read(&mut client).await;
+            let _ = client.disconnect().await;
+            users
+        }
+        .await;
+        if let Err(e) = &users {
+            log::warn!("[export] Could not read users of {}: {}", device_name, e);
+        }
+        reads.push(DeviceUserRead {
+            device_id,
+            device_name: device_name.clone(),
+            users: users.as_ref().map(|u| u.len() as u32).unwrap_or(0),
+            error: users.as_ref().err().cloned(),
+        });
+        for user in users.unwrap_or_default() {
+            rows.push(DeviceUserRow {
+                device_name: device_name.clone(),
+                profile: profiles.get(&user.device_user_id).cloned(),
+                user,
+            });
+        }
+    }
+    Ok((rows, reads))
+}
+
+fn yes_no(value: bool) -> &'static str {
+    if value {
+        "yes"
+    } else {
+        "no"
+    }
+}
+
+/// The text of each column
+fn cells(row: &DeviceUserRow) -> [String; 11] {
+    let profile = row.profile.as_ref();
+    [
+        row.device_name.clone(),
+        row.user.device_user_id.clone(),
+        row.user.name.clone(),
+        profile.map(|p| p.display_name.clone()).unwrap_or_default(),
+        profile.and_then(|p| p.employee_code.clone()).unwrap_or_default(),
+        profile.and_then(|p| p.department.clone()).unwrap_or_default(),
+        row.user.fingerprints.to_string(),
+        row.user.faces.to_string(),
+        yes_no(row.user.has_card).to_string(),
+        yes_no(row.enrolled()).to_string(),
+        yes_no(profile.is_some()).to_string(),
+    ]
+}
+
+/// Write `rows` as CSV to `path`
+pub fn write_csv(rows: &[DeviceUserRow], path: &Path) -> Result<ExportedFile, String> {
+    let file = std::fs::File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut out = std::io::BufWriter::new(file);
+    let write_err = |e: std::io::Error| format!("Failed to write {}: {}", path.display(), e);
+
+    writeln!(out, "{}", HEADINGS.map(csv_field).join(",")).map_err(write_err)?;
+    for row in rows {
+        writeln!(out, "{}", cells(row).map(|cell| csv_field(&cell)).join(",")).map_err(write_err)?;
+    }
+    out.flush().map_err(write_err)?;
+    Ok(exported(path, rows))
+}
+
+/// Write `rows` as an XLSX workbook to `path`, its sheet locked for editing
+/// when `password` is set
+pub fn write_xlsx(rows: &[DeviceUserRow], path: &Path, password: Option<&str>) -> Result<ExportedFile, String> {
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet();
+    layout(worksheet, rows).map_err(|e| format!("Failed to build device user sheet: {}", e))?;
+    if let Some(password) = password {
+        worksheet.protect_with_password(password);
+    }
+    workbook
+        .save(path)
+        .map_err(|e| format!("Failed to write XLSX file: {}", e))?;
+    Ok(exported(path, rows))
+}
+
+fn layout(ws: &mut Worksheet, rows: &[DeviceUserRow]) -> Result<(), XlsxError> {
+    ws.set_name("Device users")?;
+    let bold = Format::new().set_bold();
+    for (col, heading) in HEADINGS.iter().enumerate() {
+        ws.write_string_with_format(0, col as u16, *heading, &bold)?;
+    }
+    for (i, row) in rows.iter().enumerate() {
+        let r = i as u32 + 1;
+        for (col, cell) in cells(row).iter().enumerate() {
+            // Template counts stay numbers so they can be filtered and summed
+            match col {
+                6 => ws.write_number(r, col as u16, row.user.fingerprints)?,
+                7 => ws.write_number(r, col as u16, row.user.faces)?,
+                _ => ws.write_string(r, col as u16, cell)?,
+            };
+        }
+    }
+    ws.set_freeze_panes(1, 0)?;
+    ws.autofilter(0, 0, rows.len() as u32, HEADINGS.len() as u16 - 1)?;
+    ws.autofit();
+    Ok(())
+}
+
+fn exported(path: &Path, rows: &[DeviceUserRow]) -> ExportedFile {
+    ExportedFile {
+        path: path.to_string_lossy().to_string(),
+        rows: rows.len() as u64,
+        file_size: std::fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+    }
+}
@@ -0,0 +1,524 @@
+//! Fixed-width text exports for legacy payroll systems
+//!
+//! A layout lists the columns of each record: which field, how many
+//! characters, which side it is aligned to and what it is padded with.
+//! Layouts are stored in `export_layouts` so a payroll bureau's spec is set
+//! up once. A value longer than its column fails the export, naming the
+//! employee and column, unless the layout allows cutting text; numbers are
+//! never cut.
+//!
+//! Totals are per employee and cost center: days booked to different cost
+//! centers (see [`crate::cost_centers`]) get a record each.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use chrono::NaiveDate;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+use super::types::{ExportedFile, FixedWidthColumn, FixedWidthLayout};
+use crate::cost_centers;
+use crate::db;
+use crate::summary::rules;
+use crate::summary::types::AttendanceRules;
+
+pub const SCOPE_DAILY: &str = "daily";
+pub const SCOPE_TOTALS: &str = "totals";
+
+/// Column that prints its `value` as-is
+pub const FIELD_LITERAL: &str = "literal";
+
+/// Fields of a record per employee and day
+pub const DAILY_FIELDS: &[&str] = &[
+    "employeeCode",
+    "deviceUserId",
+    "name",
+    "department",
+    "costCenter",
+    "costCenterName",
+    "date",
+    "checkIn",
+    "checkOut",
+    "status",
+    "lateMinutes",
+    "earlyMinutes",
+    "workedMinutes",
+];
+
+/// Fields of a record per employee over the period
+pub const TOTALS_FIELDS: &[&str] = &[
+    "employeeCode",
+    "deviceUserId",
+    "name",
+    "department",
+    "costCenter",
+    "costCenterName",
+    "periodStart",
+    "periodEnd",
+    "presentDays",
+    "lateDays",
+    "earlyLeaveDays",
+    "absentDays",
+    "incompleteDays",
+    "lateMinutes",
+    "earlyMinutes",
+    "workedMinutes",
+];
+
+/// Widest record allowed
+const MAX_RECORD_WIDTH: usize = 4096;
+
+/// A record's value for one field
+#[derive(Debug, Clone)]
+enum Value {
+    Text(String),
+    /// HH:MM, empty when missing
+    Time(Option<String>),
+    Minutes(i64),
+    Count(i64),
+    Date(NaiveDate),
+}
+
+type Record = HashMap<&'static str, Value>;
+
+fn fields(scope: &str) -> Result<&'static [&'static str], String> {
+    match scope {
+        SCOPE_DAILY => Ok(DAILY_FIELDS),
+        SCOPE_TOTALS => Ok(TOTALS_FIELDS),
+        other => Err(format!("Unknown layout scope: {}", other)),
+    }
+}
+
+fn pad_char(column: &FixedWidthColumn) -> Result<char, String> {
+    let pad = column.pad.as_deref().unwrap_or(" ");
+    let mut chars = pad.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        _ => Err(format!("Padding of column '{}' must be one character", column.field)),
+    }
+}
+
+pub fn validate(layout: &FixedWidthLayout) -> Result<(), String> {
+    if layout.name.trim().is_empty() {
+        return Err("Layout name is required".to_string());
+    }
+    let allowed = fields(&layout.scope)?;
+    if layout.line_ending != "crlf" && layout.line_ending != "lf" {
+        return Err(format!("Unknown line ending: {}", layout.line_ending));
+    }
+    if layout.columns.is_empty() {
+        return Err("A layout needs at least one column".to_string());
+    }
+    for column in &layout.columns {
+        if column.field == FIELD_LITERAL {
+            if column.value.is_none() {
+                return Err("A literal column needs a value".to_string());
+            }
+        } else if !allowed.contains(&column.field.as_str()) {
+            return Err(format!(
+                "Unknown field '{}' for {} records (available: {})",
+                column.field,
+                layout.scope,
+                allowed.join(", ")
+            ));
+        }
+        if column.width == 0 {
+            return Err(format!("Column '{}' needs a width", column.field));
+        }
+        if column.align != "left" && column.align != "right" {
+            return Err(format!("Alignment of column '{}' must be left or right", column.field));
+        }
+        pad_char(column)?;
+        if let Some(format) = column.format.as_deref() {
+            let valid = match column.field.as_str() {
+                "date" | "periodStart" | "periodEnd" => format_value(&Value::Date(NaiveDate::MIN), Some(format)).is_ok(),
+                "checkIn" | "checkOut" => format == "hhmm",
+                "lateMinutes" | "earlyMinutes" | "workedMinutes" => matches!(format, "hhmm" | "hours100"),
+                _ => false,
+            };
+            if !valid {
+                return Err(format!("Invalid format '{}' for column '{}'", format, column.field));
+            }
+        }
+    }
+    let width: usize = layout.columns.iter().map(|c| c.width).sum();
+    if width > MAX_RECORD_WIDTH {
+        return Err(format!("Records may be at most {} characters wide", MAX_RECORD_WIDTH));
+    }
+    Ok(())
+}
+
+/// A value as text before padding; numbers are marked so they are never cut
+fn format_value(value: &Value, format: Option<&str>) -> Result<(String, bool), String> {
+    Ok(match value {
+        Value::Text(text) => (text.clone(), false),
+        Value::Time(time) => {
+            let time = time.clone().unwrap_or_default();
+            match format {
+                Some("hhmm") => (time.replace(':', ""), false),
+                _ => (time, false),
+            }
+        }
+        Value::Minutes(minutes) => match format {
+            Some("hhmm") => (format!("{:02}{:02}", minutes / 60, minutes % 60), true),
+            Some("hours100") => (((minutes * 100 + 30) / 60).to_string(), true),
+            _ => (minutes.to_string(), true),
+        },
+        Value::Count(count) => (count.to_string(), true),
+        Value::Date(date) => {
+            let pattern = format.unwrap_or("%Y%m%d");
+            let mut text = String::new();
+            std::fmt::Write::write_fmt(&mut text, format_args!("{}", date.format(pattern)))
+                .map_err(|_| format!("Invalid date format: {}", pattern))?;
+            (text, false)
+        }
+    })
+}
+
+/// One record laid out as `columns` describe
+fn render_record(layout: &FixedWidthLayout, record: &Record) -> Result<String, String> {
+    let mut line = String::new();
+    for column in &layout.columns {
+        let (text, numeric) = if column.field == FIELD_LITERAL {
+            (column.value.clone().unwrap_or_default(), false)
+        } else {
+            let value = record
+                .get(column.field.as_str())
+                .ok_or_else(|| format!("No value for field '{}'", column.field))?;
+            format_value(value, column.format.as_deref())?
+        };
+        let length = text.chars().count();
+        let text = if length > column.width {
+            if numeric || !layout.truncate {
+                let who = match record.get("employeeCode") {
+                    Some(Value::Text(code)) if !code.is_empty() => code.clone(),
+                    _ => match record.get("name") {
+                        Some(Value::Text(name)) => name.clone(),
+                        _ => String::new(),
+                    },
+                };
+                return Err(format!(
+                    "'{}' doesn't fit column '{}' ({} characters) for {}",
+                    text, column.field, column.width, who
+                ));
+            }
+            text.chars().take(column.width).collect()
+        } else {
+            text
+        };
+        let fill: String = std::iter::repeat(pad_char(column)?)
+            .take(column.width - text.chars().count())
+            .collect();
+        if column.align == "right" {
+            line.push_str(&fill);
+            line.push_str(&text);
+        } else {
+            line.push_str(&text);
+            line.push_str(&fill);
+        }
+    }
+    Ok(line)
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|e| format!("Invalid date {}: {}", value, e))
+}
+
+fn worked_minutes(check_in: Option<&str>, check_out: Option<&str>, attendance: &AttendanceRules) -> i64 {
+    match (check_in, check_out) {
+        (Some(check_in), Some(check_out)) => {
+            (rules::day_minutes(check_out, attendance) - rules::day_minutes(check_in, attendance)).max(0)
+        }
+        _ => 0,
+    }
+}
+
+/// Employee columns shared by both scopes: code, device user ID, name,
+/// department and cost center
+fn employee(row: &Row, record: &mut Record) -> rusqlite::Result<()> {
+    record.insert("employeeCode", Value::Text(row.get(0)?));
+    record.insert("deviceUserId", Value::Text(row.get(1)?));
+    record.insert("name", Value::Text(row.get(2)?));
+    record.insert("department", Value::Text(row.get(3)?));
+    record.insert("costCenter", Value::Text(row.get(11)?));
+    record.insert("costCenterName", Value::Text(row.get(12)?));
+    Ok(())
+}
+
+/// Summary rows from `start_date` to `end_date` of active users, optionally
+/// of one department, ordered by employee and date (by employee, cost
+/// center and date with `by_cost_center`)
+fn summary_rows(
+    conn: &Connection,
+    start_date: &str,
+    end_date: &str,
+    department_id: Option<&str>,
+    by_cost_center: bool,
+    mut each: impl FnMut(&Row) -> Result<(), String>,
+) -> Result<(), String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT COALESCE(u.employee_code, ''), COALESCE(u.device_user_id, ''), u.display_name,
+                    COALESCE(d.name, ''), u.id,
+                    s.date, s.check_in_time, s.check_out_time, s.status, s.late_minutes, s.early_minutes,
+                    COALESCE(cc.code, ''), COALESCE(cc.name, '')
+             FROM attendance_day_summary s
+             JOIN users u ON u.id = s.user_id
+             LEFT JOIN departments d ON d.id = u.department_id
+             LEFT JOIN cost_centers cc ON cc.id = {cost_center}
+             WHERE s.date >= ?1 AND s.date <= ?2 AND u.status = 'active'
+               AND (?3 IS NULL OR u.department_id = ?3)
+             ORDER BY COALESCE(u.employee_code, u.display_name), u.id, {order}s.date",
+            cost_center = cost_centers::store::effective_sql("u.id", "u.department_id", "s.date"),
+            order = if by_cost_center { "COALESCE(cc.code, ''), " } else { "" },
+        ))
+        .map_err(|e| format!("Failed to query summaries: {}", e))?;
+    let mut rows = stmt
+        .query(params![start_date, end_date, department_id])
+        .map_err(|e| format!("Failed to query summaries: {}", e))?;
+    while let Some(row) = rows.next().map_err(|e| format!("Failed to read summaries: {}", e))? {
+        each(row)?;
+    }
+    Ok(())
+}
+
+fn load_daily(
+    conn: &Connection,
+    start_date: &str,
+    end_date: &str,
+    department_id: Option<&str>,
+) -> Result<Vec<Record>, String> {
+    let attendance: AttendanceRules = db::get_json_setting(conn, "attendance")?.unwrap_or_default();
+    let mut records = Vec::new();
+    summary_rows(conn, start_date, end_date, department_id, false, |row| {
+        let read = || -> rusqlite::Result<(Record, String)> {
+            let mut record = Record::new();
+            employee(row, &mut record)?;
+            let check_in: Option<String> = row.get(6)?;
+            let check_out: Option<String> = row.get(7)?;
+            record.insert(
+                "workedMinutes",
+                Value::Minutes(worked_minutes(check_in.as_deref(), check_out.as_deref(), &attendance)),
+            );
+            record.insert("checkIn", Value::Time(check_in));
+            record.insert("checkOut", Value::Time(check_out));
+            record.insert("status", Value::Text(row.get(8)?));
+            record.insert("lateMinutes", Value::Minutes(row.get(9)?));
+            record.insert("earlyMinutes", Value::Minutes(row.get(10)?));
+            Ok((record, row.get(5)?))
+        };
+        let (mut record, date) = read().map_err(|e| format!("Failed to read summary: {}", e))?;
+        record.insert("date", Value::Date(parse_date(&date)?));
+        records.push(record);
+        Ok(())
+    })?;
+    Ok(records)
+}
+
+fn load_totals(
+    conn: &Connection,
+    start_date: &str,
+    end_date: &str,
+    department_id: Option<&str>,
+) -> Result<Vec<Record>, String> {
+    let attendance: AttendanceRules = db::get_json_setting(conn, "attendance")?.unwrap_or_default();
+    let period_start = parse_date(start_date)?;
+    let period_end = parse_date(end_date)?;
+    let mut records: Vec<Record> = Vec::new();
+    let mut current = (String::new(), String::new());
+    summary_rows(conn, start_date, end_date, department_id, true, |row| {
+        let failed = |e: rusqlite::Error| format!("Failed to read summary: {}", e);
+        let user_id: String = row.get(4).map_err(failed)?;
+        let cost_center: String = row.get(11).map_err(failed)?;
+        let check_in: Option<String> = row.get(6).map_err(failed)?;
+        let check_out: Option<String> = row.get(7).map_err(failed)?;
+        let status: String = row.get(8).map_err(failed)?;
+        let late: i64 = row.get(9).map_err(failed)?;
+        let early: i64 = row.get(10).map_err(failed)?;
+        if (&user_id, &cost_center) != (&current.0, &current.1) {
+            let mut record = Record::new();
+            employee(row, &mut record).map_err(failed)?;
+            record.insert("periodStart", Value::Date(period_start));
+            record.insert("periodEnd", Value::Date(period_end));
+            for field in [
+                "presentDays",
+                "lateDays",
+                "earlyLeaveDays",
+                "absentDays",
+                "incompleteDays",
+            ] {
+                record.insert(field, Value::Count(0));
+            }
+            for field in ["lateMinutes", "earlyMinutes", "workedMinutes"] {
+                record.insert(field, Value::Minutes(0));
+            }
+            records.push(record);
+            current = (user_id, cost_center);
+        }
+        let Some(record) = records.last_mut() else {
+            return Ok(());
+        };
+        let mut add = |field: &'static str, amount: i64| {
+            if let Some(Value::Count(n) | Value::Minutes(n)) = record.get_mut(field) {
+                *n += amount;
+            }
+        };
+        match status.as_str() {
+            "present" => add("presentDays", 1),
+            "late" => add("lateDays", 1),
+            "early_leave" => add("earlyLeaveDays", 1),
+            "absent" => add("absentDays", 1),
+            "incomplete" => add("incompleteDays", 1),
+            _ => {}
+        }
+        add("lateMinutes", late);
+        add("earlyMinutes", early);
+        add(
+            "workedMinutes",
+            worked_minutes(check_in.as_deref(), check_out.as_deref(), &attendance),
+        );
+        Ok(())
+    })?;
+    Ok(records)
+}
+
+fn load_records(
+    conn: &Connection,
+    layout: &FixedWidthLayout,
+    start_date: &str,
+    end_date: &str,
+    department_id: Option<&str>,
+) -> Result<Vec<Record>, String> {
+    match layout.scope.as_str() {
+        SCOPE_TOTALS => load_totals(conn, start_date, end_date, department_id),
+        _ => load_daily(conn, start_date, end_date, department_id),
+    }
+}
+
+/// The first `limit` records of an export, to check a layout against a spec
+pub fn preview(
+    conn: &Connection,
+    layout: &FixedWidthLayout,
+    start_date: &str,
+    end_date: &str,
+    department_id: Option<&str>,
+    limit: usize,
+) -> Result<Vec<String>, String> {
+    validate(layout)?;
+    load_records(conn, layout, start_date, end_date, department_id)?
+        .iter()
+        .take(limit)
+        .map(|record| render_record(layout, record))
+        .collect()
+}
+
+/// Write every record of the period to `path`. Nothing is written if any
+/// record doesn't fit.
+pub fn write(
+    conn: &Connection,
+    layout: &FixedWidthLayout,
+    start_date: &str,
+    end_date: &str,
+    department_id: Option<&str>,
+    path: &Path,
+) -> Result<ExportedFile, String> {
+    validate(layout)?;
+    let lines: Vec<String> = load_records(conn, layout, start_date, end_date, department_id)?
+        .iter()
+        .map(|record| render_record(layout, record))
+        .collect::<Result<_, _>>()?;
+    let ending = if layout.line_ending == "lf" { "\n" } else { "\r\n" };
+    let file = fs::File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut out = BufWriter::new(file);
+    for line in &lines {
+        out.write_all(line.as_bytes())
+            .and_then(|_| out.write_all(ending.as_bytes()))
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    }
+    out.flush().map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(ExportedFile {
+        path: path.to_string_lossy().to_string(),
+        rows: lines.len() as u64,
+        file_size: fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+    })
+}
+
+const SELECT_LAYOUT: &str = "SELECT id, name, scope, columns, line_ending, truncate, updated_at FROM export_layouts";
+
+fn layout_from_row(row: &Row) -> rusqlite::Result<FixedWidthLayout> {
+    let columns: String = row.get(3)?;
+    Ok(FixedWidthLayout {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        scope: row.get(2)?,
+        columns: serde_json::from_str(&columns).unwrap_or_default(),
+        line_ending: row.get(4)?,
+        truncate: row.get(5)?,
+        updated_at: row.get(6)?,
+    })
+}
+
+pub fn list_layouts(conn: &Connection) -> Result<Vec<FixedWidthLayout>, String> {
+    let mut stmt = conn
+        .prepare(&format!("{} ORDER BY name", SELECT_LAYOUT))
+        .map_err(|e| format!("Failed to query layouts: {}", e))?;
+    let rows = stmt
+        .query_map([], layout_from_row)
+        .map_err(|e| format!("Failed to query layouts: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read layouts: {}", e))
+}
+
+pub fn get_layout(conn: &Connection, id: &str) -> Result<FixedWidthLayout, String> {
+    conn.query_row(&format!("{} WHERE id = ?1", SELECT_LAYOUT), [id], layout_from_row)
+        .optional()
+        .map_err(|e| format!("Failed to read layout: {}", e))?
+        .ok_or_else(|| format!("Layout not found: {}", id))
+}
+
+/// Insert or update a layout (matched by ID)
+pub fn save_layout(conn: &Connection, mut layout: FixedWidthLayout) -> Result<FixedWidthLayout, String> {
+    layout.name = layout.name.trim().to_string();
+    validate(&layout)?;
+    if layout.id.is_empty() {
+        layout.id = uuid::Uuid::new_v4().to_string();
+    }
+    layout.updated_at = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+    let columns =
+        serde_json::to_string(&layout.columns).map_err(|e| format!("Failed to serialize columns: {}", e))?;
+    conn.execute(
+        "INSERT INTO export_layouts (id, name, scope, columns, line_ending, truncate, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)
+         ON CONFLICT(id) DO UPDATE SET
+           name = excluded.name,
+           scope = excluded.scope,
+           columns = excluded.columns,
+           line_ending = excluded.line_ending,
+           truncate = excluded.truncate,
+           updated_at = excluded.updated_at",
+        params![
+            layout.id,
+            layout.name,
+            layout.scope,
+            columns,
+            layout.line_ending,
+            layout.truncate,
+            layout.updated_at
+        ],
+    )
+    .map_err(|e| match e {
+        rusqlite::Error::SqliteFailure(err, _) if err.code == rusqlite::ErrorCode::ConstraintViolation => {
+            format!("A layout named '{}' already exists", layout.name)
+        }
+        e => format!("Failed to save layout: {}", e),
+    })?;
+    Ok(layout)
+}
+
+pub fn delete_layout(conn: &Connection, id: &str) -> Result<bool, String> {
+    conn.execute("DELETE FROM export_layouts WHERE id = ?1", [id])
+        .map(|n| n > 0)
+        .map_err(|e| format!("Failed to delete layout: {}", e))
+}
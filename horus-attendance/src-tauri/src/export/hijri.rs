@@ -0,0 +1,244 @@
+//! Hijri (Islamic) calendar support
+//!
+//! Uses the tabular (arithmetical) Islamic calendar, which matches the
+//! observed Umm al-Qura calendar to within a day or two. Organizations that
+//! follow local moon sighting can shift every date with the
+//! `hijriAdjustment` setting (-2..=2 days).
+//!
+//! Conversions are also exposed to SQL as `hijri_date(date)` (returns
+//! "YYYY-MM-DD" in Hijri) and `hijri_month(date)` ("YYYY-MM"), so exports and
+//! month grouping can stay in a single query.
+
+use chrono::{Datelike, NaiveDate};
+use rusqlite::functions::FunctionFlags;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use super::types::{HijriDate, MonthTotals};
+use crate::db;
+use crate::periods::calc as period_calc;
+use crate::periods::types::PeriodSettings;
+
+/// Settings key for calendar options
+pub const CALENDAR_KEY: &str = "calendar";
+
+/// Day number (chrono num_days_from_ce) of 1 Muharram 1 AH
+const EPOCH: i64 = 227_015;
+
+pub const MONTH_NAMES: [&str; 12] = [
+    "Muharram",
+    "Safar",
+    "Rabi al-Awwal",
+    "Rabi al-Thani",
+    "Jumada al-Ula",
+    "Jumada al-Akhirah",
+    "Rajab",
+    "Shaban",
+    "Ramadan",
+    "Shawwal",
+    "Dhu al-Qadah",
+    "Dhu al-Hijjah",
+];
+
+pub const MONTH_NAMES_AR: [&str; 12] = [
+    "محرم",
+    "صفر",
+    "ربيع الأول",
+    "ربيع الآخر",
+    "جمادى الأولى",
+    "جمادى الآخرة",
+    "رجب",
+    "شعبان",
+    "رمضان",
+    "شوال",
+    "ذو القعدة",
+    "ذو الحجة",
+];
+
+/// Calendar options (`calendar` setting)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CalendarSettings {
+    /// Show Hijri dates alongside Gregorian in reports and exports
+    #[serde(default)]
+    pub hijri_enabled: bool,
+    /// Days to shift the computed Hijri date by, for local moon sighting
+    #[serde(default)]
+    pub hijri_adjustment: i64,
+    /// Group monthly summaries by Hijri month instead of Gregorian
+    #[serde(default)]
+    pub group_by_hijri_month: bool,
+}
+
+impl CalendarSettings {
+    /// Load from settings, clamping the adjustment to a sane range
+    pub fn load(conn: &Connection) -> Result<Self, String> {
+        let mut settings: Self = db::get_json_setting(conn, CALENDAR_KEY)?.unwrap_or_default();
+        settings.hijri_adjustment = settings.hijri_adjustment.clamp(-2, 2);
+        Ok(settings)
+    }
+}
+
+/// Day number of a Hijri date
+fn hijri_to_day_number(year: i64, month: i64, day: i64) -> i64 {
+    day + (59 * (month - 1) + 1) / 2 + (year - 1) * 354 + (3 + 11 * year).div_euclid(30) + EPOCH - 1
+}
+
+/// Convert a Gregorian date to Hijri (year, month, day)
+pub fn to_hijri(date: NaiveDate, adjustment: i64) -> (i64, u32, u32) {
+    let n = date.num_days_from_ce() as i64 + adjustment;
+    let year = (30 * (n - EPOCH) + 10646).div_euclid(10631);
+    let since_new_year = n - (29 + hijri_to_day_number(year, 1, 1));
+    // ceil(since_new_year / 29.5) + 1
+    let month = (-(-2 * since_new_year).div_euclid(59) + 1).clamp(1, 12);
+    let day = n - hijri_to_day_number(year, month, 1) + 1;
+    (year, month as u32, day as u32)
+}
+
+/// Convert a Hijri date to Gregorian
+pub fn from_hijri(year: i64, month: u32, day: u32, adjustment: i64) -> Option<NaiveDate> {
+    if !(1..=12).contains(&month) || !(1..=30).contains(&day) {
+        return None;
+    }
+    let n = hijri_to_day_number(year, month as i64, day as i64) - adjustment;
+    NaiveDate::from_num_days_from_ce_opt(i32::try_from(n).ok()?)
+}
+
+/// First and last Gregorian dates of a Hijri month
+pub fn month_range(year: i64, month: u32, adjustment: i64) -> Option<(NaiveDate, NaiveDate)> {
+    let start = from_hijri(year, month, 1, adjustment)?;
+    let next = if month == 12 {
+        from_hijri(year + 1, 1, 1, adjustment)?
+    } else {
+        from_hijri(year, month + 1, 1, adjustment)?
+    };
+    Some((start, next.pred_opt()?))
+}
+
+/// Full description of a Gregorian date in Hijri
+pub fn describe(date: NaiveDate, adjustment: i64) -> HijriDate {
+    let (year, month, day) = to_hijri(date, adjustment);
+    let index = (month - 1) as usize;
+    HijriDate {
+        gregorian: date.format("%Y-%m-%d").to_string(),
+        year,
+        month,
+        day,
+        month_name: MONTH_NAMES[index].to_string(),
+        month_name_ar: MONTH_NAMES_AR[index].to_string(),
+        formatted: format!("{} {} {} AH", day, MONTH_NAMES[index], year),
+        iso: format!("{:04}-{:02}-{:02}", year, month, day),
+    }
+}
+
+/// Register `hijri_date(date)` and `hijri_month(date)` on a connection.
+/// Both return NULL for anything that isn't a YYYY-MM-DD date (or a
+/// timestamp starting with one).
+pub fn register_sql_functions(conn: &Connection, adjustment: i64) -> Result<(), String> {
+    let flags = FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC;
+    let parse = |ctx: &rusqlite::functions::Context<'_>| -> rusqlite::Result<Option<NaiveDate>> {
+        let value: Option<String> = ctx.get(0)?;
+        Ok(value.and_then(|v| NaiveDate::parse_from_str(v.get(..10)?, "%Y-%m-%d").ok()))
+    };
+
+    conn.create_scalar_function("hijri_date", 1, flags, move |ctx| {
+        Ok(parse(ctx)?.map(|d| {
+            let (y, m, day) = to_hijri(d, adjustment);
+            format!("{:04}-{:02}-{:02}", y, m, day)
+        }))
+    })
+    .map_err(|e| format!("Failed to register hijri_date: {}", e))?;
+
+    conn.create_scalar_function("hijri_month", 1, flags, move |ctx| {
+        Ok(parse(ctx)?.map(|d| {
+            let (y, m, _) = to_hijri(d, adjustment);
+            format!("{:04}-{:02}", y, m)
+        }))
+    })
+    .map_err(|e| format!("Failed to register hijri_month: {}", e))?;
+    Ok(())
+}
+
+/// Count summary statuses per month between two dates (inclusive), grouped
+/// by Hijri month, Gregorian month or reporting period (`calendar` is
+/// "hijri", "gregorian" or "period"). Requires register_sql_functions for
+/// Hijri and periods::calc::register_sql_function for periods.
+pub fn month_totals(
+    conn: &Connection,
+    start_date: &str,
+    end_date: &str,
+    calendar: &str,
+    periods: &PeriodSettings,
+) -> Result<Vec<MonthTotals>, String> {
+    let hijri = calendar == "hijri";
+    let key = match calendar {
+        "hijri" => "hijri_month(date)",
+        "period" => "pay_period(date)",
+        _ => "substr(date, 1, 7)",
+    };
+    let sql = format!(
+        "SELECT {key} AS month, MIN(date), MAX(date), COUNT(*),
+                SUM(status = 'present'), SUM(status = 'late'), SUM(status = 'early_leave'),
+                SUM(status = 'absent'), SUM(status = 'incomplete'), SUM(late_minutes)
+         FROM attendance_day_summary
+         WHERE date >= ?1 AND date <= ?2
+         GROUP BY month
+         ORDER BY month",
+        key = key
+    );
+    let mut stmt = conn
+        .prepare(&sql)
+        .map_err(|e| format!("Failed to query monthly totals: {}", e))?;
+    let rows = stmt
+        .query_map([start_date, end_date], |row| {
+            Ok(MonthTotals {
+                month: row.get::<_, Option<String>>(0)?.unwrap_or_default(),
+                label: String::new(),
+                calendar: calendar.to_string(),
+                first_date: row.get(1)?,
+                last_date: row.get(2)?,
+                days: row.get(3)?,
+                present: row.get(4)?,
+                late: row.get(5)?,
+                early_leave: row.get(6)?,
+                absent: row.get(7)?,
+                incomplete: row.get(8)?,
+                late_minutes: row.get(9)?,
+            })
+        })
+        .map_err(|e| format!("Failed to query monthly totals: {}", e))?;
+    let mut totals: Vec<MonthTotals> = rows
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read monthly totals: {}", e))?;
+
+    for month in &mut totals {
+        month.label = if calendar == "period" {
+            NaiveDate::parse_from_str(&month.first_date, "%Y-%m-%d")
+                .ok()
+                .and_then(|d| period_calc::containing(periods, d).ok())
+                .map(|p| p.label)
+                .unwrap_or_else(|| month.month.clone())
+        } else {
+            month_label(&month.month, hijri)
+        };
+    }
+    Ok(totals)
+}
+
+/// "Ramadan 1445" / "March 2024" from a "YYYY-MM" key
+fn month_label(key: &str, hijri: bool) -> String {
+    let Some((year, month)) = key.split_once('-') else {
+        return key.to_string();
+    };
+    let month: usize = month.parse().unwrap_or(0);
+    if hijri {
+        match MONTH_NAMES.get(month.wrapping_sub(1)) {
+            Some(name) => format!("{} {}", name, year),
+            None => key.to_string(),
+        }
+    } else {
+        NaiveDate::parse_from_str(&format!("{}-01", key), "%Y-%m-%d")
+            .map(|d| d.format("%B %Y").to_string())
+            .unwrap_or_else(|_| key.to_string())
+    }
+}
@@ -0,0 +1,15 @@
+//! Data export subsystem
+//!
+//! Bulk exports generated entirely in the backend, so large periods never
+//! have to be marshalled through the webview.
+
+pub mod commands;
+pub mod device_users;
+pub mod fixed_width;
+pub mod hijri;
+pub mod parquet;
+pub mod pdf;
+pub mod protect;
+pub mod sheet;
+pub mod types;
+pub mod xlsx;
@@ -0,0 +1,294 @@
+//! Columnar (Parquet) export of raw logs and daily summaries
+//!
+//! Files are readable by DuckDB, Power BI, pandas and friends. Rows are
+//! streamed from SQLite and flushed in row groups so memory stays bounded
+//! even for multi-year exports. Timestamps are kept as the stored strings
+//! (device wall-clock time) to avoid any timezone reinterpretation.
+
+use parquet::basic::Compression;
+use parquet::data_type::{BoolType, ByteArray, ByteArrayType, DoubleType, Int64Type};
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::parser::parse_message_type;
+use rusqlite::{params, Connection, Row};
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+use super::types::ExportedFile;
+
+/// Rows buffered before a row group is written
+const ROW_GROUP_SIZE: usize = 50_000;
+
+const LOGS_SCHEMA: &str = "
+message attendance_logs {
+    OPTIONAL BYTE_ARRAY id (UTF8);
+    OPTIONAL BYTE_ARRAY device_id (UTF8);
+    OPTIONAL BYTE_ARRAY device_user_id (UTF8);
+    OPTIONAL BYTE_ARRAY timestamp (UTF8);
+    OPTIONAL INT64 verify_type;
+    OPTIONAL INT64 punch_type;
+    OPTIONAL BYTE_ARRAY created_at (UTF8);
+    OPTIONAL BYTE_ARRAY source (UTF8);
+    OPTIONAL DOUBLE latitude;
+    OPTIONAL DOUBLE longitude;
+    OPTIONAL BYTE_ARRAY note (UTF8);
+}";
+
+const SUMMARIES_SCHEMA: &str = "
+message attendance_summaries {
+    OPTIONAL BYTE_ARRAY user_id (UTF8);
+    OPTIONAL BYTE_ARRAY user_name (UTF8);
+    OPTIONAL BYTE_ARRAY employee_code (UTF8);
+    OPTIONAL BYTE_ARRAY department (UTF8);
+    OPTIONAL BYTE_ARRAY date (UTF8);
+    OPTIONAL BYTE_ARRAY hijri_date (UTF8);
+    OPTIONAL BYTE_ARRAY check_in_time (UTF8);
+    OPTIONAL BYTE_ARRAY check_out_time (UTF8);
+    OPTIONAL BOOLEAN is_incomplete;
+    OPTIONAL INT64 late_minutes;
+    OPTIONAL INT64 early_minutes;
+    OPTIONAL BYTE_ARRAY status (UTF8);
+    OPTIONAL BYTE_ARRAY flags (UTF8);
+    OPTIONAL BYTE_ARRAY cost_center_code (UTF8);
+    OPTIONAL BYTE_ARRAY cost_center (UTF8);
+}";
+
+/// Kind of a column, matching its physical type in the schema
+#[derive(Clone, Copy)]
+enum Kind {
+    Text,
+    Int,
+    Double,
+    Bool,
+}
+
+/// Buffered values for one column (definition level 1 = present, 0 = null)
+enum ColumnBuffer {
+    Text(Vec<ByteArray>, Vec<i16>),
+    Int(Vec<i64>, Vec<i16>),
+    Double(Vec<f64>, Vec<i16>),
+    Bool(Vec<bool>, Vec<i16>),
+}
+
+impl ColumnBuffer {
+    fn new(kind: Kind) -> Self {
+        match kind {
+            Kind::Text => Self::Text(Vec::new(), Vec::new()),
+            Kind::Int => Self::Int(Vec::new(), Vec::new()),
+            Kind::Double => Self::Double(Vec::new(), Vec::new()),
+            Kind::Bool => Self::Bool(Vec::new(), Vec::new()),
+        }
+    }
+
+    /// Append column `idx` of a SQLite row
+    fn push(&mut self, row: &Row, idx: usize) -> rusqlite::Result<()> {
+        match self {
+            Self::Text(values, defs) => match row.get::<_, Option<String>>(idx)? {
+                Some(v) => {
+                    values.push(ByteArray::from(v.into_bytes()));
+                    defs.push(1);
+                }
+                None => defs.push(0),
+            },
+            Self::Int(values, defs) => match row.get::<_, Option<i64>>(idx)? {
+                Some(v) => {
+                    values.push(v);
+                    defs.push(1);
+                }
+                None => defs.push(0),
+            },
+            Self::Double(values, defs) => match row.get::<_, Option<f64>>(idx)? {
+                Some(v) => {
+                    values.push(v);
+                    defs.push(1);
+                }
+                None => defs.push(0),
+            },
+            Self::Bool(values, defs) => match row.get::<_, Option<bool>>(idx)? {
+                Some(v) => {
+                    values.push(v);
+                    defs.push(1);
+                }
+                None => defs.push(0),
+            },
+        }
+        Ok(())
+    }
+
+    fn clear(&mut self) {
+        match self {
+            Self::Text(values, defs) => {
+                values.clear();
+                defs.clear();
+            }
+            Self::Int(values, defs) => {
+                values.clear();
+                defs.clear();
+            }
+            Self::Double(values, defs) => {
+                values.clear();
+                defs.clear();
+            }
+            Self::Bool(values, defs) => {
+                values.clear();
+                defs.clear();
+            }
+        }
+    }
+}
+
+/// Stream a query into a Parquet file. The query's columns must match the
+/// schema fields in order.
+fn write_query(
+    conn: &Connection,
+    sql: &str,
+    query_params: &[&dyn rusqlite::ToSql],
+    schema: &str,
+    kinds: &[Kind],
+    path: &Path,
+) -> Result<ExportedFile, String> {
+    let schema = Arc::new(parse_message_type(schema).map_err(|e| format!("Invalid export schema: {}", e))?);
+    let props = Arc::new(
+        WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build(),
+    );
+    let file = File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut writer = SerializedFileWriter::new(file, schema, props)
+        .map_err(|e| format!("Failed to start Parquet file: {}", e))?;
+
+    let mut buffers: Vec<ColumnBuffer> = kinds.iter().map(|k| ColumnBuffer::new(*k)).collect();
+    let mut buffered = 0usize;
+    let mut total_rows = 0u64;
+
+    let mut stmt = conn.prepare(sql).map_err(|e| format!("Failed to prepare export query: {}", e))?;
+    let mut rows = stmt
+        .query(query_params)
+        .map_err(|e| format!("Failed to run export query: {}", e))?;
+
+    while let Some(row) = rows.next().map_err(|e| format!("Failed to read export row: {}", e))? {
+        for (idx, buffer) in buffers.iter_mut().enumerate() {
+            buffer.push(row, idx).map_err(|e| format!("Failed to read export column: {}", e))?;
+        }
+        buffered += 1;
+        total_rows += 1;
+
+        if buffered >= ROW_GROUP_SIZE {
+            flush_row_group(&mut writer, &mut buffers)?;
+            buffered = 0;
+        }
+    }
+    if buffered > 0 {
+        flush_row_group(&mut writer, &mut buffers)?;
+    }
+
+    writer
+        .close()
+        .map_err(|e| format!("Failed to finish Parquet file: {}", e))?;
+
+    let file_size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    Ok(ExportedFile {
+        path: path.to_string_lossy().to_string(),
+        rows: total_rows,
+        file_size,
+    })
+}
+
+/// Write buffered columns as one row group and reset the buffers
+fn flush_row_group(writer: &mut SerializedFileWriter<File>, buffers: &mut [ColumnBuffer]) -> Result<(), String> {
+    let mut row_group = writer
+        .next_row_group()
+        .map_err(|e| format!("Failed to start row group: {}", e))?;
+
+    for buffer in buffers.iter_mut() {
+        let mut column = row_group
+            .next_column()
+            .map_err(|e| format!("Failed to start column: {}", e))?
+            .ok_or("Export schema has fewer columns than the query")?;
+
+        let written = match buffer {
+            ColumnBuffer::Text(values, defs) => column
+                .typed::<ByteArrayType>()
+                .write_batch(values, Some(defs), None),
+            ColumnBuffer::Int(values, defs) => column
+                .typed::<Int64Type>()
+                .write_batch(values, Some(defs), None),
+            ColumnBuffer::Double(values, defs) => column
+                .typed::<DoubleType>()
+                .write_batch(values, Some(defs), None),
+            ColumnBuffer::Bool(values, defs) => column
+                .typed::<BoolType>()
+                .write_batch(values, Some(defs), None),
+        };
+        written.map_err(|e| format!("Failed to write column: {}", e))?;
+        column.close().map_err(|e| format!("Failed to close column: {}", e))?;
+        buffer.clear();
+    }
+
+    row_group
+        .close()
+        .map_err(|e| format!("Failed to close row group: {}", e))?;
+    Ok(())
+}
+
+/// Export raw attendance logs with timestamps between two dates (inclusive),
+/// with where each came from and, for phone punches, where it was made
+pub fn export_logs(conn: &Connection, start_date: &str, end_date: &str, path: &Path) -> Result<ExportedFile, String> {
+    let start = format!("{}T00:00:00", start_date);
+    let end = format!("{}T23:59:59.999Z", end_date);
+    write_query(
+        conn,
+        "SELECT id, device_id, device_user_id, timestamp, verify_type, punch_type, created_at,
+                source, latitude, longitude, note
+         FROM attendance_logs_raw
+         WHERE timestamp >= ?1 AND timestamp <= ?2
+         ORDER BY timestamp ASC",
+        params![start, end],
+        LOGS_SCHEMA,
+        &[
+            Kind::Text, Kind::Text, Kind::Text, Kind::Text, Kind::Int, Kind::Int, Kind::Text,
+            Kind::Text, Kind::Double, Kind::Double, Kind::Text,
+        ],
+        path,
+    )
+}
+
+/// Export daily summaries between two dates (inclusive), denormalised with
+/// user, department and cost center names for BI tools; the cost center is
+/// the one in effect for the user that day. `hijri` fills the hijri_date
+/// column (needs hijri::register_sql_functions); otherwise it is null.
+pub fn export_summaries(
+    conn: &Connection,
+    start_date: &str,
+    end_date: &str,
+    path: &Path,
+    hijri: bool,
+) -> Result<ExportedFile, String> {
+    let sql = format!(
+        "SELECT s.user_id, u.display_name, u.employee_code, d.name, s.date, {hijri_date},
+                s.check_in_time, s.check_out_time, s.is_incomplete,
+                s.late_minutes, s.early_minutes, s.status, s.flags, cc.code, cc.name
+         FROM attendance_day_summary s
+         LEFT JOIN users u ON u.id = s.user_id
+         LEFT JOIN departments d ON d.id = u.department_id
+         LEFT JOIN cost_centers cc ON cc.id = {cost_center}
+         WHERE s.date >= ?1 AND s.date <= ?2
+         ORDER BY s.date ASC, s.user_id ASC",
+        hijri_date = if hijri { "hijri_date(s.date)" } else { "NULL" },
+        cost_center = crate::cost_centers::store::effective_sql("s.user_id", "u.department_id", "s.date"),
+    );
+    write_query(
+        conn,
+        &sql,
+        params![start_date, end_date],
+        SUMMARIES_SCHEMA,
+        &[
+            Kind::Text, Kind::Text, Kind::Text, Kind::Text, Kind::Text, Kind::Text,
+            Kind::Text, Kind::Text, Kind::Bool,
+            Kind::Int, Kind::Int, Kind::Text, Kind::Text,
+            Kind::Text, Kind::Text,
+        ],
+        path,
+    )
+}
@@ -0,0 +1,304 @@
+//! PDF rendering of attendance registers
+//!
+//! Same layout as the XLSX register, drawn on landscape A3 pages with the
+//! header repeated on each page and the legend and signature blocks on the
+//! last one. Uses the built-in Helvetica fonts, so text is limited to
+//! Windows-1252; characters outside it are dropped by the PDF writer.
+
+use printpdf::{BuiltinFont, IndirectFontRef, Line, Mm, PdfDocument, PdfDocumentReference, PdfLayerReference, Point};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use super::sheet::{MonthlySheet, SheetRow, LEGEND, SIGNATURES, TOTAL_HEADINGS};
+use super::types::ExportedFile;
+
+const PAGE_WIDTH: f32 = 420.0;
+const PAGE_HEIGHT: f32 = 297.0;
+const MARGIN: f32 = 10.0;
+
+/// Height of one In or Out line; each employee takes two
+const LINE_HEIGHT: f32 = 5.0;
+/// Space above the grid for the title block
+const TITLE_HEIGHT: f32 = 22.0;
+/// Space reserved below the grid on the last page for legend and signatures
+const FOOTER_HEIGHT: f32 = 28.0;
+
+const NO_WIDTH: f32 = 7.0;
+const CODE_WIDTH: f32 = 16.0;
+const NAME_WIDTH: f32 = 40.0;
+const LABEL_WIDTH: f32 = 8.0;
+const TOTAL_WIDTH: f32 = 12.0;
+const SIGNATURE_WIDTH: f32 = 25.0;
+
+/// Helvetica's average glyph width as a fraction of the font size, used to
+/// centre text without font metrics
+const AVG_GLYPH_WIDTH: f32 = 0.5;
+const PT_TO_MM: f32 = 0.3528;
+
+/// Write the labor office monthly register as a PDF document
+pub fn write_monthly_register(sheet: &MonthlySheet, path: &Path) -> Result<ExportedFile, String> {
+    let file = File::create(path).map_err(|e| format!("Failed to create PDF file: {}", e))?;
+    build(sheet)?
+        .save(&mut BufWriter::new(file))
+        .map_err(|e| format!("Failed to write PDF file: {}", e))?;
+    let file_size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    Ok(ExportedFile {
+        path: path.to_string_lossy().to_string(),
+        rows: sheet.rows.len() as u64,
+        file_size,
+    })
+}
+
+/// The register as PDF bytes, for serving without a file
+pub fn monthly_register_bytes(sheet: &MonthlySheet) -> Result<Vec<u8>, String> {
+    build(sheet)?
+        .save_to_bytes()
+        .map_err(|e| format!("Failed to write PDF: {}", e))
+}
+
+fn build(sheet: &MonthlySheet) -> Result<PdfDocumentReference, String> {
+    let title = format!("Monthly Attendance Register - {}", sheet.period);
+    let (doc, first_page, first_layer) = PdfDocument::new(&title, Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Register");
+    let fonts = Fonts {
+        regular: doc
+            .add_builtin_font(BuiltinFont::Helvetica)
+            .map_err(|e| format!("Failed to load PDF font: {}", e))?,
+        bold: doc
+            .add_builtin_font(BuiltinFont::HelveticaBold)
+            .map_err(|e| format!("Failed to load PDF font: {}", e))?,
+    };
+
+    let columns = Columns::new(sheet.dates.len());
+    let grid_height = PAGE_HEIGHT - 2.0 * MARGIN - TITLE_HEIGHT - 2.0 * LINE_HEIGHT;
+    let per_page = ((grid_height / (2.0 * LINE_HEIGHT)) as usize).max(1);
+    let per_last_page = (((grid_height - FOOTER_HEIGHT) / (2.0 * LINE_HEIGHT)) as usize).max(1);
+
+    // Split rows into pages, leaving room for the footer on the last one
+    let mut pages: Vec<&[SheetRow]> = Vec::new();
+    let mut rest = &sheet.rows[..];
+    while rest.len() > per_last_page {
+        let take = rest.len().min(per_page);
+        pages.push(&rest[..take]);
+        rest = &rest[take..];
+    }
+    pages.push(rest);
+
+    let page_count = pages.len();
+    let mut number = 1;
+    for (i, rows) in pages.into_iter().enumerate() {
+        let layer = if i == 0 {
+            doc.get_page(first_page).get_layer(first_layer)
+        } else {
+            let (page, layer) = doc.add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Register");
+            doc.get_page(page).get_layer(layer)
+        };
+        let canvas = Canvas { layer, fonts: &fonts };
+        let mut y = draw_title(&canvas, sheet, &title, i + 1, page_count);
+        y = draw_header(&canvas, sheet, &columns, y);
+        for row in rows {
+            y = draw_row(&canvas, row, number, &columns, y);
+            number += 1;
+        }
+        if i + 1 == page_count {
+            draw_footer(&canvas, y);
+        }
+    }
+
+    Ok(doc)
+}
+
+struct Fonts {
+    regular: IndirectFontRef,
+    bold: IndirectFontRef,
+}
+
+/// X offsets of every grid column
+struct Columns {
+    day_width: f32,
+    days: usize,
+}
+
+impl Columns {
+    fn new(days: usize) -> Self {
+        let fixed = NO_WIDTH + CODE_WIDTH + NAME_WIDTH + LABEL_WIDTH
+            + TOTAL_WIDTH * TOTAL_HEADINGS.len() as f32
+            + SIGNATURE_WIDTH;
+        Self {
+            day_width: (PAGE_WIDTH - 2.0 * MARGIN - fixed) / days.max(1) as f32,
+            days,
+        }
+    }
+
+    fn no(&self) -> f32 {
+        MARGIN
+    }
+
+    fn code(&self) -> f32 {
+        self.no() + NO_WIDTH
+    }
+
+    fn name(&self) -> f32 {
+        self.code() + CODE_WIDTH
+    }
+
+    fn label(&self) -> f32 {
+        self.name() + NAME_WIDTH
+    }
+
+    fn day(&self, i: usize) -> f32 {
+        self.label() + LABEL_WIDTH + i as f32 * self.day_width
+    }
+
+    fn total(&self, i: usize) -> f32 {
+        self.day(self.days) + i as f32 * TOTAL_WIDTH
+    }
+
+    fn signature(&self) -> f32 {
+        self.total(TOTAL_HEADINGS.len())
+    }
+
+    fn right(&self) -> f32 {
+        self.signature() + SIGNATURE_WIDTH
+    }
+
+    /// Left edges of every column plus the right edge of the grid
+    fn edges(&self) -> Vec<f32> {
+        let mut edges = vec![self.no(), self.code(), self.name(), self.label()];
+        edges.extend((0..self.days).map(|i| self.day(i)));
+        edges.extend((0..TOTAL_HEADINGS.len()).map(|i| self.total(i)));
+        edges.push(self.signature());
+        edges.push(self.right());
+        edges
+    }
+}
+
+struct Canvas<'a> {
+    layer: PdfLayerReference,
+    fonts: &'a Fonts,
+}
+
+impl Canvas<'_> {
+    fn text(&self, text: &str, size: f32, x: f32, y: f32, bold: bool) {
+        let font = if bold { &self.fonts.bold } else { &self.fonts.regular };
+        self.layer.use_text(text, size, Mm(x), Mm(y), font);
+    }
+
+    /// Draw text centred in a cell of the given width, baseline near the middle
+    #[allow(clippy::too_many_arguments)]
+    fn centered(&self, text: &str, size: f32, x: f32, width: f32, top: f32, height: f32, bold: bool) {
+        let text_width = text.chars().count() as f32 * size * AVG_GLYPH_WIDTH * PT_TO_MM;
+        let baseline = top - height / 2.0 - size * PT_TO_MM / 3.0;
+        self.text(text, size, x + (width - text_width).max(0.0) / 2.0, baseline, bold);
+    }
+
+    fn line(&self, x1: f32, y1: f32, x2: f32, y2: f32) {
+        self.layer.add_line(Line {
+            points: vec![(Point::new(Mm(x1), Mm(y1)), false), (Point::new(Mm(x2), Mm(y2)), false)],
+            is_closed: false,
+        });
+    }
+}
+
+/// Draw the title block and return the y of its bottom edge
+fn draw_title(canvas: &Canvas, sheet: &MonthlySheet, title: &str, page: usize, page_count: usize) -> f32 {
+    let top = PAGE_HEIGHT - MARGIN;
+    canvas.text(&sheet.organization, 14.0, MARGIN, top - 5.0, true);
+    canvas.text(title, 11.0, MARGIN, top - 11.0, true);
+    if let Some(department) = &sheet.department {
+        canvas.text(&format!("Department: {}", department), 9.0, MARGIN, top - 17.0, false);
+    }
+    let page_label = format!("Page {} of {}", page, page_count);
+    canvas.text(&page_label, 8.0, PAGE_WIDTH - MARGIN - 20.0, top - 5.0, false);
+    top - TITLE_HEIGHT
+}
+
+/// Draw the two-row column header and return the y of its bottom edge
+fn draw_header(canvas: &Canvas, sheet: &MonthlySheet, columns: &Columns, top: f32) -> f32 {
+    let height = 2.0 * LINE_HEIGHT;
+    let merged = [
+        (columns.no(), NO_WIDTH, "No."),
+        (columns.code(), CODE_WIDTH, "Emp. Code"),
+        (columns.name(), NAME_WIDTH, "Employee Name"),
+        (columns.signature(), SIGNATURE_WIDTH, "Signature"),
+    ];
+    for (x, width, label) in merged {
+        canvas.centered(label, 7.0, x, width, top, height, true);
+    }
+    for (i, label) in TOTAL_HEADINGS.iter().enumerate() {
+        canvas.centered(label, 6.0, columns.total(i), TOTAL_WIDTH, top, height, true);
+    }
+    for (i, date) in sheet.dates.iter().enumerate() {
+        let x = columns.day(i);
+        canvas.centered(&date.format("%-d").to_string(), 7.0, x, columns.day_width, top, LINE_HEIGHT, true);
+        canvas.centered(&date.format("%a").to_string(), 6.0, x, columns.day_width, top - LINE_HEIGHT, LINE_HEIGHT, false);
+    }
+
+    canvas.line(columns.no(), top, columns.right(), top);
+    canvas.line(columns.day(0), top - LINE_HEIGHT, columns.day(sheet.dates.len()), top - LINE_HEIGHT);
+    canvas.line(columns.no(), top - height, columns.right(), top - height);
+    for x in columns.edges() {
+        canvas.line(x, top, x, top - height);
+    }
+    top - height
+}
+
+/// Draw one employee's In/Out rows and return the y of their bottom edge
+fn draw_row(canvas: &Canvas, row: &SheetRow, number: usize, columns: &Columns, top: f32) -> f32 {
+    let height = 2.0 * LINE_HEIGHT;
+    let middle = top - LINE_HEIGHT;
+
+    canvas.centered(&number.to_string(), 7.0, columns.no(), NO_WIDTH, top, height, false);
+    canvas.centered(&row.employee_code, 7.0, columns.code(), CODE_WIDTH, top, height, false);
+    canvas.text(&truncate(&row.name, 30), 7.0, columns.name() + 1.0, top - height / 2.0 - 0.8, false);
+    canvas.centered("In", 6.0, columns.label(), LABEL_WIDTH, top, LINE_HEIGHT, false);
+    canvas.centered("Out", 6.0, columns.label(), LABEL_WIDTH, middle, LINE_HEIGHT, false);
+
+    for (i, day) in row.cells.iter().enumerate() {
+        let x = columns.day(i);
+        match (&day.check_in, &day.check_out, day.mark) {
+            (None, None, Some(mark)) => canvas.centered(mark, 7.0, x, columns.day_width, top, height, true),
+            (check_in, check_out, _) => {
+                if let Some(t) = check_in {
+                    canvas.centered(t, 6.0, x, columns.day_width, top, LINE_HEIGHT, false);
+                }
+                if let Some(t) = check_out {
+                    canvas.centered(t, 6.0, x, columns.day_width, middle, LINE_HEIGHT, false);
+                }
+                // Split line only where there are times to separate
+                canvas.line(x, middle, x + columns.day_width, middle);
+            }
+        }
+    }
+    canvas.line(columns.label(), middle, columns.day(0), middle);
+    for (i, total) in row.totals().iter().enumerate() {
+        canvas.centered(&total.to_string(), 7.0, columns.total(i), TOTAL_WIDTH, top, height, false);
+    }
+
+    canvas.line(columns.no(), top - height, columns.right(), top - height);
+    for x in columns.edges() {
+        canvas.line(x, top, x, top - height);
+    }
+    top - height
+}
+
+/// Draw the legend and signature blocks below the grid
+fn draw_footer(canvas: &Canvas, top: f32) {
+    canvas.text(LEGEND, 7.0, MARGIN, top - 5.0, false);
+    let spacing = (PAGE_WIDTH - 2.0 * MARGIN) / SIGNATURES.len() as f32;
+    for (i, label) in SIGNATURES.iter().enumerate() {
+        let x = MARGIN + i as f32 * spacing;
+        canvas.text(&format!("{}: ____________________", label), 9.0, x, top - 17.0, true);
+        canvas.text("Date: ____________", 8.0, x, top - 23.0, false);
+    }
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        text.to_string()
+    } else {
+        let mut out: String = text.chars().take(max_chars - 1).collect();
+        out.push('…');
+        out
+    }
+}
@@ -0,0 +1,45 @@
+//! Password-protected exports
+//!
+//! Exports hold names and working hours and tend to get emailed around.
+//! Given a password, an export's files are packed into one AES-256 zip and
+//! the unencrypted files are removed, so only the archive is left on disk.
+//! XLSX registers also have their sheets locked for editing with the same
+//! password; XLSX files can't be encrypted themselves, so the archive is what
+//! keeps them private.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::types::ExportedFile;
+use crate::files::zip;
+
+pub use crate::files::zip::validate_password;
+
+/// Pack `files` into an encrypted archive at `archive` and delete them. The
+/// files are deleted even if packing fails, so no unprotected copy is left.
+pub fn encrypt(files: &[ExportedFile], archive: &Path, password: &str) -> Result<ExportedFile, String> {
+    let sources: Vec<PathBuf> = files.iter().map(|f| PathBuf::from(&f.path)).collect();
+    let packed = zip::create(&sources, archive, Some(password));
+    for source in &sources {
+        if let Err(e) = fs::remove_file(source) {
+            log::warn!("[export] Failed to remove unencrypted {}: {}", source.display(), e);
+        }
+    }
+    packed?;
+    Ok(ExportedFile {
+        path: archive.to_string_lossy().to_string(),
+        rows: files.iter().map(|f| f.rows).sum(),
+        file_size: fs::metadata(archive).map(|m| m.len()).unwrap_or(0),
+    })
+}
+
+/// `file` as written, or encrypted next to it as a .zip when a password is set
+pub fn finish(file: ExportedFile, password: Option<&str>) -> Result<ExportedFile, String> {
+    match password {
+        Some(password) => {
+            let archive = Path::new(&file.path).with_extension("zip");
+            encrypt(&[file], &archive, password)
+        }
+        None => Ok(file),
+    }
+}
@@ -0,0 +1,217 @@
+//! Monthly attendance register data
+//!
+//! Fixed-layout registers (e.g. the labor office monthly sheet) are built
+//! from the stored daily summaries into a [`MonthlySheet`], then rendered by
+//! the XLSX or PDF writer. Days without a summary are filled from the
+//! attendance rules and holidays so the grid has no unexplained gaps.
+
+use chrono::{Local, NaiveDate};
+use rusqlite::{params, Connection};
+use std::collections::HashMap;
+
+use crate::db;
+use crate::holidays::store::Calendar;
+use crate::periods;
+use crate::summary::rules;
+use crate::summary::types::AttendanceRules;
+use crate::templates::store;
+
+/// Labor office monthly register: employee, daily in/out grid, totals, signatures
+pub const LABOR_OFFICE_MONTHLY: &str = "labor_office_monthly";
+
+/// Sheet templates selectable in export_attendance_sheet
+pub const SHEET_TEMPLATES: &[&str] = &[LABOR_OFFICE_MONTHLY];
+
+/// Column headings for the per-employee totals, in SheetRow::totals order
+pub const TOTAL_HEADINGS: [&str; 5] = ["Present", "Absent", "Late days", "Late min", "Early min"];
+
+/// Explains the day marks, printed under the grid
+pub const LEGEND: &str = "A = Absent   H = Public holiday   W = Weekly rest day";
+
+/// Signature blocks printed at the foot of the register
+pub const SIGNATURES: [&str; 3] = ["Prepared by", "Approved by (HR)", "Employer stamp"];
+
+/// One employee's entry for one day
+#[derive(Debug, Clone, Default)]
+pub struct SheetCell {
+    /// HH:MM
+    pub check_in: Option<String>,
+    pub check_out: Option<String>,
+    /// Short code shown instead of times: A (absent), H (holiday), W (weekend)
+    pub mark: Option<&'static str>,
+}
+
+/// One employee row of the register
+#[derive(Debug, Clone)]
+pub struct SheetRow {
+    pub employee_code: String,
+    pub name: String,
+    pub cells: Vec<SheetCell>,
+    pub present: i64,
+    pub absent: i64,
+    pub late_days: i64,
+    pub late_minutes: i64,
+    pub early_minutes: i64,
+}
+
+impl SheetRow {
+    /// Totals in TOTAL_HEADINGS order
+    pub fn totals(&self) -> [i64; 5] {
+        [self.present, self.absent, self.late_days, self.late_minutes, self.early_minutes]
+    }
+}
+
+/// A month of attendance laid out for a register
+#[derive(Debug, Clone)]
+pub struct MonthlySheet {
+    pub organization: String,
+    /// e.g. "March 2024"
+    pub period: String,
+    pub department: Option<String>,
+    pub dates: Vec<NaiveDate>,
+    pub rows: Vec<SheetRow>,
+}
+
+/// Build the register for a month's period (see [`crate::periods`]),
+/// optionally for one department or one user. Only active users are listed,
+/// ordered by employee code then name.
+pub fn load_monthly(
+    conn: &Connection,
+    year: i32,
+    month: u32,
+    department_id: Option<&str>,
+    user_id: Option<&str>,
+) -> Result<MonthlySheet, String> {
+    let period_settings = periods::calc::load_settings(conn)?;
+    let (first, last) = periods::calc::month_range(&period_settings, year, month)?;
+    let dates: Vec<NaiveDate> = first.iter_days().take_while(|d| *d <= last).collect();
+    let start_date = first.format("%Y-%m-%d").to_string();
+    let end_date = last.format("%Y-%m-%d").to_string();
+    let today = Local::now().date_naive();
+
+    let attendance_rules: AttendanceRules = db::get_json_setting(conn, "attendance")?.unwrap_or_default();
+    let organization = store::organization(conn)?.name;
+
+    let holidays = Calendar::load(conn, &start_date, &end_date)?;
+
+    let department = match department_id {
+        Some(id) => Some(
+            conn.query_row("SELECT name FROM departments WHERE id = ?1", [id], |row| row.get::<_, String>(0))
+                .map_err(|e| format!("Failed to read department: {}", e))?,
+        ),
+        None => None,
+    };
+
+    // (user_id, date) -> (check_in, check_out, status, late, early)
+    type DayRow = (Option<String>, Option<String>, String, i64, i64);
+    let summaries: HashMap<(String, String), DayRow> = {
+        let mut stmt = conn
+            .prepare(
+                "SELECT user_id, date, check_in_time, check_out_time, status, late_minutes, early_minutes
+                 FROM attendance_day_summary
+                 WHERE date >= ?1 AND date <= ?2",
+            )
+            .map_err(|e| format!("Failed to query summaries: {}", e))?;
+        let rows = stmt
+            .query_map(params![start_date, end_date], |row| {
+                Ok((
+                    (row.get(0)?, row.get(1)?),
+                    (row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?, row.get(6)?),
+                ))
+            })
+            .map_err(|e| format!("Failed to query summaries: {}", e))?;
+        rows.collect::<Result<_, _>>()
+            .map_err(|e| format!("Failed to read summaries: {}", e))?
+    };
+
+    // (user_id, employee_code, name, counts absences)
+    let users: Vec<(String, String, String, bool)> = {
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT u.id, COALESCE(u.employee_code, ''), u.display_name, {}
+                 FROM users u
+                 WHERE u.status = 'active' AND (?1 IS NULL OR u.department_id = ?1) AND (?2 IS NULL OR u.id = ?2)
+                 ORDER BY u.employee_code IS NULL, u.employee_code, u.display_name",
+                db::users::counts_absences_sql("u")
+            ))
+            .map_err(|e| format!("Failed to query users: {}", e))?;
+        let rows = stmt
+            .query_map(params![department_id, user_id], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })
+            .map_err(|e| format!("Failed to query users: {}", e))?;
+        rows.collect::<Result<_, _>>()
+            .map_err(|e| format!("Failed to read users: {}", e))?
+    };
+
+    let mut rows = Vec::with_capacity(users.len());
+    for (user_id, employee_code, name, counts_absences) in users {
+        let mut row = SheetRow {
+            employee_code,
+            name,
+            cells: Vec::with_capacity(dates.len()),
+            present: 0,
+            absent: 0,
+            late_days: 0,
+            late_minutes: 0,
+            early_minutes: 0,
+        };
+        for date in &dates {
+            let key = date.format("%Y-%m-%d").to_string();
+            let mut cell = SheetCell::default();
+            match summaries.get(&(user_id.clone(), key.clone())) {
+                Some((check_in, check_out, status, late, early)) => {
+                    cell.check_in = check_in.as_deref().map(short_time);
+                    cell.check_out = check_out.as_deref().map(short_time);
+                    match status.as_str() {
+                        rules::STATUS_ABSENT => {
+                            cell.mark = Some("A");
+                            row.absent += 1;
+                        }
+                        rules::STATUS_HOLIDAY => cell.mark = Some("H"),
+                        rules::STATUS_WEEKEND => cell.mark = Some("W"),
+                        rules::STATUS_REMOTE => {
+                            cell.mark = Some("R");
+                            row.present += 1;
+                        }
+                        _ => row.present += 1,
+                    }
+                    if *late > 0 {
+                        row.late_days += 1;
+                    }
+                    row.late_minutes += late;
+                    row.early_minutes += early;
+                }
+                None if holidays.observes(&user_id, &key) => cell.mark = Some("H"),
+                None if !rules::is_workday(&key, &attendance_rules) => cell.mark = Some("W"),
+                // Future days stay blank rather than counting as absences,
+                // as do contractors' and visitors' days without punches
+                None if counts_absences && *date <= today => {
+                    cell.mark = Some("A");
+                    row.absent += 1;
+                }
+                None => {}
+            }
+            row.cells.push(cell);
+        }
+        rows.push(row);
+    }
+
+    Ok(MonthlySheet {
+        organization,
+        period: periods::calc::month_label(&period_settings, year, month)?,
+        department,
+        dates,
+        rows,
+    })
+}
+
+/// HH:MM from a stored time or timestamp
+fn short_time(value: &str) -> String {
+    let time = if value.contains('T') || value.contains(' ') {
+        rules::extract_time(value)
+    } else {
+        value.to_string()
+    };
+    time.chars().take(5).collect()
+}
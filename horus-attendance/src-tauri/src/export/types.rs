@@ -0,0 +1,137 @@
+//! Export data types for Tauri command serialization
+
+use serde::{Deserialize, Serialize};
+
+/// A file written by an export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedFile {
+    pub path: String,
+    pub rows: u64,
+    pub file_size: u64,
+}
+
+/// How reading one device for the device user export went
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceUserRead {
+    pub device_id: String,
+    pub device_name: String,
+    pub users: u32,
+    /// Why the device is missing from the file
+    pub error: Option<String>,
+}
+
+/// The device user export and the devices it covers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceUserExport {
+    pub file: ExportedFile,
+    pub devices: Vec<DeviceUserRead>,
+}
+
+/// Result of a multi-file export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportResult {
+    pub files: Vec<ExportedFile>,
+}
+
+/// A Gregorian date expressed in the Hijri calendar
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HijriDate {
+    /// The input date (YYYY-MM-DD)
+    pub gregorian: String,
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+    pub month_name: String,
+    pub month_name_ar: String,
+    /// e.g. "1 Ramadan 1445 AH"
+    pub formatted: String,
+    /// Hijri date as YYYY-MM-DD, sortable
+    pub iso: String,
+}
+
+/// Summary status counts for one calendar month
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonthTotals {
+    /// "YYYY-MM" in the grouping calendar, or the period key
+    pub month: String,
+    /// e.g. "Ramadan 1445", "March 2024" or "March 2024 (26 Feb – 25 Mar)"
+    pub label: String,
+    /// "hijri", "gregorian" or "period"
+    pub calendar: String,
+    /// First and last Gregorian dates with summaries in the month
+    pub first_date: String,
+    pub last_date: String,
+    pub days: i64,
+    pub present: i64,
+    pub late: i64,
+    pub early_leave: i64,
+    pub absent: i64,
+    pub incomplete: i64,
+    pub late_minutes: i64,
+}
+
+/// An inclusive Gregorian date range
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DateRange {
+    pub start_date: String,
+    pub end_date: String,
+}
+
+fn default_align() -> String {
+    "left".to_string()
+}
+
+fn default_line_ending() -> String {
+    "crlf".to_string()
+}
+
+/// One column of a fixed-width record
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FixedWidthColumn {
+    /// A field of the layout's scope (see fixed_width), or "literal"
+    pub field: String,
+    /// Characters the column takes
+    pub width: usize,
+    /// "left" or "right"
+    #[serde(default = "default_align")]
+    pub align: String,
+    /// Fill character; a space when not set
+    #[serde(default)]
+    pub pad: Option<String>,
+    /// Dates: a strftime pattern (default %Y%m%d). Minutes: "hhmm" or
+    /// "hours100" (hours with two implied decimals). Times: "hhmm".
+    #[serde(default)]
+    pub format: Option<String>,
+    /// Text of a "literal" column
+    #[serde(default)]
+    pub value: Option<String>,
+}
+
+/// A saved fixed-width export layout
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FixedWidthLayout {
+    /// Generated when empty on save
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    /// "daily": one record per employee and day; "totals": one per employee
+    pub scope: String,
+    pub columns: Vec<FixedWidthColumn>,
+    /// "crlf" or "lf"
+    #[serde(default = "default_line_ending")]
+    pub line_ending: String,
+    /// Cut text too long for its column instead of failing the export
+    #[serde(default)]
+    pub truncate: bool,
+    #[serde(default)]
+    pub updated_at: String,
+}
@@ -0,0 +1,147 @@
+//! XLSX rendering of attendance registers
+//!
+//! Each employee takes two rows (In / Out) under a day-of-month header, with
+//! identity, totals and signature columns merged across both rows. The sheet
+//! is set up to print landscape on A3, one page wide.
+
+use rust_xlsxwriter::{Color, Format, FormatAlign, FormatBorder, Workbook, Worksheet, XlsxError};
+use std::path::Path;
+
+use super::sheet::{MonthlySheet, LEGEND, SIGNATURES, TOTAL_HEADINGS};
+use super::types::ExportedFile;
+
+/// Paper size code for A3 in the XLSX page setup
+const PAPER_A3: u8 = 8;
+
+/// First grid row (the two header rows sit above it)
+const HEADER_ROW: u32 = 4;
+
+/// Columns before the day grid: No., code, name, In/Out label
+const LEAD_COLUMNS: u16 = 4;
+
+/// Write the labor office monthly register as an XLSX workbook, its sheet
+/// locked for editing when `password` is set
+pub fn write_monthly_register(sheet: &MonthlySheet, path: &Path, password: Option<&str>) -> Result<ExportedFile, String> {
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet();
+    layout(worksheet, sheet).map_err(|e| format!("Failed to build register sheet: {}", e))?;
+    if let Some(password) = password {
+        worksheet.protect_with_password(password);
+    }
+    workbook
+        .save(path)
+        .map_err(|e| format!("Failed to write XLSX file: {}", e))?;
+
+    let file_size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    Ok(ExportedFile {
+        path: path.to_string_lossy().to_string(),
+        rows: sheet.rows.len() as u64,
+        file_size,
+    })
+}
+
+fn layout(ws: &mut Worksheet, sheet: &MonthlySheet) -> Result<(), XlsxError> {
+    let days = sheet.dates.len() as u16;
+    let totals_col = LEAD_COLUMNS + days;
+    let signature_col = totals_col + TOTAL_HEADINGS.len() as u16;
+
+    let title = Format::new().set_bold().set_font_size(14);
+    let bold = Format::new().set_bold();
+    let header = Format::new()
+        .set_bold()
+        .set_font_size(8)
+        .set_align(FormatAlign::Center)
+        .set_align(FormatAlign::VerticalCenter)
+        .set_text_wrap()
+        .set_border(FormatBorder::Thin)
+        .set_background_color(Color::RGB(0xD9D9D9));
+    let cell = Format::new()
+        .set_font_size(8)
+        .set_align(FormatAlign::Center)
+        .set_align(FormatAlign::VerticalCenter)
+        .set_border(FormatBorder::Thin);
+    let name = Format::new()
+        .set_font_size(8)
+        .set_align(FormatAlign::VerticalCenter)
+        .set_text_wrap()
+        .set_border(FormatBorder::Thin);
+
+    ws.set_name("Attendance Register")?;
+    ws.set_landscape();
+    ws.set_paper_size(PAPER_A3);
+    ws.set_print_fit_to_pages(1, 0);
+    ws.set_repeat_rows(HEADER_ROW, HEADER_ROW + 1)?;
+    ws.set_freeze_panes(HEADER_ROW + 2, LEAD_COLUMNS)?;
+
+    ws.write_string_with_format(0, 0, &sheet.organization, &title)?;
+    ws.write_string_with_format(1, 0, format!("Monthly Attendance Register - {}", sheet.period), &bold)?;
+    if let Some(department) = &sheet.department {
+        ws.write_string(2, 0, format!("Department: {}", department))?;
+    }
+
+    // Two-row header: identity and totals merged, days split into number / weekday
+    let merged = [(0, "No."), (1, "Emp. Code"), (2, "Employee Name"), (3, "")];
+    for (col, label) in merged {
+        ws.merge_range(HEADER_ROW, col, HEADER_ROW + 1, col, label, &header)?;
+    }
+    for (i, date) in sheet.dates.iter().enumerate() {
+        let col = LEAD_COLUMNS + i as u16;
+        ws.write_string_with_format(HEADER_ROW, col, date.format("%-d").to_string(), &header)?;
+        ws.write_string_with_format(HEADER_ROW + 1, col, date.format("%a").to_string(), &header)?;
+        ws.set_column_width(col, 5.5)?;
+    }
+    for (i, label) in TOTAL_HEADINGS.iter().enumerate() {
+        let col = totals_col + i as u16;
+        ws.merge_range(HEADER_ROW, col, HEADER_ROW + 1, col, label, &header)?;
+        ws.set_column_width(col, 7)?;
+    }
+    ws.merge_range(HEADER_ROW, signature_col, HEADER_ROW + 1, signature_col, "Employee Signature", &header)?;
+
+    ws.set_column_width(0, 4)?;
+    ws.set_column_width(1, 9)?;
+    ws.set_column_width(2, 24)?;
+    ws.set_column_width(3, 4)?;
+    ws.set_column_width(signature_col, 18)?;
+
+    let mut row_idx = HEADER_ROW + 2;
+    for (n, row) in sheet.rows.iter().enumerate() {
+        let (in_row, out_row) = (row_idx, row_idx + 1);
+        ws.merge_range(in_row, 0, out_row, 0, &(n + 1).to_string(), &cell)?;
+        ws.merge_range(in_row, 1, out_row, 1, &row.employee_code, &cell)?;
+        ws.merge_range(in_row, 2, out_row, 2, &row.name, &name)?;
+        ws.write_string_with_format(in_row, 3, "In", &cell)?;
+        ws.write_string_with_format(out_row, 3, "Out", &cell)?;
+
+        for (i, day) in row.cells.iter().enumerate() {
+            let col = LEAD_COLUMNS + i as u16;
+            match (&day.check_in, &day.check_out, day.mark) {
+                (None, None, Some(mark)) => {
+                    ws.merge_range(in_row, col, out_row, col, mark, &cell)?;
+                }
+                (check_in, check_out, _) => {
+                    ws.write_string_with_format(in_row, col, check_in.as_deref().unwrap_or(""), &cell)?;
+                    ws.write_string_with_format(out_row, col, check_out.as_deref().unwrap_or(""), &cell)?;
+                }
+            }
+        }
+        for (i, total) in row.totals().iter().enumerate() {
+            let col = totals_col + i as u16;
+            ws.merge_range(in_row, col, out_row, col, "", &cell)?;
+            ws.write_number_with_format(in_row, col, *total as f64, &cell)?;
+        }
+        ws.merge_range(in_row, signature_col, out_row, signature_col, "", &cell)?;
+        row_idx += 2;
+    }
+
+    row_idx += 1;
+    ws.write_string(row_idx, 0, LEGEND)?;
+    row_idx += 3;
+    // Spread the signature blocks across the width of the grid
+    let spacing = (signature_col / SIGNATURES.len() as u16).max(1);
+    for (i, label) in SIGNATURES.iter().enumerate() {
+        let col = i as u16 * spacing;
+        ws.write_string_with_format(row_idx, col, format!("{}: ____________________", label), &bold)?;
+        ws.write_string(row_idx + 1, col, "Date: ____________")?;
+    }
+    Ok(())
+}
@@ -0,0 +1,85 @@
+//! Tauri command handlers for chunked file writes and archives
+
+use base64::Engine;
+use tauri::State;
+
+use super::stream::FileStreams;
+use super::types::{StreamedFile, ZipResult};
+use super::zip;
+use crate::path_policy;
+
+/// Start a chunked write to `path` (see path_policy for where writes may go).
+/// Returns the stream ID for append_chunk / close_file_stream.
+#[tauri::command]
+pub async fn open_file_stream(
+    app: tauri::AppHandle,
+    streams: State<'_, FileStreams>,
+    path: String,
+) -> Result<String, String> {
+    let target = path_policy::resolve_write_target(&app, &path)?;
+    log::info!("[files::cmd] open_file_stream -> {}", target.display());
+    streams.open(target)
+}
+
+/// Append a base64-encoded chunk. Returns the bytes written so far.
+#[tauri::command]
+pub async fn append_chunk(streams: State<'_, FileStreams>, stream_id: String, base64_data: String) -> Result<u64, String> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(&base64_data)
+        .map_err(|e| format!("Failed to decode base64: {}", e))?;
+    streams.append(&stream_id, &bytes)
+}
+
+/// Finish the file, or discard it when `abort` is set (e.g. the export failed
+/// half-way)
+#[tauri::command]
+pub async fn close_file_stream(
+    streams: State<'_, FileStreams>,
+    stream_id: String,
+    abort: Option<bool>,
+) -> Result<Option<StreamedFile>, String> {
+    if abort.unwrap_or(false) {
+        streams.abort(&stream_id)?;
+        return Ok(None);
+    }
+    let file = streams.finish(&stream_id)?;
+    log::info!("[files::cmd] close_file_stream {} ({} bytes)", file.path, file.file_size);
+    Ok(Some(file))
+}
+
+/// Pack files and folders into one zip archive at `dest`, e.g. a CSV per
+/// department plus photos, encrypted with AES-256 when `password` is set.
+/// Sources and destination must both be allowed by the path policy.
+#[tauri::command]
+pub async fn create_zip(
+    app: tauri::AppHandle,
+    paths: Vec<String>,
+    dest: String,
+    password: Option<String>,
+) -> Result<ZipResult, String> {
+    if paths.is_empty() {
+        return Err("No files to archive".to_string());
+    }
+    let sources = paths
+        .iter()
+        .map(|path| path_policy::resolve_source(&app, path))
+        .collect::<Result<Vec<_>, _>>()?;
+    let destination = path_policy::resolve_write_target(&app, &dest)?;
+    log::info!(
+        "[files::cmd] create_zip {} sources -> {}{}",
+        sources.len(),
+        destination.display(),
+        if password.is_some() { " (encrypted)" } else { "" }
+    );
+
+    let path = destination.clone();
+    let files = tauri::async_runtime::spawn_blocking(move || zip::create(&sources, &path, password.as_deref()))
+        .await
+        .map_err(|e| format!("Archive task failed: {}", e))??;
+
+    Ok(ZipResult {
+        path: destination.to_string_lossy().to_string(),
+        files,
+        file_size: std::fs::metadata(&destination).map(|m| m.len()).unwrap_or(0),
+    })
+}
@@ -0,0 +1,12 @@
+//! Writing files chosen by the user
+//!
+//! Large exports are written in chunks through a file stream rather than
+//! passed to the backend in one piece, so the webview never holds the whole
+//! file twice (raw bytes plus base64). Multi-file exports can be bundled
+//! into one zip. Every target and archived source goes through
+//! [`crate::path_policy`].
+
+pub mod commands;
+pub mod stream;
+pub mod types;
+pub mod zip;
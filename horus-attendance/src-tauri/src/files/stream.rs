@@ -0,0 +1,132 @@
+//! Chunked file writes
+//!
+//! Chunks go to a `.part` file next to the target, which is renamed over the
+//! target on close. An aborted or abandoned export therefore never leaves a
+//! truncated file under the name the user picked. Streams the frontend stops
+//! talking to are dropped after [`STALE_AFTER`].
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::types::StreamedFile;
+
+/// Streams idle for this long are discarded when the next one is opened
+pub const STALE_AFTER: Duration = Duration::from_secs(10 * 60);
+
+struct OpenStream {
+    target: PathBuf,
+    part: PathBuf,
+    writer: BufWriter<File>,
+    written: u64,
+    touched: Instant,
+}
+
+/// Open streams by ID, held as Tauri managed state
+#[derive(Default)]
+pub struct FileStreams(Mutex<HashMap<String, OpenStream>>);
+
+impl FileStreams {
+    /// Start writing `target` (already checked against the path policy)
+    pub fn open(&self, target: PathBuf) -> Result<String, String> {
+        let part = part_path(&target);
+        let file = File::create(&part).map_err(|e| format!("Failed to create file: {}", e))?;
+        let id = uuid::Uuid::new_v4().to_string();
+
+        let mut streams = self.lock()?;
+        let stale: Vec<String> = streams
+            .iter()
+            .filter(|(_, s)| s.touched.elapsed() > STALE_AFTER)
+            .map(|(id, _)| id.clone())
+            .collect();
+        for stale_id in stale {
+            if let Some(stream) = streams.remove(&stale_id) {
+                log::warn!("[files::stream] Discarding abandoned stream to {}", stream.target.display());
+                discard(stream);
+            }
+        }
+
+        streams.insert(
+            id.clone(),
+            OpenStream {
+                target,
+                part,
+                writer: BufWriter::new(file),
+                written: 0,
+                touched: Instant::now(),
+            },
+        );
+        Ok(id)
+    }
+
+    /// Append bytes. Returns the total written so far.
+    pub fn append(&self, id: &str, bytes: &[u8]) -> Result<u64, String> {
+        let mut streams = self.lock()?;
+        let stream = streams.get_mut(id).ok_or_else(|| format!("Unknown file stream: {}", id))?;
+        if let Err(e) = stream.writer.write_all(bytes) {
+            if let Some(stream) = streams.remove(id) {
+                discard(stream);
+            }
+            return Err(format!("Failed to write file: {}", e));
+        }
+        stream.written += bytes.len() as u64;
+        stream.touched = Instant::now();
+        Ok(stream.written)
+    }
+
+    /// Flush and move the file into place
+    pub fn finish(&self, id: &str) -> Result<StreamedFile, String> {
+        let stream = self.take(id)?;
+        let OpenStream { target, part, writer, written, .. } = stream;
+        let file = writer.into_inner().map_err(|e| {
+            let _ = fs::remove_file(&part);
+            format!("Failed to write file: {}", e.error())
+        })?;
+        if let Err(e) = file.sync_all() {
+            drop(file);
+            let _ = fs::remove_file(&part);
+            return Err(format!("Failed to write file: {}", e));
+        }
+        drop(file);
+        fs::rename(&part, &target).map_err(|e| {
+            let _ = fs::remove_file(&part);
+            format!("Failed to save {}: {}", target.display(), e)
+        })?;
+        Ok(StreamedFile {
+            path: target.to_string_lossy().to_string(),
+            file_size: written,
+        })
+    }
+
+    /// Drop the stream and its partial file
+    pub fn abort(&self, id: &str) -> Result<(), String> {
+        discard(self.take(id)?);
+        Ok(())
+    }
+
+    fn take(&self, id: &str) -> Result<OpenStream, String> {
+        self.lock()?
+            .remove(id)
+            .ok_or_else(|| format!("Unknown file stream: {}", id))
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, HashMap<String, OpenStream>>, String> {
+        self.0.lock().map_err(|_| "File stream state is poisoned".to_string())
+    }
+}
+
+fn discard(stream: OpenStream) {
+    let OpenStream { part, writer, .. } = stream;
+    drop(writer);
+    let _ = fs::remove_file(part);
+}
+
+/// `report.xlsx` -> `report.xlsx.part`
+fn part_path(target: &Path) -> PathBuf {
+    let mut name = target.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    target.with_file_name(name)
+}
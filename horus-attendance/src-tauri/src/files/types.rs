@@ -0,0 +1,18 @@
+use serde::Serialize;
+
+/// A file finished through close_file_stream
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamedFile {
+    pub path: String,
+    pub file_size: u64,
+}
+
+/// Result of create_zip
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ZipResult {
+    pub path: String,
+    pub files: u32,
+    pub file_size: u64,
+}
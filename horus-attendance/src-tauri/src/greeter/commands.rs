@@ -0,0 +1,43 @@
+//! Tauri command handlers for the greeter screen
+
+use tauri::Manager;
+
+use super::feed::RecentPunchFeed;
+use super::store;
+use super::types::RecentPunch;
+use crate::db;
+
+/// The newest punches, newest first. `since` keeps only punches after that
+/// timestamp, for polling; photos are included unless `include_photos` is
+/// false.
+#[tauri::command]
+pub async fn get_recent_punches(
+    app: tauri::AppHandle,
+    limit: Option<u32>,
+    since: Option<String>,
+    include_photos: Option<bool>,
+) -> Result<Vec<RecentPunch>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let photos = if include_photos.unwrap_or(true) {
+            Some(store::photos_dir(&app)?)
+        } else {
+            None
+        };
+        store::recent(
+            &*db::open(&app)?,
+            limit.unwrap_or(10),
+            since.as_deref(),
+            photos.as_deref(),
+        )
+    })
+    .await
+    .map_err(|e| format!("Recent punches task failed: {}", e))?
+}
+
+/// Starts or stops pushing new punches as `recent-punches` events. Each
+/// window that subscribes should unsubscribe when it closes; returns the
+/// number of subscriptions left.
+#[tauri::command]
+pub fn subscribe_recent_punches(app: tauri::AppHandle, enabled: bool) -> usize {
+    app.state::<RecentPunchFeed>().subscribe(enabled)
+}
@@ -0,0 +1,56 @@
+//! Pushing new punches to subscribed greeter windows
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use tauri::{Emitter, Manager};
+
+use super::store;
+use crate::db;
+
+/// Event carrying the punches stored since the previous one, newest first
+pub const EVENT: &str = "recent-punches";
+
+/// Punches sent with the first event after subscribing
+const FIRST_BATCH: u32 = 10;
+
+/// Subscriptions and the newest punch already sent, held as Tauri managed
+/// state
+#[derive(Default)]
+pub struct RecentPunchFeed {
+    subscribers: AtomicUsize,
+    last_sent: Mutex<Option<String>>,
+}
+
+impl RecentPunchFeed {
+    /// Adds or drops a subscription and returns how many remain
+    pub fn subscribe(&self, enabled: bool) -> usize {
+        if enabled {
+            self.subscribers.fetch_add(1, Ordering::SeqCst) + 1
+        } else {
+            self.subscribers
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| Some(n.saturating_sub(1)))
+                .map(|n| n.saturating_sub(1))
+                .unwrap_or(0)
+        }
+    }
+}
+
+/// Emits punches newer than the last ones sent. Does nothing without
+/// subscribers, so the realtime drain can call it unconditionally.
+pub fn publish(app: &tauri::AppHandle) -> Result<usize, String> {
+    let feed = app.state::<RecentPunchFeed>();
+    if feed.subscribers.load(Ordering::SeqCst) == 0 {
+        return Ok(0);
+    }
+    let mut last_sent = feed.last_sent.lock().unwrap_or_else(|e| e.into_inner());
+    let limit = if last_sent.is_some() { store::MAX_LIMIT } else { FIRST_BATCH };
+    let photos = store::photos_dir(app)?;
+    let punches = store::recent(&*db::open(app)?, limit, last_sent.as_deref(), Some(&photos))?;
+    let Some(newest) = punches.first() else {
+        return Ok(0);
+    };
+    *last_sent = Some(newest.timestamp.clone());
+    app.emit(EVENT, &punches)
+        .map_err(|e| format!("Failed to emit recent punches: {}", e))?;
+    Ok(punches.len())
+}
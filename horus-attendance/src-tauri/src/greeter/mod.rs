@@ -0,0 +1,16 @@
+//! Recent punches for a greeter screen
+//!
+//! A second display at reception shows the last few arrivals with name and
+//! photo. [`store::recent`] resolves everything in one query walking
+//! `idx_attendance_logs_timestamp` backwards, so polling it is cheap;
+//! punches stamped in the future by a device with a wrong clock are left
+//! out so they can't stick to the top. Photos are files named after the
+//! user ID (`photos/<user id>.jpg`, `.png` or `.webp` in app data) and are
+//! sent as data URLs, which the window's CSP allows.
+//! Instead of polling, a window can subscribe: punches stored from realtime
+//! device events are then also pushed as the `recent-punches` event.
+
+pub mod commands;
+pub mod feed;
+pub mod store;
+pub mod types;
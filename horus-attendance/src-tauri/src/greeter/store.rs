@@ -0,0 +1,99 @@
+//! Querying recent punches
+
+use std::path::{Path, PathBuf};
+
+use base64::Engine;
+use chrono::{Duration, Local};
+use rusqlite::{params, Connection};
+use tauri::Manager;
+
+use super::types::RecentPunch;
+use crate::quarantine::validate::TIMESTAMP_FORMAT;
+
+/// Most punches returned at once
+pub const MAX_LIMIT: u32 = 100;
+
+/// Punches up to this far ahead of the local clock still count as now
+const CLOCK_SLACK_MINUTES: i64 = 5;
+
+/// Larger photos are skipped rather than sent on every refresh
+const MAX_PHOTO_BYTES: u64 = 256 * 1024;
+
+const PHOTO_TYPES: &[(&str, &str)] = &[("jpg", "image/jpeg"), ("jpeg", "image/jpeg"), ("png", "image/png"), ("webp", "image/webp")];
+
+pub fn photos_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Cannot resolve app data dir: {}", e))?
+        .join("photos"))
+}
+
+/// The newest punches, newest first, optionally only those after `since`
+/// (a timestamp as stored). Photos are read from `photos_dir` when given.
+pub fn recent(conn: &Connection, limit: u32, since: Option<&str>, photos_dir: Option<&Path>) -> Result<Vec<RecentPunch>, String> {
+    let latest = (Local::now().naive_local() + Duration::minutes(CLOCK_SLACK_MINUTES))
+        .format(TIMESTAMP_FORMAT)
+        .to_string();
+    let mut stmt = conn
+        .prepare_cached(
+            "SELECT l.id, l.device_id, dv.name, l.device_user_id, u.id,
+                    COALESCE(u.display_name, l.device_user_id), d.name, l.timestamp, l.punch_type
+             FROM attendance_logs_raw l
+             LEFT JOIN user_device_links k ON k.device_user_id = l.device_user_id
+             LEFT JOIN users u ON u.id = COALESCE(
+                 k.user_id, (SELECT id FROM users WHERE device_user_id = l.device_user_id))
+             LEFT JOIN departments d ON d.id = u.department_id
+             LEFT JOIN devices dv ON dv.id = l.device_id
+             WHERE l.timestamp <= ?1 AND (?2 IS NULL OR l.timestamp > ?2)
+             ORDER BY l.timestamp DESC
+             LIMIT ?3",
+        )
+        .map_err(|e| format!("Failed to query recent punches: {}", e))?;
+    let rows = stmt
+        .query_map(params![latest, since, limit.clamp(1, MAX_LIMIT)], |row| {
+            Ok(RecentPunch {
+                log_id: row.get(0)?,
+                device_id: row.get(1)?,
+                device_name: row.get(2)?,
+                device_user_id: row.get(3)?,
+                user_id: row.get(4)?,
+                name: row.get(5)?,
+                department_name: row.get(6)?,
+                timestamp: row.get(7)?,
+                punch_type: row.get(8)?,
+                photo: None,
+            })
+        })
+        .map_err(|e| format!("Failed to query recent punches: {}", e))?;
+    let mut punches: Vec<RecentPunch> = rows
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read recent punches: {}", e))?;
+    if let Some(dir) = photos_dir {
+        for punch in &mut punches {
+            punch.photo = punch.user_id.as_deref().and_then(|user_id| photo(dir, user_id));
+        }
+    }
+    Ok(punches)
+}
+
+/// A user's photo as a data URL
+fn photo(dir: &Path, user_id: &str) -> Option<String> {
+    // User IDs are UUIDs; anything else could climb out of the folder
+    if !user_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return None;
+    }
+    PHOTO_TYPES.iter().find_map(|(extension, mime)| {
+        let path = dir.join(format!("{}.{}", user_id, extension));
+        let size = std::fs::metadata(&path).ok()?.len();
+        if size > MAX_PHOTO_BYTES {
+            return None;
+        }
+        let bytes = std::fs::read(&path).ok()?;
+        Some(format!(
+            "data:{};base64,{}",
+            mime,
+            base64::engine::general_purpose::STANDARD.encode(bytes)
+        ))
+    })
+}
@@ -0,0 +1,22 @@
+//! Greeter data types for Tauri command serialization
+
+use serde::{Deserialize, Serialize};
+
+/// A punch as the greeter shows it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecentPunch {
+    pub log_id: String,
+    pub device_id: String,
+    pub device_name: Option<String>,
+    pub device_user_id: String,
+    /// None when the device user isn't linked to a profile
+    pub user_id: Option<String>,
+    /// Display name, else the device user ID
+    pub name: String,
+    pub department_name: Option<String>,
+    pub timestamp: String,
+    pub punch_type: Option<i64>,
+    /// Data URL of the user's photo, if there is one
+    pub photo: Option<String>,
+}
@@ -0,0 +1,252 @@
+//! The individual checks and their repairs
+
+use rusqlite::Connection;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use super::types::{HealthCheck, HealthLevel, StartupHealth};
+use crate::data_migrations::runner;
+use crate::db;
+
+/// The startup result, held as Tauri managed state. The lock is held while
+/// the check runs, so an early `get_startup_health` waits for it.
+#[derive(Default)]
+pub struct HealthState(Mutex<Option<StartupHealth>>);
+
+impl HealthState {
+    /// The stored result, running the check first if it hasn't run yet (or
+    /// again when `refresh` is set)
+    pub fn get_or_run(&self, app: &tauri::AppHandle, refresh: bool) -> Result<StartupHealth, String> {
+        let mut stored = self.0.lock().map_err(|_| "Health state is poisoned".to_string())?;
+        if refresh || stored.is_none() {
+            *stored = Some(run(app));
+        }
+        Ok(stored.clone().expect("health result was just stored"))
+    }
+}
+
+fn check(id: &str, level: HealthLevel, message: impl Into<String>) -> HealthCheck {
+    HealthCheck {
+        id: id.to_string(),
+        level,
+        message: message.into(),
+        repair: None,
+    }
+}
+
+/// Run every check, repairing what can safely be repaired
+pub fn run(app: &tauri::AppHandle) -> StartupHealth {
+    let mut checks = Vec::new();
+    match crate::get_db_path(app) {
+        Ok(path) => database_checks(&path, &mut checks),
+        Err(e) => checks.push(check("database", HealthLevel::Error, e)),
+    }
+    checks.push(backup_directory(app));
+
+    let level = checks.iter().map(|c| c.level).max().unwrap_or(HealthLevel::Ok);
+    for c in checks.iter().filter(|c| c.level != HealthLevel::Ok) {
+        log::warn!("[health] {} ({:?}): {}", c.id, c.level, c.message);
+    }
+    for c in checks.iter().filter(|c| c.repair.is_some()) {
+        log::info!("[health] Repaired {}: {}", c.id, c.repair.as_deref().unwrap_or_default());
+    }
+
+    StartupHealth {
+        checked_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        level,
+        checks,
+        latest_backup: latest_backup(app),
+    }
+}
+
+/// `<database>-wal` or `<database>-shm`
+fn sidecar(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+fn database_checks(path: &Path, checks: &mut Vec<HealthCheck>) {
+    let wal = sidecar(path, "-wal");
+
+    if !path.exists() {
+        checks.push(check("database", HealthLevel::Ok, "No database yet; it is created when the app loads"));
+        if wal.exists() {
+            checks.push(set_aside_orphaned_wal(path, &wal));
+        }
+        return;
+    }
+
+    // A clean shutdown leaves no WAL content behind; anything left is from a
+    // crash (or another running instance) and is replayed on open
+    let leftover_wal = fs::metadata(&wal).map(|m| m.len()).unwrap_or(0);
+
+    let conn = match db::open_path(path) {
+        Ok(conn) => conn,
+        Err(e) => {
+            checks.push(check("database", HealthLevel::Error, e));
+            return;
+        }
+    };
+    let size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    checks.push(check("database", HealthLevel::Ok, format!("Opened {} ({} KB)", path.display(), size / 1024)));
+
+    checks.push(integrity(&conn));
+    checks.push(schema(&conn));
+    checks.push(wal_state(&conn, leftover_wal));
+}
+
+/// A WAL without its database can't be replayed into anything, and SQLite
+/// would apply it to the fresh database the app is about to create
+fn set_aside_orphaned_wal(path: &Path, wal: &Path) -> HealthCheck {
+    let stamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
+    let mut result = check("wal", HealthLevel::Warning, "Found a write-ahead log without its database");
+    let mut moved = Vec::new();
+    for file in [wal.to_path_buf(), sidecar(path, "-shm")] {
+        if !file.exists() {
+            continue;
+        }
+        let target = sidecar(&file, &format!(".orphaned_{}", stamp));
+        match fs::rename(&file, &target) {
+            Ok(()) => moved.push(target.display().to_string()),
+            Err(e) => {
+                result.level = HealthLevel::Error;
+                result.message = format!("Could not move aside orphaned {}: {}", file.display(), e);
+                return result;
+            }
+        }
+    }
+    result.repair = Some(format!("Moved to {}", moved.join(", ")));
+    result
+}
+
+fn integrity(conn: &Connection) -> HealthCheck {
+    let problems: Result<Vec<String>, _> = conn.prepare("PRAGMA quick_check(5)").and_then(|mut stmt| {
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        rows.collect()
+    });
+    match problems {
+        Ok(rows) if rows.len() == 1 && rows[0] == "ok" => check("integrity", HealthLevel::Ok, "Quick check passed"),
+        Ok(rows) => check(
+            "integrity",
+            HealthLevel::Error,
+            format!("Database is damaged: {}. Restore the latest backup.", rows.join("; ")),
+        ),
+        Err(e) => check(
+            "integrity",
+            HealthLevel::Error,
+            format!("Database could not be read: {}. Restore the latest backup.", e),
+        ),
+    }
+}
+
+fn schema(conn: &Connection) -> HealthCheck {
+    let supported = crate::current_schema_version();
+    let version = match runner::schema_version(conn) {
+        Ok(version) => version,
+        Err(e) => return check("schema", HealthLevel::Error, e),
+    };
+    if version > supported {
+        return check(
+            "schema",
+            HealthLevel::Error,
+            format!(
+                "Database is at schema version {} but this version of the app only knows up to {}. Update the app or restore an older backup.",
+                version, supported
+            ),
+        );
+    }
+
+    let failed: Vec<String> = conn
+        .prepare("SELECT version || ' ' || description FROM _sqlx_migrations WHERE success = 0")
+        .and_then(|mut stmt| stmt.query_map([], |row| row.get(0))?.collect())
+        .unwrap_or_default();
+    if !failed.is_empty() {
+        return check("schema", HealthLevel::Error, format!("Migration failed: {}", failed.join(", ")));
+    }
+
+    let failed_data: Vec<String> = runner::list(conn)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|m| m.status == "failed")
+        .map(|m| format!("{} ({})", m.name, m.last_error.unwrap_or_default()))
+        .collect();
+    if !failed_data.is_empty() {
+        return check(
+            "schema",
+            HealthLevel::Warning,
+            format!("Data migration failed and will be retried: {}", failed_data.join(", ")),
+        );
+    }
+
+    if version < supported {
+        check(
+            "schema",
+            HealthLevel::Ok,
+            format!("Schema version {}; migrations up to {} are applied when the app loads", version, supported),
+        )
+    } else {
+        check("schema", HealthLevel::Ok, format!("Schema version {} is current", version))
+    }
+}
+
+fn wal_state(conn: &Connection, leftover_bytes: u64) -> HealthCheck {
+    if leftover_bytes == 0 {
+        return check("wal", HealthLevel::Ok, "No write-ahead log left over");
+    }
+
+    let mut result = check(
+        "wal",
+        HealthLevel::Warning,
+        format!(
+            "Write-ahead log of {} KB left over, probably from an unclean shutdown",
+            leftover_bytes / 1024
+        ),
+    );
+    let checkpoint = conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |row| {
+        Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(2)?))
+    });
+    match checkpoint {
+        Ok((0, pages)) => result.repair = Some(format!("Checkpointed {} pages into the database", pages.max(0))),
+        Ok(_) => result.message.push_str("; another process is using the database, so it was left in place"),
+        Err(e) => {
+            result.level = HealthLevel::Error;
+            result.message = format!("Write-ahead log could not be checkpointed: {}", e);
+        }
+    }
+    result
+}
+
+fn backup_directory(app: &tauri::AppHandle) -> HealthCheck {
+    // get_backup_dir creates the folder when it is missing
+    let dir = match crate::get_backup_dir(app) {
+        Ok(dir) => dir,
+        Err(e) => return check("backupDirectory", HealthLevel::Warning, e),
+    };
+    let probe = dir.join(format!(".horus_write_test_{}", uuid::Uuid::new_v4()));
+    match fs::write(&probe, b"ok") {
+        Ok(()) => {
+            let _ = fs::remove_file(&probe);
+            check("backupDirectory", HealthLevel::Ok, format!("{} is writable", dir.display()))
+        }
+        Err(e) => check(
+            "backupDirectory",
+            HealthLevel::Warning,
+            format!("Backups can't be written to {}: {}", dir.display(), e),
+        ),
+    }
+}
+
+/// Newest `.db` file in the backup directory
+fn latest_backup(app: &tauri::AppHandle) -> Option<String> {
+    let dir = crate::get_backup_dir(app).ok()?;
+    fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "db"))
+        .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+        .max_by_key(|(modified, _)| *modified)
+        .map(|(_, path)| path.to_string_lossy().to_string())
+}
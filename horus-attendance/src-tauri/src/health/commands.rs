@@ -0,0 +1,17 @@
+//! Tauri command handlers for the startup health check.
+
+use tauri::Manager;
+
+use super::checks::HealthState;
+use super::types::StartupHealth;
+
+/// Result of the startup health check. Waits for it if it is still running;
+/// `refresh` runs it again.
+#[tauri::command]
+pub async fn get_startup_health(app: tauri::AppHandle, refresh: Option<bool>) -> Result<StartupHealth, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        app.state::<HealthState>().get_or_run(&app, refresh.unwrap_or(false))
+    })
+    .await
+    .map_err(|e| format!("Health check task failed: {}", e))?
+}
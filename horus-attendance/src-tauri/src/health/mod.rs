@@ -0,0 +1,14 @@
+//! Startup health check
+//!
+//! Runs once at launch, before the frontend opens the database: is the file
+//! there and readable, does it pass a quick integrity check, is the schema
+//! one this build understands, was a write-ahead log left behind by a crash,
+//! can backups be written. Problems that have a safe fix (checkpointing a
+//! leftover WAL, setting aside a WAL whose database is gone, creating the
+//! backup folder) are fixed on the spot; the rest are reported through
+//! `get_startup_health` so the app can say what's wrong instead of failing
+//! on its first query.
+
+pub mod checks;
+pub mod commands;
+pub mod types;
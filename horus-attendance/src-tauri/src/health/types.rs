@@ -0,0 +1,36 @@
+//! Types for the startup health check
+
+use serde::{Deserialize, Serialize};
+
+/// How serious a finding is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthLevel {
+    Ok,
+    Warning,
+    Error,
+}
+
+/// Result of one check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthCheck {
+    /// Stable identifier: database, integrity, schema, wal, backupDirectory
+    pub id: String,
+    pub level: HealthLevel,
+    pub message: String,
+    /// What was done to fix it, if anything
+    pub repair: Option<String>,
+}
+
+/// Result of the startup health check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartupHealth {
+    pub checked_at: String,
+    /// Worst level among the checks
+    pub level: HealthLevel,
+    pub checks: Vec<HealthCheck>,
+    /// Most recent backup file, for recovering from an error
+    pub latest_backup: Option<String>,
+}
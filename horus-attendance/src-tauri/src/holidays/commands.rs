@@ -0,0 +1,160 @@
+//! Tauri command handlers for holidays.
+
+use chrono::{Datelike, Local};
+use tauri::Manager;
+
+use super::rules;
+use super::store;
+use super::types::*;
+use crate::db;
+use crate::jobs::{self, registry::JobRegistry};
+
+/// ID of the holiday rule job in the jobs registry
+pub const JOB_ID: &str = "holiday_rules";
+
+/// How often the rules are turned into holidays when nothing changed
+const CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(24 * 3600);
+
+/// Most years generated by hand at once
+const MAX_YEARS: i32 = 20;
+
+/// Holidays, optionally only between two dates or only those a department
+/// observes
+#[tauri::command]
+pub async fn list_holidays(
+    app: tauri::AppHandle,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    department_id: Option<String>,
+) -> Result<Vec<Holiday>, String> {
+    store::list(
+        &*db::open(&app)?,
+        start_date.as_deref(),
+        end_date.as_deref(),
+        department_id.as_deref(),
+    )
+}
+
+/// Create (empty ID) or update a holiday. Leave the department and site
+/// out for a holiday everyone observes. Summaries of the date keep their
+/// status until it is recomputed.
+#[tauri::command]
+pub async fn save_holiday(app: tauri::AppHandle, holiday: Holiday) -> Result<Holiday, String> {
+    let saved = store::save(&*db::open(&app)?, holiday)?;
+    log::info!(
+        "[holidays::cmd] Saved holiday {} (department {}, site {})",
+        saved.date,
+        saved.department_id.as_deref().unwrap_or("any"),
+        saved.site.as_deref().unwrap_or("any")
+    );
+    Ok(saved)
+}
+
+#[tauri::command]
+pub async fn delete_holiday(app: tauri::AppHandle, id: String) -> Result<bool, String> {
+    store::delete(&*db::open(&app)?, &id)
+}
+
+/// Sites named on users or holidays
+#[tauri::command]
+pub async fn list_holiday_sites(app: tauri::AppHandle) -> Result<Vec<String>, String> {
+    store::sites(&*db::open(&app)?)
+}
+
+/// Recurring holiday rules
+#[tauri::command]
+pub async fn list_holiday_rules(app: tauri::AppHandle) -> Result<Vec<HolidayRule>, String> {
+    rules::list(&*db::open(&app)?)
+}
+
+/// Create (empty ID) or update a recurring holiday rule; its holidays are
+/// regenerated right away
+#[tauri::command]
+pub async fn save_holiday_rule(app: tauri::AppHandle, rule: HolidayRule) -> Result<HolidayRule, String> {
+    let saved = rules::save(&*db::open(&app)?, rule)?;
+    log::info!("[holidays::cmd] Saved holiday rule {}", saved.name);
+    app.state::<JobRegistry>().run_now(JOB_ID)?;
+    Ok(saved)
+}
+
+/// Delete a rule along with the holidays it generated
+#[tauri::command]
+pub async fn delete_holiday_rule(app: tauri::AppHandle, id: String) -> Result<bool, String> {
+    rules::delete(&*db::open(&app)?, &id)
+}
+
+/// Enter the first day of a lunar rule for the year of `startDate`
+#[tauri::command]
+pub async fn set_holiday_rule_date(
+    app: tauri::AppHandle,
+    rule_id: String,
+    start_date: String,
+) -> Result<HolidayRule, String> {
+    let rule = rules::set_date(&*db::open(&app)?, &rule_id, &start_date)?;
+    log::info!("[holidays::cmd] {} starts on {}", rule.name, start_date);
+    app.state::<JobRegistry>().run_now(JOB_ID)?;
+    Ok(rule)
+}
+
+#[tauri::command]
+pub async fn clear_holiday_rule_date(app: tauri::AppHandle, rule_id: String, year: i32) -> Result<bool, String> {
+    let cleared = rules::clear_date(&*db::open(&app)?, &rule_id, year)?;
+    if cleared {
+        app.state::<JobRegistry>().run_now(JOB_ID)?;
+    }
+    Ok(cleared)
+}
+
+/// Generate the rules' holidays for a range of years now, e.g. to fill in
+/// past years. The background job only keeps this year and next current.
+#[tauri::command]
+pub async fn materialize_holiday_rules(
+    app: tauri::AppHandle,
+    first_year: i32,
+    last_year: i32,
+) -> Result<HolidayRuleRun, String> {
+    if last_year - first_year >= MAX_YEARS {
+        return Err(format!("At most {} years at a time", MAX_YEARS));
+    }
+    let run = tauri::async_runtime::spawn_blocking(move || {
+        rules::materialize(&mut *db::open(&app)?, first_year, last_year)
+    })
+    .await
+    .map_err(|e| format!("Holiday rule task failed: {}", e))??;
+    log::info!(
+        "[holidays::cmd] Generated {} holidays for {}-{} ({} skipped, {} dates missing)",
+        run.generated,
+        first_year,
+        last_year,
+        run.skipped,
+        run.missing.len()
+    );
+    Ok(run)
+}
+
+/// Keep this year's and next year's holidays in line with the rules. Runs
+/// for the lifetime of the app; saving a rule runs it right away.
+pub async fn run_scheduled(app: tauri::AppHandle) {
+    let job = jobs::registry::register(
+        &app,
+        JOB_ID,
+        "Holiday rules",
+        "Turns recurring holidays into this year's and next year's holidays",
+        CHECK_INTERVAL,
+    );
+    let handle = app.clone();
+    jobs::registry::drive_blocking(
+        job,
+        move |_| {
+            let year = Local::now().year();
+            rules::materialize(&mut *db::open(&handle)?, year, year + 1)
+        },
+        |run| {
+            if !run.missing.is_empty() {
+                let names: Vec<String> = run.missing.iter().map(|m| format!("{} {}", m.name, m.year)).collect();
+                log::warn!("[holidays] No date entered for {}", names.join(", "));
+            }
+        },
+    )
+    .await;
+}
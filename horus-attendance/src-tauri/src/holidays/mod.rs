@@ -0,0 +1,24 @@
+//! Public holidays per department and site
+//!
+//! A holiday with neither a department nor a site is observed by everyone.
+//! One with a department is only observed by that department's users, one
+//! with a site only by users working at that site (`users.site`), and one
+//! with both only by the department's users at that site. The factory and
+//! the office can so keep different calendars in the same database.
+//!
+//! The engines resolve holidays per user: the row-by-row engine through a
+//! [`store::Calendar`], the set-based one through [`store::applies_sql`].
+//! Organization-wide figures such as the analytics' expected workdays only
+//! count holidays everyone observes.
+//!
+//! Recurring holidays are kept as rules ([`rules`]): fixed ones fall on the
+//! same day every year, lunar ones on a date entered for each year (with an
+//! estimate offered from the years already entered). A rule can move a day
+//! that falls on a weekly rest day to the next or previous workday. A
+//! background job turns the rules into holidays rows for this year and
+//! next; rows it made carry the rule's ID and are replaced on every run.
+
+pub mod commands;
+pub mod rules;
+pub mod store;
+pub mod types;
@@ -0,0 +1,350 @@
+//! Recurring holiday rules and turning them into holidays
+
+use std::collections::HashSet;
+
+use chrono::{Datelike, Duration, NaiveDate};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+use super::types::*;
+use crate::summary::engine;
+use crate::summary::rules::is_workday;
+use crate::summary::types::AttendanceRules;
+
+/// Days in a Hijri (Islamic) year; lunar dates move this far per year
+const LUNAR_YEAR_DAYS: f64 = 354.367;
+
+const MAX_DURATION_DAYS: u32 = 14;
+
+/// How far a day on a rest day is moved at most before giving up
+const MAX_SHIFT_DAYS: i64 = 31;
+
+fn parse_date(date: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| format!("Invalid date (expected YYYY-MM-DD): {}", date))
+}
+
+fn map_rule(row: &Row) -> rusqlite::Result<HolidayRule> {
+    let kind: String = row.get(2)?;
+    let policy: String = row.get(6)?;
+    Ok(HolidayRule {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        kind: HolidayRuleKind::parse(&kind).unwrap_or(HolidayRuleKind::Fixed),
+        month: row.get(3)?,
+        day: row.get(4)?,
+        duration_days: row.get(5)?,
+        weekend_policy: WeekendPolicy::parse(&policy).unwrap_or_default(),
+        department_id: row.get(7)?,
+        site: row.get(8)?,
+        active: row.get(9)?,
+        dates: Vec::new(),
+        created_at: row.get(10)?,
+        updated_at: row.get(11)?,
+    })
+}
+
+const SELECT_RULE: &str = "SELECT id, name, kind, month, day, duration_days, weekend_policy, department_id, site,
+                                  active, created_at, updated_at
+                           FROM holiday_rules";
+
+fn load_dates(conn: &Connection, rule: &mut HolidayRule) -> Result<(), String> {
+    let mut stmt = conn
+        .prepare("SELECT year, start_date FROM holiday_rule_dates WHERE rule_id = ?1 ORDER BY year")
+        .map_err(|e| format!("Failed to query rule dates: {}", e))?;
+    let rows = stmt
+        .query_map([&rule.id], |row| {
+            Ok(HolidayRuleDate {
+                year: row.get(0)?,
+                start_date: row.get(1)?,
+            })
+        })
+        .map_err(|e| format!("Failed to query rule dates: {}", e))?;
+    rule.dates = rows
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read rule dates: {}", e))?;
+    Ok(())
+}
+
+/// Every rule by name, with the dates entered for lunar ones
+pub fn list(conn: &Connection) -> Result<Vec<HolidayRule>, String> {
+    let mut stmt = conn
+        .prepare(&format!("{} ORDER BY name", SELECT_RULE))
+        .map_err(|e| format!("Failed to query holiday rules: {}", e))?;
+    let mut rules: Vec<HolidayRule> = stmt
+        .query_map([], map_rule)
+        .map_err(|e| format!("Failed to query holiday rules: {}", e))?
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read holiday rules: {}", e))?;
+    for rule in &mut rules {
+        load_dates(conn, rule)?;
+    }
+    Ok(rules)
+}
+
+pub fn get(conn: &Connection, id: &str) -> Result<Option<HolidayRule>, String> {
+    let rule = conn
+        .query_row(&format!("{} WHERE id = ?1", SELECT_RULE), [id], map_rule)
+        .optional()
+        .map_err(|e| format!("Failed to read holiday rule: {}", e))?;
+    match rule {
+        Some(mut rule) => {
+            load_dates(conn, &mut rule)?;
+            Ok(Some(rule))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Create a rule (empty ID) or update one. Its holidays follow on the next
+/// run of [`materialize`].
+pub fn save(conn: &Connection, mut rule: HolidayRule) -> Result<HolidayRule, String> {
+    rule.name = rule.name.trim().to_string();
+    if rule.name.is_empty() {
+        return Err("Holiday rule name is required".to_string());
+    }
+    match rule.kind {
+        HolidayRuleKind::Fixed => {
+            let (Some(month), Some(day)) = (rule.month, rule.day) else {
+                return Err("A fixed holiday needs a month and day".to_string());
+            };
+            // A leap year, so 29 February is allowed (it's skipped in other years)
+            if NaiveDate::from_ymd_opt(2000, month, day).is_none() {
+                return Err(format!("Invalid month and day: {}/{}", month, day));
+            }
+        }
+        HolidayRuleKind::Lunar => {
+            rule.month = None;
+            rule.day = None;
+        }
+    }
+    if !(1..=MAX_DURATION_DAYS).contains(&rule.duration_days) {
+        return Err(format!("A holiday lasts between 1 and {} days", MAX_DURATION_DAYS));
+    }
+    rule.site = rule.site.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+    rule.department_id = rule.department_id.filter(|d| !d.is_empty());
+    if let Some(department_id) = &rule.department_id {
+        let known: bool = conn
+            .query_row("SELECT EXISTS(SELECT 1 FROM departments WHERE id = ?1)", [department_id], |row| row.get(0))
+            .map_err(|e| format!("Failed to look up department: {}", e))?;
+        if !known {
+            return Err(format!("Department not found: {}", department_id));
+        }
+    }
+    if rule.id.is_empty() {
+        rule.id = uuid::Uuid::new_v4().to_string();
+    }
+    conn.execute(
+        "INSERT INTO holiday_rules
+            (id, name, kind, month, day, duration_days, weekend_policy, department_id, site, active)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+         ON CONFLICT(id) DO UPDATE SET
+           name = excluded.name,
+           kind = excluded.kind,
+           month = excluded.month,
+           day = excluded.day,
+           duration_days = excluded.duration_days,
+           weekend_policy = excluded.weekend_policy,
+           department_id = excluded.department_id,
+           site = excluded.site,
+           active = excluded.active,
+           updated_at = datetime('now')",
+        params![
+            rule.id,
+            rule.name,
+            rule.kind.as_str(),
+            rule.month,
+            rule.day,
+            rule.duration_days,
+            rule.weekend_policy.as_str(),
+            rule.department_id,
+            rule.site,
+            rule.active
+        ],
+    )
+    .map_err(|e| format!("Failed to save holiday rule: {}", e))?;
+    get(conn, &rule.id)?.ok_or_else(|| "Holiday rule disappeared while saving".to_string())
+}
+
+/// Delete a rule with every holiday it generated
+pub fn delete(conn: &Connection, id: &str) -> Result<bool, String> {
+    conn.execute("DELETE FROM holidays WHERE rule_id = ?1", [id])
+        .map_err(|e| format!("Failed to delete generated holidays: {}", e))?;
+    conn.execute("DELETE FROM holiday_rule_dates WHERE rule_id = ?1", [id])
+        .map_err(|e| format!("Failed to delete rule dates: {}", e))?;
+    conn.execute("DELETE FROM holiday_rules WHERE id = ?1", [id])
+        .map(|n| n > 0)
+        .map_err(|e| format!("Failed to delete holiday rule: {}", e))
+}
+
+/// Enter where a lunar rule starts in the year of `start_date`
+pub fn set_date(conn: &Connection, rule_id: &str, start_date: &str) -> Result<HolidayRule, String> {
+    let date = parse_date(start_date)?;
+    match get(conn, rule_id)? {
+        Some(rule) if rule.kind != HolidayRuleKind::Lunar => {
+            return Err(format!("'{}' falls on the same day every year", rule.name))
+        }
+        Some(_) => {}
+        None => return Err(format!("Holiday rule not found: {}", rule_id)),
+    }
+    conn.execute(
+        "INSERT INTO holiday_rule_dates (rule_id, year, start_date) VALUES (?1, ?2, ?3)
+         ON CONFLICT(rule_id, year) DO UPDATE SET start_date = excluded.start_date",
+        params![rule_id, date.year(), start_date],
+    )
+    .map_err(|e| format!("Failed to save rule date: {}", e))?;
+    get(conn, rule_id)?.ok_or_else(|| "Holiday rule disappeared while saving".to_string())
+}
+
+/// Forget where a lunar rule starts in a year
+pub fn clear_date(conn: &Connection, rule_id: &str, year: i32) -> Result<bool, String> {
+    conn.execute(
+        "DELETE FROM holiday_rule_dates WHERE rule_id = ?1 AND year = ?2",
+        params![rule_id, year],
+    )
+    .map(|n| n > 0)
+    .map_err(|e| format!("Failed to delete rule date: {}", e))
+}
+
+/// Where a lunar rule probably starts in `year`, from the nearest year
+/// entered moved by whole lunar years
+fn estimate(dates: &[HolidayRuleDate], year: i32) -> Option<NaiveDate> {
+    let known = dates.iter().min_by_key(|d| (d.year - year).abs())?;
+    let known_date = NaiveDate::parse_from_str(&known.start_date, "%Y-%m-%d").ok()?;
+    let lunar_years = ((year - known.year) as f64 * 365.2425 / LUNAR_YEAR_DAYS).round() as i64;
+    // Twice in some Gregorian years; the earlier one is taken
+    (lunar_years - 1..=lunar_years + 1)
+        .map(|n| known_date + Duration::days((n as f64 * LUNAR_YEAR_DAYS).round() as i64))
+        .find(|d| d.year() == year)
+}
+
+/// First day of a rule in a year, or why there is none
+fn first_day(rule: &HolidayRule, year: i32) -> Result<Option<NaiveDate>, MissingRuleDate> {
+    match rule.kind {
+        // 29 February only happens in leap years
+        HolidayRuleKind::Fixed => Ok(NaiveDate::from_ymd_opt(year, rule.month.unwrap_or(0), rule.day.unwrap_or(0))),
+        HolidayRuleKind::Lunar => match rule.dates.iter().find(|d| d.year == year) {
+            Some(entered) => Ok(NaiveDate::parse_from_str(&entered.start_date, "%Y-%m-%d").ok()),
+            None => Err(MissingRuleDate {
+                rule_id: rule.id.clone(),
+                name: rule.name.clone(),
+                year,
+                estimate: estimate(&rule.dates, year).map(|d| d.format("%Y-%m-%d").to_string()),
+            }),
+        },
+    }
+}
+
+type ScopedDate = (String, Option<String>, Option<String>);
+
+/// Where a day of a rule is observed: the day itself, or for a day on a
+/// rest day the nearest workday in the policy's direction that its scope
+/// doesn't have off yet
+fn observed(
+    date: NaiveDate,
+    rule: &HolidayRule,
+    attendance_rules: &AttendanceRules,
+    taken: &HashSet<ScopedDate>,
+) -> NaiveDate {
+    let step = match rule.weekend_policy {
+        WeekendPolicy::None => return date,
+        WeekendPolicy::NextWorkday => 1,
+        WeekendPolicy::PreviousWorkday => -1,
+    };
+    let key = |d: NaiveDate| d.format("%Y-%m-%d").to_string();
+    if is_workday(&key(date), attendance_rules) {
+        return date;
+    }
+    (1..=MAX_SHIFT_DAYS)
+        .map(|n| date + Duration::days(n * step))
+        .find(|d| {
+            is_workday(&key(*d), attendance_rules)
+                && !taken.contains(&(key(*d), rule.department_id.clone(), rule.site.clone()))
+        })
+        .unwrap_or(date)
+}
+
+/// Replace the holidays generated for `first_year..=last_year` with what
+/// the active rules give now. Holidays entered by hand are left alone; a
+/// rule's day that one of them already covers is skipped.
+pub fn materialize(conn: &mut Connection, first_year: i32, last_year: i32) -> Result<HolidayRuleRun, String> {
+    if first_year > last_year {
+        return Err("The first year must not be after the last".to_string());
+    }
+    let attendance_rules = engine::load_rules(conn)?;
+    let rules = list(conn)?;
+
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    let removed = tx
+        .execute(
+            "DELETE FROM holidays WHERE rule_id IS NOT NULL AND rule_year BETWEEN ?1 AND ?2",
+            params![first_year, last_year],
+        )
+        .map_err(|e| format!("Failed to clear generated holidays: {}", e))? as u32;
+
+    let mut taken: HashSet<ScopedDate> = {
+        let mut stmt = tx
+            .prepare("SELECT date, department_id, site FROM holidays")
+            .map_err(|e| format!("Failed to query holidays: {}", e))?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .map_err(|e| format!("Failed to query holidays: {}", e))?;
+        rows.collect::<Result<_, _>>()
+            .map_err(|e| format!("Failed to read holidays: {}", e))?
+    };
+
+    let mut run = HolidayRuleRun {
+        first_year,
+        last_year,
+        generated: 0,
+        removed,
+        skipped: 0,
+        missing: Vec::new(),
+    };
+    for rule in rules.iter().filter(|r| r.active) {
+        for year in first_year..=last_year {
+            let start = match first_day(rule, year) {
+                Ok(Some(start)) => start,
+                Ok(None) => continue,
+                Err(missing) => {
+                    run.missing.push(missing);
+                    continue;
+                }
+            };
+            for offset in 0..rule.duration_days as i64 {
+                let day = start + Duration::days(offset);
+                let date = observed(day, rule, &attendance_rules, &taken);
+                let name = if date == day {
+                    rule.name.clone()
+                } else {
+                    format!("{} (observed)", rule.name)
+                };
+                let key = date.format("%Y-%m-%d").to_string();
+                let inserted = tx
+                    .execute(
+                        "INSERT OR IGNORE INTO holidays (id, date, name, department_id, site, rule_id, rule_year)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                        params![
+                            uuid::Uuid::new_v4().to_string(),
+                            key,
+                            name,
+                            rule.department_id,
+                            rule.site,
+                            rule.id,
+                            year
+                        ],
+                    )
+                    .map_err(|e| format!("Failed to save holiday: {}", e))?;
+                if inserted == 0 {
+                    run.skipped += 1;
+                } else {
+                    run.generated += 1;
+                    taken.insert((key, rule.department_id.clone(), rule.site.clone()));
+                }
+            }
+        }
+    }
+    tx.commit()
+        .map_err(|e| format!("Failed to commit generated holidays: {}", e))?;
+    Ok(run)
+}
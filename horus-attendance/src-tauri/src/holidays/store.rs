@@ -0,0 +1,207 @@
+//! Storage and resolution of holidays
+
+use std::collections::{HashMap, HashSet};
+
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+use super::types::Holiday;
+use crate::summary::commands::validate_date;
+
+/// SQL condition that a user observes a holiday on a date. The arguments
+/// are SQL expressions for the user ID and the YYYY-MM-DD date.
+pub fn applies_sql(user_id: &str, date: &str) -> String {
+    format!(
+        "EXISTS (SELECT 1 FROM holidays h JOIN users hu ON hu.id = {user_id}
+                 WHERE h.date = {date}
+                   AND (h.department_id IS NULL OR h.department_id = hu.department_id)
+                   AND (h.site IS NULL OR h.site = hu.site))",
+        user_id = user_id,
+        date = date,
+    )
+}
+
+/// Who observes a holiday: None matches any department or site
+struct Scope {
+    department_id: Option<String>,
+    site: Option<String>,
+}
+
+impl Scope {
+    fn covers(&self, department_id: Option<&str>, site: Option<&str>) -> bool {
+        self.department_id.as_deref().map_or(true, |d| Some(d) == department_id)
+            && self.site.as_deref().map_or(true, |s| Some(s) == site)
+    }
+}
+
+/// The holidays of a date range, resolved per user
+pub struct Calendar {
+    holidays: HashMap<String, Vec<Scope>>,
+    /// user ID -> (department ID, site)
+    users: HashMap<String, (Option<String>, Option<String>)>,
+}
+
+impl Calendar {
+    /// Holidays between two dates (inclusive) and every user's department
+    /// and site
+    pub fn load(conn: &Connection, start_date: &str, end_date: &str) -> Result<Self, String> {
+        let mut holidays: HashMap<String, Vec<Scope>> = HashMap::new();
+        let mut stmt = conn
+            .prepare("SELECT date, department_id, site FROM holidays WHERE date >= ?1 AND date <= ?2")
+            .map_err(|e| format!("Failed to query holidays: {}", e))?;
+        let rows = stmt
+            .query_map(params![start_date, end_date], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    Scope {
+                        department_id: row.get(1)?,
+                        site: row.get(2)?,
+                    },
+                ))
+            })
+            .map_err(|e| format!("Failed to query holidays: {}", e))?;
+        for row in rows {
+            let (date, scope) = row.map_err(|e| format!("Failed to read holidays: {}", e))?;
+            holidays.entry(date).or_default().push(scope);
+        }
+
+        let mut stmt = conn
+            .prepare("SELECT id, department_id, site FROM users")
+            .map_err(|e| format!("Failed to query users: {}", e))?;
+        let users = stmt
+            .query_map([], |row| Ok((row.get(0)?, (row.get(1)?, row.get(2)?))))
+            .map_err(|e| format!("Failed to query users: {}", e))?
+            .collect::<Result<_, _>>()
+            .map_err(|e| format!("Failed to read users: {}", e))?;
+        Ok(Self { holidays, users })
+    }
+
+    /// Whether a user has a holiday on a date
+    pub fn observes(&self, user_id: &str, date: &str) -> bool {
+        let Some(scopes) = self.holidays.get(date) else {
+            return false;
+        };
+        let (department_id, site) = self
+            .users
+            .get(user_id)
+            .map(|(d, s)| (d.as_deref(), s.as_deref()))
+            .unwrap_or((None, None));
+        scopes.iter().any(|scope| scope.covers(department_id, site))
+    }
+}
+
+/// Dates between two dates (inclusive) that everyone has off
+pub fn global_dates(conn: &Connection, start_date: &str, end_date: &str) -> Result<HashSet<String>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT date FROM holidays
+             WHERE date >= ?1 AND date <= ?2 AND department_id IS NULL AND site IS NULL",
+        )
+        .map_err(|e| format!("Failed to query holidays: {}", e))?;
+    let rows = stmt
+        .query_map(params![start_date, end_date], |row| row.get(0))
+        .map_err(|e| format!("Failed to query holidays: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read holidays: {}", e))
+}
+
+fn map_holiday(row: &Row) -> rusqlite::Result<Holiday> {
+    Ok(Holiday {
+        id: row.get(0)?,
+        date: row.get(1)?,
+        name: row.get(2)?,
+        department_id: row.get(3)?,
+        site: row.get(4)?,
+        rule_id: row.get(5)?,
+        created_at: row.get(6)?,
+    })
+}
+
+const SELECT_HOLIDAY: &str = "SELECT id, date, name, department_id, site, rule_id, created_at FROM holidays";
+
+/// Holidays by date, optionally only between two dates (inclusive) or only
+/// those a department observes (its own and everyone's)
+pub fn list(
+    conn: &Connection,
+    start_date: Option<&str>,
+    end_date: Option<&str>,
+    department_id: Option<&str>,
+) -> Result<Vec<Holiday>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "{} WHERE (?1 IS NULL OR date >= ?1)
+                 AND (?2 IS NULL OR date <= ?2)
+                 AND (?3 IS NULL OR department_id IS NULL OR department_id = ?3)
+               ORDER BY date, department_id IS NOT NULL, site IS NOT NULL",
+            SELECT_HOLIDAY
+        ))
+        .map_err(|e| format!("Failed to query holidays: {}", e))?;
+    let rows = stmt
+        .query_map(params![start_date, end_date, department_id], map_holiday)
+        .map_err(|e| format!("Failed to query holidays: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read holidays: {}", e))
+}
+
+pub fn get(conn: &Connection, id: &str) -> Result<Option<Holiday>, String> {
+    conn.query_row(&format!("{} WHERE id = ?1", SELECT_HOLIDAY), [id], map_holiday)
+        .optional()
+        .map_err(|e| format!("Failed to read holiday: {}", e))
+}
+
+/// Create a holiday (empty ID) or update one
+pub fn save(conn: &Connection, mut holiday: Holiday) -> Result<Holiday, String> {
+    validate_date(&holiday.date)?;
+    holiday.name = holiday.name.map(|n| n.trim().to_string()).filter(|n| !n.is_empty());
+    holiday.site = holiday.site.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+    holiday.department_id = holiday.department_id.filter(|d| !d.is_empty());
+    if let Some(department_id) = &holiday.department_id {
+        let known: bool = conn
+            .query_row("SELECT EXISTS(SELECT 1 FROM departments WHERE id = ?1)", [department_id], |row| row.get(0))
+            .map_err(|e| format!("Failed to look up department: {}", e))?;
+        if !known {
+            return Err(format!("Department not found: {}", department_id));
+        }
+    }
+    if holiday.id.is_empty() {
+        holiday.id = uuid::Uuid::new_v4().to_string();
+    }
+    conn.execute(
+        "INSERT INTO holidays (id, date, name, department_id, site) VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT(id) DO UPDATE SET
+           date = excluded.date,
+           name = excluded.name,
+           department_id = excluded.department_id,
+           site = excluded.site",
+        params![holiday.id, holiday.date, holiday.name, holiday.department_id, holiday.site],
+    )
+    .map_err(|e| match e {
+        rusqlite::Error::SqliteFailure(err, _) if err.code == rusqlite::ErrorCode::ConstraintViolation => {
+            format!("{} is already a holiday for the same departments and sites", holiday.date)
+        }
+        e => format!("Failed to save holiday: {}", e),
+    })?;
+    get(conn, &holiday.id)?.ok_or_else(|| "Holiday disappeared while saving".to_string())
+}
+
+pub fn delete(conn: &Connection, id: &str) -> Result<bool, String> {
+    conn.execute("DELETE FROM holidays WHERE id = ?1", [id])
+        .map(|n| n > 0)
+        .map_err(|e| format!("Failed to delete holiday: {}", e))
+}
+
+/// Every site named on a user or a holiday, for pickers
+pub fn sites(conn: &Connection) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT site FROM users WHERE site IS NOT NULL
+             UNION
+             SELECT site FROM holidays WHERE site IS NOT NULL
+             ORDER BY 1",
+        )
+        .map_err(|e| format!("Failed to query sites: {}", e))?;
+    let rows = stmt
+        .query_map([], |row| row.get(0))
+        .map_err(|e| format!("Failed to query sites: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read sites: {}", e))
+}
@@ -0,0 +1,167 @@
+//! Holiday data types for Tauri command serialization
+
+use serde::{Deserialize, Serialize};
+
+/// A holiday (one holidays row)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Holiday {
+    /// Generated when saving a new holiday
+    #[serde(default)]
+    pub id: String,
+    /// YYYY-MM-DD
+    pub date: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Only this department observes it; None for every department
+    #[serde(default)]
+    pub department_id: Option<String>,
+    /// Only users at this site observe it; None for every site
+    #[serde(default)]
+    pub site: Option<String>,
+    /// The rule that generated it; its next run replaces it, so edit the
+    /// rule rather than the holiday
+    #[serde(default)]
+    pub rule_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+}
+
+/// How a holiday rule finds its first day each year
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HolidayRuleKind {
+    /// The same month and day every year, e.g. 25 December
+    Fixed,
+    /// A date entered for each year, for holidays that follow the lunar
+    /// calendar such as Eid
+    Lunar,
+}
+
+impl HolidayRuleKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Fixed => "fixed",
+            Self::Lunar => "lunar",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "fixed" => Some(Self::Fixed),
+            "lunar" => Some(Self::Lunar),
+            _ => None,
+        }
+    }
+}
+
+/// What happens to a day of a rule that falls on a weekly rest day
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WeekendPolicy {
+    /// It stays on the rest day; nobody gets another day off
+    #[default]
+    None,
+    /// The next workday that isn't already off is taken instead
+    NextWorkday,
+    /// The previous workday that isn't already off is taken instead
+    PreviousWorkday,
+}
+
+impl WeekendPolicy {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::NextWorkday => "next_workday",
+            Self::PreviousWorkday => "previous_workday",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "none" => Some(Self::None),
+            "next_workday" => Some(Self::NextWorkday),
+            "previous_workday" => Some(Self::PreviousWorkday),
+            _ => None,
+        }
+    }
+}
+
+/// A recurring holiday (one holiday_rules row)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HolidayRule {
+    /// Generated when saving a new rule
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    pub kind: HolidayRuleKind,
+    /// 1-12, for fixed rules
+    #[serde(default)]
+    pub month: Option<u32>,
+    /// 1-31, for fixed rules
+    #[serde(default)]
+    pub day: Option<u32>,
+    /// Consecutive days off from the first
+    #[serde(default = "default_duration")]
+    pub duration_days: u32,
+    #[serde(default)]
+    pub weekend_policy: WeekendPolicy,
+    #[serde(default)]
+    pub department_id: Option<String>,
+    #[serde(default)]
+    pub site: Option<String>,
+    #[serde(default = "default_active")]
+    pub active: bool,
+    /// First day per year, for lunar rules; ignored when saving
+    #[serde(default)]
+    pub dates: Vec<HolidayRuleDate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+}
+
+fn default_duration() -> u32 {
+    1
+}
+
+fn default_active() -> bool {
+    true
+}
+
+/// Where a lunar rule starts in one year
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HolidayRuleDate {
+    pub year: i32,
+    /// YYYY-MM-DD
+    pub start_date: String,
+}
+
+/// A lunar rule with no date entered for a year
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MissingRuleDate {
+    pub rule_id: String,
+    pub name: String,
+    pub year: i32,
+    /// Where it probably falls, from the nearest year entered moved by the
+    /// lunar year; still has to be confirmed
+    pub estimate: Option<String>,
+}
+
+/// Outcome of turning the rules into holidays for a range of years
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HolidayRuleRun {
+    pub first_year: i32,
+    pub last_year: i32,
+    /// Holidays the rules produced
+    pub generated: u32,
+    /// Holidays of earlier runs replaced
+    pub removed: u32,
+    /// Days left out because a holiday with the same scope already exists
+    pub skipped: u32,
+    pub missing: Vec<MissingRuleDate>,
+}
@@ -0,0 +1,60 @@
+//! Tauri command handlers for the jobs registry.
+
+use tauri::State;
+
+use super::registry::{JobRegistry, PAUSED_JOBS_KEY};
+use super::types::ScheduledJob;
+use crate::db;
+use crate::sync_scheduler::gate::SyncGate;
+use crate::sync_scheduler::types::SyncSlots;
+
+/// Every background job with its last and next run
+#[tauri::command]
+pub fn list_scheduled_jobs(registry: State<'_, JobRegistry>) -> Result<Vec<ScheduledJob>, String> {
+    registry.list()
+}
+
+/// Run a job now, paused or not, or right after its current run
+#[tauri::command]
+pub fn run_job_now(registry: State<'_, JobRegistry>, job_id: String) -> Result<ScheduledJob, String> {
+    log::info!("[jobs::cmd] Running {} now", job_id);
+    registry.run_now(&job_id)
+}
+
+/// Device syncs holding a slot and waiting for one, under the sync
+/// scheduling limits
+#[tauri::command]
+pub fn get_sync_slots(gate: State<'_, SyncGate>) -> Result<SyncSlots, String> {
+    gate.slots()
+}
+
+fn save_paused(app: &tauri::AppHandle, registry: &JobRegistry) -> Result<(), String> {
+    let json = serde_json::to_string(&registry.paused_ids()?).map_err(|e| format!("Failed to serialize jobs: {}", e))?;
+    db::set_setting(&*db::open(app)?, PAUSED_JOBS_KEY, &json)
+}
+
+/// Stop scheduled runs of a job until it is resumed, also after a restart
+#[tauri::command]
+pub async fn pause_job(
+    app: tauri::AppHandle,
+    registry: State<'_, JobRegistry>,
+    job_id: String,
+) -> Result<ScheduledJob, String> {
+    let job = registry.set_paused(&job_id, true)?;
+    save_paused(&app, &registry)?;
+    log::info!("[jobs::cmd] Paused {}", job_id);
+    Ok(job)
+}
+
+/// Resume scheduled runs of a paused job, starting with one right away
+#[tauri::command]
+pub async fn resume_job(
+    app: tauri::AppHandle,
+    registry: State<'_, JobRegistry>,
+    job_id: String,
+) -> Result<ScheduledJob, String> {
+    let job = registry.set_paused(&job_id, false)?;
+    save_paused(&app, &registry)?;
+    log::info!("[jobs::cmd] Resumed {}", job_id);
+    Ok(job)
+}
@@ -0,0 +1,20 @@
+//! Registry of background jobs
+//!
+//! Every periodic job the backend runs on its own (anomaly checks, closure
+//! emails, delivery retries, the device watchdog, realtime journal drains,
+//! holiday rules, attendance points, minimum staffing, enrollment expiry,
+//! ledger sealing, the punch archive, break checks, working-time checks,
+//! automatic device syncs, WAL checkpoints)
+//! registers here when it starts and reports each run, so the frontend can
+//! list what runs in the background, when it last ran and how that went,
+//! and when it runs next.
+//! A job can be run immediately or paused; pauses are kept in the
+//! `pausedJobs` setting so they survive a restart. A paused job still runs
+//! when asked to explicitly.
+//! Manual device syncs aren't jobs of their own but share slots (see
+//! `sync_scheduler`); which are running and which are waiting is listed
+//! here too.
+
+pub mod commands;
+pub mod registry;
+pub mod types;
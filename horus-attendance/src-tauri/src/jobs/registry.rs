@@ -0,0 +1,301 @@
+//! The registry itself, held as Tauri managed state
+
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use tauri::Manager;
+use tokio::sync::Notify;
+
+use super::types::ScheduledJob;
+use crate::db;
+
+/// Setting listing the IDs of paused jobs
+pub const PAUSED_JOBS_KEY: &str = "pausedJobs";
+
+pub const STATUS_IDLE: &str = "idle";
+pub const STATUS_RUNNING: &str = "running";
+pub const STATUS_PAUSED: &str = "paused";
+
+struct Job {
+    info: ScheduledJob,
+    started: Option<Instant>,
+    trigger: Arc<Notify>,
+}
+
+#[derive(Default)]
+struct Shared {
+    jobs: Mutex<BTreeMap<String, Job>>,
+    /// Set on shutdown; no run starts after it
+    closed: AtomicBool,
+}
+
+/// Registered background jobs
+#[derive(Default, Clone)]
+pub struct JobRegistry(Arc<Shared>);
+
+fn now() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+}
+
+impl JobRegistry {
+    fn lock(&self) -> Result<MutexGuard<'_, BTreeMap<String, Job>>, String> {
+        self.0.jobs.lock().map_err(|_| "Job registry is poisoned".to_string())
+    }
+
+    fn update(&self, id: &str, change: impl FnOnce(&mut Job)) {
+        if let Ok(mut jobs) = self.0.jobs.lock() {
+            if let Some(job) = jobs.get_mut(id) {
+                change(job);
+            }
+        }
+    }
+
+    /// Every registered job, by name
+    pub fn list(&self) -> Result<Vec<ScheduledJob>, String> {
+        let mut jobs: Vec<ScheduledJob> = self.lock()?.values().map(|job| job.info.clone()).collect();
+        jobs.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(jobs)
+    }
+
+    /// Start a run of `id` now, or as soon as the current one finishes
+    pub fn run_now(&self, id: &str) -> Result<ScheduledJob, String> {
+        let jobs = self.lock()?;
+        let job = jobs.get(id).ok_or_else(|| format!("Unknown job: {}", id))?;
+        job.trigger.notify_one();
+        Ok(job.info.clone())
+    }
+
+    /// Pause or resume scheduled runs of `id`. A running run finishes.
+    pub fn set_paused(&self, id: &str, paused: bool) -> Result<ScheduledJob, String> {
+        let mut jobs = self.lock()?;
+        let job = jobs.get_mut(id).ok_or_else(|| format!("Unknown job: {}", id))?;
+        job.info.paused = paused;
+        if job.info.status != STATUS_RUNNING {
+            job.info.status = if paused { STATUS_PAUSED } else { STATUS_IDLE }.to_string();
+        }
+        if paused {
+            job.info.next_run_at = None;
+        } else if job.info.status == STATUS_IDLE {
+            // Resumed jobs run right away rather than waiting out the interval
+            job.trigger.notify_one();
+        }
+        Ok(job.info.clone())
+    }
+
+    /// Start no more runs; the app is shutting down
+    pub fn close(&self) {
+        self.0.closed.store(true, Ordering::SeqCst);
+    }
+
+    /// IDs of paused jobs, as kept in the `pausedJobs` setting
+    pub fn paused_ids(&self) -> Result<Vec<String>, String> {
+        Ok(self
+            .lock()?
+            .values()
+            .filter(|job| job.info.paused)
+            .map(|job| job.info.id.clone())
+            .collect())
+    }
+}
+
+/// A job's side of the registry, owned by its loop, which [`drive`] runs:
+///
+/// ```ignore
+/// let job = jobs::registry::register(&app, ...);
+/// jobs::registry::drive_blocking(job, move |manual| ..., |result| ...).await;
+/// ```
+pub struct JobHandle {
+    id: &'static str,
+    name: String,
+    interval: Duration,
+    /// Where the interval is read from before each wait, for jobs whose
+    /// interval is a setting
+    interval_source: Option<Box<dyn Fn() -> Duration + Send>>,
+    registry: JobRegistry,
+    trigger: Arc<Notify>,
+    /// The current or next run was asked for with run_job_now
+    manual: bool,
+}
+
+/// Register a background job that runs every `interval`. A job paused
+/// before the app last closed starts out paused.
+pub fn register(
+    app: &tauri::AppHandle,
+    id: &'static str,
+    name: &str,
+    description: &str,
+    interval: Duration,
+) -> JobHandle {
+    // No database yet on first launch
+    let paused: Vec<String> = db::open(app)
+        .and_then(|conn| db::get_json_setting(&conn, PAUSED_JOBS_KEY))
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+    let paused = paused.iter().any(|p| p == id);
+    let registry = app.state::<JobRegistry>().inner().clone();
+    let trigger = Arc::new(Notify::new());
+    if let Ok(mut jobs) = registry.lock() {
+        jobs.insert(
+            id.to_string(),
+            Job {
+                info: ScheduledJob {
+                    id: id.to_string(),
+                    name: name.to_string(),
+                    description: description.to_string(),
+                    interval_seconds: interval.as_secs(),
+                    status: if paused { STATUS_PAUSED } else { STATUS_IDLE }.to_string(),
+                    paused,
+                    last_run_at: None,
+                    last_finished_at: None,
+                    last_result: None,
+                    last_error: None,
+                    last_duration_ms: None,
+                    next_run_at: None,
+                    run_count: 0,
+                },
+                started: None,
+                trigger: Arc::clone(&trigger),
+            },
+        );
+    }
+    JobHandle {
+        id,
+        name: name.to_string(),
+        interval,
+        interval_source: None,
+        registry,
+        trigger,
+        manual: false,
+    }
+}
+
+impl JobHandle {
+    /// Whether to run now, marking the job running if so. Scheduled runs of
+    /// a paused job are skipped; runs asked for with run_job_now aren't.
+    /// Nothing runs once the registry is closed for shutdown.
+    pub fn begin(&mut self) -> bool {
+        if self.registry.0.closed.load(Ordering::SeqCst) {
+            return false;
+        }
+        let manual = self.manual;
+        let mut run = false;
+        self.registry.update(self.id, |job| {
+            if job.info.paused && !manual {
+                return;
+            }
+            run = true;
+            job.info.status = STATUS_RUNNING.to_string();
+            job.info.last_run_at = Some(now());
+            job.info.next_run_at = None;
+            job.started = Some(Instant::now());
+        });
+        run
+    }
+
+    /// Read the interval from `source` after every run, for jobs whose
+    /// interval is a setting
+    pub fn with_interval_from(mut self, source: impl Fn() -> Duration + Send + 'static) -> Self {
+        self.interval_source = Some(Box::new(source));
+        self
+    }
+
+    /// Change how often the job runs. Takes effect from the next wait.
+    pub fn set_interval(&mut self, interval: Duration) {
+        if interval == self.interval {
+            return;
+        }
+        self.interval = interval;
+        self.registry.update(self.id, |job| job.info.interval_seconds = interval.as_secs());
+    }
+
+    /// Whether the current run was asked for with run_job_now
+    pub fn is_manual(&self) -> bool {
+        self.manual
+    }
+
+    /// Record how the run started by [`Self::begin`] went
+    pub fn finish(&self, result: Result<(), String>) {
+        self.registry.update(self.id, |job| {
+            job.info.status = if job.info.paused { STATUS_PAUSED } else { STATUS_IDLE }.to_string();
+            job.info.last_finished_at = Some(now());
+            job.info.last_duration_ms = job.started.take().map(|started| started.elapsed().as_millis() as u64);
+            job.info.run_count += 1;
+            match result {
+                Ok(()) => {
+                    job.info.last_result = Some("ok".to_string());
+                    job.info.last_error = None;
+                }
+                Err(e) => {
+                    job.info.last_result = Some("failed".to_string());
+                    job.info.last_error = Some(e);
+                }
+            }
+        });
+    }
+
+    /// Wait for the next run: the interval passing, or run_job_now
+    pub async fn wait(&mut self) {
+        let interval = self.interval;
+        self.registry.update(self.id, |job| {
+            job.info.next_run_at = if job.info.paused {
+                None
+            } else {
+                chrono::Duration::from_std(interval)
+                    .ok()
+                    .map(|d| (chrono::Utc::now() + d).to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+            };
+        });
+        self.manual = tokio::time::timeout(interval, self.trigger.notified()).await.is_ok();
+    }
+}
+
+/// Run the job whenever it's due, for as long as the app runs. `run` does one
+/// run, told whether it was asked for with run_job_now; what a run returns
+/// goes to `report`. A failed run is logged at warn and kept as the job's
+/// last error.
+pub async fn drive<T, F, Fut>(mut job: JobHandle, mut run: F, mut report: impl FnMut(T))
+where
+    F: FnMut(bool) -> Fut,
+    Fut: Future<Output = Result<T, String>>,
+{
+    loop {
+        if job.begin() {
+            let outcome = run(job.is_manual()).await;
+            job.finish(outcome.as_ref().map(|_| ()).map_err(String::clone));
+            match outcome {
+                Ok(value) => report(value),
+                Err(e) => log::warn!("[jobs] {} failed: {}", job.name, e),
+            }
+        }
+        if let Some(interval) = job.interval_source.as_ref().map(|source| source()) {
+            job.set_interval(interval);
+        }
+        job.wait().await;
+    }
+}
+
+/// [`drive`] for jobs whose runs block: each run calls `task` on the
+/// blocking thread pool
+pub async fn drive_blocking<T, F>(job: JobHandle, task: F, report: impl FnMut(T))
+where
+    T: Send + 'static,
+    F: Fn(bool) -> Result<T, String> + Clone + Send + 'static,
+{
+    drive(
+        job,
+        move |manual| {
+            let task = task.clone();
+            async move {
+                tauri::async_runtime::spawn_blocking(move || task(manual))
+                    .await
+                    .map_err(|e| format!("Task failed: {}", e))?
+            }
+        },
+        report,
+    )
+    .await
+}
@@ -0,0 +1,27 @@
+//! Types for the jobs registry
+
+use serde::{Deserialize, Serialize};
+
+/// A background job and how its runs went
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledJob {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    /// Time between runs
+    pub interval_seconds: u64,
+    /// "idle", "running" or "paused"
+    pub status: String,
+    pub paused: bool,
+    pub last_run_at: Option<String>,
+    pub last_finished_at: Option<String>,
+    /// "ok" or "failed"
+    pub last_result: Option<String>,
+    pub last_error: Option<String>,
+    pub last_duration_ms: Option<u64>,
+    /// None while running or paused
+    pub next_run_at: Option<String>,
+    /// Runs since the app started
+    pub run_count: u64,
+}
@@ -0,0 +1,90 @@
+//! Tauri command handlers for the punch ledger.
+
+use super::store::{self, SETTINGS_KEY};
+use super::types::*;
+use crate::db;
+use crate::jobs;
+
+/// How often newly ingested punches are sealed
+const SEAL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(15 * 60);
+
+/// ID of the sealing job in the jobs registry
+pub const JOB_ID: &str = "ledger_seal";
+
+#[tauri::command]
+pub async fn get_ledger_settings(app: tauri::AppHandle) -> Result<LedgerSettings, String> {
+    store::load_settings(&*db::open(&app)?)
+}
+
+#[tauri::command]
+pub async fn save_ledger_settings(app: tauri::AppHandle, settings: LedgerSettings) -> Result<(), String> {
+    let json = serde_json::to_string(&settings).map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    log::info!(
+        "[ledger::cmd] Ledger {}",
+        if settings.enabled { "enabled" } else { "disabled" }
+    );
+    db::set_setting(&*db::open(&app)?, SETTINGS_KEY, &json)
+}
+
+/// Seal the punches stored since the last seal when the ledger is enabled
+fn seal_if_enabled(app: &tauri::AppHandle) -> Result<SealResult, String> {
+    let mut conn = db::open(app)?;
+    if !store::load_settings(&conn)?.enabled {
+        return Ok(SealResult {
+            batches: Vec::new(),
+            rows_sealed: 0,
+        });
+    }
+    store::seal(&mut conn)
+}
+
+/// Seal newly stored punches now rather than on the next scheduled run,
+/// e.g. right after a sync. Does nothing while the ledger is disabled.
+#[tauri::command]
+pub async fn seal_ledger(app: tauri::AppHandle) -> Result<SealResult, String> {
+    tauri::async_runtime::spawn_blocking(move || seal_if_enabled(&app))
+        .await
+        .map_err(|e| format!("Ledger task failed: {}", e))?
+}
+
+/// Recompute every sealed batch from the punches as they are now
+#[tauri::command]
+pub async fn verify_ledger(app: tauri::AppHandle) -> Result<LedgerVerification, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let verification = store::verify(&*db::open(&app)?)?;
+        if !verification.intact {
+            log::warn!(
+                "[ledger::cmd] {} of {} batches no longer match ({} amended)",
+                verification.broken.len() + verification.amended.len(),
+                verification.batches,
+                verification.amended.len()
+            );
+        }
+        Ok(verification)
+    })
+    .await
+    .map_err(|e| format!("Ledger task failed: {}", e))?
+}
+
+/// Seal newly ingested punches every few minutes while the ledger is
+/// enabled. Runs for the lifetime of the app.
+pub async fn run_scheduled(app: tauri::AppHandle) {
+    let job = jobs::registry::register(
+        &app,
+        JOB_ID,
+        "Punch ledger",
+        "Seals newly ingested punches into the tamper-evident hash chain",
+        SEAL_INTERVAL,
+    );
+    let handle = app.clone();
+    jobs::registry::drive_blocking(
+        job,
+        move |_| seal_if_enabled(&handle),
+        |sealed| {
+            if sealed.rows_sealed > 0 {
+                log::info!("[ledger] Sealed {} punches in {} batches", sealed.rows_sealed, sealed.batches.len());
+            }
+        },
+    )
+    .await;
+}
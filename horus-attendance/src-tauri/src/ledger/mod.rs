@@ -0,0 +1,20 @@
+//! Tamper-evident ledger of ingested punches
+//!
+//! Optional, for sites that must prove raw punches weren't changed after
+//! they came in. When the `ledger` setting is enabled, a background job
+//! seals the punches stored since its last run into a batch: a hash over
+//! each punch's stored fields, chained to the previous batch's hash, is
+//! appended to `log_ledger`, and which punches went into which batch is kept
+//! in `log_ledger_entries`. `verify_ledger` recomputes every batch from the
+//! punches as they are now and reports the batches whose punches were
+//! removed or changed and any break in the chain itself.
+//! Deleting a device or undoing a correction removes punches too; those
+//! batches show up as broken, with the number of punches missing.
+//! Data migrations and restores that rewrite or remove sealed punches record
+//! an amendment (`store::amend`), chained like the batches and bound to the
+//! batch's sealed hash. Such a batch is reported as amended rather than
+//! broken, but the ledger is no longer intact either way.
+
+pub mod commands;
+pub mod store;
+pub mod types;
@@ -0,0 +1,509 @@
+//! Ledger settings, sealing and verification
+
+use std::collections::{BTreeSet, HashSet};
+
+use chrono::{SecondsFormat, Utc};
+use rusqlite::types::Value;
+use rusqlite::{params, Connection, OptionalExtension};
+use sha2::{Digest, Sha256};
+
+use super::types::*;
+use crate::db;
+
+pub const SETTINGS_KEY: &str = "ledger";
+
+/// Previous hash of the first batch
+const GENESIS: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Most punches sealed in one batch
+const MAX_BATCH_ROWS: u32 = 5000;
+
+/// Missing punch IDs listed per broken batch
+const MAX_MISSING_IDS: usize = 20;
+
+/// The stored fields of a punch that are sealed, in hashing order
+const SEALED_COLUMNS: &str = "l.id, l.device_id, l.device_user_id, l.timestamp, l.verify_type, l.punch_type,
+                              l.raw_payload, l.source, l.latitude, l.longitude, l.note, l.created_at";
+const SEALED_COLUMN_COUNT: usize = 12;
+
+pub fn load_settings(conn: &Connection) -> Result<LedgerSettings, String> {
+    Ok(db::get_json_setting(conn, SETTINGS_KEY)?.unwrap_or_default())
+}
+
+/// One punch as a JSON array, so NULLs, numbers and text hash unambiguously.
+/// The sealed fields start at column `offset`.
+fn canonical(row: &rusqlite::Row, offset: usize) -> rusqlite::Result<String> {
+    let mut fields = Vec::with_capacity(SEALED_COLUMN_COUNT);
+    for i in offset..offset + SEALED_COLUMN_COUNT {
+        fields.push(match row.get::<_, Value>(i)? {
+            Value::Null => serde_json::Value::Null,
+            Value::Integer(n) => serde_json::Value::from(n),
+            Value::Real(f) => serde_json::Value::from(f),
+            Value::Text(s) => serde_json::Value::String(s),
+            Value::Blob(b) => serde_json::Value::String(b.iter().map(|byte| format!("{:02x}", byte)).collect()),
+        });
+    }
+    Ok(serde_json::Value::Array(fields).to_string())
+}
+
+fn rows_hash<'a>(rows: impl IntoIterator<Item = &'a String>) -> String {
+    let mut hasher = Sha256::new();
+    for row in rows {
+        hasher.update(row.as_bytes());
+        hasher.update(b"\n");
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+fn chain_hash(prev_hash: &str, row_count: u64, rows_hash: &str, sealed_at: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(format!("{}\n{}\n{}\n{}", prev_hash, row_count, rows_hash, sealed_at).as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+fn amendment_hash(
+    prev_hash: &str,
+    sealed_hash: &str,
+    row_count: u64,
+    rows_hash: &str,
+    reason: &str,
+    amended_at: &str,
+) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(
+        format!("{}\n{}\n{}\n{}\n{}\n{}", prev_hash, sealed_hash, row_count, rows_hash, reason, amended_at).as_bytes(),
+    );
+    format!("{:x}", hasher.finalize())
+}
+
+/// Seal every punch not in the ledger yet, up to [`MAX_BATCH_ROWS`] a batch
+pub fn seal(conn: &mut Connection) -> Result<SealResult, String> {
+    let mut result = SealResult {
+        batches: Vec::new(),
+        rows_sealed: 0,
+    };
+    loop {
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+        let prev_hash: String = tx
+            .query_row("SELECT hash FROM log_ledger ORDER BY id DESC LIMIT 1", [], |row| row.get(0))
+            .optional()
+            .map_err(|e| format!("Failed to load ledger head: {}", e))?
+            .unwrap_or_else(|| GENESIS.to_string());
+        let rows = {
+            let mut stmt = tx
+                .prepare(&format!(
+                    "SELECT {} FROM attendance_logs_raw l
+                     LEFT JOIN log_ledger_entries e ON e.log_id = l.id
+                     WHERE e.log_id IS NULL
+                     ORDER BY l.id LIMIT ?1",
+                    SEALED_COLUMNS
+                ))
+                .map_err(|e| format!("Failed to query unsealed logs: {}", e))?;
+            let rows = stmt
+                .query_map([MAX_BATCH_ROWS], |row| Ok((row.get::<_, String>(0)?, canonical(row, 0)?)))
+                .map_err(|e| format!("Failed to query unsealed logs: {}", e))?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("Failed to read unsealed logs: {}", e))?;
+            rows
+        };
+        if rows.is_empty() {
+            break;
+        }
+
+        let row_count = rows.len() as u64;
+        let rows_hash = rows_hash(rows.iter().map(|(_, row)| row));
+        let sealed_at = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+        let hash = chain_hash(&prev_hash, row_count, &rows_hash, &sealed_at);
+        tx.execute(
+            "INSERT INTO log_ledger (row_count, rows_hash, prev_hash, hash, sealed_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![row_count as i64, rows_hash, prev_hash, hash, sealed_at],
+        )
+        .map_err(|e| format!("Failed to record ledger batch: {}", e))?;
+        let batch_id = tx.last_insert_rowid();
+        {
+            let mut insert = tx
+                .prepare("INSERT INTO log_ledger_entries (log_id, batch_id) VALUES (?1, ?2)")
+                .map_err(|e| format!("Failed to record ledger entries: {}", e))?;
+            for (log_id, _) in &rows {
+                insert
+                    .execute(params![log_id, batch_id])
+                    .map_err(|e| format!("Failed to record ledger entries: {}", e))?;
+            }
+        }
+        tx.commit()
+            .map_err(|e| format!("Failed to commit ledger batch: {}", e))?;
+
+        result.rows_sealed += row_count;
+        result.batches.push(LedgerBatch {
+            id: batch_id,
+            row_count,
+            rows_hash,
+            prev_hash,
+            hash,
+            sealed_at,
+        });
+        if row_count < MAX_BATCH_ROWS as u64 {
+            break;
+        }
+    }
+    Ok(result)
+}
+
+/// Sealed batches, oldest first
+pub fn batches(conn: &Connection) -> Result<Vec<LedgerBatch>, String> {
+    let mut stmt = conn
+        .prepare("SELECT id, row_count, rows_hash, prev_hash, hash, sealed_at FROM log_ledger ORDER BY id")
+        .map_err(|e| format!("Failed to query ledger: {}", e))?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(LedgerBatch {
+                id: row.get(0)?,
+                row_count: row.get::<_, i64>(1)? as u64,
+                rows_hash: row.get(2)?,
+                prev_hash: row.get(3)?,
+                hash: row.get(4)?,
+                sealed_at: row.get(5)?,
+            })
+        })
+        .map_err(|e| format!("Failed to query ledger: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read ledger: {}", e))?;
+    Ok(rows)
+}
+
+/// Punches stored since the last seal
+pub fn unsealed(conn: &Connection) -> Result<u64, String> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM attendance_logs_raw l
+         LEFT JOIN log_ledger_entries e ON e.log_id = l.id
+         WHERE e.log_id IS NULL",
+        [],
+        |row| row.get::<_, i64>(0),
+    )
+    .map(|n| n as u64)
+    .map_err(|e| format!("Failed to count unsealed logs: {}", e))
+}
+
+/// A batch's entries in ID order, with each punch as it is now (None once removed)
+fn batch_rows(conn: &Connection, batch_id: i64) -> Result<Vec<(String, Option<String>)>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT e.log_id, l.id IS NOT NULL, {} FROM log_ledger_entries e
+             LEFT JOIN attendance_logs_raw l ON l.id = e.log_id
+             WHERE e.batch_id = ?1
+             ORDER BY e.log_id",
+            SEALED_COLUMNS
+        ))
+        .map_err(|e| format!("Failed to query ledger entries: {}", e))?;
+    let entries = stmt
+        .query_map([batch_id], |row| {
+            let log_id: String = row.get(0)?;
+            let punch = if row.get::<_, bool>(1)? { Some(canonical(row, 2)?) } else { None };
+            Ok((log_id, punch))
+        })
+        .map_err(|e| format!("Failed to query ledger entries: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read ledger entries: {}", e))?;
+    Ok(entries)
+}
+
+/// Count and hash of the punches of a batch that are still there
+fn present_rows(entries: &[(String, Option<String>)]) -> (u64, String) {
+    let present: Vec<&String> = entries.iter().filter_map(|(_, punch)| punch.as_ref()).collect();
+    (present.len() as u64, rows_hash(present))
+}
+
+/// Record that `reason` rewrote or removed the sealed punches `log_ids`.
+/// Each batch holding one of them gets an amendment over its punches as
+/// they are now, chained to the amendment before and bound to the batch's
+/// sealed hash. Batches keep their sealed hashes, so verification reports
+/// them as amended, not intact. Call in the transaction that made the
+/// changes. Returns the batches amended.
+pub fn amend(conn: &Connection, log_ids: &[String], reason: &str) -> Result<Vec<i64>, String> {
+    let mut batch_ids = BTreeSet::new();
+    {
+        let mut stmt = conn
+            .prepare("SELECT batch_id FROM log_ledger_entries WHERE log_id = ?1")
+            .map_err(|e| format!("Failed to query ledger entries: {}", e))?;
+        for log_id in log_ids {
+            if let Some(batch_id) = stmt
+                .query_row([log_id], |row| row.get::<_, i64>(0))
+                .optional()
+                .map_err(|e| format!("Failed to query ledger entries: {}", e))?
+            {
+                batch_ids.insert(batch_id);
+            }
+        }
+    }
+    if batch_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut prev_hash: String = conn
+        .query_row("SELECT hash FROM log_ledger_amendments ORDER BY id DESC LIMIT 1", [], |row| row.get(0))
+        .optional()
+        .map_err(|e| format!("Failed to load ledger amendments: {}", e))?
+        .unwrap_or_else(|| GENESIS.to_string());
+    let amended_at = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+    for batch_id in &batch_ids {
+        let sealed_hash: String = conn
+            .query_row("SELECT hash FROM log_ledger WHERE id = ?1", [batch_id], |row| row.get(0))
+            .map_err(|e| format!("Failed to load ledger batch: {}", e))?;
+        let (row_count, rows_hash) = present_rows(&batch_rows(conn, *batch_id)?);
+        let hash = amendment_hash(&prev_hash, &sealed_hash, row_count, &rows_hash, reason, &amended_at);
+        conn.execute(
+            "INSERT INTO log_ledger_amendments (batch_id, row_count, rows_hash, reason, prev_hash, hash, amended_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![batch_id, row_count as i64, rows_hash, reason, prev_hash, hash, amended_at],
+        )
+        .map_err(|e| format!("Failed to record ledger amendment: {}", e))?;
+        prev_hash = hash;
+    }
+    log::info!("[ledger] {} amended {} sealed batches", reason, batch_ids.len());
+    Ok(batch_ids.into_iter().collect())
+}
+
+/// Every amendment, oldest first
+pub fn amendments(conn: &Connection) -> Result<Vec<LedgerAmendment>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, batch_id, row_count, rows_hash, reason, prev_hash, hash, amended_at
+             FROM log_ledger_amendments ORDER BY id",
+        )
+        .map_err(|e| format!("Failed to query ledger amendments: {}", e))?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(LedgerAmendment {
+                id: row.get(0)?,
+                batch_id: row.get(1)?,
+                row_count: row.get::<_, i64>(2)? as u64,
+                rows_hash: row.get(3)?,
+                reason: row.get(4)?,
+                prev_hash: row.get(5)?,
+                hash: row.get(6)?,
+                amended_at: row.get(7)?,
+            })
+        })
+        .map_err(|e| format!("Failed to query ledger amendments: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read ledger amendments: {}", e))?;
+    Ok(rows)
+}
+
+/// IDs of the amendments whose link in the amendment chain holds
+fn chained(batches: &[LedgerBatch], amendments: &[LedgerAmendment]) -> HashSet<i64> {
+    let mut chained = HashSet::new();
+    let mut expected_prev = GENESIS;
+    for a in amendments {
+        let sealed = batches.iter().find(|b| b.id == a.batch_id).map(|b| b.hash.as_str());
+        if a.prev_hash == expected_prev
+            && sealed.is_some_and(|sealed| {
+                amendment_hash(&a.prev_hash, sealed, a.row_count, &a.rows_hash, &a.reason, &a.amended_at) == a.hash
+            })
+        {
+            chained.insert(a.id);
+        }
+        // As with batches, one bad link doesn't mark every later one
+        expected_prev = &a.hash;
+    }
+    chained
+}
+
+/// Check one batch against the punches as they are now
+fn check(conn: &Connection, batch: &LedgerBatch, expected_prev: &str) -> Result<Option<BrokenBatch>, String> {
+    let entries = batch_rows(conn, batch.id)?;
+
+    let missing: Vec<&String> = entries.iter().filter(|(_, f)| f.is_none()).map(|(id, _)| id).collect();
+    let found_rows = (entries.len() - missing.len()) as u64;
+    let reason = if batch.prev_hash != expected_prev
+        || chain_hash(&batch.prev_hash, batch.row_count, &batch.rows_hash, &batch.sealed_at) != batch.hash
+        || entries.len() as u64 != batch.row_count
+    {
+        "chain_broken"
+    } else if !missing.is_empty() {
+        "rows_missing"
+    } else if rows_hash(entries.iter().filter_map(|(_, punch)| punch.as_ref())) != batch.rows_hash {
+        "rows_altered"
+    } else {
+        return Ok(None);
+    };
+    Ok(Some(BrokenBatch {
+        batch_id: batch.id,
+        sealed_at: batch.sealed_at.clone(),
+        reason: reason.to_string(),
+        expected_rows: batch.row_count,
+        found_rows,
+        missing_log_ids: missing.into_iter().take(MAX_MISSING_IDS).cloned().collect(),
+    }))
+}
+
+/// Recompute every batch and the chain linking them. A batch that no longer
+/// matches its seal is amended if its latest amendment is chained and
+/// matches its punches as they are now, and broken otherwise; either way
+/// the ledger isn't intact.
+pub fn verify(conn: &Connection) -> Result<LedgerVerification, String> {
+    let batches = batches(conn)?;
+    let amendments = amendments(conn)?;
+    let chained = chained(&batches, &amendments);
+    let mut broken = Vec::new();
+    let mut amended = Vec::new();
+    let mut expected_prev = GENESIS.to_string();
+    for batch in &batches {
+        if let Some(b) = check(conn, batch, &expected_prev)? {
+            // Changed and removed punches can be accounted for; a broken chain can't
+            let latest = amendments
+                .iter()
+                .rev()
+                .find(|a| a.batch_id == batch.id)
+                .filter(|a| b.reason != "chain_broken" && chained.contains(&a.id));
+            match latest {
+                Some(a) if present_rows(&batch_rows(conn, batch.id)?) == (a.row_count, a.rows_hash.clone()) => {
+                    amended.push(a.clone())
+                }
+                _ => broken.push(b),
+            }
+        }
+        // Carry on from the stored hash so one broken batch doesn't mark every later one
+        expected_prev = batch.hash.clone();
+    }
+    Ok(LedgerVerification {
+        verified_at: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+        enabled: load_settings(conn)?.enabled,
+        intact: broken.is_empty() && amended.is_empty(),
+        batches: batches.len() as u64,
+        rows: batches.iter().map(|b| b.row_count).sum(),
+        head_hash: batches.last().map(|b| b.hash.clone()),
+        broken,
+        amended,
+        unsealed_rows: unsealed(conn)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two sealed batches of two punches each
+    fn sealed() -> Connection {
+        let mut conn = db::migrated();
+        conn.execute_batch(
+            "INSERT INTO devices (id, name, ip, port) VALUES ('d1', 'Front door', '10.0.0.2', 4370);
+             INSERT INTO attendance_logs_raw (id, device_id, device_user_id, timestamp) VALUES
+                 ('l1', 'd1', '7', '2024-01-08T08:00:00.000Z'),
+                 ('l2', 'd1', '7', '2024-01-08T17:00:00.000Z');",
+        )
+        .unwrap();
+        seal(&mut conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO attendance_logs_raw (id, device_id, device_user_id, timestamp) VALUES
+                 ('l3', 'd1', '7', '2024-01-09T08:00:00.000Z'),
+                 ('l4', 'd1', '7', '2024-01-09T17:00:00.000Z');",
+        )
+        .unwrap();
+        seal(&mut conn).unwrap();
+        assert!(verify(&conn).unwrap().intact);
+        conn
+    }
+
+    /// Change the database the way someone editing the file directly would,
+    /// past the punch triggers
+    fn tamper(conn: &mut Connection, sql: &str) {
+        let tx = conn.transaction().unwrap();
+        db::maintenance::bypass(&tx, "test", |tx| tx.execute_batch(sql).map_err(|e| e.to_string())).unwrap();
+        tx.commit().unwrap();
+    }
+
+    fn reasons(conn: &Connection) -> Vec<(i64, String)> {
+        verify(conn)
+            .unwrap()
+            .broken
+            .into_iter()
+            .map(|b| (b.batch_id, b.reason))
+            .collect()
+    }
+
+    /// What a migration does: rewrite a sealed punch and amend its batch
+    fn migrate(conn: &mut Connection, sql: &str, log_id: &str) {
+        let tx = conn.transaction().unwrap();
+        db::maintenance::bypass(&tx, "test", |tx| {
+            tx.execute_batch(sql).map_err(|e| e.to_string())?;
+            amend(tx, &[log_id.to_string()], "migration")
+        })
+        .unwrap();
+        tx.commit().unwrap();
+    }
+
+    #[test]
+    fn verify_catches_an_edited_sealed_column() {
+        let mut conn = sealed();
+        tamper(&mut conn, "UPDATE attendance_logs_raw SET timestamp = '2024-01-09T07:00:00.000Z' WHERE id = 'l3'");
+        assert_eq!(reasons(&conn), [(2, "rows_altered".to_string())]);
+    }
+
+    #[test]
+    fn verify_catches_a_deleted_row() {
+        let mut conn = sealed();
+        tamper(&mut conn, "DELETE FROM attendance_logs_raw WHERE id = 'l2'");
+        let verification = verify(&conn).unwrap();
+        assert!(!verification.intact);
+        let broken = &verification.broken[0];
+        assert_eq!((broken.batch_id, broken.reason.as_str()), (1, "rows_missing"));
+        assert_eq!((broken.expected_rows, broken.found_rows), (2, 1));
+        assert_eq!(broken.missing_log_ids, ["l2"]);
+    }
+
+    #[test]
+    fn verify_catches_reordered_batches() {
+        let mut conn = sealed();
+        // Each batch keeps its own punches, but the second now comes first
+        tamper(
+            &mut conn,
+            "CREATE TEMP TABLE sealed AS SELECT * FROM log_ledger;
+             UPDATE log_ledger SET (row_count, rows_hash, prev_hash, hash, sealed_at) =
+                 (SELECT row_count, rows_hash, prev_hash, hash, sealed_at FROM sealed WHERE sealed.id = 3 - log_ledger.id);
+             UPDATE log_ledger_entries SET batch_id = 3 - batch_id;",
+        );
+        assert_eq!(reasons(&conn), [(1, "chain_broken".to_string()), (2, "chain_broken".to_string())]);
+    }
+
+    #[test]
+    fn amended_batches_are_not_intact() {
+        let mut conn = sealed();
+        migrate(&mut conn, "UPDATE attendance_logs_raw SET device_user_id = '07' WHERE id = 'l3'", "l3");
+        let verification = verify(&conn).unwrap();
+        assert!(!verification.intact);
+        assert!(verification.broken.is_empty());
+        let amended: Vec<(i64, &str)> = verification.amended.iter().map(|a| (a.batch_id, a.reason.as_str())).collect();
+        assert_eq!(amended, [(2, "migration")]);
+    }
+
+    #[test]
+    fn verify_catches_an_edit_behind_an_inserted_amendment() {
+        let mut conn = sealed();
+        tamper(&mut conn, "UPDATE attendance_logs_raw SET timestamp = '2024-01-09T07:00:00.000Z' WHERE id = 'l3'");
+        // An amendment matching the edited punches, but not chained
+        let (row_count, rows_hash) = present_rows(&batch_rows(&conn, 2).unwrap());
+        conn.execute(
+            "INSERT INTO log_ledger_amendments (batch_id, row_count, rows_hash, reason, prev_hash, hash, amended_at)
+             VALUES (2, ?1, ?2, 'migration', ?3, ?3, '2024-02-01T00:00:00.000Z')",
+            params![row_count as i64, rows_hash, GENESIS],
+        )
+        .unwrap();
+        let verification = verify(&conn).unwrap();
+        assert!(!verification.intact);
+        assert!(verification.amended.is_empty());
+        assert_eq!(reasons(&conn), [(2, "rows_altered".to_string())]);
+    }
+
+    #[test]
+    fn verify_catches_an_edit_after_an_amendment() {
+        let mut conn = sealed();
+        migrate(&mut conn, "UPDATE attendance_logs_raw SET device_user_id = '07' WHERE id = 'l3'", "l3");
+        tamper(&mut conn, "DELETE FROM attendance_logs_raw WHERE id = 'l4'");
+        let verification = verify(&conn).unwrap();
+        assert!(verification.amended.is_empty());
+        assert_eq!(reasons(&conn), [(2, "rows_missing".to_string())]);
+    }
+}
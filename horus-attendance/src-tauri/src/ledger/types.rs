@@ -0,0 +1,89 @@
+//! Ledger data types for Tauri command serialization
+
+use serde::{Deserialize, Serialize};
+
+/// Ledger settings (`ledger` setting)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LedgerSettings {
+    /// Seal newly ingested punches into the hash chain
+    pub enabled: bool,
+}
+
+/// A sealed batch of punches
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LedgerBatch {
+    pub id: i64,
+    pub row_count: u64,
+    /// SHA-256 over the batch's punches, in ID order
+    pub rows_hash: String,
+    /// Hash of the batch before; zeros for the first
+    pub prev_hash: String,
+    /// SHA-256 over the previous hash, row count, rows hash and seal time
+    pub hash: String,
+    pub sealed_at: String,
+}
+
+/// Outcome of sealing the punches not yet in the ledger
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SealResult {
+    pub batches: Vec<LedgerBatch>,
+    pub rows_sealed: u64,
+}
+
+/// A batch that no longer matches what was sealed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BrokenBatch {
+    pub batch_id: i64,
+    pub sealed_at: String,
+    /// "rows_missing", "rows_altered" or "chain_broken"
+    pub reason: String,
+    pub expected_rows: u64,
+    pub found_rows: u64,
+    /// Up to 20 IDs of the punches that are gone
+    pub missing_log_ids: Vec<String>,
+}
+
+/// Sealed punches a data migration or restore rewrote or removed, hashed as
+/// they were afterwards
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LedgerAmendment {
+    pub id: i64,
+    pub batch_id: i64,
+    /// Punches of the batch left after the change
+    pub row_count: u64,
+    /// SHA-256 over those punches, in ID order
+    pub rows_hash: String,
+    /// What made the change
+    pub reason: String,
+    /// Hash of the amendment before; zeros for the first
+    pub prev_hash: String,
+    /// SHA-256 over the previous hash, the batch's sealed hash, row count,
+    /// rows hash, reason and amendment time
+    pub hash: String,
+    pub amended_at: String,
+}
+
+/// Result of checking the whole chain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LedgerVerification {
+    pub verified_at: String,
+    pub enabled: bool,
+    /// No batch is broken or amended
+    pub intact: bool,
+    pub batches: u64,
+    pub rows: u64,
+    /// Hash of the latest batch
+    pub head_hash: Option<String>,
+    pub broken: Vec<BrokenBatch>,
+    /// Batches that no longer match their seal but match their latest
+    /// amendment, as that amendment
+    pub amended: Vec<LedgerAmendment>,
+    /// Punches stored since the last seal
+    pub unsealed_rows: u64,
+}
@@ -5,6 +5,61 @@ use std::fs;
 use std::path::PathBuf;
 use base64::Engine;
 
+mod access_events;
+mod activity;
+mod analytics;
+mod api;
+mod backup;
+mod bells;
+mod breaks;
+mod closure;
+mod corrections;
+mod cost_centers;
+mod data_migrations;
+mod db;
+mod deliveries;
+mod demo;
+mod device_names;
+mod diagnostics;
+mod enrollments;
+mod exceptions;
+mod export;
+mod files;
+mod greeter;
+mod health;
+mod holidays;
+mod jobs;
+mod ledger;
+mod load_test;
+mod messages;
+mod path_policy;
+mod pay;
+mod payloads;
+mod periods;
+mod points;
+mod punch_archive;
+mod quarantine;
+mod realtime;
+mod recognition;
+mod reconcile;
+mod remote_work;
+mod report_cache;
+mod roster;
+mod sample;
+mod secrets;
+mod shifts;
+mod shutdown;
+mod signing;
+mod staffing;
+mod summary;
+mod sync_history;
+mod sync_scheduler;
+mod templates;
+mod tls;
+mod users;
+mod wal;
+mod watchdog;
+mod working_time;
 mod zkteco;
 
 fn get_migrations() -> Vec<Migration> {
@@ -118,9 +173,1318 @@ fn get_migrations() -> Vec<Migration> {
             "#,
             kind: MigrationKind::Up,
         },
+        Migration {
+            version: 3,
+            description: "create_templates",
+            sql: r#"
+                -- Editable text templates for emails and report headers/footers
+                CREATE TABLE IF NOT EXISTS templates (
+                    id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL UNIQUE,
+                    kind TEXT NOT NULL CHECK (kind IN ('email_subject', 'email_body', 'report_header', 'report_footer')),
+                    body TEXT NOT NULL,
+                    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+                );
+
+                INSERT OR IGNORE INTO templates (id, name, kind, body) VALUES
+                    ('default_report_header', 'Report header', 'report_header',
+                     '{{ company.name }} - Attendance Report{% if period.label %} ({{ period.label }}){% endif %}'),
+                    ('default_report_footer', 'Report footer', 'report_footer',
+                     'Generated {{ generated_at | format_date("%Y-%m-%d %H:%M") }} by Horus Attendance'),
+                    ('default_email_subject', 'Report email subject', 'email_subject',
+                     '{{ company.name }} attendance report{% if period.label %}: {{ period.label }}{% endif %}'),
+                    ('default_email_body', 'Report email body', 'email_body',
+                     'Hello,
+
+Attached is the attendance report for {{ period.label or "the selected period" }}.
+{% for key, value in totals | items %}
+- {{ key }}: {{ value }}{% endfor %}
+
+{{ company.name }}');
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 4,
+            description: "create_attendance_exceptions",
+            sql: r#"
+                -- Review queue for suspicious or corrupted attendance data
+                CREATE TABLE IF NOT EXISTS attendance_exceptions (
+                    id TEXT PRIMARY KEY,
+                    kind TEXT NOT NULL,
+                    severity TEXT NOT NULL DEFAULT 'warning' CHECK (severity IN ('info', 'warning', 'critical')),
+                    device_user_id TEXT,
+                    user_id TEXT REFERENCES users(id) ON DELETE SET NULL,
+                    date TEXT,
+                    message TEXT NOT NULL,
+                    details TEXT,
+                    dedupe_key TEXT NOT NULL UNIQUE,
+                    status TEXT NOT NULL DEFAULT 'open' CHECK (status IN ('open', 'resolved', 'dismissed')),
+                    resolution_note TEXT,
+                    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                    resolved_at TEXT
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_attendance_exceptions_status ON attendance_exceptions(status, created_at);
+                CREATE INDEX IF NOT EXISTS idx_attendance_exceptions_user ON attendance_exceptions(user_id);
+                CREATE INDEX IF NOT EXISTS idx_attendance_logs_created ON attendance_logs_raw(created_at);
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 5,
+            description: "create_api_punches",
+            sql: r#"
+                -- Where a punch came from: NULL for device sync, 'api' for the REST API
+                ALTER TABLE attendance_logs_raw ADD COLUMN source TEXT;
+                ALTER TABLE attendance_logs_raw ADD COLUMN latitude REAL;
+                ALTER TABLE attendance_logs_raw ADD COLUMN longitude REAL;
+
+                -- Pseudo-device that API punches are recorded under
+                INSERT OR IGNORE INTO devices (id, name, ip, port, sync_mode)
+                VALUES ('api', 'Mobile / API', '', 0, 'manual');
+
+                -- Per-user bearer tokens for the REST API (SHA-256 hashes only)
+                CREATE TABLE IF NOT EXISTS user_api_tokens (
+                    id TEXT PRIMARY KEY,
+                    user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+                    label TEXT NOT NULL DEFAULT '',
+                    token_hash TEXT NOT NULL UNIQUE,
+                    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                    last_used_at TEXT,
+                    revoked_at TEXT
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_user_api_tokens_user ON user_api_tokens(user_id);
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 6,
+            description: "create_pairing_codes",
+            sql: r#"
+                -- One-time codes shown as QR for pairing companion apps (hashes only)
+                CREATE TABLE IF NOT EXISTS pairing_codes (
+                    id TEXT PRIMARY KEY,
+                    user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+                    code_hash TEXT NOT NULL UNIQUE,
+                    expires_at TEXT NOT NULL,
+                    used_at TEXT,
+                    token_id TEXT REFERENCES user_api_tokens(id) ON DELETE SET NULL,
+                    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+                );
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 7,
+            description: "create_data_migrations",
+            sql: r#"
+                -- Progress of Rust-code data migrations (see data_migrations module)
+                CREATE TABLE IF NOT EXISTS data_migrations (
+                    name TEXT PRIMARY KEY,
+                    status TEXT NOT NULL CHECK (status IN ('running', 'completed', 'failed')),
+                    checkpoint TEXT,
+                    attempts INTEGER NOT NULL DEFAULT 0,
+                    last_error TEXT,
+                    started_at TEXT,
+                    completed_at TEXT
+                );
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 8,
+            description: "create_device_messages",
+            sql: r#"
+                -- Short messages shown on terminals at punch time; id is the message ID on the devices
+                CREATE TABLE IF NOT EXISTS device_messages (
+                    id INTEGER PRIMARY KEY CHECK (id BETWEEN 1 AND 65535),
+                    content TEXT NOT NULL,
+                    scope TEXT NOT NULL DEFAULT 'all' CHECK (scope IN ('all', 'users')),
+                    start_at TEXT NOT NULL,
+                    valid_minutes INTEGER NOT NULL DEFAULT 0,
+                    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+                );
+
+                -- Recipients of messages with scope 'users'
+                CREATE TABLE IF NOT EXISTS device_message_users (
+                    message_id INTEGER NOT NULL REFERENCES device_messages(id) ON DELETE CASCADE,
+                    user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+                    PRIMARY KEY (message_id, user_id)
+                );
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 9,
+            description: "add_device_dst_policy",
+            sql: r#"
+                -- How the device clock handles daylight saving time (see zkteco::clock)
+                ALTER TABLE devices ADD COLUMN dst_policy TEXT NOT NULL DEFAULT 'zone'
+                    CHECK (dst_policy IN ('zone', 'none', 'rules'));
+                -- Region whose rules the device applies itself when dst_policy is 'rules'
+                ALTER TABLE devices ADD COLUMN dst_rules TEXT;
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 10,
+            description: "create_user_shifts",
+            sql: r#"
+                -- Per-user work hours replacing the global ones in summaries
+                CREATE TABLE IF NOT EXISTS user_shifts (
+                    user_id TEXT PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+                    start_time TEXT NOT NULL,
+                    end_time TEXT NOT NULL,
+                    source TEXT NOT NULL DEFAULT 'manual' CHECK (source IN ('manual', 'detected')),
+                    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+                );
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 11,
+            description: "create_roster_entries",
+            sql: r#"
+                -- Planned shifts per user and date; they override user_shifts in summaries
+                CREATE TABLE IF NOT EXISTS roster_entries (
+                    id TEXT PRIMARY KEY,
+                    user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+                    date TEXT NOT NULL,
+                    start_time TEXT NOT NULL,
+                    end_time TEXT NOT NULL,
+                    note TEXT,
+                    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                    updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+                    UNIQUE (user_id, date)
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_roster_entries_date ON roster_entries(date);
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 12,
+            description: "create_summary_history",
+            sql: r#"
+                -- Why and by whom a summary was last written; copied into summary_history
+                ALTER TABLE attendance_day_summary ADD COLUMN change_reason TEXT;
+                ALTER TABLE attendance_day_summary ADD COLUMN changed_by TEXT;
+
+                -- Previous values of every summary that changed or was deleted
+                CREATE TABLE IF NOT EXISTS summary_history (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    summary_id TEXT NOT NULL,
+                    user_id TEXT NOT NULL,
+                    date TEXT NOT NULL,
+                    check_in_time TEXT,
+                    check_out_time TEXT,
+                    is_incomplete INTEGER NOT NULL,
+                    late_minutes INTEGER NOT NULL,
+                    early_minutes INTEGER NOT NULL,
+                    status TEXT NOT NULL,
+                    flags TEXT,
+                    new_check_in_time TEXT,
+                    new_check_out_time TEXT,
+                    new_status TEXT,
+                    new_late_minutes INTEGER,
+                    new_early_minutes INTEGER,
+                    reason TEXT NOT NULL,
+                    actor TEXT,
+                    changed_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_summary_history_user_date ON summary_history(user_id, date);
+                CREATE INDEX IF NOT EXISTS idx_summary_history_date ON summary_history(date);
+
+                CREATE TRIGGER IF NOT EXISTS trg_summary_history_update
+                AFTER UPDATE ON attendance_day_summary
+                WHEN OLD.check_in_time IS NOT NEW.check_in_time
+                  OR OLD.check_out_time IS NOT NEW.check_out_time
+                  OR OLD.is_incomplete IS NOT NEW.is_incomplete
+                  OR OLD.late_minutes IS NOT NEW.late_minutes
+                  OR OLD.early_minutes IS NOT NEW.early_minutes
+                  OR OLD.status IS NOT NEW.status
+                  OR OLD.flags IS NOT NEW.flags
+                BEGIN
+                    INSERT INTO summary_history (
+                        summary_id, user_id, date, check_in_time, check_out_time, is_incomplete,
+                        late_minutes, early_minutes, status, flags,
+                        new_check_in_time, new_check_out_time, new_status, new_late_minutes, new_early_minutes,
+                        reason, actor
+                    ) VALUES (
+                        OLD.id, OLD.user_id, OLD.date, OLD.check_in_time, OLD.check_out_time, OLD.is_incomplete,
+                        OLD.late_minutes, OLD.early_minutes, OLD.status, OLD.flags,
+                        NEW.check_in_time, NEW.check_out_time, NEW.status, NEW.late_minutes, NEW.early_minutes,
+                        COALESCE(NEW.change_reason, 'update'), NEW.changed_by
+                    );
+                END;
+
+                CREATE TRIGGER IF NOT EXISTS trg_summary_history_delete
+                AFTER DELETE ON attendance_day_summary
+                BEGIN
+                    INSERT INTO summary_history (
+                        summary_id, user_id, date, check_in_time, check_out_time, is_incomplete,
+                        late_minutes, early_minutes, status, flags, reason
+                    ) VALUES (
+                        OLD.id, OLD.user_id, OLD.date, OLD.check_in_time, OLD.check_out_time, OLD.is_incomplete,
+                        OLD.late_minutes, OLD.early_minutes, OLD.status, OLD.flags, 'delete'
+                    );
+                END;
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 13,
+            description: "create_device_imports",
+            sql: r#"
+                -- Progress of each device's resumable initial import
+                CREATE TABLE IF NOT EXISTS device_imports (
+                    device_id TEXT PRIMARY KEY REFERENCES devices(id) ON DELETE CASCADE,
+                    status TEXT NOT NULL DEFAULT 'running' CHECK (status IN ('running', 'interrupted', 'completed')),
+                    next_record INTEGER NOT NULL DEFAULT 0,
+                    total_records INTEGER NOT NULL DEFAULT 0,
+                    imported INTEGER NOT NULL DEFAULT 0,
+                    duplicates INTEGER NOT NULL DEFAULT 0,
+                    chunk_size INTEGER NOT NULL,
+                    first_timestamp TEXT,
+                    last_timestamp TEXT,
+                    last_error TEXT,
+                    started_at TEXT NOT NULL DEFAULT (datetime('now')),
+                    updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+                    completed_at TEXT
+                );
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 14,
+            description: "create_quarantined_logs",
+            sql: r#"
+                -- Device records that failed validation at ingest, kept for review.
+                -- Users have no timestamp; logs have no name. Blanks instead of NULLs
+                -- keep the same record from being quarantined again on every sync.
+                CREATE TABLE IF NOT EXISTS quarantined_logs (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    device_id TEXT NOT NULL REFERENCES devices(id) ON DELETE CASCADE,
+                    record_type TEXT NOT NULL CHECK (record_type IN ('log', 'user')),
+                    device_user_id TEXT NOT NULL DEFAULT '',
+                    timestamp TEXT NOT NULL DEFAULT '',
+                    user_name TEXT NOT NULL DEFAULT '',
+                    verify_type INTEGER,
+                    punch_type INTEGER,
+                    reason TEXT NOT NULL,
+                    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'repaired', 'discarded')),
+                    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                    resolved_at TEXT,
+                    UNIQUE(device_id, record_type, device_user_id, timestamp, user_name)
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_quarantined_logs_status ON quarantined_logs(status);
+
+                ALTER TABLE device_imports ADD COLUMN quarantined INTEGER NOT NULL DEFAULT 0;
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 15,
+            description: "create_raw_payloads",
+            sql: r#"
+                -- Device records exactly as received, for debugging decoding problems.
+                -- Only written while raw payload retention is on. Keyed like
+                -- attendance_logs_raw's unique key rather than by log ID because the
+                -- records are captured before the frontend stores the logs, whose
+                -- raw_payload column holds the matched user name.
+                CREATE TABLE IF NOT EXISTS raw_payloads (
+                    device_id TEXT NOT NULL REFERENCES devices(id) ON DELETE CASCADE,
+                    device_user_id TEXT NOT NULL,
+                    timestamp TEXT NOT NULL,
+                    encoding TEXT NOT NULL CHECK (encoding IN ('hex', 'zstd')),
+                    payload BLOB NOT NULL,
+                    original_size INTEGER NOT NULL,
+                    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                    PRIMARY KEY (device_id, device_user_id, timestamp)
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_raw_payloads_created ON raw_payloads(created_at);
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 16,
+            description: "create_device_pairs",
+            sql: r#"
+                -- Terminals covering the same door, whose punches should match.
+                -- Pairs are unordered; the app refuses a pair that exists the other way round.
+                CREATE TABLE IF NOT EXISTS device_pairs (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    primary_device_id TEXT NOT NULL REFERENCES devices(id) ON DELETE CASCADE,
+                    secondary_device_id TEXT NOT NULL REFERENCES devices(id) ON DELETE CASCADE,
+                    tolerance_seconds INTEGER NOT NULL DEFAULT 60,
+                    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                    CHECK (primary_device_id != secondary_device_id),
+                    UNIQUE(primary_device_id, secondary_device_id)
+                );
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 17,
+            description: "create_attendance_closures",
+            sql: r#"
+                -- Monthly closure emails: one row per employee and month, with the
+                -- last day they may dispute that month's attendance
+                CREATE TABLE IF NOT EXISTS attendance_closures (
+                    id TEXT PRIMARY KEY,
+                    year INTEGER NOT NULL,
+                    month INTEGER NOT NULL,
+                    user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+                    email TEXT,
+                    status TEXT NOT NULL CHECK (status IN ('sent', 'failed', 'no_email')),
+                    error TEXT,
+                    dispute_deadline TEXT NOT NULL,
+                    sent_at TEXT NOT NULL DEFAULT (datetime('now')),
+                    UNIQUE(year, month, user_id)
+                );
+
+                INSERT OR IGNORE INTO templates (id, name, kind, body) VALUES
+                    ('default_closure_subject', 'Closure email subject', 'email_subject',
+                     '{{ company.name }}: your attendance for {{ extra.month }}'),
+                    ('default_closure_body', 'Closure email body', 'email_body',
+                     'Hello {{ extra.employee }},
+
+Attached is your attendance for {{ extra.month }}.
+{% for key, value in totals | items %}
+- {{ key }}: {{ value }}{% endfor %}
+
+If anything is wrong, tell us by {{ extra.dispute_deadline | format_date("%d %B %Y") }} using this link:
+{{ extra.link }}
+
+{{ company.name }}');
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 18,
+            description: "add_api_token_roles",
+            sql: r#"
+                -- Manager tokens see the users, summaries and registers of the
+                -- departments assigned to them; employee tokens only their own
+                ALTER TABLE user_api_tokens ADD COLUMN role TEXT NOT NULL DEFAULT 'employee'
+                    CHECK (role IN ('employee', 'manager'));
+
+                CREATE TABLE IF NOT EXISTS user_api_token_departments (
+                    token_id TEXT NOT NULL REFERENCES user_api_tokens(id) ON DELETE CASCADE,
+                    department_id TEXT NOT NULL REFERENCES departments(id) ON DELETE CASCADE,
+                    PRIMARY KEY (token_id, department_id)
+                );
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 19,
+            description: "create_api_keys",
+            sql: r#"
+                -- Credentials for integrations, not tied to a user. Only a hash
+                -- of each key is stored; scopes is a JSON array of grants.
+                CREATE TABLE IF NOT EXISTS api_keys (
+                    id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    key_prefix TEXT NOT NULL,
+                    key_hash TEXT NOT NULL UNIQUE,
+                    scopes TEXT NOT NULL DEFAULT '[]',
+                    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                    last_used_at TEXT,
+                    expires_at TEXT,
+                    revoked_at TEXT,
+                    replaced_by TEXT REFERENCES api_keys(id) ON DELETE SET NULL
+                );
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 20,
+            description: "create_outbound_deliveries",
+            sql: r#"
+                -- Messages sent to other systems (email, webhooks, MQTT) with
+                -- what is needed to send them again
+                CREATE TABLE IF NOT EXISTS outbound_deliveries (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    channel TEXT NOT NULL CHECK (channel IN ('email', 'webhook', 'mqtt')),
+                    target TEXT NOT NULL,
+                    summary TEXT NOT NULL,
+                    payload TEXT NOT NULL DEFAULT '{}',
+                    status TEXT NOT NULL CHECK (status IN ('pending', 'delivered', 'failed')),
+                    attempts INTEGER NOT NULL DEFAULT 0,
+                    last_error TEXT,
+                    next_retry_at TEXT,
+                    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                    updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+                    delivered_at TEXT
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_outbound_deliveries_retry ON outbound_deliveries(status, next_retry_at);
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 21,
+            description: "index_logs_for_set_based_summaries",
+            sql: r#"
+                -- Covers the set-based summary engine's range scan, so punches
+                -- are read from the index alone
+                CREATE INDEX IF NOT EXISTS idx_attendance_logs_ts_user ON attendance_logs_raw(timestamp, device_user_id);
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 22,
+            description: "create_report_cache",
+            sql: r#"
+                -- Computed report payloads, valid while the data version of
+                -- their period is unchanged
+                CREATE TABLE IF NOT EXISTS report_cache (
+                    report TEXT NOT NULL,
+                    params TEXT NOT NULL,
+                    data_version INTEGER NOT NULL,
+                    payload TEXT NOT NULL,
+                    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                    last_hit_at TEXT,
+                    hits INTEGER NOT NULL DEFAULT 0,
+                    PRIMARY KEY (report, params)
+                );
+
+                -- Change counters per date of punches and summaries; '*' counts
+                -- changes affecting every date (rules, users)
+                CREATE TABLE IF NOT EXISTS report_data_versions (
+                    date TEXT PRIMARY KEY,
+                    version INTEGER NOT NULL DEFAULT 0
+                );
+
+                CREATE TRIGGER IF NOT EXISTS trg_report_version_log_insert
+                AFTER INSERT ON attendance_logs_raw
+                BEGIN
+                    INSERT INTO report_data_versions (date, version) VALUES (substr(NEW.timestamp, 1, 10), 1)
+                    ON CONFLICT(date) DO UPDATE SET version = version + 1;
+                END;
+
+                CREATE TRIGGER IF NOT EXISTS trg_report_version_log_update
+                AFTER UPDATE OF timestamp, device_user_id ON attendance_logs_raw
+                BEGIN
+                    INSERT INTO report_data_versions (date, version) VALUES (substr(OLD.timestamp, 1, 10), 1)
+                    ON CONFLICT(date) DO UPDATE SET version = version + 1;
+                    INSERT INTO report_data_versions (date, version) VALUES (substr(NEW.timestamp, 1, 10), 1)
+                    ON CONFLICT(date) DO UPDATE SET version = version + 1;
+                END;
+
+                CREATE TRIGGER IF NOT EXISTS trg_report_version_log_delete
+                AFTER DELETE ON attendance_logs_raw
+                BEGIN
+                    INSERT INTO report_data_versions (date, version) VALUES (substr(OLD.timestamp, 1, 10), 1)
+                    ON CONFLICT(date) DO UPDATE SET version = version + 1;
+                END;
+
+                CREATE TRIGGER IF NOT EXISTS trg_report_version_summary_insert
+                AFTER INSERT ON attendance_day_summary
+                BEGIN
+                    INSERT INTO report_data_versions (date, version) VALUES (NEW.date, 1)
+                    ON CONFLICT(date) DO UPDATE SET version = version + 1;
+                END;
+
+                CREATE TRIGGER IF NOT EXISTS trg_report_version_summary_update
+                AFTER UPDATE ON attendance_day_summary
+                WHEN OLD.check_in_time IS NOT NEW.check_in_time
+                  OR OLD.check_out_time IS NOT NEW.check_out_time
+                  OR OLD.late_minutes IS NOT NEW.late_minutes
+                  OR OLD.early_minutes IS NOT NEW.early_minutes
+                  OR OLD.status IS NOT NEW.status
+                  OR OLD.date IS NOT NEW.date
+                BEGIN
+                    INSERT INTO report_data_versions (date, version) VALUES (OLD.date, 1)
+                    ON CONFLICT(date) DO UPDATE SET version = version + 1;
+                    INSERT INTO report_data_versions (date, version) VALUES (NEW.date, 1)
+                    ON CONFLICT(date) DO UPDATE SET version = version + 1;
+                END;
+
+                CREATE TRIGGER IF NOT EXISTS trg_report_version_summary_delete
+                AFTER DELETE ON attendance_day_summary
+                BEGIN
+                    INSERT INTO report_data_versions (date, version) VALUES (OLD.date, 1)
+                    ON CONFLICT(date) DO UPDATE SET version = version + 1;
+                END;
+
+                CREATE TRIGGER IF NOT EXISTS trg_report_version_holiday_insert
+                AFTER INSERT ON holidays
+                BEGIN
+                    INSERT INTO report_data_versions (date, version) VALUES (NEW.date, 1)
+                    ON CONFLICT(date) DO UPDATE SET version = version + 1;
+                END;
+
+                CREATE TRIGGER IF NOT EXISTS trg_report_version_holiday_delete
+                AFTER DELETE ON holidays
+                BEGIN
+                    INSERT INTO report_data_versions (date, version) VALUES (OLD.date, 1)
+                    ON CONFLICT(date) DO UPDATE SET version = version + 1;
+                END;
+
+                CREATE TRIGGER IF NOT EXISTS trg_report_version_users_insert
+                AFTER INSERT ON users
+                BEGIN
+                    INSERT INTO report_data_versions (date, version) VALUES ('*', 1)
+                    ON CONFLICT(date) DO UPDATE SET version = version + 1;
+                END;
+
+                CREATE TRIGGER IF NOT EXISTS trg_report_version_users_update
+                AFTER UPDATE OF status, department_id, device_user_id, device_name, display_name ON users
+                BEGIN
+                    INSERT INTO report_data_versions (date, version) VALUES ('*', 1)
+                    ON CONFLICT(date) DO UPDATE SET version = version + 1;
+                END;
+
+                CREATE TRIGGER IF NOT EXISTS trg_report_version_users_delete
+                AFTER DELETE ON users
+                BEGIN
+                    INSERT INTO report_data_versions (date, version) VALUES ('*', 1)
+                    ON CONFLICT(date) DO UPDATE SET version = version + 1;
+                END;
+
+                CREATE TRIGGER IF NOT EXISTS trg_report_version_rules
+                AFTER UPDATE ON settings
+                WHEN NEW.key = 'attendance'
+                BEGIN
+                    INSERT INTO report_data_versions (date, version) VALUES ('*', 1)
+                    ON CONFLICT(date) DO UPDATE SET version = version + 1;
+                END;
+
+                CREATE TRIGGER IF NOT EXISTS trg_report_version_rules_insert
+                AFTER INSERT ON settings
+                WHEN NEW.key = 'attendance'
+                BEGIN
+                    INSERT INTO report_data_versions (date, version) VALUES ('*', 1)
+                    ON CONFLICT(date) DO UPDATE SET version = version + 1;
+                END;
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 23,
+            description: "create_user_audit_log",
+            sql: r#"
+                -- One row per bulk change to users, with what was asked and the users it applied to
+                CREATE TABLE IF NOT EXISTS user_audit_log (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    action TEXT NOT NULL,
+                    changes TEXT NOT NULL DEFAULT '{}',
+                    user_ids TEXT NOT NULL DEFAULT '[]',
+                    user_count INTEGER NOT NULL DEFAULT 0,
+                    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_user_audit_log_created ON user_audit_log(created_at);
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 24,
+            description: "create_user_device_links",
+            sql: r#"
+                -- Device user IDs from further terminals that belong to an existing profile.
+                -- The profile keeps its own device_user_id; these resolve to it as well.
+                CREATE TABLE IF NOT EXISTS user_device_links (
+                    device_user_id TEXT PRIMARY KEY,
+                    user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+                    device_name TEXT,
+                    method TEXT NOT NULL DEFAULT 'manual',
+                    confidence REAL,
+                    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_user_device_links_user ON user_device_links(user_id);
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 25,
+            description: "protect_punches",
+            sql: r#"
+                -- Payroll periods whose punches and summaries may no longer change
+                CREATE TABLE IF NOT EXISTS locked_periods (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    start_date TEXT NOT NULL,
+                    end_date TEXT NOT NULL,
+                    label TEXT NOT NULL DEFAULT '',
+                    locked_at TEXT NOT NULL DEFAULT (datetime('now')),
+                    CHECK (start_date <= end_date)
+                );
+
+                -- Timestamp bounds of each locked period's working days, which start at
+                -- the configured cutoff hour
+                CREATE VIEW IF NOT EXISTS locked_punch_bounds AS
+                SELECT p.id, p.start_date, p.end_date,
+                       p.start_date || printf('T%02d:00:00', c.hour) AS start_ts,
+                       date(p.end_date, '+1 day') || printf('T%02d:00:00', c.hour) AS end_ts
+                FROM locked_periods p,
+                     (SELECT MIN(MAX(COALESCE((SELECT CAST(json_extract(value, '$.dayCutoffHour') AS INTEGER)
+                                               FROM settings WHERE key = 'attendance'), 0), 0), 12) AS hour) c;
+
+                -- Changes to punches. Device punches are never edited: a void or move
+                -- points at the original, and added or moved punches are stored under
+                -- the 'correction' pseudo-device with source = 'correction'.
+                CREATE TABLE IF NOT EXISTS punch_corrections (
+                    id TEXT PRIMARY KEY,
+                    action TEXT NOT NULL CHECK (action IN ('add', 'move', 'void')),
+                    user_id TEXT REFERENCES users(id) ON DELETE SET NULL,
+                    device_user_id TEXT NOT NULL,
+                    original_log_id TEXT REFERENCES attendance_logs_raw(id) ON DELETE SET NULL,
+                    original_timestamp TEXT,
+                    corrected_log_id TEXT REFERENCES attendance_logs_raw(id) ON DELETE SET NULL,
+                    corrected_timestamp TEXT,
+                    reason TEXT NOT NULL,
+                    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+                );
+
+                CREATE UNIQUE INDEX IF NOT EXISTS idx_punch_corrections_original
+                    ON punch_corrections(original_log_id) WHERE original_log_id IS NOT NULL;
+                CREATE INDEX IF NOT EXISTS idx_punch_corrections_user ON punch_corrections(device_user_id);
+
+                INSERT OR IGNORE INTO devices (id, name, ip, port, sync_mode)
+                VALUES ('correction', 'Corrections', '', 0, 'manual');
+
+                CREATE TRIGGER IF NOT EXISTS trg_logs_device_immutable
+                BEFORE UPDATE ON attendance_logs_raw
+                WHEN OLD.source IS NULL
+                BEGIN
+                    SELECT RAISE(ABORT, 'Device punches cannot be edited; record a correction instead');
+                END;
+
+                CREATE TRIGGER IF NOT EXISTS trg_logs_locked_update
+                BEFORE UPDATE ON attendance_logs_raw
+                WHEN EXISTS (SELECT 1 FROM locked_punch_bounds
+                             WHERE (OLD.timestamp >= start_ts AND OLD.timestamp < end_ts)
+                                OR (NEW.timestamp >= start_ts AND NEW.timestamp < end_ts))
+                BEGIN
+                    SELECT RAISE(ABORT, 'Attendance period is locked');
+                END;
+
+                CREATE TRIGGER IF NOT EXISTS trg_logs_locked_delete
+                BEFORE DELETE ON attendance_logs_raw
+                WHEN EXISTS (SELECT 1 FROM locked_punch_bounds
+                             WHERE OLD.timestamp >= start_ts AND OLD.timestamp < end_ts)
+                BEGIN
+                    SELECT RAISE(ABORT, 'Attendance period is locked');
+                END;
+
+                -- Device punches synced late are still stored; only corrections are refused
+                CREATE TRIGGER IF NOT EXISTS trg_logs_locked_correction
+                BEFORE INSERT ON attendance_logs_raw
+                WHEN NEW.source = 'correction'
+                 AND EXISTS (SELECT 1 FROM locked_punch_bounds
+                             WHERE NEW.timestamp >= start_ts AND NEW.timestamp < end_ts)
+                BEGIN
+                    SELECT RAISE(ABORT, 'Attendance period is locked');
+                END;
+
+                CREATE TRIGGER IF NOT EXISTS trg_corrections_locked_insert
+                BEFORE INSERT ON punch_corrections
+                WHEN EXISTS (SELECT 1 FROM locked_punch_bounds
+                             WHERE (NEW.original_timestamp >= start_ts AND NEW.original_timestamp < end_ts)
+                                OR (NEW.corrected_timestamp >= start_ts AND NEW.corrected_timestamp < end_ts))
+                BEGIN
+                    SELECT RAISE(ABORT, 'Attendance period is locked');
+                END;
+
+                CREATE TRIGGER IF NOT EXISTS trg_corrections_locked_delete
+                BEFORE DELETE ON punch_corrections
+                WHEN EXISTS (SELECT 1 FROM locked_punch_bounds
+                             WHERE (OLD.original_timestamp >= start_ts AND OLD.original_timestamp < end_ts)
+                                OR (OLD.corrected_timestamp >= start_ts AND OLD.corrected_timestamp < end_ts))
+                BEGIN
+                    SELECT RAISE(ABORT, 'Attendance period is locked');
+                END;
+
+                -- Summaries of locked dates are left as they are by syncs and recomputes,
+                -- and can't be deleted (nor can users who have them)
+                CREATE TRIGGER IF NOT EXISTS trg_summary_locked_insert
+                BEFORE INSERT ON attendance_day_summary
+                WHEN EXISTS (SELECT 1 FROM locked_periods WHERE NEW.date BETWEEN start_date AND end_date)
+                BEGIN
+                    SELECT RAISE(IGNORE);
+                END;
+
+                CREATE TRIGGER IF NOT EXISTS trg_summary_locked_update
+                BEFORE UPDATE ON attendance_day_summary
+                WHEN EXISTS (SELECT 1 FROM locked_periods
+                             WHERE OLD.date BETWEEN start_date AND end_date
+                                OR NEW.date BETWEEN start_date AND end_date)
+                BEGIN
+                    SELECT RAISE(IGNORE);
+                END;
+
+                CREATE TRIGGER IF NOT EXISTS trg_summary_locked_delete
+                BEFORE DELETE ON attendance_day_summary
+                WHEN EXISTS (SELECT 1 FROM locked_periods WHERE OLD.date BETWEEN start_date AND end_date)
+                BEGIN
+                    SELECT RAISE(ABORT, 'Attendance period is locked');
+                END;
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 26,
+            description: "create_device_sync_windows",
+            sql: r#"
+                -- Times of day a device may be synced unattended (HH:MM, local).
+                -- A window ending before it starts runs past midnight; a device
+                -- with no windows may be synced at any time.
+                CREATE TABLE IF NOT EXISTS device_sync_windows (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    device_id TEXT NOT NULL REFERENCES devices(id) ON DELETE CASCADE,
+                    start_time TEXT NOT NULL,
+                    end_time TEXT NOT NULL
+                );
+                CREATE INDEX IF NOT EXISTS idx_device_sync_windows_device ON device_sync_windows(device_id);
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 27,
+            description: "create_device_staleness_alerts",
+            sql: r#"
+                -- Devices the watchdog has escalated for not syncing; cleared once
+                -- they sync again (last_sync_at is the value seen when alerted)
+                CREATE TABLE IF NOT EXISTS device_staleness_alerts (
+                    device_id TEXT PRIMARY KEY REFERENCES devices(id) ON DELETE CASCADE,
+                    last_sync_at TEXT,
+                    alert_count INTEGER NOT NULL DEFAULT 1,
+                    first_alerted_at TEXT NOT NULL DEFAULT (datetime('now')),
+                    last_alerted_at TEXT NOT NULL DEFAULT (datetime('now'))
+                );
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 28,
+            description: "create_export_layouts",
+            sql: r#"
+                -- Column layouts for fixed-width exports (columns as JSON)
+                CREATE TABLE IF NOT EXISTS export_layouts (
+                    id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL UNIQUE,
+                    scope TEXT NOT NULL CHECK (scope IN ('daily', 'totals')),
+                    columns TEXT NOT NULL,
+                    line_ending TEXT NOT NULL DEFAULT 'crlf' CHECK (line_ending IN ('crlf', 'lf')),
+                    truncate INTEGER NOT NULL DEFAULT 0,
+                    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+                );
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 29,
+            description: "create_report_signatures",
+            sql: r#"
+                -- Every report file signed, so a disputed copy can be checked
+                -- against what was issued
+                CREATE TABLE IF NOT EXISTS report_signatures (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    report TEXT NOT NULL,
+                    file_name TEXT NOT NULL,
+                    path TEXT NOT NULL,
+                    sha256 TEXT NOT NULL,
+                    signature TEXT NOT NULL,
+                    key_fingerprint TEXT NOT NULL,
+                    signed_at TEXT NOT NULL
+                );
+                CREATE INDEX IF NOT EXISTS idx_report_signatures_sha256 ON report_signatures(sha256);
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 30,
+            description: "create_cost_centers",
+            sql: r#"
+                -- Cost centers finance bills attendance against
+                CREATE TABLE IF NOT EXISTS cost_centers (
+                    id TEXT PRIMARY KEY,
+                    code TEXT NOT NULL UNIQUE,
+                    name TEXT NOT NULL,
+                    active INTEGER NOT NULL DEFAULT 1,
+                    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+                );
+
+                -- Which cost center a user or a whole department books to
+                -- from a date (to an inclusive end date, or open-ended). A
+                -- user's own assignment wins over their department's.
+                CREATE TABLE IF NOT EXISTS cost_center_assignments (
+                    id TEXT PRIMARY KEY,
+                    cost_center_id TEXT NOT NULL REFERENCES cost_centers(id) ON DELETE CASCADE,
+                    user_id TEXT REFERENCES users(id) ON DELETE CASCADE,
+                    department_id TEXT REFERENCES departments(id) ON DELETE CASCADE,
+                    effective_from TEXT NOT NULL,
+                    effective_to TEXT,
+                    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                    CHECK ((user_id IS NULL) <> (department_id IS NULL))
+                );
+                CREATE INDEX IF NOT EXISTS idx_cost_center_assignments_user
+                    ON cost_center_assignments(user_id, effective_from);
+                CREATE INDEX IF NOT EXISTS idx_cost_center_assignments_department
+                    ON cost_center_assignments(department_id, effective_from);
+
+                -- Reports grouped by cost center change with the assignments
+                CREATE TRIGGER IF NOT EXISTS trg_report_version_cost_center_insert
+                AFTER INSERT ON cost_center_assignments
+                BEGIN
+                    INSERT INTO report_data_versions (date, version) VALUES ('*', 1)
+                    ON CONFLICT(date) DO UPDATE SET version = version + 1;
+                END;
+
+                CREATE TRIGGER IF NOT EXISTS trg_report_version_cost_center_update
+                AFTER UPDATE ON cost_center_assignments
+                BEGIN
+                    INSERT INTO report_data_versions (date, version) VALUES ('*', 1)
+                    ON CONFLICT(date) DO UPDATE SET version = version + 1;
+                END;
+
+                CREATE TRIGGER IF NOT EXISTS trg_report_version_cost_center_delete
+                AFTER DELETE ON cost_center_assignments
+                BEGIN
+                    INSERT INTO report_data_versions (date, version) VALUES ('*', 1)
+                    ON CONFLICT(date) DO UPDATE SET version = version + 1;
+                END;
+
+                CREATE TRIGGER IF NOT EXISTS trg_report_version_cost_center_rename
+                AFTER UPDATE OF code, name ON cost_centers
+                BEGIN
+                    INSERT INTO report_data_versions (date, version) VALUES ('*', 1)
+                    ON CONFLICT(date) DO UPDATE SET version = version + 1;
+                END;
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 31,
+            description: "create_demo_devices",
+            sql: r#"
+                -- Simulated devices for demos and training. The devices row
+                -- is a normal one on a TEST-NET-1 address; this holds what
+                -- the simulator generates for it.
+                CREATE TABLE IF NOT EXISTS demo_devices (
+                    device_id TEXT PRIMARY KEY REFERENCES devices(id) ON DELETE CASCADE,
+                    profile TEXT NOT NULL,
+                    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+                );
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 32,
+            description: "add_punch_notes",
+            sql: r#"
+                -- Free-text note a manual or phone punch was made with (a
+                -- correction's reason, or what the employee typed). Sits
+                -- next to source ('api', 'correction', NULL for device
+                -- sync) and the phone's latitude/longitude.
+                ALTER TABLE attendance_logs_raw ADD COLUMN note TEXT;
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 33,
+            description: "scope_holidays",
+            sql: r#"
+                -- Site a user works at (free text, e.g. 'Factory'), for site holidays
+                ALTER TABLE users ADD COLUMN site TEXT;
+
+                -- Holidays may be limited to a department, a site or both; the same
+                -- date can then appear once per scope, so the table is rebuilt
+                -- without UNIQUE(date)
+                CREATE TABLE holidays_scoped (
+                    id TEXT PRIMARY KEY,
+                    date TEXT NOT NULL,
+                    name TEXT,
+                    department_id TEXT REFERENCES departments(id) ON DELETE CASCADE,
+                    site TEXT,
+                    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+                );
+                INSERT INTO holidays_scoped (id, date, name, created_at)
+                    SELECT id, date, name, created_at FROM holidays;
+                DROP TABLE holidays;
+                ALTER TABLE holidays_scoped RENAME TO holidays;
+
+                CREATE UNIQUE INDEX IF NOT EXISTS idx_holidays_scope
+                    ON holidays(date, COALESCE(department_id, ''), COALESCE(site, ''));
+
+                CREATE TRIGGER IF NOT EXISTS trg_report_version_holiday_insert
+                AFTER INSERT ON holidays
+                BEGIN
+                    INSERT INTO report_data_versions (date, version) VALUES (NEW.date, 1)
+                    ON CONFLICT(date) DO UPDATE SET version = version + 1;
+                END;
+
+                CREATE TRIGGER IF NOT EXISTS trg_report_version_holiday_delete
+                AFTER DELETE ON holidays
+                BEGIN
+                    INSERT INTO report_data_versions (date, version) VALUES (OLD.date, 1)
+                    ON CONFLICT(date) DO UPDATE SET version = version + 1;
+                END;
+
+                CREATE TRIGGER IF NOT EXISTS trg_report_version_holiday_update
+                AFTER UPDATE ON holidays
+                BEGIN
+                    INSERT INTO report_data_versions (date, version) VALUES (OLD.date, 1)
+                    ON CONFLICT(date) DO UPDATE SET version = version + 1;
+                    INSERT INTO report_data_versions (date, version) VALUES (NEW.date, 1)
+                    ON CONFLICT(date) DO UPDATE SET version = version + 1;
+                END;
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 34,
+            description: "create_holiday_rules",
+            sql: r#"
+                -- Recurring holidays, turned into holidays rows year by year.
+                -- 'fixed' rules fall on the same month and day every year;
+                -- 'lunar' rules (Eid, Lunar New Year) start on a date entered
+                -- per year in holiday_rule_dates.
+                CREATE TABLE IF NOT EXISTS holiday_rules (
+                    id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    kind TEXT NOT NULL CHECK (kind IN ('fixed', 'lunar')),
+                    month INTEGER,
+                    day INTEGER,
+                    -- Consecutive days off from the first
+                    duration_days INTEGER NOT NULL DEFAULT 1,
+                    -- What happens to a day that falls on a weekly rest day
+                    weekend_policy TEXT NOT NULL DEFAULT 'none'
+                        CHECK (weekend_policy IN ('none', 'next_workday', 'previous_workday')),
+                    department_id TEXT REFERENCES departments(id) ON DELETE CASCADE,
+                    site TEXT,
+                    active INTEGER NOT NULL DEFAULT 1,
+                    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+                );
+
+                CREATE TABLE IF NOT EXISTS holiday_rule_dates (
+                    rule_id TEXT NOT NULL REFERENCES holiday_rules(id) ON DELETE CASCADE,
+                    year INTEGER NOT NULL,
+                    start_date TEXT NOT NULL,
+                    PRIMARY KEY (rule_id, year)
+                );
+
+                -- Holidays generated from a rule, and for which year of it
+                ALTER TABLE holidays ADD COLUMN rule_id TEXT REFERENCES holiday_rules(id) ON DELETE CASCADE;
+                ALTER TABLE holidays ADD COLUMN rule_year INTEGER;
+                CREATE INDEX IF NOT EXISTS idx_holidays_rule ON holidays(rule_id, rule_year);
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 35,
+            description: "create_attendance_point_alerts",
+            sql: r#"
+                -- Discipline thresholds users have reached; a row goes once the
+                -- user's points drop below the threshold again, so crossing it
+                -- later alerts anew
+                CREATE TABLE IF NOT EXISTS attendance_point_alerts (
+                    user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+                    threshold REAL NOT NULL,
+                    label TEXT NOT NULL,
+                    points REAL NOT NULL,
+                    alerted_at TEXT NOT NULL DEFAULT (datetime('now')),
+                    PRIMARY KEY (user_id, threshold)
+                );
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 36,
+            description: "create_staffing_rules",
+            sql: r#"
+                -- Fewest people a department and/or site must have in during
+                -- operating hours; weekdays is a JSON array, 0 = Sunday
+                CREATE TABLE IF NOT EXISTS staffing_rules (
+                    id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    department_id TEXT REFERENCES departments(id) ON DELETE CASCADE,
+                    site TEXT,
+                    weekdays TEXT NOT NULL DEFAULT '[1,2,3,4,5]',
+                    start_time TEXT NOT NULL,
+                    end_time TEXT NOT NULL,
+                    min_headcount INTEGER NOT NULL CHECK (min_headcount >= 1),
+                    active INTEGER NOT NULL DEFAULT 1,
+                    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+                );
+
+                -- Rules currently short and alerted; a row goes once the rule
+                -- is staffed again, so the next shortfall alerts anew
+                CREATE TABLE IF NOT EXISTS staffing_alerts (
+                    rule_id TEXT PRIMARY KEY REFERENCES staffing_rules(id) ON DELETE CASCADE,
+                    headcount INTEGER NOT NULL,
+                    alerted_at TEXT NOT NULL DEFAULT (datetime('now'))
+                );
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 37,
+            description: "add_user_classification",
+            sql: r#"
+                -- Contractors are tracked for hours only and visitors are
+                -- never marked absent; employees get the full treatment
+                ALTER TABLE users ADD COLUMN classification TEXT NOT NULL DEFAULT 'employee'
+                    CHECK (classification IN ('employee', 'contractor', 'visitor'));
+
+                -- Last day a temporary enrollment is valid, and when it was
+                -- removed from every device after expiring
+                ALTER TABLE users ADD COLUMN enrollment_expires_at TEXT;
+                ALTER TABLE users ADD COLUMN enrollment_removed_at TEXT;
+
+                CREATE INDEX IF NOT EXISTS idx_users_enrollment_expires_at
+                    ON users(enrollment_expires_at) WHERE enrollment_expires_at IS NOT NULL;
+
+                -- Outcome of removing an expired enrollment from each device
+                CREATE TABLE IF NOT EXISTS enrollment_removals (
+                    user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+                    device_id TEXT NOT NULL REFERENCES devices(id) ON DELETE CASCADE,
+                    status TEXT NOT NULL CHECK (status IN ('removed', 'not_enrolled', 'failed')),
+                    error TEXT,
+                    attempted_at TEXT NOT NULL DEFAULT (datetime('now')),
+                    PRIMARY KEY (user_id, device_id)
+                );
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 38,
+            description: "create_remote_days",
+            sql: r#"
+                -- Days users worked remotely; their summaries get status
+                -- 'remote' instead of counting as absences
+                CREATE TABLE IF NOT EXISTS remote_days (
+                    user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+                    date TEXT NOT NULL,
+                    note TEXT,
+                    marked_by TEXT,
+                    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                    PRIMARY KEY (user_id, date)
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_remote_days_date ON remote_days(date);
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 39,
+            description: "add_device_hardware",
+            sql: r#"
+                -- What each device is, read from it on connection tests and syncs
+                ALTER TABLE devices ADD COLUMN model TEXT;
+                ALTER TABLE devices ADD COLUMN serial_number TEXT;
+                ALTER TABLE devices ADD COLUMN firmware TEXT;
+                ALTER TABLE devices ADD COLUMN mac_address TEXT;
+                ALTER TABLE devices ADD COLUMN hardware_seen_at TEXT;
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 40,
+            description: "create_device_attempts",
+            sql: r#"
+                -- Latest failed connection, info read or sync of each device
+                ALTER TABLE devices ADD COLUMN last_error TEXT;
+                ALTER TABLE devices ADD COLUMN last_error_at TEXT;
+
+                -- Every connection test, info read and sync against a saved
+                -- device, for diagnostics; the newest 500 per device are kept
+                CREATE TABLE IF NOT EXISTS device_attempts (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    device_id TEXT NOT NULL REFERENCES devices(id) ON DELETE CASCADE,
+                    operation TEXT NOT NULL CHECK (operation IN ('connection_test', 'device_info', 'sync')),
+                    success INTEGER NOT NULL,
+                    latency_ms INTEGER NOT NULL DEFAULT 0,
+                    error TEXT,
+                    attempted_at TEXT NOT NULL DEFAULT (datetime('now'))
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_device_attempts_device ON device_attempts(device_id, attempted_at);
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 41,
+            description: "create_sync_history",
+            sql: r#"
+                -- Structured result of every sync of a saved device (counts,
+                -- transport, phase durations, warnings, errors) as JSON; the
+                -- newest 200 per device are kept
+                CREATE TABLE IF NOT EXISTS sync_history (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    device_id TEXT NOT NULL REFERENCES devices(id) ON DELETE CASCADE,
+                    started_at TEXT NOT NULL,
+                    finished_at TEXT NOT NULL,
+                    success INTEGER NOT NULL,
+                    report TEXT NOT NULL,
+                    recorded_at TEXT NOT NULL DEFAULT (datetime('now'))
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_sync_history_device ON sync_history(device_id, id);
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 42,
+            description: "add_device_sync_qos",
+            sql: r#"
+                -- Site a device is at (free text), for per-site sync limits,
+                -- and its place in the sync order: higher syncs first
+                ALTER TABLE devices ADD COLUMN site TEXT;
+                ALTER TABLE devices ADD COLUMN sync_priority INTEGER NOT NULL DEFAULT 0;
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 43,
+            description: "add_device_capabilities",
+            sql: r#"
+                -- Feature flags probed from the device firmware (JSON), see
+                -- zkteco::capabilities; NULL until the device is first reached
+                ALTER TABLE devices ADD COLUMN capabilities TEXT;
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 44,
+            description: "create_log_ledger",
+            sql: r#"
+                -- Hash chain over sealed batches of raw punches, see ledger
+                CREATE TABLE IF NOT EXISTS log_ledger (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    row_count INTEGER NOT NULL,
+                    rows_hash TEXT NOT NULL,
+                    prev_hash TEXT NOT NULL,
+                    hash TEXT NOT NULL,
+                    sealed_at TEXT NOT NULL
+                );
+
+                -- Which batch each punch was sealed in; no foreign key so
+                -- removed punches still show up as missing
+                CREATE TABLE IF NOT EXISTS log_ledger_entries (
+                    log_id TEXT PRIMARY KEY,
+                    batch_id INTEGER NOT NULL REFERENCES log_ledger(id)
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_log_ledger_entries_batch ON log_ledger_entries(batch_id);
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 45,
+            description: "create_punch_archive_entries",
+            sql: r#"
+                -- Punches appended to the plain-text archive, see punch_archive
+                CREATE TABLE IF NOT EXISTS punch_archive_entries (
+                    log_id TEXT PRIMARY KEY,
+                    file TEXT NOT NULL,
+                    archived_at TEXT NOT NULL DEFAULT (datetime('now'))
+                );
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 46,
+            description: "create_pay_rates",
+            sql: r#"
+                -- Hourly rates for gross pay estimates, see pay
+                CREATE TABLE IF NOT EXISTS pay_rates (
+                    user_id TEXT PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+                    hourly_rate REAL NOT NULL,
+                    overtime_multiplier REAL,
+                    rest_day_multiplier REAL,
+                    currency TEXT,
+                    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+                );
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 47,
+            description: "create_access_events",
+            sql: r#"
+                -- Door controller events, imported for checking against punches, see access_events
+                CREATE TABLE IF NOT EXISTS access_events (
+                    id TEXT PRIMARY KEY,
+                    source TEXT NOT NULL,
+                    timestamp TEXT NOT NULL,
+                    device_user_id TEXT,
+                    card_number TEXT,
+                    door TEXT,
+                    event TEXT,
+                    direction TEXT,
+                    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+                );
+
+                CREATE UNIQUE INDEX IF NOT EXISTS idx_access_events_unique ON access_events(
+                    source, timestamp, COALESCE(device_user_id, ''), COALESCE(card_number, ''),
+                    COALESCE(door, ''), COALESCE(event, '')
+                );
+                CREATE INDEX IF NOT EXISTS idx_access_events_timestamp ON access_events(timestamp);
+                CREATE INDEX IF NOT EXISTS idx_access_events_user ON access_events(device_user_id, timestamp);
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 48,
+            description: "create_device_log_offsets",
+            sql: r#"
+                -- How far incremental syncs have read each device's attendance buffer, see zkteco::offsets
+                CREATE TABLE IF NOT EXISTS device_log_offsets (
+                    device_id TEXT PRIMARY KEY REFERENCES devices(id) ON DELETE CASCADE,
+                    record_count INTEGER NOT NULL,
+                    last_device_user_id TEXT,
+                    last_timestamp TEXT,
+                    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+                );
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 49,
+            description: "create_log_ledger_amendments",
+            sql: r#"
+                -- Sealed punches rewritten or removed by data migrations and restores, see
+                -- ledger. Amendments form their own hash chain, each bound to the sealed
+                -- hash of its batch; the batch itself keeps its sealed hashes.
+                CREATE TABLE IF NOT EXISTS log_ledger_amendments (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    batch_id INTEGER NOT NULL REFERENCES log_ledger(id),
+                    row_count INTEGER NOT NULL,
+                    rows_hash TEXT NOT NULL,
+                    reason TEXT NOT NULL,
+                    prev_hash TEXT NOT NULL,
+                    hash TEXT NOT NULL,
+                    amended_at TEXT NOT NULL
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_log_ledger_amendments_batch ON log_ledger_amendments(batch_id);
+            "#,
+            kind: MigrationKind::Up,
+        },
     ]
 }
 
+/// Highest migration version this build applies
+fn current_schema_version() -> i64 {
+    get_migrations().iter().map(|m| m.version).max().unwrap_or(0)
+}
+
 /// Backup metadata structure
 #[derive(Debug, Serialize, Deserialize)]
 pub struct BackupMetadata {
@@ -181,7 +1545,7 @@ async fn export_backup(app: tauri::AppHandle, destination: Option<String>) -> Re
     let backup_path = match destination {
         Some(dest) => {
             // The save dialog returns a full file path — use it directly
-            PathBuf::from(dest)
+            path_policy::resolve_write_target(&app, &dest)?
         },
         None => {
             let backup_dir = get_backup_dir(&app)?;
@@ -197,9 +1561,40 @@ async fn export_backup(app: tauri::AppHandle, destination: Option<String>) -> Re
             .map_err(|e| format!("Failed to create backup directory: {}", e))?;
     }
     
-    // Copy the database file
-    fs::copy(&db_path, &backup_path)
-        .map_err(|e| format!("Failed to copy database: {}", e))?;
+    // Let a running sync finish (and hold new ones off) so the copy isn't
+    // fighting a writer for the lock
+    let _paused = match activity::registry::quiesce(&app, "Backup").await {
+        Ok(guard) => guard,
+        Err(e) => {
+            return Ok(BackupResult {
+                success: false,
+                file_path: backup_path.to_string_lossy().to_string(),
+                file_size: 0,
+                error: Some(e),
+            })
+        }
+    };
+
+    // Copy through SQLite so punches still in the WAL are included, then verify
+    let (source, destination) = (db_path.clone(), backup_path.clone());
+    let copied = tauri::async_runtime::spawn_blocking(move || {
+        backup::snapshot::copy_database(&source, &destination)
+    })
+    .await
+    .map_err(|e| format!("Backup task failed: {}", e))?;
+    if let Err(e) = copied {
+        return Ok(BackupResult {
+            success: false,
+            file_path: backup_path.to_string_lossy().to_string(),
+            file_size: 0,
+            error: Some(e),
+        });
+    }
+    
+    // Record the app version so restores can check compatibility
+    if let Err(e) = backup::compat::stamp_metadata(&backup_path) {
+        log::warn!("[export_backup] Failed to stamp backup metadata: {}", e);
+    }
     
     let file_size = fs::metadata(&backup_path)
         .map(|m| m.len())
@@ -214,9 +1609,16 @@ async fn export_backup(app: tauri::AppHandle, destination: Option<String>) -> Re
 }
 
 /// Restore database from backup
-/// NOTE: After restore the app must be restarted for the SQL plugin to pick up the new file.
+/// A full restore (the default) swaps the database file. Selective modes merge
+/// only users/departments, settings/devices, or a date range of raw logs into
+/// the live database.
+/// NOTE: After a full restore the app must be restarted for the SQL plugin to pick up the new file.
 #[tauri::command]
-async fn restore_backup(app: tauri::AppHandle, backup_path: String) -> Result<RestoreResult, String> {
+async fn restore_backup(
+    app: tauri::AppHandle,
+    backup_path: String,
+    options: Option<backup::types::RestoreOptions>,
+) -> Result<RestoreResult, String> {
     let source_path = PathBuf::from(&backup_path);
     
     if !source_path.exists() {
@@ -226,20 +1628,98 @@ async fn restore_backup(app: tauri::AppHandle, backup_path: String) -> Result<Re
         });
     }
     
-    let db_path = get_db_path(&app)?;
+    // Refuse backups this version can't safely open (e.g. from a newer app)
+    let compatibility = backup::compat::check(&source_path, current_schema_version());
+    for warning in &compatibility.warnings {
+        log::warn!("[restore_backup] {}", warning);
+    }
+    if !compatibility.compatible {
+        return Ok(RestoreResult {
+            success: false,
+            error: compatibility.error,
+        });
+    }
     
-    // Create a backup of current database before restore
+    let _paused = match activity::registry::quiesce(&app, "Restore").await {
+        Ok(guard) => guard,
+        Err(e) => {
+            return Ok(RestoreResult {
+                success: false,
+                error: Some(e),
+            })
+        }
+    };
+
+    let options = options.unwrap_or_default();
+    if options.mode == backup::types::RestoreMode::Full {
+        let label = format!("Restore from {}", file_label(&source_path));
+        return replace_database(&app, &source_path, &label);
+    }
+
+    if let Some(date) = &options.start_date {
+        summary::commands::validate_date(date)?;
+    }
+    if let Some(date) = &options.end_date {
+        summary::commands::validate_date(date)?;
+    }
+    log::info!("[restore_backup] Selective restore ({:?}) from {}", options.mode, backup_path);
+
+    let label = format!("Selective restore from {}", file_label(&source_path));
+    snapshot_current_database(&app, "pre_restore", &label, backup::selective::affected_tables(options.mode))?;
+    let mut conn = db::open(&app)?;
+    let restored = tauri::async_runtime::spawn_blocking(move || {
+        backup::selective::restore(&mut conn, &source_path, &options)
+    })
+    .await
+    .map_err(|e| format!("Restore task failed: {}", e))?;
+
+    Ok(match restored {
+        Ok(_) => RestoreResult {
+            success: true,
+            error: None,
+        },
+        Err(e) => RestoreResult {
+            success: false,
+            error: Some(e),
+        },
+    })
+}
+
+/// File name of a path, for snapshot labels
+fn file_label(path: &std::path::Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string_lossy().to_string())
+}
+
+/// Snapshot the current database into the backup directory before a
+/// destructive operation so it can be undone
+fn snapshot_current_database(
+    app: &tauri::AppHandle,
+    prefix: &str,
+    label: &str,
+    tables: Option<Vec<String>>,
+) -> Result<(), String> {
+    let db_path = get_db_path(app)?;
     if db_path.exists() {
-        let backup_dir = get_backup_dir(&app)?;
-        let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
-        let pre_restore_backup = backup_dir.join(format!("pre_restore_{}.db", timestamp));
-        fs::copy(&db_path, &pre_restore_backup)
+        let backup_dir = get_backup_dir(app)?;
+        backup::undo::create(&db_path, &backup_dir, Some(prefix), label, tables)
             .map_err(|e| format!("Failed to backup current database: {}", e))?;
     }
+    Ok(())
+}
+
+/// Swap the database file for `source_path`, keeping a pre_restore copy of the current one
+fn replace_database(app: &tauri::AppHandle, source_path: &std::path::Path, label: &str) -> Result<RestoreResult, String> {
+    let db_path = get_db_path(app)?;
+    
+    // Create a backup of current database before restore
+    snapshot_current_database(app, "pre_restore", label, None)?;
     
     // Copy the backup file to the database location
-    fs::copy(&source_path, &db_path)
+    fs::copy(source_path, &db_path)
         .map_err(|e| format!("Failed to restore database: {}", e))?;
+    db::release(app);
     
     Ok(RestoreResult {
         success: true,
@@ -295,12 +1775,19 @@ async fn reset_database(app: tauri::AppHandle) -> Result<RestoreResult, String>
         });
     }
     
+    let _paused = match activity::registry::quiesce(&app, "Database reset").await {
+        Ok(guard) => guard,
+        Err(e) => {
+            return Ok(RestoreResult {
+                success: false,
+                error: Some(e),
+            })
+        }
+    };
+
     // Create a backup before reset
     let backup_dir = get_backup_dir(&app)?;
-    let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
-    let pre_reset_backup = backup_dir.join(format!("pre_reset_{}.db", timestamp));
-    
-    if let Err(e) = fs::copy(&db_path, &pre_reset_backup) {
+    if let Err(e) = backup::undo::create(&db_path, &backup_dir, Some("pre_reset"), "Reset database", None) {
         return Ok(RestoreResult {
             success: false,
             error: Some(format!("Failed to backup before reset: {}", e)),
@@ -308,6 +1795,7 @@ async fn reset_database(app: tauri::AppHandle) -> Result<RestoreResult, String>
     }
     
     // Delete the database file
+    db::release(&app);
     if let Err(e) = fs::remove_file(&db_path) {
         return Ok(RestoreResult {
             success: false,
@@ -321,67 +1809,21 @@ async fn reset_database(app: tauri::AppHandle) -> Result<RestoreResult, String>
     })
 }
 
-/// Write text content to a file path (sandboxed to app data + documents)
+/// Write text content to a file path (see path_policy for where writes may go)
 #[tauri::command]
 async fn write_text_file(app: tauri::AppHandle, path: String, content: String) -> Result<(), String> {
-    let target = PathBuf::from(&path);
-    let target = target.canonicalize().unwrap_or_else(|_| target.clone());
-
-    let app_data = app.path().app_data_dir()
-        .map_err(|e| format!("Cannot resolve app data dir: {}", e))?;
-    let documents = app.path().document_dir()
-        .map_err(|e| format!("Cannot resolve document dir: {}", e))?;
-    let downloads = app.path().download_dir().ok();
-
-    let allowed = target.starts_with(&app_data)
-        || target.starts_with(&documents)
-        || downloads.as_ref().map_or(false, |d| target.starts_with(d));
-
-    if !allowed {
-        return Err(format!(
-            "Write denied: path must be inside app data, documents, or downloads directory. Got: {}",
-            path
-        ));
-    }
-
-    if let Some(parent) = target.parent() {
-        fs::create_dir_all(parent)
-            .map_err(|e| format!("Failed to create parent directories: {}", e))?;
-    }
+    let target = path_policy::resolve_write_target(&app, &path)?;
 
     fs::write(&target, content)
         .map_err(|e| format!("Failed to write file: {}", e))
 }
 
-/// Write binary content (base64-encoded) to a file path (sandboxed to app data + documents)
+/// Write binary content (base64-encoded) to a file path (see path_policy for where writes may go)
 #[tauri::command]
 async fn write_binary_file(app: tauri::AppHandle, path: String, base64_data: String) -> Result<(), String> {
     use std::io::Write;
 
-    let target = PathBuf::from(&path);
-    let target = target.canonicalize().unwrap_or_else(|_| target.clone());
-
-    let app_data = app.path().app_data_dir()
-        .map_err(|e| format!("Cannot resolve app data dir: {}", e))?;
-    let documents = app.path().document_dir()
-        .map_err(|e| format!("Cannot resolve document dir: {}", e))?;
-    let downloads = app.path().download_dir().ok();
-
-    let allowed = target.starts_with(&app_data)
-        || target.starts_with(&documents)
-        || downloads.as_ref().map_or(false, |d| target.starts_with(d));
-
-    if !allowed {
-        return Err(format!(
-            "Write denied: path must be inside app data, documents, or downloads directory. Got: {}",
-            path
-        ));
-    }
-
-    if let Some(parent) = target.parent() {
-        fs::create_dir_all(parent)
-            .map_err(|e| format!("Failed to create parent directories: {}", e))?;
-    }
+    let target = path_policy::resolve_write_target(&app, &path)?;
 
     let bytes = base64::engine::general_purpose::STANDARD.decode(&base64_data)
         .map_err(|e| format!("Failed to decode base64: {}", e))?;
@@ -396,6 +1838,16 @@ async fn write_binary_file(app: tauri::AppHandle, path: String, base64_data: Str
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
+        .manage(db::pool::Pool::default())
+        .manage(activity::registry::ActivityRegistry::default())
+        .manage(jobs::registry::JobRegistry::default())
+        .manage(sync_scheduler::gate::SyncGate::default())
+        .manage(shutdown::coordinator::ShutdownState::default())
+        .manage(health::checks::HealthState::default())
+        .manage(files::stream::FileStreams::default())
+        .manage(api::server::ApiServer::default())
+        .manage(greeter::feed::RecentPunchFeed::default())
+        .manage(wal::monitor::WalMonitor::default())
         .plugin(tauri_plugin_dialog::init())
         .plugin(
             tauri_plugin_sql::Builder::default()
@@ -411,11 +1863,273 @@ pub fn run() {
             reset_database,
             write_text_file,
             write_binary_file,
+            files::commands::open_file_stream,
+            files::commands::append_chunk,
+            files::commands::close_file_stream,
+            files::commands::create_zip,
+            api::commands::start_api_server,
+            api::commands::check_api_server_port,
+            api::commands::stop_api_server,
+            api::commands::get_api_server_status,
+            api::commands::get_api_metrics,
+            tls::commands::get_tls_settings,
+            tls::commands::save_tls_settings,
+            tls::commands::get_tls_status,
+            tls::commands::regenerate_tls_certificate,
+            api::commands::create_user_token,
+            api::commands::create_manager_token,
+            api::commands::set_token_departments,
+            api::commands::list_user_tokens,
+            api::commands::create_api_key,
+            api::commands::list_api_keys,
+            api::commands::revoke_api_key,
+            api::commands::rotate_api_key,
+            api::commands::revoke_user_token,
+            api::commands::create_pairing_invite,
+            api::commands::create_self_service_links,
+            api::commands::rotate_report_signing_key,
+            data_migrations::commands::run_data_migrations,
+            data_migrations::commands::list_data_migrations,
+            health::commands::get_startup_health,
+            activity::commands::begin_activity,
+            activity::commands::end_activity,
+            activity::commands::get_activity_status,
+            jobs::commands::list_scheduled_jobs,
+            jobs::commands::run_job_now,
+            jobs::commands::pause_job,
+            jobs::commands::resume_job,
+            jobs::commands::get_sync_slots,
             zkteco::commands::test_device_connection,
             zkteco::commands::get_device_info,
+            zkteco::commands::probe_device_capabilities,
+            zkteco::commands::get_device_capabilities,
             zkteco::commands::get_device_users,
             zkteco::commands::get_attendance_logs,
             zkteco::commands::sync_device_all,
+            zkteco::commands::save_sync_offset,
+            zkteco::commands::reset_sync_offset,
+            zkteco::commands::run_connectivity_diagnostics,
+            zkteco::commands::scan_ip_range,
+            zkteco::commands::get_device_network,
+            zkteco::commands::set_device_network,
+            zkteco::commands::get_device_bells,
+            zkteco::commands::set_device_bells,
+            zkteco::commands::get_device_options,
+            zkteco::commands::set_device_options,
+            zkteco::commands::set_device_time,
+            zkteco::commands::initial_import,
+            zkteco::commands::get_initial_import_status,
+            zkteco::commands::compare_device_with_db,
+            zkteco::commands::get_sync_windows,
+            zkteco::commands::set_sync_windows,
+            zkteco::commands::check_sync_window,
+            bells::commands::get_bell_schedule,
+            bells::commands::save_bell_schedule,
+            bells::commands::push_bell_schedule,
+            messages::commands::list_device_messages,
+            messages::commands::create_device_message,
+            messages::commands::assign_device_message,
+            messages::commands::push_device_message,
+            messages::commands::delete_device_message,
+            quarantine::commands::list_quarantined,
+            quarantine::commands::repair_quarantined,
+            quarantine::commands::discard_quarantined,
+            payloads::commands::get_raw_payload_settings,
+            payloads::commands::save_raw_payload_settings,
+            payloads::commands::get_raw_payload_usage,
+            payloads::commands::get_raw_payload,
+            payloads::commands::purge_raw_payloads,
+            reconcile::commands::list_device_pairs,
+            reconcile::commands::save_device_pair,
+            reconcile::commands::delete_device_pair,
+            reconcile::commands::reconcile_device_pairs,
+            closure::commands::get_closure_settings,
+            closure::commands::save_closure_settings,
+            closure::commands::send_test_email,
+            closure::commands::close_attendance_month,
+            closure::commands::list_closures,
+            deliveries::commands::list_deliveries,
+            deliveries::commands::get_delivery_stats,
+            deliveries::commands::retry_deliveries,
+            corrections::commands::list_locked_periods,
+            corrections::commands::lock_period,
+            corrections::commands::lock_pay_period,
+            corrections::commands::unlock_period,
+            corrections::commands::correct_punch,
+            corrections::commands::list_punch_corrections,
+            corrections::commands::revert_punch_correction,
+            report_cache::commands::get_report_cache_stats,
+            report_cache::commands::clear_report_cache,
+            users::commands::bulk_update_users,
+            users::commands::bulk_delete_users,
+            users::commands::list_user_audit_log,
+            users::commands::propose_user_links,
+            users::commands::confirm_user_links,
+            users::commands::list_user_device_links,
+            users::commands::unlink_device_user,
+            users::commands::merge_device_users,
+            watchdog::commands::get_watchdog_settings,
+            watchdog::commands::save_watchdog_settings,
+            watchdog::commands::check_stale_devices,
+            wal::commands::get_wal_settings,
+            wal::commands::save_wal_settings,
+            wal::commands::get_wal_status,
+            wal::commands::checkpoint_wal,
+            export::commands::list_export_layouts,
+            export::commands::save_export_layout,
+            export::commands::delete_export_layout,
+            export::commands::preview_fixed_width,
+            export::commands::export_fixed_width,
+            signing::commands::get_signing_settings,
+            signing::commands::save_signing_settings,
+            signing::commands::get_signing_public_key,
+            signing::commands::sign_report_file,
+            signing::commands::verify_report_file,
+            signing::commands::list_report_signatures,
+            cost_centers::commands::list_cost_centers,
+            cost_centers::commands::save_cost_center,
+            cost_centers::commands::delete_cost_center,
+            cost_centers::commands::list_cost_center_assignments,
+            cost_centers::commands::assign_cost_center,
+            cost_centers::commands::delete_cost_center_assignment,
+            cost_centers::commands::get_effective_cost_center,
+            realtime::commands::append_realtime_events,
+            realtime::commands::drain_realtime_journal,
+            realtime::commands::get_realtime_journal_status,
+            demo::commands::list_demo_devices,
+            demo::commands::create_demo_device,
+            demo::commands::update_demo_device,
+            demo::commands::delete_demo_device,
+            sample::commands::generate_sample_data,
+            load_test::commands::run_load_test,
+            holidays::commands::list_holidays,
+            holidays::commands::save_holiday,
+            holidays::commands::delete_holiday,
+            holidays::commands::list_holiday_sites,
+            holidays::commands::list_holiday_rules,
+            holidays::commands::save_holiday_rule,
+            holidays::commands::delete_holiday_rule,
+            holidays::commands::set_holiday_rule_date,
+            holidays::commands::clear_holiday_rule_date,
+            holidays::commands::materialize_holiday_rules,
+            points::commands::get_points_settings,
+            points::commands::save_points_settings,
+            points::commands::get_attendance_points,
+            points::commands::get_user_points,
+            points::commands::check_points_thresholds,
+            recognition::commands::get_recognition_report,
+            recognition::commands::export_recognition_report,
+            staffing::commands::get_staffing_settings,
+            staffing::commands::save_staffing_settings,
+            staffing::commands::list_staffing_rules,
+            staffing::commands::save_staffing_rule,
+            staffing::commands::delete_staffing_rule,
+            staffing::commands::get_live_presence,
+            staffing::commands::check_staffing,
+            enrollments::commands::list_enrollment_expiries,
+            enrollments::commands::run_enrollment_expiry,
+            ledger::commands::get_ledger_settings,
+            ledger::commands::save_ledger_settings,
+            ledger::commands::seal_ledger,
+            ledger::commands::verify_ledger,
+            punch_archive::commands::get_punch_archive_settings,
+            punch_archive::commands::save_punch_archive_settings,
+            punch_archive::commands::run_punch_archive,
+            punch_archive::commands::list_punch_archive_files,
+            punch_archive::commands::get_punch_archive_backlog,
+            periods::commands::get_period_settings,
+            periods::commands::save_period_settings,
+            periods::commands::get_pay_period,
+            periods::commands::list_pay_periods,
+            pay::commands::get_pay_estimation_settings,
+            pay::commands::save_pay_estimation_settings,
+            pay::commands::list_pay_rates,
+            pay::commands::set_pay_rate,
+            pay::commands::delete_pay_rate,
+            pay::commands::estimate_pay,
+            breaks::commands::get_break_rules,
+            breaks::commands::save_break_rules,
+            breaks::commands::run_break_check,
+            working_time::commands::get_working_time_settings,
+            working_time::commands::save_working_time_settings,
+            working_time::commands::get_working_time_report,
+            greeter::commands::get_recent_punches,
+            greeter::commands::subscribe_recent_punches,
+            device_names::commands::push_names_to_all_devices,
+            access_events::commands::import_access_events_csv,
+            access_events::commands::ingest_access_events,
+            access_events::commands::list_access_events,
+            access_events::commands::get_access_reconciliation,
+            remote_work::commands::get_remote_work_settings,
+            remote_work::commands::save_remote_work_settings,
+            remote_work::commands::list_remote_days,
+            remote_work::commands::mark_remote_days,
+            remote_work::commands::unmark_remote_days,
+            diagnostics::commands::get_device_diagnostics,
+            sync_history::commands::record_sync,
+            sync_history::commands::list_sync_history,
+            sync_history::commands::export_sync_report,
+            sync_scheduler::commands::get_sync_scheduler_settings,
+            sync_scheduler::commands::save_sync_scheduler_settings,
+            sync_scheduler::commands::get_sync_queue,
+            roster::commands::list_roster,
+            roster::commands::save_roster_entries,
+            roster::commands::delete_roster_entry,
+            roster::commands::import_roster_csv,
+            roster::commands::get_roster_variance,
+            shifts::commands::detect_shifts,
+            shifts::commands::list_user_shifts,
+            shifts::commands::accept_shift_proposals,
+            shifts::commands::save_user_shifts,
+            shifts::commands::delete_user_shift,
+            summary::commands::recompute_summaries,
+            summary::commands::validate_custom_rules,
+            summary::commands::save_custom_rules,
+            summary::commands::get_summary_history,
+            summary::commands::get_summary_engine,
+            summary::commands::set_summary_engine,
+            summary::commands::benchmark_recompute,
+            export::commands::export_parquet,
+            export::commands::export_attendance_sheet,
+            export::commands::export_device_users,
+            export::commands::export_summary_history,
+            export::commands::convert_dates_to_hijri,
+            export::commands::get_hijri_month_range,
+            export::commands::get_summary_month_totals,
+            analytics::commands::get_punch_heatmap,
+            analytics::commands::get_kpis,
+            analytics::commands::compare_periods,
+            analytics::commands::get_cost_center_totals,
+            analytics::commands::get_buddy_punching_report,
+            exceptions::commands::list_exceptions,
+            exceptions::commands::resolve_exception,
+            exceptions::commands::run_anomaly_check,
+            secrets::commands::store_device_comm_key,
+            secrets::commands::delete_device_comm_key,
+            secrets::commands::set_credential,
+            secrets::commands::delete_credential,
+            secrets::commands::has_credential,
+            secrets::commands::migrate_secrets_to_keychain,
+            backup::commands::check_backup_compatibility,
+            backup::commands::list_backups_detailed,
+            backup::commands::diff_backup,
+            backup::commands::list_backup_targets,
+            backup::commands::save_backup_target,
+            backup::commands::delete_backup_target,
+            backup::commands::test_backup_target,
+            backup::commands::backup_to_target,
+            backup::commands::export_full_transfer_bundle,
+            backup::commands::import_full_transfer_bundle,
+            templates::commands::list_templates,
+            templates::commands::save_template,
+            templates::commands::delete_template,
+            templates::commands::render_template,
+            templates::commands::preview_template,
+            backup::commands::create_operation_snapshot,
+            backup::commands::list_operation_snapshots,
+            backup::commands::undo_last_operation,
+            backup::commands::export_sql_dump,
+            backup::commands::import_sql_dump,
         ])
         .setup(|app| {
             // Enable logging in both debug and release builds
@@ -428,8 +2142,57 @@ pub fn run() {
                     })
                     .build(),
             )?;
+
+            // Check (and repair) the database before the frontend opens it
+            if let Err(e) = app.state::<health::checks::HealthState>().get_or_run(app.handle(), false) {
+                log::error!("[health] Startup check failed: {}", e);
+            }
+            // Let demo devices answer before anything syncs
+            demo::commands::install_on_launch(app.handle());
+            // Warn early if a scheduled backup destination has gone missing
+            tauri::async_runtime::spawn(backup::commands::check_scheduled_targets(app.handle().clone()));
+            // Move plaintext comm keys from older versions into the keychain
+            tauri::async_runtime::spawn(secrets::commands::migrate_on_startup(app.handle().clone()));
+            // Serve the LAN API when enabled
+            tauri::async_runtime::spawn(api::commands::start_on_launch(app.handle().clone()));
+            // Flag impossible punch sequences from newly synced logs
+            tauri::async_runtime::spawn(exceptions::commands::run_background_checks(app.handle().clone()));
+            // Email last month's attendance to employees on the closure day
+            tauri::async_runtime::spawn(closure::job::run_scheduled(app.handle().clone()));
+            // Resend failed outbound deliveries as their retries come due
+            tauri::async_runtime::spawn(deliveries::commands::run_background_retries(app.handle().clone()));
+            // Escalate devices that have stopped syncing
+            tauri::async_runtime::spawn(watchdog::commands::run_scheduled(app.handle().clone()));
+            // Store journalled realtime events, starting with any a crash left behind
+            tauri::async_runtime::spawn(realtime::commands::run_background_drains(app.handle().clone()));
+            // Turn recurring holidays into this year's and next year's holidays
+            tauri::async_runtime::spawn(holidays::commands::run_scheduled(app.handle().clone()));
+            // Alert HR when someone reaches an attendance points threshold
+            tauri::async_runtime::spawn(points::commands::run_scheduled(app.handle().clone()));
+            // Alert when a department or site is short-staffed during operating hours
+            tauri::async_runtime::spawn(staffing::commands::run_scheduled(app.handle().clone()));
+            // Deactivate temporary users and remove them from devices once their enrollment expires
+            tauri::async_runtime::spawn(enrollments::commands::run_scheduled(app.handle().clone()));
+            // Seal newly ingested punches into the ledger when it is enabled
+            tauri::async_runtime::spawn(ledger::commands::run_scheduled(app.handle().clone()));
+            // Append newly ingested punches to the plain-text archive when it is enabled
+            tauri::async_runtime::spawn(punch_archive::commands::run_scheduled(app.handle().clone()));
+            // Flag shifts without the required breaks when break checks are enabled
+            tauri::async_runtime::spawn(breaks::commands::run_scheduled(app.handle().clone()));
+            // Alert breaches of the maximum hours and minimum rest when working-time checks are on
+            tauri::async_runtime::spawn(working_time::commands::run_scheduled(app.handle().clone()));
+            // Sync the devices set to automatic on the configured interval
+            tauri::async_runtime::spawn(sync_scheduler::auto::run_scheduled(app.handle().clone()));
+            // Keep the write-ahead log from growing unbounded on long-running installs
+            tauri::async_runtime::spawn(wal::commands::run_scheduled(app.handle().clone()));
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            // Let running work finish before exiting
+            if let tauri::RunEvent::ExitRequested { code, api, .. } = event {
+                shutdown::coordinator::on_exit_requested(app, &api, code);
+            }
+        });
 }
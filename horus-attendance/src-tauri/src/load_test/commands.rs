@@ -0,0 +1,40 @@
+//! Tauri command handler for the pipeline load test. Not used by the UI.
+
+use super::pipeline;
+use super::types::LoadTestReport;
+use crate::db;
+
+const DEFAULT_DAYS: u32 = 30;
+const MAX_THOUSANDS: u32 = 2000;
+const MAX_DAYS: u32 = 366;
+
+/// Push `thousands` × 1000 synthesized logs, spread over `days` days,
+/// through screening, ingest and summaries in a scratch database, and
+/// report each step's throughput and the peak memory
+#[tauri::command]
+pub async fn run_load_test(app: tauri::AppHandle, thousands: u32, days: Option<u32>) -> Result<LoadTestReport, String> {
+    if !(1..=MAX_THOUSANDS).contains(&thousands) {
+        return Err(format!("Thousands of logs must be between 1 and {}", MAX_THOUSANDS));
+    }
+    let days = days.unwrap_or(DEFAULT_DAYS);
+    if !(1..=MAX_DAYS).contains(&days) {
+        return Err(format!("Days must be between 1 and {}", MAX_DAYS));
+    }
+    log::info!("[load_test::cmd] run_load_test {}k logs over {} days", thousands, days);
+
+    let settings = pipeline::settings(&*db::open(&app)?)?;
+    let report = tauri::async_runtime::spawn_blocking(move || pipeline::run(&settings, thousands as u64 * 1000, days))
+        .await
+        .map_err(|e| format!("Load test task failed: {}", e))??;
+
+    for stage in &report.stages {
+        log::info!(
+            "[load_test] {}: {} in {} ms ({:.0}/s)",
+            stage.name,
+            stage.records,
+            stage.elapsed_ms,
+            stage.per_second
+        );
+    }
+    Ok(report)
+}
@@ -0,0 +1,37 @@
+//! Peak memory of the process
+
+/// Highest resident memory the process has used so far
+#[cfg(unix)]
+pub fn peak_bytes() -> Option<u64> {
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    // SAFETY: usage is a valid out pointer
+    if unsafe { libc::getrusage(libc::RUSAGE_SELF, &mut usage) } != 0 {
+        return None;
+    }
+    #[allow(clippy::unnecessary_cast)]
+    let max_rss = usage.ru_maxrss as u64;
+    // Bytes on macOS, kilobytes elsewhere
+    if cfg!(target_os = "macos") {
+        Some(max_rss)
+    } else {
+        Some(max_rss * 1024)
+    }
+}
+
+/// Highest working set the process has used so far
+#[cfg(windows)]
+pub fn peak_bytes() -> Option<u64> {
+    use windows_sys::Win32::System::ProcessStatus::{GetProcessMemoryInfo, PROCESS_MEMORY_COUNTERS};
+    use windows_sys::Win32::System::Threading::GetCurrentProcess;
+
+    let mut counters: PROCESS_MEMORY_COUNTERS = unsafe { std::mem::zeroed() };
+    let size = std::mem::size_of::<PROCESS_MEMORY_COUNTERS>() as u32;
+    // SAFETY: the pseudo handle needs no closing and counters is a valid out pointer of `size` bytes
+    let ok = unsafe { GetProcessMemoryInfo(GetCurrentProcess(), &mut counters, size) };
+    (ok != 0).then_some(counters.PeakWorkingSetSize as u64)
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn peak_bytes() -> Option<u64> {
+    None
+}
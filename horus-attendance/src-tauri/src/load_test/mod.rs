@@ -0,0 +1,17 @@
+//! Load test of the sync and summary pipeline
+//!
+//! A command with no place in the UI, run before releases to catch
+//! performance regressions. Where `summary::benchmark` times the summary
+//! engines on the user's own data, this measures the whole pipeline at a
+//! chosen scale. It builds a scratch database in the temp
+//! directory from the app's migrations (with this installation's settings,
+//! so summary rules match), synthesizes the requested number of raw logs,
+//! including resent duplicates and a few invalid records, and pushes them
+//! through the same steps a sync takes: screening, ingest with dedup, and
+//! summary computation. Each step's throughput and the process's peak
+//! memory are reported. The live database is only read, for its settings.
+
+pub mod commands;
+pub mod memory;
+pub mod pipeline;
+pub mod types;
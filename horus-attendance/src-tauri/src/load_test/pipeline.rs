@@ -0,0 +1,217 @@
+//! The load test run
+
+use chrono::{Duration, Local, NaiveTime};
+use rusqlite::{params, Connection};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use super::memory;
+use super::types::{LoadTestReport, LoadTestStage};
+use crate::db::logs::{self, DevicePunch};
+use crate::quarantine::store as quarantine;
+use crate::summary::engine;
+use crate::summary::types::ChangeSource;
+use crate::zkteco::demo::{between, chance};
+use crate::zkteco::types::AttendanceLog;
+
+const DEVICE_ID: &str = "load_test";
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.3fZ";
+
+/// Share of logs that are a device resending earlier records
+const DUPLICATE_RATE: f64 = 0.1;
+/// Share of logs the screening step should quarantine
+const INVALID_RATE: f64 = 0.001;
+
+/// A scratch database that is deleted when dropped
+struct Scratch {
+    path: PathBuf,
+    conn: Option<Connection>,
+}
+
+impl Scratch {
+    /// Create it from the app's migrations and copy `settings` into it
+    fn create(settings: &[(String, String)]) -> Result<Self, String> {
+        let path = std::env::temp_dir().join(format!("horus-load-test-{}.db", uuid::Uuid::new_v4()));
+        let conn = Connection::open(&path).map_err(|e| format!("Failed to create scratch database: {}", e))?;
+        let scratch = Self {
+            path,
+            conn: Some(conn),
+        };
+        let conn = scratch.conn();
+        conn.execute_batch("PRAGMA journal_mode = WAL; PRAGMA foreign_keys = ON;")
+            .map_err(|e| format!("Failed to configure scratch database: {}", e))?;
+        for migration in crate::get_migrations() {
+            conn.execute_batch(migration.sql)
+                .map_err(|e| format!("Failed to apply migration {}: {}", migration.version, e))?;
+        }
+        for (key, value) in settings {
+            crate::db::set_setting(conn, key, value)?;
+        }
+        conn.execute(
+            "INSERT INTO devices (id, name, ip) VALUES (?1, 'Load test', '192.0.2.0')",
+            [DEVICE_ID],
+        )
+        .map_err(|e| format!("Failed to save device: {}", e))?;
+        Ok(scratch)
+    }
+
+    fn conn(&self) -> &Connection {
+        self.conn.as_ref().expect("scratch connection is open until drop")
+    }
+
+    fn conn_mut(&mut self) -> &mut Connection {
+        self.conn.as_mut().expect("scratch connection is open until drop")
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        self.conn.take();
+        for suffix in ["", "-wal", "-shm"] {
+            let mut path = self.path.clone().into_os_string();
+            path.push(suffix);
+            let path = Path::new(&path);
+            if path.exists() {
+                if let Err(e) = std::fs::remove_file(path) {
+                    log::warn!("[load_test] Failed to remove {}: {}", path.display(), e);
+                }
+            }
+        }
+    }
+}
+
+/// Every setting of the live database
+pub fn settings(conn: &Connection) -> Result<Vec<(String, String)>, String> {
+    let mut stmt = conn
+        .prepare("SELECT key, value FROM settings")
+        .map_err(|e| format!("Failed to prepare query: {}", e))?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| format!("Failed to query settings: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read setting: {}", e))?;
+    Ok(rows)
+}
+
+fn stage(name: &str, records: u64, started: Instant) -> LoadTestStage {
+    let elapsed = started.elapsed();
+    let seconds = elapsed.as_secs_f64();
+    LoadTestStage {
+        name: name.to_string(),
+        records,
+        elapsed_ms: elapsed.as_millis() as u64,
+        per_second: if seconds > 0.0 { records as f64 / seconds } else { 0.0 },
+    }
+}
+
+/// Two punches a working day for enough users to make up `count` logs over
+/// `days` days, then duplicates and invalid records mixed in
+fn synthesize(count: u64, days: u32) -> (Vec<AttendanceLog>, u32, u64) {
+    let unique = (count as f64 * (1.0 - DUPLICATE_RATE)) as u64;
+    let users = unique.div_ceil(2 * days as u64).max(1) as u32;
+    let first_day = Local::now().date_naive() - Duration::days(days as i64);
+    let nine = NaiveTime::from_hms_opt(9, 0, 0).unwrap_or_default();
+
+    let mut logs = Vec::with_capacity(count as usize);
+    'days: for day in 0..days as i64 {
+        let date = first_day + Duration::days(day);
+        for user in 1..=users {
+            for (salt, offset) in [(1, 0), (2, 9 * 3600)] {
+                if logs.len() as u64 >= unique {
+                    break 'days;
+                }
+                let time = date.and_time(nine) + Duration::seconds(offset + between(0, user, day, salt, -1800, 1800));
+                let invalid = chance(0, user, day, salt + 10) < INVALID_RATE;
+                logs.push(AttendanceLog {
+                    device_user_id: if invalid { String::new() } else { user.to_string() },
+                    timestamp: time.format(TIMESTAMP_FORMAT).to_string(),
+                    verify_type: 1,
+                    punch_type: if offset == 0 { 0 } else { 1 },
+                    raw: Vec::new(),
+                });
+            }
+        }
+    }
+
+    // A device resending part of its buffer
+    let duplicates = count.saturating_sub(logs.len() as u64);
+    let resent: Vec<AttendanceLog> = logs.iter().take(duplicates as usize).cloned().collect();
+    logs.extend(resent);
+    (logs, users, duplicates)
+}
+
+/// Run the pipeline over `count` synthesized logs spread over `days` days
+pub fn run(settings: &[(String, String)], count: u64, days: u32) -> Result<LoadTestReport, String> {
+    let peak_before = memory::peak_bytes();
+    let started = Instant::now();
+    let mut scratch = Scratch::create(settings)?;
+    let mut stages = Vec::new();
+
+    let step = Instant::now();
+    let (mut logs, users, duplicates) = synthesize(count, days);
+    {
+        let tx = scratch
+            .conn_mut()
+            .transaction()
+            .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+        for user in 1..=users {
+            tx.execute(
+                "INSERT INTO users (id, device_user_id, display_name) VALUES (?1, ?2, ?3)",
+                params![uuid::Uuid::new_v4().to_string(), user.to_string(), format!("User {}", user)],
+            )
+            .map_err(|e| format!("Failed to save user: {}", e))?;
+        }
+        tx.commit().map_err(|e| format!("Failed to commit users: {}", e))?;
+    }
+    stages.push(stage("synthesize", logs.len() as u64, step));
+    let total = logs.len() as u64;
+
+    let step = Instant::now();
+    let quarantined = quarantine::screen_logs(scratch.conn(), Some(DEVICE_ID), &mut logs, true)?;
+    stages.push(stage("screen", total, step));
+
+    let step = Instant::now();
+    let punches: Vec<DevicePunch> = logs
+        .iter()
+        .map(|log| DevicePunch {
+            device_user_id: &log.device_user_id,
+            timestamp: &log.timestamp,
+            verify_type: log.verify_type,
+            punch_type: log.punch_type,
+        })
+        .collect();
+    let inserted = logs::insert_from_device(scratch.conn_mut(), DEVICE_ID, &punches)?;
+    stages.push(stage("ingest", punches.len() as u64, step));
+    drop(punches);
+    drop(logs);
+
+    let step = Instant::now();
+    let end = Local::now().date_naive();
+    let start = end - Duration::days(days as i64);
+    let source = ChangeSource {
+        reason: "load_test",
+        actor: None,
+    };
+    let computed = engine::recompute(
+        scratch.conn_mut(),
+        &start.format("%Y-%m-%d").to_string(),
+        &end.format("%Y-%m-%d").to_string(),
+        &source,
+    )?;
+    stages.push(stage("summarize", computed.days_processed as u64, step));
+
+    drop(scratch);
+    let peak_after = memory::peak_bytes();
+    Ok(LoadTestReport {
+        logs: total,
+        users,
+        days,
+        duplicates,
+        quarantined: quarantined as u64,
+        inserted: inserted as u64,
+        stages,
+        total_ms: started.elapsed().as_millis() as u64,
+        peak_memory_bytes: peak_after,
+        peak_memory_growth_bytes: peak_before.zip(peak_after).map(|(before, after)| after.saturating_sub(before)),
+    })
+}
@@ -0,0 +1,35 @@
+//! Load test data types for Tauri command serialization
+
+use serde::{Deserialize, Serialize};
+
+/// One step of the pipeline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadTestStage {
+    /// "synthesize", "screen", "ingest" or "summarize"
+    pub name: String,
+    /// Records (days, for summarize) the step handled
+    pub records: u64,
+    pub elapsed_ms: u64,
+    pub per_second: f64,
+}
+
+/// Result of a load test run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadTestReport {
+    /// Raw logs synthesized, duplicates and invalid ones included
+    pub logs: u64,
+    pub users: u32,
+    pub days: u32,
+    pub duplicates: u64,
+    pub quarantined: u64,
+    pub inserted: u64,
+    pub stages: Vec<LoadTestStage>,
+    pub total_ms: u64,
+    /// Peak resident memory of the whole process after the run, where the
+    /// platform reports it
+    pub peak_memory_bytes: Option<u64>,
+    /// How much the run raised that peak
+    pub peak_memory_growth_bytes: Option<u64>,
+}
@@ -0,0 +1,156 @@
+//! Tauri command handlers for device messages.
+
+use std::future::Future;
+
+use super::push::{self, START_FORMAT};
+use super::store;
+use super::types::*;
+use crate::db;
+use crate::zkteco::commands::{resolve_comm_key, validate_config};
+use crate::zkteco::sms::MAX_CONTENT_LEN;
+use crate::zkteco::types::DeviceConfig;
+
+/// Run `action` against each device in turn, collecting the outcomes
+async fn each_device<F, Fut>(devices: Vec<DeviceConfig>, action: F) -> Vec<DevicePushResult>
+where
+    F: Fn(DeviceConfig) -> Fut,
+    Fut: Future<Output = DevicePushResult>,
+{
+    let mut results = Vec::with_capacity(devices.len());
+    for config in devices {
+        let prepared = match validate_config(&config) {
+            Ok(()) => resolve_comm_key(config.clone()).await,
+            Err(e) => Err(e),
+        };
+        results.push(match prepared {
+            Ok(config) => action(config).await,
+            Err(e) => DevicePushResult {
+                device_id: config.device_id,
+                ip: config.ip,
+                success: false,
+                error: Some(e),
+                skipped_users: Vec::new(),
+            },
+        });
+    }
+    results
+}
+
+fn validate_users(conn: &rusqlite::Connection, user_ids: &[String]) -> Result<(), String> {
+    for user_id in user_ids {
+        if db::users::get(conn, user_id)?.is_none() {
+            return Err(format!("User not found: {}", user_id));
+        }
+    }
+    Ok(())
+}
+
+/// Every stored message, newest first
+#[tauri::command]
+pub async fn list_device_messages(app: tauri::AppHandle) -> Result<Vec<DeviceMessage>, String> {
+    let conn = db::open(&app)?;
+    store::list(&conn)
+}
+
+/// Save a message and push it to `devices`
+#[tauri::command]
+pub async fn create_device_message(
+    app: tauri::AppHandle,
+    message: NewDeviceMessage,
+    devices: Vec<DeviceConfig>,
+) -> Result<DeviceMessageResult, String> {
+    let content = message.content.trim();
+    if content.is_empty() {
+        return Err("Message is empty".to_string());
+    }
+    if content.len() > MAX_CONTENT_LEN {
+        return Err(format!("Message is too long for the terminals (at most {} characters)", MAX_CONTENT_LEN));
+    }
+    let start_at = match message.start_at.as_deref().filter(|s| !s.is_empty()) {
+        Some(start) => chrono::NaiveDateTime::parse_from_str(start, START_FORMAT)
+            .map_err(|_| format!("Invalid start time (expected YYYY-MM-DDTHH:MM:SS): {}", start))?
+            .format(START_FORMAT)
+            .to_string(),
+        None => chrono::Local::now().format(START_FORMAT).to_string(),
+    };
+    let user_ids = message.user_ids.unwrap_or_default();
+
+    let (saved, recipients) = {
+        let mut conn = db::open(&app)?;
+        validate_users(&conn, &user_ids)?;
+        let saved = store::insert(&mut conn, content, &start_at, message.valid_minutes.unwrap_or(0), &user_ids)?;
+        let recipients = store::recipients(&conn, &saved)?;
+        (saved, recipients)
+    };
+    log::info!("[messages] Created message {} for {} device(s)", saved.id, devices.len());
+
+    let (message_ref, recipients) = (&saved, &recipients);
+    let results = each_device(devices, |config| async move { push::push(&config, message_ref, recipients).await }).await;
+    Ok(DeviceMessageResult {
+        message: Some(saved),
+        devices: results,
+    })
+}
+
+/// Change who sees a message (everyone when `user_ids` is empty) and push the
+/// change to `devices`
+#[tauri::command]
+pub async fn assign_device_message(
+    app: tauri::AppHandle,
+    message_id: i64,
+    user_ids: Vec<String>,
+    devices: Vec<DeviceConfig>,
+) -> Result<DeviceMessageResult, String> {
+    let (saved, recipients) = {
+        let mut conn = db::open(&app)?;
+        validate_users(&conn, &user_ids)?;
+        let saved = store::set_users(&mut conn, message_id, &user_ids)?;
+        let recipients = store::recipients(&conn, &saved)?;
+        (saved, recipients)
+    };
+
+    let (message_ref, recipients) = (&saved, &recipients);
+    let results = each_device(devices, |config| async move { push::push(&config, message_ref, recipients).await }).await;
+    Ok(DeviceMessageResult {
+        message: Some(saved),
+        devices: results,
+    })
+}
+
+/// Push a stored message to `devices` again, e.g. to a newly added terminal
+#[tauri::command]
+pub async fn push_device_message(
+    app: tauri::AppHandle,
+    message_id: i64,
+    devices: Vec<DeviceConfig>,
+) -> Result<DeviceMessageResult, String> {
+    let (saved, recipients) = {
+        let conn = db::open(&app)?;
+        let saved = store::get(&conn, message_id)?.ok_or_else(|| format!("Message not found: {}", message_id))?;
+        let recipients = store::recipients(&conn, &saved)?;
+        (saved, recipients)
+    };
+
+    let (message_ref, recipients) = (&saved, &recipients);
+    let results = each_device(devices, |config| async move { push::push(&config, message_ref, recipients).await }).await;
+    Ok(DeviceMessageResult {
+        message: Some(saved),
+        devices: results,
+    })
+}
+
+/// Delete a message from `devices` and the app
+#[tauri::command]
+pub async fn delete_device_message(
+    app: tauri::AppHandle,
+    message_id: i64,
+    devices: Vec<DeviceConfig>,
+) -> Result<DeviceMessageResult, String> {
+    let results = each_device(devices, |config| async move { push::remove(&config, message_id).await }).await;
+    store::delete(&*db::open(&app)?, message_id)?;
+    log::info!("[messages] Deleted message {}", message_id);
+    Ok(DeviceMessageResult {
+        message: None,
+        devices: results,
+    })
+}
@@ -0,0 +1,11 @@
+//! Messages shown on terminals
+//!
+//! HR writes short notices ("Submit timesheets by Friday") that terminals show
+//! at punch time, either to everyone or to chosen users. Messages are kept in
+//! `device_messages` so they can be listed and re-pushed; the row ID is the
+//! message ID on the devices.
+
+pub mod commands;
+pub mod push;
+pub mod store;
+pub mod types;
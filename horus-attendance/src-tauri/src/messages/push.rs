@@ -0,0 +1,81 @@
+//! Sending messages to devices
+
+use super::store::Recipient;
+use super::types::{DeviceMessage, DevicePushResult};
+use crate::zkteco::client::ZKClient;
+use crate::zkteco::sms::{self, Sms, TAG_PERSONAL, TAG_PUBLIC};
+use crate::zkteco::types::DeviceConfig;
+
+/// Format of `start_at`, in device-local time
+pub const START_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+fn result(config: &DeviceConfig, outcome: Result<Vec<String>, String>) -> DevicePushResult {
+    if let Err(e) = &outcome {
+        log::warn!("[messages] {}: {}", config.ip, e);
+    }
+    DevicePushResult {
+        device_id: config.device_id.clone(),
+        ip: config.ip.clone(),
+        success: outcome.is_ok(),
+        skipped_users: outcome.as_ref().cloned().unwrap_or_default(),
+        error: outcome.err(),
+    }
+}
+
+/// Write `message` to the device, replacing any earlier version and its
+/// recipients
+pub async fn push(config: &DeviceConfig, message: &DeviceMessage, recipients: &[Recipient]) -> DevicePushResult {
+    let outcome = async {
+        let start = chrono::NaiveDateTime::parse_from_str(&message.start_at, START_FORMAT)
+            .map_err(|e| format!("Invalid start time {}: {}", message.start_at, e))?;
+        let id = u16::try_from(message.id).map_err(|_| format!("Invalid message ID {}", message.id))?;
+        let personal = message.scope == "users";
+
+        let mut client = ZKClient::connect(config).await?;
+        let outcome = async {
+            // Drops the old recipients too; fails harmlessly when it isn't there
+            let _ = sms::delete(&mut client, id).await;
+            sms::write(
+                &mut client,
+                &Sms {
+                    id,
+                    tag: if personal { TAG_PERSONAL } else { TAG_PUBLIC },
+                    valid_minutes: message.valid_minutes,
+                    start,
+                    content: &message.content,
+                },
+            )
+            .await?;
+
+            let mut skipped = Vec::new();
+            if personal {
+                let uids = client.get_user_uids().await?;
+                for recipient in recipients {
+                    match recipient.device_user_id.as_ref().and_then(|id| uids.get(id)) {
+                        Some(uid) => sms::link_user(&mut client, *uid, id).await?,
+                        None => skipped.push(recipient.user_id.clone()),
+                    }
+                }
+            }
+            Ok(skipped)
+        }
+        .await;
+        let _ = client.disconnect().await;
+        outcome
+    }
+    .await;
+    result(config, outcome)
+}
+
+/// Delete the message from the device
+pub async fn remove(config: &DeviceConfig, id: i64) -> DevicePushResult {
+    let outcome = async {
+        let id = u16::try_from(id).map_err(|_| format!("Invalid message ID {}", id))?;
+        let mut client = ZKClient::connect(config).await?;
+        let outcome = sms::delete(&mut client, id).await;
+        let _ = client.disconnect().await;
+        outcome.map(|()| Vec::new())
+    }
+    .await;
+    result(config, outcome)
+}
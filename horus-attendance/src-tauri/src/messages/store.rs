@@ -0,0 +1,155 @@
+//! Storage for device messages
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use super::types::DeviceMessage;
+
+/// A recipient and the ID they are enrolled under on the devices
+pub struct Recipient {
+    pub user_id: String,
+    pub device_user_id: Option<String>,
+}
+
+const SELECT: &str = "SELECT id, content, scope, start_at, valid_minutes, created_at, updated_at FROM device_messages";
+
+fn map_row(row: &rusqlite::Row) -> rusqlite::Result<DeviceMessage> {
+    Ok(DeviceMessage {
+        id: row.get(0)?,
+        content: row.get(1)?,
+        scope: row.get(2)?,
+        start_at: row.get(3)?,
+        valid_minutes: row.get(4)?,
+        user_ids: Vec::new(),
+        created_at: row.get(5)?,
+        updated_at: row.get(6)?,
+    })
+}
+
+fn user_ids(conn: &Connection, id: i64) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare("SELECT user_id FROM device_message_users WHERE message_id = ?1 ORDER BY user_id")
+        .map_err(|e| format!("Failed to query message recipients: {}", e))?;
+    let rows = stmt
+        .query_map([id], |row| row.get(0))
+        .map_err(|e| format!("Failed to query message recipients: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read message recipients: {}", e))
+}
+
+/// Every message, newest first
+pub fn list(conn: &Connection) -> Result<Vec<DeviceMessage>, String> {
+    let mut stmt = conn
+        .prepare(&format!("{} ORDER BY created_at DESC, id DESC", SELECT))
+        .map_err(|e| format!("Failed to query messages: {}", e))?;
+    let rows = stmt
+        .query_map([], map_row)
+        .map_err(|e| format!("Failed to query messages: {}", e))?;
+    let mut messages: Vec<DeviceMessage> = rows
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read messages: {}", e))?;
+    for message in &mut messages {
+        message.user_ids = user_ids(conn, message.id)?;
+    }
+    Ok(messages)
+}
+
+pub fn get(conn: &Connection, id: i64) -> Result<Option<DeviceMessage>, String> {
+    let message = conn
+        .query_row(&format!("{} WHERE id = ?1", SELECT), [id], map_row)
+        .optional()
+        .map_err(|e| format!("Failed to read message: {}", e))?;
+    match message {
+        Some(mut message) => {
+            message.user_ids = user_ids(conn, id)?;
+            Ok(Some(message))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Store a message under the lowest free device message ID
+pub fn insert(
+    conn: &mut Connection,
+    content: &str,
+    start_at: &str,
+    valid_minutes: u16,
+    user_ids: &[String],
+) -> Result<DeviceMessage, String> {
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let id: Option<i64> = tx
+        .query_row(
+            "SELECT CASE WHEN NOT EXISTS (SELECT 1 FROM device_messages WHERE id = 1) THEN 1
+                    ELSE (SELECT MIN(id + 1) FROM device_messages
+                          WHERE id < 65535 AND id + 1 NOT IN (SELECT id FROM device_messages)) END",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to allocate message ID: {}", e))?;
+    let id = id.ok_or("Every device message ID is in use; delete old messages first")?;
+    tx.execute(
+        "INSERT INTO device_messages (id, content, scope, start_at, valid_minutes) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![id, content, if user_ids.is_empty() { "all" } else { "users" }, start_at, valid_minutes],
+    )
+    .map_err(|e| format!("Failed to save message: {}", e))?;
+    insert_users(&tx, id, user_ids)?;
+    tx.commit().map_err(|e| format!("Failed to save message: {}", e))?;
+    get(conn, id)?.ok_or_else(|| format!("Message not found: {}", id))
+}
+
+fn insert_users(conn: &Connection, id: i64, user_ids: &[String]) -> Result<(), String> {
+    for user_id in user_ids {
+        conn.execute(
+            "INSERT OR IGNORE INTO device_message_users (message_id, user_id) VALUES (?1, ?2)",
+            params![id, user_id],
+        )
+        .map_err(|e| format!("Failed to save recipient {}: {}", user_id, e))?;
+    }
+    Ok(())
+}
+
+/// Replace a message's recipients; none makes it public
+pub fn set_users(conn: &mut Connection, id: i64, user_ids: &[String]) -> Result<DeviceMessage, String> {
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let updated = tx
+        .execute(
+            "UPDATE device_messages SET scope = ?1, updated_at = datetime('now') WHERE id = ?2",
+            params![if user_ids.is_empty() { "all" } else { "users" }, id],
+        )
+        .map_err(|e| format!("Failed to update message: {}", e))?;
+    if updated == 0 {
+        return Err(format!("Message not found: {}", id));
+    }
+    tx.execute("DELETE FROM device_message_users WHERE message_id = ?1", [id])
+        .map_err(|e| format!("Failed to update recipients: {}", e))?;
+    insert_users(&tx, id, user_ids)?;
+    tx.commit().map_err(|e| format!("Failed to update message: {}", e))?;
+    get(conn, id)?.ok_or_else(|| format!("Message not found: {}", id))
+}
+
+pub fn delete(conn: &Connection, id: i64) -> Result<(), String> {
+    conn.execute("DELETE FROM device_messages WHERE id = ?1", [id])
+        .map_err(|e| format!("Failed to delete message: {}", e))?;
+    Ok(())
+}
+
+/// Recipients of a message with their device user IDs
+pub fn recipients(conn: &Connection, message: &DeviceMessage) -> Result<Vec<Recipient>, String> {
+    message
+        .user_ids
+        .iter()
+        .map(|user_id| {
+            let user = crate::db::users::get(conn, user_id)?;
+            Ok(Recipient {
+                user_id: user_id.clone(),
+                device_user_id: user
+                    .as_ref()
+                    .and_then(|u| u.linked_device_user_id())
+                    .map(str::to_string),
+            })
+        })
+        .collect()
+}
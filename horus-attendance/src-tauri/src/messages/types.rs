@@ -0,0 +1,54 @@
+//! Device message data types for Tauri command serialization
+
+use serde::{Deserialize, Serialize};
+
+/// A message as stored in the app
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceMessage {
+    pub id: i64,
+    pub content: String,
+    /// "all" or "users"
+    pub scope: String,
+    /// Device-local time the message starts showing
+    pub start_at: String,
+    /// 0 keeps the message until it is deleted
+    pub valid_minutes: u16,
+    /// Recipients when the scope is "users"
+    pub user_ids: Vec<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// Input for a new message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewDeviceMessage {
+    pub content: String,
+    /// Defaults to now
+    pub start_at: Option<String>,
+    pub valid_minutes: Option<u16>,
+    /// Only these users see it; everyone when empty or absent
+    pub user_ids: Option<Vec<String>>,
+}
+
+/// Outcome on one device
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DevicePushResult {
+    pub device_id: Option<String>,
+    pub ip: String,
+    pub success: bool,
+    pub error: Option<String>,
+    /// Recipients who aren't enrolled on this device
+    pub skipped_users: Vec<String>,
+}
+
+/// A message change and how each device took it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceMessageResult {
+    /// None after a delete
+    pub message: Option<DeviceMessage>,
+    pub devices: Vec<DevicePushResult>,
+}
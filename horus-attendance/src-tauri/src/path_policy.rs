@@ -0,0 +1,124 @@
+//! Where file-writing commands may write
+//!
+//! Every command that writes to a frontend-supplied path (text/binary saves,
+//! exports, backups, dumps, transfer bundles) resolves it here first, as do
+//! commands that pack user-chosen files into an archive. Both are confined
+//! to app data, Documents, Downloads, registered backup targets and any
+//! extra directories listed under the `allowedDirectories` setting.
+//!
+//! The check runs on the real location of the file: the deepest existing
+//! ancestor is canonicalized (following symlinks) and the missing components
+//! are appended, so neither `..` nor a symlinked file or folder can point a
+//! write outside the allowed roots.
+
+use rusqlite::Connection;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use tauri::Manager;
+
+use crate::backup::targets;
+use crate::db;
+
+/// Settings key holding extra directories (absolute paths) writes may go to
+pub const ALLOWED_DIRECTORIES_KEY: &str = "allowedDirectories";
+
+/// Directories writes may go to, canonicalized. Roots that don't exist yet
+/// are kept as given; nothing under them can be a symlink until they do.
+pub fn allowed_roots(app: &tauri::AppHandle) -> Result<Vec<PathBuf>, String> {
+    let mut roots = vec![
+        app.path().app_data_dir().map_err(|e| format!("Cannot resolve app data dir: {}", e))?,
+        app.path().document_dir().map_err(|e| format!("Cannot resolve document dir: {}", e))?,
+    ];
+    roots.extend(app.path().download_dir().ok());
+
+    // No database yet on first launch: only the built-in roots apply
+    if let Ok(conn) = db::open(app) {
+        roots.extend(configured_roots(&conn)?);
+    }
+
+    Ok(roots.into_iter().map(|root| root.canonicalize().unwrap_or(root)).collect())
+}
+
+/// Extra directories and backup target folders from settings
+fn configured_roots(conn: &Connection) -> Result<Vec<PathBuf>, String> {
+    let extra: Vec<String> = db::get_json_setting(conn, ALLOWED_DIRECTORIES_KEY)?.unwrap_or_default();
+    let target_paths = targets::list(conn)?.into_iter().map(|t| t.path);
+
+    Ok(extra
+        .into_iter()
+        .chain(target_paths)
+        .map(PathBuf::from)
+        .filter(|path| {
+            // A whole drive (or "/") would disable the policy altogether
+            let usable = path.is_absolute() && path.parent().is_some();
+            if !usable {
+                log::warn!("[path_policy] Ignoring allowed directory '{}'", path.display());
+            }
+            usable
+        })
+        .collect())
+}
+
+/// Resolve `path` to the location a write would actually touch and check it
+/// lies inside one of `roots`
+pub fn check(path: &Path, roots: &[PathBuf]) -> Result<PathBuf, String> {
+    if !path.is_absolute() {
+        return Err(format!("Write denied: path must be absolute. Got: {}", path.display()));
+    }
+    if path.components().any(|c| c == Component::ParentDir) {
+        return Err(format!("Write denied: path must not contain '..'. Got: {}", path.display()));
+    }
+
+    // Split into the deepest existing ancestor and the components still to be created
+    let mut existing = path;
+    let mut missing = Vec::new();
+    while fs::symlink_metadata(existing).is_err() {
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name.to_os_string());
+                existing = parent;
+            }
+            _ => return Err(format!("Write denied: no such location: {}", path.display())),
+        }
+    }
+
+    let mut resolved = existing
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve {}: {}", existing.display(), e))?;
+    for name in missing.into_iter().rev() {
+        resolved.push(name);
+    }
+
+    if !roots.iter().any(|root| resolved.starts_with(root)) {
+        return Err(format!(
+            "Write denied: path must be inside app data, documents, downloads, a backup target or an allowed directory. Got: {}",
+            path.display()
+        ));
+    }
+    if resolved.is_dir() {
+        return Err(format!("Write denied: {} is a directory", path.display()));
+    }
+    Ok(resolved)
+}
+
+/// Resolve a frontend-supplied file path to write to. Creates missing parent
+/// directories.
+pub fn resolve_write_target(app: &tauri::AppHandle, path: &str) -> Result<PathBuf, String> {
+    let target = check(Path::new(path), &allowed_roots(app)?)?;
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create parent directories: {}", e))?;
+    }
+    Ok(target)
+}
+
+/// Resolve an existing file or directory a command will read from and copy
+/// elsewhere (e.g. into a zip), under the same roots as writes
+pub fn resolve_source(app: &tauri::AppHandle, path: &str) -> Result<PathBuf, String> {
+    let source = Path::new(path)
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve {}: {}", path, e))?;
+    if !allowed_roots(app)?.iter().any(|root| source.starts_with(root)) {
+        return Err(format!("Read denied: {} is outside the allowed directories", path));
+    }
+    Ok(source)
+}
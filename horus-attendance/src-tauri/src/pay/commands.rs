@@ -0,0 +1,63 @@
+//! Tauri command handlers for pay estimates.
+
+use super::estimate;
+use super::store::{self, SETTINGS_KEY};
+use super::types::*;
+use crate::analytics::types::{DateRange, Scope};
+use crate::db;
+use crate::summary::commands::validate_date;
+
+#[tauri::command]
+pub async fn get_pay_estimation_settings(app: tauri::AppHandle) -> Result<PayEstimationSettings, String> {
+    store::load_settings(&*db::open(&app)?)
+}
+
+#[tauri::command]
+pub async fn save_pay_estimation_settings(app: tauri::AppHandle, mut settings: PayEstimationSettings) -> Result<(), String> {
+    settings.currency = settings.currency.trim().to_ascii_uppercase();
+    store::validate(&settings)?;
+    let json = serde_json::to_string(&settings).map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    log::info!(
+        "[pay::cmd] Default currency {}, overtime after {}h/day ({:?}h/week) at x{}, rest days at x{}",
+        settings.currency,
+        settings.daily_overtime_after_hours,
+        settings.weekly_overtime_after_hours,
+        settings.overtime_multiplier,
+        settings.rest_day_multiplier
+    );
+    db::set_setting(&*db::open(&app)?, SETTINGS_KEY, &json)
+}
+
+/// Every user's hourly rate
+#[tauri::command]
+pub async fn list_pay_rates(app: tauri::AppHandle) -> Result<Vec<PayRate>, String> {
+    store::list(&*db::open(&app)?)
+}
+
+/// Set or replace a user's hourly rate
+#[tauri::command]
+pub async fn set_pay_rate(app: tauri::AppHandle, rate: PayRate) -> Result<PayRate, String> {
+    let saved = store::save(&*db::open(&app)?, rate)?;
+    log::info!("[pay::cmd] Set pay rate of user {}", saved.user_id);
+    Ok(saved)
+}
+
+#[tauri::command]
+pub async fn delete_pay_rate(app: tauri::AppHandle, user_id: String) -> Result<bool, String> {
+    store::delete(&*db::open(&app)?, &user_id)
+}
+
+/// Gross pay estimates per user for a period, from worked and overtime hours
+#[tauri::command]
+pub async fn estimate_pay(app: tauri::AppHandle, period: DateRange, scope: Option<Scope>) -> Result<PayEstimate, String> {
+    validate_date(&period.start_date)?;
+    validate_date(&period.end_date)?;
+    if period.start_date > period.end_date {
+        return Err("Start date must not be after end date".to_string());
+    }
+    let scope = scope.unwrap_or_default();
+    let conn = db::open(&app)?;
+    tauri::async_runtime::spawn_blocking(move || estimate::estimate(&conn, &period, &scope))
+        .await
+        .map_err(|e| format!("Pay estimate task failed: {}", e))?
+}
@@ -0,0 +1,171 @@
+//! Gross pay estimation from day summaries
+
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::NaiveDate;
+use rusqlite::{params_from_iter, types::Value, Connection};
+
+use super::store;
+use super::types::*;
+use crate::analytics::scope;
+use crate::analytics::types::{DateRange, Scope};
+use crate::periods::calc::{self as period_calc, parse_date};
+use crate::summary::rules::{STATUS_HOLIDAY, STATUS_WEEKEND};
+
+/// One worked day: date, whether it's a rest day, minutes worked
+struct WorkedDay {
+    date: NaiveDate,
+    rest_day: bool,
+    minutes: i64,
+}
+
+struct Worker {
+    name: String,
+    employee_code: Option<String>,
+    days: Vec<WorkedDay>,
+}
+
+/// Hours split by how they're paid
+#[derive(Default)]
+struct Hours {
+    regular: f64,
+    overtime: f64,
+    rest_day: f64,
+}
+
+/// Estimate gross pay for users in scope who worked in the period
+pub fn estimate(conn: &Connection, period: &DateRange, scope: &Scope) -> Result<PayEstimate, String> {
+    let settings = store::load_settings(conn)?;
+    let week_settings = period_calc::load_settings(conn)?;
+    let rates: HashMap<String, PayRate> = store::list(conn)?
+        .into_iter()
+        .map(|rate| (rate.user_id.clone(), rate))
+        .collect();
+
+    let mut users_without_rate = Vec::new();
+    let mut users = Vec::new();
+    for (user_id, worker) in worked_days(conn, period, scope)? {
+        let Some(rate) = rates.get(&user_id) else {
+            users_without_rate.push(worker.name);
+            continue;
+        };
+        let overtime_multiplier = rate.overtime_multiplier.unwrap_or(settings.overtime_multiplier);
+        let rest_day_multiplier = rate.rest_day_multiplier.unwrap_or(settings.rest_day_multiplier);
+
+        let mut hours = Hours::default();
+        let mut weeks: HashMap<NaiveDate, f64> = HashMap::new();
+        for day in &worker.days {
+            let worked = day.minutes as f64 / 60.0;
+            if day.rest_day {
+                hours.rest_day += worked;
+                continue;
+            }
+            let mut regular = worked.min(settings.daily_overtime_after_hours);
+            let mut overtime = worked - regular;
+            if let Some(weekly) = settings.weekly_overtime_after_hours {
+                let week = weeks.entry(period_calc::week_start(&week_settings, day.date)).or_default();
+                let over_week = (*week + regular - weekly).max(0.0).min(regular);
+                regular -= over_week;
+                overtime += over_week;
+                *week += regular;
+            }
+            hours.regular += regular;
+            hours.overtime += overtime;
+        }
+
+        let gross = rate.hourly_rate
+            * (hours.regular + hours.overtime * overtime_multiplier + hours.rest_day * rest_day_multiplier);
+        users.push(UserPayEstimate {
+            user_id,
+            name: worker.name,
+            employee_code: worker.employee_code,
+            currency: rate.currency.clone().unwrap_or_else(|| settings.currency.clone()),
+            hourly_rate: rate.hourly_rate,
+            overtime_multiplier,
+            rest_day_multiplier,
+            days_worked: worker.days.len() as i64,
+            regular_hours: round2(hours.regular),
+            overtime_hours: round2(hours.overtime),
+            rest_day_hours: round2(hours.rest_day),
+            gross: round2(gross),
+        });
+    }
+    users.sort_by(|a, b| a.name.cmp(&b.name));
+    users_without_rate.sort();
+
+    let mut totals: BTreeMap<String, CurrencyTotal> = BTreeMap::new();
+    for user in &users {
+        let total = totals.entry(user.currency.clone()).or_insert_with(|| CurrencyTotal {
+            currency: user.currency.clone(),
+            users: 0,
+            gross: 0.0,
+        });
+        total.users += 1;
+        total.gross = round2(total.gross + user.gross);
+    }
+
+    Ok(PayEstimate {
+        period: period.clone(),
+        users,
+        totals: totals.into_values().collect(),
+        users_without_rate,
+    })
+}
+
+/// Days with both a check-in and a later check-out, by user
+fn worked_days(conn: &Connection, period: &DateRange, scope: &Scope) -> Result<BTreeMap<String, Worker>, String> {
+    let (user_sql, user_params) = scope::condition(scope, "s.user_id", "id", 3);
+    let sql = format!(
+        "SELECT s.user_id, u.display_name, u.employee_code, s.date, s.status,
+                {departure} - {arrival}
+         FROM attendance_day_summary s
+         JOIN users u ON u.id = s.user_id
+         WHERE s.date BETWEEN ?1 AND ?2
+           AND s.check_in_time IS NOT NULL AND s.check_out_time IS NOT NULL
+           AND {departure} > {arrival}
+           AND {user_sql}
+         ORDER BY s.user_id, s.date",
+        arrival = "(CAST(substr(s.check_in_time, 1, 2) AS INTEGER) * 60 + CAST(substr(s.check_in_time, 4, 2) AS INTEGER))",
+        departure = "(CAST(substr(s.check_out_time, 1, 2) AS INTEGER) * 60 + CAST(substr(s.check_out_time, 4, 2) AS INTEGER))",
+        user_sql = user_sql,
+    );
+    let mut params = vec![Value::Text(period.start_date.clone()), Value::Text(period.end_date.clone())];
+    params.extend(user_params);
+
+    let mut stmt = conn
+        .prepare(&sql)
+        .map_err(|e| format!("Failed to query worked days: {}", e))?;
+    let rows = stmt
+        .query_map(params_from_iter(params), |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, i64>(5)?,
+            ))
+        })
+        .map_err(|e| format!("Failed to query worked days: {}", e))?;
+
+    let mut workers: BTreeMap<String, Worker> = BTreeMap::new();
+    for row in rows {
+        let (user_id, name, employee_code, date, status, minutes) =
+            row.map_err(|e| format!("Failed to read worked days: {}", e))?;
+        let worker = workers.entry(user_id).or_insert_with(|| Worker {
+            name,
+            employee_code,
+            days: Vec::new(),
+        });
+        worker.days.push(WorkedDay {
+            date: parse_date(&date)?,
+            rest_day: status == STATUS_WEEKEND || status == STATUS_HOLIDAY,
+            minutes,
+        });
+    }
+    Ok(workers)
+}
+
+fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
@@ -0,0 +1,15 @@
+//! Pay estimates
+//!
+//! Not payroll: a gross-pay sanity check from worked hours. Users can carry
+//! an hourly rate, a currency and their own overtime and rest-day
+//! multipliers; anything left unset falls back to the `payEstimation`
+//! setting. Worked hours come from the day summaries (check-in to
+//! check-out). Hours on weekends and holidays are paid at the rest-day
+//! multiplier; other hours past the daily threshold, and past the optional
+//! weekly threshold (weeks start on the configured first day of the week),
+//! are overtime. Totals are kept per currency and never converted.
+
+pub mod commands;
+pub mod estimate;
+pub mod store;
+pub mod types;
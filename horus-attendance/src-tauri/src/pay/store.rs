@@ -0,0 +1,120 @@
+//! Storage of pay rates and estimation settings
+
+use rusqlite::{params, Connection, Row};
+
+use super::types::*;
+use crate::db;
+
+pub const SETTINGS_KEY: &str = "payEstimation";
+
+pub fn load_settings(conn: &Connection) -> Result<PayEstimationSettings, String> {
+    Ok(db::get_json_setting(conn, SETTINGS_KEY)?.unwrap_or_default())
+}
+
+fn validate_currency(currency: &str) -> Result<(), String> {
+    if currency.len() != 3 || !currency.chars().all(|c| c.is_ascii_uppercase()) {
+        return Err(format!("Invalid currency code (expected e.g. USD): {}", currency));
+    }
+    Ok(())
+}
+
+fn validate_multiplier(name: &str, multiplier: f64) -> Result<(), String> {
+    if !multiplier.is_finite() || multiplier < 1.0 {
+        return Err(format!("{} multiplier must be at least 1", name));
+    }
+    Ok(())
+}
+
+pub fn validate(settings: &PayEstimationSettings) -> Result<(), String> {
+    validate_currency(&settings.currency)?;
+    if !(settings.daily_overtime_after_hours > 0.0 && settings.daily_overtime_after_hours <= 24.0) {
+        return Err("Daily overtime threshold must be between 0 and 24 hours".to_string());
+    }
+    if let Some(weekly) = settings.weekly_overtime_after_hours {
+        if !(weekly > 0.0 && weekly <= 168.0) {
+            return Err("Weekly overtime threshold must be between 0 and 168 hours".to_string());
+        }
+    }
+    validate_multiplier("Overtime", settings.overtime_multiplier)?;
+    validate_multiplier("Rest day", settings.rest_day_multiplier)
+}
+
+fn map_rate(row: &Row) -> rusqlite::Result<PayRate> {
+    Ok(PayRate {
+        user_id: row.get(0)?,
+        hourly_rate: row.get(1)?,
+        overtime_multiplier: row.get(2)?,
+        rest_day_multiplier: row.get(3)?,
+        currency: row.get(4)?,
+        updated_at: row.get(5)?,
+    })
+}
+
+/// Every user's rate
+pub fn list(conn: &Connection) -> Result<Vec<PayRate>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT user_id, hourly_rate, overtime_multiplier, rest_day_multiplier, currency, updated_at
+             FROM pay_rates ORDER BY user_id",
+        )
+        .map_err(|e| format!("Failed to query pay rates: {}", e))?;
+    let rows = stmt
+        .query_map([], map_rate)
+        .map_err(|e| format!("Failed to query pay rates: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read pay rates: {}", e))
+}
+
+/// Set or replace a user's rate
+pub fn save(conn: &Connection, mut rate: PayRate) -> Result<PayRate, String> {
+    if !rate.hourly_rate.is_finite() || rate.hourly_rate < 0.0 {
+        return Err("Hourly rate must not be negative".to_string());
+    }
+    if let Some(multiplier) = rate.overtime_multiplier {
+        validate_multiplier("Overtime", multiplier)?;
+    }
+    if let Some(multiplier) = rate.rest_day_multiplier {
+        validate_multiplier("Rest day", multiplier)?;
+    }
+    rate.currency = rate
+        .currency
+        .map(|c| c.trim().to_ascii_uppercase())
+        .filter(|c| !c.is_empty());
+    if let Some(currency) = &rate.currency {
+        validate_currency(currency)?;
+    }
+    let exists: bool = conn
+        .query_row("SELECT EXISTS(SELECT 1 FROM users WHERE id = ?1)", [&rate.user_id], |row| row.get(0))
+        .map_err(|e| format!("Failed to read user: {}", e))?;
+    if !exists {
+        return Err(format!("User not found: {}", rate.user_id));
+    }
+    conn.execute(
+        "INSERT INTO pay_rates (user_id, hourly_rate, overtime_multiplier, rest_day_multiplier, currency)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT(user_id) DO UPDATE SET
+           hourly_rate = excluded.hourly_rate,
+           overtime_multiplier = excluded.overtime_multiplier,
+           rest_day_multiplier = excluded.rest_day_multiplier,
+           currency = excluded.currency,
+           updated_at = datetime('now')",
+        params![
+            rate.user_id,
+            rate.hourly_rate,
+            rate.overtime_multiplier,
+            rate.rest_day_multiplier,
+            rate.currency
+        ],
+    )
+    .map_err(|e| format!("Failed to save pay rate: {}", e))?;
+    rate.updated_at = conn
+        .query_row("SELECT updated_at FROM pay_rates WHERE user_id = ?1", [&rate.user_id], |row| row.get(0))
+        .map_err(|e| format!("Failed to read pay rate: {}", e))?;
+    Ok(rate)
+}
+
+pub fn delete(conn: &Connection, user_id: &str) -> Result<bool, String> {
+    conn.execute("DELETE FROM pay_rates WHERE user_id = ?1", [user_id])
+        .map(|n| n > 0)
+        .map_err(|e| format!("Failed to delete pay rate: {}", e))
+}
@@ -0,0 +1,87 @@
+//! Pay estimate data types for Tauri command serialization
+
+use serde::{Deserialize, Serialize};
+
+use crate::export::types::DateRange;
+
+/// Pay estimation defaults (`payEstimation` setting)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PayEstimationSettings {
+    /// ISO 4217 code for rates without their own currency
+    pub currency: String,
+    /// Hours a day before the rest are overtime
+    pub daily_overtime_after_hours: f64,
+    /// Regular hours a week before the rest are overtime, if capped
+    pub weekly_overtime_after_hours: Option<f64>,
+    pub overtime_multiplier: f64,
+    /// Multiplier for hours worked on weekends and holidays
+    pub rest_day_multiplier: f64,
+}
+
+impl Default for PayEstimationSettings {
+    fn default() -> Self {
+        Self {
+            currency: "USD".to_string(),
+            daily_overtime_after_hours: 8.0,
+            weekly_overtime_after_hours: None,
+            overtime_multiplier: 1.5,
+            rest_day_multiplier: 1.5,
+        }
+    }
+}
+
+/// A user's hourly rate (one pay_rates row)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PayRate {
+    pub user_id: String,
+    pub hourly_rate: f64,
+    /// Falls back to the setting when unset
+    #[serde(default)]
+    pub overtime_multiplier: Option<f64>,
+    #[serde(default)]
+    pub rest_day_multiplier: Option<f64>,
+    #[serde(default)]
+    pub currency: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+}
+
+/// One user's estimate for a period
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserPayEstimate {
+    pub user_id: String,
+    pub name: String,
+    pub employee_code: Option<String>,
+    pub currency: String,
+    pub hourly_rate: f64,
+    pub overtime_multiplier: f64,
+    pub rest_day_multiplier: f64,
+    pub days_worked: i64,
+    pub regular_hours: f64,
+    pub overtime_hours: f64,
+    pub rest_day_hours: f64,
+    pub gross: f64,
+}
+
+/// Gross total of one currency
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurrencyTotal {
+    pub currency: String,
+    pub users: i64,
+    pub gross: f64,
+}
+
+/// Gross pay estimates for a period
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PayEstimate {
+    pub period: DateRange,
+    pub users: Vec<UserPayEstimate>,
+    pub totals: Vec<CurrencyTotal>,
+    /// Users in scope who worked in the period but have no rate
+    pub users_without_rate: Vec<String>,
+}
@@ -0,0 +1,57 @@
+//! Tauri command handlers for raw payload retention.
+
+use super::store::{self, RAW_PAYLOAD_KEY};
+use super::types::*;
+use crate::db;
+
+/// The retention settings
+#[tauri::command]
+pub async fn get_raw_payload_settings(app: tauri::AppHandle) -> Result<RawPayloadSettings, String> {
+    store::load_settings(&*db::open(&app)?)
+}
+
+/// Save the retention settings. Payloads already kept stay until purged.
+#[tauri::command]
+pub async fn save_raw_payload_settings(app: tauri::AppHandle, settings: RawPayloadSettings) -> Result<(), String> {
+    if settings.max_megabytes == 0 {
+        return Err("Size cap must be at least 1 MB".to_string());
+    }
+    let json = serde_json::to_string(&settings).map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    log::info!(
+        "[payloads::cmd] Raw payload retention {} ({}, {} MB cap)",
+        if settings.enabled { "on" } else { "off" },
+        settings.encoding.as_str(),
+        settings.max_megabytes
+    );
+    db::set_setting(&*db::open(&app)?, RAW_PAYLOAD_KEY, &json)
+}
+
+/// What retained payloads take up
+#[tauri::command]
+pub async fn get_raw_payload_usage(app: tauri::AppHandle) -> Result<RawPayloadUsage, String> {
+    store::usage(&*db::open(&app)?)
+}
+
+/// The device bytes of one log, if they were kept
+#[tauri::command]
+pub async fn get_raw_payload(
+    app: tauri::AppHandle,
+    device_id: String,
+    device_user_id: String,
+    timestamp: String,
+) -> Result<Option<RawPayload>, String> {
+    store::get(&*db::open(&app)?, &device_id, &device_user_id, &timestamp)
+}
+
+/// Delete retained payloads, all of them unless limited to those kept
+/// before a date or from one device. Returns how many were deleted.
+#[tauri::command]
+pub async fn purge_raw_payloads(
+    app: tauri::AppHandle,
+    before: Option<String>,
+    device_id: Option<String>,
+) -> Result<usize, String> {
+    let deleted = store::purge(&*db::open(&app)?, before.as_deref(), device_id.as_deref())?;
+    log::info!("[payloads::cmd] Purged {} raw payloads", deleted);
+    Ok(deleted)
+}
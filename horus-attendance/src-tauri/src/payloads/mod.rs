@@ -0,0 +1,10 @@
+//! Raw payload retention
+//!
+//! When turned on, each attendance record is also kept exactly as the device
+//! sent it, hex-encoded or zstd-compressed, so a wrong user ID or timestamp
+//! can be traced back to the bytes it was decoded from. Retention stops at a
+//! size cap, and old payloads can be purged.
+
+pub mod commands;
+pub mod store;
+pub mod types;
@@ -0,0 +1,206 @@
+//! Storing, reading and purging raw payloads
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use super::types::*;
+use crate::db;
+use crate::zkteco::types::AttendanceLog;
+
+/// Settings key for retention
+pub const RAW_PAYLOAD_KEY: &str = "rawPayloadRetention";
+
+/// Load the retention settings, falling back to defaults (off)
+pub fn load_settings(conn: &Connection) -> Result<RawPayloadSettings, String> {
+    Ok(db::get_json_setting(conn, RAW_PAYLOAD_KEY)?.unwrap_or_default())
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn encode(bytes: &[u8], encoding: PayloadEncoding) -> Result<Vec<u8>, String> {
+    match encoding {
+        PayloadEncoding::Hex => Ok(to_hex(bytes).into_bytes()),
+        PayloadEncoding::Zstd => zstd::bulk::compress(bytes, 0).map_err(|e| format!("Failed to compress payload: {}", e)),
+    }
+}
+
+fn decode(payload: &[u8], encoding: PayloadEncoding, original_size: usize) -> Result<Vec<u8>, String> {
+    match encoding {
+        PayloadEncoding::Hex => {
+            let text = std::str::from_utf8(payload).map_err(|_| "Stored payload is not hex".to_string())?;
+            (0..text.len())
+                .step_by(2)
+                .map(|i| {
+                    text.get(i..i + 2)
+                        .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                        .ok_or_else(|| "Stored payload is not hex".to_string())
+                })
+                .collect()
+        }
+        PayloadEncoding::Zstd => zstd::bulk::decompress(payload, original_size)
+            .map_err(|e| format!("Failed to decompress payload: {}", e)),
+    }
+}
+
+/// The bytes of a stored payload, from its row's encoding, payload and
+/// original size columns
+pub(crate) fn decode_stored(encoding: &str, payload: &[u8], original_size: i64) -> Result<Vec<u8>, String> {
+    decode(payload, parse_encoding(encoding), original_size.max(0) as usize)
+}
+
+fn parse_encoding(value: &str) -> PayloadEncoding {
+    if value == "hex" {
+        PayloadEncoding::Hex
+    } else {
+        PayloadEncoding::Zstd
+    }
+}
+
+fn stored_bytes(conn: &Connection) -> Result<i64, String> {
+    conn.query_row("SELECT COALESCE(SUM(length(payload)), 0) FROM raw_payloads", [], |row| row.get(0))
+        .map_err(|e| format!("Failed to measure raw payloads: {}", e))
+}
+
+/// Keep the device bytes of `logs` if retention is on, unless the record
+/// was kept before. Stops at the size cap. Returns how many were kept.
+pub fn retain(conn: &mut Connection, device_id: Option<&str>, logs: &[AttendanceLog]) -> Result<usize, String> {
+    let settings = load_settings(conn)?;
+    let Some(device_id) = device_id.filter(|_| settings.enabled) else {
+        return Ok(0);
+    };
+    let max_bytes = i64::from(settings.max_megabytes) * 1024 * 1024;
+    let mut used = stored_bytes(conn)?;
+    if used >= max_bytes {
+        log::warn!("[payloads] Size cap of {} MB reached; not keeping raw payloads", settings.max_megabytes);
+        return Ok(0);
+    }
+
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    let mut kept = 0;
+    {
+        let mut stmt = tx
+            .prepare(
+                "INSERT OR IGNORE INTO raw_payloads
+                     (device_id, device_user_id, timestamp, encoding, payload, original_size)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            )
+            .map_err(|e| format!("Failed to prepare payload insert: {}", e))?;
+        for log in logs.iter().filter(|l| !l.raw.is_empty()) {
+            let payload = encode(&log.raw, settings.encoding)?;
+            let inserted = stmt
+                .execute(params![
+                    device_id,
+                    log.device_user_id,
+                    log.timestamp,
+                    settings.encoding.as_str(),
+                    payload,
+                    log.raw.len() as i64,
+                ])
+                .map_err(|e| format!("Failed to save raw payload: {}", e))?;
+            if inserted > 0 {
+                kept += 1;
+                used += payload.len() as i64;
+                if used >= max_bytes {
+                    log::warn!("[payloads] Size cap of {} MB reached after {} payloads", settings.max_megabytes, kept);
+                    break;
+                }
+            }
+        }
+    }
+    tx.commit().map_err(|e| format!("Failed to commit: {}", e))?;
+    Ok(kept)
+}
+
+/// What retained payloads take up, in total and per device
+pub fn usage(conn: &Connection) -> Result<RawPayloadUsage, String> {
+    let settings = load_settings(conn)?;
+    let (records, original_bytes, stored_bytes, oldest, newest) = conn
+        .query_row(
+            "SELECT COUNT(*), COALESCE(SUM(original_size), 0), COALESCE(SUM(length(payload)), 0),
+                    MIN(created_at), MAX(created_at)
+             FROM raw_payloads",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
+        )
+        .map_err(|e| format!("Failed to measure raw payloads: {}", e))?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT p.device_id, d.name, COUNT(*), SUM(length(p.payload))
+             FROM raw_payloads p LEFT JOIN devices d ON d.id = p.device_id
+             GROUP BY p.device_id ORDER BY SUM(length(p.payload)) DESC",
+        )
+        .map_err(|e| format!("Failed to measure raw payloads: {}", e))?;
+    let devices = stmt
+        .query_map([], |row| {
+            Ok(DevicePayloadUsage {
+                device_id: row.get(0)?,
+                device_name: row.get(1)?,
+                records: row.get(2)?,
+                stored_bytes: row.get(3)?,
+            })
+        })
+        .map_err(|e| format!("Failed to measure raw payloads: {}", e))?
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read raw payload usage: {}", e))?;
+
+    Ok(RawPayloadUsage {
+        records,
+        original_bytes,
+        stored_bytes,
+        max_bytes: i64::from(settings.max_megabytes) * 1024 * 1024,
+        oldest,
+        newest,
+        devices,
+    })
+}
+
+/// The retained bytes of one log, if any
+pub fn get(conn: &Connection, device_id: &str, device_user_id: &str, timestamp: &str) -> Result<Option<RawPayload>, String> {
+    let row = conn
+        .query_row(
+            "SELECT encoding, payload, original_size, created_at FROM raw_payloads
+             WHERE device_id = ?1 AND device_user_id = ?2 AND timestamp = ?3",
+            params![device_id, device_user_id, timestamp],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, Vec<u8>>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, String>(3)?,
+                ))
+            },
+        )
+        .optional()
+        .map_err(|e| format!("Failed to read raw payload: {}", e))?;
+    let Some((encoding, payload, original_size, created_at)) = row else {
+        return Ok(None);
+    };
+    let encoding = parse_encoding(&encoding);
+    let bytes = decode(&payload, encoding, original_size.max(0) as usize)?;
+    Ok(Some(RawPayload {
+        device_id: device_id.to_string(),
+        device_user_id: device_user_id.to_string(),
+        timestamp: timestamp.to_string(),
+        encoding,
+        hex: to_hex(&bytes),
+        original_size,
+        stored_size: payload.len() as i64,
+        created_at,
+    }))
+}
+
+/// Delete payloads kept before `before` (a `YYYY-MM-DD` date or datetime)
+/// and from `device_id`, either or both of which may be left out. Returns
+/// how many were deleted.
+pub fn purge(conn: &Connection, before: Option<&str>, device_id: Option<&str>) -> Result<usize, String> {
+    conn.execute(
+        "DELETE FROM raw_payloads
+         WHERE (?1 IS NULL OR created_at < ?1) AND (?2 IS NULL OR device_id = ?2)",
+        params![before, device_id],
+    )
+    .map_err(|e| format!("Failed to purge raw payloads: {}", e))
+}
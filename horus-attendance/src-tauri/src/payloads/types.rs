@@ -0,0 +1,82 @@
+//! Types for raw payload retention
+
+use serde::{Deserialize, Serialize};
+
+/// How a record's bytes are stored
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PayloadEncoding {
+    /// Readable in any SQLite browser, twice the size
+    Hex,
+    #[default]
+    Zstd,
+}
+
+impl PayloadEncoding {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Hex => "hex",
+            Self::Zstd => "zstd",
+        }
+    }
+}
+
+/// Raw payload retention settings (`rawPayloadRetention` setting)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RawPayloadSettings {
+    pub enabled: bool,
+    pub encoding: PayloadEncoding,
+    /// No more payloads are kept once they take up this much
+    pub max_megabytes: u32,
+}
+
+impl Default for RawPayloadSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            encoding: PayloadEncoding::Zstd,
+            max_megabytes: 100,
+        }
+    }
+}
+
+/// Space taken by one device's payloads
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DevicePayloadUsage {
+    pub device_id: String,
+    pub device_name: Option<String>,
+    pub records: i64,
+    pub stored_bytes: i64,
+}
+
+/// Space taken by retained payloads
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RawPayloadUsage {
+    pub records: i64,
+    /// Size of the records as received
+    pub original_bytes: i64,
+    /// Size as stored, after encoding
+    pub stored_bytes: i64,
+    pub max_bytes: i64,
+    pub oldest: Option<String>,
+    pub newest: Option<String>,
+    pub devices: Vec<DevicePayloadUsage>,
+}
+
+/// A retained record, decoded back to hex
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RawPayload {
+    pub device_id: String,
+    pub device_user_id: String,
+    pub timestamp: String,
+    pub encoding: PayloadEncoding,
+    /// The record's bytes as lowercase hex
+    pub hex: String,
+    pub original_size: i64,
+    pub stored_size: i64,
+    pub created_at: String,
+}
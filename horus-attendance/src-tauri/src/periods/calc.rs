@@ -0,0 +1,163 @@
+//! Period arithmetic
+//!
+//! Also exposed to SQL as `pay_period(date)` (the key of the period a
+//! YYYY-MM-DD date falls in), so totals can be grouped by period in a single
+//! query like `hijri_month`.
+
+use chrono::{Datelike, Duration, NaiveDate};
+use rusqlite::functions::FunctionFlags;
+use rusqlite::Connection;
+
+use super::types::*;
+use crate::db;
+
+pub const SETTINGS_KEY: &str = "periods";
+
+pub fn load_settings(conn: &Connection) -> Result<PeriodSettings, String> {
+    Ok(db::get_json_setting(conn, SETTINGS_KEY)?.unwrap_or_default())
+}
+
+pub fn parse_date(date: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| format!("Invalid date (expected YYYY-MM-DD): {}", date))
+}
+
+pub fn validate(settings: &PeriodSettings) -> Result<(), String> {
+    if settings.first_day_of_week > 6 {
+        return Err("First day of the week must be 0 (Sunday) to 6 (Saturday)".to_string());
+    }
+    match settings.kind.as_str() {
+        KIND_CALENDAR_MONTH => Ok(()),
+        KIND_CUSTOM_START if (1..=28).contains(&settings.start_day) => Ok(()),
+        KIND_CUSTOM_START => Err("Periods must start on a day between 1 and 28".to_string()),
+        KIND_BIWEEKLY => match settings.anchor_date.as_deref() {
+            Some(anchor) => parse_date(anchor).map(|_| ()),
+            None => Err("Bi-weekly periods need an anchor date".to_string()),
+        },
+        other => Err(format!("Unknown period kind: {}", other)),
+    }
+}
+
+/// First day of `month`, with months past December rolling into the next year
+fn first_of(year: i32, month: i32) -> NaiveDate {
+    let index = year * 12 + month - 1;
+    NaiveDate::from_ymd_opt(index.div_euclid(12), index.rem_euclid(12) as u32 + 1, 1).unwrap_or_default()
+}
+
+/// Day the monthly periods start on; 1 for calendar months
+fn month_start_day(settings: &PeriodSettings) -> u32 {
+    if settings.kind == KIND_CUSTOM_START {
+        settings.start_day.clamp(1, 28)
+    } else {
+        1
+    }
+}
+
+/// First and last day of the period named after `month`. Custom-start
+/// periods end in the month they're named after; bi-weekly settings fall
+/// back to calendar months here.
+pub fn month_range(settings: &PeriodSettings, year: i32, month: u32) -> Result<(NaiveDate, NaiveDate), String> {
+    if !(1..=12).contains(&month) {
+        return Err(format!("Invalid month: {}-{}", year, month));
+    }
+    let start_day = month_start_day(settings);
+    let (start, next) = if start_day == 1 {
+        (first_of(year, month as i32), first_of(year, month as i32 + 1))
+    } else {
+        let previous = first_of(year, month as i32 - 1);
+        let this = first_of(year, month as i32);
+        (
+            previous.with_day(start_day).unwrap_or(previous),
+            this.with_day(start_day).unwrap_or(this),
+        )
+    };
+    Ok((start, next - Duration::days(1)))
+}
+
+/// Year and month of the monthly period `date` falls in
+pub fn month_of(settings: &PeriodSettings, date: NaiveDate) -> (i32, u32) {
+    let start_day = month_start_day(settings);
+    if start_day > 1 && date.day() >= start_day {
+        let next = first_of(date.year(), date.month() as i32 + 1);
+        (next.year(), next.month())
+    } else {
+        (date.year(), date.month())
+    }
+}
+
+/// The latest monthly period that ended before `date`
+pub fn last_ended_month(settings: &PeriodSettings, date: NaiveDate) -> (i32, u32) {
+    let (year, month) = month_of(settings, date);
+    let previous = first_of(year, month as i32 - 1);
+    (previous.year(), previous.month())
+}
+
+/// "October 2026", or "October 2026 (26 Sep – 25 Oct)" for periods not
+/// starting on the 1st
+pub fn month_label(settings: &PeriodSettings, year: i32, month: u32) -> Result<String, String> {
+    Ok(month_period(settings, year, month)?.label)
+}
+
+fn month_period(settings: &PeriodSettings, year: i32, month: u32) -> Result<PayPeriod, String> {
+    let (start, end) = month_range(settings, year, month)?;
+    let name = first_of(year, month as i32).format("%B %Y").to_string();
+    Ok(PayPeriod {
+        key: format!("{:04}-{:02}", year, month),
+        label: if start.day() == 1 {
+            name
+        } else {
+            format!("{} ({} – {})", name, start.format("%-d %b"), end.format("%-d %b"))
+        },
+        start_date: start.format("%Y-%m-%d").to_string(),
+        end_date: end.format("%Y-%m-%d").to_string(),
+    })
+}
+
+/// The period `date` falls in
+pub fn containing(settings: &PeriodSettings, date: NaiveDate) -> Result<PayPeriod, String> {
+    if settings.kind != KIND_BIWEEKLY {
+        let (year, month) = month_of(settings, date);
+        return month_period(settings, year, month);
+    }
+    let anchor = parse_date(settings.anchor_date.as_deref().ok_or("Bi-weekly periods need an anchor date")?)?;
+    let start = anchor + Duration::days((date - anchor).num_days().div_euclid(14) * 14);
+    let end = start + Duration::days(13);
+    Ok(PayPeriod {
+        key: start.format("%Y-%m-%d").to_string(),
+        label: format!("{} – {}", start.format("%-d %b"), end.format("%-d %b %Y")),
+        start_date: start.format("%Y-%m-%d").to_string(),
+        end_date: end.format("%Y-%m-%d").to_string(),
+    })
+}
+
+/// Every period overlapping `start`..=`end`, in order
+pub fn between(settings: &PeriodSettings, start: NaiveDate, end: NaiveDate) -> Result<Vec<PayPeriod>, String> {
+    let mut periods = Vec::new();
+    let mut date = start;
+    while date <= end {
+        let period = containing(settings, date)?;
+        date = parse_date(&period.end_date)? + Duration::days(1);
+        periods.push(period);
+    }
+    Ok(periods)
+}
+
+/// First day of the week `date` falls in
+pub fn week_start(settings: &PeriodSettings, date: NaiveDate) -> NaiveDate {
+    let offset = (date.weekday().num_days_from_sunday() + 7 - settings.first_day_of_week % 7) % 7;
+    date - Duration::days(i64::from(offset))
+}
+
+/// Register `pay_period(date)` on a connection. Returns NULL for anything
+/// that isn't a YYYY-MM-DD date (or a timestamp starting with one).
+pub fn register_sql_function(conn: &Connection, settings: &PeriodSettings) -> Result<(), String> {
+    let flags = FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC;
+    let settings = settings.clone();
+    conn.create_scalar_function("pay_period", 1, flags, move |ctx| {
+        let value: Option<String> = ctx.get(0)?;
+        Ok(value
+            .and_then(|v| NaiveDate::parse_from_str(v.get(..10)?, "%Y-%m-%d").ok())
+            .and_then(|d| containing(&settings, d).ok())
+            .map(|p| p.key))
+    })
+    .map_err(|e| format!("Failed to register pay_period: {}", e))
+}
@@ -0,0 +1,54 @@
+//! Tauri command handlers for reporting periods.
+
+use chrono::Local;
+
+use super::calc::{self, parse_date, SETTINGS_KEY};
+use super::types::*;
+use crate::db;
+
+#[tauri::command]
+pub async fn get_period_settings(app: tauri::AppHandle) -> Result<PeriodSettings, String> {
+    calc::load_settings(&*db::open(&app)?)
+}
+
+#[tauri::command]
+pub async fn save_period_settings(app: tauri::AppHandle, settings: PeriodSettings) -> Result<(), String> {
+    calc::validate(&settings)?;
+    let json = serde_json::to_string(&settings).map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    log::info!(
+        "[periods::cmd] {} periods (start day {}, anchor {:?}), weeks start on day {}",
+        settings.kind,
+        settings.start_day,
+        settings.anchor_date,
+        settings.first_day_of_week
+    );
+    db::set_setting(&*db::open(&app)?, SETTINGS_KEY, &json)
+}
+
+/// The period a date falls in; today's when `date` is omitted
+#[tauri::command]
+pub async fn get_pay_period(app: tauri::AppHandle, date: Option<String>) -> Result<PayPeriod, String> {
+    let date = match date {
+        Some(date) => parse_date(&date)?,
+        None => Local::now().date_naive(),
+    };
+    calc::containing(&calc::load_settings(&*db::open(&app)?)?, date)
+}
+
+/// Every period overlapping a date range, for period pickers
+#[tauri::command]
+pub async fn list_pay_periods(
+    app: tauri::AppHandle,
+    start_date: String,
+    end_date: String,
+) -> Result<Vec<PayPeriod>, String> {
+    let start = parse_date(&start_date)?;
+    let end = parse_date(&end_date)?;
+    if end < start {
+        return Err("End date must not be before start date".to_string());
+    }
+    if (end - start).num_days() > 366 * 10 {
+        return Err("Date range must be at most 10 years".to_string());
+    }
+    calc::between(&calc::load_settings(&*db::open(&app)?)?, start, end)
+}
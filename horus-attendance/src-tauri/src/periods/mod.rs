@@ -0,0 +1,15 @@
+//! Reporting periods
+//!
+//! Payroll doesn't always run on calendar months. The `periods` setting
+//! picks how a "month" is cut: calendar months, months starting on a fixed
+//! day (e.g. the 26th to the 25th, named after the month they end in), or
+//! bi-weekly periods counted from an anchor date. It also holds the first
+//! day of the week for weekly reports.
+//! Monthly registers and closure emails take a year and month and cover
+//! that month's period; bi-weekly periods aren't month-shaped, so those
+//! stay on calendar months while period totals, pickers and period locks
+//! use the fortnights.
+
+pub mod calc;
+pub mod commands;
+pub mod types;
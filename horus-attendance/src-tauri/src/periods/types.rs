@@ -0,0 +1,45 @@
+//! Reporting period data types for Tauri command serialization
+
+use serde::{Deserialize, Serialize};
+
+pub const KIND_CALENDAR_MONTH: &str = "calendar_month";
+pub const KIND_CUSTOM_START: &str = "custom_start";
+pub const KIND_BIWEEKLY: &str = "biweekly";
+
+/// Reporting period settings (`periods` setting)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PeriodSettings {
+    /// First day of the week, 0 = Sunday to 6 = Saturday
+    pub first_day_of_week: u32,
+    /// "calendar_month", "custom_start" or "biweekly"
+    pub kind: String,
+    /// Day of the month periods start on (1-28), for "custom_start"
+    pub start_day: u32,
+    /// First day of any one bi-weekly period (YYYY-MM-DD), for "biweekly"
+    pub anchor_date: Option<String>,
+}
+
+impl Default for PeriodSettings {
+    fn default() -> Self {
+        Self {
+            first_day_of_week: 1,
+            kind: KIND_CALENDAR_MONTH.to_string(),
+            start_day: 1,
+            anchor_date: None,
+        }
+    }
+}
+
+/// One reporting period
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PayPeriod {
+    /// "YYYY-MM" for monthly periods (the month they end in), the start
+    /// date for bi-weekly ones
+    pub key: String,
+    /// e.g. "October 2026" or "October 2026 (26 Sep – 25 Oct)"
+    pub label: String,
+    pub start_date: String,
+    pub end_date: String,
+}
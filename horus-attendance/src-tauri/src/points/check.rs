@@ -0,0 +1,128 @@
+//! Alerting users who reach a points threshold
+
+use chrono::{Local, Utc};
+use rusqlite::Connection;
+use tauri::Emitter;
+
+use super::store;
+use super::types::*;
+use crate::deliveries;
+use crate::deliveries::types::NewDelivery;
+
+/// Payload kind of threshold alerts in the delivery log
+pub const DELIVERY_KIND: &str = "points_threshold";
+
+/// Event name of the in-app notification
+pub const THRESHOLD_EVENT: &str = "points-threshold";
+
+fn webhook_body(alert: &PointsAlert, window_start: &str, window_end: &str) -> serde_json::Value {
+    serde_json::json!({
+        "event": "attendance_points.threshold",
+        "userId": alert.user_id,
+        "displayName": alert.display_name,
+        "points": alert.points,
+        "thresholdPoints": alert.threshold.points,
+        "thresholdLabel": alert.threshold.label,
+        "windowStart": window_start,
+        "windowEnd": window_end,
+    })
+}
+
+/// Post one alert to the webhook, logged as a delivery; returns whether it
+/// went through. Blocks on the network.
+fn deliver(conn: &Connection, url: &str, alert: &PointsAlert, body: serde_json::Value) -> Result<bool, String> {
+    let id = deliveries::store::begin(
+        conn,
+        &NewDelivery {
+            channel: deliveries::store::CHANNEL_WEBHOOK,
+            target: url,
+            summary: format!("{} reached {} ({} points)", alert.display_name, alert.threshold.label, alert.points),
+            payload: serde_json::json!({ "kind": DELIVERY_KIND, "body": body }),
+        },
+    )?;
+    let sent = deliveries::webhook::post(url, &body);
+    deliveries::store::finish(conn, id, &sent)?;
+    if let Err(e) = &sent {
+        log::warn!("[points] Alert for {} failed: {}", alert.user_id, e);
+    }
+    Ok(sent.is_ok())
+}
+
+/// Score everyone as of today and find users who reached a threshold they
+/// weren't alerted for; with `notify`, alert them. Blocks on the network.
+pub fn run(app: &tauri::AppHandle, conn: &Connection, settings: &PointsSettings, notify: bool) -> Result<PointsCheck, String> {
+    let today = Local::now().date_naive();
+    let (start, end) = store::window(settings, today);
+    let window_start = start.format("%Y-%m-%d").to_string();
+    let window_end = end.format("%Y-%m-%d").to_string();
+    let alerted = store::alerted(conn)?;
+
+    let mut check = PointsCheck {
+        checked_at: Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        window_start,
+        window_end,
+        over_threshold: 0,
+        alerts: Vec::new(),
+        failed_deliveries: 0,
+    };
+    for (score, _) in store::scores(conn, settings, today, None, None)? {
+        store::clear_recovered(conn, &score.user_id, score.points)?;
+        if score.threshold.is_none() {
+            continue;
+        }
+        check.over_threshold += 1;
+        let known = alerted.get(&score.user_id).map(Vec::as_slice).unwrap_or_default();
+        let mut reached: Vec<&PointsThreshold> = settings
+            .thresholds
+            .iter()
+            .filter(|t| score.points >= t.points && !known.contains(&t.points))
+            .collect();
+        if reached.is_empty() {
+            continue;
+        }
+        reached.sort_by(|a, b| a.points.total_cmp(&b.points));
+        let alert = PointsAlert {
+            user_id: score.user_id.clone(),
+            display_name: score.display_name.clone(),
+            threshold: reached[reached.len() - 1].clone(),
+            points: score.points,
+        };
+        if notify {
+            // One alert for the highest threshold reached; lower ones skipped
+            // on the way are recorded so they don't alert later
+            for threshold in &reached {
+                store::record_alert(
+                    conn,
+                    &PointsAlert {
+                        threshold: (*threshold).clone(),
+                        ..alert.clone()
+                    },
+                )?;
+            }
+            log::info!(
+                "[points] {} reached {} with {} points",
+                alert.display_name,
+                alert.threshold.label,
+                alert.points
+            );
+            if let Err(e) = app.emit(THRESHOLD_EVENT, &alert) {
+                log::warn!("[points] Failed to emit {}: {}", THRESHOLD_EVENT, e);
+            }
+            let url = settings.webhook_url.trim();
+            if !url.is_empty() {
+                let body = webhook_body(&alert, &check.window_start, &check.window_end);
+                check.failed_deliveries += u32::from(!deliver(conn, url, &alert, body)?);
+            }
+        }
+        check.alerts.push(alert);
+    }
+    Ok(check)
+}
+
+/// Send a logged threshold alert again. Blocks on the network.
+pub fn redeliver(channel: &str, target: &str, payload: &serde_json::Value) -> Result<(), String> {
+    match channel {
+        deliveries::store::CHANNEL_WEBHOOK => deliveries::webhook::post(target, &payload["body"]),
+        other => Err(format!("Points alerts aren't sent by {}", other)),
+    }
+}
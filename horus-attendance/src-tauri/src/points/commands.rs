@@ -0,0 +1,155 @@
+//! Tauri command handlers for attendance points.
+
+use chrono::{Local, NaiveDate};
+
+use super::check;
+use super::store::{self, SETTINGS_KEY};
+use super::types::*;
+use crate::activity::registry;
+use crate::db;
+use crate::deliveries::webhook;
+use crate::jobs;
+
+/// How often the scheduled job checks whether today's check is due
+const CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(3600);
+
+/// ID of the scheduled check in the jobs registry
+pub const JOB_ID: &str = "attendance_points";
+
+fn as_of_date(as_of: Option<&str>) -> Result<NaiveDate, String> {
+    match as_of {
+        Some(date) => NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_| format!("Invalid date (expected YYYY-MM-DD): {}", date)),
+        None => Ok(Local::now().date_naive()),
+    }
+}
+
+#[tauri::command]
+pub async fn get_points_settings(app: tauri::AppHandle) -> Result<PointsSettings, String> {
+    store::load_settings(&*db::open(&app)?)
+}
+
+#[tauri::command]
+pub async fn save_points_settings(app: tauri::AppHandle, mut settings: PointsSettings) -> Result<(), String> {
+    store::validate_settings(&settings)?;
+    if !settings.webhook_url.trim().is_empty() {
+        webhook::validate_url(&settings.webhook_url)?;
+    }
+    settings.thresholds.sort_by(|a, b| a.points.total_cmp(&b.points));
+    let json = serde_json::to_string(&settings).map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    log::info!(
+        "[points::cmd] Attendance points {} ({} day window, {} thresholds)",
+        if settings.enabled { "on" } else { "off" },
+        settings.window_days,
+        settings.thresholds.len()
+    );
+    db::set_setting(&*db::open(&app)?, SETTINGS_KEY, &json)
+}
+
+/// Everyone's score over the window ending on `asOf` (today by default),
+/// highest first
+#[tauri::command]
+pub async fn get_attendance_points(
+    app: tauri::AppHandle,
+    as_of: Option<String>,
+    department_id: Option<String>,
+) -> Result<PointsReport, String> {
+    let as_of = as_of_date(as_of.as_deref())?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let conn = db::open(&app)?;
+        let settings = store::load_settings(&conn)?;
+        let (start, end) = store::window(&settings, as_of);
+        let users = store::scores(&conn, &settings, as_of, department_id.as_deref(), None)?
+            .into_iter()
+            .map(|(score, _)| score)
+            .collect();
+        Ok(PointsReport {
+            window_start: start.format("%Y-%m-%d").to_string(),
+            window_end: end.format("%Y-%m-%d").to_string(),
+            users,
+        })
+    })
+    .await
+    .map_err(|e| format!("Points task failed: {}", e))?
+}
+
+/// One user's score and the infractions behind it
+#[tauri::command]
+pub async fn get_user_points(
+    app: tauri::AppHandle,
+    user_id: String,
+    as_of: Option<String>,
+) -> Result<UserPointsDetail, String> {
+    let as_of = as_of_date(as_of.as_deref())?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let conn = db::open(&app)?;
+        let settings = store::load_settings(&conn)?;
+        let (start, end) = store::window(&settings, as_of);
+        let (score, events) = store::scores(&conn, &settings, as_of, None, Some(&user_id))?
+            .into_iter()
+            .next()
+            .ok_or_else(|| format!("No active user {}", user_id))?;
+        Ok(UserPointsDetail {
+            window_start: start.format("%Y-%m-%d").to_string(),
+            window_end: end.format("%Y-%m-%d").to_string(),
+            score,
+            events,
+        })
+    })
+    .await
+    .map_err(|e| format!("Points task failed: {}", e))?
+}
+
+/// Users over a threshold. With `notify`, those who newly reached one are
+/// alerted as the daily check would.
+#[tauri::command]
+pub async fn check_points_thresholds(app: tauri::AppHandle, notify: Option<bool>) -> Result<PointsCheck, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let conn = db::open(&app)?;
+        let settings = store::load_settings(&conn)?;
+        check::run(&app, &conn, &settings, notify.unwrap_or(false))
+    })
+    .await
+    .map_err(|e| format!("Points task failed: {}", e))?
+}
+
+/// Check thresholds once a day while points are enabled. Runs for the
+/// lifetime of the app.
+pub async fn run_scheduled(app: tauri::AppHandle) {
+    let job = jobs::registry::register(
+        &app,
+        JOB_ID,
+        "Attendance points",
+        "Alerts HR when someone reaches a discipline threshold, once a day",
+        CHECK_INTERVAL,
+    );
+    let handle = app.clone();
+    jobs::registry::drive_blocking(
+        job,
+        // Run now checks again even if today's check is done
+        move |forced| {
+            let conn = db::open(&handle)?;
+            let settings = store::load_settings(&conn)?;
+            let today = Local::now().format("%Y-%m-%d").to_string();
+            let last: Option<String> = db::get_json_setting(&conn, store::LAST_RUN_KEY)?;
+            if !settings.enabled || (!forced && last.as_deref() >= Some(today.as_str())) {
+                return Ok(None);
+            }
+            let _activity = registry::begin(&handle, "Attendance points check")?;
+            let run = check::run(&handle, &conn, &settings, true)?;
+            let json = serde_json::to_string(&today).map_err(|e| format!("Failed to serialize date: {}", e))?;
+            db::set_setting(&conn, store::LAST_RUN_KEY, &json)?;
+            Ok(Some(run))
+        },
+        |run| {
+            if let Some(run) = run.filter(|run| !run.alerts.is_empty()) {
+                log::info!(
+                    "[points] {} users over a threshold, {} newly",
+                    run.over_threshold,
+                    run.alerts.len()
+                );
+            }
+        },
+    )
+    .await;
+}
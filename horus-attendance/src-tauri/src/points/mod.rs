@@ -0,0 +1,18 @@
+//! Attendance points for progressive discipline
+//!
+//! Each late arrival, no-show, early leave and (optionally) incomplete day
+//! costs points, configured under the `attendancePoints` setting, and a
+//! user's score is the sum over a rolling window (90 days by default). HR
+//! sets thresholds such as a verbal warning at 4 points; a daily check
+//! alerts once when a user reaches one (the `points-threshold` event and a
+//! webhook call logged as a delivery) and again only after the user has
+//! dropped below it and reached it anew.
+//!
+//! A no-show is a workday with no summary at all or an absent one, from the
+//! user's first summarized day on; holidays the user observes and future
+//! days don't count.
+
+pub mod check;
+pub mod commands;
+pub mod store;
+pub mod types;
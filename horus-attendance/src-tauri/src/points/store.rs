@@ -0,0 +1,278 @@
+//! Points settings, infractions and scores
+
+use std::collections::HashMap;
+
+use chrono::{Duration, Local, NaiveDate};
+use rusqlite::{params, Connection};
+
+use super::types::*;
+use crate::db;
+use crate::holidays::store::Calendar;
+use crate::summary::engine;
+use crate::summary::rules::{self, is_workday};
+
+/// Settings key of the points settings
+pub const SETTINGS_KEY: &str = "attendancePoints";
+
+/// Settings key holding the date of the last scheduled check (YYYY-MM-DD)
+pub const LAST_RUN_KEY: &str = "attendancePointsLastRun";
+
+pub const MAX_WINDOW_DAYS: u32 = 730;
+
+/// Load the settings, falling back to defaults (check off)
+pub fn load_settings(conn: &Connection) -> Result<PointsSettings, String> {
+    Ok(db::get_json_setting(conn, SETTINGS_KEY)?.unwrap_or_default())
+}
+
+pub fn validate_settings(settings: &PointsSettings) -> Result<(), String> {
+    if !(1..=MAX_WINDOW_DAYS).contains(&settings.window_days) {
+        return Err(format!("The window must be between 1 and {} days", MAX_WINDOW_DAYS));
+    }
+    let points = [
+        ("Late", settings.late_points),
+        ("Absence", settings.absence_points),
+        ("Early leave", settings.early_leave_points),
+        ("Incomplete day", settings.incomplete_points),
+    ];
+    for (name, value) in points {
+        if !(0.0..=100.0).contains(&value) {
+            return Err(format!("{} points must be between 0 and 100", name));
+        }
+    }
+    for threshold in &settings.thresholds {
+        if threshold.points <= 0.0 || !threshold.points.is_finite() {
+            return Err("Thresholds must be above 0 points".to_string());
+        }
+        if threshold.label.trim().is_empty() {
+            return Err(format!("The threshold at {} points needs a label", threshold.points));
+        }
+    }
+    Ok(())
+}
+
+/// First and last day of the window ending on `as_of`
+pub fn window(settings: &PointsSettings, as_of: NaiveDate) -> (NaiveDate, NaiveDate) {
+    (as_of - Duration::days(settings.window_days.max(1) as i64 - 1), as_of)
+}
+
+/// A user's infractions between two dates
+pub struct UserInfractions {
+    pub user_id: String,
+    pub display_name: String,
+    pub employee_code: Option<String>,
+    pub department_id: Option<String>,
+    pub department_name: Option<String>,
+    /// First day the user has a summary for, before which nothing counts
+    pub first_day: Option<String>,
+    /// Workdays without punches are no-shows (employees only)
+    pub counts_absences: bool,
+    /// (date, infraction), by date
+    pub infractions: Vec<(String, Infraction)>,
+}
+
+/// Late arrivals, no-shows, early leaves and incomplete days of active
+/// users between two dates (inclusive), optionally of one department or
+/// user. Only days before today can be no-shows.
+pub fn infractions(
+    conn: &Connection,
+    start: NaiveDate,
+    end: NaiveDate,
+    department_id: Option<&str>,
+    user_id: Option<&str>,
+) -> Result<Vec<UserInfractions>, String> {
+    let start_date = start.format("%Y-%m-%d").to_string();
+    let end_date = end.format("%Y-%m-%d").to_string();
+    let attendance_rules = engine::load_rules(conn)?;
+    let holidays = Calendar::load(conn, &start_date, &end_date)?;
+    let today = Local::now().date_naive();
+
+    // (user_id, date) -> (status, late, early, incomplete)
+    type DayRow = (String, i64, i64, bool);
+    let summaries: HashMap<(String, String), DayRow> = {
+        let mut stmt = conn
+            .prepare(
+                "SELECT user_id, date, status, late_minutes, early_minutes, is_incomplete
+                 FROM attendance_day_summary
+                 WHERE date >= ?1 AND date <= ?2",
+            )
+            .map_err(|e| format!("Failed to query summaries: {}", e))?;
+        let rows = stmt
+            .query_map(params![start_date, end_date], |row| {
+                Ok((
+                    (row.get(0)?, row.get(1)?),
+                    (row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?),
+                ))
+            })
+            .map_err(|e| format!("Failed to query summaries: {}", e))?;
+        rows.collect::<Result<_, _>>()
+            .map_err(|e| format!("Failed to read summaries: {}", e))?
+    };
+
+    let mut users: Vec<UserInfractions> = {
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT u.id, u.display_name, u.employee_code, u.department_id, d.name,
+                        (SELECT MIN(s.date) FROM attendance_day_summary s WHERE s.user_id = u.id),
+                        {counts_absences}
+                 FROM users u
+                 LEFT JOIN departments d ON d.id = u.department_id
+                 WHERE u.status = 'active' AND (?1 IS NULL OR u.department_id = ?1) AND (?2 IS NULL OR u.id = ?2)
+                 ORDER BY u.display_name",
+                counts_absences = db::users::counts_absences_sql("u"),
+            ))
+            .map_err(|e| format!("Failed to query users: {}", e))?;
+        let rows = stmt
+            .query_map(params![department_id, user_id], |row| {
+                Ok(UserInfractions {
+                    user_id: row.get(0)?,
+                    display_name: row.get(1)?,
+                    employee_code: row.get(2)?,
+                    department_id: row.get(3)?,
+                    department_name: row.get(4)?,
+                    first_day: row.get(5)?,
+                    counts_absences: row.get(6)?,
+                    infractions: Vec::new(),
+                })
+            })
+            .map_err(|e| format!("Failed to query users: {}", e))?;
+        rows.collect::<Result<_, _>>()
+            .map_err(|e| format!("Failed to read users: {}", e))?
+    };
+
+    let dates: Vec<(NaiveDate, String)> = start
+        .iter_days()
+        .take_while(|d| *d <= end)
+        .map(|d| (d, d.format("%Y-%m-%d").to_string()))
+        .collect();
+    for user in &mut users {
+        let Some(first_day) = user.first_day.clone() else {
+            continue;
+        };
+        for (date, key) in &dates {
+            if *key < first_day {
+                continue;
+            }
+            match summaries.get(&(user.user_id.clone(), key.clone())) {
+                Some((status, _, _, _)) if status == rules::STATUS_HOLIDAY || status == rules::STATUS_WEEKEND => {}
+                Some((status, _, _, _)) if status == rules::STATUS_ABSENT => {
+                    user.infractions.push((key.clone(), Infraction::Absence));
+                }
+                Some((_, late, early, incomplete)) => {
+                    if *late > 0 {
+                        user.infractions.push((key.clone(), Infraction::Late));
+                    }
+                    if *early > 0 {
+                        user.infractions.push((key.clone(), Infraction::EarlyLeave));
+                    }
+                    if *incomplete {
+                        user.infractions.push((key.clone(), Infraction::Incomplete));
+                    }
+                }
+                None if user.counts_absences
+                    && *date < today
+                    && is_workday(key, &attendance_rules)
+                    && !holidays.observes(&user.user_id, key) =>
+                {
+                    user.infractions.push((key.clone(), Infraction::Absence));
+                }
+                None => {}
+            }
+        }
+    }
+    Ok(users)
+}
+
+fn points_for(settings: &PointsSettings, infraction: Infraction) -> f64 {
+    match infraction {
+        Infraction::Late => settings.late_points,
+        Infraction::Absence => settings.absence_points,
+        Infraction::EarlyLeave => settings.early_leave_points,
+        Infraction::Incomplete => settings.incomplete_points,
+    }
+}
+
+/// Scores over the window ending on `as_of`, highest first, with the
+/// events that make them up
+pub fn scores(
+    conn: &Connection,
+    settings: &PointsSettings,
+    as_of: NaiveDate,
+    department_id: Option<&str>,
+    user_id: Option<&str>,
+) -> Result<Vec<(UserPoints, Vec<PointsEvent>)>, String> {
+    let (start, end) = window(settings, as_of);
+    let mut scored: Vec<(UserPoints, Vec<PointsEvent>)> = infractions(conn, start, end, department_id, user_id)?
+        .into_iter()
+        .map(|user| {
+            let events: Vec<PointsEvent> = user
+                .infractions
+                .into_iter()
+                .map(|(date, infraction)| PointsEvent {
+                    date,
+                    infraction,
+                    points: points_for(settings, infraction),
+                })
+                .filter(|e| e.points > 0.0)
+                .collect();
+            let count = |kind: Infraction| events.iter().filter(|e| e.infraction == kind).count() as u32;
+            let points = events.iter().map(|e| e.points).sum::<f64>();
+            let threshold = settings
+                .thresholds
+                .iter()
+                .filter(|t| points >= t.points)
+                .max_by(|a, b| a.points.total_cmp(&b.points))
+                .cloned();
+            let score = UserPoints {
+                user_id: user.user_id,
+                display_name: user.display_name,
+                employee_code: user.employee_code,
+                department_name: user.department_name,
+                points,
+                late_days: count(Infraction::Late),
+                absences: count(Infraction::Absence),
+                early_leaves: count(Infraction::EarlyLeave),
+                incomplete_days: count(Infraction::Incomplete),
+                threshold,
+            };
+            (score, events)
+        })
+        .collect();
+    scored.sort_by(|a, b| b.0.points.total_cmp(&a.0.points).then_with(|| a.0.display_name.cmp(&b.0.display_name)));
+    Ok(scored)
+}
+
+/// Thresholds each user has been alerted for and is still at
+pub fn alerted(conn: &Connection) -> Result<HashMap<String, Vec<f64>>, String> {
+    let mut stmt = conn
+        .prepare("SELECT user_id, threshold FROM attendance_point_alerts")
+        .map_err(|e| format!("Failed to query point alerts: {}", e))?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?)))
+        .map_err(|e| format!("Failed to query point alerts: {}", e))?;
+    let mut alerted: HashMap<String, Vec<f64>> = HashMap::new();
+    for row in rows {
+        let (user_id, threshold) = row.map_err(|e| format!("Failed to read point alerts: {}", e))?;
+        alerted.entry(user_id).or_default().push(threshold);
+    }
+    Ok(alerted)
+}
+
+/// Forget alerts of thresholds a user has dropped below
+pub fn clear_recovered(conn: &Connection, user_id: &str, points: f64) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM attendance_point_alerts WHERE user_id = ?1 AND threshold > ?2",
+        params![user_id, points],
+    )
+    .map_err(|e| format!("Failed to clear point alerts: {}", e))?;
+    Ok(())
+}
+
+/// Record that a user was alerted for reaching a threshold
+pub fn record_alert(conn: &Connection, alert: &PointsAlert) -> Result<(), String> {
+    conn.execute(
+        "INSERT OR REPLACE INTO attendance_point_alerts (user_id, threshold, label, points) VALUES (?1, ?2, ?3, ?4)",
+        params![alert.user_id, alert.threshold.points, alert.threshold.label, alert.points],
+    )
+    .map_err(|e| format!("Failed to record point alert: {}", e))?;
+    Ok(())
+}
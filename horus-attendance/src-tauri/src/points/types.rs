@@ -0,0 +1,138 @@
+//! Attendance points data types for Tauri command serialization
+
+use serde::{Deserialize, Serialize};
+
+/// A score at which HR takes the next disciplinary step
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PointsThreshold {
+    pub points: f64,
+    /// e.g. "Written warning"
+    pub label: String,
+}
+
+/// Points settings (`attendancePoints` setting)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PointsSettings {
+    /// Whether the daily threshold check runs; scores can be viewed either way
+    pub enabled: bool,
+    pub late_points: f64,
+    pub absence_points: f64,
+    pub early_leave_points: f64,
+    /// Days with a single punch
+    pub incomplete_points: f64,
+    /// Days a point counts for, ending today
+    pub window_days: u32,
+    pub thresholds: Vec<PointsThreshold>,
+    /// URL posted a JSON alert when a user reaches a threshold; empty for none
+    pub webhook_url: String,
+}
+
+impl Default for PointsSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            late_points: 1.0,
+            absence_points: 3.0,
+            early_leave_points: 1.0,
+            incomplete_points: 0.0,
+            window_days: 90,
+            thresholds: vec![
+                PointsThreshold {
+                    points: 4.0,
+                    label: "Verbal warning".to_string(),
+                },
+                PointsThreshold {
+                    points: 6.0,
+                    label: "Written warning".to_string(),
+                },
+                PointsThreshold {
+                    points: 8.0,
+                    label: "Final warning".to_string(),
+                },
+            ],
+            webhook_url: String::new(),
+        }
+    }
+}
+
+/// What cost a user points on a day
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Infraction {
+    Late,
+    Absence,
+    EarlyLeave,
+    Incomplete,
+}
+
+/// One infraction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PointsEvent {
+    pub date: String,
+    pub infraction: Infraction,
+    pub points: f64,
+}
+
+/// A user's score over the window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserPoints {
+    pub user_id: String,
+    pub display_name: String,
+    pub employee_code: Option<String>,
+    pub department_name: Option<String>,
+    pub points: f64,
+    pub late_days: u32,
+    pub absences: u32,
+    pub early_leaves: u32,
+    pub incomplete_days: u32,
+    /// Highest threshold reached
+    pub threshold: Option<PointsThreshold>,
+}
+
+/// Everyone's scores, highest first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PointsReport {
+    pub window_start: String,
+    pub window_end: String,
+    pub users: Vec<UserPoints>,
+}
+
+/// One user's score and what made it up
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserPointsDetail {
+    pub window_start: String,
+    pub window_end: String,
+    pub score: UserPoints,
+    pub events: Vec<PointsEvent>,
+}
+
+/// A user who reached a threshold
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PointsAlert {
+    pub user_id: String,
+    pub display_name: String,
+    pub threshold: PointsThreshold,
+    pub points: f64,
+}
+
+/// Result of one threshold check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PointsCheck {
+    pub checked_at: String,
+    pub window_start: String,
+    pub window_end: String,
+    /// Users at or above a threshold
+    pub over_threshold: u32,
+    /// Thresholds newly reached, highest per user
+    pub alerts: Vec<PointsAlert>,
+    /// Webhook calls that failed; they are retried from the delivery log
+    pub failed_deliveries: u32,
+}
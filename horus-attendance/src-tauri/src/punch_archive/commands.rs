@@ -0,0 +1,133 @@
+//! Tauri command handlers for the punch archive.
+
+use chrono::Local;
+
+use super::store::{self, SETTINGS_KEY};
+use super::types::*;
+use super::writer;
+use crate::db;
+use crate::jobs;
+
+/// How often newly ingested punches are appended
+const APPEND_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10 * 60);
+
+/// Most punches appended per write
+const BATCH_ROWS: u32 = 5000;
+
+/// ID of the archive job in the jobs registry
+pub const JOB_ID: &str = "punch_archive";
+
+/// Held while appending, so a manual run and the job don't both append the
+/// same punches
+static APPENDING: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+#[tauri::command]
+pub async fn get_punch_archive_settings(app: tauri::AppHandle) -> Result<PunchArchiveSettings, String> {
+    store::load_settings(&*db::open(&app)?)
+}
+
+#[tauri::command]
+pub async fn save_punch_archive_settings(
+    app: tauri::AppHandle,
+    mut settings: PunchArchiveSettings,
+) -> Result<(), String> {
+    if settings.format != FORMAT_NDJSON && settings.format != FORMAT_CSV {
+        return Err(format!("Unknown archive format: {}", settings.format));
+    }
+    settings.directory = settings.directory.map(|d| d.trim().to_string()).filter(|d| !d.is_empty());
+    // Fail now rather than on every scheduled run
+    let dir = writer::archive_dir(&app, &settings)?;
+    let json = serde_json::to_string(&settings).map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    log::info!(
+        "[punch_archive::cmd] Archive {} ({} to {})",
+        if settings.enabled { "enabled" } else { "disabled" },
+        settings.format,
+        dir.display()
+    );
+    db::set_setting(&*db::open(&app)?, SETTINGS_KEY, &json)
+}
+
+/// Append every punch not archived yet to this month's file
+fn append_pending(app: &tauri::AppHandle) -> Result<ArchiveRun, String> {
+    let _appending = APPENDING.lock().map_err(|_| "Punch archive lock is poisoned".to_string())?;
+    let mut conn = db::open(app)?;
+    let settings = store::load_settings(&conn)?;
+    let mut run = ArchiveRun {
+        path: None,
+        rows_appended: 0,
+        sha256: None,
+    };
+    if !settings.enabled {
+        return Ok(run);
+    }
+    let dir = writer::archive_dir(app, &settings)?;
+    let path = writer::file_path(&dir, &Local::now().format("%Y-%m").to_string(), &settings.format);
+    let file = path.to_string_lossy().into_owned();
+    loop {
+        let punches = store::pending(&conn, BATCH_ROWS)?;
+        if punches.is_empty() {
+            break;
+        }
+        run.sha256 = Some(writer::append(&path, &settings.format, &punches)?);
+        store::mark(&mut conn, &punches, &file)?;
+        run.rows_appended += punches.len() as u64;
+        run.path = Some(file.clone());
+        if punches.len() < BATCH_ROWS as usize {
+            break;
+        }
+    }
+    Ok(run)
+}
+
+/// Append pending punches now rather than on the next scheduled run.
+/// Does nothing while the archive is disabled.
+#[tauri::command]
+pub async fn run_punch_archive(app: tauri::AppHandle) -> Result<ArchiveRun, String> {
+    tauri::async_runtime::spawn_blocking(move || append_pending(&app))
+        .await
+        .map_err(|e| format!("Archive task failed: {}", e))?
+}
+
+/// The archive files, newest month first, each checked against its checksum
+#[tauri::command]
+pub async fn list_punch_archive_files(app: tauri::AppHandle) -> Result<Vec<ArchiveFile>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let settings = store::load_settings(&*db::open(&app)?)?;
+        writer::list(&writer::archive_dir(&app, &settings)?)
+    })
+    .await
+    .map_err(|e| format!("Archive task failed: {}", e))?
+}
+
+/// Punches not in the archive yet
+#[tauri::command]
+pub async fn get_punch_archive_backlog(app: tauri::AppHandle) -> Result<u64, String> {
+    store::pending_count(&*db::open(&app)?)
+}
+
+/// Append newly ingested punches every few minutes while the archive is
+/// enabled. Runs for the lifetime of the app.
+pub async fn run_scheduled(app: tauri::AppHandle) {
+    let job = jobs::registry::register(
+        &app,
+        JOB_ID,
+        "Punch archive",
+        "Appends newly ingested punches to this month's plain-text archive file",
+        APPEND_INTERVAL,
+    );
+    let handle = app.clone();
+    jobs::registry::drive_blocking(
+        job,
+        move |_| append_pending(&handle),
+        |run| {
+            if run.rows_appended > 0 {
+                log::info!(
+                    "[punch_archive] Appended {} punches to {}",
+                    run.rows_appended,
+                    run.path.as_deref().unwrap_or_default()
+                );
+            }
+        },
+    )
+    .await;
+}
@@ -0,0 +1,18 @@
+//! Plain-text archive of ingested punches
+//!
+//! When the `punchArchive` setting is enabled, a background job appends
+//! every punch stored since its last run to a monthly file in
+//! Documents/HorusAttendance/archive (or a chosen folder), as NDJSON or CSV.
+//! Files are only ever appended to and a new one starts each month; a
+//! `.sha256` file next to each (in `sha256sum` format) is rewritten after
+//! every append, so a closed month's checksum is final. The files can be
+//! read with any tool and survive losing the database.
+//! Which punches were archived is kept in `punch_archive_entries`. A punch
+//! is marked after its line is flushed to disk, so a crash in between
+//! appends it again on the next run rather than losing it; readers should
+//! treat the punch ID as the key.
+
+pub mod commands;
+pub mod store;
+pub mod types;
+pub mod writer;
@@ -0,0 +1,80 @@
+//! Punch archive settings and which punches were archived
+
+use rusqlite::{params, Connection};
+
+use super::types::*;
+use crate::db;
+
+pub const SETTINGS_KEY: &str = "punchArchive";
+
+pub fn load_settings(conn: &Connection) -> Result<PunchArchiveSettings, String> {
+    Ok(db::get_json_setting(conn, SETTINGS_KEY)?.unwrap_or_default())
+}
+
+/// Up to `limit` punches not archived yet, oldest first
+pub fn pending(conn: &Connection, limit: u32) -> Result<Vec<ArchivedPunch>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT l.id, l.device_id, l.device_user_id, l.timestamp, l.verify_type, l.punch_type, l.source,
+                    l.latitude, l.longitude, l.note, l.raw_payload, l.created_at
+             FROM attendance_logs_raw l
+             LEFT JOIN punch_archive_entries a ON a.log_id = l.id
+             WHERE a.log_id IS NULL
+             ORDER BY l.timestamp, l.id
+             LIMIT ?1",
+        )
+        .map_err(|e| format!("Failed to query unarchived logs: {}", e))?;
+    let rows = stmt
+        .query_map([limit], |row| {
+            Ok(ArchivedPunch {
+                id: row.get(0)?,
+                device_id: row.get(1)?,
+                device_user_id: row.get(2)?,
+                timestamp: row.get(3)?,
+                verify_type: row.get(4)?,
+                punch_type: row.get(5)?,
+                source: row.get(6)?,
+                latitude: row.get(7)?,
+                longitude: row.get(8)?,
+                note: row.get(9)?,
+                raw_payload: row.get(10)?,
+                created_at: row.get(11)?,
+            })
+        })
+        .map_err(|e| format!("Failed to query unarchived logs: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read unarchived logs: {}", e))?;
+    Ok(rows)
+}
+
+/// Record punches as appended to `file`
+pub fn mark(conn: &mut Connection, punches: &[ArchivedPunch], file: &str) -> Result<(), String> {
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    {
+        let mut insert = tx
+            .prepare("INSERT OR IGNORE INTO punch_archive_entries (log_id, file) VALUES (?1, ?2)")
+            .map_err(|e| format!("Failed to record archived logs: {}", e))?;
+        for punch in punches {
+            insert
+                .execute(params![punch.id, file])
+                .map_err(|e| format!("Failed to record archived logs: {}", e))?;
+        }
+    }
+    tx.commit()
+        .map_err(|e| format!("Failed to record archived logs: {}", e))
+}
+
+/// Punches not archived yet
+pub fn pending_count(conn: &Connection) -> Result<u64, String> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM attendance_logs_raw l
+         LEFT JOIN punch_archive_entries a ON a.log_id = l.id
+         WHERE a.log_id IS NULL",
+        [],
+        |row| row.get::<_, i64>(0),
+    )
+    .map(|n| n as u64)
+    .map_err(|e| format!("Failed to count unarchived logs: {}", e))
+}
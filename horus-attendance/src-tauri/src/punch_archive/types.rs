@@ -0,0 +1,70 @@
+//! Punch archive data types for Tauri command serialization
+
+use serde::{Deserialize, Serialize};
+
+pub const FORMAT_NDJSON: &str = "ndjson";
+pub const FORMAT_CSV: &str = "csv";
+
+/// Punch archive settings (`punchArchive` setting)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PunchArchiveSettings {
+    pub enabled: bool,
+    /// "ndjson" or "csv"
+    pub format: String,
+    /// Folder for the files; Documents/HorusAttendance/archive when unset
+    pub directory: Option<String>,
+}
+
+impl Default for PunchArchiveSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            format: FORMAT_NDJSON.to_string(),
+            directory: None,
+        }
+    }
+}
+
+/// A punch as written to the archive
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchivedPunch {
+    pub id: String,
+    pub device_id: String,
+    pub device_user_id: String,
+    pub timestamp: String,
+    pub verify_type: Option<i64>,
+    pub punch_type: Option<i64>,
+    pub source: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub note: Option<String>,
+    pub raw_payload: Option<String>,
+    pub created_at: String,
+}
+
+/// Outcome of appending pending punches
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveRun {
+    /// File appended to, if there was anything to append
+    pub path: Option<String>,
+    pub rows_appended: u64,
+    pub sha256: Option<String>,
+}
+
+/// A monthly archive file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveFile {
+    pub path: String,
+    /// YYYY-MM the punches were ingested in
+    pub month: String,
+    pub format: String,
+    pub file_size: u64,
+    /// Checksum recorded next to the file
+    pub sha256: Option<String>,
+    /// The file still matches its recorded checksum
+    pub checksum_ok: Option<bool>,
+}
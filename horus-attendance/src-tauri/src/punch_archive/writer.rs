@@ -0,0 +1,135 @@
+//! Appending to the monthly archive files and their checksums
+
+use std::fs::{self, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+
+use super::types::*;
+use crate::signing::store::hash_file;
+use crate::summary::history::csv_field;
+
+const CSV_HEADER: &str = "id,device_id,device_user_id,timestamp,verify_type,punch_type,source,latitude,longitude,note,raw_payload,created_at";
+
+/// Folder the archive goes to, created if missing. A chosen folder must be
+/// one writes are allowed to.
+pub fn archive_dir(app: &tauri::AppHandle, settings: &PunchArchiveSettings) -> Result<PathBuf, String> {
+    if let Some(dir) = settings.directory.as_deref().filter(|d| !d.trim().is_empty()) {
+        // Resolve a file inside it so the policy sees the real location
+        let probe = crate::path_policy::resolve_write_target(app, &Path::new(dir).join("punches").to_string_lossy())?;
+        return probe
+            .parent()
+            .map(Path::to_path_buf)
+            .ok_or_else(|| format!("Invalid archive folder: {}", dir));
+    }
+    let dir = app
+        .path()
+        .document_dir()
+        .map_err(|e| format!("Failed to get document directory: {}", e))?
+        .join("HorusAttendance")
+        .join("archive");
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create archive directory: {}", e))?;
+    Ok(dir)
+}
+
+/// The file for punches ingested in `month` (YYYY-MM)
+pub fn file_path(dir: &Path, month: &str, format: &str) -> PathBuf {
+    dir.join(format!("punches_{}.{}", month, format))
+}
+
+fn checksum_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".sha256");
+    PathBuf::from(name)
+}
+
+fn csv_line(p: &ArchivedPunch) -> String {
+    let text = |v: &Option<String>| v.as_deref().map(csv_field).unwrap_or_default();
+    let num = |v: Option<i64>| v.map(|n| n.to_string()).unwrap_or_default();
+    let real = |v: Option<f64>| v.map(|n| n.to_string()).unwrap_or_default();
+    [
+        csv_field(&p.id),
+        csv_field(&p.device_id),
+        csv_field(&p.device_user_id),
+        csv_field(&p.timestamp),
+        num(p.verify_type),
+        num(p.punch_type),
+        text(&p.source),
+        real(p.latitude),
+        real(p.longitude),
+        text(&p.note),
+        text(&p.raw_payload),
+        csv_field(&p.created_at),
+    ]
+    .join(",")
+}
+
+/// Append `punches` to `path`, flush them to disk and rewrite the checksum.
+/// Returns the file's new SHA-256.
+pub fn append(path: &Path, format: &str, punches: &[ArchivedPunch]) -> Result<String, String> {
+    let write_err = |e: std::io::Error| format!("Failed to write {}: {}", path.display(), e);
+    let is_new = fs::metadata(path).map(|m| m.len() == 0).unwrap_or(true);
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut out = BufWriter::new(file);
+    if format == FORMAT_CSV && is_new {
+        writeln!(out, "{}", CSV_HEADER).map_err(write_err)?;
+    }
+    for punch in punches {
+        let line = if format == FORMAT_CSV {
+            csv_line(punch)
+        } else {
+            serde_json::to_string(punch).map_err(|e| format!("Failed to serialize punch: {}", e))?
+        };
+        writeln!(out, "{}", line).map_err(write_err)?;
+    }
+    let file = out.into_inner().map_err(|e| write_err(e.into_error()))?;
+    file.sync_all().map_err(write_err)?;
+
+    let sha256 = hash_file(path)?;
+    let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    fs::write(checksum_path(path), format!("{}  {}\n", sha256, name))
+        .map_err(|e| format!("Failed to write checksum: {}", e))?;
+    Ok(sha256)
+}
+
+/// Archive files in `dir`, newest month first, checked against their checksums
+pub fn list(dir: &Path) -> Result<Vec<ArchiveFile>, String> {
+    let mut files = Vec::new();
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(files),
+        Err(e) => return Err(format!("Failed to read {}: {}", dir.display(), e)),
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let Some((month, format)) = name
+            .strip_prefix("punches_")
+            .and_then(|rest| rest.split_once('.'))
+            .filter(|(_, ext)| *ext == FORMAT_NDJSON || *ext == FORMAT_CSV)
+        else {
+            continue;
+        };
+        let recorded = fs::read_to_string(checksum_path(&path))
+            .ok()
+            .and_then(|s| s.split_whitespace().next().map(str::to_string));
+        let checksum_ok = match &recorded {
+            Some(sha) => Some(hash_file(&path)? == *sha),
+            None => None,
+        };
+        files.push(ArchiveFile {
+            path: path.to_string_lossy().into_owned(),
+            month: month.to_string(),
+            format: format.to_string(),
+            file_size: entry.metadata().map(|m| m.len()).unwrap_or(0),
+            sha256: recorded,
+            checksum_ok,
+        });
+    }
+    files.sort_by(|a, b| b.month.cmp(&a.month).then_with(|| a.format.cmp(&b.format)));
+    Ok(files)
+}
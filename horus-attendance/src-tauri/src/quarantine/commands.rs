@@ -0,0 +1,102 @@
+//! Tauri command handlers for reviewing quarantined device records.
+
+use rusqlite::params;
+
+use super::store;
+use super::types::*;
+use super::validate;
+use crate::db;
+use crate::db::logs::DevicePunch;
+
+const STATUSES: &[&str] = &["pending", "repaired", "discarded"];
+
+/// Quarantined records, newest first, optionally by status and device
+#[tauri::command]
+pub async fn list_quarantined(
+    app: tauri::AppHandle,
+    status: Option<String>,
+    device_id: Option<String>,
+) -> Result<Vec<QuarantinedRecord>, String> {
+    if let Some(status) = status.as_deref() {
+        if !STATUSES.contains(&status) {
+            return Err(format!("Unknown status '{}'; expected one of {}", status, STATUSES.join(", ")));
+        }
+    }
+    store::list(&*db::open(&app)?, status.as_deref(), device_id.as_deref())
+}
+
+/// Store a pending record with corrected values. It is validated again
+/// first; summaries of a repaired log's date pick it up when recomputed.
+#[tauri::command]
+pub async fn repair_quarantined(app: tauri::AppHandle, repair: QuarantineRepair) -> Result<QuarantinedRecord, String> {
+    let mut conn = db::open(&app)?;
+    let record = store::get(&conn, repair.id)?.ok_or_else(|| format!("Quarantined record not found: {}", repair.id))?;
+    if record.status != "pending" {
+        return Err(format!("Record {} is already {}", record.id, record.status));
+    }
+    let device_user_id = repair.device_user_id.unwrap_or(record.device_user_id).trim().to_string();
+
+    if record.record_type == "log" {
+        let timestamp = repair.timestamp.or(record.timestamp).unwrap_or_default();
+        let timestamp = validate::parse_timestamp(&timestamp)
+            .ok_or_else(|| format!("Invalid timestamp (expected YYYY-MM-DDTHH:MM:SS): {}", timestamp))?
+            .format(validate::TIMESTAMP_FORMAT)
+            .to_string();
+        if let Some(reason) = validate::check_log(&device_user_id, &timestamp, chrono::Local::now().naive_local()) {
+            return Err(format!("Record is still invalid: {}", reason));
+        }
+        db::logs::insert_from_device(
+            &mut conn,
+            &record.device_id,
+            &[DevicePunch {
+                device_user_id: &device_user_id,
+                timestamp: &timestamp,
+                verify_type: record.verify_type.unwrap_or(0) as u8,
+                punch_type: record.punch_type.unwrap_or(0) as u8,
+            }],
+        )?;
+    } else {
+        let name = repair.user_name.or(record.user_name).unwrap_or_default().trim().to_string();
+        if let Some(reason) = validate::check_user(&device_user_id, &name) {
+            return Err(format!("Record is still invalid: {}", reason));
+        }
+        let display_name = if name.is_empty() { format!("User {}", device_user_id) } else { name.clone() };
+        conn.execute(
+            "INSERT INTO users (id, device_user_id, device_name, display_name) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(device_user_id) DO NOTHING",
+            params![uuid::Uuid::new_v4().to_string(), device_user_id, name, display_name],
+        )
+        .map_err(|e| format!("Failed to save user: {}", e))?;
+    }
+
+    // The device's values stay as they were so the same record is recognised
+    // and not quarantined again on the next sync
+    conn.execute(
+        "UPDATE quarantined_logs SET status = 'repaired', resolved_at = datetime('now') WHERE id = ?1",
+        [record.id],
+    )
+    .map_err(|e| format!("Failed to update quarantined record: {}", e))?;
+    log::info!("[quarantine::cmd] Repaired {} {}", record.record_type, record.id);
+    store::get(&conn, record.id)?.ok_or_else(|| "Quarantined record disappeared".to_string())
+}
+
+/// Mark pending records as discarded. Returns how many were.
+#[tauri::command]
+pub async fn discard_quarantined(app: tauri::AppHandle, ids: Vec<i64>) -> Result<u32, String> {
+    let mut conn = db::open(&app)?;
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    let mut discarded = 0;
+    for id in &ids {
+        discarded += tx
+            .execute(
+                "UPDATE quarantined_logs SET status = 'discarded', resolved_at = datetime('now')
+                 WHERE id = ?1 AND status = 'pending'",
+                [id],
+            )
+            .map_err(|e| format!("Failed to discard record {}: {}", id, e))?;
+    }
+    tx.commit().map_err(|e| format!("Failed to commit: {}", e))?;
+    Ok(discarded as u32)
+}
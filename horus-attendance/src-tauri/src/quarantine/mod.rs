@@ -0,0 +1,12 @@
+//! Quarantine for corrupt device records
+//!
+//! Users and logs read from a device are validated before they reach the
+//! main tables. Records with an empty or garbled user ID, a garbled name or
+//! an impossible timestamp (a failing device clock reads as year 2063, a
+//! zeroed one as 2000-01-01) go to quarantined_logs with a reason code
+//! instead. From there they can be repaired and stored, or discarded.
+
+pub mod commands;
+pub mod store;
+pub mod types;
+pub mod validate;
@@ -0,0 +1,172 @@
+//! Screening device records and storing the rejects
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use super::types::QuarantinedRecord;
+use super::validate;
+use crate::zkteco::types::{AttendanceLog, DeviceUser};
+
+/// A record that failed validation
+struct Rejected<'a> {
+    record_type: &'a str,
+    device_user_id: &'a str,
+    timestamp: &'a str,
+    user_name: &'a str,
+    verify_type: Option<u8>,
+    punch_type: Option<u8>,
+    reason: &'a str,
+}
+
+/// Keep a rejected record for review. Records from a device that isn't
+/// saved can only be dropped.
+fn record(conn: &Connection, device_id: Option<&str>, rejected: &Rejected) -> Result<(), String> {
+    let Some(device_id) = device_id else {
+        log::warn!(
+            "[quarantine] Dropped {} {:?} from an unsaved device: {}",
+            rejected.record_type,
+            rejected.device_user_id,
+            rejected.reason
+        );
+        return Ok(());
+    };
+    conn.execute(
+        "INSERT OR IGNORE INTO quarantined_logs
+             (device_id, record_type, device_user_id, timestamp, user_name, verify_type, punch_type, reason)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            device_id,
+            rejected.record_type,
+            rejected.device_user_id,
+            rejected.timestamp,
+            rejected.user_name,
+            rejected.verify_type,
+            rejected.punch_type,
+            rejected.reason,
+        ],
+    )
+    .map_err(|e| format!("Failed to quarantine {}: {}", rejected.record_type, e))?;
+    Ok(())
+}
+
+/// Remove invalid users from `users`, keeping them for review unless
+/// `store` is false (dry runs). Returns how many were removed.
+pub fn screen_users(
+    conn: &Connection,
+    device_id: Option<&str>,
+    users: &mut Vec<DeviceUser>,
+    store: bool,
+) -> Result<u32, String> {
+    let mut rejected = Vec::new();
+    users.retain(|u| match validate::check_user(&u.device_user_id, &u.device_name) {
+        Some(reason) => {
+            rejected.push((u.clone(), reason));
+            false
+        }
+        None => true,
+    });
+    if store {
+        for (user, reason) in &rejected {
+            record(
+                conn,
+                device_id,
+                &Rejected {
+                    record_type: "user",
+                    device_user_id: &user.device_user_id,
+                    timestamp: "",
+                    user_name: &user.device_name,
+                    verify_type: None,
+                    punch_type: None,
+                    reason,
+                },
+            )?;
+        }
+    }
+    if !rejected.is_empty() {
+        log::warn!("[quarantine] {} device users failed validation", rejected.len());
+    }
+    Ok(rejected.len() as u32)
+}
+
+/// Remove invalid logs from `logs`, keeping them for review unless `store`
+/// is false (dry runs). Returns how many were removed.
+pub fn screen_logs(
+    conn: &Connection,
+    device_id: Option<&str>,
+    logs: &mut Vec<AttendanceLog>,
+    store: bool,
+) -> Result<u32, String> {
+    let now = chrono::Local::now().naive_local();
+    let mut rejected = Vec::new();
+    logs.retain(|l| match validate::check_log(&l.device_user_id, &l.timestamp, now) {
+        Some(reason) => {
+            rejected.push((l.clone(), reason));
+            false
+        }
+        None => true,
+    });
+    if store {
+        for (log, reason) in &rejected {
+            record(
+                conn,
+                device_id,
+                &Rejected {
+                    record_type: "log",
+                    device_user_id: &log.device_user_id,
+                    timestamp: &log.timestamp,
+                    user_name: "",
+                    verify_type: Some(log.verify_type),
+                    punch_type: Some(log.punch_type),
+                    reason,
+                },
+            )?;
+        }
+    }
+    if !rejected.is_empty() {
+        log::warn!("[quarantine] {} device logs failed validation", rejected.len());
+    }
+    Ok(rejected.len() as u32)
+}
+
+const SELECT: &str = "SELECT id, device_id, record_type, device_user_id, timestamp, user_name,
+                             verify_type, punch_type, reason, status, created_at, resolved_at
+                      FROM quarantined_logs";
+
+fn map_row(row: &rusqlite::Row) -> rusqlite::Result<QuarantinedRecord> {
+    let blank_to_none = |v: String| Some(v).filter(|v| !v.is_empty());
+    Ok(QuarantinedRecord {
+        id: row.get(0)?,
+        device_id: row.get(1)?,
+        record_type: row.get(2)?,
+        device_user_id: row.get(3)?,
+        timestamp: blank_to_none(row.get(4)?),
+        user_name: blank_to_none(row.get(5)?),
+        verify_type: row.get(6)?,
+        punch_type: row.get(7)?,
+        reason: row.get(8)?,
+        status: row.get(9)?,
+        created_at: row.get(10)?,
+        resolved_at: row.get(11)?,
+    })
+}
+
+/// Quarantined records, newest first, optionally by status and device
+pub fn list(conn: &Connection, status: Option<&str>, device_id: Option<&str>) -> Result<Vec<QuarantinedRecord>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "{} WHERE (?1 IS NULL OR status = ?1) AND (?2 IS NULL OR device_id = ?2) ORDER BY id DESC",
+            SELECT
+        ))
+        .map_err(|e| format!("Failed to query quarantine: {}", e))?;
+    let rows = stmt
+        .query_map(params![status, device_id], map_row)
+        .map_err(|e| format!("Failed to query quarantine: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read quarantine: {}", e))
+}
+
+/// A quarantined record by ID
+pub fn get(conn: &Connection, id: i64) -> Result<Option<QuarantinedRecord>, String> {
+    conn.query_row(&format!("{} WHERE id = ?1", SELECT), [id], map_row)
+        .optional()
+        .map_err(|e| format!("Failed to read quarantined record: {}", e))
+}
@@ -0,0 +1,38 @@
+//! Types for quarantined device records
+
+use serde::{Deserialize, Serialize};
+
+/// A device user or log that failed validation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuarantinedRecord {
+    pub id: i64,
+    pub device_id: String,
+    /// log or user
+    pub record_type: String,
+    pub device_user_id: String,
+    /// Logs only
+    pub timestamp: Option<String>,
+    /// Users only
+    pub user_name: Option<String>,
+    pub verify_type: Option<i64>,
+    pub punch_type: Option<i64>,
+    /// Why it was quarantined, one of the codes in `validate`
+    pub reason: String,
+    /// pending, repaired or discarded
+    pub status: String,
+    pub created_at: String,
+    pub resolved_at: Option<String>,
+}
+
+/// Corrected values for a quarantined record; fields left out keep the
+/// device's value
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuarantineRepair {
+    pub id: i64,
+    pub device_user_id: Option<String>,
+    /// YYYY-MM-DDTHH:MM:SS, device-local
+    pub timestamp: Option<String>,
+    pub user_name: Option<String>,
+}
@@ -0,0 +1,75 @@
+//! Validation of device users and logs
+//!
+//! Each check returns the reason code for the first problem found, or `None`
+//! for a record that may be stored.
+
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+
+pub const EMPTY_USER_ID: &str = "empty_user_id";
+pub const INVALID_USER_ID: &str = "invalid_user_id";
+pub const INVALID_NAME: &str = "invalid_name";
+pub const INVALID_TIMESTAMP: &str = "invalid_timestamp";
+pub const FUTURE_TIMESTAMP: &str = "future_timestamp";
+pub const PAST_TIMESTAMP: &str = "past_timestamp";
+
+/// Format of log timestamps in attendance_logs_raw
+pub const TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.3fZ";
+
+/// How far past this computer's clock a punch may be, for devices running
+/// slightly fast
+const MAX_FUTURE_HOURS: i64 = 24;
+
+/// Punches from before this are a reset clock (the protocol's zero is
+/// 2000-01-01 00:00)
+fn earliest() -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2000, 1, 2).unwrap().and_hms_opt(0, 0, 0).unwrap()
+}
+
+/// User IDs are printable ASCII; anything else is a misread record
+fn check_user_id(device_user_id: &str) -> Option<&'static str> {
+    if device_user_id.trim().is_empty() {
+        Some(EMPTY_USER_ID)
+    } else if !device_user_id.chars().all(|c| c.is_ascii_graphic()) {
+        Some(INVALID_USER_ID)
+    } else {
+        None
+    }
+}
+
+/// Names may be in any script, but not contain control characters, the
+/// replacement character left by undecodable bytes, or private-use code points
+fn is_garbled(text: &str) -> bool {
+    text.chars().any(|c| {
+        c.is_control() || c == '\u{FFFD}' || ('\u{E000}'..='\u{F8FF}').contains(&c)
+    })
+}
+
+/// Parse a device-local timestamp, with or without milliseconds and `Z`
+pub fn parse_timestamp(timestamp: &str) -> Option<NaiveDateTime> {
+    let trimmed = timestamp.trim_end_matches('Z');
+    NaiveDateTime::parse_from_str(trimmed, "%Y-%m-%dT%H:%M:%S%.f")
+        .or_else(|_| NaiveDateTime::parse_from_str(trimmed, "%Y-%m-%dT%H:%M:%S"))
+        .ok()
+}
+
+/// Problem with a device user, if any
+pub fn check_user(device_user_id: &str, name: &str) -> Option<&'static str> {
+    check_user_id(device_user_id).or_else(|| is_garbled(name).then_some(INVALID_NAME))
+}
+
+/// Problem with a log, if any. `now` is this computer's local time.
+pub fn check_log(device_user_id: &str, timestamp: &str, now: NaiveDateTime) -> Option<&'static str> {
+    if let Some(reason) = check_user_id(device_user_id) {
+        return Some(reason);
+    }
+    let Some(time) = parse_timestamp(timestamp) else {
+        return Some(INVALID_TIMESTAMP);
+    };
+    if time > now + Duration::hours(MAX_FUTURE_HOURS) {
+        Some(FUTURE_TIMESTAMP)
+    } else if time < earliest() {
+        Some(PAST_TIMESTAMP)
+    } else {
+        None
+    }
+}
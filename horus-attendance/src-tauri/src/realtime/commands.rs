@@ -0,0 +1,97 @@
+//! Tauri command handlers for the realtime event journal.
+
+use std::time::Duration;
+use tauri::Manager;
+
+use super::journal;
+use super::types::*;
+use crate::activity::registry;
+use crate::db;
+use crate::greeter;
+use crate::jobs::{self, registry::JobRegistry};
+
+/// How often the journal is drained when nothing asks for it sooner
+const DRAIN_INTERVAL: Duration = Duration::from_secs(30);
+
+/// ID of the drain job in the jobs registry
+pub const JOB_ID: &str = "realtime_journal";
+
+fn drain_now(app: &tauri::AppHandle) -> Result<JournalDrain, String> {
+    let _activity = registry::begin(app, "Realtime journal drain")?;
+    let drained = journal::drain(&journal::path(app)?, &mut *db::open(app)?)?;
+    if drained.inserted > 0 {
+        if let Err(e) = greeter::feed::publish(app) {
+            log::warn!("[realtime] Failed to publish recent punches: {}", e);
+        }
+    }
+    Ok(drained)
+}
+
+/// Journal realtime events from a device. They are on disk when this
+/// returns and reach attendance_logs_raw on the next drain, which is
+/// started right away.
+#[tauri::command]
+pub async fn append_realtime_events(app: tauri::AppHandle, events: Vec<RealtimeEvent>) -> Result<usize, String> {
+    let path = journal::path(&app)?;
+    let appended = tauri::async_runtime::spawn_blocking(move || journal::append(&path, events))
+        .await
+        .map_err(|e| format!("Journal task failed: {}", e))??;
+    if appended > 0 {
+        app.state::<JobRegistry>().run_now(JOB_ID)?;
+    }
+    Ok(appended)
+}
+
+/// Store everything in the journal now
+#[tauri::command]
+pub async fn drain_realtime_journal(app: tauri::AppHandle) -> Result<JournalDrain, String> {
+    let drained = tauri::async_runtime::spawn_blocking(move || drain_now(&app))
+        .await
+        .map_err(|e| format!("Journal task failed: {}", e))??;
+    log::info!(
+        "[realtime::cmd] Drained journal: {} new punches, {} duplicates, {} dropped",
+        drained.inserted,
+        drained.duplicates,
+        drained.discarded
+    );
+    Ok(drained)
+}
+
+/// Pending entries and the last drain
+#[tauri::command]
+pub async fn get_realtime_journal_status(app: tauri::AppHandle) -> Result<JournalStatus, String> {
+    let path = journal::path(&app)?;
+    tauri::async_runtime::spawn_blocking(move || journal::status(&path))
+        .await
+        .map_err(|e| format!("Journal task failed: {}", e))?
+}
+
+/// Replay what a crash left in the journal, then keep draining it. Runs for
+/// the lifetime of the app. While the activity registry is paused (backup,
+/// restore) drains fail and events stay journalled until the next one.
+pub async fn run_background_drains(app: tauri::AppHandle) {
+    let job = jobs::registry::register(
+        &app,
+        JOB_ID,
+        "Realtime journal",
+        "Stores punches journalled from realtime device events",
+        DRAIN_INTERVAL,
+    );
+    let handle = app.clone();
+    // Draining first thing is the crash recovery
+    jobs::registry::drive_blocking(
+        job,
+        move |_| drain_now(&handle),
+        |drained| {
+            if drained.read > 0 {
+                log::info!(
+                    "[realtime] Drained journal: {} new punches, {} duplicates, {} dropped",
+                    drained.inserted,
+                    drained.duplicates,
+                    drained.discarded
+                );
+            }
+        },
+    )
+    .await;
+}
@@ -0,0 +1,225 @@
+//! The journal file
+//!
+//! One event per line: the first 16 hex digits of the SHA-256 of the JSON,
+//! a space, then the JSON. A line cut short by a crash mid-append, or
+//! damaged on disk, fails its checksum and is dropped when the journal is
+//! drained.
+//!
+//! Appends are only held up by other appends and by the moment a drain
+//! reads the file, never by the database: the drain stores what it read,
+//! then removes just those bytes, keeping anything appended meanwhile.
+
+use chrono::Utc;
+use rusqlite::Connection;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::Manager;
+
+use super::types::{JournalDrain, JournalStatus, RealtimeEvent};
+use crate::db::logs::{self, DevicePunch};
+use crate::quarantine::validate;
+
+/// File name of the journal in app data
+const FILE_NAME: &str = "realtime-journal.log";
+
+/// Held while the file is written or read
+static FILE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Held for a whole drain, so two drains don't both remove the same bytes.
+/// Also keeps the last drain and when it finished.
+static LAST_DRAIN: Mutex<Option<(JournalDrain, String)>> = Mutex::new(None);
+
+pub fn path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Cannot resolve app data dir: {}", e))?;
+    Ok(dir.join(FILE_NAME))
+}
+
+fn checksum(json: &str) -> String {
+    format!("{:x}", Sha256::digest(json.as_bytes()))[..16].to_string()
+}
+
+/// Normalize the timestamp to the attendance_logs_raw format, refusing
+/// events a sync would quarantine
+fn normalize(mut event: RealtimeEvent) -> Result<RealtimeEvent, String> {
+    let time = validate::parse_timestamp(&event.timestamp)
+        .ok_or_else(|| format!("Invalid timestamp (expected YYYY-MM-DDTHH:MM:SS): {}", event.timestamp))?;
+    event.timestamp = time.format(validate::TIMESTAMP_FORMAT).to_string();
+    if let Some(reason) = validate::check_log(&event.device_user_id, &event.timestamp, chrono::Local::now().naive_local()) {
+        return Err(format!("Invalid event for device user '{}': {}", event.device_user_id, reason));
+    }
+    Ok(event)
+}
+
+/// Append events and flush them to disk; once this returns they survive a
+/// crash. Nothing is written if any event is invalid.
+pub fn append(path: &Path, events: Vec<RealtimeEvent>) -> Result<usize, String> {
+    let events = events.into_iter().map(normalize).collect::<Result<Vec<_>, _>>()?;
+    let mut lines = String::new();
+    for event in &events {
+        let json = serde_json::to_string(event).map_err(|e| format!("Failed to serialize event: {}", e))?;
+        lines.push_str(&checksum(&json));
+        lines.push(' ');
+        lines.push_str(&json);
+        lines.push('\n');
+    }
+
+    let _lock = FILE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create journal directory: {}", e))?;
+    }
+    let mut file = OpenOptions::new()
+        .read(true)
+        .append(true)
+        .create(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open realtime journal: {}", e))?;
+
+    // A line torn by a crash would swallow the first event appended after it
+    let size = file
+        .metadata()
+        .map_err(|e| format!("Failed to read realtime journal: {}", e))?
+        .len();
+    if size > 0 {
+        let mut last = [0u8; 1];
+        file.seek(SeekFrom::End(-1))
+            .and_then(|_| file.read_exact(&mut last))
+            .map_err(|e| format!("Failed to read realtime journal: {}", e))?;
+        if last[0] != b'\n' {
+            lines.insert(0, '\n');
+        }
+    }
+
+    file.write_all(lines.as_bytes())
+        .and_then(|_| file.sync_data())
+        .map_err(|e| format!("Failed to write realtime journal: {}", e))?;
+    Ok(events.len())
+}
+
+/// The journal's bytes, or none if there is no journal yet
+fn read_bytes(path: &Path) -> Result<Vec<u8>, String> {
+    let _lock = FILE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    match fs::read(path) {
+        Ok(bytes) => Ok(bytes),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(format!("Failed to read realtime journal: {}", e)),
+    }
+}
+
+/// Intact entries, and how many lines were damaged
+fn parse(bytes: &[u8]) -> (Vec<RealtimeEvent>, usize) {
+    let mut events = Vec::new();
+    let mut damaged = 0;
+    for line in String::from_utf8_lossy(bytes).lines().filter(|l| !l.trim().is_empty()) {
+        let event = line
+            .split_once(' ')
+            .filter(|(sum, json)| checksum(json) == *sum)
+            .and_then(|(_, json)| serde_json::from_str(json).ok());
+        match event {
+            Some(event) => events.push(event),
+            None => damaged += 1,
+        }
+    }
+    (events, damaged)
+}
+
+/// Remove the first `len` bytes, which have been stored, keeping anything
+/// appended since
+fn consume(path: &Path, len: usize) -> Result<(), String> {
+    let _lock = FILE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read realtime journal: {}", e))?;
+    let rest = bytes.get(len..).unwrap_or_default();
+
+    // Swap in the remainder whole, so a crash leaves either file intact
+    let temp = path.with_extension("log.tmp");
+    let mut file = fs::File::create(&temp).map_err(|e| format!("Failed to write realtime journal: {}", e))?;
+    file.write_all(rest)
+        .and_then(|_| file.sync_all())
+        .map_err(|e| format!("Failed to write realtime journal: {}", e))?;
+    fs::rename(&temp, path).map_err(|e| format!("Failed to replace realtime journal: {}", e))
+}
+
+fn device_ids(conn: &Connection) -> Result<HashSet<String>, String> {
+    let mut stmt = conn
+        .prepare("SELECT id FROM devices")
+        .map_err(|e| format!("Failed to prepare query: {}", e))?;
+    let ids = stmt
+        .query_map([], |row| row.get(0))
+        .map_err(|e| format!("Failed to query devices: {}", e))?
+        .collect::<Result<HashSet<String>, _>>()
+        .map_err(|e| format!("Failed to read device: {}", e))?;
+    Ok(ids)
+}
+
+/// Store journalled events in attendance_logs_raw in one transaction, then
+/// remove them from the journal. Events for devices that have since been
+/// deleted are dropped.
+pub fn drain(path: &Path, conn: &mut Connection) -> Result<JournalDrain, String> {
+    let mut last = LAST_DRAIN.lock().unwrap_or_else(|e| e.into_inner());
+    let bytes = read_bytes(path)?;
+    if bytes.is_empty() {
+        return Ok(JournalDrain::default());
+    }
+
+    let (events, damaged) = parse(&bytes);
+    let known = device_ids(conn)?;
+    let punches: Vec<(&str, DevicePunch)> = events
+        .iter()
+        .filter(|event| known.contains(&event.device_id))
+        .map(|event| {
+            (
+                event.device_id.as_str(),
+                DevicePunch {
+                    device_user_id: &event.device_user_id,
+                    timestamp: &event.timestamp,
+                    verify_type: event.verify_type,
+                    punch_type: event.punch_type,
+                },
+            )
+        })
+        .collect();
+    let orphaned = events.len() - punches.len();
+    let punches: Vec<(&str, &DevicePunch)> = punches.iter().map(|(device_id, punch)| (*device_id, punch)).collect();
+    let inserted = logs::insert_from_devices(conn, &punches)?;
+
+    // Committed. A crash before the bytes are removed replays them, and the
+    // replayed punches are skipped as duplicates.
+    consume(path, bytes.len())?;
+
+    if damaged > 0 || orphaned > 0 {
+        log::warn!(
+            "[realtime] Dropped {} damaged journal entries and {} for unknown devices",
+            damaged,
+            orphaned
+        );
+    }
+    let result = JournalDrain {
+        read: events.len(),
+        inserted,
+        duplicates: punches.len() - inserted,
+        discarded: damaged + orphaned,
+    };
+    *last = Some((result.clone(), Utc::now().to_rfc3339()));
+    Ok(result)
+}
+
+/// Pending entries and the last drain
+pub fn status(path: &Path) -> Result<JournalStatus, String> {
+    let bytes = read_bytes(path)?;
+    let (events, _) = parse(&bytes);
+    let last = LAST_DRAIN.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    let (last_drain, last_drained_at) = last.map_or((None, None), |(drain, at)| (Some(drain), Some(at)));
+    Ok(JournalStatus {
+        path: path.to_string_lossy().to_string(),
+        pending: events.len(),
+        size_bytes: bytes.len() as u64,
+        last_drain,
+        last_drained_at,
+    })
+}
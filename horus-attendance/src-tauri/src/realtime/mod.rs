@@ -0,0 +1,15 @@
+//! Crash-resistant buffering of realtime device events
+//!
+//! Punches pushed by a device as they happen arrive while the database may
+//! be busy, paused for a backup or restore, or about to go away with the
+//! app. Rather than being written straight to attendance_logs_raw they are
+//! appended to a journal file in app data and fsync'd before being
+//! acknowledged. The journal is drained into attendance_logs_raw in one
+//! transaction and only emptied once that has committed; a crash in between
+//! replays it, and the table's uniqueness on (device, user, timestamp) drops
+//! what was already stored. Draining runs on launch and then as the
+//! `realtime_journal` background job.
+
+pub mod commands;
+pub mod journal;
+pub mod types;
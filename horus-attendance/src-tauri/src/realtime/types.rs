@@ -0,0 +1,43 @@
+//! Realtime journal data types for Tauri command serialization
+
+use serde::{Deserialize, Serialize};
+
+/// A punch reported by a device as it happened
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RealtimeEvent {
+    pub device_id: String,
+    pub device_user_id: String,
+    /// Device wall-clock time, `YYYY-MM-DDTHH:MM:SS`
+    pub timestamp: String,
+    #[serde(default)]
+    pub verify_type: u8,
+    #[serde(default)]
+    pub punch_type: u8,
+}
+
+/// What a drain of the journal did
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalDrain {
+    /// Complete entries read from the journal
+    pub read: usize,
+    /// New rows in attendance_logs_raw
+    pub inserted: usize,
+    /// Already stored, e.g. replayed after a crash
+    pub duplicates: usize,
+    /// Dropped: torn or corrupt entries, or for devices that no longer exist
+    pub discarded: usize,
+}
+
+/// Journal file state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalStatus {
+    pub path: String,
+    /// Entries waiting to be drained
+    pub pending: usize,
+    pub size_bytes: u64,
+    pub last_drain: Option<JournalDrain>,
+    pub last_drained_at: Option<String>,
+}
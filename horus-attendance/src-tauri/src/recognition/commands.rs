@@ -0,0 +1,71 @@
+//! Tauri command handlers for the recognition report.
+
+use std::fs;
+use std::path::PathBuf;
+
+use super::report;
+use super::types::*;
+use crate::db;
+use crate::export::commands::get_export_dir;
+use crate::export::protect;
+use crate::export::types::ExportedFile;
+use crate::signing;
+
+/// Perfect-attendance streaks and the month's perfect attendance list per
+/// department, optionally of one department
+#[tauri::command]
+pub async fn get_recognition_report(
+    app: tauri::AppHandle,
+    year: i32,
+    month: u32,
+    department_id: Option<String>,
+) -> Result<RecognitionReport, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        report::build(&*db::open(&app)?, year, month, department_id.as_deref())
+    })
+    .await
+    .map_err(|e| format!("Recognition task failed: {}", e))?
+}
+
+/// Export the recognition report as CSV, encrypted into a zip when
+/// `password` is set.
+/// `destination` is a directory; defaults to Documents/HorusAttendance/exports.
+#[tauri::command]
+pub async fn export_recognition_report(
+    app: tauri::AppHandle,
+    year: i32,
+    month: u32,
+    department_id: Option<String>,
+    destination: Option<String>,
+    password: Option<String>,
+) -> Result<ExportedFile, String> {
+    if let Some(password) = &password {
+        protect::validate_password(password)?;
+    }
+    log::info!("[recognition::cmd] export_recognition_report {}-{:02}", year, month);
+
+    let dir = match destination {
+        Some(dest) => PathBuf::from(dest),
+        None => get_export_dir(&app)?,
+    };
+    let path = crate::path_policy::resolve_write_target(
+        &app,
+        &dir.join(format!("recognition_{}{:02}.csv", year, month)).to_string_lossy(),
+    )?;
+
+    let conn = db::open(&app)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let report = report::build(&conn, year, month, department_id.as_deref())?;
+        let rows = report::write_csv(&report, &path)?;
+        let file = ExportedFile {
+            path: path.to_string_lossy().to_string(),
+            rows,
+            file_size: fs::metadata(&path).map(|m| m.len()).unwrap_or(0),
+        };
+        let file = protect::finish(file, password.as_deref())?;
+        signing::store::sign_exported(&conn, std::slice::from_ref(&file), "recognition")?;
+        Ok(file)
+    })
+    .await
+    .map_err(|e| format!("Export task failed: {}", e))?
+}
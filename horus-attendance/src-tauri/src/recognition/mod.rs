@@ -0,0 +1,9 @@
+//! Attendance recognition
+//!
+//! Perfect-attendance streaks and monthly lists of people with no late
+//! arrivals and no absences, per department: the positive counterpart to
+//! the lateness reports. Built on the same infractions as attendance points.
+
+pub mod commands;
+pub mod report;
+pub mod types;
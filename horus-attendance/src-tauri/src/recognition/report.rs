@@ -0,0 +1,163 @@
+//! Computing and writing the recognition report
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+
+use chrono::{Duration, Local, Months, NaiveDate};
+use rusqlite::{params, Connection};
+
+use super::types::*;
+use crate::points::store as points;
+use crate::points::types::Infraction;
+use crate::summary::history::csv_field;
+use crate::summary::rules;
+
+/// How far back a streak is followed
+const MAX_STREAK_DAYS: i64 = 730;
+
+/// Recognition for a month, optionally of one department. Users who have
+/// neither a perfect month nor a streak are left out.
+pub fn build(conn: &Connection, year: i32, month: u32, department_id: Option<&str>) -> Result<RecognitionReport, String> {
+    let start = NaiveDate::from_ymd_opt(year, month, 1).ok_or_else(|| format!("Invalid month: {}-{}", year, month))?;
+    let month_end = start
+        .checked_add_months(Months::new(1))
+        .ok_or_else(|| "Date range out of bounds".to_string())?
+        - Duration::days(1);
+    let end = month_end.min(Local::now().date_naive());
+    if end < start {
+        return Err(format!("{}-{:02} hasn't started yet", year, month));
+    }
+    let start_date = start.format("%Y-%m-%d").to_string();
+    let end_date = end.format("%Y-%m-%d").to_string();
+    let lookback = end - Duration::days(MAX_STREAK_DAYS - 1);
+    let lookback_date = lookback.format("%Y-%m-%d").to_string();
+
+    // user ID -> days worked, by date
+    let mut worked: HashMap<String, Vec<String>> = HashMap::new();
+    {
+        let mut stmt = conn
+            .prepare(
+                "SELECT user_id, date FROM attendance_day_summary
+                 WHERE date >= ?1 AND date <= ?2 AND status NOT IN (?3, ?4, ?5)
+                 ORDER BY date",
+            )
+            .map_err(|e| format!("Failed to query summaries: {}", e))?;
+        let rows = stmt
+            .query_map(
+                params![
+                    lookback_date,
+                    end_date,
+                    rules::STATUS_ABSENT,
+                    rules::STATUS_HOLIDAY,
+                    rules::STATUS_WEEKEND
+                ],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+            )
+            .map_err(|e| format!("Failed to query summaries: {}", e))?;
+        for row in rows {
+            let (user_id, date) = row.map_err(|e| format!("Failed to read summaries: {}", e))?;
+            worked.entry(user_id).or_default().push(date);
+        }
+    }
+
+    let mut departments: Vec<DepartmentRecognition> = Vec::new();
+    for user in points::infractions(conn, lookback, end, department_id, None)? {
+        let Some(first_day) = &user.first_day else {
+            continue;
+        };
+        let last_miss = user
+            .infractions
+            .iter()
+            .rev()
+            .find(|(_, infraction)| matches!(infraction, Infraction::Late | Infraction::Absence))
+            .map(|(date, _)| date.as_str());
+        let days = worked.get(&user.user_id).map(Vec::as_slice).unwrap_or_default();
+        let days_present = days.iter().filter(|d| **d >= start_date).count() as u32;
+        let streak: Vec<&String> = days.iter().filter(|d| Some(d.as_str()) > last_miss).collect();
+        let entry = RecognitionEntry {
+            user_id: user.user_id,
+            display_name: user.display_name,
+            employee_code: user.employee_code,
+            // Only people employed since the start of the month qualify
+            perfect_month: *first_day <= start_date
+                && days_present > 0
+                && last_miss.map_or(true, |d| d < start_date.as_str()),
+            days_present,
+            streak_days: streak.len() as u32,
+            streak_since: streak.first().map(|d| d.to_string()),
+        };
+        if !entry.perfect_month && entry.streak_days == 0 {
+            continue;
+        }
+        let index = match departments.iter().position(|d| d.department_id == user.department_id) {
+            Some(index) => index,
+            None => {
+                departments.push(DepartmentRecognition {
+                    department_id: user.department_id,
+                    department_name: user.department_name,
+                    perfect_count: 0,
+                    users: Vec::new(),
+                });
+                departments.len() - 1
+            }
+        };
+        let department = &mut departments[index];
+        department.perfect_count += u32::from(entry.perfect_month);
+        department.users.push(entry);
+    }
+
+    for department in &mut departments {
+        department.users.sort_by(|a, b| {
+            b.perfect_month
+                .cmp(&a.perfect_month)
+                .then(b.streak_days.cmp(&a.streak_days))
+                .then_with(|| a.display_name.cmp(&b.display_name))
+        });
+    }
+    // Users without a department last
+    departments.sort_by(|a, b| {
+        (a.department_name.is_none(), &a.department_name).cmp(&(b.department_name.is_none(), &b.department_name))
+    });
+    Ok(RecognitionReport {
+        year,
+        month,
+        start_date,
+        end_date,
+        departments,
+    })
+}
+
+/// Write `report` as CSV to `path`; returns the number of rows
+pub fn write_csv(report: &RecognitionReport, path: &Path) -> Result<u64, String> {
+    let file = std::fs::File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut out = std::io::BufWriter::new(file);
+    let write_err = |e: std::io::Error| format!("Failed to write {}: {}", path.display(), e);
+
+    writeln!(
+        out,
+        "department,employee_code,employee,perfect_month,days_present,streak_days,streak_since"
+    )
+    .map_err(write_err)?;
+    let opt = |v: &Option<String>| v.as_deref().map(csv_field).unwrap_or_default();
+    let mut rows = 0;
+    for department in &report.departments {
+        for user in &department.users {
+            writeln!(
+                out,
+                "{},{},{},{},{},{},{}",
+                opt(&department.department_name),
+                opt(&user.employee_code),
+                csv_field(&user.display_name),
+                if user.perfect_month { "yes" } else { "no" },
+                user.days_present,
+                user.streak_days,
+                opt(&user.streak_since),
+            )
+            .map_err(write_err)?;
+            rows += 1;
+        }
+    }
+    out.flush().map_err(write_err)?;
+    Ok(rows)
+}
@@ -0,0 +1,43 @@
+//! Recognition data types for Tauri command serialization
+
+use serde::{Deserialize, Serialize};
+
+/// One user's record for the month
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecognitionEntry {
+    pub user_id: String,
+    pub display_name: String,
+    pub employee_code: Option<String>,
+    /// No late arrivals and no absences all month
+    pub perfect_month: bool,
+    /// Days worked in the month
+    pub days_present: u32,
+    /// Days worked in a row without a late arrival or absence, up to the
+    /// end of the month
+    pub streak_days: u32,
+    /// First day of the streak
+    pub streak_since: Option<String>,
+}
+
+/// A department's recognition list: perfect months first, then by streak
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DepartmentRecognition {
+    /// None for users without a department
+    pub department_id: Option<String>,
+    pub department_name: Option<String>,
+    pub perfect_count: u32,
+    pub users: Vec<RecognitionEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecognitionReport {
+    pub year: i32,
+    pub month: u32,
+    pub start_date: String,
+    /// Last day of the month, or today for the current month
+    pub end_date: String,
+    pub departments: Vec<DepartmentRecognition>,
+}
@@ -0,0 +1,67 @@
+//! Tauri command handlers for paired terminal reconciliation.
+
+use super::store;
+use super::types::*;
+use crate::activity::registry;
+use crate::db;
+use crate::summary::commands::validate_date;
+
+/// Every device pair
+#[tauri::command]
+pub async fn list_device_pairs(app: tauri::AppHandle) -> Result<Vec<DevicePair>, String> {
+    store::list(&*db::open(&app)?)
+}
+
+/// Pair two devices, or change the tolerance of a pair
+#[tauri::command]
+pub async fn save_device_pair(app: tauri::AppHandle, pair: DevicePairInput) -> Result<DevicePair, String> {
+    store::save(&*db::open(&app)?, &pair)
+}
+
+/// Unpair two devices. Exceptions already queued stay.
+#[tauri::command]
+pub async fn delete_device_pair(app: tauri::AppHandle, pair_id: i64) -> Result<bool, String> {
+    store::delete(&*db::open(&app)?, pair_id)
+}
+
+/// Cross-check the punches of every pair, or of one, between two dates
+/// (inclusive), queueing punches found on only one terminal
+#[tauri::command]
+pub async fn reconcile_device_pairs(
+    app: tauri::AppHandle,
+    start_date: String,
+    end_date: String,
+    pair_id: Option<i64>,
+) -> Result<Vec<PairReconciliation>, String> {
+    validate_date(&start_date)?;
+    validate_date(&end_date)?;
+    if start_date > end_date {
+        return Err("Start date must not be after end date".to_string());
+    }
+    log::info!("[reconcile::cmd] reconcile_device_pairs {} to {} ({:?})", start_date, end_date, pair_id);
+
+    let activity = registry::begin(&app, "Device pair reconciliation")?;
+    let conn = db::open(&app)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let _activity = activity;
+        let pairs = match pair_id {
+            Some(id) => vec![store::get(&conn, id)?.ok_or_else(|| format!("Device pair not found: {}", id))?],
+            None => store::list(&conn)?,
+        };
+        let mut results = Vec::with_capacity(pairs.len());
+        for pair in &pairs {
+            let result = store::reconcile(&conn, pair, &start_date, &end_date)?;
+            log::info!(
+                "[reconcile] Pair {}: {} matched, {} only on primary, {} only on secondary",
+                pair.id,
+                result.matched,
+                result.only_on_primary,
+                result.only_on_secondary
+            );
+            results.push(result);
+        }
+        Ok(results)
+    })
+    .await
+    .map_err(|e| format!("Reconciliation task failed: {}", e))?
+}
@@ -0,0 +1,26 @@
+//! Matching punches of one user on two terminals
+
+use chrono::NaiveDateTime;
+
+/// Indexes of the punches in `a` and in `b` without a partner on the other
+/// side. Both lists must be sorted. Punches pair off in order, each with the
+/// first punch on the other side within `tolerance_seconds`.
+pub fn unmatched(a: &[NaiveDateTime], b: &[NaiveDateTime], tolerance_seconds: i64) -> (Vec<usize>, Vec<usize>) {
+    let (mut i, mut j) = (0, 0);
+    let (mut only_a, mut only_b) = (Vec::new(), Vec::new());
+    while i < a.len() && j < b.len() {
+        if (a[i] - b[j]).num_seconds().abs() <= tolerance_seconds {
+            i += 1;
+            j += 1;
+        } else if a[i] < b[j] {
+            only_a.push(i);
+            i += 1;
+        } else {
+            only_b.push(j);
+            j += 1;
+        }
+    }
+    only_a.extend(i..a.len());
+    only_b.extend(j..b.len());
+    (only_a, only_b)
+}
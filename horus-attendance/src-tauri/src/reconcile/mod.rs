@@ -0,0 +1,14 @@
+//! Cross-checking paired terminals
+//!
+//! Where two terminals cover the same door, everyone who walks through is
+//! expected to punch on both, so each punch on one should have a partner on
+//! the other within a few seconds. Reconciliation matches them per user and
+//! puts punches seen on only one terminal in the exception queue, which
+//! shows up a terminal that is dropping records. Only the period both
+//! terminals have logs for is compared, so a terminal that hasn't been
+//! synced lately doesn't flag everything the other recorded since.
+
+pub mod commands;
+pub mod matching;
+pub mod store;
+pub mod types;
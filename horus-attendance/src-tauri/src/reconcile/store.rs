@@ -0,0 +1,248 @@
+//! Storage for device pairs and reconciliation against the logs
+
+use std::collections::BTreeMap;
+
+use chrono::NaiveDateTime;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::json;
+
+use super::matching;
+use super::types::*;
+use crate::db::logs::day_bounds;
+use crate::exceptions::store as exceptions;
+use crate::exceptions::types::NewException;
+use crate::quarantine::validate::parse_timestamp;
+
+pub const KIND_UNPAIRED_PUNCH: &str = "unpaired_punch";
+
+pub const DEFAULT_TOLERANCE_SECONDS: i64 = 60;
+const MAX_TOLERANCE_SECONDS: i64 = 600;
+
+const SELECT: &str = "SELECT p.id, p.primary_device_id, pd.name, p.secondary_device_id, sd.name,
+                             p.tolerance_seconds, p.created_at
+                      FROM device_pairs p
+                      LEFT JOIN devices pd ON pd.id = p.primary_device_id
+                      LEFT JOIN devices sd ON sd.id = p.secondary_device_id";
+
+fn map_row(row: &rusqlite::Row) -> rusqlite::Result<DevicePair> {
+    Ok(DevicePair {
+        id: row.get(0)?,
+        primary_device_id: row.get(1)?,
+        primary_device_name: row.get(2)?,
+        secondary_device_id: row.get(3)?,
+        secondary_device_name: row.get(4)?,
+        tolerance_seconds: row.get(5)?,
+        created_at: row.get(6)?,
+    })
+}
+
+/// Every pair, oldest first
+pub fn list(conn: &Connection) -> Result<Vec<DevicePair>, String> {
+    let mut stmt = conn
+        .prepare(&format!("{} ORDER BY p.id", SELECT))
+        .map_err(|e| format!("Failed to query device pairs: {}", e))?;
+    let rows = stmt
+        .query_map([], map_row)
+        .map_err(|e| format!("Failed to query device pairs: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read device pairs: {}", e))
+}
+
+pub fn get(conn: &Connection, id: i64) -> Result<Option<DevicePair>, String> {
+    conn.query_row(&format!("{} WHERE p.id = ?1", SELECT), [id], map_row)
+        .optional()
+        .map_err(|e| format!("Failed to read device pair: {}", e))
+}
+
+/// Add a pair, or change the tolerance of an existing one
+pub fn save(conn: &Connection, input: &DevicePairInput) -> Result<DevicePair, String> {
+    let tolerance = input.tolerance_seconds.unwrap_or(DEFAULT_TOLERANCE_SECONDS);
+    if !(0..=MAX_TOLERANCE_SECONDS).contains(&tolerance) {
+        return Err(format!("Tolerance must be between 0 and {} seconds", MAX_TOLERANCE_SECONDS));
+    }
+    if let Some(id) = input.id {
+        let updated = conn
+            .execute("UPDATE device_pairs SET tolerance_seconds = ?2 WHERE id = ?1", params![id, tolerance])
+            .map_err(|e| format!("Failed to save device pair: {}", e))?;
+        if updated == 0 {
+            return Err(format!("Device pair not found: {}", id));
+        }
+        return get(conn, id)?.ok_or_else(|| "Device pair disappeared".to_string());
+    }
+
+    if input.primary_device_id == input.secondary_device_id {
+        return Err("A device can't be paired with itself".to_string());
+    }
+    for device_id in [&input.primary_device_id, &input.secondary_device_id] {
+        let known: bool = conn
+            .query_row("SELECT EXISTS(SELECT 1 FROM devices WHERE id = ?1)", [device_id], |row| row.get(0))
+            .map_err(|e| format!("Failed to look up device {}: {}", device_id, e))?;
+        if !known {
+            return Err(format!("Device not found: {}", device_id));
+        }
+    }
+    let paired: bool = conn
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM device_pairs
+                           WHERE (primary_device_id = ?1 AND secondary_device_id = ?2)
+                              OR (primary_device_id = ?2 AND secondary_device_id = ?1))",
+            params![input.primary_device_id, input.secondary_device_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to query device pairs: {}", e))?;
+    if paired {
+        return Err("These devices are already paired".to_string());
+    }
+    conn.execute(
+        "INSERT INTO device_pairs (primary_device_id, secondary_device_id, tolerance_seconds) VALUES (?1, ?2, ?3)",
+        params![input.primary_device_id, input.secondary_device_id, tolerance],
+    )
+    .map_err(|e| format!("Failed to save device pair: {}", e))?;
+    get(conn, conn.last_insert_rowid())?.ok_or_else(|| "Device pair was not saved".to_string())
+}
+
+pub fn delete(conn: &Connection, id: i64) -> Result<bool, String> {
+    conn.execute("DELETE FROM device_pairs WHERE id = ?1", [id])
+        .map(|n| n > 0)
+        .map_err(|e| format!("Failed to delete device pair: {}", e))
+}
+
+/// Earliest and latest punch of a device between two timestamps
+fn coverage(conn: &Connection, device_id: &str, start: &str, end: &str) -> Result<Option<(String, String)>, String> {
+    let (first, last): (Option<String>, Option<String>) = conn
+        .query_row(
+            "SELECT MIN(timestamp), MAX(timestamp) FROM attendance_logs_raw
+             WHERE device_id = ?1 AND timestamp >= ?2 AND timestamp < ?3",
+            params![device_id, start, end],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| format!("Failed to query logs: {}", e))?;
+    Ok(first.zip(last))
+}
+
+/// (log ID, time, timestamp as stored)
+type LoggedPunch = (String, NaiveDateTime, String);
+
+/// A device's punches between two timestamps by user, oldest first
+fn punches_by_user(
+    conn: &Connection,
+    device_id: &str,
+    start: &str,
+    end: &str,
+) -> Result<BTreeMap<String, Vec<LoggedPunch>>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, device_user_id, timestamp FROM attendance_logs_raw
+             WHERE device_id = ?1 AND timestamp >= ?2 AND timestamp <= ?3
+             ORDER BY device_user_id, timestamp",
+        )
+        .map_err(|e| format!("Failed to query logs: {}", e))?;
+    let rows = stmt
+        .query_map(params![device_id, start, end], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?))
+        })
+        .map_err(|e| format!("Failed to query logs: {}", e))?;
+    let mut by_user: BTreeMap<String, Vec<LoggedPunch>> = BTreeMap::new();
+    for row in rows {
+        let (id, device_user_id, timestamp) = row.map_err(|e| format!("Failed to read logs: {}", e))?;
+        // Unreadable timestamps are the anomaly check's business
+        if let Some(time) = parse_timestamp(&timestamp) {
+            by_user.entry(device_user_id).or_default().push((id, time, timestamp));
+        }
+    }
+    Ok(by_user)
+}
+
+fn name_of(pair: &DevicePair, device_id: &str) -> String {
+    if device_id == pair.primary_device_id {
+        pair.primary_device_name.clone().unwrap_or_else(|| device_id.to_string())
+    } else {
+        pair.secondary_device_name.clone().unwrap_or_else(|| device_id.to_string())
+    }
+}
+
+fn finding(pair: &DevicePair, punch: &UnmatchedPunch) -> NewException {
+    NewException {
+        kind: KIND_UNPAIRED_PUNCH,
+        severity: "warning",
+        device_user_id: Some(punch.device_user_id.clone()),
+        date: punch.timestamp.get(0..10).map(str::to_string),
+        message: format!(
+            "Punch on {} has no match on {}",
+            name_of(pair, &punch.device_id),
+            name_of(pair, &punch.missing_from_device_id)
+        ),
+        details: json!({
+            "logId": punch.log_id,
+            "deviceId": punch.device_id,
+            "missingFromDeviceId": punch.missing_from_device_id,
+            "timestamp": punch.timestamp,
+            "pairId": pair.id,
+            "toleranceSeconds": pair.tolerance_seconds,
+        }),
+        dedupe_key: format!("{}:{}:{}", KIND_UNPAIRED_PUNCH, punch.log_id, punch.missing_from_device_id),
+    }
+}
+
+/// Match the pair's punches between two dates (inclusive) and queue the
+/// ones seen on only one terminal
+pub fn reconcile(conn: &Connection, pair: &DevicePair, start_date: &str, end_date: &str) -> Result<PairReconciliation, String> {
+    let mut result = PairReconciliation {
+        pair: pair.clone(),
+        compared_from: None,
+        compared_to: None,
+        matched: 0,
+        only_on_primary: 0,
+        only_on_secondary: 0,
+        new_exceptions: 0,
+        unmatched: Vec::new(),
+    };
+    let (start, end) = day_bounds(start_date, end_date);
+    let overlap = coverage(conn, &pair.primary_device_id, &start, &end)?
+        .zip(coverage(conn, &pair.secondary_device_id, &start, &end)?)
+        .map(|((first_a, last_a), (first_b, last_b))| (first_a.max(first_b), last_a.min(last_b)))
+        .filter(|(from, to)| from <= to);
+    let Some((from, to)) = overlap else {
+        return Ok(result);
+    };
+
+    let primary = punches_by_user(conn, &pair.primary_device_id, &from, &to)?;
+    let secondary = punches_by_user(conn, &pair.secondary_device_id, &from, &to)?;
+    let mut users: Vec<&String> = primary.keys().chain(secondary.keys()).collect();
+    users.sort();
+    users.dedup();
+
+    let none = Vec::new();
+    for user in users {
+        let a = primary.get(user).unwrap_or(&none);
+        let b = secondary.get(user).unwrap_or(&none);
+        let times = |punches: &[LoggedPunch]| punches.iter().map(|p| p.1).collect::<Vec<_>>();
+        let (only_a, only_b) = matching::unmatched(&times(a), &times(b), pair.tolerance_seconds);
+        result.matched += (a.len() - only_a.len()) as u32;
+        result.only_on_primary += only_a.len() as u32;
+        result.only_on_secondary += only_b.len() as u32;
+        let sides = [
+            (a, only_a, &pair.primary_device_id, &pair.secondary_device_id),
+            (b, only_b, &pair.secondary_device_id, &pair.primary_device_id),
+        ];
+        for (punches, unmatched, device_id, other_id) in sides {
+            for index in unmatched {
+                let (log_id, _, timestamp) = &punches[index];
+                result.unmatched.push(UnmatchedPunch {
+                    log_id: log_id.clone(),
+                    device_id: device_id.clone(),
+                    missing_from_device_id: other_id.clone(),
+                    device_user_id: user.clone(),
+                    timestamp: timestamp.clone(),
+                });
+            }
+        }
+    }
+    result.unmatched.sort_by(|x, y| x.timestamp.cmp(&y.timestamp));
+
+    let findings: Vec<NewException> = result.unmatched.iter().map(|p| finding(pair, p)).collect();
+    result.new_exceptions = exceptions::insert(conn, &findings)?;
+    result.compared_from = Some(from);
+    result.compared_to = Some(to);
+    Ok(result)
+}
@@ -0,0 +1,54 @@
+//! Types for paired terminal reconciliation
+
+use serde::{Deserialize, Serialize};
+
+/// Two terminals covering the same door
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DevicePair {
+    pub id: i64,
+    pub primary_device_id: String,
+    pub primary_device_name: Option<String>,
+    pub secondary_device_id: String,
+    pub secondary_device_name: Option<String>,
+    /// Punches this far apart or closer count as the same punch
+    pub tolerance_seconds: i64,
+    pub created_at: String,
+}
+
+/// A pair to save; an `id` updates the tolerance of an existing pair
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DevicePairInput {
+    pub id: Option<i64>,
+    pub primary_device_id: String,
+    pub secondary_device_id: String,
+    pub tolerance_seconds: Option<i64>,
+}
+
+/// A punch the other terminal of its pair has no record of
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnmatchedPunch {
+    pub log_id: String,
+    pub device_id: String,
+    pub missing_from_device_id: String,
+    pub device_user_id: String,
+    pub timestamp: String,
+}
+
+/// Outcome of reconciling one pair
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PairReconciliation {
+    pub pair: DevicePair,
+    /// Period both terminals have logs for; none when they don't overlap
+    pub compared_from: Option<String>,
+    pub compared_to: Option<String>,
+    pub matched: u32,
+    pub only_on_primary: u32,
+    pub only_on_secondary: u32,
+    /// Unmatched punches not already in the exception queue
+    pub new_exceptions: i64,
+    pub unmatched: Vec<UnmatchedPunch>,
+}
@@ -0,0 +1,168 @@
+//! Tauri command handlers for remote work days.
+
+use std::collections::HashSet;
+
+use rusqlite::Connection;
+
+use super::store::{self, SETTINGS_KEY};
+use super::types::*;
+use crate::db;
+use crate::summary::commands::validate_date;
+use crate::summary::engine;
+use crate::summary::history;
+use crate::summary::types::ChangeSource;
+
+/// User/date pairs marked or unmarked in one call
+const MAX_PAIRS: usize = 20_000;
+
+/// Longest note a remote day may carry
+const MAX_NOTE_CHARS: usize = 500;
+
+fn dedup(values: &mut Vec<String>) {
+    values.sort_unstable();
+    values.dedup();
+}
+
+/// Check the users and (sorted) dates of a batch, returning the first and
+/// last date
+fn validate_batch(conn: &Connection, user_ids: &[String], dates: &[String]) -> Result<(String, String), String> {
+    if user_ids.is_empty() {
+        return Err("No users given".to_string());
+    }
+    if dates.is_empty() {
+        return Err("No dates given".to_string());
+    }
+    if user_ids.len() * dates.len() > MAX_PAIRS {
+        return Err(format!("At most {} user days can be changed at once", MAX_PAIRS));
+    }
+    for date in dates {
+        validate_date(date)?;
+    }
+    for user_id in user_ids {
+        let known: bool = conn
+            .query_row("SELECT EXISTS(SELECT 1 FROM users WHERE id = ?1)", [user_id], |row| row.get(0))
+            .map_err(|e| format!("Failed to check user: {}", e))?;
+        if !known {
+            return Err(format!("User not found: {}", user_id));
+        }
+    }
+    let first = dates.first().cloned().unwrap_or_default();
+    let last = dates.last().cloned().unwrap_or_default();
+    store::ensure_unlocked(conn, &first, &last)?;
+    Ok((first, last))
+}
+
+#[tauri::command]
+pub async fn get_remote_work_settings(app: tauri::AppHandle) -> Result<RemoteWorkSettings, String> {
+    store::load_settings(&*db::open(&app)?)
+}
+
+/// Save the settings and apply them to the remote days already marked
+#[tauri::command]
+pub async fn save_remote_work_settings(app: tauri::AppHandle, settings: RemoteWorkSettings) -> Result<(), String> {
+    let json = serde_json::to_string(&settings).map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    log::info!(
+        "[remote_work::cmd] Remote days {}",
+        if settings.require_punch { "need an API punch" } else { "count without punches" }
+    );
+    let mut conn = db::open(&app)?;
+    db::set_setting(&conn, SETTINGS_KEY, &json)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let actor = history::local_actor();
+        let source = ChangeSource {
+            reason: "remote_work",
+            actor: Some(&actor),
+        };
+        store::apply(&mut conn, "0000-01-01", "9999-12-31", &source).map(|_| ())
+    })
+    .await
+    .map_err(|e| format!("Remote work task failed: {}", e))?
+}
+
+/// Remote days between two dates (inclusive)
+#[tauri::command]
+pub async fn list_remote_days(
+    app: tauri::AppHandle,
+    start_date: String,
+    end_date: String,
+) -> Result<Vec<RemoteDay>, String> {
+    validate_date(&start_date)?;
+    validate_date(&end_date)?;
+    store::list(&*db::open(&app)?, &start_date, &end_date)
+}
+
+/// Mark many users as working remotely on the given dates, so the days
+/// stop showing as absences
+#[tauri::command]
+pub async fn mark_remote_days(
+    app: tauri::AppHandle,
+    mut user_ids: Vec<String>,
+    mut dates: Vec<String>,
+    note: Option<String>,
+) -> Result<RemoteMarkResult, String> {
+    dedup(&mut user_ids);
+    dedup(&mut dates);
+    let note = note.map(|n| n.trim().to_string()).filter(|n| !n.is_empty());
+    if note.as_ref().is_some_and(|n| n.chars().count() > MAX_NOTE_CHARS) {
+        return Err(format!("Note is longer than {} characters", MAX_NOTE_CHARS));
+    }
+    let mut conn = db::open(&app)?;
+    let (first, last) = validate_batch(&conn, &user_ids, &dates)?;
+    log::info!(
+        "[remote_work::cmd] mark_remote_days for {} users on {} dates",
+        user_ids.len(),
+        dates.len()
+    );
+    tauri::async_runtime::spawn_blocking(move || {
+        let actor = history::local_actor();
+        let marked = store::mark(&mut conn, &user_ids, &dates, note.as_deref(), &actor)?;
+        let source = ChangeSource {
+            reason: "remote_work",
+            actor: Some(&actor),
+        };
+        store::apply(&mut conn, &first, &last, &source)?;
+        let users: HashSet<&String> = user_ids.iter().collect();
+        let days: HashSet<&String> = dates.iter().collect();
+        let counted = store::list(&conn, &first, &last)?
+            .iter()
+            .filter(|day| day.counted && users.contains(&day.user_id) && days.contains(&day.date))
+            .count() as u32;
+        Ok(RemoteMarkResult {
+            marked,
+            counted,
+            awaiting_punch: marked.saturating_sub(counted),
+        })
+    })
+    .await
+    .map_err(|e| format!("Remote work task failed: {}", e))?
+}
+
+/// Remove remote marks; the days are recomputed from their punches
+#[tauri::command]
+pub async fn unmark_remote_days(
+    app: tauri::AppHandle,
+    mut user_ids: Vec<String>,
+    mut dates: Vec<String>,
+) -> Result<u32, String> {
+    dedup(&mut user_ids);
+    dedup(&mut dates);
+    let mut conn = db::open(&app)?;
+    let (first, last) = validate_batch(&conn, &user_ids, &dates)?;
+    log::info!(
+        "[remote_work::cmd] unmark_remote_days for {} users on {} dates",
+        user_ids.len(),
+        dates.len()
+    );
+    tauri::async_runtime::spawn_blocking(move || {
+        let removed = store::unmark(&mut conn, &user_ids, &dates)?;
+        let actor = history::local_actor();
+        let source = ChangeSource {
+            reason: "remote_work",
+            actor: Some(&actor),
+        };
+        engine::recompute(&mut conn, &first, &last, &source)?;
+        Ok(removed)
+    })
+    .await
+    .map_err(|e| format!("Remote work task failed: {}", e))?
+}
@@ -0,0 +1,14 @@
+//! Remote work days
+//!
+//! A day marked as worked remotely for a user (one `remote_days` row) gets a
+//! summary with status `remote` instead of showing up as an absence, with
+//! no lateness or early leave. Every recompute (and so every API punch)
+//! applies the marks again after computing the days from punches.
+//!
+//! With `requirePunch` on, a marked day only counts once the user has
+//! punched through the API (the mobile app) that day; until then the day is
+//! whatever the punches make it.
+
+pub mod commands;
+pub mod store;
+pub mod types;
@@ -0,0 +1,230 @@
+//! Remote days and their summaries
+
+use std::collections::{HashMap, HashSet};
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use super::types::*;
+use crate::api::punches::API_DEVICE_ID;
+use crate::db::{self, logs, summaries};
+use crate::summary::engine::{self, UserMatcher};
+use crate::summary::rules::{self, STATUS_REMOTE};
+use crate::summary::types::{ChangeSource, DaySummary};
+
+/// Settings key of the remote work settings
+pub const SETTINGS_KEY: &str = "remoteWork";
+
+/// Load the settings, falling back to defaults (no punch needed)
+pub fn load_settings(conn: &Connection) -> Result<RemoteWorkSettings, String> {
+    Ok(db::get_json_setting(conn, SETTINGS_KEY)?.unwrap_or_default())
+}
+
+/// Remote days between two dates (inclusive), by date then name
+pub fn list(conn: &Connection, start_date: &str, end_date: &str) -> Result<Vec<RemoteDay>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT r.user_id, u.display_name, r.date, r.note, r.marked_by, r.created_at,
+                    COALESCE(s.status = 'remote', 0)
+             FROM remote_days r
+             JOIN users u ON u.id = r.user_id
+             LEFT JOIN attendance_day_summary s ON s.user_id = r.user_id AND s.date = r.date
+             WHERE r.date >= ?1 AND r.date <= ?2
+             ORDER BY r.date, u.display_name",
+        )
+        .map_err(|e| format!("Failed to query remote days: {}", e))?;
+    let rows = stmt
+        .query_map(params![start_date, end_date], |row| {
+            Ok(RemoteDay {
+                user_id: row.get(0)?,
+                display_name: row.get(1)?,
+                date: row.get(2)?,
+                note: row.get(3)?,
+                marked_by: row.get(4)?,
+                created_at: row.get(5)?,
+                counted: row.get(6)?,
+            })
+        })
+        .map_err(|e| format!("Failed to query remote days: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read remote days: {}", e))
+}
+
+/// An error naming the first locked period overlapping the dates, if any
+pub fn ensure_unlocked(conn: &Connection, start_date: &str, end_date: &str) -> Result<(), String> {
+    let locked: Option<(String, String)> = conn
+        .query_row(
+            "SELECT start_date, end_date FROM locked_periods
+             WHERE start_date <= ?2 AND end_date >= ?1
+             ORDER BY start_date LIMIT 1",
+            params![start_date, end_date],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to check locked periods: {}", e))?;
+    match locked {
+        Some((start, end)) => Err(format!("The locked period {} to {} can't be changed", start, end)),
+        None => Ok(()),
+    }
+}
+
+/// Mark every user in `user_ids` as working remotely on every date in
+/// `dates`, replacing the note of days already marked. Returns the number
+/// of user/date pairs.
+pub fn mark(
+    conn: &mut Connection,
+    user_ids: &[String],
+    dates: &[String],
+    note: Option<&str>,
+    actor: &str,
+) -> Result<u32, String> {
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    {
+        let mut stmt = tx
+            .prepare(
+                "INSERT INTO remote_days (user_id, date, note, marked_by) VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT(user_id, date) DO UPDATE SET note = excluded.note, marked_by = excluded.marked_by",
+            )
+            .map_err(|e| format!("Failed to prepare remote days: {}", e))?;
+        for user_id in user_ids {
+            for date in dates {
+                stmt.execute(params![user_id, date, note, actor])
+                    .map_err(|e| format!("Failed to mark {} on {}: {}", user_id, date, e))?;
+            }
+        }
+    }
+    tx.commit()
+        .map_err(|e| format!("Failed to commit remote days: {}", e))?;
+    Ok((user_ids.len() * dates.len()) as u32)
+}
+
+/// Remove the marks, and the remote summaries they made, of every user in
+/// `user_ids` on every date in `dates`. Returns the number of marks removed.
+/// The days are recomputed from punches afterwards by the caller.
+pub fn unmark(conn: &mut Connection, user_ids: &[String], dates: &[String]) -> Result<u32, String> {
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let mut removed = 0;
+    for user_id in user_ids {
+        for date in dates {
+            removed += tx
+                .execute(
+                    "DELETE FROM remote_days WHERE user_id = ?1 AND date = ?2",
+                    params![user_id, date],
+                )
+                .map_err(|e| format!("Failed to unmark {} on {}: {}", user_id, date, e))?;
+            tx.execute(
+                "DELETE FROM attendance_day_summary WHERE user_id = ?1 AND date = ?2 AND status = ?3",
+                params![user_id, date, STATUS_REMOTE],
+            )
+            .map_err(|e| format!("Failed to remove summary of {} on {}: {}", user_id, date, e))?;
+        }
+    }
+    tx.commit()
+        .map_err(|e| format!("Failed to commit remote days: {}", e))?;
+    Ok(removed as u32)
+}
+
+/// Marked user/date pairs between two dates (inclusive)
+fn marked_between(conn: &Connection, start_date: &str, end_date: &str) -> Result<Vec<(String, String)>, String> {
+    let mut stmt = conn
+        .prepare("SELECT user_id, date FROM remote_days WHERE date >= ?1 AND date <= ?2")
+        .map_err(|e| format!("Failed to query remote days: {}", e))?;
+    let rows = stmt
+        .query_map(params![start_date, end_date], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| format!("Failed to query remote days: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read remote days: {}", e))
+}
+
+/// User/working day pairs with a punch through the API between two dates
+/// (inclusive)
+fn api_punch_days(conn: &Connection, start_date: &str, end_date: &str) -> Result<HashSet<(String, String)>, String> {
+    let cutoff_hour = engine::day_cutoff_hour(conn)?;
+    let (start, end) = logs::working_day_bounds(start_date, end_date, cutoff_hour);
+    let matcher = UserMatcher::load(conn)?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT device_user_id, timestamp FROM attendance_logs_raw
+             WHERE device_id = ?1 AND timestamp >= ?2 AND timestamp < ?3 AND {}",
+            logs::not_voided_sql("id")
+        ))
+        .map_err(|e| format!("Failed to query API punches: {}", e))?;
+    let punches: Vec<(String, String)> = stmt
+        .query_map(params![API_DEVICE_ID, start, end], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| format!("Failed to query API punches: {}", e))?
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read API punches: {}", e))?;
+    Ok(punches
+        .into_iter()
+        .filter_map(|(device_user_id, timestamp)| {
+            let user_id = matcher.resolve(&device_user_id)?;
+            Some((user_id.to_string(), rules::working_date(&timestamp, cutoff_hour)))
+        })
+        .collect())
+}
+
+/// Give every marked day between two dates (inclusive) a remote summary,
+/// keeping any punch times. With `requirePunch`, days without an API punch
+/// are left as computed, and remote summaries they had are removed. Days
+/// of locked periods are left as they are. Returns the number of summaries
+/// written.
+pub fn apply(conn: &mut Connection, start_date: &str, end_date: &str, source: &ChangeSource) -> Result<u32, String> {
+    let marked = marked_between(conn, start_date, end_date)?;
+    if marked.is_empty() {
+        return Ok(0);
+    }
+    let settings = load_settings(conn)?;
+    let punched = if settings.require_punch {
+        api_punch_days(conn, start_date, end_date)?
+    } else {
+        HashSet::new()
+    };
+    let existing: HashMap<(String, String), DaySummary> = summaries::between(conn, start_date, end_date, None)?
+        .into_iter()
+        .map(|s| ((s.user_id.clone(), s.date.clone()), s))
+        .collect();
+
+    let mut remote = Vec::new();
+    let mut withdrawn = Vec::new();
+    for key in marked {
+        let current = existing.get(&key);
+        let is_remote = current.is_some_and(|s| s.status == STATUS_REMOTE);
+        if settings.require_punch && !punched.contains(&key) {
+            if is_remote {
+                withdrawn.push(key);
+            }
+            continue;
+        }
+        if is_remote {
+            continue;
+        }
+        let (user_id, date) = key;
+        let summary = current.cloned().unwrap_or(DaySummary {
+            user_id,
+            date,
+            check_in_time: None,
+            check_out_time: None,
+            is_incomplete: false,
+            late_minutes: 0,
+            early_minutes: 0,
+            status: STATUS_REMOTE.to_string(),
+            flags: Vec::new(),
+        });
+        remote.push(rules::remote_day(summary));
+    }
+
+    summaries::upsert(conn, &remote, source)?;
+    for (user_id, date) in &withdrawn {
+        conn.execute(
+            "DELETE FROM attendance_day_summary
+             WHERE user_id = ?1 AND date = ?2 AND status = ?3
+               AND NOT EXISTS (SELECT 1 FROM locked_periods WHERE ?2 BETWEEN start_date AND end_date)",
+            params![user_id, date, STATUS_REMOTE],
+        )
+        .map_err(|e| format!("Failed to remove summary of {} on {}: {}", user_id, date, e))?;
+    }
+    Ok(remote.len() as u32)
+}
@@ -0,0 +1,38 @@
+//! Remote work data types for Tauri command serialization
+
+use serde::{Deserialize, Serialize};
+
+/// Remote work settings (`remoteWork` setting)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RemoteWorkSettings {
+    /// Marked days only count once the user punched through the API that day
+    pub require_punch: bool,
+}
+
+/// A day a user worked remotely (one remote_days row)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteDay {
+    pub user_id: String,
+    pub display_name: String,
+    pub date: String,
+    pub note: Option<String>,
+    pub marked_by: Option<String>,
+    pub created_at: String,
+    /// Whether the day's summary has status "remote"; false while it waits
+    /// for an API punch
+    pub counted: bool,
+}
+
+/// Result of marking remote days
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteMarkResult {
+    /// User/date pairs marked
+    pub marked: u32,
+    /// Marked days now counted as remote
+    pub counted: u32,
+    /// Marked days waiting for an API punch
+    pub awaiting_punch: u32,
+}
@@ -0,0 +1,18 @@
+//! Tauri command handlers for the report cache.
+
+use super::store;
+use super::types::*;
+use crate::db;
+
+#[tauri::command]
+pub async fn get_report_cache_stats(app: tauri::AppHandle) -> Result<ReportCacheStats, String> {
+    store::stats(&*db::open(&app)?)
+}
+
+/// Drop every cached report so the next request computes it again
+#[tauri::command]
+pub async fn clear_report_cache(app: tauri::AppHandle) -> Result<usize, String> {
+    let cleared = store::clear(&*db::open(&app)?)?;
+    log::info!("[report_cache::cmd] Cleared {} cached reports", cleared);
+    Ok(cleared)
+}
@@ -0,0 +1,12 @@
+//! Cache of computed report payloads
+//!
+//! Reports are stored under their name and parameters together with the
+//! data version of the dates they cover. Triggers bump a per-date counter
+//! whenever punches, summaries or holidays of that date change, and a global
+//! one when users or attendance rules do; the data version of a period is the
+//! sum of those counters, so any change in the period means a miss and the
+//! report is computed again.
+
+pub mod commands;
+pub mod store;
+pub mod types;
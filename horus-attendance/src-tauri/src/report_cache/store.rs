@@ -0,0 +1,142 @@
+//! Reading and writing cached reports
+
+use chrono::{Duration, NaiveDate};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use super::types::{ReportCacheCount, ReportCacheStats};
+
+/// Entries neither written nor read for this long are pruned
+const MAX_AGE_DAYS: i64 = 30;
+
+/// Data version of the dates from `start_date` to `end_date` (inclusive).
+/// The day after is included, since a working day can end on it.
+pub fn data_version(conn: &Connection, start_date: &str, end_date: &str) -> Result<i64, String> {
+    let end = NaiveDate::parse_from_str(end_date, "%Y-%m-%d")
+        .map(|d| (d + Duration::days(1)).format("%Y-%m-%d").to_string())
+        .map_err(|_| format!("Invalid date (expected YYYY-MM-DD): {}", end_date))?;
+    conn.query_row(
+        "SELECT COALESCE(SUM(version), 0) FROM report_data_versions
+         WHERE date = '*' OR (date >= ?1 AND date <= ?2)",
+        params![start_date, end],
+        |row| row.get(0),
+    )
+    .map_err(|e| format!("Failed to read data version: {}", e))
+}
+
+/// A cached payload computed at `version`, counting the hit
+fn get<T: DeserializeOwned>(conn: &Connection, report: &str, params: &str, version: i64) -> Result<Option<T>, String> {
+    let payload: Option<String> = conn
+        .query_row(
+            "SELECT payload FROM report_cache WHERE report = ?1 AND params = ?2 AND data_version = ?3",
+            params![report, params, version],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to read cached report: {}", e))?;
+    let Some(payload) = payload else {
+        return Ok(None);
+    };
+    conn.execute(
+        "UPDATE report_cache SET hits = hits + 1, last_hit_at = datetime('now') WHERE report = ?1 AND params = ?2",
+        params![report, params],
+    )
+    .map_err(|e| format!("Failed to count cache hit: {}", e))?;
+    Ok(serde_json::from_str(&payload).ok())
+}
+
+fn put<T: Serialize>(conn: &Connection, report: &str, params: &str, version: i64, value: &T) -> Result<(), String> {
+    let payload = serde_json::to_string(value).map_err(|e| format!("Failed to serialize report: {}", e))?;
+    conn.execute(
+        "INSERT INTO report_cache (report, params, data_version, payload) VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(report, params) DO UPDATE SET
+             data_version = excluded.data_version,
+             payload = excluded.payload,
+             created_at = datetime('now'),
+             last_hit_at = NULL,
+             hits = 0",
+        params![report, params, version, payload],
+    )
+    .map_err(|e| format!("Failed to cache report: {}", e))?;
+    conn.execute(
+        "DELETE FROM report_cache WHERE COALESCE(last_hit_at, created_at) < datetime('now', ?1)",
+        [format!("-{} days", MAX_AGE_DAYS)],
+    )
+    .map_err(|e| format!("Failed to prune report cache: {}", e))?;
+    Ok(())
+}
+
+/// The report `report` with `params` over the dates from `start_date` to
+/// `end_date`, from the cache while the data of those dates is unchanged,
+/// otherwise from `compute`. Cache failures are logged and the report is
+/// computed as if there were no cache.
+pub fn cached<P, T, F>(
+    conn: &Connection,
+    report: &str,
+    params: &P,
+    start_date: &str,
+    end_date: &str,
+    compute: F,
+) -> Result<T, String>
+where
+    P: Serialize,
+    T: Serialize + DeserializeOwned,
+    F: FnOnce() -> Result<T, String>,
+{
+    let params = serde_json::to_string(params).map_err(|e| format!("Failed to serialize report parameters: {}", e))?;
+    // Read before computing: a change during the computation leaves the entry stale
+    let version = match data_version(conn, start_date, end_date) {
+        Ok(version) => version,
+        Err(e) => {
+            log::warn!("[report_cache] {}", e);
+            return compute();
+        }
+    };
+    match get(conn, report, &params, version) {
+        Ok(Some(value)) => return Ok(value),
+        Ok(None) => {}
+        Err(e) => log::warn!("[report_cache] {}", e),
+    }
+    let value = compute()?;
+    if let Err(e) = put(conn, report, &params, version, &value) {
+        log::warn!("[report_cache] {}", e);
+    }
+    Ok(value)
+}
+
+pub fn stats(conn: &Connection) -> Result<ReportCacheStats, String> {
+    let (entries, hits, bytes) = conn
+        .query_row(
+            "SELECT COUNT(*), COALESCE(SUM(hits), 0), COALESCE(SUM(length(payload)), 0) FROM report_cache",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .map_err(|e| format!("Failed to read report cache: {}", e))?;
+    let mut stmt = conn
+        .prepare("SELECT report, COUNT(*), SUM(hits) FROM report_cache GROUP BY report ORDER BY report")
+        .map_err(|e| format!("Failed to read report cache: {}", e))?;
+    let reports = stmt
+        .query_map([], |row| {
+            Ok(ReportCacheCount {
+                report: row.get(0)?,
+                entries: row.get(1)?,
+                hits: row.get(2)?,
+            })
+        })
+        .map_err(|e| format!("Failed to read report cache: {}", e))?
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read report cache: {}", e))?;
+    Ok(ReportCacheStats {
+        entries,
+        hits,
+        bytes,
+        reports,
+    })
+}
+
+/// Remove every cached report; returns how many there were
+pub fn clear(conn: &Connection) -> Result<usize, String> {
+    conn.execute("DELETE FROM report_cache", [])
+        .map_err(|e| format!("Failed to clear report cache: {}", e))
+}
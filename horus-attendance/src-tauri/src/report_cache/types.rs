@@ -0,0 +1,22 @@
+//! Report cache data types for Tauri command serialization
+
+use serde::{Deserialize, Serialize};
+
+/// Size and use of the report cache
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportCacheStats {
+    pub entries: i64,
+    pub hits: i64,
+    pub bytes: i64,
+    /// Entries per report name
+    pub reports: Vec<ReportCacheCount>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportCacheCount {
+    pub report: String,
+    pub entries: i64,
+    pub hits: i64,
+}
@@ -0,0 +1,91 @@
+//! Tauri command handlers for rosters.
+
+use super::csv;
+use super::store;
+use super::types::*;
+use super::variance;
+use crate::db;
+use crate::shifts::commands::validate_time;
+use crate::summary::commands::validate_date;
+use crate::summary::engine;
+use crate::summary::rules::ends_after_start;
+use crate::summary::types::AttendanceRules;
+
+/// Check dates and times; shifts must end after they start within the
+/// working day, which may run past midnight
+pub(crate) fn validate_entry(entry: &RosterEntryInput, rules: &AttendanceRules) -> Result<(), String> {
+    validate_date(&entry.date)?;
+    validate_time(&entry.start_time)?;
+    validate_time(&entry.end_time)?;
+    if !ends_after_start(&entry.start_time, &entry.end_time, rules) {
+        return Err(format!(
+            "Planned shift on {} must end after it starts ({} - {})",
+            entry.date, entry.start_time, entry.end_time
+        ));
+    }
+    Ok(())
+}
+
+fn validate_range(start_date: &str, end_date: &str) -> Result<(), String> {
+    validate_date(start_date)?;
+    validate_date(end_date)?;
+    if start_date > end_date {
+        return Err("Start date must not be after end date".to_string());
+    }
+    Ok(())
+}
+
+/// Planned shifts between two dates (inclusive)
+#[tauri::command]
+pub async fn list_roster(app: tauri::AppHandle, start_date: String, end_date: String) -> Result<Vec<RosterEntry>, String> {
+    validate_range(&start_date, &end_date)?;
+    store::between(&*db::open(&app)?, &start_date, &end_date)
+}
+
+/// Save planned shifts, replacing any for the same user and date. Nothing is
+/// saved if any entry is invalid.
+#[tauri::command]
+pub async fn save_roster_entries(app: tauri::AppHandle, entries: Vec<RosterEntryInput>) -> Result<(), String> {
+    let mut conn = db::open(&app)?;
+    let rules = engine::load_rules(&conn)?;
+    for entry in &entries {
+        validate_entry(entry, &rules)?;
+        if db::users::get(&conn, &entry.user_id)?.is_none() {
+            return Err(format!("User not found: {}", entry.user_id));
+        }
+    }
+    store::upsert(&mut conn, &entries)
+}
+
+#[tauri::command]
+pub async fn delete_roster_entry(app: tauri::AppHandle, id: String) -> Result<bool, String> {
+    store::delete(&*db::open(&app)?, &id)
+}
+
+/// Import planned shifts from CSV text. Valid lines are saved even when
+/// others are rejected; the rejected ones are reported by line number.
+#[tauri::command]
+pub async fn import_roster_csv(app: tauri::AppHandle, content: String) -> Result<RosterImportResult, String> {
+    let mut conn = db::open(&app)?;
+    let (entries, errors) = csv::parse(&conn, &content)?;
+    store::upsert(&mut conn, &entries)?;
+    log::info!(
+        "[roster::cmd] Imported {} roster entries ({} lines rejected)",
+        entries.len(),
+        errors.len()
+    );
+    Ok(RosterImportResult {
+        imported: entries.len() as u32,
+        errors,
+    })
+}
+
+/// Planned versus actual attendance and daily coverage between two dates
+#[tauri::command]
+pub async fn get_roster_variance(app: tauri::AppHandle, start_date: String, end_date: String) -> Result<RosterReport, String> {
+    validate_range(&start_date, &end_date)?;
+    let conn = db::open(&app)?;
+    tauri::async_runtime::spawn_blocking(move || variance::report(&conn, &start_date, &end_date))
+        .await
+        .map_err(|e| format!("Roster report task failed: {}", e))?
+}
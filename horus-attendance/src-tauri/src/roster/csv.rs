@@ -0,0 +1,128 @@
+//! Roster import from CSV
+//!
+//! One planned shift per line: `employee,date,start,end[,note]`. The employee
+//! column may hold an employee code, a device user ID or a display name, tried
+//! in that order. A first line without a date in the second column is taken
+//! to be a header. Dates are YYYY-MM-DD and times HH:MM.
+
+use std::collections::HashMap;
+
+use rusqlite::Connection;
+
+use super::commands::validate_entry;
+use super::types::{RosterEntryInput, RosterImportError};
+use crate::summary::engine;
+use crate::summary::types::AttendanceRules;
+
+/// Split one CSV line, honouring double-quoted fields with `""` escapes
+pub(crate) fn split_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields.into_iter().map(|f| f.trim().to_string()).collect()
+}
+
+/// User IDs by employee code, device user ID and lowercased display name
+struct UserIndex {
+    by_code: HashMap<String, String>,
+    by_device_user_id: HashMap<String, String>,
+    by_name: HashMap<String, String>,
+}
+
+impl UserIndex {
+    fn load(conn: &Connection) -> Result<Self, String> {
+        let mut index = Self {
+            by_code: HashMap::new(),
+            by_device_user_id: HashMap::new(),
+            by_name: HashMap::new(),
+        };
+        let mut stmt = conn
+            .prepare("SELECT id, employee_code, device_user_id, display_name FROM users WHERE status = 'active'")
+            .map_err(|e| format!("Failed to query users: {}", e))?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, Option<String>>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, String>(3)?,
+                ))
+            })
+            .map_err(|e| format!("Failed to query users: {}", e))?;
+        for row in rows {
+            let (id, code, device_user_id, name) = row.map_err(|e| format!("Failed to read users: {}", e))?;
+            if let Some(code) = code.filter(|c| !c.is_empty()) {
+                index.by_code.insert(code, id.clone());
+            }
+            if let Some(device_user_id) = device_user_id.filter(|d| !d.is_empty()) {
+                index.by_device_user_id.insert(device_user_id, id.clone());
+            }
+            index.by_name.insert(name.to_lowercase(), id);
+        }
+        Ok(index)
+    }
+
+    fn resolve(&self, employee: &str) -> Option<&String> {
+        self.by_code
+            .get(employee)
+            .or_else(|| self.by_device_user_id.get(employee))
+            .or_else(|| self.by_name.get(&employee.to_lowercase()))
+    }
+}
+
+fn parse_line(fields: &[String], users: &UserIndex, rules: &AttendanceRules) -> Result<RosterEntryInput, String> {
+    if fields.len() < 4 {
+        return Err(format!("Expected at least 4 columns, found {}", fields.len()));
+    }
+    let user_id = users
+        .resolve(&fields[0])
+        .ok_or_else(|| format!("No active employee matches '{}'", fields[0]))?;
+    let entry = RosterEntryInput {
+        user_id: user_id.clone(),
+        date: fields[1].clone(),
+        start_time: fields[2].clone(),
+        end_time: fields[3].clone(),
+        note: fields.get(4).filter(|n| !n.is_empty()).cloned(),
+    };
+    validate_entry(&entry, rules)?;
+    Ok(entry)
+}
+
+/// Entries from `content`, and the lines that were rejected
+pub fn parse(conn: &Connection, content: &str) -> Result<(Vec<RosterEntryInput>, Vec<RosterImportError>), String> {
+    let users = UserIndex::load(conn)?;
+    let rules = engine::load_rules(conn)?;
+    let mut entries = Vec::new();
+    let mut errors = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields = split_line(line);
+        let is_header = i == 0
+            && fields
+                .get(1)
+                .map_or(true, |d| chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d").is_err());
+        if is_header {
+            continue;
+        }
+        match parse_line(&fields, &users, &rules) {
+            Ok(entry) => entries.push(entry),
+            Err(message) => errors.push(RosterImportError { line: i + 1, message }),
+        }
+    }
+    Ok((entries, errors))
+}
@@ -0,0 +1,12 @@
+//! Rosters: planned shifts per user and date
+//!
+//! A roster entry fixes a user's hours for one date, taking precedence over
+//! their assigned shift and the global work hours when summaries are
+//! computed. Entries can be imported from CSV. Comparing them with the day
+//! summaries shows coverage gaps as well as lateness.
+
+pub mod commands;
+pub mod csv;
+pub mod store;
+pub mod types;
+pub mod variance;
@@ -0,0 +1,82 @@
+//! Storage for roster entries
+
+use std::collections::HashMap;
+
+use rusqlite::{params, Connection};
+
+use super::types::{RosterEntry, RosterEntryInput};
+
+/// Entries between two dates (inclusive), by date then user
+pub fn between(conn: &Connection, start_date: &str, end_date: &str) -> Result<Vec<RosterEntry>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, user_id, date, start_time, end_time, note FROM roster_entries
+             WHERE date >= ?1 AND date <= ?2
+             ORDER BY date, user_id",
+        )
+        .map_err(|e| format!("Failed to query roster: {}", e))?;
+    let rows = stmt
+        .query_map(params![start_date, end_date], |row| {
+            Ok(RosterEntry {
+                id: row.get(0)?,
+                user_id: row.get(1)?,
+                date: row.get(2)?,
+                start_time: row.get(3)?,
+                end_time: row.get(4)?,
+                note: row.get(5)?,
+            })
+        })
+        .map_err(|e| format!("Failed to query roster: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read roster: {}", e))
+}
+
+/// Planned (start, end) keyed by (user, date)
+pub type PlannedHours = HashMap<(String, String), (String, String)>;
+
+/// Planned hours between two dates (inclusive)
+pub fn hours_between(conn: &Connection, start_date: &str, end_date: &str) -> Result<PlannedHours, String> {
+    Ok(between(conn, start_date, end_date)?
+        .into_iter()
+        .map(|e| ((e.user_id, e.date), (e.start_time, e.end_time)))
+        .collect())
+}
+
+/// Save `entries` in one transaction, replacing existing ones for the same
+/// user and date
+pub fn upsert(conn: &mut Connection, entries: &[RosterEntryInput]) -> Result<(), String> {
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    {
+        let mut stmt = tx
+            .prepare(
+                "INSERT INTO roster_entries (id, user_id, date, start_time, end_time, note)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                 ON CONFLICT(user_id, date) DO UPDATE SET
+                    start_time = excluded.start_time,
+                    end_time = excluded.end_time,
+                    note = excluded.note,
+                    updated_at = datetime('now')",
+            )
+            .map_err(|e| format!("Failed to prepare roster upsert: {}", e))?;
+        for entry in entries {
+            stmt.execute(params![
+                uuid::Uuid::new_v4().to_string(),
+                entry.user_id,
+                entry.date,
+                entry.start_time,
+                entry.end_time,
+                entry.note
+            ])
+            .map_err(|e| format!("Failed to save roster entry for {} on {}: {}", entry.user_id, entry.date, e))?;
+        }
+    }
+    tx.commit().map_err(|e| format!("Failed to commit roster: {}", e))
+}
+
+pub fn delete(conn: &Connection, id: &str) -> Result<bool, String> {
+    conn.execute("DELETE FROM roster_entries WHERE id = ?1", [id])
+        .map(|n| n > 0)
+        .map_err(|e| format!("Failed to delete roster entry: {}", e))
+}
@@ -0,0 +1,84 @@
+//! Roster data types for Tauri command serialization
+
+use serde::{Deserialize, Serialize};
+
+/// A planned shift (one roster_entries row)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RosterEntry {
+    pub id: String,
+    pub user_id: String,
+    /// YYYY-MM-DD
+    pub date: String,
+    /// HH:MM
+    pub start_time: String,
+    /// HH:MM
+    pub end_time: String,
+    pub note: Option<String>,
+}
+
+/// A planned shift to save; replaces any entry for the same user and date
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RosterEntryInput {
+    pub user_id: String,
+    pub date: String,
+    pub start_time: String,
+    pub end_time: String,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+/// A CSV line that could not be imported
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RosterImportError {
+    /// 1-based line number
+    pub line: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RosterImportResult {
+    pub imported: u32,
+    pub errors: Vec<RosterImportError>,
+}
+
+/// Planned versus actual for one user and date
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RosterVariance {
+    pub user_id: String,
+    pub display_name: String,
+    pub date: String,
+    pub planned_start: Option<String>,
+    pub planned_end: Option<String>,
+    pub check_in_time: Option<String>,
+    pub check_out_time: Option<String>,
+    pub planned_minutes: i64,
+    pub worked_minutes: i64,
+    /// Worked minus planned
+    pub variance_minutes: i64,
+    /// "covered", "partial", "gap" (planned but absent) or "unplanned"
+    pub status: String,
+}
+
+/// Planned headcount against attendance for one date
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CoverageDay {
+    pub date: String,
+    pub planned: u32,
+    /// Planned users who turned up
+    pub attended: u32,
+    pub gaps: u32,
+    pub unplanned: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RosterReport {
+    pub rows: Vec<RosterVariance>,
+    pub coverage: Vec<CoverageDay>,
+}
@@ -0,0 +1,124 @@
+//! Planned versus actual attendance
+//!
+//! Each roster entry is matched with the day summary for the same user and
+//! date. Days a rostered user worked without an entry are listed as
+//! unplanned; users who are never rostered in the range are left out, so a
+//! partly rostered site isn't buried in unplanned rows.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use rusqlite::Connection;
+
+use super::store;
+use super::types::{CoverageDay, RosterReport, RosterVariance};
+use crate::db::{summaries, users};
+use crate::summary::rules::span_minutes;
+use crate::summary::types::DaySummary;
+
+/// A rostered day counts as covered when worked time falls short of the plan
+/// by no more than this
+const PARTIAL_TOLERANCE_MINUTES: i64 = 15;
+
+fn worked_minutes(summary: Option<&DaySummary>) -> i64 {
+    match summary.map(|s| (s.check_in_time.as_deref(), s.check_out_time.as_deref())) {
+        Some((Some(check_in), Some(check_out))) => span_minutes(check_in, check_out),
+        _ => 0,
+    }
+}
+
+fn attended(summary: Option<&DaySummary>) -> bool {
+    summary.is_some_and(|s| s.check_in_time.is_some() || s.check_out_time.is_some())
+}
+
+/// Variance rows ordered by date then name, with per-date coverage
+pub fn report(conn: &Connection, start_date: &str, end_date: &str) -> Result<RosterReport, String> {
+    let entries = store::between(conn, start_date, end_date)?;
+    let names: HashMap<String, String> = users::list(conn)?
+        .into_iter()
+        .map(|u| (u.id, u.display_name))
+        .collect();
+    let mut by_day: HashMap<(String, String), DaySummary> = summaries::between(conn, start_date, end_date, None)?
+        .into_iter()
+        .map(|s| ((s.user_id.clone(), s.date.clone()), s))
+        .collect();
+    let rostered: HashSet<String> = entries.iter().map(|e| e.user_id.clone()).collect();
+
+    let mut rows = Vec::new();
+    let mut coverage: BTreeMap<String, CoverageDay> = BTreeMap::new();
+    for entry in entries {
+        let summary = by_day.remove(&(entry.user_id.clone(), entry.date.clone()));
+        let planned = span_minutes(&entry.start_time, &entry.end_time);
+        let worked = worked_minutes(summary.as_ref());
+        let status = if !attended(summary.as_ref()) {
+            "gap"
+        } else if worked + PARTIAL_TOLERANCE_MINUTES >= planned {
+            "covered"
+        } else {
+            "partial"
+        };
+
+        let day = coverage.entry(entry.date.clone()).or_insert_with(|| CoverageDay {
+            date: entry.date.clone(),
+            planned: 0,
+            attended: 0,
+            gaps: 0,
+            unplanned: 0,
+        });
+        day.planned += 1;
+        if status == "gap" {
+            day.gaps += 1;
+        } else {
+            day.attended += 1;
+        }
+
+        rows.push(RosterVariance {
+            display_name: names.get(&entry.user_id).cloned().unwrap_or_default(),
+            date: entry.date,
+            planned_start: Some(entry.start_time),
+            planned_end: Some(entry.end_time),
+            check_in_time: summary.as_ref().and_then(|s| s.check_in_time.clone()),
+            check_out_time: summary.as_ref().and_then(|s| s.check_out_time.clone()),
+            planned_minutes: planned,
+            worked_minutes: worked,
+            variance_minutes: worked - planned,
+            status: status.to_string(),
+            user_id: entry.user_id,
+        });
+    }
+
+    for ((user_id, date), summary) in by_day {
+        if !rostered.contains(&user_id) || !attended(Some(&summary)) {
+            continue;
+        }
+        let worked = worked_minutes(Some(&summary));
+        coverage
+            .entry(date.clone())
+            .or_insert_with(|| CoverageDay {
+                date: date.clone(),
+                planned: 0,
+                attended: 0,
+                gaps: 0,
+                unplanned: 0,
+            })
+            .unplanned += 1;
+        rows.push(RosterVariance {
+            display_name: names.get(&user_id).cloned().unwrap_or_default(),
+            date,
+            planned_start: None,
+            planned_end: None,
+            check_in_time: summary.check_in_time,
+            check_out_time: summary.check_out_time,
+            planned_minutes: 0,
+            worked_minutes: worked,
+            variance_minutes: worked,
+            status: "unplanned".to_string(),
+            user_id,
+        });
+    }
+
+    rows.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.display_name.cmp(&b.display_name)));
+    Ok(RosterReport {
+        rows,
+        coverage: coverage.into_values().collect(),
+    })
+}
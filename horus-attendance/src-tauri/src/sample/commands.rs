@@ -0,0 +1,49 @@
+//! Tauri command handlers for sample data.
+
+use super::generate::{self, MAX_MONTHS, MAX_USERS};
+use super::types::SampleDataResult;
+use crate::activity::registry;
+use crate::db;
+
+/// Fill an empty database with `users` people and `months` of attendance.
+/// Nothing happens unless `confirm` is set, and it refuses a database that
+/// already has users, departments or punches.
+#[tauri::command]
+pub async fn generate_sample_data(
+    app: tauri::AppHandle,
+    users: u32,
+    months: u32,
+    confirm: bool,
+    seed: Option<u32>,
+) -> Result<SampleDataResult, String> {
+    if !confirm {
+        return Err("Sample data fills the database with made-up records; confirm to go ahead".to_string());
+    }
+    if !(1..=MAX_USERS).contains(&users) {
+        return Err(format!("Users must be between 1 and {}", MAX_USERS));
+    }
+    if !(1..=MAX_MONTHS).contains(&months) {
+        return Err(format!("Months must be between 1 and {}", MAX_MONTHS));
+    }
+    log::info!("[sample::cmd] Generating {} users and {} months of attendance", users, months);
+
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let _activity = registry::begin(&app, "Generating sample data")?;
+        let mut conn = db::open(&app)?;
+        if !generate::is_empty(&conn)? {
+            return Err("Sample data can only go into an empty database; switch to a fresh profile first".to_string());
+        }
+        generate::generate(&mut conn, users, months, seed.unwrap_or(1))
+    })
+    .await
+    .map_err(|e| format!("Sample data task failed: {}", e))??;
+
+    log::info!(
+        "[sample::cmd] Created {} users and {} punches from {} to {}",
+        result.users,
+        result.punches,
+        result.start_date,
+        result.end_date
+    );
+    Ok(result)
+}
@@ -0,0 +1,219 @@
+//! Generating the sample data
+
+use chrono::{Datelike, Duration, Local, Months, NaiveDate, NaiveTime};
+use rusqlite::{params, Connection};
+
+use super::types::SampleDataResult;
+use crate::db::logs::{self, DevicePunch};
+use crate::shifts::{store as shifts, types::ShiftAssignment};
+use crate::summary::engine;
+use crate::summary::types::ChangeSource;
+use crate::zkteco::demo::{between, chance, person_name, roll};
+
+pub const MAX_USERS: u32 = 2000;
+pub const MAX_MONTHS: u32 = 24;
+
+/// Address of the sample device: the TEST-NET-1 broadcast address, which
+/// demo devices never get
+const DEVICE_IP: &str = "192.0.2.255";
+
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.3fZ";
+
+struct Department {
+    name: &'static str,
+    start: &'static str,
+    end: &'static str,
+    /// ISO weekdays worked
+    days: &'static [u32],
+}
+
+const DEPARTMENTS: &[Department] = &[
+    Department {
+        name: "Operations",
+        start: "07:00",
+        end: "15:30",
+        days: &[1, 2, 3, 4, 5],
+    },
+    Department {
+        name: "Warehouse",
+        start: "06:00",
+        end: "14:00",
+        days: &[1, 2, 3, 4, 5, 6],
+    },
+    Department {
+        name: "Customer Service",
+        start: "09:00",
+        end: "18:00",
+        days: &[1, 2, 3, 4, 5],
+    },
+    Department {
+        name: "Finance",
+        start: "08:30",
+        end: "17:00",
+        days: &[1, 2, 3, 4, 5],
+    },
+    Department {
+        name: "IT",
+        start: "09:00",
+        end: "17:30",
+        days: &[1, 2, 3, 4, 5],
+    },
+    Department {
+        name: "Human Resources",
+        start: "08:00",
+        end: "16:30",
+        days: &[1, 2, 3, 4, 5],
+    },
+];
+
+/// Whether the database has no users, departments or punches yet
+pub fn is_empty(conn: &Connection) -> Result<bool, String> {
+    conn.query_row(
+        "SELECT (SELECT COUNT(*) FROM users) + (SELECT COUNT(*) FROM departments)
+              + (SELECT COUNT(*) FROM attendance_logs_raw)",
+        [],
+        |row| row.get::<_, i64>(0),
+    )
+    .map(|count| count == 0)
+    .map_err(|e| format!("Failed to count existing data: {}", e))
+}
+
+/// One person: where they work and how reliably
+struct Person {
+    device_user_id: String,
+    department: &'static Department,
+    late_rate: f64,
+    absence_rate: f64,
+    overtime_rate: f64,
+}
+
+/// Create the sample device, departments, users and shifts, then `months`
+/// of punches up to yesterday, and compute their summaries
+pub fn generate(conn: &mut Connection, users: u32, months: u32, seed: u32) -> Result<SampleDataResult, String> {
+    let end = Local::now().date_naive() - Duration::days(1);
+    let start = end
+        .checked_sub_months(Months::new(months))
+        .ok_or_else(|| "Date range out of bounds".to_string())?
+        + Duration::days(1);
+
+    let device_id = uuid::Uuid::new_v4().to_string();
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    tx.execute(
+        "INSERT INTO devices (id, name, ip, port, comm_key, sync_mode) VALUES (?1, 'Sample data', ?2, 4370, '', 'manual')",
+        params![device_id, DEVICE_IP],
+    )
+    .map_err(|e| format!("Failed to save device: {}", e))?;
+
+    let mut department_ids = Vec::new();
+    for department in DEPARTMENTS {
+        let id = uuid::Uuid::new_v4().to_string();
+        tx.execute("INSERT INTO departments (id, name) VALUES (?1, ?2)", params![id, department.name])
+            .map_err(|e| format!("Failed to save department: {}", e))?;
+        department_ids.push(id);
+    }
+
+    let mut people = Vec::new();
+    let mut assignments = Vec::new();
+    for user in 1..=users {
+        let index = (roll(seed, user, 0, 3) % DEPARTMENTS.len() as u64) as usize;
+        let department = &DEPARTMENTS[index];
+        let id = uuid::Uuid::new_v4().to_string();
+        let device_user_id = (1000 + user).to_string();
+        let name = person_name(seed, user);
+        tx.execute(
+            "INSERT INTO users (id, device_user_id, device_name, display_name, department_id, employee_code)
+             VALUES (?1, ?2, ?3, ?3, ?4, ?5)",
+            params![id, device_user_id, name, department_ids[index], format!("EMP{:04}", user)],
+        )
+        .map_err(|e| format!("Failed to save user: {}", e))?;
+
+        assignments.push(ShiftAssignment {
+            user_id: id,
+            start_time: department.start.to_string(),
+            end_time: department.end.to_string(),
+        });
+        // Most people are punctual; a few are habitually late or absent
+        people.push(Person {
+            device_user_id,
+            department,
+            late_rate: 0.02 + chance(seed, user, 0, 4).powi(3) * 0.3,
+            absence_rate: 0.01 + chance(seed, user, 0, 5).powi(3) * 0.08,
+            overtime_rate: chance(seed, user, 0, 6) * 0.25,
+        });
+    }
+    tx.commit().map_err(|e| format!("Failed to commit sample users: {}", e))?;
+    shifts::upsert(conn, &assignments, "manual")?;
+
+    let punches = punches(&people, start, end, seed);
+    let rows: Vec<DevicePunch> = punches
+        .iter()
+        .map(|(device_user_id, timestamp, punch_type)| DevicePunch {
+            device_user_id,
+            timestamp,
+            verify_type: 1,
+            punch_type: *punch_type,
+        })
+        .collect();
+    let inserted = logs::insert_from_device(conn, &device_id, &rows)?;
+
+    let start_date = start.format("%Y-%m-%d").to_string();
+    let end_date = end.format("%Y-%m-%d").to_string();
+    let source = ChangeSource {
+        reason: "sample_data",
+        actor: None,
+    };
+    let computed = engine::recompute(conn, &start_date, &end_date, &source)?;
+
+    Ok(SampleDataResult {
+        device_id,
+        departments: DEPARTMENTS.len(),
+        users: people.len(),
+        punches: inserted,
+        start_date,
+        end_date,
+        days_computed: computed.days_processed,
+    })
+}
+
+/// (device user ID, timestamp, punch type) for everyone from `start` to `end`
+fn punches(people: &[Person], start: NaiveDate, end: NaiveDate, seed: u32) -> Vec<(String, String, u8)> {
+    let mut punches = Vec::new();
+    let mut date = start;
+    while date <= end {
+        let day = (date - start).num_days() + 1;
+        for (index, person) in people.iter().enumerate() {
+            let user = index as u32 + 1;
+            let department = person.department;
+            if !department.days.contains(&date.weekday().number_from_monday())
+                || chance(seed, user, day, 10) < person.absence_rate
+            {
+                continue;
+            }
+            let shift_start = NaiveTime::parse_from_str(department.start, "%H:%M").unwrap_or_default();
+            let shift_end = NaiveTime::parse_from_str(department.end, "%H:%M").unwrap_or(shift_start);
+
+            let arrival = if chance(seed, user, day, 11) < person.late_rate {
+                between(seed, user, day, 12, 3 * 60, 50 * 60)
+            } else {
+                between(seed, user, day, 12, -25 * 60, 60)
+            };
+            let departure = if chance(seed, user, day, 13) < person.overtime_rate {
+                between(seed, user, day, 14, 30 * 60, 180 * 60)
+            } else {
+                between(seed, user, day, 14, -10 * 60, 15 * 60)
+            };
+            let arrival = date.and_time(shift_start) + Duration::seconds(arrival);
+            let departure = date.and_time(shift_end) + Duration::seconds(departure);
+
+            punches.push((person.device_user_id.clone(), arrival.format(TIMESTAMP_FORMAT).to_string(), 0));
+            // The odd forgotten check-out leaves an incomplete day
+            if chance(seed, user, day, 15) >= 0.015 {
+                punches.push((person.device_user_id.clone(), departure.format(TIMESTAMP_FORMAT).to_string(), 1));
+            }
+        }
+        date += Duration::days(1);
+    }
+    punches
+}
@@ -0,0 +1,12 @@
+//! Sample data
+//!
+//! Fills an empty database with made-up departments, shifts, users and
+//! months of punches, with their summaries computed, for trying reports at
+//! scale. It only runs against a database without users, departments or
+//! punches, i.e. a fresh profile, and only when explicitly confirmed.
+//! Punches are recorded under a "Sample data" device so they can be told
+//! apart (and deleted with it).
+
+pub mod commands;
+pub mod generate;
+pub mod types;
@@ -0,0 +1,18 @@
+//! Sample data types for Tauri command serialization
+
+use serde::{Deserialize, Serialize};
+
+/// What generate_sample_data created
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SampleDataResult {
+    pub device_id: String,
+    pub departments: usize,
+    pub users: usize,
+    pub punches: usize,
+    /// First and last day with punches
+    pub start_date: String,
+    pub end_date: String,
+    /// Days whose summaries were computed
+    pub days_computed: u32,
+}
@@ -0,0 +1,97 @@
+//! Tauri command handlers for secret storage.
+
+use super::keychain;
+use super::types::*;
+use crate::db;
+
+/// Run a keychain operation off the async runtime
+async fn blocking<T: Send + 'static>(f: impl FnOnce() -> Result<T, String> + Send + 'static) -> Result<T, String> {
+    tauri::async_runtime::spawn_blocking(f)
+        .await
+        .map_err(|e| format!("Keychain task failed: {}", e))?
+}
+
+/// Store a device's comm key in the keychain. Returns the value to save in
+/// the devices table: the keychain reference, or the key itself when no
+/// keychain is available.
+#[tauri::command]
+pub async fn store_device_comm_key(device_id: String, comm_key: String) -> Result<StoredSecret, String> {
+    blocking(move || {
+        let account = keychain::device_account(&device_id);
+        if comm_key.is_empty() {
+            keychain::delete(&account)?;
+            return Ok(StoredSecret {
+                value: String::new(),
+                in_keychain: false,
+            });
+        }
+        if comm_key == keychain::KEYCHAIN_REF {
+            return Ok(StoredSecret {
+                value: comm_key,
+                in_keychain: true,
+            });
+        }
+        match keychain::set(&account, &comm_key) {
+            Ok(()) => Ok(StoredSecret {
+                value: keychain::KEYCHAIN_REF.to_string(),
+                in_keychain: true,
+            }),
+            Err(e) => {
+                log::warn!("[secrets] Keeping comm key for device {} in the database: {}", device_id, e);
+                Ok(StoredSecret {
+                    value: comm_key,
+                    in_keychain: false,
+                })
+            }
+        }
+    })
+    .await
+}
+
+/// Remove a device's comm key from the keychain (e.g. when deleting the device)
+#[tauri::command]
+pub async fn delete_device_comm_key(device_id: String) -> Result<(), String> {
+    blocking(move || keychain::delete(&keychain::device_account(&device_id))).await
+}
+
+/// Store an API credential (token, password) in the keychain
+#[tauri::command]
+pub async fn set_credential(name: String, value: String) -> Result<(), String> {
+    blocking(move || keychain::set(&keychain::credential_account(&name), &value)).await
+}
+
+/// Remove an API credential from the keychain
+#[tauri::command]
+pub async fn delete_credential(name: String) -> Result<(), String> {
+    blocking(move || keychain::delete(&keychain::credential_account(&name))).await
+}
+
+/// Whether an API credential is stored. Values are never returned to the frontend.
+#[tauri::command]
+pub async fn has_credential(name: String) -> Result<bool, String> {
+    blocking(move || Ok(keychain::get(&keychain::credential_account(&name))?.is_some())).await
+}
+
+/// Move plaintext comm keys from the devices table into the keychain
+#[tauri::command]
+pub async fn migrate_secrets_to_keychain(app: tauri::AppHandle) -> Result<SecretMigrationResult, String> {
+    let conn = db::open(&app)?;
+    blocking(move || keychain::migrate_plaintext(&conn)).await
+}
+
+/// Move plaintext comm keys left by older versions into the keychain at startup
+pub async fn migrate_on_startup(app: tauri::AppHandle) {
+    // No database yet on first launch
+    let Ok(conn) = db::open(&app) else {
+        return;
+    };
+    match blocking(move || keychain::migrate_plaintext(&conn)).await {
+        Ok(result) if result.migrated > 0 || result.failed > 0 => log::info!(
+            "[secrets] Moved {} comm keys to the keychain ({} failed)",
+            result.migrated,
+            result.failed
+        ),
+        Ok(_) => {}
+        Err(e) => log::warn!("[secrets] Comm key migration skipped: {}", e),
+    }
+}
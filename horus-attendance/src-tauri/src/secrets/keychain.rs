@@ -0,0 +1,129 @@
+//! OS keychain access
+//!
+//! Calls can block (Secret Service goes over D-Bus, macOS may prompt), so
+//! callers in async commands run them on a blocking thread.
+
+use keyring::{Entry, Error};
+use rusqlite::{params, Connection};
+
+use super::types::SecretMigrationResult;
+
+/// Keychain service name all entries are stored under
+const SERVICE: &str = "horus-attendance";
+
+/// Stored in `devices.comm_key` when the key is in the keychain
+pub const KEYCHAIN_REF: &str = "keychain:";
+
+/// Keychain account for a device's comm key
+pub fn device_account(device_id: &str) -> String {
+    format!("device:{}:comm_key", device_id)
+}
+
+/// Keychain account for a named API credential
+pub fn credential_account(name: &str) -> String {
+    format!("credential:{}", name)
+}
+
+fn entry(account: &str) -> Result<Entry, String> {
+    Entry::new(SERVICE, account).map_err(|e| format!("Failed to open keychain entry: {}", e))
+}
+
+/// Store a secret, replacing any previous value
+pub fn set(account: &str, secret: &str) -> Result<(), String> {
+    entry(account)?
+        .set_password(secret)
+        .map_err(|e| format!("Failed to write to keychain: {}", e))
+}
+
+/// Read a secret, None when there is no entry
+pub fn get(account: &str) -> Result<Option<String>, String> {
+    match entry(account)?.get_password() {
+        Ok(secret) => Ok(Some(secret)),
+        Err(Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!("Failed to read from keychain: {}", e)),
+    }
+}
+
+/// Remove a secret; missing entries are not an error
+pub fn delete(account: &str) -> Result<(), String> {
+    match entry(account)?.delete_credential() {
+        Ok(()) | Err(Error::NoEntry) => Ok(()),
+        Err(e) => Err(format!("Failed to delete from keychain: {}", e)),
+    }
+}
+
+/// Whether a keychain can be used on this machine (e.g. no Secret Service
+/// daemon on a bare Linux desktop)
+pub fn available() -> bool {
+    match entry("availability-probe").and_then(|e| match e.get_password() {
+        Ok(_) | Err(Error::NoEntry) => Ok(()),
+        Err(e) => Err(e.to_string()),
+    }) {
+        Ok(()) => true,
+        Err(e) => {
+            log::warn!("[secrets] Keychain unavailable: {}", e);
+            false
+        }
+    }
+}
+
+/// Resolve a stored comm key: plaintext is returned as is, a keychain
+/// reference is looked up under the device's account
+pub fn resolve_comm_key(device_id: Option<&str>, stored: &str) -> Result<String, String> {
+    if stored != KEYCHAIN_REF {
+        return Ok(stored.to_string());
+    }
+    let device_id = device_id.ok_or("Comm key is in the keychain but no device ID was given")?;
+    get(&device_account(device_id))?
+        .ok_or_else(|| "Comm key for this device is missing from the system keychain; re-enter it in device settings".to_string())
+}
+
+/// Move every plaintext comm key from the devices table into the keychain,
+/// leaving KEYCHAIN_REF in its place
+pub fn migrate_plaintext(conn: &Connection) -> Result<SecretMigrationResult, String> {
+    let devices: Vec<(String, String)> = {
+        let mut stmt = conn
+            .prepare("SELECT id, comm_key FROM devices WHERE comm_key IS NOT NULL AND comm_key NOT IN ('', ?1)")
+            .map_err(|e| format!("Failed to query devices: {}", e))?;
+        let rows = stmt
+            .query_map([KEYCHAIN_REF], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| format!("Failed to query devices: {}", e))?;
+        rows.collect::<Result<_, _>>()
+            .map_err(|e| format!("Failed to read devices: {}", e))?
+    };
+    let mut result = SecretMigrationResult::default();
+    if devices.is_empty() {
+        result.keychain_available = true;
+        return Ok(result);
+    }
+    if !available() {
+        return Ok(result);
+    }
+    result.keychain_available = true;
+
+    for (id, comm_key) in devices {
+        let account = device_account(&id);
+        // Read back before clearing the column so a silently failing
+        // keychain can never lose the key
+        let stored = set(&account, &comm_key).and_then(|_| get(&account));
+        match stored {
+            Ok(Some(value)) if value == comm_key => {
+                conn.execute(
+                    "UPDATE devices SET comm_key = ?1 WHERE id = ?2 AND comm_key = ?3",
+                    params![KEYCHAIN_REF, id, comm_key],
+                )
+                .map_err(|e| format!("Failed to update device: {}", e))?;
+                result.migrated += 1;
+            }
+            Ok(_) => {
+                log::warn!("[secrets] Keychain did not return the stored comm key for device {}", id);
+                result.failed += 1;
+            }
+            Err(e) => {
+                log::warn!("[secrets] Failed to move comm key for device {}: {}", id, e);
+                result.failed += 1;
+            }
+        }
+    }
+    Ok(result)
+}
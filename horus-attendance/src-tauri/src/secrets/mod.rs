@@ -0,0 +1,13 @@
+//! Secret storage
+//!
+//! Device comm keys and API credentials live in the OS keychain (macOS
+//! Keychain, Windows Credential Manager, Secret Service on Linux) instead of
+//! the database, so they don't end up in backups, dumps or transfer bundles.
+//! The `devices.comm_key` column holds [`keychain::KEYCHAIN_REF`] for keys
+//! moved to the keychain. Where no keychain is available the key stays in
+//! the column, and exports redact it.
+
+pub mod commands;
+pub mod keychain;
+pub mod redact;
+pub mod types;
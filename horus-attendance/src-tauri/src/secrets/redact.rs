@@ -0,0 +1,35 @@
+//! Redaction of secrets in exported data
+
+use rusqlite::Connection;
+
+use super::keychain::KEYCHAIN_REF;
+
+/// (table, column) pairs that may hold a plaintext secret
+pub const SECRET_COLUMNS: &[(&str, &str)] = &[("devices", "comm_key")];
+
+/// Whether a column may hold a secret
+pub fn is_secret(table: &str, column: &str) -> bool {
+    SECRET_COLUMNS.iter().any(|(t, c)| *t == table && *c == column)
+}
+
+/// Whether a stored value must be blanked: anything but an empty value or a
+/// keychain reference
+pub fn needs_redaction(value: &str) -> bool {
+    !value.is_empty() && value != KEYCHAIN_REF
+}
+
+/// Blank plaintext secrets in a copy of the database (never the live one)
+pub fn redact_database(conn: &Connection) -> Result<u32, String> {
+    let mut redacted = 0;
+    for (table, column) in SECRET_COLUMNS {
+        let sql = format!(
+            "UPDATE {table} SET {column} = '' WHERE {column} IS NOT NULL AND {column} NOT IN ('', ?1)",
+            table = table,
+            column = column
+        );
+        redacted += conn
+            .execute(&sql, [KEYCHAIN_REF])
+            .map_err(|e| format!("Failed to redact {}.{}: {}", table, column, e))? as u32;
+    }
+    Ok(redacted)
+}
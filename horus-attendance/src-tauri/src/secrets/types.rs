@@ -0,0 +1,22 @@
+//! Secret storage data types for Tauri command serialization
+
+use serde::{Deserialize, Serialize};
+
+/// Where a saved comm key ended up
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoredSecret {
+    /// Value to write to the database column
+    pub value: String,
+    /// false when no keychain is available and the key stays in the database
+    pub in_keychain: bool,
+}
+
+/// Result of moving plaintext comm keys into the keychain
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SecretMigrationResult {
+    pub keychain_available: bool,
+    pub migrated: u32,
+    pub failed: u32,
+}
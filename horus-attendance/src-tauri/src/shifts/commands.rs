@@ -0,0 +1,98 @@
+//! Tauri command handlers for user shifts.
+
+use chrono::{Duration, Local};
+
+use super::detect;
+use super::store;
+use super::types::*;
+use crate::db;
+use crate::summary::engine;
+use crate::summary::rules::ends_after_start;
+
+/// Weeks of punches analysed when none are given
+const DEFAULT_WEEKS: u32 = 4;
+const MAX_WEEKS: u32 = 26;
+
+/// Days a user needs in the window before a shift is proposed
+const DEFAULT_MIN_DAYS: u32 = 5;
+
+pub(crate) fn validate_time(time: &str) -> Result<(), String> {
+    chrono::NaiveTime::parse_from_str(time, "%H:%M")
+        .map(|_| ())
+        .map_err(|_| format!("Invalid time (expected HH:MM): {}", time))
+}
+
+fn validate_assignment(conn: &rusqlite::Connection, shift: &ShiftAssignment) -> Result<(), String> {
+    validate_time(&shift.start_time)?;
+    validate_time(&shift.end_time)?;
+    if !ends_after_start(&shift.start_time, &shift.end_time, &engine::load_rules(conn)?) {
+        return Err(format!(
+            "Shift for {} must end after it starts ({} - {})",
+            shift.user_id, shift.start_time, shift.end_time
+        ));
+    }
+    if db::users::get(conn, &shift.user_id)?.is_none() {
+        return Err(format!("User not found: {}", shift.user_id));
+    }
+    Ok(())
+}
+
+/// Infer each active user's usual start and end time from the last `weeks`
+/// weeks of punches
+#[tauri::command]
+pub async fn detect_shifts(
+    app: tauri::AppHandle,
+    weeks: Option<u32>,
+    min_days: Option<u32>,
+) -> Result<Vec<ShiftProposal>, String> {
+    let weeks = weeks.unwrap_or(DEFAULT_WEEKS);
+    if weeks == 0 || weeks > MAX_WEEKS {
+        return Err(format!("Weeks must be between 1 and {}", MAX_WEEKS));
+    }
+    let end = Local::now().date_naive();
+    let start = end - Duration::weeks(weeks as i64);
+    let (start, end) = (start.format("%Y-%m-%d").to_string(), end.format("%Y-%m-%d").to_string());
+    log::info!("[shifts::cmd] detect_shifts {} to {}", start, end);
+
+    let conn = db::open(&app)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        detect::detect(&conn, &start, &end, min_days.unwrap_or(DEFAULT_MIN_DAYS))
+    })
+    .await
+    .map_err(|e| format!("Shift detection task failed: {}", e))?
+}
+
+/// Every assigned shift
+#[tauri::command]
+pub async fn list_user_shifts(app: tauri::AppHandle) -> Result<Vec<UserShift>, String> {
+    store::list(&*db::open(&app)?)
+}
+
+/// Assign accepted proposals. Nothing is saved if any of them is invalid.
+/// Summaries pick the new times up when they are next recomputed.
+#[tauri::command]
+pub async fn accept_shift_proposals(app: tauri::AppHandle, shifts: Vec<ShiftAssignment>) -> Result<u32, String> {
+    let mut conn = db::open(&app)?;
+    for shift in &shifts {
+        validate_assignment(&conn, shift)?;
+    }
+    store::upsert(&mut conn, &shifts, "detected")?;
+    log::info!("[shifts::cmd] Assigned {} detected shifts", shifts.len());
+    Ok(shifts.len() as u32)
+}
+
+/// Assign shifts entered by hand
+#[tauri::command]
+pub async fn save_user_shifts(app: tauri::AppHandle, shifts: Vec<ShiftAssignment>) -> Result<(), String> {
+    let mut conn = db::open(&app)?;
+    for shift in &shifts {
+        validate_assignment(&conn, shift)?;
+    }
+    store::upsert(&mut conn, &shifts, "manual")
+}
+
+/// Return a user to the global work hours
+#[tauri::command]
+pub async fn delete_user_shift(app: tauri::AppHandle, user_id: String) -> Result<bool, String> {
+    store::delete(&*db::open(&app)?, &user_id)
+}
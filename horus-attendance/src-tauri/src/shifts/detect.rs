@@ -0,0 +1,120 @@
+//! Inferring shifts from past punches
+//!
+//! For each user, the first and last punch of every day in the window give a
+//! start and end time. The proposal is the median of each, snapped to the
+//! quarter hour, so the odd late night or early finish doesn't move it.
+//! Days are working days, so shifts crossing midnight are only seen whole
+//! when the day cutoff hour falls between their end and the next start.
+
+use std::collections::{BTreeMap, HashMap};
+
+use rusqlite::Connection;
+
+use super::store;
+use super::types::ShiftProposal;
+use crate::db::{logs, users};
+use crate::summary::engine::{self, UserMatcher};
+use crate::summary::rules::{day_minutes, extract_time, working_date};
+
+/// Proposed times are rounded to this many minutes
+const SNAP_MINUTES: i64 = 15;
+
+/// A day counts towards consistency when it starts this close to the proposal
+const TOLERANCE_MINUTES: i64 = 30;
+
+/// First and last punches closer than this are treated as a single punch
+const MIN_SHIFT_MINUTES: i64 = 60;
+
+fn median(values: &mut [i64]) -> i64 {
+    values.sort_unstable();
+    values[values.len() / 2]
+}
+
+fn snap(minutes: i64) -> i64 {
+    ((minutes + SNAP_MINUTES / 2) / SNAP_MINUTES) * SNAP_MINUTES
+}
+
+/// Clock time of minutes since the midnight a working day started after.
+/// Calendar days end at 23:59 rather than wrapping to 00:00.
+fn format_minutes(minutes: i64, cutoff_hour: u32) -> String {
+    let minutes = if cutoff_hour == 0 { minutes.min(24 * 60 - 1) } else { minutes % (24 * 60) };
+    format!("{:02}:{:02}", minutes / 60, minutes % 60)
+}
+
+/// Median absolute distance of `values` from `center`
+fn spread(values: &[i64], center: i64) -> i64 {
+    let mut distances: Vec<i64> = values.iter().map(|v| (v - center).abs()).collect();
+    median(&mut distances)
+}
+
+/// Proposals for active users with at least `min_days` usable days between
+/// `start_date` and `end_date`, least consistent first so doubtful ones are
+/// reviewed before bulk acceptance
+pub fn detect(conn: &Connection, start_date: &str, end_date: &str, min_days: u32) -> Result<Vec<ShiftProposal>, String> {
+    let all_users = users::list(conn)?;
+    let names: HashMap<String, String> = all_users
+        .iter()
+        .filter(|u| u.is_active())
+        .map(|u| (u.id.clone(), u.display_name.clone()))
+        .collect();
+    let matcher = UserMatcher::new(all_users).with_links(users::device_links(conn)?);
+    let mut current: HashMap<String, _> = store::list(conn)?
+        .into_iter()
+        .map(|s| (s.user_id.clone(), s))
+        .collect();
+
+    let rules = engine::load_rules(conn)?;
+    let cutoff_hour = rules.day_cutoff_hour;
+
+    // user -> working date -> (first, last) minutes since the day's midnight
+    let mut days: BTreeMap<String, BTreeMap<String, (i64, i64)>> = BTreeMap::new();
+    for punch in logs::between(conn, start_date, end_date, cutoff_hour)? {
+        let Some(user_id) = matcher.resolve(&punch.device_user_id) else {
+            continue;
+        };
+        if !names.contains_key(user_id) {
+            continue;
+        }
+        let minutes = day_minutes(&extract_time(&punch.timestamp), &rules);
+        days.entry(user_id.to_string())
+            .or_default()
+            .entry(working_date(&punch.timestamp, cutoff_hour))
+            .and_modify(|(first, last)| {
+                *first = (*first).min(minutes);
+                *last = (*last).max(minutes);
+            })
+            .or_insert((minutes, minutes));
+    }
+
+    let mut proposals = Vec::new();
+    for (user_id, by_date) in days {
+        let (mut starts, mut ends): (Vec<i64>, Vec<i64>) = by_date
+            .values()
+            .filter(|(first, last)| last - first >= MIN_SHIFT_MINUTES)
+            .copied()
+            .unzip();
+        if (starts.len() as u32) < min_days.max(1) {
+            continue;
+        }
+        let start = snap(median(&mut starts));
+        let end = snap(median(&mut ends));
+        let consistent = starts.iter().filter(|s| (*s - start).abs() <= TOLERANCE_MINUTES).count();
+        proposals.push(ShiftProposal {
+            display_name: names.get(&user_id).cloned().unwrap_or_default(),
+            start_time: format_minutes(start, cutoff_hour),
+            end_time: format_minutes(end, cutoff_hour),
+            days_observed: starts.len() as u32,
+            start_spread_minutes: spread(&starts, start),
+            end_spread_minutes: spread(&ends, end),
+            consistency: consistent as f64 / starts.len() as f64,
+            current: current.remove(&user_id),
+            user_id,
+        });
+    }
+    proposals.sort_by(|a, b| {
+        a.consistency
+            .total_cmp(&b.consistency)
+            .then_with(|| a.display_name.cmp(&b.display_name))
+    });
+    Ok(proposals)
+}
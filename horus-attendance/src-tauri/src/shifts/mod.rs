@@ -0,0 +1,11 @@
+//! Per-user shifts
+//!
+//! Sites without formal shift data get every user judged against the global
+//! work start/end times. A user's row in `user_shifts` replaces those times
+//! for that user. Shifts can be inferred from past punches ([`detect`]) and
+//! accepted in bulk.
+
+pub mod commands;
+pub mod detect;
+pub mod store;
+pub mod types;
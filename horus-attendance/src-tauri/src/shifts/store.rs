@@ -0,0 +1,56 @@
+//! Storage for user shifts
+
+use rusqlite::{params, Connection};
+
+use super::types::{ShiftAssignment, UserShift};
+
+/// Every assigned shift
+pub fn list(conn: &Connection) -> Result<Vec<UserShift>, String> {
+    let mut stmt = conn
+        .prepare("SELECT user_id, start_time, end_time, source, updated_at FROM user_shifts ORDER BY user_id")
+        .map_err(|e| format!("Failed to query shifts: {}", e))?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(UserShift {
+                user_id: row.get(0)?,
+                start_time: row.get(1)?,
+                end_time: row.get(2)?,
+                source: row.get(3)?,
+                updated_at: row.get(4)?,
+            })
+        })
+        .map_err(|e| format!("Failed to query shifts: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read shifts: {}", e))
+}
+
+/// Assign `shifts` in one transaction, replacing existing ones
+pub fn upsert(conn: &mut Connection, shifts: &[ShiftAssignment], source: &str) -> Result<(), String> {
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    {
+        let mut stmt = tx
+            .prepare(
+                "INSERT INTO user_shifts (user_id, start_time, end_time, source)
+                 VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT(user_id) DO UPDATE SET
+                    start_time = excluded.start_time,
+                    end_time = excluded.end_time,
+                    source = excluded.source,
+                    updated_at = datetime('now')",
+            )
+            .map_err(|e| format!("Failed to prepare shift upsert: {}", e))?;
+        for shift in shifts {
+            stmt.execute(params![shift.user_id, shift.start_time, shift.end_time, source])
+                .map_err(|e| format!("Failed to save shift for {}: {}", shift.user_id, e))?;
+        }
+    }
+    tx.commit().map_err(|e| format!("Failed to commit shifts: {}", e))
+}
+
+pub fn delete(conn: &Connection, user_id: &str) -> Result<bool, String> {
+    conn.execute("DELETE FROM user_shifts WHERE user_id = ?1", [user_id])
+        .map(|n| n > 0)
+        .map_err(|e| format!("Failed to delete shift: {}", e))
+}
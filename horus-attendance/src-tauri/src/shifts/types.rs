@@ -0,0 +1,46 @@
+//! Shift data types for Tauri command serialization
+
+use serde::{Deserialize, Serialize};
+
+/// A user's assigned shift (one user_shifts row)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserShift {
+    pub user_id: String,
+    /// HH:MM
+    pub start_time: String,
+    /// HH:MM
+    pub end_time: String,
+    /// "manual" or "detected"
+    pub source: String,
+    pub updated_at: String,
+}
+
+/// A shift to assign
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShiftAssignment {
+    pub user_id: String,
+    pub start_time: String,
+    pub end_time: String,
+}
+
+/// A shift inferred from a user's punches
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShiftProposal {
+    pub user_id: String,
+    pub display_name: String,
+    pub start_time: String,
+    pub end_time: String,
+    /// Days with both a first and a last punch
+    pub days_observed: u32,
+    /// Median distance of daily first/last punches from the proposed times
+    pub start_spread_minutes: i64,
+    pub end_spread_minutes: i64,
+    /// Share of days (0..1) that started within tolerance of the proposed start
+    pub consistency: f64,
+    /// The shift currently assigned, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current: Option<UserShift>,
+}
@@ -0,0 +1,99 @@
+//! Holding the exit back until running work has finished
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use tauri::{Emitter, Manager};
+
+use super::types::ShutdownReport;
+use crate::activity::registry::{self, ActivityRegistry};
+use crate::api::server::ApiServer;
+use crate::db::pool::Pool;
+use crate::jobs::registry::JobRegistry;
+use crate::zkteco::client;
+
+/// How long running work gets to finish
+const ACTIVITY_TIMEOUT: Duration = Duration::from_secs(20);
+/// How long open device sessions get to disconnect after that
+const SESSION_TIMEOUT: Duration = Duration::from_secs(10);
+const SESSION_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Progress of the shutdown, held as Tauri managed state
+#[derive(Default)]
+pub struct ShutdownState {
+    started: AtomicBool,
+    drained: AtomicBool,
+}
+
+/// Handle `RunEvent::ExitRequested`: the first request is held back and the
+/// app exits with `code` once [`drain`] is done; the exit that triggers
+/// goes through.
+pub fn on_exit_requested(app: &tauri::AppHandle, api: &tauri::ExitRequestApi, code: Option<i32>) {
+    let state = app.state::<ShutdownState>();
+    if state.drained.load(Ordering::SeqCst) {
+        return;
+    }
+    api.prevent_exit();
+    if state.started.swap(true, Ordering::SeqCst) {
+        // Already draining; asking again doesn't skip the wait
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let report = drain(&app).await;
+        if report.clean {
+            log::info!("[shutdown] Clean shutdown");
+        } else {
+            log::warn!("[shutdown] Exiting with work unfinished: {}", report.problems.join("; "));
+        }
+        app.state::<ShutdownState>().drained.store(true, Ordering::SeqCst);
+        app.exit(code.unwrap_or(0));
+    });
+}
+
+/// Stop taking new work and wait (bounded) for running work to finish
+pub async fn drain(app: &tauri::AppHandle) -> ShutdownReport {
+    let mut report = ShutdownReport {
+        running: app
+            .state::<ActivityRegistry>()
+            .status()
+            .map(|status| status.active.into_iter().map(|a| a.label).collect())
+            .unwrap_or_default(),
+        open_device_sessions: client::open_sessions(),
+        ..Default::default()
+    };
+    log::info!(
+        "[shutdown] Shutting down: {} running task(s), {} device session(s)",
+        report.running.len(),
+        report.open_device_sessions
+    );
+    if let Err(e) = app.emit("app-shutting-down", &report) {
+        log::warn!("[shutdown] Failed to emit app-shutting-down: {}", e);
+    }
+
+    // No new requests or scheduled runs
+    if let Err(e) = app.state::<ApiServer>().stop() {
+        report.problems.push(format!("API server: {}", e));
+    }
+    app.state::<JobRegistry>().close();
+
+    // Turn new work away, wait for running work, checkpoint the WAL. The
+    // pause is held until the process exits.
+    match registry::quiesce_within(app, "Shutdown", ACTIVITY_TIMEOUT).await {
+        Ok(guard) => std::mem::forget(guard),
+        Err(e) => report.problems.push(e),
+    }
+
+    let deadline = Instant::now() + SESSION_TIMEOUT;
+    while client::open_sessions() > 0 && Instant::now() < deadline {
+        tokio::time::sleep(SESSION_POLL_INTERVAL).await;
+    }
+    let open = client::open_sessions();
+    if open > 0 {
+        report.problems.push(format!("{} device session(s) still open", open));
+    }
+
+    app.state::<Pool>().clear();
+    report.clean = report.problems.is_empty();
+    report
+}
@@ -0,0 +1,12 @@
+//! Graceful shutdown
+//!
+//! Quitting mid-sync used to drop a batch halfway. When the app is asked to
+//! exit, the exit is held back while the coordinator stops the API server,
+//! closes the jobs registry, pauses the activity registry (turning new work
+//! away) and waits a bounded time for running work and open device sessions
+//! to finish. The WAL is then checkpointed and pooled connections closed
+//! before the app exits for real. Work that overruns the bound is logged and
+//! abandoned; SQLite rolls back its open transaction.
+
+pub mod coordinator;
+pub mod types;
@@ -0,0 +1,16 @@
+//! Types for graceful shutdown
+
+use serde::{Deserialize, Serialize};
+
+/// What shutdown waited for, emitted as `app-shutting-down` and logged
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShutdownReport {
+    /// Running work (syncs, recalculations, API requests) at the start
+    pub running: Vec<String>,
+    pub open_device_sessions: usize,
+    /// Everything finished within the bounds
+    pub clean: bool,
+    /// What was still running when the bounds ran out
+    pub problems: Vec<String>,
+}
@@ -0,0 +1,71 @@
+//! Tauri command handlers for report signing.
+
+use super::keys;
+use super::store::{self, SETTINGS_KEY};
+use super::types::*;
+use crate::db;
+use crate::path_policy;
+
+const DEFAULT_LIST_LIMIT: u32 = 200;
+
+#[tauri::command]
+pub async fn get_signing_settings(app: tauri::AppHandle) -> Result<SigningSettings, String> {
+    store::load_settings(&*db::open(&app)?)
+}
+
+/// Turn signing of exports on or off. Turning it on creates the signing key
+/// if there is none yet, so a missing keychain is reported here rather than
+/// by the next export.
+#[tauri::command]
+pub async fn save_signing_settings(app: tauri::AppHandle, settings: SigningSettings) -> Result<(), String> {
+    if settings.enabled {
+        tauri::async_runtime::spawn_blocking(keys::signing_key)
+            .await
+            .map_err(|e| format!("Keychain task failed: {}", e))??;
+    }
+    let json = serde_json::to_string(&settings).map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    log::info!("[signing::cmd] Report signing {}", if settings.enabled { "on" } else { "off" });
+    db::set_setting(&*db::open(&app)?, SETTINGS_KEY, &json)
+}
+
+/// The public key signatures can be checked with outside the app
+#[tauri::command]
+pub async fn get_signing_public_key() -> Result<SigningPublicKey, String> {
+    tauri::async_runtime::spawn_blocking(keys::public_key)
+        .await
+        .map_err(|e| format!("Keychain task failed: {}", e))?
+}
+
+/// Sign a file the frontend generated (e.g. a PDF report), writing `.sig`
+/// next to it. `report` names what it is, e.g. "attendance_report".
+#[tauri::command]
+pub async fn sign_report_file(app: tauri::AppHandle, path: String, report: String) -> Result<ReportSignature, String> {
+    let path = path_policy::resolve_source(&app, &path)?;
+    let conn = db::open(&app)?;
+    tauri::async_runtime::spawn_blocking(move || store::sign(&conn, &path, report.trim()))
+        .await
+        .map_err(|e| format!("Signing task failed: {}", e))?
+}
+
+/// Check whether a report file is unchanged since this app signed it
+#[tauri::command]
+pub async fn verify_report_file(
+    app: tauri::AppHandle,
+    path: String,
+    signature_path: Option<String>,
+) -> Result<SignatureVerification, String> {
+    let path = path_policy::resolve_source(&app, &path)?;
+    let signature_path = signature_path
+        .map(|p| path_policy::resolve_source(&app, &p))
+        .transpose()?;
+    let conn = db::open(&app)?;
+    tauri::async_runtime::spawn_blocking(move || store::verify(&conn, &path, signature_path.as_deref()))
+        .await
+        .map_err(|e| format!("Verification task failed: {}", e))?
+}
+
+/// Reports signed so far, newest first
+#[tauri::command]
+pub async fn list_report_signatures(app: tauri::AppHandle, limit: Option<u32>) -> Result<Vec<ReportSignature>, String> {
+    store::list(&*db::open(&app)?, limit.unwrap_or(DEFAULT_LIST_LIMIT))
+}
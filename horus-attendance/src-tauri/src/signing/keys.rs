@@ -0,0 +1,84 @@
+//! The report signing key
+
+use base64::Engine;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use sha2::{Digest, Sha256};
+
+use super::types::SigningPublicKey;
+use crate::secrets::keychain;
+
+/// Keychain credential holding the private key (base64 seed)
+const KEY_CREDENTIAL: &str = "report_signing_key";
+
+pub const FORMAT: &str = "horus-report-signature/1";
+pub const ALGORITHM: &str = "Ed25519";
+
+fn b64() -> base64::engine::GeneralPurpose {
+    base64::engine::general_purpose::STANDARD
+}
+
+/// The text a signature covers: the format, report kind, file name, hex
+/// SHA-256 and signing time, one per line
+pub fn signed_message(report: &str, file_name: &str, sha256: &str, signed_at: &str) -> String {
+    format!("{}\n{}\n{}\n{}\n{}", FORMAT, report, file_name, sha256, signed_at)
+}
+
+/// The signing key, created and stored in the keychain on first use.
+/// Blocks on the keychain.
+pub fn signing_key() -> Result<SigningKey, String> {
+    if let Some(stored) = keychain::get(&keychain::credential_account(KEY_CREDENTIAL))? {
+        let seed: [u8; 32] = b64()
+            .decode(stored.trim())
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or("The stored report signing key is damaged")?;
+        return Ok(SigningKey::from_bytes(&seed));
+    }
+    // Same randomness source as API tokens, hashed into a 32-byte seed
+    let seed: [u8; 32] = Sha256::new()
+        .chain_update(uuid::Uuid::new_v4().as_bytes())
+        .chain_update(uuid::Uuid::new_v4().as_bytes())
+        .chain_update(chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default().to_le_bytes())
+        .finalize()
+        .into();
+    keychain::set(&keychain::credential_account(KEY_CREDENTIAL), &b64().encode(seed))?;
+    log::info!("[signing] Created report signing key");
+    Ok(SigningKey::from_bytes(&seed))
+}
+
+pub fn fingerprint(key: &VerifyingKey) -> String {
+    let digest = Sha256::digest(key.as_bytes());
+    format!("{:x}", digest)[..16].to_string()
+}
+
+pub fn public_key() -> Result<SigningPublicKey, String> {
+    let key = signing_key()?.verifying_key();
+    Ok(SigningPublicKey {
+        algorithm: ALGORITHM.to_string(),
+        public_key: b64().encode(key.as_bytes()),
+        fingerprint: fingerprint(&key),
+    })
+}
+
+/// Sign `message`, returning the base64 signature
+pub fn sign(key: &SigningKey, message: &str) -> String {
+    b64().encode(key.sign(message.as_bytes()).to_bytes())
+}
+
+/// Check a base64 signature of `message` against a base64 public key
+pub fn verify(public_key: &str, message: &str, signature: &str) -> Result<VerifyingKey, String> {
+    let key_bytes: [u8; 32] = b64()
+        .decode(public_key.trim())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or("The public key in the signature file is malformed")?;
+    let key = VerifyingKey::from_bytes(&key_bytes).map_err(|_| "The public key in the signature file is invalid")?;
+    let signature_bytes: [u8; 64] = b64()
+        .decode(signature.trim())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or("The signature is malformed")?;
+    key.verify(message.as_bytes(), &Signature::from_bytes(&signature_bytes))
+        .map_err(|_| "The signature doesn't match the signed details".to_string())?;
+    Ok(key)
+}
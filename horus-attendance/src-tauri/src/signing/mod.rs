@@ -0,0 +1,18 @@
+//! Signed reports
+//!
+//! Exported timesheets end up in disputes, and HR needs to tell whether the
+//! copy in front of them is the one the app produced. When report signing is
+//! on (`reportSigning` setting), every file an export writes is hashed
+//! (SHA-256) and the hash signed with an Ed25519 key kept in the OS keychain.
+//! The signature goes into a `.sig` file next to the report and into the
+//! `report_signatures` table.
+//!
+//! Verifying a file recomputes its hash and checks it against the signature
+//! file and the table; a single changed byte fails both. The public key can
+//! be handed out so others can check signatures without the app: the
+//! signature covers the text described by [`keys::signed_message`].
+
+pub mod commands;
+pub mod keys;
+pub mod store;
+pub mod types;
@@ -0,0 +1,184 @@
+//! Signing report files and checking them
+
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use rusqlite::{params, Connection, OptionalExtension};
+use sha2::{Digest, Sha256};
+
+use super::keys;
+use super::types::*;
+use crate::db;
+use crate::export::types::ExportedFile;
+
+/// Settings key of the signing settings
+pub const SETTINGS_KEY: &str = "reportSigning";
+
+pub fn load_settings(conn: &Connection) -> Result<SigningSettings, String> {
+    Ok(db::get_json_setting(conn, SETTINGS_KEY)?.unwrap_or_default())
+}
+
+/// Hex SHA-256 of a file, read in a stream
+pub fn hash_file(path: &Path) -> Result<String, String> {
+    let mut file = File::open(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Where a report's signature is written: next to it, with `.sig` appended
+pub fn signature_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".sig");
+    PathBuf::from(name)
+}
+
+const SELECT: &str = "SELECT id, report, file_name, path, sha256, signature, key_fingerprint, signed_at
+                      FROM report_signatures";
+
+fn map_row(row: &rusqlite::Row) -> rusqlite::Result<ReportSignature> {
+    Ok(ReportSignature {
+        id: row.get(0)?,
+        report: row.get(1)?,
+        file_name: row.get(2)?,
+        path: row.get(3)?,
+        sha256: row.get(4)?,
+        signature: row.get(5)?,
+        key_fingerprint: row.get(6)?,
+        signed_at: row.get(7)?,
+    })
+}
+
+/// Signed reports, newest first
+pub fn list(conn: &Connection, limit: u32) -> Result<Vec<ReportSignature>, String> {
+    let mut stmt = conn
+        .prepare(&format!("{} ORDER BY id DESC LIMIT ?1", SELECT))
+        .map_err(|e| format!("Failed to query signatures: {}", e))?;
+    let rows = stmt
+        .query_map([limit], map_row)
+        .map_err(|e| format!("Failed to query signatures: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read signatures: {}", e))
+}
+
+/// Sign the file at `path`, writing its `.sig` file and recording it.
+/// Blocks on the keychain.
+pub fn sign(conn: &Connection, path: &Path, report: &str) -> Result<ReportSignature, String> {
+    let key = keys::signing_key()?;
+    let sha256 = hash_file(path)?;
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let signed_at = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+    let signature = keys::sign(&key, &keys::signed_message(report, &file_name, &sha256, &signed_at));
+    let public_key = keys::public_key()?;
+
+    let contents = SignatureFile {
+        format: keys::FORMAT.to_string(),
+        algorithm: keys::ALGORITHM.to_string(),
+        report: report.to_string(),
+        file_name: file_name.clone(),
+        sha256: sha256.clone(),
+        signed_at: signed_at.clone(),
+        public_key: public_key.public_key,
+        signature: signature.clone(),
+    };
+    let json = serde_json::to_string_pretty(&contents).map_err(|e| format!("Failed to serialize signature: {}", e))?;
+    let sig_path = signature_path(path);
+    fs::write(&sig_path, json).map_err(|e| format!("Failed to write {}: {}", sig_path.display(), e))?;
+
+    conn.execute(
+        "INSERT INTO report_signatures (report, file_name, path, sha256, signature, key_fingerprint, signed_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            report,
+            file_name,
+            path.to_string_lossy(),
+            sha256,
+            signature,
+            public_key.fingerprint,
+            signed_at
+        ],
+    )
+    .map_err(|e| format!("Failed to record signature: {}", e))?;
+    let id = conn.last_insert_rowid();
+    log::info!("[signing] Signed {} ({})", file_name, &sha256[..12]);
+    conn.query_row(&format!("{} WHERE id = ?1", SELECT), [id], map_row)
+        .map_err(|e| format!("Failed to read signature: {}", e))
+}
+
+/// Sign the files an export wrote when signing is enabled. Blocks on the
+/// keychain.
+pub fn sign_exported(conn: &Connection, files: &[ExportedFile], report: &str) -> Result<(), String> {
+    if !load_settings(conn)?.enabled {
+        return Ok(());
+    }
+    for file in files {
+        sign(conn, Path::new(&file.path), report)?;
+    }
+    Ok(())
+}
+
+/// Check the file at `path` against its signature file (`.sig` next to it
+/// unless given) and the signing record. Blocks on the keychain.
+pub fn verify(conn: &Connection, path: &Path, signature_file: Option<&Path>) -> Result<SignatureVerification, String> {
+    let sha256 = hash_file(path)?;
+    let sig_path = signature_file.map(Path::to_path_buf).unwrap_or_else(|| signature_path(path));
+    let mut problems = Vec::new();
+
+    let contents: Option<SignatureFile> = match fs::read_to_string(&sig_path) {
+        Ok(text) => match serde_json::from_str(&text) {
+            Ok(contents) => Some(contents),
+            Err(e) => {
+                problems.push(format!("The signature file can't be read: {}", e));
+                None
+            }
+        },
+        Err(_) => {
+            problems.push(format!("No signature file at {}", sig_path.display()));
+            None
+        }
+    };
+
+    let mut hash_matches = false;
+    let mut signature_valid = false;
+    let mut signed_by_this_app = false;
+    if let Some(contents) = &contents {
+        hash_matches = contents.sha256.eq_ignore_ascii_case(&sha256);
+        if !hash_matches {
+            problems.push("The file has changed since it was signed".to_string());
+        }
+        let message = keys::signed_message(&contents.report, &contents.file_name, &contents.sha256, &contents.signed_at);
+        match keys::verify(&contents.public_key, &message, &contents.signature) {
+            Ok(key) => {
+                signature_valid = true;
+                signed_by_this_app = keys::fingerprint(&key) == keys::public_key()?.fingerprint;
+                if !signed_by_this_app {
+                    problems.push("The file was signed with a different key".to_string());
+                }
+            }
+            Err(e) => problems.push(e),
+        }
+    }
+
+    let record = conn
+        .query_row(
+            &format!("{} WHERE sha256 = ?1 ORDER BY id DESC LIMIT 1", SELECT),
+            [&sha256],
+            map_row,
+        )
+        .optional()
+        .map_err(|e| format!("Failed to look up signature: {}", e))?;
+    if record.is_none() {
+        problems.push("This app has no record of signing a file with this content".to_string());
+    }
+
+    Ok(SignatureVerification {
+        valid: hash_matches && signature_valid && signed_by_this_app && record.is_some(),
+        sha256,
+        hash_matches,
+        signature_valid,
+        signed_by_this_app,
+        record,
+        problems,
+    })
+}
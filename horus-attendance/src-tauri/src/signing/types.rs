@@ -0,0 +1,75 @@
+//! Signing data types for Tauri command serialization
+
+use serde::{Deserialize, Serialize};
+
+/// Report signing settings (`reportSigning` setting)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SigningSettings {
+    /// Sign every file written by an export
+    pub enabled: bool,
+}
+
+/// Contents of a `.sig` file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignatureFile {
+    pub format: String,
+    pub algorithm: String,
+    pub report: String,
+    pub file_name: String,
+    /// Hex SHA-256 of the report file
+    pub sha256: String,
+    pub signed_at: String,
+    /// Base64 Ed25519 public key
+    pub public_key: String,
+    /// Base64 Ed25519 signature of the signed message
+    pub signature: String,
+}
+
+/// A signed report (one report_signatures row)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportSignature {
+    pub id: i64,
+    /// What produced the file, e.g. "attendance_sheet"
+    pub report: String,
+    pub file_name: String,
+    pub path: String,
+    pub sha256: String,
+    pub signature: String,
+    pub key_fingerprint: String,
+    pub signed_at: String,
+}
+
+/// The app's signing key, for checking signatures elsewhere
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SigningPublicKey {
+    pub algorithm: String,
+    /// Base64
+    pub public_key: String,
+    /// First 16 hex digits of the key's SHA-256
+    pub fingerprint: String,
+}
+
+/// Result of checking a report file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignatureVerification {
+    /// The file is unchanged, was signed by this app's key and is in the
+    /// signature log
+    pub valid: bool,
+    /// SHA-256 of the file as it is now
+    pub sha256: String,
+    /// The hash in the signature file matches the file
+    pub hash_matches: bool,
+    /// The signature checks out against the public key in the signature file
+    pub signature_valid: bool,
+    /// That key is this app's signing key
+    pub signed_by_this_app: bool,
+    /// The signing as recorded when the file was issued
+    pub record: Option<ReportSignature>,
+    /// Why the file isn't valid
+    pub problems: Vec<String>,
+}
@@ -0,0 +1,99 @@
+//! Alerting rules that fall below their minimum headcount
+
+use chrono::{Local, Utc};
+use rusqlite::Connection;
+use tauri::Emitter;
+
+use super::store;
+use super::types::*;
+use crate::deliveries;
+use crate::deliveries::types::NewDelivery;
+
+/// Payload kind of staffing alerts in the delivery log
+pub const DELIVERY_KIND: &str = "staffing_shortfall";
+
+/// Event name of the in-app notification
+pub const SHORTFALL_EVENT: &str = "staffing-shortfall";
+
+fn webhook_body(status: &StaffingStatus, date: &str) -> serde_json::Value {
+    serde_json::json!({
+        "event": "staffing.shortfall",
+        "ruleId": status.rule_id,
+        "ruleName": status.rule_name,
+        "departmentId": status.department_id,
+        "departmentName": status.department_name,
+        "site": status.site,
+        "minHeadcount": status.min_headcount,
+        "present": status.present,
+        "date": date,
+    })
+}
+
+/// Post one alert to the webhook, logged as a delivery; returns whether it
+/// went through. Blocks on the network.
+fn deliver(conn: &Connection, url: &str, status: &StaffingStatus, body: serde_json::Value) -> Result<bool, String> {
+    let id = deliveries::store::begin(
+        conn,
+        &NewDelivery {
+            channel: deliveries::store::CHANNEL_WEBHOOK,
+            target: url,
+            summary: format!("{}: {} of {} present", status.rule_name, status.present, status.min_headcount),
+            payload: serde_json::json!({ "kind": DELIVERY_KIND, "body": body }),
+        },
+    )?;
+    let sent = deliveries::webhook::post(url, &body);
+    deliveries::store::finish(conn, id, &sent)?;
+    if let Err(e) = &sent {
+        log::warn!("[staffing] Alert for rule {} failed: {}", status.rule_id, e);
+    }
+    Ok(sent.is_ok())
+}
+
+/// Compare every rule against live presence; with `notify`, alert those
+/// that newly fell short. Blocks on the network.
+pub fn run(app: &tauri::AppHandle, conn: &Connection, settings: &StaffingSettings, notify: bool) -> Result<StaffingCheck, String> {
+    let (date, rules) = store::statuses(conn, Local::now().naive_local())?;
+    let now = Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let mut check = StaffingCheck {
+        checked_at: now.clone(),
+        date,
+        rules: Vec::with_capacity(rules.len()),
+        alerted: Vec::new(),
+        failed_deliveries: 0,
+    };
+    for mut status in rules {
+        if !status.short {
+            if status.alerted_at.take().is_some() {
+                store::clear_alert(conn, &status.rule_id)?;
+            }
+        } else if notify && status.alerted_at.is_none() {
+            store::record_alert(conn, &status, &now)?;
+            status.alerted_at = Some(now.clone());
+            log::info!(
+                "[staffing] {} is short: {} of {} present",
+                status.rule_name,
+                status.present,
+                status.min_headcount
+            );
+            if let Err(e) = app.emit(SHORTFALL_EVENT, &status) {
+                log::warn!("[staffing] Failed to emit {}: {}", SHORTFALL_EVENT, e);
+            }
+            let url = settings.webhook_url.trim();
+            if !url.is_empty() {
+                let body = webhook_body(&status, &check.date);
+                check.failed_deliveries += u32::from(!deliver(conn, url, &status, body)?);
+            }
+            check.alerted.push(status.rule_id.clone());
+        }
+        check.rules.push(status);
+    }
+    Ok(check)
+}
+
+/// Send a logged staffing alert again. Blocks on the network.
+pub fn redeliver(channel: &str, target: &str, payload: &serde_json::Value) -> Result<(), String> {
+    match channel {
+        deliveries::store::CHANNEL_WEBHOOK => deliveries::webhook::post(target, &payload["body"]),
+        other => Err(format!("Staffing alerts aren't sent by {}", other)),
+    }
+}
@@ -0,0 +1,111 @@
+//! Tauri command handlers for minimum-staffing alerts.
+
+use chrono::Local;
+
+use super::check;
+use super::store::{self, SETTINGS_KEY};
+use super::types::*;
+use crate::db;
+use crate::deliveries::webhook;
+use crate::jobs;
+
+/// How often the scheduled job compares rules against live presence
+const CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(300);
+
+/// ID of the scheduled check in the jobs registry
+pub const JOB_ID: &str = "minimum_staffing";
+
+#[tauri::command]
+pub async fn get_staffing_settings(app: tauri::AppHandle) -> Result<StaffingSettings, String> {
+    store::load_settings(&*db::open(&app)?)
+}
+
+#[tauri::command]
+pub async fn save_staffing_settings(app: tauri::AppHandle, settings: StaffingSettings) -> Result<(), String> {
+    if !settings.webhook_url.trim().is_empty() {
+        webhook::validate_url(&settings.webhook_url)?;
+    }
+    let json = serde_json::to_string(&settings).map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    log::info!(
+        "[staffing::cmd] Minimum staffing checks {}",
+        if settings.enabled { "on" } else { "off" }
+    );
+    db::set_setting(&*db::open(&app)?, SETTINGS_KEY, &json)
+}
+
+#[tauri::command]
+pub async fn list_staffing_rules(app: tauri::AppHandle) -> Result<Vec<StaffingRule>, String> {
+    store::list(&*db::open(&app)?)
+}
+
+/// Create (empty ID) or update a staffing rule. Leave the department and
+/// site out for a rule counting everyone.
+#[tauri::command]
+pub async fn save_staffing_rule(app: tauri::AppHandle, rule: StaffingRule) -> Result<StaffingRule, String> {
+    let saved = store::save(&*db::open(&app)?, rule)?;
+    log::info!(
+        "[staffing::cmd] Saved staffing rule {} (at least {} present)",
+        saved.name,
+        saved.min_headcount
+    );
+    Ok(saved)
+}
+
+#[tauri::command]
+pub async fn delete_staffing_rule(app: tauri::AppHandle, id: String) -> Result<bool, String> {
+    store::delete(&*db::open(&app)?, &id)
+}
+
+/// Everyone on the premises now, by name
+#[tauri::command]
+pub async fn get_live_presence(app: tauri::AppHandle) -> Result<Vec<PresentUser>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let (_, present) = store::presence(&*db::open(&app)?, Local::now().naive_local())?;
+        Ok(present)
+    })
+    .await
+    .map_err(|e| format!("Presence task failed: {}", e))?
+}
+
+/// Every rule against live presence. With `notify`, rules that newly fell
+/// short are alerted as the background check would.
+#[tauri::command]
+pub async fn check_staffing(app: tauri::AppHandle, notify: Option<bool>) -> Result<StaffingCheck, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let conn = db::open(&app)?;
+        let settings = store::load_settings(&conn)?;
+        check::run(&app, &conn, &settings, notify.unwrap_or(false))
+    })
+    .await
+    .map_err(|e| format!("Staffing task failed: {}", e))?
+}
+
+/// Compare rules against live presence every few minutes while checks are
+/// enabled. Runs for the lifetime of the app.
+pub async fn run_scheduled(app: tauri::AppHandle) {
+    let job = jobs::registry::register(
+        &app,
+        JOB_ID,
+        "Minimum staffing",
+        "Alerts when a department or site has fewer people in than its minimum during operating hours",
+        CHECK_INTERVAL,
+    );
+    let handle = app.clone();
+    jobs::registry::drive_blocking(
+        job,
+        move |_| {
+            let conn = db::open(&handle)?;
+            let settings = store::load_settings(&conn)?;
+            if !settings.enabled {
+                return Ok(None);
+            }
+            Ok(Some(check::run(&handle, &conn, &settings, true)?))
+        },
+        |run| {
+            if let Some(run) = run.filter(|run| !run.alerted.is_empty()) {
+                log::info!("[staffing] {} rules newly short", run.alerted.len());
+            }
+        },
+    )
+    .await;
+}
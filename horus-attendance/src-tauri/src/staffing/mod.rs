@@ -0,0 +1,20 @@
+//! Minimum-staffing alerts
+//!
+//! A staffing rule sets the fewest people a department, a site or both must
+//! have on the premises during operating hours (weekdays and a time window,
+//! e.g. Monday to Friday 08:00-17:00). While checks are on (the
+//! `minimumStaffing` setting) a background job compares every rule in its
+//! hours against live presence every few minutes and alerts once when the
+//! headcount drops below the minimum: the `staffing-shortfall` event and a
+//! webhook call logged as a delivery. It alerts again only after staffing
+//! has recovered and dropped anew.
+//!
+//! Live presence comes from today's punches: someone who has punched an odd
+//! number of times since the day cutoff is in. Repeat punches within a
+//! minute (double taps, paired terminals) count once. Rules are skipped on
+//! holidays that cover everyone they count.
+
+pub mod check;
+pub mod commands;
+pub mod store;
+pub mod types;
@@ -0,0 +1,287 @@
+//! Staffing settings, rules, live presence and alert state
+
+use std::collections::HashMap;
+
+use chrono::{Datelike, NaiveDateTime};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+use super::types::*;
+use crate::db::{self, logs};
+use crate::shifts::commands::validate_time;
+use crate::summary::engine::{self, UserMatcher};
+use crate::summary::rules;
+
+/// Settings key of the staffing settings
+pub const SETTINGS_KEY: &str = "minimumStaffing";
+
+/// Punches of one user closer together than this count once
+const REPEAT_PUNCH_SECONDS: i64 = 60;
+
+/// Load the settings, falling back to defaults (checks off)
+pub fn load_settings(conn: &Connection) -> Result<StaffingSettings, String> {
+    Ok(db::get_json_setting(conn, SETTINGS_KEY)?.unwrap_or_default())
+}
+
+fn map_rule(row: &Row) -> rusqlite::Result<StaffingRule> {
+    let weekdays: String = row.get(4)?;
+    Ok(StaffingRule {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        department_id: row.get(2)?,
+        site: row.get(3)?,
+        weekdays: serde_json::from_str(&weekdays).unwrap_or_default(),
+        start_time: row.get(5)?,
+        end_time: row.get(6)?,
+        min_headcount: row.get(7)?,
+        active: row.get(8)?,
+        created_at: row.get(9)?,
+        updated_at: row.get(10)?,
+    })
+}
+
+const SELECT_RULE: &str = "SELECT id, name, department_id, site, weekdays, start_time, end_time, min_headcount,
+                                  active, created_at, updated_at
+                           FROM staffing_rules";
+
+/// Every rule, by name
+pub fn list(conn: &Connection) -> Result<Vec<StaffingRule>, String> {
+    let mut stmt = conn
+        .prepare(&format!("{} ORDER BY name", SELECT_RULE))
+        .map_err(|e| format!("Failed to query staffing rules: {}", e))?;
+    let rows = stmt
+        .query_map([], map_rule)
+        .map_err(|e| format!("Failed to query staffing rules: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read staffing rules: {}", e))
+}
+
+pub fn get(conn: &Connection, id: &str) -> Result<Option<StaffingRule>, String> {
+    conn.query_row(&format!("{} WHERE id = ?1", SELECT_RULE), [id], map_rule)
+        .optional()
+        .map_err(|e| format!("Failed to read staffing rule: {}", e))
+}
+
+/// Create a rule (empty ID) or update one
+pub fn save(conn: &Connection, mut rule: StaffingRule) -> Result<StaffingRule, String> {
+    rule.name = rule.name.trim().to_string();
+    if rule.name.is_empty() {
+        return Err("Staffing rule name is required".to_string());
+    }
+    validate_time(&rule.start_time)?;
+    validate_time(&rule.end_time)?;
+    if rule.end_time <= rule.start_time {
+        return Err(format!(
+            "Operating hours must end after they start ({} - {})",
+            rule.start_time, rule.end_time
+        ));
+    }
+    if rule.min_headcount == 0 {
+        return Err("The minimum headcount must be at least 1".to_string());
+    }
+    rule.weekdays.sort_unstable();
+    rule.weekdays.dedup();
+    if rule.weekdays.is_empty() || rule.weekdays.iter().any(|d| *d > 6) {
+        return Err("Pick at least one weekday (0 = Sunday to 6 = Saturday)".to_string());
+    }
+    rule.site = rule.site.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+    rule.department_id = rule.department_id.filter(|d| !d.is_empty());
+    if let Some(department_id) = &rule.department_id {
+        let known: bool = conn
+            .query_row("SELECT EXISTS(SELECT 1 FROM departments WHERE id = ?1)", [department_id], |row| row.get(0))
+            .map_err(|e| format!("Failed to look up department: {}", e))?;
+        if !known {
+            return Err(format!("Department not found: {}", department_id));
+        }
+    }
+    if rule.id.is_empty() {
+        rule.id = uuid::Uuid::new_v4().to_string();
+    }
+    let weekdays = serde_json::to_string(&rule.weekdays).map_err(|e| format!("Failed to serialize weekdays: {}", e))?;
+    conn.execute(
+        "INSERT INTO staffing_rules
+            (id, name, department_id, site, weekdays, start_time, end_time, min_headcount, active)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+         ON CONFLICT(id) DO UPDATE SET
+           name = excluded.name,
+           department_id = excluded.department_id,
+           site = excluded.site,
+           weekdays = excluded.weekdays,
+           start_time = excluded.start_time,
+           end_time = excluded.end_time,
+           min_headcount = excluded.min_headcount,
+           active = excluded.active,
+           updated_at = datetime('now')",
+        params![
+            rule.id,
+            rule.name,
+            rule.department_id,
+            rule.site,
+            weekdays,
+            rule.start_time,
+            rule.end_time,
+            rule.min_headcount,
+            rule.active,
+        ],
+    )
+    .map_err(|e| format!("Failed to save staffing rule: {}", e))?;
+    // A changed rule starts over; its next shortfall alerts anew
+    clear_alert(conn, &rule.id)?;
+    get(conn, &rule.id)?.ok_or_else(|| "Staffing rule disappeared while saving".to_string())
+}
+
+pub fn delete(conn: &Connection, id: &str) -> Result<bool, String> {
+    conn.execute("DELETE FROM staffing_rules WHERE id = ?1", [id])
+        .map(|n| n > 0)
+        .map_err(|e| format!("Failed to delete staffing rule: {}", e))
+}
+
+/// Everyone on the premises at `now` (local wall-clock time) and the
+/// working day that was counted. Users are in after an odd number of
+/// punches that day; punches after `now` are ignored.
+pub fn presence(conn: &Connection, now: NaiveDateTime) -> Result<(String, Vec<PresentUser>), String> {
+    let cutoff_hour = engine::day_cutoff_hour(conn)?;
+    let now_ts = now.format("%Y-%m-%dT%H:%M:%S").to_string();
+    let date = rules::working_date(&now_ts, cutoff_hour);
+    let matcher = UserMatcher::load(conn)?;
+
+    // user ID -> (counted punches, latest punch)
+    let mut punches: HashMap<String, (u32, String)> = HashMap::new();
+    for punch in logs::between(conn, &date, &date, cutoff_hour)? {
+        let Some(at) = punch.timestamp.get(..19).filter(|at| *at <= now_ts.as_str()) else {
+            continue;
+        };
+        let Some(user_id) = matcher.resolve(&punch.device_user_id) else {
+            continue;
+        };
+        let entry = punches.entry(user_id.to_string()).or_insert((0, String::new()));
+        let repeat = NaiveDateTime::parse_from_str(&entry.1, "%Y-%m-%dT%H:%M:%S")
+            .ok()
+            .zip(NaiveDateTime::parse_from_str(at, "%Y-%m-%dT%H:%M:%S").ok())
+            .is_some_and(|(last, this)| (this - last).num_seconds() < REPEAT_PUNCH_SECONDS);
+        if !repeat {
+            entry.0 += 1;
+        }
+        entry.1 = at.to_string();
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT u.id, u.display_name, u.department_id, d.name, u.site
+             FROM users u
+             LEFT JOIN departments d ON d.id = u.department_id
+             WHERE u.status = 'active'
+             ORDER BY u.display_name",
+        )
+        .map_err(|e| format!("Failed to query users: {}", e))?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(PresentUser {
+                user_id: row.get(0)?,
+                display_name: row.get(1)?,
+                department_id: row.get(2)?,
+                department_name: row.get(3)?,
+                site: row.get(4)?,
+                since: String::new(),
+            })
+        })
+        .map_err(|e| format!("Failed to query users: {}", e))?;
+    let mut present = Vec::new();
+    for row in rows {
+        let mut user = row.map_err(|e| format!("Failed to read users: {}", e))?;
+        if let Some((count, latest)) = punches.get(&user.user_id) {
+            if count % 2 == 1 {
+                user.since = latest.clone();
+                present.push(user);
+            }
+        }
+    }
+    Ok((date, present))
+}
+
+/// Whether a holiday on `date` covers everyone the rule counts
+fn holiday_for(conn: &Connection, rule: &StaffingRule, date: &str) -> Result<bool, String> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM holidays
+                       WHERE date = ?1
+                         AND (department_id IS NULL OR department_id = ?2)
+                         AND (site IS NULL OR site = ?3))",
+        params![date, rule.department_id, rule.site],
+        |row| row.get(0),
+    )
+    .map_err(|e| format!("Failed to look up holidays: {}", e))
+}
+
+/// Every active rule against live presence at `now`
+pub fn statuses(conn: &Connection, now: NaiveDateTime) -> Result<(String, Vec<StaffingStatus>), String> {
+    let (date, present) = presence(conn, now)?;
+    let alerted = alerted(conn)?;
+    let today = now.format("%Y-%m-%d").to_string();
+    let time = now.format("%H:%M").to_string();
+    let weekday = now.weekday().num_days_from_sunday();
+    let departments: HashMap<String, String> = {
+        let mut stmt = conn
+            .prepare("SELECT id, name FROM departments")
+            .map_err(|e| format!("Failed to query departments: {}", e))?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| format!("Failed to query departments: {}", e))?;
+        rows.collect::<Result<_, _>>()
+            .map_err(|e| format!("Failed to read departments: {}", e))?
+    };
+
+    let mut statuses = Vec::new();
+    for rule in list(conn)?.into_iter().filter(|r| r.active) {
+        let count = present
+            .iter()
+            .filter(|u| rule.department_id.is_none() || u.department_id == rule.department_id)
+            .filter(|u| rule.site.is_none() || u.site == rule.site)
+            .count() as u32;
+        let in_hours = rule.weekdays.contains(&weekday)
+            && rule.start_time <= time
+            && time < rule.end_time
+            && !holiday_for(conn, &rule, &today)?;
+        statuses.push(StaffingStatus {
+            department_name: rule.department_id.as_ref().and_then(|d| departments.get(d).cloned()),
+            alerted_at: alerted.get(&rule.id).cloned(),
+            rule_id: rule.id,
+            rule_name: rule.name,
+            department_id: rule.department_id,
+            site: rule.site,
+            min_headcount: rule.min_headcount,
+            present: count,
+            in_hours,
+            short: in_hours && count < rule.min_headcount,
+        });
+    }
+    Ok((date, statuses))
+}
+
+/// rule ID -> when its current shortfall was alerted
+pub fn alerted(conn: &Connection) -> Result<HashMap<String, String>, String> {
+    let mut stmt = conn
+        .prepare("SELECT rule_id, alerted_at FROM staffing_alerts")
+        .map_err(|e| format!("Failed to query staffing alerts: {}", e))?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| format!("Failed to query staffing alerts: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read staffing alerts: {}", e))
+}
+
+/// Record that a rule's shortfall was alerted at `now`
+pub fn record_alert(conn: &Connection, status: &StaffingStatus, now: &str) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO staffing_alerts (rule_id, headcount, alerted_at) VALUES (?1, ?2, ?3)
+         ON CONFLICT(rule_id) DO UPDATE SET headcount = excluded.headcount, alerted_at = excluded.alerted_at",
+        params![status.rule_id, status.present, now],
+    )
+    .map_err(|e| format!("Failed to record staffing alert: {}", e))?;
+    Ok(())
+}
+
+/// Forget a rule's alert once it is staffed again or out of hours
+pub fn clear_alert(conn: &Connection, rule_id: &str) -> Result<(), String> {
+    conn.execute("DELETE FROM staffing_alerts WHERE rule_id = ?1", [rule_id])
+        .map_err(|e| format!("Failed to clear staffing alert: {}", e))?;
+    Ok(())
+}
@@ -0,0 +1,97 @@
+//! Staffing data types for Tauri command serialization
+
+use serde::{Deserialize, Serialize};
+
+/// Staffing settings (`minimumStaffing` setting)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct StaffingSettings {
+    /// Whether the background check runs; presence can be viewed either way
+    pub enabled: bool,
+    /// URL posted a JSON alert when a rule falls short; empty for none
+    pub webhook_url: String,
+}
+
+/// A minimum headcount during operating hours (one staffing_rules row)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StaffingRule {
+    /// Generated when saving a new rule
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    /// Only users of this department count; None for every department
+    #[serde(default)]
+    pub department_id: Option<String>,
+    /// Only users at this site count; None for every site
+    #[serde(default)]
+    pub site: Option<String>,
+    /// Days the rule applies, 0 = Sunday
+    #[serde(default = "default_weekdays")]
+    pub weekdays: Vec<u32>,
+    /// HH:MM
+    pub start_time: String,
+    /// HH:MM, after the start
+    pub end_time: String,
+    pub min_headcount: u32,
+    #[serde(default = "default_active")]
+    pub active: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+}
+
+fn default_weekdays() -> Vec<u32> {
+    vec![1, 2, 3, 4, 5]
+}
+
+fn default_active() -> bool {
+    true
+}
+
+/// Someone on the premises now
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PresentUser {
+    pub user_id: String,
+    pub display_name: String,
+    pub department_id: Option<String>,
+    pub department_name: Option<String>,
+    pub site: Option<String>,
+    /// Their latest punch today
+    pub since: String,
+}
+
+/// A rule against live presence
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StaffingStatus {
+    pub rule_id: String,
+    pub rule_name: String,
+    pub department_id: Option<String>,
+    pub department_name: Option<String>,
+    pub site: Option<String>,
+    pub min_headcount: u32,
+    pub present: u32,
+    /// Within the rule's weekdays and hours, and not a holiday for it
+    pub in_hours: bool,
+    /// In hours and below the minimum
+    pub short: bool,
+    /// When the current shortfall was alerted, if it was
+    pub alerted_at: Option<String>,
+}
+
+/// Result of one staffing check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StaffingCheck {
+    pub checked_at: String,
+    /// Working day presence was counted for (YYYY-MM-DD)
+    pub date: String,
+    pub rules: Vec<StaffingStatus>,
+    /// Rules alerted by this check
+    pub alerted: Vec<String>,
+    /// Webhook calls that failed; they are retried from the delivery log
+    pub failed_deliveries: u32,
+}
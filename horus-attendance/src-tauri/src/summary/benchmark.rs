@@ -0,0 +1,148 @@
+//! Timing both summary engines on the user's own data
+//!
+//! Each run recomputes the range inside a transaction that is rolled back,
+//! so summaries and their history are left as they were. The summaries the
+//! two engines wrote on their first run are compared field by field.
+
+use std::collections::BTreeMap;
+use std::time::Instant;
+
+use rusqlite::Connection;
+
+use super::engine;
+use super::set_based;
+use super::types::*;
+use crate::db::summaries;
+
+pub const DEFAULT_RUNS: u32 = 3;
+pub const MAX_RUNS: u32 = 10;
+
+/// Differences listed in the report; the rest are only counted
+const MAX_LISTED_DIFFERENCES: usize = 20;
+
+type Written = BTreeMap<(String, String), DaySummary>;
+
+/// Run `engine` once and collect what it wrote, then roll back
+fn run_once(
+    conn: &mut Connection,
+    kind: SummaryEngine,
+    start_date: &str,
+    end_date: &str,
+) -> Result<(u64, RecomputeResult, Written), String> {
+    conn.execute_batch("BEGIN")
+        .map_err(|e| format!("Failed to begin benchmark transaction: {}", e))?;
+    let source = ChangeSource {
+        reason: "benchmark",
+        actor: None,
+    };
+    let started = Instant::now();
+    let outcome = match kind {
+        SummaryEngine::SetBased => set_based::recompute(conn, start_date, end_date, &source),
+        SummaryEngine::RowByRow => engine::recompute_rows(conn, start_date, end_date, &source),
+    };
+    let elapsed = started.elapsed().as_millis() as u64;
+    let written = outcome.and_then(|result| {
+        let written = summaries::between(conn, start_date, end_date, None)?
+            .into_iter()
+            .map(|s| ((s.user_id.clone(), s.date.clone()), s))
+            .collect();
+        Ok((elapsed, result, written))
+    });
+    conn.execute_batch("ROLLBACK")
+        .map_err(|e| format!("Failed to roll back benchmark: {}", e))?;
+    written
+}
+
+fn time_engine(
+    conn: &mut Connection,
+    kind: SummaryEngine,
+    start_date: &str,
+    end_date: &str,
+    runs: u32,
+) -> Result<(EngineTiming, Written), String> {
+    let (first_ms, result, written) = run_once(conn, kind, start_date, end_date)?;
+    let mut run_ms = vec![first_ms];
+    for _ in 1..runs {
+        run_ms.push(run_once(conn, kind, start_date, end_date)?.0);
+    }
+    let timing = EngineTiming {
+        engine: kind,
+        best_ms: run_ms.iter().copied().min().unwrap_or(0),
+        run_ms,
+        days_processed: result.days_processed,
+        logs_processed: result.logs_processed,
+    };
+    Ok((timing, written))
+}
+
+/// Fields in which two summaries of the same day differ
+fn differing_fields(a: &DaySummary, b: &DaySummary) -> Vec<&'static str> {
+    let mut fields = Vec::new();
+    if a.check_in_time != b.check_in_time {
+        fields.push("checkInTime");
+    }
+    if a.check_out_time != b.check_out_time {
+        fields.push("checkOutTime");
+    }
+    if a.is_incomplete != b.is_incomplete {
+        fields.push("isIncomplete");
+    }
+    if a.late_minutes != b.late_minutes {
+        fields.push("lateMinutes");
+    }
+    if a.early_minutes != b.early_minutes {
+        fields.push("earlyMinutes");
+    }
+    if a.status != b.status {
+        fields.push("status");
+    }
+    if a.flags != b.flags {
+        fields.push("flags");
+    }
+    fields
+}
+
+/// Time both engines over a date range (inclusive), best of `runs` each
+pub fn run(conn: &mut Connection, start_date: &str, end_date: &str, runs: u32) -> Result<RecomputeBenchmark, String> {
+    let (row_by_row, rows_written) = time_engine(conn, SummaryEngine::RowByRow, start_date, end_date, runs)?;
+    let (set_based, set_written) = time_engine(conn, SummaryEngine::SetBased, start_date, end_date, runs)?;
+
+    let mut differing_days = 0;
+    let mut differences = Vec::new();
+    let keys: std::collections::BTreeSet<_> = rows_written.keys().chain(set_written.keys()).collect();
+    for key in keys {
+        let fields = match (rows_written.get(key), set_written.get(key)) {
+            (Some(a), Some(b)) => differing_fields(a, b),
+            (Some(_), None) => vec!["missing from set-based"],
+            (None, Some(_)) => vec!["missing from row-by-row"],
+            (None, None) => Vec::new(),
+        };
+        if fields.is_empty() {
+            continue;
+        }
+        differing_days += 1;
+        if differences.len() < MAX_LISTED_DIFFERENCES {
+            differences.push(format!("{} {}: {}", key.0, key.1, fields.join(", ")));
+        }
+    }
+
+    let speedup = row_by_row.best_ms.max(1) as f64 / set_based.best_ms.max(1) as f64;
+    log::info!(
+        "[summary::benchmark] {} to {}: set-based {} ms, row-by-row {} ms, {} differing days",
+        start_date,
+        end_date,
+        set_based.best_ms,
+        row_by_row.best_ms,
+        differing_days
+    );
+    Ok(RecomputeBenchmark {
+        start_date: start_date.to_string(),
+        end_date: end_date.to_string(),
+        runs,
+        set_based,
+        row_by_row,
+        speedup: (speedup * 10.0).round() / 10.0,
+        differing_days,
+        differences,
+    })
+}
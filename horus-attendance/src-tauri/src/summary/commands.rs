@@ -0,0 +1,128 @@
+//! Tauri command handlers for the summary engine.
+
+use super::benchmark;
+use super::dsl;
+use super::engine;
+use super::history;
+use super::types::*;
+use crate::activity::registry;
+use crate::db;
+
+/// Validate a date string (YYYY-MM-DD)
+pub(crate) fn validate_date(date: &str) -> Result<(), String> {
+    chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map(|_| ())
+        .map_err(|_| format!("Invalid date (expected YYYY-MM-DD): {}", date))
+}
+
+/// Recompute daily summaries from raw logs for a date range (inclusive)
+#[tauri::command]
+pub async fn recompute_summaries(
+    app: tauri::AppHandle,
+    start_date: String,
+    end_date: String,
+) -> Result<RecomputeResult, String> {
+    validate_date(&start_date)?;
+    validate_date(&end_date)?;
+    if start_date > end_date {
+        return Err("Start date must not be after end date".to_string());
+    }
+    log::info!("[summary::cmd] recompute_summaries {} to {}", start_date, end_date);
+
+    let activity = registry::begin(&app, "Summary recalculation")?;
+    let mut conn = db::open(&app)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let _activity = activity;
+        let actor = history::local_actor();
+        let source = ChangeSource {
+            reason: "recompute",
+            actor: Some(&actor),
+        };
+        engine::recompute(&mut conn, &start_date, &end_date, &source)
+    })
+        .await
+        .map_err(|e| format!("Summary task failed: {}", e))?
+}
+
+/// Engine used to recompute summaries
+#[tauri::command]
+pub async fn get_summary_engine(app: tauri::AppHandle) -> Result<SummaryEngine, String> {
+    engine::load_engine(&*db::open(&app)?)
+}
+
+#[tauri::command]
+pub async fn set_summary_engine(app: tauri::AppHandle, engine: SummaryEngine) -> Result<(), String> {
+    log::info!("[summary::cmd] set_summary_engine {:?}", engine);
+    let json = serde_json::to_string(&engine).map_err(|e| format!("Failed to serialize engine: {}", e))?;
+    db::set_setting(&*db::open(&app)?, engine::ENGINE_KEY, &json)
+}
+
+/// Time both engines recomputing a date range (inclusive) without keeping
+/// what they write, and list the days they disagree on
+#[tauri::command]
+pub async fn benchmark_recompute(
+    app: tauri::AppHandle,
+    start_date: String,
+    end_date: String,
+    runs: Option<u32>,
+) -> Result<RecomputeBenchmark, String> {
+    validate_date(&start_date)?;
+    validate_date(&end_date)?;
+    if start_date > end_date {
+        return Err("Start date must not be after end date".to_string());
+    }
+    let runs = runs.unwrap_or(benchmark::DEFAULT_RUNS);
+    if runs == 0 || runs > benchmark::MAX_RUNS {
+        return Err(format!("Runs must be between 1 and {}", benchmark::MAX_RUNS));
+    }
+    log::info!("[summary::cmd] benchmark_recompute {} to {} ({} runs)", start_date, end_date, runs);
+
+    let activity = registry::begin(&app, "Summary engine benchmark")?;
+    let mut conn = db::open(&app)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let _activity = activity;
+        benchmark::run(&mut conn, &start_date, &end_date, runs)
+    })
+        .await
+        .map_err(|e| format!("Benchmark task failed: {}", e))?
+}
+
+/// Past values of summaries between two dates (inclusive), optionally for one user
+#[tauri::command]
+pub async fn get_summary_history(
+    app: tauri::AppHandle,
+    user_id: Option<String>,
+    start_date: String,
+    end_date: String,
+) -> Result<Vec<SummaryChange>, String> {
+    validate_date(&start_date)?;
+    validate_date(&end_date)?;
+    history::list(&*db::open(&app)?, user_id.as_deref(), &start_date, &end_date)
+}
+
+/// Check a custom rules script without saving it
+#[tauri::command]
+pub fn validate_custom_rules(script: String) -> RuleValidationResult {
+    dsl::validate(&script)
+}
+
+/// Validate and store the custom rules script (an empty script removes it)
+#[tauri::command]
+pub async fn save_custom_rules(app: tauri::AppHandle, script: String) -> Result<RuleValidationResult, String> {
+    let conn = db::open(&app)?;
+
+    if script.trim().is_empty() {
+        db::delete_setting(&conn, dsl::CUSTOM_RULES_KEY)?;
+        return Ok(RuleValidationResult {
+            valid: true,
+            error: None,
+            line: None,
+        });
+    }
+
+    let validation = dsl::validate(&script);
+    if validation.valid {
+        db::set_setting(&conn, dsl::CUSTOM_RULES_KEY, &script)?;
+    }
+    Ok(validation)
+}
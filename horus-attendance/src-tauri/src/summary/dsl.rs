@@ -0,0 +1,205 @@
+//! Custom summary rules (Rhai scripts)
+//!
+//! Advanced users can store a short script under the `customRules` setting.
+//! It runs once per computed day, after the built-in rules, and may override
+//! `status` or push entries onto `flags`. The day's facts are exposed as a
+//! read-only `day` map:
+//!
+//! ```text
+//! if day.is_workday && day.punch_count == 1 && day.check_in != () {
+//!     flags.push("forgot_checkout");
+//! }
+//! if day.late_minutes > 120 { status = "absent"; }
+//! ```
+//!
+//! Scripts are sandboxed: no modules or `eval`, undefined variables are
+//! rejected at compile time, and operations, call depth and collection sizes
+//! are capped so a bad script cannot hang a recompute.
+
+use rhai::{Array, Dynamic, Engine, Map, Scope, AST};
+
+use super::rules::*;
+use super::types::*;
+
+/// Settings key holding the script source
+pub const CUSTOM_RULES_KEY: &str = "customRules";
+
+/// Statuses a script is allowed to assign
+pub const ALLOWED_STATUSES: &[&str] = &[
+    STATUS_PRESENT,
+    STATUS_ABSENT,
+    STATUS_LATE,
+    STATUS_EARLY_LEAVE,
+    STATUS_INCOMPLETE,
+    STATUS_HOLIDAY,
+    STATUS_WEEKEND,
+];
+
+const MAX_OPERATIONS: u64 = 50_000;
+const MAX_EXPR_DEPTH: usize = 32;
+const MAX_CALL_LEVELS: usize = 16;
+const MAX_STRING_SIZE: usize = 1024;
+const MAX_ARRAY_SIZE: usize = 256;
+const MAX_MAP_SIZE: usize = 64;
+
+/// Facts about a day that the built-in rules don't store on the summary
+pub struct DayFacts<'a> {
+    /// Valid punch times (HH:mm), sorted
+    pub punch_times: &'a [String],
+    pub is_holiday: bool,
+    pub is_workday: bool,
+}
+
+/// A compiled custom rules script, ready to be applied per day
+pub struct CustomRules {
+    engine: Engine,
+    ast: AST,
+}
+
+impl CustomRules {
+    /// Compile a script. Returns the error message and line on failure.
+    pub fn compile(script: &str) -> Result<Self, (String, Option<usize>)> {
+        let engine = sandboxed_engine();
+        let scope = template_scope();
+        let ast = engine
+            .compile_with_scope(&scope, script)
+            .map_err(|e| (e.to_string(), e.position().line()))?;
+        Ok(Self { engine, ast })
+    }
+
+    /// Run the script against a computed day, updating its status and flags
+    pub fn apply(&self, summary: &mut DaySummary, facts: &DayFacts) -> Result<(), String> {
+        let mut scope = Scope::new();
+        scope.push_constant("day", day_map(summary, facts));
+        scope.push("status", summary.status.clone());
+        scope.push("flags", summary.flags.iter().cloned().map(Dynamic::from).collect::<Array>());
+
+        self.engine
+            .run_ast_with_scope(&mut scope, &self.ast)
+            .map_err(|e| e.to_string())?;
+
+        let status = scope
+            .get_value::<String>("status")
+            .ok_or_else(|| "`status` must be a string".to_string())?;
+        if !ALLOWED_STATUSES.contains(&status.as_str()) {
+            return Err(format!("Unknown status '{}'", status));
+        }
+
+        let flags = scope
+            .get_value::<Array>("flags")
+            .ok_or_else(|| "`flags` must be an array".to_string())?
+            .into_iter()
+            .map(|f| f.into_string().map_err(|_| "`flags` may only contain strings".to_string()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        summary.status = status;
+        summary.flags = flags;
+        Ok(())
+    }
+}
+
+/// Validate a script: it must compile and run cleanly against a sample day
+pub fn validate(script: &str) -> RuleValidationResult {
+    let rules = match CustomRules::compile(script) {
+        Ok(rules) => rules,
+        Err((error, line)) => {
+            return RuleValidationResult {
+                valid: false,
+                error: Some(error),
+                line,
+            }
+        }
+    };
+
+    let punch_times = vec!["09:20".to_string(), "17:45".to_string()];
+    let mut sample = DaySummary {
+        user_id: "sample".to_string(),
+        date: "2024-01-01".to_string(),
+        check_in_time: Some("09:20".to_string()),
+        check_out_time: Some("17:45".to_string()),
+        is_incomplete: false,
+        late_minutes: 5,
+        early_minutes: 0,
+        status: STATUS_LATE.to_string(),
+        flags: Vec::new(),
+    };
+    let facts = DayFacts {
+        punch_times: &punch_times,
+        is_holiday: false,
+        is_workday: true,
+    };
+
+    match rules.apply(&mut sample, &facts) {
+        Ok(()) => RuleValidationResult {
+            valid: true,
+            error: None,
+            line: None,
+        },
+        Err(error) => RuleValidationResult {
+            valid: false,
+            error: Some(error),
+            line: None,
+        },
+    }
+}
+
+/// Build an engine with all resource limits applied
+fn sandboxed_engine() -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_strict_variables(true)
+        .set_max_operations(MAX_OPERATIONS)
+        .set_max_expr_depths(MAX_EXPR_DEPTH, MAX_EXPR_DEPTH)
+        .set_max_call_levels(MAX_CALL_LEVELS)
+        .set_max_string_size(MAX_STRING_SIZE)
+        .set_max_array_size(MAX_ARRAY_SIZE)
+        .set_max_map_size(MAX_MAP_SIZE)
+        .disable_symbol("eval");
+    engine.on_print(|text| log::debug!("[summary::dsl] print: {}", text));
+    engine.on_debug(|text, _source, pos| log::debug!("[summary::dsl] debug at {}: {}", pos, text));
+    engine
+}
+
+/// Scope with the variables scripts may reference, for strict-mode compilation
+fn template_scope() -> Scope<'static> {
+    let mut scope = Scope::new();
+    scope.push_constant("day", Map::new());
+    scope.push("status", String::new());
+    scope.push("flags", Array::new());
+    scope
+}
+
+/// Expose a computed day to the script
+fn day_map(summary: &DaySummary, facts: &DayFacts) -> Map {
+    let optional = |value: &Option<String>| match value {
+        Some(v) => Dynamic::from(v.clone()),
+        None => Dynamic::UNIT,
+    };
+    let weekday = chrono::NaiveDate::parse_from_str(&summary.date, "%Y-%m-%d")
+        .map(|d| chrono::Datelike::weekday(&d).num_days_from_sunday() as i64)
+        .unwrap_or(-1);
+    let worked_minutes = match (&summary.check_in_time, &summary.check_out_time) {
+        (Some(i), Some(o)) => span_minutes(i, o),
+        _ => 0,
+    };
+
+    let mut map = Map::new();
+    map.insert("user_id".into(), Dynamic::from(summary.user_id.clone()));
+    map.insert("date".into(), Dynamic::from(summary.date.clone()));
+    map.insert("weekday".into(), Dynamic::from(weekday));
+    map.insert("check_in".into(), optional(&summary.check_in_time));
+    map.insert("check_out".into(), optional(&summary.check_out_time));
+    map.insert("punch_count".into(), Dynamic::from(facts.punch_times.len() as i64));
+    map.insert(
+        "punches".into(),
+        Dynamic::from(facts.punch_times.iter().cloned().map(Dynamic::from).collect::<Array>()),
+    );
+    map.insert("worked_minutes".into(), Dynamic::from(worked_minutes));
+    map.insert("late_minutes".into(), Dynamic::from(summary.late_minutes));
+    map.insert("early_minutes".into(), Dynamic::from(summary.early_minutes));
+    map.insert("is_incomplete".into(), Dynamic::from(summary.is_incomplete));
+    map.insert("is_holiday".into(), Dynamic::from(facts.is_holiday));
+    map.insert("is_workday".into(), Dynamic::from(facts.is_workday));
+    map.insert("base_status".into(), Dynamic::from(summary.status.clone()));
+    map
+}
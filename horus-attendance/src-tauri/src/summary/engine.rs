@@ -0,0 +1,248 @@
+//! Summary recomputation against the database
+//!
+//! Matches raw logs to users with the same strategy as the frontend sync
+//! engine (device user ID, then device name, then display name), processes
+//! each user/day, applies custom rules, and upserts the summaries, then
+//! gives days marked as remote work their remote status. Days are
+//! working days starting at the configured cutoff hour, so a 04:00 punch
+//! with a 05:00 cutoff closes the previous day.
+
+use rusqlite::Connection;
+use std::collections::{BTreeMap, HashMap};
+
+use super::dsl::{self, CustomRules, DayFacts};
+use super::rules;
+use super::set_based;
+use super::types::*;
+use crate::db::{self, logs, summaries, users, users::UserRecord};
+use crate::holidays::store::Calendar;
+use crate::remote_work;
+
+/// Resolves a log's device_user_id to a user ID
+pub(crate) struct UserMatcher {
+    by_device_user_id: HashMap<String, String>,
+    by_device_name: HashMap<String, String>,
+    by_display_name: HashMap<String, String>,
+}
+
+impl UserMatcher {
+    pub(crate) fn new(users: Vec<UserRecord>) -> Self {
+        let mut matcher = Self {
+            by_device_user_id: HashMap::new(),
+            by_device_name: HashMap::new(),
+            by_display_name: HashMap::new(),
+        };
+        for user in users {
+            if let Some(device_user_id) = user.device_user_id.filter(|v| !v.is_empty()) {
+                matcher.by_device_user_id.insert(device_user_id, user.id.clone());
+            }
+            if let Some(device_name) = user.device_name.filter(|v| !v.is_empty()) {
+                matcher.by_device_name.insert(device_name.to_lowercase(), user.id.clone());
+            }
+            if !user.display_name.is_empty() {
+                matcher.by_display_name.insert(user.display_name.to_lowercase(), user.id);
+            }
+        }
+        matcher
+    }
+
+    /// Also resolve device user IDs linked to a profile through
+    /// user_device_links. A profile's own device user ID takes precedence.
+    pub(crate) fn with_links(mut self, links: Vec<users::DeviceLink>) -> Self {
+        for link in links {
+            self.by_device_user_id.entry(link.device_user_id).or_insert(link.user_id);
+        }
+        self
+    }
+
+    /// Every user and linked device user ID
+    pub(crate) fn load(conn: &Connection) -> Result<Self, String> {
+        Ok(Self::new(users::list(conn)?).with_links(users::device_links(conn)?))
+    }
+
+    pub(crate) fn resolve(&self, device_user_id: &str) -> Option<&str> {
+        if let Some(id) = self.by_device_user_id.get(device_user_id) {
+            return Some(id);
+        }
+        let lower = device_user_id.to_lowercase();
+        self.by_device_name
+            .get(&lower)
+            .or_else(|| self.by_display_name.get(&lower))
+            .map(String::as_str)
+    }
+}
+
+/// Load the attendance rules, keeping the day cutoff hour within range
+pub fn load_rules(conn: &Connection) -> Result<AttendanceRules, String> {
+    let mut attendance_rules: AttendanceRules = db::get_json_setting(conn, "attendance")?.unwrap_or_default();
+    attendance_rules.day_cutoff_hour = attendance_rules.day_cutoff_hour.min(rules::MAX_DAY_CUTOFF_HOUR);
+    Ok(attendance_rules)
+}
+
+/// Hour the working day starts at, for grouping punches outside the engine
+pub fn day_cutoff_hour(conn: &Connection) -> Result<u32, String> {
+    Ok(load_rules(conn)?.day_cutoff_hour)
+}
+
+/// Load and compile the stored custom rules script, if any
+pub fn load_custom_rules(conn: &Connection) -> Result<Option<CustomRules>, String> {
+    let script = match db::get_setting(conn, dsl::CUSTOM_RULES_KEY)? {
+        Some(s) if !s.trim().is_empty() => s,
+        _ => return Ok(None),
+    };
+    match CustomRules::compile(&script) {
+        Ok(rules) => Ok(Some(rules)),
+        Err((error, _line)) => {
+            // A broken script must not block summaries; the built-in rules still apply
+            log::warn!("[summary] Custom rules failed to compile, ignoring: {}", error);
+            Ok(None)
+        }
+    }
+}
+
+/// Settings key of the engine used to recompute summaries
+pub const ENGINE_KEY: &str = "summaryEngine";
+
+/// The configured engine (set-based unless changed)
+pub fn load_engine(conn: &Connection) -> Result<SummaryEngine, String> {
+    Ok(db::get_json_setting(conn, ENGINE_KEY)?.unwrap_or_default())
+}
+
+/// Recompute summaries for every user with punches between two dates
+/// (inclusive) with the configured engine, then mark the remote days
+pub fn recompute(
+    conn: &mut Connection,
+    start_date: &str,
+    end_date: &str,
+    source: &ChangeSource,
+) -> Result<RecomputeResult, String> {
+    let result = match load_engine(conn)? {
+        SummaryEngine::SetBased => set_based::recompute(conn, start_date, end_date, source)?,
+        SummaryEngine::RowByRow => recompute_rows(conn, start_date, end_date, source)?,
+    };
+    remote_work::store::apply(conn, start_date, end_date, source)?;
+    Ok(result)
+}
+
+/// Run the custom rules script over a computed day, keeping the day as it
+/// was (and noting why) when the script fails
+pub(super) fn customise(
+    custom: &CustomRules,
+    summary: DaySummary,
+    facts: &DayFacts,
+    rule_errors: &mut Vec<String>,
+) -> DaySummary {
+    let mut customised = summary.clone();
+    match custom.apply(&mut customised, facts) {
+        Ok(()) => customised,
+        Err(e) => {
+            rule_errors.push(format!("{} {}: {}", summary.user_id, summary.date, e));
+            summary
+        }
+    }
+}
+
+/// Row-by-row engine: load the punches, group them per user and day and
+/// process each day in Rust
+pub fn recompute_rows(
+    conn: &mut Connection,
+    start_date: &str,
+    end_date: &str,
+    source: &ChangeSource,
+) -> Result<RecomputeResult, String> {
+    let attendance_rules = load_rules(conn)?;
+    let custom_rules = load_custom_rules(conn)?;
+
+    let holidays = Calendar::load(conn, start_date, end_date)?;
+
+    let matcher = UserMatcher::load(conn)?;
+
+    // Users with their own shift are judged against its hours
+    let shift_rules: HashMap<String, AttendanceRules> = crate::shifts::store::list(conn)?
+        .into_iter()
+        .map(|shift| {
+            let rules = AttendanceRules {
+                work_start_time: shift.start_time,
+                work_end_time: shift.end_time,
+                ..attendance_rules.clone()
+            };
+            (shift.user_id, rules)
+        })
+        .collect();
+    // Rostered days override both
+    let roster = crate::roster::store::hours_between(conn, start_date, end_date)?;
+    // Contractors are tracked for hours only
+    let hours_only = users::hours_only_ids(conn)?;
+
+    // Group punches by (user, date)
+    let mut result = RecomputeResult {
+        days_processed: 0,
+        logs_processed: 0,
+        unmatched_logs: 0,
+        rule_errors: Vec::new(),
+    };
+    let mut grouped: BTreeMap<(String, String), Vec<String>> = BTreeMap::new();
+    let cutoff_hour = attendance_rules.day_cutoff_hour;
+    for punch in logs::between(conn, start_date, end_date, cutoff_hour)? {
+        result.logs_processed += 1;
+        match matcher.resolve(&punch.device_user_id) {
+            Some(user_id) => grouped
+                .entry((user_id.to_string(), rules::working_date(&punch.timestamp, cutoff_hour)))
+                .or_default()
+                .push(punch.timestamp),
+            None => result.unmatched_logs += 1,
+        }
+    }
+
+    let computed: Vec<DaySummary> = grouped
+        .iter()
+        .map(|((user_id, date), timestamps)| {
+            let refs: Vec<&str> = timestamps.iter().map(String::as_str).collect();
+            let is_holiday = holidays.observes(user_id, date);
+            let rostered_rules;
+            let attendance_rules = match roster.get(&(user_id.clone(), date.clone())) {
+                Some((start, end)) => {
+                    rostered_rules = AttendanceRules {
+                        work_start_time: start.clone(),
+                        work_end_time: end.clone(),
+                        ..attendance_rules.clone()
+                    };
+                    &rostered_rules
+                }
+                None => shift_rules.get(user_id).unwrap_or(&attendance_rules),
+            };
+            let mut summary = rules::process_day(user_id, date, &refs, attendance_rules, is_holiday);
+            if hours_only.contains(user_id) {
+                summary = rules::hours_only(summary);
+            }
+
+            match &custom_rules {
+                Some(custom) => {
+                    let punch_times: Vec<String> = rules::filter_punches_in_window(&refs, attendance_rules)
+                        .into_iter()
+                        .map(rules::extract_time)
+                        .collect();
+                    let facts = DayFacts {
+                        punch_times: &punch_times,
+                        is_holiday,
+                        is_workday: rules::is_workday(date, attendance_rules),
+                    };
+                    customise(custom, summary, &facts, &mut result.rule_errors)
+                }
+                None => summary,
+            }
+        })
+        .collect();
+
+    result.days_processed = computed.len() as u32;
+    summaries::upsert(conn, &computed, source)?;
+
+    log::info!(
+        "[summary] Recomputed {} days from {} logs ({} unmatched, {} rule errors)",
+        result.days_processed,
+        result.logs_processed,
+        result.unmatched_logs,
+        result.rule_errors.len()
+    );
+    Ok(result)
+}
@@ -0,0 +1,122 @@
+//! Change history of day summaries
+//!
+//! Triggers on attendance_day_summary copy the previous values of every
+//! summary that changes or is deleted into summary_history, together with
+//! the `change_reason` and `changed_by` the writer stamped on the row. This
+//! module reads that history back and writes it out for auditors.
+
+use std::io::Write;
+use std::path::Path;
+
+use rusqlite::{params, Connection};
+
+use super::types::SummaryChange;
+
+/// Name recorded as the actor for changes made from this computer
+pub fn local_actor() -> String {
+    std::env::var("USERNAME")
+        .or_else(|_| std::env::var("USER"))
+        .map(|name| format!("local:{}", name))
+        .unwrap_or_else(|_| "local".to_string())
+}
+
+/// Changes to summaries dated between `start_date` and `end_date` (inclusive),
+/// optionally for one user, oldest first
+pub fn list(
+    conn: &Connection,
+    user_id: Option<&str>,
+    start_date: &str,
+    end_date: &str,
+) -> Result<Vec<SummaryChange>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT h.id, h.summary_id, h.user_id, u.display_name, h.date,
+                    h.check_in_time, h.check_out_time, h.is_incomplete, h.late_minutes,
+                    h.early_minutes, h.status, h.flags,
+                    h.new_check_in_time, h.new_check_out_time, h.new_status,
+                    h.new_late_minutes, h.new_early_minutes,
+                    h.reason, h.actor, h.changed_at
+             FROM summary_history h
+             LEFT JOIN users u ON u.id = h.user_id
+             WHERE h.date >= ?1 AND h.date <= ?2 AND (?3 IS NULL OR h.user_id = ?3)
+             ORDER BY h.date, h.user_id, h.id",
+        )
+        .map_err(|e| format!("Failed to query summary history: {}", e))?;
+    let rows = stmt
+        .query_map(params![start_date, end_date, user_id], |row| {
+            Ok(SummaryChange {
+                id: row.get(0)?,
+                summary_id: row.get(1)?,
+                user_id: row.get(2)?,
+                display_name: row.get(3)?,
+                date: row.get(4)?,
+                check_in_time: row.get(5)?,
+                check_out_time: row.get(6)?,
+                is_incomplete: row.get::<_, i64>(7)? != 0,
+                late_minutes: row.get(8)?,
+                early_minutes: row.get(9)?,
+                status: row.get(10)?,
+                flags: row.get(11)?,
+                new_check_in_time: row.get(12)?,
+                new_check_out_time: row.get(13)?,
+                new_status: row.get(14)?,
+                new_late_minutes: row.get(15)?,
+                new_early_minutes: row.get(16)?,
+                reason: row.get(17)?,
+                actor: row.get(18)?,
+                changed_at: row.get(19)?,
+            })
+        })
+        .map_err(|e| format!("Failed to query summary history: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read summary history: {}", e))
+}
+
+pub(crate) fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Write `changes` as CSV to `path`
+pub fn write_csv(changes: &[SummaryChange], path: &Path) -> Result<(), String> {
+    let file = std::fs::File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut out = std::io::BufWriter::new(file);
+    let write_err = |e: std::io::Error| format!("Failed to write {}: {}", path.display(), e);
+
+    writeln!(
+        out,
+        "changed_at,date,user_id,employee,reason,actor,\
+         old_check_in,old_check_out,old_status,old_late_minutes,old_early_minutes,\
+         new_check_in,new_check_out,new_status,new_late_minutes,new_early_minutes"
+    )
+    .map_err(write_err)?;
+    let opt = |v: &Option<String>| v.as_deref().map(csv_field).unwrap_or_default();
+    let num = |v: Option<i64>| v.map(|n| n.to_string()).unwrap_or_default();
+    for c in changes {
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            csv_field(&c.changed_at),
+            csv_field(&c.date),
+            csv_field(&c.user_id),
+            opt(&c.display_name),
+            csv_field(&c.reason),
+            opt(&c.actor),
+            opt(&c.check_in_time),
+            opt(&c.check_out_time),
+            csv_field(&c.status),
+            c.late_minutes,
+            c.early_minutes,
+            opt(&c.new_check_in_time),
+            opt(&c.new_check_out_time),
+            opt(&c.new_status),
+            num(c.new_late_minutes),
+            num(c.new_early_minutes),
+        )
+        .map_err(write_err)?;
+    }
+    out.flush().map_err(write_err)
+}
@@ -0,0 +1,19 @@
+//! Attendance summary engine
+//!
+//! Rust port of the frontend rule engine: groups raw punches per user and day,
+//! derives check-in/out, lateness and status, and writes attendance_day_summary.
+//! Each computed day can be post-processed by a user-defined rules script,
+//! and every change to a stored day is kept in its history.
+//!
+//! Two engines compute the same summaries: a set-based one doing the whole
+//! range in one SQL pass (the default) and the original row-by-row one. The
+//! benchmark times both on the user's data and reports where they disagree.
+
+pub mod benchmark;
+pub mod commands;
+pub mod dsl;
+pub mod engine;
+pub mod history;
+pub mod rules;
+pub mod set_based;
+pub mod types;
@@ -0,0 +1,267 @@
+//! Per-day attendance rules
+//!
+//! Mirrors processDay() in the frontend rule engine so summaries computed in
+//! Rust match the ones generated during a frontend sync.
+
+use chrono::{Datelike, Duration, NaiveDate};
+
+use super::types::*;
+
+pub const STATUS_PRESENT: &str = "present";
+pub const STATUS_ABSENT: &str = "absent";
+pub const STATUS_LATE: &str = "late";
+pub const STATUS_EARLY_LEAVE: &str = "early_leave";
+pub const STATUS_INCOMPLETE: &str = "incomplete";
+pub const STATUS_HOLIDAY: &str = "holiday";
+pub const STATUS_WEEKEND: &str = "weekend";
+pub const STATUS_REMOTE: &str = "remote";
+
+/// Minutes since midnight that split check-in punches from check-out punches
+const MIDDAY_MINUTES: i64 = 12 * 60;
+
+/// Latest working-day cutoff; a later one would split ordinary day shifts
+pub const MAX_DAY_CUTOFF_HOUR: u32 = 12;
+
+/// Parse a time string (HH:mm) to minutes since midnight
+pub fn parse_time_to_minutes(time: &str) -> i64 {
+    let mut parts = time.split(':');
+    let hours = parts.next().and_then(|h| h.trim().parse::<i64>().ok()).unwrap_or(0);
+    let minutes = parts.next().and_then(|m| m.trim().parse::<i64>().ok()).unwrap_or(0);
+    hours * 60 + minutes
+}
+
+/// Extract the time (HH:mm) from a stored timestamp.
+/// Device timestamps are local wall-clock time tagged as UTC (already moved
+/// off the device clock by its DST policy during sync), so the literal time
+/// portion is used without any timezone conversion.
+pub fn extract_time(timestamp: &str) -> String {
+    timestamp.get(11..16).unwrap_or("00:00").to_string()
+}
+
+/// Extract the date (YYYY-MM-DD) from a stored timestamp
+pub fn extract_date(timestamp: &str) -> String {
+    timestamp.get(0..10).unwrap_or(timestamp).to_string()
+}
+
+/// The working day (YYYY-MM-DD) a stored timestamp belongs to. Punches
+/// before the cutoff hour count toward the previous day.
+pub fn working_date(timestamp: &str, cutoff_hour: u32) -> String {
+    let date = extract_date(timestamp);
+    let hour = timestamp.get(11..13).and_then(|h| h.parse::<u32>().ok()).unwrap_or(0);
+    if hour >= cutoff_hour {
+        return date;
+    }
+    NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+        .map(|d| (d - Duration::days(1)).format("%Y-%m-%d").to_string())
+        .unwrap_or(date)
+}
+
+/// SQL expression giving the working day (YYYY-MM-DD) of a timestamp column
+pub fn working_date_sql(column: &str, cutoff_hour: u32) -> String {
+    if cutoff_hour == 0 {
+        format!("substr({}, 1, 10)", column)
+    } else {
+        format!("date(substr({}, 1, 19), '-{} hours')", column, cutoff_hour)
+    }
+}
+
+/// Minutes of a time (HH:mm) since the midnight the working day started
+/// after, so times before the cutoff hour sort after the evening
+pub fn day_minutes(time: &str, rules: &AttendanceRules) -> i64 {
+    let minutes = parse_time_to_minutes(time);
+    if minutes < i64::from(rules.day_cutoff_hour) * 60 {
+        minutes + 24 * 60
+    } else {
+        minutes
+    }
+}
+
+/// Minutes from one time (HH:mm) to a later one, which may be past midnight
+pub fn span_minutes(start: &str, end: &str) -> i64 {
+    (parse_time_to_minutes(end) - parse_time_to_minutes(start)).rem_euclid(24 * 60)
+}
+
+/// Minutes (as from [`day_minutes`]) that split check-in punches from
+/// check-out punches: midday for calendar days, otherwise halfway through
+/// the working hours so evening check-ins stay check-ins
+fn split_minutes(rules: &AttendanceRules) -> i64 {
+    if rules.day_cutoff_hour == 0 {
+        return MIDDAY_MINUTES;
+    }
+    (day_minutes(&rules.work_start_time, rules) + day_minutes(&rules.work_end_time, rules)) / 2
+}
+
+/// Whether a shift from `start` to `end` (HH:mm) ends after it starts
+/// within one working day
+pub fn ends_after_start(start: &str, end: &str, rules: &AttendanceRules) -> bool {
+    day_minutes(end, rules) > day_minutes(start, rules)
+}
+
+/// Check if a time is within a window (inclusive)
+pub fn is_time_in_window(time: &str, window_start: &str, window_end: &str, rules: &AttendanceRules) -> bool {
+    let minutes = day_minutes(time, rules);
+    minutes >= day_minutes(window_start, rules) && minutes <= day_minutes(window_end, rules)
+}
+
+/// Keep punches within the check-in window (mornings) or check-out window
+/// (afternoons)
+pub fn filter_punches_in_window<'a>(timestamps: &[&'a str], rules: &AttendanceRules) -> Vec<&'a str> {
+    timestamps
+        .iter()
+        .copied()
+        .filter(|ts| {
+            let time = extract_time(ts);
+            if day_minutes(&time, rules) < split_minutes(rules) {
+                is_time_in_window(&time, &rules.check_in_window_start, &rules.check_in_window_end, rules)
+            } else {
+                is_time_in_window(&time, &rules.check_out_window_start, &rules.check_out_window_end, rules)
+            }
+        })
+        .collect()
+}
+
+/// Minutes late beyond the work start time plus grace period
+pub fn calculate_late_minutes(check_in_time: &str, rules: &AttendanceRules) -> i64 {
+    let grace_end = day_minutes(&rules.work_start_time, rules) + rules.late_grace_period;
+    (day_minutes(check_in_time, rules) - grace_end).max(0)
+}
+
+/// Minutes left before the work end time minus grace period
+pub fn calculate_early_minutes(check_out_time: &str, rules: &AttendanceRules) -> i64 {
+    let grace_start = day_minutes(&rules.work_end_time, rules) - rules.early_leave_grace_period;
+    (grace_start - day_minutes(check_out_time, rules)).max(0)
+}
+
+/// Check if a date (YYYY-MM-DD) is a configured workday
+pub fn is_workday(date: &str, rules: &AttendanceRules) -> bool {
+    match NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+        Ok(d) => rules.workdays.contains(&d.weekday().num_days_from_sunday()),
+        Err(_) => false,
+    }
+}
+
+/// Derive the attendance status for a day
+#[allow(clippy::too_many_arguments)]
+pub fn derive_status(
+    check_in_time: Option<&str>,
+    check_out_time: Option<&str>,
+    is_incomplete: bool,
+    late_minutes: i64,
+    early_minutes: i64,
+    date: &str,
+    rules: &AttendanceRules,
+    is_holiday: bool,
+) -> &'static str {
+    if is_holiday {
+        return STATUS_HOLIDAY;
+    }
+    if !is_workday(date, rules) {
+        return STATUS_WEEKEND;
+    }
+    if check_in_time.is_none() && check_out_time.is_none() {
+        return STATUS_ABSENT;
+    }
+    if is_incomplete {
+        return STATUS_INCOMPLETE;
+    }
+    // Late takes priority over early leave
+    if late_minutes > 0 {
+        return STATUS_LATE;
+    }
+    if early_minutes > 0 {
+        return STATUS_EARLY_LEAVE;
+    }
+    STATUS_PRESENT
+}
+
+/// Process one user's punches for one day.
+/// First punch is check-in, last punch is check-out; a single punch is
+/// classified by time of day and marked incomplete.
+pub fn process_day(
+    user_id: &str,
+    date: &str,
+    timestamps: &[&str],
+    rules: &AttendanceRules,
+    is_holiday: bool,
+) -> DaySummary {
+    let mut valid = filter_punches_in_window(timestamps, rules);
+    valid.sort_unstable();
+
+    let mut flags = Vec::new();
+    let mut check_in_time = None;
+    let mut check_out_time = None;
+    let mut late_minutes = 0;
+    let mut early_minutes = 0;
+    let mut is_incomplete = false;
+
+    match valid.as_slice() {
+        [] => {}
+        [single] => {
+            let time = extract_time(single);
+            is_incomplete = true;
+            if day_minutes(&time, rules) < split_minutes(rules) {
+                late_minutes = calculate_late_minutes(&time, rules);
+                check_in_time = Some(time);
+                flags.push("single_punch_checkin".to_string());
+            } else {
+                early_minutes = calculate_early_minutes(&time, rules);
+                check_out_time = Some(time);
+                flags.push("single_punch_checkout".to_string());
+            }
+        }
+        [first, .., last] => {
+            let check_in = extract_time(first);
+            let check_out = extract_time(last);
+            late_minutes = calculate_late_minutes(&check_in, rules);
+            early_minutes = calculate_early_minutes(&check_out, rules);
+            check_in_time = Some(check_in);
+            check_out_time = Some(check_out);
+            if valid.len() > 2 {
+                flags.push("multiple_punches".to_string());
+            }
+        }
+    }
+
+    let status = derive_status(
+        check_in_time.as_deref(),
+        check_out_time.as_deref(),
+        is_incomplete,
+        late_minutes,
+        early_minutes,
+        date,
+        rules,
+        is_holiday,
+    );
+
+    DaySummary {
+        user_id: user_id.to_string(),
+        date: date.to_string(),
+        check_in_time,
+        check_out_time,
+        is_incomplete,
+        late_minutes,
+        early_minutes,
+        status: status.to_string(),
+        flags,
+    }
+}
+
+/// A day of someone tracked for hours only: punches and incompleteness
+/// stand, lateness and early leave don't
+pub fn hours_only(mut summary: DaySummary) -> DaySummary {
+    summary.late_minutes = 0;
+    summary.early_minutes = 0;
+    if summary.status == STATUS_LATE || summary.status == STATUS_EARLY_LEAVE {
+        summary.status = STATUS_PRESENT.to_string();
+    }
+    summary
+}
+
+/// A day marked as worked remotely: any punches stand, lateness and early
+/// leave don't, and it never counts as an absence
+pub fn remote_day(mut summary: DaySummary) -> DaySummary {
+    summary.late_minutes = 0;
+    summary.early_minutes = 0;
+    summary.status = STATUS_REMOTE.to_string();
+    summary
+}
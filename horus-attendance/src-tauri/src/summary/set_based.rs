@@ -0,0 +1,334 @@
+//! Set-based summary engine
+//!
+//! Computes every user/day in the range with one SQL statement instead of
+//! loading the punches into Rust: punches are matched to users through a
+//! temporary table, classified against the user's hours for the day (roster,
+//! then shift, then the global rules), and window functions over each
+//! user/day pick the first and last valid punch. Lateness, status and flags
+//! follow process_day() exactly (and rules::hours_only() for contractors),
+//! so both engines write the same summaries.
+//!
+//! Without a custom rules script the results go straight into
+//! attendance_day_summary with one INSERT ... SELECT; with one, each day is
+//! read back, run through the script and upserted as usual.
+
+use rusqlite::{params, Connection};
+
+use super::dsl::DayFacts;
+use super::engine::{self, UserMatcher};
+use super::rules;
+use super::types::*;
+use crate::db::{logs, summaries, users};
+use crate::holidays::store as holidays;
+
+/// A random version 4 UUID, for new summary rows
+const UUID_SQL: &str = "lower(hex(randomblob(4))) || '-' || lower(hex(randomblob(2))) || '-4' ||
+    substr(lower(hex(randomblob(2))), 2) || '-' || substr('89ab', 1 + (abs(random()) % 4), 1) ||
+    substr(lower(hex(randomblob(2))), 2) || '-' || lower(hex(randomblob(6)))";
+
+/// Minutes since midnight of an HH:mm expression
+fn minutes_sql(time: &str) -> String {
+    format!("(CAST(substr({t}, 1, 2) AS INTEGER) * 60 + CAST(substr({t}, 4, 2) AS INTEGER))", t = time)
+}
+
+/// rules::day_minutes() of an HH:mm expression; ?3 is the cutoff hour
+fn day_minutes_sql(time: &str) -> String {
+    let minutes = minutes_sql(time);
+    format!("({m} + CASE WHEN {m} < ?3 * 60 THEN 1440 ELSE 0 END)", m = minutes)
+}
+
+/// The statement filling temp.recompute_days. Parameters: ?1/?2 timestamp
+/// bounds, ?3 cutoff hour, ?4/?5 work start/end, ?6/?7 late/early grace,
+/// ?8..?11 check-in and check-out windows, ?12 workdays as a JSON array.
+fn compute_sql(cutoff_hour: u32) -> String {
+    format!(
+        "CREATE TEMP TABLE recompute_days AS
+         WITH punches AS (
+             SELECT m.user_id, {day} AS day, l.timestamp, {punch_dm} AS dm
+             FROM attendance_logs_raw l
+             JOIN temp.recompute_matches m ON m.device_user_id = l.device_user_id
+             WHERE l.timestamp >= ?1 AND l.timestamp < ?2 AND {not_voided}
+         ),
+         hours AS (
+             SELECT p.user_id, p.day, p.timestamp, p.dm,
+                    {start_dm} AS start_dm,
+                    {end_dm} AS end_dm
+             FROM punches p
+             LEFT JOIN roster_entries r ON r.user_id = p.user_id AND r.date = p.day
+             LEFT JOIN user_shifts s ON s.user_id = p.user_id
+         ),
+         classified AS (
+             SELECT *,
+                    CASE WHEN dm < split_dm THEN dm BETWEEN {in_start} AND {in_end}
+                         ELSE dm BETWEEN {out_start} AND {out_end} END AS is_valid
+             FROM (
+                 SELECT *, CASE WHEN ?3 = 0 THEN 720 ELSE (start_dm + end_dm) / 2 END AS split_dm
+                 FROM hours
+             )
+         ),
+         days AS (
+             SELECT user_id, day, start_dm, end_dm, split_dm,
+                    row_number() OVER (PARTITION BY user_id, day ORDER BY timestamp) AS rn,
+                    min(CASE WHEN is_valid THEN timestamp END) OVER whole AS first_ts,
+                    max(CASE WHEN is_valid THEN timestamp END) OVER whole AS last_ts,
+                    sum(is_valid) OVER whole AS valid_count,
+                    group_concat(CASE WHEN is_valid THEN substr(timestamp, 12, 5) END) OVER whole AS valid_times
+             FROM classified
+             WINDOW whole AS (
+                 PARTITION BY user_id, day ORDER BY timestamp
+                 ROWS BETWEEN UNBOUNDED PRECEDING AND UNBOUNDED FOLLOWING
+             )
+         ),
+         measured AS (
+             SELECT user_id, day, start_dm, end_dm, valid_count, valid_times,
+                    CASE WHEN valid_count >= 2 OR (valid_count = 1 AND {first_dm} < split_dm)
+                         THEN substr(first_ts, 12, 5) END AS check_in_time,
+                    CASE WHEN valid_count >= 2 OR (valid_count = 1 AND {first_dm} >= split_dm)
+                         THEN substr(last_ts, 12, 5) END AS check_out_time,
+                    CAST(strftime('%w', day) AS INTEGER) IN (SELECT value FROM json_each(?12)) AS is_workday,
+                    {is_holiday} AS is_holiday
+             FROM days
+             WHERE rn = 1
+         ),
+         scored AS (
+             SELECT *,
+                    CASE WHEN check_in_time IS NULL OR {hours_only} THEN 0
+                         ELSE max(0, {in_dm} - (start_dm + ?6)) END AS late_minutes,
+                    CASE WHEN check_out_time IS NULL OR {hours_only} THEN 0
+                         ELSE max(0, (end_dm - ?7) - {out_dm}) END AS early_minutes
+             FROM measured
+         )
+         SELECT user_id, day AS date, check_in_time, check_out_time,
+                valid_count = 1 AS is_incomplete, late_minutes, early_minutes,
+                CASE WHEN is_holiday THEN 'holiday'
+                     WHEN NOT is_workday THEN 'weekend'
+                     WHEN valid_count = 0 THEN 'absent'
+                     WHEN valid_count = 1 THEN 'incomplete'
+                     WHEN late_minutes > 0 THEN 'late'
+                     WHEN early_minutes > 0 THEN 'early_leave'
+                     ELSE 'present' END AS status,
+                CASE WHEN valid_count > 2 THEN '[\"multiple_punches\"]'
+                     WHEN valid_count = 1 AND check_in_time IS NOT NULL THEN '[\"single_punch_checkin\"]'
+                     WHEN valid_count = 1 THEN '[\"single_punch_checkout\"]'
+                     ELSE '[]' END AS flags,
+                valid_times, is_workday, is_holiday
+         FROM scored",
+        day = rules::working_date_sql("l.timestamp", cutoff_hour),
+        not_voided = logs::not_voided_sql("l.id"),
+        punch_dm = day_minutes_sql("substr(l.timestamp, 12, 5)"),
+        start_dm = day_minutes_sql("COALESCE(r.start_time, s.start_time, ?4)"),
+        end_dm = day_minutes_sql("COALESCE(r.end_time, s.end_time, ?5)"),
+        in_start = day_minutes_sql("?8"),
+        in_end = day_minutes_sql("?9"),
+        out_start = day_minutes_sql("?10"),
+        out_end = day_minutes_sql("?11"),
+        first_dm = day_minutes_sql("substr(first_ts, 12, 5)"),
+        in_dm = day_minutes_sql("check_in_time"),
+        out_dm = day_minutes_sql("check_out_time"),
+        is_holiday = holidays::applies_sql("user_id", "day"),
+        hours_only = users::hours_only_sql("user_id"),
+    )
+}
+
+fn drop_temp_tables(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "DROP TABLE IF EXISTS temp.recompute_days;
+         DROP TABLE IF EXISTS temp.recompute_matches;",
+    )
+    .map_err(|e| format!("Failed to clear recompute tables: {}", e))
+}
+
+/// Match every device user ID punching in the range, counting the punches
+/// that match no one
+fn match_users(conn: &Connection, start: &str, end: &str, result: &mut RecomputeResult) -> Result<(), String> {
+    let matcher = UserMatcher::load(conn)?;
+    conn.execute_batch(
+        "CREATE TEMP TABLE recompute_matches (device_user_id TEXT PRIMARY KEY, user_id TEXT NOT NULL)",
+    )
+    .map_err(|e| format!("Failed to create match table: {}", e))?;
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT device_user_id, COUNT(*) FROM attendance_logs_raw
+             WHERE timestamp >= ?1 AND timestamp < ?2 AND {}
+             GROUP BY device_user_id",
+            logs::not_voided_sql("id")
+        ))
+        .map_err(|e| format!("Failed to query punching users: {}", e))?;
+    let punching: Vec<(String, u32)> = stmt
+        .query_map(params![start, end], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| format!("Failed to query punching users: {}", e))?
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read punching users: {}", e))?;
+
+    let mut insert = conn
+        .prepare("INSERT INTO temp.recompute_matches (device_user_id, user_id) VALUES (?1, ?2)")
+        .map_err(|e| format!("Failed to prepare user matches: {}", e))?;
+    for (device_user_id, count) in punching {
+        result.logs_processed += count;
+        match matcher.resolve(&device_user_id) {
+            Some(user_id) => {
+                insert
+                    .execute(params![device_user_id, user_id])
+                    .map_err(|e| format!("Failed to save user match: {}", e))?;
+            }
+            None => result.unmatched_logs += count,
+        }
+    }
+    Ok(())
+}
+
+/// A day from temp.recompute_days with what the custom rules script needs
+struct ComputedDay {
+    summary: DaySummary,
+    punch_times: Vec<String>,
+    is_workday: bool,
+    is_holiday: bool,
+}
+
+fn computed_days(conn: &Connection) -> Result<Vec<ComputedDay>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT user_id, date, check_in_time, check_out_time, is_incomplete, late_minutes,
+                    early_minutes, status, flags, valid_times, is_workday, is_holiday
+             FROM temp.recompute_days ORDER BY user_id, date",
+        )
+        .map_err(|e| format!("Failed to read computed days: {}", e))?;
+    let rows = stmt
+        .query_map([], |row| {
+            let flags: String = row.get(8)?;
+            let valid_times: Option<String> = row.get(9)?;
+            let summary = DaySummary {
+                user_id: row.get(0)?,
+                date: row.get(1)?,
+                check_in_time: row.get(2)?,
+                check_out_time: row.get(3)?,
+                is_incomplete: row.get(4)?,
+                late_minutes: row.get(5)?,
+                early_minutes: row.get(6)?,
+                status: row.get(7)?,
+                flags: serde_json::from_str(&flags).unwrap_or_default(),
+            };
+            let punch_times = valid_times
+                .map(|t| t.split(',').map(str::to_string).collect())
+                .unwrap_or_default();
+            Ok(ComputedDay {
+                summary,
+                punch_times,
+                is_workday: row.get(10)?,
+                is_holiday: row.get(11)?,
+            })
+        })
+        .map_err(|e| format!("Failed to read computed days: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read computed days: {}", e))
+}
+
+/// Write temp.recompute_days into attendance_day_summary in one statement
+fn store_days(conn: &mut Connection, source: &ChangeSource) -> Result<(), String> {
+    let now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+    let tx = conn
+        .savepoint()
+        .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+    tx.execute(
+        &format!(
+            "INSERT INTO attendance_day_summary
+             (id, user_id, date, check_in_time, check_out_time, is_incomplete,
+              late_minutes, early_minutes, status, flags, created_at, updated_at,
+              change_reason, changed_by)
+             SELECT {}, user_id, date, check_in_time, check_out_time, is_incomplete,
+                    late_minutes, early_minutes, status, flags, ?1, ?1, ?2, ?3
+             FROM temp.recompute_days WHERE true
+             ON CONFLICT(user_id, date) DO UPDATE SET
+               check_in_time = excluded.check_in_time,
+               check_out_time = excluded.check_out_time,
+               is_incomplete = excluded.is_incomplete,
+               late_minutes = excluded.late_minutes,
+               early_minutes = excluded.early_minutes,
+               status = excluded.status,
+               flags = excluded.flags,
+               updated_at = excluded.updated_at,
+               change_reason = excluded.change_reason,
+               changed_by = excluded.changed_by",
+            UUID_SQL
+        ),
+        params![now, source.reason, source.actor],
+    )
+    .map_err(|e| format!("Failed to save summaries: {}", e))?;
+    tx.commit()
+        .map_err(|e| format!("Failed to commit summaries: {}", e))
+}
+
+/// Recompute summaries for every user with punches between two dates
+/// (inclusive) in one SQL pass
+pub fn recompute(
+    conn: &mut Connection,
+    start_date: &str,
+    end_date: &str,
+    source: &ChangeSource,
+) -> Result<RecomputeResult, String> {
+    let attendance_rules = engine::load_rules(conn)?;
+    let custom_rules = engine::load_custom_rules(conn)?;
+    let cutoff_hour = attendance_rules.day_cutoff_hour;
+    let (start, end) = logs::working_day_bounds(start_date, end_date, cutoff_hour);
+
+    let mut result = RecomputeResult {
+        days_processed: 0,
+        logs_processed: 0,
+        unmatched_logs: 0,
+        rule_errors: Vec::new(),
+    };
+    drop_temp_tables(conn)?;
+    match_users(conn, &start, &end, &mut result)?;
+
+    let workdays = serde_json::to_string(&attendance_rules.workdays).unwrap_or_else(|_| "[]".to_string());
+    conn.execute(
+        &compute_sql(cutoff_hour),
+        params![
+            start,
+            end,
+            cutoff_hour,
+            attendance_rules.work_start_time,
+            attendance_rules.work_end_time,
+            attendance_rules.late_grace_period,
+            attendance_rules.early_leave_grace_period,
+            attendance_rules.check_in_window_start,
+            attendance_rules.check_in_window_end,
+            attendance_rules.check_out_window_start,
+            attendance_rules.check_out_window_end,
+            workdays,
+        ],
+    )
+    .map_err(|e| format!("Failed to compute summaries: {}", e))?;
+    result.days_processed = conn
+        .query_row("SELECT COUNT(*) FROM temp.recompute_days", [], |row| row.get(0))
+        .map_err(|e| format!("Failed to count computed days: {}", e))?;
+
+    match &custom_rules {
+        Some(custom) => {
+            let computed: Vec<DaySummary> = computed_days(conn)?
+                .into_iter()
+                .map(|day| {
+                    let facts = DayFacts {
+                        punch_times: &day.punch_times,
+                        is_holiday: day.is_holiday,
+                        is_workday: day.is_workday,
+                    };
+                    engine::customise(custom, day.summary, &facts, &mut result.rule_errors)
+                })
+                .collect();
+            summaries::upsert(conn, &computed, source)?;
+        }
+        None => store_days(conn, source)?,
+    }
+    drop_temp_tables(conn)?;
+
+    log::info!(
+        "[summary::set_based] Recomputed {} days from {} logs ({} unmatched, {} rule errors)",
+        result.days_processed,
+        result.logs_processed,
+        result.unmatched_logs,
+        result.rule_errors.len()
+    );
+    Ok(result)
+}
@@ -0,0 +1,151 @@
+//! Summary engine data types for Tauri command serialization
+
+use serde::{Deserialize, Serialize};
+
+/// Attendance rules as stored under the `attendance` settings key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttendanceRules {
+    pub work_start_time: String,
+    pub work_end_time: String,
+    pub late_grace_period: i64,
+    pub early_leave_grace_period: i64,
+    pub check_in_window_start: String,
+    pub check_in_window_end: String,
+    pub check_out_window_start: String,
+    pub check_out_window_end: String,
+    /// 0 = Sunday, 1 = Monday, ...
+    pub workdays: Vec<u32>,
+    /// Hour the working day starts at; punches before it count toward the
+    /// previous day (0 = calendar midnight)
+    #[serde(default)]
+    pub day_cutoff_hour: u32,
+}
+
+impl Default for AttendanceRules {
+    /// Same defaults as DEFAULT_ATTENDANCE_RULES in the settings repository
+    fn default() -> Self {
+        Self {
+            work_start_time: "09:00".to_string(),
+            work_end_time: "18:00".to_string(),
+            late_grace_period: 15,
+            early_leave_grace_period: 15,
+            check_in_window_start: "06:00".to_string(),
+            check_in_window_end: "12:00".to_string(),
+            check_out_window_start: "12:00".to_string(),
+            check_out_window_end: "23:00".to_string(),
+            workdays: vec![1, 2, 3, 4, 5],
+            day_cutoff_hour: 0,
+        }
+    }
+}
+
+/// A computed daily summary (one attendance_day_summary row)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DaySummary {
+    pub user_id: String,
+    pub date: String,
+    pub check_in_time: Option<String>,
+    pub check_out_time: Option<String>,
+    pub is_incomplete: bool,
+    pub late_minutes: i64,
+    pub early_minutes: i64,
+    pub status: String,
+    pub flags: Vec<String>,
+}
+
+/// Result of recomputing summaries for a date range
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecomputeResult {
+    pub days_processed: u32,
+    pub logs_processed: u32,
+    pub unmatched_logs: u32,
+    /// Days where the custom rules script failed (the base summary was kept)
+    pub rule_errors: Vec<String>,
+}
+
+/// How summaries are recomputed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SummaryEngine {
+    /// One SQL pass over the range with window functions
+    #[default]
+    SetBased,
+    /// Punches loaded and processed day by day in Rust
+    RowByRow,
+}
+
+/// Timings of one engine over a benchmark's runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EngineTiming {
+    pub engine: SummaryEngine,
+    pub run_ms: Vec<u64>,
+    pub best_ms: u64,
+    pub days_processed: u32,
+    pub logs_processed: u32,
+}
+
+/// Both engines timed on the same range, with any days they disagree on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecomputeBenchmark {
+    pub start_date: String,
+    pub end_date: String,
+    pub runs: u32,
+    pub set_based: EngineTiming,
+    pub row_by_row: EngineTiming,
+    /// Row-by-row best time over set-based best time
+    pub speedup: f64,
+    pub differing_days: u32,
+    /// "user date: field" for the first differences found
+    pub differences: Vec<String>,
+}
+
+/// Result of validating a custom rules script
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleValidationResult {
+    pub valid: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+}
+
+/// Why summaries are being written and by whom, kept in summary_history
+#[derive(Debug, Clone, Copy)]
+pub struct ChangeSource<'a> {
+    /// e.g. "recompute", "sync", "manual"
+    pub reason: &'a str,
+    pub actor: Option<&'a str>,
+}
+
+/// Previous (and, for updates, new) values of a summary that changed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SummaryChange {
+    pub id: i64,
+    pub summary_id: String,
+    pub user_id: String,
+    pub display_name: Option<String>,
+    pub date: String,
+    pub check_in_time: Option<String>,
+    pub check_out_time: Option<String>,
+    pub is_incomplete: bool,
+    pub late_minutes: i64,
+    pub early_minutes: i64,
+    pub status: String,
+    pub flags: Option<String>,
+    /// None when the summary was deleted
+    pub new_check_in_time: Option<String>,
+    pub new_check_out_time: Option<String>,
+    pub new_status: Option<String>,
+    pub new_late_minutes: Option<i64>,
+    pub new_early_minutes: Option<i64>,
+    pub reason: String,
+    pub actor: Option<String>,
+    pub changed_at: String,
+}
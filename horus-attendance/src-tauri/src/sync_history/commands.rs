@@ -0,0 +1,72 @@
+//! Tauri command handlers for sync history.
+
+use std::fs;
+use std::path::PathBuf;
+
+use super::store;
+use super::types::*;
+use crate::db;
+use crate::export::commands::get_export_dir;
+use crate::export::types::ExportedFile;
+use crate::signing;
+
+/// Syncs listed unless asked otherwise
+const DEFAULT_LIMIT: u32 = 50;
+
+const MAX_LIMIT: u32 = 1000;
+
+/// Record a finished sync's structured result. Returns the entry's id, or
+/// None when the device isn't saved.
+#[tauri::command]
+pub async fn record_sync(app: tauri::AppHandle, report: SyncReport) -> Result<Option<i64>, String> {
+    store::record(&*db::open(&app)?, report)
+}
+
+/// Recorded syncs, newest first, optionally of one device
+#[tauri::command]
+pub async fn list_sync_history(
+    app: tauri::AppHandle,
+    device_id: Option<String>,
+    limit: Option<u32>,
+) -> Result<Vec<SyncHistoryEntry>, String> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    store::list(&*db::open(&app)?, device_id.as_deref(), limit)
+}
+
+/// Export a recorded sync as JSON, with the device's details and latest
+/// connection attempts, for attaching to a support ticket.
+/// `destination` is a directory; defaults to Documents/HorusAttendance/exports.
+#[tauri::command]
+pub async fn export_sync_report(
+    app: tauri::AppHandle,
+    sync_id: i64,
+    destination: Option<String>,
+) -> Result<ExportedFile, String> {
+    log::info!("[sync_history::cmd] export_sync_report {}", sync_id);
+    let dir = match destination {
+        Some(dest) => PathBuf::from(dest),
+        None => get_export_dir(&app)?,
+    };
+    let path = crate::path_policy::resolve_write_target(
+        &app,
+        &dir.join(format!("sync_report_{}.json", sync_id)).to_string_lossy(),
+    )?;
+    let app_version = app.package_info().version.to_string();
+
+    let conn = db::open(&app)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let report = store::report(&conn, sync_id, &app_version)?.ok_or_else(|| format!("Sync not found: {}", sync_id))?;
+        let json =
+            serde_json::to_string_pretty(&report).map_err(|e| format!("Failed to serialize sync report: {}", e))?;
+        fs::write(&path, json).map_err(|e| format!("Failed to write sync report: {}", e))?;
+        let file = ExportedFile {
+            path: path.to_string_lossy().to_string(),
+            rows: 1,
+            file_size: fs::metadata(&path).map(|m| m.len()).unwrap_or(0),
+        };
+        signing::store::sign_exported(&conn, std::slice::from_ref(&file), "sync_report")?;
+        Ok(file)
+    })
+    .await
+    .map_err(|e| format!("Export task failed: {}", e))?
+}
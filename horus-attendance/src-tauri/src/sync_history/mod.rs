@@ -0,0 +1,12 @@
+//! Sync history
+//!
+//! Each device sync ends by recording its structured result: what was
+//! fetched and stored, which transport it went over, how long every phase
+//! took, and its warnings and errors (truncated, so one runaway message
+//! can't bloat the table). A report can be exported as JSON with the
+//! device's details and recent connection attempts, which is what a
+//! "the sync failed" ticket needs attached.
+
+pub mod commands;
+pub mod store;
+pub mod types;
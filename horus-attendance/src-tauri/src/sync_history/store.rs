@@ -0,0 +1,142 @@
+//! Recorded syncs and the reports exported from them
+
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+use super::types::*;
+
+/// Syncs kept per device; older ones are pruned as new ones come in
+const KEEP_PER_DEVICE: u32 = 200;
+
+/// Longest warning or error kept, in characters
+const MAX_MESSAGE_CHARS: usize = 500;
+
+/// Warnings or errors kept per sync
+const MAX_MESSAGES: usize = 50;
+
+/// Connection attempts included in an exported report
+const REPORT_ATTEMPTS: u32 = 20;
+
+/// Cut messages down to what is worth keeping, noting what was left out
+fn truncate_messages(messages: &mut Vec<String>) {
+    let dropped = messages.len().saturating_sub(MAX_MESSAGES);
+    messages.truncate(MAX_MESSAGES);
+    for message in messages.iter_mut() {
+        if message.chars().count() > MAX_MESSAGE_CHARS {
+            *message = format!("{}…", message.chars().take(MAX_MESSAGE_CHARS).collect::<String>());
+        }
+    }
+    if dropped > 0 {
+        messages.push(format!("…and {} more", dropped));
+    }
+}
+
+/// Record a sync of a saved device. Returns the new entry's id, or None
+/// for a device that isn't saved.
+pub fn record(conn: &Connection, mut report: SyncReport) -> Result<Option<i64>, String> {
+    truncate_messages(&mut report.warnings);
+    truncate_messages(&mut report.errors);
+    let json = serde_json::to_string(&report).map_err(|e| format!("Failed to serialize sync report: {}", e))?;
+    let inserted = conn
+        .execute(
+            "INSERT INTO sync_history (device_id, started_at, finished_at, success, report)
+             SELECT ?1, ?2, ?3, ?4, ?5 WHERE EXISTS (SELECT 1 FROM devices WHERE id = ?1)",
+            params![report.device_id, report.started_at, report.finished_at, report.success, json],
+        )
+        .map_err(|e| format!("Failed to record sync: {}", e))?;
+    if inserted == 0 {
+        return Ok(None);
+    }
+    let id = conn.last_insert_rowid();
+    conn.execute(
+        "DELETE FROM sync_history
+         WHERE device_id = ?1 AND id <= (SELECT id FROM sync_history WHERE device_id = ?1
+                                         ORDER BY id DESC LIMIT 1 OFFSET ?2)",
+        params![report.device_id, KEEP_PER_DEVICE],
+    )
+    .map_err(|e| format!("Failed to prune sync history: {}", e))?;
+    Ok(Some(id))
+}
+
+const ENTRY_COLUMNS: &str = "h.id, h.device_id, d.name, h.started_at, h.finished_at, h.success, h.report";
+
+fn map_entry(row: &Row) -> rusqlite::Result<SyncHistoryEntry> {
+    let report: String = row.get(6)?;
+    let report = serde_json::from_str(&report)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(6, rusqlite::types::Type::Text, Box::new(e)))?;
+    Ok(SyncHistoryEntry {
+        id: row.get(0)?,
+        device_id: row.get(1)?,
+        device_name: row.get(2)?,
+        started_at: row.get(3)?,
+        finished_at: row.get(4)?,
+        success: row.get(5)?,
+        report,
+    })
+}
+
+/// Recorded syncs, newest first, optionally of one device
+pub fn list(conn: &Connection, device_id: Option<&str>, limit: u32) -> Result<Vec<SyncHistoryEntry>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM sync_history h JOIN devices d ON d.id = h.device_id
+             WHERE ?1 IS NULL OR h.device_id = ?1
+             ORDER BY h.id DESC LIMIT ?2",
+            ENTRY_COLUMNS
+        ))
+        .map_err(|e| format!("Failed to query sync history: {}", e))?;
+    let rows = stmt
+        .query_map(params![device_id, limit], map_entry)
+        .map_err(|e| format!("Failed to query sync history: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read sync history: {}", e))
+}
+
+/// One recorded sync
+pub fn get(conn: &Connection, sync_id: i64) -> Result<Option<SyncHistoryEntry>, String> {
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM sync_history h JOIN devices d ON d.id = h.device_id WHERE h.id = ?1",
+            ENTRY_COLUMNS
+        ),
+        [sync_id],
+        map_entry,
+    )
+    .optional()
+    .map_err(|e| format!("Failed to load sync: {}", e))
+}
+
+/// A recorded sync with the device's details and latest connection
+/// attempts, or None for an unknown sync
+pub fn report(conn: &Connection, sync_id: i64, app_version: &str) -> Result<Option<SyncReportExport>, String> {
+    let Some(sync) = get(conn, sync_id)? else {
+        return Ok(None);
+    };
+    let device = conn
+        .query_row(
+            "SELECT name, ip, port, model, serial_number, firmware, last_sync_at, last_error, last_error_at
+             FROM devices WHERE id = ?1",
+            [&sync.device_id],
+            |row| {
+                Ok(SyncReportDevice {
+                    name: row.get(0)?,
+                    ip: row.get(1)?,
+                    port: row.get(2)?,
+                    model: row.get(3)?,
+                    serial_number: row.get(4)?,
+                    firmware: row.get(5)?,
+                    last_sync_at: row.get(6)?,
+                    last_error: row.get(7)?,
+                    last_error_at: row.get(8)?,
+                })
+            },
+        )
+        .map_err(|e| format!("Failed to load device: {}", e))?;
+    let recent_attempts = crate::diagnostics::store::latest(conn, &sync.device_id, REPORT_ATTEMPTS)?;
+    Ok(Some(SyncReportExport {
+        app_version: app_version.to_string(),
+        exported_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        sync,
+        device,
+        recent_attempts,
+    }))
+}
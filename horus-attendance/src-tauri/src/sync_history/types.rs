@@ -0,0 +1,97 @@
+//! Sync history data types for Tauri command serialization
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::diagnostics::types::DeviceAttempt;
+
+/// Record counts of a sync
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SyncCounts {
+    pub users_fetched: u32,
+    /// Logs read from the device, before the date filter
+    pub logs_fetched: u32,
+    /// Logs left after the date filter
+    pub logs_in_range: u32,
+    pub users_added: u32,
+    pub users_synced: u32,
+    pub logs_added: u32,
+    pub logs_deduplicated: u32,
+    pub quarantined: u32,
+    pub summary_dates: u32,
+}
+
+/// The structured result of one sync, as the sync engine reports it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncReport {
+    pub device_id: String,
+    pub started_at: String,
+    pub finished_at: String,
+    pub success: bool,
+    #[serde(default)]
+    pub dry_run: bool,
+    #[serde(default)]
+    pub scheduled: bool,
+    /// "latest", "days" or "range"
+    #[serde(default)]
+    pub mode: Option<String>,
+    /// Connection the fetch went over: "tcp", "udp" or "demo"
+    #[serde(default)]
+    pub transport: Option<String>,
+    /// Whether users and logs had to be fetched separately after the
+    /// combined fetch failed
+    #[serde(default)]
+    pub fallback: bool,
+    #[serde(default)]
+    pub counts: SyncCounts,
+    /// Milliseconds per phase ("fetch", "users", "logs", "summaries", ...)
+    #[serde(default)]
+    pub durations_ms: BTreeMap<String, u64>,
+    #[serde(default)]
+    pub warnings: Vec<String>,
+    #[serde(default)]
+    pub errors: Vec<String>,
+}
+
+/// A recorded sync
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncHistoryEntry {
+    pub id: i64,
+    pub device_id: String,
+    pub device_name: String,
+    pub started_at: String,
+    pub finished_at: String,
+    pub success: bool,
+    pub report: SyncReport,
+}
+
+/// The device a sync report is about, as it is now
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncReportDevice {
+    pub name: String,
+    pub ip: String,
+    pub port: u16,
+    pub model: Option<String>,
+    pub serial_number: Option<String>,
+    pub firmware: Option<String>,
+    pub last_sync_at: Option<String>,
+    pub last_error: Option<String>,
+    pub last_error_at: Option<String>,
+}
+
+/// Contents of an exported sync report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncReportExport {
+    pub app_version: String,
+    pub exported_at: String,
+    pub sync: SyncHistoryEntry,
+    pub device: SyncReportDevice,
+    /// The device's latest connection attempts, newest first
+    pub recent_attempts: Vec<DeviceAttempt>,
+}
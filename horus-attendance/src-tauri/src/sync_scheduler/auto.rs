@@ -0,0 +1,286 @@
+//! Automatic syncs of devices set to `auto`
+//!
+//! Every `autoSyncMinutes` each device with `sync_mode = 'auto'` is synced
+//! in queue order, outside its sync windows excepted. Syncs go through the
+//! gate like manual ones, so the concurrency and stagger limits hold. Logs
+//! are read from the device's sync offset on where the firmware allows it
+//! (see `zkteco::offsets`), and only those newer than the device's latest
+//! stored punch are kept; they are
+//! screened and stored, the days they fall on are summarized again, and the
+//! device's `last_sync_at` and sync history are updated. Users aren't
+//! synced here; that stays with manual syncs.
+
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use rusqlite::params;
+
+use super::gate as sync_gate;
+use super::store;
+use super::types::{AutoSyncDevice, AutoSyncRun, QueuedDevice, SyncSchedulerSettings};
+use crate::activity::registry;
+use crate::db::{self, logs::DevicePunch};
+use crate::diagnostics::store as diagnostics;
+use crate::greeter;
+use crate::jobs;
+use crate::summary::{engine, types::ChangeSource};
+use crate::sync_history::types::{SyncCounts, SyncReport};
+use crate::zkteco::client::ZKClient;
+use crate::zkteco::capabilities::{self, FEATURE_PAGED_ATTENDANCE};
+use crate::zkteco::clock::DeviceClock;
+use crate::zkteco::commands::{resolve_comm_key, saved_config, validate_config};
+use crate::zkteco::types::{AttendanceLog, DeviceLogOffset};
+use crate::zkteco::{offsets, sync_window};
+
+/// ID of the automatic sync job in the jobs registry
+pub const JOB_ID: &str = "auto_sync";
+
+pub const STATUS_SYNCED: &str = "synced";
+pub const STATUS_SKIPPED: &str = "skipped";
+pub const STATUS_FAILED: &str = "failed";
+
+/// Longest and shortest interval accepted for the setting
+pub const MIN_INTERVAL_MINUTES: u32 = 1;
+pub const MAX_INTERVAL_MINUTES: u32 = 24 * 60;
+
+fn now() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+}
+
+fn interval(minutes: u32) -> Duration {
+    Duration::from_secs(minutes.clamp(MIN_INTERVAL_MINUTES, MAX_INTERVAL_MINUTES) as u64 * 60)
+}
+
+/// What storing a device's logs came to
+struct Stored {
+    quarantined: u32,
+    inserted: u32,
+    summary_dates: u32,
+    durations_ms: BTreeMap<String, u64>,
+}
+
+/// Screen and store the logs, summarize their days again if any were new and
+/// mark the device synced up to `offset`. Punches already stored are left
+/// out by the insert, so `inserted` counts the new ones.
+fn store_logs(
+    conn: &mut rusqlite::Connection,
+    device_id: &str,
+    mut logs: Vec<AttendanceLog>,
+    offset: Option<DeviceLogOffset>,
+) -> Result<Stored, String> {
+    let started = Instant::now();
+    let quarantined = crate::quarantine::store::screen_logs(conn, Some(device_id), &mut logs, true)?;
+    let punches: Vec<DevicePunch> = logs
+        .iter()
+        .map(|log| DevicePunch {
+            device_user_id: &log.device_user_id,
+            timestamp: &log.timestamp,
+            verify_type: log.verify_type,
+            punch_type: log.punch_type,
+        })
+        .collect();
+    let inserted = db::logs::insert_from_device(conn, device_id, &punches)? as u32;
+    crate::payloads::store::retain(conn, Some(device_id), &logs)?;
+    let mut durations_ms = BTreeMap::from([("logs".to_string(), started.elapsed().as_millis() as u64)]);
+
+    let mut summary_dates = 0;
+    let first = logs.iter().map(|l| l.timestamp.as_str()).min();
+    let last = logs.iter().map(|l| l.timestamp.as_str()).max();
+    if let (true, Some(first), Some(last)) = (inserted > 0, first, last) {
+        let started = Instant::now();
+        // A punch after midnight can belong to the previous working day
+        let start = chrono::NaiveDate::parse_from_str(first.get(..10).unwrap_or_default(), "%Y-%m-%d")
+            .map(|d| (d - chrono::Duration::days(1)).format("%Y-%m-%d").to_string())
+            .unwrap_or_else(|_| first.chars().take(10).collect());
+        let end: String = last.chars().take(10).collect();
+        let source = ChangeSource {
+            reason: "sync",
+            actor: None,
+        };
+        summary_dates = engine::recompute(conn, &start, &end, &source)?.days_processed;
+        durations_ms.insert("summaries".to_string(), started.elapsed().as_millis() as u64);
+    }
+
+    conn.execute(
+        "UPDATE devices SET last_sync_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now'), updated_at = datetime('now')
+         WHERE id = ?1",
+        params![device_id],
+    )
+    .map_err(|e| format!("Failed to update last sync time: {}", e))?;
+    if let Some(offset) = &offset {
+        offsets::save(conn, device_id, offset)?;
+    }
+    Ok(Stored {
+        quarantined,
+        inserted,
+        summary_dates,
+        durations_ms,
+    })
+}
+
+/// Read and store one device's new logs
+async fn sync_device(app: &tauri::AppHandle, device: &QueuedDevice, report: &mut SyncReport) -> Result<AutoSyncDevice, String> {
+    let mut result = AutoSyncDevice {
+        device_id: device.device_id.clone(),
+        device_name: device.name.clone(),
+        status: STATUS_SYNCED.to_string(),
+        fetched: 0,
+        inserted: 0,
+        quarantined: 0,
+        message: None,
+    };
+    let (config, clock) = {
+        let conn = db::open(app)?;
+        let window = sync_window::check(&conn, &device.device_id, true, chrono::Local::now().naive_local())?;
+        if !window.allowed {
+            result.status = STATUS_SKIPPED.to_string();
+            result.message = window.message;
+            return Ok(result);
+        }
+        (saved_config(&conn, &device.device_id)?, DeviceClock::load(&conn, &device.device_id)?)
+    };
+    let incremental = {
+        let conn = db::open(app)?;
+        capabilities::require(&conn, Some(&device.device_id), FEATURE_PAGED_ATTENDANCE)
+            .is_ok()
+            .then(|| offsets::load(&conn, &device.device_id))
+            .transpose()?
+    };
+    validate_config(&config)?;
+    let config = resolve_comm_key(config).await?;
+    let _slot = sync_gate::enter(app, &config).await?;
+    let _activity = registry::begin(app, "Automatic device sync")?;
+
+    let started = Instant::now();
+    let fetched = async {
+        let mut client = ZKClient::connect(&config).await?;
+        let transport = client.transport_name().map(str::to_string);
+        let logs = match &incremental {
+            Some(offset) => offsets::read_logs(&mut client, offset.as_ref())
+                .await
+                .map(|(logs, read)| (logs, Some(read.offset))),
+            None => client.get_attendance_logs(None).await.map(|logs| (logs, None)),
+        };
+        let _ = client.disconnect().await;
+        logs.map(|(logs, offset)| (logs, offset, transport))
+    }
+    .await;
+    let fetch_ms = started.elapsed().as_millis() as u64;
+    diagnostics::remember(app, Some(&device.device_id), diagnostics::OP_SYNC, fetch_ms, fetched.as_ref().err().map(String::as_str));
+    report.durations_ms.insert("fetch".to_string(), fetch_ms);
+    let (mut logs, offset, transport) = fetched?;
+    report.transport = transport;
+    clock.normalize(&mut logs);
+    result.fetched = logs.len() as u32;
+
+    let mut conn = db::open(app)?;
+    let device_id = device.device_id.clone();
+    let stored = tauri::async_runtime::spawn_blocking(move || store_logs(&mut conn, &device_id, logs, offset))
+        .await
+        .map_err(|e| format!("Sync task failed: {}", e))??;
+    result.inserted = stored.inserted;
+    result.quarantined = stored.quarantined;
+    report.counts = SyncCounts {
+        logs_fetched: result.fetched,
+        logs_in_range: result.fetched,
+        logs_added: stored.inserted,
+        logs_deduplicated: result.fetched.saturating_sub(stored.quarantined + stored.inserted),
+        quarantined: stored.quarantined,
+        summary_dates: stored.summary_dates,
+        ..SyncCounts::default()
+    };
+    report.durations_ms.extend(stored.durations_ms);
+    Ok(result)
+}
+
+/// Sync every auto device once, one after another in queue order
+async fn sync_all(app: &tauri::AppHandle) -> Result<AutoSyncRun, String> {
+    let devices = store::queue(&*db::open(app)?, true)?;
+    let mut run = AutoSyncRun {
+        started_at: now(),
+        devices: Vec::new(),
+    };
+    for device in &devices {
+        let mut report = SyncReport {
+            device_id: device.device_id.clone(),
+            started_at: now(),
+            finished_at: String::new(),
+            success: false,
+            dry_run: false,
+            scheduled: true,
+            mode: Some("latest".to_string()),
+            transport: None,
+            fallback: false,
+            counts: SyncCounts::default(),
+            durations_ms: BTreeMap::new(),
+            warnings: Vec::new(),
+            errors: Vec::new(),
+        };
+        let outcome = sync_device(app, device, &mut report).await;
+        let result = outcome.unwrap_or_else(|e| {
+            log::warn!("[sync_scheduler::auto] Sync of {} failed: {}", device.name, e);
+            AutoSyncDevice {
+                device_id: device.device_id.clone(),
+                device_name: device.name.clone(),
+                status: STATUS_FAILED.to_string(),
+                fetched: 0,
+                        inserted: 0,
+                quarantined: 0,
+                message: Some(e),
+            }
+        });
+        if result.status != STATUS_SKIPPED {
+            report.finished_at = now();
+            report.success = result.status == STATUS_SYNCED;
+            report.errors.extend(result.message.clone().filter(|_| !report.success));
+            if let Err(e) = crate::sync_history::store::record(&*db::open(app)?, report) {
+                log::warn!("[sync_scheduler::auto] Failed to record sync of {}: {}", device.name, e);
+            }
+        }
+        run.devices.push(result);
+    }
+    if run.devices.iter().any(|d| d.inserted > 0) {
+        if let Err(e) = greeter::feed::publish(app) {
+            log::warn!("[sync_scheduler::auto] Failed to publish recent punches: {}", e);
+        }
+    }
+    Ok(run)
+}
+
+/// Sync the auto devices on the interval in the `syncScheduler` setting.
+/// Runs for the lifetime of the app; a changed interval applies from the
+/// next wait.
+pub async fn run_scheduled(app: tauri::AppHandle) {
+    let minutes = |app: &tauri::AppHandle| {
+        db::open(app)
+            .and_then(|conn| store::load_settings(&conn))
+            .map(|settings| settings.auto_sync_minutes)
+            .unwrap_or_else(|_| SyncSchedulerSettings::default().auto_sync_minutes)
+    };
+    let job = jobs::registry::register(
+        &app,
+        JOB_ID,
+        "Automatic device sync",
+        "Pulls new punches from the devices set to sync automatically",
+        interval(minutes(&app)),
+    );
+    let handle = app.clone();
+    jobs::registry::drive(
+        job.with_interval_from(move || interval(minutes(&handle))),
+        move |_| {
+            let app = app.clone();
+            async move { sync_all(&app).await }
+        },
+        |run| {
+            if !run.devices.is_empty() {
+                log::info!(
+                    "[sync_scheduler::auto] Synced {} of {} auto devices, {} new punches",
+                    run.devices.iter().filter(|d| d.status == STATUS_SYNCED).count(),
+                    run.devices.len(),
+                    run.devices.iter().map(|d| d.inserted).sum::<u32>()
+                );
+            }
+        },
+    )
+    .await;
+}
@@ -0,0 +1,51 @@
+//! Tauri command handlers for sync scheduling.
+
+use tauri::State;
+
+use super::auto;
+use super::gate::SyncGate;
+use super::store::{self, SETTINGS_KEY};
+use super::types::*;
+use crate::db;
+
+#[tauri::command]
+pub async fn get_sync_scheduler_settings(app: tauri::AppHandle) -> Result<SyncSchedulerSettings, String> {
+    store::load_settings(&*db::open(&app)?)
+}
+
+/// Save the settings; syncs waiting for a slot are held to them right away
+#[tauri::command]
+pub async fn save_sync_scheduler_settings(
+    app: tauri::AppHandle,
+    gate: State<'_, SyncGate>,
+    settings: SyncSchedulerSettings,
+) -> Result<(), String> {
+    if !(1..=32).contains(&settings.max_concurrent) {
+        return Err("Concurrent devices must be between 1 and 32".to_string());
+    }
+    if !(1..=32).contains(&settings.per_site_concurrency) {
+        return Err("Concurrent devices per site must be between 1 and 32".to_string());
+    }
+    if settings.stagger_seconds > 600 {
+        return Err("Stagger delay must be at most 600 seconds".to_string());
+    }
+    if !(auto::MIN_INTERVAL_MINUTES..=auto::MAX_INTERVAL_MINUTES).contains(&settings.auto_sync_minutes) {
+        return Err("Automatic sync interval must be between 1 minute and 24 hours".to_string());
+    }
+    let json = serde_json::to_string(&settings).map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    log::info!(
+        "[sync_scheduler::cmd] Up to {} devices ({} per site), {}s apart, auto sync every {} min",
+        settings.max_concurrent,
+        settings.per_site_concurrency,
+        settings.stagger_seconds,
+        settings.auto_sync_minutes
+    );
+    db::set_setting(&*db::open(&app)?, SETTINGS_KEY, &json)?;
+    gate.configure(settings)
+}
+
+/// Saved devices in sync order, optionally only those synced automatically
+#[tauri::command]
+pub async fn get_sync_queue(app: tauri::AppHandle, auto_only: Option<bool>) -> Result<Vec<QueuedDevice>, String> {
+    store::queue(&*db::open(&app)?, auto_only.unwrap_or(false))
+}
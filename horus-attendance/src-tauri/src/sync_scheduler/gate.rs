@@ -0,0 +1,211 @@
+//! The sync gate, held as Tauri managed state
+
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use tauri::Manager;
+use tokio::sync::Notify;
+
+use super::store;
+use super::types::*;
+use crate::db;
+use crate::zkteco::types::DeviceConfig;
+
+struct Ticket {
+    id: u64,
+    device: String,
+    site: Option<String>,
+    priority: i64,
+    /// When it started waiting, then when it started running
+    since: String,
+}
+
+#[derive(Default)]
+struct State {
+    settings: SyncSchedulerSettings,
+    running: Vec<Ticket>,
+    /// In start order: highest priority first, then first come
+    waiting: Vec<Ticket>,
+    next_id: u64,
+    /// Earliest the next sync may start, for the stagger
+    next_start: Option<Instant>,
+}
+
+impl State {
+    fn has_room(&self, site: Option<&str>) -> bool {
+        if self.running.len() >= self.settings.max_concurrent as usize {
+            return false;
+        }
+        match site {
+            Some(site) => {
+                self.running.iter().filter(|t| t.site.as_deref() == Some(site)).count()
+                    < self.settings.per_site_concurrency as usize
+            }
+            None => true,
+        }
+    }
+
+    /// Whether `id` is the first waiting sync with room to start. One whose
+    /// site is full doesn't hold up syncs of other sites behind it.
+    fn may_start(&self, id: u64) -> bool {
+        self.waiting
+            .iter()
+            .find(|t| self.has_room(t.site.as_deref()))
+            .is_some_and(|t| t.id == id)
+    }
+}
+
+#[derive(Default)]
+struct Shared {
+    state: Mutex<State>,
+    changed: Notify,
+}
+
+impl Shared {
+    fn lock(&self) -> Result<MutexGuard<'_, State>, String> {
+        self.state.lock().map_err(|_| "Sync gate is poisoned".to_string())
+    }
+}
+
+fn now() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+}
+
+/// Device syncs running and waiting for a slot
+#[derive(Default, Clone)]
+pub struct SyncGate(Arc<Shared>);
+
+impl SyncGate {
+    /// Apply new limits; syncs waiting for a slot get another look
+    pub fn configure(&self, settings: SyncSchedulerSettings) -> Result<(), String> {
+        let mut state = self.0.lock()?;
+        if state.settings != settings {
+            state.settings = settings;
+            drop(state);
+            self.0.changed.notify_waiters();
+        }
+        Ok(())
+    }
+
+    /// Wait for a slot for a sync of `device`, then for the stagger since
+    /// the previous sync started. The slot is held until the permit drops.
+    pub async fn acquire(&self, device: &str, site: Option<String>, priority: i64) -> Result<SyncPermit, String> {
+        let id = {
+            let mut state = self.0.lock()?;
+            state.next_id += 1;
+            let id = state.next_id;
+            let ticket = Ticket {
+                id,
+                device: device.to_string(),
+                site,
+                priority,
+                since: now(),
+            };
+            let at = state
+                .waiting
+                .iter()
+                .position(|t| t.priority < priority)
+                .unwrap_or(state.waiting.len());
+            state.waiting.insert(at, ticket);
+            id
+        };
+        // Dropping the permit gives up the slot, or the place in line when
+        // the sync is abandoned while waiting
+        let permit = SyncPermit {
+            id,
+            shared: Arc::clone(&self.0),
+        };
+
+        let delay = loop {
+            // Registered before looking, so a slot freed in between isn't missed
+            let notified = self.0.changed.notified();
+            {
+                let mut state = self.0.lock()?;
+                if state.may_start(id) {
+                    if let Some(at) = state.waiting.iter().position(|t| t.id == id) {
+                        let mut ticket = state.waiting.remove(at);
+                        ticket.since = now();
+                        state.running.push(ticket);
+                    }
+                    let current = Instant::now();
+                    let start = state.next_start.map_or(current, |next| next.max(current));
+                    state.next_start = Some(start + Duration::from_secs(u64::from(state.settings.stagger_seconds)));
+                    break start - current;
+                }
+            }
+            log::info!("[sync_scheduler] Sync of {} waiting for a slot", device);
+            notified.await;
+        };
+        // The next in line may be a sync of another site
+        self.0.changed.notify_waiters();
+        if !delay.is_zero() {
+            log::info!("[sync_scheduler] Sync of {} starts in {}ms (stagger)", device, delay.as_millis());
+            tokio::time::sleep(delay).await;
+        }
+        Ok(permit)
+    }
+
+    /// The limits in force and the syncs running and waiting
+    pub fn slots(&self) -> Result<SyncSlots, String> {
+        let state = self.0.lock()?;
+        Ok(SyncSlots {
+            settings: state.settings.clone(),
+            running: state
+                .running
+                .iter()
+                .map(|t| RunningSync {
+                    device: t.device.clone(),
+                    site: t.site.clone(),
+                    priority: t.priority,
+                    started_at: t.since.clone(),
+                })
+                .collect(),
+            waiting: state
+                .waiting
+                .iter()
+                .map(|t| WaitingSync {
+                    device: t.device.clone(),
+                    site: t.site.clone(),
+                    priority: t.priority,
+                    waiting_since: t.since.clone(),
+                })
+                .collect(),
+        })
+    }
+}
+
+/// A sync's slot in the gate, or its place in line
+pub struct SyncPermit {
+    id: u64,
+    shared: Arc<Shared>,
+}
+
+impl Drop for SyncPermit {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.shared.state.lock() {
+            state.running.retain(|t| t.id != self.id);
+            state.waiting.retain(|t| t.id != self.id);
+        }
+        self.shared.changed.notify_waiters();
+    }
+}
+
+/// Wait for a slot to sync the device in `config`, under the current
+/// settings and the device's site and priority
+pub async fn enter(app: &tauri::AppHandle, config: &DeviceConfig) -> Result<SyncPermit, String> {
+    let (settings, qos) = {
+        let conn = db::open(app)?;
+        let qos = match config.device_id.as_deref() {
+            Some(device_id) => store::device_qos(&conn, device_id)?,
+            None => None,
+        };
+        (store::load_settings(&conn)?, qos)
+    };
+    let gate = app.state::<SyncGate>().inner().clone();
+    gate.configure(settings)?;
+    let (device, site, priority) = match qos {
+        Some((name, site, priority)) => (name, site, priority),
+        None => (config.ip.clone(), None, 0),
+    };
+    gate.acquire(&device, site, priority).await
+}
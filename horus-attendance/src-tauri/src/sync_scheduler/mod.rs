@@ -0,0 +1,22 @@
+//! Device sync scheduling: parallelism and priority
+//!
+//! Pulling a full attendance buffer keeps a terminal busy and the network
+//! loaded, so device syncs go through a gate instead of all starting at
+//! once. The `syncScheduler` setting caps how many devices sync at a time,
+//! overall and per site (`devices.site`), and how long to wait between one
+//! sync starting and the next. Syncs waiting for a slot start in order of
+//! the device's `sync_priority`, highest first (the main gate before the
+//! canteen), then in the order they asked.
+//!
+//! The sync queue lists devices in the same order, for whatever syncs them
+//! one after another; the gate's current state shows up in the jobs
+//! registry next to the background jobs.
+//!
+//! Devices set to `auto` are synced in that order by a background job every
+//! `autoSyncMinutes`, through the same gate.
+
+pub mod auto;
+pub mod commands;
+pub mod gate;
+pub mod store;
+pub mod types;
@@ -0,0 +1,53 @@
+//! Sync scheduling settings and the device sync order
+
+use rusqlite::{Connection, OptionalExtension};
+
+use super::types::*;
+use crate::db;
+
+pub const SETTINGS_KEY: &str = "syncScheduler";
+
+/// Load the settings, falling back to defaults (two devices at a time, one
+/// per site, five seconds apart)
+pub fn load_settings(conn: &Connection) -> Result<SyncSchedulerSettings, String> {
+    Ok(db::get_json_setting(conn, SETTINGS_KEY)?.unwrap_or_default())
+}
+
+/// Name, site and sync priority of a saved device
+pub fn device_qos(conn: &Connection, device_id: &str) -> Result<Option<(String, Option<String>, i64)>, String> {
+    conn.query_row(
+        "SELECT name, NULLIF(TRIM(site), ''), sync_priority FROM devices WHERE id = ?1",
+        [device_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )
+    .optional()
+    .map_err(|e| format!("Failed to load device: {}", e))
+}
+
+/// Saved devices in the order they are synced: highest priority first,
+/// then the longest since their last sync. Pseudo-devices (API punches,
+/// corrections) have no address and are left out.
+pub fn queue(conn: &Connection, auto_only: bool) -> Result<Vec<QueuedDevice>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, name, ip, NULLIF(TRIM(site), ''), sync_priority, sync_mode, last_sync_at FROM devices
+             WHERE ip != '' AND (?1 = 0 OR sync_mode = 'auto')
+             ORDER BY sync_priority DESC, last_sync_at IS NOT NULL, last_sync_at, name",
+        )
+        .map_err(|e| format!("Failed to query devices: {}", e))?;
+    let rows = stmt
+        .query_map([auto_only], |row| {
+            Ok(QueuedDevice {
+                device_id: row.get(0)?,
+                name: row.get(1)?,
+                ip: row.get(2)?,
+                site: row.get(3)?,
+                sync_priority: row.get(4)?,
+                sync_mode: row.get(5)?,
+                last_sync_at: row.get(6)?,
+            })
+        })
+        .map_err(|e| format!("Failed to query devices: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read devices: {}", e))
+}
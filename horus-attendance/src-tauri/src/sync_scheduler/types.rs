@@ -0,0 +1,98 @@
+//! Sync scheduling data types for Tauri command serialization
+
+use serde::{Deserialize, Serialize};
+
+/// Sync scheduling settings (`syncScheduler` setting)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SyncSchedulerSettings {
+    /// Devices syncing at the same time
+    pub max_concurrent: u32,
+    /// Devices of one site syncing at the same time; devices without a site
+    /// only count against `max_concurrent`
+    pub per_site_concurrency: u32,
+    /// Least time between one sync starting and the next
+    pub stagger_seconds: u32,
+    /// Minutes between automatic syncs of the devices set to `auto`
+    pub auto_sync_minutes: u32,
+}
+
+impl Default for SyncSchedulerSettings {
+    fn default() -> Self {
+        Self {
+            max_concurrent: 2,
+            per_site_concurrency: 1,
+            stagger_seconds: 5,
+            auto_sync_minutes: 15,
+        }
+    }
+}
+
+/// A saved device in sync order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueuedDevice {
+    pub device_id: String,
+    pub name: String,
+    pub ip: String,
+    pub site: Option<String>,
+    pub sync_priority: i64,
+    /// "auto" or "manual"
+    pub sync_mode: String,
+    pub last_sync_at: Option<String>,
+}
+
+/// A sync holding a slot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunningSync {
+    /// The saved device, or the address of an unsaved one
+    pub device: String,
+    pub site: Option<String>,
+    pub priority: i64,
+    pub started_at: String,
+}
+
+/// A sync waiting for a slot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WaitingSync {
+    pub device: String,
+    pub site: Option<String>,
+    pub priority: i64,
+    pub waiting_since: String,
+}
+
+/// What the sync gate is doing right now
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncSlots {
+    pub settings: SyncSchedulerSettings,
+    pub running: Vec<RunningSync>,
+    /// In the order they will start
+    pub waiting: Vec<WaitingSync>,
+}
+
+/// What an automatic sync did with one device
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutoSyncDevice {
+    pub device_id: String,
+    pub device_name: String,
+    /// "synced", "skipped" (outside its sync windows) or "failed"
+    pub status: String,
+    /// Logs read from the device
+    pub fetched: u32,
+    /// Punches new to the database
+    pub inserted: u32,
+    pub quarantined: u32,
+    pub message: Option<String>,
+}
+
+/// One pass over the devices set to `auto`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutoSyncRun {
+    pub started_at: String,
+    pub devices: Vec<AutoSyncDevice>,
+}
@@ -0,0 +1,54 @@
+//! Tauri command handlers for templates.
+
+use super::render;
+use super::store;
+use super::types::*;
+use crate::db;
+
+/// List templates, optionally filtered by kind
+#[tauri::command]
+pub async fn list_templates(app: tauri::AppHandle, kind: Option<String>) -> Result<Vec<Template>, String> {
+    let conn = db::open(&app)?;
+    store::list(&conn, kind.as_deref())
+}
+
+/// Create or update a template. The body must parse.
+#[tauri::command]
+pub async fn save_template(app: tauri::AppHandle, template: Template) -> Result<Template, String> {
+    render::validate(&template.body)?;
+    log::info!("[templates::cmd] save_template '{}' ({})", template.name, template.kind);
+    let conn = db::open(&app)?;
+    store::save(&conn, template)
+}
+
+/// Delete a template
+#[tauri::command]
+pub async fn delete_template(app: tauri::AppHandle, template_id: String) -> Result<(), String> {
+    let conn = db::open(&app)?;
+    store::delete(&conn, &template_id)
+}
+
+/// Render a stored template by name
+#[tauri::command]
+pub async fn render_template(
+    app: tauri::AppHandle,
+    name: String,
+    context: Option<TemplateContext>,
+) -> Result<String, String> {
+    let conn = db::open(&app)?;
+    let template = store::get_by_name(&conn, &name)?;
+    let organization = store::organization(&conn)?;
+    render::render(&template.body, &organization, &context.unwrap_or_default())
+}
+
+/// Render an unsaved template body, for live previews while editing
+#[tauri::command]
+pub async fn preview_template(
+    app: tauri::AppHandle,
+    body: String,
+    context: Option<TemplateContext>,
+) -> Result<String, String> {
+    let conn = db::open(&app)?;
+    let organization = store::organization(&conn)?;
+    render::render(&body, &organization, &context.unwrap_or_default())
+}
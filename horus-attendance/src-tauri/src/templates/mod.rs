@@ -0,0 +1,12 @@
+//! Text templates
+//!
+//! Emails and report headers/footers are rendered from templates stored in
+//! the `templates` table (MiniJinja syntax) instead of hardcoded English, so
+//! each organization can word and translate them. Templates see the company
+//! name and logo from the `organization` setting plus the report's period and
+//! totals.
+
+pub mod commands;
+pub mod render;
+pub mod store;
+pub mod types;
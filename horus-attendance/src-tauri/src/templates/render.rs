@@ -0,0 +1,107 @@
+//! Template rendering
+//!
+//! Variables available to every template:
+//!
+//! ```text
+//! company.name   company.logo (data: URI, empty when unset)
+//! period.start   period.end   period.label ("2026-01-01 to 2026-01-31")
+//! totals.*       extra.*      generated_at   app_version
+//! ```
+//!
+//! Besides the MiniJinja builtins there is a `format_date(fmt)` filter taking
+//! a chrono format string, for dates (YYYY-MM-DD) and RFC 3339 timestamps.
+
+use base64::Engine as _;
+use minijinja::{context, Environment, Error, ErrorKind, UndefinedBehavior};
+use std::path::Path;
+
+use super::types::{OrganizationSettings, TemplateContext};
+
+/// Largest logo embedded into rendered output
+const MAX_LOGO_BYTES: u64 = 512 * 1024;
+
+fn environment() -> Environment<'static> {
+    let mut env = Environment::new();
+    // Typos in placeholders should fail loudly in previews, not render blank;
+    // use `is defined` for optional values
+    env.set_undefined_behavior(UndefinedBehavior::Strict);
+    env.add_filter("format_date", format_date);
+    env
+}
+
+/// `{{ value | format_date("%d/%m/%Y") }}`
+fn format_date(value: String, format: String) -> Result<String, Error> {
+    if let Ok(ts) = chrono::DateTime::parse_from_rfc3339(&value) {
+        return Ok(ts.format(&format).to_string());
+    }
+    chrono::NaiveDate::parse_from_str(&value, "%Y-%m-%d")
+        .map(|d| d.format(&format).to_string())
+        .map_err(|_| Error::new(ErrorKind::InvalidOperation, format!("not a date: {}", value)))
+}
+
+/// Read a logo file into a data: URI so it can be embedded in HTML
+fn logo_data_uri(path: &str) -> Option<String> {
+    let path = Path::new(path);
+    let mime = match path.extension()?.to_string_lossy().to_lowercase().as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "svg" => "image/svg+xml",
+        _ => return None,
+    };
+    if std::fs::metadata(path).ok()?.len() > MAX_LOGO_BYTES {
+        log::warn!("[templates] Logo {} is too large to embed", path.display());
+        return None;
+    }
+    let bytes = std::fs::read(path).ok()?;
+    Some(format!(
+        "data:{};base64,{}",
+        mime,
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    ))
+}
+
+/// Check that a template parses
+pub fn validate(body: &str) -> Result<(), String> {
+    environment()
+        .template_from_str(body)
+        .map(|_| ())
+        .map_err(|e| describe_error(&e))
+}
+
+/// Render a template body with the organization and report context
+pub fn render(body: &str, organization: &OrganizationSettings, ctx: &TemplateContext) -> Result<String, String> {
+    let env = environment();
+    let template = env.template_from_str(body).map_err(|e| describe_error(&e))?;
+
+    let label = match (&ctx.period_start, &ctx.period_end) {
+        (Some(start), Some(end)) if start == end => start.clone(),
+        (Some(start), Some(end)) => format!("{} to {}", start, end),
+        (Some(start), None) => format!("from {}", start),
+        (None, Some(end)) => format!("until {}", end),
+        (None, None) => String::new(),
+    };
+    let logo = organization
+        .logo_path
+        .as_deref()
+        .and_then(logo_data_uri)
+        .unwrap_or_default();
+
+    template
+        .render(context! {
+            company => context! { name => organization.name, logo => logo },
+            period => context! { start => ctx.period_start, end => ctx.period_end, label => label },
+            totals => ctx.totals,
+            extra => ctx.extra,
+            generated_at => chrono::Local::now().to_rfc3339(),
+            app_version => env!("CARGO_PKG_VERSION"),
+        })
+        .map_err(|e| describe_error(&e))
+}
+
+/// Error message with the template line when known
+fn describe_error(e: &Error) -> String {
+    match e.line() {
+        Some(line) => format!("Line {}: {}", line, e),
+        None => e.to_string(),
+    }
+}
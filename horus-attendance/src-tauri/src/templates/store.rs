@@ -0,0 +1,96 @@
+//! Template persistence
+
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+use super::types::{OrganizationSettings, Template, TEMPLATE_KINDS};
+use crate::db;
+
+/// Settings key holding the organization name and logo
+pub const ORGANIZATION_KEY: &str = "organization";
+
+fn from_row(row: &Row) -> rusqlite::Result<Template> {
+    Ok(Template {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        kind: row.get(2)?,
+        body: row.get(3)?,
+        updated_at: row.get(4)?,
+    })
+}
+
+/// All templates, optionally of one kind
+pub fn list(conn: &Connection, kind: Option<&str>) -> Result<Vec<Template>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, name, kind, body, updated_at FROM templates
+             WHERE ?1 IS NULL OR kind = ?1
+             ORDER BY kind, name",
+        )
+        .map_err(|e| format!("Failed to query templates: {}", e))?;
+    let rows = stmt
+        .query_map([kind], from_row)
+        .map_err(|e| format!("Failed to query templates: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read templates: {}", e))
+}
+
+/// Look up a template by name
+pub fn get_by_name(conn: &Connection, name: &str) -> Result<Template, String> {
+    conn.query_row(
+        "SELECT id, name, kind, body, updated_at FROM templates WHERE name = ?1",
+        [name],
+        from_row,
+    )
+    .optional()
+    .map_err(|e| format!("Failed to read template '{}': {}", name, e))?
+    .ok_or_else(|| format!("Template not found: {}", name))
+}
+
+/// Insert or update a template (matched by ID)
+pub fn save(conn: &Connection, mut template: Template) -> Result<Template, String> {
+    template.name = template.name.trim().to_string();
+    if template.name.is_empty() {
+        return Err("Template name is required".to_string());
+    }
+    if !TEMPLATE_KINDS.contains(&template.kind.as_str()) {
+        return Err(format!("Unknown template kind: {}", template.kind));
+    }
+    if template.id.is_empty() {
+        template.id = uuid::Uuid::new_v4().to_string();
+    }
+    template.updated_at = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+
+    conn.execute(
+        "INSERT INTO templates (id, name, kind, body, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?5)
+         ON CONFLICT(id) DO UPDATE SET
+           name = excluded.name,
+           kind = excluded.kind,
+           body = excluded.body,
+           updated_at = excluded.updated_at",
+        params![template.id, template.name, template.kind, template.body, template.updated_at],
+    )
+    .map_err(|e| match e {
+        rusqlite::Error::SqliteFailure(err, _) if err.code == rusqlite::ErrorCode::ConstraintViolation => {
+            format!("A template named '{}' already exists", template.name)
+        }
+        e => format!("Failed to save template: {}", e),
+    })?;
+    Ok(template)
+}
+
+/// Delete a template
+pub fn delete(conn: &Connection, id: &str) -> Result<(), String> {
+    let deleted = conn
+        .execute("DELETE FROM templates WHERE id = ?1", [id])
+        .map_err(|e| format!("Failed to delete template: {}", e))?;
+    if deleted == 0 {
+        return Err("Template not found".to_string());
+    }
+    Ok(())
+}
+
+/// Organization name and logo, defaulting to empty
+pub fn organization(conn: &Connection) -> Result<OrganizationSettings, String> {
+    Ok(db::get_json_setting(conn, ORGANIZATION_KEY)?.unwrap_or_default())
+}
@@ -0,0 +1,50 @@
+//! Template data types for Tauri command serialization
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Template kinds, matching the CHECK constraint on the templates table
+pub const TEMPLATE_KINDS: &[&str] = &["email_subject", "email_body", "report_header", "report_footer"];
+
+/// A stored template
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Template {
+    /// Generated when empty on save
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    pub kind: String,
+    pub body: String,
+    #[serde(default)]
+    pub updated_at: String,
+}
+
+/// Organization details shown in templates (`organization` setting)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrganizationSettings {
+    #[serde(default)]
+    pub name: String,
+    /// Path to a PNG/JPEG/SVG logo
+    #[serde(default)]
+    pub logo_path: Option<String>,
+}
+
+/// Report-specific values supplied by the caller
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateContext {
+    /// YYYY-MM-DD
+    #[serde(default)]
+    pub period_start: Option<String>,
+    /// YYYY-MM-DD
+    #[serde(default)]
+    pub period_end: Option<String>,
+    /// Named totals, e.g. { "Present": 412, "Late": 17 }
+    #[serde(default)]
+    pub totals: BTreeMap<String, serde_json::Value>,
+    /// Anything else the caller wants to expose, available as `extra.*`
+    #[serde(default)]
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
@@ -0,0 +1,42 @@
+//! Tauri command handlers for TLS.
+
+use super::store::{self, SETTINGS_KEY};
+use super::types::*;
+use crate::db;
+
+#[tauri::command]
+pub async fn get_tls_settings(app: tauri::AppHandle) -> Result<TlsSettings, String> {
+    store::load_settings(&*db::open(&app)?)
+}
+
+/// Save the TLS mode; takes effect when the server next starts
+#[tauri::command]
+pub async fn save_tls_settings(app: tauri::AppHandle, settings: TlsSettings) -> Result<(), String> {
+    store::validate(&settings)?;
+    let json = serde_json::to_string(&settings).map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    log::info!("[tls::cmd] TLS mode {}", settings.mode);
+    db::set_setting(&*db::open(&app)?, SETTINGS_KEY, &json)
+}
+
+/// The certificate in use and its fingerprint for pinning
+#[tauri::command]
+pub async fn get_tls_status(app: tauri::AppHandle) -> Result<TlsStatus, String> {
+    let settings = store::load_settings(&*db::open(&app)?)?;
+    store::status(&app, &settings)
+}
+
+/// Replace the self-signed certificate, e.g. after adding a hostname.
+/// Clients pinning the old one must be given the new fingerprint.
+#[tauri::command]
+pub async fn regenerate_tls_certificate(app: tauri::AppHandle) -> Result<TlsStatus, String> {
+    let settings = store::load_settings(&*db::open(&app)?)?;
+    if settings.mode != MODE_SELF_SIGNED {
+        return Err("Only the self-signed certificate can be regenerated".to_string());
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        store::generate_self_signed(&app, &settings.hostnames)?;
+        store::status(&app, &settings)
+    })
+    .await
+    .map_err(|e| format!("Certificate task failed: {}", e))?
+}
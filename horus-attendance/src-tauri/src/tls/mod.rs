@@ -0,0 +1,18 @@
+//! TLS for the app's listeners
+//!
+//! Punches, tokens and reports shouldn't cross the office LAN in cleartext.
+//! The `tls` setting picks what the embedded REST API (the only listener so
+//! far; later ones take their certificate from here too) serves HTTPS with:
+//! nothing, a certificate and key the organization provides as PEM files,
+//! or a self-signed certificate the app generates. The self-signed
+//! certificate is kept in app data and its private key in the OS keychain.
+//! Clients can't check a self-signed certificate against a CA, so they
+//! should pin it instead: [`types::TlsStatus`] carries its SHA-256
+//! fingerprint to configure in the companion app or integration (or to
+//! compare against what a browser shows). Regenerating the certificate
+//! changes the fingerprint, and changes take effect when the server is
+//! next started.
+
+pub mod commands;
+pub mod store;
+pub mod types;
@@ -0,0 +1,164 @@
+//! Certificates: settings, the self-signed certificate and fingerprints
+
+use std::path::{Path, PathBuf};
+
+use base64::Engine;
+use rusqlite::Connection;
+use sha2::{Digest, Sha256};
+use tauri::Manager;
+
+use super::types::*;
+use crate::db;
+use crate::secrets::keychain;
+
+pub const SETTINGS_KEY: &str = "tls";
+
+/// Keychain credential holding the self-signed certificate's private key (PEM)
+const KEY_CREDENTIAL: &str = "tls_private_key";
+
+const SELF_SIGNED_FILE: &str = "self_signed.pem";
+
+const BEGIN_CERTIFICATE: &str = "-----BEGIN CERTIFICATE-----";
+const END_CERTIFICATE: &str = "-----END CERTIFICATE-----";
+
+pub fn load_settings(conn: &Connection) -> Result<TlsSettings, String> {
+    Ok(db::get_json_setting(conn, SETTINGS_KEY)?.unwrap_or_default())
+}
+
+/// Whether listeners serve HTTPS, for building links to them
+pub fn enabled(conn: &Connection) -> Result<bool, String> {
+    Ok(load_settings(conn)?.mode != MODE_OFF)
+}
+
+pub fn validate(settings: &TlsSettings) -> Result<(), String> {
+    match settings.mode.as_str() {
+        MODE_OFF | MODE_SELF_SIGNED => Ok(()),
+        MODE_CUSTOM => {
+            let (Some(cert_path), Some(key_path)) = (&settings.cert_path, &settings.key_path) else {
+                return Err("Choose both a certificate file and a private key file".to_string());
+            };
+            let certificate = read(Path::new(cert_path), "certificate")?;
+            fingerprint(&certificate).ok_or("The certificate file has no PEM certificate in it")?;
+            let key = read(Path::new(key_path), "private key")?;
+            if !String::from_utf8_lossy(&key).contains("PRIVATE KEY-----") {
+                return Err("The private key file has no PEM private key in it".to_string());
+            }
+            Ok(())
+        }
+        other => Err(format!("Unknown TLS mode: {} (expected off, self_signed or custom)", other)),
+    }
+}
+
+fn read(path: &Path, what: &str) -> Result<Vec<u8>, String> {
+    std::fs::read(path).map_err(|e| format!("Failed to read {} {}: {}", what, path.display(), e))
+}
+
+fn self_signed_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Cannot resolve app data dir: {}", e))?
+        .join("tls")
+        .join(SELF_SIGNED_FILE))
+}
+
+/// SHA-256 of the first PEM certificate in `pem`, as colon-separated hex
+pub fn fingerprint(pem: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(pem);
+    let start = text.find(BEGIN_CERTIFICATE)? + BEGIN_CERTIFICATE.len();
+    let end = start + text[start..].find(END_CERTIFICATE)?;
+    let body: String = text[start..end].split_whitespace().collect();
+    let der = base64::engine::general_purpose::STANDARD.decode(body).ok()?;
+    let digest = Sha256::digest(&der);
+    Some(digest.iter().map(|b| format!("{:02X}", b)).collect::<Vec<_>>().join(":"))
+}
+
+/// Generate a new self-signed certificate for localhost and `hostnames`,
+/// replacing any previous one. Blocks on the keychain.
+pub fn generate_self_signed(app: &tauri::AppHandle, hostnames: &[String]) -> Result<TlsMaterial, String> {
+    let mut names = vec!["localhost".to_string()];
+    for hostname in hostnames.iter().map(|h| h.trim()) {
+        if !hostname.is_empty() && !names.iter().any(|n| n == hostname) {
+            names.push(hostname.to_string());
+        }
+    }
+    let rcgen::CertifiedKey { cert, key_pair } = rcgen::generate_simple_self_signed(names)
+        .map_err(|e| format!("Failed to generate certificate: {}", e))?;
+    let certificate = cert.pem();
+    let private_key = key_pair.serialize_pem();
+
+    keychain::set(&keychain::credential_account(KEY_CREDENTIAL), &private_key)?;
+    let path = self_signed_path(app)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    std::fs::write(&path, &certificate).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    log::info!(
+        "[tls] Generated self-signed certificate {}",
+        fingerprint(certificate.as_bytes()).unwrap_or_default()
+    );
+    Ok(TlsMaterial {
+        certificate: certificate.into_bytes(),
+        private_key: private_key.into_bytes(),
+    })
+}
+
+/// The self-signed certificate and key, generated on first use. Blocks on
+/// the keychain.
+fn self_signed(app: &tauri::AppHandle, settings: &TlsSettings) -> Result<TlsMaterial, String> {
+    let path = self_signed_path(app)?;
+    let key = keychain::get(&keychain::credential_account(KEY_CREDENTIAL))?;
+    match (std::fs::read(&path).ok(), key) {
+        (Some(certificate), Some(private_key)) => Ok(TlsMaterial {
+            certificate,
+            private_key: private_key.into_bytes(),
+        }),
+        _ => generate_self_signed(app, &settings.hostnames),
+    }
+}
+
+/// What a listener should serve: None when TLS is off. Blocks on the
+/// keychain.
+pub fn server_material(app: &tauri::AppHandle, settings: &TlsSettings) -> Result<Option<TlsMaterial>, String> {
+    match settings.mode.as_str() {
+        MODE_SELF_SIGNED => self_signed(app, settings).map(Some),
+        MODE_CUSTOM => {
+            validate(settings)?;
+            let cert_path = settings.cert_path.as_deref().unwrap_or_default();
+            let key_path = settings.key_path.as_deref().unwrap_or_default();
+            Ok(Some(TlsMaterial {
+                certificate: read(Path::new(cert_path), "certificate")?,
+                private_key: read(Path::new(key_path), "private key")?,
+            }))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// The certificate in use and its fingerprint. Doesn't generate anything.
+pub fn status(app: &tauri::AppHandle, settings: &TlsSettings) -> Result<TlsStatus, String> {
+    let cert_path = match settings.mode.as_str() {
+        MODE_SELF_SIGNED => Some(self_signed_path(app)?),
+        MODE_CUSTOM => settings.cert_path.as_ref().map(PathBuf::from),
+        _ => None,
+    };
+    let (fingerprint_sha256, error) = match &cert_path {
+        Some(path) if settings.mode == MODE_SELF_SIGNED && !path.exists() => {
+            (None, Some("Generated when the server next starts".to_string()))
+        }
+        Some(path) => match read(path, "certificate") {
+            Ok(pem) => match fingerprint(&pem) {
+                Some(fingerprint) => (Some(fingerprint), None),
+                None => (None, Some("The certificate file has no PEM certificate in it".to_string())),
+            },
+            Err(e) => (None, Some(e)),
+        },
+        None => (None, None),
+    };
+    Ok(TlsStatus {
+        mode: settings.mode.clone(),
+        cert_path: cert_path.map(|p| p.to_string_lossy().to_string()),
+        fingerprint_sha256,
+        error,
+    })
+}
@@ -0,0 +1,52 @@
+//! TLS data types for Tauri command serialization
+
+use serde::{Deserialize, Serialize};
+
+pub const MODE_OFF: &str = "off";
+pub const MODE_SELF_SIGNED: &str = "self_signed";
+pub const MODE_CUSTOM: &str = "custom";
+
+/// TLS settings (`tls` setting)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TlsSettings {
+    /// "off", "self_signed" or "custom"
+    pub mode: String,
+    /// PEM certificate (chain) file, for "custom"
+    pub cert_path: Option<String>,
+    /// PEM private key file, for "custom"
+    pub key_path: Option<String>,
+    /// Names and addresses clients reach the PC by, put in the self-signed
+    /// certificate besides localhost
+    pub hostnames: Vec<String>,
+}
+
+impl Default for TlsSettings {
+    fn default() -> Self {
+        Self {
+            mode: MODE_OFF.to_string(),
+            cert_path: None,
+            key_path: None,
+            hostnames: Vec::new(),
+        }
+    }
+}
+
+/// The certificate listeners serve
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TlsStatus {
+    pub mode: String,
+    /// Certificate file, if there is one yet
+    pub cert_path: Option<String>,
+    /// SHA-256 of the (first) certificate, colon-separated hex, for pinning
+    pub fingerprint_sha256: Option<String>,
+    /// Why the certificate can't be used, if it can't
+    pub error: Option<String>,
+}
+
+/// Certificate and key PEM for a listener
+pub struct TlsMaterial {
+    pub certificate: Vec<u8>,
+    pub private_key: Vec<u8>,
+}
@@ -0,0 +1,47 @@
+//! The user_audit_log table
+
+use rusqlite::{params, Connection};
+
+use super::types::UserAuditEntry;
+
+pub const ACTION_BULK_UPDATE: &str = "bulk_update";
+pub const ACTION_BULK_DELETE: &str = "bulk_delete";
+pub const ACTION_LINK_DEVICE_USERS: &str = "link_device_users";
+pub const ACTION_DEVICE_USER_MERGE: &str = "device_user_merge";
+
+/// Record a change to `user_ids`; returns the entry's ID
+pub fn record(conn: &Connection, action: &str, changes: &serde_json::Value, user_ids: &[String]) -> Result<i64, String> {
+    let ids = serde_json::to_string(user_ids).map_err(|e| format!("Failed to serialize user IDs: {}", e))?;
+    conn.execute(
+        "INSERT INTO user_audit_log (action, changes, user_ids, user_count) VALUES (?1, ?2, ?3, ?4)",
+        params![action, changes.to_string(), ids, user_ids.len() as i64],
+    )
+    .map_err(|e| format!("Failed to write audit entry: {}", e))?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Audit entries, newest first
+pub fn list(conn: &Connection, limit: u32) -> Result<Vec<UserAuditEntry>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, action, changes, user_ids, user_count, created_at
+             FROM user_audit_log ORDER BY id DESC LIMIT ?1",
+        )
+        .map_err(|e| format!("Failed to query audit log: {}", e))?;
+    let rows = stmt
+        .query_map([limit], |row| {
+            let changes: String = row.get(2)?;
+            let user_ids: String = row.get(3)?;
+            Ok(UserAuditEntry {
+                id: row.get(0)?,
+                action: row.get(1)?,
+                changes: serde_json::from_str(&changes).unwrap_or_default(),
+                user_ids: serde_json::from_str(&user_ids).unwrap_or_default(),
+                user_count: row.get(4)?,
+                created_at: row.get(5)?,
+            })
+        })
+        .map_err(|e| format!("Failed to query audit log: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read audit log: {}", e))
+}
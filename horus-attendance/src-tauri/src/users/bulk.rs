@@ -0,0 +1,189 @@
+//! Applying a change to many users at once
+
+use std::collections::HashSet;
+
+use rusqlite::{params, Connection, Transaction};
+
+use super::audit;
+use super::types::*;
+use crate::shifts::commands::validate_time;
+use crate::summary::commands::validate_date;
+use crate::summary::engine;
+use crate::summary::rules::ends_after_start;
+
+const NOW_SQL: &str = "strftime('%Y-%m-%dT%H:%M:%fZ', 'now')";
+
+/// Check the parts of `update` that are the same for every user
+pub fn validate_update(conn: &Connection, update: &BulkUserUpdate) -> Result<(), String> {
+    if update.department_id.is_none()
+        && update.site.is_none()
+        && update.status.is_none()
+        && update.shift.is_none()
+        && !update.clear_shift
+        && update.classification.is_none()
+        && update.enrollment_expires_at.is_none()
+    {
+        return Err("Nothing to change".to_string());
+    }
+    if let Some(status) = update.status.as_deref() {
+        if status != "active" && status != "inactive" {
+            return Err(format!("Unknown status: {}", status));
+        }
+    }
+    if let Some(expires) = update.enrollment_expires_at.as_deref().filter(|d| !d.is_empty()) {
+        validate_date(expires)?;
+    }
+    if let Some(department_id) = update.department_id.as_deref().filter(|id| !id.is_empty()) {
+        let known: bool = conn
+            .query_row("SELECT EXISTS(SELECT 1 FROM departments WHERE id = ?1)", [department_id], |row| row.get(0))
+            .map_err(|e| format!("Failed to look up department: {}", e))?;
+        if !known {
+            return Err(format!("Department not found: {}", department_id));
+        }
+    }
+    if let Some(shift) = &update.shift {
+        if update.clear_shift {
+            return Err("Choose either a shift to assign or clearing shifts, not both".to_string());
+        }
+        validate_time(&shift.start_time)?;
+        validate_time(&shift.end_time)?;
+        if !ends_after_start(&shift.start_time, &shift.end_time, &engine::load_rules(conn)?) {
+            return Err(format!("Shift must end after it starts ({} - {})", shift.start_time, shift.end_time));
+        }
+    }
+    Ok(())
+}
+
+/// Run `apply` for each user inside `tx`, committing with an audit entry only
+/// if it succeeded for all of them
+fn run_batch<F>(
+    tx: Transaction,
+    user_ids: &[String],
+    action: &str,
+    changes: &serde_json::Value,
+    mut apply: F,
+) -> Result<BulkUserOutcome, String>
+where
+    F: FnMut(&Transaction, &str) -> Result<(), String>,
+{
+    let mut seen = HashSet::new();
+    let results: Vec<BulkUserResult> = user_ids
+        .iter()
+        .map(|user_id| {
+            let outcome = if seen.insert(user_id.as_str()) {
+                apply(&tx, user_id)
+            } else {
+                Err("Listed more than once".to_string())
+            };
+            BulkUserResult {
+                user_id: user_id.clone(),
+                ok: outcome.is_ok(),
+                error: outcome.err(),
+            }
+        })
+        .collect();
+    let failed = results.iter().filter(|r| !r.ok).count() as u32;
+    let succeeded = results.len() as u32 - failed;
+    if failed > 0 {
+        // Dropping the transaction rolls it back
+        log::warn!("[users] {} rolled back: {} of {} users failed", action, failed, results.len());
+        return Ok(BulkUserOutcome {
+            applied: false,
+            succeeded: 0,
+            failed,
+            results,
+            audit_id: None,
+        });
+    }
+    let audit_id = audit::record(&tx, action, changes, user_ids)?;
+    tx.commit().map_err(|e| format!("Failed to commit: {}", e))?;
+    Ok(BulkUserOutcome {
+        applied: true,
+        succeeded,
+        failed,
+        results,
+        audit_id: Some(audit_id),
+    })
+}
+
+fn not_found(user_id: &str) -> String {
+    format!("User not found: {}", user_id)
+}
+
+/// Apply `update` to every user in `user_ids`, or to none of them
+pub fn update(conn: &mut Connection, user_ids: &[String], update: &BulkUserUpdate) -> Result<BulkUserOutcome, String> {
+    let mut assignments = vec![format!("updated_at = {}", NOW_SQL)];
+    let mut values: Vec<Option<String>> = Vec::new();
+    if let Some(department_id) = &update.department_id {
+        values.push(Some(department_id.clone()).filter(|id| !id.is_empty()));
+        assignments.push(format!("department_id = ?{}", values.len() + 1));
+    }
+    if let Some(site) = &update.site {
+        values.push(Some(site.trim().to_string()).filter(|s| !s.is_empty()));
+        assignments.push(format!("site = ?{}", values.len() + 1));
+    }
+    if let Some(status) = &update.status {
+        values.push(Some(status.clone()));
+        assignments.push(format!("status = ?{}", values.len() + 1));
+    }
+    if let Some(classification) = update.classification {
+        values.push(Some(classification.as_str().to_string()));
+        assignments.push(format!("classification = ?{}", values.len() + 1));
+    }
+    if let Some(expires) = &update.enrollment_expires_at {
+        values.push(Some(expires.clone()).filter(|d| !d.is_empty()));
+        assignments.push(format!("enrollment_expires_at = ?{}", values.len() + 1));
+        // A new expiry date is carried out again when it passes
+        assignments.push("enrollment_removed_at = NULL".to_string());
+    }
+    let sql = format!("UPDATE users SET {} WHERE id = ?1", assignments.join(", "));
+    let changes = serde_json::to_value(update).map_err(|e| format!("Failed to serialize changes: {}", e))?;
+
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    run_batch(tx, user_ids, audit::ACTION_BULK_UPDATE, &changes, |tx, user_id| {
+        let mut args: Vec<&dyn rusqlite::ToSql> = vec![&user_id];
+        args.extend(values.iter().map(|v| v as &dyn rusqlite::ToSql));
+        let updated = tx.execute(&sql, args.as_slice()).map_err(|e| e.to_string())?;
+        if updated == 0 {
+            return Err(not_found(user_id));
+        }
+        if let Some(shift) = &update.shift {
+            tx.execute(
+                "INSERT INTO user_shifts (user_id, start_time, end_time, source)
+                 VALUES (?1, ?2, ?3, 'manual')
+                 ON CONFLICT(user_id) DO UPDATE SET
+                    start_time = excluded.start_time,
+                    end_time = excluded.end_time,
+                    source = excluded.source,
+                    updated_at = datetime('now')",
+                params![user_id, shift.start_time, shift.end_time],
+            )
+            .map_err(|e| format!("Failed to save shift: {}", e))?;
+        } else if update.clear_shift {
+            tx.execute("DELETE FROM user_shifts WHERE user_id = ?1", [user_id])
+                .map_err(|e| format!("Failed to delete shift: {}", e))?;
+        }
+        if update.enrollment_expires_at.is_some() {
+            tx.execute("DELETE FROM enrollment_removals WHERE user_id = ?1", [user_id])
+                .map_err(|e| format!("Failed to reset enrollment removals: {}", e))?;
+        }
+        Ok(())
+    })
+}
+
+/// Delete every user in `user_ids`, or none of them. Their summaries,
+/// shifts and roster entries go with them; raw punches are kept.
+pub fn delete(conn: &mut Connection, user_ids: &[String]) -> Result<BulkUserOutcome, String> {
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    run_batch(tx, user_ids, audit::ACTION_BULK_DELETE, &serde_json::json!({}), |tx, user_id| {
+        match tx.execute("DELETE FROM users WHERE id = ?1", [user_id]) {
+            Ok(0) => Err(not_found(user_id)),
+            Ok(_) => Ok(()),
+            Err(e) => Err(e.to_string()),
+        }
+    })
+}
@@ -0,0 +1,124 @@
+//! Tauri command handlers for bulk user operations.
+
+use super::audit;
+use super::bulk;
+use super::linking;
+use super::merge;
+use super::types::*;
+use crate::db;
+use crate::zkteco::types::DeviceUser;
+
+/// Users changed or deleted in one call
+const MAX_BATCH: usize = 5000;
+
+const DEFAULT_AUDIT_LIMIT: u32 = 100;
+
+fn validate_batch(user_ids: &[String]) -> Result<(), String> {
+    if user_ids.is_empty() {
+        return Err("No users given".to_string());
+    }
+    if user_ids.len() > MAX_BATCH {
+        return Err(format!("At most {} users can be changed at once", MAX_BATCH));
+    }
+    Ok(())
+}
+
+/// Assign a department, change status or set a shift for many users in one
+/// transaction. If any user can't be changed, none is; the per-user results
+/// say which failed. Summaries pick up shift changes when next recomputed.
+#[tauri::command]
+pub async fn bulk_update_users(
+    app: tauri::AppHandle,
+    user_ids: Vec<String>,
+    update: BulkUserUpdate,
+) -> Result<BulkUserOutcome, String> {
+    validate_batch(&user_ids)?;
+    let mut conn = db::open(&app)?;
+    bulk::validate_update(&conn, &update)?;
+    log::info!("[users::cmd] bulk_update_users for {} users", user_ids.len());
+    tauri::async_runtime::spawn_blocking(move || bulk::update(&mut conn, &user_ids, &update))
+        .await
+        .map_err(|e| format!("Bulk update task failed: {}", e))?
+}
+
+/// Delete many users in one transaction, all or none
+#[tauri::command]
+pub async fn bulk_delete_users(app: tauri::AppHandle, user_ids: Vec<String>) -> Result<BulkUserOutcome, String> {
+    validate_batch(&user_ids)?;
+    let mut conn = db::open(&app)?;
+    log::info!("[users::cmd] bulk_delete_users for {} users", user_ids.len());
+    tauri::async_runtime::spawn_blocking(move || bulk::delete(&mut conn, &user_ids))
+        .await
+        .map_err(|e| format!("Bulk delete task failed: {}", e))?
+}
+
+/// Recent bulk user operations, newest first
+#[tauri::command]
+pub async fn list_user_audit_log(app: tauri::AppHandle, limit: Option<u32>) -> Result<Vec<UserAuditEntry>, String> {
+    audit::list(&*db::open(&app)?, limit.unwrap_or(DEFAULT_AUDIT_LIMIT))
+}
+
+/// Propose which existing profile each device user created by sync belongs
+/// to, by employee code, exact name and name similarity
+#[tauri::command]
+pub async fn propose_user_links(app: tauri::AppHandle, min_confidence: Option<f64>) -> Result<Vec<LinkProposal>, String> {
+    let min_confidence = min_confidence.unwrap_or(linking::DEFAULT_MIN_CONFIDENCE);
+    if !(0.0..=1.0).contains(&min_confidence) {
+        return Err("Minimum confidence must be between 0 and 1".to_string());
+    }
+    let conn = db::open(&app)?;
+    tauri::async_runtime::spawn_blocking(move || linking::propose(&conn, min_confidence))
+        .await
+        .map_err(|e| format!("Link proposal task failed: {}", e))?
+}
+
+/// Link confirmed device users to their profiles in one transaction, all or
+/// none. Summaries include the linked punches once recomputed.
+#[tauri::command]
+pub async fn confirm_user_links(app: tauri::AppHandle, links: Vec<LinkConfirmation>) -> Result<LinkOutcome, String> {
+    if links.is_empty() {
+        return Err("No links given".to_string());
+    }
+    if links.len() > MAX_BATCH {
+        return Err(format!("At most {} links can be confirmed at once", MAX_BATCH));
+    }
+    let mut conn = db::open(&app)?;
+    log::info!("[users::cmd] confirm_user_links for {} device users", links.len());
+    tauri::async_runtime::spawn_blocking(move || linking::confirm(&mut conn, &links))
+        .await
+        .map_err(|e| format!("Link task failed: {}", e))?
+}
+
+/// Device user IDs linked to profiles besides their own
+#[tauri::command]
+pub async fn list_user_device_links(app: tauri::AppHandle) -> Result<Vec<DeviceUserLink>, String> {
+    linking::list(&*db::open(&app)?)
+}
+
+#[tauri::command]
+pub async fn unlink_device_user(app: tauri::AppHandle, device_user_id: String) -> Result<bool, String> {
+    log::info!("[users::cmd] unlink_device_user {}", device_user_id);
+    linking::unlink(&*db::open(&app)?, &device_user_id)
+}
+
+/// Add the users read from a device that nobody has yet and refresh the
+/// device names of the rest. Display names and profile details are kept.
+#[tauri::command]
+pub async fn merge_device_users(
+    app: tauri::AppHandle,
+    device_id: Option<String>,
+    users: Vec<DeviceUser>,
+) -> Result<UserMergeSummary, String> {
+    let mut conn = db::open(&app)?;
+    let summary = tauri::async_runtime::spawn_blocking(move || merge::merge(&mut conn, device_id.as_deref(), &users))
+        .await
+        .map_err(|e| format!("User merge task failed: {}", e))??;
+    log::info!(
+        "[users::cmd] Merged {} device users: {} added, {} renamed, {} unchanged",
+        summary.device_users,
+        summary.added,
+        summary.renamed,
+        summary.unchanged
+    );
+    Ok(summary)
+}
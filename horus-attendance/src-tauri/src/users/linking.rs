@@ -0,0 +1,305 @@
+//! Linking device users to existing profiles
+//!
+//! Sync creates a profile for every device user ID it hasn't seen, named
+//! after the device. A second terminal enrolled with its own IDs therefore
+//! adds a placeholder for everyone who already has a profile. Proposals pair
+//! each placeholder with the profiles it most likely belongs to; confirming
+//! one records the device user ID in user_device_links (or as the profile's
+//! own ID when it has none) and removes the placeholder. Summaries include
+//! the linked punches once recomputed.
+
+use std::collections::HashSet;
+
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+
+use super::audit;
+use super::similarity::{normalize, similarity};
+use super::types::*;
+
+pub const METHOD_EMPLOYEE_CODE: &str = "employee_code";
+pub const METHOD_EXACT_NAME: &str = "exact_name";
+pub const METHOD_FUZZY_NAME: &str = "fuzzy_name";
+
+/// Confidence of an employee code equal to the device user ID, and of one
+/// equal once leading zeros are dropped
+const EMPLOYEE_CODE_CONFIDENCE: f64 = 1.0;
+const PADDED_CODE_CONFIDENCE: f64 = 0.95;
+const EXACT_NAME_CONFIDENCE: f64 = 0.9;
+/// Fuzzy matches are scaled by this, so they never outrank an exact name
+const FUZZY_NAME_WEIGHT: f64 = 0.8;
+
+pub const DEFAULT_MIN_CONFIDENCE: f64 = 0.6;
+
+/// Candidates scoring within this of the best make a proposal ambiguous
+const AMBIGUITY_MARGIN: f64 = 0.05;
+
+const MAX_ALTERNATIVES: usize = 3;
+
+/// A profile still as sync created it: named after the device, no employee code
+const PLACEHOLDER_SQL: &str = "COALESCE(u.device_user_id, '') != ''
+     AND COALESCE(u.employee_code, '') = ''
+     AND (u.display_name = '' OR u.display_name = COALESCE(u.device_name, '')
+          OR u.display_name = 'User ' || u.device_user_id)";
+
+struct Profile {
+    id: String,
+    device_user_id: Option<String>,
+    device_name: Option<String>,
+    display_name: String,
+    employee_code: Option<String>,
+    placeholder: bool,
+}
+
+fn load_profiles(conn: &Connection) -> Result<Vec<Profile>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT u.id, u.device_user_id, u.device_name, u.display_name, u.employee_code,
+                    {} AS placeholder
+             FROM users u ORDER BY u.created_at, u.id",
+            PLACEHOLDER_SQL
+        ))
+        .map_err(|e| format!("Failed to query users: {}", e))?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(Profile {
+                id: row.get(0)?,
+                device_user_id: row.get(1)?,
+                device_name: row.get(2)?,
+                display_name: row.get(3)?,
+                employee_code: row.get(4)?,
+                placeholder: row.get(5)?,
+            })
+        })
+        .map_err(|e| format!("Failed to query users: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read users: {}", e))
+}
+
+/// How well `profile` matches the device user, if at all
+fn score(device_user_id: &str, device_name: &str, profile: &Profile) -> Option<(&'static str, f64)> {
+    if let Some(code) = profile.employee_code.as_deref().map(str::trim).filter(|c| !c.is_empty()) {
+        if code.eq_ignore_ascii_case(device_user_id) {
+            return Some((METHOD_EMPLOYEE_CODE, EMPLOYEE_CODE_CONFIDENCE));
+        }
+        let unpadded = |id: &str| id.trim_start_matches('0').to_ascii_lowercase();
+        if !unpadded(code).is_empty() && unpadded(code) == unpadded(device_user_id) {
+            return Some((METHOD_EMPLOYEE_CODE, PADDED_CODE_CONFIDENCE));
+        }
+    }
+    let names = [Some(profile.display_name.as_str()), profile.device_name.as_deref()];
+    let names = names.iter().flatten().filter(|n| !n.trim().is_empty());
+    let normalized = normalize(device_name);
+    if normalized.is_empty() {
+        return None;
+    }
+    let mut best = 0.0f64;
+    for name in names {
+        if normalize(name) == normalized {
+            return Some((METHOD_EXACT_NAME, EXACT_NAME_CONFIDENCE));
+        }
+        best = best.max(similarity(device_name, name));
+    }
+    (best > 0.0).then_some((METHOD_FUZZY_NAME, best * FUZZY_NAME_WEIGHT))
+}
+
+fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+/// Proposed links for every placeholder with a candidate scoring at least
+/// `min_confidence`, most confident first. A placeholder is only proposed
+/// for profiles created before it, so two placeholders of the same person
+/// aren't proposed for each other both ways.
+pub fn propose(conn: &Connection, min_confidence: f64) -> Result<Vec<LinkProposal>, String> {
+    let profiles = load_profiles(conn)?;
+    let mut proposals = Vec::new();
+    for (index, placeholder) in profiles.iter().enumerate() {
+        if !placeholder.placeholder {
+            continue;
+        }
+        let Some(device_user_id) = placeholder.device_user_id.as_deref() else {
+            continue;
+        };
+        let device_name = placeholder.device_name.as_deref().unwrap_or(&placeholder.display_name);
+        let mut candidates: Vec<LinkCandidate> = profiles
+            .iter()
+            .enumerate()
+            .filter(|(i, p)| *i != index && (!p.placeholder || *i < index))
+            .filter_map(|(_, p)| {
+                let (method, confidence) = score(device_user_id, device_name, p)?;
+                (confidence >= min_confidence).then(|| LinkCandidate {
+                    user_id: p.id.clone(),
+                    display_name: p.display_name.clone(),
+                    employee_code: p.employee_code.clone(),
+                    method: method.to_string(),
+                    confidence: round2(confidence),
+                })
+            })
+            .collect();
+        if candidates.is_empty() {
+            continue;
+        }
+        candidates.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+        let best = candidates.remove(0);
+        let ambiguous = candidates.first().is_some_and(|c| c.confidence >= best.confidence - AMBIGUITY_MARGIN);
+        candidates.truncate(MAX_ALTERNATIVES);
+        proposals.push(LinkProposal {
+            device_user_id: device_user_id.to_string(),
+            device_name: placeholder.device_name.clone(),
+            placeholder_user_id: placeholder.id.clone(),
+            best,
+            alternatives: candidates,
+            ambiguous,
+        });
+    }
+    proposals.sort_by(|a, b| {
+        b.best
+            .confidence
+            .total_cmp(&a.best.confidence)
+            .then_with(|| a.device_user_id.cmp(&b.device_user_id))
+    });
+    Ok(proposals)
+}
+
+/// Link one device user inside `tx`
+fn link(tx: &Transaction, confirmation: &LinkConfirmation) -> Result<(), String> {
+    let device_user_id = confirmation.device_user_id.as_str();
+    let target: Option<Option<String>> = tx
+        .query_row("SELECT device_user_id FROM users WHERE id = ?1", [&confirmation.user_id], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let Some(target_device_user_id) = target else {
+        return Err(format!("User not found: {}", confirmation.user_id));
+    };
+    let current: Option<(String, bool, Option<String>)> = tx
+        .query_row(
+            &format!(
+                "SELECT u.id, {} AS placeholder, u.device_name FROM users u WHERE u.device_user_id = ?1",
+                PLACEHOLDER_SQL
+            ),
+            [device_user_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let mut device_name = None;
+    if let Some((owner_id, placeholder, name)) = current {
+        if owner_id == confirmation.user_id {
+            return Err("Already the profile's own device user ID".to_string());
+        }
+        if !placeholder {
+            return Err(format!("Device user ID belongs to edited profile {}", owner_id));
+        }
+        tx.execute("DELETE FROM users WHERE id = ?1", [&owner_id])
+            .map_err(|e| format!("Failed to remove placeholder: {}", e))?;
+        device_name = name;
+    }
+    if target_device_user_id.as_deref().map_or(true, str::is_empty) {
+        tx.execute(
+            "UPDATE users SET device_user_id = ?2, device_name = COALESCE(device_name, ?3),
+                 updated_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+             WHERE id = ?1",
+            params![confirmation.user_id, device_user_id, device_name],
+        )
+        .map_err(|e| format!("Failed to link: {}", e))?;
+        tx.execute("DELETE FROM user_device_links WHERE device_user_id = ?1", [device_user_id])
+            .map_err(|e| format!("Failed to link: {}", e))?;
+    } else {
+        tx.execute(
+            "INSERT INTO user_device_links (device_user_id, user_id, device_name, method, confidence)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(device_user_id) DO UPDATE SET
+                 user_id = excluded.user_id,
+                 device_name = COALESCE(excluded.device_name, device_name),
+                 method = excluded.method,
+                 confidence = excluded.confidence,
+                 created_at = datetime('now')",
+            params![
+                device_user_id,
+                confirmation.user_id,
+                device_name,
+                confirmation.method.as_deref().unwrap_or("manual"),
+                confirmation.confidence,
+            ],
+        )
+        .map_err(|e| format!("Failed to link: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Apply every confirmed link, or none of them
+pub fn confirm(conn: &mut Connection, confirmations: &[LinkConfirmation]) -> Result<LinkOutcome, String> {
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let mut seen = HashSet::new();
+    let results: Vec<LinkResult> = confirmations
+        .iter()
+        .map(|confirmation| {
+            let outcome = if seen.insert(confirmation.device_user_id.as_str()) {
+                link(&tx, confirmation)
+            } else {
+                Err("Listed more than once".to_string())
+            };
+            LinkResult {
+                device_user_id: confirmation.device_user_id.clone(),
+                ok: outcome.is_ok(),
+                error: outcome.err(),
+            }
+        })
+        .collect();
+    let failed = results.iter().filter(|r| !r.ok).count() as u32;
+    if failed > 0 {
+        // Dropping the transaction rolls it back
+        log::warn!("[users] Linking rolled back: {} of {} links failed", failed, results.len());
+        return Ok(LinkOutcome {
+            applied: false,
+            linked: 0,
+            failed,
+            results,
+            audit_id: None,
+        });
+    }
+    let changes = serde_json::json!({ "links": confirmations });
+    let user_ids: Vec<String> = confirmations.iter().map(|c| c.user_id.clone()).collect();
+    let audit_id = audit::record(&tx, audit::ACTION_LINK_DEVICE_USERS, &changes, &user_ids)?;
+    tx.commit().map_err(|e| format!("Failed to commit: {}", e))?;
+    Ok(LinkOutcome {
+        applied: true,
+        linked: results.len() as u32,
+        failed,
+        results,
+        audit_id: Some(audit_id),
+    })
+}
+
+/// Every linked device user ID
+pub fn list(conn: &Connection) -> Result<Vec<DeviceUserLink>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT device_user_id, user_id, device_name, method, confidence, created_at
+             FROM user_device_links ORDER BY user_id, device_user_id",
+        )
+        .map_err(|e| format!("Failed to query device links: {}", e))?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(DeviceUserLink {
+                device_user_id: row.get(0)?,
+                user_id: row.get(1)?,
+                device_name: row.get(2)?,
+                method: row.get(3)?,
+                confidence: row.get(4)?,
+                created_at: row.get(5)?,
+            })
+        })
+        .map_err(|e| format!("Failed to query device links: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read device links: {}", e))
+}
+
+/// Remove a link. The next sync creates a placeholder for the device user again.
+pub fn unlink(conn: &Connection, device_user_id: &str) -> Result<bool, String> {
+    conn.execute("DELETE FROM user_device_links WHERE device_user_id = ?1", [device_user_id])
+        .map(|n| n > 0)
+        .map_err(|e| format!("Failed to remove device link: {}", e))
+}
@@ -0,0 +1,168 @@
+//! Merging users read from a device into the users table
+//!
+//! Device names are short and often cut off, while profiles get enriched
+//! in the app with full names and contact details. A sync therefore only
+//! adds device users nobody has yet and refreshes the `device_name` of the
+//! others; the display name and the rest of the profile are never touched.
+//! IDs linked to another profile (see [`super::linking`]) get the device
+//! name on their link instead. What changed is returned as a summary and
+//! recorded in `user_audit_log`.
+
+use std::collections::HashSet;
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use super::audit;
+use super::types::*;
+use crate::zkteco::types::DeviceUser;
+
+pub const CHANGE_ADDED: &str = "added";
+pub const CHANGE_RENAMED: &str = "renamed";
+
+/// The existing holder of a device user ID: a profile of its own, or a link
+enum Holder {
+    User {
+        id: String,
+        display_name: String,
+        device_name: Option<String>,
+    },
+    Link {
+        user_id: String,
+        display_name: String,
+        device_name: Option<String>,
+    },
+}
+
+fn holder(conn: &Connection, device_user_id: &str) -> Result<Option<Holder>, String> {
+    let user = conn
+        .query_row(
+            "SELECT id, display_name, device_name FROM users WHERE device_user_id = ?1",
+            [device_user_id],
+            |row| {
+                Ok(Holder::User {
+                    id: row.get(0)?,
+                    display_name: row.get(1)?,
+                    device_name: row.get(2)?,
+                })
+            },
+        )
+        .optional()
+        .map_err(|e| format!("Failed to read user: {}", e))?;
+    if user.is_some() {
+        return Ok(user);
+    }
+    conn.query_row(
+        "SELECT l.user_id, u.display_name, l.device_name
+         FROM user_device_links l JOIN users u ON u.id = l.user_id
+         WHERE l.device_user_id = ?1",
+        [device_user_id],
+        |row| {
+            Ok(Holder::Link {
+                user_id: row.get(0)?,
+                display_name: row.get(1)?,
+                device_name: row.get(2)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(|e| format!("Failed to read device link: {}", e))
+}
+
+/// Add the device users nobody has and refresh the device names of the
+/// rest, in one transaction. `device_id` is only recorded in the audit log.
+pub fn merge(conn: &mut Connection, device_id: Option<&str>, users: &[DeviceUser]) -> Result<UserMergeSummary, String> {
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    // A device lists each ID once, but merged lists from several may not
+    let mut seen = HashSet::new();
+    let users: Vec<&DeviceUser> = users
+        .iter()
+        .filter(|u| !u.device_user_id.trim().is_empty() && seen.insert(u.device_user_id.as_str()))
+        .collect();
+
+    let mut summary = UserMergeSummary {
+        device_users: users.len() as u32,
+        ..UserMergeSummary::default()
+    };
+    for user in users {
+        let name = user.device_name.trim();
+        match holder(&tx, &user.device_user_id)? {
+            None => {
+                let id = uuid::Uuid::new_v4().to_string();
+                let display_name = if name.is_empty() { format!("User {}", user.device_user_id) } else { name.to_string() };
+                tx.execute(
+                    "INSERT INTO users (id, device_user_id, device_name, display_name, status) VALUES (?1, ?2, ?3, ?4, 'active')",
+                    params![id, user.device_user_id, Some(name).filter(|n| !n.is_empty()), display_name],
+                )
+                .map_err(|e| format!("Failed to add user {}: {}", user.device_user_id, e))?;
+                summary.added += 1;
+                summary.changes.push(UserMergeChange {
+                    change: CHANGE_ADDED.to_string(),
+                    user_id: id,
+                    device_user_id: user.device_user_id.clone(),
+                    display_name,
+                    previous_device_name: None,
+                    device_name: name.to_string(),
+                });
+            }
+            // A blank name on the device doesn't erase the one kept here
+            Some(Holder::User { device_name, .. } | Holder::Link { device_name, .. })
+                if name.is_empty() || device_name.as_deref() == Some(name) =>
+            {
+                summary.unchanged += 1;
+            }
+            Some(Holder::User {
+                id,
+                display_name,
+                device_name,
+            }) => {
+                tx.execute(
+                    "UPDATE users SET device_name = ?2, updated_at = datetime('now') WHERE id = ?1",
+                    params![id, name],
+                )
+                .map_err(|e| format!("Failed to update user {}: {}", user.device_user_id, e))?;
+                summary.renamed += 1;
+                summary.changes.push(UserMergeChange {
+                    change: CHANGE_RENAMED.to_string(),
+                    user_id: id,
+                    device_user_id: user.device_user_id.clone(),
+                    display_name,
+                    previous_device_name: device_name,
+                    device_name: name.to_string(),
+                });
+            }
+            Some(Holder::Link {
+                user_id,
+                display_name,
+                device_name,
+            }) => {
+                tx.execute(
+                    "UPDATE user_device_links SET device_name = ?2 WHERE device_user_id = ?1",
+                    params![user.device_user_id, name],
+                )
+                .map_err(|e| format!("Failed to update device link {}: {}", user.device_user_id, e))?;
+                summary.renamed += 1;
+                summary.changes.push(UserMergeChange {
+                    change: CHANGE_RENAMED.to_string(),
+                    user_id,
+                    device_user_id: user.device_user_id.clone(),
+                    display_name,
+                    previous_device_name: device_name,
+                    device_name: name.to_string(),
+                });
+            }
+        }
+    }
+
+    if !summary.changes.is_empty() {
+        let changes = serde_json::json!({ "deviceId": device_id, "changes": summary.changes });
+        let mut user_ids: Vec<String> = summary.changes.iter().map(|c| c.user_id.clone()).collect();
+        user_ids.sort();
+        user_ids.dedup();
+        summary.audit_id = Some(audit::record(&tx, audit::ACTION_DEVICE_USER_MERGE, &changes, &user_ids)?);
+    }
+    tx.commit().map_err(|e| format!("Failed to commit: {}", e))?;
+    Ok(summary)
+}
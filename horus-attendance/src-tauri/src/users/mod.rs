@@ -0,0 +1,21 @@
+//! Bulk changes to users and linking device users to profiles
+//!
+//! Single users are edited by the frontend through the SQL plugin. Moving a
+//! whole department or deactivating a batch that way takes one round trip per
+//! user and can stop halfway, so bulk changes run here in one transaction:
+//! every user is checked first, and if any of them can't be changed nothing
+//! is. Each applied batch is recorded in `user_audit_log`.
+//!
+//! [`linking`] proposes which existing profile each device user created by
+//! sync belongs to, for an admin to confirm in bulk the same way.
+//!
+//! [`merge`] brings the users read in a sync into the table, refreshing
+//! device names without touching the names and details edited here.
+
+pub mod audit;
+pub mod bulk;
+pub mod commands;
+pub mod linking;
+pub mod merge;
+pub mod similarity;
+pub mod types;
@@ -0,0 +1,90 @@
+//! Name similarity for matching device users to profiles
+//!
+//! Terminal names are typed on a keypad and cut to the device's field
+//! length, so the same person can appear as "J. SMITH", "Smith John" or
+//! "Jonathan Smi". Names are compared after normalisation by edit distance,
+//! with word order ignored and truncated names matched by prefix.
+
+use std::collections::HashSet;
+
+/// Shortest name matched as a prefix of a longer one
+const MIN_PREFIX_CHARS: usize = 8;
+
+/// Similarity given to a name that is a prefix of the other
+const PREFIX_SIMILARITY: f64 = 0.9;
+
+/// Lower case words with punctuation removed, in their original order
+fn words(name: &str) -> Vec<String> {
+    name.chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .to_lowercase()
+        .split_whitespace()
+        .map(str::to_string)
+        .collect()
+}
+
+/// The name with case, punctuation, spacing and word order normalised
+pub fn normalize(name: &str) -> String {
+    let mut words = words(name);
+    words.sort();
+    words.join(" ")
+}
+
+fn levenshtein(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+/// 1 minus the edit distance over the longer length
+fn edit_ratio(a: &str, b: &str) -> f64 {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 0.0;
+    }
+    1.0 - levenshtein(&a, &b) as f64 / longest as f64
+}
+
+/// Share of words the names have in common (Dice coefficient)
+fn word_overlap(a: &[String], b: &[String]) -> f64 {
+    let a: HashSet<&String> = a.iter().collect();
+    let b: HashSet<&String> = b.iter().collect();
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    2.0 * a.intersection(&b).count() as f64 / (a.len() + b.len()) as f64
+}
+
+/// How alike two names are, from 0 (nothing in common) to 1 (the same once
+/// normalised)
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let (words_a, words_b) = (words(a), words(b));
+    if words_a.is_empty() || words_b.is_empty() {
+        return 0.0;
+    }
+    let (normal_a, normal_b) = (normalize(a), normalize(b));
+    if normal_a == normal_b {
+        return 1.0;
+    }
+    let (joined_a, joined_b) = (words_a.join(" "), words_b.join(" "));
+    let (shorter, longer) = if joined_a.len() <= joined_b.len() { (&joined_a, &joined_b) } else { (&joined_b, &joined_a) };
+    let prefix = if shorter.chars().count() >= MIN_PREFIX_CHARS && longer.starts_with(shorter.as_str()) {
+        PREFIX_SIMILARITY
+    } else {
+        0.0
+    };
+    edit_ratio(&normal_a, &normal_b)
+        .max(edit_ratio(&joined_a, &joined_b))
+        .max(word_overlap(&words_a, &words_b))
+        .max(prefix)
+}
@@ -0,0 +1,216 @@
+//! Bulk user operation types for Tauri command serialization
+
+use serde::{Deserialize, Serialize};
+
+/// How a user's attendance is treated (users.classification)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UserClassification {
+    /// Judged against their hours: late arrivals, early leaves, absences
+    #[default]
+    Employee,
+    /// Tracked for hours worked only; never late, early or absent
+    Contractor,
+    /// Punches are recorded, but days without any aren't absences
+    Visitor,
+}
+
+impl UserClassification {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Employee => "employee",
+            Self::Contractor => "contractor",
+            Self::Visitor => "visitor",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "employee" => Some(Self::Employee),
+            "contractor" => Some(Self::Contractor),
+            "visitor" => Some(Self::Visitor),
+            _ => None,
+        }
+    }
+}
+
+/// Changes applied to every user of a batch. Fields left out stay as they are.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkUserUpdate {
+    /// Department to move the users to; an empty string removes them from
+    /// their department
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub department_id: Option<String>,
+    /// Site the users work at, for site holidays; an empty string clears it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub site: Option<String>,
+    /// "active" or "inactive"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    /// Shift to assign, replacing the users' current ones
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shift: Option<ShiftTimes>,
+    /// Remove the users' shifts so the global work hours apply again
+    #[serde(default)]
+    pub clear_shift: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub classification: Option<UserClassification>,
+    /// Last day (YYYY-MM-DD) the users' device enrollments are valid; after
+    /// it they are removed from the devices. An empty string clears it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enrollment_expires_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShiftTimes {
+    /// HH:MM
+    pub start_time: String,
+    /// HH:MM
+    pub end_time: String,
+}
+
+/// What happened to one user of a batch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkUserResult {
+    pub user_id: String,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Outcome of a bulk operation. When any user fails, `applied` is false and
+/// no user was changed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkUserOutcome {
+    pub applied: bool,
+    pub succeeded: u32,
+    pub failed: u32,
+    pub results: Vec<BulkUserResult>,
+    /// The user_audit_log entry recording the batch, when applied
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audit_id: Option<i64>,
+}
+
+/// One user_audit_log row
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserAuditEntry {
+    pub id: i64,
+    /// "bulk_update", "bulk_delete" or "link_device_users"
+    pub action: String,
+    /// The requested changes, as JSON
+    pub changes: serde_json::Value,
+    pub user_ids: Vec<String>,
+    pub user_count: u32,
+    pub created_at: String,
+}
+
+/// A profile a device user may belong to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkCandidate {
+    pub user_id: String,
+    pub display_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub employee_code: Option<String>,
+    /// "employee_code", "exact_name" or "fuzzy_name"
+    pub method: String,
+    /// 0..1
+    pub confidence: f64,
+}
+
+/// Proposed link for a device user that has no profile of its own yet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkProposal {
+    pub device_user_id: String,
+    pub device_name: Option<String>,
+    /// The profile created for the device user by sync, removed when the
+    /// link is confirmed
+    pub placeholder_user_id: String,
+    pub best: LinkCandidate,
+    /// Other candidates, most likely first
+    pub alternatives: Vec<LinkCandidate>,
+    /// Another candidate scored about as high as the best one
+    pub ambiguous: bool,
+}
+
+/// A link the admin accepted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkConfirmation {
+    pub device_user_id: String,
+    pub user_id: String,
+    #[serde(default)]
+    pub method: Option<String>,
+    #[serde(default)]
+    pub confidence: Option<f64>,
+}
+
+/// What happened to one confirmed link
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkResult {
+    pub device_user_id: String,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Outcome of confirming links. When any link fails, `applied` is false and
+/// nothing was linked.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkOutcome {
+    pub applied: bool,
+    pub linked: u32,
+    pub failed: u32,
+    pub results: Vec<LinkResult>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audit_id: Option<i64>,
+}
+
+/// One user_device_links row
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceUserLink {
+    pub device_user_id: String,
+    pub user_id: String,
+    pub device_name: Option<String>,
+    pub method: String,
+    pub confidence: Option<f64>,
+    pub created_at: String,
+}
+
+/// A user added or renamed by merging device users
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserMergeChange {
+    /// "added" or "renamed"
+    pub change: String,
+    pub user_id: String,
+    pub device_user_id: String,
+    /// The profile's name, which the merge leaves alone
+    pub display_name: String,
+    pub previous_device_name: Option<String>,
+    pub device_name: String,
+}
+
+/// What merging a device's users changed
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserMergeSummary {
+    /// Distinct device user IDs merged
+    pub device_users: u32,
+    pub added: u32,
+    /// Device name refreshed, on the user or their device link
+    pub renamed: u32,
+    pub unchanged: u32,
+    pub changes: Vec<UserMergeChange>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audit_id: Option<i64>,
+}
@@ -0,0 +1,103 @@
+//! Tauri command handlers for the WAL watchdog.
+
+use std::time::Duration;
+
+use tauri::Manager;
+
+use super::monitor::{self, WalMonitor};
+use super::store::{self, SETTINGS_KEY};
+use super::types::*;
+use crate::db;
+use crate::jobs;
+
+/// ID of the scheduled check in the jobs registry
+pub const JOB_ID: &str = "wal_watchdog";
+
+fn interval(settings: &WalSettings) -> Duration {
+    Duration::from_secs(settings.check_minutes.clamp(1, 24 * 60) as u64 * 60)
+}
+
+#[tauri::command]
+pub async fn get_wal_settings(app: tauri::AppHandle) -> Result<WalSettings, String> {
+    store::load_settings(&*db::open(&app)?)
+}
+
+/// Save the settings; a changed interval applies after the next check
+#[tauri::command]
+pub async fn save_wal_settings(app: tauri::AppHandle, settings: WalSettings) -> Result<(), String> {
+    if !(1..=1440).contains(&settings.check_minutes) {
+        return Err("Check interval must be between 1 minute and 24 hours".to_string());
+    }
+    if !(1..=4096).contains(&settings.truncate_mb) {
+        return Err("Truncate size must be between 1 and 4096 MB".to_string());
+    }
+    if settings.alert_mb < settings.truncate_mb || settings.alert_mb > 65536 {
+        return Err("Alert size must be at least the truncate size and at most 65536 MB".to_string());
+    }
+    let json = serde_json::to_string(&settings).map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    log::info!(
+        "[wal::cmd] WAL watchdog {} (every {} min, truncate above {} MB, alert above {} MB)",
+        if settings.enabled { "on" } else { "off" },
+        settings.check_minutes,
+        settings.truncate_mb,
+        settings.alert_mb
+    );
+    db::set_setting(&*db::open(&app)?, SETTINGS_KEY, &json)
+}
+
+/// WAL size now, the watchdog's counters and its recent checkpoints
+#[tauri::command]
+pub async fn get_wal_status(app: tauri::AppHandle) -> Result<WalStatus, String> {
+    let settings = store::load_settings(&*db::open(&app)?)?;
+    app.state::<WalMonitor>().status(&app, settings)
+}
+
+/// Checkpoint the WAL now, truncating it when `truncate` is set. Unlike the
+/// scheduled check this doesn't wait for the app to be idle.
+#[tauri::command]
+pub async fn checkpoint_wal(app: tauri::AppHandle, truncate: Option<bool>) -> Result<WalCheck, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let settings = store::load_settings(&*db::open(&app)?)?;
+        let mode = if truncate.unwrap_or(false) { monitor::MODE_TRUNCATE } else { monitor::MODE_PASSIVE };
+        monitor::check(&app, &settings, Some(mode))
+    })
+    .await
+    .map_err(|e| format!("Checkpoint task failed: {}", e))?
+}
+
+/// Check the WAL on the interval in the settings while the watchdog is
+/// enabled. Runs for the lifetime of the app.
+pub async fn run_scheduled(app: tauri::AppHandle) {
+    let load = |app: &tauri::AppHandle| {
+        db::open(app)
+            .and_then(|conn| store::load_settings(&conn))
+            .unwrap_or_default()
+    };
+    let job = jobs::registry::register(
+        &app,
+        JOB_ID,
+        "WAL watchdog",
+        "Checkpoints the database's write-ahead log while the app is idle and warns when it grows large",
+        interval(&load(&app)),
+    );
+    let handle = app.clone();
+    jobs::registry::drive_blocking(
+        job.with_interval_from(move || interval(&load(&app))),
+        move |_| {
+            // No database yet on first launch
+            if !crate::get_db_path(&handle)?.exists() {
+                return Ok(None);
+            }
+            let settings = store::load_settings(&*db::open(&handle)?)?;
+            if !settings.enabled {
+                return Ok(None);
+            }
+            match monitor::check(&handle, &settings, None)? {
+                WalCheck { error: Some(e), .. } => Err(e),
+                check => Ok(Some(check)),
+            }
+        },
+        |_| {},
+    )
+    .await;
+}
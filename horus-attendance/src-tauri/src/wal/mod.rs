@@ -0,0 +1,16 @@
+//! Write-ahead log checkpointing and size watchdog
+//!
+//! SQLite only folds the WAL back into the database when a checkpoint gets
+//! to the end of it, which never happens while some connection keeps a read
+//! open, and even then the file keeps its size. Installs that run for months
+//! ended up with WAL files of hundreds of MB. A background job now checks the
+//! WAL every few minutes (`walWatchdog` setting): while nothing is writing
+//! (see `activity`) it runs a checkpoint, truncating the file once it has
+//! grown past a threshold; while work is running it only checkpoints
+//! passively, and only once the WAL is past the alert size. Recent checks are
+//! kept in memory for the status view and logged.
+
+pub mod commands;
+pub mod monitor;
+pub mod store;
+pub mod types;
@@ -0,0 +1,186 @@
+//! WAL checks and their counters, held as Tauri managed state
+
+use std::collections::VecDeque;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use tauri::Manager;
+
+use super::types::{WalCheck, WalSettings, WalStatus};
+use crate::activity::registry::ActivityRegistry;
+use crate::db;
+
+pub const MODE_PASSIVE: &str = "passive";
+pub const MODE_TRUNCATE: &str = "truncate";
+pub const MODE_NONE: &str = "none";
+
+/// Checks kept for the status view
+const KEEP_RECENT: usize = 50;
+
+const MB: u64 = 1024 * 1024;
+
+#[derive(Default)]
+struct Counters {
+    peak_wal_bytes: u64,
+    checkpoints: u64,
+    truncations: u64,
+    busy_checkpoints: u64,
+    recent: VecDeque<WalCheck>,
+}
+
+/// What the watchdog has seen and done since the app started
+#[derive(Default)]
+pub struct WalMonitor(Mutex<Counters>);
+
+impl WalMonitor {
+    fn record(&self, check: &WalCheck) {
+        let Ok(mut counters) = self.0.lock() else {
+            return;
+        };
+        counters.peak_wal_bytes = counters.peak_wal_bytes.max(check.wal_bytes_before);
+        if check.mode != MODE_NONE && check.error.is_none() {
+            counters.checkpoints += 1;
+            if check.busy {
+                counters.busy_checkpoints += 1;
+            } else if check.mode == MODE_TRUNCATE {
+                counters.truncations += 1;
+            }
+        }
+        if check.mode != MODE_NONE {
+            counters.recent.push_front(check.clone());
+            counters.recent.truncate(KEEP_RECENT);
+        }
+    }
+
+    /// Current WAL size with the counters and recent checkpoints
+    pub fn status(&self, app: &tauri::AppHandle, settings: WalSettings) -> Result<WalStatus, String> {
+        let path = crate::get_db_path(app)?;
+        let wal_bytes = file_size(&wal_path(&path));
+        let counters = self.0.lock().map_err(|_| "WAL monitor is poisoned".to_string())?;
+        Ok(WalStatus {
+            settings,
+            wal_bytes,
+            database_bytes: file_size(&path),
+            peak_wal_bytes: counters.peak_wal_bytes.max(wal_bytes),
+            checkpoints: counters.checkpoints,
+            truncations: counters.truncations,
+            busy_checkpoints: counters.busy_checkpoints,
+            recent: counters.recent.iter().cloned().collect(),
+        })
+    }
+}
+
+/// `<database>-wal`
+fn wal_path(path: &Path) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push("-wal");
+    PathBuf::from(name)
+}
+
+fn file_size(path: &Path) -> u64 {
+    fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+/// Which checkpoint the scheduled check runs for a WAL of `wal_bytes`
+fn scheduled_mode(settings: &WalSettings, wal_bytes: u64, idle: bool) -> &'static str {
+    if wal_bytes == 0 {
+        MODE_NONE
+    } else if idle && wal_bytes >= settings.truncate_mb as u64 * MB {
+        MODE_TRUNCATE
+    } else if idle || wal_bytes >= settings.alert_mb as u64 * MB {
+        // A passive checkpoint never waits on readers or writers
+        MODE_PASSIVE
+    } else {
+        MODE_NONE
+    }
+}
+
+/// Check the WAL and checkpoint it. `mode` forces a checkpoint ("passive"
+/// or "truncate"); without it the mode follows the settings and whether
+/// anything is writing. Blocks; a truncating checkpoint waits up to the
+/// busy timeout for other connections.
+pub fn check(app: &tauri::AppHandle, settings: &WalSettings, mode: Option<&str>) -> Result<WalCheck, String> {
+    let path = crate::get_db_path(app)?;
+    let wal = wal_path(&path);
+    let activity = app.state::<ActivityRegistry>().status()?;
+    let idle = activity.active.is_empty() && activity.paused_for.is_none();
+    let wal_bytes_before = file_size(&wal);
+    let mut result = WalCheck {
+        checked_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        mode: MODE_NONE.to_string(),
+        idle,
+        wal_bytes_before,
+        wal_bytes_after: wal_bytes_before,
+        log_frames: 0,
+        checkpointed_frames: 0,
+        busy: false,
+        duration_ms: 0,
+        error: None,
+    };
+
+    // Backup and restore checkpoint on their own while they hold work off
+    let mode = match mode {
+        Some(mode) => mode,
+        None if activity.paused_for.is_some() || !path.exists() => MODE_NONE,
+        None => scheduled_mode(settings, wal_bytes_before, idle),
+    };
+    if mode != MODE_NONE {
+        let pragma = match mode {
+            MODE_TRUNCATE => "PRAGMA wal_checkpoint(TRUNCATE)",
+            MODE_PASSIVE => "PRAGMA wal_checkpoint(PASSIVE)",
+            other => return Err(format!("Unknown checkpoint mode: {}", other)),
+        };
+        result.mode = mode.to_string();
+        let started = Instant::now();
+        let checkpoint = db::open(app).and_then(|conn| {
+            conn.query_row(pragma, [], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, row.get::<_, i64>(2)?)))
+                .map_err(|e| format!("Failed to checkpoint the WAL: {}", e))
+        });
+        result.duration_ms = started.elapsed().as_millis() as u64;
+        match checkpoint {
+            // A passive checkpoint reports busy = 0 even when readers kept
+            // it from the end of the log
+            Ok((busy, log, checkpointed)) => {
+                result.busy = busy != 0 || checkpointed < log;
+                result.log_frames = log.max(0);
+                result.checkpointed_frames = checkpointed.max(0);
+            }
+            Err(e) => result.error = Some(e),
+        }
+        result.wal_bytes_after = file_size(&wal);
+    }
+
+    app.state::<WalMonitor>().record(&result);
+    log_check(settings, &result);
+    Ok(result)
+}
+
+fn log_check(settings: &WalSettings, check: &WalCheck) {
+    let (before, after) = (check.wal_bytes_before / 1024, check.wal_bytes_after / 1024);
+    if let Some(e) = &check.error {
+        log::warn!("[wal] {} checkpoint of a {} KB WAL failed: {}", check.mode, before, e);
+    } else if check.mode == MODE_TRUNCATE || check.busy {
+        log::info!(
+            "[wal] {} checkpoint: {} of {} frames in {} ms, WAL {} KB -> {} KB{}",
+            check.mode,
+            check.checkpointed_frames,
+            check.log_frames,
+            check.duration_ms,
+            before,
+            after,
+            if check.busy { " (another connection kept it from finishing)" } else { "" }
+        );
+    } else if check.mode != MODE_NONE {
+        log::debug!("[wal] {} checkpoint of {} frames in {} ms", check.mode, check.log_frames, check.duration_ms);
+    }
+    if check.wal_bytes_after >= settings.alert_mb as u64 * MB {
+        log::warn!(
+            "[wal] WAL is {} MB, above the {} MB alert size; a long-running read may be holding it",
+            check.wal_bytes_after / MB,
+            settings.alert_mb
+        );
+    }
+}
@@ -0,0 +1,14 @@
+//! WAL watchdog settings
+
+use rusqlite::Connection;
+
+use super::types::WalSettings;
+use crate::db;
+
+/// Settings key of the WAL watchdog settings
+pub const SETTINGS_KEY: &str = "walWatchdog";
+
+/// Load the settings, falling back to defaults (on)
+pub fn load_settings(conn: &Connection) -> Result<WalSettings, String> {
+    Ok(db::get_json_setting(conn, SETTINGS_KEY)?.unwrap_or_default())
+}
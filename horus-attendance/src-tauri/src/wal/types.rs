@@ -0,0 +1,65 @@
+//! WAL watchdog data types for Tauri command serialization
+
+use serde::{Deserialize, Serialize};
+
+/// WAL watchdog settings (`walWatchdog` setting)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WalSettings {
+    pub enabled: bool,
+    /// Minutes between checks of the WAL size
+    pub check_minutes: u32,
+    /// WAL size (MB) above which an idle checkpoint also truncates the file
+    pub truncate_mb: u32,
+    /// WAL size (MB) that is logged as a warning, and checkpointed even
+    /// while work is running
+    pub alert_mb: u32,
+}
+
+impl Default for WalSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            check_minutes: 5,
+            truncate_mb: 32,
+            alert_mb: 256,
+        }
+    }
+}
+
+/// One check of the WAL, and the checkpoint it ran if any
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WalCheck {
+    pub checked_at: String,
+    /// "passive", "truncate" or "none"
+    pub mode: String,
+    /// Nothing was writing to the database
+    pub idle: bool,
+    pub wal_bytes_before: u64,
+    pub wal_bytes_after: u64,
+    /// Frames in the WAL and how many of them the checkpoint copied
+    pub log_frames: i64,
+    pub checkpointed_frames: i64,
+    /// The checkpoint couldn't finish because a connection was reading or writing
+    pub busy: bool,
+    pub duration_ms: u64,
+    pub error: Option<String>,
+}
+
+/// WAL size now and what the watchdog has done since the app started
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WalStatus {
+    pub settings: WalSettings,
+    pub wal_bytes: u64,
+    pub database_bytes: u64,
+    /// Largest WAL seen since the app started
+    pub peak_wal_bytes: u64,
+    pub checkpoints: u64,
+    pub truncations: u64,
+    /// Checkpoints that couldn't finish
+    pub busy_checkpoints: u64,
+    /// Newest first
+    pub recent: Vec<WalCheck>,
+}
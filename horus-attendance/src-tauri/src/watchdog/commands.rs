@@ -0,0 +1,97 @@
+//! Tauri command handlers for the stale device watchdog.
+
+use chrono::Local;
+
+use super::notify;
+use super::store::{self, SETTINGS_KEY};
+use super::types::*;
+use crate::activity::registry;
+use crate::db;
+use crate::deliveries::webhook;
+use crate::jobs;
+
+/// How often the scheduled job checks whether today's check is due
+const CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(3600);
+
+/// ID of the scheduled check in the jobs registry
+pub const JOB_ID: &str = "device_watchdog";
+
+#[tauri::command]
+pub async fn get_watchdog_settings(app: tauri::AppHandle) -> Result<WatchdogSettings, String> {
+    store::load_settings(&*db::open(&app)?)
+}
+
+/// Save the watchdog settings. Emails use the mail server set up for
+/// closure emails.
+#[tauri::command]
+pub async fn save_watchdog_settings(app: tauri::AppHandle, settings: WatchdogSettings) -> Result<(), String> {
+    if !(1..=365).contains(&settings.threshold_days) {
+        return Err("Threshold must be between 1 and 365 days".to_string());
+    }
+    if !(1..=90).contains(&settings.repeat_days) {
+        return Err("Repeat interval must be between 1 and 90 days".to_string());
+    }
+    if !settings.webhook_url.trim().is_empty() {
+        webhook::validate_url(&settings.webhook_url)?;
+    }
+    if let Some(bad) = settings.email_recipients.iter().find(|r| !r.trim().is_empty() && !r.contains('@')) {
+        return Err(format!("Invalid email address: {}", bad));
+    }
+    let json = serde_json::to_string(&settings).map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    log::info!(
+        "[watchdog::cmd] Device watchdog {} ({} days)",
+        if settings.enabled { "on" } else { "off" },
+        settings.threshold_days
+    );
+    db::set_setting(&*db::open(&app)?, SETTINGS_KEY, &json)
+}
+
+/// Devices that haven't synced within the threshold. With `notify`, those
+/// due an alert are escalated as the daily check would.
+#[tauri::command]
+pub async fn check_stale_devices(app: tauri::AppHandle, notify: Option<bool>) -> Result<WatchdogRun, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let conn = db::open(&app)?;
+        let settings = store::load_settings(&conn)?;
+        notify::run(&app, &conn, &settings, notify.unwrap_or(false))
+    })
+    .await
+    .map_err(|e| format!("Watchdog task failed: {}", e))?
+}
+
+/// Check devices once a day while the watchdog is enabled, escalating those
+/// gone stale. Runs for the lifetime of the app.
+pub async fn run_scheduled(app: tauri::AppHandle) {
+    let job = jobs::registry::register(
+        &app,
+        JOB_ID,
+        "Device watchdog",
+        "Escalates devices that haven't synced within the threshold, once a day",
+        CHECK_INTERVAL,
+    );
+    let handle = app.clone();
+    jobs::registry::drive_blocking(
+        job,
+        // Run now checks again even if today's check is done
+        move |forced| {
+            let conn = db::open(&handle)?;
+            let settings = store::load_settings(&conn)?;
+            let today = Local::now().format("%Y-%m-%d").to_string();
+            let last: Option<String> = db::get_json_setting(&conn, store::LAST_RUN_KEY)?;
+            if !settings.enabled || (!forced && last.as_deref() >= Some(today.as_str())) {
+                return Ok(None);
+            }
+            let _activity = registry::begin(&handle, "Device watchdog")?;
+            let run = notify::run(&handle, &conn, &settings, true)?;
+            let json = serde_json::to_string(&today).map_err(|e| format!("Failed to serialize date: {}", e))?;
+            db::set_setting(&conn, store::LAST_RUN_KEY, &json)?;
+            Ok(Some(run))
+        },
+        |run| {
+            if let Some(run) = run.filter(|run| !run.stale.is_empty()) {
+                log::info!("[watchdog] {} stale devices, {} escalated", run.stale.len(), run.escalated.len());
+            }
+        },
+    )
+    .await;
+}
@@ -0,0 +1,17 @@
+//! Stale device watchdog
+//!
+//! A terminal that stops answering is easy to miss: nothing fails loudly,
+//! its punches just never arrive. Once a day the watchdog looks at when each
+//! device was last synced, and any device that hasn't been for longer than
+//! the threshold set under the `deviceWatchdog` setting is escalated: the
+//! app shows a notification (`device-stale`), and a webhook call and emails
+//! go out when configured. Those are logged in the outbound delivery log and
+//! retried like any other delivery.
+//!
+//! A device is escalated once when it goes stale and again every few days
+//! while it stays that way; a sync clears its alert.
+
+pub mod commands;
+pub mod notify;
+pub mod store;
+pub mod types;
@@ -0,0 +1,158 @@
+//! Escalating stale devices
+
+use chrono::Utc;
+use rusqlite::Connection;
+use tauri::Emitter;
+
+use super::store;
+use super::types::*;
+use crate::closure::mail::Mailer;
+use crate::deliveries;
+use crate::deliveries::types::NewDelivery;
+
+/// Payload kind of watchdog alerts in the delivery log
+pub const DELIVERY_KIND: &str = "device_stale";
+
+/// Event name of the in-app notification
+pub const STALE_EVENT: &str = "device-stale";
+
+fn sync_description(device: &StaleDevice) -> String {
+    match &device.last_sync_at {
+        Some(at) => format!("last synced {} ({} days ago)", at, device.days_since_sync),
+        None => format!("never synced since it was added {} days ago", device.days_since_sync),
+    }
+}
+
+fn webhook_body(device: &StaleDevice, threshold_days: u32) -> serde_json::Value {
+    serde_json::json!({
+        "event": "device.stale",
+        "deviceId": device.device_id,
+        "deviceName": device.device_name,
+        "ip": device.ip,
+        "lastSyncAt": device.last_sync_at,
+        "daysSinceSync": device.days_since_sync,
+        "thresholdDays": threshold_days,
+    })
+}
+
+fn email(device: &StaleDevice, threshold_days: u32) -> (String, String) {
+    let subject = format!("Attendance device '{}' has stopped syncing", device.device_name);
+    let body = format!(
+        "The attendance device '{}' ({}) has {}, more than the {} days allowed.\n\n\
+         Punches made on it since are not in the attendance records. Check that the \
+         terminal is powered on and reachable on the network, then sync it from the app.",
+        device.device_name,
+        device.ip,
+        sync_description(device),
+        threshold_days
+    );
+    (subject, body)
+}
+
+/// Log and send one delivery; returns whether it went through
+fn deliver(
+    conn: &Connection,
+    channel: &'static str,
+    target: &str,
+    device: &StaleDevice,
+    payload: serde_json::Value,
+    send: impl FnOnce() -> Result<(), String>,
+) -> Result<bool, String> {
+    let id = deliveries::store::begin(
+        conn,
+        &NewDelivery {
+            channel,
+            target,
+            summary: format!("Device '{}' not synced for {} days", device.device_name, device.days_since_sync),
+            payload,
+        },
+    )?;
+    let sent = send();
+    deliveries::store::finish(conn, id, &sent)?;
+    if let Err(e) = &sent {
+        log::warn!("[watchdog] Alert for {} to {} failed: {}", device.device_id, target, e);
+    }
+    Ok(sent.is_ok())
+}
+
+/// Notify the app, the webhook and the email recipients about `device`.
+/// Returns how many deliveries failed. Blocks on the network.
+fn escalate(
+    app: &tauri::AppHandle,
+    conn: &Connection,
+    settings: &WatchdogSettings,
+    device: &StaleDevice,
+) -> Result<u32, String> {
+    log::warn!("[watchdog] Device {} ({}) {}", device.device_name, device.device_id, sync_description(device));
+    if let Err(e) = app.emit(STALE_EVENT, device) {
+        log::warn!("[watchdog] Failed to emit {}: {}", STALE_EVENT, e);
+    }
+    let mut failed = 0;
+    let url = settings.webhook_url.trim();
+    if !url.is_empty() {
+        let body = webhook_body(device, settings.threshold_days);
+        let payload = serde_json::json!({ "kind": DELIVERY_KIND, "body": body });
+        let sent = deliver(conn, deliveries::store::CHANNEL_WEBHOOK, url, device, payload, || {
+            deliveries::webhook::post(url, &body)
+        })?;
+        failed += u32::from(!sent);
+    }
+    let recipients: Vec<&str> = settings
+        .email_recipients
+        .iter()
+        .map(|r| r.trim())
+        .filter(|r| !r.is_empty())
+        .collect();
+    if !recipients.is_empty() {
+        let (subject, body) = email(device, settings.threshold_days);
+        let mailer = Mailer::new(&crate::closure::store::load_settings(conn)?.smtp);
+        for to in recipients {
+            let payload = serde_json::json!({ "kind": DELIVERY_KIND, "subject": subject, "body": body });
+            let sent = deliver(conn, deliveries::store::CHANNEL_EMAIL, to, device, payload, || {
+                mailer.as_ref().map_err(|e| e.clone())?.send(to, &subject, &body, None)
+            })?;
+            failed += u32::from(!sent);
+        }
+    }
+    Ok(failed)
+}
+
+/// Find stale devices and, when `notify`, escalate those due an alert.
+/// Blocks on the network.
+pub fn run(app: &tauri::AppHandle, conn: &Connection, settings: &WatchdogSettings, notify: bool) -> Result<WatchdogRun, String> {
+    let now = Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let stale = store::stale(conn, settings.threshold_days.max(1), &now)?;
+    store::clear_recovered(conn, &stale)?;
+    let mut escalated = Vec::new();
+    let mut failed_deliveries = 0;
+    if notify {
+        for device in &stale {
+            if !store::alert_due(conn, device, settings.repeat_days, &now)? {
+                continue;
+            }
+            failed_deliveries += escalate(app, conn, settings, device)?;
+            store::record_alert(conn, device, &now)?;
+            escalated.push(device.device_id.clone());
+        }
+    }
+    Ok(WatchdogRun {
+        checked_at: now,
+        threshold_days: settings.threshold_days,
+        stale,
+        escalated,
+        failed_deliveries,
+    })
+}
+
+/// Send a logged watchdog alert again. Blocks on the network.
+pub fn redeliver(conn: &Connection, channel: &str, target: &str, payload: &serde_json::Value) -> Result<(), String> {
+    match channel {
+        deliveries::store::CHANNEL_WEBHOOK => deliveries::webhook::post(target, &payload["body"]),
+        deliveries::store::CHANNEL_EMAIL => {
+            let subject = payload["subject"].as_str().ok_or("Delivery has no subject")?;
+            let body = payload["body"].as_str().ok_or("Delivery has no body")?;
+            Mailer::new(&crate::closure::store::load_settings(conn)?.smtp)?.send(target, subject, body, None)
+        }
+        other => Err(format!("Watchdog alerts aren't sent by {}", other)),
+    }
+}
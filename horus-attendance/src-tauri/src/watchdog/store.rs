@@ -0,0 +1,93 @@
+//! Watchdog settings and alert state
+
+use rusqlite::{params, Connection};
+
+use super::types::*;
+use crate::db;
+
+/// Settings key of the watchdog settings
+pub const SETTINGS_KEY: &str = "deviceWatchdog";
+
+/// Settings key holding the date of the last scheduled check (YYYY-MM-DD)
+pub const LAST_RUN_KEY: &str = "deviceWatchdogLastRun";
+
+/// Load the settings, falling back to defaults (off)
+pub fn load_settings(conn: &Connection) -> Result<WatchdogSettings, String> {
+    Ok(db::get_json_setting(conn, SETTINGS_KEY)?.unwrap_or_default())
+}
+
+/// Real devices not synced for `threshold_days` or more as of `now` (UTC,
+/// `YYYY-MM-DD HH:MM:SS`), longest first. Pseudo-devices (API punches,
+/// corrections) have no address and are left out.
+pub fn stale(conn: &Connection, threshold_days: u32, now: &str) -> Result<Vec<StaleDevice>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT d.id, d.name, d.ip, d.last_sync_at,
+                    CAST(julianday(?1) - julianday(COALESCE(d.last_sync_at, d.created_at)) AS INTEGER) AS days,
+                    a.first_alerted_at, a.last_alerted_at
+             FROM devices d
+             LEFT JOIN device_staleness_alerts a
+                 ON a.device_id = d.id AND a.last_sync_at IS d.last_sync_at
+             WHERE d.ip != ''
+               AND julianday(?1) - julianday(COALESCE(d.last_sync_at, d.created_at)) >= ?2
+             ORDER BY days DESC, d.name",
+        )
+        .map_err(|e| format!("Failed to query devices: {}", e))?;
+    let rows = stmt
+        .query_map(params![now, threshold_days], |row| {
+            Ok(StaleDevice {
+                device_id: row.get(0)?,
+                device_name: row.get(1)?,
+                ip: row.get(2)?,
+                last_sync_at: row.get(3)?,
+                days_since_sync: row.get(4)?,
+                first_alerted_at: row.get(5)?,
+                last_alerted_at: row.get(6)?,
+            })
+        })
+        .map_err(|e| format!("Failed to query devices: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read devices: {}", e))
+}
+
+/// Forget alerts of devices that synced since, or are no longer in `stale`
+pub fn clear_recovered(conn: &Connection, stale: &[StaleDevice]) -> Result<(), String> {
+    let ids = serde_json::to_string(&stale.iter().map(|d| &d.device_id).collect::<Vec<_>>())
+        .map_err(|e| format!("Failed to serialize device IDs: {}", e))?;
+    conn.execute(
+        "DELETE FROM device_staleness_alerts
+         WHERE device_id NOT IN (SELECT value FROM json_each(?1))
+            OR last_sync_at IS NOT (SELECT last_sync_at FROM devices WHERE id = device_id)",
+        [ids],
+    )
+    .map_err(|e| format!("Failed to clear device alerts: {}", e))?;
+    Ok(())
+}
+
+/// The device was never escalated, or not for `repeat_days`
+pub fn alert_due(conn: &Connection, device: &StaleDevice, repeat_days: u32, now: &str) -> Result<bool, String> {
+    let Some(last) = &device.last_alerted_at else {
+        return Ok(true);
+    };
+    conn.query_row(
+        "SELECT julianday(?1) - julianday(?2) >= ?3",
+        params![now, last, repeat_days.max(1)],
+        |row| row.get(0),
+    )
+    .map_err(|e| format!("Failed to compare alert times: {}", e))
+}
+
+/// Record that `device` was escalated at `now`
+pub fn record_alert(conn: &Connection, device: &StaleDevice, now: &str) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO device_staleness_alerts (device_id, last_sync_at, first_alerted_at, last_alerted_at)
+         VALUES (?1, ?2, ?3, ?3)
+         ON CONFLICT(device_id) DO UPDATE SET
+             last_sync_at = excluded.last_sync_at,
+             alert_count = alert_count + 1,
+             last_alerted_at = excluded.last_alerted_at",
+        params![device.device_id, device.last_sync_at, now],
+    )
+    .map_err(|e| format!("Failed to record device alert: {}", e))?;
+    Ok(())
+}
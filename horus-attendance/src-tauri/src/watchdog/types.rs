@@ -0,0 +1,58 @@
+//! Watchdog data types for Tauri command serialization
+
+use serde::{Deserialize, Serialize};
+
+/// Watchdog settings (`deviceWatchdog` setting)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WatchdogSettings {
+    pub enabled: bool,
+    /// Days without a sync after which a device is escalated
+    pub threshold_days: u32,
+    /// Days between repeated escalations while a device stays stale
+    pub repeat_days: u32,
+    /// URL posted a JSON alert; empty for none
+    pub webhook_url: String,
+    /// Addresses emailed an alert, sent with the closure email mail server
+    pub email_recipients: Vec<String>,
+}
+
+impl Default for WatchdogSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold_days: 3,
+            repeat_days: 7,
+            webhook_url: String::new(),
+            email_recipients: Vec::new(),
+        }
+    }
+}
+
+/// A device that hasn't synced within the threshold
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StaleDevice {
+    pub device_id: String,
+    pub device_name: String,
+    pub ip: String,
+    /// None if it was never synced; then staleness counts from when it was added
+    pub last_sync_at: Option<String>,
+    pub days_since_sync: i64,
+    /// When it was first escalated, if it was
+    pub first_alerted_at: Option<String>,
+    pub last_alerted_at: Option<String>,
+}
+
+/// Result of one watchdog check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchdogRun {
+    pub checked_at: String,
+    pub threshold_days: u32,
+    pub stale: Vec<StaleDevice>,
+    /// Devices escalated by this check
+    pub escalated: Vec<String>,
+    /// Webhook calls and emails that failed; they are retried from the delivery log
+    pub failed_deliveries: u32,
+}
@@ -0,0 +1,304 @@
+//! Checking shifts against working-time limits
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::{Datelike, Duration, NaiveDate};
+use rusqlite::{params_from_iter, types::Value, Connection};
+use serde_json::json;
+
+use super::types::*;
+use crate::analytics::scope;
+use crate::analytics::types::{DateRange, Scope};
+use crate::db;
+use crate::deliveries;
+use crate::deliveries::types::NewDelivery;
+use crate::exceptions::store as exceptions;
+use crate::exceptions::types::NewException;
+use crate::periods::calc::{self as period_calc, parse_date};
+use crate::summary::engine;
+use crate::summary::rules::{day_minutes, span_minutes};
+
+pub const SETTINGS_KEY: &str = "workingTime";
+
+pub const KIND_MAX_DAILY_HOURS: &str = "max_daily_hours";
+pub const KIND_MAX_WEEKLY_HOURS: &str = "max_weekly_hours";
+pub const KIND_MIN_REST: &str = "min_rest";
+
+/// Payload kind of violation alerts in the delivery log
+pub const DELIVERY_KIND: &str = "working_time_violation";
+
+pub fn load_settings(conn: &Connection) -> Result<WorkingTimeSettings, String> {
+    Ok(db::get_json_setting(conn, SETTINGS_KEY)?.unwrap_or_default())
+}
+
+pub fn validate(settings: &WorkingTimeSettings) -> Result<(), String> {
+    let limits = [
+        ("Maximum daily hours", settings.max_daily_hours, 24.0),
+        ("Maximum weekly hours", settings.max_weekly_hours, 168.0),
+        ("Minimum rest", settings.min_rest_hours, 48.0),
+    ];
+    for (name, hours, max) in limits {
+        if let Some(hours) = hours {
+            if !(hours > 0.0 && hours <= max) {
+                return Err(format!("{} must be between 0 and {} hours", name, max));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A day's complete shift
+struct Shift {
+    user_id: String,
+    device_user_id: Option<String>,
+    user_name: String,
+    employee_code: Option<String>,
+    date: NaiveDate,
+    /// Minutes since 0001-01-01 of the check-in
+    start: i64,
+    minutes: i64,
+}
+
+impl Shift {
+    fn end(&self) -> i64 {
+        self.start + self.minutes
+    }
+
+    fn violation(&self, kind: &str, date: NaiveDate, minutes: i64, limit_hours: f64) -> WorkingTimeViolation {
+        WorkingTimeViolation {
+            kind: kind.to_string(),
+            user_id: self.user_id.clone(),
+            device_user_id: self.device_user_id.clone(),
+            user_name: self.user_name.clone(),
+            employee_code: self.employee_code.clone(),
+            date: date.format("%Y-%m-%d").to_string(),
+            hours: round1(minutes as f64 / 60.0),
+            limit_hours,
+        }
+    }
+}
+
+/// Every violation of the limits in a period
+pub fn report(conn: &Connection, period: &DateRange, scope: &Scope) -> Result<WorkingTimeReport, String> {
+    let settings = load_settings(conn)?;
+    let week_settings = period_calc::load_settings(conn)?;
+    let start = parse_date(&period.start_date)?;
+    let end = parse_date(&period.end_date)?;
+    // Whole weeks for the weekly totals, and the day before for the rest
+    // before the first shift
+    let load_start = period_calc::week_start(&week_settings, start).min(start - Duration::days(1));
+    let load_end = period_calc::week_start(&week_settings, end) + Duration::days(6);
+
+    let mut users = BTreeSet::new();
+    let mut shifts_checked = 0;
+    let mut violations = Vec::new();
+    let by_user = shifts(conn, load_start, load_end, scope)?;
+    for user_shifts in by_user.values() {
+        let mut weeks: BTreeMap<NaiveDate, (i64, &Shift)> = BTreeMap::new();
+        let mut previous: Option<&Shift> = None;
+        for shift in user_shifts {
+            let in_period = shift.date >= start && shift.date <= end;
+            if in_period {
+                users.insert(shift.user_id.as_str());
+                shifts_checked += 1;
+                if let Some(max) = settings.max_daily_hours {
+                    if shift.minutes as f64 > max * 60.0 {
+                        violations.push(shift.violation(KIND_MAX_DAILY_HOURS, shift.date, shift.minutes, max));
+                    }
+                }
+                if let (Some(min), Some(previous)) = (settings.min_rest_hours, previous) {
+                    let rest = (shift.start - previous.end()).max(0);
+                    if (rest as f64) < min * 60.0 {
+                        violations.push(shift.violation(KIND_MIN_REST, shift.date, rest, min));
+                    }
+                }
+            }
+            let week = weeks
+                .entry(period_calc::week_start(&week_settings, shift.date))
+                .or_insert((0, shift));
+            week.0 += shift.minutes;
+            previous = Some(shift);
+        }
+        if let Some(max) = settings.max_weekly_hours {
+            for (week_start, (minutes, shift)) in weeks {
+                let overlaps = week_start <= end && week_start + Duration::days(6) >= start;
+                if overlaps && minutes as f64 > max * 60.0 {
+                    violations.push(shift.violation(KIND_MAX_WEEKLY_HOURS, week_start, minutes, max));
+                }
+            }
+        }
+    }
+    violations.sort_by(|a, b| (&a.date, &a.user_name, &a.kind).cmp(&(&b.date, &b.user_name, &b.kind)));
+
+    Ok(WorkingTimeReport {
+        period: period.clone(),
+        settings,
+        users_checked: users.len() as i64,
+        shifts_checked,
+        violations,
+    })
+}
+
+/// Complete shifts between two dates by user, oldest first
+fn shifts(conn: &Connection, start: NaiveDate, end: NaiveDate, scope: &Scope) -> Result<BTreeMap<String, Vec<Shift>>, String> {
+    let rules = engine::load_rules(conn)?;
+    let (user_sql, user_params) = scope::condition(scope, "s.user_id", "id", 3);
+    let sql = format!(
+        "SELECT s.user_id, u.device_user_id, u.display_name, u.employee_code, s.date,
+                s.check_in_time, s.check_out_time
+         FROM attendance_day_summary s
+         JOIN users u ON u.id = s.user_id
+         WHERE s.date BETWEEN ?1 AND ?2
+           AND s.check_in_time IS NOT NULL AND s.check_out_time IS NOT NULL
+           AND {}
+         ORDER BY s.user_id, s.date",
+        user_sql
+    );
+    let mut params = vec![
+        Value::Text(start.format("%Y-%m-%d").to_string()),
+        Value::Text(end.format("%Y-%m-%d").to_string()),
+    ];
+    params.extend(user_params);
+
+    let mut stmt = conn
+        .prepare(&sql)
+        .map_err(|e| format!("Failed to query shifts: {}", e))?;
+    let rows = stmt
+        .query_map(params_from_iter(params), |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<String>>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, Option<String>>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, String>(5)?,
+                row.get::<_, String>(6)?,
+            ))
+        })
+        .map_err(|e| format!("Failed to query shifts: {}", e))?;
+
+    let mut by_user: BTreeMap<String, Vec<Shift>> = BTreeMap::new();
+    for row in rows {
+        let (user_id, device_user_id, user_name, employee_code, date, check_in, check_out) =
+            row.map_err(|e| format!("Failed to read shifts: {}", e))?;
+        let minutes = span_minutes(&check_in, &check_out);
+        if minutes == 0 {
+            continue;
+        }
+        let date = parse_date(&date)?;
+        let start = i64::from(date.num_days_from_ce()) * 24 * 60 + day_minutes(&check_in, &rules);
+        by_user.entry(user_id.clone()).or_default().push(Shift {
+            user_id,
+            device_user_id,
+            user_name,
+            employee_code,
+            date,
+            start,
+            minutes,
+        });
+    }
+    Ok(by_user)
+}
+
+fn finding(violation: &WorkingTimeViolation) -> NewException {
+    let (kind, message) = match violation.kind.as_str() {
+        KIND_MAX_DAILY_HOURS => (
+            KIND_MAX_DAILY_HOURS,
+            format!("Worked {:.1} h in one day (limit {} h)", violation.hours, violation.limit_hours),
+        ),
+        KIND_MAX_WEEKLY_HOURS => (
+            KIND_MAX_WEEKLY_HOURS,
+            format!(
+                "Worked {:.1} h in the week from {} (limit {} h)",
+                violation.hours, violation.date, violation.limit_hours
+            ),
+        ),
+        _ => (
+            KIND_MIN_REST,
+            format!(
+                "Only {:.1} h of rest before this shift (minimum {} h)",
+                violation.hours, violation.limit_hours
+            ),
+        ),
+    };
+    NewException {
+        kind,
+        severity: "warning",
+        device_user_id: violation.device_user_id.clone(),
+        date: Some(violation.date.clone()),
+        message,
+        details: json!({
+            "userId": violation.user_id,
+            "hours": violation.hours,
+            "limitHours": violation.limit_hours,
+        }),
+        dedupe_key: format!("{}:{}:{}", kind, violation.user_id, violation.date),
+    }
+}
+
+fn webhook_body(violation: &WorkingTimeViolation) -> serde_json::Value {
+    json!({
+        "event": "working_time.violation",
+        "kind": violation.kind,
+        "userId": violation.user_id,
+        "userName": violation.user_name,
+        "employeeCode": violation.employee_code,
+        "date": violation.date,
+        "hours": violation.hours,
+        "limitHours": violation.limit_hours,
+    })
+}
+
+/// Post one alert to the webhook, logged as a delivery; returns whether it
+/// went through. Blocks on the network.
+fn deliver(conn: &Connection, url: &str, violation: &WorkingTimeViolation) -> Result<bool, String> {
+    let body = webhook_body(violation);
+    let id = deliveries::store::begin(
+        conn,
+        &NewDelivery {
+            channel: deliveries::store::CHANNEL_WEBHOOK,
+            target: url,
+            summary: format!("{}: {} on {}", violation.user_name, violation.kind, violation.date),
+            payload: json!({ "kind": DELIVERY_KIND, "body": body }),
+        },
+    )?;
+    let sent = deliveries::webhook::post(url, &body);
+    deliveries::store::finish(conn, id, &sent)?;
+    if let Err(e) = &sent {
+        log::warn!("[working_time] Alert for {} failed: {}", violation.user_id, e);
+    }
+    Ok(sent.is_ok())
+}
+
+/// Check a period for everyone and alert violations not alerted before.
+/// Blocks on the network.
+pub fn run(conn: &Connection, settings: &WorkingTimeSettings, period: &DateRange) -> Result<WorkingTimeCheck, String> {
+    let report = report(conn, period, &Scope::default())?;
+    let findings: Vec<NewException> = report.violations.iter().map(finding).collect();
+    let new = exceptions::insert_new(conn, &findings)?;
+
+    let mut check = WorkingTimeCheck {
+        violations: report.violations.len() as i64,
+        new_exceptions: new.len() as i64,
+        failed_deliveries: 0,
+    };
+    let url = settings.webhook_url.trim();
+    if !url.is_empty() {
+        for index in new {
+            check.failed_deliveries += u32::from(!deliver(conn, url, &report.violations[index])?);
+        }
+    }
+    Ok(check)
+}
+
+/// Send a logged violation alert again. Blocks on the network.
+pub fn redeliver(channel: &str, target: &str, payload: &serde_json::Value) -> Result<(), String> {
+    match channel {
+        deliveries::store::CHANNEL_WEBHOOK => deliveries::webhook::post(target, &payload["body"]),
+        other => Err(format!("Working-time alerts aren't sent by {}", other)),
+    }
+}
+
+fn round1(value: f64) -> f64 {
+    (value * 10.0).round() / 10.0
+}
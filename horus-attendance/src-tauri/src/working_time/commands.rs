@@ -0,0 +1,104 @@
+//! Tauri command handlers for working-time compliance.
+
+use std::time::Duration;
+
+use chrono::Local;
+use tauri::Emitter;
+
+use super::check::{self, SETTINGS_KEY};
+use super::types::*;
+use crate::activity::registry;
+use crate::analytics::types::{DateRange, Scope};
+use crate::db;
+use crate::deliveries::webhook;
+use crate::jobs;
+use crate::summary::commands::validate_date;
+
+/// How often the scheduled check runs
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Days back the scheduled check covers, ending yesterday
+const CHECK_DAYS: i64 = 14;
+
+/// ID of the scheduled check in the jobs registry
+pub const JOB_ID: &str = "working_time";
+
+#[tauri::command]
+pub async fn get_working_time_settings(app: tauri::AppHandle) -> Result<WorkingTimeSettings, String> {
+    check::load_settings(&*db::open(&app)?)
+}
+
+#[tauri::command]
+pub async fn save_working_time_settings(app: tauri::AppHandle, settings: WorkingTimeSettings) -> Result<(), String> {
+    check::validate(&settings)?;
+    if !settings.webhook_url.trim().is_empty() {
+        webhook::validate_url(&settings.webhook_url)?;
+    }
+    let json = serde_json::to_string(&settings).map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    log::info!(
+        "[working_time::cmd] Working-time checks {} (max {:?} h/day, {:?} h/week, rest {:?} h)",
+        if settings.enabled { "on" } else { "off" },
+        settings.max_daily_hours,
+        settings.max_weekly_hours,
+        settings.min_rest_hours
+    );
+    db::set_setting(&*db::open(&app)?, SETTINGS_KEY, &json)
+}
+
+/// Every breach of the daily, weekly and rest limits in a period
+#[tauri::command]
+pub async fn get_working_time_report(
+    app: tauri::AppHandle,
+    period: DateRange,
+    scope: Option<Scope>,
+) -> Result<WorkingTimeReport, String> {
+    validate_date(&period.start_date)?;
+    validate_date(&period.end_date)?;
+    if period.start_date > period.end_date {
+        return Err("Start date must not be after end date".to_string());
+    }
+    let scope = scope.unwrap_or_default();
+    let conn = db::open(&app)?;
+    tauri::async_runtime::spawn_blocking(move || check::report(&conn, &period, &scope))
+        .await
+        .map_err(|e| format!("Working-time report task failed: {}", e))?
+}
+
+/// Check the last two weeks while checks are enabled and alert new
+/// violations. Runs for the lifetime of the app.
+pub async fn run_scheduled(app: tauri::AppHandle) {
+    let job = jobs::registry::register(
+        &app,
+        JOB_ID,
+        "Working-time compliance",
+        "Alerts when shifts break the maximum daily or weekly hours or the minimum rest",
+        CHECK_INTERVAL,
+    );
+    let handle = app.clone();
+    jobs::registry::drive_blocking(
+        job,
+        move |_| {
+            let _activity = registry::begin(&handle, "Working-time check")?;
+            let conn = db::open(&handle)?;
+            let settings = check::load_settings(&conn)?;
+            if !settings.enabled {
+                return Ok(WorkingTimeCheck::default());
+            }
+            let yesterday = Local::now().date_naive() - chrono::Duration::days(1);
+            let period = DateRange {
+                start_date: (yesterday - chrono::Duration::days(CHECK_DAYS - 1)).format("%Y-%m-%d").to_string(),
+                end_date: yesterday.format("%Y-%m-%d").to_string(),
+            };
+            check::run(&conn, &settings, &period)
+        },
+        |result| {
+            if result.new_exceptions > 0 {
+                log::info!("[working_time] {} new working-time violations", result.new_exceptions);
+                if let Err(e) = app.emit("exceptions-updated", &result) {
+                    log::warn!("[working_time] Failed to emit exceptions update: {}", e);
+                }
+            }
+        },
+    )
+    .await;
+}
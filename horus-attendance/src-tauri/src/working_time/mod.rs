@@ -0,0 +1,16 @@
+//! Working-time compliance
+//!
+//! Labour law caps shift workers' hours and requires a rest period between
+//! shifts. The `workingTime` setting holds the limits: maximum hours a day,
+//! maximum hours a week (weeks start on the configured first day of the
+//! week) and minimum hours of rest between one shift's check-out and the
+//! next check-in. Hours come from the day summaries; a shift ending past
+//! midnight counts towards the day it started. The compliance report lists
+//! every violation in a period. While checks are on a background job looks
+//! at the last two weeks and alerts once per violation: an entry in the
+//! exception queue, the `exceptions-updated` event and a webhook call
+//! logged as a delivery.
+
+pub mod check;
+pub mod commands;
+pub mod types;
@@ -0,0 +1,73 @@
+//! Working-time compliance data types for Tauri command serialization
+
+use serde::{Deserialize, Serialize};
+
+use crate::export::types::DateRange;
+
+/// Working-time limits (`workingTime` setting). A limit left unset isn't
+/// checked.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WorkingTimeSettings {
+    /// Whether the background check runs; the report works either way
+    pub enabled: bool,
+    pub max_daily_hours: Option<f64>,
+    pub max_weekly_hours: Option<f64>,
+    /// Minimum hours between a check-out and the next check-in
+    pub min_rest_hours: Option<f64>,
+    /// URL posted a JSON alert for each new violation; empty for none
+    pub webhook_url: String,
+}
+
+impl Default for WorkingTimeSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_daily_hours: Some(12.0),
+            max_weekly_hours: Some(48.0),
+            min_rest_hours: Some(11.0),
+            webhook_url: String::new(),
+        }
+    }
+}
+
+/// One breach of a limit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkingTimeViolation {
+    /// "max_daily_hours", "max_weekly_hours" or "min_rest"
+    pub kind: String,
+    pub user_id: String,
+    pub device_user_id: Option<String>,
+    pub user_name: String,
+    pub employee_code: Option<String>,
+    /// The day; the first day of the week for weekly hours; the day of the
+    /// check-in that came too soon for rest
+    pub date: String,
+    /// Hours worked, or hours of rest for "min_rest"
+    pub hours: f64,
+    pub limit_hours: f64,
+}
+
+/// Every violation in a period
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkingTimeReport {
+    pub period: DateRange,
+    pub settings: WorkingTimeSettings,
+    /// Users with at least one complete shift in the period
+    pub users_checked: i64,
+    pub shifts_checked: i64,
+    pub violations: Vec<WorkingTimeViolation>,
+}
+
+/// Result of a check that alerts new violations
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkingTimeCheck {
+    /// Including ones alerted before
+    pub violations: i64,
+    /// Newly queued and alerted
+    pub new_exceptions: i64,
+    pub failed_deliveries: u32,
+}
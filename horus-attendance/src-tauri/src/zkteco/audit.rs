@@ -0,0 +1,214 @@
+//! Spot audit of a device against the database
+//!
+//! Reads a saved device's users and its most recent records (or all of them)
+//! and compares them with what is stored for the device, without storing
+//! anything. Records count as the same when user and time match; times are
+//! compared after the device's clock correction, as sync stores them.
+//! Stored records are only compared over the period the device records
+//! cover, since a device whose log was cleared no longer holds older ones.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use rusqlite::{params, Connection};
+use sha2::{Digest, Sha256};
+
+use super::client::ZKClient;
+use super::clock::DeviceClock;
+use super::types::*;
+use crate::activity::registry;
+use crate::db;
+use crate::quarantine::validate::{check_log, parse_timestamp, TIMESTAMP_FORMAT};
+
+pub const DEFAULT_SAMPLE_SIZE: u32 = 1000;
+pub const MAX_SAMPLE_SIZE: u32 = 100_000;
+
+/// Records listed per side of a discrepancy
+const SAMPLE_LIMIT: usize = 50;
+
+/// (timestamp, device user ID), ordered by time
+type Key = (String, String);
+
+/// Timestamp in the stored format, so differently formatted copies match
+fn normalize(timestamp: &str) -> String {
+    parse_timestamp(timestamp)
+        .map(|t| t.format(TIMESTAMP_FORMAT).to_string())
+        .unwrap_or_else(|| timestamp.to_string())
+}
+
+fn day_hash(keys: &[&Key]) -> String {
+    let mut hasher = Sha256::new();
+    for (timestamp, user) in keys {
+        hasher.update(format!("{}|{}\n", user, timestamp).as_bytes());
+    }
+    hasher.finalize().iter().take(8).map(|b| format!("{:02x}", b)).collect()
+}
+
+fn by_day(keys: &BTreeSet<Key>) -> BTreeMap<String, Vec<&Key>> {
+    let mut days: BTreeMap<String, Vec<&Key>> = BTreeMap::new();
+    for key in keys {
+        days.entry(key.0.chars().take(10).collect()).or_default().push(key);
+    }
+    days
+}
+
+fn sample<'a>(keys: impl Iterator<Item = &'a Key>) -> Vec<AuditRecord> {
+    keys.take(SAMPLE_LIMIT)
+        .map(|(timestamp, user)| AuditRecord {
+            device_user_id: user.clone(),
+            timestamp: timestamp.clone(),
+        })
+        .collect()
+}
+
+fn count(conn: &Connection, sql: &str, device_id: &str) -> Result<u32, String> {
+    conn.query_row(sql, [device_id], |row| row.get(0))
+        .map_err(|e| format!("Failed to count stored records: {}", e))
+}
+
+/// Compare what was read from the device with the database
+fn compare(
+    conn: &Connection,
+    device_id: &str,
+    info: &DeviceInfo,
+    users: &[DeviceUser],
+    logs: &[AttendanceLog],
+    full: bool,
+) -> Result<DeviceAuditReport, String> {
+    let now = chrono::Local::now().naive_local();
+    let mut invalid_records = 0;
+    let mut on_device = BTreeSet::new();
+    for log in logs {
+        if check_log(&log.device_user_id, &log.timestamp, now).is_some() {
+            invalid_records += 1;
+        } else {
+            on_device.insert((normalize(&log.timestamp), log.device_user_id.clone()));
+        }
+    }
+    let first_timestamp = on_device.first().map(|k| k.0.clone());
+    let last_timestamp = on_device.last().map(|k| k.0.clone());
+
+    let in_db: BTreeSet<Key> = match (&first_timestamp, &last_timestamp) {
+        (Some(first), Some(last)) => {
+            // Stored copies of the last second may carry milliseconds or not
+            let upper = format!("{}~", &last[..19.min(last.len())]);
+            let lower = first[..19.min(first.len())].to_string();
+            let mut stmt = conn
+                .prepare(
+                    "SELECT timestamp, device_user_id FROM attendance_logs_raw
+                     WHERE device_id = ?1 AND timestamp >= ?2 AND timestamp <= ?3",
+                )
+                .map_err(|e| format!("Failed to query stored records: {}", e))?;
+            let rows = stmt
+                .query_map(params![device_id, lower, upper], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+                })
+                .map_err(|e| format!("Failed to query stored records: {}", e))?;
+            rows.map(|row| row.map(|(timestamp, user)| (normalize(&timestamp), user)))
+                .collect::<Result<_, _>>()
+                .map_err(|e| format!("Failed to read stored records: {}", e))?
+        }
+        _ => BTreeSet::new(),
+    };
+
+    let known: HashSet<String> = {
+        let mut stmt = conn
+            .prepare(
+                "SELECT device_user_id FROM users WHERE device_user_id IS NOT NULL
+                 UNION SELECT device_user_id FROM user_device_links",
+            )
+            .map_err(|e| format!("Failed to query users: {}", e))?;
+        let rows = stmt
+            .query_map([], |row| row.get(0))
+            .map_err(|e| format!("Failed to query users: {}", e))?;
+        rows.collect::<Result<_, _>>()
+            .map_err(|e| format!("Failed to read users: {}", e))?
+    };
+
+    let device_days = by_day(&on_device);
+    let db_days = by_day(&in_db);
+    let dates: BTreeSet<&String> = device_days.keys().chain(db_days.keys()).collect();
+    let mismatched_days = dates
+        .into_iter()
+        .filter_map(|date| {
+            let device = device_days.get(date).map(Vec::as_slice).unwrap_or_default();
+            let db = db_days.get(date).map(Vec::as_slice).unwrap_or_default();
+            let (device_hash, db_hash) = (day_hash(device), day_hash(db));
+            (device_hash != db_hash).then(|| AuditDayDigest {
+                date: date.clone(),
+                device_records: device.len() as u32,
+                db_records: db.len() as u32,
+                device_hash,
+                db_hash,
+            })
+        })
+        .collect();
+
+    let missing_from_db = on_device.difference(&in_db).count() as u32;
+    let missing_from_device = in_db.difference(&on_device).count() as u32;
+    Ok(DeviceAuditReport {
+        device_id: device_id.to_string(),
+        checked_at: chrono::Local::now().format("%Y-%m-%dT%H:%M:%S").to_string(),
+        full,
+        device_user_count: info.user_count,
+        device_log_count: info.log_count,
+        db_log_count: count(conn, "SELECT COUNT(*) FROM attendance_logs_raw WHERE device_id = ?1", device_id)?,
+        unknown_users: users.iter().filter(|u| !known.contains(&u.device_user_id)).count() as u32,
+        compared_records: logs.len() as u32,
+        first_timestamp,
+        last_timestamp,
+        invalid_records,
+        db_records_in_period: in_db.len() as u32,
+        matched: on_device.intersection(&in_db).count() as u32,
+        missing_from_db,
+        missing_from_device,
+        missing_from_db_sample: sample(on_device.difference(&in_db)),
+        missing_from_device_sample: sample(in_db.difference(&on_device)),
+        mismatched_days,
+        consistent: missing_from_db == 0 && missing_from_device == 0,
+    })
+}
+
+/// Audit the saved device `device_id` that `config` connects to
+pub async fn run(
+    app: &tauri::AppHandle,
+    config: &DeviceConfig,
+    device_id: &str,
+    options: &DeviceAuditOptions,
+) -> Result<DeviceAuditReport, String> {
+    let clock = DeviceClock::load(&*db::open(app)?, device_id)?;
+    let sample_size = options.sample_size.unwrap_or(DEFAULT_SAMPLE_SIZE) as usize;
+    let _activity = registry::begin(app, "Device audit")?;
+
+    let mut client = ZKClient::connect(config).await?;
+    let fetched = async {
+        let info = client.get_device_info().await?;
+        let users = client.get_users().await?;
+        let logs = if options.full {
+            client.get_attendance_logs(None).await?
+        } else {
+            let total = info.log_count as usize;
+            let count = sample_size.min(total);
+            client.get_attendance_page(total - count, count).await?.0
+        };
+        Ok::<_, String>((info, users, logs))
+    }
+    .await;
+    let _ = client.disconnect().await;
+    let (info, users, mut logs) = fetched?;
+    clock.normalize(&mut logs);
+
+    let conn = db::open(app)?;
+    let device_id = device_id.to_string();
+    let full = options.full;
+    let report = tauri::async_runtime::spawn_blocking(move || compare(&conn, &device_id, &info, &users, &logs, full))
+        .await
+        .map_err(|e| format!("Device audit task failed: {}", e))??;
+    log::info!(
+        "[zkteco::audit] {} compared {} records: {} missing from database, {} missing from device",
+        report.device_id,
+        report.compared_records,
+        report.missing_from_db,
+        report.missing_from_device
+    );
+    Ok(report)
+}
@@ -0,0 +1,100 @@
+//! Bell (ring) schedule
+//!
+//! Terminals wired to a bell keep a fixed number of numbered entries, each
+//! stored as a group of device options (`BellTime3`, `BellDays3`, ...).
+//! Firmware without a bell relay rejects the options.
+
+use super::client::ZKClient;
+use super::types::Bell;
+
+/// Entries the terminals hold
+pub const MAX_BELLS: u8 = 24;
+
+const LONGEST_RING_SECS: u16 = 999;
+
+fn option(field: &str, slot: u8) -> String {
+    format!("Bell{}{}", field, slot)
+}
+
+/// Days bitmask, bit 0 = Sunday
+fn days_mask(days: &[u8]) -> u8 {
+    days.iter().filter(|d| **d < 7).fold(0, |mask, d| mask | (1 << d))
+}
+
+fn mask_days(mask: u8) -> Vec<u8> {
+    (0..7).filter(|d| mask & (1 << d) != 0).collect()
+}
+
+/// Reject entries the device can't store
+pub fn validate(bells: &[Bell]) -> Result<(), String> {
+    let mut seen = [false; MAX_BELLS as usize + 1];
+    for bell in bells {
+        if bell.slot == 0 || bell.slot > MAX_BELLS {
+            return Err(format!("Bell slot must be between 1 and {}: {}", MAX_BELLS, bell.slot));
+        }
+        if std::mem::replace(&mut seen[bell.slot as usize], true) {
+            return Err(format!("Bell slot {} is used twice", bell.slot));
+        }
+        chrono::NaiveTime::parse_from_str(&bell.time, "%H:%M")
+            .map_err(|_| format!("Invalid bell time (expected HH:MM): {}", bell.time))?;
+        if let Some(day) = bell.days.iter().find(|d| **d > 6) {
+            return Err(format!("Invalid day {} for bell {} (0 = Sunday to 6 = Saturday)", day, bell.slot));
+        }
+        if bell.duration_secs == 0 || bell.duration_secs > LONGEST_RING_SECS {
+            return Err(format!(
+                "Bell {} must ring for 1 to {} seconds",
+                bell.slot, LONGEST_RING_SECS
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// The device's schedule, one entry per slot
+pub async fn read(client: &mut ZKClient) -> Result<Vec<Bell>, String> {
+    let mut bells = Vec::new();
+    for slot in 1..=MAX_BELLS {
+        let time = match client.get_option(&option("Time", slot)).await {
+            Ok(time) => time,
+            Err(e) if slot == 1 => return Err(format!("Device has no bell schedule: {}", e)),
+            // Fewer slots than the usual number
+            Err(_) => break,
+        };
+        let days = client.get_option(&option("Days", slot)).await?;
+        let duration = client.get_option(&option("Duration", slot)).await?;
+        let enabled = client.get_option(&option("Enable", slot)).await?;
+        bells.push(Bell {
+            slot,
+            time,
+            days: mask_days(days.parse().unwrap_or(0)),
+            duration_secs: duration.parse().unwrap_or(0),
+            enabled: enabled == "1",
+        });
+    }
+    Ok(bells)
+}
+
+/// Replace the device's schedule with `bells`; slots not listed are turned off
+pub async fn write(client: &mut ZKClient, bells: &[Bell]) -> Result<(), String> {
+    validate(bells)?;
+    for slot in 1..=MAX_BELLS {
+        match bells.iter().find(|b| b.slot == slot) {
+            Some(bell) => {
+                client.set_option(&option("Time", slot), &bell.time).await?;
+                client.set_option(&option("Days", slot), &days_mask(&bell.days).to_string()).await?;
+                client.set_option(&option("Duration", slot), &bell.duration_secs.to_string()).await?;
+                client.set_option(&option("Enable", slot), if bell.enabled { "1" } else { "0" }).await?;
+            }
+            None => {
+                if let Err(e) = client.set_option(&option("Enable", slot), "0").await {
+                    if slot == 1 {
+                        return Err(format!("Device has no bell schedule: {}", e));
+                    }
+                    // Fewer slots than the usual number
+                    break;
+                }
+            }
+        }
+    }
+    client.refresh_options().await
+}
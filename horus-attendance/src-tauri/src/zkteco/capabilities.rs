@@ -0,0 +1,201 @@
+//! Firmware capability probing
+//!
+//! Terminals differ in what their firmware answers: partial attendance
+//! reads, bell relays, face and photo support, how long a user ID may be.
+//! The first time a saved device is reached (and again after a firmware
+//! change) a handful of read-only requests find out, and the result is kept
+//! on its `devices` row as a set of feature flags. Features that depend on
+//! one check the flag first, so an unsupported device fails with an
+//! explanation rather than a protocol error halfway through.
+
+use std::collections::BTreeMap;
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use super::client::ZKClient;
+use super::protocol::{cmd, option_read_request, parse_option_reply};
+use super::types::{DeviceCapabilities, DeviceConfig};
+
+/// Device options can be read and written (settings, network, bells)
+pub const FEATURE_OPTIONS: &str = "options";
+/// Part of the attendance buffer can be read, for paged imports
+pub const FEATURE_PAGED_ATTENDANCE: &str = "pagedAttendance";
+/// A bell relay with a schedule
+pub const FEATURE_BELLS: &str = "bells";
+pub const FEATURE_FINGERPRINT: &str = "fingerprint";
+pub const FEATURE_FACE: &str = "face";
+/// Photos taken at punch time
+pub const FEATURE_PHOTOS: &str = "photos";
+/// User names are stored as UTF-8 rather than a code page
+pub const FEATURE_UTF8_NAMES: &str = "utf8Names";
+
+/// Firmware from which names are stored as UTF-8 ("Ver 6.60")
+const UTF8_FIRMWARE: (u32, u32) = (6, 60);
+
+/// How a feature is described when a device lacks it
+fn describe(feature: &str) -> &str {
+    match feature {
+        FEATURE_OPTIONS => "reading or changing device settings",
+        FEATURE_PAGED_ATTENDANCE => "reading part of the attendance log (needed for paged imports)",
+        FEATURE_BELLS => "a bell schedule",
+        FEATURE_FINGERPRINT => "fingerprints",
+        FEATURE_FACE => "face recognition",
+        FEATURE_PHOTOS => "punch photos",
+        FEATURE_UTF8_NAMES => "non-Latin user names",
+        other => other,
+    }
+}
+
+/// Major and minor version in a firmware string such as "Ver 6.60 Apr 28 2017"
+fn firmware_version(firmware: &str) -> Option<(u32, u32)> {
+    let version = firmware.split_whitespace().find(|w| w.starts_with(|c: char| c.is_ascii_digit()))?;
+    let (major, minor) = version.split_once('.')?;
+    let minor: String = minor.chars().take_while(char::is_ascii_digit).collect();
+    Some((major.parse().ok()?, minor.parse().ok()?))
+}
+
+/// An option's value; None when the device doesn't have it. Errors only
+/// when the device stops answering.
+async fn read_option(client: &mut ZKClient, name: &str) -> Result<Option<String>, String> {
+    let (reply, data) = client.request(cmd::CMD_OPTIONS_RRQ, &option_read_request(name)).await?;
+    if reply != cmd::CMD_ACK_OK {
+        return Ok(None);
+    }
+    Ok(parse_option_reply(&data, name).filter(|v| !v.is_empty()))
+}
+
+/// Find out what the connected device supports
+pub async fn probe(client: &mut ZKClient) -> Result<DeviceCapabilities, String> {
+    let mut features = BTreeMap::new();
+
+    let (reply, _) = client.request(cmd::CMD_OPTIONS_RRQ, &option_read_request("~SerialNumber")).await?;
+    let options = reply == cmd::CMD_ACK_OK;
+    features.insert(FEATURE_OPTIONS.to_string(), options);
+
+    let mut user_id_width = None;
+    if options {
+        // Firmware that doesn't know these options lacks the feature
+        features.insert(FEATURE_BELLS.to_string(), read_option(client, "BellTime1").await?.is_some());
+        features.insert(
+            FEATURE_FINGERPRINT.to_string(),
+            read_option(client, "~ZKFPVersion").await?.is_some_and(|v| v != "0"),
+        );
+        features.insert(FEATURE_FACE.to_string(), read_option(client, "FaceFunOn").await?.as_deref() == Some("1"));
+        features.insert(FEATURE_PHOTOS.to_string(), read_option(client, "PhotoFunOn").await?.as_deref() == Some("1"));
+        user_id_width = read_option(client, "~PIN2Width").await?.and_then(|v| v.parse().ok());
+    }
+
+    match client.get_attendance_page(0, 1).await {
+        Ok(_) => {
+            features.insert(FEATURE_PAGED_ATTENDANCE.to_string(), true);
+        }
+        Err(e) if e.starts_with("Device rejected command") => {
+            features.insert(FEATURE_PAGED_ATTENDANCE.to_string(), false);
+        }
+        // A timeout says nothing about the firmware
+        Err(e) => log::debug!("[zkteco] No answer probing paged attendance: {}", e),
+    }
+
+    let firmware = client.get_firmware_version().await.ok();
+    if let Some(version) = firmware.as_deref().and_then(firmware_version) {
+        features.insert(FEATURE_UTF8_NAMES.to_string(), version >= UTF8_FIRMWARE);
+    }
+
+    Ok(DeviceCapabilities {
+        features,
+        user_id_width,
+        firmware,
+        probed_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+    })
+}
+
+/// The capabilities stored for a saved device, if it was probed
+pub fn load(conn: &Connection, device_id: &str) -> Result<Option<DeviceCapabilities>, String> {
+    let json: Option<String> = conn
+        .query_row("SELECT capabilities FROM devices WHERE id = ?1", [device_id], |row| row.get(0))
+        .optional()
+        .map_err(|e| format!("Failed to load device capabilities: {}", e))?
+        .flatten();
+    Ok(json.and_then(|json| match serde_json::from_str(&json) {
+        Ok(capabilities) => Some(capabilities),
+        Err(e) => {
+            log::warn!("[zkteco] Ignoring malformed capabilities of {}: {}", device_id, e);
+            None
+        }
+    }))
+}
+
+/// Store a saved device's capabilities. Returns false for an unknown device.
+pub fn record(conn: &Connection, device_id: &str, capabilities: &DeviceCapabilities) -> Result<bool, String> {
+    let json =
+        serde_json::to_string(capabilities).map_err(|e| format!("Failed to serialize device capabilities: {}", e))?;
+    conn.execute("UPDATE devices SET capabilities = ?2 WHERE id = ?1", params![device_id, json])
+        .map(|n| n > 0)
+        .map_err(|e| format!("Failed to save device capabilities: {}", e))
+}
+
+/// Whether a saved device needs probing: never probed, or its firmware
+/// changed since
+fn needs_probe(conn: &Connection, device_id: &str) -> Result<bool, String> {
+    let firmware: Option<Option<String>> = conn
+        .query_row("SELECT firmware FROM devices WHERE id = ?1", [device_id], |row| row.get(0))
+        .optional()
+        .map_err(|e| format!("Failed to load device: {}", e))?;
+    let Some(firmware) = firmware else {
+        return Ok(false);
+    };
+    Ok(match load(conn, device_id)? {
+        Some(capabilities) => firmware.is_some() && capabilities.firmware != firmware,
+        None => true,
+    })
+}
+
+/// Probe a saved device reached for the first time, or since a firmware
+/// change, logging rather than failing: what reached it succeeded either way
+pub async fn ensure(app: &tauri::AppHandle, config: &DeviceConfig) {
+    let Some(device_id) = config.device_id.as_deref().filter(|id| !id.is_empty()) else {
+        return;
+    };
+    match crate::db::open(app).and_then(|conn| needs_probe(&conn, device_id)) {
+        Ok(true) => {}
+        Ok(false) => return,
+        Err(e) => {
+            log::warn!("[zkteco] Could not check capabilities of {}: {}", device_id, e);
+            return;
+        }
+    }
+    let probed = async {
+        let mut client = ZKClient::connect(config).await?;
+        let capabilities = probe(&mut client).await;
+        let _ = client.disconnect().await;
+        capabilities
+    }
+    .await;
+    let saved = probed.and_then(|capabilities| {
+        log::info!("[zkteco] Capabilities of {}: {:?}", device_id, capabilities.features);
+        record(&*crate::db::open(app)?, device_id, &capabilities)
+    });
+    if let Err(e) = saved {
+        log::warn!("[zkteco] Could not probe capabilities of {}: {}", device_id, e);
+    }
+}
+
+/// Fail unless the device may have `feature`: devices that aren't saved or
+/// weren't probed are given the benefit of the doubt
+pub fn require(conn: &Connection, device_id: Option<&str>, feature: &str) -> Result<(), String> {
+    let Some(device_id) = device_id.filter(|id| !id.is_empty()) else {
+        return Ok(());
+    };
+    let Some(capabilities) = load(conn, device_id)? else {
+        return Ok(());
+    };
+    if capabilities.features.get(feature) == Some(&false) {
+        let firmware = capabilities.firmware.as_deref().unwrap_or("unknown firmware");
+        return Err(format!(
+            "This device's firmware ({}) doesn't support {}. If it was updated since, probe its capabilities again.",
+            firmware,
+            describe(feature)
+        ));
+    }
+    Ok(())
+}
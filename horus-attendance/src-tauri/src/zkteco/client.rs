@@ -3,6 +3,13 @@
 //! Tries TCP first, falls back to UDP (mirrors node-zklib behavior).
 //! Provides a clean async API for Tauri commands.
 
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::protocol::{
+    cmd, decode_user_record, encode_user_data_72, encode_zk_time, expect_ack, is_busy_error, option_read_request,
+    option_write_request, parse_option_reply,
+};
+use super::demo::DemoDevice;
 use super::tcp::ZKTcp;
 use super::types::*;
 use super::udp::ZKUdp;
@@ -11,6 +18,16 @@ use super::udp::ZKUdp;
 enum Transport {
     Tcp(ZKTcp),
     Udp(ZKUdp),
+    /// A demo device, answered in-process
+    Demo(DemoDevice),
+}
+
+/// Connected clients across the app, so shutdown can wait for them
+static OPEN_SESSIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// Number of device sessions currently open
+pub fn open_sessions() -> usize {
+    OPEN_SESSIONS.load(Ordering::SeqCst)
 }
 
 /// High-level ZKTeco device client
@@ -18,10 +35,34 @@ pub struct ZKClient {
     transport: Option<Transport>,
 }
 
+impl Drop for ZKClient {
+    fn drop(&mut self) {
+        // Dropped without disconnect (e.g. on an error path): the socket closes with it
+        if self.transport.take().is_some() {
+            OPEN_SESSIONS.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
 impl ZKClient {
     /// Create a new client and connect to the device.
-    /// Tries TCP first, falls back to UDP.
+    /// Tries TCP first, falls back to UDP. Demo device addresses get the
+    /// simulator instead.
     pub async fn connect(config: &DeviceConfig) -> Result<Self, String> {
+        let client = match DemoDevice::connect(&config.ip) {
+            Some(demo) => {
+                log::info!("[zkteco] {} is a demo device; simulating it", config.ip);
+                Self {
+                    transport: Some(Transport::Demo(demo)),
+                }
+            }
+            None => Self::open(config).await?,
+        };
+        OPEN_SESSIONS.fetch_add(1, Ordering::SeqCst);
+        Ok(client)
+    }
+
+    async fn open(config: &DeviceConfig) -> Result<Self, String> {
         let ip = &config.ip;
         let port = config.port;
         let timeout_ms = config.timeout.unwrap_or(30000);
@@ -41,6 +82,9 @@ impl ZKClient {
                     if let Err(e) = tcp.auth(comm_key).await {
                         log::warn!("[zkteco] TCP auth failed: {}", e);
                         let _ = tcp.disconnect().await;
+                        if is_busy_error(&e) {
+                            return Err(e);
+                        }
                         return Err(format!("Device authentication failed: {}", e));
                     }
                     log::info!("[zkteco] TCP auth successful");
@@ -50,6 +94,12 @@ impl ZKClient {
                     transport: Some(Transport::Tcp(tcp)),
                 });
             }
+            Err(e) if is_busy_error(&e) => {
+                // The device answered, it just won't serve us right now
+                log::warn!("[zkteco] {}", e);
+                let _ = tcp.disconnect().await;
+                return Err(e);
+            }
             Err(e) => {
                 log::warn!("[zkteco] TCP failed ({}), trying UDP...", e);
                 tcp_error = e;
@@ -67,6 +117,9 @@ impl ZKClient {
                     if let Err(e) = udp.auth(comm_key).await {
                         log::warn!("[zkteco] UDP auth failed: {}", e);
                         let _ = udp.disconnect().await;
+                        if is_busy_error(&e) {
+                            return Err(e);
+                        }
                         return Err(format!("Device authentication failed: {}", e));
                     }
                     log::info!("[zkteco] UDP auth successful");
@@ -104,6 +157,8 @@ impl ZKClient {
                             user_count: 0,
                             log_count: 0,
                             last_activity: chrono::Utc::now().to_rfc3339(),
+                            model: None,
+                            mac_address: None,
                         })
                     }
                 };
@@ -124,28 +179,84 @@ impl ZKClient {
         }
     }
 
-    /// Get device info (user count, log count)
+    /// Get device info (user count, log count, and the hardware details
+    /// the device answers with)
     pub async fn get_device_info(&mut self) -> Result<DeviceInfo, String> {
-        let (user_count, log_count) = match self.transport.as_mut() {
-            Some(Transport::Tcp(tcp)) => tcp.get_info().await?,
-            Some(Transport::Udp(udp)) => udp.get_info().await?,
-            None => return Err("Not connected".to_string()),
-        };
+        let (user_count, log_count) = self.get_free_sizes().await?;
+        let hardware = self.get_hardware().await;
 
         Ok(DeviceInfo {
-            serial_number: "Unknown".to_string(),
-            firmware_version: "Unknown".to_string(),
+            serial_number: hardware.serial_number.unwrap_or_else(|| "Unknown".to_string()),
+            firmware_version: hardware.firmware.unwrap_or_else(|| "Unknown".to_string()),
             user_count,
             log_count,
             last_activity: chrono::Utc::now().to_rfc3339(),
+            model: hardware.model,
+            mac_address: hardware.mac_address,
         })
     }
 
+    /// User and log counts from the device's free sizes
+    pub async fn get_free_sizes(&mut self) -> Result<(u32, u32), String> {
+        match self.transport.as_mut() {
+            Some(Transport::Tcp(tcp)) => tcp.get_info().await,
+            Some(Transport::Udp(udp)) => udp.get_info().await,
+            Some(Transport::Demo(demo)) => Ok(demo.get_info()),
+            None => Err("Not connected".to_string()),
+        }
+    }
+
+    /// Model, serial number, firmware version and MAC address. Each is
+    /// left out when the device doesn't answer for it.
+    pub async fn get_hardware(&mut self) -> DeviceHardware {
+        let model = self.read_optional("~DeviceName").await;
+        let serial_number = self.read_optional("~SerialNumber").await;
+        let mac_address = self.read_optional("MAC").await;
+        let firmware = match self.get_firmware_version().await {
+            Ok(version) => Some(version),
+            Err(e) => {
+                log::debug!("[zkteco] No firmware version from device: {}", e);
+                None
+            }
+        };
+        DeviceHardware {
+            model,
+            serial_number,
+            firmware,
+            mac_address,
+        }
+    }
+
+    /// A device option, or None when the device doesn't have it
+    async fn read_optional(&mut self, name: &str) -> Option<String> {
+        match self.get_option(name).await {
+            Ok(value) if !value.is_empty() => Some(value),
+            Ok(_) => None,
+            Err(e) => {
+                log::debug!("[zkteco] No {} from device: {}", name, e);
+                None
+            }
+        }
+    }
+
+    /// The firmware version string, e.g. "Ver 6.60 Apr 28 2017"
+    pub async fn get_firmware_version(&mut self) -> Result<String, String> {
+        let (reply, data) = self.request(cmd::CMD_GET_VERSION, &[]).await?;
+        expect_ack(cmd::CMD_GET_VERSION, reply)?;
+        let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+        let version = String::from_utf8_lossy(&data[..end]).trim().to_string();
+        if version.is_empty() {
+            return Err("Device returned no version".to_string());
+        }
+        Ok(version)
+    }
+
     /// Get all users from the device
     pub async fn get_users(&mut self) -> Result<Vec<DeviceUser>, String> {
         let raw_users = match self.transport.as_mut() {
             Some(Transport::Tcp(tcp)) => tcp.get_users().await?,
             Some(Transport::Udp(udp)) => udp.get_users().await?,
+            Some(Transport::Demo(demo)) => demo.get_users(),
             None => return Err("Not connected".to_string()),
         };
 
@@ -167,6 +278,61 @@ impl ZKClient {
             .collect())
     }
 
+    /// The device's internal record number for each user ID. Commands that
+    /// target a user (messages, for one) address it by this number.
+    pub async fn get_user_uids(&mut self) -> Result<std::collections::HashMap<String, u16>, String> {
+        let raw_users = match self.transport.as_mut() {
+            Some(Transport::Tcp(tcp)) => tcp.get_users().await?,
+            Some(Transport::Udp(udp)) => udp.get_users().await?,
+            Some(Transport::Demo(demo)) => demo.get_users(),
+            None => return Err("Not connected".to_string()),
+        };
+        Ok(raw_users.into_iter().map(|(uid, user_id, _)| (user_id, uid)).collect())
+    }
+
+    /// Raw user records keyed by user ID, for changing a user with
+    /// [`Self::write_user_record`] without losing the fields this client
+    /// doesn't decode
+    pub async fn get_user_records(&mut self) -> Result<std::collections::HashMap<String, Vec<u8>>, String> {
+        let records = match self.transport.as_mut() {
+            Some(Transport::Tcp(tcp)) => tcp.get_user_records().await?,
+            Some(Transport::Udp(udp)) => udp.get_user_records().await?,
+            Some(Transport::Demo(demo)) => demo
+                .get_users()
+                .into_iter()
+                .map(|(uid, user_id, name)| encode_user_data_72(uid, &user_id, &name))
+                .collect(),
+            None => return Err("Not connected".to_string()),
+        };
+        Ok(records
+            .into_iter()
+            .map(|record| (decode_user_record(&record).1, record))
+            .collect())
+    }
+
+    /// The raw biometric template table (see [`super::templates`])
+    pub async fn get_templates(&mut self) -> Result<Vec<u8>, String> {
+        match self.transport.as_mut() {
+            Some(Transport::Tcp(tcp)) => tcp.get_templates().await,
+            Some(Transport::Udp(udp)) => udp.get_templates().await,
+            Some(Transport::Demo(_)) => Ok(Vec::new()),
+            None => Err("Not connected".to_string()),
+        }
+    }
+
+    /// Create or replace a user, given a record as read by
+    /// [`Self::get_user_records`]. Takes effect after [`Self::refresh_data`].
+    pub async fn write_user_record(&mut self, record: &[u8]) -> Result<(), String> {
+        let (reply, _) = self.request(cmd::CMD_USER_WRQ, record).await?;
+        expect_ack(cmd::CMD_USER_WRQ, reply)
+    }
+
+    /// Make the device reload users written since the last refresh
+    pub async fn refresh_data(&mut self) -> Result<(), String> {
+        let (reply, _) = self.request(cmd::CMD_REFRESHDATA, &[]).await?;
+        expect_ack(cmd::CMD_REFRESHDATA, reply)
+    }
+
     /// Get attendance logs from the device, optionally filtered by date range
     pub async fn get_attendance_logs(
         &mut self,
@@ -175,6 +341,7 @@ impl ZKClient {
         let raw_records = match self.transport.as_mut() {
             Some(Transport::Tcp(tcp)) => tcp.get_attendances().await?,
             Some(Transport::Udp(udp)) => udp.get_attendances().await?,
+            Some(Transport::Demo(demo)) => demo.get_attendances(),
             None => return Err("Not connected".to_string()),
         };
 
@@ -185,11 +352,12 @@ impl ZKClient {
 
         let mut logs: Vec<AttendanceLog> = raw_records
             .into_iter()
-            .map(|(device_user_id, timestamp, verify_type, punch_type)| AttendanceLog {
+            .map(|(device_user_id, timestamp, verify_type, punch_type, raw)| AttendanceLog {
                 device_user_id,
                 timestamp,
                 verify_type,
                 punch_type,
+                raw,
             })
             .collect();
 
@@ -224,10 +392,34 @@ impl ZKClient {
         Ok(logs)
     }
 
-    /// Combined sync: get users AND attendance logs in one session
+    /// Attendance logs `start..start + count` in the order the device stored
+    /// them, and how many the device holds. Only that part is transferred.
+    pub async fn get_attendance_page(&mut self, start: usize, count: usize) -> Result<(Vec<AttendanceLog>, usize), String> {
+        let (raw_records, total) = match self.transport.as_mut() {
+            Some(Transport::Tcp(tcp)) => tcp.get_attendance_page(start, count).await?,
+            Some(Transport::Udp(udp)) => udp.get_attendance_page(start, count).await?,
+            Some(Transport::Demo(demo)) => demo.get_attendance_page(start, count),
+            None => return Err("Not connected".to_string()),
+        };
+        let logs = raw_records
+            .into_iter()
+            .map(|(device_user_id, timestamp, verify_type, punch_type, raw)| AttendanceLog {
+                device_user_id,
+                timestamp,
+                verify_type,
+                punch_type,
+                raw,
+            })
+            .collect();
+        Ok((logs, total))
+    }
+
+    /// Combined sync: get users AND attendance logs in one session. In
+    /// incremental mode logs are read from `offset` on (see `offsets`).
     pub async fn sync_all(
         config: &DeviceConfig,
         options: Option<&SyncOptions>,
+        offset: Option<&DeviceLogOffset>,
     ) -> Result<SyncAllResult, String> {
         log::info!(
             "[zkteco] Starting combined sync for device {}:{}",
@@ -240,6 +432,7 @@ impl ZKClient {
         // Get users first
         let users = client.get_users().await?;
         log::info!("[zkteco] Got {} users", users.len());
+        let hardware = client.get_hardware().await;
 
         // Disconnect and reconnect for attendance logs
         // (device needs a fresh connection, mirrors sidecar behavior)
@@ -247,7 +440,10 @@ impl ZKClient {
 
         // Retry reconnection up to 3 times with increasing delays.
         // ZKTeco devices are slow to release the TCP socket after disconnect.
+        let incremental_mode = options.is_some_and(|o| o.mode == "incremental");
         let mut logs = Vec::new();
+        let mut incremental = None;
+        let mut transport = None;
         let mut last_err = String::new();
         for attempt in 0..3 {
             let delay_ms = 500 + (attempt as u64 * 500); // 500ms, 1000ms, 1500ms
@@ -255,7 +451,18 @@ impl ZKClient {
 
             match Self::connect(config).await {
                 Ok(mut reconnected) => {
-                    match reconnected.get_attendance_logs(options).await {
+                    transport = reconnected.transport_name().map(str::to_string);
+                    let fetched = if incremental_mode {
+                        super::offsets::read_logs(&mut reconnected, offset)
+                            .await
+                            .map(|(fetched, read)| {
+                                incremental = Some(read);
+                                fetched
+                            })
+                    } else {
+                        reconnected.get_attendance_logs(options).await
+                    };
+                    match fetched {
                         Ok(fetched) => {
                             log::info!("[zkteco] Got {} attendance logs (attempt {})", fetched.len(), attempt + 1);
                             logs = fetched;
@@ -281,23 +488,107 @@ impl ZKClient {
             return Err(format!("Got {} users but failed to fetch attendance logs: {}", users.len(), last_err));
         }
 
-        Ok(SyncAllResult { users, logs })
+        Ok(SyncAllResult {
+            users,
+            logs,
+            quarantined: 0,
+            preview: None,
+            hardware: Some(hardware),
+            transport,
+            incremental,
+        })
+    }
+
+    /// "tcp", "udp" or "demo"; None once disconnected
+    pub fn transport_name(&self) -> Option<&'static str> {
+        match self.transport.as_ref()? {
+            Transport::Tcp(_) => Some("tcp"),
+            Transport::Udp(_) => Some("udp"),
+            Transport::Demo(_) => Some("demo"),
+        }
     }
 
     /// Disconnect from the device
     pub async fn disconnect(&mut self) -> Result<(), String> {
-        match self.transport.take() {
+        let transport = self.transport.take();
+        if transport.is_some() {
+            OPEN_SESSIONS.fetch_sub(1, Ordering::SeqCst);
+        }
+        match transport {
             Some(Transport::Tcp(mut tcp)) => tcp.disconnect().await,
             Some(Transport::Udp(mut udp)) => udp.disconnect().await,
+            Some(Transport::Demo(_)) => Ok(()),
             None => Ok(()),
         }
     }
+
+    /// Send a command and return the reply code and payload
+    pub async fn request(&mut self, command: u16, data: &[u8]) -> Result<(u16, Vec<u8>), String> {
+        match self.transport.as_mut() {
+            Some(Transport::Tcp(tcp)) => tcp.request(command, data).await,
+            Some(Transport::Udp(udp)) => udp.request(command, data).await,
+            Some(Transport::Demo(demo)) => Ok(demo.request(command, data)),
+            None => Err("Not connected".to_string()),
+        }
+    }
+
+    /// Read a device option such as "IPAddress"
+    pub async fn get_option(&mut self, name: &str) -> Result<String, String> {
+        let (reply, data) = self.request(cmd::CMD_OPTIONS_RRQ, &option_read_request(name)).await?;
+        expect_ack(cmd::CMD_OPTIONS_RRQ, reply)?;
+        parse_option_reply(&data, name).ok_or_else(|| format!("Device does not support option {}", name))
+    }
+
+    /// Write a device option. Takes effect after [`Self::refresh_options`]
+    /// (or a restart, for network settings).
+    pub async fn set_option(&mut self, name: &str, value: &str) -> Result<(), String> {
+        let (reply, _) = self.request(cmd::CMD_OPTIONS_WRQ, &option_write_request(name, value)).await?;
+        expect_ack(cmd::CMD_OPTIONS_WRQ, reply).map_err(|e| format!("Failed to set {}: {}", name, e))
+    }
+
+    /// Make the device apply options written since the last refresh
+    pub async fn refresh_options(&mut self) -> Result<(), String> {
+        let (reply, _) = self.request(cmd::CMD_REFRESHOPTION, &[]).await?;
+        expect_ack(cmd::CMD_REFRESHOPTION, reply)
+    }
+
+    /// Remove the user with record number `uid` (see [`Self::get_user_uids`])
+    /// and their fingerprints and cards from the device
+    pub async fn delete_user(&mut self, uid: u16) -> Result<(), String> {
+        let (reply, _) = self.request(cmd::CMD_DELETE_USER, &uid.to_le_bytes()).await?;
+        expect_ack(cmd::CMD_DELETE_USER, reply).map_err(|e| format!("Failed to delete user {}: {}", uid, e))
+    }
+
+    /// Set the device clock
+    pub async fn set_time(&mut self, time: &chrono::NaiveDateTime) -> Result<(), String> {
+        let (reply, _) = self.request(cmd::CMD_SET_TIME, &encode_zk_time(time).to_le_bytes()).await?;
+        expect_ack(cmd::CMD_SET_TIME, reply).map_err(|e| format!("Failed to set device time: {}", e))
+    }
+
+    /// Reboot the device. The connection is gone afterwards; a device that
+    /// drops it before acknowledging is treated as restarting.
+    pub async fn restart(&mut self) -> Result<(), String> {
+        let result = self.request(cmd::CMD_RESTART, &[]).await;
+        self.transport = None;
+        match result {
+            Ok((reply, _)) => expect_ack(cmd::CMD_RESTART, reply),
+            Err(e) => {
+                log::warn!("[zkteco] No reply to restart ({}); assuming the device is rebooting", e);
+                Ok(())
+            }
+        }
+    }
 }
 
 /// Format error messages for user-friendly display
 fn format_error(error: &str) -> String {
     let lower = error.to_lowercase();
 
+    // Already explains itself, and mentions replies that look like auth errors
+    if is_busy_error(error) {
+        return error.to_string();
+    }
+
     // Device unreachable — common on macOS when ARP/ICMP fails
     if lower.contains("no route to host")
         || lower.contains("ehostunreach")
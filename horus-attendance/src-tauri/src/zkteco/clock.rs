@@ -0,0 +1,200 @@
+//! Device clocks and daylight saving time
+//!
+//! Punches are stored as the site's wall-clock time. How a device's clock
+//! relates to that depends on its DST policy:
+//!
+//! - `zone`: the clock is kept on the site's local time, including DST
+//!   (someone changes it, or the app sets it). Timestamps are used as-is.
+//! - `none`: the clock stays on standard time all year. Timestamps are moved
+//!   onto the site's wall clock, so summer punches don't read an hour early.
+//! - `rules`: the device changes its own clock by a built-in regional rule
+//!   (`us`, `eu` or `au`) that may not match the site. Timestamps are undone
+//!   with that rule and then moved onto the site's wall clock.
+//!
+//! The site's zone is the device's `timezone` column (an IANA name).
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, TimeZone, Utc, Weekday};
+use chrono_tz::{OffsetComponents, Tz};
+use rusqlite::{Connection, OptionalExtension};
+
+use super::types::AttendanceLog;
+
+pub const POLICY_ZONE: &str = "zone";
+pub const POLICY_NONE: &str = "none";
+pub const POLICY_RULES: &str = "rules";
+
+/// Regional rules a device can apply by itself
+pub const REGIONS: &[&str] = &["us", "eu", "au"];
+
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.3fZ";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Policy {
+    Zone,
+    None,
+    Rules(Region),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Region {
+    Us,
+    Eu,
+    Au,
+}
+
+/// How one device's clock maps to the site's wall clock
+#[derive(Debug, Clone)]
+pub struct DeviceClock {
+    tz: Tz,
+    policy: Policy,
+}
+
+impl DeviceClock {
+    /// Build from a device's `timezone`, `dst_policy` and `dst_rules` values
+    pub fn new(timezone: &str, policy: &str, rules: Option<&str>) -> Result<Self, String> {
+        let policy = match policy {
+            POLICY_ZONE => Policy::Zone,
+            POLICY_NONE => Policy::None,
+            POLICY_RULES => Policy::Rules(match rules.unwrap_or("") {
+                "us" => Region::Us,
+                "eu" => Region::Eu,
+                "au" => Region::Au,
+                other => {
+                    return Err(format!(
+                        "Unknown DST rule '{}'; expected one of {}",
+                        other,
+                        REGIONS.join(", ")
+                    ))
+                }
+            }),
+            other => return Err(format!("Unknown DST policy: {}", other)),
+        };
+        let tz = match timezone.trim().parse::<Tz>() {
+            Ok(tz) => tz,
+            // Only the zone policy can do without a known zone
+            Err(_) if policy == Policy::Zone => Tz::UTC,
+            Err(_) => return Err(format!("Unknown timezone: {}", timezone)),
+        };
+        Ok(Self { tz, policy })
+    }
+
+    /// Clock settings of a saved device. Unknown devices are taken to be on
+    /// the site's local time.
+    pub fn load(conn: &Connection, device_id: &str) -> Result<Self, String> {
+        let row = conn
+            .query_row(
+                "SELECT timezone, dst_policy, dst_rules FROM devices WHERE id = ?1",
+                [device_id],
+                |row| {
+                    Ok((
+                        row.get::<_, Option<String>>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, Option<String>>(2)?,
+                    ))
+                },
+            )
+            .optional()
+            .map_err(|e| format!("Failed to load clock settings for device {}: {}", device_id, e))?;
+        match row {
+            Some((timezone, policy, rules)) => {
+                Self::new(timezone.as_deref().unwrap_or("UTC"), &policy, rules.as_deref())
+            }
+            None => Self::new("UTC", POLICY_ZONE, None),
+        }
+    }
+
+    /// True when timestamps from this device are already wall-clock time
+    pub fn is_identity(&self) -> bool {
+        self.policy == Policy::Zone
+    }
+
+    /// Site wall-clock time of a time read from the device
+    pub fn to_wall(&self, device_time: NaiveDateTime) -> NaiveDateTime {
+        let standard = match self.policy {
+            Policy::Zone => return device_time,
+            Policy::None => device_time,
+            Policy::Rules(region) => {
+                // During the repeated hour in autumn the earlier reading wins
+                let shifted = device_time - Duration::hours(1);
+                if region.is_dst(shifted, self.standard_offset(shifted)) {
+                    shifted
+                } else {
+                    device_time
+                }
+            }
+        };
+        let utc = standard - self.standard_offset(standard);
+        self.tz.from_utc_datetime(&utc).naive_local()
+    }
+
+    /// What the device should show at `now`
+    pub fn device_time(&self, now: DateTime<Utc>) -> NaiveDateTime {
+        let utc = now.naive_utc();
+        let standard = utc + self.standard_offset(utc);
+        match self.policy {
+            Policy::Zone => self.tz.from_utc_datetime(&utc).naive_local(),
+            Policy::None => standard,
+            Policy::Rules(region) if region.is_dst(standard, self.standard_offset(standard)) => {
+                standard + Duration::hours(1)
+            }
+            Policy::Rules(_) => standard,
+        }
+    }
+
+    /// Rewrite log timestamps onto the site's wall clock
+    pub fn normalize(&self, logs: &mut [AttendanceLog]) {
+        if self.is_identity() {
+            return;
+        }
+        for log in logs {
+            match NaiveDateTime::parse_from_str(&log.timestamp, TIMESTAMP_FORMAT) {
+                Ok(time) => log.timestamp = self.to_wall(time).format(TIMESTAMP_FORMAT).to_string(),
+                Err(e) => log::warn!("[zkteco::clock] Unparseable timestamp {}: {}", log.timestamp, e),
+            }
+        }
+    }
+
+    /// The zone's offset from UTC without DST around `time`
+    fn standard_offset(&self, time: NaiveDateTime) -> Duration {
+        let offset = self.tz.offset_from_utc_datetime(&time);
+        Duration::seconds(offset.base_utc_offset().num_seconds())
+    }
+}
+
+impl Region {
+    /// Whether the rule is in effect at `standard`, a standard-time reading
+    /// in a zone `offset` from UTC
+    fn is_dst(self, standard: NaiveDateTime, offset: Duration) -> bool {
+        let year = standard.year();
+        match self {
+            // Second Sunday in March to first Sunday in November, 02:00 local
+            Region::Us => {
+                let start = nth_sunday(year, 3, 2).and_hms_opt(2, 0, 0).unwrap();
+                let end = nth_sunday(year, 11, 1).and_hms_opt(1, 0, 0).unwrap();
+                standard >= start && standard < end
+            }
+            // Last Sunday in March to last Sunday in October, 01:00 UTC
+            Region::Eu => {
+                let start = last_sunday(year, 3).and_hms_opt(1, 0, 0).unwrap() + offset;
+                let end = last_sunday(year, 10).and_hms_opt(1, 0, 0).unwrap() + offset;
+                standard >= start && standard < end
+            }
+            // First Sunday in October to first Sunday in April, 02:00 standard
+            Region::Au => {
+                let end = nth_sunday(year, 4, 1).and_hms_opt(2, 0, 0).unwrap();
+                let start = nth_sunday(year, 10, 1).and_hms_opt(2, 0, 0).unwrap();
+                standard < end || standard >= start
+            }
+        }
+    }
+}
+
+/// The `n`th Sunday of a month
+fn nth_sunday(year: i32, month: u32, n: u8) -> NaiveDate {
+    NaiveDate::from_weekday_of_month_opt(year, month, Weekday::Sun, n).unwrap()
+}
+
+fn last_sunday(year: i32, month: u32) -> NaiveDate {
+    NaiveDate::from_weekday_of_month_opt(year, month, Weekday::Sun, 5)
+        .unwrap_or_else(|| nth_sunday(year, month, 4))
+}
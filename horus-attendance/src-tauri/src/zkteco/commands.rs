@@ -3,9 +3,18 @@
 //! These commands are invoked directly from the frontend,
 //! replacing the old sidecar HTTP proxy approach.
 
+use super::capabilities::{self, FEATURE_BELLS, FEATURE_OPTIONS, FEATURE_PAGED_ATTENDANCE};
 use super::client::ZKClient;
+use super::clock::DeviceClock;
+use super::hardware;
+use crate::diagnostics::store as diagnostics;
+use crate::sync_scheduler::gate as sync_gate;
+use super::protocol::is_busy_error;
 use super::types::*;
 
+/// Wait between sync attempts while the device menu is open
+const BUSY_RETRY_SECS: u64 = 15;
+
 /// Validate IP address format (basic IPv4 check)
 fn validate_ip(ip: &str) -> Result<(), String> {
     let parts: Vec<&str> = ip.split('.').collect();
@@ -30,43 +39,163 @@ fn validate_port(port: u16) -> Result<(), String> {
 }
 
 /// Validate device config before use
-fn validate_config(config: &DeviceConfig) -> Result<(), String> {
+pub(crate) fn validate_config(config: &DeviceConfig) -> Result<(), String> {
     validate_ip(&config.ip)?;
     validate_port(config.port)?;
     Ok(())
 }
 
-/// Test connection to a ZKTeco device
+/// Replace a keychain reference in the config with the actual comm key
+pub(crate) async fn resolve_comm_key(mut config: DeviceConfig) -> Result<DeviceConfig, String> {
+    if config.comm_key.as_deref() == Some(crate::secrets::keychain::KEYCHAIN_REF) {
+        let device_id = config.device_id.clone();
+        let key = tauri::async_runtime::spawn_blocking(move || {
+            crate::secrets::keychain::resolve_comm_key(device_id.as_deref(), crate::secrets::keychain::KEYCHAIN_REF)
+        })
+        .await
+        .map_err(|e| format!("Keychain task failed: {}", e))??;
+        config.comm_key = Some(key);
+    }
+    Ok(config)
+}
+
+/// Connection settings of a saved device
+pub(crate) fn saved_config(conn: &rusqlite::Connection, device_id: &str) -> Result<DeviceConfig, String> {
+    use rusqlite::OptionalExtension;
+    conn.query_row("SELECT ip, port, comm_key FROM devices WHERE id = ?1", [device_id], |row| {
+        Ok(DeviceConfig {
+            device_id: Some(device_id.to_string()),
+            ip: row.get(0)?,
+            port: row.get(1)?,
+            comm_key: row.get::<_, Option<String>>(2)?.filter(|k| !k.is_empty()),
+            timeout: Some(30000),
+        })
+    })
+    .optional()
+    .map_err(|e| format!("Failed to load device {}: {}", device_id, e))?
+    .ok_or_else(|| format!("Device not found: {}", device_id))
+}
+
+/// Clock settings of the saved device `config` refers to, if any
+fn device_clock(app: &tauri::AppHandle, config: &DeviceConfig) -> Result<Option<DeviceClock>, String> {
+    match config.device_id.as_deref() {
+        Some(device_id) => DeviceClock::load(&*crate::db::open(app)?, device_id).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// The offset an incremental sync reads from. Unsaved devices and firmware
+/// that can't read part of the buffer are switched to a full read.
+fn incremental_offset(
+    app: &tauri::AppHandle,
+    config: &DeviceConfig,
+    options: &mut Option<SyncOptions>,
+) -> Result<Option<DeviceLogOffset>, String> {
+    let Some(options) = options.as_mut().filter(|o| o.mode == "incremental") else {
+        return Ok(None);
+    };
+    let conn = crate::db::open(app)?;
+    match config.device_id.as_deref() {
+        Some(device_id) if capabilities::require(&conn, Some(device_id), FEATURE_PAGED_ATTENDANCE).is_ok() => {
+            super::offsets::load(&conn, device_id)
+        }
+        _ => {
+            log::info!("[zkteco::cmd] Incremental sync not possible for {}, reading all records", config.ip);
+            options.mode = "all".to_string();
+            Ok(None)
+        }
+    }
+}
+
+/// Test connection to a ZKTeco device. A saved device's hardware details
+/// are updated from the reply, and its capabilities probed the first time.
 #[tauri::command]
-pub async fn test_device_connection(config: DeviceConfig) -> Result<ConnectionTestResult, String> {
+pub async fn test_device_connection(app: tauri::AppHandle, config: DeviceConfig) -> Result<ConnectionTestResult, String> {
     validate_config(&config)?;
+    let config = resolve_comm_key(config).await?;
     log::info!(
         "[zkteco::cmd] test_device_connection {}:{}",
         config.ip,
         config.port
     );
-    Ok(ZKClient::test_connection(&config).await)
+    let result = ZKClient::test_connection(&config).await;
+    if let Some(info) = result.device_info.as_ref().filter(|_| result.success) {
+        hardware::remember(&app, config.device_id.as_deref(), &hardware::from_info(info));
+        capabilities::ensure(&app, &config).await;
+    }
+    diagnostics::remember(
+        &app,
+        config.device_id.as_deref(),
+        diagnostics::OP_CONNECTION_TEST,
+        result.latency,
+        (!result.success).then(|| result.error.as_deref().unwrap_or("Connection failed")),
+    );
+    Ok(result)
 }
 
-/// Get device info (user count, log count, serial, firmware)
+/// Get device info (user count, log count, serial, firmware, model, MAC)
 #[tauri::command]
-pub async fn get_device_info(config: DeviceConfig) -> Result<DeviceInfo, String> {
+pub async fn get_device_info(app: tauri::AppHandle, config: DeviceConfig) -> Result<DeviceInfo, String> {
     validate_config(&config)?;
+    let config = resolve_comm_key(config).await?;
     log::info!(
         "[zkteco::cmd] get_device_info {}:{}",
         config.ip,
         config.port
     );
+    let started = std::time::Instant::now();
+    let info = async {
+        let mut client = ZKClient::connect(&config).await?;
+        let info = client.get_device_info().await;
+        let _ = client.disconnect().await;
+        info
+    }
+    .await;
+    let latency = started.elapsed().as_millis() as u64;
+    diagnostics::remember(
+        &app,
+        config.device_id.as_deref(),
+        diagnostics::OP_DEVICE_INFO,
+        latency,
+        info.as_ref().err().map(String::as_str),
+    );
+    let info = info?;
+    hardware::remember(&app, config.device_id.as_deref(), &hardware::from_info(&info));
+    Ok(info)
+}
+
+/// Probe what a device's firmware supports, storing the result for a saved
+/// device. Saved devices are probed on their own when first reached; this
+/// is for after a change the firmware version doesn't show.
+#[tauri::command]
+pub async fn probe_device_capabilities(app: tauri::AppHandle, config: DeviceConfig) -> Result<DeviceCapabilities, String> {
+    validate_config(&config)?;
+    let config = resolve_comm_key(config).await?;
+    log::info!("[zkteco::cmd] probe_device_capabilities {}:{}", config.ip, config.port);
     let mut client = ZKClient::connect(&config).await?;
-    let info = client.get_device_info().await;
+    let probed = capabilities::probe(&mut client).await;
     let _ = client.disconnect().await;
-    info
+    let probed = probed?;
+    if let Some(device_id) = config.device_id.as_deref() {
+        capabilities::record(&*crate::db::open(&app)?, device_id, &probed)?;
+    }
+    Ok(probed)
+}
+
+/// What a saved device was found to support; None until it is probed
+#[tauri::command]
+pub async fn get_device_capabilities(
+    app: tauri::AppHandle,
+    device_id: String,
+) -> Result<Option<DeviceCapabilities>, String> {
+    capabilities::load(&*crate::db::open(&app)?, &device_id)
 }
 
 /// Get users from a ZKTeco device
 #[tauri::command]
-pub async fn get_device_users(config: DeviceConfig) -> Result<Vec<DeviceUser>, String> {
+pub async fn get_device_users(app: tauri::AppHandle, config: DeviceConfig) -> Result<Vec<DeviceUser>, String> {
     validate_config(&config)?;
+    let config = resolve_comm_key(config).await?;
     log::info!(
         "[zkteco::cmd] get_device_users {}:{}",
         config.ip,
@@ -75,59 +204,128 @@ pub async fn get_device_users(config: DeviceConfig) -> Result<Vec<DeviceUser>, S
     let mut client = ZKClient::connect(&config).await?;
     let users = client.get_users().await;
     let _ = client.disconnect().await;
-    users
+    let mut users = users?;
+    let conn = crate::db::open(&app)?;
+    crate::quarantine::store::screen_users(&conn, config.device_id.as_deref(), &mut users, true)?;
+    Ok(users)
 }
 
 /// Get attendance logs from a ZKTeco device
 #[tauri::command]
 pub async fn get_attendance_logs(
+    app: tauri::AppHandle,
     config: DeviceConfig,
     options: Option<SyncOptions>,
 ) -> Result<Vec<AttendanceLog>, String> {
     validate_config(&config)?;
+    let clock = device_clock(&app, &config)?;
+    let config = resolve_comm_key(config).await?;
     log::info!(
         "[zkteco::cmd] get_attendance_logs {}:{}",
         config.ip,
         config.port
     );
+    let _slot = sync_gate::enter(&app, &config).await?;
     let mut client = ZKClient::connect(&config).await?;
     let logs = client.get_attendance_logs(options.as_ref()).await;
     let _ = client.disconnect().await;
-    logs
+    let mut logs = logs?;
+    if let Some(clock) = clock {
+        clock.normalize(&mut logs);
+    }
+    let mut conn = crate::db::open(&app)?;
+    crate::quarantine::store::screen_logs(&conn, config.device_id.as_deref(), &mut logs, true)?;
+    crate::payloads::store::retain(&mut conn, config.device_id.as_deref(), &logs)?;
+    Ok(logs)
 }
 
 /// Combined sync: get users AND attendance logs in one operation
 /// Includes automatic retry on transient failures
 #[tauri::command]
 pub async fn sync_device_all(
+    app: tauri::AppHandle,
     config: DeviceConfig,
-    options: Option<SyncOptions>,
+    mut options: Option<SyncOptions>,
 ) -> Result<SyncAllResult, String> {
+    use tauri::Emitter;
+
     validate_config(&config)?;
+    let clock = device_clock(&app, &config)?;
+    let offset = incremental_offset(&app, &config, &mut options)?;
+    let config = resolve_comm_key(config).await?;
     log::info!(
         "[zkteco::cmd] sync_device_all {}:{}",
         config.ip,
         config.port
     );
+    // Held across the retries, so a busy terminal doesn't lose its turn
+    let _slot = sync_gate::enter(&app, &config).await?;
 
     // Retry up to 3 times on transient connection failures, with increasing backoff
     let max_retries = 3;
     let mut last_error = String::new();
+    let mut busy = false;
 
     for attempt in 0..=max_retries {
         if attempt > 0 {
-            let delay_secs = attempt as u64 * 2; // 2s, 4s, 6s
+            // Someone at the terminal needs time to leave the menu
+            let delay_secs = if busy { BUSY_RETRY_SECS } else { attempt as u64 * 2 }; // 2s, 4s, 6s
             log::info!("[zkteco::cmd] Retry attempt {} for sync_device_all (waiting {}s)", attempt, delay_secs);
+            if busy {
+                let event = DeviceBusyEvent {
+                    ip: config.ip.clone(),
+                    attempt,
+                    max_attempts: max_retries,
+                    retry_in_secs: delay_secs,
+                    message: last_error.clone(),
+                };
+                if let Err(e) = app.emit("device-busy", &event) {
+                    log::warn!("[zkteco::cmd] Failed to emit device-busy: {}", e);
+                }
+            }
             tokio::time::sleep(std::time::Duration::from_secs(delay_secs)).await;
         }
 
-        match ZKClient::sync_all(&config, options.as_ref()).await {
-            Ok(result) => return Ok(result),
+        let started = std::time::Instant::now();
+        let synced = ZKClient::sync_all(&config, options.as_ref(), offset.as_ref()).await;
+        diagnostics::remember(
+            &app,
+            config.device_id.as_deref(),
+            diagnostics::OP_SYNC,
+            started.elapsed().as_millis() as u64,
+            synced.as_ref().err().map(String::as_str),
+        );
+        match synced {
+            Ok(mut result) => {
+                if let Some(clock) = &clock {
+                    clock.normalize(&mut result.logs);
+                }
+                // Invalid records are kept for review, except on dry runs
+                // which only report how many there are
+                let dry_run = options.as_ref().is_some_and(|o| o.dry_run);
+                let mut conn = crate::db::open(&app)?;
+                let device_id = config.device_id.as_deref();
+                result.quarantined =
+                    crate::quarantine::store::screen_users(&conn, device_id, &mut result.users, !dry_run)?
+                        + crate::quarantine::store::screen_logs(&conn, device_id, &mut result.logs, !dry_run)?;
+                if dry_run {
+                    result.preview = Some(super::dry_run::preview(&conn, config.device_id.as_deref(), &result)?);
+                } else {
+                    crate::payloads::store::retain(&mut conn, device_id, &result.logs)?;
+                    if let Some(hardware) = &result.hardware {
+                        hardware::remember(&app, device_id, hardware);
+                    }
+                    drop(conn);
+                    capabilities::ensure(&app, &config).await;
+                }
+                return Ok(result);
+            }
             Err(e) => {
                 last_error = e.clone();
+                busy = is_busy_error(&e);
                 // Only retry on connection/timeout errors, not auth failures
                 let lower = e.to_lowercase();
-                if lower.contains("auth") || lower.contains("denied") {
+                if !busy && (lower.contains("auth") || lower.contains("denied")) {
                     return Err(e);
                 }
                 log::warn!("[zkteco::cmd] sync_device_all attempt {} failed: {}", attempt, e);
@@ -137,3 +335,253 @@ pub async fn sync_device_all(
 
     Err(last_error)
 }
+
+/// Save how far an incremental sync read a device, once the logs it
+/// returned are stored. Returns false for an unknown device.
+#[tauri::command]
+pub async fn save_sync_offset(app: tauri::AppHandle, device_id: String, offset: DeviceLogOffset) -> Result<bool, String> {
+    let saved = super::offsets::save(&*crate::db::open(&app)?, &device_id, &offset)?;
+    if saved {
+        log::info!("[zkteco::cmd] Sync offset of {} now at record {}", device_id, offset.record_count);
+    }
+    Ok(saved)
+}
+
+/// Forget a device's sync offset, so its next incremental sync reads every
+/// record again
+#[tauri::command]
+pub async fn reset_sync_offset(app: tauri::AppHandle, device_id: String) -> Result<bool, String> {
+    log::info!("[zkteco::cmd] reset_sync_offset {}", device_id);
+    super::offsets::clear(&*crate::db::open(&app)?, &device_id)
+}
+
+/// Check ping, the TCP and UDP ports, the ARP table, authentication and a
+/// free-sizes read one at a time, for finding out why a device won't
+/// connect. Each step reports whether it passed and what to try if not.
+#[tauri::command]
+pub async fn run_connectivity_diagnostics(config: DeviceConfig) -> Result<ConnectivityReport, String> {
+    validate_config(&config)?;
+    let config = resolve_comm_key(config).await?;
+    log::info!("[zkteco::cmd] run_connectivity_diagnostics {}:{}", config.ip, config.port);
+    let report = super::connectivity::diagnose(&config).await;
+    log::info!(
+        "[zkteco::cmd] Diagnostics for {}:{}: {}",
+        config.ip,
+        config.port,
+        if report.success { "connected" } else { "failed" }
+    );
+    Ok(report)
+}
+
+/// Probe every address in `cidr` (e.g. "192.168.1.0/24") for devices, for
+/// networks where discovery broadcasts are blocked
+#[tauri::command]
+pub async fn scan_ip_range(cidr: String, port: Option<u16>, timeout_ms: Option<u64>) -> Result<Vec<ScanHost>, String> {
+    let port = port.unwrap_or(4370);
+    validate_port(port)?;
+    super::scan::scan(&cidr, port, timeout_ms.unwrap_or(super::scan::DEFAULT_PROBE_TIMEOUT_MS)).await
+}
+
+/// Read a device's IP address, netmask, gateway and DHCP flag
+#[tauri::command]
+pub async fn get_device_network(app: tauri::AppHandle, config: DeviceConfig) -> Result<NetworkSettings, String> {
+    validate_config(&config)?;
+    capabilities::require(&*crate::db::open(&app)?, config.device_id.as_deref(), FEATURE_OPTIONS)?;
+    let config = resolve_comm_key(config).await?;
+    log::info!("[zkteco::cmd] get_device_network {}:{}", config.ip, config.port);
+    let mut client = ZKClient::connect(&config).await?;
+    let result = super::network::read(&mut client).await;
+    let _ = client.disconnect().await;
+    result
+}
+
+/// Change a device's network settings, restart it and reconnect at the new
+/// address. The device drops off the network while it restarts, so the
+/// caller has to pass `confirm`. When the device answers at a new static
+/// address its stored IP is updated.
+#[tauri::command]
+pub async fn set_device_network(
+    app: tauri::AppHandle,
+    config: DeviceConfig,
+    settings: NetworkSettings,
+    confirm: bool,
+) -> Result<NetworkChangeResult, String> {
+    if !confirm {
+        return Err("Changing network settings restarts the device and may make it unreachable; confirm to continue".to_string());
+    }
+    validate_config(&config)?;
+    capabilities::require(&*crate::db::open(&app)?, config.device_id.as_deref(), FEATURE_OPTIONS)?;
+    let config = resolve_comm_key(config).await?;
+    log::info!("[zkteco::cmd] set_device_network {}:{}", config.ip, config.port);
+    let result = super::network::apply(&config, &settings).await?;
+
+    if let (true, Some(address), Some(device_id)) = (result.reconnected, &result.address, &config.device_id) {
+        if *address != config.ip {
+            crate::db::open(&app)?
+                .execute(
+                    "UPDATE devices SET ip = ?1, updated_at = datetime('now') WHERE id = ?2",
+                    rusqlite::params![address, device_id],
+                )
+                .map_err(|e| format!("Device moved to {} but its address could not be saved: {}", address, e))?;
+        }
+    }
+    Ok(result)
+}
+
+/// Read a device's bell schedule
+#[tauri::command]
+pub async fn get_device_bells(app: tauri::AppHandle, config: DeviceConfig) -> Result<Vec<Bell>, String> {
+    validate_config(&config)?;
+    capabilities::require(&*crate::db::open(&app)?, config.device_id.as_deref(), FEATURE_BELLS)?;
+    let config = resolve_comm_key(config).await?;
+    log::info!("[zkteco::cmd] get_device_bells {}:{}", config.ip, config.port);
+    let mut client = ZKClient::connect(&config).await?;
+    let result = super::bells::read(&mut client).await;
+    let _ = client.disconnect().await;
+    result
+}
+
+/// Replace a device's bell schedule
+#[tauri::command]
+pub async fn set_device_bells(app: tauri::AppHandle, config: DeviceConfig, bells: Vec<Bell>) -> Result<(), String> {
+    validate_config(&config)?;
+    capabilities::require(&*crate::db::open(&app)?, config.device_id.as_deref(), FEATURE_BELLS)?;
+    super::bells::validate(&bells)?;
+    let config = resolve_comm_key(config).await?;
+    log::info!("[zkteco::cmd] set_device_bells {}:{} ({} bells)", config.ip, config.port, bells.len());
+    let mut client = ZKClient::connect(&config).await?;
+    let result = super::bells::write(&mut client, &bells).await;
+    let _ = client.disconnect().await;
+    result
+}
+
+/// Read the device settings the app manages (volume, match thresholds,
+/// 1:1 mode)
+#[tauri::command]
+pub async fn get_device_options(app: tauri::AppHandle, config: DeviceConfig) -> Result<Vec<DeviceOptionValue>, String> {
+    validate_config(&config)?;
+    capabilities::require(&*crate::db::open(&app)?, config.device_id.as_deref(), FEATURE_OPTIONS)?;
+    let config = resolve_comm_key(config).await?;
+    log::info!("[zkteco::cmd] get_device_options {}:{}", config.ip, config.port);
+    let mut client = ZKClient::connect(&config).await?;
+    let result = super::options::read(&mut client).await;
+    let _ = client.disconnect().await;
+    result
+}
+
+/// Change device settings, keyed as returned by `get_device_options`
+#[tauri::command]
+pub async fn set_device_options(
+    app: tauri::AppHandle,
+    config: DeviceConfig,
+    values: std::collections::BTreeMap<String, String>,
+) -> Result<Vec<DeviceOptionValue>, String> {
+    validate_config(&config)?;
+    capabilities::require(&*crate::db::open(&app)?, config.device_id.as_deref(), FEATURE_OPTIONS)?;
+    super::options::validate(&values)?;
+    let config = resolve_comm_key(config).await?;
+    log::info!("[zkteco::cmd] set_device_options {}:{} ({:?})", config.ip, config.port, values.keys());
+    let mut client = ZKClient::connect(&config).await?;
+    let result = match super::options::write(&mut client, &values).await {
+        Ok(()) => super::options::read(&mut client).await,
+        Err(e) => Err(e),
+    };
+    let _ = client.disconnect().await;
+    result
+}
+
+/// Set the device clock to the current time as its DST policy expects it,
+/// returning the time written
+#[tauri::command]
+pub async fn set_device_time(app: tauri::AppHandle, config: DeviceConfig) -> Result<String, String> {
+    validate_config(&config)?;
+    let clock = device_clock(&app, &config)?;
+    let config = resolve_comm_key(config).await?;
+    let mut client = ZKClient::connect(&config).await?;
+    // A device that isn't saved yet gets this computer's local time
+    let time = match clock {
+        Some(clock) => clock.device_time(chrono::Utc::now()),
+        None => chrono::Local::now().naive_local(),
+    };
+    log::info!("[zkteco::cmd] set_device_time {}:{} to {}", config.ip, config.port, time);
+    let result = client.set_time(&time).await;
+    let _ = client.disconnect().await;
+    result.map(|_| time.format("%Y-%m-%dT%H:%M:%S").to_string())
+}
+
+/// Import all attendance records of a saved device in bounded passes,
+/// resuming from where an interrupted import stopped. Progress is emitted as
+/// `initial-import-progress` after every pass.
+#[tauri::command]
+pub async fn initial_import(
+    app: tauri::AppHandle,
+    config: DeviceConfig,
+    options: Option<InitialImportOptions>,
+) -> Result<InitialImportStatus, String> {
+    validate_config(&config)?;
+    let options = options.unwrap_or_default();
+    super::import::validate_options(&options)?;
+    capabilities::require(&*crate::db::open(&app)?, config.device_id.as_deref(), FEATURE_PAGED_ATTENDANCE)?;
+    let config = resolve_comm_key(config).await?;
+    log::info!("[zkteco::cmd] initial_import {}:{}", config.ip, config.port);
+    super::import::run(&app, &config, &options).await
+}
+
+/// Progress of a device's initial import, if one was started
+#[tauri::command]
+pub async fn get_initial_import_status(
+    app: tauri::AppHandle,
+    device_id: String,
+) -> Result<Option<InitialImportStatus>, String> {
+    super::import::load(&*crate::db::open(&app)?, &device_id)
+}
+
+/// Compare a saved device's records with those stored for it, without
+/// syncing: its most recent records by default, or all of them
+#[tauri::command]
+pub async fn compare_device_with_db(
+    app: tauri::AppHandle,
+    device_id: String,
+    options: Option<DeviceAuditOptions>,
+) -> Result<DeviceAuditReport, String> {
+    let options = options.unwrap_or_default();
+    if options.sample_size.is_some_and(|n| n == 0 || n > super::audit::MAX_SAMPLE_SIZE) {
+        return Err(format!("Sample size must be between 1 and {}", super::audit::MAX_SAMPLE_SIZE));
+    }
+    let config = saved_config(&*crate::db::open(&app)?, &device_id)?;
+    validate_config(&config)?;
+    let config = resolve_comm_key(config).await?;
+    log::info!("[zkteco::cmd] compare_device_with_db {} ({}:{})", device_id, config.ip, config.port);
+    super::audit::run(&app, &config, &device_id, &options).await
+}
+
+/// Times of day a device may be synced unattended; none means any time
+#[tauri::command]
+pub async fn get_sync_windows(app: tauri::AppHandle, device_id: String) -> Result<Vec<SyncWindow>, String> {
+    super::sync_window::load(&*crate::db::open(&app)?, &device_id)
+}
+
+/// Replace a device's sync windows; an empty list lifts the restriction
+#[tauri::command]
+pub async fn set_sync_windows(
+    app: tauri::AppHandle,
+    device_id: String,
+    windows: Vec<SyncWindow>,
+) -> Result<Vec<SyncWindow>, String> {
+    log::info!("[zkteco::cmd] set_sync_windows {} ({} windows)", device_id, windows.len());
+    let mut conn = crate::db::open(&app)?;
+    super::sync_window::save(&mut conn, &device_id, &windows)?;
+    super::sync_window::load(&conn, &device_id)
+}
+
+/// Whether a device may be synced now. Scheduled syncs are refused outside its
+/// windows; manual ones are allowed with a warning.
+#[tauri::command]
+pub async fn check_sync_window(
+    app: tauri::AppHandle,
+    device_id: String,
+    scheduled: Option<bool>,
+) -> Result<SyncWindowCheck, String> {
+    let now = chrono::Local::now().naive_local();
+    super::sync_window::check(&*crate::db::open(&app)?, &device_id, scheduled.unwrap_or(false), now)
+}
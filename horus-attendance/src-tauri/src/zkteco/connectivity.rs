@@ -0,0 +1,316 @@
+//! Stepwise connectivity diagnosis
+//!
+//! A failed connection only says how the last attempt ended. This walks the
+//! layers one at a time — ping, the TCP port, the ARP table, a UDP
+//! handshake, authentication and a free-sizes read — so the help screen can
+//! show which one broke and what to try. Ping and ARP go through the
+//! system's `ping` and `arp` tools (raw sockets need privileges the app
+//! doesn't have), and count as warnings only: many networks block ICMP, and
+//! routed devices never appear in the ARP table.
+
+use std::net::{Ipv4Addr, SocketAddr};
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+
+use super::client::ZKClient;
+use super::demo::DemoDevice;
+use super::protocol::is_busy_error;
+use super::types::{ConnectivityReport, ConnectivityStep, DeviceConfig};
+use super::udp::ZKUdp;
+
+pub const STEP_PASSED: &str = "passed";
+pub const STEP_WARNING: &str = "warning";
+pub const STEP_FAILED: &str = "failed";
+pub const STEP_SKIPPED: &str = "skipped";
+
+/// Longest wait for any single step
+const STEP_TIMEOUT_MS: u64 = 5000;
+
+/// Ping wait per echo request
+const PING_TIMEOUT_MS: u64 = 1500;
+
+fn step(id: &str, label: &str, status: &str, started: Instant) -> ConnectivityStep {
+    ConnectivityStep {
+        id: id.to_string(),
+        label: label.to_string(),
+        status: status.to_string(),
+        detail: None,
+        suggestion: None,
+        duration_ms: started.elapsed().as_millis() as u64,
+    }
+}
+
+impl ConnectivityStep {
+    fn detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    fn suggest(mut self, suggestion: &str) -> Self {
+        self.suggestion = Some(suggestion.to_string());
+        self
+    }
+}
+
+/// Run a system tool, or None when it can't be started
+pub(crate) fn run_tool(program: &str, args: &[String]) -> Option<std::process::Output> {
+    let mut command = Command::new(program);
+    command.args(args);
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        // CREATE_NO_WINDOW: don't flash a console window
+        command.creation_flags(0x0800_0000);
+    }
+    command.output().ok()
+}
+
+/// One ICMP echo through the system `ping`: Some(true) on a reply, None
+/// when ping can't be run here
+fn ping(ip: &str) -> Option<bool> {
+    let args: Vec<String> = if cfg!(windows) {
+        vec!["-n".into(), "1".into(), "-w".into(), PING_TIMEOUT_MS.to_string(), ip.into()]
+    } else if cfg!(target_os = "macos") {
+        vec!["-c".into(), "1".into(), "-W".into(), PING_TIMEOUT_MS.to_string(), ip.into()]
+    } else {
+        vec!["-c".into(), "1".into(), "-W".into(), PING_TIMEOUT_MS.div_ceil(1000).to_string(), ip.into()]
+    };
+    let output = run_tool("ping", &args)?;
+    // Windows ping exits 0 on "Destination host unreachable" from the gateway
+    let replied = output.status.success()
+        && (!cfg!(windows) || String::from_utf8_lossy(&output.stdout).contains("TTL="));
+    Some(replied)
+}
+
+/// A MAC address in `text`, unless it's blank or incomplete
+fn find_mac(text: &str) -> Option<String> {
+    text.split_whitespace().find_map(|token| {
+        let groups: Vec<&str> = token.split([':', '-']).collect();
+        let is_mac = groups.len() == 6
+            && groups
+                .iter()
+                .all(|g| (1..=2).contains(&g.len()) && g.chars().all(|c| c.is_ascii_hexdigit()));
+        let blank = groups.iter().all(|g| g.chars().all(|c| c == '0'));
+        (is_mac && !blank).then(|| token.replace('-', ":").to_lowercase())
+    })
+}
+
+/// The device's MAC address from this computer's ARP table: Some(None)
+/// when there's no entry, None when the table can't be read
+fn arp_entry(ip: &str) -> Option<Option<String>> {
+    if cfg!(target_os = "linux") {
+        let table = std::fs::read_to_string("/proc/net/arp").ok()?;
+        return Some(
+            table
+                .lines()
+                .skip(1)
+                .find(|line| line.split_whitespace().next() == Some(ip))
+                .and_then(find_mac),
+        );
+    }
+    let args: Vec<String> = if cfg!(windows) {
+        vec!["-a".into(), ip.into()]
+    } else {
+        vec!["-n".into(), ip.into()]
+    };
+    let output = run_tool("arp", &args)?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    Some(text.lines().filter(|line| line.contains(ip)).find_map(find_mac))
+}
+
+async fn check_ping(ip: &str) -> ConnectivityStep {
+    let started = Instant::now();
+    let ip_owned = ip.to_string();
+    let replied = tauri::async_runtime::spawn_blocking(move || ping(&ip_owned))
+        .await
+        .ok()
+        .flatten();
+    match replied {
+        Some(true) => step("ping", "Ping", STEP_PASSED, started).detail("The device answered ping"),
+        Some(false) => step("ping", "Ping", STEP_WARNING, started)
+            .detail("No reply to ping")
+            .suggest(
+                "Many networks and some devices block ping, so this alone isn't a fault. \
+                 If the port checks fail too, check the device is on and its IP address.",
+            ),
+        None => step("ping", "Ping", STEP_SKIPPED, started).detail("ping isn't available on this computer"),
+    }
+}
+
+async fn check_tcp_port(ip: Ipv4Addr, port: u16, timeout_ms: u64) -> ConnectivityStep {
+    let started = Instant::now();
+    let label = format!("TCP port {}", port);
+    let result = timeout(Duration::from_millis(timeout_ms), TcpStream::connect(SocketAddr::from((ip, port)))).await;
+    match result {
+        Ok(Ok(stream)) => {
+            drop(stream);
+            step("tcp_port", &label, STEP_PASSED, started).detail("The port accepted a connection")
+        }
+        Ok(Err(e)) if e.kind() == std::io::ErrorKind::ConnectionRefused => step("tcp_port", &label, STEP_FAILED, started)
+            .detail(format!("Connection refused: {}", e))
+            .suggest(
+                "Something answered at this address but not on this port. Check the port in the device's \
+                 Comm. settings (4370 by default), and that the IP isn't used by another machine.",
+            ),
+        Ok(Err(e)) => step("tcp_port", &label, STEP_FAILED, started)
+            .detail(format!("Connection failed: {}", e))
+            .suggest(
+                "The device can't be reached. Check it is powered on and cabled, and that this computer \
+                 is on the same network or has a route to it.",
+            ),
+        Err(_) => step("tcp_port", &label, STEP_FAILED, started)
+            .detail(format!("No answer within {}ms", timeout_ms))
+            .suggest(
+                "Nothing answered. Check the device's IP address on its Comm. menu, that it is powered on, \
+                 and that no firewall blocks the port. Older devices may only answer over UDP.",
+            ),
+    }
+}
+
+async fn check_arp(ip: &str) -> ConnectivityStep {
+    let started = Instant::now();
+    let ip_owned = ip.to_string();
+    let entry = tauri::async_runtime::spawn_blocking(move || arp_entry(&ip_owned))
+        .await
+        .ok()
+        .flatten();
+    match entry {
+        Some(Some(mac)) => step("arp", "ARP table", STEP_PASSED, started).detail(format!("Found on the local network as {}", mac)),
+        Some(None) => step("arp", "ARP table", STEP_WARNING, started)
+            .detail("No ARP entry for this address")
+            .suggest(
+                "The device isn't on this computer's local network segment, or didn't answer. That is \
+                 expected across routers or VPNs; on the same network it means the device is off or \
+                 has another IP address.",
+            ),
+        None => step("arp", "ARP table", STEP_SKIPPED, started).detail("The ARP table can't be read on this computer"),
+    }
+}
+
+async fn check_udp(ip: &str, port: u16, timeout_ms: u64) -> ConnectivityStep {
+    let started = Instant::now();
+    let label = format!("UDP port {}", port);
+    let mut udp = ZKUdp::new(ip, port, timeout_ms);
+    let result = udp.connect().await;
+    let _ = udp.disconnect().await;
+    match result {
+        Ok(()) => step("udp", &label, STEP_PASSED, started).detail("The device answered the handshake over UDP"),
+        Err(e) if is_busy_error(&e) => step("udp", &label, STEP_PASSED, started).detail(e),
+        Err(e) => step("udp", &label, STEP_FAILED, started)
+            .detail(e)
+            .suggest("The device didn't answer over UDP. This is fine when TCP works; otherwise check the port and firewall."),
+    }
+}
+
+/// Check each layer between this computer and the device in turn. Later
+/// steps are skipped when nothing reached the device.
+pub async fn diagnose(config: &DeviceConfig) -> ConnectivityReport {
+    let timeout_ms = config.timeout.unwrap_or(STEP_TIMEOUT_MS).min(STEP_TIMEOUT_MS);
+    let mut report = ConnectivityReport {
+        ip: config.ip.clone(),
+        port: config.port,
+        success: false,
+        transport: None,
+        steps: Vec::new(),
+        summary: String::new(),
+    };
+
+    let is_demo = DemoDevice::connect(&config.ip).is_some();
+    let mut reachable = true;
+    if is_demo {
+        for (id, label) in [("ping", "Ping"), ("tcp_port", "TCP port"), ("arp", "ARP table"), ("udp", "UDP port")] {
+            report
+                .steps
+                .push(step(id, label, STEP_SKIPPED, Instant::now()).detail("Demo device, simulated in the app"));
+        }
+    } else {
+        let ip: Ipv4Addr = match config.ip.parse() {
+            Ok(ip) => ip,
+            Err(_) => {
+                report.summary = format!("Invalid IP address: {}", config.ip);
+                return report;
+            }
+        };
+        report.steps.push(check_ping(&config.ip).await);
+        report.steps.push(check_tcp_port(ip, config.port, timeout_ms).await);
+        // The connection attempts above fill the ARP table when the device is local
+        report.steps.push(check_arp(&config.ip).await);
+        report.steps.push(check_udp(&config.ip, config.port, timeout_ms).await);
+        reachable = report
+            .steps
+            .iter()
+            .any(|s| (s.id == "tcp_port" || s.id == "udp") && s.status == STEP_PASSED);
+    }
+
+    let auth_label = if config.comm_key.as_deref().map_or(true, |k| k.is_empty() || k == "0") {
+        "Handshake (no comm key)"
+    } else {
+        "Handshake and comm key"
+    };
+    if !reachable {
+        report.steps.push(step("auth", auth_label, STEP_SKIPPED, Instant::now()).detail("The device port couldn't be reached"));
+        report.steps.push(step("free_sizes", "Read device counts", STEP_SKIPPED, Instant::now()).detail("Not connected"));
+    } else {
+        let started = Instant::now();
+        let connect_config = DeviceConfig {
+            timeout: Some(timeout_ms),
+            ..config.clone()
+        };
+        match ZKClient::connect(&connect_config).await {
+            Ok(mut client) => {
+                report.transport = client.transport_name().map(str::to_string);
+                report.steps.push(step("auth", auth_label, STEP_PASSED, started).detail(format!(
+                    "Connected over {}",
+                    report.transport.as_deref().unwrap_or("?").to_uppercase()
+                )));
+                let started = Instant::now();
+                match client.get_free_sizes().await {
+                    Ok((users, logs)) => {
+                        report.success = true;
+                        report.steps.push(
+                            step("free_sizes", "Read device counts", STEP_PASSED, started)
+                                .detail(format!("{} users and {} attendance records on the device", users, logs)),
+                        );
+                    }
+                    Err(e) => report.steps.push(
+                        step("free_sizes", "Read device counts", STEP_FAILED, started).detail(e).suggest(
+                            "The device accepted the connection but refused to answer. A comm key set on the \
+                             device but not here has this effect; otherwise restart the device.",
+                        ),
+                    ),
+                }
+                let _ = client.disconnect().await;
+            }
+            Err(e) => {
+                let suggestion = if is_busy_error(&e) {
+                    "The device is busy, usually because its menu is open. Close the menu on the device and try again."
+                } else if e.to_lowercase().contains("auth") {
+                    "The comm key doesn't match the one set on the device (System > Comm. > Comm Key)."
+                } else {
+                    "The port is open but the device didn't complete the handshake. Check that this is a \
+                     ZKTeco terminal and that no other program is connected to it."
+                };
+                report.steps.push(step("auth", auth_label, STEP_FAILED, started).detail(e).suggest(suggestion));
+                report.steps.push(step("free_sizes", "Read device counts", STEP_SKIPPED, Instant::now()).detail("Not connected"));
+            }
+        }
+    }
+
+    // The deepest failure explains the most; UDP failing only matters when TCP did too
+    report.summary = if report.success {
+        "The device is reachable and answering.".to_string()
+    } else {
+        report
+            .steps
+            .iter()
+            .rev()
+            .filter(|s| s.status == STEP_FAILED)
+            .min_by_key(|s| s.id == "udp")
+            .and_then(|s| s.suggestion.clone())
+            .unwrap_or_else(|| "The device couldn't be reached.".to_string())
+    };
+    report
+}
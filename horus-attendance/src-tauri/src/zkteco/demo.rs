@@ -0,0 +1,271 @@
+//! Simulated device for demo devices
+//!
+//! Stands in for the TCP/UDP transports when the client connects to the
+//! address of a demo device. Users and punches are derived from the
+//! device's profile and seed alone, so every session sees the same data
+//! plus whatever the passing time has added. Options written are kept for
+//! the rest of the app's run, so settings screens behave; other commands
+//! are acknowledged and ignored.
+
+use chrono::{Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use super::protocol::{cmd, encode_zk_time, RawAttendance};
+use crate::demo::types::DemoProfile;
+
+/// Timestamp format of device records
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.3fZ";
+
+const VERIFY_FINGERPRINT: u8 = 1;
+const VERIFY_FACE: u8 = 15;
+const PUNCH_IN: u8 = 0;
+const PUNCH_OUT: u8 = 1;
+
+const FIRST_NAMES: &[&str] = &[
+    "Amina", "Ben", "Carla", "Dev", "Elena", "Farid", "Grace", "Hassan", "Ines", "Jonas", "Kofi", "Lina", "Mateo",
+    "Nadia", "Omar", "Priya", "Quinn", "Rosa", "Samir", "Tara", "Umar", "Vera", "Wei", "Yusuf",
+];
+const LAST_NAMES: &[&str] = &[
+    "Adeyemi", "Baker", "Costa", "Dubois", "Eriksen", "Fischer", "Garcia", "Haddad", "Ibrahim", "Jensen", "Kim",
+    "Lopez", "Mensah", "Novak", "Okafor", "Patel", "Rossi", "Santos", "Tanaka", "Usman", "Varga", "Walsh", "Yilmaz",
+    "Zhang",
+];
+
+/// A demo device's profile and the options written to it
+struct Simulated {
+    profile: DemoProfile,
+    options: HashMap<String, String>,
+}
+
+/// Demo devices by address
+static DEVICES: Mutex<Option<HashMap<String, Simulated>>> = Mutex::new(None);
+
+/// Replace the set of demo devices. Options written to a device that is
+/// still there are kept.
+pub fn install(devices: Vec<(String, DemoProfile)>) {
+    let mut registry = DEVICES.lock().unwrap_or_else(|e| e.into_inner());
+    let mut previous = registry.take().unwrap_or_default();
+    let devices = devices
+        .into_iter()
+        .map(|(ip, profile)| {
+            let options = previous.remove(&ip).map(|s| s.options).unwrap_or_default();
+            (ip, Simulated { profile, options })
+        })
+        .collect();
+    *registry = Some(devices);
+}
+
+/// A session with a demo device
+pub struct DemoDevice {
+    ip: String,
+    profile: DemoProfile,
+}
+
+impl DemoDevice {
+    /// A session if `ip` is a demo device's address
+    pub fn connect(ip: &str) -> Option<Self> {
+        let registry = DEVICES.lock().unwrap_or_else(|e| e.into_inner());
+        let simulated = registry.as_ref()?.get(ip)?;
+        Some(Self {
+            ip: ip.to_string(),
+            profile: simulated.profile.clone(),
+        })
+    }
+
+    /// (user count, log count)
+    pub fn get_info(&self) -> (u32, u32) {
+        (self.profile.user_count, self.records().len() as u32)
+    }
+
+    /// (uid, user ID, name) for every user
+    pub fn get_users(&self) -> Vec<(u16, String, String)> {
+        (1..=self.profile.user_count)
+            .map(|user| (user as u16, user.to_string(), person_name(self.profile.seed, user)))
+            .collect()
+    }
+
+    pub fn get_attendances(&self) -> Vec<RawAttendance> {
+        self.records()
+    }
+
+    /// Records `start..start + count` and the total
+    pub fn get_attendance_page(&self, start: usize, count: usize) -> (Vec<RawAttendance>, usize) {
+        let records = self.records();
+        let total = records.len();
+        let page = records.into_iter().skip(start).take(count).collect();
+        (page, total)
+    }
+
+    /// Reply code and payload for a protocol command
+    pub fn request(&mut self, command: u16, data: &[u8]) -> (u16, Vec<u8>) {
+        let text = || {
+            let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+            String::from_utf8_lossy(&data[..end]).to_string()
+        };
+        match command {
+            cmd::CMD_OPTIONS_RRQ => {
+                let name = text();
+                match self.option(&name) {
+                    Some(value) => {
+                        let mut reply = format!("{}={}", name, value).into_bytes();
+                        reply.push(0);
+                        (cmd::CMD_ACK_OK, reply)
+                    }
+                    None => (cmd::CMD_ACK_OK, Vec::new()),
+                }
+            }
+            cmd::CMD_OPTIONS_WRQ => {
+                if let Some((name, value)) = text().split_once('=') {
+                    let mut registry = DEVICES.lock().unwrap_or_else(|e| e.into_inner());
+                    if let Some(simulated) = registry.as_mut().and_then(|r| r.get_mut(&self.ip)) {
+                        simulated.options.insert(name.to_string(), value.to_string());
+                    }
+                }
+                (cmd::CMD_ACK_OK, Vec::new())
+            }
+            cmd::CMD_GET_VERSION => (cmd::CMD_ACK_OK, b"Ver 6.60 Demo\0".to_vec()),
+            cmd::CMD_GET_TIME => (
+                cmd::CMD_ACK_OK,
+                encode_zk_time(&Local::now().naive_local()).to_le_bytes().to_vec(),
+            ),
+            _ => (cmd::CMD_ACK_OK, Vec::new()),
+        }
+    }
+
+    fn option(&self, name: &str) -> Option<String> {
+        let registry = DEVICES.lock().unwrap_or_else(|e| e.into_inner());
+        let written = registry
+            .as_ref()
+            .and_then(|r| r.get(&self.ip))
+            .and_then(|s| s.options.get(name).cloned());
+        written.or_else(|| match name {
+            "IPAddress" => Some(self.ip.clone()),
+            "NetMask" => Some("255.255.255.0".to_string()),
+            "GATEWAYIPAddress" => Some("192.0.2.254".to_string()),
+            "DHCP" => Some("0".to_string()),
+            "~DeviceName" => Some("Demo Terminal".to_string()),
+            "~SerialNumber" => Some(format!("DEMO{:08X}", self.profile.seed)),
+            "~ZKFPVersion" => Some("10".to_string()),
+            "FaceFunOn" => Some("1".to_string()),
+            "~PIN2Width" => Some("9".to_string()),
+            "MAC" => {
+                let octets: Vec<u8> = self.ip.split('.').filter_map(|o| o.parse().ok()).collect();
+                let tail = octets.iter().rev().take(3).rev().map(|o| format!(":{:02x}", o)).collect::<String>();
+                Some(format!("00:17:61{}", tail))
+            }
+            _ => None,
+        })
+    }
+
+    /// Pseudo-random number for a user on a day; `salt` picks which decision
+    fn roll(&self, user: u32, day: i64, salt: u64) -> u64 {
+        roll(self.profile.seed, user, day, salt)
+    }
+
+    fn chance(&self, user: u32, day: i64, salt: u64) -> f64 {
+        chance(self.profile.seed, user, day, salt)
+    }
+
+    fn between(&self, user: u32, day: i64, salt: u64, low: i64, high: i64) -> i64 {
+        between(self.profile.seed, user, day, salt, low, high)
+    }
+
+    /// Every punch up to now, oldest first, as the device would store them
+    fn records(&self) -> Vec<RawAttendance> {
+        let profile = &self.profile;
+        let now = Local::now().naive_local();
+        let start = profile
+            .start_date
+            .as_deref()
+            .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+            .unwrap_or(now.date());
+        let work_start = NaiveTime::parse_from_str(&profile.work_start, "%H:%M").unwrap_or_default();
+        let work_end = NaiveTime::parse_from_str(&profile.work_end, "%H:%M").unwrap_or(work_start);
+
+        let mut records: Vec<(NaiveDateTime, u32, u8)> = Vec::new();
+        let mut date = start;
+        while date <= now.date() {
+            let day = (date - start).num_days();
+            if profile.work_days.contains(&date.weekday().number_from_monday()) {
+                for user in 1..=profile.user_count {
+                    if self.chance(user, day, 10) < profile.absence_rate {
+                        continue;
+                    }
+                    let arrival = if self.chance(user, day, 11) < profile.late_rate {
+                        self.between(user, day, 12, 5 * 60, 45 * 60)
+                    } else {
+                        self.between(user, day, 12, -20 * 60, 2 * 60)
+                    };
+                    let departure = if self.chance(user, day, 13) < profile.overtime_rate {
+                        self.between(user, day, 14, 30 * 60, 150 * 60)
+                    } else {
+                        self.between(user, day, 14, -5 * 60, 15 * 60)
+                    };
+                    let arrival = date.and_time(work_start) + Duration::seconds(arrival);
+                    let departure = date.and_time(work_end) + Duration::seconds(departure);
+
+                    // A forgotten punch is the arrival or the departure
+                    let missed = self.chance(user, day, 15) < profile.missed_punch_rate;
+                    let missed_arrival = missed && self.roll(user, day, 16) % 2 == 0;
+                    if !missed_arrival {
+                        records.push((arrival, user, PUNCH_IN));
+                    }
+                    if !missed || missed_arrival {
+                        records.push((departure, user, PUNCH_OUT));
+                    }
+                }
+            }
+            date += Duration::days(1);
+        }
+
+        records.retain(|(time, _, _)| *time <= now);
+        records.sort_by_key(|(time, user, _)| (*time, *user));
+        records
+            .into_iter()
+            .map(|(time, user, punch_type)| {
+                let verify_type = if user % 3 == 0 { VERIFY_FACE } else { VERIFY_FINGERPRINT };
+                (
+                    user.to_string(),
+                    time.format(TIMESTAMP_FORMAT).to_string(),
+                    verify_type,
+                    punch_type,
+                    Vec::new(),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Pseudo-random number from a seed for a user on a day; `salt` picks which
+/// decision. Also drives the sample data generator.
+pub(crate) fn roll(seed: u32, user: u32, day: i64, salt: u64) -> u64 {
+    [user as u64, day as u64, salt]
+        .into_iter()
+        .fold(seed as u64, |x, part| mix(x ^ part))
+}
+
+/// [`roll`] as a number uniform in [0, 1)
+pub(crate) fn chance(seed: u32, user: u32, day: i64, salt: u64) -> f64 {
+    (roll(seed, user, day, salt) >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// [`roll`] as a number uniform in [low, high]
+pub(crate) fn between(seed: u32, user: u32, day: i64, salt: u64, low: i64, high: i64) -> i64 {
+    low + (roll(seed, user, day, salt) % (high - low + 1) as u64) as i64
+}
+
+/// A made-up full name for a user
+pub(crate) fn person_name(seed: u32, user: u32) -> String {
+    let first = FIRST_NAMES[(roll(seed, user, 0, 1) % FIRST_NAMES.len() as u64) as usize];
+    let last = LAST_NAMES[(roll(seed, user, 0, 2) % LAST_NAMES.len() as u64) as usize];
+    format!("{} {}", first, last)
+}
+
+/// splitmix64 finalizer
+fn mix(x: u64) -> u64 {
+    let x = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    let x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
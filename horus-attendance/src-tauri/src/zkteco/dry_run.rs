@@ -0,0 +1,80 @@
+//! Dry-run sync
+//!
+//! Compares what was fetched from a device with the database so the user can
+//! see what a sync would add before letting a device with doubtful data write
+//! anything. Users count as known by device user ID, their own or a linked
+//! one, as in the sync engine; logs are duplicates when this device already
+//! stored the same user and timestamp.
+
+use std::collections::HashSet;
+
+use rusqlite::{params, Connection};
+
+use super::types::{SyncAllResult, SyncPreview};
+
+/// What storing `result` for the saved device `device_id` would add. Every
+/// log is new for a device that isn't saved yet.
+pub fn preview(conn: &Connection, device_id: Option<&str>, result: &SyncAllResult) -> Result<SyncPreview, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT device_user_id FROM users WHERE device_user_id IS NOT NULL
+             UNION SELECT device_user_id FROM user_device_links",
+        )
+        .map_err(|e| format!("Failed to query users: {}", e))?;
+    let known_users: HashSet<String> = stmt
+        .query_map([], |row| row.get(0))
+        .map_err(|e| format!("Failed to query users: {}", e))?
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read users: {}", e))?;
+
+    let (new_users, existing): (Vec<_>, Vec<_>) = result
+        .users
+        .iter()
+        .cloned()
+        .partition(|u| !known_users.contains(&u.device_user_id));
+
+    let first_timestamp = result.logs.iter().map(|l| l.timestamp.clone()).min();
+    let last_timestamp = result.logs.iter().map(|l| l.timestamp.clone()).max();
+
+    let stored: HashSet<(String, String)> = match (device_id, &first_timestamp, &last_timestamp) {
+        (Some(device_id), Some(first), Some(last)) => {
+            let mut stmt = conn
+                .prepare(
+                    "SELECT device_user_id, timestamp FROM attendance_logs_raw
+                     WHERE device_id = ?1 AND timestamp >= ?2 AND timestamp <= ?3",
+                )
+                .map_err(|e| format!("Failed to query logs: {}", e))?;
+            let rows = stmt
+                .query_map(params![device_id, first, last], |row| Ok((row.get(0)?, row.get(1)?)))
+                .map_err(|e| format!("Failed to query logs: {}", e))?;
+            rows.collect::<Result<_, _>>()
+                .map_err(|e| format!("Failed to read logs: {}", e))?
+        }
+        _ => HashSet::new(),
+    };
+
+    let device_users: HashSet<&str> = result.users.iter().map(|u| u.device_user_id.as_str()).collect();
+    let mut new_logs = 0;
+    let mut duplicate_logs = 0;
+    let mut unknown_user_logs = 0;
+    for log in &result.logs {
+        if stored.contains(&(log.device_user_id.clone(), log.timestamp.clone())) {
+            duplicate_logs += 1;
+        } else {
+            new_logs += 1;
+        }
+        if !device_users.contains(log.device_user_id.as_str()) && !known_users.contains(&log.device_user_id) {
+            unknown_user_logs += 1;
+        }
+    }
+
+    Ok(SyncPreview {
+        new_users,
+        existing_users: existing.len() as u32,
+        new_logs,
+        duplicate_logs,
+        unknown_user_logs,
+        first_timestamp,
+        last_timestamp,
+    })
+}
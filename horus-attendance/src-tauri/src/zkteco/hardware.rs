@@ -0,0 +1,66 @@
+//! Hardware details kept with saved devices
+//!
+//! Model, serial number, firmware and MAC address are read whenever a
+//! connection test or sync reaches a saved device and stored on its
+//! `devices` row, so the device list shows what hardware is where. A detail
+//! the device doesn't answer for keeps its previous value.
+
+use rusqlite::{params, Connection};
+
+use super::types::{DeviceHardware, DeviceInfo};
+
+/// `DeviceInfo` reports details the device didn't give as "Unknown"
+const UNKNOWN: &str = "Unknown";
+
+/// The hardware details in a device info reply
+pub fn from_info(info: &DeviceInfo) -> DeviceHardware {
+    let known = |value: &str| Some(value.to_string()).filter(|v| v != UNKNOWN && !v.is_empty());
+    DeviceHardware {
+        model: info.model.clone(),
+        serial_number: known(&info.serial_number),
+        firmware: known(&info.firmware_version),
+        mac_address: info.mac_address.clone(),
+    }
+}
+
+/// Store what the device answered on its row. Returns false for an unknown
+/// device or when it answered with nothing.
+pub fn record(conn: &Connection, device_id: &str, hardware: &DeviceHardware) -> Result<bool, String> {
+    if hardware.model.is_none()
+        && hardware.serial_number.is_none()
+        && hardware.firmware.is_none()
+        && hardware.mac_address.is_none()
+    {
+        return Ok(false);
+    }
+    conn.execute(
+        "UPDATE devices SET
+             model = COALESCE(?2, model),
+             serial_number = COALESCE(?3, serial_number),
+             firmware = COALESCE(?4, firmware),
+             mac_address = COALESCE(?5, mac_address),
+             hardware_seen_at = datetime('now')
+         WHERE id = ?1",
+        params![
+            device_id,
+            hardware.model,
+            hardware.serial_number,
+            hardware.firmware,
+            hardware.mac_address
+        ],
+    )
+    .map(|n| n > 0)
+    .map_err(|e| format!("Failed to save device hardware: {}", e))
+}
+
+/// Store the hardware details of a saved device, logging rather than
+/// failing: the connection or sync that read them succeeded either way
+pub fn remember(app: &tauri::AppHandle, device_id: Option<&str>, hardware: &DeviceHardware) {
+    let Some(device_id) = device_id.filter(|id| !id.is_empty()) else {
+        return;
+    };
+    let saved = crate::db::open(app).and_then(|conn| record(&conn, device_id, hardware));
+    if let Err(e) = saved {
+        log::warn!("[zkteco] Could not save hardware details of {}: {}", device_id, e);
+    }
+}
@@ -0,0 +1,332 @@
+//! Resumable initial import of a device's attendance history
+//!
+//! A device holding years of punches can take longer to send them than one
+//! transfer is allowed. The initial import reads the attendance buffer a
+//! page of records at a time, one connection per pass with a pause between
+//! passes so the device keeps serving the terminal, and stores each page
+//! before recording in device_imports how far it got. Running it again after
+//! an interruption carries on from the next unread record.
+//!
+//! Records come in the order the device stored them, so positions stay valid
+//! between passes unless the device log is cleared; then the import starts
+//! over and records already stored are skipped.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use tauri::Emitter;
+
+use super::client::ZKClient;
+use super::clock::DeviceClock;
+use super::protocol::is_busy_error;
+use super::types::*;
+use crate::activity::registry;
+use crate::db;
+use crate::db::logs::DevicePunch;
+
+pub const DEFAULT_CHUNK_SIZE: u32 = 5000;
+const MIN_CHUNK_SIZE: u32 = 100;
+const MAX_CHUNK_SIZE: u32 = 50000;
+
+pub const DEFAULT_PAUSE_MS: u64 = 1000;
+const MAX_PAUSE_MS: u64 = 60000;
+
+/// Attempts at one pass before the import is left interrupted
+const PASS_ATTEMPTS: u32 = 3;
+
+/// Wait before retrying a pass while the device menu is open
+const BUSY_RETRY_SECS: u64 = 15;
+
+pub const STATUS_RUNNING: &str = "running";
+pub const STATUS_INTERRUPTED: &str = "interrupted";
+pub const STATUS_COMPLETED: &str = "completed";
+
+/// Devices with an import running in this process
+static RUNNING: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Holds a device's place in [`RUNNING`] until dropped
+struct RunningGuard(String);
+
+impl RunningGuard {
+    fn claim(device_id: &str) -> Result<Self, String> {
+        let mut running = RUNNING.lock().map_err(|_| "Import registry poisoned".to_string())?;
+        if running.iter().any(|id| id == device_id) {
+            return Err(format!("An initial import of device {} is already running", device_id));
+        }
+        running.push(device_id.to_string());
+        Ok(Self(device_id.to_string()))
+    }
+}
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        if let Ok(mut running) = RUNNING.lock() {
+            running.retain(|id| *id != self.0);
+        }
+    }
+}
+
+pub fn validate_options(options: &InitialImportOptions) -> Result<(), String> {
+    if let Some(size) = options.chunk_size {
+        if !(MIN_CHUNK_SIZE..=MAX_CHUNK_SIZE).contains(&size) {
+            return Err(format!("Chunk size must be between {} and {}", MIN_CHUNK_SIZE, MAX_CHUNK_SIZE));
+        }
+    }
+    if options.pause_ms.is_some_and(|ms| ms > MAX_PAUSE_MS) {
+        return Err(format!("Pause must be at most {} ms", MAX_PAUSE_MS));
+    }
+    Ok(())
+}
+
+/// Progress of a device's initial import, if one was ever started
+pub fn load(conn: &Connection, device_id: &str) -> Result<Option<InitialImportStatus>, String> {
+    conn.query_row(
+        "SELECT device_id, status, next_record, total_records, imported, duplicates, chunk_size,
+                first_timestamp, last_timestamp, last_error, started_at, updated_at, completed_at, quarantined
+         FROM device_imports WHERE device_id = ?1",
+        [device_id],
+        |row| {
+            Ok(InitialImportStatus {
+                device_id: row.get(0)?,
+                status: row.get(1)?,
+                next_record: row.get(2)?,
+                total_records: row.get(3)?,
+                imported: row.get(4)?,
+                duplicates: row.get(5)?,
+                chunk_size: row.get(6)?,
+                first_timestamp: row.get(7)?,
+                last_timestamp: row.get(8)?,
+                last_error: row.get(9)?,
+                started_at: row.get(10)?,
+                updated_at: row.get(11)?,
+                completed_at: row.get(12)?,
+                quarantined: row.get(13)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(|e| format!("Failed to load import progress for device {}: {}", device_id, e))
+}
+
+/// Mark the import running, starting it over when asked to or when there is
+/// none yet. A changed chunk size applies from the next pass.
+fn begin(conn: &Connection, device_id: &str, chunk_size: u32, restart: bool) -> Result<InitialImportStatus, String> {
+    let known: bool = conn
+        .query_row("SELECT EXISTS(SELECT 1 FROM devices WHERE id = ?1)", [device_id], |row| row.get(0))
+        .map_err(|e| format!("Failed to look up device {}: {}", device_id, e))?;
+    if !known {
+        return Err(format!("Device not found: {}; save it before importing", device_id));
+    }
+    if restart {
+        conn.execute("DELETE FROM device_imports WHERE device_id = ?1", [device_id])
+            .map_err(|e| format!("Failed to reset import progress: {}", e))?;
+    }
+    conn.execute(
+        "INSERT INTO device_imports (device_id, status, chunk_size) VALUES (?1, ?2, ?3)
+         ON CONFLICT(device_id) DO UPDATE SET
+             status = excluded.status,
+             chunk_size = excluded.chunk_size,
+             last_error = NULL,
+             completed_at = NULL,
+             updated_at = datetime('now')",
+        params![device_id, STATUS_RUNNING, chunk_size],
+    )
+    .map_err(|e| format!("Failed to start import: {}", e))?;
+    load(conn, device_id)?.ok_or_else(|| "Import progress was not saved".to_string())
+}
+
+/// Record progress after a pass, or why the import stopped
+fn save(conn: &Connection, status: &InitialImportStatus) -> Result<InitialImportStatus, String> {
+    conn.execute(
+        "UPDATE device_imports SET
+             status = ?2, next_record = ?3, total_records = ?4, imported = ?5, duplicates = ?6,
+             first_timestamp = ?7, last_timestamp = ?8, last_error = ?9, quarantined = ?10,
+             updated_at = datetime('now'),
+             completed_at = CASE WHEN ?2 = 'completed' THEN datetime('now') END
+         WHERE device_id = ?1",
+        params![
+            status.device_id,
+            status.status,
+            status.next_record,
+            status.total_records,
+            status.imported,
+            status.duplicates,
+            status.first_timestamp,
+            status.last_timestamp,
+            status.last_error,
+            status.quarantined,
+        ],
+    )
+    .map_err(|e| format!("Failed to save import progress: {}", e))?;
+    load(conn, &status.device_id)?.ok_or_else(|| "Import progress disappeared".to_string())
+}
+
+/// Read one page, reconnecting for each attempt
+async fn fetch_page(config: &DeviceConfig, start: u64, count: u32) -> Result<(Vec<AttendanceLog>, u64), String> {
+    let mut last_error = String::new();
+    for attempt in 0..PASS_ATTEMPTS {
+        if attempt > 0 {
+            let delay_secs = if is_busy_error(&last_error) { BUSY_RETRY_SECS } else { attempt as u64 * 2 };
+            log::info!("[zkteco::import] Retrying pass at record {} in {}s", start, delay_secs);
+            tokio::time::sleep(Duration::from_secs(delay_secs)).await;
+        }
+        let mut client = match ZKClient::connect(config).await {
+            Ok(client) => client,
+            Err(e) => {
+                last_error = e;
+                continue;
+            }
+        };
+        let page = client.get_attendance_page(start as usize, count as usize).await;
+        let _ = client.disconnect().await;
+        match page {
+            Ok((logs, total)) => return Ok((logs, total as u64)),
+            Err(e) => {
+                log::warn!("[zkteco::import] Pass at record {} failed: {}", start, e);
+                last_error = e;
+            }
+        }
+    }
+    Err(last_error)
+}
+
+fn emit(app: &tauri::AppHandle, status: &InitialImportStatus) {
+    if let Err(e) = app.emit("initial-import-progress", status) {
+        log::warn!("[zkteco::import] Failed to emit initial-import-progress: {}", e);
+    }
+}
+
+/// Stop the import, keeping its position for the next run
+async fn interrupt(
+    app: &tauri::AppHandle,
+    mut status: InitialImportStatus,
+    error: String,
+) -> Result<InitialImportStatus, String> {
+    log::warn!("[zkteco::import] Import of {} interrupted at record {}: {}", status.device_id, status.next_record, error);
+    status.status = STATUS_INTERRUPTED.to_string();
+    status.last_error = Some(error.clone());
+    let status = save(&*db::open(app)?, &status)?;
+    emit(app, &status);
+    Err(error)
+}
+
+/// Import every attendance record of the saved device `config` refers to,
+/// resuming where a previous run stopped
+pub async fn run(
+    app: &tauri::AppHandle,
+    config: &DeviceConfig,
+    options: &InitialImportOptions,
+) -> Result<InitialImportStatus, String> {
+    let device_id = config
+        .device_id
+        .clone()
+        .ok_or_else(|| "Initial import needs a saved device".to_string())?;
+    let _running = RunningGuard::claim(&device_id)?;
+    let chunk_size = options.chunk_size.unwrap_or(DEFAULT_CHUNK_SIZE);
+    let pause = Duration::from_millis(options.pause_ms.unwrap_or(DEFAULT_PAUSE_MS));
+
+    let (mut status, clock) = {
+        let conn = db::open(app)?;
+        (
+            begin(&conn, &device_id, chunk_size, options.restart)?,
+            DeviceClock::load(&conn, &device_id)?,
+        )
+    };
+    log::info!(
+        "[zkteco::import] Initial import of {} from record {} ({} per pass)",
+        device_id,
+        status.next_record,
+        chunk_size
+    );
+    emit(app, &status);
+
+    loop {
+        let activity = match registry::begin(app, "Initial device import") {
+            Ok(activity) => activity,
+            Err(e) => return interrupt(app, status, e).await,
+        };
+        let (mut logs, total) = match fetch_page(config, status.next_record, chunk_size).await {
+            Ok(page) => page,
+            Err(e) => return interrupt(app, status, e).await,
+        };
+        if total < status.next_record {
+            log::warn!(
+                "[zkteco::import] Device {} now holds {} records, fewer than the {} read; starting over",
+                device_id,
+                total,
+                status.next_record
+            );
+            status.next_record = 0;
+            status.total_records = total;
+            continue;
+        }
+        clock.normalize(&mut logs);
+
+        let mut conn = db::open(app)?;
+        let id = device_id.clone();
+        let page = tauri::async_runtime::spawn_blocking(move || {
+            let read = logs.len() as u64;
+            let quarantined = crate::quarantine::store::screen_logs(&conn, Some(&id), &mut logs, true)?;
+            let punches: Vec<DevicePunch> = logs
+                .iter()
+                .map(|log| DevicePunch {
+                    device_user_id: &log.device_user_id,
+                    timestamp: &log.timestamp,
+                    verify_type: log.verify_type,
+                    punch_type: log.punch_type,
+                })
+                .collect();
+            let inserted = db::logs::insert_from_device(&mut conn, &id, &punches)?;
+            crate::payloads::store::retain(&mut conn, Some(&id), &logs)?;
+            let first = logs.iter().map(|l| l.timestamp.clone()).min();
+            let last = logs.iter().map(|l| l.timestamp.clone()).max();
+            Ok::<_, String>((read, quarantined as u64, inserted as u64, first, last))
+        })
+        .await
+        .map_err(|e| format!("Import task failed: {}", e))?;
+        let (read, quarantined, inserted, first, last) = match page {
+            Ok(page) => page,
+            Err(e) => return interrupt(app, status, e).await,
+        };
+        drop(activity);
+        if read == 0 && status.next_record < total {
+            let error = format!("Device sent no records at position {} of {}", status.next_record, total);
+            return interrupt(app, status, error).await;
+        }
+
+        status.next_record += read;
+        status.total_records = total;
+        status.imported += inserted;
+        status.quarantined += quarantined;
+        status.duplicates += read - quarantined - inserted;
+        if let Some(first) = first {
+            if status.first_timestamp.as_ref().map_or(true, |t| first < *t) {
+                status.first_timestamp = Some(first);
+            }
+        }
+        if let Some(last) = last {
+            if status.last_timestamp.as_ref().map_or(true, |t| last > *t) {
+                status.last_timestamp = Some(last);
+            }
+        }
+        let done = status.next_record >= total;
+        if done {
+            status.status = STATUS_COMPLETED.to_string();
+        }
+        status = save(&*db::open(app)?, &status)?;
+        emit(app, &status);
+
+        if done {
+            log::info!(
+                "[zkteco::import] Initial import of {} complete: {} new, {} already stored, {} quarantined",
+                device_id,
+                status.imported,
+                status.duplicates,
+                status.quarantined
+            );
+            return Ok(status);
+        }
+        tokio::time::sleep(pause).await;
+    }
+}
@@ -9,3 +9,19 @@ pub mod udp;
 pub mod client;
 pub mod commands;
 pub mod types;
+pub mod audit;
+pub mod bells;
+pub mod capabilities;
+pub mod clock;
+pub mod connectivity;
+pub mod demo;
+pub mod dry_run;
+pub mod hardware;
+pub mod import;
+pub mod network;
+pub mod offsets;
+pub mod options;
+pub mod scan;
+pub mod sms;
+pub mod sync_window;
+pub mod templates;
@@ -0,0 +1,181 @@
+//! Reading and changing a device's network settings
+//!
+//! The settings are ordinary device options. Most firmware only applies them
+//! on reboot, so a change is written, the device restarted, and then polled
+//! at its new address until it answers again.
+
+use std::net::Ipv4Addr;
+use std::time::{Duration, Instant};
+
+use super::client::ZKClient;
+use super::types::{DeviceConfig, NetworkChangeResult, NetworkSettings};
+
+const IP_ADDRESS: &str = "IPAddress";
+const NETMASK: &str = "NetMask";
+const GATEWAY: &str = "GATEWAYIPAddress";
+const DHCP: &str = "DHCP";
+
+/// How long to wait for the device to come back after restarting
+const RECONNECT_TIMEOUT: Duration = Duration::from_secs(120);
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(5);
+const RECONNECT_ATTEMPT_TIMEOUT_MS: u64 = 3000;
+
+/// Current settings of a connected device
+pub async fn read(client: &mut ZKClient) -> Result<NetworkSettings, String> {
+    Ok(NetworkSettings {
+        ip: client.get_option(IP_ADDRESS).await?,
+        netmask: client.get_option(NETMASK).await?,
+        gateway: client.get_option(GATEWAY).await?,
+        // Older firmware has no DHCP option and is always static
+        dhcp: client.get_option(DHCP).await.map(|v| v == "1").unwrap_or(false),
+    })
+}
+
+fn parse_addr(label: &str, value: &str) -> Result<Ipv4Addr, String> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| format!("Invalid {}: {}", label, value))
+}
+
+/// Reject settings that would leave the device unreachable
+pub fn validate(settings: &NetworkSettings) -> Result<(), String> {
+    if settings.dhcp {
+        return Ok(());
+    }
+    let ip = parse_addr("IP address", &settings.ip)?;
+    let mask = u32::from(parse_addr("netmask", &settings.netmask)?);
+    if mask == 0 || mask.leading_ones() + mask.trailing_zeros() != 32 {
+        return Err(format!("Invalid netmask: {}", settings.netmask));
+    }
+    let host = u32::from(ip) & !mask;
+    if ip.is_unspecified() || ip.is_multicast() || (mask != u32::MAX && (host == 0 || host == !mask)) {
+        return Err(format!("{} can't be used as a device address", settings.ip));
+    }
+    let gateway = parse_addr("gateway", &settings.gateway)?;
+    if !gateway.is_unspecified() && u32::from(gateway) & mask != u32::from(ip) & mask {
+        return Err(format!(
+            "Gateway {} is not on the same subnet as {}/{}",
+            settings.gateway, settings.ip, settings.netmask
+        ));
+    }
+    Ok(())
+}
+
+/// Write `settings`, restart the device and reconnect to it
+pub async fn apply(config: &DeviceConfig, settings: &NetworkSettings) -> Result<NetworkChangeResult, String> {
+    validate(settings)?;
+
+    let mut client = ZKClient::connect(config).await?;
+    let previous = match read(&mut client).await {
+        Ok(previous) => previous,
+        Err(e) => {
+            let _ = client.disconnect().await;
+            return Err(format!("Failed to read current network settings: {}", e));
+        }
+    };
+    if same_settings(&previous, settings) {
+        let _ = client.disconnect().await;
+        return Ok(NetworkChangeResult {
+            previous: previous.clone(),
+            applied: previous,
+            reconnected: true,
+            address: Some(config.ip.clone()),
+            message: "Network settings are unchanged".to_string(),
+        });
+    }
+
+    log::info!(
+        "[zkteco::network] Changing {} to {} (dhcp {})",
+        config.ip,
+        settings.ip,
+        settings.dhcp
+    );
+    if let Err(e) = write(&mut client, settings).await {
+        let _ = client.disconnect().await;
+        return Err(e);
+    }
+    client.restart().await?;
+
+    if settings.dhcp {
+        return Ok(NetworkChangeResult {
+            previous,
+            applied: settings.clone(),
+            reconnected: false,
+            address: None,
+            message: "The device now gets its address from DHCP and is restarting. Find its new address with a subnet scan or on the device screen.".to_string(),
+        });
+    }
+
+    let target = DeviceConfig {
+        ip: settings.ip.trim().to_string(),
+        timeout: Some(RECONNECT_ATTEMPT_TIMEOUT_MS),
+        ..config.clone()
+    };
+    match reconnect(&target).await {
+        Ok(()) => Ok(NetworkChangeResult {
+            previous,
+            applied: settings.clone(),
+            reconnected: true,
+            address: Some(target.ip.clone()),
+            message: format!("Device restarted and answered at {}", target.ip),
+        }),
+        Err(e) => Ok(NetworkChangeResult {
+            previous,
+            applied: settings.clone(),
+            reconnected: false,
+            address: Some(target.ip.clone()),
+            message: format!(
+                "Settings were written and the device restarted, but it did not answer at {} within {} seconds ({}). \
+                 Check that this computer can reach that subnet.",
+                target.ip,
+                RECONNECT_TIMEOUT.as_secs(),
+                e
+            ),
+        }),
+    }
+}
+
+fn same_settings(a: &NetworkSettings, b: &NetworkSettings) -> bool {
+    if a.dhcp || b.dhcp {
+        return a.dhcp == b.dhcp;
+    }
+    a.ip.trim() == b.ip.trim() && a.netmask.trim() == b.netmask.trim() && a.gateway.trim() == b.gateway.trim()
+}
+
+async fn write(client: &mut ZKClient, settings: &NetworkSettings) -> Result<(), String> {
+    if settings.dhcp {
+        client.set_option(DHCP, "1").await?;
+    } else {
+        // Firmware without DHCP support rejects the option; it is static anyway
+        if let Err(e) = client.set_option(DHCP, "0").await {
+            log::warn!("[zkteco::network] {}", e);
+        }
+        client.set_option(IP_ADDRESS, settings.ip.trim()).await?;
+        client.set_option(NETMASK, settings.netmask.trim()).await?;
+        client.set_option(GATEWAY, settings.gateway.trim()).await?;
+    }
+    client.refresh_options().await
+}
+
+/// Poll until the device answers at `target` with the expected address
+async fn reconnect(target: &DeviceConfig) -> Result<(), String> {
+    let started = Instant::now();
+    let mut last_error = String::from("no reply");
+    while started.elapsed() < RECONNECT_TIMEOUT {
+        tokio::time::sleep(RECONNECT_INTERVAL).await;
+        match ZKClient::connect(target).await {
+            Ok(mut client) => {
+                let ip = client.get_option(IP_ADDRESS).await;
+                let _ = client.disconnect().await;
+                match ip {
+                    Ok(ip) if ip == target.ip => return Ok(()),
+                    Ok(ip) => last_error = format!("device reports address {}", ip),
+                    Err(e) => last_error = e,
+                }
+            }
+            Err(e) => last_error = e,
+        }
+    }
+    Err(last_error)
+}
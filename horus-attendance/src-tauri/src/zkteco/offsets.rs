@@ -0,0 +1,223 @@
+//! Incremental attendance reads from a record offset
+//!
+//! A full read sends the device's whole attendance buffer, which takes
+//! minutes once it holds tens of thousands of records. Devices append
+//! records in order, so after a sync only the records past the count read
+//! last time are new. device_log_offsets keeps that count per device along
+//! with the last record read; an incremental sync reads from that record on
+//! and checks it is still in place. When the device holds fewer records, or
+//! a different record sits there, the log was cleared or rewritten and the
+//! whole buffer is read again.
+//!
+//! The offset is saved only once the logs it covers are stored, so an
+//! interrupted sync reads them again next time.
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use super::client::ZKClient;
+use super::types::{AttendanceLog, DeviceLogOffset, IncrementalSync};
+
+/// The saved offset of a device, if it was synced incrementally before
+pub fn load(conn: &Connection, device_id: &str) -> Result<Option<DeviceLogOffset>, String> {
+    conn.query_row(
+        "SELECT record_count, last_device_user_id, last_timestamp FROM device_log_offsets WHERE device_id = ?1",
+        [device_id],
+        |row| {
+            Ok(DeviceLogOffset {
+                record_count: row.get::<_, i64>(0)?.max(0) as u64,
+                last_device_user_id: row.get(1)?,
+                last_timestamp: row.get(2)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(|e| format!("Failed to load sync offset: {}", e))
+}
+
+/// Save a device's offset once the logs read up to it are stored. Returns
+/// false for an unknown device.
+pub fn save(conn: &Connection, device_id: &str, offset: &DeviceLogOffset) -> Result<bool, String> {
+    let known: bool = conn
+        .query_row("SELECT EXISTS(SELECT 1 FROM devices WHERE id = ?1)", [device_id], |row| row.get(0))
+        .map_err(|e| format!("Failed to load device: {}", e))?;
+    if !known {
+        return Ok(false);
+    }
+    conn.execute(
+        "INSERT INTO device_log_offsets (device_id, record_count, last_device_user_id, last_timestamp)
+         VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(device_id) DO UPDATE SET
+             record_count = excluded.record_count,
+             last_device_user_id = excluded.last_device_user_id,
+             last_timestamp = excluded.last_timestamp,
+             updated_at = datetime('now')",
+        params![device_id, offset.record_count as i64, offset.last_device_user_id, offset.last_timestamp],
+    )
+    .map_err(|e| format!("Failed to save sync offset: {}", e))?;
+    Ok(true)
+}
+
+/// Forget a device's offset, so its next incremental sync reads everything
+pub fn clear(conn: &Connection, device_id: &str) -> Result<bool, String> {
+    conn.execute("DELETE FROM device_log_offsets WHERE device_id = ?1", [device_id])
+        .map(|n| n > 0)
+        .map_err(|e| format!("Failed to clear sync offset: {}", e))
+}
+
+fn offset_after(logs: &[AttendanceLog], record_count: u64) -> DeviceLogOffset {
+    let last = logs.last();
+    DeviceLogOffset {
+        record_count,
+        last_device_user_id: last.map(|log| log.device_user_id.clone()),
+        last_timestamp: last.map(|log| log.timestamp.clone()),
+    }
+}
+
+fn is_anchor(offset: &DeviceLogOffset, log: Option<&AttendanceLog>) -> bool {
+    log.is_some_and(|log| {
+        offset.last_device_user_id.as_deref() == Some(log.device_user_id.as_str())
+            && offset.last_timestamp.as_deref() == Some(log.timestamp.as_str())
+    })
+}
+
+/// The records after `offset`, from a page read starting at its last record
+/// off a device holding `total` records. Fails with the reason to read the
+/// whole buffer instead when that record is no longer where it was.
+fn continue_from(
+    offset: &DeviceLogOffset,
+    mut page: Vec<AttendanceLog>,
+    total: u64,
+) -> Result<(Vec<AttendanceLog>, IncrementalSync), String> {
+    if total < offset.record_count {
+        return Err(format!(
+            "The device holds {} records, fewer than the {} read last time; its log was cleared",
+            total, offset.record_count
+        ));
+    }
+    if !is_anchor(offset, page.first()) {
+        return Err("The last record read no longer matches; the device log was rewritten".to_string());
+    }
+    page.remove(0);
+    let next = if page.is_empty() { offset.clone() } else { offset_after(&page, total) };
+    log::info!(
+        "[zkteco::offsets] Read {} new records after record {} of {}",
+        page.len(),
+        offset.record_count,
+        total
+    );
+    Ok((
+        page,
+        IncrementalSync {
+            full: false,
+            reason: None,
+            skipped_records: offset.record_count,
+            offset: DeviceLogOffset { record_count: total, ..next },
+        },
+    ))
+}
+
+/// Read the records added since `offset`, or all of them when there is no
+/// usable offset. Timestamps are as the device sent them.
+pub async fn read_logs(
+    client: &mut ZKClient,
+    offset: Option<&DeviceLogOffset>,
+) -> Result<(Vec<AttendanceLog>, IncrementalSync), String> {
+    let reason = match offset {
+        None => "No records were read from this device incrementally yet".to_string(),
+        // An empty buffer last time leaves nothing to check against
+        Some(offset) if offset.record_count == 0 => "The device held no records at the last sync".to_string(),
+        Some(offset) => {
+            // Start at the last record read, to check it is still there
+            let start = offset.record_count - 1;
+            let (page, total) = client.get_attendance_page(start as usize, usize::MAX).await?;
+            let reason = match continue_from(offset, page, total as u64) {
+                Ok(read) => return Ok(read),
+                Err(reason) => reason,
+            };
+            log::warn!("[zkteco::offsets] {}; reading all records", reason);
+            reason
+        }
+    };
+
+    let logs = client.get_attendance_logs(None).await?;
+    let offset = offset_after(&logs, logs.len() as u64);
+    Ok((
+        logs,
+        IncrementalSync {
+            full: true,
+            reason: Some(reason),
+            skipped_records: 0,
+            offset,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn punch(device_user_id: &str, timestamp: &str) -> AttendanceLog {
+        AttendanceLog {
+            device_user_id: device_user_id.to_string(),
+            timestamp: timestamp.to_string(),
+            verify_type: 1,
+            punch_type: 0,
+            raw: Vec::new(),
+        }
+    }
+
+    /// Three records read last time, the third by user 7 at 17:00
+    fn offset() -> DeviceLogOffset {
+        DeviceLogOffset {
+            record_count: 3,
+            last_device_user_id: Some("7".to_string()),
+            last_timestamp: Some("2024-01-08 17:00:00".to_string()),
+        }
+    }
+
+    #[test]
+    fn new_records_follow_the_anchor() {
+        let page = vec![punch("7", "2024-01-08 17:00:00"), punch("8", "2024-01-09 08:00:00")];
+        let (logs, sync) = continue_from(&offset(), page, 4).unwrap();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].device_user_id, "8");
+        assert!(!sync.full);
+        assert_eq!(sync.skipped_records, 3);
+        assert_eq!(sync.offset.record_count, 4);
+        assert_eq!(sync.offset.last_timestamp.as_deref(), Some("2024-01-09 08:00:00"));
+
+        // Nothing new keeps the anchor
+        let (logs, sync) = continue_from(&offset(), vec![punch("7", "2024-01-08 17:00:00")], 3).unwrap();
+        assert!(logs.is_empty());
+        assert_eq!(sync.offset.last_timestamp.as_deref(), Some("2024-01-08 17:00:00"));
+    }
+
+    #[test]
+    fn cleared_log_reads_everything() {
+        let reason = continue_from(&offset(), Vec::new(), 1).unwrap_err();
+        assert!(reason.contains("fewer than the 3 read last time"), "{}", reason);
+    }
+
+    /// A full buffer wraps: the oldest records are overwritten, so the count
+    /// stays the same but the records have moved
+    #[test]
+    fn wrapped_log_reads_everything() {
+        let page = vec![punch("9", "2024-01-10 08:00:00"), punch("9", "2024-01-10 17:00:00")];
+        let reason = continue_from(&offset(), page, 4).unwrap_err();
+        assert!(reason.contains("no longer matches"), "{}", reason);
+    }
+
+    /// The same user at that position, but not the punch read last time
+    #[test]
+    fn stale_anchor_reads_everything() {
+        let page = vec![punch("7", "2024-01-08 17:05:00"), punch("8", "2024-01-09 08:00:00")];
+        assert!(continue_from(&offset(), page, 4).is_err());
+
+        let stale = DeviceLogOffset {
+            last_timestamp: None,
+            ..offset()
+        };
+        let page = vec![punch("7", "2024-01-08 17:00:00")];
+        assert!(continue_from(&stale, page, 3).is_err());
+    }
+}
@@ -0,0 +1,143 @@
+//! Device settings exposed in the app
+//!
+//! Only the options listed here can be written from the app, each with the
+//! range the firmware accepts, so a typo can't leave a terminal unable to
+//! match fingerprints. Options a model doesn't have are reported as
+//! unsupported rather than failing the whole read.
+
+use std::collections::BTreeMap;
+
+use super::client::ZKClient;
+use super::types::DeviceOptionValue;
+
+/// A setting the app manages
+pub struct OptionDef {
+    /// Stable key used by the frontend
+    pub key: &'static str,
+    /// Option name on the device
+    pub name: &'static str,
+    pub label: &'static str,
+    /// Values are 0/1 when set, otherwise whole numbers in `min..=max`
+    pub boolean: bool,
+    pub min: i64,
+    pub max: i64,
+}
+
+pub const OPTIONS: &[OptionDef] = &[
+    OptionDef {
+        key: "volume",
+        name: "VOLUME",
+        label: "Beep volume",
+        boolean: false,
+        min: 0,
+        max: 100,
+    },
+    OptionDef {
+        key: "fingerprintMatchThreshold",
+        name: "MThreshold",
+        label: "Fingerprint threshold (1:N)",
+        boolean: false,
+        min: 1,
+        max: 100,
+    },
+    OptionDef {
+        key: "fingerprintVerifyThreshold",
+        name: "VThreshold",
+        label: "Fingerprint threshold (1:1)",
+        boolean: false,
+        min: 1,
+        max: 100,
+    },
+    OptionDef {
+        key: "faceMatchThreshold",
+        name: "FaceMThreshold",
+        label: "Face threshold (1:N)",
+        boolean: false,
+        min: 1,
+        max: 100,
+    },
+    OptionDef {
+        key: "faceVerifyThreshold",
+        name: "FaceVThreshold",
+        label: "Face threshold (1:1)",
+        boolean: false,
+        min: 1,
+        max: 100,
+    },
+    OptionDef {
+        key: "oneToOneOnly",
+        name: "OnlyPINCard",
+        label: "1:1 only (user enters ID or shows card before verifying)",
+        boolean: true,
+        min: 0,
+        max: 1,
+    },
+];
+
+fn find(key: &str) -> Result<&'static OptionDef, String> {
+    OPTIONS
+        .iter()
+        .find(|o| o.key == key)
+        .ok_or_else(|| format!("Unknown device setting: {}", key))
+}
+
+/// Normalized value for `def`, or an error naming the allowed range
+fn check(def: &OptionDef, value: &str) -> Result<String, String> {
+    let value = value.trim();
+    let parsed = match (def.boolean, value) {
+        (true, "true") => Some(1),
+        (true, "false") => Some(0),
+        _ => value.parse::<i64>().ok(),
+    };
+    match parsed {
+        Some(n) if (def.min..=def.max).contains(&n) => Ok(n.to_string()),
+        _ if def.boolean => Err(format!("{} must be on or off", def.label)),
+        _ => Err(format!("{} must be between {} and {}", def.label, def.min, def.max)),
+    }
+}
+
+/// Current values of every managed setting
+pub async fn read(client: &mut ZKClient) -> Result<Vec<DeviceOptionValue>, String> {
+    let mut values = Vec::with_capacity(OPTIONS.len());
+    for def in OPTIONS {
+        let value = match client.get_option(def.name).await {
+            Ok(value) => Some(value),
+            // A busy or unauthenticated device would fail every option
+            Err(e) if e.contains("not authenticated") || super::protocol::is_busy_error(&e) => return Err(e),
+            Err(e) => {
+                log::debug!("[zkteco::options] {} not available: {}", def.name, e);
+                None
+            }
+        };
+        values.push(DeviceOptionValue {
+            key: def.key.to_string(),
+            label: def.label.to_string(),
+            boolean: def.boolean,
+            min: def.min,
+            max: def.max,
+            supported: value.is_some(),
+            value,
+        });
+    }
+    Ok(values)
+}
+
+/// Check every value, then write them and have the device apply them
+pub async fn write(client: &mut ZKClient, values: &BTreeMap<String, String>) -> Result<(), String> {
+    let checked = validate(values)?;
+    for (def, value) in &checked {
+        client.set_option(def.name, value).await?;
+    }
+    client.refresh_options().await
+}
+
+/// Resolve and range-check `values` by key
+pub fn validate(values: &BTreeMap<String, String>) -> Result<Vec<(&'static OptionDef, String)>, String> {
+    values
+        .iter()
+        .map(|(key, value)| {
+            let def = find(key)?;
+            Ok((def, check(def, value)?))
+        })
+        .collect()
+}
@@ -2,7 +2,7 @@
 //!
 //! Faithfully mirrors the node-zklib protocol implementation.
 
-use chrono::Datelike;
+use chrono::{Datelike, Timelike};
 
 /// ZKTeco protocol command codes
 #[allow(dead_code)]
@@ -13,6 +13,8 @@ pub mod cmd {
     pub const CMD_DISABLEDEVICE: u16 = 1003;
     pub const CMD_RESTART: u16 = 1004;
     pub const CMD_POWEROFF: u16 = 1005;
+    pub const CMD_REFRESHDATA: u16 = 1013;
+    pub const CMD_REFRESHOPTION: u16 = 1014;
     pub const CMD_GET_VERSION: u16 = 1100;
     pub const CMD_AUTH: u16 = 1102;
     pub const CMD_PREPARE_DATA: u16 = 1500;
@@ -24,9 +26,15 @@ pub mod cmd {
     pub const CMD_USER_WRQ: u16 = 8;
     pub const CMD_USERTEMP_RRQ: u16 = 9;
     pub const CMD_OPTIONS_RRQ: u16 = 11;
+    pub const CMD_OPTIONS_WRQ: u16 = 12;
     pub const CMD_ATTLOG_RRQ: u16 = 13;
     pub const CMD_CLEAR_ATTLOG: u16 = 15;
+    pub const CMD_DELETE_USER: u16 = 18;
     pub const CMD_GET_FREE_SIZES: u16 = 50;
+    pub const CMD_SMS_WRQ: u16 = 70;
+    pub const CMD_DELETE_SMS: u16 = 72;
+    pub const CMD_UDATA_WRQ: u16 = 73;
+    pub const CMD_DELETE_UDATA: u16 = 74;
     pub const CMD_GET_TIME: u16 = 201;
     pub const CMD_SET_TIME: u16 = 202;
     pub const CMD_REG_EVENT: u16 = 500;
@@ -35,6 +43,8 @@ pub mod cmd {
     pub const CMD_ACK_OK: u16 = 2000;
     pub const CMD_ACK_ERROR: u16 = 2001;
     pub const CMD_ACK_DATA: u16 = 2002;
+    pub const CMD_ACK_RETRY: u16 = 2003;
+    pub const CMD_ACK_REPEAT: u16 = 2004;
     pub const CMD_ACK_UNAUTH: u16 = 2005;
     pub const CMD_ACK_UNKNOWN: u16 = 0xFFFF;
     pub const CMD_ACK_ERROR_CMD: u16 = 0xFFFD;
@@ -45,6 +55,35 @@ pub mod cmd {
 pub const USHRT_MAX: u32 = 65535;
 pub const MAX_CHUNK: usize = 65472;
 
+/// Bytes before the first record of a user or attendance buffer (its length)
+pub const DATA_SIZE_PREFIX: usize = 4;
+
+/// Reply to CMD_DATA_WRRQ
+pub enum PreparedData {
+    /// Small data sent straight back in the reply
+    Inline(Vec<u8>),
+    /// Data of this many bytes waiting on the device, read with CMD_DATA_RDY
+    Buffered(usize),
+}
+
+/// CMD_DATA_RDY requests (start, size) covering `len` bytes from `offset`
+pub fn chunk_plan(offset: usize, len: usize) -> Vec<(usize, usize)> {
+    (0..len)
+        .step_by(MAX_CHUNK)
+        .map(|start| (offset + start, MAX_CHUNK.min(len - start)))
+        .collect()
+}
+
+/// Records `start..start + count` of a prepared buffer holding `size` bytes
+/// of `record_size`-byte records: the byte range to read and the number of
+/// records the buffer holds
+pub fn record_range(size: usize, record_size: usize, start: usize, count: usize) -> ((usize, usize), usize) {
+    let total = size.saturating_sub(DATA_SIZE_PREFIX) / record_size;
+    let first = start.min(total);
+    let count = count.min(total - first);
+    ((DATA_SIZE_PREFIX + first * record_size, count * record_size), total)
+}
+
 /// Pre-built request data payloads
 #[allow(dead_code)]
 pub mod request_data {
@@ -57,6 +96,10 @@ pub mod request_data {
     pub const GET_USERS: &[u8] = &[
         0x01, 0x09, 0x00, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
     ];
+
+    pub const GET_TEMPLATES: &[u8] = &[
+        0x01, 0x07, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
 }
 
 /// TCP packet prefix bytes
@@ -188,11 +231,69 @@ pub fn check_not_event_tcp(data: &[u8]) -> bool {
     event == 1 && command_id == cmd::CMD_REG_EVENT // EF_ATTLOG = 1
 }
 
+/// Reply code and payload of an inner packet (TCP prefix already removed)
+pub fn split_reply(packet: &[u8]) -> (u16, Vec<u8>) {
+    if packet.len() < 2 {
+        return (cmd::CMD_ACK_UNKNOWN, Vec::new());
+    }
+    let command_id = u16::from_le_bytes([packet[0], packet[1]]);
+    let data = if packet.len() > 8 { packet[8..].to_vec() } else { Vec::new() };
+    (command_id, data)
+}
+
+/// Turn a reply to `command` into an error unless it is CMD_ACK_OK
+pub fn expect_ack(command: u16, reply: u16) -> Result<(), String> {
+    match reply {
+        cmd::CMD_ACK_OK => Ok(()),
+        cmd::CMD_ACK_UNAUTH => Err(format!(
+            "Device refused command {}: not authenticated (check the communication key)",
+            command
+        )),
+        busy if is_busy_reply(busy) => Err(busy_error(command, busy)),
+        other => Err(format!("Device rejected command {}: {}", command, command_name(other))),
+    }
+}
+
+// ============================================================================
+// Device options ("Name=value" strings)
+// ============================================================================
+
+/// CMD_OPTIONS_RRQ payload asking for one option
+pub fn option_read_request(name: &str) -> Vec<u8> {
+    let mut data = name.as_bytes().to_vec();
+    data.push(0);
+    data
+}
+
+/// CMD_OPTIONS_WRQ payload setting one option
+pub fn option_write_request(name: &str, value: &str) -> Vec<u8> {
+    let mut data = format!("{}={}", name, value).into_bytes();
+    data.push(0);
+    data
+}
+
+/// Value from a CMD_OPTIONS_RRQ reply payload ("Name=value\0"), if it is
+/// the option that was asked for
+pub fn parse_option_reply(data: &[u8], name: &str) -> Option<String> {
+    let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+    let text = String::from_utf8_lossy(&data[..end]);
+    let (key, value) = text.split_once('=')?;
+    key.trim()
+        .eq_ignore_ascii_case(name)
+        .then(|| value.trim().to_string())
+}
+
 // ============================================================================
 // Data record decoders
 // ============================================================================
 
-/// Decode a 28-byte user record (UDP format)
+/// Size of a user record read over UDP
+pub const USER_RECORD_SIZE_UDP: usize = 28;
+/// Size of a user record read over TCP
+pub const USER_RECORD_SIZE_TCP: usize = 72;
+
+/// Decode a 28-byte user record (UDP format). This format stores the user
+/// ID as a number, so the device has no leading zeros to lose.
 pub fn decode_user_data_28(data: &[u8]) -> (u16, String, String) {
     let uid = u16::from_le_bytes([data[0], data[1]]);
     let name = extract_ascii_string(&data[8..16]);
@@ -203,19 +304,76 @@ pub fn decode_user_data_28(data: &[u8]) -> (u16, String, String) {
     (uid, user_id, name)
 }
 
-/// Decode a 72-byte user record (TCP format)
+/// Decode a 72-byte user record (TCP format). The user ID is the whole
+/// 24-byte field: extended ("SSR") firmware allows IDs longer than 9
+/// characters, and it is kept as text, leading zeros included.
 pub fn decode_user_data_72(data: &[u8]) -> (u16, String, String) {
     let uid = u16::from_le_bytes([data[0], data[1]]);
     let name = extract_ascii_string(&data[11..35]);
-    let user_id = extract_ascii_string(&data[48..57]);
+    let user_id = extract_ascii_string(&data[48..72]);
     (uid, user_id, name)
 }
 
-/// Decode a 40-byte attendance record (TCP format)
+/// Decode a user record in either format
+pub fn decode_user_record(data: &[u8]) -> (u16, String, String) {
+    if data.len() == USER_RECORD_SIZE_UDP {
+        decode_user_data_28(data)
+    } else {
+        decode_user_data_72(data)
+    }
+}
+
+/// A 72-byte user record with no password, card or group
+pub fn encode_user_data_72(uid: u16, user_id: &str, name: &str) -> Vec<u8> {
+    let mut data = vec![0u8; USER_RECORD_SIZE_TCP];
+    data[..2].copy_from_slice(&uid.to_le_bytes());
+    let name = &name.as_bytes()[..name.len().min(24)];
+    data[11..11 + name.len()].copy_from_slice(name);
+    let user_id = &user_id.as_bytes()[..user_id.len().min(24)];
+    data[48..48 + user_id.len()].copy_from_slice(user_id);
+    data
+}
+
+/// A user record (as read, in either format) with its name replaced and
+/// everything else (privilege, password, card, group) kept. Fails when the
+/// name doesn't fit the record's name field.
+pub fn rename_user_record(record: &[u8], name: &str) -> Result<Vec<u8>, String> {
+    let field = match record.len() {
+        USER_RECORD_SIZE_UDP => 8..16,
+        USER_RECORD_SIZE_TCP => 11..35,
+        size => return Err(format!("Unexpected user record size {}", size)),
+    };
+    let bytes = name.as_bytes();
+    if bytes.len() > field.len() {
+        return Err(format!("Name is too long for the device (at most {} bytes)", field.len()));
+    }
+    let mut record = record.to_vec();
+    record[field.clone()].fill(0);
+    record[field.start..field.start + bytes.len()].copy_from_slice(bytes);
+    Ok(record)
+}
+
+/// Fields of an attendance record (user ID, timestamp, verify type, punch
+/// type) and the bytes they were decoded from
+pub type RawAttendance = (String, String, u8, u8, Vec<u8>);
+
+/// Decode consecutive `record_size`-byte attendance records, ignoring a
+/// trailing partial one
+pub fn decode_records(data: &[u8], record_size: usize, decode: fn(&[u8]) -> (String, String, u8, u8)) -> Vec<RawAttendance> {
+    data.chunks_exact(record_size)
+        .map(|record| {
+            let (device_user_id, timestamp, verify_type, punch_type) = decode(record);
+            (device_user_id, timestamp, verify_type, punch_type, record.to_vec())
+        })
+        .collect()
+}
+
+/// Decode a 40-byte attendance record (TCP format): record number, 24-byte
+/// user ID, verify type, time, punch state
 pub fn decode_record_data_40(data: &[u8]) -> (String, String, u8, u8) {
-    let device_user_id = extract_ascii_string(&data[2..11]);
-    let verify_type = data[11];
-    let in_out_state = data[12];
+    let device_user_id = extract_ascii_string(&data[2..26]);
+    let verify_type = data[26];
+    let in_out_state = data[31];
     let time_val = u32::from_le_bytes([data[27], data[28], data[29], data[30]]);
     let timestamp = parse_zk_time(time_val);
     (device_user_id, timestamp, verify_type, in_out_state)
@@ -280,12 +438,48 @@ pub fn parse_zk_time(mut time: u32) -> String {
     dt.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string()
 }
 
+/// Encode a device-local time the way [`parse_zk_time`] decodes it
+pub fn encode_zk_time(time: &chrono::NaiveDateTime) -> u32 {
+    let days = ((time.year() as u32 % 100) * 12 * 31) + (time.month0() * 31) + time.day0();
+    days * 24 * 60 * 60 + (time.hour() * 60 + time.minute()) * 60 + time.second()
+}
+
 /// Extract a null-terminated ASCII string from a byte slice
 pub fn extract_ascii_string(data: &[u8]) -> String {
     let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
     String::from_utf8_lossy(&data[..end]).trim().to_string()
 }
 
+/// Prefix of errors caused by the terminal being busy, so callers can tell
+/// them apart from protocol failures
+pub const DEVICE_BUSY: &str = "Device busy";
+
+/// Replies a terminal sends while someone is in its on-screen menu (or it is
+/// serving another client): an explicit retry/repeat, or a generic error /
+/// "not initialised" to a command that normally succeeds
+pub fn is_busy_reply(cmd_id: u16) -> bool {
+    matches!(
+        cmd_id,
+        cmd::CMD_ACK_RETRY | cmd::CMD_ACK_REPEAT | cmd::CMD_ACK_ERROR | cmd::CMD_ACK_ERROR_INIT
+    )
+}
+
+/// Error for a busy reply to `command`
+pub fn busy_error(command: u16, reply: u16) -> String {
+    format!(
+        "{}: the device menu is open on the terminal, or another program is using it. \
+         Close the menu and try again. (reply {} to command {})",
+        DEVICE_BUSY,
+        command_name(reply),
+        command
+    )
+}
+
+/// Whether an error came from [`busy_error`]
+pub fn is_busy_error(error: &str) -> bool {
+    error.contains(DEVICE_BUSY)
+}
+
 /// Map command ID to error name
 pub fn command_name(cmd_id: u16) -> &'static str {
     match cmd_id {
@@ -0,0 +1,141 @@
+//! Subnet scan for devices
+//!
+//! Managed switches often drop UDP broadcast, so instead of asking devices to
+//! announce themselves every address in the range is probed directly: a bare
+//! TCP connect with a short timeout first, then the protocol handshake on
+//! hosts that accepted.
+
+use std::net::{Ipv4Addr, SocketAddr};
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+use tokio::task::JoinSet;
+use tokio::time::timeout;
+
+use super::client::ZKClient;
+use super::types::{DeviceConfig, ScanHost};
+
+/// Largest range scanned in one go (a /22)
+const MAX_HOSTS: u32 = 1024;
+
+/// Probes in flight at once
+const CONCURRENCY: usize = 64;
+
+/// Timeout for the bare TCP connect
+pub const DEFAULT_PROBE_TIMEOUT_MS: u64 = 400;
+
+/// Timeout for the handshake and info request on hosts that answered
+const HANDSHAKE_TIMEOUT_MS: u64 = 3000;
+
+/// Host addresses in `cidr` ("192.168.1.0/24"; a bare address is a /32).
+/// Network and broadcast addresses are skipped for prefixes up to /30.
+pub fn parse_cidr(cidr: &str) -> Result<Vec<Ipv4Addr>, String> {
+    let cidr = cidr.trim();
+    let (addr, prefix) = match cidr.split_once('/') {
+        Some((addr, prefix)) => (
+            addr,
+            prefix
+                .trim()
+                .parse::<u32>()
+                .ok()
+                .filter(|p| *p <= 32)
+                .ok_or_else(|| format!("Invalid prefix length in {}", cidr))?,
+        ),
+        None => (cidr, 32),
+    };
+    let addr: Ipv4Addr = addr
+        .trim()
+        .parse()
+        .map_err(|_| format!("Invalid IP address in {}", cidr))?;
+
+    let size = 1u64 << (32 - prefix);
+    if size > MAX_HOSTS as u64 {
+        return Err(format!(
+            "{} has {} addresses; scan at most {} at a time (a /22 or smaller)",
+            cidr, size, MAX_HOSTS
+        ));
+    }
+    let mask = if prefix == 0 { 0 } else { u32::MAX << (32 - prefix) };
+    let network = u32::from(addr) & mask;
+    let last = network + (size as u32 - 1);
+    let (first, last) = if prefix <= 30 { (network + 1, last - 1) } else { (network, last) };
+    Ok((first..=last).map(Ipv4Addr::from).collect())
+}
+
+/// Probe every host in `cidr` on `port` and return the ones that answered,
+/// devices first
+pub async fn scan(cidr: &str, port: u16, probe_timeout_ms: u64) -> Result<Vec<ScanHost>, String> {
+    let hosts = parse_cidr(cidr)?;
+    log::info!("[zkteco::scan] Probing {} hosts in {} on port {}", hosts.len(), cidr, port);
+    let started = Instant::now();
+
+    let mut found = Vec::new();
+    for batch in hosts.chunks(CONCURRENCY) {
+        let mut probes = JoinSet::new();
+        for ip in batch {
+            probes.spawn(probe(*ip, port, probe_timeout_ms));
+        }
+        while let Some(result) = probes.join_next().await {
+            match result {
+                Ok(Some(host)) => found.push(host),
+                Ok(None) => {}
+                Err(e) => log::warn!("[zkteco::scan] Probe task failed: {}", e),
+            }
+        }
+    }
+
+    found.sort_by(|a, b| {
+        b.is_device
+            .cmp(&a.is_device)
+            .then_with(|| a.ip.parse::<Ipv4Addr>().ok().cmp(&b.ip.parse::<Ipv4Addr>().ok()))
+    });
+    log::info!(
+        "[zkteco::scan] {} of {} hosts answered ({} devices) in {}ms",
+        found.len(),
+        hosts.len(),
+        found.iter().filter(|h| h.is_device).count(),
+        started.elapsed().as_millis()
+    );
+    Ok(found)
+}
+
+/// `None` when nothing accepted the connection in time
+async fn probe(ip: Ipv4Addr, port: u16, probe_timeout_ms: u64) -> Option<ScanHost> {
+    let addr = SocketAddr::from((ip, port));
+    let start = Instant::now();
+    let stream = timeout(Duration::from_millis(probe_timeout_ms), TcpStream::connect(addr))
+        .await
+        .ok()?
+        .ok()?;
+    let latency = start.elapsed().as_millis() as u64;
+    drop(stream);
+
+    let config = DeviceConfig {
+        device_id: None,
+        ip: ip.to_string(),
+        port,
+        comm_key: None,
+        timeout: Some(HANDSHAKE_TIMEOUT_MS),
+    };
+    let mut host = ScanHost {
+        ip: ip.to_string(),
+        port,
+        latency,
+        is_device: false,
+        device_info: None,
+        error: None,
+    };
+    match ZKClient::connect(&config).await {
+        Ok(mut client) => {
+            host.is_device = true;
+            // Devices with a comm key set accept the handshake but refuse
+            // anything else until authenticated
+            match client.get_device_info().await {
+                Ok(info) => host.device_info = Some(info),
+                Err(e) => host.error = Some(format!("Device answered but did not return its info: {}", e)),
+            }
+            let _ = client.disconnect().await;
+        }
+        Err(e) => host.error = Some(format!("Port is open but the device handshake failed: {}", e)),
+    }
+    Some(host)
+}
@@ -0,0 +1,68 @@
+//! Short messages shown on the terminal at punch time
+//!
+//! A message is either public (shown to everyone) or personal, in which case
+//! it is linked to each user it is for by a separate "user data" record.
+
+use super::client::ZKClient;
+use super::protocol::{cmd, encode_zk_time, expect_ack};
+
+/// Shown to everyone who punches
+pub const TAG_PUBLIC: u8 = 253;
+/// Shown only to the users linked to it
+pub const TAG_PERSONAL: u8 = 254;
+
+/// Longest message the terminals display
+pub const MAX_CONTENT_LEN: usize = 160;
+
+/// A message as the device stores it
+pub struct Sms<'a> {
+    pub id: u16,
+    pub tag: u8,
+    /// 0 keeps the message until it is deleted
+    pub valid_minutes: u16,
+    /// Device-local time the message starts showing
+    pub start: chrono::NaiveDateTime,
+    pub content: &'a str,
+}
+
+/// Packed record: tag, ID, valid minutes, reserved, start time, content
+fn encode(sms: &Sms) -> Vec<u8> {
+    let mut data = Vec::with_capacity(11 + MAX_CONTENT_LEN + 1);
+    data.push(sms.tag);
+    data.extend_from_slice(&sms.id.to_le_bytes());
+    data.extend_from_slice(&sms.valid_minutes.to_le_bytes());
+    data.extend_from_slice(&0u16.to_le_bytes());
+    data.extend_from_slice(&encode_zk_time(&sms.start).to_le_bytes());
+    let mut content = [0u8; MAX_CONTENT_LEN + 1];
+    let bytes = sms.content.as_bytes();
+    let len = bytes.len().min(MAX_CONTENT_LEN);
+    content[..len].copy_from_slice(&bytes[..len]);
+    data.extend_from_slice(&content);
+    data
+}
+
+/// Create or replace a message
+pub async fn write(client: &mut ZKClient, sms: &Sms<'_>) -> Result<(), String> {
+    let (reply, _) = client.request(cmd::CMD_SMS_WRQ, &encode(sms)).await?;
+    expect_ack(cmd::CMD_SMS_WRQ, reply).map_err(|e| format!("Failed to write message {}: {}", sms.id, e))
+}
+
+/// Delete a message. Its user links go with it.
+pub async fn delete(client: &mut ZKClient, id: u16) -> Result<(), String> {
+    let (reply, _) = client.request(cmd::CMD_DELETE_SMS, &id.to_le_bytes()).await?;
+    expect_ack(cmd::CMD_DELETE_SMS, reply).map_err(|e| format!("Failed to delete message {}: {}", id, e))
+}
+
+/// User data record linking the user with record number `uid` to a message
+fn user_link(uid: u16, id: u16) -> Vec<u8> {
+    let mut data = Vec::with_capacity(4);
+    data.extend_from_slice(&uid.to_le_bytes());
+    data.extend_from_slice(&id.to_le_bytes());
+    data
+}
+
+/// Show a personal message to a user
+pub async fn link_user(client: &mut ZKClient, uid: u16, id: u16) -> Result<(), String> {
+    let (reply, _) = client.request(cmd::CMD_UDATA_WRQ, &user_link(uid, id)).await?;
+    expect_ack(cmd::CMD_UDATA_WRQ, reply)
+}
@@ -0,0 +1,132 @@
+//! Per-device sync windows (quiet hours)
+//!
+//! Reading a device's whole attendance buffer keeps it busy, and punching in
+//! is sluggish meanwhile. A device can be given the times of day it may be
+//! synced unattended, e.g. 12:00–14:00 and 19:00–00:00. Scheduled syncs
+//! outside them are refused; manual syncs go ahead with a warning. A device
+//! with no windows may be synced at any time.
+
+use chrono::{Duration, NaiveDateTime, NaiveTime};
+use rusqlite::{params, Connection};
+
+use super::types::{SyncWindow, SyncWindowCheck};
+
+/// Most windows a device may have
+const MAX_WINDOWS: usize = 12;
+
+fn parse_time(value: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(value, "%H:%M").map_err(|_| format!("Invalid time (expected HH:MM): {}", value))
+}
+
+pub fn validate(windows: &[SyncWindow]) -> Result<(), String> {
+    if windows.len() > MAX_WINDOWS {
+        return Err(format!("A device can have at most {} sync windows", MAX_WINDOWS));
+    }
+    for window in windows {
+        if parse_time(&window.start_time)? == parse_time(&window.end_time)? {
+            return Err(format!("Sync window {}–{} is empty", window.start_time, window.end_time));
+        }
+    }
+    Ok(())
+}
+
+/// `time` falls in `window`, which includes its start but not its end
+fn contains(window: &SyncWindow, time: NaiveTime) -> bool {
+    let (Ok(start), Ok(end)) = (parse_time(&window.start_time), parse_time(&window.end_time)) else {
+        return false;
+    };
+    if start < end {
+        start <= time && time < end
+    } else {
+        time >= start || time < end
+    }
+}
+
+/// `now` is inside one of `windows`, or there are none
+pub fn is_open(windows: &[SyncWindow], now: NaiveDateTime) -> bool {
+    windows.is_empty() || windows.iter().any(|w| contains(w, now.time()))
+}
+
+/// When the first of `windows` to open after `now` does
+pub fn next_open(windows: &[SyncWindow], now: NaiveDateTime) -> Option<NaiveDateTime> {
+    windows
+        .iter()
+        .filter_map(|w| parse_time(&w.start_time).ok())
+        .map(|start| {
+            let today = now.date().and_time(start);
+            if today > now {
+                today
+            } else {
+                today + Duration::days(1)
+            }
+        })
+        .min()
+}
+
+/// A device's windows, earliest first
+pub fn load(conn: &Connection, device_id: &str) -> Result<Vec<SyncWindow>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT start_time, end_time FROM device_sync_windows
+             WHERE device_id = ?1 ORDER BY start_time, end_time",
+        )
+        .map_err(|e| format!("Failed to query sync windows: {}", e))?;
+    let rows = stmt
+        .query_map([device_id], |row| {
+            Ok(SyncWindow {
+                start_time: row.get(0)?,
+                end_time: row.get(1)?,
+            })
+        })
+        .map_err(|e| format!("Failed to query sync windows: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read sync windows: {}", e))
+}
+
+/// Replace a device's windows; none lifts the restriction
+pub fn save(conn: &mut Connection, device_id: &str, windows: &[SyncWindow]) -> Result<(), String> {
+    validate(windows)?;
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    tx.execute("DELETE FROM device_sync_windows WHERE device_id = ?1", [device_id])
+        .map_err(|e| format!("Failed to clear sync windows: {}", e))?;
+    for window in windows {
+        tx.execute(
+            "INSERT INTO device_sync_windows (device_id, start_time, end_time) VALUES (?1, ?2, ?3)",
+            params![device_id, window.start_time, window.end_time],
+        )
+        .map_err(|e| format!("Failed to save sync window: {}", e))?;
+    }
+    tx.commit().map_err(|e| format!("Failed to commit sync windows: {}", e))
+}
+
+/// Whether `device_id` may be synced at `now`: scheduled syncs only inside
+/// its windows, manual ones at any time
+pub fn check(conn: &Connection, device_id: &str, scheduled: bool, now: NaiveDateTime) -> Result<SyncWindowCheck, String> {
+    let windows = load(conn, device_id)?;
+    let open = is_open(&windows, now);
+    let next_open_at = if open {
+        None
+    } else {
+        next_open(&windows, now).map(|t| t.format("%Y-%m-%dT%H:%M:%S").to_string())
+    };
+    let message = (!open).then(|| {
+        let allowed: Vec<String> = windows.iter().map(|w| format!("{}–{}", w.start_time, w.end_time)).collect();
+        if scheduled {
+            format!("Outside this device's sync windows ({}); skipped", allowed.join(", "))
+        } else {
+            format!(
+                "Synced outside this device's sync windows ({}); the terminal may be slow for employees meanwhile",
+                allowed.join(", ")
+            )
+        }
+    });
+    Ok(SyncWindowCheck {
+        device_id: device_id.to_string(),
+        open,
+        allowed: open || !scheduled,
+        next_open_at,
+        message,
+    })
+}
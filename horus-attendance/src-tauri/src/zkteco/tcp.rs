@@ -51,6 +51,12 @@ impl ZKTcp {
 
         let reply = self.execute_cmd(cmd::CMD_CONNECT, &[]).await?;
         let inner = remove_tcp_header(&reply);
+        if inner.len() >= 2 {
+            let cmd_id = u16::from_le_bytes([inner[0], inner[1]]);
+            if is_busy_reply(cmd_id) {
+                return Err(busy_error(cmd::CMD_CONNECT, cmd_id));
+            }
+        }
         if inner.len() >= 6 {
             self.session_id = u16::from_le_bytes([inner[4], inner[5]]);
         }
@@ -69,6 +75,9 @@ impl ZKTcp {
             if cmd_id == cmd::CMD_ACK_OK {
                 return Ok(());
             }
+            if matches!(cmd_id, cmd::CMD_ACK_RETRY | cmd::CMD_ACK_REPEAT) {
+                return Err(busy_error(cmd::CMD_AUTH, cmd_id));
+            }
             return Err(format!("Device authentication failed (response: {})", command_name(cmd_id)));
         }
         Err("Device authentication failed: empty response".to_string())
@@ -118,6 +127,14 @@ impl ZKTcp {
 
     /// Read large data (users or attendance) via multi-packet protocol
     pub async fn read_with_buffer(&mut self, req_data: &[u8]) -> Result<(Vec<u8>, bool), String> {
+        match self.prepare_data(req_data).await? {
+            PreparedData::Inline(data) => Ok((data, true)),
+            PreparedData::Buffered(size) => Ok((self.read_buffer(0, size).await?, false)),
+        }
+    }
+
+    /// Ask the device to prepare users or attendance for reading
+    async fn prepare_data(&mut self, req_data: &[u8]) -> Result<PreparedData, String> {
         self.reply_id = self.reply_id.wrapping_add(1);
         let buf = create_tcp_header(cmd::CMD_DATA_WRRQ, self.session_id, self.reply_id, req_data);
 
@@ -154,8 +171,7 @@ impl ZKTcp {
         match header.command_id {
             cmd::CMD_DATA => {
                 // Small data response — all data in one packet
-                let data = reply_buf[16..].to_vec();
-                Ok((data, true))
+                Ok(PreparedData::Inline(reply_buf[16..].to_vec()))
             }
             cmd::CMD_ACK_OK | cmd::CMD_PREPARE_DATA => {
                 // Large data — need to receive in chunks
@@ -169,147 +185,151 @@ impl ZKTcp {
                     recv_data[3],
                     recv_data[4],
                 ]) as usize;
+                Ok(PreparedData::Buffered(size))
+            }
+            busy if is_busy_reply(busy) => Err(busy_error(cmd::CMD_DATA_WRRQ, busy)),
+            _ => Err(format!(
+                "Unexpected command in data response: {} ({})",
+                header.command_id,
+                command_name(header.command_id)
+            )),
+        }
+    }
 
-                let remain = size % MAX_CHUNK;
-                let total_packets = (size + MAX_CHUNK - 1) / MAX_CHUNK; // ceil division
-
-                let mut reply_data = Vec::with_capacity(size);
-                let mut total_buffer = Vec::new();
-                let mut real_total_buffer = Vec::new();
-                let mut packets_remaining = total_packets;
-
-                // Pre-build all chunk requests (avoids borrow conflict with stream)
-                let mut chunk_requests = Vec::new();
-                for i in 0..total_packets {
-                    let start = i * MAX_CHUNK;
-                    let chunk_size = if i == total_packets - 1 && remain > 0 {
-                        remain
-                    } else {
-                        MAX_CHUNK
-                    };
-                    let chunk_buf =
-                        self.build_chunk_request(start as u32, chunk_size as u32);
-                    chunk_requests.push(chunk_buf);
-                }
+    /// Read `len` bytes from `offset` of the prepared buffer, in chunks
+    async fn read_buffer(&mut self, offset: usize, len: usize) -> Result<Vec<u8>, String> {
+        let chunks = chunk_plan(offset, len);
+        let total_packets = chunks.len();
 
-                // Send all chunk requests
-                let stream = self.stream.as_mut().ok_or("TCP not connected")?;
-                for chunk_buf in &chunk_requests {
-                    stream
-                        .write_all(chunk_buf)
-                        .await
-                        .map_err(|e| format!("TCP write chunk request failed: {}", e))?;
-                }
+        let mut tmp = vec![0u8; 65536];
+        let mut reply_data = Vec::with_capacity(len);
+        let mut total_buffer = Vec::new();
+        let mut real_total_buffer = Vec::new();
+        let mut packets_remaining = total_packets;
+
+        // Pre-build all chunk requests (avoids borrow conflict with stream)
+        let chunk_requests: Vec<Vec<u8>> = chunks
+            .iter()
+            .map(|&(start, size)| self.build_chunk_request(start as u32, size as u32))
+            .collect();
+
+        // Send all chunk requests
+        let stream = self.stream.as_mut().ok_or("TCP not connected")?;
+        for chunk_buf in &chunk_requests {
+            stream
+                .write_all(chunk_buf)
+                .await
+                .map_err(|e| format!("TCP write chunk request failed: {}", e))?;
+        }
 
-                // Receive all chunk responses
-                // Scale timeout with data size: base 60s + 30s per chunk.
-                // For 11k records (~464KB, ~8 chunks) this gives ~300s.
-                let chunk_timeout_secs = 60 + (total_packets as u64 * 30);
-                log::info!(
-                    "[zkteco] TCP: expecting {} bytes in {} chunks, timeout {}s",
-                    size, total_packets, chunk_timeout_secs
-                );
-                let chunk_timeout = Duration::from_secs(chunk_timeout_secs);
-                let deadline = tokio::time::Instant::now() + chunk_timeout;
-
-                while packets_remaining > 0 {
-                    let remaining_time = deadline.saturating_duration_since(tokio::time::Instant::now());
-                    if remaining_time.is_zero() {
-                        return Err(format!(
-                            "Timeout receiving chunks, {} packets remain, got {}/{} bytes",
-                            packets_remaining,
-                            reply_data.len(),
-                            size
-                        ));
-                    }
+        // Receive all chunk responses
+        // Scale timeout with data size: base 60s + 30s per chunk.
+        // For 11k records (~464KB, ~8 chunks) this gives ~300s.
+        let chunk_timeout_secs = 60 + (total_packets as u64 * 30);
+        log::info!(
+            "[zkteco] TCP: expecting {} bytes in {} chunks, timeout {}s",
+            len, total_packets, chunk_timeout_secs
+        );
+        let chunk_timeout = Duration::from_secs(chunk_timeout_secs);
+        let deadline = tokio::time::Instant::now() + chunk_timeout;
+
+        while packets_remaining > 0 {
+            let remaining_time = deadline.saturating_duration_since(tokio::time::Instant::now());
+            if remaining_time.is_zero() {
+                return Err(format!(
+                    "Timeout receiving chunks, {} packets remain, got {}/{} bytes",
+                    packets_remaining,
+                    reply_data.len(),
+                    len
+                ));
+            }
 
-                    let stream = self.stream.as_mut().ok_or("TCP not connected")?;
-                    let n = timeout(remaining_time, stream.read(&mut tmp))
-                        .await
-                        .map_err(|_| {
-                            format!(
-                                "Timeout receiving chunk data, {} packets remain",
-                                packets_remaining
-                            )
-                        })?
-                        .map_err(|e| format!("TCP read chunk failed: {}", e))?;
-
-                    if n == 0 {
-                        return Err("Connection closed during chunk transfer".to_string());
-                    }
+            let stream = self.stream.as_mut().ok_or("TCP not connected")?;
+            let n = timeout(remaining_time, stream.read(&mut tmp))
+                .await
+                .map_err(|_| {
+                    format!(
+                        "Timeout receiving chunk data, {} packets remain",
+                        packets_remaining
+                    )
+                })?
+                .map_err(|e| format!("TCP read chunk failed: {}", e))?;
 
-                    // Skip real-time event packets
-                    if check_not_event_tcp(&tmp[..n]) {
-                        continue;
-                    }
+            if n == 0 {
+                return Err("Connection closed during chunk transfer".to_string());
+            }
 
-                    total_buffer.extend_from_slice(&tmp[..n]);
-
-                    // Process complete packets from total_buffer
-                    while total_buffer.len() >= 8 {
-                        let packet_length =
-                            u16::from_le_bytes([total_buffer[4], total_buffer[5]]) as usize;
-
-                        if total_buffer.len() < 8 + packet_length {
-                            break; // Wait for more data
-                        }
-
-                        real_total_buffer
-                            .extend_from_slice(&total_buffer[16..8 + packet_length]);
-                        total_buffer = total_buffer[8 + packet_length..].to_vec();
-
-                        let expected_size = if packets_remaining > 1 {
-                            MAX_CHUNK + 8
-                        } else {
-                            remain + 8
-                        };
-
-                        if real_total_buffer.len() >= expected_size {
-                            if real_total_buffer.len() > 8 {
-                                reply_data.extend_from_slice(&real_total_buffer[8..]);
-                            }
-                            real_total_buffer.clear();
-                            packets_remaining -= 1;
-                        }
-                    }
+            // Skip real-time event packets
+            if check_not_event_tcp(&tmp[..n]) {
+                continue;
+            }
+
+            total_buffer.extend_from_slice(&tmp[..n]);
+
+            // Process complete packets from total_buffer
+            while total_buffer.len() >= 8 && packets_remaining > 0 {
+                let packet_length =
+                    u16::from_le_bytes([total_buffer[4], total_buffer[5]]) as usize;
+
+                if total_buffer.len() < 8 + packet_length {
+                    break; // Wait for more data
                 }
 
-                Ok((reply_data, false))
+                real_total_buffer
+                    .extend_from_slice(&total_buffer[16..8 + packet_length]);
+                total_buffer = total_buffer[8 + packet_length..].to_vec();
+
+                let expected_size = chunks[total_packets - packets_remaining].1 + 8;
+
+                if real_total_buffer.len() >= expected_size {
+                    if real_total_buffer.len() > 8 {
+                        reply_data.extend_from_slice(&real_total_buffer[8..]);
+                    }
+                    real_total_buffer.clear();
+                    packets_remaining -= 1;
+                }
             }
-            _ => Err(format!(
-                "Unexpected command in data response: {} ({})",
-                header.command_id,
-                command_name(header.command_id)
-            )),
         }
+
+        Ok(reply_data)
     }
 
-    /// Get users from device (TCP uses 72-byte records)
-    pub async fn get_users(&mut self) -> Result<Vec<(u16, String, String)>, String> {
+    /// Raw user records from the device (TCP uses 72-byte records)
+    pub async fn get_user_records(&mut self) -> Result<Vec<Vec<u8>>, String> {
         self.free_data().await.ok();
 
         let (data, _is_small) = self.read_with_buffer(request_data::GET_USERS).await?;
 
         self.free_data().await.ok();
 
-        let user_packet_size = 72;
-        if data.len() < 4 {
-            return Ok(vec![]);
-        }
-        let mut user_data = &data[4..];
-        let mut users = Vec::new();
+        Ok(data
+            .get(DATA_SIZE_PREFIX..)
+            .unwrap_or_default()
+            .chunks_exact(USER_RECORD_SIZE_TCP)
+            .map(<[u8]>::to_vec)
+            .collect())
+    }
 
-        while user_data.len() >= user_packet_size {
-            let user = decode_user_data_72(&user_data[..user_packet_size]);
-            users.push(user);
-            user_data = &user_data[user_packet_size..];
-        }
+    /// The biometric template table: each record is a size, the user's
+    /// record number, the template index, a valid flag and the template
+    pub async fn get_templates(&mut self) -> Result<Vec<u8>, String> {
+        self.free_data().await.ok();
 
-        Ok(users)
+        let (data, _is_small) = self.read_with_buffer(request_data::GET_TEMPLATES).await?;
+
+        self.free_data().await.ok();
+
+        Ok(data.get(DATA_SIZE_PREFIX..).map(<[u8]>::to_vec).unwrap_or_default())
+    }
+
+    /// Get users from device
+    pub async fn get_users(&mut self) -> Result<Vec<(u16, String, String)>, String> {
+        let records = self.get_user_records().await?;
+        Ok(records.iter().map(|record| decode_user_data_72(record)).collect())
     }
 
     /// Get attendance logs from device (TCP uses 40-byte records)
-    pub async fn get_attendances(&mut self) -> Result<Vec<(String, String, u8, u8)>, String> {
+    pub async fn get_attendances(&mut self) -> Result<Vec<RawAttendance>, String> {
         self.free_data().await.ok();
 
         let (data, _is_small) = self
@@ -318,20 +338,51 @@ impl ZKTcp {
 
         self.free_data().await.ok();
 
-        let record_packet_size = 40;
         if data.len() < 4 {
             return Ok(vec![]);
         }
-        let mut record_data = &data[4..];
-        let mut records = Vec::new();
+        Ok(decode_records(&data[4..], 40, decode_record_data_40))
+    }
 
-        while record_data.len() >= record_packet_size {
-            let record = decode_record_data_40(&record_data[..record_packet_size]);
-            records.push(record);
-            record_data = &record_data[record_packet_size..];
-        }
+    /// Attendance records `start..start + count` and the number of records
+    /// on the device, reading only that part of the device's buffer
+    pub async fn get_attendance_page(
+        &mut self,
+        start: usize,
+        count: usize,
+    ) -> Result<(Vec<RawAttendance>, usize), String> {
+        self.free_data().await.ok();
+        let page = self.read_attendance_page(start, count).await;
+        self.free_data().await.ok();
+        page
+    }
 
-        Ok(records)
+    async fn read_attendance_page(
+        &mut self,
+        start: usize,
+        count: usize,
+    ) -> Result<(Vec<RawAttendance>, usize), String> {
+        let record_packet_size = 40;
+        let data = match self.prepare_data(request_data::GET_ATTENDANCE_LOGS).await? {
+            PreparedData::Inline(data) => data,
+            PreparedData::Buffered(size) => {
+                let ((offset, len), total) = record_range(size, record_packet_size, start, count);
+                if len == 0 {
+                    return Ok((vec![], total));
+                }
+                let data = self.read_buffer(offset, len).await?;
+                return Ok((decode_records(&data, record_packet_size, decode_record_data_40), total));
+            }
+        };
+
+        // Small enough to have come back whole
+        let records = decode_records(
+            data.get(DATA_SIZE_PREFIX..).unwrap_or_default(),
+            record_packet_size,
+            decode_record_data_40,
+        );
+        let total = records.len();
+        Ok((records.into_iter().skip(start).take(count).collect(), total))
     }
 
     /// Get device info (free sizes)
@@ -360,6 +411,12 @@ impl ZKTcp {
         }
     }
 
+    /// Send a command and return its reply code and payload
+    pub async fn request(&mut self, command: u16, data: &[u8]) -> Result<(u16, Vec<u8>), String> {
+        let reply = self.execute_cmd(command, data).await?;
+        Ok(split_reply(remove_tcp_header(&reply)))
+    }
+
     /// Free data buffer on device
     pub async fn free_data(&mut self) -> Result<(), String> {
         self.execute_cmd(cmd::CMD_FREE_DATA, &[]).await?;
@@ -0,0 +1,87 @@
+//! Who is enrolled with what
+//!
+//! Fingerprints and faces are counted from the device's template table in
+//! one read, by template index: 0-9 are fingers and 50 is the face, where
+//! face firmware keeps it alongside the fingerprints. A card is whatever
+//! card number the user record holds.
+
+use std::collections::HashMap;
+
+use super::client::ZKClient;
+use super::protocol::{decode_user_record, USER_RECORD_SIZE_TCP, USER_RECORD_SIZE_UDP};
+
+/// Template index of a face
+const FACE_INDEX: u8 = 50;
+
+/// Highest template index of a finger
+const LAST_FINGER_INDEX: u8 = 9;
+
+/// Record header: size, record number, index, valid flag
+const TEMPLATE_HEADER_SIZE: usize = 6;
+
+/// A device user and what they are enrolled with
+#[derive(Debug, Clone)]
+pub struct UserEnrollment {
+    pub uid: u16,
+    pub device_user_id: String,
+    pub name: String,
+    pub fingerprints: u32,
+    pub faces: u32,
+    pub has_card: bool,
+}
+
+/// Card number in a user record (either format); 0 when there is none
+fn card_number(record: &[u8]) -> u32 {
+    let offset = match record.len() {
+        USER_RECORD_SIZE_UDP => 16,
+        USER_RECORD_SIZE_TCP => 35,
+        _ => return 0,
+    };
+    u32::from_le_bytes([record[offset], record[offset + 1], record[offset + 2], record[offset + 3]])
+}
+
+/// Fingerprint and face counts by record number, from the template table.
+/// Stops at a malformed record rather than guessing past it.
+pub fn count_templates(data: &[u8]) -> HashMap<u16, (u32, u32)> {
+    let mut counts: HashMap<u16, (u32, u32)> = HashMap::new();
+    let mut rest = data;
+    while rest.len() >= TEMPLATE_HEADER_SIZE {
+        let size = u16::from_le_bytes([rest[0], rest[1]]) as usize;
+        if size < TEMPLATE_HEADER_SIZE || size > rest.len() {
+            log::warn!("[zkteco] Malformed template record of {} bytes; stopped counting", size);
+            break;
+        }
+        let uid = u16::from_le_bytes([rest[2], rest[3]]);
+        let entry = counts.entry(uid).or_default();
+        match rest[4] {
+            FACE_INDEX => entry.1 += 1,
+            index if index <= LAST_FINGER_INDEX => entry.0 += 1,
+            _ => {}
+        }
+        rest = &rest[size..];
+    }
+    counts
+}
+
+/// Every user on the device with their template counts
+pub async fn read(client: &mut ZKClient) -> Result<Vec<UserEnrollment>, String> {
+    let records = client.get_user_records().await?;
+    let counts = count_templates(&client.get_templates().await?);
+    let mut users: Vec<UserEnrollment> = records
+        .values()
+        .map(|record| {
+            let (uid, device_user_id, name) = decode_user_record(record);
+            let (fingerprints, faces) = counts.get(&uid).copied().unwrap_or_default();
+            UserEnrollment {
+                uid,
+                device_user_id,
+                name,
+                fingerprints,
+                faces,
+                has_card: card_number(record) != 0,
+            }
+        })
+        .collect();
+    users.sort_by_key(|u| u.uid);
+    Ok(users)
+}
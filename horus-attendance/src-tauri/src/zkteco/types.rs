@@ -6,6 +6,9 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct DeviceConfig {
+    /// Needed to look up a comm key stored in the keychain
+    #[serde(default)]
+    pub device_id: Option<String>,
     pub ip: String,
     #[serde(default = "default_port")]
     pub port: u16,
@@ -32,6 +35,35 @@ pub struct DeviceInfo {
     pub user_count: u32,
     pub log_count: u32,
     pub last_activity: String,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub mac_address: Option<String>,
+}
+
+/// What hardware a device is, as far as it answers; older firmware leaves
+/// some of it out
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceHardware {
+    pub model: Option<String>,
+    pub serial_number: Option<String>,
+    pub firmware: Option<String>,
+    pub mac_address: Option<String>,
+}
+
+/// What a device's firmware was found to support
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceCapabilities {
+    /// Feature flags (see `capabilities::FEATURE_*`). A feature that isn't
+    /// listed wasn't probed or got no clear answer, and is tried anyway.
+    pub features: std::collections::BTreeMap<String, bool>,
+    /// Longest user ID the device takes
+    pub user_id_width: Option<u32>,
+    /// Firmware version the probe ran against; a different one is probed again
+    pub firmware: Option<String>,
+    pub probed_at: String,
 }
 
 /// A user record from the device
@@ -50,6 +82,9 @@ pub struct AttendanceLog {
     pub timestamp: String,
     pub verify_type: u8,
     pub punch_type: u8,
+    /// The record as the device sent it, kept when raw payload retention is on
+    #[serde(skip)]
+    pub raw: Vec<u8>,
 }
 
 /// Connection test result
@@ -68,9 +103,13 @@ pub struct ConnectionTestResult {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SyncOptions {
-    pub mode: String, // "all" or "range"
+    pub mode: String, // "all", "range" or "incremental"
     pub start_date: Option<String>,
     pub end_date: Option<String>,
+    /// Report what a sync would store in [`SyncAllResult::preview`]; nothing
+    /// is written either way, this only adds the comparison with the database
+    #[serde(default)]
+    pub dry_run: bool,
 }
 
 /// Combined sync result (users + logs)
@@ -79,4 +118,319 @@ pub struct SyncOptions {
 pub struct SyncAllResult {
     pub users: Vec<DeviceUser>,
     pub logs: Vec<AttendanceLog>,
+    /// Users and logs left out because they failed validation
+    #[serde(default)]
+    pub quarantined: u32,
+    /// Set for dry runs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preview: Option<SyncPreview>,
+    /// Model, serial number, firmware and MAC address read during the sync
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hardware: Option<DeviceHardware>,
+    /// Connection the sync went over: "tcp", "udp" or "demo"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transport: Option<String>,
+    /// Set for incremental syncs: how the logs were read and the offset to
+    /// save once they are stored
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub incremental: Option<IncrementalSync>,
+}
+
+/// How far a device's attendance buffer has been read: the number of
+/// records it held and the last of them, which must still be in place for
+/// the next read to carry on from there
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceLogOffset {
+    pub record_count: u64,
+    pub last_device_user_id: Option<String>,
+    /// As the device stores it, before clock correction
+    pub last_timestamp: Option<String>,
+}
+
+/// How an incremental sync read the attendance buffer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IncrementalSync {
+    /// The whole buffer was read: there was no offset yet, or the device
+    /// log was cleared or rewritten since
+    pub full: bool,
+    /// Why the whole buffer was read
+    pub reason: Option<String>,
+    /// Records before the offset that weren't transferred
+    pub skipped_records: u64,
+    /// Where the next incremental sync carries on from
+    pub offset: DeviceLogOffset,
+}
+
+/// What syncing the fetched users and logs would store
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncPreview {
+    /// Device users no app user has the ID of
+    pub new_users: Vec<DeviceUser>,
+    pub existing_users: u32,
+    pub new_logs: u32,
+    /// Logs already stored for this device
+    pub duplicate_logs: u32,
+    /// Logs whose user ID is neither on the device nor in the app
+    pub unknown_user_logs: u32,
+    /// Range covered by the fetched logs
+    pub first_timestamp: Option<String>,
+    pub last_timestamp: Option<String>,
+}
+
+/// Emitted as `device-busy` before sync retries a device that reported busy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceBusyEvent {
+    pub ip: String,
+    pub attempt: u32,
+    pub max_attempts: u32,
+    pub retry_in_secs: u64,
+    pub message: String,
+}
+
+/// One check of a connectivity diagnosis
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectivityStep {
+    /// "ping", "tcp_port", "arp", "udp", "auth" or "free_sizes"
+    pub id: String,
+    pub label: String,
+    /// "passed", "warning", "failed" or "skipped"
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// What to try next, for warnings and failures
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<String>,
+    pub duration_ms: u64,
+}
+
+/// Outcome of checking each layer between this computer and a device
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectivityReport {
+    pub ip: String,
+    pub port: u16,
+    /// Whether the device could be connected to and read
+    pub success: bool,
+    /// "tcp", "udp" or "demo" when connected
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transport: Option<String>,
+    pub steps: Vec<ConnectivityStep>,
+    /// The suggestion of the first failed step, or that all is well
+    pub summary: String,
+}
+
+/// A host that answered during a subnet scan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanHost {
+    pub ip: String,
+    pub port: u16,
+    /// TCP connect time in milliseconds
+    pub latency: u64,
+    /// Whether the host completed the device handshake
+    pub is_device: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device_info: Option<DeviceInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A device's network configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkSettings {
+    pub ip: String,
+    pub netmask: String,
+    pub gateway: String,
+    pub dhcp: bool,
+}
+
+/// Outcome of changing a device's network settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkChangeResult {
+    pub previous: NetworkSettings,
+    pub applied: NetworkSettings,
+    /// Whether the device answered again after restarting
+    pub reconnected: bool,
+    /// Where the device is now expected; unknown after switching to DHCP
+    pub address: Option<String>,
+    pub message: String,
+}
+
+/// One entry of a device's bell schedule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Bell {
+    /// Position on the device, from 1
+    pub slot: u8,
+    /// HH:MM, device-local
+    pub time: String,
+    /// Days it rings, 0 = Sunday through 6 = Saturday
+    pub days: Vec<u8>,
+    /// How long it rings, in seconds
+    pub duration_secs: u16,
+    pub enabled: bool,
+}
+
+/// Outcome of writing settings to one device
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceWriteResult {
+    pub device_id: Option<String>,
+    pub ip: String,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+/// A managed device setting and its current value
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceOptionValue {
+    pub key: String,
+    pub label: String,
+    /// On/off (0/1) rather than a number in `min..=max`
+    pub boolean: bool,
+    pub min: i64,
+    pub max: i64,
+    /// False when this model doesn't have the setting
+    pub supported: bool,
+    pub value: Option<String>,
+}
+
+/// Settings for a device's initial import
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InitialImportOptions {
+    /// Records read per pass
+    pub chunk_size: Option<u32>,
+    /// Pause between passes, in milliseconds
+    pub pause_ms: Option<u64>,
+    /// Start again from the first record instead of resuming
+    #[serde(default)]
+    pub restart: bool,
+}
+
+/// Progress of a device's initial import, also emitted as
+/// `initial-import-progress` after every pass
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InitialImportStatus {
+    pub device_id: String,
+    /// running, interrupted or completed
+    pub status: String,
+    /// Position of the next record to read
+    pub next_record: u64,
+    /// Records on the device when last read
+    pub total_records: u64,
+    pub imported: u64,
+    /// Records that were already stored
+    pub duplicates: u64,
+    pub chunk_size: u32,
+    /// Earliest and latest punch imported, to recompute summaries over
+    pub first_timestamp: Option<String>,
+    pub last_timestamp: Option<String>,
+    pub last_error: Option<String>,
+    pub started_at: String,
+    pub updated_at: String,
+    pub completed_at: Option<String>,
+    /// Records that failed validation
+    pub quarantined: u64,
+}
+
+/// What to compare in a device audit
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceAuditOptions {
+    /// Compare only this many of the device's most recent records instead
+    /// of all of them (default 1000); ignored when `full` is set
+    pub sample_size: Option<u32>,
+    /// Compare every record on the device
+    #[serde(default)]
+    pub full: bool,
+}
+
+/// A punch found on only one side of a device audit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditRecord {
+    pub device_user_id: String,
+    pub timestamp: String,
+}
+
+/// Record counts and digest of one date that differs between device and
+/// database
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditDayDigest {
+    pub date: String,
+    pub device_records: u32,
+    pub db_records: u32,
+    /// SHA-256 of the date's sorted "user|timestamp" lines, shortened
+    pub device_hash: String,
+    pub db_hash: String,
+}
+
+/// Comparison of a saved device's records with those stored for it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceAuditReport {
+    pub device_id: String,
+    pub checked_at: String,
+    /// Every record was compared, not just the most recent ones
+    pub full: bool,
+    pub device_user_count: u32,
+    pub device_log_count: u32,
+    /// Every record stored for the device
+    pub db_log_count: u32,
+    /// Device users with no profile and no link to one
+    pub unknown_users: u32,
+    /// Device records compared, and the period they cover
+    pub compared_records: u32,
+    pub first_timestamp: Option<String>,
+    pub last_timestamp: Option<String>,
+    /// Device records failing validation, which sync quarantines
+    pub invalid_records: u32,
+    /// Stored records for the device in the compared period
+    pub db_records_in_period: u32,
+    pub matched: u32,
+    pub missing_from_db: u32,
+    pub missing_from_device: u32,
+    /// The first few of each, oldest first
+    pub missing_from_db_sample: Vec<AuditRecord>,
+    pub missing_from_device_sample: Vec<AuditRecord>,
+    /// Dates whose records differ
+    pub mismatched_days: Vec<AuditDayDigest>,
+    /// Nothing is missing on either side
+    pub consistent: bool,
+}
+
+/// A time of day a device may be synced unattended
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncWindow {
+    /// Local time the window opens (HH:MM)
+    pub start_time: String,
+    /// Local time it closes (HH:MM); before `start_time` when it runs past midnight
+    pub end_time: String,
+}
+
+/// Whether a device may be synced now
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncWindowCheck {
+    pub device_id: String,
+    /// Now is inside one of the device's windows, or it has none
+    pub open: bool,
+    /// The sync may go ahead: the window is open or the sync is manual
+    pub allowed: bool,
+    /// When the next window opens, if the device is outside them
+    pub next_open_at: Option<String>,
+    /// Why an unattended sync is refused, or what a manual one is overriding
+    pub message: Option<String>,
 }
@@ -53,6 +53,12 @@ impl ZKUdp {
         self.reply_id = 0;
 
         let reply = self.execute_cmd(cmd::CMD_CONNECT, &[]).await?;
+        if reply.len() >= 2 {
+            let cmd_id = u16::from_le_bytes([reply[0], reply[1]]);
+            if is_busy_reply(cmd_id) {
+                return Err(busy_error(cmd::CMD_CONNECT, cmd_id));
+            }
+        }
         if reply.len() >= 6 {
             self.session_id = u16::from_le_bytes([reply[4], reply[5]]);
         }
@@ -70,6 +76,9 @@ impl ZKUdp {
             if cmd_id == cmd::CMD_ACK_OK {
                 return Ok(());
             }
+            if matches!(cmd_id, cmd::CMD_ACK_RETRY | cmd::CMD_ACK_REPEAT) {
+                return Err(busy_error(cmd::CMD_AUTH, cmd_id));
+            }
             return Err(format!("Device authentication failed (response: {})", command_name(cmd_id)));
         }
         Err("Device authentication failed: empty response".to_string())
@@ -115,6 +124,14 @@ impl ZKUdp {
 
     /// Read large data via multi-packet protocol
     pub async fn read_with_buffer(&mut self, req_data: &[u8]) -> Result<(Vec<u8>, bool), String> {
+        match self.prepare_data(req_data).await? {
+            PreparedData::Inline(data) => Ok((data, true)),
+            PreparedData::Buffered(size) => Ok((self.read_buffer(0, size).await?, false)),
+        }
+    }
+
+    /// Ask the device to prepare users or attendance for reading
+    async fn prepare_data(&mut self, req_data: &[u8]) -> Result<PreparedData, String> {
         self.reply_id = self.reply_id.wrapping_add(1);
         let buf = create_udp_header(cmd::CMD_DATA_WRRQ, self.session_id, self.reply_id, req_data);
 
@@ -143,7 +160,7 @@ impl ZKUdp {
         match header.command_id {
             cmd::CMD_DATA => {
                 // Small data — single packet response
-                Ok((reply[8..].to_vec(), true))
+                Ok(PreparedData::Inline(reply[8..].to_vec()))
             }
             cmd::CMD_ACK_OK | cmd::CMD_PREPARE_DATA => {
                 // Large data — multi-packet
@@ -157,91 +174,9 @@ impl ZKUdp {
                     recv_data[3],
                     recv_data[4],
                 ]) as usize;
-
-                let remain = size % MAX_CHUNK;
-                let total_packets = (size + MAX_CHUNK - 1) / MAX_CHUNK; // ceil division
-
-                let mut total_buffer = Vec::with_capacity(size);
-
-                // Send all chunk requests
-                for i in 0..total_packets {
-                    let start = i * MAX_CHUNK;
-                    let chunk_size = if i == total_packets - 1 && remain > 0 {
-                        remain
-                    } else {
-                        MAX_CHUNK
-                    };
-                    let chunk_buf =
-                        self.build_chunk_request(start as u32, chunk_size as u32);
-                    let socket = self.socket.as_ref().ok_or("UDP not connected")?;
-                    socket
-                        .send(&chunk_buf)
-                        .await
-                        .map_err(|e| format!("UDP send chunk request failed: {}", e))?;
-                }
-
-                // Receive chunks
-                // Scale timeout with data size: base 60s + 30s per chunk.
-                let chunk_timeout_secs = 60 + (total_packets as u64 * 30);
-                log::info!(
-                    "[zkteco] UDP: expecting {} bytes in {} chunks, timeout {}s",
-                    size, total_packets, chunk_timeout_secs
-                );
-                let chunk_timeout = Duration::from_secs(chunk_timeout_secs);
-                let deadline = tokio::time::Instant::now() + chunk_timeout;
-
-                while total_buffer.len() < size {
-                    let remaining_time = deadline.saturating_duration_since(tokio::time::Instant::now());
-                    if remaining_time.is_zero() {
-                        return Err(format!(
-                            "Timeout receiving UDP chunks, got {}/{} bytes",
-                            total_buffer.len(),
-                            size
-                        ));
-                    }
-
-                    let socket = self.socket.as_ref().ok_or("UDP not connected")?;
-                    let n = timeout(remaining_time, socket.recv(&mut resp_buf))
-                        .await
-                        .map_err(|_| {
-                            format!(
-                                "Timeout receiving UDP chunk, got {}/{} bytes",
-                                total_buffer.len(),
-                                size
-                            )
-                        })?
-                        .map_err(|e| format!("UDP recv chunk failed: {}", e))?;
-
-                    let chunk = &resp_buf[..n];
-
-                    // Skip event packets
-                    if check_not_event_udp(chunk) {
-                        continue;
-                    }
-
-                    if chunk.len() < 8 {
-                        continue;
-                    }
-
-                    let chunk_header = decode_udp_header(&chunk[0..8]);
-                    match chunk_header.command_id {
-                        cmd::CMD_PREPARE_DATA => {
-                            // Info packet, skip
-                        }
-                        cmd::CMD_DATA => {
-                            total_buffer.extend_from_slice(&chunk[8..]);
-                        }
-                        cmd::CMD_ACK_OK => {
-                            if total_buffer.len() >= size {
-                                break;
-                            }
-                        }
-                        _ => {}
-                    }
-                }
-
-                Ok((total_buffer, false))
+                Ok(PreparedData::Buffered(size))
             }
+            busy if is_busy_reply(busy) => Err(busy_error(cmd::CMD_DATA_WRRQ, busy)),
             _ => Err(format!(
                 "Unexpected command in data response: {} ({})",
                 header.command_id,
@@ -250,32 +185,118 @@ impl ZKUdp {
         }
     }
 
-    /// Get users from device (UDP uses 28-byte records)
-    pub async fn get_users(&mut self) -> Result<Vec<(u16, String, String)>, String> {
+    /// Read `len` bytes from `offset` of the prepared buffer, in chunks
+    async fn read_buffer(&mut self, offset: usize, len: usize) -> Result<Vec<u8>, String> {
+        let chunks = chunk_plan(offset, len);
+        let total_packets = chunks.len();
+        let mut resp_buf = vec![0u8; 65536];
+        let mut total_buffer = Vec::with_capacity(len);
+
+        // Send all chunk requests
+        for (start, size) in chunks {
+            let chunk_buf = self.build_chunk_request(start as u32, size as u32);
+            let socket = self.socket.as_ref().ok_or("UDP not connected")?;
+            socket
+                .send(&chunk_buf)
+                .await
+                .map_err(|e| format!("UDP send chunk request failed: {}", e))?;
+        }
+
+        // Receive chunks
+        // Scale timeout with data size: base 60s + 30s per chunk.
+        let chunk_timeout_secs = 60 + (total_packets as u64 * 30);
+        log::info!(
+            "[zkteco] UDP: expecting {} bytes in {} chunks, timeout {}s",
+            len, total_packets, chunk_timeout_secs
+        );
+        let chunk_timeout = Duration::from_secs(chunk_timeout_secs);
+        let deadline = tokio::time::Instant::now() + chunk_timeout;
+
+        while total_buffer.len() < len {
+            let remaining_time = deadline.saturating_duration_since(tokio::time::Instant::now());
+            if remaining_time.is_zero() {
+                return Err(format!(
+                    "Timeout receiving UDP chunks, got {}/{} bytes",
+                    total_buffer.len(),
+                    len
+                ));
+            }
+
+            let socket = self.socket.as_ref().ok_or("UDP not connected")?;
+            let n = timeout(remaining_time, socket.recv(&mut resp_buf))
+                .await
+                .map_err(|_| {
+                    format!(
+                        "Timeout receiving UDP chunk, got {}/{} bytes",
+                        total_buffer.len(),
+                        len
+                    )
+                })?
+                .map_err(|e| format!("UDP recv chunk failed: {}", e))?;
+
+            let chunk = &resp_buf[..n];
+
+            // Skip event packets
+            if check_not_event_udp(chunk) {
+                continue;
+            }
+
+            if chunk.len() < 8 {
+                continue;
+            }
+
+            let chunk_header = decode_udp_header(&chunk[0..8]);
+            match chunk_header.command_id {
+                cmd::CMD_PREPARE_DATA => {
+                    // Info packet, skip
+                }
+                cmd::CMD_DATA => {
+                    total_buffer.extend_from_slice(&chunk[8..]);
+                }
+                cmd::CMD_ACK_OK if total_buffer.len() >= len => break,
+                _ => {}
+            }
+        }
+
+        Ok(total_buffer)
+    }
+
+    /// Raw user records from the device (UDP uses 28-byte records)
+    pub async fn get_user_records(&mut self) -> Result<Vec<Vec<u8>>, String> {
         self.free_data().await.ok();
 
         let (data, _is_small) = self.read_with_buffer(request_data::GET_USERS).await?;
 
         self.free_data().await.ok();
 
-        let user_packet_size = 28;
-        if data.len() < 4 {
-            return Ok(vec![]);
-        }
-        let mut user_data = &data[4..];
-        let mut users = Vec::new();
+        Ok(data
+            .get(DATA_SIZE_PREFIX..)
+            .unwrap_or_default()
+            .chunks_exact(USER_RECORD_SIZE_UDP)
+            .map(<[u8]>::to_vec)
+            .collect())
+    }
 
-        while user_data.len() >= user_packet_size {
-            let user = decode_user_data_28(&user_data[..user_packet_size]);
-            users.push(user);
-            user_data = &user_data[user_packet_size..];
-        }
+    /// The biometric template table: each record is a size, the user's
+    /// record number, the template index, a valid flag and the template
+    pub async fn get_templates(&mut self) -> Result<Vec<u8>, String> {
+        self.free_data().await.ok();
 
-        Ok(users)
+        let (data, _is_small) = self.read_with_buffer(request_data::GET_TEMPLATES).await?;
+
+        self.free_data().await.ok();
+
+        Ok(data.get(DATA_SIZE_PREFIX..).map(<[u8]>::to_vec).unwrap_or_default())
+    }
+
+    /// Get users from device
+    pub async fn get_users(&mut self) -> Result<Vec<(u16, String, String)>, String> {
+        let records = self.get_user_records().await?;
+        Ok(records.iter().map(|record| decode_user_data_28(record)).collect())
     }
 
     /// Get attendance logs from device (UDP uses 16-byte records, small uses 8-byte)
-    pub async fn get_attendances(&mut self) -> Result<Vec<(String, String, u8, u8)>, String> {
+    pub async fn get_attendances(&mut self) -> Result<Vec<RawAttendance>, String> {
         self.free_data().await.ok();
 
         let (data, is_small) = self
@@ -287,28 +308,53 @@ impl ZKUdp {
         if data.len() < 4 {
             return Ok(vec![]);
         }
-        let mut record_data = &data[4..];
-        let mut records = Vec::new();
+        let record_data = &data[4..];
 
         if is_small {
             // Small response: 8-byte records
-            let record_packet_size = 8;
-            while record_data.len() >= record_packet_size {
-                let record = decode_record_data_8(&record_data[..record_packet_size]);
-                records.push(record);
-                record_data = &record_data[record_packet_size..];
-            }
+            Ok(decode_records(record_data, 8, decode_record_data_8))
         } else {
             // Normal response: 16-byte records
-            let record_packet_size = 16;
-            while record_data.len() >= record_packet_size {
-                let record = decode_record_data_16(&record_data[..record_packet_size]);
-                records.push(record);
-                record_data = &record_data[record_packet_size..];
-            }
+            Ok(decode_records(record_data, 16, decode_record_data_16))
         }
+    }
 
-        Ok(records)
+    /// Attendance records `start..start + count` and the number of records
+    /// on the device, reading only that part of the device's buffer
+    pub async fn get_attendance_page(
+        &mut self,
+        start: usize,
+        count: usize,
+    ) -> Result<(Vec<RawAttendance>, usize), String> {
+        self.free_data().await.ok();
+        let page = self.read_attendance_page(start, count).await;
+        self.free_data().await.ok();
+        page
+    }
+
+    async fn read_attendance_page(
+        &mut self,
+        start: usize,
+        count: usize,
+    ) -> Result<(Vec<RawAttendance>, usize), String> {
+        let data = match self.prepare_data(request_data::GET_ATTENDANCE_LOGS).await? {
+            PreparedData::Inline(data) => data,
+            PreparedData::Buffered(size) => {
+                // Normal response: 16-byte records
+                let record_packet_size = 16;
+                let ((offset, len), total) = record_range(size, record_packet_size, start, count);
+                if len == 0 {
+                    return Ok((vec![], total));
+                }
+                let data = self.read_buffer(offset, len).await?;
+                return Ok((decode_records(&data, record_packet_size, decode_record_data_16), total));
+            }
+        };
+
+        // Small response: 8-byte records, all of them
+        let records = decode_records(data.get(DATA_SIZE_PREFIX..).unwrap_or_default(), 8, decode_record_data_8);
+        let total = records.len();
+        Ok((records.into_iter().skip(start).take(count).collect(), total))
     }
 
     /// Get device info
@@ -334,6 +380,12 @@ impl ZKUdp {
         }
     }
 
+    /// Send a command and return its reply code and payload
+    pub async fn request(&mut self, command: u16, data: &[u8]) -> Result<(u16, Vec<u8>), String> {
+        let reply = self.execute_cmd(command, data).await?;
+        Ok(split_reply(&reply))
+    }
+
     /// Free data buffer
     pub async fn free_data(&mut self) -> Result<(), String> {
         self.execute_cmd(cmd::CMD_FREE_DATA, &[]).await?;
@@ -9,6 +9,7 @@ import {
   UsersPage,
   UserAttendancePage,
   ReportsPage,
+  RosterPage,
   DepartmentsPage,
   SettingsPage,
   NotFoundPage,
@@ -41,6 +42,7 @@ function AppContent() {
             <Route path="users" element={<UsersPage />} />
             <Route path="users/:userId/attendance" element={<UserAttendancePage />} />
             <Route path="reports" element={<ReportsPage />} />
+            <Route path="roster" element={<RosterPage />} />
             <Route path="departments" element={<DepartmentsPage />} />
             <Route path="settings" element={<SettingsPage />} />
             <Route path="*" element={<NotFoundPage />} />
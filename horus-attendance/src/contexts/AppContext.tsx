@@ -5,6 +5,7 @@
 
 import { createContext, useContext, useState, useEffect, useCallback, type ReactNode } from 'react';
 import { initDatabase } from '../lib/database';
+import { getStartupHealth, type StartupHealth } from '../lib/tauri-commands';
 import { getDashboardStats, type DashboardStats } from '../lib/services/dashboard';
 
 interface AppState {
@@ -12,6 +13,7 @@ interface AppState {
   initializing: boolean;
   error: string | null;
   dashboardStats: DashboardStats | null;
+  startupHealth: StartupHealth | null;
 }
 
 interface AppContextValue extends AppState {
@@ -47,6 +49,7 @@ export function AppProvider({ children }: AppProviderProps) {
     initializing: true,
     error: null,
     dashboardStats: null,
+    startupHealth: null,
   });
 
   const [notification, setNotification] = useState<Notification | null>(null);
@@ -54,6 +57,11 @@ export function AppProvider({ children }: AppProviderProps) {
   // Initialize database on mount
   useEffect(() => {
     const init = async () => {
+      // Already run (and any repairs made) by the backend before the window opened
+      const startupHealth = await getStartupHealth().catch((error) => {
+        console.error('Failed to read startup health:', error);
+        return null;
+      });
       try {
         await initDatabase();
         const stats = await getDashboardStats();
@@ -62,14 +70,20 @@ export function AppProvider({ children }: AppProviderProps) {
           initializing: false,
           error: null,
           dashboardStats: stats,
+          startupHealth,
         });
       } catch (error) {
         console.error('Failed to initialize app:', error);
+        // The health check usually knows why (damaged file, newer schema)
+        const problems = startupHealth?.checks.filter(c => c.level === 'error').map(c => c.message) ?? [];
         setState({
           initialized: false,
           initializing: false,
-          error: error instanceof Error ? error.message : 'Failed to initialize application',
+          error: problems.length > 0
+            ? problems.join(' ')
+            : error instanceof Error ? error.message : 'Failed to initialize application',
           dashboardStats: null,
+          startupHealth,
         });
       }
     };
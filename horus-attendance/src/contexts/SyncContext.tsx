@@ -129,6 +129,19 @@ export function SyncProvider({ children }: { children: ReactNode }) {
         setActiveSync(null);
         abortRef.current = null;
 
+        if (result.preview) {
+          // A dry run changed nothing; the result panel shows what it found
+          if (result.success) {
+            showNotification(
+              `Dry run: ${result.preview.newLogs} new logs, ${result.preview.newUsers.length} new users would be added`,
+              'info'
+            );
+          } else {
+            showNotification(`Dry run failed: ${result.errors.join(', ')}`, 'error');
+          }
+          return result;
+        }
+
         // Refresh dashboard after sync
         await refreshDashboard();
 
@@ -1,4 +1,5 @@
 import Database from '@tauri-apps/plugin-sql';
+import { runDataMigrations } from './tauri-commands';
 
 let db: Database | null = null;
 
@@ -21,6 +22,16 @@ export async function initDatabase(): Promise<Database> {
   await db.execute('PRAGMA synchronous = NORMAL');
   // Enable foreign key enforcement (SQLite has it off by default)
   await db.execute('PRAGMA foreign_keys = ON');
+  // Row rewrites that come with an upgrade run after the schema is current.
+  // A failed step is retried next launch; it shouldn't keep the app from opening.
+  try {
+    const report = await runDataMigrations();
+    if (report.failed) {
+      console.error(`[database] Data migration ${report.failed.name} failed:`, report.failed.lastError);
+    }
+  } catch (error) {
+    console.error('[database] Could not run data migrations:', error);
+  }
   return db;
 }
 
@@ -104,20 +115,41 @@ export function yieldToUI(): Promise<void> {
 
 /**
  * Flush all data from the database tables.
- * This deletes all records but keeps the schema intact.
+ * This deletes all records but keeps the schema intact. Locked periods go
+ * first so their triggers don't refuse the punches and summaries after them,
+ * and everything runs in one transaction so a failure leaves nothing half-deleted.
  */
 export async function flushDatabase(): Promise<void> {
   const database = getDatabase();
-  
-  // Delete in order to respect foreign key constraints
-  await database.execute('DELETE FROM attendance_day_summary');
-  await database.execute('DELETE FROM attendance_logs_raw');
-  await database.execute('DELETE FROM users');
-  await database.execute('DELETE FROM departments');
-  await database.execute('DELETE FROM devices');
-  await database.execute('DELETE FROM holidays');
-  await database.execute('DELETE FROM settings');
-  
+
+  await database.execute('BEGIN TRANSACTION');
+  try {
+    // Delete in order to respect foreign key constraints
+    await database.execute('DELETE FROM locked_periods');
+    await database.execute('DELETE FROM punch_corrections');
+    await database.execute('DELETE FROM attendance_day_summary');
+    await database.execute('DELETE FROM summary_history');
+    await database.execute('DELETE FROM log_ledger_entries');
+    await database.execute('DELETE FROM log_ledger');
+    await database.execute('DELETE FROM punch_archive_entries');
+    await database.execute('DELETE FROM attendance_logs_raw');
+    await database.execute('DELETE FROM users');
+    await database.execute('DELETE FROM departments');
+    await database.execute('DELETE FROM devices');
+    await database.execute('DELETE FROM holidays');
+    await database.execute('DELETE FROM settings');
+
+    // The pseudo-devices API punches and corrections are stored under, as the migrations left them
+    await database.execute(
+      `INSERT OR IGNORE INTO devices (id, name, ip, port, sync_mode)
+       VALUES ('api', 'Mobile / API', '', 0, 'manual'), ('correction', 'Corrections', '', 0, 'manual')`
+    );
+    await database.execute('COMMIT');
+  } catch (error) {
+    await database.execute('ROLLBACK');
+    throw error;
+  }
+
   console.log('Database flushed successfully');
 }
 
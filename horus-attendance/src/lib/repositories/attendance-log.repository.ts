@@ -34,6 +34,10 @@ function mapRowToLog(row: AttendanceLogRow): AttendanceLog {
     verifyType: row.verify_type ?? 0,
     punchType: row.punch_type ?? 0,
     rawPayload: row.raw_payload,
+    source: row.source ?? null,
+    latitude: row.latitude ?? null,
+    longitude: row.longitude ?? null,
+    note: row.note ?? null,
     createdAt: row.created_at,
   };
 }
@@ -359,9 +363,37 @@ export async function getLogsForDateRange(
 }
 
 /**
- * Delete logs for a device
+ * Count a device's punches that fall in a locked attendance period
+ */
+export async function countLockedLogsForDevice(deviceId: string): Promise<number> {
+  const rows = await select<Record<string, unknown>>(
+    `SELECT COUNT(*) as count FROM attendance_logs_raw l
+     WHERE l.device_id = ?
+       AND EXISTS (SELECT 1 FROM locked_punch_bounds b
+                   WHERE l.timestamp >= b.start_ts AND l.timestamp < b.end_ts)`,
+    [deviceId]
+  );
+  return (rows[0] as { count: number }).count;
+}
+
+/**
+ * Throw when a device has punches in a locked period, which can't be deleted
+ */
+export async function ensureDeviceLogsUnlocked(deviceId: string): Promise<void> {
+  const locked = await countLockedLogsForDevice(deviceId);
+  if (locked > 0) {
+    throw new Error(
+      `This device has ${locked} punch${locked === 1 ? '' : 'es'} in a locked attendance period. ` +
+        'Unlock the period before deleting them.'
+    );
+  }
+}
+
+/**
+ * Delete logs for a device. Refuses when any of them are in a locked period.
  */
 export async function deleteLogsForDevice(deviceId: string): Promise<number> {
+  await ensureDeviceLogsUnlocked(deviceId);
   const result = await execute(
     'DELETE FROM attendance_logs_raw WHERE device_id = ?',
     [deviceId]
@@ -386,6 +418,7 @@ export const attendanceLogRepository = {
   listLogs,
   getLogsForUserOnDate,
   getLogsForDateRange,
+  countLockedLogsForDevice,
   deleteLogsForDevice,
   getLogCount,
 };
@@ -93,8 +93,9 @@ export async function upsertSummary(summary: {
   await execute(
     `INSERT INTO attendance_day_summary 
      (id, user_id, date, check_in_time, check_out_time, is_incomplete, 
-      late_minutes, early_minutes, status, flags, created_at, updated_at)
-     VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+      late_minutes, early_minutes, status, flags, created_at, updated_at,
+      change_reason, changed_by)
+     VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 'manual', NULL)
      ON CONFLICT(user_id, date) DO UPDATE SET
        check_in_time = excluded.check_in_time,
        check_out_time = excluded.check_out_time,
@@ -103,7 +104,9 @@ export async function upsertSummary(summary: {
        early_minutes = excluded.early_minutes,
        status = excluded.status,
        flags = excluded.flags,
-       updated_at = excluded.updated_at`,
+       updated_at = excluded.updated_at,
+       change_reason = excluded.change_reason,
+       changed_by = excluded.changed_by`,
     [
       id,
       summary.userId,
@@ -158,8 +161,9 @@ export async function upsertSummaryBatch(summaries: Array<{
         await execute(
           `INSERT INTO attendance_day_summary 
            (id, user_id, date, check_in_time, check_out_time, is_incomplete, 
-            late_minutes, early_minutes, status, flags, created_at, updated_at)
-           VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            late_minutes, early_minutes, status, flags, created_at, updated_at,
+            change_reason, changed_by)
+           VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 'backfill', NULL)
            ON CONFLICT(user_id, date) DO UPDATE SET
              check_in_time = excluded.check_in_time,
              check_out_time = excluded.check_out_time,
@@ -168,7 +172,9 @@ export async function upsertSummaryBatch(summaries: Array<{
              early_minutes = excluded.early_minutes,
              status = excluded.status,
              flags = excluded.flags,
-             updated_at = excluded.updated_at`,
+             updated_at = excluded.updated_at,
+             change_reason = excluded.change_reason,
+             changed_by = excluded.changed_by`,
           [
             id,
             summary.userId,
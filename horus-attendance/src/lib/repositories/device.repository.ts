@@ -5,9 +5,15 @@
  */
 
 import { execute, select } from '../database';
-import type { Device, DeviceConfig, DeviceRepository } from '../../types';
+import { ensureDeviceLogsUnlocked } from './attendance-log.repository';
+import type { Device, DeviceConfig, DeviceRepository, DstPolicy, DstRegion } from '../../types';
 import type { DeviceRow } from '../../types/api';
 
+/**
+ * Pseudo-device that punches from the REST API are recorded under
+ */
+export const API_DEVICE_ID = 'api';
+
 /**
  * Generate a unique ID for new devices
  */
@@ -33,8 +39,19 @@ function mapRowToDevice(row: DeviceRow): Device {
     port: row.port,
     commKey: row.comm_key,
     timezone: row.timezone,
+    dstPolicy: (row.dst_policy ?? 'zone') as DstPolicy,
+    dstRules: (row.dst_rules ?? null) as DstRegion | null,
     syncMode: row.sync_mode as 'auto' | 'manual',
+    site: row.site ?? null,
+    syncPriority: row.sync_priority ?? 0,
     lastSyncAt: row.last_sync_at,
+    model: row.model ?? null,
+    serialNumber: row.serial_number ?? null,
+    firmware: row.firmware ?? null,
+    macAddress: row.mac_address ?? null,
+    hardwareSeenAt: row.hardware_seen_at ?? null,
+    lastError: row.last_error ?? null,
+    lastErrorAt: row.last_error_at ?? null,
     createdAt: row.created_at,
     updatedAt: row.updated_at,
   };
@@ -53,11 +70,12 @@ export async function getDeviceById(id: string): Promise<Device | null> {
 }
 
 /**
- * List all devices
+ * List all devices (excluding the API pseudo-device, which can't be synced)
  */
 export async function listDevices(): Promise<Device[]> {
   const rows = await select<Record<string, unknown>>(
-    'SELECT * FROM devices ORDER BY name ASC'
+    'SELECT * FROM devices WHERE id != ? ORDER BY name ASC',
+    [API_DEVICE_ID]
   );
   return rows.map((row) => mapRowToDevice(row as unknown as DeviceRow));
 }
@@ -72,13 +90,17 @@ export async function saveDevice(config: DeviceConfig): Promise<Device> {
   
   // Check if device exists
   const existing = await getDeviceById(id);
+  const dstPolicy = config.dstPolicy ?? existing?.dstPolicy ?? 'zone';
+  const dstRules = dstPolicy === 'rules' ? (config.dstRules ?? existing?.dstRules ?? null) : null;
+  const site = config.site !== undefined ? (config.site?.trim() || null) : (existing?.site ?? null);
+  const syncPriority = config.syncPriority ?? existing?.syncPriority ?? 0;
   
   if (existing) {
     // Update existing device
     await execute(
       `UPDATE devices SET 
         name = ?, ip = ?, port = ?, comm_key = ?, 
-        timezone = ?, sync_mode = ?, updated_at = ?
+        timezone = ?, dst_policy = ?, dst_rules = ?, sync_mode = ?, site = ?, sync_priority = ?, updated_at = ?
        WHERE id = ?`,
       [
         config.name,
@@ -86,7 +108,11 @@ export async function saveDevice(config: DeviceConfig): Promise<Device> {
         config.port,
         config.commKey,
         config.timezone,
+        dstPolicy,
+        dstRules,
         config.syncMode,
+        site,
+        syncPriority,
         timestamp,
         id,
       ]
@@ -94,8 +120,8 @@ export async function saveDevice(config: DeviceConfig): Promise<Device> {
   } else {
     // Insert new device
     await execute(
-      `INSERT INTO devices (id, name, ip, port, comm_key, timezone, sync_mode, created_at, updated_at)
-       VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)`,
+      `INSERT INTO devices (id, name, ip, port, comm_key, timezone, dst_policy, dst_rules, sync_mode, site, sync_priority, created_at, updated_at)
+       VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)`,
       [
         id,
         config.name,
@@ -103,7 +129,11 @@ export async function saveDevice(config: DeviceConfig): Promise<Device> {
         config.port,
         config.commKey,
         config.timezone,
+        dstPolicy,
+        dstRules,
         config.syncMode,
+        site,
+        syncPriority,
         timestamp,
         timestamp,
       ]
@@ -153,10 +183,22 @@ export async function updateDevice(
     fields.push('timezone = ?');
     values.push(updates.timezone);
   }
+  if (updates.dstPolicy !== undefined) {
+    fields.push('dst_policy = ?', 'dst_rules = ?');
+    values.push(updates.dstPolicy, updates.dstPolicy === 'rules' ? (updates.dstRules ?? null) : null);
+  }
   if (updates.syncMode !== undefined) {
     fields.push('sync_mode = ?');
     values.push(updates.syncMode);
   }
+  if (updates.site !== undefined) {
+    fields.push('site = ?');
+    values.push(updates.site?.trim() || null);
+  }
+  if (updates.syncPriority !== undefined) {
+    fields.push('sync_priority = ?');
+    values.push(updates.syncPriority);
+  }
   
   if (fields.length > 0) {
     fields.push('updated_at = ?');
@@ -177,9 +219,11 @@ export async function updateDevice(
 }
 
 /**
- * Delete a device by ID
+ * Delete a device by ID, with its punches. Refuses when any of them are in a
+ * locked period.
  */
 export async function deleteDevice(id: string): Promise<void> {
+  await ensureDeviceLogsUnlocked(id);
   await execute('DELETE FROM devices WHERE id = ?', [id]);
 }
 
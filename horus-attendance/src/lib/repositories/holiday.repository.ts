@@ -12,6 +12,8 @@ interface HolidayRow extends Record<string, unknown> {
   id: string;
   date: string;
   name: string | null;
+  department_id: string | null;
+  site: string | null;
   created_at: string;
 }
 
@@ -20,6 +22,8 @@ function rowToHoliday(row: HolidayRow): Holiday {
     id: row.id,
     date: row.date,
     name: row.name,
+    departmentId: row.department_id,
+    site: row.site,
     createdAt: row.created_at,
   };
 }
@@ -29,7 +33,7 @@ function rowToHoliday(row: HolidayRow): Holiday {
  */
 export async function listHolidays(): Promise<Holiday[]> {
   const rows = await select<HolidayRow>(
-    'SELECT id, date, name, created_at FROM holidays ORDER BY date ASC'
+    'SELECT id, date, name, department_id, site, created_at FROM holidays ORDER BY date ASC'
   );
   return rows.map(rowToHoliday);
 }
@@ -39,7 +43,7 @@ export async function listHolidays(): Promise<Holiday[]> {
  */
 export async function getHoliday(id: string): Promise<Holiday | null> {
   const rows = await select<HolidayRow>(
-    'SELECT id, date, name, created_at FROM holidays WHERE id = ?',
+    'SELECT id, date, name, department_id, site, created_at FROM holidays WHERE id = ?',
     [id]
   );
   return rows.length > 0 ? rowToHoliday(rows[0]!) : null;
@@ -50,7 +54,7 @@ export async function getHoliday(id: string): Promise<Holiday | null> {
  */
 export async function getHolidayByDate(date: string): Promise<Holiday | null> {
   const rows = await select<HolidayRow>(
-    'SELECT id, date, name, created_at FROM holidays WHERE date = ?',
+    'SELECT id, date, name, department_id, site, created_at FROM holidays WHERE date = ?',
     [date]
   );
   return rows.length > 0 ? rowToHoliday(rows[0]!) : null;
@@ -62,9 +66,9 @@ export async function getHolidayByDate(date: string): Promise<Holiday | null> {
 export async function createHoliday(data: CreateHolidayInput): Promise<Holiday> {
   const id = crypto.randomUUID();
   await execute(
-    `INSERT INTO holidays (id, date, name, created_at) 
-     VALUES (?, ?, ?, datetime('now'))`,
-    [id, data.date, data.name || null]
+    `INSERT INTO holidays (id, date, name, department_id, site, created_at) 
+     VALUES (?, ?, ?, ?, ?, datetime('now'))`,
+    [id, data.date, data.name || null, data.departmentId || null, data.site?.trim() || null]
   );
   const holiday = await getHoliday(id);
   if (!holiday) {
@@ -93,6 +97,14 @@ export async function updateHoliday(id: string, data: Partial<CreateHolidayInput
     updates.push('name = ?');
     values.push(data.name || null);
   }
+  if (data.departmentId !== undefined) {
+    updates.push('department_id = ?');
+    values.push(data.departmentId || null);
+  }
+  if (data.site !== undefined) {
+    updates.push('site = ?');
+    values.push(data.site?.trim() || null);
+  }
 
   if (updates.length > 0) {
     values.push(id);
@@ -117,11 +129,11 @@ export async function deleteHoliday(id: string): Promise<void> {
 }
 
 /**
- * Check if a date is a holiday
+ * Check if a date is a holiday for everyone (not limited to a department or site)
  */
 export async function isHoliday(date: string): Promise<boolean> {
   const rows = await select<{ count: number }>(
-    'SELECT COUNT(*) as count FROM holidays WHERE date = ?',
+    'SELECT COUNT(*) as count FROM holidays WHERE date = ? AND department_id IS NULL AND site IS NULL',
     [date]
   );
   return rows.length > 0 && rows[0]!.count > 0;
@@ -132,12 +144,25 @@ export async function isHoliday(date: string): Promise<boolean> {
  */
 export async function getHolidaysInRange(startDate: string, endDate: string): Promise<Holiday[]> {
   const rows = await select<HolidayRow>(
-    'SELECT id, date, name, created_at FROM holidays WHERE date >= ? AND date <= ? ORDER BY date ASC',
+    'SELECT id, date, name, department_id, site, created_at FROM holidays WHERE date >= ? AND date <= ? ORDER BY date ASC',
     [startDate, endDate]
   );
   return rows.map(rowToHoliday);
 }
 
+/**
+ * Whether a user in a department and at a site observes a holiday.
+ * Unset scopes match everyone; setting both limits it to that department at that site.
+ */
+export function holidayApplies(
+  holiday: Pick<Holiday, 'departmentId' | 'site'>,
+  departmentId: string | null | undefined,
+  site: string | null | undefined
+): boolean {
+  return (!holiday.departmentId || holiday.departmentId === departmentId)
+    && (!holiday.site || holiday.site === site);
+}
+
 // Export repository object for consistency with other repositories
 export const holidayRepository = {
   listHolidays,
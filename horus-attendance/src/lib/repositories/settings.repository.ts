@@ -6,7 +6,7 @@
  */
 
 import { execute, select } from '../database';
-import type { AppSettings, AttendanceRules, AppearanceSettings, BackupSettings, ExportSettings, DeviceConfig, TimezoneSettings } from '../../types/models';
+import type { AppSettings, AttendanceRules, AppearanceSettings, BackupSettings, ExportSettings, DeviceConfig, TimezoneSettings, OrganizationSettings, CalendarSettings, AnomalyRules, ApiServerSettings } from '../../types/models';
 
 // Default attendance rules
 export const DEFAULT_ATTENDANCE_RULES: AttendanceRules = {
@@ -19,6 +19,7 @@ export const DEFAULT_ATTENDANCE_RULES: AttendanceRules = {
   checkOutWindowStart: '12:00',
   checkOutWindowEnd: '23:00',
   workdays: [1, 2, 3, 4, 5], // Monday to Friday
+  dayCutoffHour: 0,
 };
 
 // Default appearance settings
@@ -51,6 +52,35 @@ export const DEFAULT_TIMEZONE_SETTINGS: TimezoneSettings = {
   timeFormat: '24h',
 };
 
+export const DEFAULT_ORGANIZATION_SETTINGS: OrganizationSettings = {
+  name: '',
+  logoPath: null,
+};
+
+export const DEFAULT_CALENDAR_SETTINGS: CalendarSettings = {
+  hijriEnabled: false,
+  hijriAdjustment: 0,
+  groupByHijriMonth: false,
+};
+
+export const DEFAULT_ANOMALY_RULES: AnomalyRules = {
+  enabled: true,
+  travelMinutes: 10,
+  maxPunchesPerDay: 20,
+};
+
+export const DEFAULT_API_SERVER_SETTINGS: ApiServerSettings = {
+  enabled: false,
+  bindAddress: '0.0.0.0',
+  port: 8787,
+  rateLimitPerMinute: 120,
+  maxBodyBytes: 64 * 1024,
+  lockoutAfterFailures: 5,
+  lockoutMinutes: 15,
+  fallbackPortCount: 10,
+  fallbackPort: null,
+};
+
 // Default app settings
 export const DEFAULT_APP_SETTINGS: AppSettings = {
   device: null,
@@ -60,6 +90,11 @@ export const DEFAULT_APP_SETTINGS: AppSettings = {
   backup: DEFAULT_BACKUP_SETTINGS,
   export: DEFAULT_EXPORT_SETTINGS,
   timezone: DEFAULT_TIMEZONE_SETTINGS,
+  organization: DEFAULT_ORGANIZATION_SETTINGS,
+  calendar: DEFAULT_CALENDAR_SETTINGS,
+  anomalyRules: DEFAULT_ANOMALY_RULES,
+  allowedDirectories: [],
+  apiServer: DEFAULT_API_SERVER_SETTINGS,
 };
 
 interface SettingsRow extends Record<string, unknown> {
@@ -136,7 +171,7 @@ export async function setTypedSetting<T>(key: string, value: T): Promise<void> {
  * Get full app settings
  */
 export async function getAppSettings(): Promise<AppSettings> {
-  const [device, attendance, holidays, appearance, backup, exportSettings, timezone] = await Promise.all([
+  const [device, attendance, holidays, appearance, backup, exportSettings, timezone, organization, calendar, anomalyRules, allowedDirectories, apiServer] = await Promise.all([
     getTypedSetting<DeviceConfig | null>('device', DEFAULT_APP_SETTINGS.device),
     getTypedSetting<AttendanceRules>('attendance', DEFAULT_APP_SETTINGS.attendance),
     getTypedSetting<string[]>('holidays', DEFAULT_APP_SETTINGS.holidays),
@@ -144,6 +179,11 @@ export async function getAppSettings(): Promise<AppSettings> {
     getTypedSetting<BackupSettings>('backup', DEFAULT_APP_SETTINGS.backup),
     getTypedSetting<ExportSettings>('exportSettings', DEFAULT_APP_SETTINGS.export),
     getTypedSetting<TimezoneSettings>('timezone', DEFAULT_APP_SETTINGS.timezone),
+    getTypedSetting<OrganizationSettings>('organization', DEFAULT_APP_SETTINGS.organization),
+    getTypedSetting<CalendarSettings>('calendar', DEFAULT_APP_SETTINGS.calendar),
+    getTypedSetting<AnomalyRules>('anomalyRules', DEFAULT_APP_SETTINGS.anomalyRules),
+    getTypedSetting<string[]>('allowedDirectories', DEFAULT_APP_SETTINGS.allowedDirectories),
+    getTypedSetting<ApiServerSettings>('apiServer', DEFAULT_APP_SETTINGS.apiServer),
   ]);
 
   return {
@@ -154,6 +194,11 @@ export async function getAppSettings(): Promise<AppSettings> {
     backup,
     export: exportSettings,
     timezone,
+    organization,
+    calendar,
+    anomalyRules,
+    allowedDirectories,
+    apiServer,
   };
 }
 
@@ -184,6 +229,21 @@ export async function updateAppSettings(settings: Partial<AppSettings>): Promise
   if (settings.timezone !== undefined) {
     updates.push(setTypedSetting('timezone', settings.timezone));
   }
+  if (settings.organization !== undefined) {
+    updates.push(setTypedSetting('organization', settings.organization));
+  }
+  if (settings.calendar !== undefined) {
+    updates.push(setTypedSetting('calendar', settings.calendar));
+  }
+  if (settings.anomalyRules !== undefined) {
+    updates.push(setTypedSetting('anomalyRules', settings.anomalyRules));
+  }
+  if (settings.allowedDirectories !== undefined) {
+    updates.push(setTypedSetting('allowedDirectories', settings.allowedDirectories));
+  }
+  if (settings.apiServer !== undefined) {
+    updates.push(setTypedSetting('apiServer', settings.apiServer));
+  }
 
   await Promise.all(updates);
   return getAppSettings();
@@ -201,6 +261,11 @@ export async function resetToDefaults(): Promise<AppSettings> {
     setTypedSetting('backup', DEFAULT_APP_SETTINGS.backup),
     setTypedSetting('exportSettings', DEFAULT_APP_SETTINGS.export),
     setTypedSetting('timezone', DEFAULT_APP_SETTINGS.timezone),
+    setTypedSetting('organization', DEFAULT_APP_SETTINGS.organization),
+    setTypedSetting('calendar', DEFAULT_APP_SETTINGS.calendar),
+    setTypedSetting('anomalyRules', DEFAULT_APP_SETTINGS.anomalyRules),
+    setTypedSetting('allowedDirectories', DEFAULT_APP_SETTINGS.allowedDirectories),
+    setTypedSetting('apiServer', DEFAULT_APP_SETTINGS.apiServer),
   ]);
   return DEFAULT_APP_SETTINGS;
 }
@@ -5,7 +5,7 @@
  */
 
 import { execute, select } from '../database';
-import type { User, CreateUserInput, UpdateUserInput, UserFilter, DeviceUser } from '../../types';
+import type { User, UserClassification, CreateUserInput, UpdateUserInput, UserFilter, DeviceUser } from '../../types';
 import type { UserRow } from '../../types/api';
 
 /**
@@ -32,6 +32,9 @@ function mapRowToUser(row: UserRow): User {
     deviceName: row.device_name,
     displayName: row.display_name,
     departmentId: row.department_id,
+    site: row.site ?? null,
+    classification: (row.classification ?? 'employee') as UserClassification,
+    enrollmentExpiresAt: row.enrollment_expires_at ?? null,
     email: row.email,
     phone: row.phone,
     address: row.address,
@@ -191,6 +194,19 @@ export async function updateUser(id: string, data: UpdateUserInput): Promise<Use
     fields.push('department_id = ?');
     values.push(data.departmentId);
   }
+  if (data.site !== undefined) {
+    fields.push('site = ?');
+    values.push(data.site?.trim() || null);
+  }
+  if (data.classification !== undefined) {
+    fields.push('classification = ?');
+    values.push(data.classification);
+  }
+  if (data.enrollmentExpiresAt !== undefined) {
+    // A new expiry date is carried out again when it passes
+    fields.push('enrollment_expires_at = ?', 'enrollment_removed_at = NULL');
+    values.push(data.enrollmentExpiresAt || null);
+  }
   if (data.email !== undefined) {
     fields.push('email = ?');
     values.push(data.email);
@@ -226,6 +242,9 @@ export async function updateUser(id: string, data: UpdateUserInput): Promise<Use
       values
     );
   }
+  if (data.enrollmentExpiresAt !== undefined) {
+    await execute('DELETE FROM enrollment_removals WHERE user_id = ?', [id]);
+  }
   
   const user = await getUserById(id);
   if (!user) {
@@ -273,6 +292,20 @@ export async function getUnlinkedDeviceUsers(): Promise<DeviceUser[]> {
   }));
 }
 
+/**
+ * Device user IDs from further terminals linked to existing profiles
+ * (besides each profile's own device_user_id)
+ */
+export async function listDeviceUserLinks(): Promise<{ deviceUserId: string; userId: string }[]> {
+  const rows = await select<Record<string, unknown>>(
+    'SELECT device_user_id, user_id FROM user_device_links'
+  );
+  return rows.map((row) => ({
+    deviceUserId: (row as { device_user_id: string }).device_user_id,
+    userId: (row as { user_id: string }).user_id,
+  }));
+}
+
 /**
  * Delete a user
  */
@@ -299,6 +332,7 @@ export const userRepository = {
   updateUser,
   linkDeviceUser,
   getUnlinkedDeviceUsers,
+  listDeviceUserLinks,
   deleteUser,
   getActiveUserCount,
 };
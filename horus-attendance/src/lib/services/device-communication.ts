@@ -6,8 +6,9 @@
  */
 
 import type { DeviceConfig, DeviceInfo } from '../../types/models';
-import type { ConnectionTestResult, SidecarUser, SidecarAttendanceLog, SidecarSyncOptions } from './sidecar-client';
+import type { ConnectionTestResult, ConnectivityReport, ConnectivityStep, SidecarUser, SidecarAttendanceLog, SidecarSyncOptions, ScanHost, NetworkSettings, NetworkChangeResult, DeviceMessage, NewDeviceMessage, DevicePushResult, DeviceMessageResult, Bell, DeviceWriteResult, DeviceOptionValue, InitialImportOptions, InitialImportStatus, DeviceAuditOptions, DeviceAuditReport, SyncWindow, SyncWindowCheck, DeviceLogOffset, IncrementalSync, DeviceCapabilities } from './sidecar-client';
 import { SidecarClient } from './sidecar-client';
+import type { SyncPreview } from '../../types/services';
 
 // Error codes for device communication
 export const DeviceErrorCodes = {
@@ -16,6 +17,7 @@ export const DeviceErrorCodes = {
   UNREACHABLE: 'DEVICE_UNREACHABLE',
   PROTOCOL_ERROR: 'DEVICE_PROTOCOL_ERROR',
   SIDECAR_NOT_READY: 'SIDECAR_NOT_READY',
+  DEVICE_BUSY: 'DEVICE_BUSY',
 } as const;
 
 export type DeviceErrorCode = typeof DeviceErrorCodes[keyof typeof DeviceErrorCodes];
@@ -32,6 +34,10 @@ export interface DeviceError {
 function parseErrorCode(message: string): DeviceErrorCode {
   const lowerMessage = message.toLowerCase();
   
+  // Checked first: busy errors quote device replies that look like other failures
+  if (lowerMessage.includes('device busy')) {
+    return DeviceErrorCodes.DEVICE_BUSY;
+  }
   if (lowerMessage.includes('timeout') || lowerMessage.includes('etimedout')) {
     return DeviceErrorCodes.CONNECTION_TIMEOUT;
   }
@@ -60,6 +66,7 @@ function createDeviceError(message: string): DeviceError {
     [DeviceErrorCodes.UNREACHABLE]: 'Device is unreachable. Please check the network connection and IP address.',
     [DeviceErrorCodes.PROTOCOL_ERROR]: 'Communication error with the device. Please try again.',
     [DeviceErrorCodes.SIDECAR_NOT_READY]: 'Device communication service is not ready. Please restart the application.',
+    [DeviceErrorCodes.DEVICE_BUSY]: 'The device menu is open on the terminal. Close the menu on the device and try again.',
   };
   
   return {
@@ -168,7 +175,7 @@ export class DeviceCommunicationService {
   async syncAll(
     config: DeviceConfig,
     options?: SidecarSyncOptions
-  ): Promise<{ users: SidecarUser[]; logs: SidecarAttendanceLog[] }> {
+  ): Promise<{ users: SidecarUser[]; logs: SidecarAttendanceLog[]; quarantined?: number; preview?: SyncPreview; transport?: 'tcp' | 'udp' | 'demo'; incremental?: IncrementalSync }> {
     try {
       return await this.sidecarClient.syncAll(config, options);
     } catch (error) {
@@ -178,6 +185,229 @@ export class DeviceCommunicationService {
       throw new Error(message);
     }
   }
+
+  /**
+   * Check ping, the TCP and UDP ports, the ARP table, authentication and a
+   * free-sizes read one at a time, to show which step a connection fails at
+   */
+  async runConnectivityDiagnostics(config: DeviceConfig): Promise<ConnectivityReport> {
+    return await this.sidecarClient.runConnectivityDiagnostics(config);
+  }
+
+  /**
+   * Probe a subnet (e.g. "192.168.1.0/24") for devices when discovery
+   * broadcasts are blocked by the network
+   */
+  async scanIpRange(cidr: string, port?: number, timeoutMs?: number): Promise<ScanHost[]> {
+    return await this.sidecarClient.scanIpRange(cidr, port, timeoutMs);
+  }
+
+  /**
+   * Get the device's IP address, netmask, gateway and DHCP flag
+   */
+  async getNetworkSettings(config: DeviceConfig): Promise<NetworkSettings> {
+    try {
+      return await this.sidecarClient.getNetworkSettings(config);
+    } catch (error) {
+      const message = error instanceof Error ? error.message : String(error);
+      const deviceError = createDeviceError(message);
+      throw new Error(deviceError.message);
+    }
+  }
+
+  /**
+   * Change the device's network settings. The device restarts and is
+   * reconnected at its new address; `confirm` must be set once the user has
+   * agreed to that.
+   */
+  async setNetworkSettings(
+    config: DeviceConfig,
+    settings: NetworkSettings,
+    confirm: boolean
+  ): Promise<NetworkChangeResult> {
+    return await this.sidecarClient.setNetworkSettings(config, settings, confirm);
+  }
+
+  /**
+   * Messages stored in the app, newest first
+   */
+  async listMessages(): Promise<DeviceMessage[]> {
+    return await this.sidecarClient.listMessages();
+  }
+
+  /**
+   * Save a message shown at punch time and push it to the given devices
+   */
+  async createMessage(message: NewDeviceMessage, devices: DeviceConfig[]): Promise<DeviceMessageResult> {
+    return await this.sidecarClient.createMessage(message, devices);
+  }
+
+  /**
+   * Change who sees a message (everyone when `userIds` is empty) on the given devices
+   */
+  async assignMessage(messageId: number, userIds: string[], devices: DeviceConfig[]): Promise<DeviceMessageResult> {
+    return await this.sidecarClient.assignMessage(messageId, userIds, devices);
+  }
+
+  /**
+   * Send a stored message to devices again, e.g. a newly added terminal
+   */
+  async pushMessage(messageId: number, devices: DeviceConfig[]): Promise<DeviceMessageResult> {
+    return await this.sidecarClient.pushMessage(messageId, devices);
+  }
+
+  /**
+   * Remove a message from the given devices and the app
+   */
+  async deleteMessage(messageId: number, devices: DeviceConfig[]): Promise<DeviceMessageResult> {
+    return await this.sidecarClient.deleteMessage(messageId, devices);
+  }
+
+  /**
+   * Get the bell schedule stored on a device
+   */
+  async getBells(config: DeviceConfig): Promise<Bell[]> {
+    try {
+      return await this.sidecarClient.getBells(config);
+    } catch (error) {
+      const message = error instanceof Error ? error.message : String(error);
+      const deviceError = createDeviceError(message);
+      throw new Error(deviceError.message);
+    }
+  }
+
+  /**
+   * Replace the bell schedule on a device; slots not listed are turned off
+   */
+  async setBells(config: DeviceConfig, bells: Bell[]): Promise<void> {
+    await this.sidecarClient.setBells(config, bells);
+  }
+
+  /**
+   * The bell schedule configured in the app
+   */
+  async getBellSchedule(): Promise<Bell[]> {
+    return await this.sidecarClient.getBellSchedule();
+  }
+
+  /**
+   * Save the app's bell schedule; devices keep theirs until it is pushed
+   */
+  async saveBellSchedule(bells: Bell[]): Promise<void> {
+    await this.sidecarClient.saveBellSchedule(bells);
+  }
+
+  /**
+   * Write the app's bell schedule to each device
+   */
+  async pushBellSchedule(devices: DeviceConfig[]): Promise<DeviceWriteResult[]> {
+    return await this.sidecarClient.pushBellSchedule(devices);
+  }
+
+  /**
+   * Get the device settings the app manages (volume, match thresholds, 1:1 mode)
+   */
+  async getDeviceOptions(config: DeviceConfig): Promise<DeviceOptionValue[]> {
+    try {
+      return await this.sidecarClient.getDeviceOptions(config);
+    } catch (error) {
+      const message = error instanceof Error ? error.message : String(error);
+      const deviceError = createDeviceError(message);
+      throw new Error(deviceError.message);
+    }
+  }
+
+  /**
+   * Change device settings by key; returns the values read back afterwards
+   */
+  async setDeviceOptions(config: DeviceConfig, values: Record<string, string>): Promise<DeviceOptionValue[]> {
+    return await this.sidecarClient.setDeviceOptions(config, values);
+  }
+
+  /**
+   * Set the device clock to now, following the device's DST policy; returns
+   * the time written
+   */
+  async setDeviceTime(config: DeviceConfig): Promise<string> {
+    return await this.sidecarClient.setDeviceTime(config);
+  }
+
+  /**
+   * Import a saved device's whole attendance history in bounded passes,
+   * resuming an interrupted import. Listen with onInitialImportProgress for
+   * progress, and recompute summaries over the imported range afterwards.
+   */
+  async initialImport(config: DeviceConfig, options?: InitialImportOptions): Promise<InitialImportStatus> {
+    return await this.sidecarClient.initialImport(config, options);
+  }
+
+  /**
+   * Progress of a device's initial import, or null if none was started
+   */
+  async getInitialImportStatus(deviceId: string): Promise<InitialImportStatus | null> {
+    return await this.sidecarClient.getInitialImportStatus(deviceId);
+  }
+
+  /**
+   * Compare a saved device's records with the database without syncing, for
+   * spot audits: the most recent records by default, or all with `full`
+   */
+  async compareDeviceWithDb(deviceId: string, options?: DeviceAuditOptions): Promise<DeviceAuditReport> {
+    return await this.sidecarClient.compareDeviceWithDb(deviceId, options);
+  }
+
+  /**
+   * Times of day a device may be synced unattended; empty means any time
+   */
+  async getSyncWindows(deviceId: string): Promise<SyncWindow[]> {
+    return await this.sidecarClient.getSyncWindows(deviceId);
+  }
+
+  /**
+   * Replace a device's sync windows; an empty list lifts the restriction
+   */
+  async setSyncWindows(deviceId: string, windows: SyncWindow[]): Promise<SyncWindow[]> {
+    return await this.sidecarClient.setSyncWindows(deviceId, windows);
+  }
+
+  /**
+   * Whether a device may be synced now: scheduled syncs only inside its
+   * windows, manual ones at any time with a warning
+   */
+  async checkSyncWindow(deviceId: string, scheduled = false): Promise<SyncWindowCheck> {
+    return await this.sidecarClient.checkSyncWindow(deviceId, scheduled);
+  }
+
+  /**
+   * Save how far an incremental sync read the device, once the logs it
+   * returned are stored
+   */
+  async saveSyncOffset(deviceId: string, offset: DeviceLogOffset): Promise<boolean> {
+    return await this.sidecarClient.saveSyncOffset(deviceId, offset);
+  }
+
+  /**
+   * Forget the device's sync offset, so the next incremental sync reads
+   * every record again
+   */
+  async resetSyncOffset(deviceId: string): Promise<boolean> {
+    return await this.sidecarClient.resetSyncOffset(deviceId);
+  }
+
+  /**
+   * Probe what a device's firmware supports again, e.g. after a change the
+   * firmware version doesn't show. Saved devices are probed on first contact.
+   */
+  async probeCapabilities(config: DeviceConfig): Promise<DeviceCapabilities> {
+    return await this.sidecarClient.probeCapabilities(config);
+  }
+
+  /**
+   * What a saved device was found to support; null until it is probed
+   */
+  async getCapabilities(deviceId: string): Promise<DeviceCapabilities | null> {
+    return await this.sidecarClient.getCapabilities(deviceId);
+  }
 }
 
 // Export singleton instance
@@ -191,4 +421,4 @@ export function getDeviceCommunicationService(): DeviceCommunicationService {
 }
 
 // Re-export types
-export type { ConnectionTestResult, SidecarUser, SidecarAttendanceLog, SidecarSyncOptions };
+export type { ConnectionTestResult, ConnectivityReport, ConnectivityStep, SidecarUser, SidecarAttendanceLog, SidecarSyncOptions, ScanHost, NetworkSettings, NetworkChangeResult, DeviceMessage, NewDeviceMessage, DevicePushResult, DeviceMessageResult, Bell, DeviceWriteResult, DeviceOptionValue, InitialImportOptions, InitialImportStatus };
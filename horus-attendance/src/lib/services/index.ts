@@ -6,6 +6,7 @@ export {
   RuleEngine,
   DEFAULT_ATTENDANCE_RULES,
   processDay,
+  hoursOnly,
   calculateLateMinutes,
   calculateEarlyMinutes,
   isWorkday,
@@ -15,6 +16,11 @@ export {
   extractTimeFromTimestamp,
   extractDateFromTimestamp,
   isTimeInWindow,
+  MAX_DAY_CUTOFF_HOUR,
+  getDayCutoffHour,
+  workingDateFromTimestamp,
+  workingDayBounds,
+  dayMinutes,
 } from './rule-engine';
 
 export type { HolidayChecker } from './rule-engine';
@@ -110,6 +110,25 @@ describe('Report Generator - Helper Functions', () => {
         { numRuns: 100 }
       );
     });
+
+    it('returns the configured first day of the week, at most 6 days before', () => {
+      fc.assert(
+        fc.property(
+          fc.date({ min: new Date('2024-01-01'), max: new Date('2025-12-31') }),
+          fc.integer({ min: 0, max: 6 }),
+          (date, firstDayOfWeek) => {
+            const dateStr = formatDate(date);
+            const weekStart = getWeekStart(dateStr, firstDayOfWeek);
+            const start = new Date(weekStart);
+            expect(start.getDay()).toBe(firstDayOfWeek);
+            const daysBefore = (new Date(dateStr).getTime() - start.getTime()) / (24 * 60 * 60 * 1000);
+            expect(Math.round(daysBefore)).toBeGreaterThanOrEqual(0);
+            expect(Math.round(daysBefore)).toBeLessThanOrEqual(6);
+          }
+        ),
+        { numRuns: 100 }
+      );
+    });
   });
 
   describe('getWeekDates', () => {
@@ -143,6 +162,17 @@ describe('Report Generator - Helper Functions', () => {
         { numRuns: 100 }
       );
     });
+
+    it('runs custom-start periods from the start day of the previous month', () => {
+      const dates = getMonthDates(2026, 10, 26);
+      expect(dates[0]).toBe('2026-09-26');
+      expect(dates[dates.length - 1]).toBe('2026-10-25');
+      expect(dates.length).toBe(30);
+
+      const january = getMonthDates(2026, 1, 26);
+      expect(january[0]).toBe('2025-12-26');
+      expect(january[january.length - 1]).toBe('2026-01-25');
+    });
   });
 });
 
@@ -20,17 +20,20 @@ import type {
 import { DEFAULT_ATTENDANCE_RULES, isWorkday } from './rule-engine';
 
 /**
- * Get the Monday of the week containing the given date
+ * Get the first day of the week containing the given date
+ * @param firstDayOfWeek - 0 (Sunday) to 6 (Saturday); Monday by default
  */
-export function getWeekStart(date: string): string {
+export function getWeekStart(date: string, firstDayOfWeek: number = 1): string {
   const d = new Date(date);
-  const day = d.getDay();
-  // Adjust to Monday (day 1). If Sunday (0), go back 6 days
-  const diff = day === 0 ? -6 : 1 - day;
-  d.setDate(d.getDate() + diff);
+  // Days since the week started, e.g. 6 for a Sunday in a Monday-first week
+  const diff = (d.getDay() - firstDayOfWeek + 7) % 7;
+  d.setDate(d.getDate() - diff);
   return formatDate(d);
 }
 
+/** Short weekday names, Sunday first as Date.getDay() counts */
+const WEEKDAY_NAMES = ['Sun', 'Mon', 'Tue', 'Wed', 'Thu', 'Fri', 'Sat'];
+
 /**
  * Format a Date object to YYYY-MM-DD string
  */
@@ -42,7 +45,7 @@ export function formatDate(date: Date): string {
 }
 
 /**
- * Get all dates in a week starting from Monday
+ * Get all seven dates of a week from its first day
  */
 export function getWeekDates(weekStart: string): string[] {
   const dates: string[] = [];
@@ -57,9 +60,20 @@ export function getWeekDates(weekStart: string): string[] {
 
 /**
  * Get all dates in a month
+ * @param startDay - Day the month's period starts on; with e.g. 26 the
+ *   period runs from the 26th of the previous month to the 25th of this one
  */
-export function getMonthDates(year: number, month: number): string[] {
+export function getMonthDates(year: number, month: number, startDay: number = 1): string[] {
   const dates: string[] = [];
+  if (startDay > 1) {
+    const d = new Date(year, month - 2, startDay);
+    const end = new Date(year, month - 1, startDay - 1);
+    while (d <= end) {
+      dates.push(formatDate(d));
+      d.setDate(d.getDate() + 1);
+    }
+    return dates;
+  }
   const daysInMonth = new Date(year, month, 0).getDate();
   for (let day = 1; day <= daysInMonth; day++) {
     const d = new Date(year, month - 1, day);
@@ -133,7 +147,7 @@ export function calculateWeeklySummary(
       continue;
     }
 
-    if (day.status === 'present' || day.status === 'late' || day.status === 'early_leave') {
+    if (day.status === 'present' || day.status === 'late' || day.status === 'early_leave' || day.status === 'remote') {
       daysPresent++;
     } else if (day.status === 'absent') {
       daysAbsent++;
@@ -179,7 +193,7 @@ export function calculateMonthlySummary(
 
     totalWorkingDays++;
 
-    if (day.status === 'present' || day.status === 'late' || day.status === 'early_leave') {
+    if (day.status === 'present' || day.status === 'late' || day.status === 'early_leave' || day.status === 'remote') {
       daysPresent++;
     } else if (day.status === 'absent') {
       daysAbsent++;
@@ -218,6 +232,7 @@ export class ReportGenerator {
   private userFetcher: (filter?: ReportFilter) => Promise<User[]>;
   private summaryFetcher: (userId: string, startDate: string, endDate: string) => Promise<DailySummary[]>;
   private holidayChecker: (date: string) => boolean;
+  private monthStartDay = 1;
 
   constructor(
     userFetcher: (filter?: ReportFilter) => Promise<User[]>,
@@ -246,7 +261,14 @@ export class ReportGenerator {
   }
 
   /**
-   * Generate weekly report with Mon-Sun columns
+   * Set the day monthly periods start on (1 for calendar months)
+   */
+  setMonthStartDay(day: number): void {
+    this.monthStartDay = day;
+  }
+
+  /**
+   * Generate weekly report with a column per day from the week's first day
    * Requirement 7.1: Display table with users as rows and Mon-Sun as columns
    * Requirement 7.4: Filter by department
    * Requirement 7.5: Calculate weekly totals
@@ -263,7 +285,7 @@ export class ReportGenerator {
       ? users.filter(u => filter.userIds!.includes(u.id))
       : users;
 
-    // Get week dates (Mon-Sun)
+    // Get week dates
     const weekDates = getWeekDates(weekStart);
     const endDate = weekDates[weekDates.length - 1]!;
 
@@ -319,8 +341,8 @@ export class ReportGenerator {
       ? users.filter(u => filter.userIds!.includes(u.id))
       : users;
 
-    // Get month dates
-    const monthDates = getMonthDates(year, month);
+    // Get the month's period dates
+    const monthDates = getMonthDates(year, month, this.monthStartDay);
     const startDate = monthDates[0]!;
     const endDate = monthDates[monthDates.length - 1]!;
 
@@ -390,7 +412,7 @@ export function exportWeeklyReportToCSV(report: WeeklyReportRow[]): string {
   }
 
   const weekDates = report[0]!.days.map(d => d.date);
-  const dayNames = ['Mon', 'Tue', 'Wed', 'Thu', 'Fri', 'Sat', 'Sun'];
+  const dayNames = weekDates.map(date => WEEKDAY_NAMES[new Date(`${date}T00:00:00`).getDay()] ?? '');
 
   // Each day gets two columns: In and Out
   const headers = [
@@ -611,6 +633,7 @@ function getCheckInFill(checkIn: string | null, status: string, settings: Export
   const c = settings.colors ?? DEFAULT_EXPORT_SETTINGS.colors;
   if (status === 'weekend') return makeFill(c.weekend);
   if (status === 'holiday') return makeFill(c.weekend);
+  if (status === 'remote' && !checkIn) return undefined;
   if (status === 'absent' || (!checkIn && status !== 'weekend' && status !== 'holiday')) {
     return makeFill(c.absent);
   }
@@ -648,7 +671,7 @@ export async function exportWeeklyReportToExcel(
   if (report.length === 0) return new Uint8Array(await wb.xlsx.writeBuffer());
 
   const weekDates = report[0]!.days.map(d => d.date);
-  const dayNames = ['Mon', 'Tue', 'Wed', 'Thu', 'Fri', 'Sat', 'Sun'];
+  const dayNames = weekDates.map(date => WEEKDAY_NAMES[new Date(`${date}T00:00:00`).getDay()] ?? '');
 
   // Headers
   const headers = [
@@ -16,6 +16,8 @@ import {
   filterPunchesInWindow,
   deriveAttendanceStatus,
   isWorkday,
+  workingDateFromTimestamp,
+  workingDayBounds,
 } from './rule-engine';
 import type { PunchRecord, AttendanceRules } from '../../types';
 
@@ -864,3 +866,56 @@ describe('Rule Engine - Status Derivation and Holiday Exclusion', () => {
     });
   });
 });
+
+describe('Rule Engine - Working Day Cutoff', () => {
+  const nightRules: AttendanceRules = {
+    ...DEFAULT_ATTENDANCE_RULES,
+    workStartTime: '20:00',
+    workEndTime: '04:00',
+    checkInWindowStart: '16:00',
+    checkInWindowEnd: '23:59',
+    checkOutWindowStart: '00:00',
+    checkOutWindowEnd: '04:59',
+    dayCutoffHour: 5,
+  };
+
+  it('assigns punches before the cutoff to the previous day', () => {
+    expect(workingDateFromTimestamp('2024-03-01T04:59:00.000Z', 5)).toBe('2024-02-29');
+    expect(workingDateFromTimestamp('2024-03-01T05:00:00.000Z', 5)).toBe('2024-03-01');
+    expect(workingDateFromTimestamp('2024-03-01T00:30:00.000Z', 0)).toBe('2024-03-01');
+  });
+
+  it('widens query bounds to the following morning', () => {
+    expect(workingDayBounds('2024-03-01', '2024-03-31', 5)).toEqual([
+      '2024-03-01T05:00:00',
+      '2024-04-01T05:00:00',
+    ]);
+    expect(workingDayBounds('2024-03-01', '2024-03-31', 0)).toEqual([
+      '2024-03-01T00:00:00',
+      '2024-04-01T00:00:00',
+    ]);
+  });
+
+  it('keeps punches up to the cutoff inside the bounds', () => {
+    const [start, end] = workingDayBounds('2024-03-01', '2024-03-01', 5);
+    const inside = (timestamp: string) => timestamp >= start && timestamp < end;
+    expect(inside('2024-03-01T05:00:00.000Z')).toBe(true);
+    expect(inside('2024-03-02T04:59:59.999Z')).toBe(true);
+    expect(inside('2024-03-02T05:00:00.000Z')).toBe(false);
+    expect(inside('2024-03-01T04:59:59.000Z')).toBe(false);
+  });
+
+  it('treats a shift crossing midnight as one day', () => {
+    const punches = [
+      createPunchRecord('1', '2024-03-04T20:10:00.000Z'),
+      createPunchRecord('1', '2024-03-05T03:30:00.000Z'),
+    ];
+    const summary = processDay('user-1', '2024-03-04', punches, nightRules);
+
+    expect(summary.checkInTime).toBe('20:10');
+    expect(summary.checkOutTime).toBe('03:30');
+    expect(summary.isIncomplete).toBe(false);
+    expect(summary.lateMinutes).toBe(0);
+    expect(summary.earlyMinutes).toBe(15);
+  });
+});
@@ -25,8 +25,14 @@ export const DEFAULT_ATTENDANCE_RULES: AttendanceRules = {
   checkOutWindowStart: '12:00',
   checkOutWindowEnd: '23:59',
   workdays: [1, 2, 3, 4, 5], // Monday to Friday
+  dayCutoffHour: 0,
 };
 
+/**
+ * Latest working-day cutoff hour; a later one would split ordinary day shifts
+ */
+export const MAX_DAY_CUTOFF_HOUR = 12;
+
 /**
  * Generate a unique ID
  */
@@ -74,17 +80,79 @@ export function extractDateFromTimestamp(timestamp: string): string {
   return `${year}-${month}-${day}`;
 }
 
+/**
+ * Hour the working day starts at, kept within range (0 = calendar midnight)
+ */
+export function getDayCutoffHour(rules: AttendanceRules): number {
+  const hour = Math.floor(rules.dayCutoffHour ?? 0);
+  return Math.min(Math.max(hour, 0), MAX_DAY_CUTOFF_HOUR);
+}
+
+/**
+ * Working day (YYYY-MM-DD) a stored timestamp belongs to.
+ * Punches before the cutoff hour count toward the previous day.
+ */
+export function workingDateFromTimestamp(timestamp: string, cutoffHour: number = 0): string {
+  const date = timestamp.slice(0, 10);
+  const hour = Number(timestamp.slice(11, 13));
+  if (!(hour < cutoffHour)) {
+    return date;
+  }
+  const previous = new Date(`${date}T00:00:00Z`);
+  previous.setUTCDate(previous.getUTCDate() - 1);
+  return Number.isNaN(previous.getTime()) ? date : previous.toISOString().slice(0, 10);
+}
+
+/**
+ * Half-open timestamp bounds covering the working days from startDate to endDate
+ * (inclusive), as `timestamp >= start AND timestamp < end`: from the cutoff on
+ * the first day to the cutoff on the day after the last, like locked_punch_bounds
+ */
+export function workingDayBounds(
+  startDate: string,
+  endDate: string,
+  cutoffHour: number = 0
+): [string, string] {
+  const next = new Date(`${endDate}T00:00:00Z`);
+  next.setUTCDate(next.getUTCDate() + 1);
+  const nextDate = next.toISOString().slice(0, 10);
+  const hour = Math.max(cutoffHour, 0).toString().padStart(2, '0');
+  return [`${startDate}T${hour}:00:00`, `${nextDate}T${hour}:00:00`];
+}
+
+/**
+ * Minutes of a time (HH:mm) since the midnight the working day started after,
+ * so times before the cutoff hour sort after the evening
+ */
+export function dayMinutes(time: string, rules: AttendanceRules): number {
+  const minutes = parseTimeToMinutes(time);
+  return minutes < getDayCutoffHour(rules) * 60 ? minutes + 24 * 60 : minutes;
+}
+
+/**
+ * Minutes that split check-in punches from check-out punches: midday for
+ * calendar days, otherwise halfway through the working hours
+ */
+function splitMinutes(rules: AttendanceRules): number {
+  if (getDayCutoffHour(rules) === 0) {
+    return parseTimeToMinutes('12:00');
+  }
+  return Math.floor((dayMinutes(rules.workStartTime, rules) + dayMinutes(rules.workEndTime, rules)) / 2);
+}
+
 /**
  * Check if a time is within a window
  */
 export function isTimeInWindow(
   time: string,
   windowStart: string,
-  windowEnd: string
+  windowEnd: string,
+  rules?: AttendanceRules
 ): boolean {
-  const timeMinutes = parseTimeToMinutes(time);
-  const startMinutes = parseTimeToMinutes(windowStart);
-  const endMinutes = parseTimeToMinutes(windowEnd);
+  const toMinutes = (t: string) => (rules ? dayMinutes(t, rules) : parseTimeToMinutes(t));
+  const timeMinutes = toMinutes(time);
+  const startMinutes = toMinutes(windowStart);
+  const endMinutes = toMinutes(windowEnd);
   
   return timeMinutes >= startMinutes && timeMinutes <= endMinutes;
 }
@@ -99,16 +167,14 @@ export function filterPunchesInWindow(
 ): PunchRecord[] {
   return punches.filter(punch => {
     const time = extractTimeFromTimestamp(punch.timestamp);
-    const timeMinutes = parseTimeToMinutes(time);
-    const midday = parseTimeToMinutes('12:00');
     
     // Determine if this is likely a check-in or check-out based on time
-    if (timeMinutes < midday) {
+    if (dayMinutes(time, rules) < splitMinutes(rules)) {
       // Morning punch - check against check-in window
-      return isTimeInWindow(time, rules.checkInWindowStart, rules.checkInWindowEnd);
+      return isTimeInWindow(time, rules.checkInWindowStart, rules.checkInWindowEnd, rules);
     } else {
       // Afternoon/evening punch - check against check-out window
-      return isTimeInWindow(time, rules.checkOutWindowStart, rules.checkOutWindowEnd);
+      return isTimeInWindow(time, rules.checkOutWindowStart, rules.checkOutWindowEnd, rules);
     }
   });
 }
@@ -121,8 +187,8 @@ export function calculateLateMinutes(
   checkInTime: string,
   rules: AttendanceRules
 ): number {
-  const checkInMinutes = parseTimeToMinutes(checkInTime);
-  const workStartMinutes = parseTimeToMinutes(rules.workStartTime);
+  const checkInMinutes = dayMinutes(checkInTime, rules);
+  const workStartMinutes = dayMinutes(rules.workStartTime, rules);
   const graceEndMinutes = workStartMinutes + rules.lateGracePeriod;
   
   if (checkInMinutes <= graceEndMinutes) {
@@ -140,8 +206,8 @@ export function calculateEarlyMinutes(
   checkOutTime: string,
   rules: AttendanceRules
 ): number {
-  const checkOutMinutes = parseTimeToMinutes(checkOutTime);
-  const workEndMinutes = parseTimeToMinutes(rules.workEndTime);
+  const checkOutMinutes = dayMinutes(checkOutTime, rules);
+  const workEndMinutes = dayMinutes(rules.workEndTime, rules);
   const graceStartMinutes = workEndMinutes - rules.earlyLeaveGracePeriod;
   
   if (checkOutMinutes >= graceStartMinutes) {
@@ -262,8 +328,6 @@ export function processDay(
   if (sortedPunches.length === 1) {
     const singlePunch = sortedPunches[0]!;
     const punchTime = extractTimeFromTimestamp(singlePunch.timestamp);
-    const punchMinutes = parseTimeToMinutes(punchTime);
-    const midday = parseTimeToMinutes('12:00');
     
     // Use time-of-day logic to determine if it's check-in or check-out
     let checkInTime: string | null = null;
@@ -271,7 +335,7 @@ export function processDay(
     let lateMinutes = 0;
     let earlyMinutes = 0;
     
-    if (punchMinutes < midday) {
+    if (dayMinutes(punchTime, rules) < splitMinutes(rules)) {
       // Morning punch - treat as check-in
       checkInTime = punchTime;
       lateMinutes = calculateLateMinutes(punchTime, rules);
@@ -329,6 +393,15 @@ export function processDay(
   };
 }
 
+/**
+ * A day of someone tracked for hours only (contractors): punches and
+ * incompleteness stand, lateness and early leave don't
+ */
+export function hoursOnly(summary: DailySummary): DailySummary {
+  const status = summary.status === 'late' || summary.status === 'early_leave' ? 'present' : summary.status;
+  return { ...summary, lateMinutes: 0, earlyMinutes: 0, status };
+}
+
 /**
  * RuleEngine class implementation
  */
@@ -7,9 +7,11 @@
 
 import { invoke } from '@tauri-apps/api/core';
 import type { DeviceConfig, DeviceInfo } from '../../types/models';
+import type { SyncPreview } from '../../types/services';
 
 // Types for device communication
 interface SidecarDeviceConfig {
+  deviceId?: string | undefined;
   ip: string;
   port: number;
   commKey?: string | undefined;
@@ -30,9 +32,247 @@ interface SidecarAttendanceLog {
 }
 
 interface SidecarSyncOptions {
-  mode: 'all' | 'range';
+  /** 'incremental' reads only the records added since the saved sync offset */
+  mode: 'all' | 'range' | 'incremental';
   startDate?: string | undefined;
   endDate?: string | undefined;
+  /** Also report what syncing would store, in `preview` */
+  dryRun?: boolean | undefined;
+}
+
+interface ScanHost {
+  ip: string;
+  port: number;
+  latency: number;
+  isDevice: boolean;
+  deviceInfo?: DeviceInfo | undefined;
+  error?: string | undefined;
+}
+
+/** One check of a connectivity diagnosis */
+interface ConnectivityStep {
+  id: 'ping' | 'tcp_port' | 'arp' | 'udp' | 'auth' | 'free_sizes';
+  label: string;
+  /** Ping and ARP only ever warn: networks may block ICMP, and routed devices aren't in the ARP table */
+  status: 'passed' | 'warning' | 'failed' | 'skipped';
+  detail?: string | undefined;
+  /** What to try next, for warnings and failures */
+  suggestion?: string | undefined;
+  durationMs: number;
+}
+
+interface ConnectivityReport {
+  ip: string;
+  port: number;
+  success: boolean;
+  transport?: 'tcp' | 'udp' | 'demo' | undefined;
+  steps: ConnectivityStep[];
+  /** The suggestion of the deepest failed step, or that all is well */
+  summary: string;
+}
+
+interface NetworkSettings {
+  ip: string;
+  netmask: string;
+  gateway: string;
+  dhcp: boolean;
+}
+
+interface NetworkChangeResult {
+  previous: NetworkSettings;
+  applied: NetworkSettings;
+  reconnected: boolean;
+  address: string | null;
+  message: string;
+}
+
+interface DeviceMessage {
+  id: number;
+  content: string;
+  scope: 'all' | 'users';
+  startAt: string;
+  validMinutes: number;
+  userIds: string[];
+  createdAt: string;
+  updatedAt: string;
+}
+
+interface NewDeviceMessage {
+  content: string;
+  /** Device-local YYYY-MM-DDTHH:MM:SS; defaults to now */
+  startAt?: string | undefined;
+  /** 0 keeps the message until it is deleted */
+  validMinutes?: number | undefined;
+  /** Only these users see it; everyone when empty */
+  userIds?: string[] | undefined;
+}
+
+interface DevicePushResult {
+  deviceId: string | null;
+  ip: string;
+  success: boolean;
+  error: string | null;
+  skippedUsers: string[];
+}
+
+interface DeviceMessageResult {
+  message: DeviceMessage | null;
+  devices: DevicePushResult[];
+}
+
+interface Bell {
+  /** Position on the device, from 1 */
+  slot: number;
+  /** HH:MM, device-local */
+  time: string;
+  /** 0 = Sunday through 6 = Saturday */
+  days: number[];
+  durationSecs: number;
+  enabled: boolean;
+}
+
+interface DeviceWriteResult {
+  deviceId: string | null;
+  ip: string;
+  success: boolean;
+  error: string | null;
+}
+
+interface DeviceOptionValue {
+  key: string;
+  label: string;
+  /** On/off ('0'/'1') rather than a number between min and max */
+  boolean: boolean;
+  min: number;
+  max: number;
+  /** False when this model doesn't have the setting */
+  supported: boolean;
+  value: string | null;
+}
+
+interface InitialImportOptions {
+  /** Records read per pass (default 5000) */
+  chunkSize?: number | undefined;
+  /** Pause between passes in milliseconds (default 1000) */
+  pauseMs?: number | undefined;
+  /** Start again from the first record instead of resuming */
+  restart?: boolean | undefined;
+}
+
+interface InitialImportStatus {
+  deviceId: string;
+  status: 'running' | 'interrupted' | 'completed';
+  /** Position of the next record to read */
+  nextRecord: number;
+  /** Records on the device when last read */
+  totalRecords: number;
+  imported: number;
+  /** Records that were already stored */
+  duplicates: number;
+  chunkSize: number;
+  /** Earliest and latest punch imported, to recompute summaries over */
+  firstTimestamp: string | null;
+  lastTimestamp: string | null;
+  lastError: string | null;
+  startedAt: string;
+  updatedAt: string;
+  completedAt: string | null;
+  /** Records that failed validation */
+  quarantined: number;
+}
+
+interface DeviceAuditOptions {
+  /** Compare only this many of the most recent records (default 1000) */
+  sampleSize?: number | undefined;
+  /** Compare every record on the device */
+  full?: boolean | undefined;
+}
+
+interface AuditRecord {
+  deviceUserId: string;
+  timestamp: string;
+}
+
+interface AuditDayDigest {
+  date: string;
+  deviceRecords: number;
+  dbRecords: number;
+  deviceHash: string;
+  dbHash: string;
+}
+
+interface DeviceAuditReport {
+  deviceId: string;
+  checkedAt: string;
+  full: boolean;
+  deviceUserCount: number;
+  deviceLogCount: number;
+  /** Every record stored for the device */
+  dbLogCount: number;
+  /** Device users with no profile and no link to one */
+  unknownUsers: number;
+  /** Device records compared, and the period they cover */
+  comparedRecords: number;
+  firstTimestamp: string | null;
+  lastTimestamp: string | null;
+  /** Device records failing validation, which sync quarantines */
+  invalidRecords: number;
+  dbRecordsInPeriod: number;
+  matched: number;
+  missingFromDb: number;
+  missingFromDevice: number;
+  missingFromDbSample: AuditRecord[];
+  missingFromDeviceSample: AuditRecord[];
+  mismatchedDays: AuditDayDigest[];
+  consistent: boolean;
+}
+
+/** A time of day a device may be synced unattended (HH:MM, local) */
+interface SyncWindow {
+  startTime: string;
+  /** Before startTime when the window runs past midnight */
+  endTime: string;
+}
+
+interface SyncWindowCheck {
+  deviceId: string;
+  /** Now is inside one of the device's windows, or it has none */
+  open: boolean;
+  /** The window is open or the sync is manual */
+  allowed: boolean;
+  nextOpenAt: string | null;
+  /** Why a scheduled sync is refused, or what a manual one is overriding */
+  message: string | null;
+}
+
+/** How far a device's attendance buffer has been read */
+interface DeviceLogOffset {
+  recordCount: number;
+  lastDeviceUserId: string | null;
+  /** As the device stores it, before clock correction */
+  lastTimestamp: string | null;
+}
+
+/** How an incremental sync read the attendance buffer */
+interface IncrementalSync {
+  /** The whole buffer was read: no offset yet, or the device log was cleared or rewritten */
+  full: boolean;
+  reason: string | null;
+  /** Records before the offset that weren't transferred */
+  skippedRecords: number;
+  /** Save with saveSyncOffset once the logs are stored */
+  offset: DeviceLogOffset;
+}
+
+/** What a device's firmware was found to support */
+interface DeviceCapabilities {
+  /** Feature flags; a feature that isn't listed wasn't probed and is tried anyway */
+  features: Partial<Record<'options' | 'pagedAttendance' | 'bells' | 'fingerprint' | 'face' | 'photos' | 'utf8Names', boolean>>;
+  /** Longest user ID the device takes */
+  userIdWidth: number | null;
+  /** Firmware version the probe ran against; a different one is probed again */
+  firmware: string | null;
+  probedAt: string;
 }
 
 interface ConnectionTestResult {
@@ -47,6 +287,7 @@ interface ConnectionTestResult {
  */
 function toDeviceConfig(config: DeviceConfig): SidecarDeviceConfig {
   return {
+    deviceId: config.id || undefined,
     ip: config.ip,
     port: config.port,
     commKey: config.commKey || undefined,
@@ -118,13 +359,169 @@ export class SidecarClient {
   async syncAll(
     config: DeviceConfig,
     options?: SidecarSyncOptions
-  ): Promise<{ users: SidecarUser[]; logs: SidecarAttendanceLog[] }> {
-    return await invoke<{ users: SidecarUser[]; logs: SidecarAttendanceLog[] }>('sync_device_all', {
+  ): Promise<{ users: SidecarUser[]; logs: SidecarAttendanceLog[]; quarantined?: number; preview?: SyncPreview; transport?: 'tcp' | 'udp' | 'demo'; incremental?: IncrementalSync }> {
+    return await invoke<{ users: SidecarUser[]; logs: SidecarAttendanceLog[]; quarantined?: number; preview?: SyncPreview; transport?: 'tcp' | 'udp' | 'demo'; incremental?: IncrementalSync }>('sync_device_all', {
       config: toDeviceConfig(config),
       options: options ?? null,
     });
   }
 
+  async runConnectivityDiagnostics(config: DeviceConfig): Promise<ConnectivityReport> {
+    return await invoke<ConnectivityReport>('run_connectivity_diagnostics', {
+      config: toDeviceConfig(config),
+    });
+  }
+
+  async scanIpRange(cidr: string, port?: number, timeoutMs?: number): Promise<ScanHost[]> {
+    return await invoke<ScanHost[]>('scan_ip_range', {
+      cidr,
+      port: port ?? null,
+      timeoutMs: timeoutMs ?? null,
+    });
+  }
+
+  async getNetworkSettings(config: DeviceConfig): Promise<NetworkSettings> {
+    return await invoke<NetworkSettings>('get_device_network', {
+      config: toDeviceConfig(config),
+    });
+  }
+
+  async setNetworkSettings(
+    config: DeviceConfig,
+    settings: NetworkSettings,
+    confirm: boolean
+  ): Promise<NetworkChangeResult> {
+    return await invoke<NetworkChangeResult>('set_device_network', {
+      config: toDeviceConfig(config),
+      settings,
+      confirm,
+    });
+  }
+
+  async listMessages(): Promise<DeviceMessage[]> {
+    return await invoke<DeviceMessage[]>('list_device_messages');
+  }
+
+  async createMessage(message: NewDeviceMessage, devices: DeviceConfig[]): Promise<DeviceMessageResult> {
+    return await invoke<DeviceMessageResult>('create_device_message', {
+      message,
+      devices: devices.map(toDeviceConfig),
+    });
+  }
+
+  async assignMessage(messageId: number, userIds: string[], devices: DeviceConfig[]): Promise<DeviceMessageResult> {
+    return await invoke<DeviceMessageResult>('assign_device_message', {
+      messageId,
+      userIds,
+      devices: devices.map(toDeviceConfig),
+    });
+  }
+
+  async pushMessage(messageId: number, devices: DeviceConfig[]): Promise<DeviceMessageResult> {
+    return await invoke<DeviceMessageResult>('push_device_message', {
+      messageId,
+      devices: devices.map(toDeviceConfig),
+    });
+  }
+
+  async deleteMessage(messageId: number, devices: DeviceConfig[]): Promise<DeviceMessageResult> {
+    return await invoke<DeviceMessageResult>('delete_device_message', {
+      messageId,
+      devices: devices.map(toDeviceConfig),
+    });
+  }
+
+  async getBells(config: DeviceConfig): Promise<Bell[]> {
+    return await invoke<Bell[]>('get_device_bells', {
+      config: toDeviceConfig(config),
+    });
+  }
+
+  async setBells(config: DeviceConfig, bells: Bell[]): Promise<void> {
+    await invoke('set_device_bells', {
+      config: toDeviceConfig(config),
+      bells,
+    });
+  }
+
+  async getBellSchedule(): Promise<Bell[]> {
+    return await invoke<Bell[]>('get_bell_schedule');
+  }
+
+  async saveBellSchedule(bells: Bell[]): Promise<void> {
+    await invoke('save_bell_schedule', { bells });
+  }
+
+  async pushBellSchedule(devices: DeviceConfig[]): Promise<DeviceWriteResult[]> {
+    return await invoke<DeviceWriteResult[]>('push_bell_schedule', {
+      devices: devices.map(toDeviceConfig),
+    });
+  }
+
+  async getDeviceOptions(config: DeviceConfig): Promise<DeviceOptionValue[]> {
+    return await invoke<DeviceOptionValue[]>('get_device_options', {
+      config: toDeviceConfig(config),
+    });
+  }
+
+  async setDeviceOptions(config: DeviceConfig, values: Record<string, string>): Promise<DeviceOptionValue[]> {
+    return await invoke<DeviceOptionValue[]>('set_device_options', {
+      config: toDeviceConfig(config),
+      values,
+    });
+  }
+
+  async setDeviceTime(config: DeviceConfig): Promise<string> {
+    return await invoke<string>('set_device_time', {
+      config: toDeviceConfig(config),
+    });
+  }
+
+  async initialImport(config: DeviceConfig, options?: InitialImportOptions): Promise<InitialImportStatus> {
+    return await invoke<InitialImportStatus>('initial_import', {
+      config: toDeviceConfig(config),
+      options,
+    });
+  }
+
+  async getInitialImportStatus(deviceId: string): Promise<InitialImportStatus | null> {
+    return await invoke<InitialImportStatus | null>('get_initial_import_status', { deviceId });
+  }
+
+  async compareDeviceWithDb(deviceId: string, options?: DeviceAuditOptions): Promise<DeviceAuditReport> {
+    return await invoke<DeviceAuditReport>('compare_device_with_db', { deviceId, options });
+  }
+
+  async getSyncWindows(deviceId: string): Promise<SyncWindow[]> {
+    return await invoke<SyncWindow[]>('get_sync_windows', { deviceId });
+  }
+
+  async setSyncWindows(deviceId: string, windows: SyncWindow[]): Promise<SyncWindow[]> {
+    return await invoke<SyncWindow[]>('set_sync_windows', { deviceId, windows });
+  }
+
+  async checkSyncWindow(deviceId: string, scheduled: boolean): Promise<SyncWindowCheck> {
+    return await invoke<SyncWindowCheck>('check_sync_window', { deviceId, scheduled });
+  }
+
+  async saveSyncOffset(deviceId: string, offset: DeviceLogOffset): Promise<boolean> {
+    return await invoke<boolean>('save_sync_offset', { deviceId, offset });
+  }
+
+  async resetSyncOffset(deviceId: string): Promise<boolean> {
+    return await invoke<boolean>('reset_sync_offset', { deviceId });
+  }
+
+  async probeCapabilities(config: DeviceConfig): Promise<DeviceCapabilities> {
+    return await invoke<DeviceCapabilities>('probe_device_capabilities', {
+      config: toDeviceConfig(config),
+    });
+  }
+
+  async getCapabilities(deviceId: string): Promise<DeviceCapabilities | null> {
+    return await invoke<DeviceCapabilities | null>('get_device_capabilities', { deviceId });
+  }
+
   async disconnect(): Promise<void> {
     this.isConnected = false;
   }
@@ -136,4 +533,27 @@ export type {
   SidecarAttendanceLog,
   SidecarSyncOptions,
   ConnectionTestResult,
+  ConnectivityStep,
+  ConnectivityReport,
+  ScanHost,
+  NetworkSettings,
+  NetworkChangeResult,
+  DeviceMessage,
+  NewDeviceMessage,
+  DevicePushResult,
+  DeviceMessageResult,
+  Bell,
+  DeviceWriteResult,
+  DeviceOptionValue,
+  InitialImportOptions,
+  InitialImportStatus,
+  DeviceAuditOptions,
+  AuditRecord,
+  AuditDayDigest,
+  DeviceAuditReport,
+  SyncWindow,
+  SyncWindowCheck,
+  DeviceLogOffset,
+  IncrementalSync,
+  DeviceCapabilities,
 };
@@ -8,13 +8,22 @@
 
 import { execute, select, yieldToUI } from '../database';
 import { getDeviceCommunicationService, type DeviceError } from './device-communication';
+import type { DeviceLogOffset, SidecarSyncOptions } from './sidecar-client';
 import { getDeviceById, updateLastSyncAt } from '../repositories/device.repository';
-import { createUser, listUsers } from '../repositories/user.repository';
+import { listDeviceUserLinks, listUsers } from '../repositories/user.repository';
 import { insertLogs, getLatestLogTimestamp } from '../repositories/attendance-log.repository';
-import { processDay, DEFAULT_ATTENDANCE_RULES } from './rule-engine';
+import {
+  processDay,
+  hoursOnly,
+  DEFAULT_ATTENDANCE_RULES,
+  getDayCutoffHour,
+  workingDateFromTimestamp,
+  workingDayBounds,
+} from './rule-engine';
 import { settingsRepository } from '../repositories/settings.repository';
-import { holidayRepository } from '../repositories/holiday.repository';
-import type { DeviceConfig, DeviceInfo, PunchRecord, CreateUserInput } from '../../types/models';
+import { holidayApplies, holidayRepository } from '../repositories/holiday.repository';
+import { beginActivity, endActivity, mergeDeviceUsers, onDeviceBusy, recordSync, sealLedger } from '../tauri-commands';
+import type { DeviceConfig, DeviceInfo, Holiday, PunchRecord } from '../../types/models';
 import type { 
   SyncOptions, 
   SyncResult, 
@@ -240,11 +249,16 @@ export class SyncEngine {
     };
 
     state.isSyncing = true;
+    // Backups and restores wait for this to finish; one in progress fails the sync below
+    let activityId: number | null = null;
     const errors: string[] = [];
+    const warnings: string[] = [];
     let usersAdded = 0;
     let usersSynced = 0;
+    let usersRenamed = 0;
     let logsAdded = 0;
     let logsDeduplicated = 0;
+    let quarantined = 0;
 
     // Shared details object updated throughout the sync
     const details: NonNullable<SyncProgress['details']> = {
@@ -258,8 +272,60 @@ export class SyncEngine {
       summariesProcessed: 0,
     };
 
+    // Recorded in the sync history when the sync finishes
+    const startedMs = Date.now();
+    const durationsMs: Record<string, number> = {};
+    let phaseStartedMs = startedMs;
+    let transport: 'tcp' | 'udp' | 'demo' | null = null;
+    let fallback = false;
+    // Saved once the logs an incremental read returned are stored
+    let offsetToSave: DeviceLogOffset | null = null;
+    let logsFetched = 0;
+    let summaryDates = 0;
+
+    /** Helper: note how long the phase ending now took */
+    const endPhase = (phase: string) => {
+      const now = Date.now();
+      durationsMs[phase] = now - phaseStartedMs;
+      phaseStartedMs = now;
+    };
+
+    /** Helper: record the sync's structured result, then return it */
+    const finish = async (result: SyncResult): Promise<SyncResult> => {
+      durationsMs.total = Date.now() - startedMs;
+      await recordSync({
+        deviceId,
+        startedAt: details.startedAt,
+        finishedAt: new Date().toISOString(),
+        success: result.success,
+        dryRun: options.dryRun ?? false,
+        scheduled: options.scheduled ?? false,
+        mode: options.mode,
+        transport,
+        fallback,
+        counts: {
+          usersFetched: details.usersTotal,
+          logsFetched,
+          logsInRange: details.logsTotal,
+          usersAdded: result.usersAdded,
+          usersSynced: result.usersSynced,
+          logsAdded: result.logsAdded,
+          logsDeduplicated: result.logsDeduplicated,
+          quarantined: result.quarantined ?? 0,
+          summaryDates,
+        },
+        durationsMs,
+        warnings: result.warnings ?? [],
+        errors: result.errors,
+      }).catch((error) => {
+        console.warn('[SyncEngine] Failed to record sync history:', error);
+      });
+      return result;
+    };
+
     try {
       checkAbort();
+      activityId = await beginActivity('Device sync');
 
       // Get device configuration
       const device = await getDeviceById(deviceId);
@@ -277,15 +343,30 @@ export class SyncEngine {
         syncMode: device.syncMode,
       };
 
+      // Reading the whole buffer slows the terminal down, so unattended syncs
+      // keep to the device's sync windows; manual ones only get a warning
+      const window = await this.deviceCommunication.checkSyncWindow(deviceId, options.scheduled ?? false);
+      if (!window.allowed) {
+        const next = window.nextOpenAt ? ` (next window opens ${window.nextOpenAt})` : '';
+        throw new Error(`${window.message ?? 'Outside the device sync windows'}${next}`);
+      }
+      if (window.message) {
+        console.warn(`[SyncEngine] ${window.message}`);
+        warnings.push(window.message);
+      }
+
       // ── Phase 1: Connect & fetch ──────────────────────────────────────
+      endPhase('prepare');
       updateProgress(deviceId, 'connecting', 0, 100, 'Connecting to device...', progressCallback, details);
 
       const logSyncOptions = toAttendanceLogSyncOptions(options);
 
       // The ZKTeco protocol always returns ALL records from the device regardless
       // of any date filter we send. Filtering must happen client-side after fetch.
-      // We always request mode:'all' from the device and filter ourselves.
-      const sidecarOptions = { mode: 'all' as const };
+      // "Latest" syncs read only the records added since the device's saved
+      // offset (the backend reads everything when it can't); other modes
+      // request mode:'all' and filter ourselves.
+      const sidecarOptions: SidecarSyncOptions = { mode: logSyncOptions.mode === 'latest' ? 'incremental' : 'all' };
 
       // Determine the client-side filter window based on sync mode.
       // filterStartDate / filterEndDate define which records we actually keep.
@@ -310,6 +391,41 @@ export class SyncEngine {
         console.log(`[SyncEngine] Range mode: filter ${filterStartDate} to ${filterEndDate}`);
       }
 
+      if (options.dryRun) {
+        // Nothing is stored: the backend compares what it fetched with the database
+        checkAbort();
+        updateProgress(deviceId, 'fetching', 5, 100, 'Fetching data from device (dry run)...', progressCallback, details);
+        const { users, logs, preview, ...fetched } = await this.deviceCommunication.syncAll(config, {
+          mode: filterStartDate ? 'range' : 'all',
+          startDate: filterStartDate ?? undefined,
+          endDate: filterStartDate ? (filterEndDate ?? '9999-12-31') : undefined,
+          dryRun: true,
+        });
+        quarantined = fetched.quarantined ?? 0;
+        transport = fetched.transport ?? null;
+        logsFetched = logs.length;
+        endPhase('fetch');
+        details.usersTotal = users.length;
+        details.logsTotal = logs.length;
+        details.totalRecordsFetched = users.length + logs.length;
+        updateProgress(deviceId, 'complete', 100, 100, 'Dry run complete, nothing was stored', progressCallback, details);
+        if (!preview) {
+          errors.push('The device backend did not report a dry-run preview');
+        }
+        return await finish({
+          success: errors.length === 0,
+          usersAdded: 0,
+          usersSynced: 0,
+          logsAdded: 0,
+          logsDeduplicated: 0,
+          errors,
+          syncedAt: new Date().toISOString(),
+          ...(quarantined > 0 ? { quarantined } : {}),
+          ...(preview ? { preview } : {}),
+          ...(warnings.length > 0 ? { warnings } : {}),
+        });
+      }
+
       let deviceUsers: { deviceUserId: string; deviceName: string }[] = [];
       let deviceLogs: { deviceUserId: string; timestamp: string; verifyType: number; punchType: number; userName?: string | null }[] = [];
 
@@ -318,19 +434,44 @@ export class SyncEngine {
         updateProgress(deviceId, 'fetching', 5, 100, 'Fetching data from device...', progressCallback, details);
 
         // Try combined sync first; fall back to separate fetches on failure.
+        // The backend retries itself while the device menu is open.
         let syncError: string | null = null;
+        const stopBusyUpdates = await onDeviceBusy((busy) => {
+          if (busy.ip !== config.ip) return;
+          updateProgress(
+            deviceId, 'fetching', 5, 100,
+            `Device menu is open on the terminal. Retrying in ${busy.retryInSecs}s (attempt ${busy.attempt} of ${busy.maxAttempts})...`,
+            progressCallback, details
+          );
+        }).catch(() => null);
         try {
           const syncResult = await this.deviceCommunication.syncAll(config, sidecarOptions);
           deviceUsers = syncResult.users;
           deviceLogs = syncResult.logs;
+          quarantined = syncResult.quarantined ?? 0;
+          transport = syncResult.transport ?? null;
+          if (syncResult.incremental) {
+            const { full, reason, skippedRecords, offset } = syncResult.incremental;
+            console.log(full
+              ? `[SyncEngine] Read all records: ${reason ?? 'no usable offset'}`
+              : `[SyncEngine] Incremental read: skipped ${skippedRecords} records already synced`);
+            offsetToSave = offset;
+          }
         } catch (combinedError) {
           syncError = combinedError instanceof Error ? combinedError.message : String(combinedError);
+          if (syncError.toLowerCase().includes('device busy')) {
+            // Separate fetches would hit the same open menu
+            throw new Error('The device menu is open on the terminal. Close the menu on the device and sync again.');
+          }
           console.warn(`[SyncEngine] Combined sync failed: ${syncError}. Trying separate fetches...`);
           updateProgress(deviceId, 'fetching', 7, 100, 'Retrying with separate fetches...', progressCallback, details);
+        } finally {
+          stopBusyUpdates?.();
         }
 
         // Fallback: fetch users and logs in separate calls
         if (syncError && !abortSignal?.aborted) {
+          fallback = true;
           try {
             deviceUsers = await this.deviceCommunication.getUsers(config);
             console.log(`[SyncEngine] Separate fetch: got ${deviceUsers.length} users`);
@@ -349,6 +490,7 @@ export class SyncEngine {
         }
 
         const totalFetched = deviceLogs.length;
+        logsFetched = totalFetched;
 
         // ── Client-side date filter ──
         // Apply the date window BEFORE any DB work so we never waste time on
@@ -379,6 +521,7 @@ export class SyncEngine {
         const errMsg = error instanceof Error ? error.message : String(error);
         errors.push(`Device sync error: ${errMsg}`);
       }
+      endPhase('fetch');
 
       // ── Phase 2: Process users ────────────────────────────────────────
       checkAbort();
@@ -387,59 +530,26 @@ export class SyncEngine {
       
       let syncedAt = new Date().toISOString();
 
-      const existingUsers = await listUsers({ status: 'all' });
-      const existingDeviceUserIds = new Set(
-        existingUsers
-          .filter(u => u.deviceUserId)
-          .map(u => u.deviceUserId as string)
-      );
-
-      const totalNewUsers = deviceUsers.length;
-      for (let i = 0; i < totalNewUsers; i++) {
-        if (i % 10 === 0) checkAbort();
-
-        const deviceUser = deviceUsers[i];
-        if (!deviceUser) continue;
-        
-        const userData: CreateUserInput = {
-          deviceUserId: deviceUser.deviceUserId,
-          deviceName: deviceUser.deviceName,
-          displayName: deviceUser.deviceName || `User ${deviceUser.deviceUserId}`,
-          status: 'active',
-        };
-
-        if (existingDeviceUserIds.has(deviceUser.deviceUserId)) {
-          usersSynced++;
-          details.usersProcessed = usersAdded + usersSynced;
-          continue;
-        }
-
-        try {
-          await createUser(userData);
-          usersAdded++;
-        } catch (error) {
-          const errMsg = error instanceof Error ? error.message : String(error);
-          if (!errMsg.includes('UNIQUE constraint')) {
-            errors.push(`Failed to create user ${userData.displayName}: ${errMsg}`);
-          } else {
-            usersSynced++;
-          }
-        }
-        
-        details.usersProcessed = usersAdded + usersSynced;
-
-        // Yield every 10 users so the UI can re-render and other CRUD works
-        if (i % 10 === 0) {
-          await yieldToUI();
-          updateProgress(
-            deviceId, 'users',
-            20 + Math.floor(((i + 1) / totalNewUsers) * 10),
-            100,
-            `Users: ${details.usersProcessed} / ${totalNewUsers}`,
-            progressCallback, details
-          );
+      // The backend adds new device users and refreshes device names only,
+      // so names and details edited in the app survive every sync
+      try {
+        const merge = await mergeDeviceUsers(
+          deviceId,
+          deviceUsers.map(u => ({ deviceUserId: u.deviceUserId, deviceName: u.deviceName }))
+        );
+        usersAdded = merge.added;
+        usersSynced = merge.deviceUsers - merge.added;
+        usersRenamed = merge.renamed;
+        if (merge.changes.length > 0) {
+          console.log(`[SyncEngine] User merge: ${merge.added} added, ${merge.renamed} device names refreshed`);
         }
+      } catch (error) {
+        const errMsg = error instanceof Error ? error.message : String(error);
+        errors.push(`Failed to merge device users: ${errMsg}`);
       }
+      details.usersProcessed = usersAdded + usersSynced;
+
+      endPhase('users');
 
       // ── Phase 3: Insert attendance logs ───────────────────────────────
       checkAbort();
@@ -471,8 +581,16 @@ export class SyncEngine {
           console.warn('[SyncEngine] Could not query latest timestamp, inserting all records');
         }
 
+        // Summaries are per working day, which may start after midnight
+        let cutoffHour = 0;
+        try {
+          cutoffHour = getDayCutoffHour((await settingsRepository.getAppSettings()).attendance);
+        } catch (err) {
+          console.warn('[SyncEngine] Could not load day cutoff hour, using calendar days');
+        }
+
         const logsToInsert = logsToProcess.map(log => {
-          const date = log.timestamp.split('T')[0];
+          const date = workingDateFromTimestamp(log.timestamp, cutoffHour);
           if (date) syncedDates.add(date);
           return {
             deviceId: config.id,
@@ -506,6 +624,8 @@ export class SyncEngine {
             const errMsg = error instanceof Error ? error.message : String(error);
             console.error('[SyncEngine] Failed to insert logs:', errMsg);
             errors.push(`Failed to insert logs: ${errMsg}`);
+            // Read these records again next time
+            offsetToSave = null;
           }
         } else {
           console.log(`[SyncEngine] All ${deviceLogs.length} records already in DB, nothing to insert`);
@@ -513,6 +633,19 @@ export class SyncEngine {
         }
       }
 
+      if (offsetToSave) {
+        try {
+          await this.deviceCommunication.saveSyncOffset(deviceId, offsetToSave);
+        } catch (error) {
+          const errMsg = error instanceof Error ? error.message : String(error);
+          console.warn('[SyncEngine] Failed to save sync offset:', errMsg);
+          warnings.push(`Failed to save sync offset, the next sync reads every record: ${errMsg}`);
+        }
+      }
+
+      endPhase('logs');
+      summaryDates = syncedDates.size;
+
       // ── Phase 4: Generate daily summaries ─────────────────────────────
       // ONLY regenerate summaries if we actually fetched and inserted new data.
       // When the device fetch fails, syncedDates is empty and there's nothing
@@ -538,6 +671,8 @@ export class SyncEngine {
         updateProgress(deviceId, 'processing', 90, 100, 'No new data to summarize', progressCallback, details);
       }
 
+      endPhase('summaries');
+
       // Update last sync timestamp
       syncedAt = new Date().toISOString();
       try {
@@ -550,6 +685,17 @@ export class SyncEngine {
         errors.push(`Failed to update sync timestamp: ${errMsg}`);
       }
 
+      // Seal the new punches into the ledger now rather than on its next run (no-op while disabled)
+      if (logsAdded > 0) {
+        try {
+          await sealLedger();
+        } catch (error) {
+          const errMsg = error instanceof Error ? error.message : String(error);
+          console.warn('[SyncEngine] Failed to seal ledger:', errMsg);
+          warnings.push(`Failed to seal ledger: ${errMsg}`);
+        }
+      }
+
       // ── Phase 5: Complete ─────────────────────────────────────────────
       updateProgress(deviceId, 'complete', 100, 100, 'Sync complete!', progressCallback, details);
 
@@ -559,7 +705,7 @@ export class SyncEngine {
         console.log(`[SyncEngine] Sync completed successfully: ${logsAdded} logs, ${usersAdded} users added`);
       }
 
-      return {
+      return await finish({
         success: errors.length === 0,
         usersAdded,
         usersSynced,
@@ -567,13 +713,16 @@ export class SyncEngine {
         logsDeduplicated,
         errors,
         syncedAt,
-      };
+        ...(usersRenamed > 0 ? { usersRenamed } : {}),
+        ...(quarantined > 0 ? { quarantined } : {}),
+        ...(warnings.length > 0 ? { warnings } : {}),
+      });
 
     } catch (error) {
       const errorMessage = error instanceof Error ? error.message : String(error);
       errors.push(errorMessage);
 
-      return {
+      return await finish({
         success: false,
         usersAdded,
         usersSynced,
@@ -581,10 +730,16 @@ export class SyncEngine {
         logsDeduplicated,
         errors,
         syncedAt: new Date().toISOString(),
-      };
+        ...(warnings.length > 0 ? { warnings } : {}),
+      });
 
     } finally {
       state.isSyncing = false;
+      if (activityId !== null) {
+        await endActivity(activityId).catch((error) => {
+          console.warn('[SyncEngine] Failed to end sync activity:', error);
+        });
+      }
     }
   }
 
@@ -592,7 +747,8 @@ export class SyncEngine {
    * Generate daily summaries from raw attendance logs
    * 
    * Matching strategy (in order of priority):
-   * 1. Exact match: log.device_user_id === user.device_user_id
+   * 1. Exact match: log.device_user_id === user.device_user_id, or a device
+   *    user ID linked to the user
    * 2. Name match: log.device_user_id === user.device_name (case-insensitive)
    * 3. Name match: log.device_user_id === user.display_name (case-insensitive)
    * 
@@ -638,6 +794,15 @@ export class SyncEngine {
       }
     }
     
+    // Device user IDs linked to a profile, unless some profile has the ID as its own
+    const usersById = new Map(users.map(u => [u.id, u]));
+    for (const link of await listDeviceUserLinks()) {
+      const user = usersById.get(link.userId);
+      if (user && !byDeviceUserId.has(link.deviceUserId)) {
+        byDeviceUserId.set(link.deviceUserId, user);
+      }
+    }
+    
     console.log(`[SyncEngine] Built lookup maps: ${byDeviceUserId.size} by ID, ${byDeviceName.size} by device name, ${byDisplayName.size} by display name`);
 
     /**
@@ -668,17 +833,80 @@ export class SyncEngine {
       console.log('[SyncEngine] Using default attendance rules');
     }
 
-    // Get holidays
-    let holidays: Set<string> = new Set();
+    // Get holidays, which may only apply to some departments or sites
+    const holidays = new Map<string, Holiday[]>();
     try {
       const holidayList = await holidayRepository.listHolidays();
-      holidays = new Set(holidayList.map(h => h.date));
+      for (const holiday of holidayList) {
+        holidays.set(holiday.date, [...(holidays.get(holiday.date) ?? []), holiday]);
+      }
     } catch (error) {
       console.log('[SyncEngine] Could not load holidays');
     }
 
+    // Punches before the cutoff hour belong to the previous working day
+    const cutoffHour = getDayCutoffHour(rules);
+
+    // Users with their own shift are judged against its hours
+    const shiftRules = new Map<string, typeof rules>();
+    try {
+      const shifts = await select<{ user_id: string; start_time: string; end_time: string }>(
+        'SELECT user_id, start_time, end_time FROM user_shifts'
+      );
+      for (const shift of shifts) {
+        shiftRules.set(shift.user_id, { ...rules, workStartTime: shift.start_time, workEndTime: shift.end_time });
+      }
+    } catch (error) {
+      console.log('[SyncEngine] Could not load user shifts');
+    }
+
+    // Rostered days override both
+    const rosterRules = new Map<string, typeof rules>();
+    try {
+      let rosterQuery = 'SELECT user_id, date, start_time, end_time FROM roster_entries';
+      const rosterParams: unknown[] = [];
+      if (datesToProcess && datesToProcess.length > 0) {
+        const sortedDates = [...datesToProcess].sort();
+        rosterQuery += ' WHERE date >= ? AND date <= ?';
+        rosterParams.push(sortedDates[0], sortedDates[sortedDates.length - 1]);
+      }
+      const entries = await select<{ user_id: string; date: string; start_time: string; end_time: string }>(
+        rosterQuery,
+        rosterParams
+      );
+      for (const entry of entries) {
+        rosterRules.set(`${entry.user_id}|${entry.date}`, {
+          ...rules,
+          workStartTime: entry.start_time,
+          workEndTime: entry.end_time,
+        });
+      }
+    } catch (error) {
+      console.log('[SyncEngine] Could not load roster');
+    }
+
+    // Days marked as remote work keep their remote summaries
+    const remoteDays = new Set<string>();
+    try {
+      let remoteQuery = "SELECT user_id, date FROM attendance_day_summary WHERE status = 'remote'";
+      const remoteParams: unknown[] = [];
+      if (datesToProcess && datesToProcess.length > 0) {
+        const sortedDates = [...datesToProcess].sort();
+        remoteQuery += ' AND date >= ? AND date <= ?';
+        remoteParams.push(sortedDates[0], sortedDates[sortedDates.length - 1]);
+      }
+      const rows = await select<{ user_id: string; date: string }>(remoteQuery, remoteParams);
+      for (const row of rows) {
+        remoteDays.add(`${row.user_id}|${row.date}`);
+      }
+    } catch (error) {
+      console.log('[SyncEngine] Could not load remote days');
+    }
+
     // Get all raw logs (scoped to synced dates if available for performance)
-    let logQuery = `SELECT * FROM attendance_logs_raw WHERE device_id = ?`;
+    // Punches voided or moved by a correction don't count
+    let logQuery = `SELECT * FROM attendance_logs_raw WHERE device_id = ?
+      AND id NOT IN (SELECT original_log_id FROM punch_corrections WHERE original_log_id IS NOT NULL)`;
     const logParams: unknown[] = [deviceId];
 
     if (datesToProcess && datesToProcess.length > 0) {
@@ -687,7 +915,7 @@ export class SyncEngine {
       const minDate = sortedDates[0]!;
       const maxDate = sortedDates[sortedDates.length - 1]!;
       logQuery += ` AND timestamp >= ? AND timestamp < ?`;
-      logParams.push(`${minDate}T00:00:00`, `${maxDate}T23:59:59.999Z`);
+      logParams.push(...workingDayBounds(minDate, maxDate, cutoffHour));
       console.log(`[SyncEngine] Querying logs scoped to ${minDate} — ${maxDate} (${datesToProcess.length} dates)`);
     }
 
@@ -726,7 +954,7 @@ export class SyncEngine {
       }
       
       matchedCount++;
-      const date = log.timestamp.split('T')[0] as string;
+      const date = workingDateFromTimestamp(log.timestamp, cutoffHour);
       const key = `${user.id}|${date}`;
       
       if (!userDateLogs.has(key)) {
@@ -778,6 +1006,10 @@ export class SyncEngine {
 
       for (const [key, { user, logs: dateLogs }] of batch) {
         const date = key.split('|')[1] as string;
+        if (remoteDays.has(key)) {
+          processed++;
+          continue;
+        }
 
         try {
           const punches: PunchRecord[] = dateLogs.map(log => ({
@@ -791,8 +1023,9 @@ export class SyncEngine {
             createdAt: log.created_at,
           }));
 
-          const isHoliday = holidays.has(date);
-          const summary = processDay(user.id, date, punches, rules, isHoliday);
+          const isHoliday = (holidays.get(date) ?? []).some(h => holidayApplies(h, user.departmentId, user.site));
+          const computed = processDay(user.id, date, punches, rosterRules.get(key) ?? shiftRules.get(user.id) ?? rules, isHoliday);
+          const summary = user.classification === 'contractor' ? hoursOnly(computed) : computed;
 
           computedSummaries.push({
             id: crypto.randomUUID(),
@@ -824,7 +1057,7 @@ export class SyncEngine {
           const params: unknown[] = [];
 
           for (const s of computedSummaries) {
-            placeholders.push('(?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)');
+            placeholders.push("(?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 'sync', NULL)");
             params.push(
               s.id, s.userId, s.date,
               s.checkInTime, s.checkOutTime, s.isIncomplete,
@@ -836,7 +1069,8 @@ export class SyncEngine {
           await execute(
             `INSERT INTO attendance_day_summary
              (id, user_id, date, check_in_time, check_out_time, is_incomplete,
-              late_minutes, early_minutes, status, flags, created_at, updated_at)
+              late_minutes, early_minutes, status, flags, created_at, updated_at,
+              change_reason, changed_by)
              VALUES ${placeholders.join(',\n                    ')}
              ON CONFLICT(user_id, date) DO UPDATE SET
                check_in_time = excluded.check_in_time,
@@ -846,7 +1080,9 @@ export class SyncEngine {
                early_minutes = excluded.early_minutes,
                status = excluded.status,
                flags = excluded.flags,
-               updated_at = excluded.updated_at`,
+               updated_at = excluded.updated_at,
+               change_reason = excluded.change_reason,
+               changed_by = excluded.changed_by`,
             params
           );
 
@@ -4,7 +4,10 @@
  */
 
 import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
 import { open, save } from '@tauri-apps/plugin-dialog';
+import type { InitialImportStatus } from './services/sidecar-client';
+import type { UserClassification } from '../types';
 
 // ============================================================================
 // Types
@@ -22,73 +25,4551 @@ export interface RestoreResult {
   error: string | null;
 }
 
+export interface RecomputeResult {
+  daysProcessed: number;
+  logsProcessed: number;
+  unmatchedLogs: number;
+  ruleErrors: string[];
+}
+
+export interface RuleValidationResult {
+  valid: boolean;
+  error?: string;
+  line?: number;
+}
+
+export interface ExportedFile {
+  path: string;
+  rows: number;
+  fileSize: number;
+}
+
+export interface ExportResult {
+  files: ExportedFile[];
+}
+
+/** How reading one device for the device user export went */
+export interface DeviceUserRead {
+  deviceId: string;
+  deviceName: string;
+  users: number;
+  /** Why the device is missing from the file */
+  error: string | null;
+}
+
+export interface DeviceUserExport {
+  file: ExportedFile;
+  devices: DeviceUserRead[];
+}
+
+export interface SqlDumpOptions {
+  /** Restrict the dump to these tables (default: all) */
+  tables?: string[];
+  /** Omit CREATE statements */
+  dataOnly?: boolean;
+}
+
+export type RestoreMode = 'full' | 'usersDepartments' | 'settingsDevices' | 'logs';
+
+export interface RestoreOptions {
+  mode?: RestoreMode;
+  /** First day of logs to restore (YYYY-MM-DD), required for 'logs' mode */
+  startDate?: string;
+  /** Last day of logs to restore (YYYY-MM-DD), required for 'logs' mode */
+  endDate?: string;
+}
+
+export interface BackupCompatibility {
+  compatible: boolean;
+  schemaVersion: number | null;
+  currentSchemaVersion: number;
+  appVersion: string | null;
+  currentAppVersion: string;
+  warnings: string[];
+  error?: string;
+}
+
+export interface OperationSnapshot {
+  id: string;
+  label: string;
+  fileName: string;
+  /** Tables the operation touches; null means the whole database */
+  tables: string[] | null;
+  createdAt: string;
+  undone: boolean;
+}
+
+export interface UndoResult {
+  success: boolean;
+  label: string | null;
+  restartRequired: boolean;
+  error: string | null;
+}
+
+export interface BackupInfo {
+  fileName: string;
+  path: string;
+  fileSize: number;
+  createdAt: string | null;
+  kind: 'backup' | 'pre_restore' | 'pre_reset' | 'pre_op';
+  userCount: number | null;
+  logCount: number | null;
+  summaryCount: number | null;
+  deviceCount: number | null;
+  schemaVersion: number | null;
+  appVersion: string | null;
+  integrityOk: boolean;
+  error: string | null;
+}
+
+/** Row counts of one table in the live database and a backup */
+export interface TableDiff {
+  table: string;
+  /** null when the table doesn't exist on that side */
+  liveRows: number | null;
+  backupRows: number | null;
+  /** Backup rows minus live rows: what a full restore would change */
+  delta: number;
+}
+
+/** Raw logs held on one side */
+export interface LogRange {
+  count: number;
+  first: string | null;
+  last: string | null;
+  /** Distinct days with punches */
+  days: number;
+}
+
+export interface DeviceLogDiff {
+  deviceId: string;
+  deviceName: string | null;
+  live: LogRange;
+  backup: LogRange;
+}
+
+/** What restoring a backup would change */
+export interface BackupDiff {
+  backupPath: string;
+  schemaVersion: number | null;
+  currentSchemaVersion: number;
+  tables: TableDiff[];
+  liveLogs: LogRange;
+  backupLogs: LogRange;
+  /** Live logs the backup doesn't have, lost by a full restore */
+  logsOnlyInLive: number;
+  /** Backup logs the live database doesn't have */
+  logsOnlyInBackup: number;
+  devices: DeviceLogDiff[];
+}
+
+export interface BackupTarget {
+  /** Empty for a new target */
+  id: string;
+  name: string;
+  path: string;
+  kind: 'local' | 'removable' | 'network';
+  /** Used by scheduled backups; checked at startup */
+  scheduled: boolean;
+  createdAt?: string;
+}
+
+export interface BackupTargetStatus {
+  targetId: string;
+  name: string;
+  path: string;
+  available: boolean;
+  writable: boolean;
+  freeBytes: number | null;
+  requiredBytes: number;
+  warnings: string[];
+  error: string | null;
+}
+
+export interface TransferExportResult {
+  success: boolean;
+  filePath: string;
+  fileSize: number;
+  files: number;
+  error: string | null;
+}
+
+export interface TransferImportResult {
+  success: boolean;
+  /** The database was replaced; migrations run when the app restarts */
+  restartRequired: boolean;
+  filesRestored: number;
+  pathsUpdated: number;
+  warnings: string[];
+  error: string | null;
+}
+
+export type TemplateKind = 'email_subject' | 'email_body' | 'report_header' | 'report_footer';
+
+export interface Template {
+  /** Empty for a new template */
+  id: string;
+  name: string;
+  kind: TemplateKind;
+  /** MiniJinja syntax, e.g. "{{ company.name }} - {{ period.label }}" */
+  body: string;
+  updatedAt?: string;
+}
+
+export interface TemplateContext {
+  periodStart?: string;
+  periodEnd?: string;
+  totals?: Record<string, unknown>;
+  extra?: Record<string, unknown>;
+}
+
+export interface HijriDate {
+  gregorian: string;
+  year: number;
+  month: number;
+  day: number;
+  monthName: string;
+  monthNameAr: string;
+  formatted: string;
+  iso: string;
+}
+
+export interface DateRange {
+  startDate: string;
+  endDate: string;
+}
+
+export interface MonthTotals {
+  /** "YYYY-MM", or the period key when grouped by reporting period */
+  month: string;
+  label: string;
+  calendar: 'hijri' | 'gregorian' | 'period';
+  firstDate: string;
+  lastDate: string;
+  days: number;
+  present: number;
+  late: number;
+  earlyLeave: number;
+  absent: number;
+  incomplete: number;
+  lateMinutes: number;
+}
+
+export type SheetTemplate = 'labor_office_monthly';
+
+export interface AnalyticsScope {
+  departmentId?: string;
+  userIds?: string[];
+}
+
+export interface PunchHeatmap {
+  /** 7 rows (0 = Sunday) of 24 hourly buckets */
+  matrix: number[][];
+  max: number;
+  total: number;
+  arrivalsOnly: boolean;
+}
+
+export interface KpiValues {
+  workingDays: number;
+  expectedDays: number;
+  daysPresent: number;
+  /** Percent of expected working days attended */
+  attendanceRate: number;
+  /** HH:MM */
+  averageArrival: string | null;
+  averageArrivalMinutes: number | null;
+  averageWorkedHours: number | null;
+  /** Percent of check-ins that were late */
+  latePercentage: number;
+}
+
+export interface KpiTrend {
+  attendanceRate: number;
+  averageArrivalMinutes: number | null;
+  averageWorkedHours: number | null;
+  latePercentage: number;
+}
+
+export interface Kpis {
+  period: DateRange;
+  previousPeriod: DateRange;
+  current: KpiValues;
+  previous: KpiValues;
+  /** current - previous */
+  trend: KpiTrend;
+}
+
+export type ExceptionStatus = 'open' | 'resolved' | 'dismissed';
+
+export interface AttendanceException {
+  id: string;
+  /**
+   * e.g. 'impossible_travel', 'excessive_punches', 'invalid_timestamp', 'unpaired_punch', 'attendance_dispute',
+   * 'break_violation', 'max_daily_hours', 'max_weekly_hours', 'min_rest'
+   */
+  kind: string;
+  severity: 'info' | 'warning' | 'critical';
+  deviceUserId: string | null;
+  userId: string | null;
+  userName: string | null;
+  date: string | null;
+  message: string;
+  details: Record<string, unknown> | null;
+  status: ExceptionStatus;
+  resolutionNote: string | null;
+  createdAt: string;
+  resolvedAt: string | null;
+}
+
+export interface AnomalyCheckResult {
+  logsChecked: number;
+  /** Findings per kind, including ones already queued */
+  findings: Record<string, number>;
+  newExceptions: number;
+}
+
+export interface PeriodMetrics {
+  /** Check-in to check-out on working days */
+  workedHours: number;
+  lateCount: number;
+  /** Working days absent or without a summary */
+  absenceDays: number;
+}
+
+export interface UserPeriodComparison {
+  userId: string;
+  name: string;
+  departmentId: string | null;
+  departmentName: string | null;
+  periodA: PeriodMetrics;
+  periodB: PeriodMetrics;
+  /** B - A */
+  delta: PeriodMetrics;
+}
+
+export interface DepartmentPeriodComparison {
+  /** null groups users without a department */
+  departmentId: string | null;
+  departmentName: string | null;
+  userCount: number;
+  periodA: PeriodMetrics;
+  periodB: PeriodMetrics;
+  /** B - A */
+  delta: PeriodMetrics;
+}
+
+export interface PeriodComparison {
+  periodA: DateRange;
+  periodB: DateRange;
+  workingDaysA: number;
+  workingDaysB: number;
+  users: UserPeriodComparison[];
+  departments: DepartmentPeriodComparison[];
+}
+
+export interface CostCenterTotals {
+  /** null for days without a cost center */
+  costCenterId: string | null;
+  code: string | null;
+  name: string | null;
+  /** Users who booked at least one working day to it */
+  userCount: number;
+  daysPresent: number;
+  metrics: PeriodMetrics;
+}
+
+export interface CostCenterReport {
+  period: DateRange;
+  workingDays: number;
+  costCenters: CostCenterTotals[];
+}
+
+export interface BuddyPunchOptions {
+  /** Punches on the same device this close together count as "together" (default 30) */
+  windowSeconds?: number;
+  /** Minimum days a pair punched together (default 5) */
+  minSharedDays?: number;
+  /** Minimum share of days both were in on which they punched together (default 0.6) */
+  minRatio?: number;
+  /** Minimum card-only punches for a fingerprint user (default 3) */
+  minCardPunches?: number;
+}
+
+export interface SuspiciousPair {
+  deviceUserIds: [string, string];
+  userIds: [string | null, string | null];
+  names: [string | null, string | null];
+  sharedDays: number;
+  daysBothPresent: number;
+  ratio: number;
+  punchesTogether: number;
+  averageGapSeconds: number;
+}
+
+export interface CardOnlyUser {
+  deviceUserId: string;
+  userId: string | null;
+  name: string | null;
+  cardPunches: number;
+  fingerprintPunches: number;
+  lastCardPunch: string;
+}
+
+export interface BuddyPunchReport {
+  period: DateRange;
+  options: Required<BuddyPunchOptions>;
+  pairs: SuspiciousPair[];
+  cardOnly: CardOnlyUser[];
+}
+
+/**
+ * Stored in the devices.comm_key column when the real key is in the OS keychain
+ */
+export const KEYCHAIN_REF = 'keychain:';
+
+/**
+ * Where a secret ended up after store_device_comm_key
+ */
+export interface StoredSecret {
+  /** Value to save in the database: KEYCHAIN_REF, or the key itself when no keychain is available */
+  value: string;
+  inKeychain: boolean;
+}
+
+/**
+ * Result of moving plaintext comm keys into the keychain
+ */
+export interface SecretMigrationResult {
+  keychainAvailable: boolean;
+  migrated: number;
+  failed: number;
+}
+
+/**
+ * A file finished through closeFileStream
+ */
+export interface StreamedFile {
+  path: string;
+  fileSize: number;
+}
+
+/**
+ * Result of createZip
+ */
+export interface ZipResult {
+  path: string;
+  files: number;
+  fileSize: number;
+}
+
+/**
+ * Whether the REST API server is listening, and where
+ */
+export interface ApiServerStatus {
+  running: boolean;
+  address: string | null;
+  /** Serving HTTPS */
+  tls: boolean;
+  startedAt: string | null;
+  /** How the last start went, including failed ones */
+  lastStart: ApiServerStart | null;
+}
+
+/** Whether a port can be listened on */
+export interface PortCheck {
+  bindAddress: string;
+  port: number;
+  status: 'free' | 'in_use' | 'permission_denied' | 'address_unavailable' | 'error';
+  detail: string | null;
+}
+
+/** Whether the OS firewall lets LAN clients reach the server */
+export interface FirewallCheck {
+  /** `not_applicable` when listening on loopback only */
+  status: 'allowed' | 'blocked' | 'off' | 'not_applicable' | 'unknown';
+  detail: string | null;
+}
+
+/** A start attempt of the REST API server: the ports tried and where it ended up */
+export interface ApiServerStart {
+  attemptedAt: string;
+  /** The configured port */
+  requestedPort: number;
+  /** The port listened on; null when the start failed */
+  port: number | null;
+  fallbackUsed: boolean;
+  checks: PortCheck[];
+  /** Checked after starting; null until the check finishes */
+  firewall: FirewallCheck | null;
+  error: string | null;
+}
+
+export interface ApiPortReport {
+  port: PortCheck;
+  firewall: FirewallCheck;
+}
+
+/** TLS settings for the app's listeners (`tls` setting) */
+export interface TlsSettings {
+  mode: 'off' | 'self_signed' | 'custom';
+  /** PEM certificate (chain) file, for custom */
+  certPath: string | null;
+  /** PEM private key file, for custom */
+  keyPath: string | null;
+  /** Names and addresses clients reach this PC by, added to the self-signed certificate */
+  hostnames: string[];
+}
+
+/** The certificate listeners serve */
+export interface TlsStatus {
+  mode: TlsSettings['mode'];
+  certPath: string | null;
+  /** SHA-256 of the certificate (colon-separated hex); clients pin this for a self-signed certificate */
+  fingerprintSha256: string | null;
+  /** Why the certificate can't be used, if it can't */
+  error: string | null;
+}
+
+/** REST API request counters since the server started */
+export interface ApiMetrics {
+  uptimeSeconds: number;
+  requests: number;
+  /** Refused for going over the rate limit */
+  rateLimited: number;
+  /** Refused for a body over the size cap */
+  bodyTooLarge: number;
+  /** Bad tokens, API keys, pairing codes and report links */
+  authFailures: number;
+  /** IP addresses locked out for failing too often */
+  lockouts: number;
+  /** Requests refused while their IP address was locked out */
+  lockedOutRequests: number;
+  /** IP addresses locked out right now */
+  activeLockouts: number;
+}
+
+/**
+ * A per-user REST API token (the secret is never listed)
+ */
+export interface UserToken {
+  id: string;
+  userId: string;
+  userName: string;
+  label: string;
+  role: 'employee' | 'manager';
+  /** Departments a manager token can read through the /team routes */
+  departmentIds: string[];
+  createdAt: string;
+  lastUsedAt: string | null;
+  revokedAt: string | null;
+}
+
+/**
+ * A newly issued token; `token` is only returned once
+ */
+export interface IssuedToken {
+  token: string;
+  info: UserToken;
+}
+
+/**
+ * An integration API key (without the secret)
+ */
+export interface ApiKey {
+  id: string;
+  name: string;
+  /** First characters of the key, to tell keys apart */
+  keyPrefix: string;
+  /** e.g. 'users:read', 'attendance:read', 'reports:read' */
+  scopes: string[];
+  createdAt: string;
+  lastUsedAt: string | null;
+  /** Set when the key was rotated with a grace period */
+  expiresAt: string | null;
+  revokedAt: string | null;
+  /** The key that replaced this one on rotation */
+  replacedBy: string | null;
+}
+
+/**
+ * A newly issued API key; `key` is only returned once
+ */
+export interface IssuedApiKey {
+  key: string;
+  info: ApiKey;
+}
+
+/**
+ * A one-time invite for pairing an employee's companion app
+ */
+export interface PairingInvite {
+  id: string;
+  userId: string;
+  userName: string;
+  /** Base URL the companion app will call */
+  endpoint: string;
+  /** JSON encoded in the QR code */
+  payload: string;
+  /** QR code as an SVG document */
+  qrSvg: string;
+  expiresAt: string;
+}
+
+/**
+ * A signed link to one employee's monthly report
+ */
+export interface SelfServiceLink {
+  userId: string;
+  userName: string;
+  /** Where to send the link, when the user has an email address */
+  email: string | null;
+  url: string;
+  expiresAt: string;
+}
+
+/**
+ * State of one data migration
+ */
+export interface DataMigrationStatus {
+  name: string;
+  description: string;
+  /** "pending", "running" (interrupted), "completed" or "failed" */
+  status: string;
+  attempts: number;
+  lastError: string | null;
+  completedAt: string | null;
+}
+
+/**
+ * Outcome of running pending data migrations
+ */
+export interface DataMigrationReport {
+  schemaVersion: number;
+  applied: string[];
+  /** Steps waiting for a newer schema or behind a failed step */
+  pending: string[];
+  /** Retried on the next run */
+  failed: DataMigrationStatus | null;
+}
+
+/**
+ * Result of one startup health check
+ */
+export interface HealthCheck {
+  /** database, integrity, schema, wal or backupDirectory */
+  id: string;
+  level: 'ok' | 'warning' | 'error';
+  message: string;
+  /** What was done to fix it, if anything */
+  repair: string | null;
+}
+
+/**
+ * Result of the startup health check
+ */
+export interface StartupHealth {
+  checkedAt: string;
+  /** Worst level among the checks */
+  level: 'ok' | 'warning' | 'error';
+  checks: HealthCheck[];
+  /** Most recent backup file, for recovering from an error */
+  latestBackup: string | null;
+}
+
+/**
+ * A registered piece of running write work
+ */
+export interface Activity {
+  id: number;
+  label: string;
+  startedAt: string;
+}
+
+/**
+ * Running work and whether new work is being held off
+ */
+export interface ActivityStatus {
+  active: Activity[];
+  /** The backup or restore holding new work off */
+  pausedFor: string | null;
+}
+
+/** What the app waits for before exiting */
+export interface ShutdownReport {
+  /** Labels of work running when the exit was requested */
+  running: string[];
+  openDeviceSessions: number;
+  clean: boolean;
+  problems: string[];
+}
+
+/**
+ * Sent before sync retries a device whose menu is open
+ */
+export interface DeviceBusyEvent {
+  ip: string;
+  attempt: number;
+  maxAttempts: number;
+  retryInSecs: number;
+  message: string;
+}
+
+export interface UserShift {
+  userId: string;
+  /** HH:MM */
+  startTime: string;
+  /** HH:MM */
+  endTime: string;
+  source: 'manual' | 'detected';
+  updatedAt: string;
+}
+
+export interface ShiftAssignment {
+  userId: string;
+  startTime: string;
+  endTime: string;
+}
+
+export interface ShiftProposal {
+  userId: string;
+  displayName: string;
+  startTime: string;
+  endTime: string;
+  daysObserved: number;
+  startSpreadMinutes: number;
+  endSpreadMinutes: number;
+  /** Share of days (0..1) that started within 30 minutes of startTime */
+  consistency: number;
+  current?: UserShift;
+}
+
+export interface RosterEntry {
+  id: string;
+  userId: string;
+  /** YYYY-MM-DD */
+  date: string;
+  /** HH:MM */
+  startTime: string;
+  /** HH:MM */
+  endTime: string;
+  note: string | null;
+}
+
+export interface RosterEntryInput {
+  userId: string;
+  date: string;
+  startTime: string;
+  endTime: string;
+  note?: string | null;
+}
+
+export interface RosterImportResult {
+  imported: number;
+  errors: { line: number; message: string }[];
+}
+
+export interface RosterVariance {
+  userId: string;
+  displayName: string;
+  date: string;
+  plannedStart: string | null;
+  plannedEnd: string | null;
+  checkInTime: string | null;
+  checkOutTime: string | null;
+  plannedMinutes: number;
+  workedMinutes: number;
+  /** Worked minus planned */
+  varianceMinutes: number;
+  status: 'covered' | 'partial' | 'gap' | 'unplanned';
+}
+
+export interface CoverageDay {
+  date: string;
+  planned: number;
+  attended: number;
+  gaps: number;
+  unplanned: number;
+}
+
+export interface RosterReport {
+  rows: RosterVariance[];
+  coverage: CoverageDay[];
+}
+
+/**
+ * Previous values of a day summary, recorded when it changed or was deleted
+ */
+export interface SummaryChange {
+  id: number;
+  summaryId: string;
+  userId: string;
+  displayName: string | null;
+  date: string;
+  checkInTime: string | null;
+  checkOutTime: string | null;
+  isIncomplete: boolean;
+  lateMinutes: number;
+  earlyMinutes: number;
+  status: string;
+  flags: string | null;
+  /** New values; null when the summary was deleted */
+  newCheckInTime: string | null;
+  newCheckOutTime: string | null;
+  newStatus: string | null;
+  newLateMinutes: number | null;
+  newEarlyMinutes: number | null;
+  /** 'sync', 'recompute', 'api_punch', 'manual', 'backfill', 'update' or 'delete' */
+  reason: string;
+  actor: string | null;
+  changedAt: string;
+}
+
+/**
+ * A device user or log that failed validation and was kept for review
+ */
+export interface QuarantinedRecord {
+  id: number;
+  deviceId: string;
+  recordType: 'log' | 'user';
+  deviceUserId: string;
+  /** Logs only */
+  timestamp: string | null;
+  /** Users only */
+  userName: string | null;
+  verifyType: number | null;
+  punchType: number | null;
+  /** 'empty_user_id', 'invalid_user_id', 'invalid_name', 'invalid_timestamp', 'future_timestamp' or 'past_timestamp' */
+  reason: string;
+  status: 'pending' | 'repaired' | 'discarded';
+  createdAt: string;
+  resolvedAt: string | null;
+}
+
+/**
+ * Corrected values for a quarantined record; fields left out keep the device's value
+ */
+export interface QuarantineRepair {
+  id: number;
+  deviceUserId?: string;
+  /** YYYY-MM-DDTHH:MM:SS, device-local */
+  timestamp?: string;
+  userName?: string;
+}
+
+/**
+ * Raw payload retention settings. Payloads are off by default.
+ */
+export interface RawPayloadSettings {
+  enabled: boolean;
+  /** 'hex' is readable in any SQLite browser; 'zstd' is smaller */
+  encoding: 'hex' | 'zstd';
+  /** No more payloads are kept once they take up this much */
+  maxMegabytes: number;
+}
+
+/**
+ * Space taken by retained payloads
+ */
+export interface RawPayloadUsage {
+  records: number;
+  /** Size of the records as received */
+  originalBytes: number;
+  /** Size as stored, after encoding */
+  storedBytes: number;
+  maxBytes: number;
+  oldest: string | null;
+  newest: string | null;
+  devices: {
+    deviceId: string;
+    deviceName: string | null;
+    records: number;
+    storedBytes: number;
+  }[];
+}
+
+/**
+ * A log's record exactly as the device sent it
+ */
+export interface RawPayload {
+  deviceId: string;
+  deviceUserId: string;
+  timestamp: string;
+  encoding: 'hex' | 'zstd';
+  /** The record's bytes as lowercase hex */
+  hex: string;
+  originalSize: number;
+  storedSize: number;
+  createdAt: string;
+}
+
+/**
+ * Two terminals covering the same door, whose punches should match
+ */
+export interface DevicePair {
+  id: number;
+  primaryDeviceId: string;
+  primaryDeviceName: string | null;
+  secondaryDeviceId: string;
+  secondaryDeviceName: string | null;
+  /** Punches this far apart or closer count as the same punch */
+  toleranceSeconds: number;
+  createdAt: string;
+}
+
+/**
+ * A pair to save; an id updates the tolerance of an existing pair
+ */
+export interface DevicePairInput {
+  id?: number;
+  primaryDeviceId: string;
+  secondaryDeviceId: string;
+  /** Defaults to 60 */
+  toleranceSeconds?: number;
+}
+
+/**
+ * A punch the other terminal of its pair has no record of
+ */
+export interface UnmatchedPunch {
+  logId: string;
+  deviceId: string;
+  missingFromDeviceId: string;
+  deviceUserId: string;
+  timestamp: string;
+}
+
+/**
+ * Outcome of reconciling one device pair
+ */
+export interface PairReconciliation {
+  pair: DevicePair;
+  /** Period both terminals have logs for; null when they don't overlap */
+  comparedFrom: string | null;
+  comparedTo: string | null;
+  matched: number;
+  onlyOnPrimary: number;
+  onlyOnSecondary: number;
+  /** Unmatched punches not already in the exception queue */
+  newExceptions: number;
+  unmatched: UnmatchedPunch[];
+}
+
+/**
+ * Outgoing mail server. The password is stored with setCredential('smtp-password', ...).
+ */
+export interface SmtpSettings {
+  host: string;
+  port: number;
+  security: 'starttls' | 'tls' | 'none';
+  /** Empty for servers that don't need a login */
+  username: string;
+  fromAddress: string;
+}
+
+/**
+ * Monthly closure email settings
+ */
+export interface ClosureSettings {
+  /** Send the previous month's emails automatically */
+  enabled: boolean;
+  /** Day of the month the previous month is closed on (1-28) */
+  dayOfMonth: number;
+  /** Days after sending that employees may dispute */
+  disputeDays: number;
+  smtp: SmtpSettings;
+}
+
+/**
+ * What happened when a month was closed for one employee
+ */
+export interface AttendanceClosure {
+  id: string;
+  year: number;
+  month: number;
+  userId: string;
+  userName: string | null;
+  email: string | null;
+  status: 'sent' | 'failed' | 'no_email';
+  error: string | null;
+  /** YYYY-MM-DD, last day a dispute is accepted */
+  disputeDeadline: string;
+  sentAt: string;
+}
+
+/**
+ * Outcome of closing a month
+ */
+export interface ClosureRun {
+  year: number;
+  month: number;
+  sent: number;
+  failed: number;
+  /** Active employees without an email address */
+  noEmail: number;
+  /** Skipped because they were sent this month before */
+  alreadySent: number;
+  disputeDeadline: string;
+}
+
+/**
+ * A logged outbound message (email, webhook call, change queued for an
+ * offline device or MQTT publish)
+ */
+export interface Delivery {
+  id: number;
+  channel: 'email' | 'webhook' | 'device' | 'mqtt';
+  /** Address, URL, device ID or topic it went to */
+  target: string;
+  summary: string;
+  /** What is needed to send it again */
+  payload: Record<string, unknown>;
+  status: 'pending' | 'delivered' | 'failed';
+  attempts: number;
+  lastError: string | null;
+  /** When it is retried automatically; null once delivered or out of attempts */
+  nextRetryAt: string | null;
+  createdAt: string;
+  updatedAt: string;
+  deliveredAt: string | null;
+}
+
+/**
+ * Delivery counts by status
+ */
+export interface DeliveryStats {
+  pending: number;
+  delivered: number;
+  failed: number;
+  /** Failed deliveries that will be retried automatically */
+  retrying: number;
+}
+
+/**
+ * Result of retrying one delivery by hand
+ */
+export interface RetryOutcome {
+  id: number;
+  delivered: boolean;
+  error: string | null;
+}
+
+export type SummaryEngine = 'set_based' | 'row_by_row';
+
+export interface EngineTiming {
+  engine: SummaryEngine;
+  runMs: number[];
+  bestMs: number;
+  daysProcessed: number;
+  logsProcessed: number;
+}
+
+export interface RecomputeBenchmark {
+  startDate: string;
+  endDate: string;
+  runs: number;
+  setBased: EngineTiming;
+  rowByRow: EngineTiming;
+  /** Row-by-row best time over set-based best time */
+  speedup: number;
+  differingDays: number;
+  /** "user date: field" for the first differences found */
+  differences: string[];
+}
+
+export interface ReportCacheCount {
+  report: string;
+  entries: number;
+  hits: number;
+}
+
+export interface ReportCacheStats {
+  entries: number;
+  hits: number;
+  bytes: number;
+  /** Entries per report name */
+  reports: ReportCacheCount[];
+}
+
+export interface ShiftTimes {
+  /** HH:MM */
+  startTime: string;
+  /** HH:MM */
+  endTime: string;
+}
+
+/** Changes applied to every user of a batch; fields left out stay as they are */
+export interface BulkUserUpdate {
+  /** Department to move the users to; an empty string removes them from their department */
+  departmentId?: string;
+  /** Site the users work at, for site holidays; an empty string clears it */
+  site?: string;
+  status?: 'active' | 'inactive';
+  /** Shift to assign, replacing the users' current ones */
+  shift?: ShiftTimes;
+  /** Remove the users' shifts so the global work hours apply again */
+  clearShift?: boolean;
+  classification?: UserClassification;
+  /** Last day of a temporary enrollment (YYYY-MM-DD); an empty string clears it */
+  enrollmentExpiresAt?: string;
+}
+
+export interface BulkUserResult {
+  userId: string;
+  ok: boolean;
+  error?: string;
+}
+
+/** When any user fails, `applied` is false and no user was changed */
+export interface BulkUserOutcome {
+  applied: boolean;
+  succeeded: number;
+  failed: number;
+  results: BulkUserResult[];
+  auditId?: number;
+}
+
+export interface UserAuditEntry {
+  id: number;
+  action: 'bulk_update' | 'bulk_delete' | 'link_device_users';
+  changes: Record<string, unknown>;
+  userIds: string[];
+  userCount: number;
+  createdAt: string;
+}
+
+/** A profile a device user may belong to */
+export interface LinkCandidate {
+  userId: string;
+  displayName: string;
+  employeeCode?: string;
+  method: 'employee_code' | 'exact_name' | 'fuzzy_name';
+  /** 0..1 */
+  confidence: number;
+}
+
+/** Proposed link for a device user that has no profile of its own yet */
+export interface LinkProposal {
+  deviceUserId: string;
+  deviceName: string | null;
+  /** The profile sync created for the device user, removed when the link is confirmed */
+  placeholderUserId: string;
+  best: LinkCandidate;
+  alternatives: LinkCandidate[];
+  /** Another candidate scored about as high as the best one */
+  ambiguous: boolean;
+}
+
+export interface LinkConfirmation {
+  deviceUserId: string;
+  userId: string;
+  method?: string;
+  confidence?: number;
+}
+
+export interface LinkResult {
+  deviceUserId: string;
+  ok: boolean;
+  error?: string;
+}
+
+/** When any link fails, `applied` is false and nothing was linked */
+export interface LinkOutcome {
+  applied: boolean;
+  linked: number;
+  failed: number;
+  results: LinkResult[];
+  auditId?: number;
+}
+
+/** A user added, or a device name refreshed, by a device user merge */
+export interface UserMergeChange {
+  change: 'added' | 'renamed';
+  userId: string;
+  deviceUserId: string;
+  /** The profile's name, which the merge leaves alone */
+  displayName: string;
+  previousDeviceName: string | null;
+  deviceName: string;
+}
+
+/** What merging a device's users changed */
+export interface UserMergeSummary {
+  deviceUsers: number;
+  added: number;
+  renamed: number;
+  unchanged: number;
+  changes: UserMergeChange[];
+  auditId?: number;
+}
+
+export interface DeviceUserLink {
+  deviceUserId: string;
+  userId: string;
+  deviceName: string | null;
+  method: string;
+  confidence: number | null;
+  createdAt: string;
+}
+
+/** A payroll period whose punches and summaries can no longer change */
+export interface LockedPeriod {
+  id: number;
+  startDate: string;
+  endDate: string;
+  label: string;
+  lockedAt: string;
+}
+
+export type PunchCorrectionAction = 'add' | 'move' | 'void';
+
+export interface PunchCorrectionInput {
+  action: PunchCorrectionAction;
+  /** The punch to move or void */
+  logId?: string;
+  /** Whose punch to add */
+  userId?: string;
+  /** Local time of the added or moved punch (YYYY-MM-DDTHH:MM[:SS]) */
+  timestamp?: string;
+  reason: string;
+}
+
+export interface PunchCorrection {
+  id: string;
+  action: PunchCorrectionAction;
+  userId?: string;
+  deviceUserId: string;
+  /** The punch voided or moved */
+  originalLogId?: string;
+  originalTimestamp?: string;
+  /** The punch added, or where the original was moved to */
+  correctedLogId?: string;
+  correctedTimestamp?: string;
+  reason: string;
+  createdAt: string;
+}
+
+/**
+ * Stale device watchdog settings
+ */
+export interface WatchdogSettings {
+  enabled: boolean;
+  /** Days without a sync after which a device is escalated */
+  thresholdDays: number;
+  /** Days between repeated escalations while a device stays stale */
+  repeatDays: number;
+  /** URL posted a JSON alert; empty for none */
+  webhookUrl: string;
+  /** Addresses emailed an alert, sent with the closure email mail server */
+  emailRecipients: string[];
+}
+
+/** A device that hasn't synced within the threshold */
+export interface StaleDevice {
+  deviceId: string;
+  deviceName: string;
+  ip: string;
+  /** Null if it was never synced; staleness then counts from when it was added */
+  lastSyncAt: string | null;
+  daysSinceSync: number;
+  firstAlertedAt: string | null;
+  lastAlertedAt: string | null;
+}
+
+export interface WatchdogRun {
+  checkedAt: string;
+  thresholdDays: number;
+  stale: StaleDevice[];
+  /** Devices escalated by this check */
+  escalated: string[];
+  /** Webhook calls and emails that failed; they are retried from the delivery log */
+  failedDeliveries: number;
+}
+
+/** Fields of a record per employee and day */
+export type FixedWidthDailyField =
+  | 'employeeCode' | 'deviceUserId' | 'name' | 'department' | 'costCenter' | 'costCenterName'
+  | 'date' | 'checkIn' | 'checkOut' | 'status' | 'lateMinutes' | 'earlyMinutes' | 'workedMinutes';
+
+/** Fields of a record per employee and cost center over the period */
+export type FixedWidthTotalsField =
+  | 'employeeCode' | 'deviceUserId' | 'name' | 'department' | 'costCenter' | 'costCenterName'
+  | 'periodStart' | 'periodEnd'
+  | 'presentDays' | 'lateDays' | 'earlyLeaveDays' | 'absentDays' | 'incompleteDays'
+  | 'lateMinutes' | 'earlyMinutes' | 'workedMinutes';
+
+/**
+ * One column of a fixed-width record
+ */
+export interface FixedWidthColumn {
+  field: FixedWidthDailyField | FixedWidthTotalsField | 'literal';
+  /** Characters the column takes */
+  width: number;
+  align?: 'left' | 'right';
+  /** Fill character; a space when not set */
+  pad?: string;
+  /**
+   * Dates: a strftime pattern (default %Y%m%d). Minutes: 'hhmm' or
+   * 'hours100' (hours with two implied decimals). Times: 'hhmm'.
+   */
+  format?: string;
+  /** Text of a 'literal' column */
+  value?: string;
+}
+
+/**
+ * A fixed-width export layout matching a payroll bureau's spec
+ */
+export interface FixedWidthLayout {
+  /** Empty to create */
+  id?: string;
+  name: string;
+  /** 'daily': one record per employee and day; 'totals': one per employee */
+  scope: 'daily' | 'totals';
+  columns: FixedWidthColumn[];
+  lineEnding?: 'crlf' | 'lf';
+  /** Cut text too long for its column instead of failing the export */
+  truncate?: boolean;
+  updatedAt?: string;
+}
+
+export interface SigningSettings {
+  /** Sign every file written by an export */
+  enabled: boolean;
+}
+
+export interface ReportSignature {
+  id: number;
+  /** What produced the file, e.g. "attendance_sheet" */
+  report: string;
+  fileName: string;
+  path: string;
+  sha256: string;
+  signature: string;
+  keyFingerprint: string;
+  signedAt: string;
+}
+
+export interface SigningPublicKey {
+  algorithm: string;
+  /** Base64 Ed25519 public key */
+  publicKey: string;
+  /** First 16 hex digits of the key's SHA-256 */
+  fingerprint: string;
+}
+
+export interface SignatureVerification {
+  /** Unchanged, signed by this app's key and in the signature log */
+  valid: boolean;
+  /** SHA-256 of the file as it is now */
+  sha256: string;
+  hashMatches: boolean;
+  signatureValid: boolean;
+  signedByThisApp: boolean;
+  record: ReportSignature | null;
+  /** Why the file isn't valid */
+  problems: string[];
+}
+
+export interface CostCenter {
+  /** Empty when creating */
+  id: string;
+  /** Code as used by finance, unique */
+  code: string;
+  name: string;
+  /** Inactive cost centers can't be assigned but keep their history */
+  active: boolean;
+  createdAt?: string;
+  updatedAt?: string;
+}
+
+/** A cost center assigned to a user or a department (exactly one of them) */
+export interface CostCenterAssignment {
+  /** Empty when creating */
+  id: string;
+  costCenterId: string;
+  userId?: string | null;
+  departmentId?: string | null;
+  /** YYYY-MM-DD */
+  effectiveFrom: string;
+  /** YYYY-MM-DD, inclusive; null while open-ended */
+  effectiveTo?: string | null;
+  createdAt?: string;
+}
+
+export interface EffectiveCostCenter {
+  userId: string;
+  date: string;
+  costCenter: CostCenter | null;
+  /** Whether the user's own or their department's assignment applies */
+  source: 'user' | 'department' | null;
+}
+
+/** A background job the backend runs on its own */
+export interface ScheduledJob {
+  /** e.g. "anomaly_checks", "attendance_closure", "delivery_retries", "device_watchdog", "realtime_journal", "holiday_rules", "attendance_points", "minimum_staffing", "enrollment_expiry", "ledger_seal", "punch_archive", "break_checks", "working_time" */
+  id: string;
+  name: string;
+  description: string;
+  intervalSeconds: number;
+  status: 'idle' | 'running' | 'paused';
+  paused: boolean;
+  lastRunAt: string | null;
+  lastFinishedAt: string | null;
+  lastResult: 'ok' | 'failed' | null;
+  lastError: string | null;
+  lastDurationMs: number | null;
+  /** null while running or paused */
+  nextRunAt: string | null;
+  /** Runs since the app started */
+  runCount: number;
+}
+
+/** Limits on device syncs running at once */
+export interface SyncSchedulerSettings {
+  /** Devices syncing at the same time */
+  maxConcurrent: number;
+  /** Devices of one site syncing at the same time; devices without a site only count against maxConcurrent */
+  perSiteConcurrency: number;
+  /** Least time between one sync starting and the next */
+  staggerSeconds: number;
+  /** Minutes between automatic syncs of the devices set to `auto` */
+  autoSyncMinutes: number;
+}
+
+/** A saved device in sync order */
+export interface QueuedDevice {
+  deviceId: string;
+  name: string;
+  ip: string;
+  site: string | null;
+  syncPriority: number;
+  syncMode: 'auto' | 'manual';
+  lastSyncAt: string | null;
+}
+
+/** A device sync holding a slot */
+export interface RunningSync {
+  /** The saved device's name, or the address of an unsaved one */
+  device: string;
+  site: string | null;
+  priority: number;
+  startedAt: string;
+}
+
+/** A device sync waiting for a slot */
+export interface WaitingSync {
+  device: string;
+  site: string | null;
+  priority: number;
+  waitingSince: string;
+}
+
+/** What the sync gate is doing right now */
+export interface SyncSlots {
+  settings: SyncSchedulerSettings;
+  running: RunningSync[];
+  /** In the order they will start */
+  waiting: WaitingSync[];
+}
+
+/**
+ * A punch reported by a device as it happened
+ */
+export interface RealtimeEvent {
+  deviceId: string;
+  deviceUserId: string;
+  /** Device wall-clock time, YYYY-MM-DDTHH:MM:SS */
+  timestamp: string;
+  verifyType?: number;
+  punchType?: number;
+}
+
+/**
+ * What a drain of the realtime journal did
+ */
+export interface JournalDrain {
+  read: number;
+  inserted: number;
+  duplicates: number;
+  /** Torn or corrupt entries, or for devices that no longer exist */
+  discarded: number;
+}
+
+/**
+ * Realtime journal file state
+ */
+export interface JournalStatus {
+  path: string;
+  pending: number;
+  sizeBytes: number;
+  lastDrain: JournalDrain | null;
+  lastDrainedAt: string | null;
+}
+
+/**
+ * What a demo device generates
+ */
+export interface DemoProfile {
+  /** Enrolled users, 1-500 */
+  userCount: number;
+  /** Days of punches already on the device when it is created, up to 366 */
+  historyDays: number;
+  /** HH:MM */
+  workStart: string;
+  workEnd: string;
+  /** ISO weekdays people work (1 = Monday) */
+  workDays: number[];
+  /** Shares of working days, 0-1 */
+  lateRate: number;
+  absenceRate: number;
+  missedPunchRate: number;
+  overtimeRate: number;
+  /** Same seed, same people and punches */
+  seed: number;
+  /** Set when the device is created */
+  startDate?: string | null;
+}
+
+/**
+ * A simulated device for demos and training
+ */
+export interface DemoDevice {
+  deviceId: string;
+  name: string;
+  /** TEST-NET-1 address the device client recognises it by */
+  ip: string;
+  profile: DemoProfile;
+  createdAt: string;
+}
+
+/**
+ * What generateSampleData created
+ */
+export interface SampleDataResult {
+  deviceId: string;
+  departments: number;
+  users: number;
+  punches: number;
+  /** First and last day with punches */
+  startDate: string;
+  endDate: string;
+  daysComputed: number;
+}
+
+/**
+ * One step of the load-tested pipeline
+ */
+export interface LoadTestStage {
+  /** "synthesize", "screen", "ingest" or "summarize" */
+  name: string;
+  /** Records handled (summaries, for summarize) */
+  records: number;
+  elapsedMs: number;
+  perSecond: number;
+}
+
+/**
+ * Result of a pipeline load test
+ */
+export interface LoadTestReport {
+  logs: number;
+  users: number;
+  days: number;
+  duplicates: number;
+  quarantined: number;
+  inserted: number;
+  stages: LoadTestStage[];
+  totalMs: number;
+  /** Peak memory of the whole process, where the platform reports it */
+  peakMemoryBytes: number | null;
+  peakMemoryGrowthBytes: number | null;
+}
+
+/** A holiday, possibly limited to a department, a site or both */
+export interface ScopedHoliday {
+  /** Empty when saving a new holiday */
+  id: string;
+  date: string;
+  name?: string | null;
+  /** Only this department observes it; null for every department */
+  departmentId?: string | null;
+  /** Only users at this site observe it; null for every site */
+  site?: string | null;
+  /** The rule that generated it; edit the rule rather than the holiday */
+  ruleId?: string | null;
+  createdAt?: string;
+}
+
+export type HolidayRuleKind = 'fixed' | 'lunar';
+
+/** What happens to a day of a rule that falls on a weekly rest day */
+export type WeekendPolicy = 'none' | 'next_workday' | 'previous_workday';
+
+export interface HolidayRuleDate {
+  year: number;
+  startDate: string;
+}
+
+/** A recurring holiday, turned into holidays for this year and next by a background job */
+export interface HolidayRule {
+  /** Empty when saving a new rule */
+  id: string;
+  name: string;
+  /** 'fixed' falls on month/day every year; 'lunar' starts on a date entered per year */
+  kind: HolidayRuleKind;
+  month?: number | null;
+  day?: number | null;
+  durationDays?: number;
+  weekendPolicy?: WeekendPolicy;
+  departmentId?: string | null;
+  site?: string | null;
+  active?: boolean;
+  /** First day per year, for lunar rules (read only) */
+  dates?: HolidayRuleDate[];
+  createdAt?: string;
+  updatedAt?: string;
+}
+
+export interface MissingRuleDate {
+  ruleId: string;
+  name: string;
+  year: number;
+  /** Estimated from the nearest year entered; still has to be confirmed */
+  estimate: string | null;
+}
+
+export interface HolidayRuleRun {
+  firstYear: number;
+  lastYear: number;
+  generated: number;
+  removed: number;
+  skipped: number;
+  missing: MissingRuleDate[];
+}
+
+export interface PointsThreshold {
+  points: number;
+  /** e.g. "Written warning" */
+  label: string;
+}
+
+export interface PointsSettings {
+  /** Whether the daily threshold check runs; scores can be viewed either way */
+  enabled: boolean;
+  latePoints: number;
+  absencePoints: number;
+  earlyLeavePoints: number;
+  /** Days with a single punch */
+  incompletePoints: number;
+  /** Days a point counts for, ending today */
+  windowDays: number;
+  thresholds: PointsThreshold[];
+  /** URL posted a JSON alert when a user reaches a threshold; empty for none */
+  webhookUrl: string;
+}
+
+export type Infraction = 'late' | 'absence' | 'early_leave' | 'incomplete';
+
+export interface PointsEvent {
+  date: string;
+  infraction: Infraction;
+  points: number;
+}
+
+export interface UserPoints {
+  userId: string;
+  displayName: string;
+  employeeCode: string | null;
+  departmentName: string | null;
+  points: number;
+  lateDays: number;
+  absences: number;
+  earlyLeaves: number;
+  incompleteDays: number;
+  /** Highest threshold reached */
+  threshold: PointsThreshold | null;
+}
+
+export interface PointsReport {
+  windowStart: string;
+  windowEnd: string;
+  /** Highest first */
+  users: UserPoints[];
+}
+
+export interface UserPointsDetail {
+  windowStart: string;
+  windowEnd: string;
+  score: UserPoints;
+  events: PointsEvent[];
+}
+
+/** Payload of the "points-threshold" event */
+export interface PointsAlert {
+  userId: string;
+  displayName: string;
+  threshold: PointsThreshold;
+  points: number;
+}
+
+export interface PointsCheck {
+  checkedAt: string;
+  windowStart: string;
+  windowEnd: string;
+  /** Users at or above a threshold */
+  overThreshold: number;
+  /** Thresholds newly reached, highest per user */
+  alerts: PointsAlert[];
+  /** Webhook calls that failed; they are retried from the delivery log */
+  failedDeliveries: number;
+}
+
+export interface RecognitionEntry {
+  userId: string;
+  displayName: string;
+  employeeCode: string | null;
+  /** No late arrivals and no absences all month */
+  perfectMonth: boolean;
+  /** Days worked in the month */
+  daysPresent: number;
+  /** Days worked in a row without a late arrival or absence, up to the end of the month */
+  streakDays: number;
+  /** First day of the streak */
+  streakSince: string | null;
+}
+
+/** Perfect months first, then by streak */
+export interface DepartmentRecognition {
+  /** null for users without a department */
+  departmentId: string | null;
+  departmentName: string | null;
+  perfectCount: number;
+  users: RecognitionEntry[];
+}
+
+export interface RecognitionReport {
+  year: number;
+  month: number;
+  startDate: string;
+  /** Last day of the month, or today for the current month */
+  endDate: string;
+  departments: DepartmentRecognition[];
+}
+
+export interface StaffingSettings {
+  /** Whether the background check runs; presence can be viewed either way */
+  enabled: boolean;
+  /** URL posted a JSON alert when a rule falls short; empty for none */
+  webhookUrl: string;
+}
+
+/** A minimum headcount during operating hours */
+export interface StaffingRule {
+  /** Empty to create a new rule */
+  id: string;
+  name: string;
+  /** Only users of this department count; null for every department */
+  departmentId: string | null;
+  /** Only users at this site count; null for every site */
+  site: string | null;
+  /** Days the rule applies, 0 = Sunday */
+  weekdays: number[];
+  /** HH:MM */
+  startTime: string;
+  /** HH:MM, after the start */
+  endTime: string;
+  minHeadcount: number;
+  active: boolean;
+  createdAt?: string;
+  updatedAt?: string;
+}
+
+/** Someone on the premises now */
+export interface PresentUser {
+  userId: string;
+  displayName: string;
+  departmentId: string | null;
+  departmentName: string | null;
+  site: string | null;
+  /** Their latest punch today */
+  since: string;
+}
+
+/** A rule against live presence; payload of the "staffing-shortfall" event */
+export interface StaffingStatus {
+  ruleId: string;
+  ruleName: string;
+  departmentId: string | null;
+  departmentName: string | null;
+  site: string | null;
+  minHeadcount: number;
+  present: number;
+  /** Within the rule's weekdays and hours, and not a holiday for it */
+  inHours: boolean;
+  /** In hours and below the minimum */
+  short: boolean;
+  /** When the current shortfall was alerted, if it was */
+  alertedAt: string | null;
+}
+
+export interface StaffingCheck {
+  checkedAt: string;
+  /** Working day presence was counted for */
+  date: string;
+  rules: StaffingStatus[];
+  /** Rules alerted by this check */
+  alerted: string[];
+  /** Webhook calls that failed; they are retried from the delivery log */
+  failedDeliveries: number;
+}
+
+/** A user with an enrollment expiry date */
+export interface EnrollmentExpiry {
+  userId: string;
+  displayName: string;
+  classification: UserClassification;
+  status: string;
+  /** Last valid day (YYYY-MM-DD) */
+  expiresAt: string;
+  /** When the enrollment was removed from every device */
+  removedAt: string | null;
+  /** Devices the enrollment couldn't be removed from yet */
+  failedDevices: number;
+}
+
+/** What became of an expired enrollment on one device */
+export interface EnrollmentRemoval {
+  userId: string;
+  deviceId: string;
+  deviceName: string;
+  status: 'removed' | 'not_enrolled' | 'failed';
+  error: string | null;
+}
+
+export interface ExpiryRun {
+  checkedAt: string;
+  /** Users past their expiry date whose enrollment wasn't gone everywhere */
+  expired: number;
+  /** Users this run set inactive */
+  deactivated: string[];
+  removals: EnrollmentRemoval[];
+  /** Users whose enrollment is now gone from every device */
+  completed: string[];
+}
+
+/** Remote work settings ("remoteWork" setting) */
+export interface RemoteWorkSettings {
+  /** Marked days only count once the user punched through the API that day */
+  requirePunch: boolean;
+}
+
+/** A day a user worked remotely */
+export interface RemoteDay {
+  userId: string;
+  displayName: string;
+  date: string;
+  note: string | null;
+  markedBy: string | null;
+  createdAt: string;
+  /** Whether the day's summary has status "remote"; false while it waits for an API punch */
+  counted: boolean;
+}
+
+export interface RemoteMarkResult {
+  /** User/date pairs marked */
+  marked: number;
+  /** Marked days now counted as remote */
+  counted: number;
+  /** Marked days waiting for an API punch */
+  awaitingPunch: number;
+}
+
+/** One connection test, info read or sync against a device */
+export interface DeviceAttempt {
+  id: number;
+  operation: 'connection_test' | 'device_info' | 'sync';
+  success: boolean;
+  latencyMs: number;
+  error: string | null;
+  attemptedAt: string;
+}
+
+/** A device's recent connection history */
+export interface DeviceDiagnostics {
+  deviceId: string;
+  deviceName: string;
+  ip: string;
+  lastError: string | null;
+  lastErrorAt: string | null;
+  lastSuccessAt: string | null;
+  lastSyncAt: string | null;
+  /** Attempts within the window */
+  attempts: number;
+  failures: number;
+  /** Failed attempts in a row, most recent first */
+  consecutiveFailures: number;
+  /** Of successful attempts; null without any */
+  averageLatencyMs: number | null;
+  maxLatencyMs: number | null;
+  /** Newest first */
+  recentFailures: DeviceAttempt[];
+  /** Successful attempts, oldest first */
+  latencyHistory: DeviceAttempt[];
+}
+
+/** Record counts of a sync */
+export interface SyncCounts {
+  usersFetched: number;
+  /** Logs read from the device, before the date filter */
+  logsFetched: number;
+  /** Logs left after the date filter */
+  logsInRange: number;
+  usersAdded: number;
+  usersSynced: number;
+  logsAdded: number;
+  logsDeduplicated: number;
+  quarantined: number;
+  summaryDates: number;
+}
+
+/** The structured result of one sync */
+export interface SyncReport {
+  deviceId: string;
+  startedAt: string;
+  finishedAt: string;
+  success: boolean;
+  dryRun: boolean;
+  scheduled: boolean;
+  mode: 'latest' | 'days' | 'range' | null;
+  /** Connection the fetch went over */
+  transport: 'tcp' | 'udp' | 'demo' | null;
+  /** Whether users and logs had to be fetched separately after the combined fetch failed */
+  fallback: boolean;
+  counts: SyncCounts;
+  /** Milliseconds per phase ("fetch", "users", "logs", "summaries", ...) */
+  durationsMs: Record<string, number>;
+  /** Truncated to 50 messages of 500 characters when recorded */
+  warnings: string[];
+  errors: string[];
+}
+
+/** A recorded sync */
+export interface SyncHistoryEntry {
+  id: number;
+  deviceId: string;
+  deviceName: string;
+  startedAt: string;
+  finishedAt: string;
+  success: boolean;
+  report: SyncReport;
+}
+
+/** Punch ledger settings (`ledger` setting) */
+export interface LedgerSettings {
+  /** Seal newly ingested punches into the hash chain */
+  enabled: boolean;
+}
+
+/** A sealed batch of punches */
+export interface LedgerBatch {
+  id: number;
+  rowCount: number;
+  /** SHA-256 over the batch's punches, in ID order */
+  rowsHash: string;
+  /** Hash of the batch before; zeros for the first */
+  prevHash: string;
+  hash: string;
+  sealedAt: string;
+}
+
+export interface SealResult {
+  batches: LedgerBatch[];
+  rowsSealed: number;
+}
+
+/** A batch that no longer matches what was sealed */
+export interface BrokenBatch {
+  batchId: number;
+  sealedAt: string;
+  reason: 'rows_missing' | 'rows_altered' | 'chain_broken';
+  expectedRows: number;
+  foundRows: number;
+  /** Up to 20 IDs of the punches that are gone */
+  missingLogIds: string[];
+}
+
+/** Sealed punches a data migration or restore rewrote or removed, hashed as they were afterwards */
+export interface LedgerAmendment {
+  id: number;
+  batchId: number;
+  /** Punches of the batch left after the change */
+  rowCount: number;
+  rowsHash: string;
+  /** What made the change */
+  reason: string;
+  /** Hash of the amendment before; zeros for the first */
+  prevHash: string;
+  hash: string;
+  amendedAt: string;
+}
+
+export interface LedgerVerification {
+  verifiedAt: string;
+  enabled: boolean;
+  /** No batch is broken or amended */
+  intact: boolean;
+  batches: number;
+  rows: number;
+  /** Hash of the latest batch */
+  headHash: string | null;
+  broken: BrokenBatch[];
+  /** Batches that no longer match their seal but match their latest amendment, as that amendment */
+  amended: LedgerAmendment[];
+  /** Punches stored since the last seal */
+  unsealedRows: number;
+}
+
+/** Reporting period settings (`periods` setting) */
+export interface PeriodSettings {
+  /** 0 (Sunday) to 6 (Saturday) */
+  firstDayOfWeek: number;
+  kind: 'calendar_month' | 'custom_start' | 'biweekly';
+  /** Day of the month periods start on (1-28), for custom_start */
+  startDay: number;
+  /** First day of any one bi-weekly period (YYYY-MM-DD), for biweekly */
+  anchorDate: string | null;
+}
+
+/** One reporting period */
+export interface PayPeriod {
+  /** "YYYY-MM" for monthly periods (the month they end in), the start date for bi-weekly ones */
+  key: string;
+  /** e.g. "October 2026" or "October 2026 (26 Sep – 25 Oct)" */
+  label: string;
+  startDate: string;
+  endDate: string;
+}
+
+/** Pay estimation defaults (`payEstimation` setting) */
+export interface PayEstimationSettings {
+  /** ISO 4217 code for rates without their own currency */
+  currency: string;
+  dailyOvertimeAfterHours: number;
+  /** Regular hours a week before the rest are overtime, if capped */
+  weeklyOvertimeAfterHours: number | null;
+  overtimeMultiplier: number;
+  /** Multiplier for hours worked on weekends and holidays */
+  restDayMultiplier: number;
+}
+
+/** A user's hourly rate; unset fields fall back to the settings */
+export interface PayRate {
+  userId: string;
+  hourlyRate: number;
+  overtimeMultiplier?: number | null;
+  restDayMultiplier?: number | null;
+  currency?: string | null;
+  updatedAt?: string;
+}
+
+export interface UserPayEstimate {
+  userId: string;
+  name: string;
+  employeeCode: string | null;
+  currency: string;
+  hourlyRate: number;
+  overtimeMultiplier: number;
+  restDayMultiplier: number;
+  daysWorked: number;
+  regularHours: number;
+  overtimeHours: number;
+  restDayHours: number;
+  gross: number;
+}
+
+export interface CurrencyTotal {
+  currency: string;
+  users: number;
+  gross: number;
+}
+
+/** Gross pay estimates for a period; totals are per currency, never converted */
+export interface PayEstimate {
+  period: DateRange;
+  users: UserPayEstimate[];
+  totals: CurrencyTotal[];
+  /** Names of users who worked in the period but have no rate */
+  usersWithoutRate: string[];
+}
+
+/** A minimum break once a shift runs longer than some hours */
+export interface BreakRule {
+  afterHours: number;
+  minBreakMinutes: number;
+}
+
+/** Break compliance settings (`breakRules` setting) */
+export interface BreakRules {
+  /** Run the scheduled check over the last week */
+  enabled: boolean;
+  /** The strictest rule a shift falls under applies */
+  rules: BreakRule[];
+  /** Gaps shorter than this don't count as a break */
+  minCountedBreakMinutes: number;
+}
+
+export interface BreakCheckResult {
+  daysChecked: number;
+  /** Including ones already in the queue */
+  violations: number;
+  newExceptions: number;
+}
+
+/** Working-time limits (`workingTime` setting); a limit left null isn't checked */
+export interface WorkingTimeSettings {
+  /** Run the background check; the report works either way */
+  enabled: boolean;
+  maxDailyHours: number | null;
+  maxWeeklyHours: number | null;
+  /** Minimum hours between a check-out and the next check-in */
+  minRestHours: number | null;
+  /** URL posted a JSON alert for each new violation; empty for none */
+  webhookUrl: string;
+}
+
+export interface WorkingTimeViolation {
+  kind: 'max_daily_hours' | 'max_weekly_hours' | 'min_rest';
+  userId: string;
+  deviceUserId: string | null;
+  userName: string;
+  employeeCode: string | null;
+  /** The day; the first day of the week for weekly hours */
+  date: string;
+  /** Hours worked, or hours of rest for min_rest */
+  hours: number;
+  limitHours: number;
+}
+
+export interface WorkingTimeReport {
+  period: DateRange;
+  settings: WorkingTimeSettings;
+  usersChecked: number;
+  shiftsChecked: number;
+  violations: WorkingTimeViolation[];
+}
+
+/** A punch as the greeter screen shows it */
+export interface RecentPunch {
+  logId: string;
+  deviceId: string;
+  deviceName: string | null;
+  deviceUserId: string;
+  /** Null when the device user isn't linked to a profile */
+  userId: string | null;
+  /** Display name, else the device user ID */
+  name: string;
+  departmentName: string | null;
+  timestamp: string;
+  punchType: number | null;
+  /** Data URL of the user's photo, if there is one */
+  photo: string | null;
+}
+
+/** What happened to one name on one device */
+export interface UserNameResult {
+  deviceUserId: string;
+  name: string;
+  /** `skipped` when the device can't store the name (too long, or non-Latin on older firmware) */
+  status: 'updated' | 'unchanged' | 'not_on_device' | 'skipped' | 'failed';
+  error: string | null;
+}
+
+export interface DeviceNamesResult {
+  deviceId: string;
+  deviceName: string;
+  /** False when the device couldn't be reached or any write failed */
+  success: boolean;
+  error: string | null;
+  users: UserNameResult[];
+  /** Delivery log entry retrying what didn't go through */
+  retryDeliveryId: number | null;
+}
+
+export interface NamePushReport {
+  /** Names pushed to each device */
+  names: number;
+  updated: number;
+  failed: number;
+  devices: DeviceNamesResult[];
+}
+
+/** A door controller event as pushed by a connector */
+export interface NewAccessEvent {
+  /** Panel-local time, ISO 8601 or `YYYY-MM-DD HH:MM:SS` */
+  timestamp: string;
+  /** The panel's PIN, matching the device user ID */
+  deviceUserId?: string | null;
+  cardNumber?: string | null;
+  door?: string | null;
+  event?: string | null;
+  direction?: 'in' | 'out' | null;
+}
+
+export interface AccessEvent {
+  id: string;
+  /** Name of the panel or export the event came from */
+  source: string;
+  timestamp: string;
+  deviceUserId: string | null;
+  cardNumber: string | null;
+  door: string | null;
+  event: string | null;
+  direction: 'in' | 'out' | null;
+}
+
+export interface AccessImportResult {
+  imported: number;
+  /** Events already stored from an earlier import */
+  duplicates: number;
+  errors: { line: number; message: string }[];
+}
+
+/** An access event with the punch it was paired with */
+export interface ReconciledEvent {
+  event: AccessEvent;
+  userId: string | null;
+  displayName: string | null;
+  punchLogId: string | null;
+  punchTimestamp: string | null;
+  punchDeviceName: string | null;
+  /** Punch time minus event time */
+  offsetSeconds: number | null;
+  status: 'matched' | 'no_punch' | 'unknown_person';
+}
+
+export interface UnmatchedPunch {
+  logId: string;
+  userId: string;
+  displayName: string;
+  deviceUserId: string;
+  deviceName: string | null;
+  timestamp: string;
+}
+
+export interface AccessReconciliation {
+  startDate: string;
+  endDate: string;
+  windowMinutes: number;
+  matched: number;
+  noPunch: number;
+  unknownPerson: number;
+  events: ReconciledEvent[];
+  /** Punches by people with access events in the period that no event was paired with */
+  unmatchedPunches: UnmatchedPunch[];
+}
+
+/** Write-ahead log watchdog settings (`walWatchdog` setting) */
+export interface WalSettings {
+  enabled: boolean;
+  /** Minutes between checks of the WAL size */
+  checkMinutes: number;
+  /** WAL size (MB) above which an idle checkpoint also truncates the file */
+  truncateMb: number;
+  /** WAL size (MB) logged as a warning, and checkpointed even while work is running */
+  alertMb: number;
+}
+
+/** One check of the WAL, and the checkpoint it ran if any */
+export interface WalCheck {
+  checkedAt: string;
+  mode: 'passive' | 'truncate' | 'none';
+  /** Nothing was writing to the database */
+  idle: boolean;
+  walBytesBefore: number;
+  walBytesAfter: number;
+  logFrames: number;
+  checkpointedFrames: number;
+  /** Another connection kept the checkpoint from finishing */
+  busy: boolean;
+  durationMs: number;
+  error: string | null;
+}
+
+/** WAL size now and what the watchdog has done since the app started */
+export interface WalStatus {
+  settings: WalSettings;
+  walBytes: number;
+  databaseBytes: number;
+  /** Largest WAL seen since the app started */
+  peakWalBytes: number;
+  checkpoints: number;
+  truncations: number;
+  busyCheckpoints: number;
+  /** Newest first */
+  recent: WalCheck[];
+}
+
+/** Plain-text punch archive settings (`punchArchive` setting) */
+export interface PunchArchiveSettings {
+  enabled: boolean;
+  format: 'ndjson' | 'csv';
+  /** Folder for the files; Documents/HorusAttendance/archive when unset */
+  directory: string | null;
+}
+
+export interface ArchiveRun {
+  /** File appended to, if there was anything to append */
+  path: string | null;
+  rowsAppended: number;
+  sha256: string | null;
+}
+
+/** A monthly archive file */
+export interface ArchiveFile {
+  path: string;
+  /** YYYY-MM the punches were ingested in */
+  month: string;
+  format: 'ndjson' | 'csv';
+  fileSize: number;
+  /** Checksum recorded next to the file */
+  sha256: string | null;
+  /** The file still matches its recorded checksum */
+  checksumOk: boolean | null;
+}
+
+// ============================================================================
+// Backup Commands
+// ============================================================================
+
+/**
+ * Export database backup to a file
+ * @param destination Optional destination directory path
+ * @returns BackupResult with file path and size
+ */
+export async function exportBackup(destination?: string): Promise<BackupResult> {
+  return invoke<BackupResult>('export_backup', { destination });
+}
+
+/**
+ * Restore database from a backup file
+ * @param backupPath Path to the backup file
+ * @param options Optional selective mode; defaults to a full restore (requires app restart)
+ * @returns RestoreResult indicating success or failure
+ */
+export async function restoreBackup(backupPath: string, options?: RestoreOptions): Promise<RestoreResult> {
+  return invoke<RestoreResult>('restore_backup', { backupPath, options });
+}
+
+/**
+ * Get the default backup directory path
+ * @returns Path to the backup directory
+ */
+export async function getBackupDirectory(): Promise<string> {
+  return invoke<string>('get_backup_directory');
+}
+
+/**
+ * List available backup files
+ * @returns Array of backup filenames
+ */
+export async function listBackups(): Promise<string[]> {
+  return invoke<string[]>('list_backups');
+}
+
+/**
+ * Get the application version
+ * @returns Version string
+ */
+export async function getAppVersion(): Promise<string> {
+  return invoke<string>('get_app_version');
+}
+
+/**
+ * Reset the database by deleting all data
+ * Creates a backup before reset
+ * @returns RestoreResult indicating success or failure
+ */
+export async function resetDatabase(): Promise<RestoreResult> {
+  try {
+    // First create a backup
+    await invoke<BackupResult>('export_backup', { destination: null });
+    
+    // Then flush all tables via SQL (this works even with open connection)
+    const { flushDatabase } = await import('./database');
+    await flushDatabase();
+    
+    return { success: true, error: null };
+  } catch (error) {
+    return { 
+      success: false, 
+      error: error instanceof Error ? error.message : String(error) 
+    };
+  }
+}
+
+/**
+ * Export the database as a plain-text SQL dump (schema + data)
+ * @param destination Optional destination file path
+ * @param options Optional table selection / data-only mode
+ * @returns BackupResult with file path and size
+ */
+export async function exportSqlDump(destination?: string, options?: SqlDumpOptions): Promise<BackupResult> {
+  return invoke<BackupResult>('export_sql_dump', { destination, options });
+}
+
+/**
+ * Restore the database from a full SQL dump. The app must be restarted afterwards.
+ * @param dumpPath Path to the .sql file
+ * @returns RestoreResult indicating success or failure
+ */
+export async function importSqlDump(dumpPath: string): Promise<RestoreResult> {
+  return invoke<RestoreResult>('import_sql_dump', { dumpPath });
+}
+
+/**
+ * Check whether a backup can be restored by this app version
+ * @param backupPath Path to the backup file
+ * @returns BackupCompatibility with schema/app versions and any warnings
+ */
+export async function checkBackupCompatibility(backupPath: string): Promise<BackupCompatibility> {
+  return invoke<BackupCompatibility>('check_backup_compatibility', { backupPath });
+}
+
+/**
+ * Snapshot the tables an operation is about to change so it can be undone
+ * @param label Description of the operation, e.g. "Delete device Front Door"
+ * @param tables Tables the operation modifies
+ * @returns The recorded snapshot
+ */
+export async function createOperationSnapshot(label: string, tables: string[]): Promise<OperationSnapshot> {
+  return invoke<OperationSnapshot>('create_operation_snapshot', { label, tables });
+}
+
+/**
+ * List pre-operation snapshots, newest first
+ * @returns Array of snapshots
+ */
+export async function listOperationSnapshots(): Promise<OperationSnapshot[]> {
+  return invoke<OperationSnapshot[]>('list_operation_snapshots');
+}
+
+/**
+ * Roll back the most recent snapshotted operation
+ * @returns UndoResult; whole-database undos require an app restart
+ */
+export async function undoLastOperation(): Promise<UndoResult> {
+  return invoke<UndoResult>('undo_last_operation');
+}
+
+/**
+ * List backups with size, record counts, schema version and integrity, newest first
+ * @returns Array of BackupInfo
+ */
+export async function listBackupsDetailed(): Promise<BackupInfo[]> {
+  return invoke<BackupInfo[]>('list_backups_detailed');
+}
+
+/**
+ * Preview what restoring a backup would change without restoring it
+ * @param path - Path to the backup file
+ * @returns Row-count deltas per table and the logs each side holds, overall and per device
+ */
+export async function diffBackup(path: string): Promise<BackupDiff> {
+  return invoke<BackupDiff>('diff_backup', { path });
+}
+
+/**
+ * List registered backup targets
+ * @returns Array of BackupTarget
+ */
+export async function listBackupTargets(): Promise<BackupTarget[]> {
+  return invoke<BackupTarget[]>('list_backup_targets');
+}
+
+/**
+ * Register or update a named backup target
+ * @param target Target to save (empty id to create)
+ * @returns The saved target
+ */
+export async function saveBackupTarget(target: BackupTarget): Promise<BackupTarget> {
+  return invoke<BackupTarget>('save_backup_target', { target });
+}
+
+/**
+ * Remove a backup target
+ * @param targetId Target ID
+ */
+export async function deleteBackupTarget(targetId: string): Promise<void> {
+  return invoke<void>('delete_backup_target', { targetId });
+}
+
+/**
+ * Check a target is connected, writable and has enough free space
+ * @param targetId Target ID
+ * @returns BackupTargetStatus
+ */
+export async function testBackupTarget(targetId: string): Promise<BackupTargetStatus> {
+  return invoke<BackupTargetStatus>('test_backup_target', { targetId });
+}
+
+/**
+ * Back up to a registered target
+ * @param targetId Target ID
+ * @returns BackupResult with file path and size
+ */
+export async function backupToTarget(targetId: string): Promise<BackupResult> {
+  return invoke<BackupResult>('backup_to_target', { targetId });
+}
+
+/**
+ * Subscribe to warnings about scheduled backup targets that are unavailable
+ * @param handler Called with the failing target's status
+ * @returns Function to unsubscribe
+ */
+export async function onBackupTargetUnavailable(
+  handler: (status: BackupTargetStatus) => void
+): Promise<UnlistenFn> {
+  return listen<BackupTargetStatus>('backup-target-unavailable', (event) => handler(event.payload));
+}
+
+/**
+ * Package the database, backups and photos into one archive for moving to a new computer
+ * @param destination Optional destination file path (.zip)
+ * @returns TransferExportResult with file path, size and file count
+ */
+export async function exportFullTransferBundle(destination?: string): Promise<TransferExportResult> {
+  return invoke<TransferExportResult>('export_full_transfer_bundle', { destination });
+}
+
+/**
+ * Restore a transfer bundle on this computer. The app must be restarted afterwards.
+ * @param bundlePath Path to the bundle archive
+ * @returns TransferImportResult
+ */
+export async function importFullTransferBundle(bundlePath: string): Promise<TransferImportResult> {
+  return invoke<TransferImportResult>('import_full_transfer_bundle', { bundlePath });
+}
+
+// ============================================================================
+// Summary Commands
+// ============================================================================
+
+/**
+ * Recompute daily summaries from raw logs in the backend summary engine
+ * @param startDate First date (YYYY-MM-DD)
+ * @param endDate Last date (YYYY-MM-DD), inclusive
+ * @returns Counts of processed days/logs and any custom rule errors
+ */
+export async function recomputeSummaries(startDate: string, endDate: string): Promise<RecomputeResult> {
+  return invoke<RecomputeResult>('recompute_summaries', { startDate, endDate });
+}
+
+/**
+ * Validate a custom summary rules script without saving it
+ * @param script Rhai script source
+ */
+export async function validateCustomRules(script: string): Promise<RuleValidationResult> {
+  return invoke<RuleValidationResult>('validate_custom_rules', { script });
+}
+
+/**
+ * Validate and save the custom summary rules script (empty script removes it)
+ * @param script Rhai script source
+ */
+export async function saveCustomRules(script: string): Promise<RuleValidationResult> {
+  return invoke<RuleValidationResult>('save_custom_rules', { script });
+}
+
+/**
+ * Engine used to recompute summaries (set-based unless changed)
+ */
+export async function getSummaryEngine(): Promise<SummaryEngine> {
+  return invoke<SummaryEngine>('get_summary_engine');
+}
+
+/**
+ * Choose the engine used to recompute summaries
+ * @param engine 'set_based' or 'row_by_row'
+ */
+export async function setSummaryEngine(engine: SummaryEngine): Promise<void> {
+  return invoke<void>('set_summary_engine', { engine });
+}
+
+/**
+ * Time both summary engines on a date range without keeping what they write
+ * @param startDate First date (YYYY-MM-DD)
+ * @param endDate Last date (YYYY-MM-DD), inclusive
+ * @param runs Runs per engine (default 3, at most 10); the best is reported
+ */
+export async function benchmarkRecompute(startDate: string, endDate: string, runs?: number): Promise<RecomputeBenchmark> {
+  return invoke<RecomputeBenchmark>('benchmark_recompute', { startDate, endDate, runs });
+}
+
+// ============================================================================
+// Export Commands
+// ============================================================================
+
+/**
+ * Export raw logs and daily summaries for a period as Parquet files
+ * (readable by DuckDB, Power BI, pandas)
+ * @param startDate First date (YYYY-MM-DD)
+ * @param endDate Last date (YYYY-MM-DD), inclusive
+ * @param destination Optional destination directory; defaults to Documents/HorusAttendance/exports
+ * @param password Optional password (8+ characters); both files then go into one AES-256 encrypted zip
+ */
+export async function exportParquet(
+  startDate: string,
+  endDate: string,
+  destination?: string,
+  password?: string
+): Promise<ExportResult> {
+  return invoke<ExportResult>('export_parquet', { startDate, endDate, destination, password });
+}
+
+/**
+ * Convert Gregorian dates to Hijri using the configured day adjustment
+ * @param dates Dates (YYYY-MM-DD)
+ * @returns Array of HijriDate in the same order
+ */
+export async function convertDatesToHijri(dates: string[]): Promise<HijriDate[]> {
+  return invoke<HijriDate[]>('convert_dates_to_hijri', { dates });
+}
+
+/**
+ * Get the Gregorian date range covered by a Hijri month
+ * @param year Hijri year
+ * @param month Hijri month (1-12)
+ */
+export async function getHijriMonthRange(year: number, month: number): Promise<DateRange> {
+  return invoke<DateRange>('get_hijri_month_range', { year, month });
+}
+
+/**
+ * Get summary status counts per month
+ * @param startDate First date (YYYY-MM-DD)
+ * @param endDate Last date (YYYY-MM-DD), inclusive
+ * @param calendar Group by Hijri month, Gregorian month or reporting period; defaults to
+ *   Hijri months with the calendar setting, else to reporting periods
+ */
+export async function getSummaryMonthTotals(
+  startDate: string,
+  endDate: string,
+  calendar?: 'hijri' | 'gregorian' | 'period'
+): Promise<MonthTotals[]> {
+  return invoke<MonthTotals[]>('get_summary_month_totals', { startDate, endDate, calendar });
+}
+
+/**
+ * Export a fixed-layout monthly attendance register (e.g. the labor office sheet)
+ * @param template Sheet template name, e.g. 'labor_office_monthly'
+ * @param year Gregorian year
+ * @param month Month (1-12)
+ * @param format 'xlsx' or 'pdf'
+ * @param departmentId Optional department to limit the register to
+ * @param destination Optional destination directory; defaults to Documents/HorusAttendance/exports
+ * @param password Optional password (8+ characters); the register is then an AES-256 encrypted
+ *   zip, and an XLSX sheet is also locked for editing
+ */
+export async function exportAttendanceSheet(
+  template: SheetTemplate,
+  year: number,
+  month: number,
+  format: 'xlsx' | 'pdf',
+  departmentId?: string,
+  destination?: string,
+  password?: string
+): Promise<ExportedFile> {
+  return invoke<ExportedFile>('export_attendance_sheet', {
+    template,
+    year,
+    month,
+    format,
+    departmentId,
+    destination,
+    password,
+  });
+}
+
+/**
+ * Export every user on the saved devices with their fingerprint and face
+ * template counts and whether a card is set, to audit who still has to enroll
+ * @param format 'csv' or 'xlsx'
+ * @param deviceIds Optional devices to limit the export to; all saved devices otherwise
+ * @param destination Optional destination directory; defaults to Documents/HorusAttendance/exports
+ * @param password Optional password (8+ characters); the file is then an AES-256 encrypted zip
+ */
+export async function exportDeviceUsers(
+  format: 'csv' | 'xlsx',
+  deviceIds?: string[],
+  destination?: string,
+  password?: string
+): Promise<DeviceUserExport> {
+  return invoke<DeviceUserExport>('export_device_users', { format, deviceIds, destination, password });
+}
+
+/**
+ * Saved fixed-width export layouts
+ */
+export async function listExportLayouts(): Promise<FixedWidthLayout[]> {
+  return invoke<FixedWidthLayout[]>('list_export_layouts');
+}
+
+/**
+ * Create (empty id) or update a fixed-width layout
+ */
+export async function saveExportLayout(layout: FixedWidthLayout): Promise<FixedWidthLayout> {
+  return invoke<FixedWidthLayout>('save_export_layout', { layout });
+}
+
+export async function deleteExportLayout(id: string): Promise<boolean> {
+  return invoke<boolean>('delete_export_layout', { id });
+}
+
+/**
+ * The first records a layout (saved or not) produces for a period
+ * @param limit Records to render (default 20)
+ */
+export async function previewFixedWidth(
+  layout: FixedWidthLayout,
+  startDate: string,
+  endDate: string,
+  departmentId?: string,
+  limit?: number
+): Promise<string[]> {
+  return invoke<string[]>('preview_fixed_width', { layout, startDate, endDate, departmentId, limit });
+}
+
+/**
+ * Export summaries as a fixed-width text file laid out by a saved layout.
+ * Fails, writing nothing, if a value doesn't fit its column.
+ * @param destination Optional destination directory; defaults to Documents/HorusAttendance/exports
+ * @param password Optional password (8+ characters); the export is then an AES-256 encrypted zip
+ */
+export async function exportFixedWidth(
+  layoutId: string,
+  startDate: string,
+  endDate: string,
+  departmentId?: string,
+  destination?: string,
+  password?: string
+): Promise<ExportedFile> {
+  return invoke<ExportedFile>('export_fixed_width', {
+    layoutId,
+    startDate,
+    endDate,
+    departmentId,
+    destination,
+    password,
+  });
+}
+
+// ============================================================================
+// Template Commands
+// ============================================================================
+
+/**
+ * List templates, optionally of one kind
+ * @param kind Optional template kind filter
+ * @returns Array of Template
+ */
+export async function listTemplates(kind?: TemplateKind): Promise<Template[]> {
+  return invoke<Template[]>('list_templates', { kind });
+}
+
+/**
+ * Create or update a template; fails if the body doesn't parse
+ * @param template Template to save (empty id to create)
+ * @returns The saved template
+ */
+export async function saveTemplate(template: Template): Promise<Template> {
+  return invoke<Template>('save_template', { template });
+}
+
+/**
+ * Delete a template
+ * @param templateId Template ID
+ */
+export async function deleteTemplate(templateId: string): Promise<void> {
+  return invoke<void>('delete_template', { templateId });
+}
+
+/**
+ * Render a stored template by name
+ * @param name Template name
+ * @param context Period, totals and extra values
+ * @returns Rendered text
+ */
+export async function renderTemplate(name: string, context?: TemplateContext): Promise<string> {
+  return invoke<string>('render_template', { name, context });
+}
+
+/**
+ * Render an unsaved template body for previews
+ * @param body Template source
+ * @param context Period, totals and extra values
+ * @returns Rendered text
+ */
+export async function previewTemplate(body: string, context?: TemplateContext): Promise<string> {
+  return invoke<string>('preview_template', { body, context });
+}
+
+// ============================================================================
+// Analytics Commands
+// ============================================================================
+
+/**
+ * Get punch counts by weekday × hour for the dashboard heatmap
+ * @param dateRange Inclusive date range (YYYY-MM-DD)
+ * @param scope Optional department/user filter
+ * @param arrivalsOnly Count only each user's first punch of the day
+ */
+export async function getPunchHeatmap(
+  dateRange: DateRange,
+  scope?: AnalyticsScope,
+  arrivalsOnly?: boolean
+): Promise<PunchHeatmap> {
+  return invoke<PunchHeatmap>('get_punch_heatmap', { dateRange, scope, arrivalsOnly });
+}
+
+/**
+ * Get attendance KPIs for a period with the change from the previous period
+ * @param period Inclusive date range (YYYY-MM-DD)
+ * @param scope Optional department/user filter
+ */
+export async function getKpis(period: DateRange, scope?: AnalyticsScope): Promise<Kpis> {
+  return invoke<Kpis>('get_kpis', { period, scope });
+}
+
+/**
+ * Worked hours, late arrivals and absences of two periods per user and
+ * department, e.g. this quarter against the last
+ */
+export async function comparePeriods(
+  periodA: DateRange,
+  periodB: DateRange,
+  scope?: AnalyticsScope
+): Promise<PeriodComparison> {
+  return invoke<PeriodComparison>('compare_periods', { periodA, periodB, scope });
+}
+
+/**
+ * Worked hours, late arrivals and absences per cost center, each day booked
+ * to the cost center in effect for the user that day
+ */
+export async function getCostCenterTotals(period: DateRange, scope?: AnalyticsScope): Promise<CostCenterReport> {
+  return invoke<CostCenterReport>('get_cost_center_totals', { period, scope });
+}
+
+/**
+ * Report patterns suggestive of buddy punching for manual review
+ * @param dateRange Inclusive date range (YYYY-MM-DD)
+ * @param scope Optional department/user filter
+ * @param options Optional thresholds
+ */
+export async function getBuddyPunchingReport(
+  dateRange: DateRange,
+  scope?: AnalyticsScope,
+  options?: BuddyPunchOptions
+): Promise<BuddyPunchReport> {
+  return invoke<BuddyPunchReport>('get_buddy_punching_report', { dateRange, scope, options });
+}
+
+// ============================================================================
+// Exception Queue Commands
+// ============================================================================
+
+/**
+ * List exception queue entries, newest first
+ * @param status Optional status filter
+ * @param kind Optional kind filter
+ * @param limit Maximum entries (default 500)
+ */
+export async function listExceptions(status?: ExceptionStatus, kind?: string, limit?: number): Promise<AttendanceException[]> {
+  return invoke<AttendanceException[]>('list_exceptions', { status, kind, limit });
+}
+
+/**
+ * Resolve, dismiss or re-open an exception
+ * @param exceptionId Exception ID
+ * @param status New status
+ * @param note Optional resolution note
+ */
+export async function resolveException(exceptionId: string, status: ExceptionStatus, note?: string): Promise<void> {
+  return invoke<void>('resolve_exception', { exceptionId, status, note });
+}
+
+/**
+ * Run the anomaly checks now (impossible travel, excessive punches, invalid timestamps)
+ * @param startDate Optional first date (YYYY-MM-DD); all logs when omitted
+ * @param endDate Optional last date (YYYY-MM-DD), inclusive
+ */
+export async function runAnomalyCheck(startDate?: string, endDate?: string): Promise<AnomalyCheckResult> {
+  return invoke<AnomalyCheckResult>('run_anomaly_check', { startDate, endDate });
+}
+
+/**
+ * Listen for new entries queued by the background anomaly check
+ * @returns Function to unsubscribe
+ */
+export async function onExceptionsUpdated(
+  handler: (result: AnomalyCheckResult) => void
+): Promise<UnlistenFn> {
+  return listen<AnomalyCheckResult>('exceptions-updated', (event) => handler(event.payload));
+}
+
+// ============================================================================
+// Secret Commands
+// ============================================================================
+
+/**
+ * Store a device comm key in the OS keychain
+ * @param deviceId Device ID
+ * @param commKey Comm key; empty removes the stored key
+ * @returns The value to save as the device's commKey
+ */
+export async function storeDeviceCommKey(deviceId: string, commKey: string): Promise<StoredSecret> {
+  return invoke<StoredSecret>('store_device_comm_key', { deviceId, commKey });
+}
+
+/**
+ * Remove a device comm key from the OS keychain
+ * @param deviceId Device ID
+ */
+export async function deleteDeviceCommKey(deviceId: string): Promise<void> {
+  return invoke<void>('delete_device_comm_key', { deviceId });
+}
+
+/**
+ * Store an API credential in the OS keychain
+ * @param name Credential name
+ * @param value Secret value
+ */
+export async function setCredential(name: string, value: string): Promise<void> {
+  return invoke<void>('set_credential', { name, value });
+}
+
+/**
+ * Remove an API credential from the OS keychain
+ * @param name Credential name
+ */
+export async function deleteCredential(name: string): Promise<void> {
+  return invoke<void>('delete_credential', { name });
+}
+
+/**
+ * Check whether an API credential is stored (values are never returned)
+ * @param name Credential name
+ */
+export async function hasCredential(name: string): Promise<boolean> {
+  return invoke<boolean>('has_credential', { name });
+}
+
+/**
+ * Move plaintext comm keys from the database into the OS keychain
+ */
+export async function migrateSecretsToKeychain(): Promise<SecretMigrationResult> {
+  return invoke<SecretMigrationResult>('migrate_secrets_to_keychain');
+}
+
+// ============================================================================
+// File Stream Commands
+// ============================================================================
+
+/** Bytes per appendChunk call when streaming a file */
+export const FILE_STREAM_CHUNK_SIZE = 1024 * 1024;
+
+/**
+ * Start a chunked write
+ * @param path Full file path
+ * @returns Stream ID
+ */
+export async function openFileStream(path: string): Promise<string> {
+  return invoke<string>('open_file_stream', { path });
+}
+
+/**
+ * Append a chunk to an open stream
+ * @param streamId Stream ID from openFileStream
+ * @param chunk Bytes to append
+ * @returns Bytes written so far
+ */
+export async function appendChunk(streamId: string, chunk: Uint8Array): Promise<number> {
+  return invoke<number>('append_chunk', { streamId, base64Data: toBase64(chunk) });
+}
+
+/**
+ * Finish a stream, or discard the partial file
+ * @param streamId Stream ID from openFileStream
+ * @param abort Discard instead of saving
+ */
+export async function closeFileStream(streamId: string, abort?: boolean): Promise<StreamedFile | null> {
+  return invoke<StreamedFile | null>('close_file_stream', { streamId, abort });
+}
+
+/**
+ * Write bytes to a file in chunks, so large exports are never base64-encoded in one piece
+ * @param path Full file path
+ * @param data File contents
+ */
+export async function writeFileStreamed(path: string, data: Uint8Array): Promise<StreamedFile> {
+  const streamId = await openFileStream(path);
+  try {
+    for (let offset = 0; offset < data.length; offset += FILE_STREAM_CHUNK_SIZE) {
+      await appendChunk(streamId, data.subarray(offset, offset + FILE_STREAM_CHUNK_SIZE));
+    }
+  } catch (error) {
+    await closeFileStream(streamId, true).catch(() => undefined);
+    throw error;
+  }
+  return (await closeFileStream(streamId))!;
+}
+
+function toBase64(bytes: Uint8Array): string {
+  // Convert in slices: spreading a whole chunk into fromCharCode overflows the stack
+  let binary = '';
+  for (let i = 0; i < bytes.length; i += 0x8000) {
+    binary += String.fromCharCode(...bytes.subarray(i, i + 0x8000));
+  }
+  return btoa(binary);
+}
+
+/**
+ * Pack files and folders into one zip archive
+ * @param paths Files or folders to include; each becomes a top-level entry
+ * @param dest Full path of the archive to create
+ * @param password Optional password (8+ characters) to encrypt every entry with AES-256
+ */
+export async function createZip(paths: string[], dest: string, password?: string): Promise<ZipResult> {
+  return invoke<ZipResult>('create_zip', { paths, dest, password });
+}
+
+// ============================================================================
+// API Server Commands
+// ============================================================================
+
+/**
+ * Start (or restart) the REST API server with the saved apiServer settings
+ */
+export async function startApiServer(): Promise<ApiServerStatus> {
+  return invoke<ApiServerStatus>('start_api_server');
+}
+
+/**
+ * Check the configured port (or `port`) and the firewall without starting
+ * the server
+ */
+export async function checkApiServerPort(port?: number): Promise<ApiPortReport> {
+  return invoke<ApiPortReport>('check_api_server_port', { port });
+}
+
+/**
+ * Stop the REST API server
+ */
+export async function stopApiServer(): Promise<void> {
+  return invoke<void>('stop_api_server');
+}
+
+/**
+ * Get whether the REST API server is running
+ */
+export async function getApiServerStatus(): Promise<ApiServerStatus> {
+  return invoke<ApiServerStatus>('get_api_server_status');
+}
+
+export async function getTlsSettings(): Promise<TlsSettings> {
+  return invoke<TlsSettings>('get_tls_settings');
+}
+
+/**
+ * Save the TLS mode; takes effect when the API server is next started
+ */
+export async function saveTlsSettings(settings: TlsSettings): Promise<void> {
+  return invoke('save_tls_settings', { settings });
+}
+
+/**
+ * The certificate in use and its fingerprint for pinning
+ */
+export async function getTlsStatus(): Promise<TlsStatus> {
+  return invoke<TlsStatus>('get_tls_status');
+}
+
+/**
+ * Replace the self-signed certificate; clients pinning the old one need the new fingerprint
+ */
+export async function regenerateTlsCertificate(): Promise<TlsStatus> {
+  return invoke<TlsStatus>('regenerate_tls_certificate');
+}
+
+/**
+ * REST API request, rate limit and lockout counters; null when the server isn't running
+ */
+export async function getApiMetrics(): Promise<ApiMetrics | null> {
+  return invoke<ApiMetrics | null>('get_api_metrics');
+}
+
+/**
+ * Issue a REST API token for an employee (e.g. for mobile punches)
+ * @param userId User ID; the user must have a device user ID
+ * @param label Optional label, e.g. the phone it is for
+ */
+export async function createUserToken(userId: string, label?: string): Promise<IssuedToken> {
+  return invoke<IssuedToken>('create_user_token', { userId, label });
+}
+
+/**
+ * Issue a manager REST API token, which can also read the users, summaries and
+ * registers of the given departments
+ * @param userId Manager the token is for
+ * @param departmentIds Departments it may read (at least one)
+ * @param label Optional label
+ */
+export async function createManagerToken(userId: string, departmentIds: string[], label?: string): Promise<IssuedToken> {
+  return invoke<IssuedToken>('create_manager_token', { userId, departmentIds, label });
+}
+
+/**
+ * Change the departments a manager token can read
+ * @param tokenId Token ID
+ * @param departmentIds Departments it may read (at least one)
+ */
+export async function setTokenDepartments(tokenId: string, departmentIds: string[]): Promise<UserToken> {
+  return invoke<UserToken>('set_token_departments', { tokenId, departmentIds });
+}
+
+/**
+ * List REST API tokens
+ * @param userId Optional user filter
+ */
+export async function listUserTokens(userId?: string): Promise<UserToken[]> {
+  return invoke<UserToken[]>('list_user_tokens', { userId });
+}
+
+/**
+ * Revoke a REST API token
+ * @param tokenId Token ID
+ */
+export async function revokeUserToken(tokenId: string): Promise<void> {
+  return invoke<void>('revoke_user_token', { tokenId });
+}
+
+/**
+ * Issue an integration API key
+ * @param name What the key is for, e.g. the payroll system
+ * @param scopes Any of 'users:read', 'attendance:read', 'reports:read', 'metrics:read'
+ */
+export async function createApiKey(name: string, scopes: string[]): Promise<IssuedApiKey> {
+  return invoke<IssuedApiKey>('create_api_key', { name, scopes });
+}
+
+/**
+ * List integration API keys, including revoked ones
+ */
+export async function listApiKeys(): Promise<ApiKey[]> {
+  return invoke<ApiKey[]>('list_api_keys');
+}
+
+/**
+ * Revoke an integration API key at once
+ * @param keyId Key ID
+ */
+export async function revokeApiKey(keyId: string): Promise<void> {
+  return invoke<void>('revoke_api_key', { keyId });
+}
+
+/**
+ * Replace an integration API key with a new one of the same name and scopes
+ * @param keyId Key ID
+ * @param graceHours Hours the old key keeps working (default 24, 0 to revoke at once)
+ */
+export async function rotateApiKey(keyId: string, graceHours?: number): Promise<IssuedApiKey> {
+  return invoke<IssuedApiKey>('rotate_api_key', { keyId, graceHours });
+}
+
+/**
+ * Create a one-time QR invite for pairing a companion app; the API server must be running
+ * @param userId Employee the paired app will act as
+ * @param ttlMinutes Minutes the invite stays valid (default 10)
+ */
+export async function createPairingInvite(userId: string, ttlMinutes?: number): Promise<PairingInvite> {
+  return invoke<PairingInvite>('create_pairing_invite', { userId, ttlMinutes });
+}
+
+/**
+ * Create signed links employees can open to fetch only their own monthly report
+ * @param year Year
+ * @param month Month (1-12)
+ * @param departmentId Optional department filter
+ * @param userId Optional single user
+ * @param ttlDays Days the links stay valid (default 30)
+ */
+export async function createSelfServiceLinks(
+  year: number,
+  month: number,
+  departmentId?: string,
+  userId?: string,
+  ttlDays?: number
+): Promise<SelfServiceLink[]> {
+  return invoke<SelfServiceLink[]>('create_self_service_links', { year, month, departmentId, userId, ttlDays });
+}
+
+/**
+ * Replace the report signing key, invalidating every self-service link issued so far
+ */
+export async function rotateReportSigningKey(): Promise<void> {
+  return invoke<void>('rotate_report_signing_key');
+}
+
+// ============================================================================
+// Data Migration Commands
+// ============================================================================
+
+/**
+ * Run pending Rust data migrations. Call after the database is loaded so
+ * the SQL migrations have been applied.
+ */
+export async function runDataMigrations(): Promise<DataMigrationReport> {
+  return invoke<DataMigrationReport>('run_data_migrations');
+}
+
+/**
+ * Status of every data migration
+ */
+export async function listDataMigrations(): Promise<DataMigrationStatus[]> {
+  return invoke<DataMigrationStatus[]>('list_data_migrations');
+}
+
+// ============================================================================
+// Startup Health Commands
+// ============================================================================
+
+/**
+ * Result of the startup health check (database, schema, WAL, backup folder).
+ * Pass refresh to run it again.
+ */
+export async function getStartupHealth(refresh?: boolean): Promise<StartupHealth> {
+  return invoke<StartupHealth>('get_startup_health', { refresh: refresh ?? null });
+}
+
+// ============================================================================
+// Activity Commands
+// ============================================================================
+
+/**
+ * Register write work (e.g. a device sync) so backup and restore wait for it.
+ * Rejects while a backup or restore is in progress. Pass the ID to endActivity.
+ */
+export async function beginActivity(label: string): Promise<number> {
+  return invoke<number>('begin_activity', { label });
+}
+
+/**
+ * Mark registered work as finished
+ */
+export async function endActivity(activityId: number): Promise<void> {
+  return invoke('end_activity', { activityId });
+}
+
+/**
+ * Running work, and the backup or restore holding new work off, if any
+ */
+export async function getActivityStatus(): Promise<ActivityStatus> {
+  return invoke<ActivityStatus>('get_activity_status');
+}
+
+/**
+ * Subscribe to the app shutting down: running work gets a short time to
+ * finish (end registered activity promptly) and new work is refused
+ * @returns Function to unsubscribe
+ */
+export async function onAppShuttingDown(handler: (report: ShutdownReport) => void): Promise<UnlistenFn> {
+  return listen<ShutdownReport>('app-shutting-down', (event) => handler(event.payload));
+}
+
+// ============================================================================
+// Device Events
+// ============================================================================
+
+/**
+ * Listen for sync waiting on a device whose menu is open on the terminal
+ * @returns Function to unsubscribe
+ */
+export async function onDeviceBusy(
+  handler: (event: DeviceBusyEvent) => void
+): Promise<UnlistenFn> {
+  return listen<DeviceBusyEvent>('device-busy', (event) => handler(event.payload));
+}
+
+/**
+ * Listen for progress of a device's initial import, sent after every pass
+ * @returns Function to unsubscribe
+ */
+export async function onInitialImportProgress(
+  handler: (status: InitialImportStatus) => void
+): Promise<UnlistenFn> {
+  return listen<InitialImportStatus>('initial-import-progress', (event) => handler(event.payload));
+}
+
+// ============================================================================
+// Shift Commands
+// ============================================================================
+
+/**
+ * Infer each active user's usual start and end time from past punches
+ * @param weeks How many weeks back to analyse (default 4)
+ * @param minDays Days a user needs before a shift is proposed (default 5)
+ * @returns Proposals, least consistent first
+ */
+export async function detectShifts(weeks?: number, minDays?: number): Promise<ShiftProposal[]> {
+  return invoke<ShiftProposal[]>('detect_shifts', { weeks, minDays });
+}
+
+/**
+ * List every assigned user shift
+ */
+export async function listUserShifts(): Promise<UserShift[]> {
+  return invoke<UserShift[]>('list_user_shifts');
+}
+
+/**
+ * Assign accepted shift proposals in bulk; recompute summaries afterwards
+ * to apply them to past days
+ * @returns Number of shifts assigned
+ */
+export async function acceptShiftProposals(shifts: ShiftAssignment[]): Promise<number> {
+  return invoke<number>('accept_shift_proposals', { shifts });
+}
+
+/**
+ * Assign shifts entered by hand
+ */
+export async function saveUserShifts(shifts: ShiftAssignment[]): Promise<void> {
+  return invoke<void>('save_user_shifts', { shifts });
+}
+
+/**
+ * Remove a user's shift so the global work hours apply again
+ */
+export async function deleteUserShift(userId: string): Promise<boolean> {
+  return invoke<boolean>('delete_user_shift', { userId });
+}
+
+// ============================================================================
+// Roster Commands
+// ============================================================================
+
+/**
+ * List planned shifts between two dates (inclusive)
+ */
+export async function listRoster(startDate: string, endDate: string): Promise<RosterEntry[]> {
+  return invoke<RosterEntry[]>('list_roster', { startDate, endDate });
+}
+
+/**
+ * Save planned shifts, replacing any for the same user and date
+ */
+export async function saveRosterEntries(entries: RosterEntryInput[]): Promise<void> {
+  return invoke<void>('save_roster_entries', { entries });
+}
+
+/**
+ * Delete a planned shift
+ */
+export async function deleteRosterEntry(id: string): Promise<boolean> {
+  return invoke<boolean>('delete_roster_entry', { id });
+}
+
+/**
+ * Import planned shifts from CSV text (employee,date,start,end[,note])
+ * @returns How many were imported and which lines were rejected
+ */
+export async function importRosterCsv(content: string): Promise<RosterImportResult> {
+  return invoke<RosterImportResult>('import_roster_csv', { content });
+}
+
+/**
+ * Planned versus actual attendance and daily coverage between two dates
+ */
+export async function getRosterVariance(startDate: string, endDate: string): Promise<RosterReport> {
+  return invoke<RosterReport>('get_roster_variance', { startDate, endDate });
+}
+
+// ============================================================================
+// Summary History Commands
+// ============================================================================
+
+/**
+ * Change history of day summaries between two dates (inclusive), oldest first
+ * @param userId Optional user to limit the history to
+ */
+export async function getSummaryHistory(
+  startDate: string,
+  endDate: string,
+  userId?: string
+): Promise<SummaryChange[]> {
+  return invoke<SummaryChange[]>('get_summary_history', { userId, startDate, endDate });
+}
+
+/**
+ * Export the change history of day summaries between two dates as CSV
+ * @param userId Optional user to limit the history to
+ * @param destination Optional destination directory; defaults to Documents/HorusAttendance/exports
+ * @param password Optional password (8+ characters); the export is then an AES-256 encrypted zip
+ */
+export async function exportSummaryHistory(
+  startDate: string,
+  endDate: string,
+  userId?: string,
+  destination?: string,
+  password?: string
+): Promise<ExportedFile> {
+  return invoke<ExportedFile>('export_summary_history', { userId, startDate, endDate, destination, password });
+}
+
+// ============================================================================
+// Quarantine Commands
+// ============================================================================
+
+/**
+ * Quarantined device records, newest first
+ * @param status Optional status to filter by
+ * @param deviceId Optional device to filter by
+ */
+export async function listQuarantined(
+  status?: QuarantinedRecord['status'],
+  deviceId?: string
+): Promise<QuarantinedRecord[]> {
+  return invoke<QuarantinedRecord[]>('list_quarantined', { status, deviceId });
+}
+
+/**
+ * Store a pending record with corrected values. Recompute summaries for a
+ * repaired log's date to include it.
+ */
+export async function repairQuarantined(repair: QuarantineRepair): Promise<QuarantinedRecord> {
+  return invoke<QuarantinedRecord>('repair_quarantined', { repair });
+}
+
+/**
+ * Discard pending records
+ * @returns How many were discarded
+ */
+export async function discardQuarantined(ids: number[]): Promise<number> {
+  return invoke<number>('discard_quarantined', { ids });
+}
+
+// ============================================================================
+// Raw Payload Commands
+// ============================================================================
+
+/**
+ * Get the raw payload retention settings
+ */
+export async function getRawPayloadSettings(): Promise<RawPayloadSettings> {
+  return invoke<RawPayloadSettings>('get_raw_payload_settings');
+}
+
+/**
+ * Save the raw payload retention settings. Payloads already kept stay until purged.
+ */
+export async function saveRawPayloadSettings(settings: RawPayloadSettings): Promise<void> {
+  return invoke<void>('save_raw_payload_settings', { settings });
+}
+
+/**
+ * Space taken by retained payloads, in total and per device
+ */
+export async function getRawPayloadUsage(): Promise<RawPayloadUsage> {
+  return invoke<RawPayloadUsage>('get_raw_payload_usage');
+}
+
+/**
+ * The device bytes of one log, or null when they were not kept
+ */
+export async function getRawPayload(
+  deviceId: string,
+  deviceUserId: string,
+  timestamp: string
+): Promise<RawPayload | null> {
+  return invoke<RawPayload | null>('get_raw_payload', { deviceId, deviceUserId, timestamp });
+}
+
+/**
+ * Delete retained payloads
+ * @param before Optional date (YYYY-MM-DD); only payloads kept before it are deleted
+ * @param deviceId Optional device to limit the purge to
+ * @returns How many were deleted
+ */
+export async function purgeRawPayloads(before?: string, deviceId?: string): Promise<number> {
+  return invoke<number>('purge_raw_payloads', { before, deviceId });
+}
+
+// ============================================================================
+// Device Pair Commands
+// ============================================================================
+
+/**
+ * Get every device pair
+ */
+export async function listDevicePairs(): Promise<DevicePair[]> {
+  return invoke<DevicePair[]>('list_device_pairs');
+}
+
+/**
+ * Pair two devices, or change the tolerance of a pair
+ */
+export async function saveDevicePair(pair: DevicePairInput): Promise<DevicePair> {
+  return invoke<DevicePair>('save_device_pair', { pair });
+}
+
+/**
+ * Unpair two devices. Exceptions already queued stay.
+ */
+export async function deleteDevicePair(pairId: number): Promise<boolean> {
+  return invoke<boolean>('delete_device_pair', { pairId });
+}
+
+/**
+ * Cross-check the punches of every pair, or of one, between two dates (inclusive).
+ * Punches found on only one terminal are added to the exception queue as 'unpaired_punch'.
+ */
+export async function reconcileDevicePairs(
+  startDate: string,
+  endDate: string,
+  pairId?: number
+): Promise<PairReconciliation[]> {
+  return invoke<PairReconciliation[]>('reconcile_device_pairs', { startDate, endDate, pairId });
+}
+
+// ============================================================================
+// Closure Email Commands
+// ============================================================================
+
+/**
+ * Get the closure email settings
+ */
+export async function getClosureSettings(): Promise<ClosureSettings> {
+  return invoke<ClosureSettings>('get_closure_settings');
+}
+
+/**
+ * Save the closure email settings
+ */
+export async function saveClosureSettings(settings: ClosureSettings): Promise<void> {
+  return invoke<void>('save_closure_settings', { settings });
+}
+
+/**
+ * Send a test message with the saved mail settings
+ */
+export async function sendTestEmail(to: string): Promise<void> {
+  return invoke<void>('send_test_email', { to });
+}
+
+/**
+ * Email employees their attendance for a month now. Those already sent it are skipped
+ * unless resend is set. Disputes arrive in the exception queue as 'attendance_dispute'.
+ */
+export async function closeAttendanceMonth(year: number, month: number, resend?: boolean): Promise<ClosureRun> {
+  return invoke<ClosureRun>('close_attendance_month', { year, month, resend });
+}
+
+/**
+ * Who was sent a month's closure and what happened
+ */
+export async function listClosures(year: number, month: number): Promise<AttendanceClosure[]> {
+  return invoke<AttendanceClosure[]>('list_closures', { year, month });
+}
+
+// ============================================================================
+// Delivery Log Commands
+// ============================================================================
+
+/**
+ * Get logged outbound deliveries, newest first
+ * @param status Optional status filter
+ * @param channel Optional channel filter
+ * @param limit Maximum rows (default 200)
+ */
+export async function listDeliveries(
+  status?: Delivery['status'],
+  channel?: Delivery['channel'],
+  limit?: number
+): Promise<Delivery[]> {
+  return invoke<Delivery[]>('list_deliveries', { status, channel, limit });
+}
+
+/**
+ * Get delivery counts by status
+ */
+export async function getDeliveryStats(): Promise<DeliveryStats> {
+  return invoke<DeliveryStats>('get_delivery_stats');
+}
+
+/**
+ * Send failed deliveries again now. Failed deliveries are also retried
+ * automatically with backoff; listen for 'deliveries-updated'.
+ */
+export async function retryDeliveries(ids: number[]): Promise<RetryOutcome[]> {
+  return invoke<RetryOutcome[]>('retry_deliveries', { ids });
+}
+
+// ============================================================================
+// Report Cache Commands
+// ============================================================================
+
+/**
+ * Size and use of the cache of computed reports (KPIs, heatmap,
+ * buddy-punching report, monthly totals)
+ */
+export async function getReportCacheStats(): Promise<ReportCacheStats> {
+  return invoke<ReportCacheStats>('get_report_cache_stats');
+}
+
+/**
+ * Drop every cached report; returns how many were removed.
+ * Cached reports are invalidated automatically when their data changes.
+ */
+export async function clearReportCache(): Promise<number> {
+  return invoke<number>('clear_report_cache');
+}
+
+// ============================================================================
+// Bulk User Commands
+// ============================================================================
+
+/**
+ * Assign a department, change status or set a shift for many users in one
+ * transaction. If any user can't be changed, none is.
+ */
+export async function bulkUpdateUsers(userIds: string[], update: BulkUserUpdate): Promise<BulkUserOutcome> {
+  return invoke<BulkUserOutcome>('bulk_update_users', { userIds, update });
+}
+
+/**
+ * Delete many users in one transaction, all or none
+ */
+export async function bulkDeleteUsers(userIds: string[]): Promise<BulkUserOutcome> {
+  return invoke<BulkUserOutcome>('bulk_delete_users', { userIds });
+}
+
+/**
+ * Recent bulk user operations, newest first
+ */
+export async function listUserAuditLog(limit?: number): Promise<UserAuditEntry[]> {
+  return invoke<UserAuditEntry[]>('list_user_audit_log', { limit });
+}
+
+/**
+ * Propose which existing profile each device user created by sync belongs to,
+ * by employee code, exact name and name similarity
+ * @param minConfidence Lowest confidence proposed (0..1, default 0.6)
+ */
+export async function proposeUserLinks(minConfidence?: number): Promise<LinkProposal[]> {
+  return invoke<LinkProposal[]>('propose_user_links', { minConfidence });
+}
+
+/**
+ * Link confirmed device users to their profiles, all or none. Summaries include
+ * the linked punches once recomputed.
+ */
+export async function confirmUserLinks(links: LinkConfirmation[]): Promise<LinkOutcome> {
+  return invoke<LinkOutcome>('confirm_user_links', { links });
+}
+
+/**
+ * Device user IDs linked to profiles besides their own
+ */
+export async function listUserDeviceLinks(): Promise<DeviceUserLink[]> {
+  return invoke<DeviceUserLink[]>('list_user_device_links');
+}
+
+/**
+ * Remove a link; the next sync creates a profile for the device user again
+ */
+export async function unlinkDeviceUser(deviceUserId: string): Promise<boolean> {
+  return invoke<boolean>('unlink_device_user', { deviceUserId });
+}
+
+/**
+ * Add the users read from a device that nobody has yet and refresh the device
+ * names of the rest. Display names and profile details are never overwritten.
+ */
+export async function mergeDeviceUsers(
+  deviceId: string | null,
+  users: { deviceUserId: string; deviceName: string }[]
+): Promise<UserMergeSummary> {
+  return invoke<UserMergeSummary>('merge_device_users', { deviceId, users });
+}
+
+// ============================================================================
+// Punch Correction Commands
+// ============================================================================
+
+/**
+ * Locked payroll periods
+ */
+export async function listLockedPeriods(): Promise<LockedPeriod[]> {
+  return invoke<LockedPeriod[]>('list_locked_periods');
+}
+
+/**
+ * Lock the working days from startDate to endDate (inclusive): their punches
+ * can't be corrected or deleted and their summaries stay as they are
+ */
+export async function lockPeriod(startDate: string, endDate: string, label?: string): Promise<LockedPeriod> {
+  return invoke<LockedPeriod>('lock_period', { startDate, endDate, label });
+}
+
+/**
+ * Lock the reporting period a date falls in, labelled with the period's name unless given
+ */
+export async function lockPayPeriod(date: string, label?: string): Promise<LockedPeriod> {
+  return invoke<LockedPeriod>('lock_pay_period', { date, label });
+}
+
+export async function unlockPeriod(id: number): Promise<boolean> {
+  return invoke<boolean>('unlock_period', { id });
+}
+
+/**
+ * Add, move or void a punch. Device punches are never edited; the change is
+ * recorded as a correction and the affected days are recomputed.
+ */
+export async function correctPunch(correction: PunchCorrectionInput): Promise<PunchCorrection> {
+  return invoke<PunchCorrection>('correct_punch', { correction });
+}
+
+/**
+ * Corrections touching punches between two dates (inclusive), newest first
+ */
+export async function listPunchCorrections(startDate: string, endDate: string): Promise<PunchCorrection[]> {
+  return invoke<PunchCorrection[]>('list_punch_corrections', { startDate, endDate });
+}
+
+/**
+ * Undo a correction and recompute the days it touched
+ */
+export async function revertPunchCorrection(id: string): Promise<boolean> {
+  return invoke<boolean>('revert_punch_correction', { id });
+}
+
+// ============================================================================
+// Device Watchdog Commands
+// ============================================================================
+
+export async function getWatchdogSettings(): Promise<WatchdogSettings> {
+  return invoke<WatchdogSettings>('get_watchdog_settings');
+}
+
+/**
+ * Save the watchdog settings; emails use the closure email mail server
+ */
+export async function saveWatchdogSettings(settings: WatchdogSettings): Promise<void> {
+  return invoke<void>('save_watchdog_settings', { settings });
+}
+
+/**
+ * Devices that haven't synced within the threshold
+ * @param notify Escalate those due an alert, as the daily check does
+ */
+export async function checkStaleDevices(notify?: boolean): Promise<WatchdogRun> {
+  return invoke<WatchdogRun>('check_stale_devices', { notify });
+}
+
+/**
+ * Subscribe to alerts about devices that have stopped syncing
+ * @returns Function to unsubscribe
+ */
+export async function onDeviceStale(handler: (device: StaleDevice) => void): Promise<UnlistenFn> {
+  return listen<StaleDevice>('device-stale', (event) => handler(event.payload));
+}
+
+// ============================================================================
+// Report Signing Commands
+// ============================================================================
+
+export async function getSigningSettings(): Promise<SigningSettings> {
+  return invoke<SigningSettings>('get_signing_settings');
+}
+
+/**
+ * Turn signing of exports on or off; turning it on creates the signing key
+ */
+export async function saveSigningSettings(settings: SigningSettings): Promise<void> {
+  return invoke<void>('save_signing_settings', { settings });
+}
+
+/**
+ * The public key recipients can check signatures with
+ */
+export async function getSigningPublicKey(): Promise<SigningPublicKey> {
+  return invoke<SigningPublicKey>('get_signing_public_key');
+}
+
+/**
+ * Sign a report file, writing `<path>.sig` next to it
+ * @param report What the file is, e.g. "attendance_report"
+ */
+export async function signReportFile(path: string, report: string): Promise<ReportSignature> {
+  return invoke<ReportSignature>('sign_report_file', { path, report });
+}
+
+/**
+ * Check a report file against its signature
+ * @param signaturePath Defaults to `<path>.sig`
+ */
+export async function verifyReportFile(path: string, signaturePath?: string): Promise<SignatureVerification> {
+  return invoke<SignatureVerification>('verify_report_file', { path, signaturePath });
+}
+
+export async function listReportSignatures(limit?: number): Promise<ReportSignature[]> {
+  return invoke<ReportSignature[]>('list_report_signatures', { limit });
+}
+
+// ============================================================================
+// Cost Center Commands
+// ============================================================================
+
+export async function listCostCenters(): Promise<CostCenter[]> {
+  return invoke<CostCenter[]>('list_cost_centers');
+}
+
+/**
+ * Create (empty id) or update a cost center
+ */
+export async function saveCostCenter(costCenter: CostCenter): Promise<CostCenter> {
+  return invoke<CostCenter>('save_cost_center', { costCenter });
+}
+
+/**
+ * Delete a cost center and its assignments; deactivate it to keep history
+ */
+export async function deleteCostCenter(id: string): Promise<boolean> {
+  return invoke<boolean>('delete_cost_center', { id });
+}
+
+/**
+ * Assignments, optionally only of one cost center, user or department
+ */
+export async function listCostCenterAssignments(
+  costCenterId?: string,
+  userId?: string,
+  departmentId?: string
+): Promise<CostCenterAssignment[]> {
+  return invoke<CostCenterAssignment[]>('list_cost_center_assignments', { costCenterId, userId, departmentId });
+}
+
+/**
+ * Assign a cost center from its effective date; an open-ended earlier
+ * assignment of the same user or department ends the day before
+ */
+export async function assignCostCenter(assignment: CostCenterAssignment): Promise<CostCenterAssignment> {
+  return invoke<CostCenterAssignment>('assign_cost_center', { assignment });
+}
+
+export async function deleteCostCenterAssignment(id: string): Promise<boolean> {
+  return invoke<boolean>('delete_cost_center_assignment', { id });
+}
+
+/**
+ * The cost center a user books to on a date
+ */
+export async function getEffectiveCostCenter(userId: string, date: string): Promise<EffectiveCostCenter> {
+  return invoke<EffectiveCostCenter>('get_effective_cost_center', { userId, date });
+}
+
+// ============================================================================
+// Background Job Commands
+// ============================================================================
+
+/**
+ * Every background job with its last and next run
+ */
+export async function listScheduledJobs(): Promise<ScheduledJob[]> {
+  return invoke<ScheduledJob[]>('list_scheduled_jobs');
+}
+
+/**
+ * Run a job now, even while paused (or right after its current run)
+ */
+export async function runJobNow(jobId: string): Promise<ScheduledJob> {
+  return invoke<ScheduledJob>('run_job_now', { jobId });
+}
+
+/**
+ * Stop scheduled runs of a job until resumed; kept across restarts
+ */
+export async function pauseJob(jobId: string): Promise<ScheduledJob> {
+  return invoke<ScheduledJob>('pause_job', { jobId });
+}
+
+/**
+ * Resume a paused job, starting with a run right away
+ */
+export async function resumeJob(jobId: string): Promise<ScheduledJob> {
+  return invoke<ScheduledJob>('resume_job', { jobId });
+}
+
+/**
+ * Device syncs holding a slot and waiting for one, under the sync scheduling limits
+ */
+export async function getSyncSlots(): Promise<SyncSlots> {
+  return invoke<SyncSlots>('get_sync_slots');
+}
+
+// ============================================================================
+// Realtime Journal Commands
+// ============================================================================
+
+/**
+ * Journal realtime events from a device; they survive a crash once this resolves
+ */
+export async function appendRealtimeEvents(events: RealtimeEvent[]): Promise<number> {
+  return invoke<number>('append_realtime_events', { events });
+}
+
+/**
+ * Store everything in the realtime journal now
+ */
+export async function drainRealtimeJournal(): Promise<JournalDrain> {
+  return invoke<JournalDrain>('drain_realtime_journal');
+}
+
+/**
+ * Pending journal entries and the last drain
+ */
+export async function getRealtimeJournalStatus(): Promise<JournalStatus> {
+  return invoke<JournalStatus>('get_realtime_journal_status');
+}
+
+// ============================================================================
+// Demo Device, Sample Data and Load Test Commands
+// ============================================================================
+
+/**
+ * Demo devices and their profiles
+ */
+export async function listDemoDevices(): Promise<DemoDevice[]> {
+  return invoke<DemoDevice[]>('list_demo_devices');
+}
+
+/**
+ * Add a simulated device; it syncs like any other device
+ * @param profile Defaults to 25 users and 30 days of history
+ */
+export async function createDemoDevice(
+  name: string,
+  timezone?: string,
+  profile?: Partial<DemoProfile>
+): Promise<DemoDevice> {
+  return invoke<DemoDevice>('create_demo_device', { name, timezone, profile });
+}
+
+/**
+ * Change what a demo device generates
+ */
+export async function updateDemoDevice(deviceId: string, profile: Partial<DemoProfile>): Promise<DemoDevice> {
+  return invoke<DemoDevice>('update_demo_device', { deviceId, profile });
+}
+
+/**
+ * Remove a demo device and everything synced from it
+ */
+export async function deleteDemoDevice(deviceId: string): Promise<void> {
+  return invoke<void>('delete_demo_device', { deviceId });
+}
+
+/**
+ * Fill an empty database with made-up departments, shifts, users and
+ * attendance history, for trying reports at scale
+ * @param users 1-2000
+ * @param months 1-24 months of history, up to yesterday
+ * @param confirm Must be true; nothing is written otherwise
+ */
+export async function generateSampleData(
+  users: number,
+  months: number,
+  confirm: boolean,
+  seed?: number
+): Promise<SampleDataResult> {
+  return invoke<SampleDataResult>('generate_sample_data', { users, months, confirm, seed });
+}
+
+/**
+ * Load-test sync ingest and summaries in a scratch database. Not used by
+ * the UI; run before releases to catch performance regressions.
+ * @param thousands Thousands of raw logs to synthesize (1-2000)
+ * @param days Days to spread them over (default 30)
+ */
+export async function runLoadTest(thousands: number, days?: number): Promise<LoadTestReport> {
+  return invoke<LoadTestReport>('run_load_test', { thousands, days });
+}
+
+// ============================================================================
+// Holiday Commands
+// ============================================================================
+
+/**
+ * List holidays, optionally only between two dates or only those a department observes
+ */
+export async function listScopedHolidays(
+  startDate?: string,
+  endDate?: string,
+  departmentId?: string
+): Promise<ScopedHoliday[]> {
+  return invoke<ScopedHoliday[]>('list_holidays', { startDate, endDate, departmentId });
+}
+
+/**
+ * Create (empty id) or update a holiday. Leave department and site out for everyone.
+ */
+export async function saveHoliday(holiday: ScopedHoliday): Promise<ScopedHoliday> {
+  return invoke<ScopedHoliday>('save_holiday', { holiday });
+}
+
+export async function deleteHolidayById(id: string): Promise<boolean> {
+  return invoke<boolean>('delete_holiday', { id });
+}
+
+/**
+ * Sites named on users or holidays
+ */
+export async function listHolidaySites(): Promise<string[]> {
+  return invoke<string[]>('list_holiday_sites');
+}
+
+export async function listHolidayRules(): Promise<HolidayRule[]> {
+  return invoke<HolidayRule[]>('list_holiday_rules');
+}
+
+/**
+ * Create (empty id) or update a recurring holiday rule; its holidays are regenerated right away
+ */
+export async function saveHolidayRule(rule: HolidayRule): Promise<HolidayRule> {
+  return invoke<HolidayRule>('save_holiday_rule', { rule });
+}
+
+/**
+ * Delete a rule along with the holidays it generated
+ */
+export async function deleteHolidayRule(id: string): Promise<boolean> {
+  return invoke<boolean>('delete_holiday_rule', { id });
+}
+
+/**
+ * Enter the first day of a lunar rule for the year of startDate
+ */
+export async function setHolidayRuleDate(ruleId: string, startDate: string): Promise<HolidayRule> {
+  return invoke<HolidayRule>('set_holiday_rule_date', { ruleId, startDate });
+}
+
+export async function clearHolidayRuleDate(ruleId: string, year: number): Promise<boolean> {
+  return invoke<boolean>('clear_holiday_rule_date', { ruleId, year });
+}
+
+/**
+ * Generate the rules' holidays for a range of years now, e.g. to fill in past years
+ */
+export async function materializeHolidayRules(firstYear: number, lastYear: number): Promise<HolidayRuleRun> {
+  return invoke<HolidayRuleRun>('materialize_holiday_rules', { firstYear, lastYear });
+}
+
+// ============================================================================
+// Attendance Points Commands
+// ============================================================================
+
+export async function getPointsSettings(): Promise<PointsSettings> {
+  return invoke<PointsSettings>('get_points_settings');
+}
+
+export async function savePointsSettings(settings: PointsSettings): Promise<void> {
+  return invoke('save_points_settings', { settings });
+}
+
+/**
+ * Everyone's attendance points over the window ending on asOf (today by default)
+ */
+export async function getAttendancePoints(asOf?: string, departmentId?: string): Promise<PointsReport> {
+  return invoke<PointsReport>('get_attendance_points', { asOf, departmentId });
+}
+
+/**
+ * One user's points and the late days, absences and early leaves behind them
+ */
+export async function getUserPoints(userId: string, asOf?: string): Promise<UserPointsDetail> {
+  return invoke<UserPointsDetail>('get_user_points', { userId, asOf });
+}
+
+/**
+ * Users over a threshold; with notify, alert those who newly reached one
+ */
+export async function checkPointsThresholds(notify?: boolean): Promise<PointsCheck> {
+  return invoke<PointsCheck>('check_points_thresholds', { notify });
+}
+
+// ============================================================================
+// Recognition Commands
+// ============================================================================
+
+/**
+ * Perfect-attendance streaks and the month's perfect attendance list per department
+ * @param month 1-12
+ */
+export async function getRecognitionReport(
+  year: number,
+  month: number,
+  departmentId?: string
+): Promise<RecognitionReport> {
+  return invoke<RecognitionReport>('get_recognition_report', { year, month, departmentId });
+}
+
+/**
+ * Export the recognition report as CSV
+ * @param destination Optional destination directory; defaults to Documents/HorusAttendance/exports
+ * @param password Optional password (8+ characters); the export is then an AES-256 encrypted zip
+ */
+export async function exportRecognitionReport(
+  year: number,
+  month: number,
+  departmentId?: string,
+  destination?: string,
+  password?: string
+): Promise<ExportedFile> {
+  return invoke<ExportedFile>('export_recognition_report', { year, month, departmentId, destination, password });
+}
+
 // ============================================================================
-// Backup Commands
+// Minimum Staffing Commands
 // ============================================================================
 
+export async function getStaffingSettings(): Promise<StaffingSettings> {
+  return invoke<StaffingSettings>('get_staffing_settings');
+}
+
+export async function saveStaffingSettings(settings: StaffingSettings): Promise<void> {
+  return invoke('save_staffing_settings', { settings });
+}
+
+export async function listStaffingRules(): Promise<StaffingRule[]> {
+  return invoke<StaffingRule[]>('list_staffing_rules');
+}
+
 /**
- * Export database backup to a file
- * @param destination Optional destination directory path
- * @returns BackupResult with file path and size
+ * Create (empty id) or update a staffing rule
  */
-export async function exportBackup(destination?: string): Promise<BackupResult> {
-  return invoke<BackupResult>('export_backup', { destination });
+export async function saveStaffingRule(rule: StaffingRule): Promise<StaffingRule> {
+  return invoke<StaffingRule>('save_staffing_rule', { rule });
+}
+
+export async function deleteStaffingRule(id: string): Promise<boolean> {
+  return invoke<boolean>('delete_staffing_rule', { id });
 }
 
 /**
- * Restore database from a backup file
- * @param backupPath Path to the backup file
- * @returns RestoreResult indicating success or failure
+ * Everyone on the premises now: users with an odd number of punches today
  */
-export async function restoreBackup(backupPath: string): Promise<RestoreResult> {
-  return invoke<RestoreResult>('restore_backup', { backupPath });
+export async function getLivePresence(): Promise<PresentUser[]> {
+  return invoke<PresentUser[]>('get_live_presence');
 }
 
 /**
- * Get the default backup directory path
- * @returns Path to the backup directory
+ * Every staffing rule against live presence; with notify, alert rules that newly fell short
  */
-export async function getBackupDirectory(): Promise<string> {
-  return invoke<string>('get_backup_directory');
+export async function checkStaffing(notify?: boolean): Promise<StaffingCheck> {
+  return invoke<StaffingCheck>('check_staffing', { notify });
 }
 
+// ============================================================================
+// Enrollment Expiry Commands
+// ============================================================================
+
 /**
- * List available backup files
- * @returns Array of backup filenames
+ * Users with an enrollment expiry date; with withinDays, only those expiring
+ * within that many days or expired and not yet removed from every device
  */
-export async function listBackups(): Promise<string[]> {
-  return invoke<string[]>('list_backups');
+export async function listEnrollmentExpiries(withinDays?: number): Promise<EnrollmentExpiry[]> {
+  return invoke<EnrollmentExpiry[]>('list_enrollment_expiries', { withinDays });
 }
 
 /**
- * Get the application version
- * @returns Version string
+ * Deactivate users whose enrollment expired and remove them from the devices now
  */
-export async function getAppVersion(): Promise<string> {
-  return invoke<string>('get_app_version');
+export async function runEnrollmentExpiry(): Promise<ExpiryRun> {
+  return invoke<ExpiryRun>('run_enrollment_expiry');
+}
+
+// ============================================================================
+// Remote Work Commands
+// ============================================================================
+
+export async function getRemoteWorkSettings(): Promise<RemoteWorkSettings> {
+  return invoke<RemoteWorkSettings>('get_remote_work_settings');
 }
 
 /**
- * Reset the database by deleting all data
- * Creates a backup before reset
- * @returns RestoreResult indicating success or failure
+ * Save the settings and apply them to the remote days already marked
  */
-export async function resetDatabase(): Promise<RestoreResult> {
-  try {
-    // First create a backup
-    await invoke<BackupResult>('export_backup', { destination: null });
-    
-    // Then flush all tables via SQL (this works even with open connection)
-    const { flushDatabase } = await import('./database');
-    await flushDatabase();
-    
-    return { success: true, error: null };
-  } catch (error) {
-    return { 
-      success: false, 
-      error: error instanceof Error ? error.message : String(error) 
-    };
-  }
+export async function saveRemoteWorkSettings(settings: RemoteWorkSettings): Promise<void> {
+  return invoke('save_remote_work_settings', { settings });
+}
+
+export async function listRemoteDays(startDate: string, endDate: string): Promise<RemoteDay[]> {
+  return invoke<RemoteDay[]>('list_remote_days', { startDate, endDate });
+}
+
+/**
+ * Mark every user as working remotely on every date (YYYY-MM-DD), so the days stop showing as absences
+ */
+export async function markRemoteDays(userIds: string[], dates: string[], note?: string): Promise<RemoteMarkResult> {
+  return invoke<RemoteMarkResult>('mark_remote_days', { userIds, dates, note });
+}
+
+/**
+ * Remove remote marks; the days are recomputed from their punches. Returns the number removed.
+ */
+export async function unmarkRemoteDays(userIds: string[], dates: string[]): Promise<number> {
+  return invoke<number>('unmark_remote_days', { userIds, dates });
+}
+
+// ============================================================================
+// Device Diagnostics Commands
+// ============================================================================
+
+/**
+ * A device's last error, recent failures and latency history over the last days (30 by default)
+ */
+export async function getDeviceDiagnostics(deviceId: string, days?: number): Promise<DeviceDiagnostics> {
+  return invoke<DeviceDiagnostics>('get_device_diagnostics', { deviceId, days });
+}
+
+// ============================================================================
+// Sync History Commands
+// ============================================================================
+
+/**
+ * Record a finished sync's structured result. Resolves to the entry's id, or null when the device isn't saved.
+ */
+export async function recordSync(report: SyncReport): Promise<number | null> {
+  return invoke<number | null>('record_sync', { report });
+}
+
+/**
+ * Recorded syncs, newest first (50 by default), optionally of one device
+ */
+export async function listSyncHistory(deviceId?: string, limit?: number): Promise<SyncHistoryEntry[]> {
+  return invoke<SyncHistoryEntry[]>('list_sync_history', { deviceId, limit });
+}
+
+/**
+ * Export a recorded sync as JSON with the device's details and latest connection attempts,
+ * for attaching to a support ticket. Defaults to Documents/HorusAttendance/exports.
+ */
+export async function exportSyncReport(syncId: number, destination?: string): Promise<ExportedFile> {
+  return invoke<ExportedFile>('export_sync_report', { syncId, destination });
+}
+
+// ============================================================================
+// Sync Scheduling Commands
+// ============================================================================
+
+export async function getSyncSchedulerSettings(): Promise<SyncSchedulerSettings> {
+  return invoke<SyncSchedulerSettings>('get_sync_scheduler_settings');
+}
+
+/**
+ * Save the sync limits; syncs already waiting for a slot are held to them right away
+ */
+export async function saveSyncSchedulerSettings(settings: SyncSchedulerSettings): Promise<void> {
+  return invoke('save_sync_scheduler_settings', { settings });
+}
+
+/**
+ * Saved devices in sync order: highest priority first, then the longest since their last sync
+ */
+export async function getSyncQueue(autoOnly?: boolean): Promise<QueuedDevice[]> {
+  return invoke<QueuedDevice[]>('get_sync_queue', { autoOnly });
+}
+
+// ============================================================================
+// Punch Ledger Commands
+// ============================================================================
+
+export async function getLedgerSettings(): Promise<LedgerSettings> {
+  return invoke<LedgerSettings>('get_ledger_settings');
+}
+
+export async function saveLedgerSettings(settings: LedgerSettings): Promise<void> {
+  return invoke('save_ledger_settings', { settings });
+}
+
+/**
+ * Seal punches stored since the last seal now instead of on the next scheduled run.
+ * Does nothing while the ledger is disabled.
+ */
+export async function sealLedger(): Promise<SealResult> {
+  return invoke<SealResult>('seal_ledger');
+}
+
+/**
+ * Recompute every sealed batch from the punches as they are now, reporting
+ * batches whose punches were removed or changed since they were ingested
+ */
+export async function verifyLedger(): Promise<LedgerVerification> {
+  return invoke<LedgerVerification>('verify_ledger');
+}
+
+// ============================================================================
+// Punch Archive Commands
+// ============================================================================
+
+export async function getPunchArchiveSettings(): Promise<PunchArchiveSettings> {
+  return invoke<PunchArchiveSettings>('get_punch_archive_settings');
+}
+
+/**
+ * Save the archive settings; a chosen folder must be one writes are allowed to
+ */
+export async function savePunchArchiveSettings(settings: PunchArchiveSettings): Promise<void> {
+  return invoke('save_punch_archive_settings', { settings });
+}
+
+/**
+ * Append punches not archived yet to this month's file now instead of on the next
+ * scheduled run. Does nothing while the archive is disabled.
+ */
+export async function runPunchArchive(): Promise<ArchiveRun> {
+  return invoke<ArchiveRun>('run_punch_archive');
+}
+
+/**
+ * Archive files, newest month first, each checked against its recorded checksum
+ */
+export async function listPunchArchiveFiles(): Promise<ArchiveFile[]> {
+  return invoke<ArchiveFile[]>('list_punch_archive_files');
+}
+
+/**
+ * Number of punches not in the archive yet
+ */
+export async function getPunchArchiveBacklog(): Promise<number> {
+  return invoke<number>('get_punch_archive_backlog');
+}
+
+// ============================================================================
+// Reporting Period Commands
+// ============================================================================
+
+export async function getPeriodSettings(): Promise<PeriodSettings> {
+  return invoke<PeriodSettings>('get_period_settings');
+}
+
+/**
+ * Save how months are cut (calendar, custom start day or bi-weekly) and the first day of the week
+ */
+export async function savePeriodSettings(settings: PeriodSettings): Promise<void> {
+  return invoke('save_period_settings', { settings });
+}
+
+/**
+ * The reporting period a date falls in; today's when omitted
+ */
+export async function getPayPeriod(date?: string): Promise<PayPeriod> {
+  return invoke<PayPeriod>('get_pay_period', { date });
+}
+
+/**
+ * Every reporting period overlapping a date range, for period pickers
+ */
+export async function listPayPeriods(startDate: string, endDate: string): Promise<PayPeriod[]> {
+  return invoke<PayPeriod[]>('list_pay_periods', { startDate, endDate });
+}
+
+// ============================================================================
+// Pay Estimate Commands
+// ============================================================================
+
+export async function getPayEstimationSettings(): Promise<PayEstimationSettings> {
+  return invoke<PayEstimationSettings>('get_pay_estimation_settings');
+}
+
+/**
+ * Save the default currency, overtime thresholds and multipliers
+ */
+export async function savePayEstimationSettings(settings: PayEstimationSettings): Promise<void> {
+  return invoke('save_pay_estimation_settings', { settings });
+}
+
+export async function listPayRates(): Promise<PayRate[]> {
+  return invoke<PayRate[]>('list_pay_rates');
+}
+
+/**
+ * Set or replace a user's hourly rate
+ */
+export async function setPayRate(rate: PayRate): Promise<PayRate> {
+  return invoke<PayRate>('set_pay_rate', { rate });
+}
+
+export async function deletePayRate(userId: string): Promise<boolean> {
+  return invoke<boolean>('delete_pay_rate', { userId });
+}
+
+/**
+ * Gross pay estimates per user from worked and overtime hours.
+ * A sanity check, not payroll.
+ */
+export async function estimatePay(period: DateRange, scope?: AnalyticsScope): Promise<PayEstimate> {
+  return invoke<PayEstimate>('estimate_pay', { period, scope });
+}
+
+// ============================================================================
+// Break Compliance Commands
+// ============================================================================
+
+export async function getBreakRules(): Promise<BreakRules> {
+  return invoke<BreakRules>('get_break_rules');
+}
+
+export async function saveBreakRules(rules: BreakRules): Promise<void> {
+  return invoke('save_break_rules', { rules });
+}
+
+/**
+ * Check breaks between two dates now; violations go to the exception queue
+ */
+export async function runBreakCheck(startDate: string, endDate: string): Promise<BreakCheckResult> {
+  return invoke<BreakCheckResult>('run_break_check', { startDate, endDate });
+}
+
+// ============================================================================
+// Working-Time Compliance Commands
+// ============================================================================
+
+export async function getWorkingTimeSettings(): Promise<WorkingTimeSettings> {
+  return invoke<WorkingTimeSettings>('get_working_time_settings');
+}
+
+export async function saveWorkingTimeSettings(settings: WorkingTimeSettings): Promise<void> {
+  return invoke('save_working_time_settings', { settings });
+}
+
+/**
+ * Every breach of the maximum daily/weekly hours and minimum rest in a period
+ */
+export async function getWorkingTimeReport(period: DateRange, scope?: AnalyticsScope): Promise<WorkingTimeReport> {
+  return invoke<WorkingTimeReport>('get_working_time_report', { period, scope });
+}
+
+// ============================================================================
+// Greeter Screen Commands
+// ============================================================================
+
+/**
+ * The newest punches, newest first. Pass the newest timestamp already shown
+ * as `since` to poll for new ones.
+ */
+export async function getRecentPunches(
+  limit?: number,
+  since?: string,
+  includePhotos?: boolean
+): Promise<RecentPunch[]> {
+  return invoke<RecentPunch[]>('get_recent_punches', { limit, since, includePhotos });
+}
+
+/**
+ * Receive punches from realtime device events as they are stored. The
+ * subscription ends when the returned function is called.
+ */
+export async function onRecentPunches(handler: (punches: RecentPunch[]) => void): Promise<UnlistenFn> {
+  const unlisten = await listen<RecentPunch[]>('recent-punches', (event) => handler(event.payload));
+  await invoke<number>('subscribe_recent_punches', { enabled: true });
+  return () => {
+    unlisten();
+    void invoke<number>('subscribe_recent_punches', { enabled: false });
+  };
+}
+
+// ============================================================================
+// Device Name Push Commands
+// ============================================================================
+
+/**
+ * Write each active user's display name to every saved device, e.g. after
+ * importing names. Devices that can't be reached are retried from the
+ * delivery log.
+ */
+export async function pushNamesToAllDevices(): Promise<NamePushReport> {
+  return invoke<NamePushReport>('push_names_to_all_devices');
+}
+
+// ============================================================================
+// Access Event Commands
+// ============================================================================
+
+/**
+ * Import the access panel software's CSV event export. Columns are found by
+ * header; slashed dates are read day first unless `monthFirst` is set.
+ */
+export async function importAccessEventsCsv(
+  source: string,
+  content: string,
+  monthFirst?: boolean
+): Promise<AccessImportResult> {
+  return invoke<AccessImportResult>('import_access_events_csv', { source, content, monthFirst });
+}
+
+/** Store events pushed by a connector reading the access panel */
+export async function ingestAccessEvents(source: string, events: NewAccessEvent[]): Promise<AccessImportResult> {
+  return invoke<AccessImportResult>('ingest_access_events', { source, events });
+}
+
+export async function listAccessEvents(
+  period: DateRange,
+  deviceUserId?: string,
+  limit?: number
+): Promise<AccessEvent[]> {
+  return invoke<AccessEvent[]>('list_access_events', { period, deviceUserId, limit });
+}
+
+/**
+ * Pair access events in a period with the nearest punch by the same person
+ * within `windowMinutes` (default 15)
+ */
+export async function getAccessReconciliation(
+  period: DateRange,
+  userId?: string,
+  windowMinutes?: number
+): Promise<AccessReconciliation> {
+  return invoke<AccessReconciliation>('get_access_reconciliation', { period, userId, windowMinutes });
+}
+
+// ============================================================================
+// WAL Watchdog Commands
+// ============================================================================
+
+export async function getWalSettings(): Promise<WalSettings> {
+  return invoke<WalSettings>('get_wal_settings');
+}
+
+export async function saveWalSettings(settings: WalSettings): Promise<void> {
+  return invoke<void>('save_wal_settings', { settings });
+}
+
+/**
+ * WAL size now, the watchdog's counters and its recent checkpoints
+ */
+export async function getWalStatus(): Promise<WalStatus> {
+  return invoke<WalStatus>('get_wal_status');
+}
+
+/**
+ * Checkpoint the WAL now, without waiting for the app to be idle
+ * @param truncate Also shrink the WAL file back to empty
+ */
+export async function checkpointWal(truncate?: boolean): Promise<WalCheck> {
+  return invoke<WalCheck>('checkpoint_wal', { truncate });
 }
 
 // ============================================================================
@@ -132,6 +4613,22 @@ export async function selectBackupDestination(): Promise<string | null> {
   return selected;
 }
 
+/**
+ * Open save dialog to choose where to save a zip bundle
+ * @param defaultName Suggested file name
+ * @returns Selected file path or null if cancelled
+ */
+export async function selectZipDestination(defaultName: string): Promise<string | null> {
+  return save({
+    defaultPath: defaultName,
+    filters: [{
+      name: 'Zip Archive',
+      extensions: ['zip'],
+    }],
+    title: 'Save Bundle As',
+  });
+}
+
 // ============================================================================
 // Utility Functions
 // ============================================================================
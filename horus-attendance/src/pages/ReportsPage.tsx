@@ -2,7 +2,6 @@ import { motion, AnimatePresence } from 'framer-motion';
 import { useState, useEffect, useCallback, useRef } from 'react';
 import { useSearchParams } from 'react-router-dom';
 import { save } from '@tauri-apps/plugin-dialog';
-import { invoke } from '@tauri-apps/api/core';
 import type { 
   Department, 
   WeeklyReportRow, 
@@ -23,6 +22,7 @@ import {
   exportWeeklyReportToExcel, 
   exportMonthlyReportToExcel 
 } from '../lib/services/report-generator';
+import { getPeriodSettings, writeFileStreamed, type PeriodSettings } from '../lib/tauri-commands';
 import { useDebounce } from '../lib/hooks/useDebounce';
 import { useApp } from '../contexts';
 
@@ -49,8 +49,15 @@ const modalVariants = {
 // Report type
 type ReportType = 'weekly' | 'monthly';
 
-// Day names for weekly report
-const DAY_NAMES = ['Mon', 'Tue', 'Wed', 'Thu', 'Fri', 'Sat', 'Sun'];
+// Day names for weekly report, Sunday first
+const DAY_NAMES = ['Sun', 'Mon', 'Tue', 'Wed', 'Thu', 'Fri', 'Sat'];
+
+const DEFAULT_PERIOD_SETTINGS: PeriodSettings = {
+  firstDayOfWeek: 1,
+  kind: 'calendar_month',
+  startDay: 1,
+  anchorDate: null,
+};
 
 // Helper function to format date to YYYY-MM-DD
 function formatDateString(date: Date): string {
@@ -67,6 +74,7 @@ function StatusBadge({ status, compact = false }: { status: AttendanceStatus; co
     incomplete: 'bg-secondary-600/20 text-secondary-400 border-secondary-600/30',
     holiday: 'bg-primary-600/20 text-primary-400 border-primary-600/30',
     weekend: 'bg-secondary-600/20 text-secondary-500 border-secondary-600/30',
+    remote: 'bg-teal-600/20 text-teal-400 border-teal-600/30',
   };
   
   const labels: Record<AttendanceStatus, string> = {
@@ -77,6 +85,7 @@ function StatusBadge({ status, compact = false }: { status: AttendanceStatus; co
     incomplete: compact ? 'I' : 'Incomplete',
     holiday: compact ? 'H' : 'Holiday',
     weekend: compact ? '-' : 'Weekend',
+    remote: compact ? 'R' : 'Remote',
   };
   
   return (
@@ -224,9 +233,13 @@ interface WeeklyReportTableProps {
   weekDates: string[];
   loading: boolean;
   showTimes: boolean;
+  /** 0 (Sunday) to 6 (Saturday) */
+  firstDayOfWeek: number;
 }
 
-function WeeklyReportTable({ report, weekDates, loading, showTimes }: WeeklyReportTableProps) {
+function WeeklyReportTable({ report, weekDates, loading, showTimes, firstDayOfWeek }: WeeklyReportTableProps) {
+  const dayNames = DAY_NAMES.map((_, i) => DAY_NAMES[(firstDayOfWeek + i) % 7]!);
+
   if (loading) {
     return (
       <div className="py-12 text-center">
@@ -255,7 +268,7 @@ function WeeklyReportTable({ report, weekDates, loading, showTimes }: WeeklyRepo
             <th className="text-left py-3 px-4 text-sm font-medium text-secondary-400 sticky left-0 bg-secondary-800">
               Employee
             </th>
-            {DAY_NAMES.map((day, i) => (
+            {dayNames.map((day, i) => (
               <th key={day} className="text-center py-3 px-2 text-sm font-medium text-secondary-400 min-w-[80px]">
                 <div>{day}</div>
                 <div className="text-xs text-secondary-500">
@@ -633,6 +646,9 @@ export function ReportsPage() {
   // Drill-down modal state
   const [selectedUserRow, setSelectedUserRow] = useState<MonthlyReportRow | null>(null);
   
+  // Week start and monthly period start from the periods setting
+  const [periodSettings, setPeriodSettings] = useState<PeriodSettings>(DEFAULT_PERIOD_SETTINGS);
+
   // View toggles
   const [showTimes, setShowTimes] = useState(false);
   const [monthlyView, setMonthlyView] = useState<'detail' | 'summary'>('detail');
@@ -655,6 +671,18 @@ export function ReportsPage() {
     loadFilterData();
   }, []);
 
+  // Load period settings; weeks start on Monday and months on the 1st until they arrive
+  useEffect(() => {
+    getPeriodSettings()
+      .then(settings => {
+        setPeriodSettings(settings);
+        if (!searchParams.get('weekStart')) {
+          setSelectedWeekStart(getWeekStart(formatDateString(new Date()), settings.firstDayOfWeek));
+        }
+      })
+      .catch(error => console.error('Failed to load period settings:', error));
+  }, []);
+
   // Create report generator
   const createReportGenerator = useCallback(() => {
     const userFetcher = async (filter?: { departmentId?: string }) => {
@@ -670,8 +698,10 @@ export function ReportsPage() {
       return attendanceSummaryRepository.getSummariesForDateRange(userId, startDate, endDate);
     };
 
-    return new ReportGenerator(userFetcher, summaryFetcher);
-  }, [statusFilter]);
+    const generator = new ReportGenerator(userFetcher, summaryFetcher);
+    generator.setMonthStartDay(periodSettings.kind === 'custom_start' ? periodSettings.startDay : 1);
+    return generator;
+  }, [statusFilter, periodSettings]);
 
   // Load weekly report
   const loadWeeklyReport = useCallback(async () => {
@@ -754,7 +784,7 @@ export function ReportsPage() {
   };
 
   const handleCurrentWeek = () => {
-    setSelectedWeekStart(getWeekStart(formatDateString(new Date())));
+    setSelectedWeekStart(getWeekStart(formatDateString(new Date()), periodSettings.firstDayOfWeek));
   };
 
   // Navigation handlers for monthly report
@@ -809,10 +839,8 @@ export function ReportsPage() {
       
       if (!filePath) return;
       
-      // Convert Uint8Array to base64 and write via Tauri command
-      const binary = Array.from(buffer).map(b => String.fromCharCode(b)).join('');
-      const base64 = btoa(binary);
-      await invoke('write_binary_file', { path: filePath, base64Data: base64 });
+      // Write in chunks so large workbooks aren't held twice in memory
+      await writeFileStreamed(filePath, buffer);
     } catch (error) {
       console.error('Export failed:', error);
       showNotification('Failed to export report', 'error');
@@ -1132,6 +1160,7 @@ export function ReportsPage() {
                 weekDates={weekDates}
                 loading={loading}
                 showTimes={showTimes}
+                firstDayOfWeek={periodSettings.firstDayOfWeek}
               />
             </motion.div>
           ) : monthlyView === 'detail' ? (
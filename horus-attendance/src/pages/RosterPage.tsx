@@ -0,0 +1,352 @@
+import { motion, AnimatePresence } from 'framer-motion';
+import { useState, useEffect, useCallback, useRef } from 'react';
+import type { User } from '../types/models';
+import { userRepository } from '../lib/repositories/user.repository';
+import { getWeekStart } from '../lib/services/report-generator';
+import {
+  isTauriEnvironment,
+  getRosterVariance,
+  listRoster,
+  saveRosterEntries,
+  deleteRosterEntry,
+  importRosterCsv,
+} from '../lib/tauri-commands';
+import type { RosterEntry, RosterReport, RosterVariance, RosterImportResult } from '../lib/tauri-commands';
+import { useApp } from '../contexts';
+
+const modalVariants = {
+  hidden: { opacity: 0, scale: 0.95 },
+  visible: { opacity: 1, scale: 1, transition: { duration: 0.2 } },
+  exit: { opacity: 0, scale: 0.95, transition: { duration: 0.15 } },
+};
+
+const DAY_NAMES = ['Mon', 'Tue', 'Wed', 'Thu', 'Fri', 'Sat', 'Sun'];
+
+const STATUS_COLORS: Record<RosterVariance['status'], string> = {
+  covered: 'bg-success-600/20 text-success-500 border-success-600/30',
+  partial: 'bg-warning-600/20 text-warning-500 border-warning-600/30',
+  gap: 'bg-danger-600/20 text-danger-500 border-danger-600/30',
+  unplanned: 'bg-secondary-600/20 text-secondary-400 border-secondary-600/30',
+};
+
+function addDays(date: string, days: number): string {
+  const d = new Date(date + 'T00:00:00');
+  d.setDate(d.getDate() + days);
+  return `${d.getFullYear()}-${String(d.getMonth() + 1).padStart(2, '0')}-${String(d.getDate()).padStart(2, '0')}`;
+}
+
+function formatVariance(minutes: number): string {
+  const sign = minutes < 0 ? '-' : '+';
+  const abs = Math.abs(minutes);
+  return `${sign}${Math.floor(abs / 60)}h${String(abs % 60).padStart(2, '0')}`;
+}
+
+// Planned Shift Modal Component
+interface ShiftModalProps {
+  user: User;
+  date: string;
+  entry: RosterEntry | null;
+  onSave: (startTime: string, endTime: string, note: string) => Promise<void>;
+  onDelete: () => Promise<void>;
+  onClose: () => void;
+}
+
+function ShiftModal({ user, date, entry, onSave, onDelete, onClose }: ShiftModalProps) {
+  const [startTime, setStartTime] = useState(entry?.startTime ?? '09:00');
+  const [endTime, setEndTime] = useState(entry?.endTime ?? '18:00');
+  const [note, setNote] = useState(entry?.note ?? '');
+  const [saving, setSaving] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  const run = async (action: () => Promise<void>) => {
+    setSaving(true);
+    setError(null);
+    try {
+      await action();
+      onClose();
+    } catch (err) {
+      setError(err instanceof Error ? err.message : String(err));
+    } finally {
+      setSaving(false);
+    }
+  };
+
+  return (
+    <div className="fixed inset-0 z-50 flex items-center justify-center p-4">
+      <motion.div
+        initial={{ opacity: 0 }}
+        animate={{ opacity: 1 }}
+        exit={{ opacity: 0 }}
+        className="absolute inset-0 bg-black/60"
+        onClick={onClose}
+      />
+      <motion.div
+        variants={modalVariants}
+        initial="hidden"
+        animate="visible"
+        exit="exit"
+        className="relative bg-secondary-800 rounded-xl p-6 w-full max-w-md"
+      >
+        <h2 className="text-xl font-semibold text-white mb-1">Planned Shift</h2>
+        <p className="text-secondary-400 text-sm mb-6">{user.displayName} · {date}</p>
+        <div className="grid grid-cols-2 gap-4 mb-4">
+          <div>
+            <label className="block text-sm font-medium text-secondary-300 mb-1">Start</label>
+            <input type="time" value={startTime} onChange={(e) => setStartTime(e.target.value)} className="input w-full" />
+          </div>
+          <div>
+            <label className="block text-sm font-medium text-secondary-300 mb-1">End</label>
+            <input type="time" value={endTime} onChange={(e) => setEndTime(e.target.value)} className="input w-full" />
+          </div>
+        </div>
+        <div className="mb-4">
+          <label className="block text-sm font-medium text-secondary-300 mb-1">Note</label>
+          <input type="text" value={note} onChange={(e) => setNote(e.target.value)} className="input w-full" />
+        </div>
+        {error && <p className="text-danger-500 text-sm mb-4">{error}</p>}
+        <div className="flex gap-3">
+          <motion.button
+            whileHover={{ scale: 1.02 }}
+            whileTap={{ scale: 0.98 }}
+            onClick={() => run(() => onSave(startTime, endTime, note))}
+            disabled={saving}
+            className="btn-primary flex-1"
+          >
+            Save
+          </motion.button>
+          {entry && (
+            <motion.button
+              whileHover={{ scale: 1.02 }}
+              whileTap={{ scale: 0.98 }}
+              onClick={() => run(onDelete)}
+              disabled={saving}
+              className="btn-secondary text-danger-500"
+            >
+              Remove
+            </motion.button>
+          )}
+          <motion.button
+            whileHover={{ scale: 1.02 }}
+            whileTap={{ scale: 0.98 }}
+            onClick={onClose}
+            className="btn-secondary"
+          >
+            Cancel
+          </motion.button>
+        </div>
+      </motion.div>
+    </div>
+  );
+}
+
+export function RosterPage() {
+  const { showNotification } = useApp();
+  const [weekStart, setWeekStart] = useState(() => getWeekStart(new Date().toISOString().slice(0, 10)));
+  const [users, setUsers] = useState<User[]>([]);
+  const [entries, setEntries] = useState<RosterEntry[]>([]);
+  const [report, setReport] = useState<RosterReport | null>(null);
+  const [importResult, setImportResult] = useState<RosterImportResult | null>(null);
+  const [editing, setEditing] = useState<{ user: User; date: string } | null>(null);
+  const [loading, setLoading] = useState(false);
+  const fileInput = useRef<HTMLInputElement>(null);
+
+  const days = DAY_NAMES.map((_, i) => addDays(weekStart, i));
+  const weekEnd = days[6] as string;
+
+  const load = useCallback(async () => {
+    if (!isTauriEnvironment()) return;
+    setLoading(true);
+    try {
+      const [userList, roster, variance] = await Promise.all([
+        userRepository.listUsers(),
+        listRoster(weekStart, weekEnd),
+        getRosterVariance(weekStart, weekEnd),
+      ]);
+      setUsers(userList);
+      setEntries(roster);
+      setReport(variance);
+    } catch (error) {
+      showNotification(`Failed to load roster: ${error instanceof Error ? error.message : String(error)}`, 'error');
+    } finally {
+      setLoading(false);
+    }
+  }, [weekStart, weekEnd, showNotification]);
+
+  useEffect(() => {
+    load();
+  }, [load]);
+
+  const handleImport = async (file: File) => {
+    try {
+      const result = await importRosterCsv(await file.text());
+      setImportResult(result);
+      showNotification(`Imported ${result.imported} planned shifts`, result.errors.length > 0 ? 'info' : 'success');
+      await load();
+    } catch (error) {
+      showNotification(`Import failed: ${error instanceof Error ? error.message : String(error)}`, 'error');
+    }
+  };
+
+  if (!isTauriEnvironment()) {
+    return (
+      <div className="p-8">
+        <h1 className="text-2xl font-bold text-white">Roster</h1>
+        <p className="text-secondary-400 mt-1">The roster is only available in the desktop app.</p>
+      </div>
+    );
+  }
+
+  const entryFor = (userId: string, date: string) => entries.find((e) => e.userId === userId && e.date === date) ?? null;
+  const varianceFor = (userId: string, date: string) =>
+    report?.rows.find((r) => r.userId === userId && r.date === date) ?? null;
+  const rowUsers = users.filter((u) => report?.rows.some((r) => r.userId === u.id) || entries.some((e) => e.userId === u.id));
+  const otherUsers = users.filter((u) => !rowUsers.includes(u));
+
+  return (
+    <div className="p-8">
+      <motion.div initial={{ opacity: 0, y: -10 }} animate={{ opacity: 1, y: 0 }} className="mb-8 flex items-center justify-between">
+        <div>
+          <h1 className="text-2xl font-bold text-white">Roster</h1>
+          <p className="text-secondary-400 mt-1">Planned shifts against actual attendance</p>
+        </div>
+        <div className="flex items-center gap-2">
+          <button onClick={() => setWeekStart(addDays(weekStart, -7))} className="btn-secondary">‹</button>
+          <span className="text-white text-sm w-48 text-center">{weekStart} – {weekEnd}</span>
+          <button onClick={() => setWeekStart(addDays(weekStart, 7))} className="btn-secondary">›</button>
+          <input
+            ref={fileInput}
+            type="file"
+            accept=".csv,text/csv"
+            className="hidden"
+            onChange={(e) => {
+              const file = e.target.files?.[0];
+              if (file) handleImport(file);
+              e.target.value = '';
+            }}
+          />
+          <motion.button
+            whileHover={{ scale: 1.02 }}
+            whileTap={{ scale: 0.98 }}
+            onClick={() => fileInput.current?.click()}
+            className="btn-primary"
+          >
+            Import CSV
+          </motion.button>
+        </div>
+      </motion.div>
+
+      {importResult && importResult.errors.length > 0 && (
+        <div className="card mb-6">
+          <p className="text-warning-500 text-sm mb-2">{importResult.errors.length} lines were not imported:</p>
+          <ul className="text-sm text-secondary-300 max-h-32 overflow-y-auto">
+            {importResult.errors.map((e) => (
+              <li key={e.line}>Line {e.line}: {e.message}</li>
+            ))}
+          </ul>
+        </div>
+      )}
+
+      <div className="card overflow-x-auto">
+        {loading && <p className="text-secondary-400 text-sm mb-2">Loading...</p>}
+        <table className="w-full text-sm">
+          <thead>
+            <tr className="text-left text-secondary-400">
+              <th className="p-2">Employee</th>
+              {days.map((date, i) => (
+                <th key={date} className="p-2">{DAY_NAMES[i]} <span className="text-secondary-500">{date.slice(5)}</span></th>
+              ))}
+            </tr>
+          </thead>
+          <tbody>
+            {rowUsers.map((user) => (
+              <tr key={user.id} className="border-t border-secondary-700">
+                <td className="p-2 text-white">{user.displayName}</td>
+                {days.map((date) => {
+                  const variance = varianceFor(user.id, date);
+                  return (
+                    <td key={date} className="p-1">
+                      <button
+                        onClick={() => setEditing({ user, date })}
+                        className={`w-full min-h-[3rem] rounded border p-1 text-left text-xs ${
+                          variance ? STATUS_COLORS[variance.status] : 'border-secondary-700 text-secondary-500 hover:border-secondary-500'
+                        }`}
+                      >
+                        {variance?.plannedStart && <div>{variance.plannedStart}–{variance.plannedEnd}</div>}
+                        {variance && (variance.checkInTime || variance.checkOutTime) && (
+                          <div className="opacity-80">
+                            {variance.checkInTime ?? '--:--'}–{variance.checkOutTime ?? '--:--'}
+                          </div>
+                        )}
+                        {variance && variance.status !== 'gap' && <div>{formatVariance(variance.varianceMinutes)}</div>}
+                        {variance?.status === 'gap' && <div>No show</div>}
+                      </button>
+                    </td>
+                  );
+                })}
+              </tr>
+            ))}
+            {report && report.coverage.length > 0 && (
+              <tr className="border-t-2 border-secondary-600 text-secondary-300">
+                <td className="p-2 font-medium">Coverage</td>
+                {days.map((date) => {
+                  const day = report.coverage.find((c) => c.date === date);
+                  return (
+                    <td key={date} className="p-2 text-xs">
+                      {day && day.planned > 0 && (
+                        <span className={day.gaps > 0 ? 'text-danger-500' : 'text-success-500'}>
+                          {day.attended}/{day.planned}
+                        </span>
+                      )}
+                      {day && day.unplanned > 0 && <span className="text-secondary-500"> +{day.unplanned}</span>}
+                    </td>
+                  );
+                })}
+              </tr>
+            )}
+          </tbody>
+        </table>
+        {rowUsers.length === 0 && !loading && (
+          <p className="text-secondary-400 text-sm mt-2">Nobody is rostered this week. Import a CSV or add a shift below.</p>
+        )}
+        {otherUsers.length > 0 && (
+          <div className="mt-4 flex items-center gap-2">
+            <select
+              defaultValue=""
+              onChange={(e) => {
+                const user = otherUsers.find((u) => u.id === e.target.value);
+                if (user) setEditing({ user, date: weekStart });
+                e.target.value = '';
+              }}
+              className="input"
+            >
+              <option value="" disabled>Add shift for...</option>
+              {otherUsers.map((u) => (
+                <option key={u.id} value={u.id}>{u.displayName}</option>
+              ))}
+            </select>
+          </div>
+        )}
+      </div>
+
+      <AnimatePresence>
+        {editing && (
+          <ShiftModal
+            user={editing.user}
+            date={editing.date}
+            entry={entryFor(editing.user.id, editing.date)}
+            onSave={async (startTime, endTime, note) => {
+              await saveRosterEntries([{ userId: editing.user.id, date: editing.date, startTime, endTime, note: note || null }]);
+              await load();
+            }}
+            onDelete={async () => {
+              const entry = entryFor(editing.user.id, editing.date);
+              if (entry) await deleteRosterEntry(entry.id);
+              await load();
+            }}
+            onClose={() => setEditing(null)}
+          />
+        )}
+      </AnimatePresence>
+    </div>
+  );
+}
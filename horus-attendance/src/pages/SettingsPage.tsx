@@ -3,7 +3,8 @@ import { useState, useEffect, useCallback } from 'react';
 import type { AppSettings, AttendanceRules, Holiday, CreateHolidayInput, ExportSettings, TimezoneSettings } from '../types/models';
 import { settingsRepository } from '../lib/repositories/settings.repository';
 import { holidayRepository } from '../lib/repositories/holiday.repository';
-import { exportBackup, restoreBackup, selectBackupFile, selectBackupDestination, isTauriEnvironment, formatFileSize, resetDatabase } from '../lib/tauri-commands';
+import { exportBackup, restoreBackup, selectBackupFile, selectBackupDestination, isTauriEnvironment, formatFileSize, resetDatabase, detectShifts, acceptShiftProposals, recomputeSummaries, getRawPayloadSettings, saveRawPayloadSettings, getRawPayloadUsage, purgeRawPayloads, getClosureSettings, saveClosureSettings, sendTestEmail, closeAttendanceMonth, setCredential, getSummaryEngine, setSummaryEngine, benchmarkRecompute } from '../lib/tauri-commands';
+import type { ShiftProposal, RawPayloadSettings, RawPayloadUsage, ClosureSettings, SmtpSettings, SummaryEngine, RecomputeBenchmark } from '../lib/tauri-commands';
 import { useApp } from '../contexts';
 import { ConfirmDialog } from '../components/ui';
 import { TIMEZONE_OPTIONS } from '../lib/utils/timezone';
@@ -281,6 +282,13 @@ function AttendanceRulesSection({ rules, onChange, onSave, saving }: AttendanceR
               value={rules.workEndTime}
               onChange={(v) => handleChange('workEndTime', v)}
             />
+            <NumberInput
+              label="Working Day Starts At"
+              value={rules.dayCutoffHour ?? 0}
+              onChange={(v) => handleChange('dayCutoffHour', Math.min(v, 12))}
+              max={12}
+              suffix="h (punches before this count toward the previous day)"
+            />
           </div>
         </div>
 
@@ -477,6 +485,546 @@ function HolidaysSection({ holidays, onAdd, onDelete }: HolidaysSectionProps) {
 }
 
 
+// Shift Detection Section Component
+/** Proposals at least this consistent are selected for acceptance by default */
+const CONSISTENT_SHIFT = 0.7;
+
+function ShiftDetectionSection() {
+  const { showNotification } = useApp();
+  const [weeks, setWeeks] = useState(4);
+  const [proposals, setProposals] = useState<ShiftProposal[] | null>(null);
+  const [selected, setSelected] = useState<Set<string>>(new Set());
+  const [busy, setBusy] = useState<'detecting' | 'accepting' | null>(null);
+
+  const handleDetect = async () => {
+    setBusy('detecting');
+    try {
+      const result = await detectShifts(weeks);
+      setProposals(result);
+      setSelected(new Set(result.filter((p) => p.consistency >= CONSISTENT_SHIFT).map((p) => p.userId)));
+    } catch (error) {
+      showNotification(`Shift detection failed: ${error instanceof Error ? error.message : String(error)}`, 'error');
+    } finally {
+      setBusy(null);
+    }
+  };
+
+  const handleAccept = async () => {
+    if (!proposals) return;
+    setBusy('accepting');
+    try {
+      const accepted = proposals
+        .filter((p) => selected.has(p.userId))
+        .map(({ userId, startTime, endTime }) => ({ userId, startTime, endTime }));
+      const count = await acceptShiftProposals(accepted);
+      // Re-judge the analysed weeks against the new shifts
+      const end = new Date();
+      const start = new Date(end.getTime() - weeks * 7 * 24 * 60 * 60 * 1000);
+      await recomputeSummaries(start.toISOString().slice(0, 10), end.toISOString().slice(0, 10));
+      setProposals(proposals.filter((p) => !selected.has(p.userId)));
+      setSelected(new Set());
+      showNotification(`Assigned ${count} shifts`, 'success');
+    } catch (error) {
+      showNotification(`Failed to assign shifts: ${error instanceof Error ? error.message : String(error)}`, 'error');
+    } finally {
+      setBusy(null);
+    }
+  };
+
+  const toggle = (userId: string) => {
+    const next = new Set(selected);
+    if (next.has(userId)) next.delete(userId);
+    else next.add(userId);
+    setSelected(next);
+  };
+
+  return (
+    <motion.div variants={cardVariants} className="card">
+      <SectionHeader
+        title="Shift Detection"
+        description="Infer each employee's usual start and end time from past punches and assign it as their shift"
+      />
+      <div className="flex items-end gap-3 mb-4">
+        <div className="w-40">
+          <NumberInput label="Weeks to analyse" value={weeks} onChange={setWeeks} min={1} max={26} />
+        </div>
+        <motion.button
+          whileHover={{ scale: 1.02 }}
+          whileTap={{ scale: 0.98 }}
+          onClick={handleDetect}
+          disabled={busy !== null}
+          className="btn-secondary"
+        >
+          {busy === 'detecting' ? 'Analysing...' : 'Analyse Punches'}
+        </motion.button>
+        {proposals && proposals.length > 0 && (
+          <motion.button
+            whileHover={{ scale: 1.02 }}
+            whileTap={{ scale: 0.98 }}
+            onClick={handleAccept}
+            disabled={busy !== null || selected.size === 0}
+            className="btn-primary"
+          >
+            {busy === 'accepting' ? 'Assigning...' : `Accept ${selected.size} Selected`}
+          </motion.button>
+        )}
+      </div>
+      {proposals && proposals.length === 0 && (
+        <p className="text-secondary-400 text-sm">Not enough punches to propose any shifts</p>
+      )}
+      {proposals && proposals.length > 0 && (
+        <div className="max-h-80 overflow-y-auto">
+          <table className="w-full text-sm">
+            <thead>
+              <tr className="text-left text-secondary-400">
+                <th className="p-2" />
+                <th className="p-2">Employee</th>
+                <th className="p-2">Proposed</th>
+                <th className="p-2">Current</th>
+                <th className="p-2">Days</th>
+                <th className="p-2">Consistency</th>
+              </tr>
+            </thead>
+            <tbody>
+              {proposals.map((p) => (
+                <tr key={p.userId} className="border-t border-secondary-700 text-white">
+                  <td className="p-2">
+                    <input type="checkbox" checked={selected.has(p.userId)} onChange={() => toggle(p.userId)} />
+                  </td>
+                  <td className="p-2">{p.displayName}</td>
+                  <td className="p-2">{p.startTime} – {p.endTime}</td>
+                  <td className="p-2 text-secondary-400">
+                    {p.current ? `${p.current.startTime} – ${p.current.endTime}` : 'Global hours'}
+                  </td>
+                  <td className="p-2">{p.daysObserved}</td>
+                  <td className={`p-2 ${p.consistency >= CONSISTENT_SHIFT ? 'text-success-500' : 'text-warning-500'}`}>
+                    {Math.round(p.consistency * 100)}%
+                  </td>
+                </tr>
+              ))}
+            </tbody>
+          </table>
+        </div>
+      )}
+    </motion.div>
+  );
+}
+
+// Summary Engine Section Component
+function SummaryEngineSection() {
+  const { showNotification } = useApp();
+  const [engine, setEngine] = useState<SummaryEngine>('set_based');
+  const [benchmark, setBenchmark] = useState<RecomputeBenchmark | null>(null);
+  const [running, setRunning] = useState(false);
+
+  useEffect(() => {
+    getSummaryEngine().then(setEngine).catch(() => {});
+  }, []);
+
+  const handleEngineChange = async (value: SummaryEngine) => {
+    try {
+      await setSummaryEngine(value);
+      setEngine(value);
+      showNotification('Summary engine saved', 'success');
+    } catch (error) {
+      showNotification(`Failed to save engine: ${error instanceof Error ? error.message : String(error)}`, 'error');
+    }
+  };
+
+  const handleBenchmark = async () => {
+    setRunning(true);
+    try {
+      // The last full month, a typical recompute
+      const now = new Date();
+      const start = new Date(now.getFullYear(), now.getMonth() - 1, 1);
+      const end = new Date(now.getFullYear(), now.getMonth(), 0);
+      const format = (d: Date) =>
+        `${d.getFullYear()}-${String(d.getMonth() + 1).padStart(2, '0')}-${String(d.getDate()).padStart(2, '0')}`;
+      setBenchmark(await benchmarkRecompute(format(start), format(end)));
+    } catch (error) {
+      showNotification(`Benchmark failed: ${error instanceof Error ? error.message : String(error)}`, 'error');
+    } finally {
+      setRunning(false);
+    }
+  };
+
+  return (
+    <motion.div variants={cardVariants} className="card">
+      <SectionHeader
+        title="Summary Engine"
+        description="How daily summaries are recomputed. The benchmark times both engines on last month without changing any data."
+      />
+      <div className="flex items-end gap-3 mb-4">
+        <div>
+          <label className="block text-sm font-medium text-secondary-300 mb-1">Engine</label>
+          <select
+            value={engine}
+            onChange={(e) => handleEngineChange(e.target.value as SummaryEngine)}
+            className="input"
+          >
+            <option value="set_based">Set-based (fast)</option>
+            <option value="row_by_row">Row-by-row</option>
+          </select>
+        </div>
+        <motion.button
+          whileHover={{ scale: 1.02 }}
+          whileTap={{ scale: 0.98 }}
+          onClick={handleBenchmark}
+          disabled={running}
+          className="btn-secondary"
+        >
+          {running ? 'Benchmarking...' : 'Run Benchmark'}
+        </motion.button>
+      </div>
+      {benchmark && (
+        <div className="text-sm text-secondary-300 space-y-1">
+          <p>
+            {benchmark.setBased.daysProcessed.toLocaleString()} days from{' '}
+            {benchmark.setBased.logsProcessed.toLocaleString()} punches ({benchmark.startDate} – {benchmark.endDate})
+          </p>
+          <p>
+            Set-based: <span className="text-white">{benchmark.setBased.bestMs} ms</span> · Row-by-row:{' '}
+            <span className="text-white">{benchmark.rowByRow.bestMs} ms</span> · {benchmark.speedup}× faster
+          </p>
+          {benchmark.differingDays === 0 ? (
+            <p className="text-success-500">Both engines produced identical summaries</p>
+          ) : (
+            <div className="text-warning-500">
+              <p>{benchmark.differingDays} days differ between the engines</p>
+              <ul className="list-disc list-inside text-xs">
+                {benchmark.differences.map((d) => (
+                  <li key={d}>{d}</li>
+                ))}
+              </ul>
+            </div>
+          )}
+        </div>
+      )}
+    </motion.div>
+  );
+}
+
+// Raw Payload Section Component
+function RawPayloadSection() {
+  const { showNotification } = useApp();
+  const [settings, setSettings] = useState<RawPayloadSettings | null>(null);
+  const [usage, setUsage] = useState<RawPayloadUsage | null>(null);
+  const [purgeBefore, setPurgeBefore] = useState('');
+  const [confirmPurgeOpen, setConfirmPurgeOpen] = useState(false);
+  const [busy, setBusy] = useState<'saving' | 'purging' | null>(null);
+
+  const loadUsage = useCallback(async () => {
+    try {
+      setUsage(await getRawPayloadUsage());
+    } catch (error) {
+      console.error('Failed to load raw payload usage:', error);
+    }
+  }, []);
+
+  useEffect(() => {
+    getRawPayloadSettings()
+      .then(setSettings)
+      .catch((error) => console.error('Failed to load raw payload settings:', error));
+    loadUsage();
+  }, [loadUsage]);
+
+  const handleSave = async () => {
+    if (!settings) return;
+    setBusy('saving');
+    try {
+      await saveRawPayloadSettings(settings);
+      showNotification('Raw payload settings saved', 'success');
+    } catch (error) {
+      showNotification(`Failed to save raw payload settings: ${error instanceof Error ? error.message : String(error)}`, 'error');
+    } finally {
+      setBusy(null);
+    }
+  };
+
+  const handlePurge = async () => {
+    setConfirmPurgeOpen(false);
+    setBusy('purging');
+    try {
+      const deleted = await purgeRawPayloads(purgeBefore || undefined);
+      showNotification(`Purged ${deleted} raw payloads`, 'success');
+      await loadUsage();
+    } catch (error) {
+      showNotification(`Failed to purge raw payloads: ${error instanceof Error ? error.message : String(error)}`, 'error');
+    } finally {
+      setBusy(null);
+    }
+  };
+
+  if (!settings) return null;
+
+  return (
+    <motion.div variants={cardVariants} className="card">
+      <SectionHeader
+        title="Raw Payload Retention"
+        description="Keep each attendance record exactly as the device sent it, for tracing decoding problems"
+      />
+      <div className="space-y-4">
+        <label className="flex items-center gap-2 text-sm text-secondary-300">
+          <input
+            type="checkbox"
+            checked={settings.enabled}
+            onChange={(e) => setSettings({ ...settings, enabled: e.target.checked })}
+          />
+          Keep raw payloads when syncing
+        </label>
+        <div className="grid grid-cols-2 gap-4">
+          <div>
+            <label className="block text-sm font-medium text-secondary-300 mb-1">Encoding</label>
+            <select
+              value={settings.encoding}
+              onChange={(e) => setSettings({ ...settings, encoding: e.target.value as RawPayloadSettings['encoding'] })}
+              className="input w-full"
+            >
+              <option value="zstd">Compressed (zstd)</option>
+              <option value="hex">Hex</option>
+            </select>
+          </div>
+          <NumberInput
+            label="Size cap"
+            value={settings.maxMegabytes}
+            onChange={(maxMegabytes) => setSettings({ ...settings, maxMegabytes })}
+            min={1}
+            max={10240}
+            suffix="MB"
+          />
+        </div>
+        {usage && (
+          <p className="text-sm text-secondary-400">
+            {usage.records} payloads using {formatFileSize(usage.storedBytes)} of {formatFileSize(usage.maxBytes)}
+            {usage.records > 0 && ` (${formatFileSize(usage.originalBytes)} as received)`}
+          </p>
+        )}
+        <div className="flex items-end gap-3">
+          <motion.button
+            whileHover={{ scale: 1.02 }}
+            whileTap={{ scale: 0.98 }}
+            onClick={handleSave}
+            disabled={busy !== null}
+            className="btn-primary"
+          >
+            {busy === 'saving' ? 'Saving...' : 'Save'}
+          </motion.button>
+          <div>
+            <label className="block text-sm font-medium text-secondary-300 mb-1">Kept before (optional)</label>
+            <input type="date" value={purgeBefore} onChange={(e) => setPurgeBefore(e.target.value)} className="input" />
+          </div>
+          <motion.button
+            whileHover={{ scale: 1.02 }}
+            whileTap={{ scale: 0.98 }}
+            onClick={() => setConfirmPurgeOpen(true)}
+            disabled={busy !== null || !usage || usage.records === 0}
+            className="btn-secondary"
+          >
+            {busy === 'purging' ? 'Purging...' : 'Purge'}
+          </motion.button>
+        </div>
+      </div>
+      <ConfirmDialog
+        open={confirmPurgeOpen}
+        title="Purge Raw Payloads"
+        message={purgeBefore ? `Delete raw payloads kept before ${purgeBefore}?` : 'Delete all raw payloads?'}
+        confirmLabel="Purge"
+        variant="danger"
+        onConfirm={handlePurge}
+        onCancel={() => setConfirmPurgeOpen(false)}
+      />
+    </motion.div>
+  );
+}
+
+// Closure Email Section Component
+function ClosureEmailSection() {
+  const { showNotification } = useApp();
+  const [settings, setSettings] = useState<ClosureSettings | null>(null);
+  const [password, setPassword] = useState('');
+  const [testAddress, setTestAddress] = useState('');
+  const [closeMonth, setCloseMonth] = useState('');
+  const [resend, setResend] = useState(false);
+  const [busy, setBusy] = useState<'saving' | 'testing' | 'closing' | null>(null);
+
+  useEffect(() => {
+    getClosureSettings()
+      .then(setSettings)
+      .catch((error) => console.error('Failed to load closure settings:', error));
+  }, []);
+
+  const errorText = (error: unknown) => (error instanceof Error ? error.message : String(error));
+
+  const handleSave = async () => {
+    if (!settings) return;
+    setBusy('saving');
+    try {
+      await saveClosureSettings(settings);
+      if (password) {
+        await setCredential('smtp-password', password);
+        setPassword('');
+      }
+      showNotification('Closure email settings saved', 'success');
+    } catch (error) {
+      showNotification(`Failed to save closure email settings: ${errorText(error)}`, 'error');
+    } finally {
+      setBusy(null);
+    }
+  };
+
+  const handleTest = async () => {
+    setBusy('testing');
+    try {
+      await sendTestEmail(testAddress);
+      showNotification(`Test email sent to ${testAddress}`, 'success');
+    } catch (error) {
+      showNotification(`Test email failed: ${errorText(error)}`, 'error');
+    } finally {
+      setBusy(null);
+    }
+  };
+
+  const handleClose = async () => {
+    const [year, month] = closeMonth.split('-').map(Number);
+    if (!year || !month) return;
+    setBusy('closing');
+    try {
+      const run = await closeAttendanceMonth(year, month, resend);
+      showNotification(
+        `Closed ${closeMonth}: ${run.sent} sent, ${run.failed} failed, ${run.noEmail} without email` +
+          (run.alreadySent > 0 ? `, ${run.alreadySent} already sent` : ''),
+        run.failed > 0 ? 'error' : 'success'
+      );
+    } catch (error) {
+      showNotification(`Failed to close ${closeMonth}: ${errorText(error)}`, 'error');
+    } finally {
+      setBusy(null);
+    }
+  };
+
+  if (!settings) return null;
+  const smtp = settings.smtp;
+  const setSmtp = (changes: Partial<SmtpSettings>) => setSettings({ ...settings, smtp: { ...smtp, ...changes } });
+
+  return (
+    <motion.div variants={cardVariants} className="card">
+      <SectionHeader
+        title="Monthly Closure Emails"
+        description="Email each employee their month with a link to dispute it; disputes arrive in the exception queue"
+      />
+      <div className="space-y-4">
+        <label className="flex items-center gap-2 text-sm text-secondary-300">
+          <input
+            type="checkbox"
+            checked={settings.enabled}
+            onChange={(e) => setSettings({ ...settings, enabled: e.target.checked })}
+          />
+          Send the previous month automatically
+        </label>
+        <div className="grid grid-cols-2 gap-4">
+          <NumberInput
+            label="Closure day"
+            value={settings.dayOfMonth}
+            onChange={(dayOfMonth) => setSettings({ ...settings, dayOfMonth })}
+            min={1}
+            max={28}
+          />
+          <NumberInput
+            label="Dispute window"
+            value={settings.disputeDays}
+            onChange={(disputeDays) => setSettings({ ...settings, disputeDays })}
+            min={1}
+            max={60}
+            suffix="days"
+          />
+        </div>
+        <div className="grid grid-cols-2 gap-4">
+          <div>
+            <label className="block text-sm font-medium text-secondary-300 mb-1">Mail server</label>
+            <input type="text" value={smtp.host} onChange={(e) => setSmtp({ host: e.target.value })} className="input w-full" placeholder="smtp.example.com" />
+          </div>
+          <div className="grid grid-cols-2 gap-2">
+            <NumberInput label="Port" value={smtp.port} onChange={(port) => setSmtp({ port })} min={1} max={65535} />
+            <div>
+              <label className="block text-sm font-medium text-secondary-300 mb-1">Security</label>
+              <select
+                value={smtp.security}
+                onChange={(e) => setSmtp({ security: e.target.value as SmtpSettings['security'] })}
+                className="input w-full"
+              >
+                <option value="starttls">STARTTLS</option>
+                <option value="tls">TLS</option>
+                <option value="none">None</option>
+              </select>
+            </div>
+          </div>
+          <div>
+            <label className="block text-sm font-medium text-secondary-300 mb-1">Username</label>
+            <input type="text" value={smtp.username} onChange={(e) => setSmtp({ username: e.target.value })} className="input w-full" />
+          </div>
+          <div>
+            <label className="block text-sm font-medium text-secondary-300 mb-1">Password</label>
+            <input
+              type="password"
+              value={password}
+              onChange={(e) => setPassword(e.target.value)}
+              className="input w-full"
+              placeholder="Unchanged"
+            />
+          </div>
+          <div>
+            <label className="block text-sm font-medium text-secondary-300 mb-1">From address</label>
+            <input type="email" value={smtp.fromAddress} onChange={(e) => setSmtp({ fromAddress: e.target.value })} className="input w-full" placeholder="hr@example.com" />
+          </div>
+        </div>
+        <div className="flex items-end gap-3">
+          <motion.button
+            whileHover={{ scale: 1.02 }}
+            whileTap={{ scale: 0.98 }}
+            onClick={handleSave}
+            disabled={busy !== null}
+            className="btn-primary"
+          >
+            {busy === 'saving' ? 'Saving...' : 'Save'}
+          </motion.button>
+          <div>
+            <label className="block text-sm font-medium text-secondary-300 mb-1">Test recipient</label>
+            <input type="email" value={testAddress} onChange={(e) => setTestAddress(e.target.value)} className="input" />
+          </div>
+          <motion.button
+            whileHover={{ scale: 1.02 }}
+            whileTap={{ scale: 0.98 }}
+            onClick={handleTest}
+            disabled={busy !== null || !testAddress}
+            className="btn-secondary"
+          >
+            {busy === 'testing' ? 'Sending...' : 'Send Test'}
+          </motion.button>
+        </div>
+        <div className="flex items-end gap-3">
+          <div>
+            <label className="block text-sm font-medium text-secondary-300 mb-1">Close month now</label>
+            <input type="month" value={closeMonth} onChange={(e) => setCloseMonth(e.target.value)} className="input" />
+          </div>
+          <label className="flex items-center gap-2 text-sm text-secondary-300 pb-2">
+            <input type="checkbox" checked={resend} onChange={(e) => setResend(e.target.checked)} />
+            Resend to those already sent
+          </label>
+          <motion.button
+            whileHover={{ scale: 1.02 }}
+            whileTap={{ scale: 0.98 }}
+            onClick={handleClose}
+            disabled={busy !== null || !closeMonth}
+            className="btn-secondary"
+          >
+            {busy === 'closing' ? 'Sending...' : 'Send'}
+          </motion.button>
+        </div>
+      </div>
+    </motion.div>
+  );
+}
+
 // Backup Section Component
 interface BackupSectionProps {
   lastBackupAt: string | null;
@@ -1121,6 +1669,16 @@ export function SettingsPage() {
           />
         </div>
 
+        {/* Shift Detection */}
+        {isTauriEnvironment() && (
+          <div className="lg:col-span-2">
+            <ShiftDetectionSection />
+          </div>
+        )}
+
+        {/* Summary Engine */}
+        {isTauriEnvironment() && <SummaryEngineSection />}
+
         {/* Timezone Settings */}
         <TimezoneSection
           settings={settings.timezone}
@@ -1144,6 +1702,10 @@ export function SettingsPage() {
           onReset={handleResetDatabase}
         />
 
+        {/* Raw Payload Retention */}
+        {isTauriEnvironment() && <RawPayloadSection />}
+        {isTauriEnvironment() && <ClosureEmailSection />}
+
         {/* Export Settings */}
         <ExportSettingsSection
           settings={settings.export}
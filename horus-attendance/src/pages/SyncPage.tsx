@@ -1,11 +1,14 @@
 import { motion, AnimatePresence } from 'framer-motion';
 import { useState, useEffect, useCallback } from 'react';
-import type { Device, DeviceConfig, DeviceInfo } from '../types/models';
-import type { SyncOptions, SyncResult, SyncProgress } from '../types/services';
+import type { Device, DeviceConfig, DeviceInfo, DstPolicy, DstRegion } from '../types/models';
+import type { SyncOptions, SyncResult, SyncProgress, SyncPreview } from '../types/services';
 import { listDevices, saveDevice, deleteDevice } from '../lib/repositories/device.repository';
+import { ensureDeviceLogsUnlocked } from '../lib/repositories/attendance-log.repository';
 import { getSyncEngine } from '../lib/services/sync-engine';
+import { getDeviceCommunicationService, type DeviceOptionValue } from '../lib/services/device-communication';
 import { useApp, useSync } from '../contexts';
 import { ConfirmDialog } from '../components/ui';
+import { createOperationSnapshot, deleteDeviceCommKey, isTauriEnvironment, KEYCHAIN_REF, storeDeviceCommKey } from '../lib/tauri-commands';
 
 // Animation variants
 const containerVariants = {
@@ -45,7 +48,11 @@ interface DeviceFormData {
   port: string;
   commKey: string;
   timezone: string;
+  dstPolicy: DstPolicy;
+  dstRules: DstRegion;
   syncMode: 'auto' | 'manual';
+  site: string;
+  syncPriority: string;
 }
 
 const defaultFormData: DeviceFormData = {
@@ -54,7 +61,11 @@ const defaultFormData: DeviceFormData = {
   port: '4370',
   commKey: '',
   timezone: 'UTC',
+  dstPolicy: 'zone',
+  dstRules: 'us',
   syncMode: 'manual',
+  site: '',
+  syncPriority: '0',
 };
 
 function validateIp(ip: string): boolean {
@@ -160,6 +171,14 @@ function DeviceInfoDisplay({ info, latency }: { info: DeviceInfo; latency: numbe
         <p className="text-secondary-400">Firmware</p>
         <p className="text-white font-medium">{info.firmwareVersion}</p>
       </div>
+      <div>
+        <p className="text-secondary-400">Model</p>
+        <p className="text-white font-medium">{info.model || 'Unknown'}</p>
+      </div>
+      <div>
+        <p className="text-secondary-400">MAC Address</p>
+        <p className="text-white font-medium">{info.macAddress || 'Unknown'}</p>
+      </div>
       <div>
         <p className="text-secondary-400">Users on Device</p>
         <p className="text-white font-medium">{info.userCount}</p>
@@ -180,8 +199,63 @@ function DeviceInfoDisplay({ info, latency }: { info: DeviceInfo; latency: numbe
   );
 }
 
+// Dry Run Result Display Component
+function SyncPreviewDisplay({ preview, quarantined }: { preview: SyncPreview; quarantined: number }) {
+  const formatRange = (timestamp: string | null) => (timestamp ? timestamp.replace('T', ' ').slice(0, 16) : '—');
+  return (
+    <div className="space-y-4">
+      <div className="flex items-center gap-2">
+        <svg className="w-5 h-5 text-primary-400" fill="none" stroke="currentColor" viewBox="0 0 24 24">
+          <path strokeLinecap="round" strokeLinejoin="round" strokeWidth={2} d="M15 12a3 3 0 11-6 0 3 3 0 016 0z" />
+          <path strokeLinecap="round" strokeLinejoin="round" strokeWidth={2} d="M2.458 12C3.732 7.943 7.523 5 12 5c4.478 0 8.268 2.943 9.542 7-1.274 4.057-5.064 7-9.542 7-4.477 0-8.268-2.943-9.542-7z" />
+        </svg>
+        <span className="text-primary-400 font-medium">Dry Run — nothing was stored</span>
+      </div>
+      <div className="grid grid-cols-2 md:grid-cols-4 gap-4 text-sm">
+        <div className="bg-secondary-700/50 rounded-lg p-3">
+          <p className="text-secondary-400">New Users</p>
+          <p className="text-2xl font-bold text-white">{preview.newUsers.length}</p>
+        </div>
+        <div className="bg-secondary-700/50 rounded-lg p-3">
+          <p className="text-secondary-400">Known Users</p>
+          <p className="text-2xl font-bold text-white">{preview.existingUsers}</p>
+        </div>
+        <div className="bg-secondary-700/50 rounded-lg p-3">
+          <p className="text-secondary-400">New Logs</p>
+          <p className="text-2xl font-bold text-white">{preview.newLogs}</p>
+        </div>
+        <div className="bg-secondary-700/50 rounded-lg p-3">
+          <p className="text-secondary-400">Already Stored</p>
+          <p className="text-2xl font-bold text-white">{preview.duplicateLogs}</p>
+        </div>
+      </div>
+      <p className="text-sm text-secondary-400">
+        Logs cover {formatRange(preview.firstTimestamp)} to {formatRange(preview.lastTimestamp)}
+        {preview.unknownUserLogs > 0 && (
+          <span className="text-warning-500"> · {preview.unknownUserLogs} logs belong to user IDs not found on the device or in the app</span>
+        )}
+        {quarantined > 0 && (
+          <span className="text-warning-500"> · {quarantined} invalid records would be quarantined</span>
+        )}
+      </p>
+      {preview.newUsers.length > 0 && (
+        <div className="text-sm text-secondary-300">
+          <p className="text-secondary-400 mb-1">Users that would be added:</p>
+          <p>
+            {preview.newUsers.slice(0, 20).map((u) => `${u.deviceName} (${u.deviceUserId})`).join(', ')}
+            {preview.newUsers.length > 20 && ` and ${preview.newUsers.length - 20} more`}
+          </p>
+        </div>
+      )}
+    </div>
+  );
+}
+
 // Sync Result Display Component
 function SyncResultDisplay({ result }: { result: SyncResult }) {
+  if (result.preview && result.success) {
+    return <SyncPreviewDisplay preview={result.preview} quarantined={result.quarantined ?? 0} />;
+  }
   return (
     <div className="space-y-4">
       <div className="flex items-center gap-2">
@@ -219,6 +293,14 @@ function SyncResultDisplay({ result }: { result: SyncResult }) {
           <p className="text-2xl font-bold text-white">{result.logsDeduplicated}</p>
         </div>
       </div>
+      {(result.quarantined ?? 0) > 0 && (
+        <p className="text-sm text-warning-500">
+          {result.quarantined} device records failed validation and were quarantined for review
+        </p>
+      )}
+      {result.warnings?.map((warning, i) => (
+        <p key={i} className="text-sm text-warning-500">{warning}</p>
+      ))}
       {result.errors.length > 0 && (
         <div className="bg-danger-600/10 border border-danger-600/30 rounded-lg p-4">
           <p className="text-danger-500 font-medium mb-2">Errors:</p>
@@ -242,9 +324,11 @@ interface DeviceFormProps {
   saving: boolean;
   isEditing: boolean;
   errors: Record<string, string>;
+  /** The saved comm key is in the keychain; a blank field keeps it */
+  commKeyStored: boolean;
 }
 
-function DeviceForm({ formData, onChange, onSave, onDelete, saving, isEditing, errors }: DeviceFormProps) {
+function DeviceForm({ formData, onChange, onSave, onDelete, saving, isEditing, errors, commKeyStored }: DeviceFormProps) {
   const handleChange = (field: keyof DeviceFormData, value: string) => {
     onChange({ ...formData, [field]: value });
   };
@@ -291,7 +375,7 @@ function DeviceForm({ formData, onChange, onSave, onDelete, saving, isEditing, e
             type="password"
             value={formData.commKey}
             onChange={(e) => handleChange('commKey', e.target.value)}
-            placeholder="Optional"
+            placeholder={commKeyStored ? 'Stored in system keychain (leave blank to keep)' : 'Optional'}
             className="input w-full"
           />
         </div>
@@ -307,6 +391,32 @@ function DeviceForm({ formData, onChange, onSave, onDelete, saving, isEditing, e
             ))}
           </select>
         </div>
+        <div>
+          <label className="block text-sm font-medium text-secondary-300 mb-1">Daylight Saving</label>
+          <select
+            value={formData.dstPolicy}
+            onChange={(e) => handleChange('dstPolicy', e.target.value as DstPolicy)}
+            className="input w-full"
+          >
+            <option value="zone">Device clock follows the timezone</option>
+            <option value="none">Device clock stays on standard time</option>
+            <option value="rules">Device changes its own clock</option>
+          </select>
+        </div>
+        {formData.dstPolicy === 'rules' && (
+          <div>
+            <label className="block text-sm font-medium text-secondary-300 mb-1">Device DST Rule</label>
+            <select
+              value={formData.dstRules}
+              onChange={(e) => handleChange('dstRules', e.target.value as DstRegion)}
+              className="input w-full"
+            >
+              <option value="us">United States / Canada</option>
+              <option value="eu">Europe</option>
+              <option value="au">Australia</option>
+            </select>
+          </div>
+        )}
         <div>
           <label className="block text-sm font-medium text-secondary-300 mb-1">Sync Mode</label>
           <select
@@ -318,6 +428,32 @@ function DeviceForm({ formData, onChange, onSave, onDelete, saving, isEditing, e
             <option value="auto">Automatic</option>
           </select>
         </div>
+        <div>
+          <label className="block text-sm font-medium text-secondary-300 mb-1">Site</label>
+          <input
+            type="text"
+            value={formData.site}
+            onChange={(e) => handleChange('site', e.target.value)}
+            placeholder="Head Office"
+            className="input w-full"
+          />
+        </div>
+        <div>
+          <label className="block text-sm font-medium text-secondary-300 mb-1">Sync Priority</label>
+          <input
+            type="number"
+            step={1}
+            value={formData.syncPriority}
+            onChange={(e) => handleChange('syncPriority', e.target.value)}
+            placeholder="0"
+            className={`input w-full ${errors.syncPriority ? 'border-danger-500' : ''}`}
+          />
+          {errors.syncPriority ? (
+            <p className="text-danger-500 text-xs mt-1">{errors.syncPriority}</p>
+          ) : (
+            <p className="text-secondary-500 text-xs mt-1">Higher syncs first when devices queue for a slot</p>
+          )}
+        </div>
       </div>
       <div className="flex gap-3 pt-2">
         <motion.button
@@ -355,6 +491,133 @@ function DeviceForm({ formData, onChange, onSave, onDelete, saving, isEditing, e
   );
 }
 
+// Device Settings Component (options stored on the terminal itself)
+function DeviceSettingsSection({ device }: { device: DeviceConfig }) {
+  const { showNotification } = useApp();
+  const [options, setOptions] = useState<DeviceOptionValue[] | null>(null);
+  const [edits, setEdits] = useState<Record<string, string>>({});
+  const [busy, setBusy] = useState<'loading' | 'saving' | 'clock' | null>(null);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    setOptions(null);
+    setEdits({});
+    setError(null);
+  }, [device.id]);
+
+  const handleLoad = async () => {
+    setBusy('loading');
+    setError(null);
+    try {
+      setOptions(await getDeviceCommunicationService().getDeviceOptions(device));
+      setEdits({});
+    } catch (err) {
+      setError(err instanceof Error ? err.message : String(err));
+    } finally {
+      setBusy(null);
+    }
+  };
+
+  const handleSave = async () => {
+    setBusy('saving');
+    setError(null);
+    try {
+      setOptions(await getDeviceCommunicationService().setDeviceOptions(device, edits));
+      setEdits({});
+      showNotification('Device settings saved', 'success');
+    } catch (err) {
+      setError(err instanceof Error ? err.message : String(err));
+    } finally {
+      setBusy(null);
+    }
+  };
+
+  const handleSetClock = async () => {
+    setBusy('clock');
+    setError(null);
+    try {
+      const time = await getDeviceCommunicationService().setDeviceTime(device);
+      showNotification(`Device clock set to ${time.replace('T', ' ')}`, 'success');
+    } catch (err) {
+      setError(err instanceof Error ? err.message : String(err));
+    } finally {
+      setBusy(null);
+    }
+  };
+
+  return (
+    <div className="mt-6 pt-6 border-t border-secondary-700">
+      <div className="flex items-center justify-between mb-4">
+        <h3 className="text-md font-medium text-white">Device Settings</h3>
+        <div className="flex gap-2">
+          <motion.button
+            whileHover={{ scale: 1.02 }}
+            whileTap={{ scale: 0.98 }}
+            onClick={handleSetClock}
+            disabled={busy !== null}
+            className="btn-secondary"
+          >
+            {busy === 'clock' ? 'Setting...' : 'Set Clock'}
+          </motion.button>
+          <motion.button
+            whileHover={{ scale: 1.02 }}
+            whileTap={{ scale: 0.98 }}
+            onClick={handleLoad}
+            disabled={busy !== null}
+            className="btn-secondary"
+          >
+            {busy === 'loading' ? 'Reading...' : options ? 'Reload' : 'Read from Device'}
+          </motion.button>
+          {options && (
+            <motion.button
+              whileHover={{ scale: 1.02 }}
+              whileTap={{ scale: 0.98 }}
+              onClick={handleSave}
+              disabled={busy !== null || Object.keys(edits).length === 0}
+              className="btn-primary"
+            >
+              {busy === 'saving' ? 'Saving...' : 'Save to Device'}
+            </motion.button>
+          )}
+        </div>
+      </div>
+      {error && <p className="text-sm text-danger-400 mb-3">{error}</p>}
+      {options && (
+        <div className="grid grid-cols-1 md:grid-cols-2 gap-4">
+          {options.map((option) => {
+            const value = edits[option.key] ?? option.value ?? '';
+            const setValue = (v: string) => setEdits({ ...edits, [option.key]: v });
+            return (
+              <div key={option.key}>
+                <label className="block text-sm font-medium text-secondary-300 mb-1">{option.label}</label>
+                {!option.supported ? (
+                  <p className="text-sm text-secondary-500">Not available on this device</p>
+                ) : option.boolean ? (
+                  <input
+                    type="checkbox"
+                    checked={value === '1'}
+                    onChange={(e) => setValue(e.target.checked ? '1' : '0')}
+                    className="w-4 h-4"
+                  />
+                ) : (
+                  <input
+                    type="number"
+                    min={option.min}
+                    max={option.max}
+                    value={value}
+                    onChange={(e) => setValue(e.target.value)}
+                    className="input w-32"
+                  />
+                )}
+              </div>
+            );
+          })}
+        </div>
+      )}
+    </div>
+  );
+}
+
 // Sync Options Component
 interface SyncOptionsProps {
   mode: SyncMode;
@@ -456,6 +719,7 @@ export function SyncPage() {
   const [saving, setSaving] = useState(false);
   const [loading, setLoading] = useState(true);
   const [confirmDeleteOpen, setConfirmDeleteOpen] = useState(false);
+  const [commKeyStored, setCommKeyStored] = useState(false);
 
   // Connection test state
   const [connectionStatus, setConnectionStatus] = useState<ConnectionStatus>({
@@ -501,16 +765,24 @@ export function SyncPage() {
   const selectDevice = (device: Device | null) => {
     if (device) {
       setSelectedDeviceId(device.id);
+      // Never show a keychain reference as if it were the key
+      const stored = device.commKey === KEYCHAIN_REF;
+      setCommKeyStored(stored);
       setFormData({
         name: device.name,
         ip: device.ip,
         port: device.port.toString(),
-        commKey: device.commKey,
+        commKey: stored ? '' : device.commKey,
         timezone: device.timezone,
+        dstPolicy: device.dstPolicy ?? 'zone',
+        dstRules: device.dstRules ?? 'us',
         syncMode: device.syncMode,
+        site: device.site ?? '',
+        syncPriority: String(device.syncPriority ?? 0),
       });
     } else {
       setSelectedDeviceId(null);
+      setCommKeyStored(false);
       setFormData(defaultFormData);
     }
     setConnectionStatus({ testing: false, success: null, deviceInfo: null, error: null, latency: 0 });
@@ -524,6 +796,7 @@ export function SyncPage() {
     else if (!validateIp(formData.ip)) errors.ip = 'Invalid IP address format';
     if (!formData.port.trim()) errors.port = 'Port is required';
     else if (!validatePort(formData.port)) errors.port = 'Port must be between 1 and 65535';
+    if (!/^-?\d+$/.test(formData.syncPriority.trim())) errors.syncPriority = 'Priority must be a whole number';
     setFormErrors(errors);
     return Object.keys(errors).length === 0;
   };
@@ -532,14 +805,25 @@ export function SyncPage() {
     if (!validateForm()) return;
     try {
       setSaving(true);
+      const id = selectedDeviceId || crypto.randomUUID();
+      let commKey = formData.commKey;
+      if (!commKey && commKeyStored) {
+        commKey = KEYCHAIN_REF;
+      } else if (isTauriEnvironment()) {
+        commKey = (await storeDeviceCommKey(id, commKey)).value;
+      }
       const config: DeviceConfig = {
-        id: selectedDeviceId || crypto.randomUUID(),
+        id,
         name: formData.name,
         ip: formData.ip,
         port: parseInt(formData.port, 10),
-        commKey: formData.commKey,
+        commKey,
         timezone: formData.timezone,
+        dstPolicy: formData.dstPolicy,
+        dstRules: formData.dstPolicy === 'rules' ? formData.dstRules : null,
         syncMode: formData.syncMode,
+        site: formData.site.trim() || null,
+        syncPriority: parseInt(formData.syncPriority, 10),
       };
       const saved = await saveDevice(config);
       await loadDevices();
@@ -556,12 +840,25 @@ export function SyncPage() {
     if (!selectedDeviceId) return;
     setConfirmDeleteOpen(false);
     try {
+      // Refuse before taking a snapshot when punches of a locked period would go
+      await ensureDeviceLogsUnlocked(selectedDeviceId);
+      // Deleting a device cascades to its punches; keep a snapshot so it can be undone
+      if (isTauriEnvironment()) {
+        const name = devices.find((d) => d.id === selectedDeviceId)?.name ?? selectedDeviceId;
+        await createOperationSnapshot(`Delete device ${name}`, ['devices', 'attendance_logs_raw']);
+      }
       await deleteDevice(selectedDeviceId);
+      if (isTauriEnvironment()) {
+        await deleteDeviceCommKey(selectedDeviceId);
+      }
       await loadDevices();
       selectDevice(null);
     } catch (error) {
       console.error('Failed to delete device:', error);
-      showNotification('Failed to delete device', 'error');
+      showNotification(
+        `Failed to delete device: ${error instanceof Error ? error.message : String(error)}`,
+        'error'
+      );
     }
   };
 
@@ -574,7 +871,7 @@ export function SyncPage() {
         name: formData.name,
         ip: formData.ip,
         port: parseInt(formData.port, 10),
-        commKey: formData.commKey,
+        commKey: !formData.commKey && commKeyStored ? KEYCHAIN_REF : formData.commKey,
         timezone: formData.timezone,
         syncMode: formData.syncMode,
       };
@@ -595,9 +892,9 @@ export function SyncPage() {
         latency: 0,
       });
     }
-  }, [formData, selectedDeviceId, syncEngine]);
+  }, [formData, selectedDeviceId, syncEngine, commKeyStored]);
 
-  const handleSync = useCallback(async () => {
+  const handleSync = useCallback(async (dryRun = false) => {
     if (!selectedDeviceId) return;
     const selectedDev = devices.find((d) => d.id === selectedDeviceId);
     if (!selectedDev) return;
@@ -608,9 +905,10 @@ export function SyncPage() {
       options.startDate = syncStartDate;
       options.endDate = syncEndDate;
     }
+    if (dryRun) options.dryRun = true;
     
     await startSync(selectedDeviceId, selectedDev.name, options);
-    await loadDevices(); // Refresh to get updated lastSyncAt
+    if (!dryRun) await loadDevices(); // Refresh to get updated lastSyncAt
   }, [selectedDeviceId, devices, syncMode, syncDays, syncStartDate, syncEndDate, startSync]);
 
   const selectedDevice = devices.find((d) => d.id === selectedDeviceId);
@@ -668,11 +966,21 @@ export function SyncPage() {
                 >
                   <p className="font-medium text-white">{device.name}</p>
                   <p className="text-sm text-secondary-400">{device.ip}:{device.port}</p>
+                  {(device.model || device.serialNumber) && (
+                    <p className="text-xs text-secondary-500 mt-1">
+                      {[device.model, device.serialNumber && `S/N ${device.serialNumber}`].filter(Boolean).join(' · ')}
+                    </p>
+                  )}
                   {device.lastSyncAt && (
                     <p className="text-xs text-secondary-500 mt-1">
                       Last sync: {new Date(device.lastSyncAt).toLocaleString()}
                     </p>
                   )}
+                  {device.lastError && device.lastErrorAt && (!device.lastSyncAt || device.lastErrorAt > device.lastSyncAt) && (
+                    <p className="text-xs text-danger-500 mt-1 truncate" title={device.lastError}>
+                      Last error: {device.lastError}
+                    </p>
+                  )}
                 </motion.button>
               ))
             )}
@@ -692,6 +1000,7 @@ export function SyncPage() {
             saving={saving}
             isEditing={!!selectedDeviceId}
             errors={formErrors}
+            commKeyStored={commKeyStored}
           />
 
           {/* Connection Test Section */}
@@ -744,6 +1053,8 @@ export function SyncPage() {
               )}
             </AnimatePresence>
           </div>
+
+          {selectedDevice && isTauriEnvironment() && <DeviceSettingsSection device={selectedDevice} />}
         </motion.div>
 
         {/* Sync Section - Only show when a device is selected */}
@@ -766,7 +1077,7 @@ export function SyncPage() {
               <motion.button
                 whileHover={{ scale: 1.02 }}
                 whileTap={{ scale: 0.98 }}
-                onClick={handleSync}
+                onClick={() => handleSync()}
                 disabled={isSyncing}
                 className="btn-primary flex items-center gap-2"
               >
@@ -783,6 +1094,17 @@ export function SyncPage() {
                 )}
                 {isSyncing ? 'Syncing...' : 'Sync Now'}
               </motion.button>
+              {!isSyncing && (
+                <motion.button
+                  whileHover={{ scale: 1.02 }}
+                  whileTap={{ scale: 0.98 }}
+                  onClick={() => handleSync(true)}
+                  className="btn-secondary flex items-center gap-2"
+                  title="Fetch from the device and show what a sync would add, without storing anything"
+                >
+                  Dry Run
+                </motion.button>
+              )}
               {isSyncing && (
                 <motion.button
                   initial={{ opacity: 0, scale: 0.9 }}
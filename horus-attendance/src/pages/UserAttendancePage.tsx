@@ -42,6 +42,7 @@ function StatusBadge({ status }: { status: AttendanceStatus }) {
     incomplete: 'bg-secondary-600/20 text-secondary-400 border-secondary-600/30',
     holiday: 'bg-primary-600/20 text-primary-400 border-primary-600/30',
     weekend: 'bg-secondary-600/20 text-secondary-500 border-secondary-600/30',
+    remote: 'bg-teal-600/20 text-teal-400 border-teal-600/30',
   };
   
   const labels: Record<AttendanceStatus, string> = {
@@ -52,6 +53,7 @@ function StatusBadge({ status }: { status: AttendanceStatus }) {
     incomplete: 'Incomplete',
     holiday: 'Holiday',
     weekend: 'Weekend',
+    remote: 'Remote',
   };
   
   return (
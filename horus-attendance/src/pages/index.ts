@@ -4,6 +4,7 @@ export { RecordsPage } from './RecordsPage';
 export { UsersPage } from './UsersPage';
 export { UserAttendancePage } from './UserAttendancePage';
 export { ReportsPage } from './ReportsPage';
+export { RosterPage } from './RosterPage';
 export { DepartmentsPage } from './DepartmentsPage';
 export { SettingsPage } from './SettingsPage';
 export { NotFoundPage } from './NotFoundPage';
@@ -118,8 +118,19 @@ export interface DeviceRow {
   port: number;
   comm_key: string;
   timezone: string;
+  dst_policy?: string;
+  dst_rules?: string | null;
   sync_mode: string;
+  site?: string | null;
+  sync_priority?: number;
   last_sync_at: string | null;
+  model?: string | null;
+  serial_number?: string | null;
+  firmware?: string | null;
+  mac_address?: string | null;
+  hardware_seen_at?: string | null;
+  last_error?: string | null;
+  last_error_at?: string | null;
   created_at: string;
   updated_at: string;
 }
@@ -137,6 +148,9 @@ export interface UserRow {
   device_name: string | null;
   display_name: string;
   department_id: string | null;
+  site?: string | null;
+  classification?: string;
+  enrollment_expires_at?: string | null;
   email: string | null;
   phone: string | null;
   address: string | null;
@@ -155,6 +169,10 @@ export interface AttendanceLogRow {
   verify_type: number | null;
   punch_type: number | null;
   raw_payload: string | null;
+  source?: string | null;
+  latitude?: number | null;
+  longitude?: number | null;
+  note?: string | null;
   created_at: string;
 }
 
@@ -183,5 +201,7 @@ export interface HolidayRow {
   id: string;
   date: string;
   name: string | null;
+  department_id?: string | null;
+  site?: string | null;
   created_at: string;
 }
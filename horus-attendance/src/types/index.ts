@@ -6,12 +6,15 @@
 export type {
   Device,
   DeviceConfig,
+  DstPolicy,
+  DstRegion,
   DeviceInfo,
   Department,
   CreateDepartmentInput,
   UpdateDepartmentInput,
   User,
   UserStatus,
+  UserClassification,
   DeviceUser,
   CreateUserInput,
   UpdateUserInput,
@@ -32,6 +35,10 @@ export type {
   AppSettings,
   ExportSettings,
   TimezoneSettings,
+  OrganizationSettings,
+  CalendarSettings,
+  AnomalyRules,
+  ApiServerSettings,
   Holiday,
   CreateHolidayInput,
 } from './models';
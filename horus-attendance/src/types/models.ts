@@ -6,6 +6,16 @@
 // Device Types
 // ============================================================================
 
+/**
+ * How a device clock handles daylight saving time:
+ * 'zone' follows the device timezone, 'none' stays on standard time all year,
+ * 'rules' applies the device's own regional rule (see dstRules)
+ */
+export type DstPolicy = 'zone' | 'none' | 'rules';
+
+/** Regional DST rules a device can apply by itself */
+export type DstRegion = 'us' | 'eu' | 'au';
+
 export interface Device {
   id: string;
   name: string;
@@ -14,7 +24,23 @@ export interface Device {
   commKey: string;
   timezone: string;
   syncMode: 'auto' | 'manual';
+  dstPolicy?: DstPolicy;
+  dstRules?: DstRegion | null;
+  /** Where the device is, for per-site sync limits */
+  site?: string | null;
+  /** Higher syncs first when devices wait for a sync slot */
+  syncPriority?: number;
   lastSyncAt: string | null;
+  /** Read from the device on connection tests and syncs */
+  model?: string | null;
+  serialNumber?: string | null;
+  firmware?: string | null;
+  macAddress?: string | null;
+  /** When the hardware details were last read */
+  hardwareSeenAt?: string | null;
+  /** Latest failed connection, info read or sync */
+  lastError?: string | null;
+  lastErrorAt?: string | null;
   createdAt: string;
   updatedAt: string;
 }
@@ -27,6 +53,10 @@ export interface DeviceConfig {
   commKey: string;
   timezone: string;
   syncMode: 'auto' | 'manual';
+  dstPolicy?: DstPolicy;
+  dstRules?: DstRegion | null;
+  site?: string | null;
+  syncPriority?: number;
 }
 
 export interface DeviceInfo {
@@ -35,6 +65,8 @@ export interface DeviceInfo {
   userCount: number;
   logCount: number;
   lastActivity: string;
+  model?: string | null;
+  macAddress?: string | null;
 }
 
 // ============================================================================
@@ -62,12 +94,23 @@ export interface UpdateDepartmentInput {
 
 export type UserStatus = 'active' | 'inactive';
 
+/**
+ * How a user's attendance is treated: contractors are tracked for hours only,
+ * visitors' days without punches aren't absences
+ */
+export type UserClassification = 'employee' | 'contractor' | 'visitor';
+
 export interface User {
   id: string;
   deviceUserId: string | null;
   deviceName: string | null;
   displayName: string;
   departmentId: string | null;
+  /** Site the user works at, for site holidays */
+  site?: string | null;
+  classification?: UserClassification;
+  /** Last day (YYYY-MM-DD) the device enrollment is valid; removed from devices after it */
+  enrollmentExpiresAt?: string | null;
   email: string | null;
   phone: string | null;
   address: string | null;
@@ -100,6 +143,9 @@ export interface CreateUserInput {
 export interface UpdateUserInput {
   displayName?: string;
   departmentId?: string | null;
+  site?: string | null;
+  classification?: UserClassification;
+  enrollmentExpiresAt?: string | null;
   email?: string | null;
   phone?: string | null;
   address?: string | null;
@@ -127,7 +173,8 @@ export type AttendanceStatus =
   | 'early_leave' 
   | 'incomplete' 
   | 'holiday' 
-  | 'weekend';
+  | 'weekend'
+  | 'remote';
 
 export interface PunchRecord {
   id: string;
@@ -148,6 +195,11 @@ export interface AttendanceLog {
   verifyType: number;
   punchType: number;
   rawPayload: string | null;
+  /** Where a manual or API punch came from; null for device punches */
+  source?: string | null;
+  latitude?: number | null;
+  longitude?: number | null;
+  note?: string | null;
   createdAt: string;
 }
 
@@ -230,6 +282,7 @@ export interface AttendanceRules {
   checkOutWindowStart: string;
   checkOutWindowEnd: string;
   workdays: number[]; // 0=Sunday, 1=Monday, etc.
+  dayCutoffHour?: number; // hour the working day starts; earlier punches count toward the previous day
 }
 
 export interface AppearanceSettings {
@@ -265,6 +318,50 @@ export interface TimezoneSettings {
   timeFormat: '12h' | '24h';
 }
 
+export interface OrganizationSettings {
+  /** Company name shown in report headers and emails */
+  name: string;
+  /** Path to a PNG/JPEG/SVG logo embedded in templates */
+  logoPath: string | null;
+}
+
+export interface CalendarSettings {
+  /** Show Hijri dates alongside Gregorian dates in reports and exports */
+  hijriEnabled: boolean;
+  /** Day offset (-2..2) to match local moon sighting */
+  hijriAdjustment: number;
+  /** Group monthly summaries by Hijri month instead of Gregorian month */
+  groupByHijriMonth: boolean;
+}
+
+export interface AnomalyRules {
+  /** Run the background check for impossible punch sequences */
+  enabled: boolean;
+  /** Punches on two different devices closer together than this are flagged */
+  travelMinutes: number;
+  /** More punches than this for one user on one day are flagged */
+  maxPunchesPerDay: number;
+}
+
+export interface ApiServerSettings {
+  /** Start the REST API with the app */
+  enabled: boolean;
+  /** Interface to listen on; 0.0.0.0 for the whole LAN */
+  bindAddress: string;
+  port: number;
+  /** Requests a minute allowed per IP address and per credential; 0 for no limit */
+  rateLimitPerMinute: number;
+  /** Largest request body accepted */
+  maxBodyBytes: number;
+  /** Failed sign-ins in a row from one IP address before it is locked out; 0 to never lock out */
+  lockoutAfterFailures: number;
+  lockoutMinutes: number;
+  /** Ports above `port` tried in turn when it is taken; 0 to never fall back */
+  fallbackPortCount: number;
+  /** Port the server fell back to, kept while `port` stays taken; set by the server */
+  fallbackPort: number | null;
+}
+
 export interface AppSettings {
   device: DeviceConfig | null;
   attendance: AttendanceRules;
@@ -273,6 +370,11 @@ export interface AppSettings {
   backup: BackupSettings;
   export: ExportSettings;
   timezone: TimezoneSettings;
+  organization: OrganizationSettings;
+  calendar: CalendarSettings;
+  anomalyRules: AnomalyRules;
+  allowedDirectories: string[]; // extra absolute paths file writes may go to
+  apiServer: ApiServerSettings;
 }
 
 // ============================================================================
@@ -283,10 +385,16 @@ export interface Holiday {
   id: string;
   date: string;
   name: string | null;
+  /** Only this department observes it; null for every department */
+  departmentId?: string | null;
+  /** Only users at this site observe it; null for every site */
+  site?: string | null;
   createdAt: string;
 }
 
 export interface CreateHolidayInput {
   date: string;
   name?: string;
+  departmentId?: string | null;
+  site?: string | null;
 }
@@ -34,16 +34,45 @@ export interface SyncOptions {
   days?: number;
   startDate?: string;
   endDate?: string;
+  /** Fetch and compare with the database without storing anything */
+  dryRun?: boolean;
+  /** Started by a schedule rather than a person; refused outside the device's sync windows */
+  scheduled?: boolean;
+}
+
+/**
+ * What a sync would store, reported by a dry run
+ */
+export interface SyncPreview {
+  /** Device users no app user has the ID of */
+  newUsers: { deviceUserId: string; deviceName: string }[];
+  existingUsers: number;
+  newLogs: number;
+  /** Logs already stored for this device */
+  duplicateLogs: number;
+  /** Logs whose user ID is neither on the device nor in the app */
+  unknownUserLogs: number;
+  /** Range covered by the fetched logs */
+  firstTimestamp: string | null;
+  lastTimestamp: string | null;
 }
 
 export interface SyncResult {
   success: boolean;
   usersAdded: number;
   usersSynced: number;
+  /** Existing users whose device name changed on the device; display names are kept */
+  usersRenamed?: number;
   logsAdded: number;
   logsDeduplicated: number;
   errors: string[];
   syncedAt: string;
+  /** Device users and logs left out because they failed validation */
+  quarantined?: number;
+  /** Set for dry runs, which store nothing */
+  preview?: SyncPreview;
+  /** Things that didn't stop the sync, e.g. running outside the device's sync windows */
+  warnings?: string[];
 }
 
 export interface ConnectionTestResult {